@@ -14,6 +14,7 @@ use blockstack_lib::types::chainstate::BlockHeaderHash;
 use blockstack_lib::types::chainstate::BurnchainHeaderHash;
 use blockstack_lib::types::chainstate::VRFSeed;
 use blockstack_lib::types::proof::ClarityMarfTrieId;
+use blockstack_lib::util::hash::{MerkleTree, Sha512Trunc256Sum};
 use blockstack_lib::vm::ast::build_ast;
 use blockstack_lib::vm::contexts::GlobalContext;
 use blockstack_lib::vm::costs::LimitedCostTracker;
@@ -72,6 +73,13 @@ impl HeadersDB for TestHeadersDB {
     fn get_miner_address(&self, _id_bhh: &StacksBlockId) -> Option<StacksAddress> {
         None
     }
+
+    fn get_withdrawal_tree_for_block(
+        &self,
+        _id_bhh: &StacksBlockId,
+    ) -> Option<MerkleTree<Sha512Trunc256Sum>> {
+        Some(MerkleTree::empty())
+    }
 }
 
 fn as_hash160(inp: u32) -> [u8; 20] {
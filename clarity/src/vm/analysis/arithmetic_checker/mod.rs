@@ -27,6 +27,7 @@ use crate::vm::types::{
 };
 
 use crate::vm::variables::NativeVariables;
+use crate::vm::ClarityVersion;
 use std::collections::HashMap;
 
 pub use super::errors::{
@@ -44,7 +45,9 @@ mod tests;
 ///  any database operations, traits, or iterating operations (e.g., list
 ///  operations)
 ///
-pub struct ArithmeticOnlyChecker();
+pub struct ArithmeticOnlyChecker {
+    clarity_version: ClarityVersion,
+}
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Error {
@@ -74,7 +77,9 @@ impl ArithmeticOnlyChecker {
     }
 
     pub fn run(contract_analysis: &ContractAnalysis) -> Result<(), Error> {
-        let checker = ArithmeticOnlyChecker();
+        let checker = ArithmeticOnlyChecker {
+            clarity_version: contract_analysis.clarity_version,
+        };
         for exp in contract_analysis.expressions.iter() {
             checker.check_top_levels(&exp)?;
         }
@@ -120,6 +125,9 @@ impl ArithmeticOnlyChecker {
                 Trait { .. } => Err(Error::DefineTypeForbidden(DefineFunctions::Trait)),
                 UseTrait { .. } => Err(Error::DefineTypeForbidden(DefineFunctions::UseTrait)),
                 ImplTrait { .. } => Err(Error::DefineTypeForbidden(DefineFunctions::ImplTrait)),
+                DepositHandler { .. } => {
+                    Err(Error::DefineTypeForbidden(DefineFunctions::DepositHandler))
+                }
             }
         } else {
             self.check_expression(expr)
@@ -140,10 +148,12 @@ impl ArithmeticOnlyChecker {
 
     fn check_variables_allowed(&self, var_name: &ClarityName) -> Result<(), Error> {
         use crate::vm::variables::NativeVariables::*;
-        if let Some(native_var) = NativeVariables::lookup_by_name(var_name) {
+        if let Some(native_var) =
+            NativeVariables::lookup_by_name_at_version(var_name, self.clarity_version)
+        {
             match native_var {
                 ContractCaller | TxSender | TotalLiquidMicroSTX | BlockHeight | BurnBlockHeight
-                | Regtest => Err(Error::VariableForbidden(native_var)),
+                | L1BlockHeight | Regtest => Err(Error::VariableForbidden(native_var)),
                 NativeNone | NativeTrue | NativeFalse => Ok(()),
             }
         } else {
@@ -156,7 +166,7 @@ impl ArithmeticOnlyChecker {
         function: &str,
         args: &[SymbolicExpression],
     ) -> Option<Result<(), Error>> {
-        NativeFunctions::lookup_by_name(function)
+        NativeFunctions::lookup_by_name_at_version(function, self.clarity_version)
             .map(|function| self.check_native_function(function, args))
     }
 
@@ -171,11 +181,13 @@ impl ArithmeticOnlyChecker {
             | DeleteEntry | InsertEntry | SetVar | MintAsset | MintToken | TransferAsset
             | TransferToken | ContractCall | StxTransfer | StxBurn | AtBlock | GetStxBalance
             | GetTokenSupply | BurnToken | BurnAsset | WithdrawToken | WithdrawAsset
-            | StxWithdraw => {
+            | StxWithdraw | GetWithdrawalInfo | VarExists | MapSize | GetL1BlockInfo
+            | TryContractCall | StxGetAccount => {
                 return Err(Error::FunctionNotPermitted(function));
             }
             Append | Concat | AsMaxLen | ContractOf | PrincipalOf | ListCons | Print
-            | AsContract | ElementAt | IndexOf | Map | Filter | Fold => {
+            | AsContract | ElementAt | IndexOf | Slice | ReplaceAt | BuffToUIntBe
+            | BuffToUIntLe | ToConsensusBuff | FromConsensusBuff | Map | Filter | Fold => {
                 return Err(Error::FunctionNotPermitted(function));
             }
             Sha512 | Sha512Trunc256 | Secp256k1Recover | Secp256k1Verify | Hash160 | Sha256
@@ -183,10 +195,11 @@ impl ArithmeticOnlyChecker {
                 return Err(Error::FunctionNotPermitted(function));
             }
             Add | Subtract | Divide | Multiply | CmpGeq | CmpLeq | CmpLess | CmpGreater
-            | Modulo | Power | Sqrti | Log2 | BitwiseXOR | And | Or | Not | Equals | If
-            | ConsSome | ConsOkay | ConsError | DefaultTo | UnwrapRet | UnwrapErrRet | IsOkay
-            | IsNone | Asserts | Unwrap | UnwrapErr | IsErr | IsSome | TryRet | ToUInt | ToInt
-            | Len | Begin | TupleMerge => self.check_all(args),
+            | Modulo | Power | Sqrti | Log2 | BitwiseXOR | BitwiseAnd | BitwiseOr | BitwiseNot
+            | BitwiseLShift | BitwiseRShift | And | Or | Not | Equals | If | ConsSome
+            | ConsOkay | ConsError | DefaultTo | UnwrapRet | UnwrapErrRet | IsOkay | IsNone
+            | Asserts | Unwrap | UnwrapErr | IsErr | IsSome | TryRet | ToUInt | ToInt | Len
+            | Begin | TupleMerge => self.check_all(args),
             // we need to treat all the remaining functions specially, because these
             //   do not eval all of their arguments (rather, one or more of their arguments
             //   is a name)
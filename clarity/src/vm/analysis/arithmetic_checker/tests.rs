@@ -24,6 +24,7 @@ use crate::vm::functions::define::DefineFunctions;
 use crate::vm::functions::NativeFunctions;
 use crate::vm::types::QualifiedContractIdentifier;
 use crate::vm::variables::NativeVariables;
+use crate::vm::ClarityVersion;
 
 fn arithmetic_check(contract: &str) -> Result<(), Error> {
     let contract_identifier = QualifiedContractIdentifier::transient();
@@ -32,6 +33,7 @@ fn arithmetic_check(contract: &str) -> Result<(), Error> {
     let analysis = ContractAnalysis::new(
         contract_identifier,
         expressions,
+        ClarityVersion::latest(),
         LimitedCostTracker::new_free(),
     );
 
@@ -72,6 +74,10 @@ fn test_variables() {
             "(define-private (foo) burn-block-height)",
             VariableForbidden(NativeVariables::BurnBlockHeight),
         ),
+        (
+            "(define-private (foo) l1-block-height)",
+            VariableForbidden(NativeVariables::L1BlockHeight),
+        ),
         (
             "(define-private (foo) block-height)",
             VariableForbidden(NativeVariables::BlockHeight),
@@ -130,6 +136,10 @@ fn test_functions() {
          FunctionNotPermitted(NativeFunctions::FetchVar)),
         ("(define-private (foo) (var-set foo-var u2))",
          FunctionNotPermitted(NativeFunctions::SetVar)),
+        ("(define-private (foo) (var-exists? foo-var))",
+         FunctionNotPermitted(NativeFunctions::VarExists)),
+        ("(define-private (foo) (map-size foo-map))",
+         FunctionNotPermitted(NativeFunctions::MapSize)),
         ("(define-private (foo (a principal)) (ft-get-balance tokaroos a))",
          FunctionNotPermitted(NativeFunctions::GetTokenBalance)),
         ("(define-private (foo (a principal)) 
@@ -147,10 +157,30 @@ fn test_functions() {
          FunctionNotPermitted(NativeFunctions::GetAssetOwner)),
         ("(get-block-info? id-header-hash 0)",
          FunctionNotPermitted(NativeFunctions::GetBlockInfo)),
+        ("(get-withdrawal-info? stx u1 u0 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF u100)",
+         FunctionNotPermitted(NativeFunctions::GetWithdrawalInfo)),
+        ("(get-l1-block-info? header-hash u1)",
+         FunctionNotPermitted(NativeFunctions::GetL1BlockInfo)),
+        ("(slice 0x0102 u0 u1)",
+         FunctionNotPermitted(NativeFunctions::Slice)),
+        ("(replace-at? 0x0102 u0 0x03)",
+         FunctionNotPermitted(NativeFunctions::ReplaceAt)),
+        ("(buff-to-uint-be 0x0102)",
+         FunctionNotPermitted(NativeFunctions::BuffToUIntBe)),
+        ("(buff-to-uint-le 0x0102)",
+         FunctionNotPermitted(NativeFunctions::BuffToUIntLe)),
+        ("(to-consensus-buff? u1)",
+         FunctionNotPermitted(NativeFunctions::ToConsensusBuff)),
+        ("(from-consensus-buff? uint 0x0100000000000000000000000000000001)",
+         FunctionNotPermitted(NativeFunctions::FromConsensusBuff)),
         ("(define-private (foo) (contract-call? .bar outer-call))",
          FunctionNotPermitted(NativeFunctions::ContractCall)),
+        ("(define-private (foo) (try-contract-call? .bar outer-call))",
+         FunctionNotPermitted(NativeFunctions::TryContractCall)),
         ("(stx-get-balance 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF)",
          FunctionNotPermitted(NativeFunctions::GetStxBalance)),
+        ("(stx-account 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF)",
+         FunctionNotPermitted(NativeFunctions::StxGetAccount)),
         ("(stx-burn? u100 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF)",
          FunctionNotPermitted(NativeFunctions::StxBurn)),
         ("(stx-withdraw? u100 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF)",
@@ -0,0 +1,178 @@
+// Copyright (C) 2013-2020 Blocstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use crate::vm::analysis::type_checker::contexts::TypeMap;
+use crate::vm::callables::CallableType;
+use crate::vm::costs::{runtime_cost, CostTracker};
+use crate::vm::database::ClaritySerializable;
+use crate::vm::functions::{lookup_reserved_functions, NativeFunctions};
+use crate::vm::representations::SymbolicExpression;
+use crate::vm::representations::SymbolicExpressionType::{AtomValue, List, LiteralValue};
+use crate::vm::types::{TypeSignature, Value};
+use crate::vm::ClarityVersion;
+
+use super::errors::{CheckError, CheckResult};
+
+#[cfg(test)]
+mod tests;
+
+/// Native functions whose result depends only on their arguments. Only applications of these
+///  functions are ever folded.
+const FOLDABLE_NATIVES: &[NativeFunctions] = &[
+    NativeFunctions::Add,
+    NativeFunctions::Subtract,
+    NativeFunctions::Multiply,
+    NativeFunctions::Divide,
+    NativeFunctions::Modulo,
+    NativeFunctions::Power,
+    NativeFunctions::Sqrti,
+    NativeFunctions::Log2,
+    NativeFunctions::BitwiseXOR,
+    NativeFunctions::BitwiseAnd,
+    NativeFunctions::BitwiseOr,
+    NativeFunctions::BitwiseNot,
+    NativeFunctions::BitwiseLShift,
+    NativeFunctions::BitwiseRShift,
+    NativeFunctions::CmpLess,
+    NativeFunctions::CmpLeq,
+    NativeFunctions::CmpGreater,
+    NativeFunctions::CmpGeq,
+    NativeFunctions::Not,
+    NativeFunctions::ToInt,
+    NativeFunctions::ToUInt,
+];
+
+///
+/// An optimization pass that replaces applications of pure native functions to literal
+///  arguments with the value they evaluate to, so that the work is done once at deploy time
+///  instead of on every invocation.
+///
+/// To guarantee that a folded contract behaves exactly like the unfolded one:
+///  * only expressions the type checker annotated as function applications are considered,
+///    so data positions (tuple pairs, `let` bindings, type signatures) are never rewritten.
+///  * an application whose evaluation errors (e.g., overflow, division by zero) is left in
+///    place, so the same runtime error is raised when (and only if) it is reached.
+///  * the runtime cost of each distinct folded application is charged to the analysis cost
+///    tracker. Identical applications within a contract are evaluated (and charged) once.
+///
+/// Because a folded contract is cheaper to execute than an unfolded one, this pass changes
+///  block validity. It is only run on Clarity 2 contracts.
+///
+pub struct ConstantFolder<'a, T: CostTracker> {
+    type_map: &'a TypeMap,
+    cost_track: &'a mut T,
+    clarity_version: ClarityVersion,
+    folded: HashMap<(NativeFunctions, Vec<String>), Option<Value>>,
+}
+
+impl<'a, T: CostTracker> ConstantFolder<'a, T> {
+    fn new(
+        type_map: &'a TypeMap,
+        clarity_version: ClarityVersion,
+        cost_track: &'a mut T,
+    ) -> ConstantFolder<'a, T> {
+        ConstantFolder {
+            type_map,
+            cost_track,
+            clarity_version,
+            folded: HashMap::new(),
+        }
+    }
+
+    /// Fold the constant expressions in `expressions`, which must be the expressions that
+    ///  produced `type_map` for a contract of version `clarity_version`. Returns the number of
+    ///  applications that were replaced.
+    pub fn run(
+        expressions: &mut [SymbolicExpression],
+        type_map: &'a TypeMap,
+        clarity_version: ClarityVersion,
+        cost_track: &'a mut T,
+    ) -> CheckResult<u64> {
+        let mut folder = ConstantFolder::new(type_map, clarity_version, cost_track);
+        let mut count = 0;
+        for expr in expressions.iter_mut() {
+            count += folder.fold_expression(expr)?;
+        }
+        Ok(count)
+    }
+
+    fn fold_expression(&mut self, expr: &mut SymbolicExpression) -> CheckResult<u64> {
+        let mut count = 0;
+        if let List(ref mut children) = expr.expr {
+            for child in children.iter_mut() {
+                count += self.fold_expression(child)?;
+            }
+        } else {
+            return Ok(0);
+        }
+
+        if let Some(value) = self.try_fold_application(expr)? {
+            expr.expr = LiteralValue(value);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Evaluate `expr` if it is an application of a foldable native to literal arguments.
+    fn try_fold_application(&mut self, expr: &SymbolicExpression) -> CheckResult<Option<Value>> {
+        match self.type_map.get_type(expr) {
+            None | Some(TypeSignature::NoType) => return Ok(None),
+            Some(_) => {}
+        }
+
+        let (function_name, args) = match expr.match_list().and_then(|list| list.split_first()) {
+            Some((function_name, args)) => (function_name, args),
+            None => return Ok(None),
+        };
+        let native_function = match function_name.match_atom().and_then(|name| {
+            NativeFunctions::lookup_by_name_at_version(name.as_str(), self.clarity_version)
+        }) {
+            Some(native_function) if FOLDABLE_NATIVES.contains(&native_function) => native_function,
+            _ => return Ok(None),
+        };
+
+        let mut arg_values = Vec::with_capacity(args.len());
+        for arg in args.iter() {
+            match arg.expr {
+                AtomValue(ref value) | LiteralValue(ref value) => arg_values.push(value.clone()),
+                _ => return Ok(None),
+            }
+        }
+
+        let key = (
+            native_function,
+            arg_values.iter().map(|value| value.serialize()).collect(),
+        );
+        if let Some(result) = self.folded.get(&key) {
+            return Ok(result.clone());
+        }
+
+        let result =
+            match lookup_reserved_functions(native_function.get_name_str(), self.clarity_version) {
+                Some(CallableType::NativeFunction(_, handle, cost_function)) => {
+                    runtime_cost(cost_function, self.cost_track, arg_values.len())
+                        .map_err(CheckError::from)?;
+                    handle.apply(arg_values).ok()
+                }
+                _ => None,
+            };
+
+        self.folded.insert(key, result.clone());
+        Ok(result)
+    }
+}
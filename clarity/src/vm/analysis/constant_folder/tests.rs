@@ -0,0 +1,217 @@
+// Copyright (C) 2013-2020 Blocstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::vm::analysis::constant_folder::ConstantFolder;
+use crate::vm::analysis::mem_type_check;
+use crate::vm::costs::cost_functions::ClarityCostFunction;
+use crate::vm::costs::{CostErrors, CostTracker, ExecutionCost, LimitedCostTracker};
+use crate::vm::representations::SymbolicExpressionType::LiteralValue;
+use crate::vm::representations::{ClarityName, SymbolicExpression};
+use crate::vm::types::{QualifiedContractIdentifier, Value};
+use crate::vm::ClarityVersion;
+
+fn fold(contract: &str) -> (u64, Vec<SymbolicExpression>) {
+    let analysis = mem_type_check(contract).unwrap().1;
+    let mut expressions = analysis.expressions.clone();
+    let mut cost_track = LimitedCostTracker::new_free();
+    let count = ConstantFolder::run(
+        &mut expressions,
+        analysis.type_map.as_ref().unwrap(),
+        ClarityVersion::latest(),
+        &mut cost_track,
+    )
+    .unwrap();
+    (count, expressions)
+}
+
+/// Returns the value expression of a `(define-constant name value)` expression.
+fn constant_value(expr: &SymbolicExpression) -> &SymbolicExpression {
+    &expr.match_list().unwrap()[2]
+}
+
+#[test]
+fn test_fold_nested_arithmetic() {
+    let (count, expressions) = fold("(define-constant x (+ 1 (* 2 (pow 3 2))))");
+    assert_eq!(count, 3);
+    assert_eq!(
+        constant_value(&expressions[0]).expr,
+        LiteralValue(Value::Int(19))
+    );
+
+    let (count, expressions) =
+        fold("(define-constant x (and (< u1 u2) (not (is-eq (to-uint 1) u2))))");
+    // `and`, `not` and `is-eq` are not folded, but their foldable arguments are.
+    assert_eq!(count, 2);
+    let and_args = constant_value(&expressions[0]).match_list().unwrap();
+    assert_eq!(and_args[1].expr, LiteralValue(Value::Bool(true)));
+    let is_eq_args = and_args[2].match_list().unwrap()[1].match_list().unwrap();
+    assert_eq!(is_eq_args[1].expr, LiteralValue(Value::UInt(1)));
+}
+
+#[test]
+fn test_fold_keeps_expression_ids() {
+    let contract = "(define-read-only (f) (+ 1 2))";
+    let analysis = mem_type_check(contract).unwrap().1;
+    let (count, expressions) = fold(contract);
+    assert_eq!(count, 1);
+
+    let original_body = &analysis.expressions[0].match_list().unwrap()[2];
+    let folded_body = &expressions[0].match_list().unwrap()[2];
+    assert_eq!(original_body.id, folded_body.id);
+    assert_eq!(folded_body.expr, LiteralValue(Value::Int(3)));
+}
+
+#[test]
+fn test_no_fold_of_runtime_errors() {
+    let tests = [
+        "(define-read-only (f) (- u0 u1))",
+        "(define-read-only (f) (/ 1 0))",
+        "(define-read-only (f) (pow 2 128))",
+        "(define-read-only (f) (to-uint -1))",
+        "(define-read-only (f) (sqrti -1))",
+    ];
+
+    for contract in tests.iter() {
+        let (count, expressions) = fold(contract);
+        assert_eq!(count, 0, "Should not fold {}", contract);
+        assert!(expressions[0].match_list().unwrap()[2]
+            .match_list()
+            .is_some());
+    }
+}
+
+#[test]
+fn test_no_fold_of_non_constants() {
+    let tests = [
+        "(define-read-only (f) (+ block-height u1))",
+        "(define-read-only (f (a int)) (+ a 1))",
+        "(define-data-var v int 1) (define-read-only (f) (+ (var-get v) 1))",
+        "(define-read-only (f) (sha256 u1))",
+        "(define-private (g) 1) (define-read-only (f) (+ (g) 1))",
+    ];
+
+    for contract in tests.iter() {
+        let (count, _) = fold(contract);
+        assert_eq!(count, 0, "Should not fold {}", contract);
+    }
+}
+
+#[test]
+fn test_no_fold_of_data_positions() {
+    // tuple pairs and let bindings which look like applications of foldable natives
+    let tests = [
+        "(define-constant t { not: true })",
+        "(define-constant t (tuple (not true)))",
+        "(define-read-only (f) (let ((a (+ 1 2))) a))",
+        "(define-map m { not: bool } uint)",
+    ];
+
+    let (count, expressions) = fold(tests[0]);
+    assert_eq!(count, 0);
+    let tuple_args = constant_value(&expressions[0]).match_list().unwrap();
+    assert!(tuple_args[1].match_list().is_some());
+
+    let (count, _) = fold(tests[1]);
+    assert_eq!(count, 0);
+
+    // only the bound value is folded
+    let (count, expressions) = fold(tests[2]);
+    assert_eq!(count, 1);
+    let let_args = expressions[0].match_list().unwrap()[2]
+        .match_list()
+        .unwrap();
+    let binding = let_args[1].match_list().unwrap()[0].match_list().unwrap();
+    assert_eq!(binding[0].match_atom().unwrap().as_str(), "a");
+    assert_eq!(binding[1].expr, LiteralValue(Value::Int(3)));
+
+    let (count, _) = fold(tests[3]);
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn test_fold_is_memoized() {
+    let (count, expressions) = fold(
+        "(define-constant a (+ 1 2))
+         (define-constant b (+ 1 2))
+         (define-constant c (+ 2 1))",
+    );
+    assert_eq!(count, 3);
+    for expr in expressions.iter() {
+        assert_eq!(constant_value(expr).expr, LiteralValue(Value::Int(3)));
+    }
+}
+
+/// Records the cost functions charged against it.
+#[derive(Default)]
+struct RecordingCostTracker {
+    charged: Vec<(ClarityCostFunction, Vec<u64>)>,
+}
+
+impl CostTracker for RecordingCostTracker {
+    fn compute_cost(
+        &mut self,
+        cost_function: ClarityCostFunction,
+        input: &[u64],
+    ) -> Result<ExecutionCost, CostErrors> {
+        self.charged.push((cost_function, input.to_vec()));
+        Ok(ExecutionCost::zero())
+    }
+    fn add_cost(&mut self, _cost: ExecutionCost) -> Result<(), CostErrors> {
+        Ok(())
+    }
+    fn add_memory(&mut self, _memory: u64) -> Result<(), CostErrors> {
+        Ok(())
+    }
+    fn drop_memory(&mut self, _memory: u64) {}
+    fn reset_memory(&mut self) {}
+    fn short_circuit_contract_call(
+        &mut self,
+        _contract: &QualifiedContractIdentifier,
+        _function: &ClarityName,
+        _input: &[u64],
+    ) -> Result<bool, CostErrors> {
+        Ok(false)
+    }
+}
+
+#[test]
+fn test_fold_charges_cost() {
+    let contract = "(define-constant a (+ 1 2 3))
+                    (define-constant b (+ 1 2 3))
+                    (define-constant c (* 2 3))
+                    (define-read-only (f) (/ 1 0))";
+    let analysis = mem_type_check(contract).unwrap().1;
+    let mut expressions = analysis.expressions.clone();
+    let mut cost_track = RecordingCostTracker::default();
+    let count = ConstantFolder::run(
+        &mut expressions,
+        analysis.type_map.as_ref().unwrap(),
+        ClarityVersion::latest(),
+        &mut cost_track,
+    )
+    .unwrap();
+    assert_eq!(count, 3);
+
+    // each distinct application is charged once, including the one that could not be folded
+    assert_eq!(
+        cost_track.charged,
+        vec![
+            (ClarityCostFunction::Add, vec![3]),
+            (ClarityCostFunction::Mul, vec![2]),
+            (ClarityCostFunction::Div, vec![2]),
+        ]
+    );
+}
@@ -41,6 +41,9 @@ pub fn build_contract_interface(contract_analysis: &ContractAnalysis) -> Contrac
         cost_track: _,
         contract_interface: _,
         is_cost_contract_eligible: _,
+        allow_unchecked_intermediary_responses: _,
+        warnings: _,
+        clarity_version: _,
     } = contract_analysis;
 
     contract_interface
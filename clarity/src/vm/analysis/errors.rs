@@ -71,6 +71,7 @@ pub enum CheckErrors {
     UncheckedIntermediaryResponses,
 
     CouldNotDetermineMatchTypes,
+    CouldNotDetermineSerializationType,
 
     // Checker runtime failures
     TypeAlreadyAnnotatedFailure,
@@ -113,6 +114,8 @@ pub enum CheckErrors {
     PublicFunctionMustReturnResponse(TypeSignature),
     DefineVariableBadSignature,
     ReturnTypesMustMatch(TypeSignature, TypeSignature),
+    DepositHandlerAlreadyDefined,
+    BadDepositHandler(String),
 
     CircularReference(Vec<String>),
 
@@ -127,6 +130,14 @@ pub enum CheckErrors {
     NoSuchBlockInfoProperty(String),
     GetBlockInfoExpectPropertyName,
 
+    // get-withdrawal-info? errors
+    NoSuchWithdrawalAssetType(String),
+    GetWithdrawalInfoExpectAssetType,
+
+    // get-l1-block-info? errors
+    NoSuchL1BlockInfoProperty(String),
+    GetL1BlockInfoExpectPropertyName,
+
     NameAlreadyUsed(String),
 
     // expect a function, or applying a function to a list
@@ -285,6 +296,14 @@ pub fn check_arguments_at_least<T>(expected: usize, args: &[T]) -> Result<(), Ch
     }
 }
 
+pub fn check_arguments_at_most<T>(expected: usize, args: &[T]) -> Result<(), CheckErrors> {
+    if args.len() > expected {
+        Err(CheckErrors::IncorrectArgumentCount(expected, args.len()))
+    } else {
+        Ok(())
+    }
+}
+
 fn formatted_expected_types(expected_types: &Vec<TypeSignature>) -> String {
     let mut expected_types_joined = format!("'{}'", expected_types[0]);
 
@@ -342,6 +361,7 @@ impl DiagnosableError for CheckErrors {
             CheckErrors::CouldNotDetermineResponseOkType => format!("attempted to obtain 'ok' value from response, but 'ok' type is indeterminate"),
             CheckErrors::CouldNotDetermineResponseErrType => format!("attempted to obtain 'err' value from response, but 'err' type is indeterminate"),
             CheckErrors::CouldNotDetermineMatchTypes => format!("attempted to match on an (optional) or (response) type where either the some, ok, or err type is indeterminate. you may wish to use unwrap-panic or unwrap-err-panic instead."),
+            CheckErrors::CouldNotDetermineSerializationType => format!("could not determine the serialization size of a value whose type is indeterminate"),
             CheckErrors::BadTupleFieldName => format!("invalid tuple field name"),
             CheckErrors::ExpectedTuple(type_signature) => format!("expecting tuple, found '{}'", type_signature),
             CheckErrors::NoSuchTupleField(field_name, tuple_signature) => format!("cannot find field '{}' in tuple '{}'", field_name, tuple_signature),
@@ -362,6 +382,8 @@ impl DiagnosableError for CheckErrors {
             CheckErrors::PublicFunctionMustReturnResponse(found_type) => format!("public functions must return an expression of type 'response', found '{}'", found_type),
             CheckErrors::DefineVariableBadSignature => format!("invalid variable definition"),
             CheckErrors::ReturnTypesMustMatch(type_1, type_2) => format!("detected two execution paths, returning two different expression types (got '{}' and '{}')", type_1, type_2),
+            CheckErrors::DepositHandlerAlreadyDefined => format!("a contract can only define one deposit handler"),
+            CheckErrors::BadDepositHandler(function_name) => format!("deposit handler '{}' must be a private function of (principal, (optional principal), uint) that returns a response", function_name),
             CheckErrors::NoSuchContract(contract_identifier) => format!("use of unresolved contract '{}'", contract_identifier),
             CheckErrors::NoSuchPublicFunction(contract_identifier, function_name) => format!("contract '{}' has no public function '{}'", contract_identifier, function_name),
             CheckErrors::PublicFunctionNotReadOnly(contract_identifier, function_name) => format!("function '{}' in '{}' is not read-only", contract_identifier, function_name),
@@ -369,6 +391,10 @@ impl DiagnosableError for CheckErrors {
             CheckErrors::ContractCallExpectName => format!("missing contract name for call"),
             CheckErrors::NoSuchBlockInfoProperty(property_name) => format!("use of block unknown property '{}'", property_name),
             CheckErrors::GetBlockInfoExpectPropertyName => format!("missing property name for block info introspection"),
+            CheckErrors::NoSuchWithdrawalAssetType(asset_type) => format!("use of unknown withdrawal asset type '{}'", asset_type),
+            CheckErrors::GetWithdrawalInfoExpectAssetType => format!("missing asset type for withdrawal introspection"),
+            CheckErrors::NoSuchL1BlockInfoProperty(property_name) => format!("use of unknown L1 block property '{}'", property_name),
+            CheckErrors::GetL1BlockInfoExpectPropertyName => format!("missing property name for L1 block info introspection"),
             CheckErrors::NameAlreadyUsed(name) => format!("defining '{}' conflicts with previous value", name),
             CheckErrors::NonFunctionApplication => format!("expecting expression of type function"),
             CheckErrors::ExpectedListApplication => format!("expecting expression of type list"),
@@ -430,7 +456,136 @@ impl DiagnosableError for CheckErrors {
             CheckErrors::NoSuchBlockInfoProperty(_) => Some(format!(
                 "properties available: time, header-hash, burnchain-header-hash, vrf-seed"
             )),
+            CheckErrors::NoSuchWithdrawalAssetType(_) => {
+                Some(format!("asset types available: stx, ft, nft"))
+            }
+            CheckErrors::NoSuchL1BlockInfoProperty(_) => {
+                Some(format!("properties available: header-hash, time"))
+            }
+            CheckErrors::UncheckedIntermediaryResponses => Some(format!(
+                "handle the response before the next statement, e.g. with try!, unwrap!, unwrap-panic or match: (try! (stx-transfer? u100 tx-sender recipient))"
+            )),
             _ => None,
         }
     }
+
+    fn code(&self) -> Option<String> {
+        // codes are part of the diagnostics API, so they are spelled out rather than derived
+        //  from the variant names, which may change
+        use self::CheckErrors::*;
+        let code = match self {
+            CostOverflow => "CostOverflow",
+            CostBalanceExceeded(..) => "CostBalanceExceeded",
+            MemoryBalanceExceeded(..) => "MemoryBalanceExceeded",
+            CostComputationFailed(..) => "CostComputationFailed",
+            ValueTooLarge => "ValueTooLarge",
+            ValueOutOfBounds => "ValueOutOfBounds",
+            TypeSignatureTooDeep => "TypeSignatureTooDeep",
+            ExpectedName => "ExpectedName",
+            BadMatchOptionSyntax(..) => "BadMatchOptionSyntax",
+            BadMatchResponseSyntax(..) => "BadMatchResponseSyntax",
+            BadMatchInput(..) => "BadMatchInput",
+            UnknownListConstructionFailure => "UnknownListConstructionFailure",
+            ListTypesMustMatch => "ListTypesMustMatch",
+            ConstructedListTooLarge => "ConstructedListTooLarge",
+            TypeError(..) => "TypeError",
+            TypeLiteralError(..) => "TypeLiteralError",
+            TypeValueError(..) => "TypeValueError",
+            NoSuperType(..) => "NoSuperType",
+            InvalidTypeDescription => "InvalidTypeDescription",
+            UnknownTypeName(..) => "UnknownTypeName",
+            UnionTypeError(..) => "UnionTypeError",
+            UnionTypeValueError(..) => "UnionTypeValueError",
+            ExpectedLiteral => "ExpectedLiteral",
+            ExpectedOptionalType(..) => "ExpectedOptionalType",
+            ExpectedResponseType(..) => "ExpectedResponseType",
+            ExpectedOptionalOrResponseType(..) => "ExpectedOptionalOrResponseType",
+            ExpectedOptionalValue(..) => "ExpectedOptionalValue",
+            ExpectedResponseValue(..) => "ExpectedResponseValue",
+            ExpectedOptionalOrResponseValue(..) => "ExpectedOptionalOrResponseValue",
+            CouldNotDetermineResponseOkType => "CouldNotDetermineResponseOkType",
+            CouldNotDetermineResponseErrType => "CouldNotDetermineResponseErrType",
+            UncheckedIntermediaryResponses => "UncheckedIntermediaryResponses",
+            CouldNotDetermineMatchTypes => "CouldNotDetermineMatchTypes",
+            CouldNotDetermineSerializationType => "CouldNotDetermineSerializationType",
+            TypeAlreadyAnnotatedFailure => "TypeAlreadyAnnotatedFailure",
+            TypeAnnotationExpectedFailure => "TypeAnnotationExpectedFailure",
+            CheckerImplementationFailure => "CheckerImplementationFailure",
+            BadTokenName => "BadTokenName",
+            DefineFTBadSignature => "DefineFTBadSignature",
+            DefineNFTBadSignature => "DefineNFTBadSignature",
+            NoSuchNFT(..) => "NoSuchNFT",
+            NoSuchFT(..) => "NoSuchFT",
+            BadTransferSTXArguments => "BadTransferSTXArguments",
+            BadTransferFTArguments => "BadTransferFTArguments",
+            BadTransferNFTArguments => "BadTransferNFTArguments",
+            BadMintFTArguments => "BadMintFTArguments",
+            BadBurnFTArguments => "BadBurnFTArguments",
+            BadWithdrawFTArguments => "BadWithdrawFTArguments",
+            BadTupleFieldName => "BadTupleFieldName",
+            ExpectedTuple(..) => "ExpectedTuple",
+            NoSuchTupleField(..) => "NoSuchTupleField",
+            EmptyTuplesNotAllowed => "EmptyTuplesNotAllowed",
+            BadTupleConstruction => "BadTupleConstruction",
+            TupleExpectsPairs => "TupleExpectsPairs",
+            NoSuchDataVariable(..) => "NoSuchDataVariable",
+            BadMapName => "BadMapName",
+            NoSuchMap(..) => "NoSuchMap",
+            DefineFunctionBadSignature => "DefineFunctionBadSignature",
+            BadFunctionName => "BadFunctionName",
+            BadMapTypeDefinition => "BadMapTypeDefinition",
+            PublicFunctionMustReturnResponse(..) => "PublicFunctionMustReturnResponse",
+            DefineVariableBadSignature => "DefineVariableBadSignature",
+            ReturnTypesMustMatch(..) => "ReturnTypesMustMatch",
+            DepositHandlerAlreadyDefined => "DepositHandlerAlreadyDefined",
+            BadDepositHandler(..) => "BadDepositHandler",
+            CircularReference(..) => "CircularReference",
+            NoSuchContract(..) => "NoSuchContract",
+            NoSuchPublicFunction(..) => "NoSuchPublicFunction",
+            PublicFunctionNotReadOnly(..) => "PublicFunctionNotReadOnly",
+            ContractAlreadyExists(..) => "ContractAlreadyExists",
+            ContractCallExpectName => "ContractCallExpectName",
+            NoSuchBlockInfoProperty(..) => "NoSuchBlockInfoProperty",
+            GetBlockInfoExpectPropertyName => "GetBlockInfoExpectPropertyName",
+            NoSuchWithdrawalAssetType(..) => "NoSuchWithdrawalAssetType",
+            GetWithdrawalInfoExpectAssetType => "GetWithdrawalInfoExpectAssetType",
+            NoSuchL1BlockInfoProperty(..) => "NoSuchL1BlockInfoProperty",
+            GetL1BlockInfoExpectPropertyName => "GetL1BlockInfoExpectPropertyName",
+            NameAlreadyUsed(..) => "NameAlreadyUsed",
+            NonFunctionApplication => "NonFunctionApplication",
+            ExpectedListApplication => "ExpectedListApplication",
+            ExpectedSequence(..) => "ExpectedSequence",
+            MaxLengthOverflow => "MaxLengthOverflow",
+            BadLetSyntax => "BadLetSyntax",
+            BadSyntaxBinding => "BadSyntaxBinding",
+            BadSyntaxExpectedListOfPairs => "BadSyntaxExpectedListOfPairs",
+            MaxContextDepthReached => "MaxContextDepthReached",
+            UndefinedFunction(..) => "UndefinedFunction",
+            UndefinedVariable(..) => "UndefinedVariable",
+            RequiresAtLeastArguments(..) => "RequiresAtLeastArguments",
+            IncorrectArgumentCount(..) => "IncorrectArgumentCount",
+            IfArmsMustMatch(..) => "IfArmsMustMatch",
+            MatchArmsMustMatch(..) => "MatchArmsMustMatch",
+            DefaultTypesMustMatch(..) => "DefaultTypesMustMatch",
+            TooManyExpressions => "TooManyExpressions",
+            IllegalOrUnknownFunctionApplication(..) => "IllegalOrUnknownFunctionApplication",
+            UnknownFunction(..) => "UnknownFunction",
+            NoSuchTrait(..) => "NoSuchTrait",
+            TraitReferenceUnknown(..) => "TraitReferenceUnknown",
+            TraitMethodUnknown(..) => "TraitMethodUnknown",
+            ExpectedTraitIdentifier => "ExpectedTraitIdentifier",
+            ImportTraitBadSignature => "ImportTraitBadSignature",
+            TraitReferenceNotAllowed => "TraitReferenceNotAllowed",
+            BadTraitImplementation(..) => "BadTraitImplementation",
+            DefineTraitBadSignature => "DefineTraitBadSignature",
+            UnexpectedTraitOrFieldReference => "UnexpectedTraitOrFieldReference",
+            TraitBasedContractCallInReadOnly => "TraitBasedContractCallInReadOnly",
+            ContractOfExpectsTrait => "ContractOfExpectsTrait",
+            InvalidCharactersDetected => "InvalidCharactersDetected",
+            InvalidSecp65k1Signature => "InvalidSecp65k1Signature",
+            WriteAttemptedInReadOnly => "WriteAttemptedInReadOnly",
+            AtBlockClosureMustBeReadOnly => "AtBlockClosureMustBeReadOnly",
+        };
+        Some(code.to_string())
+    }
 }
@@ -16,6 +16,7 @@
 
 pub mod analysis_db;
 pub mod arithmetic_checker;
+pub mod constant_folder;
 pub mod contract_interface_builder;
 pub mod errors;
 pub mod read_only_checker;
@@ -31,6 +32,7 @@ use crate::vm::costs::LimitedCostTracker;
 use crate::vm::database::STORE_CONTRACT_SRC_INTERFACE;
 use crate::vm::representations::SymbolicExpression;
 use crate::vm::types::{QualifiedContractIdentifier, TypeSignature};
+use crate::vm::ClarityVersion;
 
 pub use self::analysis_db::AnalysisDatabase;
 pub use self::errors::{CheckError, CheckErrors, CheckResult};
@@ -54,6 +56,8 @@ pub fn mem_type_check(snippet: &str) -> CheckResult<(Option<TypeSignature>, Cont
         &mut contract,
         &mut analysis_db,
         false,
+        false,
+        ClarityVersion::latest(),
         cost_tracker,
     ) {
         Ok(x) => {
@@ -84,6 +88,8 @@ pub fn type_check(
         expressions,
         analysis_db,
         insert_contract,
+        false,
+        ClarityVersion::latest(),
         // for the type check tests, the cost tracker's epoch doesn't
         //  matter: the costs in those tests are all free anyways.
         LimitedCostTracker::new_free(),
@@ -96,13 +102,18 @@ pub fn run_analysis(
     expressions: &mut [SymbolicExpression],
     analysis_db: &mut AnalysisDatabase,
     save_contract: bool,
+    allow_unchecked_intermediary_responses: bool,
+    clarity_version: ClarityVersion,
     cost_tracker: LimitedCostTracker,
 ) -> Result<ContractAnalysis, (CheckError, LimitedCostTracker)> {
     let mut contract_analysis = ContractAnalysis::new(
         contract_identifier.clone(),
         expressions.to_vec(),
+        clarity_version,
         cost_tracker,
     );
+    contract_analysis.allow_unchecked_intermediary_responses =
+        allow_unchecked_intermediary_responses;
     let result = analysis_db.execute(|db| {
         ReadOnlyChecker::run_pass(&mut contract_analysis, db)?;
         TypeChecker::run_pass(&mut contract_analysis, db)?;
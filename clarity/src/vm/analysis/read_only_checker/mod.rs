@@ -27,6 +27,7 @@ use crate::vm::types::{
 };
 
 use crate::vm::variables::NativeVariables;
+use crate::vm::ClarityVersion;
 use std::collections::HashMap;
 
 pub use super::errors::{
@@ -40,6 +41,7 @@ mod tests;
 pub struct ReadOnlyChecker<'a, 'b> {
     db: &'a mut AnalysisDatabase<'b>,
     defined_functions: HashMap<ClarityName, bool>,
+    clarity_version: ClarityVersion,
 }
 
 impl<'a, 'b> AnalysisPass for ReadOnlyChecker<'a, 'b> {
@@ -47,17 +49,21 @@ impl<'a, 'b> AnalysisPass for ReadOnlyChecker<'a, 'b> {
         contract_analysis: &mut ContractAnalysis,
         analysis_db: &mut AnalysisDatabase,
     ) -> CheckResult<()> {
-        let mut command = ReadOnlyChecker::new(analysis_db);
+        let mut command = ReadOnlyChecker::new(analysis_db, contract_analysis.clarity_version);
         command.run(contract_analysis)?;
         Ok(())
     }
 }
 
 impl<'a, 'b> ReadOnlyChecker<'a, 'b> {
-    fn new(db: &'a mut AnalysisDatabase<'b>) -> ReadOnlyChecker<'a, 'b> {
+    fn new(
+        db: &'a mut AnalysisDatabase<'b>,
+        clarity_version: ClarityVersion,
+    ) -> ReadOnlyChecker<'a, 'b> {
         Self {
             db,
             defined_functions: HashMap::new(),
+            clarity_version,
         }
     }
 
@@ -125,6 +131,9 @@ impl<'a, 'b> ReadOnlyChecker<'a, 'b> {
                 Trait { .. } | UseTrait { .. } | ImplTrait { .. } => {
                     // No arguments to (use-trait ...), (define-trait ...). or (impl-trait) are eval'ed.
                 }
+                DepositHandler { .. } => {
+                    // (define-deposit-handler) names a private function, which is checked where it is defined.
+                }
             }
         } else {
             self.check_read_only(expr)?;
@@ -161,7 +170,7 @@ impl<'a, 'b> ReadOnlyChecker<'a, 'b> {
         function: &str,
         args: &[SymbolicExpression],
     ) -> Option<CheckResult<bool>> {
-        NativeFunctions::lookup_by_name(function)
+        NativeFunctions::lookup_by_name_at_version(function, self.clarity_version)
             .map(|function| self.check_native_function(&function, args))
     }
 
@@ -174,14 +183,17 @@ impl<'a, 'b> ReadOnlyChecker<'a, 'b> {
 
         match function {
             Add | Subtract | Divide | Multiply | CmpGeq | CmpLeq | CmpLess | CmpGreater
-            | Modulo | Power | Sqrti | Log2 | BitwiseXOR | And | Or | Not | Hash160 | Sha256
-            | Keccak256 | Equals | If | Sha512 | Sha512Trunc256 | Secp256k1Recover
-            | Secp256k1Verify | ConsSome | ConsOkay | ConsError | DefaultTo | UnwrapRet
-            | UnwrapErrRet | IsOkay | IsNone | Asserts | Unwrap | UnwrapErr | Match | IsErr
-            | IsSome | TryRet | ToUInt | ToInt | Append | Concat | AsMaxLen | ContractOf
-            | PrincipalOf | ListCons | GetBlockInfo | TupleGet | TupleMerge | Len | Print
-            | AsContract | Begin | FetchVar | GetStxBalance | GetTokenBalance | GetAssetOwner
-            | GetTokenSupply | ElementAt | IndexOf => self.check_all_read_only(args),
+            | Modulo | Power | Sqrti | Log2 | BitwiseXOR | BitwiseAnd | BitwiseOr | BitwiseNot
+            | BitwiseLShift | BitwiseRShift | And | Or | Not | Hash160 | Sha256 | Keccak256
+            | Equals | If | Sha512 | Sha512Trunc256 | Secp256k1Recover | Secp256k1Verify
+            | ConsSome | ConsOkay | ConsError | DefaultTo | UnwrapRet | UnwrapErrRet | IsOkay
+            | IsNone | Asserts | Unwrap | UnwrapErr | Match | IsErr | IsSome | TryRet | ToUInt
+            | ToInt | Append | Concat | AsMaxLen | ContractOf | PrincipalOf | ListCons
+            | GetBlockInfo | TupleGet | TupleMerge | Len | Print | AsContract | Begin
+            | FetchVar | GetStxBalance | GetTokenBalance | GetAssetOwner | GetTokenSupply
+            | ElementAt | IndexOf | Slice | ReplaceAt | BuffToUIntBe | BuffToUIntLe
+            | GetWithdrawalInfo | VarExists | MapSize | GetL1BlockInfo | StxGetAccount
+            | ToConsensusBuff => self.check_all_read_only(args),
             AtBlock => {
                 check_argument_count(2, args)?;
 
@@ -196,6 +208,11 @@ impl<'a, 'b> ReadOnlyChecker<'a, 'b> {
                 check_argument_count(2, args)?;
                 self.check_all_read_only(args)
             }
+            FromConsensusBuff => {
+                // the first argument is a type, not an expression
+                check_argument_count(2, args)?;
+                self.check_read_only(&args[1])
+            }
             StxTransfer | StxBurn | SetEntry | DeleteEntry | InsertEntry | SetVar | MintAsset
             | MintToken | TransferAsset | TransferToken | BurnAsset | BurnToken | WithdrawAsset
             | WithdrawToken | StxWithdraw => {
@@ -260,7 +277,7 @@ impl<'a, 'b> ReadOnlyChecker<'a, 'b> {
                 }
                 Ok(true)
             }
-            ContractCall => {
+            ContractCall | TryContractCall => {
                 check_arguments_at_least(2, args)?;
 
                 let function_name = args[1]
@@ -47,6 +47,7 @@ pub struct ContractContext {
     non_fungible_tokens: HashMap<ClarityName, TypeSignature>,
     traits: HashMap<ClarityName, BTreeMap<ClarityName, FunctionSignature>>,
     pub implemented_traits: HashSet<TraitIdentifier>,
+    deposit_handler: Option<ClarityName>,
 }
 
 impl TypeMap {
@@ -86,6 +87,7 @@ impl ContractContext {
             non_fungible_tokens: HashMap::new(),
             traits: HashMap::new(),
             implemented_traits: HashSet::new(),
+            deposit_handler: None,
         }
     }
 
@@ -210,6 +212,51 @@ impl ContractContext {
         Ok(())
     }
 
+    /// Register the private function `name` as the contract's deposit handler.  It must take the
+    /// depositor, the asset and the amount, and return a response.
+    pub fn set_deposit_handler(&mut self, name: ClarityName) -> CheckResult<()> {
+        if self.deposit_handler.is_some() {
+            return Err(CheckError::new(CheckErrors::DepositHandlerAlreadyDefined));
+        }
+        let function_type = match self.private_function_types.get(&name) {
+            Some(function_type) => function_type,
+            None if self.get_function_type(&name).is_some() => {
+                return Err(CheckError::new(CheckErrors::BadDepositHandler(
+                    name.to_string(),
+                )))
+            }
+            None => {
+                return Err(CheckError::new(CheckErrors::UndefinedFunction(
+                    name.to_string(),
+                )))
+            }
+        };
+        let expected_args = [
+            TypeSignature::PrincipalType,
+            TypeSignature::new_option(TypeSignature::PrincipalType)?,
+            TypeSignature::UIntType,
+        ];
+        let is_valid = match function_type {
+            FunctionType::Fixed(function) => {
+                function.returns.is_response_type()
+                    && function.args.len() == expected_args.len()
+                    && function
+                        .args
+                        .iter()
+                        .zip(expected_args.iter())
+                        .all(|(arg, expected)| &arg.signature == expected)
+            }
+            _ => false,
+        };
+        if !is_valid {
+            return Err(CheckError::new(CheckErrors::BadDepositHandler(
+                name.to_string(),
+            )));
+        }
+        self.deposit_handler = Some(name);
+        Ok(())
+    }
+
     pub fn get_trait(&self, trait_name: &str) -> Option<&BTreeMap<ClarityName, FunctionSignature>> {
         self.traits.get(trait_name)
     }
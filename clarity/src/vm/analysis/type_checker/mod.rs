@@ -22,7 +22,7 @@ use crate::vm::costs::{
     analysis_typecheck_cost, cost_functions, runtime_cost, ClarityCostFunctionReference,
     CostErrors, CostOverflowingMath, CostTracker, ExecutionCost, LimitedCostTracker,
 };
-use crate::vm::functions::define::DefineFunctionsParsed;
+use crate::vm::functions::define::{DefineFunctions, DefineFunctionsParsed};
 use crate::vm::functions::NativeFunctions;
 use crate::vm::representations::SymbolicExpressionType::{
     Atom, AtomValue, Field, List, LiteralValue, TraitReference,
@@ -34,6 +34,7 @@ use crate::vm::types::{
     QualifiedContractIdentifier, TupleTypeSignature, TypeSignature, Value,
 };
 use crate::vm::variables::NativeVariables;
+use crate::vm::ClarityVersion;
 use std::collections::{BTreeMap, HashMap};
 use std::convert::TryInto;
 
@@ -49,6 +50,7 @@ pub use super::errors::{
 };
 use crate::vm::contexts::Environment;
 use crate::vm::costs::cost_functions::ClarityCostFunction;
+use crate::vm::diagnostic::Diagnostic;
 
 #[cfg(test)]
 mod tests;
@@ -75,6 +77,9 @@ pub struct TypeChecker<'a, 'b> {
     function_return_tracker: Option<Option<TypeSignature>>,
     db: &'a mut AnalysisDatabase<'b>,
     pub cost_track: LimitedCostTracker,
+    allow_unchecked_intermediary_responses: bool,
+    warnings: Vec<Diagnostic>,
+    clarity_version: ClarityVersion,
 }
 
 impl CostTracker for TypeChecker<'_, '_> {
@@ -115,7 +120,12 @@ impl AnalysisPass for TypeChecker<'_, '_> {
         analysis_db: &mut AnalysisDatabase,
     ) -> CheckResult<()> {
         let cost_track = contract_analysis.take_contract_cost_tracker();
-        let mut command = TypeChecker::new(analysis_db, cost_track);
+        let mut command = TypeChecker::new(
+            analysis_db,
+            cost_track,
+            contract_analysis.allow_unchecked_intermediary_responses,
+            contract_analysis.clarity_version,
+        );
         // run the analysis, and replace the cost tracker whether or not the
         //   analysis succeeded.
         match command.run(contract_analysis) {
@@ -287,14 +297,15 @@ fn trait_type_size(trait_sig: &BTreeMap<ClarityName, FunctionSignature>) -> Chec
     Ok(total_size)
 }
 
-fn type_reserved_variable(variable_name: &str) -> Option<TypeSignature> {
-    if let Some(variable) = NativeVariables::lookup_by_name(variable_name) {
+fn type_reserved_variable(variable_name: &str, version: ClarityVersion) -> Option<TypeSignature> {
+    if let Some(variable) = NativeVariables::lookup_by_name_at_version(variable_name, version) {
         use crate::vm::variables::NativeVariables::*;
         let var_type = match variable {
             TxSender => TypeSignature::PrincipalType,
             ContractCaller => TypeSignature::PrincipalType,
             BlockHeight => TypeSignature::UIntType,
             BurnBlockHeight => TypeSignature::UIntType,
+            L1BlockHeight => TypeSignature::UIntType,
             NativeNone => TypeSignature::new_option(no_type()).unwrap(),
             NativeTrue => TypeSignature::BoolType,
             NativeFalse => TypeSignature::BoolType,
@@ -315,6 +326,8 @@ impl<'a, 'b> TypeChecker<'a, 'b> {
     fn new(
         db: &'a mut AnalysisDatabase<'b>,
         cost_track: LimitedCostTracker,
+        allow_unchecked_intermediary_responses: bool,
+        clarity_version: ClarityVersion,
     ) -> TypeChecker<'a, 'b> {
         Self {
             db,
@@ -322,6 +335,9 @@ impl<'a, 'b> TypeChecker<'a, 'b> {
             contract_context: ContractContext::new(),
             function_return_tracker: None,
             type_map: TypeMap::new(),
+            allow_unchecked_intermediary_responses,
+            warnings: vec![],
+            clarity_version,
         }
     }
 
@@ -332,6 +348,7 @@ impl<'a, 'b> TypeChecker<'a, 'b> {
         self.contract_context
             .into_contract_analysis(contract_analysis);
         contract_analysis.type_map = Some(self.type_map);
+        contract_analysis.warnings.extend(self.warnings);
         self.cost_track
     }
 
@@ -438,16 +455,60 @@ impl<'a, 'b> TypeChecker<'a, 'b> {
         let actual_type = self.type_check(expr, context)?;
         analysis_typecheck_cost(self, expected_type, &actual_type)?;
 
-        if !expected_type.admits_type(&actual_type) {
+        if expected_type.admits_type(&actual_type) {
+            Ok(actual_type)
+        } else if self.admits_compatible_trait(expected_type, &actual_type)? {
+            Ok(expected_type.clone())
+        } else {
             let mut err: CheckError =
                 CheckErrors::TypeError(expected_type.clone(), actual_type).into();
             err.set_expression(expr);
             Err(err)
-        } else {
-            Ok(actual_type)
         }
     }
 
+    /// Since Clarity 2, a trait reference can be passed where a different trait is expected if
+    /// its trait has every function of the expected one, with the same signature.  For example, a
+    /// wrapper that defines its own `withdrawable-trait` can pass its `<withdrawable-trait>`
+    /// arguments on to contracts that expect the one in `trait-standards`.
+    fn admits_compatible_trait(
+        &mut self,
+        expected_type: &TypeSignature,
+        actual_type: &TypeSignature,
+    ) -> CheckResult<bool> {
+        let (expected_trait, actual_trait) = match (expected_type, actual_type) {
+            (
+                TypeSignature::TraitReferenceType(expected_trait),
+                TypeSignature::TraitReferenceType(actual_trait),
+            ) => (expected_trait, actual_trait),
+            _ => return Ok(false),
+        };
+        if self.clarity_version < ClarityVersion::Clarity2 {
+            return Ok(false);
+        }
+
+        let expected_functions = match self
+            .db
+            .get_defined_trait(&expected_trait.contract_identifier, &expected_trait.name)?
+        {
+            Some(functions) => functions,
+            None => return Ok(false),
+        };
+        runtime_cost(
+            ClarityCostFunction::AnalysisUseTraitEntry,
+            self,
+            trait_type_size(&expected_functions)?,
+        )?;
+
+        let actual_functions = match self.contract_context.get_trait(&actual_trait.name) {
+            Some(functions) => functions,
+            None => return Ok(false),
+        };
+        Ok(expected_functions
+            .iter()
+            .all(|(name, signature)| actual_functions.get(name) == Some(signature)))
+    }
+
     // Type checks an expression, recursively type checking its subexpressions
     pub fn type_check(&mut self, expr: &SymbolicExpression, context: &TypingContext) -> TypeResult {
         runtime_cost(ClarityCostFunction::AnalysisVisit, self, 0)?;
@@ -474,9 +535,21 @@ impl<'a, 'b> TypeChecker<'a, 'b> {
             .pop()
             .ok_or(CheckError::new(CheckErrors::CheckerImplementationFailure))?;
 
-        for type_return in types_returned.iter() {
-            if type_return.is_response_type() {
-                return Err(CheckErrors::UncheckedIntermediaryResponses.into());
+        let unchecked: Vec<_> = args
+            .iter()
+            .zip(types_returned.iter())
+            .filter(|(_, type_return)| type_return.is_response_type())
+            .map(|(arg, _)| arg.clone())
+            .collect();
+        if !unchecked.is_empty() {
+            if self.allow_unchecked_intermediary_responses {
+                let mut warning = Diagnostic::warn(&CheckErrors::UncheckedIntermediaryResponses);
+                warning.spans = unchecked.iter().map(|e| e.span.clone()).collect();
+                self.warnings.push(warning);
+            } else {
+                let mut err = CheckError::new(CheckErrors::UncheckedIntermediaryResponses);
+                err.set_expressions(&unchecked);
+                return Err(err);
             }
         }
         Ok(last_return)
@@ -611,7 +684,9 @@ impl<'a, 'b> TypeChecker<'a, 'b> {
         args: &[SymbolicExpression],
         context: &TypingContext,
     ) -> Option<TypeResult> {
-        if let Some(ref native_function) = NativeFunctions::lookup_by_name(function) {
+        if let Some(ref native_function) =
+            NativeFunctions::lookup_by_name_at_version(function, self.clarity_version)
+        {
             let typed_function = TypedNativeFunction::type_native_function(native_function);
             Some(typed_function.type_check_appliction(self, args, context))
         } else {
@@ -653,7 +728,7 @@ impl<'a, 'b> TypeChecker<'a, 'b> {
     fn lookup_variable(&mut self, name: &str, context: &TypingContext) -> TypeResult {
         runtime_cost(ClarityCostFunction::AnalysisLookupVariableConst, self, 0)?;
 
-        if let Some(type_result) = type_reserved_variable(name) {
+        if let Some(type_result) = type_reserved_variable(name, self.clarity_version) {
             Ok(type_result)
         } else if let Some(type_result) = self.contract_context.get_variable_type(name) {
             Ok(type_result.clone())
@@ -914,6 +989,15 @@ impl<'a, 'b> TypeChecker<'a, 'b> {
                     self.contract_context
                         .add_implemented_trait(trait_identifier.clone())?;
                 }
+                DefineFunctionsParsed::DepositHandler { name } => {
+                    if DefineFunctions::DepositHandler.get_version() > self.clarity_version {
+                        return Err(CheckErrors::UnknownFunction(
+                            DefineFunctions::DepositHandler.get_name(),
+                        )
+                        .into());
+                    }
+                    self.contract_context.set_deposit_handler(name.clone())?;
+                }
             };
             Ok(Some(()))
         } else {
@@ -15,11 +15,15 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::{no_type, FunctionType, TypeChecker, TypeResult, TypingContext};
-use crate::vm::analysis::errors::{check_argument_count, CheckError, CheckErrors, CheckResult};
+use crate::vm::analysis::errors::{
+    check_argument_count, check_arguments_at_least, check_arguments_at_most, CheckError,
+    CheckErrors, CheckResult,
+};
 use crate::vm::costs::cost_functions::ClarityCostFunction;
 use crate::vm::costs::{cost_functions, runtime_cost};
 use crate::vm::representations::SymbolicExpression;
 use crate::vm::types::{BlockInfoProperty, TupleTypeSignature, TypeSignature, MAX_VALUE_SIZE};
+use crate::vm::ClarityVersion;
 
 pub fn check_special_get_owner(
     checker: &mut TypeChecker,
@@ -261,3 +265,59 @@ pub fn check_special_burn_withdraw_token(
             .into(),
     )
 }
+
+/// Type check the optional miner tip which follows the `required` arguments of a withdrawal
+/// native.
+fn check_withdrawal_tip(
+    checker: &mut TypeChecker,
+    required: usize,
+    args: &[SymbolicExpression],
+    context: &TypingContext,
+) -> CheckResult<()> {
+    // Clarity 1 withdrawals take no tip
+    if checker.clarity_version < ClarityVersion::Clarity2 {
+        check_argument_count(required, args)?;
+        return Ok(());
+    }
+    check_arguments_at_least(required, args)?;
+    check_arguments_at_most(required + 1, args)?;
+
+    if let Some(tip) = args.get(required) {
+        checker.type_check_expects(tip, context, &TypeSignature::UIntType)?;
+    }
+    Ok(())
+}
+
+pub fn check_special_stx_withdraw(
+    checker: &mut TypeChecker,
+    args: &[SymbolicExpression],
+    context: &TypingContext,
+) -> TypeResult {
+    check_withdrawal_tip(checker, 2, args, context)?;
+
+    checker.type_check_expects(&args[0], context, &TypeSignature::UIntType)?;
+    checker.type_check_expects(&args[1], context, &TypeSignature::PrincipalType)?;
+
+    Ok(
+        TypeSignature::ResponseType(Box::new((TypeSignature::BoolType, TypeSignature::UIntType)))
+            .into(),
+    )
+}
+
+pub fn check_special_withdraw_asset(
+    checker: &mut TypeChecker,
+    args: &[SymbolicExpression],
+    context: &TypingContext,
+) -> TypeResult {
+    check_withdrawal_tip(checker, 3, args, context)?;
+    check_special_burn_or_withdraw_asset(checker, &args[..3], context)
+}
+
+pub fn check_special_withdraw_token(
+    checker: &mut TypeChecker,
+    args: &[SymbolicExpression],
+    context: &TypingContext,
+) -> TypeResult {
+    check_withdrawal_tip(checker, 3, args, context)?;
+    check_special_burn_withdraw_token(checker, &args[..3], context)
+}
@@ -21,8 +21,8 @@ use crate::vm::functions::tuples;
 
 use super::check_special_tuple_cons;
 use crate::vm::analysis::type_checker::{
-    check_arguments_at_least, no_type, CheckError, CheckErrors, TypeChecker, TypeResult,
-    TypingContext,
+    check_argument_count, check_arguments_at_least, no_type, CheckError, CheckErrors, TypeChecker,
+    TypeResult, TypingContext,
 };
 
 use crate::vm::costs::cost_functions::ClarityCostFunction;
@@ -68,6 +68,23 @@ pub fn check_special_fetch_entry(
     }
 }
 
+pub fn check_special_map_size(
+    checker: &mut TypeChecker,
+    args: &[SymbolicExpression],
+    _context: &TypingContext,
+) -> TypeResult {
+    check_argument_count(1, args)?;
+
+    let map_name = args[0].match_atom().ok_or(CheckErrors::BadMapName)?;
+
+    checker
+        .contract_context
+        .get_map_type(map_name)
+        .ok_or(CheckErrors::NoSuchMap(map_name.to_string()))?;
+
+    Ok(TypeSignature::UIntType)
+}
+
 pub fn check_special_delete_entry(
     checker: &mut TypeChecker,
     args: &[SymbolicExpression],
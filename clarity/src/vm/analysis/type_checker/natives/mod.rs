@@ -17,15 +17,16 @@
 use super::{
     check_argument_count, check_arguments_at_least, no_type, TypeChecker, TypeResult, TypingContext,
 };
-use crate::vm::analysis::errors::{CheckError, CheckErrors, CheckResult};
+use crate::vm::analysis::errors::{check_arguments_at_most, CheckError, CheckErrors, CheckResult};
 use crate::vm::errors::{Error as InterpError, RuntimeErrorType};
 use crate::vm::functions::{handle_binding_list, NativeFunctions};
 use crate::vm::types::{
-    BlockInfoProperty, FixedFunction, FunctionArg, FunctionSignature, FunctionType, PrincipalData,
-    TupleTypeSignature, TypeSignature, Value, BUFF_20, BUFF_32, BUFF_33, BUFF_64, BUFF_65,
-    MAX_VALUE_SIZE,
+    BlockInfoProperty, BufferLength, FixedFunction, FunctionArg, FunctionSignature, FunctionType,
+    L1BlockInfoProperty, PrincipalData, SequenceSubtype, TupleTypeSignature, TypeSignature, Value,
+    WithdrawalAssetType, BUFF_16, BUFF_20, BUFF_32, BUFF_33, BUFF_64, BUFF_65, MAX_VALUE_SIZE,
+    PRINT_TOPIC,
 };
-use crate::vm::{ClarityName, SymbolicExpression, SymbolicExpressionType};
+use crate::vm::{ClarityName, ClarityVersion, SymbolicExpression, SymbolicExpressionType};
 use std::convert::TryFrom;
 
 use crate::vm::costs::cost_functions::ClarityCostFunction;
@@ -71,7 +72,15 @@ fn check_special_print(
     args: &[SymbolicExpression],
     context: &TypingContext,
 ) -> TypeResult {
-    check_argument_count(1, args)?;
+    // Clarity 1 prints take no topic
+    if checker.clarity_version < ClarityVersion::Clarity2 {
+        check_argument_count(1, args)?;
+    }
+    check_arguments_at_least(1, args)?;
+    check_arguments_at_most(2, args)?;
+    if let Some(topic) = args.get(1) {
+        checker.type_check_expects(topic, context, &PRINT_TOPIC)?;
+    }
     checker.type_check(&args[0], context)
 }
 
@@ -278,6 +287,27 @@ fn check_special_fetch_var(
     Ok(value_type.clone())
 }
 
+fn check_special_var_exists(
+    checker: &mut TypeChecker,
+    args: &[SymbolicExpression],
+    _context: &TypingContext,
+) -> TypeResult {
+    check_argument_count(1, args)?;
+
+    let var_name = args[0]
+        .match_atom()
+        .ok_or(CheckError::new(CheckErrors::BadMapName))?;
+
+    checker
+        .contract_context
+        .get_persisted_variable_type(var_name)
+        .ok_or(CheckError::new(CheckErrors::NoSuchDataVariable(
+            var_name.to_string(),
+        )))?;
+
+    Ok(TypeSignature::BoolType)
+}
+
 fn check_special_set_var(
     checker: &mut TypeChecker,
     args: &[SymbolicExpression],
@@ -350,6 +380,54 @@ fn check_special_if(
         .map_err(|_| CheckErrors::IfArmsMustMatch(expr1.clone(), expr2.clone()).into())
 }
 
+fn check_special_bit_shift(
+    checker: &mut TypeChecker,
+    args: &[SymbolicExpression],
+    context: &TypingContext,
+) -> TypeResult {
+    check_argument_count(2, args)?;
+    // the shifted value may be an int or a uint, but the shift amount is always a uint
+    let input_type = checker.type_check(&args[0], context)?;
+    match input_type {
+        TypeSignature::IntType | TypeSignature::UIntType => {}
+        _ => {
+            return Err(CheckErrors::UnionTypeError(
+                vec![TypeSignature::IntType, TypeSignature::UIntType],
+                input_type,
+            )
+            .into())
+        }
+    }
+    checker.type_check_expects(&args[1], context, &TypeSignature::UIntType)?;
+    Ok(input_type)
+}
+
+fn check_special_to_consensus_buff(
+    checker: &mut TypeChecker,
+    args: &[SymbolicExpression],
+    context: &TypingContext,
+) -> TypeResult {
+    check_argument_count(1, args)?;
+    let input_type = checker.type_check(&args[0], context)?;
+    let buffer_max_len = BufferLength::try_from(input_type.max_serialized_size()?)?;
+    TypeSignature::new_option(TypeSignature::SequenceType(SequenceSubtype::BufferType(
+        buffer_max_len,
+    )))
+    .map_err(CheckError::from)
+}
+
+fn check_special_from_consensus_buff(
+    checker: &mut TypeChecker,
+    args: &[SymbolicExpression],
+    context: &TypingContext,
+) -> TypeResult {
+    check_argument_count(2, args)?;
+    // the first argument is a type, not an expression
+    let result_type = TypeSignature::parse_type_repr(&args[0], checker)?;
+    checker.type_check_expects(&args[1], context, &TypeSignature::max_buffer())?;
+    TypeSignature::new_option(result_type).map_err(CheckError::from)
+}
+
 fn check_contract_call(
     checker: &mut TypeChecker,
     args: &[SymbolicExpression],
@@ -439,6 +517,24 @@ fn check_contract_call(
     Ok(expected_sig.returns)
 }
 
+fn check_try_contract_call(
+    checker: &mut TypeChecker,
+    args: &[SymbolicExpression],
+    context: &TypingContext,
+) -> TypeResult {
+    let returns = check_contract_call(checker, args, context)?;
+    match returns {
+        TypeSignature::ResponseType(response_type) => {
+            let (ok_type, err_type) = *response_type;
+            Ok(TypeSignature::new_response(
+                ok_type,
+                TypeSignature::new_option(err_type)?,
+            )?)
+        }
+        _ => Err(CheckErrors::ExpectedResponseType(returns).into()),
+    }
+}
+
 fn check_contract_of(
     checker: &mut TypeChecker,
     args: &[SymbolicExpression],
@@ -520,6 +616,54 @@ fn check_get_block_info(
     Ok(TypeSignature::new_option(block_info_prop.type_result())?)
 }
 
+fn check_get_l1_block_info(
+    checker: &mut TypeChecker,
+    args: &[SymbolicExpression],
+    context: &TypingContext,
+) -> TypeResult {
+    check_arguments_at_least(2, args)?;
+
+    let block_info_prop_str = args[0].match_atom().ok_or(CheckError::new(
+        CheckErrors::GetL1BlockInfoExpectPropertyName,
+    ))?;
+
+    let block_info_prop =
+        L1BlockInfoProperty::lookup_by_name(block_info_prop_str).ok_or(CheckError::new(
+            CheckErrors::NoSuchL1BlockInfoProperty(block_info_prop_str.to_string()),
+        ))?;
+
+    checker.type_check_expects(&args[1], &context, &TypeSignature::UIntType)?;
+
+    Ok(TypeSignature::new_option(block_info_prop.type_result())?)
+}
+
+fn check_get_withdrawal_info(
+    checker: &mut TypeChecker,
+    args: &[SymbolicExpression],
+    context: &TypingContext,
+) -> TypeResult {
+    check_arguments_at_least(1, args)?;
+
+    let asset_type_str = args[0].match_atom().ok_or(CheckError::new(
+        CheckErrors::GetWithdrawalInfoExpectAssetType,
+    ))?;
+
+    let asset_type = WithdrawalAssetType::lookup_by_name(asset_type_str).ok_or(CheckError::new(
+        CheckErrors::NoSuchWithdrawalAssetType(asset_type_str.to_string()),
+    ))?;
+
+    let arg_types = asset_type.argument_types();
+    check_argument_count(arg_types.len() + 1, args)?;
+
+    for (arg, expected_type) in args[1..].iter().zip(arg_types.iter()) {
+        checker.type_check_expects(arg, &context, expected_type)?;
+    }
+
+    Ok(TypeSignature::new_option(
+        WithdrawalAssetType::type_result(),
+    )?)
+}
+
 impl TypedNativeFunction {
     pub fn type_check_appliction(
         &self,
@@ -540,16 +684,21 @@ impl TypedNativeFunction {
         use self::TypedNativeFunction::{Simple, Special};
         use crate::vm::functions::NativeFunctions::*;
         match function {
-            Add | Subtract | Divide | Multiply => {
+            Add | Subtract | Divide | Multiply | BitwiseAnd | BitwiseOr => {
                 Simple(SimpleNativeFunction(FunctionType::ArithmeticVariadic))
             }
             CmpGeq | CmpLeq | CmpLess | CmpGreater => {
                 Simple(SimpleNativeFunction(FunctionType::ArithmeticComparison))
             }
-            Sqrti | Log2 => Simple(SimpleNativeFunction(FunctionType::ArithmeticUnary)),
+            Sqrti | Log2 | BitwiseNot => {
+                Simple(SimpleNativeFunction(FunctionType::ArithmeticUnary))
+            }
             Modulo | Power | BitwiseXOR => {
                 Simple(SimpleNativeFunction(FunctionType::ArithmeticBinary))
             }
+            BitwiseLShift | BitwiseRShift => {
+                Special(SpecialNativeFunction(&check_special_bit_shift))
+            }
             And | Or => Simple(SimpleNativeFunction(FunctionType::Variadic(
                 TypeSignature::BoolType,
                 TypeSignature::BoolType,
@@ -570,6 +719,16 @@ impl TypedNativeFunction {
                 )],
                 returns: TypeSignature::IntType,
             }))),
+            BuffToUIntBe | BuffToUIntLe => {
+                Simple(SimpleNativeFunction(FunctionType::Fixed(FixedFunction {
+                    args: vec![FunctionArg::new(
+                        BUFF_16.clone(),
+                        ClarityName::try_from("value".to_owned())
+                            .expect("FAIL: ClarityName failed to accept default arg name"),
+                    )],
+                    returns: TypeSignature::UIntType,
+                })))
+            }
             Not => Simple(SimpleNativeFunction(FunctionType::Fixed(FixedFunction {
                 args: vec![FunctionArg::new(
                     TypeSignature::BoolType,
@@ -628,6 +787,20 @@ impl TypedNativeFunction {
                 )],
                 returns: TypeSignature::UIntType,
             }))),
+            StxGetAccount => Simple(SimpleNativeFunction(FunctionType::Fixed(FixedFunction {
+                args: vec![FunctionArg::new(
+                    TypeSignature::PrincipalType,
+                    ClarityName::try_from("owner".to_owned())
+                        .expect("FAIL: ClarityName failed to accept default arg name"),
+                )],
+                returns: TupleTypeSignature::try_from(vec![
+                    ("unlocked".into(), TypeSignature::UIntType),
+                    ("total".into(), TypeSignature::UIntType),
+                    ("pending-withdrawal".into(), TypeSignature::UIntType),
+                ])
+                .expect("FAIL: STX account tuple type is invalid")
+                .into(),
+            }))),
             StxTransfer => Simple(SimpleNativeFunction(FunctionType::Fixed(FixedFunction {
                 args: vec![
                     FunctionArg::new(
@@ -671,25 +844,7 @@ impl TypedNativeFunction {
                 )
                 .unwrap(),
             }))),
-            StxWithdraw => Simple(SimpleNativeFunction(FunctionType::Fixed(FixedFunction {
-                args: vec![
-                    FunctionArg::new(
-                        TypeSignature::UIntType,
-                        ClarityName::try_from("amount".to_owned())
-                            .expect("FAIL: ClarityName failed to accept default arg name"),
-                    ),
-                    FunctionArg::new(
-                        TypeSignature::PrincipalType,
-                        ClarityName::try_from("sender".to_owned())
-                            .expect("FAIL: ClarityName failed to accept default arg name"),
-                    ),
-                ],
-                returns: TypeSignature::new_response(
-                    TypeSignature::BoolType,
-                    TypeSignature::UIntType,
-                )
-                .unwrap(),
-            }))),
+            StxWithdraw => Special(SpecialNativeFunction(&assets::check_special_stx_withdraw)),
             GetTokenBalance => Special(SpecialNativeFunction(&assets::check_special_get_balance)),
             GetAssetOwner => Special(SpecialNativeFunction(&assets::check_special_get_owner)),
             TransferToken => Special(SpecialNativeFunction(&assets::check_special_transfer_token)),
@@ -702,12 +857,8 @@ impl TypedNativeFunction {
             BurnToken => Special(SpecialNativeFunction(
                 &assets::check_special_burn_withdraw_token,
             )),
-            WithdrawAsset => Special(SpecialNativeFunction(
-                &assets::check_special_burn_or_withdraw_asset,
-            )),
-            WithdrawToken => Special(SpecialNativeFunction(
-                &assets::check_special_burn_withdraw_token,
-            )),
+            WithdrawAsset => Special(SpecialNativeFunction(&assets::check_special_withdraw_asset)),
+            WithdrawToken => Special(SpecialNativeFunction(&assets::check_special_withdraw_token)),
             GetTokenSupply => Special(SpecialNativeFunction(
                 &assets::check_special_get_token_supply,
             )),
@@ -725,6 +876,10 @@ impl TypedNativeFunction {
             Len => Special(SpecialNativeFunction(&sequences::check_special_len)),
             ElementAt => Special(SpecialNativeFunction(&sequences::check_special_element_at)),
             IndexOf => Special(SpecialNativeFunction(&sequences::check_special_index_of)),
+            Slice => Special(SpecialNativeFunction(&sequences::check_special_slice)),
+            ReplaceAt => Special(SpecialNativeFunction(&sequences::check_special_replace_at)),
+            ToConsensusBuff => Special(SpecialNativeFunction(&check_special_to_consensus_buff)),
+            FromConsensusBuff => Special(SpecialNativeFunction(&check_special_from_consensus_buff)),
             ListCons => Special(SpecialNativeFunction(&check_special_list_cons)),
             FetchEntry => Special(SpecialNativeFunction(&maps::check_special_fetch_entry)),
             SetEntry => Special(SpecialNativeFunction(&maps::check_special_set_entry)),
@@ -737,9 +892,14 @@ impl TypedNativeFunction {
             Print => Special(SpecialNativeFunction(&check_special_print)),
             AsContract => Special(SpecialNativeFunction(&check_special_as_contract)),
             ContractCall => Special(SpecialNativeFunction(&check_contract_call)),
+            TryContractCall => Special(SpecialNativeFunction(&check_try_contract_call)),
             ContractOf => Special(SpecialNativeFunction(&check_contract_of)),
             PrincipalOf => Special(SpecialNativeFunction(&check_principal_of)),
             GetBlockInfo => Special(SpecialNativeFunction(&check_get_block_info)),
+            GetWithdrawalInfo => Special(SpecialNativeFunction(&check_get_withdrawal_info)),
+            GetL1BlockInfo => Special(SpecialNativeFunction(&check_get_l1_block_info)),
+            VarExists => Special(SpecialNativeFunction(&check_special_var_exists)),
+            MapSize => Special(SpecialNativeFunction(&maps::check_special_map_size)),
             ConsSome => Special(SpecialNativeFunction(&options::check_special_some)),
             ConsOkay => Special(SpecialNativeFunction(&options::check_special_okay)),
             ConsError => Special(SpecialNativeFunction(&options::check_special_error)),
@@ -37,7 +37,9 @@ fn get_simple_native_or_user_define(
     checker: &mut TypeChecker,
 ) -> CheckResult<FunctionType> {
     runtime_cost(ClarityCostFunction::AnalysisLookupFunction, checker, 0)?;
-    if let Some(ref native_function) = NativeFunctions::lookup_by_name(function_name) {
+    if let Some(ref native_function) =
+        NativeFunctions::lookup_by_name_at_version(function_name, checker.clarity_version)
+    {
         if let TypedNativeFunction::Simple(SimpleNativeFunction(function_type)) =
             TypedNativeFunction::type_native_function(native_function)
         {
@@ -389,3 +391,58 @@ pub fn check_special_index_of(
 
     TypeSignature::new_option(TypeSignature::UIntType).map_err(|e| e.into())
 }
+
+pub fn check_special_slice(
+    checker: &mut TypeChecker,
+    args: &[SymbolicExpression],
+    context: &TypingContext,
+) -> TypeResult {
+    check_argument_count(3, args)?;
+
+    checker.type_check_expects(&args[1], context, &TypeSignature::UIntType)?;
+    checker.type_check_expects(&args[2], context, &TypeSignature::UIntType)?;
+
+    let sequence_type = checker.type_check(&args[0], context)?;
+    runtime_cost(ClarityCostFunction::AnalysisIterableFunc, checker, 0)?;
+
+    match sequence_type {
+        TypeSignature::SequenceType(_) => {
+            TypeSignature::new_option(sequence_type).map_err(|e| e.into())
+        }
+        _ => Err(CheckErrors::ExpectedSequence(sequence_type).into()),
+    }
+}
+
+pub fn check_special_replace_at(
+    checker: &mut TypeChecker,
+    args: &[SymbolicExpression],
+    context: &TypingContext,
+) -> TypeResult {
+    check_argument_count(3, args)?;
+
+    let sequence_type = checker.type_check(&args[0], context)?;
+    runtime_cost(ClarityCostFunction::AnalysisIterableFunc, checker, 0)?;
+
+    checker.type_check_expects(&args[1], context, &TypeSignature::UIntType)?;
+
+    let return_type = match sequence_type {
+        TypeSignature::SequenceType(ListType(list)) => {
+            // like `append`, the new element only needs to share a supertype with the list's
+            //  items, and the resulting list is typed with that supertype
+            let element_type = checker.type_check(&args[2], context)?;
+            let (entry_type, max_len) = list.destruct();
+
+            analysis_typecheck_cost(checker, &entry_type, &element_type)?;
+
+            let entry_type = TypeSignature::least_supertype(&entry_type, &element_type)?;
+            TypeSignature::list_of(entry_type, max_len)?
+        }
+        TypeSignature::SequenceType(ref sequence_subtype) => {
+            checker.type_check_expects(&args[2], context, &sequence_subtype.unit_type())?;
+            sequence_type
+        }
+        _ => return Err(CheckErrors::ExpectedSequence(sequence_type).into()),
+    };
+
+    TypeSignature::new_option(return_type).map_err(|e| e.into())
+}
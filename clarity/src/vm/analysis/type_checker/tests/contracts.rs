@@ -19,15 +19,15 @@ use serde_json;
 
 use crate::vm::analysis::errors::CheckErrors;
 use crate::vm::analysis::mem_type_check;
-use crate::vm::analysis::type_check;
 use crate::vm::analysis::{contract_interface_builder::build_contract_interface, AnalysisDatabase};
+use crate::vm::analysis::{run_analysis, type_check};
 use crate::vm::ast::parse;
 use crate::vm::database::MemoryBackingStore;
 use crate::vm::types::QualifiedContractIdentifier;
 use crate::vm::{
     analysis::{CheckError, ContractAnalysis},
     costs::LimitedCostTracker,
-    SymbolicExpression,
+    ClarityVersion, SymbolicExpression,
 };
 
 const SIMPLE_TOKENS: &str = "(define-map tokens { account: principal } { balance: uint })
@@ -514,6 +514,145 @@ fn test_same_function_name() {
     .unwrap();
 }
 
+#[test]
+fn test_try_contract_call() {
+    let ca_id = QualifiedContractIdentifier::local("contract-a").unwrap();
+    let cb_id = QualifiedContractIdentifier::local("contract-b").unwrap();
+    let cc_id = QualifiedContractIdentifier::local("contract-c").unwrap();
+
+    let contract_b = "(define-public (foo-function (a int)) (if (> a 0) (ok a) (err u1)))
+         (define-read-only (bar-function (a int)) (+ a 1))";
+
+    // the callee's err type is wrapped in an optional
+    let contract_a = "(define-public (call-foo (a int))
+           (match (try-contract-call? .contract-b foo-function a)
+             value (ok value)
+             error (err (+ (unwrap! error (err u0)) u1))))";
+
+    // the callee must return a response
+    let contract_c = "(define-public (call-bar (a int))
+           (try-contract-call? .contract-b bar-function a))";
+
+    let mut ca = parse(&ca_id, contract_a).unwrap();
+    let mut cb = parse(&cb_id, contract_b).unwrap();
+    let mut cc = parse(&cc_id, contract_c).unwrap();
+    let mut marf = MemoryBackingStore::new();
+    let mut db = marf.as_analysis_db();
+
+    db.execute(|db| {
+        db.test_insert_contract_hash(&cb_id);
+        type_check(&cb_id, &mut cb, db, true)?;
+        type_check(&ca_id, &mut ca, db, true)
+    })
+    .unwrap();
+
+    let err = db
+        .execute(|db| type_check(&cc_id, &mut cc, db, true))
+        .unwrap_err();
+    assert!(match err.err {
+        CheckErrors::ExpectedResponseType(_) => true,
+        _ => false,
+    });
+}
+
+#[test]
+fn test_withdraw_through_trait() {
+    let wrapper_id = QualifiedContractIdentifier::local("wrapper").unwrap();
+    let ft_id = QualifiedContractIdentifier::local("ft-contract").unwrap();
+    let bad_ft_id = QualifiedContractIdentifier::local("bad-ft-contract").unwrap();
+    let outer_wrapper_id = QualifiedContractIdentifier::local("outer-wrapper").unwrap();
+
+    let wrapper = "(define-trait withdrawable-trait
+           ((withdraw (uint principal) (response bool uint))))
+         (define-public (withdraw-from (token <withdrawable-trait>) (amount uint))
+           (contract-call? token withdraw amount tx-sender))";
+
+    // the token only lets the owner of the tokens withdraw them
+    let ft_contract = "(impl-trait .wrapper.withdrawable-trait)
+         (define-fungible-token ft-token)
+         (define-public (withdraw (amount uint) (sender principal))
+           (begin
+             (asserts! (is-eq tx-sender sender) (err u1001))
+             (ft-withdraw? ft-token amount sender)))";
+
+    // the withdrawn amount must be a uint
+    let bad_ft_contract = "(impl-trait .wrapper.withdrawable-trait)
+         (define-fungible-token ft-token)
+         (define-public (withdraw (amount int) (sender principal))
+           (begin
+             (asserts! (is-eq tx-sender sender) (err u1001))
+             (ft-withdraw? ft-token (to-uint amount) sender)))";
+
+    // a contract with its own, compatible trait can pass its token on to the wrapper
+    let outer_wrapper = "(define-trait withdrawable-trait
+           ((withdraw (uint principal) (response bool uint))
+            (get-balance (principal) (response uint uint))))
+         (define-public (withdraw-all (token <withdrawable-trait>))
+           (let ((balance (try! (contract-call? token get-balance tx-sender))))
+             (contract-call? .wrapper withdraw-from token balance)))";
+
+    let mut wrapper = parse(&wrapper_id, wrapper).unwrap();
+    let mut ft_contract = parse(&ft_id, ft_contract).unwrap();
+    let mut bad_ft_contract = parse(&bad_ft_id, bad_ft_contract).unwrap();
+    let mut outer_wrapper = parse(&outer_wrapper_id, outer_wrapper).unwrap();
+    let mut marf = MemoryBackingStore::new();
+    let mut db = marf.as_analysis_db();
+
+    db.execute(|db| {
+        db.test_insert_contract_hash(&wrapper_id);
+        type_check(&wrapper_id, &mut wrapper, db, true)?;
+        type_check(&ft_id, &mut ft_contract, db, true)
+    })
+    .unwrap();
+
+    let err = db
+        .execute(|db| type_check(&bad_ft_id, &mut bad_ft_contract, db, true))
+        .unwrap_err();
+    assert!(match err.err {
+        CheckErrors::BadTraitImplementation(_, _) => true,
+        _ => false,
+    });
+
+    // Clarity 1 only admits the exact trait
+    let err = db
+        .execute(|db| {
+            run_analysis(
+                &outer_wrapper_id,
+                &mut outer_wrapper,
+                db,
+                false,
+                false,
+                ClarityVersion::Clarity1,
+                LimitedCostTracker::new_free(),
+            )
+            .map_err(|(e, _)| e)
+        })
+        .unwrap_err();
+    assert!(match err.err {
+        CheckErrors::TypeError(_, _) => true,
+        _ => false,
+    });
+    db.execute(|db| type_check(&outer_wrapper_id, &mut outer_wrapper, db, false))
+        .unwrap();
+
+    // a trait that is missing the expected function is not compatible
+    let mut incompatible = parse(
+        &outer_wrapper_id,
+        "(define-trait withdrawable-trait
+           ((withdraw (int principal) (response bool uint))))
+         (define-public (withdraw-one (token <withdrawable-trait>))
+           (contract-call? .wrapper withdraw-from token u1))",
+    )
+    .unwrap();
+    let err = db
+        .execute(|db| type_check(&outer_wrapper_id, &mut incompatible, db, false))
+        .unwrap_err();
+    assert!(match err.err {
+        CheckErrors::TypeError(_, _) => true,
+        _ => false,
+    });
+}
+
 #[test]
 fn test_expects() {
     use crate::vm::analysis::type_check;
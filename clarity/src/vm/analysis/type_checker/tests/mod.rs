@@ -26,12 +26,16 @@ use crate::vm::contexts::OwnedEnvironment;
 use crate::vm::representations::SymbolicExpression;
 use crate::vm::types::{
     FixedFunction, FunctionType, PrincipalData, QualifiedContractIdentifier, TypeSignature, Value,
-    BUFF_32, BUFF_64,
+    BUFF_1, BUFF_16, BUFF_32, BUFF_64, PRINT_TOPIC,
 };
 
+use crate::vm::analysis::run_analysis;
+use crate::vm::costs::LimitedCostTracker;
 use crate::vm::database::MemoryBackingStore;
+use crate::vm::diagnostic::{Diagnostic, Level};
 use crate::vm::types::TypeSignature::{BoolType, IntType, PrincipalType, SequenceType, UIntType};
 use crate::vm::types::{SequenceSubtype::*, StringSubtype::*};
+use crate::vm::ClarityVersion;
 
 use std::convert::TryInto;
 
@@ -94,6 +98,76 @@ fn test_get_block_info() {
     }
 }
 
+#[test]
+fn test_get_l1_block_info() {
+    let good = [
+        "(get-l1-block-info? header-hash u1)",
+        "(get-l1-block-info? time (* u2 u3))",
+    ];
+    let expected = ["(optional (buff 32))", "(optional uint)"];
+
+    let bad = [
+        "(get-l1-block-info? vrf-seed u1)",
+        "(get-l1-block-info? time 1)",
+        "(get-l1-block-info? time)",
+    ];
+    let bad_expected = [
+        CheckErrors::NoSuchL1BlockInfoProperty("vrf-seed".to_string()),
+        CheckErrors::TypeError(UIntType, IntType),
+        CheckErrors::RequiresAtLeastArguments(2, 1),
+    ];
+
+    for (good_test, expected) in good.iter().zip(expected.iter()) {
+        assert_eq!(
+            expected,
+            &format!("{}", type_check_helper(&good_test).unwrap())
+        );
+    }
+
+    for (bad_test, expected) in bad.iter().zip(bad_expected.iter()) {
+        assert_eq!(expected, &type_check_helper(&bad_test).unwrap_err().err);
+    }
+}
+
+#[test]
+fn test_get_withdrawal_info() {
+    let good = [
+        "(get-withdrawal-info? stx u1 u0 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR u100)",
+        "(get-withdrawal-info? ft u1 u0 tx-sender 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR.ft u100)",
+        "(get-withdrawal-info? nft (+ u1 u2) u0 tx-sender 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR.nft u7)",
+    ];
+    let expected =
+        "(optional (tuple (withdrawal-leaf-hash (buff 32)) (withdrawal-root (buff 32))))";
+
+    let bad = [
+        "(get-withdrawal-info? stacks u1 u0 tx-sender u100)",
+        "(get-withdrawal-info? stx 1 u0 tx-sender u100)",
+        "(get-withdrawal-info? stx u1 u0 u100 u100)",
+        "(get-withdrawal-info? stx u1 u0 tx-sender tx-sender u100)",
+        "(get-withdrawal-info? nft u1 u0 tx-sender u7)",
+        "(get-withdrawal-info?)",
+    ];
+    let bad_expected = [
+        CheckErrors::NoSuchWithdrawalAssetType("stacks".to_string()),
+        CheckErrors::TypeError(UIntType, IntType),
+        CheckErrors::TypeError(PrincipalType, UIntType),
+        CheckErrors::IncorrectArgumentCount(5, 6),
+        CheckErrors::IncorrectArgumentCount(6, 5),
+        CheckErrors::RequiresAtLeastArguments(1, 0),
+    ];
+
+    for good_test in good.iter() {
+        assert_eq!(
+            expected,
+            &format!("{}", type_check_helper(&good_test).unwrap())
+        );
+    }
+
+    for (bad_test, expected) in bad.iter().zip(bad_expected.iter()) {
+        assert_eq!(expected, &type_check_helper(&bad_test).unwrap_err().err);
+    }
+}
+
 #[test]
 fn test_define_trait() {
     let good = [
@@ -175,6 +249,50 @@ fn test_impl_trait() {
     }
 }
 
+#[test]
+fn test_define_deposit_handler() {
+    let handler = "(define-private (on-deposit (depositor principal) (asset (optional principal)) (amount uint))
+          (ok true))";
+    let good = [
+        format!("{} (define-deposit-handler on-deposit)", handler),
+        // the handler may be registered before it is defined
+        format!("(define-deposit-handler on-deposit) {}", handler),
+    ];
+    for good_test in good.iter() {
+        mem_type_check(good_test).unwrap();
+    }
+
+    let bad = [
+        format!(
+            "{} (define-deposit-handler on-deposit) (define-deposit-handler on-deposit)",
+            handler
+        ),
+        "(define-deposit-handler on-deposit)".to_string(),
+        "(define-public (on-deposit (depositor principal) (asset (optional principal)) (amount uint))
+          (ok true))
+         (define-deposit-handler on-deposit)"
+            .to_string(),
+        "(define-private (on-deposit (depositor principal) (asset principal) (amount uint))
+          (ok true))
+         (define-deposit-handler on-deposit)"
+            .to_string(),
+        "(define-private (on-deposit (depositor principal) (asset (optional principal)) (amount uint))
+          true)
+         (define-deposit-handler on-deposit)"
+            .to_string(),
+    ];
+    let bad_expected = [
+        CheckErrors::DepositHandlerAlreadyDefined,
+        CheckErrors::UndefinedFunction("on-deposit".to_string()),
+        CheckErrors::BadDepositHandler("on-deposit".to_string()),
+        CheckErrors::BadDepositHandler("on-deposit".to_string()),
+        CheckErrors::BadDepositHandler("on-deposit".to_string()),
+    ];
+    for (bad_test, expected) in bad.iter().zip(bad_expected.iter()) {
+        assert_eq!(expected, &mem_type_check(bad_test).unwrap_err().err);
+    }
+}
+
 #[test]
 fn test_stx_ops() {
     let good = [
@@ -623,6 +741,45 @@ fn test_simple_lets() {
     }
 }
 
+#[test]
+fn test_print() {
+    let good = [
+        "(print (+ 1 2))",
+        "(print u1 \"topic\")",
+        "(let ((x (list 1 2))) (print x \"a-topic\"))",
+    ];
+
+    let expected = ["int", "uint", "(list 2 int)"];
+
+    let bad = [
+        "(print)",
+        "(print 1 \"topic\" \"another\")",
+        "(print 1 u\"topic\")",
+        "(print 1 \"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\")",
+    ];
+
+    let bad_expected = [
+        CheckErrors::RequiresAtLeastArguments(1, 0),
+        CheckErrors::IncorrectArgumentCount(2, 3),
+        CheckErrors::TypeError(
+            PRINT_TOPIC.clone(),
+            SequenceType(StringType(UTF8(5u32.try_into().unwrap()))),
+        ),
+        CheckErrors::TypeError(PRINT_TOPIC.clone(), ascii_type(65)),
+    ];
+
+    for (good_test, expected) in good.iter().zip(expected.iter()) {
+        assert_eq!(
+            expected,
+            &format!("{}", type_check_helper(&good_test).unwrap())
+        );
+    }
+
+    for (bad_test, expected) in bad.iter().zip(bad_expected.iter()) {
+        assert_eq!(expected, &type_check_helper(&bad_test).unwrap_err().err);
+    }
+}
+
 #[test]
 fn test_index_of() {
     let good = [
@@ -715,6 +872,105 @@ fn test_element_at() {
     }
 }
 
+#[test]
+fn test_slice() {
+    let good = [
+        "(slice (list 1 2 3 4 5) u1 u3)",
+        "(slice \"abcd\" u0 (+ u1 u2))",
+        "(slice 0xfedb u0 u1)",
+        "(slice u\"abcd\" u1 u2)",
+    ];
+
+    let expected = [
+        "(optional (list 5 int))",
+        "(optional (string-ascii 4))",
+        "(optional (buff 2))",
+        "(optional (string-utf8 4))",
+    ];
+
+    let bad = [
+        "(slice (list 1 2 3 4 5) 1 u3)",
+        "(slice (list 1 2 3 4 5) u1 3)",
+        "(slice 3 u0 u1)",
+        "(slice (list 1 2 3) u0)",
+    ];
+
+    let bad_expected = [
+        CheckErrors::TypeError(TypeSignature::UIntType, TypeSignature::IntType),
+        CheckErrors::TypeError(TypeSignature::UIntType, TypeSignature::IntType),
+        CheckErrors::ExpectedSequence(TypeSignature::IntType),
+        CheckErrors::IncorrectArgumentCount(3, 2),
+    ];
+
+    for (good_test, expected) in good.iter().zip(expected.iter()) {
+        assert_eq!(
+            expected,
+            &format!("{}", type_check_helper(&good_test).unwrap())
+        );
+    }
+
+    for (bad_test, expected) in bad.iter().zip(bad_expected.iter()) {
+        assert_eq!(expected, &type_check_helper(&bad_test).unwrap_err().err);
+    }
+}
+
+#[test]
+fn test_replace_at() {
+    let good = [
+        "(replace-at? (list 1 2 3 4 5) u1 7)",
+        "(replace-at? \"abcd\" u0 \"z\")",
+        "(replace-at? 0xfedb u0 0x00)",
+        "(replace-at? u\"abcd\" u1 u\"e\")",
+        "(replace-at? (list (some 1) none) u1 none)",
+        "(replace-at? (list) u0 1)",
+        "(replace-at? (list none) u0 (some 1))",
+        "(replace-at? (list (list)) u0 (list 1))",
+        "(replace-at? (list 0x01 0x0203) u0 0x04)",
+    ];
+
+    let expected = [
+        "(optional (list 5 int))",
+        "(optional (string-ascii 4))",
+        "(optional (buff 2))",
+        "(optional (string-utf8 4))",
+        "(optional (list 2 (optional int)))",
+        "(optional (list 0 int))",
+        "(optional (list 1 (optional int)))",
+        "(optional (list 1 (list 1 int)))",
+        "(optional (list 2 (buff 2)))",
+    ];
+
+    let bad = [
+        "(replace-at? (list 1 2 3 4 5) 1 7)",
+        "(replace-at? (list 1 2 3 4 5) u1 u7)",
+        "(replace-at? 0xfedb u0 0x0001)",
+        "(replace-at? 3 u0 1)",
+        "(replace-at? (list 1 2 3) u0)",
+    ];
+
+    let bad_expected = [
+        CheckErrors::TypeError(TypeSignature::UIntType, TypeSignature::IntType),
+        CheckErrors::TypeError(TypeSignature::IntType, TypeSignature::UIntType),
+        CheckErrors::TypeError(
+            BUFF_1.clone(),
+            SequenceType(BufferType(2u32.try_into().unwrap())),
+        ),
+        CheckErrors::ExpectedSequence(TypeSignature::IntType),
+        CheckErrors::IncorrectArgumentCount(3, 2),
+    ];
+
+    for (good_test, expected) in good.iter().zip(expected.iter()) {
+        assert_eq!(
+            expected,
+            &format!("{}", type_check_helper(&good_test).unwrap())
+        );
+    }
+
+    for (bad_test, expected) in bad.iter().zip(bad_expected.iter()) {
+        assert_eq!(expected, &type_check_helper(&bad_test).unwrap_err().err);
+    }
+}
+
 #[test]
 fn test_eqs() {
     let good = [
@@ -921,12 +1177,12 @@ fn test_buff_fold() {
     let good = [
         "(define-private (get-len (x (buff 1)) (acc uint)) (+ acc u1))
         (fold get-len 0x000102030405 u0)",
-        "(define-private (slice (x (buff 1)) (acc (tuple (limit uint) (cursor uint) (data (buff 10)))))
+        "(define-private (slice-step (x (buff 1)) (acc (tuple (limit uint) (cursor uint) (data (buff 10)))))
             (if (< (get cursor acc) (get limit acc))
                 (let ((data (default-to (get data acc) (as-max-len? (concat (get data acc) x) u10))))
                     (tuple (limit (get limit acc)) (cursor (+ u1 (get cursor acc))) (data data)))
                 acc))
-        (fold slice 0x00010203040506070809 (tuple (limit u5) (cursor u0) (data 0x)))"];
+        (fold slice-step 0x00010203040506070809 (tuple (limit u5) (cursor u0) (data 0x)))"];
     let expected = [
         "uint",
         "(tuple (cursor uint) (data (buff 10)) (limit uint))",
@@ -1234,6 +1490,148 @@ fn test_simple_uints() {
     }
 }
 
+#[test]
+fn test_buff_to_uint() {
+    let good = [
+        "(buff-to-uint-be 0x0102)",
+        "(buff-to-uint-le 0x)",
+        "(buff-to-uint-be 0x00000000000000000000000000000001)",
+    ];
+
+    let expected = ["uint", "uint", "uint"];
+
+    let bad = [
+        "(buff-to-uint-be u1)",
+        "(buff-to-uint-le \"01\")",
+        "(buff-to-uint-be 0x0000000000000000000000000000000000)",
+        "(buff-to-uint-le (sha256 u1))",
+        "(buff-to-uint-le 0x01 0x02)",
+    ];
+
+    let bad_expected = [
+        CheckErrors::TypeError(BUFF_16.clone(), UIntType),
+        CheckErrors::TypeError(
+            BUFF_16.clone(),
+            SequenceType(StringType(ASCII(2u32.try_into().unwrap()))),
+        ),
+        CheckErrors::TypeError(
+            BUFF_16.clone(),
+            SequenceType(BufferType(17u32.try_into().unwrap())),
+        ),
+        CheckErrors::TypeError(BUFF_16.clone(), BUFF_32.clone()),
+        CheckErrors::IncorrectArgumentCount(1, 2),
+    ];
+
+    for (good_test, expected) in good.iter().zip(expected.iter()) {
+        let type_sig = mem_type_check(good_test).unwrap().0.unwrap();
+        assert_eq!(expected, &type_sig.to_string());
+    }
+
+    for (bad_test, expected) in bad.iter().zip(bad_expected.iter()) {
+        assert_eq!(&mem_type_check(bad_test).unwrap_err().err, expected);
+    }
+}
+
+#[test]
+fn test_bitwise() {
+    let good = [
+        "(bit-and 24 16)",
+        "(bit-and u24 u16 u8)",
+        "(bit-or 4 8)",
+        "(bit-or u4 u8)",
+        "(bit-not -1)",
+        "(bit-not u1)",
+        "(bit-shift-left 1 u2)",
+        "(bit-shift-left u1 u2)",
+        "(bit-shift-right -8 u2)",
+        "(bit-shift-right u8 (+ u1 u1))",
+    ];
+
+    let expected = [
+        "int", "uint", "int", "uint", "int", "uint", "int", "uint", "int", "uint",
+    ];
+
+    let bad = [
+        "(bit-and 1 u2)",
+        "(bit-or)",
+        "(bit-not 1 2)",
+        "(bit-not \"a\")",
+        "(bit-shift-left 1 2)",
+        "(bit-shift-right true u1)",
+        "(bit-shift-left 1)",
+    ];
+
+    let bad_expected = [
+        CheckErrors::TypeError(IntType, UIntType),
+        CheckErrors::RequiresAtLeastArguments(1, 0),
+        CheckErrors::IncorrectArgumentCount(1, 2),
+        CheckErrors::UnionTypeError(
+            vec![IntType, UIntType],
+            SequenceType(StringType(ASCII(1u32.try_into().unwrap()))),
+        ),
+        CheckErrors::TypeError(UIntType, IntType),
+        CheckErrors::UnionTypeError(vec![IntType, UIntType], BoolType),
+        CheckErrors::IncorrectArgumentCount(2, 1),
+    ];
+
+    for (good_test, expected) in good.iter().zip(expected.iter()) {
+        let type_sig = mem_type_check(good_test).unwrap().0.unwrap();
+        assert_eq!(expected, &type_sig.to_string());
+    }
+
+    for (bad_test, expected) in bad.iter().zip(bad_expected.iter()) {
+        assert_eq!(&mem_type_check(bad_test).unwrap_err().err, expected);
+    }
+}
+
+#[test]
+fn test_consensus_buff_conversions() {
+    let good = [
+        "(to-consensus-buff? 1)",
+        "(to-consensus-buff? true)",
+        "(to-consensus-buff? \"abc\")",
+        "(to-consensus-buff? (list 1 2))",
+        "(to-consensus-buff? none)",
+        "(to-consensus-buff? (ok 1))",
+        "(to-consensus-buff? (tuple (a 1) (bc u2)))",
+        "(from-consensus-buff? int 0x01)",
+        "(from-consensus-buff? (tuple (a uint)) (sha256 u1))",
+    ];
+
+    let expected = [
+        "(optional (buff 17))",
+        "(optional (buff 1))",
+        "(optional (buff 8))",
+        "(optional (buff 39))",
+        "(optional (buff 1))",
+        "(optional (buff 18))",
+        "(optional (buff 44))",
+        "(optional int)",
+        "(optional (tuple (a uint)))",
+    ];
+
+    let bad = [
+        "(to-consensus-buff? 1 2)",
+        "(from-consensus-buff? int u1)",
+        "(from-consensus-buff? int)",
+    ];
+
+    let bad_expected = [
+        CheckErrors::IncorrectArgumentCount(1, 2),
+        CheckErrors::TypeError(TypeSignature::max_buffer(), UIntType),
+        CheckErrors::IncorrectArgumentCount(2, 1),
+    ];
+
+    for (good_test, expected) in good.iter().zip(expected.iter()) {
+        let type_sig = mem_type_check(good_test).unwrap().0.unwrap();
+        assert_eq!(expected, &type_sig.to_string());
+    }
+
+    for (bad_test, expected) in bad.iter().zip(bad_expected.iter()) {
+        assert_eq!(&mem_type_check(bad_test).unwrap_err().err, expected);
+    }
+}
+
 #[test]
 fn test_response_inference() {
     let good = [
@@ -1595,6 +1993,32 @@ fn test_accessing_unknown_data_var_should_fail() {
     });
 }
 
+#[test]
+fn test_var_exists_and_map_size() {
+    let good = [
+        "(define-data-var cursor int 0) (var-exists? cursor)",
+        "(define-map entries uint int) (map-size entries)",
+    ];
+    let expected = [BoolType, UIntType];
+    for (good_test, expected) in good.iter().zip(expected.iter()) {
+        assert_eq!(expected, &mem_type_check(good_test).unwrap().0.unwrap());
+    }
+
+    let bad = [
+        "(define-private (foo) (var-exists? cursor))",
+        "(define-private (foo) (map-size entries))",
+        "(define-data-var cursor int 0) (define-private (foo) (map-size cursor))",
+    ];
+    let bad_expected = [
+        CheckErrors::NoSuchDataVariable("cursor".to_string()),
+        CheckErrors::NoSuchMap("entries".to_string()),
+        CheckErrors::NoSuchMap("cursor".to_string()),
+    ];
+    for (bad_test, expected) in bad.iter().zip(bad_expected.iter()) {
+        assert_eq!(expected, &mem_type_check(bad_test).unwrap_err().err);
+    }
+}
+
 #[test]
 fn test_let_shadowed_by_let_should_fail() {
     let contract_src = r#"
@@ -2089,12 +2513,12 @@ fn test_string_ascii_fold() {
     let good = [
         "(define-private (get-len (x (string-ascii 1)) (acc uint)) (+ acc u1))
         (fold get-len \"blockstack\" u0)",
-        "(define-private (slice (x (string-ascii 1)) (acc (tuple (limit uint) (cursor uint) (data (string-ascii 10)))))
+        "(define-private (slice-step (x (string-ascii 1)) (acc (tuple (limit uint) (cursor uint) (data (string-ascii 10)))))
             (if (< (get cursor acc) (get limit acc))
                 (let ((data (default-to (get data acc) (as-max-len? (concat (get data acc) x) u10))))
                     (tuple (limit (get limit acc)) (cursor (+ u1 (get cursor acc))) (data data)))
                 acc))
-        (fold slice \"blockstack\" (tuple (limit u5) (cursor u0) (data \"\")))"];
+        (fold slice-step \"blockstack\" (tuple (limit u5) (cursor u0) (data \"\")))"];
     let expected = [
         "uint",
         "(tuple (cursor uint) (data (string-ascii 10)) (limit uint))",
@@ -2142,12 +2566,12 @@ fn test_string_utf8_fold() {
     let good = [
         "(define-private (get-len (x (string-utf8 1)) (acc uint)) (+ acc u1))
         (fold get-len u\"blockstack\" u0)",
-        "(define-private (slice (x (string-utf8 1)) (acc (tuple (limit uint) (cursor uint) (data (string-utf8 11)))))
+        "(define-private (slice-step (x (string-utf8 1)) (acc (tuple (limit uint) (cursor uint) (data (string-utf8 11)))))
             (if (< (get cursor acc) (get limit acc))
                 (let ((data (default-to (get data acc) (as-max-len? (concat (get data acc) x) u11))))
                     (tuple (limit (get limit acc)) (cursor (+ u1 (get cursor acc))) (data data)))
                 acc))
-        (fold slice u\"blockstack\\u{1F926}\" (tuple (limit u5) (cursor u0) (data u\"\")))"];
+        (fold slice-step u\"blockstack\\u{1F926}\" (tuple (limit u5) (cursor u0) (data u\"\")))"];
     let expected = [
         "uint",
         "(tuple (cursor uint) (data (string-utf8 11)) (limit uint))",
@@ -2225,3 +2649,146 @@ fn test_string_utf8_negative_len() {
         _ => false,
     });
 }
+
+#[test]
+fn test_unchecked_intermediary_responses() {
+    let contract_src = "(define-public (transfer (amount uint))
+  (begin
+    (stx-transfer? amount tx-sender 'S1G2081040G2081040G2081040G208105NK8PE5)
+    (ok true)))
+(define-private (f (a int))
+  (let ((b (+ a 1)))
+    (if (> a 0) (ok a) (err u1))
+    (some b)
+    (ok b)
+    a))";
+
+    // strict mode: the error points at the unchecked statement, not the whole `begin`
+    let err = mem_type_check(contract_src).unwrap_err();
+    assert_eq!(err.err, CheckErrors::UncheckedIntermediaryResponses);
+    assert_eq!(
+        err.diagnostic.code,
+        Some("UncheckedIntermediaryResponses".to_string())
+    );
+    assert!(err.diagnostic.suggestion.is_some());
+    assert_eq!(err.diagnostic.spans.len(), 1);
+    assert_eq!(err.diagnostic.spans[0].start_line, 3);
+    assert_eq!(err.diagnostic.spans[0].start_column, 5);
+    assert_eq!(err.expressions.as_ref().unwrap().len(), 1);
+
+    // non-strict mode: each offending `begin` or `let` raises a warning
+    let contract_id = QualifiedContractIdentifier::transient();
+    let mut expressions = parse(&contract_id, contract_src).unwrap();
+    let mut marf = MemoryBackingStore::new();
+    let mut analysis_db = marf.as_analysis_db();
+    let analysis = run_analysis(
+        &contract_id,
+        &mut expressions,
+        &mut analysis_db,
+        false,
+        true,
+        ClarityVersion::latest(),
+        LimitedCostTracker::new_free(),
+    )
+    .map_err(|(e, _)| e)
+    .unwrap();
+
+    assert_eq!(analysis.warnings.len(), 2);
+    for warning in analysis.warnings.iter() {
+        assert_eq!(warning.level, Level::Warning);
+        assert_eq!(
+            warning.code,
+            Some("UncheckedIntermediaryResponses".to_string())
+        );
+    }
+    let span_lines = |warning: &Diagnostic| -> Vec<u32> {
+        warning.spans.iter().map(|span| span.start_line).collect()
+    };
+    assert_eq!(span_lines(&analysis.warnings[0]), vec![3]);
+    // `(some b)` is not a response, so it is not flagged
+    assert_eq!(span_lines(&analysis.warnings[1]), vec![7, 9]);
+
+    // contracts without unchecked responses raise no warnings
+    let contract_src = "(define-public (transfer (amount uint))
+  (begin
+    (try! (stx-transfer? amount tx-sender 'S1G2081040G2081040G2081040G208105NK8PE5))
+    (ok true)))";
+    let mut expressions = parse(&contract_id, contract_src).unwrap();
+    let analysis = run_analysis(
+        &contract_id,
+        &mut expressions,
+        &mut analysis_db,
+        false,
+        true,
+        ClarityVersion::latest(),
+        LimitedCostTracker::new_free(),
+    )
+    .map_err(|(e, _)| e)
+    .unwrap();
+    assert!(analysis.warnings.is_empty());
+}
+
+#[test]
+fn test_natives_gated_by_clarity_version() {
+    let analyze = |contract_src: &str, clarity_version: ClarityVersion| {
+        let contract_id = QualifiedContractIdentifier::transient();
+        let mut expressions = parse(&contract_id, contract_src).unwrap();
+        let mut marf = MemoryBackingStore::new();
+        let mut analysis_db = marf.as_analysis_db();
+        run_analysis(
+            &contract_id,
+            &mut expressions,
+            &mut analysis_db,
+            false,
+            false,
+            clarity_version,
+            LimitedCostTracker::new_free(),
+        )
+        .map_err(|(e, _)| e.err)
+    };
+
+    // a Clarity 1 contract may define functions and constants named like later natives
+    let user_defined = "(define-private (map-size (a uint)) (+ a u1))
+                        (define-constant l1-block-height u5)
+                        (define-read-only (get-size) (map-size l1-block-height))";
+    analyze(user_defined, ClarityVersion::Clarity1).unwrap();
+
+    let uses_native = "(define-map entries uint uint)
+                       (define-read-only (size) (map-size entries))";
+    assert_eq!(
+        analyze(uses_native, ClarityVersion::Clarity1).unwrap_err(),
+        CheckErrors::UnknownFunction("map-size".to_string())
+    );
+    analyze(uses_native, ClarityVersion::Clarity2).unwrap();
+
+    let uses_variable = "(define-read-only (height) l1-block-height)";
+    assert_eq!(
+        analyze(uses_variable, ClarityVersion::Clarity1).unwrap_err(),
+        CheckErrors::UndefinedVariable("l1-block-height".to_string())
+    );
+    analyze(uses_variable, ClarityVersion::Clarity2).unwrap();
+
+    let uses_deposit_handler = "(define-private (on-deposit (depositor principal) (asset (optional principal)) (amount uint))
+                                  (ok true))
+                                (define-deposit-handler on-deposit)";
+    assert_eq!(
+        analyze(uses_deposit_handler, ClarityVersion::Clarity1).unwrap_err(),
+        CheckErrors::UnknownFunction("define-deposit-handler".to_string())
+    );
+    analyze(uses_deposit_handler, ClarityVersion::Clarity2).unwrap();
+
+    let tips_withdrawal = "(define-public (withdraw (amount uint))
+                             (stx-withdraw? amount tx-sender u10))";
+    assert_eq!(
+        analyze(tips_withdrawal, ClarityVersion::Clarity1).unwrap_err(),
+        CheckErrors::IncorrectArgumentCount(2, 3)
+    );
+    analyze(tips_withdrawal, ClarityVersion::Clarity2).unwrap();
+
+    let prints_topic = "(define-read-only (log) (print u1 \"topic\"))";
+    assert_eq!(
+        analyze(prints_topic, ClarityVersion::Clarity1).unwrap_err(),
+        CheckErrors::IncorrectArgumentCount(1, 2)
+    );
+    analyze(prints_topic, ClarityVersion::Clarity2).unwrap();
+}
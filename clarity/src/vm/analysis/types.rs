@@ -19,9 +19,10 @@ use crate::vm::analysis::contract_interface_builder::ContractInterface;
 use crate::vm::analysis::errors::{CheckErrors, CheckResult};
 use crate::vm::analysis::type_checker::contexts::TypeMap;
 use crate::vm::costs::{CostTracker, ExecutionCost, LimitedCostTracker};
+use crate::vm::diagnostic::Diagnostic;
 use crate::vm::types::signatures::FunctionSignature;
 use crate::vm::types::{FunctionType, QualifiedContractIdentifier, TraitIdentifier, TypeSignature};
-use crate::vm::{ClarityName, SymbolicExpression};
+use crate::vm::{ClarityName, ClarityVersion, SymbolicExpression};
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 const DESERIALIZE_FAIL_MESSAGE: &str =
@@ -57,12 +58,22 @@ pub struct ContractAnalysis {
     pub type_map: Option<TypeMap>,
     #[serde(skip)]
     pub cost_track: Option<LimitedCostTracker>,
+    /// If set, unchecked intermediary responses are reported as warnings instead of errors.
+    #[serde(skip)]
+    pub allow_unchecked_intermediary_responses: bool,
+    /// Warnings raised while analyzing the contract
+    #[serde(skip)]
+    pub warnings: Vec<Diagnostic>,
+    /// The Clarity version the contract is analyzed as
+    #[serde(default)]
+    pub clarity_version: ClarityVersion,
 }
 
 impl ContractAnalysis {
     pub fn new(
         contract_identifier: QualifiedContractIdentifier,
         expressions: Vec<SymbolicExpression>,
+        clarity_version: ClarityVersion,
         cost_track: LimitedCostTracker,
     ) -> ContractAnalysis {
         ContractAnalysis {
@@ -82,6 +93,9 @@ impl ContractAnalysis {
             non_fungible_tokens: BTreeMap::new(),
             cost_track: Some(cost_track),
             is_cost_contract_eligible: false,
+            allow_unchecked_intermediary_responses: false,
+            warnings: vec![],
+            clarity_version,
         }
     }
 
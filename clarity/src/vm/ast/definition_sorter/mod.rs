@@ -194,6 +194,13 @@ impl<'a> DefinitionSorter {
                                 DefineFunctions::ImplTrait | DefineFunctions::UseTrait => {
                                     return Ok(())
                                 }
+                                DefineFunctions::DepositHandler => {
+                                    // Args: [function-name]: the handler must be defined first
+                                    if function_args.len() == 1 {
+                                        self.probe_for_dependencies(&function_args[0], tle_index)?;
+                                    }
+                                    return Ok(());
+                                }
                                 DefineFunctions::NonFungibleToken => return Ok(()),
                                 DefineFunctions::FungibleToken => {
                                     // probe_for_dependencies if the supply arg (optional) is being passed
@@ -207,7 +214,8 @@ impl<'a> DefinitionSorter {
                             NativeFunctions::lookup_by_name(function_name)
                         {
                             match native_function {
-                                NativeFunctions::ContractCall => {
+                                NativeFunctions::ContractCall
+                                | NativeFunctions::TryContractCall => {
                                     // Args: [contract-name, function-name, ...]: ignore contract-name, function-name, handle rest
                                     if function_args.len() > 2 {
                                         for expr in function_args[2..].iter() {
@@ -342,8 +350,11 @@ impl<'a> DefinitionSorter {
             let exp = exp.match_list()?;
             let (function_name, args) = exp.split_first()?;
             let function_name = function_name.match_atom()?;
-            DefineFunctions::lookup_by_name(function_name)?;
-            Some(args)
+            match DefineFunctions::lookup_by_name(function_name)? {
+                // names a function defined elsewhere, rather than defining one
+                DefineFunctions::DepositHandler => return None,
+                _ => Some(args),
+            }
         }?;
         let defined_name = match args.get(0)?.match_list() {
             Some(list) => list.get(0)?,
@@ -153,6 +153,7 @@ impl TraitsResolver {
                 | DefineFunctions::NonFungibleToken => {
                     self.probe_for_generics(&args[1..], &mut referenced_traits, false)?;
                 }
+                DefineFunctions::DepositHandler => {}
             };
         }
 
@@ -1,4 +1,5 @@
 use crate::vm::analysis;
+use crate::vm::analysis::constant_folder::ConstantFolder;
 use crate::vm::analysis::ContractAnalysis;
 use crate::vm::analysis::{AnalysisDatabase, CheckError, CheckErrors};
 use crate::vm::ast::errors::{ParseError, ParseErrors};
@@ -11,8 +12,9 @@ use crate::vm::database::ClarityDatabase;
 use crate::vm::errors::Error as InterpreterError;
 use crate::vm::events::StacksTransactionEvent;
 use crate::vm::types::{PrincipalData, QualifiedContractIdentifier};
-use crate::vm::{ast, SymbolicExpression, Value};
+use crate::vm::{ast, ClarityName, ClarityVersion, SymbolicExpression, Value};
 use stacks_common::types::StacksEpochId;
+use std::cell::Cell;
 use std::fmt;
 
 #[derive(Debug)]
@@ -165,6 +167,9 @@ pub trait TransactionConnection: ClarityConnection {
         identifier: &QualifiedContractIdentifier,
         contract_content: &str,
     ) -> Result<(ContractAST, ContractAnalysis), Error> {
+        let allow_unchecked_intermediary_responses =
+            self.with_clarity_db_readonly(|db| db.get_allow_unchecked_intermediary_responses());
+        let clarity_version = ClarityVersion::default_for_epoch(self.get_epoch());
         self.with_analysis_db(|db, mut cost_track| {
             let ast_result = ast::build_ast(identifier, contract_content, &mut cost_track);
 
@@ -178,12 +183,28 @@ pub trait TransactionConnection: ClarityConnection {
                 &mut contract_ast.expressions,
                 db,
                 false,
+                allow_unchecked_intermediary_responses,
+                clarity_version,
                 cost_track,
             );
 
             match result {
                 Ok(mut contract_analysis) => {
-                    let cost_track = contract_analysis.take_contract_cost_tracker();
+                    let mut cost_track = contract_analysis.take_contract_cost_tracker();
+                    // folding changes the cost of calling the contract, so it is a consensus
+                    //  rule that only applies to contracts of the version that introduced it
+                    if clarity_version >= ClarityVersion::Clarity2 {
+                        if let Some(type_map) = contract_analysis.type_map.as_ref() {
+                            if let Err(e) = ConstantFolder::run(
+                                &mut contract_ast.expressions,
+                                type_map,
+                                clarity_version,
+                                &mut cost_track,
+                            ) {
+                                return (cost_track, Err(e.into()));
+                            }
+                        }
+                    }
                     (cost_track, Ok((contract_ast, contract_analysis)))
                 }
                 Err((e, cost_track)) => (cost_track, Err(e.into())),
@@ -273,6 +294,58 @@ pub trait TransactionConnection: ClarityConnection {
         })
     }
 
+    /// Invoke the deposit handler of `contract`, if it defines one, for a deposit of `amount` of
+    ///  `asset` (or of STX, if `asset` is None) by `depositor`.
+    ///  If an error occurs while running the handler, or it returns an `err`, its modifications will
+    ///  be rolled back.  Returns None if the contract does not define a deposit handler.
+    fn run_deposit_handler(
+        &mut self,
+        depositor: &PrincipalData,
+        contract: &QualifiedContractIdentifier,
+        asset: Option<PrincipalData>,
+        amount: u128,
+    ) -> Result<Option<(Value, AssetMap, Vec<StacksTransactionEvent>)>, Error> {
+        let (value, assets, events, _) = self.with_abort_callback(
+            |vm_env| {
+                vm_env
+                    .execute_deposit_handler(depositor.clone(), contract.clone(), asset, amount)
+                    .map_err(Error::from)
+            },
+            |_, _| false,
+        )?;
+        Ok(value.map(|value| (value, assets, events)))
+    }
+
+    /// Materialize a deposit of several assets by `depositor` as one unit: credit `stx_amount`
+    ///  uSTX to `depositor`, then call each of `deposit_calls` with `depositor` as the sender.
+    ///  If any call errors or returns an `err`, all of the deposit's modifications are rolled back;
+    ///  in the latter case, the `err` is returned in `Error::AbortedByCallback`.
+    fn run_multi_asset_deposit(
+        &mut self,
+        depositor: &PrincipalData,
+        stx_amount: u128,
+        deposit_calls: &[(QualifiedContractIdentifier, ClarityName, Vec<Value>)],
+    ) -> Result<(Value, AssetMap, Vec<StacksTransactionEvent>), Error> {
+        let failed = Cell::new(false);
+        self.with_abort_callback(
+            |vm_env| {
+                let result = vm_env
+                    .execute_multi_asset_deposit(depositor.clone(), stx_amount, deposit_calls)
+                    .map_err(Error::from)?;
+                failed.set(!matches!(result.0, Value::Response(ref data) if data.committed));
+                Ok(result)
+            },
+            |_, _| failed.get(),
+        )
+        .and_then(|(value, assets, events, aborted)| {
+            if aborted {
+                Err(Error::AbortedByCallback(Some(value), assets, events))
+            } else {
+                Ok((value, assets, events))
+            }
+        })
+    }
+
     /// Initialize a contract in the current block.
     ///  If an error occurs while processing the initialization, it's modifications will be rolled back.
     /// abort_call_back is called with an AssetMap and a ClarityDatabase reference,
@@ -42,7 +42,7 @@ use crate::vm::types::{
     AssetIdentifier, PrincipalData, QualifiedContractIdentifier, TraitIdentifier, TypeSignature,
     Value,
 };
-use crate::vm::{eval, is_reserved};
+use crate::vm::{eval, is_reserved, ClarityVersion};
 
 use crate::{types::chainstate::StacksBlockId, types::StacksEpochId};
 
@@ -50,6 +50,7 @@ use crate::vm::costs::cost_functions::ClarityCostFunction;
 use serde::Serialize;
 
 use crate::vm::coverage::CoverageReporter;
+use crate::vm::hooks::EvalHook;
 
 pub const MAX_CONTEXT_DEPTH: u16 = 256;
 
@@ -191,8 +192,15 @@ pub struct GlobalContext<'a> {
     pub cost_track: LimitedCostTracker,
     pub mainnet: bool,
     pub coverage_reporting: Option<CoverageReporter>,
+    /// Hooks invoked around each expression evaluation, in builds with the `eval-hooks` feature
+    pub eval_hooks: Vec<Box<dyn EvalHook>>,
     /// This is the epoch of the the block that this transaction is executing within.
     epoch_id: StacksEpochId,
+    /// The most bytes of event payloads that the transaction may emit, if capped
+    max_event_bytes: Option<u64>,
+    /// The bytes of event payloads that the transaction has emitted so far, including events
+    /// from contexts that were later rolled back
+    event_bytes: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -211,6 +219,12 @@ pub struct ContractContext {
     pub meta_nft: HashMap<ClarityName, NonFungibleTokenMetadata>,
     pub meta_ft: HashMap<ClarityName, FungibleTokenMetadata>,
     pub data_size: u64,
+    /// The private function to invoke when a deposit to this contract is credited, if any
+    #[serde(default)]
+    pub deposit_handler: Option<ClarityName>,
+    /// The Clarity version the contract is written in, which decides the natives it can call
+    #[serde(default)]
+    pub clarity_version: ClarityVersion,
 }
 
 pub struct LocalContext<'a> {
@@ -531,7 +545,10 @@ impl<'a> OwnedEnvironment<'a> {
         let epoch = StacksEpochId::Epoch2_05;
         OwnedEnvironment {
             context: GlobalContext::new(false, database, LimitedCostTracker::new_free(), epoch),
-            default_contract: ContractContext::new(QualifiedContractIdentifier::transient()),
+            default_contract: ContractContext::new(
+                QualifiedContractIdentifier::transient(),
+                ClarityVersion::latest(),
+            ),
             call_stack: CallStack::new(),
         }
     }
@@ -546,7 +563,10 @@ impl<'a> OwnedEnvironment<'a> {
             .expect("FAIL: problem instantiating cost tracking");
         OwnedEnvironment {
             context: GlobalContext::new(use_mainnet, database, cost_track, epoch),
-            default_contract: ContractContext::new(QualifiedContractIdentifier::transient()),
+            default_contract: ContractContext::new(
+                QualifiedContractIdentifier::transient(),
+                ClarityVersion::latest(),
+            ),
             call_stack: CallStack::new(),
         }
     }
@@ -559,6 +579,18 @@ impl<'a> OwnedEnvironment<'a> {
         self.context.coverage_reporting.take()
     }
 
+    /// Register a hook to be invoked around each expression evaluation.  Hooks are only invoked
+    /// in builds with the `eval-hooks` feature.
+    pub fn add_eval_hook(&mut self, hook: Box<dyn EvalHook>) {
+        self.context.eval_hooks.push(hook)
+    }
+
+    /// Cap the bytes of event payloads that the transactions run in this environment may emit.
+    /// A transaction that emits more fails with `RuntimeErrorType::EventSizeLimitExceeded`.
+    pub fn set_max_event_bytes(&mut self, max_event_bytes: Option<u64>) {
+        self.context.max_event_bytes = max_event_bytes;
+    }
+
     pub fn new_free(
         mainnet: bool,
         database: ClarityDatabase<'a>,
@@ -571,7 +603,10 @@ impl<'a> OwnedEnvironment<'a> {
                 LimitedCostTracker::new_free(),
                 epoch_id,
             ),
-            default_contract: ContractContext::new(QualifiedContractIdentifier::transient()),
+            default_contract: ContractContext::new(
+                QualifiedContractIdentifier::transient(),
+                ClarityVersion::default_for_epoch(epoch_id),
+            ),
             call_stack: CallStack::new(),
         }
     }
@@ -584,7 +619,10 @@ impl<'a> OwnedEnvironment<'a> {
     ) -> OwnedEnvironment<'a> {
         OwnedEnvironment {
             context: GlobalContext::new(mainnet, database, cost_tracker, epoch_id),
-            default_contract: ContractContext::new(QualifiedContractIdentifier::transient()),
+            default_contract: ContractContext::new(
+                QualifiedContractIdentifier::transient(),
+                ClarityVersion::default_for_epoch(epoch_id),
+            ),
             call_stack: CallStack::new(),
         }
     }
@@ -668,6 +706,63 @@ impl<'a> OwnedEnvironment<'a> {
         })
     }
 
+    /// Invoke the deposit handler of `contract_identifier`, with `depositor` as the sender.
+    /// Returns None if the contract does not define a deposit handler.
+    pub fn execute_deposit_handler(
+        &mut self,
+        depositor: PrincipalData,
+        contract_identifier: QualifiedContractIdentifier,
+        asset: Option<PrincipalData>,
+        amount: u128,
+    ) -> Result<(Option<Value>, AssetMap, Vec<StacksTransactionEvent>)> {
+        let args = [
+            Value::Principal(depositor.clone()),
+            match asset {
+                Some(asset) => Value::some(Value::Principal(asset))?,
+                None => Value::none(),
+            },
+            Value::UInt(amount),
+        ];
+        self.execute_in_env(depositor, |exec_env| {
+            exec_env.execute_deposit_handler(&contract_identifier, &args)
+        })
+    }
+
+    /// Credit `depositor` with `stx_amount` uSTX, and then call each of `deposit_calls` (a
+    /// contract, one of its public functions, and the arguments to pass) with `depositor` as the
+    /// sender.  Stops at the first call that returns an `err`, and returns that result; otherwise
+    /// returns `(ok true)`.  The caller is responsible for rolling back a deposit that did not
+    /// succeed.
+    pub fn execute_multi_asset_deposit(
+        &mut self,
+        depositor: PrincipalData,
+        stx_amount: u128,
+        deposit_calls: &[(QualifiedContractIdentifier, ClarityName, Vec<Value>)],
+    ) -> Result<(Value, AssetMap, Vec<StacksTransactionEvent>)> {
+        self.execute_in_env(depositor.clone(), |exec_env| {
+            if stx_amount > 0 {
+                let mut snapshot = exec_env
+                    .global_context
+                    .database
+                    .get_stx_balance_snapshot(&depositor);
+                snapshot.credit(stx_amount);
+                snapshot.save();
+            }
+            for (contract_identifier, function_name, args) in deposit_calls.iter() {
+                let args: Vec<_> = args
+                    .iter()
+                    .map(|arg| SymbolicExpression::atom_value(arg.clone()))
+                    .collect();
+                let result =
+                    exec_env.execute_contract(contract_identifier, function_name, &args, false)?;
+                if !matches!(result, Value::Response(ref data) if data.committed) {
+                    return Ok(result);
+                }
+            }
+            Ok(Value::okay_true())
+        })
+    }
+
     pub fn stx_transfer(
         &mut self,
         from: &PrincipalData,
@@ -993,6 +1088,43 @@ impl<'a, 'b> Environment<'a, 'b> {
         })
     }
 
+    /// Call the deposit handler of `contract_identifier` with `args`, if the contract defines one.
+    /// The handler's effects are rolled back if it returns an `err`.
+    pub fn execute_deposit_handler(
+        &mut self,
+        contract_identifier: &QualifiedContractIdentifier,
+        args: &[Value],
+    ) -> Result<Option<Value>> {
+        let contract_size = self
+            .global_context
+            .database
+            .get_contract_size(contract_identifier)?;
+        runtime_cost(ClarityCostFunction::LoadContract, self, contract_size)?;
+
+        self.global_context.add_memory(contract_size)?;
+
+        finally_drop_memory!(self.global_context, contract_size; {
+            let contract = self.global_context.database.get_contract(contract_identifier)?;
+
+            let handler_name = match contract.contract_context.deposit_handler.as_ref() {
+                Some(name) => name,
+                None => return Ok(None),
+            };
+            let func = contract.contract_context.lookup_function(handler_name)
+                .ok_or_else(|| { CheckErrors::UndefinedFunction(handler_name.to_string()) })?;
+
+            let func_identifier = func.get_identifier();
+            if self.call_stack.contains(&func_identifier) {
+                return Err(CheckErrors::CircularReference(vec![func_identifier.to_string()]).into())
+            }
+            self.call_stack.insert(&func_identifier, true);
+            let res = self.execute_function_as_transaction(&func, args, Some(&contract.contract_context));
+            self.call_stack.remove(&func_identifier, true)?;
+
+            res.map(Some)
+        })
+    }
+
     pub fn execute_function_as_transaction(
         &mut self,
         function: &DefinedFunction,
@@ -1102,9 +1234,11 @@ impl<'a, 'b> Environment<'a, 'b> {
             let memory_use = contract_string.len() as u64;
             self.add_memory(memory_use)?;
 
+            // the contract is written in the version of the environment that deploys it
             let result = Contract::initialize_from_ast(
                 contract_identifier.clone(),
                 contract_content,
+                self.contract_context.get_clarity_version(),
                 &mut self.global_context,
             );
             self.drop_memory(memory_use);
@@ -1180,21 +1314,40 @@ impl<'a, 'b> Environment<'a, 'b> {
         }
     }
 
-    pub fn register_print_event(&mut self, value: Value) -> Result<()> {
+    /// Record `event` in the current event batch.  From the subnet-1 epoch on, emitting an event
+    /// is charged in proportion to its payload, so that event spam is bounded by the block budget.
+    /// If the node caps the bytes of events per transaction, going over the cap is a runtime error.
+    fn push_event(&mut self, event: StacksTransactionEvent) -> Result<()> {
+        let payload_size = event.payload_size();
+        if *self.epoch() >= StacksEpochId::EpochSubnet1 {
+            runtime_cost(ClarityCostFunction::EventEmission, self, payload_size)?;
+        }
+
+        let event_bytes = self.global_context.event_bytes.saturating_add(payload_size);
+        if let Some(max_event_bytes) = self.global_context.max_event_bytes {
+            if event_bytes > max_event_bytes {
+                return Err(RuntimeErrorType::EventSizeLimitExceeded(max_event_bytes).into());
+            }
+        }
+        self.global_context.event_bytes = event_bytes;
+
+        if let Some(batch) = self.global_context.event_batches.last_mut() {
+            batch.events.push(event);
+        }
+        Ok(())
+    }
+
+    /// Emit `value` as a contract event, under `topic` if one is given and "print" otherwise
+    pub fn register_print_event(&mut self, value: Value, topic: Option<String>) -> Result<()> {
         let print_event = SmartContractEventData {
             key: (
                 self.contract_context.contract_identifier.clone(),
-                "print".to_string(),
+                topic.unwrap_or_else(|| "print".to_string()),
             ),
             value,
         };
 
-        if let Some(batch) = self.global_context.event_batches.last_mut() {
-            batch
-                .events
-                .push(StacksTransactionEvent::SmartContractEvent(print_event));
-        }
-        Ok(())
+        self.push_event(StacksTransactionEvent::SmartContractEvent(print_event))
     }
 
     pub fn register_stx_transfer_event(
@@ -1209,23 +1362,17 @@ impl<'a, 'b> Environment<'a, 'b> {
             amount,
         };
 
-        if let Some(batch) = self.global_context.event_batches.last_mut() {
-            batch.events.push(StacksTransactionEvent::STXEvent(
-                STXEventType::STXTransferEvent(event_data),
-            ));
-        }
-        Ok(())
+        self.push_event(StacksTransactionEvent::STXEvent(
+            STXEventType::STXTransferEvent(event_data),
+        ))
     }
 
     pub fn register_stx_burn_event(&mut self, sender: PrincipalData, amount: u128) -> Result<()> {
         let event_data = STXBurnEventData { sender, amount };
 
-        if let Some(batch) = self.global_context.event_batches.last_mut() {
-            batch.events.push(StacksTransactionEvent::STXEvent(
-                STXEventType::STXBurnEvent(event_data),
-            ));
-        }
-        Ok(())
+        self.push_event(StacksTransactionEvent::STXEvent(
+            STXEventType::STXBurnEvent(event_data),
+        ))
     }
 
     pub fn register_stx_withdraw_event(
@@ -1239,12 +1386,26 @@ impl<'a, 'b> Environment<'a, 'b> {
             withdrawal_id: None,
         };
 
-        if let Some(batch) = self.global_context.event_batches.last_mut() {
-            batch.events.push(StacksTransactionEvent::STXEvent(
-                STXEventType::STXWithdrawEvent(event_data),
-            ));
-        }
-        Ok(())
+        self.push_event(StacksTransactionEvent::STXEvent(
+            STXEventType::STXWithdrawEvent(event_data),
+        ))
+    }
+
+    pub fn register_stx_withdraw_tip_event(
+        &mut self,
+        sender: PrincipalData,
+        amount: u128,
+    ) -> Result<()> {
+        let event_data = STXWithdrawTipEventData {
+            sender,
+            amount,
+            recipient: None,
+            withdrawal_id: None,
+        };
+
+        self.push_event(StacksTransactionEvent::STXEvent(
+            STXEventType::STXWithdrawTipEvent(event_data),
+        ))
     }
 
     pub fn register_nft_transfer_event(
@@ -1261,12 +1422,9 @@ impl<'a, 'b> Environment<'a, 'b> {
             value,
         };
 
-        if let Some(batch) = self.global_context.event_batches.last_mut() {
-            batch.events.push(StacksTransactionEvent::NFTEvent(
-                NFTEventType::NFTTransferEvent(event_data),
-            ));
-        }
-        Ok(())
+        self.push_event(StacksTransactionEvent::NFTEvent(
+            NFTEventType::NFTTransferEvent(event_data),
+        ))
     }
 
     pub fn register_nft_mint_event(
@@ -1281,12 +1439,9 @@ impl<'a, 'b> Environment<'a, 'b> {
             value,
         };
 
-        if let Some(batch) = self.global_context.event_batches.last_mut() {
-            batch.events.push(StacksTransactionEvent::NFTEvent(
-                NFTEventType::NFTMintEvent(event_data),
-            ));
-        }
-        Ok(())
+        self.push_event(StacksTransactionEvent::NFTEvent(
+            NFTEventType::NFTMintEvent(event_data),
+        ))
     }
 
     pub fn register_nft_burn_event(
@@ -1301,12 +1456,9 @@ impl<'a, 'b> Environment<'a, 'b> {
             value,
         };
 
-        if let Some(batch) = self.global_context.event_batches.last_mut() {
-            batch.events.push(StacksTransactionEvent::NFTEvent(
-                NFTEventType::NFTBurnEvent(event_data),
-            ));
-        }
-        Ok(())
+        self.push_event(StacksTransactionEvent::NFTEvent(
+            NFTEventType::NFTBurnEvent(event_data),
+        ))
     }
 
     pub fn register_nft_withdraw_event(
@@ -1322,12 +1474,9 @@ impl<'a, 'b> Environment<'a, 'b> {
             withdrawal_id: None,
         };
 
-        if let Some(batch) = self.global_context.event_batches.last_mut() {
-            batch.events.push(StacksTransactionEvent::NFTEvent(
-                NFTEventType::NFTWithdrawEvent(event_data),
-            ));
-        }
-        Ok(())
+        self.push_event(StacksTransactionEvent::NFTEvent(
+            NFTEventType::NFTWithdrawEvent(event_data),
+        ))
     }
 
     pub fn register_ft_transfer_event(
@@ -1344,12 +1493,9 @@ impl<'a, 'b> Environment<'a, 'b> {
             amount,
         };
 
-        if let Some(batch) = self.global_context.event_batches.last_mut() {
-            batch.events.push(StacksTransactionEvent::FTEvent(
-                FTEventType::FTTransferEvent(event_data),
-            ));
-        }
-        Ok(())
+        self.push_event(StacksTransactionEvent::FTEvent(
+            FTEventType::FTTransferEvent(event_data),
+        ))
     }
 
     pub fn register_ft_mint_event(
@@ -1364,14 +1510,9 @@ impl<'a, 'b> Environment<'a, 'b> {
             amount,
         };
 
-        if let Some(batch) = self.global_context.event_batches.last_mut() {
-            batch
-                .events
-                .push(StacksTransactionEvent::FTEvent(FTEventType::FTMintEvent(
-                    event_data,
-                )));
-        }
-        Ok(())
+        self.push_event(StacksTransactionEvent::FTEvent(FTEventType::FTMintEvent(
+            event_data,
+        )))
     }
 
     pub fn register_ft_burn_event(
@@ -1386,14 +1527,9 @@ impl<'a, 'b> Environment<'a, 'b> {
             amount,
         };
 
-        if let Some(batch) = self.global_context.event_batches.last_mut() {
-            batch
-                .events
-                .push(StacksTransactionEvent::FTEvent(FTEventType::FTBurnEvent(
-                    event_data,
-                )));
-        }
-        Ok(())
+        self.push_event(StacksTransactionEvent::FTEvent(FTEventType::FTBurnEvent(
+            event_data,
+        )))
     }
 
     pub fn register_ft_withdraw_event(
@@ -1409,12 +1545,9 @@ impl<'a, 'b> Environment<'a, 'b> {
             withdrawal_id: None,
         };
 
-        if let Some(batch) = self.global_context.event_batches.last_mut() {
-            batch.events.push(StacksTransactionEvent::FTEvent(
-                FTEventType::FTWithdrawEvent(event_data),
-            ));
-        }
-        Ok(())
+        self.push_event(StacksTransactionEvent::FTEvent(
+            FTEventType::FTWithdrawEvent(event_data),
+        ))
     }
 }
 
@@ -1435,6 +1568,9 @@ impl<'a> GlobalContext<'a> {
             mainnet,
             epoch_id,
             coverage_reporting: None,
+            eval_hooks: vec![],
+            max_event_bytes: None,
+            event_bytes: 0,
         }
     }
 
@@ -1599,7 +1735,10 @@ impl<'a> GlobalContext<'a> {
 }
 
 impl ContractContext {
-    pub fn new(contract_identifier: QualifiedContractIdentifier) -> Self {
+    pub fn new(
+        contract_identifier: QualifiedContractIdentifier,
+        clarity_version: ClarityVersion,
+    ) -> Self {
         Self {
             contract_identifier,
             variables: HashMap::new(),
@@ -1612,9 +1751,15 @@ impl ContractContext {
             meta_data_var: HashMap::new(),
             meta_nft: HashMap::new(),
             meta_ft: HashMap::new(),
+            deposit_handler: None,
+            clarity_version,
         }
     }
 
+    pub fn get_clarity_version(&self) -> ClarityVersion {
+        self.clarity_version
+    }
+
     pub fn lookup_variable(&self, name: &str) -> Option<&Value> {
         self.variables.get(name)
     }
@@ -1635,7 +1780,7 @@ impl ContractContext {
     }
 
     pub fn is_name_used(&self, name: &str) -> bool {
-        is_reserved(name)
+        is_reserved(name, self.clarity_version)
             || self.variables.contains_key(name)
             || self.functions.contains_key(name)
             || self.persisted_names.contains(name)
@@ -133,5 +133,21 @@ define_named_enum!(ClarityCostFunction {
     NftOwner("cost_nft_owner"),
     NftBurn("cost_nft_burn"),
     NftWithdraw("cost_nft_burn"),
+    // only defined by .costs-3, like the native itself
+    GetWithdrawalInfo("cost_get_withdrawal_info"),
+    VarExists("cost_fetch_var"),
+    MapSize("cost_fetch_var"),
+    GetL1BlockInfo("cost_block_info"),
+    StxGetAccount("cost_stx_balance"),
+    // only defined by .costs-3, so only assessed from the subnet-1 epoch on
+    EventEmission("cost_event_emission"),
+    // only defined by .costs-3; earlier epochs charge `slice` as a `concat`
+    Slice("cost_slice"),
+    // only defined by .costs-3; earlier epochs charge `replace-at?` as a `concat`
+    ReplaceAt("cost_replace_at"),
+    // only defined by .costs-3; earlier epochs charge `to-consensus-buff?` as a `sha256`
+    ToConsensusBuff("cost_to_consensus_buff"),
+    // only defined by .costs-3; earlier epochs charge `from-consensus-buff?` as a `sha256`
+    FromConsensusBuff("cost_from_consensus_buff"),
     PoisonMicroblock("poison_microblock"),
 });
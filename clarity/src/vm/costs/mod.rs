@@ -48,6 +48,8 @@ pub const CLARITY_MEMORY_LIMIT: u64 = 100 * 1000 * 1000;
 // TODO: factor out into a boot lib?
 pub const COSTS_1_NAME: &'static str = "costs";
 pub const COSTS_2_NAME: &'static str = "costs-2";
+pub const COSTS_3_NAME: &'static str = "costs-3";
+pub const COSTS_SUBNET_NAME: &'static str = "costs-subnet";
 
 lazy_static! {
     static ref COST_TUPLE_TYPE_SIGNATURE: TypeSignature = TypeSignature::TupleType(
@@ -679,6 +681,7 @@ impl LimitedCostTracker {
             }
             StacksEpochId::Epoch20 => COSTS_1_NAME.to_string(),
             StacksEpochId::Epoch2_05 => COSTS_2_NAME.to_string(),
+            StacksEpochId::EpochSubnet1 => COSTS_3_NAME.to_string(),
         }
     }
 }
@@ -706,10 +709,41 @@ impl TrackerData {
         self.contract_call_circuits = contract_call_circuits;
 
         let mut cost_contracts = HashMap::new();
+
+        // subnets that selected the subnet cost profile instantiate .costs-subnet when they enter
+        //  the subnet-1 epoch: the cost functions it defines take the place of the default ones.
+        let subnet_costs_id = boot_code_id(COSTS_SUBNET_NAME, self.mainnet);
+        let subnet_cost_functions = if epoch_id == StacksEpochId::EpochSubnet1
+            && clarity_db.has_contract(&subnet_costs_id)
+        {
+            let contract_context = match clarity_db.get_contract(&subnet_costs_id) {
+                Ok(contract) => contract.contract_context,
+                Err(e) => {
+                    error!("Failed to load intended Clarity cost contract";
+                           "contract" => %subnet_costs_id,
+                           "error" => ?e);
+                    clarity_db.roll_back();
+                    return Err(CostErrors::CostContractLoadFailure);
+                }
+            };
+            let defined: Vec<_> = contract_context.functions.keys().cloned().collect();
+            cost_contracts.insert(subnet_costs_id.clone(), contract_context);
+            defined
+        } else {
+            vec![]
+        };
+
         let mut m = HashMap::new();
         for f in ClarityCostFunction::ALL.iter() {
             let cost_function_ref = cost_function_references.remove(&f).unwrap_or_else(|| {
-                ClarityCostFunctionReference::new(boot_costs_id.clone(), f.get_name())
+                if subnet_cost_functions
+                    .iter()
+                    .any(|name| name.as_str() == f.get_name_str())
+                {
+                    ClarityCostFunctionReference::new(subnet_costs_id.clone(), f.get_name())
+                } else {
+                    ClarityCostFunctionReference::new(boot_costs_id.clone(), f.get_name())
+                }
             });
             if !cost_contracts.contains_key(&cost_function_ref.contract_id) {
                 let contract_context = match clarity_db.get_contract(&cost_function_ref.contract_id)
@@ -778,6 +812,21 @@ impl LimitedCostTracker {
             Self::Free => ExecutionCost::max_value(),
         }
     }
+    pub fn set_limit(&mut self, limit: ExecutionCost) -> () {
+        // used by the miner to hold some transactions to a smaller budget than the block's.
+        match self {
+            Self::Limited(ref mut data) => data.limit = limit,
+            Self::Free => panic!("Cannot set limit on free tracker"),
+        }
+    }
+    /// Reload the cost functions from `clarity_db`, keeping the running total.  Used when an epoch
+    /// transition changes the default cost contract partway through a block.
+    pub fn reload_costs(&mut self, clarity_db: &mut ClarityDatabase) -> Result<()> {
+        match self {
+            Self::Limited(ref mut data) => data.load_costs(clarity_db, false),
+            Self::Free => Ok(()),
+        }
+    }
 }
 
 fn parse_cost(
@@ -135,6 +135,7 @@ impl CoverageReporter {
                         DefineFunctionsParsed::Trait { .. } => {}
                         DefineFunctionsParsed::UseTrait { .. } => {}
                         DefineFunctionsParsed::ImplTrait { .. } => {}
+                        DefineFunctionsParsed::DepositHandler { .. } => {}
                     }
 
                     continue;
@@ -38,7 +38,7 @@ use crate::vm::types::{
     QualifiedContractIdentifier, StandardPrincipalData, TupleData, TupleTypeSignature,
     TypeSignature, Value, NONE,
 };
-use stacks_common::util::hash::{to_hex, Hash160, Sha256Sum, Sha512Trunc256Sum};
+use stacks_common::util::hash::{to_hex, Hash160, MerkleTree, Sha256Sum, Sha512Trunc256Sum};
 
 use crate::types::chainstate::{
     BlockHeaderHash, BurnchainHeaderHash, SortitionId, StacksAddress, StacksBlockId, VRFSeed,
@@ -77,6 +77,9 @@ pub enum StoreType {
     STXBalance = 0x13,
     PoxSTXLockup = 0x14,
     PoxUnlockHeight = 0x15,
+    DataMapSize = 0x16,
+    PendingSTXWithdrawal = 0x17,
+    WithdrawalClaim = 0x18,
 }
 
 pub struct ClarityDatabase<'a> {
@@ -96,6 +99,10 @@ pub trait HeadersDB {
     fn get_burn_block_time_for_block(&self, id_bhh: &StacksBlockId) -> Option<u64>;
     fn get_burn_block_height_for_block(&self, id_bhh: &StacksBlockId) -> Option<u32>;
     fn get_miner_address(&self, id_bhh: &StacksBlockId) -> Option<StacksAddress>;
+    fn get_withdrawal_tree_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+    ) -> Option<MerkleTree<Sha512Trunc256Sum>>;
 }
 
 pub trait BurnStateDB {
@@ -105,6 +112,7 @@ pub trait BurnStateDB {
         height: u32,
         sortition_id: &SortitionId,
     ) -> Option<BurnchainHeaderHash>;
+    fn get_burn_header_timestamp(&self, height: u32, sortition_id: &SortitionId) -> Option<u64>;
     fn get_stacks_epoch(&self, height: u32) -> Option<StacksEpoch>;
     fn get_stacks_epoch_by_epoch_id(&self, epoch_id: &StacksEpochId) -> Option<StacksEpoch>;
 }
@@ -131,6 +139,12 @@ impl HeadersDB for &dyn HeadersDB {
     fn get_miner_address(&self, bhh: &StacksBlockId) -> Option<StacksAddress> {
         (*self).get_miner_address(bhh)
     }
+    fn get_withdrawal_tree_for_block(
+        &self,
+        bhh: &StacksBlockId,
+    ) -> Option<MerkleTree<Sha512Trunc256Sum>> {
+        (*self).get_withdrawal_tree_for_block(bhh)
+    }
 }
 
 impl BurnStateDB for &dyn BurnStateDB {
@@ -146,6 +160,10 @@ impl BurnStateDB for &dyn BurnStateDB {
         (*self).get_burn_header_hash(height, sortition_id)
     }
 
+    fn get_burn_header_timestamp(&self, height: u32, sortition_id: &SortitionId) -> Option<u64> {
+        (*self).get_burn_header_timestamp(height, sortition_id)
+    }
+
     fn get_stacks_epoch(&self, height: u32) -> Option<StacksEpoch> {
         (*self).get_stacks_epoch(height)
     }
@@ -208,6 +226,17 @@ impl HeadersDB for NullHeadersDB {
     fn get_miner_address(&self, _id_bhh: &StacksBlockId) -> Option<StacksAddress> {
         None
     }
+    fn get_withdrawal_tree_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+    ) -> Option<MerkleTree<Sha512Trunc256Sum>> {
+        if *id_bhh == StacksBlockId::new(&FIRST_BURNCHAIN_CONSENSUS_HASH, &FIRST_STACKS_BLOCK_HASH)
+        {
+            Some(MerkleTree::empty())
+        } else {
+            None
+        }
+    }
 }
 
 impl BurnStateDB for NullBurnStateDB {
@@ -223,6 +252,10 @@ impl BurnStateDB for NullBurnStateDB {
         None
     }
 
+    fn get_burn_header_timestamp(&self, _height: u32, _sortition_id: &SortitionId) -> Option<u64> {
+        None
+    }
+
     fn get_stacks_epoch(&self, _height: u32) -> Option<StacksEpoch> {
         Some(StacksEpoch {
             epoch_id: StacksEpochId::Epoch20,
@@ -593,7 +626,19 @@ impl<'a> ClarityDatabase<'a> {
     /// This is the burnchain block height of its parent.
     pub fn get_current_burnchain_block_height(&mut self) -> u32 {
         let cur_stacks_height = self.store.get_current_block_height();
-        let last_mined_bhh = if cur_stacks_height == 0 {
+        let last_mined_bhh = self.get_last_mined_index_block_hash();
+
+        self.get_burnchain_block_height(&last_mined_bhh)
+            .expect(&format!(
+                "Block header hash '{}' must return for provided stacks block height {}",
+                &last_mined_bhh, cur_stacks_height
+            ))
+    }
+
+    /// Get the index block hash of the parent of the block currently being evaluated.
+    fn get_last_mined_index_block_hash(&mut self) -> StacksBlockId {
+        let cur_stacks_height = self.store.get_current_block_height();
+        if cur_stacks_height == 0 {
             StacksBlockId::new(&FIRST_BURNCHAIN_CONSENSUS_HASH, &FIRST_STACKS_BLOCK_HASH)
         } else {
             self.get_index_block_header_hash(
@@ -601,13 +646,32 @@ impl<'a> ClarityDatabase<'a> {
                     .checked_sub(1)
                     .expect("BUG: cannot eval burn-block-height in boot code"),
             )
-        };
+        }
+    }
 
-        self.get_burnchain_block_height(&last_mined_bhh)
-            .expect(&format!(
-                "Block header hash '{}' must return for provided stacks block height {}",
-                &last_mined_bhh, cur_stacks_height
-            ))
+    /// Get the sortition in which the parent of the block currently being evaluated was mined.
+    /// L1 blocks are read from the L1 fork that this sortition belongs to.
+    fn get_current_sortition_id(&mut self) -> Option<SortitionId> {
+        let last_mined_bhh = self.get_last_mined_index_block_hash();
+        self.headers_db
+            .get_burn_header_hash_for_block(&last_mined_bhh)
+            .map(|burn_header_hash| SortitionId::new(&burn_header_hash))
+    }
+
+    /// Get the header hash of the L1 block at the given L1 block height.
+    /// Returns None if the L1 fork that the current block builds on has no block at this height.
+    pub fn get_l1_block_header_hash(&mut self, l1_height: u32) -> Option<BurnchainHeaderHash> {
+        let sortition_id = self.get_current_sortition_id()?;
+        self.burn_state_db
+            .get_burn_header_hash(l1_height, &sortition_id)
+    }
+
+    /// Get the timestamp of the L1 block at the given L1 block height.
+    /// Returns None if the L1 fork that the current block builds on has no block at this height.
+    pub fn get_l1_block_time(&mut self, l1_height: u32) -> Option<u64> {
+        let sortition_id = self.get_current_sortition_id()?;
+        self.burn_state_db
+            .get_burn_header_timestamp(l1_height, &sortition_id)
     }
 
     pub fn get_block_header_hash(&mut self, block_height: u32) -> BlockHeaderHash {
@@ -650,6 +714,13 @@ impl<'a> ClarityDatabase<'a> {
             .into()
     }
 
+    pub fn get_withdrawal_tree(&mut self, block_height: u32) -> MerkleTree<Sha512Trunc256Sum> {
+        let id_bhh = self.get_index_block_header_hash(block_height);
+        self.headers_db
+            .get_withdrawal_tree_for_block(&id_bhh)
+            .expect("Failed to get block data.")
+    }
+
     pub fn get_stx_btc_ops_processed(&mut self) -> u64 {
         self.get("vm_pox::stx_btc_ops::processed_blocks")
             .unwrap_or(0)
@@ -658,6 +729,149 @@ impl<'a> ClarityDatabase<'a> {
     pub fn set_stx_btc_ops_processed(&mut self, processed: u64) {
         self.put("vm_pox::stx_btc_ops::processed_blocks", &processed);
     }
+
+    fn l1_block_height_key() -> &'static str {
+        "_stx-data::l1_block_height"
+    }
+
+    /// Returns the height of the L1 block that the current subnet block is anchored to.  If it
+    /// was never recorded (e.g., in boot code), this is the same as `burn-block-height`.
+    pub fn get_current_l1_block_height(&mut self) -> u32 {
+        match self.get(Self::l1_block_height_key()) {
+            Some(height) => height,
+            None => self.get_current_burnchain_block_height(),
+        }
+    }
+
+    /// Record the height of the L1 block that the current subnet block is anchored to.  Must be
+    /// called when a block in `EpochSubnet1` or later is set up, before any of its transactions
+    /// are evaluated.
+    pub fn set_current_l1_block_height(&mut self, height: u32) {
+        self.put(Self::l1_block_height_key(), &height);
+    }
+
+    fn allow_unchecked_intermediary_responses_key() -> &'static str {
+        "vm-subnet::allow-unchecked-intermediary-responses"
+    }
+
+    /// Returns whether this subnet accepts contracts that leave intermediary responses unchecked,
+    /// reporting them as warnings instead.  This is committed to the Clarity state in the genesis
+    /// block, so every node in the subnet analyzes contracts the same way.
+    pub fn get_allow_unchecked_intermediary_responses(&mut self) -> bool {
+        match self.get::<u32>(Self::allow_unchecked_intermediary_responses_key()) {
+            Some(allow) => allow != 0,
+            None => false,
+        }
+    }
+
+    /// Should only be called while instantiating the genesis block.
+    pub fn set_allow_unchecked_intermediary_responses(&mut self, allow: bool) {
+        self.put(
+            Self::allow_unchecked_intermediary_responses_key(),
+            &(allow as u32),
+        );
+    }
+
+    fn epoch_schedule_key() -> &'static str {
+        "vm-subnet::epoch-schedule"
+    }
+
+    /// Returns this subnet's epoch schedule, with each epoch's heights and block limit, if it was
+    /// committed to the Clarity state in the genesis block.
+    pub fn get_epoch_schedule(&mut self) -> Option<Vec<StacksEpoch>> {
+        self.get::<String>(Self::epoch_schedule_key())
+            .map(|schedule| {
+                serde_json::from_str(&schedule)
+                    .expect("FATAL: failed to deserialize epoch schedule")
+            })
+    }
+
+    /// Should only be called while instantiating the genesis block.
+    pub fn set_epoch_schedule(&mut self, epochs: &[StacksEpoch]) {
+        let schedule =
+            serde_json::to_string(epochs).expect("FATAL: failed to serialize epoch schedule");
+        self.put(Self::epoch_schedule_key(), &schedule);
+    }
+
+    fn system_priority_policy_key() -> &'static str {
+        "vm-subnet::system-priority-policy"
+    }
+
+    /// Returns the system priority policy committed to the Clarity state in the genesis block, if
+    /// the subnet has one.  The policy is stored in the chainstate's own encoding.
+    pub fn get_system_priority_policy(&mut self) -> Option<String> {
+        self.get::<String>(Self::system_priority_policy_key())
+    }
+
+    /// Should only be called while instantiating the genesis block.
+    pub fn set_system_priority_policy(&mut self, policy: String) {
+        self.put(Self::system_priority_policy_key(), &policy);
+    }
+
+    fn max_tx_event_bytes_key() -> &'static str {
+        "vm-subnet::max-tx-event-bytes"
+    }
+
+    /// Returns the cap on the size of the events a single transaction may emit, if one was
+    /// committed to the Clarity state in the genesis block.
+    pub fn get_max_tx_event_bytes(&mut self) -> Option<u64> {
+        self.get::<u64>(Self::max_tx_event_bytes_key())
+    }
+
+    /// Should only be called while instantiating the genesis block.
+    pub fn set_max_tx_event_bytes(&mut self, max_bytes: u64) {
+        self.put(Self::max_tx_event_bytes_key(), &max_bytes);
+    }
+
+    fn use_subnet_costs_key() -> &'static str {
+        "vm-subnet::use-subnet-costs"
+    }
+
+    /// Returns whether this subnet instantiates `.costs-subnet` at the subnet-1 epoch transition.
+    /// This is committed to the Clarity state in the genesis block.
+    pub fn get_use_subnet_costs(&mut self) -> bool {
+        match self.get::<u32>(Self::use_subnet_costs_key()) {
+            Some(use_subnet_costs) => use_subnet_costs != 0,
+            None => false,
+        }
+    }
+
+    /// Should only be called while instantiating the genesis block.
+    pub fn set_use_subnet_costs(&mut self, use_subnet_costs: bool) {
+        self.put(Self::use_subnet_costs_key(), &(use_subnet_costs as u32));
+    }
+
+    fn withdrawal_batch_blocks_key() -> &'static str {
+        "vm-subnet::withdrawal-batch-blocks"
+    }
+
+    fn withdrawal_batch_start_height_key() -> &'static str {
+        "vm-subnet::withdrawal-batch-start-height"
+    }
+
+    /// Returns the number of consecutive blocks whose withdrawal roots are registered with the L1
+    /// as one batch, or 0 if this subnet does not batch withdrawals.  This is committed to the
+    /// Clarity state in the genesis block.
+    pub fn get_withdrawal_batch_blocks(&mut self) -> u64 {
+        self.get::<u64>(Self::withdrawal_batch_blocks_key())
+            .unwrap_or(0)
+    }
+
+    /// Should only be called while instantiating the genesis block.
+    pub fn set_withdrawal_batch_blocks(&mut self, batch_blocks: u64) {
+        self.put(Self::withdrawal_batch_blocks_key(), &batch_blocks);
+    }
+
+    /// Returns the height of the first block whose withdrawals are batched, if the chain has
+    /// started batching withdrawals.  Batching starts with the first block of `EpochSubnet1`.
+    pub fn get_withdrawal_batch_start_height(&mut self) -> Option<u64> {
+        self.get::<u64>(Self::withdrawal_batch_start_height_key())
+    }
+
+    /// Should only be called while applying the transition to `EpochSubnet1`.
+    pub fn set_withdrawal_batch_start_height(&mut self, start_height: u64) {
+        self.put(Self::withdrawal_batch_start_height_key(), &start_height);
+    }
 }
 
 // poison-microblock
@@ -840,6 +1054,21 @@ impl<'a> ClarityDatabase<'a> {
         self.lookup_variable(contract_identifier, variable_name, &descriptor)
     }
 
+    /// Has a value been stored for this data var?  This is false until the var's definition has
+    /// been evaluated, e.g. when read `at-block` from before the contract was published.
+    pub fn variable_exists(
+        &mut self,
+        contract_identifier: &QualifiedContractIdentifier,
+        variable_name: &str,
+    ) -> bool {
+        let key = ClarityDatabase::make_key_for_trip(
+            contract_identifier,
+            StoreType::Variable,
+            variable_name,
+        );
+        self.store.has_entry(&key)
+    }
+
     pub fn lookup_variable(
         &mut self,
         contract_identifier: &QualifiedContractIdentifier,
@@ -888,21 +1117,37 @@ impl<'a> ClarityDatabase<'a> {
 
 // Data Map Functions
 impl<'a> ClarityDatabase<'a> {
+    /// Define a data map.  If `track_size` is set, the map keeps count of its entries for
+    /// `map-size`; only maps defined by Clarity 2 contracts do, so that defining and writing to
+    /// the maps of older contracts touches the same keys it always has.
     pub fn create_map(
         &mut self,
         contract_identifier: &QualifiedContractIdentifier,
         map_name: &str,
         key_type: TypeSignature,
         value_type: TypeSignature,
+        track_size: bool,
     ) -> DataMapMetadata {
         let data = DataMapMetadata {
             key_type,
             value_type,
+            track_size,
+            keys_indexed: true,
         };
 
         let key = ClarityDatabase::make_metadata_key(StoreType::DataMapMeta, map_name);
         self.insert_metadata(contract_identifier, &key, &data);
 
+        if track_size {
+            // the number of entries _is_ included in the consensus hash
+            let size_key = ClarityDatabase::make_key_for_trip(
+                contract_identifier,
+                StoreType::DataMapSize,
+                map_name,
+            );
+            self.put(&size_key, &(0 as u128));
+        }
+
         data
     }
 
@@ -917,6 +1162,52 @@ impl<'a> ClarityDatabase<'a> {
             .ok_or(CheckErrors::NoSuchMap(map_name.to_string()).into())
     }
 
+    /// Get the number of entries in a data map.
+    pub fn get_map_size(
+        &mut self,
+        contract_identifier: &QualifiedContractIdentifier,
+        map_name: &str,
+    ) -> Result<u128> {
+        let key = ClarityDatabase::make_key_for_trip(
+            contract_identifier,
+            StoreType::DataMapSize,
+            map_name,
+        );
+        self.get(&key).ok_or(
+            InterpreterError::InterpreterError(format!(
+                "Data map {} does not track its size",
+                map_name
+            ))
+            .into(),
+        )
+    }
+
+    /// Add `delta` entries to the number of entries in a data map.  Maps that do not track their
+    /// size (see `create_map()`) are left alone.
+    fn update_map_size(
+        &mut self,
+        contract_identifier: &QualifiedContractIdentifier,
+        map_name: &str,
+        delta: i8,
+    ) {
+        let key = ClarityDatabase::make_key_for_trip(
+            contract_identifier,
+            StoreType::DataMapSize,
+            map_name,
+        );
+        let current_size: u128 = match self.get(&key) {
+            Some(size) => size,
+            None => return,
+        };
+        let new_size = if delta >= 0 {
+            current_size.checked_add(delta as u128)
+        } else {
+            current_size.checked_sub(delta.unsigned_abs() as u128)
+        }
+        .expect("ERROR: Clarity VM failed to track data map size.");
+        self.put(&key, &new_size);
+    }
+
     pub fn make_key_for_data_map_entry(
         contract_identifier: &QualifiedContractIdentifier,
         map_name: &str,
@@ -942,6 +1233,78 @@ impl<'a> ClarityDatabase<'a> {
         )
     }
 
+    /// Whether `key` is the key of a data map entry, as made by `make_key_for_data_map_entry`
+    pub fn is_data_map_entry_key(key: &str) -> bool {
+        let mut parts = key.splitn(5, "::");
+        parts.next() == Some("vm")
+            && parts.next().is_some()
+            && parts
+                .next()
+                .and_then(|store_type| store_type.parse::<u8>().ok())
+                == Some(StoreType::DataMap as u8)
+            && parts.nth(1).is_some()
+    }
+
+    /// List up to `limit` entries of a data map, as (key, value) pairs in the order of their
+    /// serialized keys, starting after the key `after` if it is given.  The entries are found
+    /// through the backing store's record of written map keys (see
+    /// `SqliteConnection::put_map_key`).  Returns None if the map was defined before the
+    /// backing store recorded map keys, so that its entries cannot all be found.
+    pub fn list_data_map_entries(
+        &mut self,
+        contract_identifier: &QualifiedContractIdentifier,
+        map_name: &str,
+        after: Option<&Value>,
+        limit: u32,
+    ) -> Result<Option<Vec<(Value, Value)>>> {
+        let descriptor = self.load_map(contract_identifier, map_name)?;
+        if !descriptor.keys_indexed {
+            return Ok(None);
+        }
+        if limit == 0 {
+            return Ok(Some(vec![]));
+        }
+        let prefix = ClarityDatabase::make_key_for_data_map_entry_serialized(
+            contract_identifier,
+            map_name,
+            "",
+        );
+        let mut after_key = after.map(|key_value| {
+            ClarityDatabase::make_key_for_data_map_entry(contract_identifier, map_name, key_value)
+        });
+
+        // keys are kept after their entries are deleted, so keep reading keys until `limit` of
+        //  them have entries at this block
+        let mut entries = vec![];
+        loop {
+            let (keys, next_key) = self
+                .store
+                .get_map_keys(&prefix, after_key.as_deref(), limit);
+            for key in keys.iter() {
+                let key_value =
+                    Value::try_deserialize_hex(&key[prefix.len()..], &descriptor.key_type)
+                        .map_err(|_e| {
+                            InterpreterError::InterpreterError(format!(
+                                "Failed to deserialize the key of data map entry {}",
+                                key
+                            ))
+                        })?;
+                let entry =
+                    self.fetch_entry(contract_identifier, map_name, &key_value, &descriptor)?;
+                if let Some(value) = entry.expect_optional() {
+                    entries.push((key_value, value));
+                    if entries.len() >= limit as usize {
+                        return Ok(Some(entries));
+                    }
+                }
+            }
+            match next_key {
+                Some(next_key) => after_key = Some(next_key),
+                None => return Ok(Some(entries)),
+            }
+        }
+    }
+
     pub fn fetch_entry_unknown_descriptor(
         &mut self,
         contract_identifier: &QualifiedContractIdentifier,
@@ -1118,7 +1481,14 @@ impl<'a> ClarityDatabase<'a> {
         );
         let stored_type = TypeSignature::new_option(map_descriptor.value_type.clone())?;
 
-        if return_if_exists && self.data_map_entry_exists(&key, &stored_type)? {
+        // only look the entry up if the answer matters: writes to maps that don't count their
+        // entries just overwrite it
+        let entry_exists = if return_if_exists || map_descriptor.track_size {
+            self.data_map_entry_exists(&key, &stored_type)?
+        } else {
+            false
+        };
+        if return_if_exists && entry_exists {
             return Ok(ValueResult {
                 value: Value::Bool(false),
                 serialized_byte_len: key_serialized_byte_len,
@@ -1127,6 +1497,9 @@ impl<'a> ClarityDatabase<'a> {
 
         let placed_value = Value::some(value)?;
         let placed_size = self.put_with_size(&key, &placed_value);
+        if map_descriptor.track_size && !entry_exists {
+            self.update_map_size(contract_identifier, map_name, 1);
+        }
 
         Ok(ValueResult {
             value: Value::Bool(true),
@@ -1168,6 +1541,9 @@ impl<'a> ClarityDatabase<'a> {
         }
 
         self.put(&key, &(Value::none()));
+        if map_descriptor.track_size {
+            self.update_map_size(contract_identifier, map_name, -1);
+        }
 
         Ok(ValueResult {
             value: Value::Bool(true),
@@ -1530,6 +1906,61 @@ impl<'a> ClarityDatabase<'a> {
     }
 }
 
+// track STX withdrawals until they are claimed on the L1
+impl<'a> ClarityDatabase<'a> {
+    pub fn make_key_for_account_pending_stx_withdrawal(principal: &PrincipalData) -> String {
+        ClarityDatabase::make_key_for_account(principal, StoreType::PendingSTXWithdrawal)
+    }
+
+    fn make_key_for_withdrawal_claim(withdrawal_leaf_hash: &Sha512Trunc256Sum) -> String {
+        format!(
+            "vm-withdrawal::{}::{}",
+            StoreType::WithdrawalClaim as u8,
+            withdrawal_leaf_hash
+        )
+    }
+
+    /// The uSTX a principal withdrew from the subnet that has not yet been claimed on the L1.
+    /// Withdrawn uSTX leave the principal's balance when the withdrawal is made, and are held in
+    /// escrow until the L1 claim is observed.
+    pub fn get_pending_stx_withdrawal(&mut self, principal: &PrincipalData) -> u128 {
+        let key = ClarityDatabase::make_key_for_account_pending_stx_withdrawal(principal);
+        self.get(&key).unwrap_or(0)
+    }
+
+    /// Escrow `amount` uSTX that `principal` just withdrew, until the withdrawal is claimed
+    pub fn add_pending_stx_withdrawal(&mut self, principal: &PrincipalData, amount: u128) {
+        let pending = self
+            .get_pending_stx_withdrawal(principal)
+            .checked_add(amount)
+            .expect("FATAL: pending uSTX withdrawals overflowed");
+        let key = ClarityDatabase::make_key_for_account_pending_stx_withdrawal(principal);
+        self.put(&key, &pending);
+    }
+
+    /// Release the escrow of an STX withdrawal of `amount` uSTX by `recipient`, whose L1 claim
+    /// was observed.  Each withdrawal is released at most once per fork, so replayed claims are
+    /// ignored.  Returns the number of uSTX released.
+    pub fn release_claimed_stx_withdrawal(
+        &mut self,
+        withdrawal_leaf_hash: &Sha512Trunc256Sum,
+        recipient: &PrincipalData,
+        amount: u128,
+    ) -> u128 {
+        let claim_key = ClarityDatabase::make_key_for_withdrawal_claim(withdrawal_leaf_hash);
+        if self.get::<u128>(&claim_key).is_some() {
+            return 0;
+        }
+
+        let pending = self.get_pending_stx_withdrawal(recipient);
+        let released = pending.min(amount);
+        let key = ClarityDatabase::make_key_for_account_pending_stx_withdrawal(recipient);
+        self.put(&key, &(pending - released));
+        self.put(&claim_key, &released);
+        released
+    }
+}
+
 // access burnchain state
 impl<'a> ClarityDatabase<'a> {
     pub fn get_burn_block_height(&self, sortition_id: &SortitionId) -> Option<u32> {
@@ -313,7 +313,11 @@ impl ClarityBackingStore for MemoryBackingStore {
     }
 
     fn put_all(&mut self, items: Vec<(String, String)>) {
+        let tip = self.get_open_chain_tip();
         for (key, value) in items.into_iter() {
+            if ClarityDatabase::is_data_map_entry_key(&key) {
+                SqliteConnection::put_map_key(self.get_side_store(), &key, &tip, 0);
+            }
             SqliteConnection::put(self.get_side_store(), &key, &value);
         }
     }
@@ -26,7 +26,7 @@ use stacks_common::util::hash::Sha512Trunc256Sum;
 use crate::types::chainstate::StacksBlockId;
 
 use super::clarity_store::SpecialCaseHandler;
-use super::{ClarityBackingStore, ClarityDeserializable};
+use super::{ClarityBackingStore, ClarityDeserializable, SqliteConnection};
 
 #[cfg(rollback_value_check)]
 type RollbackValueCheck = String;
@@ -509,4 +509,36 @@ impl<'a> RollbackWrapper<'a> {
             _ => false,
         }
     }
+
+    /// Get the data map keys recorded in the underlying store (see
+    /// `SqliteConnection::get_map_keys`) that were written by this block or one of its
+    /// ancestors, out of the next `limit` recorded keys after `after`.  Also returns the last
+    /// recorded key that was looked at, to continue from, or None if there are no more.  Keys
+    /// first written in this context are not included until they are committed.
+    pub fn get_map_keys(
+        &mut self,
+        prefix: &str,
+        after: Option<&str>,
+        limit: u32,
+    ) -> (Vec<String>, Option<String>) {
+        let recorded =
+            SqliteConnection::get_map_keys(self.store.get_side_store(), prefix, after, limit);
+        let mut keys: Vec<String> = vec![];
+        let mut scanned = 0;
+        let mut last_key = None;
+        for (key, bhh, height) in recorded.into_iter() {
+            if last_key.as_ref() != Some(&key) {
+                scanned += 1;
+                last_key = Some(key.clone());
+            }
+            // keys written on other forks are skipped
+            if keys.last() != Some(&key)
+                && self.store.get_block_at_height(height).as_ref() == Some(&bhh)
+            {
+                keys.push(key);
+            }
+        }
+        let next = if scanned < limit { None } else { last_key };
+        (keys, next)
+    }
 }
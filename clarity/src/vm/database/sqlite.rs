@@ -155,6 +155,78 @@ impl SqliteConnection {
     pub fn has_entry(conn: &Connection, key: &str) -> bool {
         sqlite_has_entry(conn, key)
     }
+
+    /// Record that `key` was written by the block `bhh` at height `height`, so that it can be
+    /// found by `get_map_keys`.  A key written on several forks is recorded once per block.
+    pub fn put_map_key(conn: &Connection, key: &str, bhh: &StacksBlockId, height: u32) {
+        let params: [&dyn ToSql; 3] = [&key, &bhh, &height];
+        if let Err(e) = conn.execute(
+            "INSERT OR IGNORE INTO map_keys_table (key, blockhash, height) VALUES (?, ?, ?)",
+            &params,
+        ) {
+            error!("Failed to record map key {}: {:?}", key, &e);
+            panic!("{}", SQL_FAIL_MESSAGE);
+        }
+    }
+
+    /// Move the map keys recorded for block `from` to block `to` (see `commit_metadata_to`).
+    pub fn commit_map_keys_to(conn: &Connection, from: &StacksBlockId, to: &StacksBlockId) {
+        let params = [to, from];
+        if let Err(e) = conn.execute(
+            "UPDATE OR IGNORE map_keys_table SET blockhash = ? WHERE blockhash = ?",
+            &params,
+        ) {
+            error!(
+                "Failed to update map keys of {} to {}: {:?}",
+                &from, &to, &e
+            );
+            panic!("{}", SQL_FAIL_MESSAGE);
+        }
+    }
+
+    /// Forget the map keys recorded for block `from` (see `drop_metadata`).
+    pub fn drop_map_keys(conn: &Connection, from: &StacksBlockId) {
+        if let Err(e) = conn.execute("DELETE FROM map_keys_table WHERE blockhash = ?", &[from]) {
+            error!("Failed to drop map keys of {}: {:?}", &from, &e);
+            panic!("{}", SQL_FAIL_MESSAGE);
+        }
+    }
+
+    /// Get the recorded keys for up to `limit` distinct keys that start with `prefix`, in order,
+    /// starting after `after` if it is given.  Each key is returned once for each block that
+    /// wrote it, as (key, block, block height).
+    pub fn get_map_keys(
+        conn: &Connection,
+        prefix: &str,
+        after: Option<&str>,
+        limit: u32,
+    ) -> Vec<(String, StacksBlockId, u32)> {
+        let after = after.unwrap_or(prefix);
+        // the keys that start with `prefix` sort before `prefix` with its last character
+        // incremented
+        let mut end = prefix.to_string();
+        let last = end.pop().expect("BUG: empty map key prefix");
+        end.push(char::from_u32(last as u32 + 1).expect("BUG: invalid map key prefix"));
+        let params: [&dyn ToSql; 3] = [&after, &end, &limit];
+        let res = conn
+            .prepare(
+                "SELECT key, blockhash, height FROM map_keys_table WHERE key IN
+                   (SELECT DISTINCT key FROM map_keys_table WHERE key > ? AND key < ?
+                    ORDER BY key ASC LIMIT ?)
+                 ORDER BY key ASC",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(&params, |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+                    .and_then(|rows| rows.collect::<std::result::Result<Vec<_>, _>>())
+            });
+        match res {
+            Ok(keys) => keys,
+            Err(e) => {
+                error!("Failed to query map keys with prefix {}: {:?}", prefix, &e);
+                panic!("{}", SQL_FAIL_MESSAGE);
+            }
+        }
+    }
 }
 
 impl SqliteConnection {
@@ -177,10 +249,25 @@ impl SqliteConnection {
         )
         .map_err(|x| InterpreterError::SqliteError(IncomparableError { err: x }))?;
 
+        Self::initialize_map_keys(conn)?;
+
         Self::check_schema(conn)?;
 
         Ok(())
     }
+    /// Create the table of data map keys, if it does not exist yet.  It was added after the
+    /// other tables, so databases created before it need it added when they are opened.  The
+    /// maps such a database already holds are not in it (see `DataMapMetadata::keys_indexed`).
+    pub fn initialize_map_keys(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS map_keys_table
+                      (key TEXT NOT NULL, blockhash TEXT NOT NULL, height INTEGER NOT NULL,
+                       UNIQUE (key, blockhash))",
+            NO_PARAMS,
+        )
+        .map_err(|x| InterpreterError::SqliteError(IncomparableError { err: x }))?;
+        Ok(())
+    }
     pub fn memory() -> Result<Connection> {
         let contract_db = SqliteConnection::inner_open(":memory:")?;
         SqliteConnection::initialize_conn(&contract_db)?;
@@ -88,6 +88,13 @@ clarity_serializable!(NonFungibleTokenMetadata);
 pub struct DataMapMetadata {
     pub key_type: TypeSignature,
     pub value_type: TypeSignature,
+    /// whether the map keeps count of its entries (see `ClarityDatabase::create_map()`)
+    #[serde(default)]
+    pub track_size: bool,
+    /// whether the backing store recorded every key written to the map, which holds for maps
+    /// defined since it started recording them (see `ClarityDatabase::list_data_map_entries()`)
+    #[serde(default)]
+    pub keys_indexed: bool,
 }
 
 clarity_serializable!(DataMapMetadata);
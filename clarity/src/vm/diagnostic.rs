@@ -18,23 +18,31 @@ use crate::vm::representations::Span;
 use std::fmt;
 
 /// In a near future, we can go further in our static analysis and provide different levels
-/// of diagnostics, such as warnings, hints, best practices, etc.
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// of diagnostics, such as hints, best practices, etc.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum Level {
     Error,
+    Warning,
 }
 
 pub trait DiagnosableError {
     fn message(&self) -> String;
     fn suggestion(&self) -> Option<String>;
+    /// A stable, machine-readable name for the kind of error
+    fn code(&self) -> Option<String> {
+        None
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Diagnostic {
     pub level: Level,
     pub message: String,
     pub spans: Vec<Span>,
     pub suggestion: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
 }
 
 impl Diagnostic {
@@ -44,6 +52,14 @@ impl Diagnostic {
             level: Level::Error,
             message: error.message(),
             suggestion: error.suggestion(),
+            code: error.code(),
+        }
+    }
+
+    pub fn warn(error: &dyn DiagnosableError) -> Diagnostic {
+        Diagnostic {
+            level: Level::Warning,
+            ..Diagnostic::err(error)
         }
     }
 
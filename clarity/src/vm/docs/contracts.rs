@@ -1,6 +1,6 @@
 use crate::vm::analysis::{mem_type_check, ContractAnalysis};
 use crate::vm::docs::{get_input_type_string, get_output_type_string, get_signature};
-use crate::vm::types::{FunctionType, Value};
+use crate::vm::types::{FunctionSignature, FunctionType, Value};
 
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::iter::FromIterator;
@@ -10,7 +10,7 @@ use crate::vm::contexts::GlobalContext;
 use crate::vm::costs::LimitedCostTracker;
 use crate::vm::database::MemoryBackingStore;
 use crate::vm::types::QualifiedContractIdentifier;
-use crate::vm::{self, ContractContext};
+use crate::vm::{self, ClarityName, ClarityVersion, ContractContext};
 
 const DOCS_GENERATION_EPOCH: StacksEpochId = StacksEpochId::Epoch2_05;
 
@@ -19,6 +19,7 @@ pub struct ContractRef {
     public_functions: Vec<FunctionRef>,
     read_only_functions: Vec<FunctionRef>,
     error_codes: Vec<ErrorCode>,
+    traits: Vec<TraitRef>,
 }
 
 #[derive(Serialize)]
@@ -30,6 +31,21 @@ struct FunctionRef {
     description: String,
 }
 
+#[derive(Serialize)]
+struct TraitRef {
+    name: String,
+    description: String,
+    functions: Vec<TraitFunctionRef>,
+}
+
+#[derive(Serialize)]
+struct TraitFunctionRef {
+    name: String,
+    input_type: String,
+    output_type: String,
+    signature: String,
+}
+
 #[derive(Serialize)]
 struct ErrorCode {
     name: String,
@@ -57,6 +73,41 @@ fn make_func_ref(func_name: &str, func_type: &FunctionType, description: &str) -
     }
 }
 
+/// Trait functions have no argument names, so their signature is given in the same form as in
+/// the `define-trait` statement, e.g. `(transfer (uint principal) (response bool uint))`.
+fn make_trait_ref(
+    trait_name: &str,
+    functions: &BTreeMap<ClarityName, FunctionSignature>,
+    description: &str,
+) -> TraitRef {
+    let functions = functions
+        .iter()
+        .map(|(func_name, func_signature)| {
+            let arg_types: Vec<String> = func_signature
+                .args
+                .iter()
+                .map(|arg| format!("{}", arg))
+                .collect();
+            TraitFunctionRef {
+                name: func_name.to_string(),
+                input_type: arg_types.join(", "),
+                output_type: format!("{}", func_signature.returns),
+                signature: format!(
+                    "({} ({}) {})",
+                    func_name,
+                    arg_types.join(" "),
+                    func_signature.returns
+                ),
+            }
+        })
+        .collect();
+    TraitRef {
+        name: trait_name.to_string(),
+        description: description.to_string(),
+        functions,
+    }
+}
+
 fn get_constant_value(var_name: &str, contract_content: &str) -> Value {
     let to_eval = format!("{}\n{}", contract_content, var_name);
     doc_execute(&to_eval)
@@ -66,7 +117,7 @@ fn get_constant_value(var_name: &str, contract_content: &str) -> Value {
 
 fn doc_execute(program: &str) -> Result<Option<Value>, vm::Error> {
     let contract_id = QualifiedContractIdentifier::transient();
-    let mut contract_context = ContractContext::new(contract_id.clone());
+    let mut contract_context = ContractContext::new(contract_id.clone(), ClarityVersion::latest());
     let mut marf = MemoryBackingStore::new();
     let conn = marf.as_clarity_db();
     let mut global_context = GlobalContext::new(
@@ -89,6 +140,7 @@ pub fn make_docs(content: &str, support_docs: &ContractSupportDocs) -> ContractR
         public_function_types,
         read_only_function_types,
         variable_types,
+        defined_traits,
         ..
     } = contract_analysis;
     let public_functions: Vec<_> = public_function_types
@@ -115,6 +167,17 @@ pub fn make_docs(content: &str, support_docs: &ContractSupportDocs) -> ContractR
         })
         .collect();
 
+    let traits: Vec<_> = defined_traits
+        .iter()
+        .map(|(trait_name, functions)| {
+            let description = support_docs
+                .descriptions
+                .get(trait_name.as_str())
+                .expect(&format!("BUG: no description for {}", trait_name.as_str()));
+            make_trait_ref(trait_name, functions, description)
+        })
+        .collect();
+
     let ecode_names = variable_types
         .iter()
         .filter_map(|(var_name, _)| {
@@ -155,6 +218,7 @@ pub fn make_docs(content: &str, support_docs: &ContractSupportDocs) -> ContractR
         public_functions,
         read_only_functions,
         error_codes,
+        traits,
     }
 }
 
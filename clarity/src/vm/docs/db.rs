@@ -0,0 +1,113 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Fixed header and burnchain data that the documentation examples are evaluated against.  Any
+//! code that needs plausible chain data without a chain, such as a REPL, can use them too.
+
+use stacks_common::types::{StacksEpochId, PEER_VERSION_EPOCH_2_0};
+use stacks_common::util::hash::{MerkleTree, Sha512Trunc256Sum};
+
+use crate::types::chainstate::{
+    BlockHeaderHash, BurnchainHeaderHash, SortitionId, StacksAddress, StacksBlockId, VRFSeed,
+};
+use crate::vm::costs::ExecutionCost;
+use crate::vm::database::{BurnStateDB, HeadersDB};
+use crate::vm::StacksEpoch;
+
+/// A headers DB returning the same fixed header data for every block
+pub struct DocHeadersDB {}
+pub const DOC_HEADER_DB: DocHeadersDB = DocHeadersDB {};
+
+impl HeadersDB for DocHeadersDB {
+    fn get_burn_header_hash_for_block(&self, _bhh: &StacksBlockId) -> Option<BurnchainHeaderHash> {
+        Some(
+            BurnchainHeaderHash::from_hex(
+                "e67141016c88a7f1203eca0b4312f2ed141531f59303a1c267d7d83ab6b977d8",
+            )
+            .unwrap(),
+        )
+    }
+    fn get_vrf_seed_for_block(&self, _bhh: &StacksBlockId) -> Option<VRFSeed> {
+        Some(
+            VRFSeed::from_hex("f490de2920c8a35fabeb13208852aa28c76f9be9b03a4dd2b3c075f7a26923b4")
+                .unwrap(),
+        )
+    }
+    fn get_stacks_block_header_hash_for_block(
+        &self,
+        _id_bhh: &StacksBlockId,
+    ) -> Option<BlockHeaderHash> {
+        Some(
+            BlockHeaderHash::from_hex(
+                "374708fff7719dd5979ec875d56cd2286f6d3cf7ec317a3b25632aab28ec37bb",
+            )
+            .unwrap(),
+        )
+    }
+    fn get_burn_block_time_for_block(&self, _id_bhh: &StacksBlockId) -> Option<u64> {
+        Some(1557860301)
+    }
+    fn get_burn_block_height_for_block(&self, _id_bhh: &StacksBlockId) -> Option<u32> {
+        Some(567890)
+    }
+    fn get_miner_address(&self, _id_bhh: &StacksBlockId) -> Option<StacksAddress> {
+        None
+    }
+    fn get_withdrawal_tree_for_block(
+        &self,
+        _id_bhh: &StacksBlockId,
+    ) -> Option<MerkleTree<Sha512Trunc256Sum>> {
+        Some(MerkleTree::empty())
+    }
+}
+
+/// A burn state DB returning the same fixed burnchain data for every height, in a single
+/// epoch with no block limit
+pub struct DocBurnStateDB {}
+pub const DOC_POX_STATE_DB: DocBurnStateDB = DocBurnStateDB {};
+
+impl BurnStateDB for DocBurnStateDB {
+    fn get_burn_block_height(&self, _sortition_id: &SortitionId) -> Option<u32> {
+        Some(5678)
+    }
+    fn get_burn_header_hash(
+        &self,
+        _height: u32,
+        _sortition_id: &SortitionId,
+    ) -> Option<BurnchainHeaderHash> {
+        Some(
+            BurnchainHeaderHash::from_hex(
+                "e67141016c88a7f1203eca0b4312f2ed141531f59303a1c267d7d83ab6b977d8",
+            )
+            .unwrap(),
+        )
+    }
+    fn get_burn_header_timestamp(&self, _height: u32, _sortition_id: &SortitionId) -> Option<u64> {
+        Some(1557860301)
+    }
+    fn get_stacks_epoch(&self, _height: u32) -> Option<StacksEpoch> {
+        Some(StacksEpoch {
+            epoch_id: StacksEpochId::Epoch20,
+            start_height: 0,
+            end_height: u64::max_value(),
+            block_limit: ExecutionCost::max_value(),
+            network_epoch: PEER_VERSION_EPOCH_2_0,
+        })
+    }
+    fn get_stacks_epoch_by_epoch_id(&self, _epoch_id: &StacksEpochId) -> Option<StacksEpoch> {
+        self.get_stacks_epoch(0)
+    }
+}
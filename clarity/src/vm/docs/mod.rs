@@ -23,6 +23,7 @@ use crate::vm::types::{FixedFunction, FunctionType, Value};
 use crate::vm::variables::NativeVariables;
 
 pub mod contracts;
+pub mod db;
 
 #[derive(Serialize)]
 struct ReferenceAPIs {
@@ -86,6 +87,15 @@ const BURN_BLOCK_HEIGHT: KeywordAPI = KeywordAPI {
     example: "(> burn-block-height 1000) ;; returns true if the current height of the underlying burn blockchain has passed 1000 blocks."
 };
 
+const L1_BLOCK_HEIGHT: KeywordAPI = KeywordAPI {
+    name: "l1-block-height",
+    output_type: "uint",
+    description: "Returns the height of the L1 Stacks block that the current subnet block is anchored to, as a uint.
+Unlike `burn-block-height`, which is the L1 height of the _parent_ subnet block, this is the height of the L1 block
+that contains the current block's commitment, so it advances with L1 time even when subnet blocks are skipped.",
+    example: "(>= l1-block-height u1000) ;; returns true if the current subnet block is anchored at or after L1 block 1000."
+};
+
 const CONTRACT_CALLER_KEYWORD: KeywordAPI = KeywordAPI {
     name: "contract-caller",
     output_type: "principal",
@@ -167,6 +177,24 @@ const TO_INT_API: SimpleFunctionAPI = SimpleFunctionAPI {
     example: "(to-int u238) ;; Returns 238"
 };
 
+const BUFF_TO_UINT_BE_API: SimpleFunctionAPI = SimpleFunctionAPI {
+    name: None,
+    signature: "(buff-to-uint-be b)",
+    description: "Converts a buffer of at most 16 bytes to a `uint`, reading the first byte as the most significant. Buffers shorter than 16 bytes are treated as if padded with leading zero bytes, and the empty buffer converts to `u0`.",
+    example: "(buff-to-uint-be 0x0102) ;; Returns u258
+(buff-to-uint-be 0x) ;; Returns u0
+(buff-to-uint-be 0xffffffffffffffffffffffffffffffff) ;; Returns u340282366920938463463374607431768211455"
+};
+
+const BUFF_TO_UINT_LE_API: SimpleFunctionAPI = SimpleFunctionAPI {
+    name: None,
+    signature: "(buff-to-uint-le b)",
+    description: "Converts a buffer of at most 16 bytes to a `uint`, reading the first byte as the least significant. Buffers shorter than 16 bytes are treated as if padded with trailing zero bytes, and the empty buffer converts to `u0`.",
+    example: "(buff-to-uint-le 0x0102) ;; Returns u513
+(buff-to-uint-le 0x) ;; Returns u0
+(buff-to-uint-le 0x01000000000000000000000000000000) ;; Returns u1"
+};
+
 const ADD_API: SimpleFunctionAPI = SimpleFunctionAPI {
     name: Some("+ (add)"),
     signature: "(+ i1 i2...)",
@@ -254,6 +282,70 @@ const XOR_API: SimpleFunctionAPI = SimpleFunctionAPI {
 ",
 };
 
+const BITWISE_AND_API: SimpleFunctionAPI = SimpleFunctionAPI {
+    name: None,
+    signature: "(bit-and i1 i2...)",
+    description: "Returns the result of bitwise and'ing a variable number of integer inputs.",
+    example: "(bit-and 24 16) ;; Returns 16
+(bit-and 28 24 -1) ;; Returns 24
+(bit-and u24 u16) ;; Returns u16
+(bit-and -128 -64) ;; Returns -128
+",
+};
+
+const BITWISE_OR_API: SimpleFunctionAPI = SimpleFunctionAPI {
+    name: None,
+    signature: "(bit-or i1 i2...)",
+    description:
+        "Returns the result of bitwise inclusive or'ing a variable number of integer inputs.",
+    example: "(bit-or 4 8) ;; Returns 12
+(bit-or 1 2 4) ;; Returns 7
+(bit-or u4 u8) ;; Returns u12
+(bit-or -1 0) ;; Returns -1
+",
+};
+
+const BITWISE_NOT_API: SimpleFunctionAPI = SimpleFunctionAPI {
+    name: None,
+    signature: "(bit-not i1)",
+    description: "Returns the one's complement (sometimes also called the bitwise complement or not operator) of `i1`, effectively reversing the bits in `i1`.
+In other words, every bit that is `1` in `i1` will be `0` in the result. Conversely, every bit that is `0` in `i1` will be `1` in the result.",
+    example: "(bit-not 3) ;; Returns -4
+(bit-not u128) ;; Returns u340282366920938463463374607431768211327
+(bit-not -1) ;; Returns 0
+",
+};
+
+const BITWISE_LEFT_SHIFT_API: SpecialAPI = SpecialAPI {
+    input_type: "int, uint | uint, uint",
+    output_type: "int | uint",
+    signature: "(bit-shift-left i1 shamt)",
+    description: "Shifts all the bits in `i1` to the left by the number of places specified in `shamt` modulo 128 (the bit width of Clarity integers). Bits shifted out of the value are discarded, and the vacated low bits are filled with `0`.
+
+Note that the shift never fails: shifting by 128 places or more wraps around, and shifting a bit into the sign position of an `int` makes it negative.",
+    example: "(bit-shift-left 2 u1) ;; Returns 4
+(bit-shift-left 16 u2) ;; Returns 64
+(bit-shift-left -64 u1) ;; Returns -128
+(bit-shift-left u4 u2) ;; Returns u16
+(bit-shift-left 1 u128) ;; Returns 1
+",
+};
+
+const BITWISE_RIGHT_SHIFT_API: SpecialAPI = SpecialAPI {
+    input_type: "int, uint | uint, uint",
+    output_type: "int | uint",
+    signature: "(bit-shift-right i1 shamt)",
+    description: "Shifts all the bits in `i1` to the right by the number of places specified in `shamt` modulo 128 (the bit width of Clarity integers). Bits shifted out of the value are discarded. For a `uint`, the vacated high bits are filled with `0`. For an `int`, the shift is arithmetic: the vacated high bits are filled with copies of the sign bit, so negative numbers stay negative.
+
+Note that the shift never fails: shifting by 128 places or more wraps around.",
+    example: "(bit-shift-right 2 u1) ;; Returns 1
+(bit-shift-right 128 u2) ;; Returns 32
+(bit-shift-right -64 u1) ;; Returns -32
+(bit-shift-right u128 u2) ;; Returns u32
+(bit-shift-right 8 u129) ;; Returns 4
+",
+};
+
 const AND_API: SimpleFunctionAPI = SimpleFunctionAPI {
     name: None,
     signature: "(and b1 b2 ...)",
@@ -450,6 +542,17 @@ The value is looked up using `var-name`.",
 (var-get cursor) ;; Returns 6",
 };
 
+const VAR_EXISTS_API: SpecialAPI = SpecialAPI {
+    input_type: "VarName",
+    output_type: "bool",
+    signature: "(var-exists? var-name)",
+    description: "The `var-exists?` function returns `true` if the data variable `var-name` has a
+stored value in the contract's state. Every defined data variable is stored when the
+contract is deployed, so this returns `true` for any variable defined by the contract.",
+    example: "(define-data-var counter uint u0)
+(var-exists? counter) ;; Returns true",
+};
+
 const SET_VAR_API: SpecialAPI = SpecialAPI {
     input_type: "VarName, AnyType",
     output_type: "bool",
@@ -607,6 +710,76 @@ for which the corresponding element types are, respectively, `A`, `(buff 1)`, `(
 "#,
 };
 
+const SLICE_API: SpecialAPI = SpecialAPI {
+    input_type: "sequence_A, uint, uint",
+    output_type: "(optional sequence_A)",
+    signature: "(slice sequence start end)",
+    description: "The `slice` function returns the sub-sequence of the provided sequence
+that begins at index `start` and ends before index `end`. If `start` is greater than `end`, or
+`end` is greater than the length of the sequence, the function returns `none`.
+Applicable sequence types are `(list A)`, `buff`, `string-ascii` and `string-utf8`.
+",
+    example: r#"
+(slice "blockstack" u5 u10) ;; Returns (some "stack")
+(slice (list 1 2 3 4 5) u1 u3) ;; Returns (some (2 3))
+(slice 0x00010203 u2 u4) ;; Returns (some 0x0203)
+(slice "abcd" u2 u2) ;; Returns (some "")
+(slice "abcd" u3 u5) ;; Returns none
+"#,
+};
+
+const REPLACE_AT_API: SpecialAPI = SpecialAPI {
+    input_type: "sequence_A, uint, A",
+    output_type: "(optional sequence_A)",
+    signature: "(replace-at? sequence index new-element)",
+    description: "The `replace-at?` function returns a copy of the provided sequence in which the
+element at `index` is replaced by `new-element`. If `index` is not less than the length of the
+sequence, the function returns `none`. For buffers and strings, `new-element` must be a buffer
+or string of length 1.
+Applicable sequence types are `(list A)`, `buff`, `string-ascii` and `string-utf8`.
+",
+    example: r#"
+(replace-at? (list 1 2 3 4) u1 5) ;; Returns (some (1 5 3 4))
+(replace-at? "abcd" u0 "z") ;; Returns (some "zbcd")
+(replace-at? 0x00010203 u3 0xff) ;; Returns (some 0x000102ff)
+(replace-at? (list 1 2 3) u3 4) ;; Returns none
+"#,
+};
+
+const TO_CONSENSUS_BUFF_API: SpecialAPI = SpecialAPI {
+    input_type: "any",
+    output_type: "(optional buff)",
+    signature: "(to-consensus-buff? value)",
+    description: "The `to-consensus-buff?` function serializes the input Clarity value into a
+buffer with the SIP-005 consensus serialization, i.e. the same wire format used for Clarity values in
+transactions and the MARF. The maximum size of the returned buffer is determined by the type of
+`value`. If the serialization does not fit in a buffer, the function returns `none`.
+",
+    example: r#"
+(to-consensus-buff? 1) ;; Returns (some 0x0000000000000000000000000000000001)
+(to-consensus-buff? u1) ;; Returns (some 0x0100000000000000000000000000000001)
+(to-consensus-buff? true) ;; Returns (some 0x03)
+(to-consensus-buff? none) ;; Returns (some 0x09)
+(to-consensus-buff? (tuple (abc 3))) ;; Returns (some 0x0c00000001036162630000000000000000000000000000000003)
+"#,
+};
+
+const FROM_CONSENSUS_BUFF_API: SpecialAPI = SpecialAPI {
+    input_type: "type-signature(t), buff",
+    output_type: "(optional t)",
+    signature: "(from-consensus-buff? type-signature buffer)",
+    description: "The `from-consensus-buff?` function deserializes a buffer holding a Clarity value
+in the SIP-005 consensus serialization into a value of the given type. If the buffer does not hold
+exactly one value of that type, the function returns `none`.
+",
+    example: r#"
+(from-consensus-buff? int 0x0000000000000000000000000000000001) ;; Returns (some 1)
+(from-consensus-buff? uint 0x0000000000000000000000000000000001) ;; Returns none
+(from-consensus-buff? bool 0x0303) ;; Returns none
+(from-consensus-buff? (tuple (abc int)) 0x0c00000001036162630000000000000000000000000000000003) ;; Returns (some (tuple (abc 3)))
+"#,
+};
+
 const INDEX_OF_API: SpecialAPI = SpecialAPI {
     input_type: "sequence_A, A",
     output_type: "(optional uint)",
@@ -647,12 +820,21 @@ Note: intermediary statements returning a response type must be checked.",
 };
 
 const PRINT_API: SpecialAPI = SpecialAPI {
-    input_type: "A",
+    input_type: "A, (string-ascii 64)",
     output_type: "A",
-    signature: "(print expr)",
+    signature: "(print expr), (print expr topic)",
     description: "The `print` function evaluates and returns its input expression. On Stacks Core
-nodes configured for development (as opposed to production mining nodes), this function prints the resulting value to `STDOUT` (standard output).",
-    example: "(print (+ 1 2 3)) ;; Returns 6",
+nodes configured for development (as opposed to production mining nodes), this function prints the resulting value to `STDOUT` (standard output).
+The value is also emitted as a contract event. The event's topic is `\"print\"`, unless the optional
+`topic` argument (an ASCII string of at most 64 characters) is given, in which case that string is used instead.
+Only Clarity 2 contracts may pass a `topic`.
+From the subnet-1 epoch on, emitting the event costs an
+amount proportional to the serialized size of the value, which counts against the block's write budget.
+A subnet may also cap, in its genesis block, the total size of the events that a single transaction
+emits: a transaction that goes over the cap fails with a runtime error.",
+    example: "(print (+ 1 2 3)) ;; Returns 6
+(print (+ 1 2 3) \"sum\") ;; Returns 6
+",
 };
 
 const FETCH_ENTRY_API: SpecialAPI = SpecialAPI {
@@ -719,6 +901,22 @@ If a value did not exist for this key in the data map, the function returns `fal
 ",
 };
 
+const MAP_SIZE_API: SpecialAPI = SpecialAPI {
+    input_type: "MapName",
+    output_type: "uint",
+    signature: "(map-size map-name)",
+    description: "The `map-size` function returns the number of entries currently stored in the
+given data map. Inserting a new key increases the count, overwriting an existing key leaves
+it unchanged, and deleting a key decreases it.",
+    example: "(define-map scores principal uint)
+(map-size scores) ;; Returns u0
+(map-set scores tx-sender u10) ;; Returns true
+(map-set scores tx-sender u20) ;; Returns true
+(map-size scores) ;; Returns u1
+(map-delete scores tx-sender) ;; Returns true
+(map-size scores) ;; Returns u0",
+};
+
 const TUPLE_CONS_API: SpecialAPI = SpecialAPI {
     input_type: "(key-name A), (key-name-2 B), ...",
     output_type: "(tuple (key-name A) (key-name-2 B) ...)",
@@ -856,6 +1054,23 @@ If the function returns _ok_, database changes occurred.",
 (as-contract (contract-call? .tokens mint! u19)) ;; Returns (ok u19)"
 };
 
+const TRY_CONTRACT_CALL_API: SpecialAPI = SpecialAPI {
+    input_type: "ContractName, PublicFunctionName, Arg0, ...",
+    output_type: "(response A (optional B))",
+    signature: "(try-contract-call? .contract-name function-name arg0 arg1 ...)",
+    description: "The `try-contract-call?` function executes the given public function of the given contract,
+like `contract-call?`, but a runtime error in the called function (e.g., an arithmetic overflow or a failed
+`unwrap-panic`) does not abort the caller's transaction. If the function returns `(ok a)`, its database
+changes are kept and `(ok a)` is returned. If the function returns `(err e)`, its database changes are
+aborted and `(err (some e))` is returned. If the function aborts with a runtime error, its database changes,
+events and asset transfers are aborted and `(err none)` is returned. Errors in evaluating the arguments,
+exceeding the cost budget, or exceeding the maximum call stack depth still abort the transaction.",
+    example: "
+;; instantiate the sample-contracts/tokens.clar contract first!
+(as-contract (try-contract-call? .tokens mint! u19)) ;; Returns (ok u19)
+(as-contract (try-contract-call? .tokens mint! u0)) ;; Returns (err (some \"must move positive balance\"))"
+};
+
 const CONTRACT_OF_API: SpecialAPI = SpecialAPI {
     input_type: "Trait",
     output_type: "principal",
@@ -1170,6 +1385,55 @@ The `id-header-hash` is the block identifier value that must be used as input to
 "
 };
 
+const GET_WITHDRAWAL_INFO_API: SpecialAPI = SpecialAPI {
+    input_type: "WithdrawalAssetType, uint, uint, principal, <principal>, uint",
+    output_type: "(optional (tuple (withdrawal-root (buff 32)) (withdrawal-leaf-hash (buff 32))))",
+    signature: "(get-withdrawal-info? asset-type block-height withdrawal-id sender <asset-contract> amount-or-id)",
+    description: "The `get-withdrawal-info?` function checks whether a withdrawal was made in the block at the
+given block height, and returns information about it that can be used to finalize the withdrawal on the L1 chain.
+If the withdrawal exists, the function returns the `withdrawal-root` of the block's withdrawal Merkle tree, and
+the `withdrawal-leaf-hash` of the withdrawal. Otherwise, or if the provided block height does not correspond to
+an existing block prior to the current block, the function returns `none`.
+
+The `asset-type` is one of `stx`, `ft`, and `nft`, and determines the remaining arguments:
+
+`(get-withdrawal-info? stx block-height withdrawal-id sender amount)`
+
+`(get-withdrawal-info? ft block-height withdrawal-id sender asset-contract amount)`
+
+`(get-withdrawal-info? nft block-height withdrawal-id sender asset-contract nft-id)`
+
+The `withdrawal-id` is the index of the withdrawal among all of the withdrawals made in the block, and is
+reported by the node's withdrawal events. The `asset-contract` is the contract that defines the withdrawn
+token or NFT.
+",
+    example: ";; the block at height 10 contains a single withdrawal, of 100 microSTX
+(get-withdrawal-info? stx u10 u0 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF u100) ;; Returns (some (tuple (withdrawal-leaf-hash 0x005ea90746f073a3b714984655ccb41a44f1cd108f22280e7aaf1301749ff58f) (withdrawal-root 0xa780cb5644e09985840fefde2d172fad5f7417b4747df7ffb0713aa1e76915bd)))
+(get-withdrawal-info? stx u10 u0 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF u99) ;; Returns none
+(get-withdrawal-info? nft u10 u1 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF.nft-contract u7) ;; Returns none
+"
+};
+
+const GET_L1_BLOCK_INFO_API: SpecialAPI = SpecialAPI {
+    input_type: "L1BlockInfoPropertyName, L1BlockHeightInt",
+    output_type: "(optional buff) | (optional uint)",
+    signature: "(get-l1-block-info? prop-name l1-block-height-expr)",
+    description: "The `get-l1-block-info?` function fetches data for the L1 block at the given L1 block height.
+The L1 block is read from the L1 fork that the parent of the current block was committed to, so subnet
+nodes agree on its value without relying on an oracle. If that L1 fork does not have a block at the provided
+`L1BlockHeightInt`, the function returns `none`. The value and type returned are determined by the specified
+`L1BlockInfoPropertyName`. The currently available property names are `header-hash` and `time`.
+
+The `header-hash` property returns the 32-byte header hash of the L1 block.
+
+The `time` property returns an integer value of the L1 block header time field. This is a Unix epoch timestamp
+in seconds which roughly corresponds to when the L1 block was mined.
+",
+    example: "(get-l1-block-info? header-hash u1) ;; Returns (some 0xe67141016c88a7f1203eca0b4312f2ed141531f59303a1c267d7d83ab6b977d8)
+(get-l1-block-info? time u1) ;; Returns (some u1557860301)
+"
+};
+
 const DEFINE_TOKEN_API: DefineAPI = DefineAPI {
     input_type: "TokenName, <uint>",
     output_type: "Not Applicable",
@@ -1394,6 +1658,35 @@ definition (i.e., you cannot put such a statement in the middle of a function bo
 "
 };
 
+const DEFINE_DEPOSIT_HANDLER_API: DefineAPI = DefineAPI {
+    input_type: "FunctionName",
+    output_type: "Not Applicable",
+    signature: "(define-deposit-handler function-name)",
+    description: "`define-deposit-handler` registers a private function of the contract to be invoked
+whenever the subnet credits a deposit from the L1 to the contract's principal. The function is called with
+the depositor, the asset, and the amount deposited:
+
+- for STX deposits, the asset is `none` and the amount is in micro-STX,
+- for fungible token deposits, the asset is the subnet token contract and the amount is the number of tokens,
+- for NFT deposits, the asset is the subnet NFT contract and the amount is the NFT's identifier.
+
+The handler must take arguments of type `principal`, `(optional principal)` and `uint`, and return a
+response. `tx-sender` and `contract-caller` are the depositor. If the handler returns an `err`, or fails,
+its effects are rolled back, but the deposit is still credited. A contract can define at most one deposit handler.
+
+Like other kinds of definition statements, `define-deposit-handler` may only be used at the top level of a smart contract
+definition (i.e., you cannot put such a statement in the middle of a function body).
+",
+    example: "
+(define-data-var total-deposited uint u0)
+(define-private (on-deposit (depositor principal) (asset (optional principal)) (amount uint))
+  (begin
+    (var-set total-deposited (+ (var-get total-deposited) amount))
+    (ok true)))
+(define-deposit-handler on-deposit)
+"
+};
+
 const MINT_TOKEN: SpecialAPI = SpecialAPI {
     input_type: "TokenName, uint, principal",
     output_type: "(response bool uint)",
@@ -1560,9 +1853,9 @@ Otherwise, on successfuly burn, it returns `(ok true)`.
 };
 
 const WITHDRAW_TOKEN: SpecialAPI = SpecialAPI {
-    input_type: "TokenName, uint, principal",
+    input_type: "TokenName, uint, principal, [uint]",
     output_type: "(response bool uint)",
-    signature: "(ft-withdraw? token-name amount sender)",
+    signature: "(ft-withdraw? token-name amount sender [tip])",
     description:
         "`ft-withdraw?` is used to withdraw the token balance for the `sender` principal for a token
 type defined using `define-fungible-token` from the subnet. The Stacks L1 chain will then be
@@ -1573,6 +1866,20 @@ returns one of the following error codes:
 
 `(err u1)` -- `sender` does not have enough balance to withdraw this amount
 `(err u3)` -- the amount specified is not positive
+`(err u5)` -- `sender` does not have enough STX to pay the `tip`
+`(err u6)` -- a `tip` was given, but `sender` is not the current `tx-sender`
+
+The optional `tip` is an amount of uSTX debited from `sender` and paid, on the L1, to the miner of
+the block that includes the withdrawal. Miners may decline to include withdrawals whose tip is below
+their configured minimum. Only Clarity 2 contracts may pass a `tip`.
+
+`ft-withdraw?` can only withdraw tokens defined in the calling contract. To let other contracts
+withdraw any conforming token, a token contract can expose it through a public function matching a
+trait, e.g. `(define-trait withdrawable-trait ((withdraw (uint principal) (response bool uint))))`.
+A wrapper contract can then accept the token contract as a `<withdrawable-trait>` argument, and
+withdraw from it with `contract-call?`. Since any contract can call the token contract's `withdraw`
+function, it must check that `sender` is `tx-sender`, e.g. with
+`(asserts! (is-eq tx-sender sender) (err u1001))`, or anyone could withdraw anyone's tokens.
 ",
     example: "
 (define-fungible-token stackaroo)
@@ -1582,9 +1889,9 @@ returns one of the following error codes:
 };
 
 const WITHDRAW_ASSET: SpecialAPI = SpecialAPI {
-    input_type: "AssetName, A, principal",
+    input_type: "AssetName, A, principal, [uint]",
     output_type: "(response bool uint)",
-    signature: "(nft-withdraw? asset-class asset-identifier recipient)",
+    signature: "(nft-withdraw? asset-class asset-identifier recipient [tip])",
     description: "`nft-withdraw?` is used to withdraw an asset for the `sender` principal for an
 asset defined using `define-non-fungible-token` on the subnet. The Stacks L1 chain will then be
 able to verify this withdraw when it processes the withdrawal of this asset.
@@ -1601,6 +1908,16 @@ returns one of the following error codes:
 `(err u1)` -- `sender` does not own the specified asset
 `(err u3)` -- the asset specified by `asset-identifier` does not exist
 `(err u4)` -- the asset specified by `asset-identifier` does not have type uint.
+`(err u5)` -- `sender` does not have enough STX to pay the `tip`
+`(err u6)` -- a `tip` was given, but `sender` is not the current `tx-sender`
+
+As with `ft-withdraw?`, the optional `tip` is an amount of uSTX paid to the miner of the block that
+includes the withdrawal.
+
+Like `ft-withdraw?`, `nft-withdraw?` can only withdraw assets defined in the calling contract, but a
+token contract can expose it through a trait function (e.g., `(withdraw (uint principal) (response bool uint))`),
+so that wrapper contracts can withdraw from any conforming token contract passed to them. That
+function must check that `sender` is `tx-sender` before withdrawing.
 ",
     example: "
 (define-non-fungible-token foo uint)
@@ -1627,6 +1944,24 @@ principal isn't materialized, it returns 0.
 ",
 };
 
+const STX_GET_ACCOUNT: SimpleFunctionAPI = SimpleFunctionAPI {
+    name: None,
+    signature: "(stx-account owner)",
+    description: "`stx-account` is used to query the STX account of the `owner` principal.
+
+This function returns a tuple with the `unlocked` STX balance of the `owner` principal, which it may
+spend or withdraw, the `pending-withdrawal` amount, which it withdrew from the subnet but which has not
+yet been claimed on the L1, and its `total` balance, which includes the pending withdrawals. Withdrawn
+STX are held in escrow until their L1 claim is observed, so contracts can tell which portion of a
+principal's funds is already earmarked for withdrawal. In the event that the `owner` principal isn't
+materialized, every field is 0.
+",
+    example: "
+(stx-account 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR) ;; Returns (tuple (pending-withdrawal u0) (total u0) (unlocked u0))
+(stx-account (as-contract tx-sender)) ;; Returns (tuple (pending-withdrawal u0) (total u1000) (unlocked u1000))
+",
+};
+
 const STX_TRANSFER: SimpleFunctionAPI = SimpleFunctionAPI {
     name: None,
     signature: "(stx-transfer? amount sender recipient)",
@@ -1670,25 +2005,32 @@ one of the following error codes:
 "
 };
 
-const STX_WITHDRAW: SimpleFunctionAPI = SimpleFunctionAPI {
-    name: None,
-    signature: "(stx-withdraw? amount sender)",
+const STX_WITHDRAW: SpecialAPI = SpecialAPI {
+    input_type: "uint, principal, [uint]",
+    output_type: "(response bool uint)",
+    signature: "(stx-withdraw? amount sender [tip])",
     description: "`stx-withdraw?` debits the `sender` principal's STX holdings by `amount`, destroying
 the STX on the subnet. The Stacks L1 chain will then be able to verify this withdraw when
 it processes the withdrawal of this asset.
 
 The `sender` principal _must_ be equal to the current context's `tx-sender`.
 
+The optional `tip` is an additional amount of uSTX debited from `sender` and paid, on the L1, to
+the miner of the block that includes the withdrawal. Miners may decline to include withdrawals whose
+tip is below their configured minimum. Only Clarity 2 contracts may pass a `tip`.
+
 This function returns (ok true) if the transfer is successful. In the event of an unsuccessful withdraw it returns
 one of the following error codes:
 
-`(err u1)` -- `sender` does not have enough balance to withdraw this amount
+`(err u1)` -- `sender` does not have enough balance to withdraw this amount and pay the `tip`
 `(err u3)` -- amount to withdraw is non-positive
 `(err u4)` -- the `sender` principal is not the current `tx-sender`
 ",
     example: "
 (as-contract
   (stx-withdraw? u60 tx-sender)) ;; Returns (ok true)
+(as-contract
+  (stx-withdraw? u50 tx-sender u10)) ;; Returns (ok true)
 (as-contract
   (stx-withdraw? u50 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)) ;; Returns (err u4)
 "
@@ -1701,6 +2043,8 @@ fn make_api_reference(function: &NativeFunctions) -> FunctionAPI {
         Add => make_for_simple_native(&ADD_API, &Add, name),
         ToUInt => make_for_simple_native(&TO_UINT_API, &ToUInt, name),
         ToInt => make_for_simple_native(&TO_INT_API, &ToInt, name),
+        BuffToUIntBe => make_for_simple_native(&BUFF_TO_UINT_BE_API, &BuffToUIntBe, name),
+        BuffToUIntLe => make_for_simple_native(&BUFF_TO_UINT_LE_API, &BuffToUIntLe, name),
         Subtract => make_for_simple_native(&SUB_API, &Subtract, name),
         Multiply => make_for_simple_native(&MUL_API, &Multiply, name),
         Divide => make_for_simple_native(&DIV_API, &Divide, name),
@@ -1713,6 +2057,11 @@ fn make_api_reference(function: &NativeFunctions) -> FunctionAPI {
         Sqrti => make_for_simple_native(&SQRTI_API, &Sqrti, name),
         Log2 => make_for_simple_native(&LOG2_API, &Log2, name),
         BitwiseXOR => make_for_simple_native(&XOR_API, &BitwiseXOR, name),
+        BitwiseAnd => make_for_simple_native(&BITWISE_AND_API, &BitwiseAnd, name),
+        BitwiseOr => make_for_simple_native(&BITWISE_OR_API, &BitwiseOr, name),
+        BitwiseNot => make_for_simple_native(&BITWISE_NOT_API, &BitwiseNot, name),
+        BitwiseLShift => make_for_special(&BITWISE_LEFT_SHIFT_API, name),
+        BitwiseRShift => make_for_special(&BITWISE_RIGHT_SHIFT_API, name),
         And => make_for_simple_native(&AND_API, &And, name),
         Or => make_for_simple_native(&OR_API, &Or, name),
         Not => make_for_simple_native(&NOT_API, &Not, name),
@@ -1720,6 +2069,7 @@ fn make_api_reference(function: &NativeFunctions) -> FunctionAPI {
         If => make_for_special(&IF_API, name),
         Let => make_for_special(&LET_API, name),
         FetchVar => make_for_special(&FETCH_VAR_API, name),
+        VarExists => make_for_special(&VAR_EXISTS_API, name),
         SetVar => make_for_special(&SET_VAR_API, name),
         Map => make_for_special(&MAP_API, name),
         Filter => make_for_special(&FILTER_API, name),
@@ -1729,12 +2079,17 @@ fn make_api_reference(function: &NativeFunctions) -> FunctionAPI {
         AsMaxLen => make_for_special(&ASSERTS_MAX_LEN_API, name),
         Len => make_for_special(&LEN_API, name),
         ElementAt => make_for_special(&ELEMENT_AT_API, name),
+        Slice => make_for_special(&SLICE_API, name),
+        ReplaceAt => make_for_special(&REPLACE_AT_API, name),
+        ToConsensusBuff => make_for_special(&TO_CONSENSUS_BUFF_API, name),
+        FromConsensusBuff => make_for_special(&FROM_CONSENSUS_BUFF_API, name),
         IndexOf => make_for_special(&INDEX_OF_API, name),
         ListCons => make_for_special(&LIST_API, name),
         FetchEntry => make_for_special(&FETCH_ENTRY_API, name),
         SetEntry => make_for_special(&SET_ENTRY_API, name),
         InsertEntry => make_for_special(&INSERT_ENTRY_API, name),
         DeleteEntry => make_for_special(&DELETE_ENTRY_API, name),
+        MapSize => make_for_special(&MAP_SIZE_API, name),
         TupleCons => make_for_special(&TUPLE_CONS_API, name),
         TupleGet => make_for_special(&TUPLE_GET_API, name),
         TupleMerge => make_for_special(&TUPLE_MERGE_API, name),
@@ -1748,6 +2103,7 @@ fn make_api_reference(function: &NativeFunctions) -> FunctionAPI {
         Secp256k1Verify => make_for_special(&SECP256K1VERIFY_API, name),
         Print => make_for_special(&PRINT_API, name),
         ContractCall => make_for_special(&CONTRACT_CALL_API, name),
+        TryContractCall => make_for_special(&TRY_CONTRACT_CALL_API, name),
         ContractOf => make_for_special(&CONTRACT_OF_API, name),
         PrincipalOf => make_for_special(&PRINCIPAL_OF_API, name),
         AsContract => make_for_special(&AS_CONTRACT_API, name),
@@ -1778,11 +2134,14 @@ fn make_api_reference(function: &NativeFunctions) -> FunctionAPI {
         GetTokenSupply => make_for_special(&GET_TOKEN_SUPPLY, name),
         AtBlock => make_for_special(&AT_BLOCK, name),
         GetStxBalance => make_for_simple_native(&STX_GET_BALANCE, &GetStxBalance, name),
+        StxGetAccount => make_for_simple_native(&STX_GET_ACCOUNT, &StxGetAccount, name),
         StxTransfer => make_for_simple_native(&STX_TRANSFER, &StxTransfer, name),
         StxBurn => make_for_simple_native(&STX_BURN, &StxBurn, name),
         WithdrawToken => make_for_special(&WITHDRAW_TOKEN, name),
         WithdrawAsset => make_for_special(&WITHDRAW_ASSET, name),
-        StxWithdraw => make_for_simple_native(&STX_WITHDRAW, &StxWithdraw, name),
+        StxWithdraw => make_for_special(&STX_WITHDRAW, name),
+        GetWithdrawalInfo => make_for_special(&GET_WITHDRAWAL_INFO_API, name),
+        GetL1BlockInfo => make_for_special(&GET_L1_BLOCK_INFO_API, name),
     }
 }
 
@@ -1795,6 +2154,7 @@ fn make_keyword_reference(variable: &NativeVariables) -> Option<KeywordAPI> {
         NativeVariables::NativeFalse => Some(FALSE_KEYWORD.clone()),
         NativeVariables::BlockHeight => Some(BLOCK_HEIGHT.clone()),
         NativeVariables::BurnBlockHeight => Some(BURN_BLOCK_HEIGHT.clone()),
+        NativeVariables::L1BlockHeight => Some(L1_BLOCK_HEIGHT.clone()),
         NativeVariables::TotalLiquidMicroSTX => Some(TOTAL_LIQUID_USTX_KEYWORD.clone()),
         NativeVariables::Regtest => Some(REGTEST_KEYWORD.clone()),
     }
@@ -1837,6 +2197,7 @@ fn make_define_reference(define_type: &DefineFunctions) -> FunctionAPI {
         Trait => make_for_define(&DEFINE_TRAIT_API, name),
         UseTrait => make_for_define(&USE_TRAIT_API, name),
         ImplTrait => make_for_define(&IMPL_TRAIT_API, name),
+        DepositHandler => make_for_define(&DEFINE_DEPOSIT_HANDLER_API, name),
     }
 }
 
@@ -1875,30 +2236,21 @@ pub fn make_json_api_reference() -> String {
 #[cfg(test)]
 mod test {
     use crate::vm::{
-        ast,
-        contexts::OwnedEnvironment,
-        database::{BurnStateDB, HeadersDB, STXBalance},
-        eval_all, execute,
-        types::PrincipalData,
-        ContractContext, Error, GlobalContext, LimitedCostTracker, QualifiedContractIdentifier,
-        Value,
+        ast, contexts::OwnedEnvironment, database::STXBalance, eval_all, execute,
+        types::PrincipalData, ClarityVersion, ContractContext, Error, GlobalContext,
+        LimitedCostTracker, QualifiedContractIdentifier, Value,
     };
-    use stacks_common::types::{StacksEpochId, PEER_VERSION_EPOCH_2_0};
+    use stacks_common::types::StacksEpochId;
+
+    use std::collections::{HashMap, HashSet};
+    use std::iter::FromIterator;
 
+    use super::contracts::{make_docs, ContractSupportDocs};
+    use super::db::{DOC_HEADER_DB, DOC_POX_STATE_DB};
     use super::make_all_api_reference;
     use super::make_json_api_reference;
-    use crate::types::chainstate::{SortitionId, StacksAddress, StacksBlockId};
     use crate::vm::analysis::type_check;
-    use crate::{types::chainstate::VRFSeed, vm::StacksEpoch};
-    use crate::{
-        types::chainstate::{BlockHeaderHash, BurnchainHeaderHash},
-        vm::database::{ClarityDatabase, MemoryBackingStore},
-    };
-
-    use crate::vm::costs::ExecutionCost;
-
-    struct DocHeadersDB {}
-    const DOC_HEADER_DB: DocHeadersDB = DocHeadersDB {};
+    use crate::vm::database::{ClarityDatabase, MemoryBackingStore};
 
     impl MemoryBackingStore {
         pub fn as_docs_clarity_db<'a>(&'a mut self) -> ClarityDatabase<'a> {
@@ -1906,76 +2258,6 @@ mod test {
         }
     }
 
-    impl HeadersDB for DocHeadersDB {
-        fn get_burn_header_hash_for_block(
-            &self,
-            _bhh: &StacksBlockId,
-        ) -> Option<BurnchainHeaderHash> {
-            None
-        }
-        fn get_vrf_seed_for_block(&self, _bhh: &StacksBlockId) -> Option<VRFSeed> {
-            Some(
-                VRFSeed::from_hex(
-                    "f490de2920c8a35fabeb13208852aa28c76f9be9b03a4dd2b3c075f7a26923b4",
-                )
-                .unwrap(),
-            )
-        }
-        fn get_stacks_block_header_hash_for_block(
-            &self,
-            _id_bhh: &StacksBlockId,
-        ) -> Option<BlockHeaderHash> {
-            Some(
-                BlockHeaderHash::from_hex(
-                    "374708fff7719dd5979ec875d56cd2286f6d3cf7ec317a3b25632aab28ec37bb",
-                )
-                .unwrap(),
-            )
-        }
-        fn get_burn_block_time_for_block(&self, _id_bhh: &StacksBlockId) -> Option<u64> {
-            Some(1557860301)
-        }
-        fn get_burn_block_height_for_block(&self, _id_bhh: &StacksBlockId) -> Option<u32> {
-            Some(567890)
-        }
-        fn get_miner_address(&self, _id_bhh: &StacksBlockId) -> Option<StacksAddress> {
-            None
-        }
-    }
-
-    struct DocBurnStateDB {}
-    const DOC_POX_STATE_DB: DocBurnStateDB = DocBurnStateDB {};
-
-    impl BurnStateDB for DocBurnStateDB {
-        fn get_burn_block_height(&self, _sortition_id: &SortitionId) -> Option<u32> {
-            Some(5678)
-        }
-        fn get_burn_header_hash(
-            &self,
-            height: u32,
-            _sortition_id: &SortitionId,
-        ) -> Option<BurnchainHeaderHash> {
-            Some(
-                BurnchainHeaderHash::from_hex(
-                    "e67141016c88a7f1203eca0b4312f2ed141531f59303a1c267d7d83ab6b977d8",
-                )
-                .unwrap(),
-            )
-        }
-        fn get_stacks_epoch(&self, height: u32) -> Option<StacksEpoch> {
-            Some(StacksEpoch {
-                epoch_id: StacksEpochId::Epoch20,
-                start_height: 0,
-                end_height: u64::max_value(),
-                block_limit: ExecutionCost::max_value(),
-                network_epoch: PEER_VERSION_EPOCH_2_0,
-            })
-        }
-        fn get_stacks_epoch_by_epoch_id(&self, epoch_id: &StacksEpochId) -> Option<StacksEpoch> {
-            self.get_stacks_epoch(0)
-        }
-    }
-
     fn docs_execute(store: &mut MemoryBackingStore, program: &str) {
         // execute the program, iterating at each ";; Returns" comment
         // there are maybe more rust-y ways of doing this, but this is the simplest.
@@ -2008,7 +2290,8 @@ mod test {
         }
 
         let conn = store.as_docs_clarity_db();
-        let mut contract_context = ContractContext::new(contract_id.clone());
+        let mut contract_context =
+            ContractContext::new(contract_id.clone(), ClarityVersion::latest());
         let mut global_context = GlobalContext::new(
             false,
             conn,
@@ -2051,6 +2334,46 @@ mod test {
         make_json_api_reference();
     }
 
+    #[test]
+    fn test_contract_docs_traits() {
+        let contract = "(define-constant ERR_NOT_AUTHORIZED (err u1))
+            (define-trait withdrawable-trait
+              ((withdraw (uint principal) (response bool uint))))
+            (define-public (withdraw-from (token <withdrawable-trait>) (amount uint))
+              (contract-call? token withdraw amount tx-sender))";
+        let support_docs = ContractSupportDocs {
+            descriptions: HashMap::from_iter(vec![
+                (
+                    "withdrawable-trait",
+                    "A token that can be withdrawn to the L1",
+                ),
+                ("withdraw-from", "Withdraw `amount` of the caller's `token`"),
+            ]),
+            skip_func_display: HashSet::new(),
+        };
+        let docs = serde_json::to_value(make_docs(contract, &support_docs)).unwrap();
+
+        let trait_ref = &docs["traits"][0];
+        assert_eq!(trait_ref["name"], "withdrawable-trait");
+        assert_eq!(
+            trait_ref["description"],
+            "A token that can be withdrawn to the L1"
+        );
+        assert_eq!(trait_ref["functions"][0]["name"], "withdraw");
+        assert_eq!(trait_ref["functions"][0]["input_type"], "uint, principal");
+        assert_eq!(
+            trait_ref["functions"][0]["output_type"],
+            "(response bool uint)"
+        );
+        assert_eq!(
+            trait_ref["functions"][0]["signature"],
+            "(withdraw (uint principal) (response bool uint))"
+        );
+
+        let input_type = docs["public_functions"][0]["input_type"].as_str().unwrap();
+        assert!(input_type.ends_with(".withdrawable-trait>, uint"));
+    }
+
     #[test]
     fn test_examples() {
         let apis = make_all_api_reference();
@@ -2066,6 +2389,12 @@ mod test {
                 );
                 continue;
             }
+            if func_api.name == "get-withdrawal-info?" {
+                eprintln!(
+                    "Skipping get-withdrawal-info?, because it cannot be evaluated without a MARF"
+                );
+                continue;
+            }
 
             let mut store = MemoryBackingStore::new();
             // first, load the samples for contract-call
@@ -16,7 +16,9 @@
 
 use crate::types::chainstate::BlockHeaderHash;
 pub use crate::vm::analysis::errors::CheckErrors;
-pub use crate::vm::analysis::errors::{check_argument_count, check_arguments_at_least};
+pub use crate::vm::analysis::errors::{
+    check_argument_count, check_arguments_at_least, check_arguments_at_most,
+};
 use crate::vm::ast::errors::ParseError;
 use crate::vm::contexts::StackTrace;
 use crate::vm::costs::CostErrors;
@@ -95,6 +97,8 @@ pub enum RuntimeErrorType {
     UnknownBlockHeaderHash(BlockHeaderHash),
     BadBlockHash(Vec<u8>),
     UnwrapFailure,
+    /// the transaction's events took more than this many bytes, the node's per-transaction cap
+    EventSizeLimitExceeded(u64),
 }
 
 #[derive(Debug, PartialEq)]
@@ -20,7 +20,7 @@ use crate::vm::analysis::ContractAnalysis;
 use crate::vm::costs::ExecutionCost;
 use crate::vm::types::{
     AssetIdentifier, BuffData, PrincipalData, QualifiedContractIdentifier, StandardPrincipalData,
-    Value,
+    TupleData, Value,
 };
 
 #[derive(Debug, Clone, PartialEq)]
@@ -81,6 +81,15 @@ impl StacksTransactionEvent {
                 "type": "stx_withdraw_event",
                 "stx_withdraw_event": event_data.json_serialize()
             }),
+            StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawTipEvent(event_data)) => {
+                json!({
+                    "txid": format!("0x{:?}", txid),
+                    "event_index": event_index,
+                    "committed": committed,
+                    "type": "stx_withdraw_tip_event",
+                    "stx_withdraw_tip_event": event_data.json_serialize()
+                })
+            }
             StacksTransactionEvent::NFTEvent(NFTEventType::NFTTransferEvent(event_data)) => json!({
                 "txid": format!("0x{:?}", txid),
                 "event_index": event_index,
@@ -139,6 +148,96 @@ impl StacksTransactionEvent {
             }),
         }
     }
+
+    /// The number of bytes of Clarity data carried by this event, which is what emitting it
+    /// is charged for from the subnet-1 epoch on.
+    pub fn payload_size(&self) -> u64 {
+        match self {
+            StacksTransactionEvent::SmartContractEvent(event_data) => {
+                let (contract_identifier, topic) = &event_data.key;
+                contract_size(contract_identifier)
+                    + topic.len() as u64
+                    + u64::from(event_data.value.serialized_size())
+            }
+            StacksTransactionEvent::STXEvent(STXEventType::STXTransferEvent(event_data)) => {
+                principal_size(&event_data.sender)
+                    + principal_size(&event_data.recipient)
+                    + UINT_SIZE
+            }
+            StacksTransactionEvent::STXEvent(STXEventType::STXMintEvent(event_data)) => {
+                principal_size(&event_data.recipient) + UINT_SIZE
+            }
+            StacksTransactionEvent::STXEvent(STXEventType::STXBurnEvent(event_data)) => {
+                principal_size(&event_data.sender) + UINT_SIZE
+            }
+            StacksTransactionEvent::STXEvent(STXEventType::STXLockEvent(event_data)) => {
+                principal_size(&event_data.locked_address) + 2 * UINT_SIZE
+            }
+            StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawEvent(event_data)) => {
+                principal_size(&event_data.sender) + UINT_SIZE
+            }
+            StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawTipEvent(event_data)) => {
+                principal_size(&event_data.sender) + UINT_SIZE
+            }
+            StacksTransactionEvent::NFTEvent(NFTEventType::NFTTransferEvent(event_data)) => {
+                principal_size(&event_data.sender)
+                    + principal_size(&event_data.recipient)
+                    + asset_identifier_size(&event_data.asset_identifier)
+                    + u64::from(event_data.value.serialized_size())
+            }
+            StacksTransactionEvent::NFTEvent(NFTEventType::NFTMintEvent(event_data)) => {
+                principal_size(&event_data.recipient)
+                    + asset_identifier_size(&event_data.asset_identifier)
+                    + u64::from(event_data.value.serialized_size())
+            }
+            StacksTransactionEvent::NFTEvent(NFTEventType::NFTBurnEvent(event_data)) => {
+                principal_size(&event_data.sender)
+                    + asset_identifier_size(&event_data.asset_identifier)
+                    + u64::from(event_data.value.serialized_size())
+            }
+            StacksTransactionEvent::NFTEvent(NFTEventType::NFTWithdrawEvent(event_data)) => {
+                principal_size(&event_data.sender)
+                    + asset_identifier_size(&event_data.asset_identifier)
+                    + UINT_SIZE
+            }
+            StacksTransactionEvent::FTEvent(FTEventType::FTTransferEvent(event_data)) => {
+                principal_size(&event_data.sender)
+                    + principal_size(&event_data.recipient)
+                    + asset_identifier_size(&event_data.asset_identifier)
+                    + UINT_SIZE
+            }
+            StacksTransactionEvent::FTEvent(FTEventType::FTMintEvent(event_data)) => {
+                principal_size(&event_data.recipient)
+                    + asset_identifier_size(&event_data.asset_identifier)
+                    + UINT_SIZE
+            }
+            StacksTransactionEvent::FTEvent(FTEventType::FTBurnEvent(event_data)) => {
+                principal_size(&event_data.sender)
+                    + asset_identifier_size(&event_data.asset_identifier)
+                    + UINT_SIZE
+            }
+            StacksTransactionEvent::FTEvent(FTEventType::FTWithdrawEvent(event_data)) => {
+                principal_size(&event_data.sender)
+                    + asset_identifier_size(&event_data.asset_identifier)
+                    + UINT_SIZE
+            }
+        }
+    }
+}
+
+/// Serialized size of a Clarity `uint`
+const UINT_SIZE: u64 = 17;
+
+fn principal_size(principal: &PrincipalData) -> u64 {
+    u64::from(Value::Principal(principal.clone()).serialized_size())
+}
+
+fn contract_size(contract_identifier: &QualifiedContractIdentifier) -> u64 {
+    principal_size(&PrincipalData::Contract(contract_identifier.clone()))
+}
+
+fn asset_identifier_size(asset_identifier: &AssetIdentifier) -> u64 {
+    contract_size(&asset_identifier.contract_identifier) + asset_identifier.asset_name.len() as u64
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -148,6 +247,7 @@ pub enum STXEventType {
     STXBurnEvent(STXBurnEventData),
     STXLockEvent(STXLockEventData),
     STXWithdrawEvent(STXWithdrawEventData),
+    STXWithdrawTipEvent(STXWithdrawTipEventData),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -249,6 +349,31 @@ impl STXWithdrawEventData {
     }
 }
 
+/// The miner tip attached to the withdrawal in the preceding event.  The tip gets its own leaf in
+/// the withdrawal tree, which pays it out on the L1 to the miner of the block that includes the
+/// withdrawal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct STXWithdrawTipEventData {
+    pub sender: PrincipalData,
+    pub amount: u128,
+    /// The miner who can claim the tip, set along with `withdrawal_id`
+    pub recipient: Option<PrincipalData>,
+    pub withdrawal_id: Option<u32>,
+}
+
+impl STXWithdrawTipEventData {
+    /// Serialize to a JSON value. This method fails to serialize if
+    /// `withdrawal_id` or `recipient` is not set, returning `None`
+    pub fn json_serialize(&self) -> Option<serde_json::Value> {
+        Some(json!({
+            "sender": self.sender.to_string(),
+            "amount": self.amount.to_string(),
+            "recipient": self.recipient.as_ref()?.to_string(),
+            "withdrawal_id": self.withdrawal_id?,
+        }))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct NFTTransferEventData {
     pub asset_identifier: AssetIdentifier,
@@ -440,3 +565,80 @@ impl SmartContractEventData {
         })
     }
 }
+
+fn clarity_ascii_str(input: &str) -> Value {
+    Value::string_ascii_from_bytes(input.as_bytes().to_vec())
+        .expect("Supplied string was not ASCII")
+}
+
+/// Make the key of an STX withdrawal in a block's withdrawal Merkle tree.
+/// The tree's leaves are the consensus serializations of these keys.
+pub fn make_key_for_stx_withdrawal(
+    recipient: &PrincipalData,
+    withdrawal_id: u32,
+    amount: u128,
+    block_height: u64,
+) -> Value {
+    TupleData::from_data(vec![
+        ("type".into(), clarity_ascii_str("stx")),
+        ("height".into(), Value::UInt(u128::from(block_height))),
+        (
+            "withdrawal-id".into(),
+            Value::UInt(u128::from(withdrawal_id)),
+        ),
+        ("recipient".into(), Value::Principal(recipient.clone())),
+        ("amount".into(), Value::UInt(amount)),
+    ])
+    .expect("Withdrawal key tuple is too large for Clarity")
+    .into()
+}
+
+/// Make the key of an NFT withdrawal in a block's withdrawal Merkle tree.
+/// Only the contract that defines the asset is part of the key, not the asset name.
+pub fn make_key_for_nft_withdrawal(
+    sender: &PrincipalData,
+    withdrawal_id: u32,
+    asset_contract: &QualifiedContractIdentifier,
+    id: u128,
+    block_height: u64,
+) -> Value {
+    let asset_contract = Value::Principal(PrincipalData::from(asset_contract.clone()));
+    TupleData::from_data(vec![
+        ("type".into(), clarity_ascii_str("nft")),
+        ("asset-contract".into(), asset_contract),
+        ("height".into(), Value::UInt(u128::from(block_height))),
+        (
+            "withdrawal-id".into(),
+            Value::UInt(u128::from(withdrawal_id)),
+        ),
+        ("recipient".into(), Value::Principal(sender.clone())),
+        ("nft-id".into(), Value::UInt(id)),
+    ])
+    .expect("Withdrawal key tuple is too large for Clarity")
+    .into()
+}
+
+/// Make the key of a fungible token withdrawal in a block's withdrawal Merkle tree.
+/// Only the contract that defines the token is part of the key, not the token name.
+pub fn make_key_for_ft_withdrawal(
+    sender: &PrincipalData,
+    withdrawal_id: u32,
+    asset_contract: &QualifiedContractIdentifier,
+    amount: u128,
+    block_height: u64,
+) -> Value {
+    let asset_contract = Value::Principal(PrincipalData::from(asset_contract.clone()));
+    TupleData::from_data(vec![
+        ("type".into(), clarity_ascii_str("ft")),
+        ("asset-contract".into(), asset_contract),
+        ("height".into(), Value::UInt(u128::from(block_height))),
+        (
+            "withdrawal-id".into(),
+            Value::UInt(u128::from(withdrawal_id)),
+        ),
+        ("recipient".into(), Value::Principal(sender.clone())),
+        ("amount".into(), Value::UInt(amount)),
+    ])
+    .expect("Withdrawal key tuple is too large for Clarity")
+    .into()
+}
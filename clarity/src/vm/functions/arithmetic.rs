@@ -15,7 +15,7 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::vm::errors::{check_argument_count, CheckErrors, InterpreterResult, RuntimeErrorType};
-use crate::vm::types::{TypeSignature, Value};
+use crate::vm::types::{BuffData, SequenceData, TypeSignature, Value, BUFF_16};
 use std::convert::TryFrom;
 
 use integer_sqrt::IntegerSquareRoot;
@@ -66,6 +66,25 @@ macro_rules! type_force_unary_arithmetic {
     }};
 }
 
+// This macro checks that the shift amount is a uint, and then dispatches the evaluation
+//   on the type of the shifted value, like `type_force_unary_arithmetic`.
+macro_rules! type_force_shift_arithmetic {
+    ($function: ident, $x: expr, $shamt: expr) => {{
+        match ($x, $shamt) {
+            (Value::Int(x), Value::UInt(shamt)) => I128Ops::$function(x, shamt),
+            (Value::UInt(x), Value::UInt(shamt)) => U128Ops::$function(x, shamt),
+            (Value::Int(_), shamt) | (Value::UInt(_), shamt) => {
+                Err(CheckErrors::TypeValueError(TypeSignature::UIntType, shamt).into())
+            }
+            (x, _) => Err(CheckErrors::UnionTypeValueError(
+                vec![TypeSignature::IntType, TypeSignature::UIntType],
+                x,
+            )
+            .into()),
+        }
+    }};
+}
+
 // This macro checks the type of the first argument and then dispatches the evaluation
 //   to the correct arithmetic type handler (after deconstructing the Clarity Values into
 //   the corresponding Rust integer type.
@@ -122,6 +141,24 @@ macro_rules! make_arithmetic_ops {
             fn xor(x: $type, y: $type) -> InterpreterResult<Value> {
                 Self::make_value(x ^ y)
             }
+            fn bitwise_and(args: &[$type]) -> InterpreterResult<Value> {
+                let result = args.iter().fold(!0, |acc, x| acc & x);
+                Self::make_value(result)
+            }
+            fn bitwise_or(args: &[$type]) -> InterpreterResult<Value> {
+                let result = args.iter().fold(0, |acc, x| acc | x);
+                Self::make_value(result)
+            }
+            fn bitwise_not(x: $type) -> InterpreterResult<Value> {
+                Self::make_value(!x)
+            }
+            // shift amounts wrap around the width of the type, so a shift is never an error
+            fn bitwise_left_shift(x: $type, shamt: u128) -> InterpreterResult<Value> {
+                Self::make_value(x.wrapping_shl((shamt % 128) as u32))
+            }
+            fn bitwise_right_shift(x: $type, shamt: u128) -> InterpreterResult<Value> {
+                Self::make_value(x.wrapping_shr((shamt % 128) as u32))
+            }
             fn leq(x: $type, y: $type) -> InterpreterResult<Value> {
                 Ok(Value::Bool(x <= y))
             }
@@ -246,6 +283,21 @@ make_arithmetic_ops!(I128Ops, i128);
 pub fn native_xor(a: Value, b: Value) -> InterpreterResult<Value> {
     type_force_binary_arithmetic!(xor, a, b)
 }
+pub fn native_bitwise_and(mut args: Vec<Value>) -> InterpreterResult<Value> {
+    type_force_variadic_arithmetic!(bitwise_and, args)
+}
+pub fn native_bitwise_or(mut args: Vec<Value>) -> InterpreterResult<Value> {
+    type_force_variadic_arithmetic!(bitwise_or, args)
+}
+pub fn native_bitwise_not(a: Value) -> InterpreterResult<Value> {
+    type_force_unary_arithmetic!(bitwise_not, a)
+}
+pub fn native_bitwise_left_shift(input: Value, shamt: Value) -> InterpreterResult<Value> {
+    type_force_shift_arithmetic!(bitwise_left_shift, input, shamt)
+}
+pub fn native_bitwise_right_shift(input: Value, shamt: Value) -> InterpreterResult<Value> {
+    type_force_shift_arithmetic!(bitwise_right_shift, input, shamt)
+}
 pub fn native_geq(a: Value, b: Value) -> InterpreterResult<Value> {
     type_force_binary_arithmetic!(geq, a, b)
 }
@@ -301,3 +353,29 @@ pub fn native_to_int(input: Value) -> InterpreterResult<Value> {
         Err(CheckErrors::TypeValueError(TypeSignature::UIntType, input).into())
     }
 }
+
+/// Reads a buffer of at most 16 bytes as an unsigned integer, most significant byte first if
+///  `big_endian` is set. The empty buffer reads as `u0`.
+fn buff_to_uint(input: Value, big_endian: bool) -> InterpreterResult<Value> {
+    match input {
+        Value::Sequence(SequenceData::Buffer(BuffData { ref data })) if data.len() <= 16 => {
+            let mut bytes = [0u8; 16];
+            if big_endian {
+                bytes[16 - data.len()..].copy_from_slice(data);
+                Ok(Value::UInt(u128::from_be_bytes(bytes)))
+            } else {
+                bytes[..data.len()].copy_from_slice(data);
+                Ok(Value::UInt(u128::from_le_bytes(bytes)))
+            }
+        }
+        _ => Err(CheckErrors::TypeValueError(BUFF_16.clone(), input).into()),
+    }
+}
+
+pub fn native_buff_to_uint_be(input: Value) -> InterpreterResult<Value> {
+    buff_to_uint(input, true)
+}
+
+pub fn native_buff_to_uint_le(input: Value) -> InterpreterResult<Value> {
+    buff_to_uint(input, false)
+}
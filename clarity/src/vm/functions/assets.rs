@@ -20,14 +20,15 @@ use crate::vm::costs::cost_functions::ClarityCostFunction;
 use crate::vm::costs::{cost_functions, runtime_cost, CostTracker};
 use crate::vm::database::{ClarityDatabase, ClaritySerializable, STXBalance};
 use crate::vm::errors::{
-    check_argument_count, CheckErrors, Error, InterpreterError, InterpreterResult as Result,
-    RuntimeErrorType,
+    check_argument_count, check_arguments_at_least, check_arguments_at_most, CheckErrors, Error,
+    InterpreterError, InterpreterResult as Result, RuntimeErrorType,
 };
 use crate::vm::representations::SymbolicExpression;
 use crate::vm::types::{
-    AssetIdentifier, BlockInfoProperty, BuffData, OptionalData, PrincipalData, TypeSignature, Value,
+    AssetIdentifier, BlockInfoProperty, BuffData, OptionalData, PrincipalData, TupleData,
+    TypeSignature, Value,
 };
-use crate::vm::{eval, Environment, LocalContext};
+use crate::vm::{eval, ClarityVersion, Environment, LocalContext};
 use std::convert::TryFrom;
 
 use crate::types::StacksEpochId;
@@ -62,10 +63,14 @@ enum WithdrawAssetErrorCodes {
     NOT_OWNED_BY = 1,
     DOES_NOT_EXIST = 3,
     ASSET_WITHDRAWAL_PROHIBITED = 4,
+    TIP_NOT_ENOUGH_BALANCE = 5,
+    TIP_SENDER_IS_NOT_TX_SENDER = 6,
 }
 enum WithdrawTokenErrorCodes {
     NOT_ENOUGH_BALANCE = 1,
     NON_POSITIVE_AMOUNT = 3,
+    TIP_NOT_ENOUGH_BALANCE = 5,
+    TIP_SENDER_IS_NOT_TX_SENDER = 6,
 }
 
 enum StxErrorCodes {
@@ -126,6 +131,49 @@ pub fn special_stx_balance(
     }
 }
 
+pub fn special_stx_account(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    check_argument_count(1, args)?;
+
+    runtime_cost(ClarityCostFunction::StxGetAccount, env, 0)?;
+
+    let owner = eval(&args[0], env, context)?;
+
+    if let Value::Principal(ref principal) = owner {
+        let (unlocked, balance) = {
+            let snapshot = env
+                .global_context
+                .database
+                .get_stx_balance_snapshot(principal);
+            (
+                snapshot.get_available_balance(),
+                snapshot.balance().get_total_balance(),
+            )
+        };
+        // withdrawn uSTX are held in escrow until the withdrawal is claimed on the L1, and still
+        // count towards the principal's total balance until then
+        let pending_withdrawal = env
+            .global_context
+            .database
+            .get_pending_stx_withdrawal(principal);
+        let total = balance
+            .checked_add(pending_withdrawal)
+            .ok_or(RuntimeErrorType::ArithmeticOverflow)?;
+
+        let account = TupleData::from_data(vec![
+            ("unlocked".into(), Value::UInt(unlocked)),
+            ("total".into(), Value::UInt(total)),
+            ("pending-withdrawal".into(), Value::UInt(pending_withdrawal)),
+        ])?;
+        Ok(Value::from(account))
+    } else {
+        Err(CheckErrors::TypeValueError(TypeSignature::PrincipalType, owner).into())
+    }
+}
+
 /// Do a "consolidated" STX transfer.
 /// If the 'from' principal has locked STX, and they have unlocked, then process the STX unlock
 /// and update its balance in addition to spending tokens out of it.
@@ -235,17 +283,97 @@ pub fn special_stx_burn(
     }
 }
 
+/// Check the argument count of a withdrawal native, which takes `required` arguments followed by
+/// an optional miner tip.
+fn check_withdrawal_argument_count(
+    required: usize,
+    args: &[SymbolicExpression],
+    env: &Environment,
+) -> Result<()> {
+    // Clarity 1 withdrawals take no tip
+    if env.contract_context.get_clarity_version() < ClarityVersion::Clarity2 {
+        check_argument_count(required, args)?;
+        return Ok(());
+    }
+    check_arguments_at_least(required, args)?;
+    check_arguments_at_most(required + 1, args)?;
+    Ok(())
+}
+
+/// Evaluate the optional miner tip of a withdrawal native, in uSTX, which follows its `required`
+/// arguments.  An omitted tip is 0.
+fn eval_withdrawal_tip(
+    required: usize,
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<u128> {
+    match args.get(required) {
+        Some(tip_arg) => match eval(tip_arg, env, context)? {
+            Value::UInt(tip) => Ok(tip),
+            tip => Err(CheckErrors::TypeValueError(TypeSignature::UIntType, tip).into()),
+        },
+        None => Ok(0),
+    }
+}
+
+/// Can `payer` tip `tip` uSTX to the miner of a withdrawal of fungible or non-fungible tokens?
+/// Returns the error code to fail the withdrawal with if not.
+fn check_withdrawal_tip(
+    env: &mut Environment,
+    payer: &PrincipalData,
+    tip: u128,
+    not_tx_sender_code: u128,
+    not_enough_balance_code: u128,
+) -> Option<u128> {
+    if tip == 0 {
+        return None;
+    }
+    if Some(payer) != env.sender.as_ref() {
+        return Some(not_tx_sender_code);
+    }
+    let payer_snapshot = env.global_context.database.get_stx_balance_snapshot(payer);
+    if !payer_snapshot.can_transfer(tip) {
+        return Some(not_enough_balance_code);
+    }
+    None
+}
+
+/// Debit a withdrawal's miner tip from `payer`.  The tip leaves the subnet along with the
+/// withdrawal: the tip event gets its own leaf in the withdrawal tree, paying it out on the L1 to
+/// the miner of the block that includes the withdrawal.
+fn pay_withdrawal_tip(env: &mut Environment, payer: &PrincipalData, tip: u128) -> Result<()> {
+    if tip == 0 {
+        return Ok(());
+    }
+
+    env.add_memory(STXBalance::size_of as u64)?;
+    env.add_memory(TypeSignature::UIntType.size() as u64)?;
+
+    let mut payer_snapshot = env.global_context.database.get_stx_balance_snapshot(payer);
+    payer_snapshot.debit(tip);
+    payer_snapshot.save();
+
+    env.global_context
+        .database
+        .decrement_ustx_liquid_supply(tip)?;
+
+    env.global_context.log_stx_burn(payer, tip)?;
+    env.register_stx_withdraw_tip_event(payer.clone(), tip)
+}
+
 pub fn special_stx_withdraw(
     args: &[SymbolicExpression],
     env: &mut Environment,
     context: &LocalContext,
 ) -> Result<Value> {
-    check_argument_count(2, args)?;
+    check_withdrawal_argument_count(2, args, env)?;
 
     runtime_cost(ClarityCostFunction::StxWithdraw, env, 0)?;
 
     let amount_val = eval(&args[0], env, context)?;
     let from_val = eval(&args[1], env, context)?;
+    let tip = eval_withdrawal_tip(2, args, env, context)?;
 
     if let (Value::Principal(ref from), Value::UInt(amount)) = (&from_val, amount_val) {
         if amount == 0 {
@@ -260,13 +388,22 @@ pub fn special_stx_withdraw(
         env.add_memory(STXBalance::size_of as u64)?;
 
         let mut withdrawer_snapshot = env.global_context.database.get_stx_balance_snapshot(&from);
-        if !withdrawer_snapshot.can_transfer(amount) {
+        let can_pay = amount
+            .checked_add(tip)
+            .map(|total| withdrawer_snapshot.can_transfer(total))
+            .unwrap_or(false);
+        if !can_pay {
             return clarity_ecode!(StxErrorCodes::NOT_ENOUGH_BALANCE);
         }
 
         withdrawer_snapshot.debit(amount);
         withdrawer_snapshot.save();
 
+        env.add_memory(TypeSignature::UIntType.size() as u64)?;
+        env.global_context
+            .database
+            .add_pending_stx_withdrawal(&from, amount);
+
         env.global_context
             .database
             .decrement_ustx_liquid_supply(amount)?;
@@ -274,6 +411,8 @@ pub fn special_stx_withdraw(
         env.global_context.log_stx_burn(&from, amount)?;
         env.register_stx_withdraw_event(from.clone(), amount)?;
 
+        pay_withdrawal_tip(env, from, tip)?;
+
         Ok(Value::okay_true())
     } else {
         Err(CheckErrors::BadTransferSTXArguments.into())
@@ -1106,7 +1245,7 @@ pub fn special_withdraw_token(
     env: &mut Environment,
     context: &LocalContext,
 ) -> Result<Value> {
-    check_argument_count(3, args)?;
+    check_withdrawal_argument_count(3, args, env)?;
 
     runtime_cost(ClarityCostFunction::FtWithdraw, env, 0)?;
 
@@ -1114,12 +1253,23 @@ pub fn special_withdraw_token(
 
     let amount = eval(&args[1], env, context)?;
     let from = eval(&args[2], env, context)?;
+    let tip = eval_withdrawal_tip(3, args, env, context)?;
 
     if let (Value::UInt(amount), Value::Principal(ref withdrawer)) = (amount, from) {
         if amount == 0 {
             return clarity_ecode!(WithdrawTokenErrorCodes::NON_POSITIVE_AMOUNT);
         }
 
+        if let Some(code) = check_withdrawal_tip(
+            env,
+            withdrawer,
+            tip,
+            WithdrawTokenErrorCodes::TIP_SENDER_IS_NOT_TX_SENDER as u128,
+            WithdrawTokenErrorCodes::TIP_NOT_ENOUGH_BALANCE as u128,
+        ) {
+            return clarity_ecode!(code);
+        }
+
         let withdrawer_bal = env.global_context.database.get_ft_balance(
             &env.contract_context.contract_identifier,
             token_name,
@@ -1162,6 +1312,8 @@ pub fn special_withdraw_token(
             amount,
         )?;
 
+        pay_withdrawal_tip(env, withdrawer, tip)?;
+
         Ok(Value::okay_true())
     } else {
         Err(CheckErrors::BadWithdrawFTArguments.into())
@@ -1173,7 +1325,7 @@ pub fn special_withdraw_asset(
     env: &mut Environment,
     context: &LocalContext,
 ) -> Result<Value> {
-    check_argument_count(3, args)?;
+    check_withdrawal_argument_count(3, args, env)?;
 
     runtime_cost(ClarityCostFunction::NftWithdraw, env, 0)?;
 
@@ -1181,6 +1333,7 @@ pub fn special_withdraw_asset(
 
     let asset = eval(&args[1], env, context)?;
     let sender = eval(&args[2], env, context)?;
+    let tip = eval_withdrawal_tip(3, args, env, context)?;
 
     let nft_metadata = env
         .contract_context
@@ -1220,6 +1373,16 @@ pub fn special_withdraw_asset(
             return clarity_ecode!(WithdrawAssetErrorCodes::ASSET_WITHDRAWAL_PROHIBITED);
         };
 
+        if let Some(code) = check_withdrawal_tip(
+            env,
+            sender_principal,
+            tip,
+            WithdrawAssetErrorCodes::TIP_SENDER_IS_NOT_TX_SENDER as u128,
+            WithdrawAssetErrorCodes::TIP_NOT_ENOUGH_BALANCE as u128,
+        ) {
+            return clarity_ecode!(code);
+        }
+
         env.add_memory(TypeSignature::PrincipalType.size() as u64)?;
         env.add_memory(asset_size)?;
 
@@ -1243,6 +1406,8 @@ pub fn special_withdraw_asset(
         };
         env.register_nft_withdraw_event(sender_principal.clone(), id, asset_identifier)?;
 
+        pay_withdrawal_tip(env, sender_principal, tip)?;
+
         Ok(Value::okay_true())
     } else {
         Err(CheckErrors::TypeValueError(TypeSignature::PrincipalType, sender).into())
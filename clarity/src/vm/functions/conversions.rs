@@ -0,0 +1,81 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::vm::costs::cost_functions::ClarityCostFunction;
+use crate::vm::costs::runtime_cost;
+use crate::vm::errors::{check_argument_count, CheckErrors, InterpreterResult as Result};
+use crate::vm::representations::SymbolicExpression;
+use crate::vm::types::{SequenceData, TypeSignature, Value};
+use crate::vm::{eval, Environment, LocalContext};
+
+/// Serialize a Clarity value to its consensus wire format, returning `(some buff)`, or `none` if
+///  the serialization would not fit in a Clarity buffer.
+pub fn special_to_consensus_buff(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    // (to-consensus-buff? value)
+    check_argument_count(1, args)?;
+
+    let value = eval(&args[0], env, context)?;
+
+    let mut clar_buff_serialized = vec![];
+    value
+        .serialize_write(&mut clar_buff_serialized)
+        .expect("FATAL: failed to serialize Clarity value to a byte buffer");
+
+    runtime_cost(
+        ClarityCostFunction::ToConsensusBuff,
+        env,
+        clar_buff_serialized.len(),
+    )?;
+
+    match Value::buff_from(clar_buff_serialized) {
+        Ok(buff) => Value::some(buff),
+        Err(_) => Ok(Value::none()),
+    }
+}
+
+/// Deserialize a buffer holding a value in its consensus wire format, returning `(some value)`
+///  if the buffer holds exactly one value of the given type, and `none` otherwise.
+pub fn special_from_consensus_buff(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    // (from-consensus-buff? type buffer)
+    check_argument_count(2, args)?;
+
+    let type_arg = TypeSignature::parse_type_repr(&args[0], env)?;
+    let value = eval(&args[1], env, context)?;
+
+    let input_bytes = match value {
+        Value::Sequence(SequenceData::Buffer(buff_data)) => buff_data.data,
+        _ => return Err(CheckErrors::TypeValueError(TypeSignature::max_buffer(), value).into()),
+    };
+
+    runtime_cost(
+        ClarityCostFunction::FromConsensusBuff,
+        env,
+        input_bytes.len(),
+    )?;
+
+    match Value::try_deserialize_bytes_exact(&input_bytes, &type_arg) {
+        Ok(value) => Value::some(value),
+        Err(_) => Ok(Value::none()),
+    }
+}
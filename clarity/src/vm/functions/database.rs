@@ -19,22 +19,28 @@ use std::convert::{TryFrom, TryInto};
 
 use crate::vm::functions::tuples;
 
+use crate::codec::StacksMessageCodec;
 use crate::vm::callables::DefineType;
 use crate::vm::costs::{
-    constants as cost_constants, cost_functions, runtime_cost, CostTracker, MemoryConsumer,
+    constants as cost_constants, cost_functions, runtime_cost, CostTracker, ExecutionCost,
+    MemoryConsumer,
 };
 use crate::vm::errors::{
-    check_argument_count, check_arguments_at_least, CheckErrors, InterpreterError,
+    check_argument_count, check_arguments_at_least, CheckErrors, Error, InterpreterError,
     InterpreterResult as Result, RuntimeErrorType,
 };
+use crate::vm::events::{
+    make_key_for_ft_withdrawal, make_key_for_nft_withdrawal, make_key_for_stx_withdrawal,
+};
 use crate::vm::representations::{SymbolicExpression, SymbolicExpressionType};
 use crate::vm::types::{
-    BlockInfoProperty, BuffData, OptionalData, PrincipalData, SequenceData, TypeSignature, Value,
-    BUFF_32,
+    BlockInfoProperty, BuffData, L1BlockInfoProperty, OptionalData, PrincipalData, SequenceData,
+    TupleData, TypeSignature, Value, WithdrawalAssetType, BUFF_32,
 };
-use crate::vm::{eval, Environment, LocalContext};
+use crate::vm::{eval, ClarityVersion, Environment, LocalContext};
 use stacks_common::types::chainstate::StacksBlockId;
 use stacks_common::types::StacksEpochId;
+use stacks_common::util::hash::{MerkleTree, Sha512Trunc256Sum};
 
 use crate::vm::costs::cost_functions::ClarityCostFunction;
 
@@ -75,14 +81,78 @@ pub fn special_contract_call(
     //   is checked in callables::DefinedFunction::execute_apply.
     runtime_cost(ClarityCostFunction::ContractCall, env, 0)?;
 
-    let function_name = args[1].match_atom().ok_or(CheckErrors::ExpectedName)?;
+    let (rest_args, rest_args_sizes) = eval_contract_call_args(&args[2..], env, context)?;
+    execute_contract_call(args, &rest_args, &rest_args_sizes, env, context)
+}
+
+/// `try-contract-call?` behaves like `contract-call?`, except that the callee cannot abort the
+///  caller's transaction with a runtime error (e.g., an arithmetic overflow or a failed
+///  `unwrap-panic`). The callee must return a response:
+///   * `(ok a)` is returned as-is, and the callee's writes are kept.
+///   * `(err e)` is returned as `(err (some e))`, and the callee's writes are rolled back.
+///   * a runtime error is returned as `(err none)`, and the callee's writes, events and asset
+///     transfers are rolled back, exactly as they would be for `(err e)`.
+///  Errors that are not the callee's doing -- evaluating the arguments in the caller, check
+///  errors, running out of budget, or exceeding the stack depth -- still abort the transaction.
+pub fn special_try_contract_call(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    check_arguments_at_least(2, args)?;
+
+    runtime_cost(ClarityCostFunction::ContractCall, env, 0)?;
+
+    let (rest_args, rest_args_sizes) = eval_contract_call_args(&args[2..], env, context)?;
+    match execute_contract_call(args, &rest_args, &rest_args_sizes, env, context) {
+        Ok(Value::Response(response)) => {
+            if response.committed {
+                Ok(Value::Response(response))
+            } else {
+                Value::error(Value::some(*response.data)?)
+            }
+        }
+        Ok(value) => Err(CheckErrors::ExpectedResponseValue(value).into()),
+        Err(Error::Runtime(RuntimeErrorType::MaxStackDepthReached, stack_trace)) => Err(
+            Error::Runtime(RuntimeErrorType::MaxStackDepthReached, stack_trace),
+        ),
+        Err(Error::Runtime(RuntimeErrorType::MaxContextDepthReached, stack_trace)) => Err(
+            Error::Runtime(RuntimeErrorType::MaxContextDepthReached, stack_trace),
+        ),
+        Err(Error::Runtime(_, _)) => {
+            // the callee's transaction was already rolled back by
+            //  `Environment::execute_function_as_transaction()`
+            Value::error(Value::none())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Evaluate the arguments that a contract call passes to its callee, in the caller's context.
+fn eval_contract_call_args(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<(Vec<SymbolicExpression>, Vec<u64>)> {
     let mut rest_args = vec![];
     let mut rest_args_sizes = vec![];
-    for arg in args[2..].iter() {
+    for arg in args.iter() {
         let evaluated_arg = eval(arg, env, context)?;
         rest_args_sizes.push(evaluated_arg.size() as u64);
         rest_args.push(SymbolicExpression::atom_value(evaluated_arg));
     }
+    Ok((rest_args, rest_args_sizes))
+}
+
+/// Dispatch a contract call whose arguments were already evaluated.
+fn execute_contract_call(
+    args: &[SymbolicExpression],
+    rest_args: &[SymbolicExpression],
+    rest_args_sizes: &[u64],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    let function_name = args[1].match_atom().ok_or(CheckErrors::ExpectedName)?;
 
     let (contract_identifier, type_returns_constraint) = match &args[0].expr {
         SymbolicExpressionType::LiteralValue(Value::Principal(PrincipalData::Contract(
@@ -183,13 +253,13 @@ pub fn special_contract_call(
     let result = if nested_env.short_circuit_contract_call(
         &contract_identifier,
         function_name,
-        &rest_args_sizes,
+        rest_args_sizes,
     )? {
         nested_env.run_free(|free_env| {
-            free_env.execute_contract(&contract_identifier, function_name, &rest_args, false)
+            free_env.execute_contract(&contract_identifier, function_name, rest_args, false)
         })
     } else {
-        nested_env.execute_contract(&contract_identifier, function_name, &rest_args, false)
+        nested_env.execute_contract(&contract_identifier, function_name, rest_args, false)
     }?;
 
     // Ensure that the expected type from the trait spec admits
@@ -347,6 +417,29 @@ pub fn special_set_variable_v205(
     result.map(|data| data.value)
 }
 
+pub fn special_var_exists(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    _context: &LocalContext,
+) -> Result<Value> {
+    // (var-exists? var-name)
+    check_argument_count(1, args)?;
+
+    let var_name = args[0].match_atom().ok_or(CheckErrors::ExpectedName)?;
+
+    runtime_cost(ClarityCostFunction::VarExists, env, 0)?;
+
+    // check the database rather than the contract context, so that the result reflects the
+    //  block being read (e.g., inside `at-block`)
+    let contract = &env.contract_context.contract_identifier;
+    let exists = env
+        .global_context
+        .database
+        .variable_exists(contract, var_name);
+
+    Ok(Value::Bool(exists))
+}
+
 pub fn special_fetch_entry_v200(
     args: &[SymbolicExpression],
     env: &mut Environment,
@@ -440,6 +533,26 @@ pub fn special_at_block(
     result
 }
 
+/// The cost of reading and writing a map's entry count, which is at most this many bytes long
+const MAP_SIZE_UPDATE_COST: ExecutionCost = ExecutionCost {
+    write_length: 39,
+    write_count: 1,
+    read_length: 39,
+    read_count: 1,
+    runtime: 0,
+};
+
+/// Maps defined by Clarity 2 contracts track their number of entries for `map-size`, so every
+/// write that may add or remove an entry also reads and writes the entry count.  Maps defined by
+/// Clarity 1 contracts do not, and are not charged for it.
+fn charge_map_size_update(env: &mut Environment) -> Result<()> {
+    if env.contract_context.get_clarity_version() < ClarityVersion::Clarity2 {
+        return Ok(());
+    }
+    env.add_cost(MAP_SIZE_UPDATE_COST)?;
+    Ok(())
+}
+
 pub fn special_set_entry_v200(
     args: &[SymbolicExpression],
     env: &mut Environment,
@@ -457,6 +570,8 @@ pub fn special_set_entry_v200(
 
     let map_name = args[0].match_atom().ok_or(CheckErrors::ExpectedName)?;
 
+    charge_map_size_update(env)?;
+
     let contract = &env.contract_context.contract_identifier;
 
     let data_types = env
@@ -499,6 +614,8 @@ pub fn special_set_entry_v205(
 
     let map_name = args[0].match_atom().ok_or(CheckErrors::ExpectedName)?;
 
+    charge_map_size_update(env)?;
+
     let contract = &env.contract_context.contract_identifier;
 
     let data_types = env
@@ -541,6 +658,8 @@ pub fn special_insert_entry_v200(
 
     let map_name = args[0].match_atom().ok_or(CheckErrors::ExpectedName)?;
 
+    charge_map_size_update(env)?;
+
     let contract = &env.contract_context.contract_identifier;
 
     let data_types = env
@@ -583,6 +702,8 @@ pub fn special_insert_entry_v205(
 
     let map_name = args[0].match_atom().ok_or(CheckErrors::ExpectedName)?;
 
+    charge_map_size_update(env)?;
+
     let contract = &env.contract_context.contract_identifier;
 
     let data_types = env
@@ -623,6 +744,8 @@ pub fn special_delete_entry_v200(
 
     let map_name = args[0].match_atom().ok_or(CheckErrors::ExpectedName)?;
 
+    charge_map_size_update(env)?;
+
     let contract = &env.contract_context.contract_identifier;
 
     let data_types = env
@@ -662,6 +785,8 @@ pub fn special_delete_entry_v205(
 
     let map_name = args[0].match_atom().ok_or(CheckErrors::ExpectedName)?;
 
+    charge_map_size_update(env)?;
+
     let contract = &env.contract_context.contract_identifier;
 
     let data_types = env
@@ -687,6 +812,33 @@ pub fn special_delete_entry_v205(
     result.map(|data| data.value)
 }
 
+pub fn special_map_size(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    _context: &LocalContext,
+) -> Result<Value> {
+    // (map-size map-name)
+    check_argument_count(1, args)?;
+
+    let map_name = args[0].match_atom().ok_or(CheckErrors::ExpectedName)?;
+
+    let contract = &env.contract_context.contract_identifier;
+
+    env.contract_context
+        .meta_data_map
+        .get(map_name)
+        .ok_or(CheckErrors::NoSuchMap(map_name.to_string()))?;
+
+    runtime_cost(ClarityCostFunction::MapSize, env, 0)?;
+
+    let size = env
+        .global_context
+        .database
+        .get_map_size(contract, map_name)?;
+
+    Ok(Value::UInt(size))
+}
+
 pub fn special_get_block_info(
     args: &[SymbolicExpression],
     env: &mut Environment,
@@ -768,3 +920,184 @@ pub fn special_get_block_info(
 
     Ok(Value::some(result)?)
 }
+
+pub fn special_get_l1_block_info(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    // (get-l1-block-info? property-name l1-block-height-int)
+    runtime_cost(ClarityCostFunction::GetL1BlockInfo, env, 0)?;
+
+    check_argument_count(2, args)?;
+
+    // Handle the block property name input arg.
+    let property_name = args[0]
+        .match_atom()
+        .ok_or(CheckErrors::GetL1BlockInfoExpectPropertyName)?;
+
+    let block_info_prop = L1BlockInfoProperty::lookup_by_name(property_name)
+        .ok_or(CheckErrors::GetL1BlockInfoExpectPropertyName)?;
+
+    // Handle the block-height input arg clause.
+    let height_eval = eval(&args[1], env, context)?;
+    let height_value = match height_eval {
+        Value::UInt(result) => Ok(result),
+        x => Err(CheckErrors::TypeValueError(TypeSignature::UIntType, x)),
+    }?;
+
+    let height_value = match u32::try_from(height_value) {
+        Ok(result) => result,
+        _ => return Ok(Value::none()),
+    };
+
+    // The L1 block must be known to the L1 fork that the current block builds on.
+    let result = match block_info_prop {
+        L1BlockInfoProperty::HeaderHash => env
+            .global_context
+            .database
+            .get_l1_block_header_hash(height_value)
+            .map(|header_hash| {
+                Value::Sequence(SequenceData::Buffer(BuffData {
+                    data: header_hash.as_bytes().to_vec(),
+                }))
+            }),
+        L1BlockInfoProperty::Time => env
+            .global_context
+            .database
+            .get_l1_block_time(height_value)
+            .map(|block_time| Value::UInt(block_time as u128)),
+    };
+
+    match result {
+        Some(result) => Ok(Value::some(result)?),
+        None => Ok(Value::none()),
+    }
+}
+
+pub fn special_get_withdrawal_info(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    // (get-withdrawal-info? stx block-height withdrawal-id sender amount)
+    // (get-withdrawal-info? ft block-height withdrawal-id sender asset-contract amount)
+    // (get-withdrawal-info? nft block-height withdrawal-id sender asset-contract nft-id)
+    check_arguments_at_least(1, args)?;
+
+    // Handle the asset type input arg.
+    let asset_type_name = args[0]
+        .match_atom()
+        .ok_or(CheckErrors::GetWithdrawalInfoExpectAssetType)?;
+
+    let asset_type = WithdrawalAssetType::lookup_by_name(asset_type_name)
+        .ok_or(CheckErrors::GetWithdrawalInfoExpectAssetType)?;
+
+    check_argument_count(asset_type.argument_types().len() + 1, args)?;
+
+    let mut values = Vec::with_capacity(args.len() - 1);
+    for arg in args[1..].iter() {
+        values.push(eval(arg, env, context)?);
+    }
+
+    let mut uints = vec![];
+    let mut principals = vec![];
+    for (value, expected_type) in values.into_iter().zip(asset_type.argument_types()) {
+        match (value, expected_type) {
+            (Value::UInt(x), TypeSignature::UIntType) => uints.push(x),
+            (Value::Principal(x), TypeSignature::PrincipalType) => principals.push(x),
+            (x, expected_type) => {
+                return Err(CheckErrors::TypeValueError(expected_type, x).into());
+            }
+        }
+    }
+
+    // uints is [block height, withdrawal id, amount or NFT id], and principals is
+    // [sender] or [sender, asset contract]
+    let (height_value, withdrawal_id, amount_or_id) = (uints[0], uints[1], uints[2]);
+    let sender = &principals[0];
+
+    // the current block's withdrawal tree is not built until the block is complete
+    let current_block_height = env.global_context.database.get_current_block_height();
+    let withdrawal_tree = match u32::try_from(height_value) {
+        Ok(height_value) if height_value < current_block_height => Some(
+            env.global_context
+                .database
+                .get_withdrawal_tree(height_value),
+        ),
+        _ => None,
+    };
+
+    // the cost of the lookup is in proportion to the size of the withdrawal tree it reads
+    runtime_cost(
+        ClarityCostFunction::GetWithdrawalInfo,
+        env,
+        withdrawal_tree
+            .as_ref()
+            .map(|tree| tree.num_leaves())
+            .unwrap_or(0),
+    )?;
+
+    let (height_value, withdrawal_tree) = match withdrawal_tree {
+        Some(withdrawal_tree) => (height_value as u32, withdrawal_tree),
+        None => return Ok(Value::none()),
+    };
+
+    let withdrawal_id = match u32::try_from(withdrawal_id) {
+        Ok(result) => result,
+        _ => return Ok(Value::none()),
+    };
+
+    let withdrawal_key = match asset_type {
+        WithdrawalAssetType::Stx => make_key_for_stx_withdrawal(
+            sender,
+            withdrawal_id,
+            amount_or_id,
+            u64::from(height_value),
+        ),
+        WithdrawalAssetType::FungibleToken | WithdrawalAssetType::NonFungibleToken => {
+            // only contracts can define withdrawable assets
+            let asset_contract = match principals[1] {
+                PrincipalData::Contract(ref contract_identifier) => contract_identifier,
+                PrincipalData::Standard(_) => return Ok(Value::none()),
+            };
+            if asset_type == WithdrawalAssetType::FungibleToken {
+                make_key_for_ft_withdrawal(
+                    sender,
+                    withdrawal_id,
+                    asset_contract,
+                    amount_or_id,
+                    u64::from(height_value),
+                )
+            } else {
+                make_key_for_nft_withdrawal(
+                    sender,
+                    withdrawal_id,
+                    asset_contract,
+                    amount_or_id,
+                    u64::from(height_value),
+                )
+            }
+        }
+    };
+
+    let leaf_hash =
+        MerkleTree::<Sha512Trunc256Sum>::get_leaf_hash(&withdrawal_key.serialize_to_vec());
+    // the withdrawal ID is the index of the withdrawal's leaf in the tree
+    if withdrawal_tree.leaf_at_index(withdrawal_id as usize) != Some(leaf_hash.clone()) {
+        return Ok(Value::none());
+    }
+
+    let withdrawal_info = TupleData::from_data(vec![
+        (
+            "withdrawal-root".into(),
+            Value::buff_from(withdrawal_tree.root().0.to_vec())?,
+        ),
+        (
+            "withdrawal-leaf-hash".into(),
+            Value::buff_from(leaf_hash.0.to_vec())?,
+        ),
+    ])?;
+
+    Ok(Value::some(Value::from(withdrawal_info))?)
+}
@@ -30,6 +30,7 @@ use crate::vm::types::{
     parse_name_type_pairs, PrincipalData, QualifiedContractIdentifier, TraitIdentifier,
     TupleTypeSignature, TypeSignature, Value,
 };
+use crate::vm::ClarityVersion;
 use std::collections::{BTreeMap, HashMap};
 
 define_named_enum!(DefineFunctions {
@@ -44,6 +45,7 @@ define_named_enum!(DefineFunctions {
     Trait("define-trait"),
     UseTrait("use-trait"),
     ImplTrait("impl-trait"),
+    DepositHandler("define-deposit-handler"),
 });
 
 pub enum DefineFunctionsParsed<'a> {
@@ -95,6 +97,9 @@ pub enum DefineFunctionsParsed<'a> {
     ImplTrait {
         trait_identifier: &'a TraitIdentifier,
     },
+    DepositHandler {
+        name: &'a ClarityName,
+    },
 }
 
 pub enum DefineResult {
@@ -107,6 +112,7 @@ pub enum DefineResult {
     Trait(ClarityName, BTreeMap<ClarityName, FunctionSignature>),
     UseTrait(ClarityName, TraitIdentifier),
     ImplTrait(TraitIdentifier),
+    DepositHandler(ClarityName),
     NoDefine,
 }
 
@@ -265,6 +271,25 @@ fn handle_impl_trait(trait_identifier: &TraitIdentifier) -> Result<DefineResult>
     Ok(DefineResult::ImplTrait(trait_identifier.clone()))
 }
 
+fn handle_define_deposit_handler(
+    name: &ClarityName,
+    env: &mut Environment,
+) -> Result<DefineResult> {
+    if DefineFunctions::DepositHandler.get_version() > env.contract_context.get_clarity_version() {
+        return Err(
+            CheckErrors::UnknownFunction(DefineFunctions::DepositHandler.get_name()).into(),
+        );
+    }
+    if env.contract_context.deposit_handler.is_some() {
+        return Err(CheckErrors::DepositHandlerAlreadyDefined.into());
+    }
+    match env.contract_context.functions.get(name) {
+        Some(function) if !function.is_public() => Ok(DefineResult::DepositHandler(name.clone())),
+        Some(_) => Err(CheckErrors::BadDepositHandler(name.to_string()).into()),
+        None => Err(CheckErrors::UndefinedFunction(name.to_string()).into()),
+    }
+}
+
 impl DefineFunctions {
     pub fn try_parse(
         expression: &SymbolicExpression,
@@ -275,6 +300,14 @@ impl DefineFunctions {
         let define_type = DefineFunctions::lookup_by_name(function_name)?;
         Some((define_type, args))
     }
+
+    /// The first Clarity version in which this define is available.
+    pub fn get_version(&self) -> ClarityVersion {
+        match self {
+            DefineFunctions::DepositHandler => ClarityVersion::Clarity2,
+            _ => ClarityVersion::Clarity1,
+        }
+    }
 }
 
 impl<'a> DefineFunctionsParsed<'a> {
@@ -393,6 +426,11 @@ impl<'a> DefineFunctionsParsed<'a> {
                     _ => return Err(CheckErrors::ExpectedTraitIdentifier.into()),
                 }
             }
+            DefineFunctions::DepositHandler => {
+                check_argument_count(1, args)?;
+                let name = args[0].match_atom().ok_or(CheckErrors::ExpectedName)?;
+                DefineFunctionsParsed::DepositHandler { name }
+            }
         };
         Ok(Some(result))
     }
@@ -445,6 +483,9 @@ pub fn evaluate_define(
             DefineFunctionsParsed::ImplTrait { trait_identifier } => {
                 handle_impl_trait(trait_identifier)
             }
+            DefineFunctionsParsed::DepositHandler { name } => {
+                handle_define_deposit_handler(name, env)
+            }
         }
     } else {
         Ok(DefineResult::NoDefine)
@@ -20,7 +20,7 @@ use crate::vm::costs::{
     constants as cost_constants, cost_functions, runtime_cost, CostTracker, MemoryConsumer,
 };
 use crate::vm::errors::{
-    check_argument_count, check_arguments_at_least, CheckErrors, Error,
+    check_argument_count, check_arguments_at_least, check_arguments_at_most, CheckErrors, Error,
     InterpreterResult as Result, RuntimeErrorType, ShortReturnType,
 };
 pub use crate::vm::functions::assets::stx_transfer_consolidated;
@@ -29,9 +29,9 @@ use crate::vm::representations::SymbolicExpressionType::{Atom, List};
 use crate::vm::representations::{ClarityName, SymbolicExpression, SymbolicExpressionType};
 use crate::vm::types::{
     BuffData, CharType, PrincipalData, ResponseData, SequenceData, TypeSignature, Value, BUFF_32,
-    BUFF_33, BUFF_65,
+    BUFF_33, BUFF_65, PRINT_TOPIC,
 };
-use crate::vm::{eval, Environment, LocalContext};
+use crate::vm::{eval, ClarityVersion, Environment, LocalContext};
 use stacks_common::address::AddressHashMode;
 use stacks_common::util::hash;
 
@@ -50,7 +50,9 @@ macro_rules! switch_on_global_epoch {
                     panic!("Executing Clarity method during Epoch 1.0, before Clarity")
                 }
                 StacksEpochId::Epoch20 => $Epoch2Version(args, env, context),
-                StacksEpochId::Epoch2_05 => $Epoch205Version(args, env, context),
+                StacksEpochId::Epoch2_05 | StacksEpochId::EpochSubnet1 => {
+                    $Epoch205Version(args, env, context)
+                }
             }
         }
     };
@@ -59,6 +61,7 @@ macro_rules! switch_on_global_epoch {
 mod arithmetic;
 mod assets;
 mod boolean;
+mod conversions;
 mod crypto;
 mod database;
 pub mod define;
@@ -147,17 +150,55 @@ define_named_enum!(NativeFunctions {
     BurnToken("ft-burn?"),
     BurnAsset("nft-burn?"),
     GetStxBalance("stx-get-balance"),
+    StxGetAccount("stx-account"),
     StxTransfer("stx-transfer?"),
     StxBurn("stx-burn?"),
     StxWithdraw("stx-withdraw?"),
     WithdrawToken("ft-withdraw?"),
     WithdrawAsset("nft-withdraw?"),
+    GetWithdrawalInfo("get-withdrawal-info?"),
+    GetL1BlockInfo("get-l1-block-info?"),
+    VarExists("var-exists?"),
+    MapSize("map-size"),
+    TryContractCall("try-contract-call?"),
+    Slice("slice"),
+    BuffToUIntBe("buff-to-uint-be"),
+    BuffToUIntLe("buff-to-uint-le"),
+    ReplaceAt("replace-at?"),
+    ToConsensusBuff("to-consensus-buff?"),
+    FromConsensusBuff("from-consensus-buff?"),
+    BitwiseAnd("bit-and"),
+    BitwiseOr("bit-or"),
+    BitwiseNot("bit-not"),
+    BitwiseLShift("bit-shift-left"),
+    BitwiseRShift("bit-shift-right"),
 });
 
-pub fn lookup_reserved_functions(name: &str) -> Option<CallableType> {
+impl NativeFunctions {
+    /// The first Clarity version in which this native is defined.  A contract of an earlier
+    ///  version may use the name for something else, so these natives are only looked up in
+    ///  contracts of at least this version.
+    pub fn get_version(&self) -> ClarityVersion {
+        use crate::vm::functions::NativeFunctions::*;
+        match self {
+            GetWithdrawalInfo | GetL1BlockInfo | VarExists | MapSize | TryContractCall | Slice
+            | BuffToUIntBe | BuffToUIntLe | ReplaceAt | StxGetAccount | ToConsensusBuff
+            | FromConsensusBuff | BitwiseAnd | BitwiseOr | BitwiseNot | BitwiseLShift
+            | BitwiseRShift => ClarityVersion::Clarity2,
+            _ => ClarityVersion::Clarity1,
+        }
+    }
+
+    /// Look up a native by name, if it is defined in contracts of `version`.
+    pub fn lookup_by_name_at_version(name: &str, version: ClarityVersion) -> Option<Self> {
+        NativeFunctions::lookup_by_name(name).filter(|native| native.get_version() <= version)
+    }
+}
+
+pub fn lookup_reserved_functions(name: &str, version: ClarityVersion) -> Option<CallableType> {
     use crate::vm::callables::CallableType::{NativeFunction, NativeFunction205, SpecialFunction};
     use crate::vm::functions::NativeFunctions::*;
-    if let Some(native_function) = NativeFunctions::lookup_by_name(name) {
+    if let Some(native_function) = NativeFunctions::lookup_by_name_at_version(name, version) {
         let callable = match native_function {
             Add => NativeFunction(
                 "native_add",
@@ -209,6 +250,16 @@ pub fn lookup_reserved_functions(name: &str) -> Option<CallableType> {
                 NativeHandle::SingleArg(&arithmetic::native_to_int),
                 ClarityCostFunction::IntCast,
             ),
+            BuffToUIntBe => NativeFunction(
+                "native_buff_to_uint_be",
+                NativeHandle::SingleArg(&arithmetic::native_buff_to_uint_be),
+                ClarityCostFunction::IntCast,
+            ),
+            BuffToUIntLe => NativeFunction(
+                "native_buff_to_uint_le",
+                NativeHandle::SingleArg(&arithmetic::native_buff_to_uint_le),
+                ClarityCostFunction::IntCast,
+            ),
             Modulo => NativeFunction(
                 "native_mod",
                 NativeHandle::DoubleArg(&arithmetic::native_mod),
@@ -234,6 +285,31 @@ pub fn lookup_reserved_functions(name: &str) -> Option<CallableType> {
                 NativeHandle::DoubleArg(&arithmetic::native_xor),
                 ClarityCostFunction::Xor,
             ),
+            BitwiseAnd => NativeFunction(
+                "native_bitwise_and",
+                NativeHandle::MoreArg(&arithmetic::native_bitwise_and),
+                ClarityCostFunction::Add,
+            ),
+            BitwiseOr => NativeFunction(
+                "native_bitwise_or",
+                NativeHandle::MoreArg(&arithmetic::native_bitwise_or),
+                ClarityCostFunction::Add,
+            ),
+            BitwiseNot => NativeFunction(
+                "native_bitwise_not",
+                NativeHandle::SingleArg(&arithmetic::native_bitwise_not),
+                ClarityCostFunction::Xor,
+            ),
+            BitwiseLShift => NativeFunction(
+                "native_bitwise_left_shift",
+                NativeHandle::DoubleArg(&arithmetic::native_bitwise_left_shift),
+                ClarityCostFunction::Xor,
+            ),
+            BitwiseRShift => NativeFunction(
+                "native_bitwise_right_shift",
+                NativeHandle::DoubleArg(&arithmetic::native_bitwise_right_shift),
+                ClarityCostFunction::Xor,
+            ),
             And => SpecialFunction("special_and", &boolean::special_and),
             Or => SpecialFunction("special_or", &boolean::special_or),
             Not => NativeFunction(
@@ -273,6 +349,16 @@ pub fn lookup_reserved_functions(name: &str) -> Option<CallableType> {
                 ClarityCostFunction::IndexOf,
                 &cost_input_sized_vararg,
             ),
+            Slice => SpecialFunction("special_slice", &sequences::special_slice),
+            ReplaceAt => SpecialFunction("special_replace_at", &sequences::special_replace_at),
+            ToConsensusBuff => SpecialFunction(
+                "special_to_consensus_buff",
+                &conversions::special_to_consensus_buff,
+            ),
+            FromConsensusBuff => SpecialFunction(
+                "special_from_consensus_buff",
+                &conversions::special_from_consensus_buff,
+            ),
             ListCons => SpecialFunction("special_list_cons", &sequences::list_cons),
             FetchEntry => SpecialFunction("special_map-get?", &database::special_fetch_entry),
             SetEntry => SpecialFunction("special_set-entry", &database::special_set_entry),
@@ -332,6 +418,10 @@ pub fn lookup_reserved_functions(name: &str) -> Option<CallableType> {
             ContractCall => {
                 SpecialFunction("special_contract-call", &database::special_contract_call)
             }
+            TryContractCall => SpecialFunction(
+                "special_try-contract-call",
+                &database::special_try_contract_call,
+            ),
             AsContract => SpecialFunction("special_as-contract", &special_as_contract),
             ContractOf => SpecialFunction("special_contract-of", &special_contract_of),
             PrincipalOf => SpecialFunction("special_principal-of", &crypto::special_principal_of),
@@ -423,6 +513,7 @@ pub fn lookup_reserved_functions(name: &str) -> Option<CallableType> {
             ),
             AtBlock => SpecialFunction("special_at_block", &database::special_at_block),
             GetStxBalance => SpecialFunction("special_stx_balance", &assets::special_stx_balance),
+            StxGetAccount => SpecialFunction("special_stx_account", &assets::special_stx_account),
             StxTransfer => SpecialFunction("special_stx_transfer", &assets::special_stx_transfer),
             StxBurn => SpecialFunction("special_stx_burn", &assets::special_stx_burn),
             StxWithdraw => SpecialFunction("special_stx_withdraw", &assets::special_stx_withdraw),
@@ -432,6 +523,16 @@ pub fn lookup_reserved_functions(name: &str) -> Option<CallableType> {
             WithdrawToken => {
                 SpecialFunction("special_withdraw_token", &assets::special_withdraw_token)
             }
+            GetWithdrawalInfo => SpecialFunction(
+                "special_get_withdrawal_info",
+                &database::special_get_withdrawal_info,
+            ),
+            VarExists => SpecialFunction("special_var_exists", &database::special_var_exists),
+            MapSize => SpecialFunction("special_map_size", &database::special_map_size),
+            GetL1BlockInfo => SpecialFunction(
+                "special_get_l1_block_info",
+                &database::special_get_l1_block_info,
+            ),
         };
         Some(callable)
     } else {
@@ -472,15 +573,33 @@ fn special_print(
     env: &mut Environment,
     context: &LocalContext,
 ) -> Result<Value> {
+    // Clarity 1 prints take no topic
+    if env.contract_context.get_clarity_version() < ClarityVersion::Clarity2 {
+        check_argument_count(1, args)?;
+    }
+    check_arguments_at_least(1, args)?;
+    check_arguments_at_most(2, args)?;
+
     let input = eval(&args[0], env, context)?;
+    let topic = match args.get(1) {
+        Some(topic_expr) => {
+            let topic = eval(topic_expr, env, context)?;
+            if !PRINT_TOPIC.admits(&topic) {
+                return Err(CheckErrors::TypeValueError(PRINT_TOPIC.clone(), topic).into());
+            }
+            Some(topic.expect_ascii())
+        }
+        None => None,
+    };
+    let topic_len = topic.as_ref().map(|topic| topic.len() as u32).unwrap_or(0);
 
-    runtime_cost(ClarityCostFunction::Print, env, input.size())?;
+    runtime_cost(ClarityCostFunction::Print, env, input.size() + topic_len)?;
 
     if cfg!(feature = "developer-mode") {
         info!("{}", &input);
     }
 
-    env.register_print_event(input.clone())?;
+    env.register_print_event(input.clone(), topic)?;
     Ok(input)
 }
 
@@ -591,7 +710,7 @@ fn special_let(
 
     finally_drop_memory!( env, memory_use; {
         handle_binding_list::<_, Error>(bindings, |binding_name, var_sexp| {
-            if is_reserved(binding_name) ||
+            if is_reserved(binding_name, env.contract_context.get_clarity_version()) ||
                 env.contract_context.lookup_function(binding_name).is_some() ||
                 inner_context.lookup_variable(binding_name).is_some() {
                     return Err(CheckErrors::NameAlreadyUsed(binding_name.clone().into()).into())
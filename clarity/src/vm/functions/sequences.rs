@@ -343,3 +343,80 @@ pub fn native_element_at(sequence: Value, index: Value) -> Result<Value> {
         Ok(Value::none())
     }
 }
+
+pub fn special_slice(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    // (slice sequence start end)
+    check_argument_count(3, args)?;
+
+    let sequence = eval(&args[0], env, context)?;
+    let start = eval(&args[1], env, context)?;
+    let end = eval(&args[2], env, context)?;
+
+    let sequence_data = if let Value::Sequence(sequence_data) = sequence {
+        sequence_data
+    } else {
+        return Err(CheckErrors::ExpectedSequence(TypeSignature::type_of(&sequence)).into());
+    };
+    let (start, end) = match (start, end) {
+        (Value::UInt(start), Value::UInt(end)) => (start, end),
+        (Value::UInt(_), end) => {
+            return Err(CheckErrors::TypeValueError(TypeSignature::UIntType, end).into())
+        }
+        (start, _) => {
+            return Err(CheckErrors::TypeValueError(TypeSignature::UIntType, start).into())
+        }
+    };
+
+    let (start, end) = match (usize::try_from(start), usize::try_from(end)) {
+        (Ok(start), Ok(end)) if start <= end && end <= sequence_data.len() => (start, end),
+        _ => {
+            runtime_cost(ClarityCostFunction::Slice, env, 0)?;
+            return Ok(Value::none());
+        }
+    };
+    runtime_cost(ClarityCostFunction::Slice, env, end - start)?;
+
+    match sequence_data.slice(start, end) {
+        Some(slice) => Value::some(Value::Sequence(slice)),
+        None => Ok(Value::none()),
+    }
+}
+
+pub fn special_replace_at(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    // (replace-at? sequence index new-element)
+    check_argument_count(3, args)?;
+
+    let sequence = eval(&args[0], env, context)?;
+    let index = eval(&args[1], env, context)?;
+    let element = eval(&args[2], env, context)?;
+
+    let sequence_data = if let Value::Sequence(sequence_data) = sequence {
+        sequence_data
+    } else {
+        return Err(CheckErrors::ExpectedSequence(TypeSignature::type_of(&sequence)).into());
+    };
+    let index = if let Value::UInt(index) = index {
+        index
+    } else {
+        return Err(CheckErrors::TypeValueError(TypeSignature::UIntType, index).into());
+    };
+
+    runtime_cost(ClarityCostFunction::ReplaceAt, env, sequence_data.len())?;
+
+    let index = match usize::try_from(index) {
+        Ok(index) => index,
+        Err(_) => return Ok(Value::none()),
+    };
+    match sequence_data.replace_at(index, element)? {
+        Some(sequence_data) => Value::some(Value::Sequence(sequence_data)),
+        None => Ok(Value::none()),
+    }
+}
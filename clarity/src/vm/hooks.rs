@@ -0,0 +1,292 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Hooks into expression evaluation, for debugging contracts.  Hooks are only invoked in builds
+//! with the `eval-hooks` feature, so production nodes pay nothing for them.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use crate::vm::contexts::{Environment, LocalContext};
+use crate::vm::costs::{CostTracker, ExecutionCost};
+use crate::vm::errors::InterpreterResult as Result;
+use crate::vm::representations::SymbolicExpression;
+use crate::vm::types::Value;
+
+/// Observer of expression evaluation.  Hooks are registered in a `GlobalContext`, and are
+/// invoked in registration order before and after every expression the VM evaluates.  While
+/// a hook runs, the context's hooks are detached, so a hook will not observe any evaluation it
+/// performs itself.
+pub trait EvalHook {
+    /// Called before `expr` is evaluated in `context`
+    fn will_begin_eval(
+        &mut self,
+        env: &mut Environment,
+        context: &LocalContext,
+        expr: &SymbolicExpression,
+    );
+
+    /// Called after `expr` has been evaluated in `context`, with the outcome
+    fn did_finish_eval(
+        &mut self,
+        env: &mut Environment,
+        context: &LocalContext,
+        expr: &SymbolicExpression,
+        result: &Result<Value>,
+    );
+}
+
+/// One evaluated expression in a step trace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalStep {
+    /// How many enclosing expressions were being evaluated when this one began
+    pub depth: u32,
+    /// The contract whose code this expression belongs to
+    pub contract: String,
+    /// The expression, in Clarity syntax
+    pub expression: String,
+    pub line: u32,
+    pub column: u32,
+    /// The local variables in scope when evaluation began, in Clarity syntax
+    pub bindings: BTreeMap<String, String>,
+    /// The value the expression evaluated to, in Clarity syntax
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    /// The error the expression failed with
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Cost of evaluating the expression, including its subexpressions
+    pub cost: ExecutionCost,
+}
+
+/// The expressions evaluated by a call, in the order in which their evaluation began
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StepTrace {
+    pub steps: Vec<EvalStep>,
+    /// Set if evaluation went on past the tracer's step limit
+    pub truncated: bool,
+}
+
+struct StepTracerState {
+    trace: StepTrace,
+    max_steps: usize,
+    /// For each expression under evaluation, its index in `trace.steps` (if it was recorded)
+    /// and the total cost consumed when it began.
+    open: Vec<(Option<usize>, ExecutionCost)>,
+}
+
+/// An `EvalHook` that records a `StepTrace` of up to `max_steps` expressions.  Clones share the
+/// same trace, so a caller can keep one clone and register the other.
+#[derive(Clone)]
+pub struct StepTracer {
+    state: Rc<RefCell<StepTracerState>>,
+}
+
+impl StepTracer {
+    pub fn new(max_steps: usize) -> StepTracer {
+        StepTracer {
+            state: Rc::new(RefCell::new(StepTracerState {
+                trace: StepTrace::default(),
+                max_steps,
+                open: vec![],
+            })),
+        }
+    }
+
+    /// Take the steps recorded so far, leaving the trace empty
+    pub fn take_trace(&self) -> StepTrace {
+        std::mem::take(&mut self.state.borrow_mut().trace)
+    }
+}
+
+/// All local variables visible from `context`, rendered in Clarity syntax
+fn local_bindings(context: &LocalContext) -> BTreeMap<String, String> {
+    let mut bindings = BTreeMap::new();
+    let mut next = Some(context);
+    while let Some(context) = next {
+        for (name, value) in context.variables.iter() {
+            // inner scopes are visited first, and win
+            bindings
+                .entry(name.to_string())
+                .or_insert_with(|| value.to_string());
+        }
+        next = context.parent;
+    }
+    bindings
+}
+
+impl EvalHook for StepTracer {
+    fn will_begin_eval(
+        &mut self,
+        env: &mut Environment,
+        context: &LocalContext,
+        expr: &SymbolicExpression,
+    ) {
+        let mut state = self.state.borrow_mut();
+        let start_cost = env.global_context.cost_track.get_total();
+        if state.trace.steps.len() >= state.max_steps {
+            state.trace.truncated = true;
+            state.open.push((None, start_cost));
+            return;
+        }
+
+        let step = EvalStep {
+            depth: state.open.len() as u32,
+            contract: env.contract_context.contract_identifier.to_string(),
+            expression: expr.to_string(),
+            line: expr.span.start_line,
+            column: expr.span.start_column,
+            bindings: local_bindings(context),
+            result: None,
+            error: None,
+            cost: ExecutionCost::zero(),
+        };
+        let index = state.trace.steps.len();
+        state.trace.steps.push(step);
+        state.open.push((Some(index), start_cost));
+    }
+
+    fn did_finish_eval(
+        &mut self,
+        env: &mut Environment,
+        _context: &LocalContext,
+        _expr: &SymbolicExpression,
+        result: &Result<Value>,
+    ) {
+        let mut state = self.state.borrow_mut();
+        let (index, start_cost) = match state.open.pop() {
+            Some((Some(index), start_cost)) => (index, start_cost),
+            _ => {
+                return;
+            }
+        };
+
+        let mut cost = env.global_context.cost_track.get_total();
+        if cost.sub(&start_cost).is_err() {
+            // the cost tracker was reset mid-evaluation
+            cost = ExecutionCost::zero();
+        }
+
+        let step = &mut state.trace.steps[index];
+        step.cost = cost;
+        match result {
+            Ok(value) => step.result = Some(value.to_string()),
+            Err(e) => step.error = Some(e.to_string()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "eval-hooks"))]
+mod test {
+    use super::*;
+    use crate::vm::contexts::OwnedEnvironment;
+    use crate::vm::database::MemoryBackingStore;
+    use crate::vm::types::{QualifiedContractIdentifier, StandardPrincipalData};
+
+    #[test]
+    fn test_step_tracer() {
+        let mut marf = MemoryBackingStore::new();
+        let mut owned_env = OwnedEnvironment::new(marf.as_clarity_db());
+
+        let contract_id = QualifiedContractIdentifier::local("tracee").unwrap();
+        owned_env
+            .initialize_contract(
+                contract_id.clone(),
+                "(define-read-only (double (x int)) (let ((y (* x 2))) (+ y 0)))
+                 (define-read-only (divide (x int)) (/ x 0))",
+            )
+            .unwrap();
+
+        let tracer = StepTracer::new(100);
+        owned_env.add_eval_hook(Box::new(tracer.clone()));
+
+        let sender = StandardPrincipalData::transient().into();
+        let (result, ..) = owned_env
+            .execute_transaction(
+                sender,
+                contract_id.clone(),
+                "double",
+                &[SymbolicExpression::atom_value(Value::Int(21))],
+            )
+            .unwrap();
+        assert_eq!(result, Value::Int(42));
+
+        let trace = tracer.take_trace();
+        assert!(!trace.truncated);
+        let step_of = |expression: &str| {
+            trace
+                .steps
+                .iter()
+                .find(|step| step.expression == expression)
+                .expect(&format!("no step for {}", expression))
+        };
+
+        // the function body is the outermost step
+        let body = &trace.steps[0];
+        assert_eq!(body.depth, 0);
+        assert_eq!(body.contract, contract_id.to_string());
+        assert_eq!(body.result, Some("42".to_string()));
+        assert_eq!(body.bindings.get("x"), Some(&"21".to_string()));
+
+        let mul = step_of("( * x 2 )");
+        assert_eq!(mul.depth, 1);
+        assert_eq!(mul.result, Some("42".to_string()));
+        assert!(mul.bindings.get("y").is_none());
+
+        let add = step_of("( + y 0 )");
+        assert_eq!(add.bindings.get("x"), Some(&"21".to_string()));
+        assert_eq!(add.bindings.get("y"), Some(&"42".to_string()));
+
+        // a failure is recorded on the expression that failed
+        owned_env
+            .execute_transaction(
+                StandardPrincipalData::transient().into(),
+                contract_id.clone(),
+                "divide",
+                &[SymbolicExpression::atom_value(Value::Int(1))],
+            )
+            .unwrap_err();
+        let trace = tracer.take_trace();
+        assert_eq!(trace.steps[0].expression, "( / x 0 )");
+        assert!(trace.steps[0].error.is_some());
+        assert!(trace.steps[0].result.is_none());
+        assert_eq!(trace.steps[1].result, Some("1".to_string()));
+    }
+
+    #[test]
+    fn test_step_tracer_truncates() {
+        let mut marf = MemoryBackingStore::new();
+        let mut owned_env = OwnedEnvironment::new(marf.as_clarity_db());
+
+        let tracer = StepTracer::new(3);
+        owned_env.add_eval_hook(Box::new(tracer.clone()));
+
+        let contract_id = QualifiedContractIdentifier::local("tracee").unwrap();
+        owned_env
+            .initialize_contract(contract_id, "(define-constant c (+ 1 (+ 2 (+ 3 4))))")
+            .unwrap();
+
+        let trace = tracer.take_trace();
+        assert!(trace.truncated);
+        assert_eq!(trace.steps.len(), 3);
+        // the recorded steps are still completed
+        assert_eq!(trace.steps[0].result, Some("10".to_string()));
+        assert_eq!(trace.steps[2].result, Some("9".to_string()));
+    }
+}
@@ -39,6 +39,7 @@ pub mod analysis;
 pub mod docs;
 
 pub mod coverage;
+pub mod hooks;
 
 pub mod events;
 
@@ -50,6 +51,8 @@ pub mod test_util;
 
 pub mod clarity;
 
+pub mod version;
+
 // publish the non-generic StacksEpoch form for use throughout module
 use crate::types::StacksEpochId;
 pub use crate::vm::database::clarity_db::StacksEpoch;
@@ -77,6 +80,7 @@ pub use crate::vm::representations::{
 pub use crate::vm::contexts::MAX_CONTEXT_DEPTH;
 use crate::vm::costs::cost_functions::ClarityCostFunction;
 pub use crate::vm::functions::stx_transfer_consolidated;
+pub use crate::vm::version::ClarityVersion;
 use std::convert::{TryFrom, TryInto};
 
 const MAX_CALL_STACK_DEPTH: usize = 64;
@@ -118,7 +122,9 @@ fn lookup_variable(name: &str, context: &LocalContext, env: &mut Environment) ->
 pub fn lookup_function(name: &str, env: &mut Environment) -> Result<CallableType> {
     runtime_cost(ClarityCostFunction::LookupFunction, env, 0)?;
 
-    if let Some(result) = functions::lookup_reserved_functions(name) {
+    if let Some(result) =
+        functions::lookup_reserved_functions(name, env.contract_context.get_clarity_version())
+    {
         Ok(result)
     } else {
         let user_function = env
@@ -221,10 +227,48 @@ pub fn apply(
     }
 }
 
+#[cfg(not(feature = "eval-hooks"))]
+pub fn eval<'a>(
+    exp: &SymbolicExpression,
+    env: &'a mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    eval_expression(exp, env, context)
+}
+
+/// Evaluate `exp`, invoking the global context's eval hooks before and after.  The hooks are
+/// detached from the context while they run.
+#[cfg(feature = "eval-hooks")]
 pub fn eval<'a>(
     exp: &SymbolicExpression,
     env: &'a mut Environment,
     context: &LocalContext,
+) -> Result<Value> {
+    if env.global_context.eval_hooks.is_empty() {
+        return eval_expression(exp, env, context);
+    }
+
+    let mut hooks = std::mem::take(&mut env.global_context.eval_hooks);
+    for hook in hooks.iter_mut() {
+        hook.will_begin_eval(env, context, exp);
+    }
+    env.global_context.eval_hooks = hooks;
+
+    let result = eval_expression(exp, env, context);
+
+    let mut hooks = std::mem::take(&mut env.global_context.eval_hooks);
+    for hook in hooks.iter_mut() {
+        hook.did_finish_eval(env, context, exp, &result);
+    }
+    env.global_context.eval_hooks = hooks;
+
+    result
+}
+
+fn eval_expression(
+    exp: &SymbolicExpression,
+    env: &mut Environment,
+    context: &LocalContext,
 ) -> Result<Value> {
     use crate::vm::representations::SymbolicExpressionType::{
         Atom, AtomValue, Field, List, LiteralValue, TraitReference,
@@ -259,10 +303,11 @@ pub fn eval<'a>(
     }
 }
 
-pub fn is_reserved(name: &str) -> bool {
-    if let Some(_result) = functions::lookup_reserved_functions(name) {
+/// Is `name` the name of a native function or keyword in contracts of `version`?
+pub fn is_reserved(name: &str, version: ClarityVersion) -> bool {
+    if let Some(_result) = functions::lookup_reserved_functions(name, version) {
         true
-    } else if variables::is_reserved_name(name) {
+    } else if variables::is_reserved_name(name, version) {
         true
     } else {
         false
@@ -330,7 +375,9 @@ pub fn eval_all(
                     global_context.add_memory(value_type.type_size()
                                               .expect("type size should be realizable") as u64)?;
 
-                    let data_type = global_context.database.create_map(&contract_context.contract_identifier, &name, key_type, value_type);
+                    // only Clarity 2 contracts can ask for a map's size
+                    let track_size = contract_context.get_clarity_version() >= ClarityVersion::Clarity2;
+                    let data_type = global_context.database.create_map(&contract_context.contract_identifier, &name, key_type, value_type, track_size);
 
                     contract_context.meta_data_map.insert(name, data_type);
                 },
@@ -363,6 +410,9 @@ pub fn eval_all(
                 DefineResult::ImplTrait(trait_identifier) => {
                     contract_context.implemented_traits.insert(trait_identifier);
                 },
+                DefineResult::DepositHandler(name) => {
+                    contract_context.deposit_handler = Some(name);
+                },
                 DefineResult::NoDefine => {
                     // not a define function, evaluate normally.
                     global_context.execute(|global_context| {
@@ -414,7 +464,7 @@ pub fn execute_in_epoch(
     use crate::vm::database::MemoryBackingStore;
 
     let contract_id = QualifiedContractIdentifier::transient();
-    let mut contract_context = ContractContext::new(contract_id.clone());
+    let mut contract_context = ContractContext::new(contract_id.clone(), ClarityVersion::latest());
     let mut marf = MemoryBackingStore::new();
     let conn = marf.as_clarity_db();
     let mut global_context =
@@ -436,8 +486,8 @@ mod test {
     use crate::vm::execute;
     use crate::vm::types::{QualifiedContractIdentifier, TypeSignature};
     use crate::vm::{
-        CallStack, ContractContext, Environment, GlobalContext, LocalContext, SymbolicExpression,
-        Value,
+        CallStack, ClarityVersion, ContractContext, Environment, GlobalContext, LocalContext,
+        SymbolicExpression, Value,
     };
     use std::collections::HashMap;
 
@@ -470,7 +520,10 @@ mod test {
         );
 
         let context = LocalContext::new();
-        let mut contract_context = ContractContext::new(QualifiedContractIdentifier::transient());
+        let mut contract_context = ContractContext::new(
+            QualifiedContractIdentifier::transient(),
+            ClarityVersion::latest(),
+        );
 
         let mut marf = MemoryBackingStore::new();
         let mut global_context = GlobalContext::new(
@@ -16,6 +16,7 @@ use stacks_common::types::chainstate::{
 };
 use stacks_common::types::chainstate::{StacksPrivateKey, StacksPublicKey};
 use stacks_common::types::{StacksEpochId, PEER_VERSION_EPOCH_2_0};
+use stacks_common::util::hash::{MerkleTree, Sha512Trunc256Sum};
 
 pub struct UnitTestBurnStateDB {
     pub epoch_id: StacksEpochId,
@@ -141,6 +142,13 @@ impl HeadersDB for UnitTestHeaderDB {
     fn get_miner_address(&self, _id_bhh: &StacksBlockId) -> Option<StacksAddress> {
         None
     }
+    fn get_withdrawal_tree_for_block(
+        &self,
+        _id_bhh: &StacksBlockId,
+    ) -> Option<MerkleTree<Sha512Trunc256Sum>> {
+        // no unit test blocks contain withdrawals
+        Some(MerkleTree::empty())
+    }
 }
 
 impl BurnStateDB for UnitTestBurnStateDB {
@@ -156,6 +164,10 @@ impl BurnStateDB for UnitTestBurnStateDB {
         None
     }
 
+    fn get_burn_header_timestamp(&self, _height: u32, _sortition_id: &SortitionId) -> Option<u64> {
+        None
+    }
+
     fn get_stacks_epoch(&self, _height: u32) -> Option<StacksEpoch> {
         Some(StacksEpoch {
             epoch_id: self.epoch_id,
@@ -17,7 +17,7 @@
 use crate::vm::contexts::{AssetMap, AssetMapEntry, GlobalContext, OwnedEnvironment};
 use crate::vm::contracts::Contract;
 use crate::vm::errors::{CheckErrors, Error, RuntimeErrorType};
-use crate::vm::events::StacksTransactionEvent;
+use crate::vm::events::{STXEventType, StacksTransactionEvent};
 use crate::vm::execute as vm_execute;
 use crate::vm::representations::SymbolicExpression;
 use crate::vm::tests::{
@@ -142,6 +142,7 @@ fn test_native_stx_ops(owned_env: &mut OwnedEnvironment) {
                     (define-public (withdraw-stx (amount uint) (p principal)) (stx-withdraw? amount p))
                     (define-public (xfer-stx (amount uint) (p principal) (t principal)) (stx-transfer? amount p t))
                     (define-read-only (balance-stx (p principal)) (stx-get-balance p))
+                    (define-read-only (account-stx (p principal)) (stx-account p))
                     (define-public (to-contract (amount uint) (p principal))
                       (let ((contract-principal (as-contract tx-sender)))
                         (stx-transfer? amount p contract-principal)))
@@ -408,6 +409,21 @@ fn test_native_stx_ops(owned_env: &mut OwnedEnvironment) {
         &AssetMapEntry::Burn(10)
     );
 
+    // the withdrawn amount is pending until claimed on the L1
+    let (result, _asset_map, _events) = execute_transaction(
+        owned_env,
+        p2_principal.clone(),
+        &token_contract_id,
+        "account-stx",
+        &symbols_from_values(vec![p2.clone()]),
+    )
+    .unwrap();
+
+    assert_eq!(
+        result,
+        execute("(tuple (unlocked u980) (total u990) (pending-withdrawal u10))")
+    );
+
     let (result, asset_map, _events) = execute_transaction(
         owned_env,
         p2_principal.clone(),
@@ -594,6 +610,93 @@ fn test_native_stx_ops(owned_env: &mut OwnedEnvironment) {
     );
 }
 
+fn test_native_stx_withdraw_tip(owned_env: &mut OwnedEnvironment) {
+    let contract = "(define-public (withdraw-stx (amount uint) (p principal) (tip uint))
+                      (stx-withdraw? amount p tip))
+                    (define-read-only (account-stx (p principal)) (stx-account p))";
+
+    let p1 = execute("'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR");
+    let p2 = execute("'SM2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQVX8X0G");
+
+    let p1_std_principal_data = match p1 {
+        Value::Principal(PrincipalData::Standard(ref data)) => data.clone(),
+        _ => panic!(),
+    };
+
+    let p2_principal = match p2 {
+        Value::Principal(ref data) => data.clone(),
+        _ => panic!(),
+    };
+
+    let token_contract_id =
+        QualifiedContractIdentifier::new(p1_std_principal_data.clone(), "tokens".into());
+
+    owned_env
+        .initialize_contract(token_contract_id.clone(), contract)
+        .unwrap();
+    owned_env.stx_faucet(&p2_principal, 1000);
+
+    // the balance must cover both the withdrawal and the tip
+    let (result, asset_map, events) = execute_transaction(
+        owned_env,
+        p2_principal.clone(),
+        &token_contract_id,
+        "withdraw-stx",
+        &symbols_from_values(vec![Value::UInt(10), p2.clone(), Value::UInt(991)]),
+    )
+    .unwrap();
+
+    assert!(is_err_code(&result, 1));
+    assert_eq!(asset_map.to_table().len(), 0);
+    assert!(events.is_empty());
+
+    let (result, asset_map, events) = execute_transaction(
+        owned_env,
+        p2_principal.clone(),
+        &token_contract_id,
+        "withdraw-stx",
+        &symbols_from_values(vec![Value::UInt(10), p2.clone(), Value::UInt(5)]),
+    )
+    .unwrap();
+
+    assert!(is_committed(&result));
+    let table = asset_map.to_table();
+    assert_eq!(
+        table
+            .get(&p2_principal)
+            .unwrap()
+            .get(&AssetIdentifier::STX_burned())
+            .unwrap(),
+        &AssetMapEntry::Burn(15)
+    );
+
+    // the tip event immediately follows its withdrawal's event
+    assert_eq!(events.len(), 2);
+    match &events[1] {
+        StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawTipEvent(data)) => {
+            assert_eq!(data.sender, p2_principal);
+            assert_eq!(data.amount, 5);
+            assert_eq!(data.recipient, None);
+        }
+        event => panic!("Expected a withdraw tip event, got {:?}", event),
+    }
+
+    // only the withdrawn amount is pending: the tip is paid to the miner
+    let (result, _asset_map, _events) = execute_transaction(
+        owned_env,
+        p2_principal.clone(),
+        &token_contract_id,
+        "account-stx",
+        &symbols_from_values(vec![p2.clone()]),
+    )
+    .unwrap();
+
+    assert_eq!(
+        result,
+        execute("(tuple (unlocked u985) (total u995) (pending-withdrawal u10))")
+    );
+}
+
 fn test_simple_token_system(owned_env: &mut OwnedEnvironment) {
     let tokens_contract = FIRST_CLASS_TOKENS;
 
@@ -1448,6 +1551,7 @@ fn test_all() {
         test_simple_naming_system,
         test_total_supply,
         test_native_stx_ops,
+        test_native_stx_withdraw_tip,
     ];
     for test in to_test.iter() {
         with_memory_environment(test, true);
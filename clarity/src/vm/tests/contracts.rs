@@ -700,6 +700,133 @@ fn test_aborts(owned_env: &mut OwnedEnvironment) {
     );
 }
 
+fn test_try_contract_call_aborts(owned_env: &mut OwnedEnvironment) {
+    let contract_1 = "
+(define-map data { id: int } { value: int })
+
+(define-public (modify-data
+                 (id int)
+                 (value int))
+   (begin
+     (map-set data (tuple (id id))
+                      (tuple (value value)))
+     (if (is-eq id value)
+         (ok 1)
+         (err 1))))
+
+;; this writes, and then aborts with a division by zero
+(define-public (panic-after-write
+                 (id int)
+                 (value int))
+   (begin
+     (map-set data (tuple (id id))
+                      (tuple (value value)))
+     (print value)
+     (ok (/ value (- id id)))))
+
+(define-private (get-data (id int))
+  (default-to 0
+    (get value
+     (map-get? data (tuple (id id))))))
+";
+
+    let contract_2 = "
+(define-public (call-ok)
+  (try-contract-call? .contract-1 modify-data 10 10))
+
+(define-public (call-err)
+  (try-contract-call? .contract-1 modify-data 20 10))
+
+(define-public (call-panic)
+  (try-contract-call? .contract-1 panic-after-write 30 30))
+
+(define-public (recover-from-panic)
+  (begin
+    (asserts! (is-err (try-contract-call? .contract-1 panic-after-write 40 40)) (err 2))
+    (contract-call? .contract-1 modify-data 41 41)))
+
+(define-public (panic-in-args)
+  (try-contract-call? .contract-1 modify-data (/ 50 (- 50 50)) 50))
+";
+    let mut env = owned_env.get_exec_environment(None);
+
+    let contract_identifier = QualifiedContractIdentifier::local("contract-1").unwrap();
+    env.initialize_contract(contract_identifier, contract_1)
+        .unwrap();
+
+    let contract_identifier = QualifiedContractIdentifier::local("contract-2").unwrap();
+    env.initialize_contract(contract_identifier, contract_2)
+        .unwrap();
+
+    env.sender = Some(get_principal_as_principal_data());
+
+    assert_eq!(
+        env.execute_contract(
+            &QualifiedContractIdentifier::local("contract-2").unwrap(),
+            "call-ok",
+            &symbols_from_values(vec![]),
+            false
+        )
+        .unwrap(),
+        Value::okay(Value::Int(1)).unwrap()
+    );
+
+    assert_eq!(
+        env.execute_contract(
+            &QualifiedContractIdentifier::local("contract-2").unwrap(),
+            "call-err",
+            &symbols_from_values(vec![]),
+            false
+        )
+        .unwrap(),
+        Value::error(Value::some(Value::Int(1)).unwrap()).unwrap()
+    );
+
+    assert_eq!(
+        env.execute_contract(
+            &QualifiedContractIdentifier::local("contract-2").unwrap(),
+            "call-panic",
+            &symbols_from_values(vec![]),
+            false
+        )
+        .unwrap(),
+        Value::error(Value::none()).unwrap()
+    );
+
+    assert_eq!(
+        env.execute_contract(
+            &QualifiedContractIdentifier::local("contract-2").unwrap(),
+            "recover-from-panic",
+            &symbols_from_values(vec![]),
+            false
+        )
+        .unwrap(),
+        Value::okay(Value::Int(1)).unwrap()
+    );
+
+    // a runtime error in the caller's own argument evaluation still aborts
+    assert!(matches!(
+        env.execute_contract(
+            &QualifiedContractIdentifier::local("contract-2").unwrap(),
+            "panic-in-args",
+            &symbols_from_values(vec![]),
+            false
+        ),
+        Err(Error::Runtime(RuntimeErrorType::DivisionByZero, _))
+    ));
+
+    for (id, expected) in [(10, 10), (20, 0), (30, 0), (40, 0), (41, 41)].iter() {
+        assert_eq!(
+            env.eval_read_only(
+                &QualifiedContractIdentifier::local("contract-1").unwrap(),
+                &format!("(get-data {})", id)
+            )
+            .unwrap(),
+            Value::Int(*expected)
+        );
+    }
+}
+
 fn test_factorial_contract(owned_env: &mut OwnedEnvironment) {
     let mut env = owned_env.get_exec_environment(None);
 
@@ -930,6 +1057,7 @@ fn test_all() {
     let to_test = [
         test_factorial_contract,
         test_aborts,
+        test_try_contract_call_aborts,
         test_contract_caller,
         test_fully_qualified_contract_call,
         test_simple_naming_system,
@@ -15,7 +15,7 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::vm::contexts::OwnedEnvironment;
-use crate::vm::database::MemoryBackingStore;
+use crate::vm::database::{ClarityBackingStore, MemoryBackingStore};
 use crate::vm::errors::{CheckErrors, Error, RuntimeErrorType, ShortReturnType};
 use crate::vm::execute;
 use crate::vm::types::{
@@ -23,6 +23,8 @@ use crate::vm::types::{
     TupleTypeSignature, TypeSignature, Value,
 };
 use crate::vm::ClarityName;
+use rusqlite::NO_PARAMS;
+use stacks_common::types::StacksEpochId;
 use std::convert::From;
 use std::convert::TryFrom;
 
@@ -243,6 +245,147 @@ fn test_set_bool_variable() {
     assert_executes(expected, &contract_src);
 }
 
+#[test]
+fn test_var_exists_and_map_size() {
+    let contract_src = r#"
+        (define-data-var cursor int 0)
+        (define-map entries int int)
+        (define-private (size-after-insert (key int))
+            (begin
+                (map-insert entries key 1)
+                (map-size entries)))
+        (define-private (size-after-set (key int))
+            (begin
+                (map-set entries key 2)
+                (map-size entries)))
+        (define-private (size-after-delete (key int))
+            (begin
+                (map-delete entries key)
+                (map-size entries)))
+    "#;
+
+    let mut contract_src = contract_src.to_string();
+    contract_src.push_str(
+        "(list (var-exists? cursor) (is-eq (map-size entries) u0)
+               (is-eq (size-after-insert 1) u1) (is-eq (size-after-insert 1) u1)
+               (is-eq (size-after-set 1) u1) (is-eq (size-after-set 2) u2)
+               (is-eq (size-after-delete 3) u2) (is-eq (size-after-delete 1) u1))",
+    );
+    let expected = Value::list_from(vec![Value::Bool(true); 8]);
+    assert_executes(expected, &contract_src);
+}
+
+#[test]
+fn test_map_size_only_tracked_for_clarity_2() {
+    let contract_src = r#"
+        (define-map entries int int)
+        (map-insert entries 1 10)
+        (map-insert entries 2 20)
+        (map-delete entries 1)
+        (map-set entries 2 21)
+    "#;
+    let contract_identifier = QualifiedContractIdentifier::local("map-owner").unwrap();
+
+    for (epoch, expected_size) in [
+        (StacksEpochId::Epoch2_05, None),
+        (StacksEpochId::EpochSubnet1, Some(1)),
+    ] {
+        let mut marf = MemoryBackingStore::new();
+        {
+            let mut owned_env = OwnedEnvironment::new_free(false, marf.as_clarity_db(), epoch);
+            owned_env
+                .initialize_contract(contract_identifier.clone(), contract_src)
+                .unwrap();
+        }
+
+        let mut db = marf.as_clarity_db();
+        db.begin();
+        assert_eq!(
+            db.get_map_size(&contract_identifier, "entries").ok(),
+            expected_size
+        );
+        assert_eq!(
+            db.load_map(&contract_identifier, "entries")
+                .unwrap()
+                .track_size,
+            expected_size.is_some()
+        );
+        // untracked maps still hold their entries, overwritten without being looked up first
+        assert_eq!(
+            db.list_data_map_entries(&contract_identifier, "entries", None, 10)
+                .unwrap(),
+            Some(vec![(Value::Int(2), Value::Int(21))])
+        );
+        db.roll_back();
+    }
+}
+
+#[test]
+fn test_list_data_map_entries() {
+    let contract_src = r#"
+        (define-map entries int int)
+        (define-map entries-2 int int)
+        (map-insert entries 3 30)
+        (map-insert entries 1 10)
+        (map-insert entries 2 20)
+        (map-insert entries 4 40)
+        (map-delete entries 2)
+        (map-insert entries-2 5 50)
+    "#;
+    let contract_identifier = QualifiedContractIdentifier::local("map-owner").unwrap();
+
+    let mut marf = MemoryBackingStore::new();
+    {
+        let mut owned_env = OwnedEnvironment::new(marf.as_clarity_db());
+        owned_env
+            .initialize_contract(contract_identifier.clone(), contract_src)
+            .unwrap();
+    }
+
+    let mut db = marf.as_clarity_db();
+    db.begin();
+    // entries are listed in the order of their serialized keys, and deleted entries and other
+    //  maps' entries are left out
+    let first_page = db
+        .list_data_map_entries(&contract_identifier, "entries", None, 2)
+        .unwrap();
+    assert_eq!(
+        first_page,
+        Some(vec![
+            (Value::Int(1), Value::Int(10)),
+            (Value::Int(3), Value::Int(30))
+        ])
+    );
+    let second_page = db
+        .list_data_map_entries(&contract_identifier, "entries", Some(&Value::Int(3)), 2)
+        .unwrap();
+    assert_eq!(second_page, Some(vec![(Value::Int(4), Value::Int(40))]));
+    let other_map = db
+        .list_data_map_entries(&contract_identifier, "entries-2", None, 10)
+        .unwrap();
+    assert_eq!(other_map, Some(vec![(Value::Int(5), Value::Int(50))]));
+    assert!(db
+        .list_data_map_entries(&contract_identifier, "no-such-map", None, 10)
+        .is_err());
+    db.roll_back();
+
+    // maps defined before the store recorded map keys are not listed
+    marf.get_side_store()
+        .execute(
+            "UPDATE metadata_table SET value = replace(value, ',\"keys_indexed\":true', '')",
+            NO_PARAMS,
+        )
+        .unwrap();
+    let mut db = marf.as_clarity_db();
+    db.begin();
+    assert_eq!(
+        db.list_data_map_entries(&contract_identifier, "entries", None, 10)
+            .unwrap(),
+        None
+    );
+    db.roll_back();
+}
+
 #[test]
 fn test_set_tuple_variable() {
     let contract_src = r#"
@@ -16,9 +16,12 @@
 
 use crate::vm::ast::build_ast;
 use crate::vm::ast::errors::ParseErrors;
+use crate::vm::contexts::OwnedEnvironment;
+use crate::vm::database::MemoryBackingStore;
 use crate::vm::errors::{CheckErrors, Error, RuntimeErrorType};
 use crate::vm::execute;
-use crate::vm::types::{QualifiedContractIdentifier, TypeSignature, Value};
+use crate::vm::types::{PrincipalData, QualifiedContractIdentifier, TypeSignature, Value};
+use stacks_common::types::StacksEpochId;
 
 fn assert_eq_err(e1: CheckErrors, e2: Error) {
     let e1: Error = e1.into();
@@ -266,3 +269,55 @@ fn test_define_parse_panic_2() {
         execute(&tests).unwrap_err(),
     );
 }
+
+#[test]
+fn test_names_reserved_by_later_versions() {
+    let contract = "(define-private (map-size (a uint)) (+ a u1))
+         (define-constant l1-block-height u5)
+         (define-read-only (get-size) (map-size l1-block-height))";
+    let contract_id = QualifiedContractIdentifier::local("old-contract").unwrap();
+    let sender: PrincipalData = contract_id.issuer.clone().into();
+    let mut marf = MemoryBackingStore::new();
+
+    // `map-size` and `l1-block-height` are only reserved from the subnet-1 epoch on
+    let mut owned_env =
+        OwnedEnvironment::new_free(false, marf.as_clarity_db(), StacksEpochId::Epoch2_05);
+    owned_env
+        .initialize_contract(contract_id.clone(), contract)
+        .unwrap();
+
+    // a deployed contract keeps its version, so it can still be called in later epochs
+    let mut owned_env =
+        OwnedEnvironment::new_free(false, marf.as_clarity_db(), StacksEpochId::EpochSubnet1);
+    let (result, _, _) = owned_env
+        .execute_transaction(sender, contract_id, "get-size", &[])
+        .unwrap();
+    assert_eq!(result, Value::UInt(6));
+
+    let new_contract_id = QualifiedContractIdentifier::local("new-contract").unwrap();
+    assert_eq_err(
+        CheckErrors::NameAlreadyUsed("map-size".to_string()),
+        owned_env
+            .initialize_contract(new_contract_id, contract)
+            .unwrap_err(),
+    );
+
+    // natives introduced in the subnet-1 epoch are not defined for older contracts
+    let contract = "(define-map entries uint uint)
+         (define-read-only (size) (map-size entries))
+         (size)";
+    let contract_id = QualifiedContractIdentifier::local("uses-map-size").unwrap();
+    let mut owned_env =
+        OwnedEnvironment::new_free(false, marf.as_clarity_db(), StacksEpochId::Epoch2_05);
+    assert_eq_err(
+        CheckErrors::UndefinedFunction("map-size".to_string()),
+        owned_env
+            .initialize_contract(contract_id.clone(), contract)
+            .unwrap_err(),
+    );
+    let mut owned_env =
+        OwnedEnvironment::new_free(false, marf.as_clarity_db(), StacksEpochId::EpochSubnet1);
+    owned_env
+        .initialize_contract(contract_id, contract)
+        .unwrap();
+}
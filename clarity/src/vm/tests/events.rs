@@ -16,6 +16,7 @@
 
 use crate::vm::contexts::OwnedEnvironment;
 use crate::vm::costs::ExecutionCost;
+use crate::vm::errors::{Error, RuntimeErrorType};
 use crate::vm::events::*;
 use crate::vm::tests::execute;
 use crate::vm::types::{AssetIdentifier, BuffData, QualifiedContractIdentifier, Value};
@@ -26,6 +27,14 @@ use crate::vm::database::MemoryBackingStore;
 use crate::vm::tests::{TEST_BURN_STATE_DB, TEST_HEADER_DB};
 
 fn helper_execute(contract: &str, method: &str) -> (Value, Vec<StacksTransactionEvent>) {
+    helper_execute_capped(contract, method, None).unwrap()
+}
+
+fn helper_execute_capped(
+    contract: &str,
+    method: &str,
+    max_event_bytes: Option<u64>,
+) -> Result<(Value, Vec<StacksTransactionEvent>), Error> {
     let contract_id = QualifiedContractIdentifier::local("contract").unwrap();
     let address = "'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR";
     let sender = execute(address).expect_principal();
@@ -40,11 +49,10 @@ fn helper_execute(contract: &str, method: &str) -> (Value, Vec<StacksTransaction
     }
 
     owned_env.stx_faucet(&sender, 10);
+    owned_env.set_max_event_bytes(max_event_bytes);
 
-    let (value, _, events) = owned_env
-        .execute_transaction(sender, contract_id, method, &vec![])
-        .unwrap();
-    (value, events)
+    let (value, _, events) = owned_env.execute_transaction(sender, contract_id, method, &vec![])?;
+    Ok((value, events))
 }
 
 #[test]
@@ -79,6 +87,51 @@ fn test_emit_print_nok() {
     assert_eq!(events.len(), 0);
 }
 
+#[test]
+fn test_emit_print_with_topic() {
+    let contract = "(define-public (emit-event-ok)
+            (begin
+                (print \"Hello world\" \"greeting\")
+                (ok u1)))";
+
+    let (value, mut events) = helper_execute(contract, "emit-event-ok");
+    assert_eq!(value, Value::okay(Value::UInt(1)).unwrap());
+    assert_eq!(events.len(), 1);
+    match events.pop() {
+        Some(StacksTransactionEvent::SmartContractEvent(data)) => {
+            let contract_id = QualifiedContractIdentifier::local("contract").unwrap();
+            assert_eq!(data.key, (contract_id, "greeting".to_string()));
+            assert_eq!(data.value, execute("\"Hello world\""));
+        }
+        _ => panic!("assertion failed"),
+    };
+}
+
+#[test]
+fn test_emit_over_event_size_cap() {
+    let contract = "(define-public (emit-event-ok)
+            (begin
+                (print \"Hello world\")
+                (print \"Hello again\")
+                (ok u1)))";
+
+    let (_, events) = helper_execute(contract, "emit-event-ok");
+    let total_size: u64 = events.iter().map(|event| event.payload_size()).sum();
+
+    let (value, events) =
+        helper_execute_capped(contract, "emit-event-ok", Some(total_size)).unwrap();
+    assert_eq!(value, Value::okay(Value::UInt(1)).unwrap());
+    assert_eq!(events.len(), 2);
+
+    // the cap counts all of the transaction's events, not just the largest one
+    match helper_execute_capped(contract, "emit-event-ok", Some(total_size - 1)) {
+        Err(Error::Runtime(RuntimeErrorType::EventSizeLimitExceeded(max), _)) => {
+            assert_eq!(max, total_size - 1)
+        }
+        other => panic!("expected the event size cap to be hit, got {:?}", other),
+    }
+}
+
 #[test]
 fn test_emit_stx_transfer_ok() {
     let contract = "(define-constant sender 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)
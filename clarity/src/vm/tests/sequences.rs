@@ -16,7 +16,7 @@
 
 use crate::vm::types::signatures::{ListTypeData, SequenceSubtype};
 use crate::vm::types::TypeSignature::{BoolType, IntType, SequenceType, UIntType};
-use crate::vm::types::{TypeSignature, Value};
+use crate::vm::types::{TypeSignature, Value, BUFF_1};
 
 use crate::vm::analysis::errors::CheckError;
 use crate::vm::errors::{CheckErrors, Error, RuntimeErrorType};
@@ -174,6 +174,115 @@ fn test_element_at() {
     }
 }
 
+#[test]
+fn test_slice() {
+    let good = [
+        "(slice (list 1 2 3 4 5) u1 u3)",
+        "(slice \"blockstack\" u5 u10)",
+        "(slice 0x00010203 u2 u4)",
+        "(slice u\"abcd\" u0 u1)",
+        "(slice \"abcd\" u2 u2)",
+        "(slice (list 1 2 3 4 5) u3 u6)",
+        "(slice 0x00010203 u3 u2)",
+        "(slice \"abcd\" u100 u101)",
+        "(is-eq (slice (list 1 2 3) u0 u2) (some (list 1 2)))",
+    ];
+
+    let expected = [
+        "(some (2 3))",
+        "(some \"stack\")",
+        "(some 0x0203)",
+        "(some u\"a\")",
+        "(some \"\")",
+        "none",
+        "none",
+        "none",
+        "true",
+        "none",
+        "(some ((some 1)))",
+        "(some ((1)))",
+    ];
+
+    for (good_test, expected) in good.iter().zip(expected.iter()) {
+        assert_eq!(
+            expected,
+            &format!("{}", execute(&good_test).unwrap().unwrap())
+        );
+    }
+
+    let bad = ["(slice 3 u1 u2)", "(slice (list 1 2 3) u1 2)"];
+
+    let bad_expected = [
+        CheckErrors::ExpectedSequence(TypeSignature::IntType),
+        CheckErrors::TypeValueError(TypeSignature::UIntType, Value::Int(2)),
+    ];
+
+    for (bad_test, expected) in bad.iter().zip(bad_expected.iter()) {
+        match execute(&bad_test).unwrap_err() {
+            Error::Unchecked(check_error) => {
+                assert_eq!(&check_error, expected);
+            }
+            _ => unreachable!("Should have raised unchecked errors"),
+        }
+    }
+}
+
+#[test]
+fn test_replace_at() {
+    let good = [
+        "(replace-at? (list 1 2 3 4) u1 5)",
+        "(replace-at? \"abcd\" u0 \"z\")",
+        "(replace-at? 0x00010203 u3 0xff)",
+        "(replace-at? u\"abcd\" u3 u\"\\u{1F98A}\")",
+        "(replace-at? (list 1 2 3) u3 4)",
+        "(replace-at? \"abcd\" u340282366920938463463374607431768211455 \"z\")",
+        "(is-eq (replace-at? (list (some 1) none) u1 (some 2)) (some (list (some 1) (some 2))))",
+        "(replace-at? (list) u0 1)",
+        "(replace-at? (list none) u0 (some 1))",
+        "(replace-at? (list (list)) u0 (list 1))",
+    ];
+
+    let expected = [
+        "(some (1 5 3 4))",
+        "(some \"zbcd\")",
+        "(some 0x000102ff)",
+        "(some u\"abc\\u{f09fa68a}\")",
+        "none",
+        "none",
+        "true",
+    ];
+
+    for (good_test, expected) in good.iter().zip(expected.iter()) {
+        assert_eq!(
+            expected,
+            &format!("{}", execute(&good_test).unwrap().unwrap())
+        );
+    }
+
+    let bad = [
+        "(replace-at? 3 u1 2)",
+        "(replace-at? (list 1 2 3) 1 2)",
+        "(replace-at? (list 1 2 3) u1 u2)",
+        "(replace-at? 0x0102 u1 0x0304)",
+    ];
+
+    let bad_expected = [
+        CheckErrors::ExpectedSequence(TypeSignature::IntType),
+        CheckErrors::TypeValueError(TypeSignature::UIntType, Value::Int(1)),
+        CheckErrors::TypeValueError(TypeSignature::IntType, Value::UInt(2)),
+        CheckErrors::TypeValueError(BUFF_1.clone(), Value::buff_from(vec![3, 4]).unwrap()),
+    ];
+
+    for (bad_test, expected) in bad.iter().zip(bad_expected.iter()) {
+        match execute(&bad_test).unwrap_err() {
+            Error::Unchecked(check_error) => {
+                assert_eq!(&check_error, expected);
+            }
+            _ => unreachable!("Should have raised unchecked errors"),
+        }
+    }
+}
+
 #[test]
 fn test_string_ascii_admission() {
     let defines = "(define-private (set-name (x (string-ascii 11))) x)";
@@ -684,12 +793,12 @@ fn test_simple_folds_string() {
     let tests =
         ["(define-private (get-len (x (string-ascii 1)) (acc int)) (+ acc 1))
          (fold get-len \"blockstack\" 0)",
-        "(define-private (slice (x (string-ascii 1)) (acc (tuple (limit uint) (cursor uint) (data (string-ascii 10)))))
+        "(define-private (slice-step (x (string-ascii 1)) (acc (tuple (limit uint) (cursor uint) (data (string-ascii 10)))))
             (if (< (get cursor acc) (get limit acc))
                 (let ((data (default-to (get data acc) (as-max-len? (concat (get data acc) x) u10))))
                     (tuple (limit (get limit acc)) (cursor (+ u1 (get cursor acc))) (data data))) 
                 acc))
-        (get data (fold slice \"0123456789\" (tuple (limit u5) (cursor u0) (data \"\"))))"];
+        (get data (fold slice-step \"0123456789\" (tuple (limit u5) (cursor u0) (data \"\"))))"];
 
     let expected = [
         Value::Int(10),
@@ -26,7 +26,9 @@ use crate::vm::types::signatures::*;
 use crate::vm::types::{BuffData, QualifiedContractIdentifier, TypeSignature};
 use crate::vm::types::{PrincipalData, ResponseData, SequenceData, SequenceSubtype};
 use crate::vm::{eval, execute as vm_execute};
-use crate::vm::{CallStack, ContractContext, Environment, GlobalContext, LocalContext, Value};
+use crate::vm::{
+    CallStack, ClarityVersion, ContractContext, Environment, GlobalContext, LocalContext, Value,
+};
 use stacks_common::address::c32;
 use stacks_common::address::AddressHashMode;
 use stacks_common::address::C32_ADDRESS_VERSION_MAINNET_SINGLESIG;
@@ -90,6 +92,22 @@ fn test_simple_let() {
     }
 }
 
+#[test]
+fn test_l1_block_height() {
+    let mut marf = MemoryBackingStore::new();
+    {
+        let mut db = marf.as_clarity_db();
+        db.begin();
+        db.set_current_l1_block_height(1234);
+        db.commit();
+    }
+    let mut env = OwnedEnvironment::new(marf.as_clarity_db());
+
+    // the height is read from the Clarity DB, so it must be evaluated in a nested context
+    let (value, _, _) = env.eval_raw("l1-block-height").unwrap();
+    assert_eq!(Value::UInt(1234), value);
+}
+
 #[test]
 fn test_sha256() {
     let sha256_evals = [
@@ -383,7 +401,10 @@ fn test_simple_if_functions() {
         );
 
         let context = LocalContext::new();
-        let mut contract_context = ContractContext::new(QualifiedContractIdentifier::transient());
+        let mut contract_context = ContractContext::new(
+            QualifiedContractIdentifier::transient(),
+            ClarityVersion::latest(),
+        );
         let mut marf = MemoryBackingStore::new();
         let mut global_context = GlobalContext::new(
             false,
@@ -636,6 +657,178 @@ fn test_unsigned_arithmetic() {
     }
 }
 
+#[test]
+fn test_buff_to_uint() {
+    let tests = [
+        "(buff-to-uint-be 0x0102)",
+        "(buff-to-uint-le 0x0102)",
+        "(buff-to-uint-be 0x)",
+        "(buff-to-uint-le 0x)",
+        "(buff-to-uint-be 0x00000000000000000000000000000001)",
+        "(buff-to-uint-le 0x00000000000000000000000000000001)",
+        "(buff-to-uint-be 0xffffffffffffffffffffffffffffffff)",
+        "(buff-to-uint-le 0xffffffffffffffffffffffffffffffff)",
+    ];
+
+    let expectations = [
+        Value::UInt(258),
+        Value::UInt(513),
+        Value::UInt(0),
+        Value::UInt(0),
+        Value::UInt(1),
+        Value::UInt(1 << 120),
+        Value::UInt(u128::MAX),
+        Value::UInt(u128::MAX),
+    ];
+
+    for (program, expectation) in tests.iter().zip(expectations.iter()) {
+        assert_eq!(expectation.clone(), execute(program));
+    }
+
+    let too_long = Value::buff_from(vec![0; 17]).unwrap();
+    assert_eq!(
+        vm_execute("(buff-to-uint-be 0x0000000000000000000000000000000000)").unwrap_err(),
+        CheckErrors::TypeValueError(BUFF_16.clone(), too_long.clone()).into()
+    );
+    assert_eq!(
+        vm_execute("(buff-to-uint-le 0x0000000000000000000000000000000000)").unwrap_err(),
+        CheckErrors::TypeValueError(BUFF_16.clone(), too_long).into()
+    );
+}
+
+#[test]
+fn test_bitwise() {
+    let tests = [
+        "(bit-and 24 16)",
+        "(bit-and 28 24 -1)",
+        "(bit-and u24 u16)",
+        "(bit-and -128 -64)",
+        "(bit-or 4 8)",
+        "(bit-or 1 2 4)",
+        "(bit-or u4 u8)",
+        "(bit-or -1 0)",
+        "(bit-not 3)",
+        "(bit-not u128)",
+        "(bit-not -1)",
+        "(bit-shift-left 2 u1)",
+        "(bit-shift-left -64 u1)",
+        "(bit-shift-left u1 u127)",
+        "(bit-shift-left 1 u127)",
+        "(bit-shift-left 1 u128)",
+        "(bit-shift-left u3 u130)",
+        "(bit-shift-right 128 u2)",
+        "(bit-shift-right -64 u1)",
+        "(bit-shift-right -1 u127)",
+        "(bit-shift-right u340282366920938463463374607431768211455 u127)",
+        "(bit-shift-right 8 u129)",
+    ];
+
+    let expectations = [
+        Value::Int(16),
+        Value::Int(24),
+        Value::UInt(16),
+        Value::Int(-128),
+        Value::Int(12),
+        Value::Int(7),
+        Value::UInt(12),
+        Value::Int(-1),
+        Value::Int(-4),
+        Value::UInt(u128::MAX - 128),
+        Value::Int(0),
+        Value::Int(4),
+        Value::Int(-128),
+        Value::UInt(1 << 127),
+        Value::Int(i128::MIN),
+        Value::Int(1),
+        Value::UInt(12),
+        Value::Int(32),
+        Value::Int(-32),
+        Value::Int(-1),
+        Value::UInt(1),
+        Value::Int(4),
+    ];
+
+    for (program, expectation) in tests.iter().zip(expectations.iter()) {
+        assert_eq!(expectation.clone(), execute(program));
+    }
+
+    let bad = [
+        "(bit-and 1 u2)",
+        "(bit-or u1 2)",
+        "(bit-not true)",
+        "(bit-shift-left 1 2)",
+        "(bit-shift-right true u1)",
+    ];
+
+    let bad_expectations: &[Error] = &[
+        CheckErrors::TypeValueError(TypeSignature::IntType, Value::UInt(2)).into(),
+        CheckErrors::TypeValueError(TypeSignature::UIntType, Value::Int(2)).into(),
+        CheckErrors::UnionTypeValueError(
+            vec![TypeSignature::IntType, TypeSignature::UIntType],
+            Value::Bool(true),
+        )
+        .into(),
+        CheckErrors::TypeValueError(TypeSignature::UIntType, Value::Int(2)).into(),
+        CheckErrors::UnionTypeValueError(
+            vec![TypeSignature::IntType, TypeSignature::UIntType],
+            Value::Bool(true),
+        )
+        .into(),
+    ];
+
+    for (program, expectation) in bad.iter().zip(bad_expectations.iter()) {
+        assert_eq!(*expectation, vm_execute(program).unwrap_err());
+    }
+}
+
+#[test]
+fn test_consensus_buff_conversions() {
+    let tests = [
+        "(to-consensus-buff? 1)",
+        "(to-consensus-buff? u1)",
+        "(to-consensus-buff? (tuple (abc 3)))",
+        "(from-consensus-buff? int 0x0000000000000000000000000000000001)",
+        "(from-consensus-buff? uint 0x0000000000000000000000000000000001)",
+        "(from-consensus-buff? bool 0x0303)",
+        "(from-consensus-buff? bool 0x)",
+        "(from-consensus-buff? (string-ascii 2) (unwrap-panic (to-consensus-buff? \"abc\")))",
+        "(from-consensus-buff? (list 3 (optional uint)) (unwrap-panic (to-consensus-buff? (list (some u1) none))))",
+        "(from-consensus-buff? (response int bool) (unwrap-panic (to-consensus-buff? (if true (err false) (ok 1)))))",
+    ];
+
+    let expectations = [
+        Value::some(
+            Value::buff_from(hex_bytes("0000000000000000000000000000000001").unwrap()).unwrap(),
+        )
+        .unwrap(),
+        Value::some(
+            Value::buff_from(hex_bytes("0100000000000000000000000000000001").unwrap()).unwrap(),
+        )
+        .unwrap(),
+        Value::some(
+            Value::buff_from(
+                hex_bytes("0c00000001036162630000000000000000000000000000000003").unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap(),
+        Value::some(Value::Int(1)).unwrap(),
+        Value::none(),
+        Value::none(),
+        Value::none(),
+        Value::none(),
+        Value::some(
+            Value::list_from(vec![Value::some(Value::UInt(1)).unwrap(), Value::none()]).unwrap(),
+        )
+        .unwrap(),
+        Value::some(Value::error(Value::Bool(false)).unwrap()).unwrap(),
+    ];
+
+    for (program, expectation) in tests.iter().zip(expectations.iter()) {
+        assert_eq!(expectation.clone(), execute(program));
+    }
+}
+
 #[test]
 fn test_options_errors() {
     let tests = [
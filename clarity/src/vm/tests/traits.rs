@@ -50,6 +50,7 @@ fn test_all() {
         test_return_trait_with_contract_of,
         test_return_trait_with_contract_of_wrapped_in_begin,
         test_return_trait_with_contract_of_wrapped_in_let,
+        test_dynamic_dispatch_withdraw_through_trait,
     ];
     for test in to_test.iter() {
         with_memory_environment(test, false);
@@ -1103,3 +1104,77 @@ fn test_return_trait_with_contract_of(owned_env: &mut OwnedEnvironment) {
         );
     }
 }
+
+fn test_dynamic_dispatch_withdraw_through_trait(owned_env: &mut OwnedEnvironment) {
+    let wrapper_contract = "(define-trait withdrawable-trait (
+            (withdraw (uint principal) (response bool uint))))
+        (define-public (withdraw-from (token <withdrawable-trait>) (amount-or-id uint))
+            (contract-call? token withdraw amount-or-id tx-sender))";
+    let ft_contract = "(define-fungible-token ft-token)
+        (define-public (mint (amount uint))
+            (ft-mint? ft-token amount tx-sender))
+        (define-public (withdraw (amount uint) (sender principal))
+            (begin
+                (asserts! (is-eq tx-sender sender) (err u1001))
+                (ft-withdraw? ft-token amount sender)))";
+    let nft_contract = "(define-non-fungible-token nft-token uint)
+        (define-public (mint (id uint))
+            (nft-mint? nft-token id tx-sender))
+        (define-public (withdraw (id uint) (sender principal))
+            (begin
+                (asserts! (is-eq tx-sender sender) (err u1001))
+                (nft-withdraw? nft-token id sender)))";
+
+    let p1 = execute("'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR");
+    let wrapper_id = QualifiedContractIdentifier::local("wrapper").unwrap();
+    let ft_id = QualifiedContractIdentifier::local("ft-contract").unwrap();
+    let nft_id = QualifiedContractIdentifier::local("nft-contract").unwrap();
+
+    {
+        let mut env = owned_env.get_exec_environment(None);
+        env.initialize_contract(wrapper_id.clone(), wrapper_contract)
+            .unwrap();
+        env.initialize_contract(ft_id.clone(), ft_contract).unwrap();
+        env.initialize_contract(nft_id.clone(), nft_contract)
+            .unwrap();
+    }
+
+    {
+        let mut env = owned_env.get_exec_environment(Some(p1.clone().expect_principal()));
+        for (token_id, amount_or_id) in [(&ft_id, 10), (&nft_id, 7)].iter() {
+            assert_eq!(
+                env.execute_contract(
+                    token_id,
+                    "mint",
+                    &symbols_from_values(vec![Value::UInt(*amount_or_id)]),
+                    false
+                )
+                .unwrap(),
+                Value::okay_true()
+            );
+        }
+    }
+
+    let tests = [
+        (&ft_id, 4, Value::okay_true()),
+        // only 6 tokens are left
+        (&ft_id, 7, Value::error(Value::UInt(1)).unwrap()),
+        (&nft_id, 7, Value::okay_true()),
+        // the NFT no longer exists
+        (&nft_id, 7, Value::error(Value::UInt(3)).unwrap()),
+    ];
+    for (token_id, amount_or_id, expected) in tests.iter() {
+        let token = Value::from(PrincipalData::Contract((*token_id).clone()));
+        let mut env = owned_env.get_exec_environment(Some(p1.clone().expect_principal()));
+        assert_eq!(
+            &env.execute_contract(
+                &wrapper_id,
+                "withdraw-from",
+                &symbols_from_values(vec![token, Value::UInt(*amount_or_id)]),
+                false
+            )
+            .unwrap(),
+            expected
+        );
+    }
+}
@@ -39,8 +39,8 @@ use crate::vm::representations::{
 pub use crate::vm::types::signatures::{
     parse_name_type_pairs, AssetIdentifier, BufferLength, FixedFunction, FunctionArg,
     FunctionSignature, FunctionType, ListTypeData, SequenceSubtype, StringSubtype,
-    StringUTF8Length, TupleTypeSignature, TypeSignature, BUFF_1, BUFF_20, BUFF_32, BUFF_33,
-    BUFF_64, BUFF_65,
+    StringUTF8Length, TupleTypeSignature, TypeSignature, BUFF_1, BUFF_16, BUFF_20, BUFF_32,
+    BUFF_33, BUFF_64, BUFF_65, PRINT_TOPIC,
 };
 
 pub const MAX_VALUE_SIZE: u32 = 1024 * 1024; // 1MB
@@ -48,6 +48,8 @@ pub const BOUND_VALUE_SERIALIZATION_BYTES: u32 = MAX_VALUE_SIZE * 2;
 pub const BOUND_VALUE_SERIALIZATION_HEX: u32 = BOUND_VALUE_SERIALIZATION_BYTES * 2;
 
 pub const MAX_TYPE_DEPTH: u8 = 32;
+// the longest topic that a `print` event may be emitted under
+pub const MAX_PRINT_TOPIC_LEN: u32 = 64;
 // this is the charged size for wrapped values, i.e., response or optionals
 pub const WRAPPER_VALUE_SIZE: u32 = 1;
 
@@ -280,6 +282,115 @@ impl SequenceData {
         Some(result)
     }
 
+    /// Get the elements from `start` (inclusive) to `end` (exclusive), or `None` if the range is
+    /// not within the sequence.
+    pub fn slice(self, start: usize, end: usize) -> Option<SequenceData> {
+        if start > end || end > self.len() {
+            return None;
+        }
+        let result = match self {
+            SequenceData::Buffer(data) => SequenceData::Buffer(BuffData {
+                data: data.data[start..end].to_vec(),
+            }),
+            SequenceData::List(mut data) => {
+                data.data.truncate(end);
+                data.data.drain(..start);
+                data.type_signature.reduce_max_len((end - start) as u32);
+                SequenceData::List(data)
+            }
+            SequenceData::String(CharType::ASCII(data)) => {
+                SequenceData::String(CharType::ASCII(ASCIIData {
+                    data: data.data[start..end].to_vec(),
+                }))
+            }
+            SequenceData::String(CharType::UTF8(data)) => {
+                SequenceData::String(CharType::UTF8(UTF8Data {
+                    data: data.data[start..end].to_vec(),
+                }))
+            }
+        };
+
+        Some(result)
+    }
+
+    /// Replace the element at `index` with `element`, or return `None` if `index` is not within
+    /// the sequence.  `element` must be a single element of the sequence: a list item, or a
+    /// buffer or string of length 1.  A list is retyped with the least supertype of its items
+    /// and `element`.
+    pub fn replace_at(self, index: usize, element: Value) -> Result<Option<SequenceData>> {
+        let element_type = self.element_type();
+        let result = match (self, element) {
+            (SequenceData::List(mut data), element) => {
+                let entry_type = match TypeSignature::least_supertype(
+                    &element_type,
+                    &TypeSignature::type_of(&element),
+                ) {
+                    Ok(entry_type) => entry_type,
+                    Err(_) => return Err(CheckErrors::TypeValueError(element_type, element).into()),
+                };
+                if index >= data.data.len() {
+                    return Ok(None);
+                }
+                data.type_signature =
+                    ListTypeData::new_list(entry_type, data.type_signature.get_max_len())?;
+                data.data[index] = element;
+                SequenceData::List(data)
+            }
+            (SequenceData::Buffer(mut data), Value::Sequence(SequenceData::Buffer(byte)))
+                if byte.data.len() == 1 =>
+            {
+                if index >= data.data.len() {
+                    return Ok(None);
+                }
+                data.data[index] = byte.data[0];
+                SequenceData::Buffer(data)
+            }
+            (
+                SequenceData::String(CharType::ASCII(mut data)),
+                Value::Sequence(SequenceData::String(CharType::ASCII(character))),
+            ) if character.data.len() == 1 => {
+                if index >= data.data.len() {
+                    return Ok(None);
+                }
+                data.data[index] = character.data[0];
+                SequenceData::String(CharType::ASCII(data))
+            }
+            (
+                SequenceData::String(CharType::UTF8(mut data)),
+                Value::Sequence(SequenceData::String(CharType::UTF8(mut character))),
+            ) if character.data.len() == 1 => {
+                if index >= data.data.len() {
+                    return Ok(None);
+                }
+                data.data[index] = character.data.remove(0);
+                SequenceData::String(CharType::UTF8(data))
+            }
+            (_, element) => {
+                return Err(CheckErrors::TypeValueError(element_type, element).into());
+            }
+        };
+
+        Ok(Some(result))
+    }
+
+    /// The type of a single element of this sequence
+    fn element_type(&self) -> TypeSignature {
+        match self {
+            SequenceData::List(data) => data.type_signature.get_list_item_type().clone(),
+            SequenceData::Buffer(_) => BUFF_1.clone(),
+            SequenceData::String(CharType::ASCII(_)) => {
+                TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::ASCII(
+                    BufferLength::try_from(1u32).unwrap(),
+                )))
+            }
+            SequenceData::String(CharType::UTF8(_)) => {
+                TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::UTF8(
+                    StringUTF8Length::try_from(1u32).unwrap(),
+                )))
+            }
+        }
+    }
+
     pub fn contains(&self, to_find: Value) -> Result<Option<usize>> {
         match self {
             SequenceData::Buffer(ref data) => {
@@ -583,6 +694,17 @@ define_named_enum!(BlockInfoProperty {
     MinerAddress("miner-address"),
 });
 
+define_named_enum!(L1BlockInfoProperty {
+    HeaderHash("header-hash"),
+    Time("time"),
+});
+
+define_named_enum!(WithdrawalAssetType {
+    Stx("stx"),
+    FungibleToken("ft"),
+    NonFungibleToken("nft"),
+});
+
 impl OptionalData {
     pub fn type_signature(&self) -> TypeSignature {
         let type_result = match self.data {
@@ -620,6 +742,50 @@ impl BlockInfoProperty {
     }
 }
 
+impl L1BlockInfoProperty {
+    pub fn type_result(&self) -> TypeSignature {
+        use self::L1BlockInfoProperty::*;
+        match self {
+            HeaderHash => BUFF_32.clone(),
+            Time => TypeSignature::UIntType,
+        }
+    }
+}
+
+impl WithdrawalAssetType {
+    /// Types of the arguments that follow the asset type in `get-withdrawal-info?`
+    pub fn argument_types(&self) -> Vec<TypeSignature> {
+        use self::WithdrawalAssetType::*;
+        match self {
+            // block height, withdrawal id, sender, amount
+            Stx => vec![
+                TypeSignature::UIntType,
+                TypeSignature::UIntType,
+                TypeSignature::PrincipalType,
+                TypeSignature::UIntType,
+            ],
+            // block height, withdrawal id, sender, asset contract, amount or NFT id
+            FungibleToken | NonFungibleToken => vec![
+                TypeSignature::UIntType,
+                TypeSignature::UIntType,
+                TypeSignature::PrincipalType,
+                TypeSignature::PrincipalType,
+                TypeSignature::UIntType,
+            ],
+        }
+    }
+
+    /// Type of the value returned by `get-withdrawal-info?` if the withdrawal exists
+    pub fn type_result() -> TypeSignature {
+        TupleTypeSignature::try_from(vec![
+            ("withdrawal-root".into(), BUFF_32.clone()),
+            ("withdrawal-leaf-hash".into(), BUFF_32.clone()),
+        ])
+        .expect("FAIL: withdrawal info tuple type is invalid")
+        .into()
+    }
+}
+
 impl PartialEq for ListData {
     fn eq(&self, other: &ListData) -> bool {
         self.data == other.data
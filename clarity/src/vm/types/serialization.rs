@@ -15,6 +15,7 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::borrow::Borrow;
+use std::cmp;
 use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 use std::io::{Read, Write};
@@ -625,6 +626,21 @@ impl Value {
         Value::deserialize_read(&mut bytes.as_slice(), Some(expected))
     }
 
+    /// Like `try_deserialize_bytes`, but fails if `bytes` has any data left over after the value.
+    pub fn try_deserialize_bytes_exact(
+        bytes: &Vec<u8>,
+        expected: &TypeSignature,
+    ) -> Result<Value, SerializationError> {
+        let mut reader = bytes.as_slice();
+        let value = Value::deserialize_read(&mut reader, Some(expected))?;
+        if reader.len() > 0 {
+            return Err(SerializationError::DeserializationError(
+                "Trailing bytes after the serialized value".into(),
+            ));
+        }
+        Ok(value)
+    }
+
     pub fn try_deserialize_hex(
         hex: &str,
         expected: &TypeSignature,
@@ -660,6 +676,95 @@ impl Value {
     }
 }
 
+impl TypeSignature {
+    /// The length of the longest serialization of a value of this type.  This may be larger than
+    ///  `MAX_VALUE_SIZE`, e.g. a `(buff 1048576)` serializes to 1048581 bytes with its type prefix
+    ///  and length.
+    pub fn max_serialized_size(&self) -> Result<u32, CheckErrors> {
+        let type_prefix_size = 1;
+        let max_output_size = match self {
+            TypeSignature::NoType => {
+                return Err(CheckErrors::CouldNotDetermineSerializationType);
+            }
+            TypeSignature::IntType | TypeSignature::UIntType => 16,
+            TypeSignature::BoolType => 0,
+            TypeSignature::SequenceType(SequenceSubtype::ListType(list_type)) => {
+                // u32 length, then the items
+                let list_length_encode = 4;
+                if list_type.get_max_len() == 0 {
+                    // the empty list's item type is indeterminate
+                    list_length_encode
+                } else {
+                    list_type
+                        .get_list_item_type()
+                        .max_serialized_size()?
+                        .checked_mul(list_type.get_max_len())
+                        .and_then(|x| x.checked_add(list_length_encode))
+                        .ok_or(CheckErrors::ValueTooLarge)?
+                }
+            }
+            TypeSignature::SequenceType(SequenceSubtype::BufferType(len))
+            | TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::ASCII(len))) =>
+            {
+                // u32 length, then one byte per byte or character
+                u32::from(len)
+                    .checked_add(4)
+                    .ok_or(CheckErrors::ValueTooLarge)?
+            }
+            TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::UTF8(len))) => {
+                // u32 length, then up to four bytes per code point
+                u32::from(len)
+                    .checked_mul(4)
+                    .and_then(|x| x.checked_add(4))
+                    .ok_or(CheckErrors::ValueTooLarge)?
+            }
+            TypeSignature::PrincipalType | TypeSignature::TraitReferenceType(_) => {
+                // version byte and hash160 of the issuer, then the length-prefixed contract name
+                21 + 1 + MAX_STRING_LEN as u32
+            }
+            TypeSignature::TupleType(tuple_type) => {
+                // u32 field count, then each length-prefixed field name and its value
+                let mut total_size: u32 = 4;
+                for (name, field_type) in tuple_type.get_type_map().iter() {
+                    let field_size = field_type.max_serialized_size()?;
+                    total_size = total_size
+                        .checked_add(1 + name.len() as u32)
+                        .and_then(|x| x.checked_add(field_size))
+                        .ok_or(CheckErrors::ValueTooLarge)?;
+                }
+                total_size
+            }
+            TypeSignature::OptionalType(some_type) => match some_type.max_serialized_size() {
+                Ok(size) => size,
+                // only `none` has this type, which is just the type prefix
+                Err(CheckErrors::CouldNotDetermineSerializationType) => 0,
+                Err(e) => return Err(e),
+            },
+            TypeSignature::ResponseType(response_types) => {
+                let (ok_type, err_type) = response_types.as_ref();
+                let ok_size = match ok_type.max_serialized_size() {
+                    Ok(size) => Some(size),
+                    Err(CheckErrors::CouldNotDetermineSerializationType) => None,
+                    Err(e) => return Err(e),
+                };
+                let err_size = match err_type.max_serialized_size() {
+                    Ok(size) => Some(size),
+                    Err(CheckErrors::CouldNotDetermineSerializationType) => None,
+                    Err(e) => return Err(e),
+                };
+                match (ok_size, err_size) {
+                    (None, None) => return Err(CheckErrors::CouldNotDetermineSerializationType),
+                    (ok_size, err_size) => cmp::max(ok_size.unwrap_or(0), err_size.unwrap_or(0)),
+                }
+            }
+        };
+
+        max_output_size
+            .checked_add(type_prefix_size)
+            .ok_or(CheckErrors::ValueTooLarge)
+    }
+}
+
 /// A writer that just counts the bytes written
 struct WriteCounter {
     count: u32,
@@ -776,6 +881,11 @@ mod tests {
             v.serialize().len() as u32 / 2,
             "serialized_size() should return the byte length of the serialization (half the length of the hex encoding)",
         );
+        // test the max_serialized_size implementation
+        assert!(
+            TypeSignature::type_of(&v).max_serialized_size().unwrap() >= v.serialized_size(),
+            "max_serialized_size() should bound the serialization of every value of the type",
+        );
     }
 
     fn test_deser_u32_helper(num: u32) {
@@ -27,7 +27,8 @@ use crate::vm::representations::{
 };
 use crate::vm::types::{
     CharType, QualifiedContractIdentifier, SequenceData, SequencedValue, StandardPrincipalData,
-    TraitIdentifier, Value, MAX_TYPE_DEPTH, MAX_VALUE_SIZE, WRAPPER_VALUE_SIZE,
+    TraitIdentifier, Value, MAX_PRINT_TOPIC_LEN, MAX_TYPE_DEPTH, MAX_VALUE_SIZE,
+    WRAPPER_VALUE_SIZE,
 };
 use stacks_common::address::c32;
 use stacks_common::util::hash;
@@ -147,6 +148,11 @@ lazy_static! {
     pub static ref BUFF_16: TypeSignature = SequenceType(SequenceSubtype::BufferType(
         BufferLength::try_from(16u32).expect("BUG: Legal Clarity buffer length marked invalid")
     ));
+    /// The type of the optional topic argument to `print`
+    pub static ref PRINT_TOPIC: TypeSignature = SequenceType(SequenceSubtype::StringType(
+        StringSubtype::ASCII(BufferLength::try_from(MAX_PRINT_TOPIC_LEN)
+            .expect("BUG: Legal Clarity string length marked invalid"))
+    ));
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -22,17 +22,33 @@ use std::convert::TryFrom;
 
 use crate::vm::costs::cost_functions::ClarityCostFunction;
 use crate::vm::costs::runtime_cost;
+use crate::vm::ClarityVersion;
 
 define_named_enum!(NativeVariables {
     ContractCaller("contract-caller"), TxSender("tx-sender"), BlockHeight("block-height"),
-    BurnBlockHeight("burn-block-height"), NativeNone("none"),
+    BurnBlockHeight("burn-block-height"), L1BlockHeight("l1-block-height"), NativeNone("none"),
     NativeTrue("true"), NativeFalse("false"),
     TotalLiquidMicroSTX("stx-liquid-supply"),
     Regtest("is-in-regtest"),
 });
 
-pub fn is_reserved_name(name: &str) -> bool {
-    NativeVariables::lookup_by_name(name).is_some()
+impl NativeVariables {
+    /// The first Clarity version in which this keyword is reserved.
+    pub fn get_version(&self) -> ClarityVersion {
+        match self {
+            NativeVariables::L1BlockHeight => ClarityVersion::Clarity2,
+            _ => ClarityVersion::Clarity1,
+        }
+    }
+
+    /// Look up a keyword by name, if it is reserved in contracts of `version`.
+    pub fn lookup_by_name_at_version(name: &str, version: ClarityVersion) -> Option<Self> {
+        NativeVariables::lookup_by_name(name).filter(|variable| variable.get_version() <= version)
+    }
+}
+
+pub fn is_reserved_name(name: &str, version: ClarityVersion) -> bool {
+    NativeVariables::lookup_by_name_at_version(name, version).is_some()
 }
 
 pub fn lookup_reserved_variable(
@@ -40,7 +56,8 @@ pub fn lookup_reserved_variable(
     _context: &LocalContext,
     env: &mut Environment,
 ) -> Result<Option<Value>> {
-    if let Some(variable) = NativeVariables::lookup_by_name(name) {
+    let version = env.contract_context.get_clarity_version();
+    if let Some(variable) = NativeVariables::lookup_by_name_at_version(name, version) {
         match variable {
             NativeVariables::TxSender => {
                 let sender = env
@@ -69,6 +86,11 @@ pub fn lookup_reserved_variable(
                     .get_current_burnchain_block_height();
                 Ok(Some(Value::UInt(burn_block_height as u128)))
             }
+            NativeVariables::L1BlockHeight => {
+                runtime_cost(ClarityCostFunction::FetchVar, env, 1)?;
+                let l1_block_height = env.global_context.database.get_current_l1_block_height();
+                Ok(Some(Value::UInt(l1_block_height as u128)))
+            }
             NativeVariables::NativeNone => Ok(Some(Value::none())),
             NativeVariables::NativeTrue => Ok(Some(Value::Bool(true))),
             NativeVariables::NativeFalse => Ok(Some(Value::Bool(false))),
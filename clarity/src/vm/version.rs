@@ -0,0 +1,64 @@
+// Copyright (C) 2013-2020 Blocstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+
+use crate::types::StacksEpochId;
+
+/// The version of the Clarity language that a contract is written in.  Each version reserves a
+///  set of native function and keyword names: a name that is only reserved by a later version
+///  can still be used by contracts of an earlier version, e.g., to define a private function.
+///
+/// A contract's version is fixed when it is deployed, from the epoch it is deployed in, so that
+///  contracts deployed before a native was introduced keep their meaning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ClarityVersion {
+    Clarity1,
+    /// Adds the natives and keywords introduced by the subnet-1 epoch.
+    Clarity2,
+}
+
+impl Default for ClarityVersion {
+    /// Contracts that were stored before versions were tracked are Clarity 1 contracts.
+    fn default() -> ClarityVersion {
+        ClarityVersion::Clarity1
+    }
+}
+
+impl fmt::Display for ClarityVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClarityVersion::Clarity1 => write!(f, "Clarity 1"),
+            ClarityVersion::Clarity2 => write!(f, "Clarity 2"),
+        }
+    }
+}
+
+impl ClarityVersion {
+    pub fn latest() -> ClarityVersion {
+        ClarityVersion::Clarity2
+    }
+
+    /// The version of the contracts deployed in `epoch_id`.
+    pub fn default_for_epoch(epoch_id: StacksEpochId) -> ClarityVersion {
+        match epoch_id {
+            StacksEpochId::Epoch10 | StacksEpochId::Epoch20 | StacksEpochId::Epoch2_05 => {
+                ClarityVersion::Clarity1
+            }
+            StacksEpochId::EpochSubnet1 => ClarityVersion::Clarity2,
+        }
+    }
+}
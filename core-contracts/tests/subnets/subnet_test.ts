@@ -46,6 +46,16 @@ Clarinet.test({
    }
 })
 
+Clarinet.test({
+    name: "Ensure that the subnet contract reports its interface version",
+    async fn(chain: Chain, accounts: Map<string, Account>, contracts: Map<string, Contract>) {
+        const alice = accounts.get("wallet_1")!;
+
+        // Subnet nodes check this on startup: see SUBNET_CONTRACT_INTERFACE_VERSION
+        chain.callReadOnlyFn('subnet', 'get-interface-version', [], alice.address).result.expectUint(1);
+    }
+});
+
 Clarinet.test({
     name: "Ensure that block can be committed by subnet miner",
     async fn(chain: Chain, accounts: Map<string, Account>, contracts: Map<string, Contract>) {
@@ -127,6 +137,196 @@ Clarinet.test({
     },
 });
 
+Clarinet.test({
+    name: "Ensure that subnet governance can halt and resume block commits",
+    async fn(chain: Chain, accounts: Map<string, Account>, contracts: Map<string, Contract>) {
+
+        // miner
+        const alice = accounts.get("wallet_1")!;
+        // governance
+        const bob = accounts.get("wallet_2")!;
+
+        // set alice as a miner and bob as governance
+        let initialize = chain.mineBlock([
+            Tx.contractCall("subnet", "set-subnet-miner",
+            [
+                types.principal(alice.address),
+            ],
+            alice.address),
+            Tx.contractCall("subnet", "set-subnet-governance",
+            [
+                types.principal(bob.address),
+            ],
+            bob.address),
+        ]);
+        initialize.receipts[1].result.expectOk().expectBool(true);
+
+        // Try and fail to halt the subnet as a non-governance principal.
+        let block = chain.mineBlock([
+            Tx.contractCall("subnet", "set-halted", [types.bool(true)], alice.address),
+        ]);
+        // should return (err ERR_INVALID_GOVERNANCE)
+        block.receipts[0].result
+            .expectErr()
+            .expectInt(22);
+
+        // Successfully halt the subnet as governance.
+        block = chain.mineBlock([
+            Tx.contractCall("subnet", "set-halted", [types.bool(true)], bob.address),
+        ]);
+        block.receipts[0].result
+            .expectOk()
+            .expectBool(true);
+        chain.callReadOnlyFn('subnet', 'is-halted', [], alice.address).result.expectBool(true);
+
+        // Try and fail to commit a block while halted.
+        const id_header_hash1 = chain.callReadOnlyFn('test-helpers', 'get-id-header-hash', [], alice.address).result.expectOk().toString();
+        block = chain.mineBlock([
+            Tx.contractCall("subnet", "commit-block",
+                [
+                    types.buff(new Uint8Array([0, 1, 1, 1, 1])),
+                    id_header_hash1,
+                    types.buff(new Uint8Array([0, 1, 1, 1, 2])),
+                ],
+                alice.address),
+        ]);
+        // should return (err ERR_SUBNET_HALTED)
+        block.receipts[0].result
+            .expectErr()
+            .expectInt(23);
+
+        // Resume block production, and successfully commit a block.
+        block = chain.mineBlock([
+            Tx.contractCall("subnet", "set-halted", [types.bool(false)], bob.address),
+        ]);
+        block.receipts[0].result
+            .expectOk()
+            .expectBool(false);
+
+        const id_header_hash2 = chain.callReadOnlyFn('test-helpers', 'get-id-header-hash', [], alice.address).result.expectOk().toString();
+        block = chain.mineBlock([
+            Tx.contractCall("subnet", "commit-block",
+                [
+                    types.buff(new Uint8Array([0, 1, 1, 1, 1])),
+                    id_header_hash2,
+                    types.buff(new Uint8Array([0, 1, 1, 1, 2])),
+                ],
+                alice.address),
+        ]);
+        block.receipts[0].result
+            .expectOk()
+            .expectBuff(new Uint8Array([0, 1, 1, 1, 1]));
+    },
+});
+
+Clarinet.test({
+    name: "Ensure that the miner can schedule its rotation to a new miner",
+    async fn(chain: Chain, accounts: Map<string, Account>, contracts: Map<string, Contract>) {
+
+        // current miner
+        const alice = accounts.get("wallet_1")!;
+        // next miner
+        const bob = accounts.get("wallet_2")!;
+
+        // set alice as the miner
+        let initialize = chain.mineBlock([
+            Tx.contractCall("subnet", "set-subnet-miner",
+            [
+                types.principal(alice.address),
+            ],
+            alice.address),
+        ]);
+        initialize.receipts[0].result.expectOk().expectBool(true);
+
+        // Try and fail to schedule a rotation as a principal other than the miner.
+        const activation_height = chain.blockHeight + 5;
+        let block = chain.mineBlock([
+            Tx.contractCall("subnet", "register-next-miner",
+                [types.principal(bob.address), types.uint(activation_height)],
+                bob.address),
+        ]);
+        // should return (err ERR_INVALID_MINER)
+        block.receipts[0].result
+            .expectErr()
+            .expectInt(2);
+
+        // Try and fail to schedule a rotation that would activate in the past.
+        block = chain.mineBlock([
+            Tx.contractCall("subnet", "register-next-miner",
+                [types.principal(bob.address), types.uint(1)],
+                alice.address),
+        ]);
+        // should return (err ERR_INVALID_ROTATION_HEIGHT)
+        block.receipts[0].result
+            .expectErr()
+            .expectInt(24);
+
+        // Successfully schedule the rotation as the miner.
+        block = chain.mineBlock([
+            Tx.contractCall("subnet", "register-next-miner",
+                [types.principal(bob.address), types.uint(activation_height)],
+                alice.address),
+        ]);
+        block.receipts[0].result
+            .expectOk()
+            .expectBool(true);
+        chain.callReadOnlyFn('subnet', 'get-current-miner', [], alice.address).result
+            .expectSome()
+            .expectPrincipal(alice.address);
+
+        // Before the rotation activates, only the current miner can commit a block.
+        const id_header_hash1 = chain.callReadOnlyFn('test-helpers', 'get-id-header-hash', [], alice.address).result.expectOk().toString();
+        block = chain.mineBlock([
+            Tx.contractCall("subnet", "commit-block",
+                [
+                    types.buff(new Uint8Array([0, 1, 1, 1, 1])),
+                    id_header_hash1,
+                    types.buff(new Uint8Array([0, 1, 1, 1, 2])),
+                ],
+                bob.address),
+        ]);
+        // should return (err ERR_INVALID_MINER)
+        block.receipts[0].result
+            .expectErr()
+            .expectInt(2);
+
+        // Once the rotation activates, only the next miner can commit a block.
+        chain.mineEmptyBlockUntil(activation_height);
+        chain.callReadOnlyFn('subnet', 'get-current-miner', [], alice.address).result
+            .expectSome()
+            .expectPrincipal(bob.address);
+
+        const id_header_hash2 = chain.callReadOnlyFn('test-helpers', 'get-id-header-hash', [], alice.address).result.expectOk().toString();
+        block = chain.mineBlock([
+            Tx.contractCall("subnet", "commit-block",
+                [
+                    types.buff(new Uint8Array([0, 1, 1, 1, 1])),
+                    id_header_hash2,
+                    types.buff(new Uint8Array([0, 1, 1, 1, 2])),
+                ],
+                alice.address),
+        ]);
+        // should return (err ERR_INVALID_MINER)
+        block.receipts[0].result
+            .expectErr()
+            .expectInt(2);
+
+        const id_header_hash3 = chain.callReadOnlyFn('test-helpers', 'get-id-header-hash', [], alice.address).result.expectOk().toString();
+        block = chain.mineBlock([
+            Tx.contractCall("subnet", "commit-block",
+                [
+                    types.buff(new Uint8Array([0, 1, 1, 1, 1])),
+                    id_header_hash3,
+                    types.buff(new Uint8Array([0, 1, 1, 1, 2])),
+                ],
+                bob.address),
+        ]);
+        block.receipts[0].result
+            .expectOk()
+            .expectBuff(new Uint8Array([0, 1, 1, 1, 1]));
+    },
+});
+
 Clarinet.test({
     name: "Ensure that user can register and setup assets ",
     async fn(chain: Chain, accounts: Map<string, Account>, contracts: Map<string, Contract>) {
@@ -0,0 +1,20 @@
+#![no_main]
+
+use clarity_fuzz::{subnet, upstream, Program};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|program: Program| {
+    let program = program.to_string();
+    let subnet_outcome = subnet::execute(&program);
+    let upstream_outcome = upstream::execute(&program);
+    assert_eq!(
+        subnet_outcome.result, upstream_outcome.result,
+        "Subnet and upstream Clarity results diverge on program:\n{}",
+        program
+    );
+    assert_eq!(
+        subnet_outcome.cost, upstream_outcome.cost,
+        "Subnet and upstream Clarity costs diverge on program:\n{}",
+        program
+    );
+});
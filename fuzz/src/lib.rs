@@ -0,0 +1,503 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Differential fuzzing of the subnet Clarity VM against upstream Clarity.
+//!
+//! `Program` generates Clarity programs from fuzzer input, using only natives that both VMs
+//! define.  The `subnet` and `upstream` modules run a program in a fresh in-memory database,
+//! with the same boot cost contracts instantiated, and report the result and the execution
+//! cost.  Any difference between the two `Outcome`s is semantic drift in the subnet fork.
+
+use std::fmt;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+/// Natives that only the subnet VM defines.  The generator never emits these.
+pub const SUBNET_ONLY_NATIVES: &[&str] = &[
+    "stx-account",
+    "stx-withdraw?",
+    "ft-withdraw?",
+    "nft-withdraw?",
+    "get-withdrawal-info?",
+    "get-l1-block-info?",
+    "var-exists?",
+    "map-size",
+    "try-contract-call?",
+    "slice",
+    "buff-to-uint-be",
+    "buff-to-uint-le",
+    "replace-at?",
+    "to-consensus-buff?",
+    "from-consensus-buff?",
+    "bit-and",
+    "bit-or",
+    "bit-not",
+    "bit-shift-left",
+    "bit-shift-right",
+];
+
+/// Both VMs evaluate the same cost contracts, so that any cost difference comes from the VMs
+const COST_VOTING_CONTRACT: &str =
+    include_str!("../../src/chainstate/stacks/boot/cost-voting.clar");
+const COSTS_2_CONTRACT: &str = include_str!("../../src/chainstate/stacks/boot/costs-2.clar");
+
+/// Definitions that every generated program starts with, so that expressions can exercise
+/// user-defined functions and the data store
+const PRELUDE: &str = "(define-data-var counter int 0)
+(define-map store int int)
+(define-private (positive (x int)) (> x 0))
+(define-private (accumulate (x int) (acc int)) (+ x acc))
+";
+
+/// Maximum nesting depth of a generated expression
+const MAX_DEPTH: u32 = 4;
+/// Maximum number of top-level expressions in a generated program
+const MAX_EXPRESSIONS: usize = 8;
+/// Maximum number of elements in a generated list, buffer or string literal
+const MAX_LITERAL_LEN: usize = 8;
+
+/// The types of expression the generator produces
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Ty {
+    Int,
+    UInt,
+    Bool,
+    Buff,
+    Ascii,
+    IntList,
+    OptionalInt,
+    ResponseInt,
+}
+
+const ALL_TYPES: &[Ty] = &[
+    Ty::Int,
+    Ty::UInt,
+    Ty::Bool,
+    Ty::Buff,
+    Ty::Ascii,
+    Ty::IntList,
+    Ty::OptionalInt,
+    Ty::ResponseInt,
+];
+
+/// A generated Clarity program: the prelude, followed by top-level expressions.  The program
+/// evaluates to the value of its last expression.
+#[derive(Debug, Clone)]
+pub struct Program {
+    expressions: Vec<String>,
+}
+
+impl<'a> Arbitrary<'a> for Program {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let count = u.int_in_range(1..=MAX_EXPRESSIONS)?;
+        let mut expressions = Vec::with_capacity(count);
+        for _ in 0..count {
+            let ty = *u.choose(ALL_TYPES)?;
+            expressions.push(gen_expr(u, ty, MAX_DEPTH)?);
+        }
+        Ok(Program { expressions })
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", PRELUDE)?;
+        for expression in self.expressions.iter() {
+            writeln!(f, "{}", expression)?;
+        }
+        Ok(())
+    }
+}
+
+fn gen_literal(u: &mut Unstructured, ty: Ty) -> Result<String> {
+    let literal = match ty {
+        Ty::Int => match u.int_in_range(0..=3)? {
+            0 => "0".to_string(),
+            1 => i128::MAX.to_string(),
+            2 => i128::MIN.to_string(),
+            _ => u.arbitrary::<i128>()?.to_string(),
+        },
+        Ty::UInt => match u.int_in_range(0..=2)? {
+            0 => "u0".to_string(),
+            1 => format!("u{}", u128::MAX),
+            _ => format!("u{}", u.arbitrary::<u128>()?),
+        },
+        Ty::Bool => u.arbitrary::<bool>()?.to_string(),
+        Ty::Buff => {
+            let len = u.int_in_range(0..=MAX_LITERAL_LEN)?;
+            let mut hex = "0x".to_string();
+            for _ in 0..len {
+                hex.push_str(&format!("{:02x}", u.arbitrary::<u8>()?));
+            }
+            hex
+        }
+        Ty::Ascii => {
+            let len = u.int_in_range(0..=MAX_LITERAL_LEN)?;
+            let mut s = "\"".to_string();
+            for _ in 0..len {
+                s.push(*u.choose(&['a', 'z', 'A', '0', ' ', '-'])?);
+            }
+            s.push('"');
+            s
+        }
+        Ty::IntList => {
+            let len = u.int_in_range(1..=MAX_LITERAL_LEN)?;
+            let mut items = vec![];
+            for _ in 0..len {
+                items.push(gen_literal(u, Ty::Int)?);
+            }
+            format!("(list {})", items.join(" "))
+        }
+        Ty::OptionalInt => format!("(some {})", gen_literal(u, Ty::Int)?),
+        Ty::ResponseInt => format!("(ok {})", gen_literal(u, Ty::Int)?),
+    };
+    Ok(literal)
+}
+
+/// Generate an expression of type `ty`, nested at most `depth` deep
+fn gen_expr(u: &mut Unstructured, ty: Ty, depth: u32) -> Result<String> {
+    if depth == 0 || u.ratio(1, 4)? {
+        return gen_literal(u, ty);
+    }
+    let d = depth - 1;
+    let expr = match ty {
+        Ty::Int => match u.int_in_range(0..=13)? {
+            0 => format!(
+                "({} {} {})",
+                u.choose(&["+", "-", "*", "/", "mod", "xor"])?,
+                gen_expr(u, Ty::Int, d)?,
+                gen_expr(u, Ty::Int, d)?
+            ),
+            1 => format!(
+                "(pow {} {})",
+                gen_expr(u, Ty::Int, d)?,
+                u.int_in_range(0..=130)?
+            ),
+            2 => format!(
+                "({} {})",
+                u.choose(&["sqrti", "log2"])?,
+                gen_expr(u, Ty::Int, d)?
+            ),
+            3 => format!("(to-int {})", gen_expr(u, Ty::UInt, d)?),
+            4 => format!(
+                "(if {} {} {})",
+                gen_expr(u, Ty::Bool, d)?,
+                gen_expr(u, Ty::Int, d)?,
+                gen_expr(u, Ty::Int, d)?
+            ),
+            5 => format!(
+                "(let ((v{} {})) (+ v{} {}))",
+                depth,
+                gen_expr(u, Ty::Int, d)?,
+                depth,
+                gen_expr(u, Ty::Int, d)?
+            ),
+            6 => format!(
+                "(default-to {} {})",
+                gen_expr(u, Ty::Int, d)?,
+                gen_expr(u, Ty::OptionalInt, d)?
+            ),
+            7 => format!("(unwrap-panic {})", gen_expr(u, Ty::OptionalInt, d)?),
+            8 => format!(
+                "(fold accumulate {} {})",
+                gen_expr(u, Ty::IntList, d)?,
+                gen_expr(u, Ty::Int, d)?
+            ),
+            9 => format!(
+                "(match {} v{} (+ v{} {}) e{} (- e{} {}))",
+                gen_expr(u, Ty::ResponseInt, d)?,
+                depth,
+                depth,
+                gen_expr(u, Ty::Int, d)?,
+                depth,
+                depth,
+                gen_expr(u, Ty::Int, d)?
+            ),
+            10 => "(var-get counter)".to_string(),
+            11 => format!(
+                "(default-to 0 (map-get? store {}))",
+                gen_expr(u, Ty::Int, d)?
+            ),
+            12 => format!(
+                "(get a (merge {{ a: {}, b: {} }} {{ a: {} }}))",
+                gen_expr(u, Ty::Int, d)?,
+                gen_expr(u, Ty::Bool, d)?,
+                gen_expr(u, Ty::Int, d)?
+            ),
+            _ => format!("(unwrap-err-panic {})", gen_expr(u, Ty::ResponseInt, d)?),
+        },
+        Ty::UInt => match u.int_in_range(0..=5)? {
+            0 => format!(
+                "({} {} {})",
+                u.choose(&["+", "-", "*", "/", "mod"])?,
+                gen_expr(u, Ty::UInt, d)?,
+                gen_expr(u, Ty::UInt, d)?
+            ),
+            1 => format!("(to-uint {})", gen_expr(u, Ty::Int, d)?),
+            2 => format!("(len {})", gen_expr(u, Ty::IntList, d)?),
+            3 => format!("(len {})", gen_expr(u, Ty::Buff, d)?),
+            4 => format!("(len {})", gen_expr(u, Ty::Ascii, d)?),
+            _ => format!(
+                "(unwrap-panic (index-of {} {}))",
+                gen_expr(u, Ty::IntList, d)?,
+                gen_expr(u, Ty::Int, d)?
+            ),
+        },
+        Ty::Bool => match u.int_in_range(0..=9)? {
+            0 => format!(
+                "({} {} {})",
+                u.choose(&["and", "or"])?,
+                gen_expr(u, Ty::Bool, d)?,
+                gen_expr(u, Ty::Bool, d)?
+            ),
+            1 => format!("(not {})", gen_expr(u, Ty::Bool, d)?),
+            2 => format!(
+                "({} {} {})",
+                u.choose(&["<", "<=", ">", ">=", "is-eq"])?,
+                gen_expr(u, Ty::Int, d)?,
+                gen_expr(u, Ty::Int, d)?
+            ),
+            3 => format!(
+                "(is-eq {} {})",
+                gen_expr(u, Ty::Buff, d)?,
+                gen_expr(u, Ty::Buff, d)?
+            ),
+            4 => format!(
+                "({} {})",
+                u.choose(&["is-some", "is-none"])?,
+                gen_expr(u, Ty::OptionalInt, d)?
+            ),
+            5 => format!(
+                "({} {})",
+                u.choose(&["is-ok", "is-err"])?,
+                gen_expr(u, Ty::ResponseInt, d)?
+            ),
+            6 => format!("(positive {})", gen_expr(u, Ty::Int, d)?),
+            7 => format!("(var-set counter {})", gen_expr(u, Ty::Int, d)?),
+            8 => format!(
+                "({} store {} {})",
+                u.choose(&["map-set", "map-insert"])?,
+                gen_expr(u, Ty::Int, d)?,
+                gen_expr(u, Ty::Int, d)?
+            ),
+            _ => format!("(map-delete store {})", gen_expr(u, Ty::Int, d)?),
+        },
+        Ty::Buff => match u.int_in_range(0..=3)? {
+            0 => format!(
+                "({} {})",
+                u.choose(&["sha256", "sha512", "sha512/256", "keccak256", "hash160"])?,
+                gen_expr(u, Ty::Buff, d)?
+            ),
+            1 => format!(
+                "({} {})",
+                u.choose(&["sha256", "keccak256", "hash160"])?,
+                gen_expr(u, Ty::Int, d)?
+            ),
+            2 => format!(
+                "(concat {} {})",
+                gen_expr(u, Ty::Buff, d)?,
+                gen_expr(u, Ty::Buff, d)?
+            ),
+            _ => format!(
+                "(unwrap-panic (as-max-len? {} u{}))",
+                gen_expr(u, Ty::Buff, d)?,
+                u.int_in_range(0..=64)?
+            ),
+        },
+        Ty::Ascii => match u.int_in_range(0..=1)? {
+            0 => format!(
+                "(concat {} {})",
+                gen_expr(u, Ty::Ascii, d)?,
+                gen_expr(u, Ty::Ascii, d)?
+            ),
+            _ => format!(
+                "(unwrap-panic (as-max-len? {} u{}))",
+                gen_expr(u, Ty::Ascii, d)?,
+                u.int_in_range(0..=16)?
+            ),
+        },
+        Ty::IntList => match u.int_in_range(0..=4)? {
+            0 => format!(
+                "(list {} {})",
+                gen_expr(u, Ty::Int, d)?,
+                gen_expr(u, Ty::Int, d)?
+            ),
+            1 => format!(
+                "(append {} {})",
+                gen_expr(u, Ty::IntList, d)?,
+                gen_expr(u, Ty::Int, d)?
+            ),
+            2 => format!(
+                "(concat {} {})",
+                gen_expr(u, Ty::IntList, d)?,
+                gen_expr(u, Ty::IntList, d)?
+            ),
+            3 => format!("(filter positive {})", gen_expr(u, Ty::IntList, d)?),
+            _ => format!(
+                "(map + {} {})",
+                gen_expr(u, Ty::IntList, d)?,
+                gen_expr(u, Ty::IntList, d)?
+            ),
+        },
+        Ty::OptionalInt => match u.int_in_range(0..=2)? {
+            0 => "none".to_string(),
+            1 => format!("(some {})", gen_expr(u, Ty::Int, d)?),
+            _ => format!(
+                "(element-at {} {})",
+                gen_expr(u, Ty::IntList, d)?,
+                gen_expr(u, Ty::UInt, d)?
+            ),
+        },
+        Ty::ResponseInt => match u.int_in_range(0..=1)? {
+            0 => format!(
+                "(if {} (ok {}) (err {}))",
+                gen_expr(u, Ty::Bool, d)?,
+                gen_expr(u, Ty::Int, d)?,
+                gen_expr(u, Ty::Int, d)?
+            ),
+            _ => format!("(err {})", gen_expr(u, Ty::Int, d)?),
+        },
+    };
+    Ok(expr)
+}
+
+/// What running a program did, in terms that can be compared across the two VMs
+#[derive(Debug, PartialEq)]
+pub struct Outcome {
+    /// The program's value, or the error it failed with
+    pub result: std::result::Result<Option<String>, String>,
+    /// Total cost as (runtime, read_count, read_length, write_count, write_length)
+    pub cost: (u64, u64, u64, u64, u64),
+}
+
+/// Define a module that runs programs with the Clarity crate `$clarity`.  Both crates share
+/// the API of the fork point, so the same code drives each of them.  The subnet crate also
+/// takes the Clarity version of the contract context, `$version`.
+macro_rules! clarity_runner {
+    ($name:ident, $clarity:ident $(, $version:path)?) => {
+        pub mod $name {
+            use super::{Outcome, COSTS_2_CONTRACT, COST_VOTING_CONTRACT};
+            use $clarity::boot_util::boot_code_id;
+            use $clarity::types::StacksEpochId;
+            use $clarity::vm::ast;
+            use $clarity::vm::contexts::{GlobalContext, OwnedEnvironment};
+            use $clarity::vm::costs::{ExecutionCost, LimitedCostTracker};
+            use $clarity::vm::database::MemoryBackingStore;
+            use $clarity::vm::errors::Error;
+            use $clarity::vm::types::QualifiedContractIdentifier;
+            use $clarity::vm::{eval_all, ContractContext};
+
+            const EPOCH: StacksEpochId = StacksEpochId::Epoch2_05;
+
+            fn describe_error(e: &Error) -> String {
+                match e {
+                    // stack traces are not part of the semantics
+                    Error::Runtime(e, _) => format!("Runtime({:?})", e),
+                    e => format!("{:?}", e),
+                }
+            }
+
+            /// Run `program` in a fresh database holding only the boot cost contracts
+            pub fn execute(program: &str) -> Outcome {
+                let mut store = MemoryBackingStore::new();
+                let mut env = OwnedEnvironment::new_free(true, store.as_clarity_db(), EPOCH);
+                env.initialize_contract(boot_code_id("cost-voting", true), COST_VOTING_CONTRACT)
+                    .expect("FATAL: failed to instantiate .cost-voting");
+                env.initialize_contract(boot_code_id("costs-2", true), COSTS_2_CONTRACT)
+                    .expect("FATAL: failed to instantiate .costs-2");
+                let (mut db, _) = env.destruct().expect("FATAL: environment still in use");
+
+                db.begin();
+                db.set_clarity_epoch_version(EPOCH);
+                db.commit();
+
+                let cost_tracker =
+                    LimitedCostTracker::new(true, ExecutionCost::max_value(), &mut db, EPOCH)
+                        .expect("FATAL: failed to load cost functions");
+                let contract_id = QualifiedContractIdentifier::transient();
+                let mut contract_context = ContractContext::new(contract_id.clone() $(, $version)?);
+                let mut global_context = GlobalContext::new(true, db, cost_tracker, EPOCH);
+                let result = global_context.execute(|g| {
+                    let parsed = ast::build_ast(&contract_id, program, &mut g.cost_track)?;
+                    eval_all(&parsed.expressions, &mut contract_context, g)
+                });
+
+                let cost = global_context.cost_track.get_total();
+                Outcome {
+                    result: result
+                        .map(|value| value.map(|v| v.to_string()))
+                        .map_err(|e| describe_error(&e)),
+                    cost: (
+                        cost.runtime,
+                        cost.read_count,
+                        cost.read_length,
+                        cost.write_count,
+                        cost.write_length,
+                    ),
+                }
+            }
+        }
+    };
+}
+
+clarity_runner!(subnet, clarity, clarity::vm::ClarityVersion::Clarity1);
+clarity_runner!(upstream, upstream_clarity);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subnet_only_natives_are_subnet_only() {
+        for name in SUBNET_ONLY_NATIVES.iter() {
+            assert!(
+                clarity::vm::functions::NativeFunctions::lookup_by_name(name).is_some(),
+                "{} is not a subnet native",
+                name
+            );
+            assert!(
+                upstream_clarity::vm::functions::NativeFunctions::lookup_by_name(name).is_none(),
+                "{} is an upstream native",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn generator_avoids_subnet_only_natives() {
+        let data: Vec<u8> = (0..4096u32).map(|i| (i * 7919 % 251) as u8).collect();
+        for offset in 0..64 {
+            let mut u = Unstructured::new(&data[offset..]);
+            let program = Program::arbitrary(&mut u).unwrap().to_string();
+            for name in SUBNET_ONLY_NATIVES.iter() {
+                assert!(
+                    !program.contains(&format!("({} ", name)),
+                    "generated {}:\n{}",
+                    name,
+                    program
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn vms_agree_on_simple_program() {
+        let program = format!("{}(var-set counter 2) (+ (var-get counter) 1)", PRELUDE);
+        let outcome = subnet::execute(&program);
+        assert_eq!(outcome.result, Ok(Some("3".to_string())));
+        assert_eq!(outcome, upstream::execute(&program));
+    }
+}
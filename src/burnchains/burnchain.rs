@@ -42,9 +42,10 @@ use crate::burnchains::{
 };
 use crate::chainstate::burn::db::sortdb::{SortitionDB, SortitionHandleConn, SortitionHandleTx};
 use crate::chainstate::burn::operations::{
-    leader_block_commit::MissedBlockCommit, BlockstackOperationType, DepositFtOp, DepositNftOp,
-    DepositStxOp, LeaderBlockCommitOp, LeaderKeyRegisterOp, PreStxOp, StackStxOp, TransferStxOp,
-    UserBurnSupportOp, WithdrawFtOp, WithdrawNftOp, WithdrawStxOp,
+    leader_block_commit::MissedBlockCommit, BlockstackOperationType, DepositFtOp,
+    DepositMultiAssetOp, DepositNftOp, DepositStxOp, LeaderBlockCommitOp, LeaderKeyRegisterOp,
+    PreStxOp, StackStxOp, SubnetHaltOp, TransferStxOp, UserBurnSupportOp, WithdrawFtOp,
+    WithdrawNftOp, WithdrawStxOp,
 };
 use crate::chainstate::burn::{BlockSnapshot, Opcodes};
 use crate::chainstate::coordinator::comm::CoordinatorChannels;
@@ -55,7 +56,7 @@ use crate::core::NETWORK_ID_TESTNET;
 use crate::core::PEER_VERSION_MAINNET;
 use crate::core::PEER_VERSION_TESTNET;
 use crate::deps;
-use crate::monitoring::update_burnchain_height;
+use crate::monitoring::{record_commit_observed, update_burnchain_height};
 use crate::types::chainstate::StacksAddress;
 use crate::types::chainstate::TrieHash;
 use crate::util_lib::db::DBConn;
@@ -97,6 +98,7 @@ impl BurnchainStateTransition {
                 BlockstackOperationType::LeaderBlockCommit(op) => {
                     // we don't yet know which block commits are going to be accepted until we have
                     // the burn distribution, so just account for them for now.
+                    record_commit_observed(&op.txid);
                     accepted_ops.push(op.clone().into());
                 }
                 BlockstackOperationType::DepositStx(op) => {
@@ -108,6 +110,9 @@ impl BurnchainStateTransition {
                 BlockstackOperationType::DepositNft(op) => {
                     accepted_ops.push(op.clone().into());
                 }
+                BlockstackOperationType::DepositMultiAsset(op) => {
+                    accepted_ops.push(op.clone().into());
+                }
                 BlockstackOperationType::WithdrawStx(op) => {
                     accepted_ops.push(op.clone().into());
                 }
@@ -117,6 +122,9 @@ impl BurnchainStateTransition {
                 BlockstackOperationType::WithdrawNft(op) => {
                     accepted_ops.push(op.clone().into());
                 }
+                BlockstackOperationType::SubnetHalt(op) => {
+                    accepted_ops.push(op.clone().into());
+                }
             };
         }
 
@@ -441,6 +449,19 @@ impl Burnchain {
                         None
                     }
                 },
+                StacksSubnetOpType::DepositMultiAsset { .. } => {
+                    match DepositMultiAssetOp::try_from(event) {
+                        Ok(op) => Some(BlockstackOperationType::from(op)),
+                        Err(e) => {
+                            warn!(
+                                "Failed to parse multi-asset deposit operation";
+                                "txid" => %burn_tx.txid(),
+                                "error" => ?e,
+                            );
+                            None
+                        }
+                    }
+                }
                 StacksSubnetOpType::WithdrawStx { .. } => match WithdrawStxOp::try_from(event) {
                     Ok(op) => Some(BlockstackOperationType::from(op)),
                     Err(e) => {
@@ -474,6 +495,17 @@ impl Burnchain {
                         None
                     }
                 },
+                StacksSubnetOpType::SubnetHalt { .. } => match SubnetHaltOp::try_from(event) {
+                    Ok(op) => Some(BlockstackOperationType::from(op)),
+                    Err(e) => {
+                        warn!(
+                            "Failed to parse subnet halt operation";
+                            "txid" => %burn_tx.txid(),
+                            "error" => ?e,
+                        );
+                        None
+                    }
+                },
             },
         }
     }
@@ -4,7 +4,7 @@ use std::fmt::Formatter;
 
 use crate::burnchains::Txid;
 use clarity::vm::types::Value as ClarityValue;
-use clarity::vm::types::{QualifiedContractIdentifier, TraitIdentifier};
+use clarity::vm::types::{QualifiedContractIdentifier, TraitIdentifier, TupleData};
 use serde::de::Error as DeserError;
 use serde::Deserialize;
 use serde::Deserializer;
@@ -21,6 +21,7 @@ use stacks_common::util::hash::{to_hex, Sha512Trunc256Sum};
 use super::StacksSubnetBlock;
 use super::StacksSubnetOp;
 use super::StacksSubnetOpType;
+use crate::chainstate::burn::operations::DepositAsset;
 use clarity::vm::types::PrincipalData;
 use stacks_common::codec::StacksMessageCodec;
 use std::fmt::Write;
@@ -193,6 +194,118 @@ where
     serializer.serialize_str(as_str)
 }
 
+/// Parse the optional `withdrawal-leaf-hash` field of a withdrawal event.  Subnet contracts
+/// deployed before this field was added to their withdrawal events do not emit it.
+fn parse_withdrawal_leaf_hash(tuple: &TupleData) -> Result<Option<Sha512Trunc256Sum>, String> {
+    let leaf_hash = match tuple.get("withdrawal-leaf-hash") {
+        Ok(leaf_hash) => leaf_hash,
+        Err(_) => return Ok(None),
+    };
+    if let ClarityValue::Sequence(SequenceData::Buffer(buff_data)) = leaf_hash {
+        if u32::from(buff_data.len()) != 32 {
+            Err(format!(
+                "Expected 'withdrawal-leaf-hash' type to be length 32, found {}",
+                buff_data.len()
+            ))
+        } else {
+            let mut buff = [0; 32];
+            buff.copy_from_slice(&buff_data.data);
+            Ok(Some(Sha512Trunc256Sum(buff)))
+        }
+    } else {
+        Err("Expected 'withdrawal-leaf-hash' type to be buffer".into())
+    }
+}
+
+/// Parse a contract principal field of a deposit event
+fn parse_contract_id(
+    tuple: &TupleData,
+    field: &str,
+) -> Result<QualifiedContractIdentifier, String> {
+    let principal = tuple
+        .get(field)
+        .map_err(|_| format!("No '{}' field in Clarity tuple", field))?
+        .clone()
+        .expect_principal();
+    if let PrincipalData::Contract(id) = principal {
+        Ok(id)
+    } else {
+        Err(format!("Expected '{}' to be a contract principal", field))
+    }
+}
+
+/// Parse the items of a list field of a `deposit-multi-asset` event.  Each item must be a tuple.
+fn parse_asset_list(tuple: &TupleData, field: &str) -> Result<Vec<TupleData>, String> {
+    let list = tuple
+        .get(field)
+        .map_err(|_| format!("No '{}' field in Clarity tuple", field))?;
+    let items = if let ClarityValue::Sequence(SequenceData::List(list_data)) = list {
+        Ok(&list_data.data)
+    } else {
+        Err(format!("Expected '{}' type to be list", field))
+    }?;
+    items
+        .iter()
+        .map(|item| {
+            if let ClarityValue::Tuple(item) = item {
+                Ok(item.clone())
+            } else {
+                Err(format!("Expected '{}' items to be tuples", field))
+            }
+        })
+        .collect()
+}
+
+/// Parse one item of the `fts` list of a `deposit-multi-asset` event
+fn parse_ft_asset(tuple: &TupleData) -> Result<DepositAsset, String> {
+    let amount = tuple
+        .get("ft-amount")
+        .map_err(|_| "No 'ft-amount' field in Clarity tuple")?
+        .clone()
+        .expect_u128();
+    let name = tuple
+        .get("ft-name")
+        .map_err(|_| "No 'ft-name' field in Clarity tuple")?
+        .clone()
+        .expect_ascii();
+    let subnet_function_name = tuple
+        .get("subnet-function-name")
+        .map_err(|_| "No 'subnet-function-name' field in Clarity tuple")?
+        .clone()
+        .expect_ascii();
+    let subnet_function_name = ClarityName::try_from(subnet_function_name)
+        .map_err(|e| format!("Failed to parse Clarity name: {:?}", e))?;
+    Ok(DepositAsset::Ft {
+        l1_contract_id: parse_contract_id(tuple, "l1-contract-id")?,
+        subnet_contract_id: parse_contract_id(tuple, "subnet-contract-id")?,
+        subnet_function_name,
+        name,
+        amount,
+    })
+}
+
+/// Parse one item of the `nfts` list of a `deposit-multi-asset` event
+fn parse_nft_asset(tuple: &TupleData) -> Result<DepositAsset, String> {
+    let id = tuple
+        .get("nft-id")
+        .map_err(|_| "No 'nft-id' field in Clarity tuple")?
+        .clone()
+        .expect_u128();
+    let subnet_function_name = tuple
+        .get("subnet-function-name")
+        .map_err(|_| "No 'subnet-function-name' field in Clarity tuple")?
+        .clone()
+        .expect_ascii();
+    let subnet_function_name = ClarityName::try_from(subnet_function_name)
+        .map_err(|e| format!("Failed to parse Clarity name: {:?}", e))?;
+    Ok(DepositAsset::Nft {
+        l1_contract_id: parse_contract_id(tuple, "l1-contract-id")?,
+        subnet_contract_id: parse_contract_id(tuple, "subnet-contract-id")?,
+        subnet_function_name,
+        id,
+    })
+}
+
 impl StacksSubnetOp {
     /// This method tries to parse a `StacksSubnetOp` from a Clarity value: this should be a tuple
     /// emitted from the subnet contract in a statement like:
@@ -407,6 +520,38 @@ impl StacksSubnetOp {
                     },
                 })
             }
+            "\"deposit-multi-asset\"" => {
+                // Parse 4 fields: stx-amount, fts, nfts, and sender
+                let stx_amount = tuple
+                    .get("stx-amount")
+                    .map_err(|_| "No 'stx-amount' field in Clarity tuple")?
+                    .clone()
+                    .expect_u128();
+                let mut assets = vec![];
+                for ft in parse_asset_list(&tuple, "fts")?.iter() {
+                    assets.push(parse_ft_asset(ft)?);
+                }
+                for nft in parse_asset_list(&tuple, "nfts")?.iter() {
+                    assets.push(parse_nft_asset(nft)?);
+                }
+                let sender = tuple
+                    .get("sender")
+                    .map_err(|_| "No 'sender' field in Clarity tuple")?
+                    .clone()
+                    .expect_principal();
+
+                Ok(Self {
+                    txid,
+                    event_index,
+                    in_block: in_block.clone(),
+                    opcode: 7,
+                    event: StacksSubnetOpType::DepositMultiAsset {
+                        stx_amount,
+                        assets,
+                        sender,
+                    },
+                })
+            }
             "\"withdraw-stx\"" => {
                 // Parse 2 fields: amount and recipient
                 let amount = tuple
@@ -419,13 +564,18 @@ impl StacksSubnetOp {
                     .map_err(|_| "No 'recipient' field in Clarity tuple")?
                     .clone()
                     .expect_principal();
+                let withdrawal_leaf_hash = parse_withdrawal_leaf_hash(&tuple)?;
 
                 Ok(Self {
                     txid,
                     event_index,
                     in_block: in_block.clone(),
                     opcode: 1,
-                    event: StacksSubnetOpType::WithdrawStx { amount, recipient },
+                    event: StacksSubnetOpType::WithdrawStx {
+                        amount,
+                        recipient,
+                        withdrawal_leaf_hash,
+                    },
                 })
             }
             "\"withdraw-ft\"" => {
@@ -455,6 +605,7 @@ impl StacksSubnetOp {
                     .map_err(|_| "No 'recipient' field in Clarity tuple")?
                     .clone()
                     .expect_principal();
+                let withdrawal_leaf_hash = parse_withdrawal_leaf_hash(&tuple)?;
                 Ok(Self {
                     txid,
                     event_index,
@@ -465,6 +616,7 @@ impl StacksSubnetOp {
                         name,
                         amount,
                         recipient,
+                        withdrawal_leaf_hash,
                     },
                 })
             }
@@ -491,6 +643,7 @@ impl StacksSubnetOp {
                     .map_err(|_| "No 'recipient' field in Clarity tuple")?
                     .clone()
                     .expect_principal();
+                let withdrawal_leaf_hash = parse_withdrawal_leaf_hash(&tuple)?;
 
                 Ok(Self {
                     txid,
@@ -501,9 +654,29 @@ impl StacksSubnetOp {
                         l1_contract_id,
                         id,
                         recipient,
+                        withdrawal_leaf_hash,
                     },
                 })
             }
+            "\"subnet-halt\"" => {
+                // Parse 1 field: halted
+                let halted = tuple
+                    .get("halted")
+                    .map_err(|_| "No 'halted' field in Clarity tuple")?;
+                let halted = if let ClarityValue::Bool(halted) = halted {
+                    Ok(*halted)
+                } else {
+                    Err("Expected 'halted' type to be bool")
+                }?;
+
+                Ok(Self {
+                    txid,
+                    event_index,
+                    in_block: in_block.clone(),
+                    opcode: 6,
+                    event: StacksSubnetOpType::SubnetHalt { halted },
+                })
+            }
             event_type => Err(format!("Unexpected 'event' string: {}", event_type)),
         }
     }
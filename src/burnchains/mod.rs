@@ -27,6 +27,7 @@ use rusqlite::Error as sqlite_error;
 
 use crate::chainstate::burn::operations::leader_block_commit::OUTPUTS_PER_COMMIT;
 use crate::chainstate::burn::operations::BlockstackOperationType;
+use crate::chainstate::burn::operations::DepositAsset;
 use crate::chainstate::burn::operations::Error as op_error;
 use crate::chainstate::burn::operations::LeaderKeyRegisterOp;
 use crate::chainstate::burn::ConsensusHash;
@@ -220,20 +221,31 @@ pub enum StacksSubnetOpType {
         id: u128,
         sender: PrincipalData,
     },
+    DepositMultiAsset {
+        stx_amount: u128,
+        assets: Vec<DepositAsset>,
+        sender: PrincipalData,
+    },
     WithdrawStx {
         amount: u128,
         recipient: PrincipalData,
+        withdrawal_leaf_hash: Option<Sha512Trunc256Sum>,
     },
     WithdrawFt {
         l1_contract_id: QualifiedContractIdentifier,
         name: String,
         amount: u128,
         recipient: PrincipalData,
+        withdrawal_leaf_hash: Option<Sha512Trunc256Sum>,
     },
     WithdrawNft {
         l1_contract_id: QualifiedContractIdentifier,
         id: u128,
         recipient: PrincipalData,
+        withdrawal_leaf_hash: Option<Sha512Trunc256Sum>,
+    },
+    SubnetHalt {
+        halted: bool,
     },
 }
 
@@ -416,6 +416,28 @@ impl TestBurnchainBlock {
         txop
     }
 
+    pub fn add_subnet_halt(&mut self, halted: bool) -> SubnetHaltOp {
+        let burn_header_hash = bhh_from_test_data(
+            self.block_height,
+            &self.parent_snapshot.index_root,
+            self.fork_id,
+        );
+        let txop = SubnetHaltOp {
+            txid: Txid::from_test_data(
+                self.block_height,
+                self.txs.len() as u32,
+                &burn_header_hash,
+                0,
+            ),
+            burn_header_hash,
+            halted,
+        };
+
+        self.txs
+            .push(BlockstackOperationType::SubnetHalt(txop.clone()));
+        txop
+    }
+
     // TODO: user burn support
 
     pub fn patch_from_chain_tip(&mut self, parent_snapshot: &BlockSnapshot) -> () {
@@ -741,6 +763,102 @@ fn mine_10_stacks_blocks_1_fork() {
     }
 }
 
+#[test]
+fn subnet_halt_rejects_block_commits() {
+    let mut node = TestBurnchainNode::new();
+    let mut miner_factory = TestMinerFactory::new();
+    let mut miners =
+        vec![miner_factory.next_miner(&node.burnchain, 1, 1, AddressHashMode::SerializeP2PKH)];
+
+    let first_snapshot = SortitionDB::get_first_block_snapshot(node.sortdb.conn()).unwrap();
+    let mut fork = TestBurnchainFork::new(
+        first_snapshot.block_height,
+        &first_snapshot.burn_header_hash,
+        &first_snapshot.index_root,
+        0,
+    );
+
+    // commits are accepted before the halt
+    let (tip, commits, _) = process_next_sortition(
+        &mut node,
+        &mut fork,
+        &mut miners,
+        &vec![BlockHeaderHash([0x01; 32])],
+    );
+    verify_commits_accepted(&node, &commits);
+    assert!(tip.sortition);
+
+    // halt the subnet.  A commit in the same block is still accepted.
+    let mut block = {
+        let ic = node.sortdb.index_conn();
+        fork.next_block(&ic)
+    };
+    let commit = {
+        let ic = node.sortdb.index_conn();
+        block.add_leader_block_commit(
+            &ic,
+            &mut miners[0],
+            &BlockHeaderHash([0x02; 32]),
+            1000,
+            None,
+            None,
+        )
+    };
+    block.add_subnet_halt(true);
+    fork.append_block(block);
+    let tip = node.mine_fork(&mut fork);
+    verify_commits_accepted(&node, &vec![commit]);
+    assert!(tip.sortition);
+    assert!(node
+        .sortdb
+        .index_handle(&tip.sortition_id)
+        .is_subnet_halted()
+        .unwrap());
+
+    // commits are rejected while halted
+    let (tip, commits, _) = process_next_sortition(
+        &mut node,
+        &mut fork,
+        &mut miners,
+        &vec![BlockHeaderHash([0x03; 32])],
+    );
+    assert!(
+        SortitionDB::get_burnchain_transaction(node.sortdb.conn(), &commits[0].txid)
+            .unwrap()
+            .is_none()
+    );
+    assert!(!tip.sortition);
+    assert!(node
+        .sortdb
+        .index_handle(&tip.sortition_id)
+        .is_subnet_halted()
+        .unwrap());
+
+    // resume the subnet
+    let mut block = {
+        let ic = node.sortdb.index_conn();
+        fork.next_block(&ic)
+    };
+    block.add_subnet_halt(false);
+    fork.append_block(block);
+    let tip = node.mine_fork(&mut fork);
+    assert!(!node
+        .sortdb
+        .index_handle(&tip.sortition_id)
+        .is_subnet_halted()
+        .unwrap());
+
+    // commits are accepted again
+    let (tip, commits, _) = process_next_sortition(
+        &mut node,
+        &mut fork,
+        &mut miners,
+        &vec![BlockHeaderHash([0x04; 32])],
+    );
+    verify_commits_accepted(&node, &commits);
+    assert!(tip.sortition);
+}
+
 #[test]
 fn mine_10_stacks_blocks_2_forks_disjoint() {
     let mut node = TestBurnchainNode::new();
@@ -1156,6 +1274,154 @@ fn create_stacks_events_failures_withdraw_stx() {
     }
 }
 
+#[test]
+fn create_stacks_events_withdraw_stx_leaf_hash() {
+    let value = execute(
+        r#"{ event: "withdraw-stx", amount: u100, recipient: 'ST000000000000000000002AMW42H,
+        withdrawal-leaf-hash: 0x0101010101010101010101010101010101010101010101010101010101010101 }"#,
+    )
+    .unwrap()
+    .unwrap();
+    let op = StacksSubnetOp::try_from_clar_value(value, Txid([0; 32]), 0, &StacksBlockId([0; 32]))
+        .unwrap();
+    match op.event {
+        StacksSubnetOpType::WithdrawStx {
+            withdrawal_leaf_hash,
+            ..
+        } => assert_eq!(withdrawal_leaf_hash, Some(Sha512Trunc256Sum([1; 32]))),
+        _ => panic!("Expected a withdraw-stx op"),
+    }
+
+    // events from contracts that do not report the leaf hash are still accepted
+    let value = execute(
+        r#"{ event: "withdraw-stx", amount: u100, recipient: 'ST000000000000000000002AMW42H }"#,
+    )
+    .unwrap()
+    .unwrap();
+    let op = StacksSubnetOp::try_from_clar_value(value, Txid([0; 32]), 0, &StacksBlockId([0; 32]))
+        .unwrap();
+    match op.event {
+        StacksSubnetOpType::WithdrawStx {
+            withdrawal_leaf_hash,
+            ..
+        } => assert_eq!(withdrawal_leaf_hash, None),
+        _ => panic!("Expected a withdraw-stx op"),
+    }
+
+    let value = execute(
+        r#"{ event: "withdraw-stx", amount: u100, recipient: 'ST000000000000000000002AMW42H,
+        withdrawal-leaf-hash: 0x0101 }"#,
+    )
+    .unwrap()
+    .unwrap();
+    let err_str =
+        StacksSubnetOp::try_from_clar_value(value, Txid([0; 32]), 0, &StacksBlockId([0; 32]))
+            .unwrap_err();
+    assert!(err_str.starts_with("Expected 'withdrawal-leaf-hash' type to be length 32"));
+}
+
+#[test]
+fn create_stacks_events_deposit_multi_asset() {
+    let value = execute(
+        r#"{ event: "deposit-multi-asset", stx-amount: u10, sender: 'ST000000000000000000002AMW42H,
+        fts: (list { ft-amount: u100, ft-name: "simple-ft", l1-contract-id: 'ST000000000000000000002AMW42H.simple-ft,
+                     subnet-contract-id: 'STTHM8422MZMP02R6KHPSCBAHKDTZZ6Y4FRH7CSH.simple-ft,
+                     subnet-function-name: "subnet-deposit-simple-ft" }),
+        nfts: (list { nft-id: u1, l1-contract-id: 'ST000000000000000000002AMW42H.simple-nft,
+                      subnet-contract-id: 'STTHM8422MZMP02R6KHPSCBAHKDTZZ6Y4FRH7CSH.simple-nft,
+                      subnet-function-name: "subnet-deposit-simple-nft" }
+                    { nft-id: u2, l1-contract-id: 'ST000000000000000000002AMW42H.simple-nft,
+                      subnet-contract-id: 'STTHM8422MZMP02R6KHPSCBAHKDTZZ6Y4FRH7CSH.simple-nft,
+                      subnet-function-name: "subnet-deposit-simple-nft" }) }"#,
+    )
+    .unwrap()
+    .unwrap();
+    let op = StacksSubnetOp::try_from_clar_value(value, Txid([0; 32]), 0, &StacksBlockId([0; 32]))
+        .unwrap();
+    let (stx_amount, assets) = match op.event {
+        StacksSubnetOpType::DepositMultiAsset {
+            stx_amount, assets, ..
+        } => (stx_amount, assets),
+        _ => panic!("Expected a deposit-multi-asset op"),
+    };
+    assert_eq!(stx_amount, 10);
+    assert_eq!(assets.len(), 3);
+    match &assets[0] {
+        DepositAsset::Ft { name, amount, .. } => {
+            assert_eq!(name, "simple-ft");
+            assert_eq!(*amount, 100);
+        }
+        _ => panic!("Expected the fungible token first"),
+    }
+    let nft_ids: Vec<_> = assets[1..]
+        .iter()
+        .map(|asset| asset.amount_or_id())
+        .collect();
+    assert_eq!(nft_ids, vec![1, 2]);
+
+    // a deposit may carry only tokens, or only STX
+    let value = execute(
+        r#"{ event: "deposit-multi-asset", stx-amount: u10, sender: 'ST000000000000000000002AMW42H,
+        fts: (list), nfts: (list) }"#,
+    )
+    .unwrap()
+    .unwrap();
+    StacksSubnetOp::try_from_clar_value(value, Txid([0; 32]), 0, &StacksBlockId([0; 32])).unwrap();
+}
+
+#[test]
+fn create_stacks_events_failures_deposit_multi_asset() {
+    let inputs = [
+        (
+            r#"{ event: "deposit-multi-asset", sender: 'ST000000000000000000002AMW42H, fts: (list), nfts: (list) }"#,
+            "No 'stx-amount' field in Clarity tuple",
+        ),
+        (
+            r#"{ event: "deposit-multi-asset", stx-amount: u10, sender: 'ST000000000000000000002AMW42H, nfts: (list) }"#,
+            "No 'fts' field in Clarity tuple",
+        ),
+        (
+            r#"{ event: "deposit-multi-asset", stx-amount: u10, sender: 'ST000000000000000000002AMW42H, fts: u1, nfts: (list) }"#,
+            "Expected 'fts' type to be list",
+        ),
+        (
+            r#"{ event: "deposit-multi-asset", stx-amount: u10, sender: 'ST000000000000000000002AMW42H, fts: (list), nfts: (list u1) }"#,
+            "Expected 'nfts' items to be tuples",
+        ),
+        (
+            r#"{ event: "deposit-multi-asset", stx-amount: u10, sender: 'ST000000000000000000002AMW42H, fts: (list),
+            nfts: (list { l1-contract-id: 'ST000000000000000000002AMW42H.simple-nft,
+                          subnet-contract-id: 'STTHM8422MZMP02R6KHPSCBAHKDTZZ6Y4FRH7CSH.simple-nft,
+                          subnet-function-name: "subnet-deposit-simple-nft" }) }"#,
+            "No 'nft-id' field in Clarity tuple",
+        ),
+        (
+            r#"{ event: "deposit-multi-asset", stx-amount: u10, sender: 'ST000000000000000000002AMW42H, nfts: (list),
+            fts: (list { ft-amount: u100, ft-name: "simple-ft", l1-contract-id: 'ST000000000000000000002AMW42H,
+                         subnet-contract-id: 'STTHM8422MZMP02R6KHPSCBAHKDTZZ6Y4FRH7CSH.simple-ft,
+                         subnet-function-name: "subnet-deposit-simple-ft" }) }"#,
+            "Expected 'l1-contract-id' to be a contract principal",
+        ),
+        (
+            r#"{ event: "deposit-multi-asset", stx-amount: u10, fts: (list), nfts: (list) }"#,
+            "No 'sender' field in Clarity tuple",
+        ),
+    ];
+
+    for (test_input, expected_err) in inputs.iter() {
+        let value = execute(test_input).unwrap().unwrap();
+        let err_str =
+            StacksSubnetOp::try_from_clar_value(value, Txid([0; 32]), 0, &StacksBlockId([0; 32]))
+                .unwrap_err();
+        assert!(
+            err_str.starts_with(expected_err),
+            "{} starts_with? {}",
+            err_str,
+            expected_err
+        );
+    }
+}
+
 #[test]
 fn create_stacks_events_failures_withdraw_ft() {
     let inputs = [
@@ -1237,6 +1503,40 @@ fn create_stacks_events_failures_withdraw_nft() {
     }
 }
 
+#[test]
+fn create_stacks_events_failures_subnet_halt() {
+    let inputs = [
+        (
+            r#"{ event: "subnet-halt" }"#,
+            "No 'halted' field in Clarity tuple",
+        ),
+        (
+            r#"{ event: "subnet-halt", halted: u1 }"#,
+            "Expected 'halted' type to be bool",
+        ),
+    ];
+
+    for (test_input, expected_err) in inputs.iter() {
+        let value = execute(test_input).unwrap().unwrap();
+        let err_str =
+            StacksSubnetOp::try_from_clar_value(value, Txid([0; 32]), 0, &StacksBlockId([0; 32]))
+                .unwrap_err();
+        assert!(
+            err_str.starts_with(expected_err),
+            "{} starts_with? {}",
+            err_str,
+            expected_err
+        );
+    }
+
+    let value = execute(r#"{ event: "subnet-halt", halted: true }"#)
+        .unwrap()
+        .unwrap();
+    let op = StacksSubnetOp::try_from_clar_value(value, Txid([0; 32]), 0, &StacksBlockId([0; 32]))
+        .unwrap();
+    assert_eq!(op.event, StacksSubnetOpType::SubnetHalt { halted: true });
+}
+
 #[test]
 fn create_stacks_event_block_for_block_commit() {
     let watched_contract = QualifiedContractIdentifier::new(
@@ -98,6 +98,20 @@ impl<'a> SortitionHandleTx<'a> {
                 );
                 BurnchainError::OpError(e)
             }),
+            BlockstackOperationType::DepositMultiAsset(ref op) => {
+                op.check(burnchain, self).map_err(|e| {
+                    warn!(
+                        "REJECTED burnchain operation";
+                        "op" => "deposit_multi_asset",
+                        "l1_stacks_block_id" => %op.burn_header_hash,
+                        "txid" => %op.txid,
+                        "stx_amount" => %op.stx_amount,
+                        "num_assets" => op.assets.len(),
+                        "sender" => %op.sender,
+                    );
+                    BurnchainError::OpError(e)
+                })
+            }
             BlockstackOperationType::WithdrawFt(ref op) => op.check(burnchain, self).map_err(|e| {
                 warn!(
                     "REJECTED burnchain operation";
@@ -138,6 +152,16 @@ impl<'a> SortitionHandleTx<'a> {
                     BurnchainError::OpError(e)
                 })
             }
+            BlockstackOperationType::SubnetHalt(ref op) => op.check(burnchain, self).map_err(|e| {
+                warn!(
+                    "REJECTED burnchain operation";
+                    "op" => "subnet_halt",
+                    "l1_stacks_block_id" => %op.burn_header_hash,
+                    "txid" => %op.txid,
+                    "halted" => op.halted,
+                );
+                BurnchainError::OpError(e)
+            }),
         }
     }
 
@@ -41,8 +41,9 @@ use crate::burnchains::{
 };
 use crate::chainstate::burn::operations::{
     leader_block_commit::{MissedBlockCommit, RewardSetInfo, OUTPUTS_PER_COMMIT},
-    BlockstackOperationType, DepositFtOp, DepositNftOp, DepositStxOp, LeaderBlockCommitOp,
-    LeaderKeyRegisterOp, PreStxOp, StackStxOp, TransferStxOp, UserBurnSupportOp,
+    BlockstackOperationType, DepositFtOp, DepositMultiAssetOp, DepositNftOp, DepositStxOp,
+    LeaderBlockCommitOp, LeaderKeyRegisterOp, PreStxOp, StackStxOp, TransferStxOp,
+    UserBurnSupportOp, WithdrawStxOp,
 };
 use crate::chainstate::burn::Opcodes;
 use crate::chainstate::burn::{BlockSnapshot, ConsensusHash, OpsHash, SortitionHash};
@@ -258,6 +259,55 @@ impl FromRow<StacksEpoch> for StacksEpoch {
     }
 }
 
+/// An L1 claim of a subnet withdrawal, observed in a withdrawal event from the subnet contract
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithdrawalClaim {
+    /// the L1 transaction that claimed the withdrawal
+    pub txid: Txid,
+    /// the L1 block that contains the claim
+    pub l1_block_id: BurnchainHeaderHash,
+    pub withdrawal_leaf_hash: Sha512Trunc256Sum,
+    pub sortition_id: SortitionId,
+}
+
+impl FromRow<WithdrawalClaim> for WithdrawalClaim {
+    fn from_row<'a>(row: &'a Row) -> Result<WithdrawalClaim, db_error> {
+        let txid = Txid::from_column(row, "txid")?;
+        let l1_block_id = BurnchainHeaderHash::from_column(row, "l1_block_id")?;
+        let withdrawal_leaf_hash = Sha512Trunc256Sum::from_column(row, "withdrawal_leaf_hash")?;
+        let sortition_id = SortitionId::from_column(row, "sortition_id")?;
+
+        Ok(WithdrawalClaim {
+            txid,
+            l1_block_id,
+            withdrawal_leaf_hash,
+            sortition_id,
+        })
+    }
+}
+
+impl FromRow<WithdrawStxOp> for WithdrawStxOp {
+    fn from_row<'a>(row: &'a Row) -> Result<WithdrawStxOp, db_error> {
+        let txid = Txid::from_column(row, "txid")?;
+        let burn_header_hash = BurnchainHeaderHash::from_column(row, "l1_block_id")?;
+
+        let amount_str: String = row.get_unwrap("amount");
+        let amount =
+            u128::from_str_radix(&amount_str, 10).expect("CORRUPTION: bad u128 written to sortdb");
+        let recipient_str: String = row.get_unwrap("recipient");
+        let recipient = PrincipalData::parse(&recipient_str).map_err(|_| db_error::ParseError)?;
+        let withdrawal_leaf_hash = Sha512Trunc256Sum::from_column(row, "withdrawal_leaf_hash")?;
+
+        Ok(WithdrawStxOp {
+            txid,
+            burn_header_hash,
+            amount,
+            recipient,
+            withdrawal_leaf_hash: Some(withdrawal_leaf_hash),
+        })
+    }
+}
+
 impl FromRow<DepositStxOp> for DepositStxOp {
     fn from_row<'a>(row: &'a Row) -> Result<DepositStxOp, db_error> {
         let txid = Txid::from_column(row, "txid")?;
@@ -330,7 +380,30 @@ impl FromRow<DepositNftOp> for DepositNftOp {
     }
 }
 
-pub const SORTITION_DB_VERSION: &'static str = "3";
+impl FromRow<DepositMultiAssetOp> for DepositMultiAssetOp {
+    fn from_row<'a>(row: &'a Row) -> Result<DepositMultiAssetOp, db_error> {
+        let txid = Txid::from_column(row, "txid")?;
+        let burn_header_hash = BurnchainHeaderHash::from_column(row, "l1_block_id")?;
+
+        let stx_amount_str: String = row.get_unwrap("stx_amount");
+        let stx_amount = u128::from_str_radix(&stx_amount_str, 10)
+            .expect("CORRUPTION: bad u128 written to sortdb");
+        let assets_json: String = row.get_unwrap("assets");
+        let assets = serde_json::from_str(&assets_json)
+            .expect("CORRUPTION: bad deposit assets written to sortdb");
+        let sender = StacksAddress::from_column(row, "sender")?;
+
+        Ok(DepositMultiAssetOp {
+            txid,
+            burn_header_hash,
+            stx_amount,
+            assets,
+            sender: PrincipalData::from(sender),
+        })
+    }
+}
+
+pub const SORTITION_DB_VERSION: &'static str = "6";
 
 const SORTITION_DB_INITIAL_SCHEMA: &'static [&'static str] = &[
     r#"
@@ -533,8 +606,48 @@ const SORTITION_DB_SCHEMA_3: &'static [&'static str] = &[r#"
         FOREIGN KEY(block_commit_txid,block_commit_sortition_id) REFERENCES block_commits(txid,sortition_id)
     );"#];
 
+const SORTITION_DB_SCHEMA_4: &'static [&'static str] = &[r#"
+    -- L1 claims of subnet withdrawals, keyed by the claimed withdrawal's leaf hash
+    CREATE TABLE withdrawal_claims (
+        txid TEXT NOT NULL,
+        l1_block_id TEXT NOT NULL,
+        withdrawal_leaf_hash TEXT NOT NULL,
+        sortition_id TEXT NOT NULL,
+
+        PRIMARY KEY(txid,sortition_id),
+        FOREIGN KEY(sortition_id) REFERENCES snapshots(sortition_id)
+    );"#];
+
+const SORTITION_DB_SCHEMA_5: &'static [&'static str] = &[r#"
+    -- L1 claims of subnet STX withdrawals, which release the withdrawn STX from escrow
+    CREATE TABLE withdraw_stx (
+        txid TEXT NOT NULL,
+        l1_block_id TEXT NOT NULL,
+        amount TEXT NOT NULL,
+        recipient TEXT NOT NULL,
+        withdrawal_leaf_hash TEXT NOT NULL,
+        sortition_id TEXT NOT NULL,
+
+        PRIMARY KEY(txid,sortition_id),
+        FOREIGN KEY(sortition_id) REFERENCES snapshots(sortition_id)
+    );"#];
+
+const SORTITION_DB_SCHEMA_6: &'static [&'static str] = &[r#"
+    -- L1 deposits of STX and several tokens at once.  The tokens are stored as a JSON list.
+    CREATE TABLE deposit_multi_asset (
+        txid TEXT NOT NULL,
+        l1_block_id TEXT NOT NULL,
+        stx_amount TEXT NOT NULL,
+        assets TEXT NOT NULL,
+        sender TEXT NOT NULL,
+        sortition_id TEXT NOT NULL,
+
+        PRIMARY KEY(txid,sortition_id),
+        FOREIGN KEY(sortition_id) REFERENCES snapshots(sortition_id)
+    );"#];
+
 // update this to add new indexes
-const LAST_SORTITION_DB_INDEX: &'static str = "index_parent_sortition_id";
+const LAST_SORTITION_DB_INDEX: &'static str = "index_deposit_multi_asset_l1_block_id";
 
 const SORTITION_DB_INDEXES: &'static [&'static str] = &[
     "CREATE INDEX IF NOT EXISTS snapshots_block_hashes ON snapshots(block_height,index_root,winning_stacks_block_hash);",
@@ -553,6 +666,9 @@ const SORTITION_DB_INDEXES: &'static [&'static str] = &[
     "CREATE INDEX IF NOT EXISTS index_missed_commits_intended_sortition_id ON missed_commits(intended_sortition_id);",
     "CREATE INDEX IF NOT EXISTS canonical_stacks_blocks ON canonical_accepted_stacks_blocks(tip_consensus_hash,stacks_block_hash);",
     "CREATE INDEX IF NOT EXISTS index_parent_sortition_id ON block_commit_parents(parent_sortition_id);",
+    "CREATE INDEX IF NOT EXISTS index_withdrawal_claims_leaf_hash ON withdrawal_claims(withdrawal_leaf_hash);",
+    "CREATE INDEX IF NOT EXISTS index_withdraw_stx_l1_block_id ON withdraw_stx(l1_block_id);",
+    "CREATE INDEX IF NOT EXISTS index_deposit_multi_asset_l1_block_id ON deposit_multi_asset(l1_block_id);",
 ];
 
 pub struct SortitionDB {
@@ -693,6 +809,12 @@ impl db_keys {
         "sortition_db::last_sortition"
     }
 
+    /// MARF index key for whether or not the subnet's L1 governance has halted block production.
+    /// Maps to "1" if halted, and "0" (or no value) if not.
+    pub fn subnet_halted() -> &'static str {
+        "sortition_db::subnet_halted"
+    }
+
     /// MARF index key for a processed stacks block.  Maps to its height.
     pub fn stacks_block_index(stacks_block_hash: &BlockHeaderHash) -> String {
         format!("sortition_db::stacks::block::{}", stacks_block_hash)
@@ -1054,6 +1176,15 @@ impl<'a> SortitionHandleTx<'a> {
         )))
     }
 
+    /// Has the subnet's L1 governance halted block production as of this handle's chain tip?
+    pub fn is_subnet_halted(&mut self) -> Result<bool, db_error> {
+        let chain_tip = self.context.chain_tip.clone();
+        Ok(self
+            .get_indexed(&chain_tip, db_keys::subnet_halted())?
+            .map(|s| s == "1")
+            .unwrap_or(false))
+    }
+
     fn get_reward_set_size(&mut self) -> Result<u16, db_error> {
         let chain_tip = self.context.chain_tip.clone();
         self.get_indexed(&chain_tip, db_keys::pox_reward_set_size())
@@ -1326,6 +1457,68 @@ impl<'a> SortitionHandleConn<'a> {
         SortitionDB::get_ancestor_snapshot(self, block_height, &self.context.chain_tip)
     }
 
+    /// Has the subnet's L1 governance halted block production as of this handle's chain tip?
+    pub fn is_subnet_halted(&self) -> Result<bool, db_error> {
+        Ok(self
+            .get_tip_indexed(db_keys::subnet_halted())?
+            .map(|s| s == "1")
+            .unwrap_or(false))
+    }
+
+    /// Has a block commit in this fork registered the given withdrawal root on the L1?
+    pub fn has_block_commit_with_withdrawal_root(
+        &self,
+        withdrawal_root: &Sha512Trunc256Sum,
+    ) -> Result<bool, db_error> {
+        Ok(self
+            .get_block_commit_snapshot_with_withdrawal_root(withdrawal_root)?
+            .is_some())
+    }
+
+    /// Get the snapshot of the L1 block in this fork whose block commit registered the given
+    /// withdrawal root, if any
+    pub fn get_block_commit_snapshot_with_withdrawal_root(
+        &self,
+        withdrawal_root: &Sha512Trunc256Sum,
+    ) -> Result<Option<BlockSnapshot>, db_error> {
+        let qry = "SELECT sortition_id FROM block_commits WHERE withdrawal_merkle_root = ?1";
+        let args: &[&dyn ToSql] = &[withdrawal_root];
+        let sortition_ids: Vec<SortitionId> = query_rows(self.conn(), qry, args)?;
+        for sortition_id in sortition_ids.iter() {
+            let snapshot = match SortitionDB::get_block_snapshot(self.conn(), sortition_id)? {
+                Some(sn) => sn,
+                None => continue,
+            };
+            if self
+                .get_sortition_id_for_bhh(&snapshot.burn_header_hash)?
+                .as_ref()
+                == Some(sortition_id)
+            {
+                return Ok(Some(snapshot));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get the claim of the withdrawal with the given leaf hash in this fork, if it has been
+    /// claimed on the L1
+    pub fn get_withdrawal_claim(
+        &self,
+        withdrawal_leaf_hash: &Sha512Trunc256Sum,
+    ) -> Result<Option<WithdrawalClaim>, db_error> {
+        let qry = "SELECT * FROM withdrawal_claims WHERE withdrawal_leaf_hash = ?1";
+        let args: &[&dyn ToSql] = &[withdrawal_leaf_hash];
+        let claims: Vec<WithdrawalClaim> = query_rows(self.conn(), qry, args)?;
+        for claim in claims.into_iter() {
+            if self.get_sortition_id_for_bhh(&claim.l1_block_id)?.as_ref()
+                == Some(&claim.sortition_id)
+            {
+                return Ok(Some(claim));
+            }
+        }
+        Ok(None)
+    }
+
     /// Get all user burns that burned for the winning block in the chain_tip sortition
     /// Returns list of user burns in order by vtxindex.
     pub fn get_winning_user_burns_by_block(&self) -> Result<Vec<UserBurnSupportOp>, db_error> {
@@ -1683,6 +1876,15 @@ impl SortitionDB {
         for row_text in SORTITION_DB_SCHEMA_3 {
             db_tx.execute_batch(row_text)?;
         }
+        for row_text in SORTITION_DB_SCHEMA_4 {
+            db_tx.execute_batch(row_text)?;
+        }
+        for row_text in SORTITION_DB_SCHEMA_5 {
+            db_tx.execute_batch(row_text)?;
+        }
+        for row_text in SORTITION_DB_SCHEMA_6 {
+            db_tx.execute_batch(row_text)?;
+        }
 
         SortitionDB::validate_and_insert_epochs(&db_tx, epochs_ref)?;
 
@@ -1794,12 +1996,43 @@ impl SortitionDB {
             .expect("BUG: no snapshots in block_snapshots"))
     }
 
+    /// Get all StacksEpochs, in order by ascending start height, given the DB path.
+    /// Like `get_highest_block_height_from_path`, this will *not* apply any schema migrations.
+    pub fn get_stacks_epochs_from_path(path: &str) -> Result<Vec<StacksEpoch>, db_error> {
+        if fs::metadata(path).is_err() {
+            return Err(db_error::NoDBError);
+        }
+        let index_path = db_mkdirs(path)?;
+        let marf = SortitionDB::open_index(&index_path)?;
+        SortitionDB::get_stacks_epochs(marf.sqlite_conn())
+    }
+
     /// Is a particular database version supported by a given epoch?
     pub fn is_db_version_supported_in_epoch(epoch: StacksEpochId, version: &str) -> bool {
         match epoch {
             StacksEpochId::Epoch10 => false,
-            StacksEpochId::Epoch20 => version == "1" || version == "2" || version == "3",
-            StacksEpochId::Epoch2_05 => version == "2" || version == "3",
+            StacksEpochId::Epoch20 => {
+                version == "1"
+                    || version == "2"
+                    || version == "3"
+                    || version == "4"
+                    || version == "5"
+                    || version == "6"
+            }
+            StacksEpochId::Epoch2_05 => {
+                version == "2"
+                    || version == "3"
+                    || version == "4"
+                    || version == "5"
+                    || version == "6"
+            }
+            StacksEpochId::EpochSubnet1 => {
+                version == "2"
+                    || version == "3"
+                    || version == "4"
+                    || version == "5"
+                    || version == "6"
+            }
         }
     }
 
@@ -1841,6 +2074,39 @@ impl SortitionDB {
         Ok(())
     }
 
+    fn apply_schema_4(tx: &DBTx) -> Result<(), db_error> {
+        for sql_exec in SORTITION_DB_SCHEMA_4 {
+            tx.execute_batch(sql_exec)?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO db_config (version) VALUES (?1)",
+            &["4"],
+        )?;
+        Ok(())
+    }
+
+    fn apply_schema_5(tx: &DBTx) -> Result<(), db_error> {
+        for sql_exec in SORTITION_DB_SCHEMA_5 {
+            tx.execute_batch(sql_exec)?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO db_config (version) VALUES (?1)",
+            &["5"],
+        )?;
+        Ok(())
+    }
+
+    fn apply_schema_6(tx: &DBTx) -> Result<(), db_error> {
+        for sql_exec in SORTITION_DB_SCHEMA_6 {
+            tx.execute_batch(sql_exec)?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO db_config (version) VALUES (?1)",
+            &["6"],
+        )?;
+        Ok(())
+    }
+
     fn check_schema_version_or_error(&mut self) -> Result<(), db_error> {
         match SortitionDB::get_schema_version(self.conn()) {
             Ok(Some(version)) => {
@@ -1875,6 +2141,23 @@ impl SortitionDB {
                         let tx = self.tx_begin()?;
                         SortitionDB::apply_schema_3(&tx.deref())?;
                         tx.commit()?;
+                    } else if version == "3" {
+                        // claims that were observed before this migration are not recovered.
+                        let tx = self.tx_begin()?;
+                        SortitionDB::apply_schema_4(&tx.deref())?;
+                        tx.commit()?;
+                    } else if version == "4" {
+                        // STX claims that were observed before this migration do not release
+                        // their withdrawals from escrow.
+                        let tx = self.tx_begin()?;
+                        SortitionDB::apply_schema_5(&tx.deref())?;
+                        tx.commit()?;
+                    } else if version == "5" {
+                        // multi-asset deposits that were observed before this migration are not
+                        // recovered.
+                        let tx = self.tx_begin()?;
+                        SortitionDB::apply_schema_6(&tx.deref())?;
+                        tx.commit()?;
                     } else if version == expected_version {
                         return Ok(());
                     } else {
@@ -2480,6 +2763,19 @@ impl SortitionDB {
         )
     }
 
+    /// Get the L1 claims of STX withdrawals in an L1 block.  Claims that do not report the claimed
+    /// withdrawal's leaf hash are not recorded.
+    pub fn get_withdraw_stx_ops(
+        conn: &Connection,
+        l1_block_id: &BurnchainHeaderHash,
+    ) -> Result<Vec<WithdrawStxOp>, db_error> {
+        query_rows(
+            conn,
+            "SELECT * FROM withdraw_stx WHERE l1_block_id = ?",
+            &[l1_block_id],
+        )
+    }
+
     pub fn get_deposit_ft_ops(
         conn: &Connection,
         l1_block_id: &BurnchainHeaderHash,
@@ -2502,6 +2798,30 @@ impl SortitionDB {
         )
     }
 
+    pub fn get_deposit_multi_asset_ops(
+        conn: &Connection,
+        l1_block_id: &BurnchainHeaderHash,
+    ) -> Result<Vec<DepositMultiAssetOp>, db_error> {
+        query_rows(
+            conn,
+            "SELECT * FROM deposit_multi_asset WHERE l1_block_id = ?",
+            &[l1_block_id],
+        )
+    }
+
+    /// Has a deposit operation with the given L1 txid been observed in any L1 fork?
+    pub fn has_deposit_op(conn: &Connection, txid: &Txid) -> Result<bool, db_error> {
+        let count = query_count(
+            conn,
+            "SELECT COUNT(*) FROM (SELECT txid FROM deposit_stx WHERE txid = ?1 \
+             UNION ALL SELECT txid FROM deposit_ft WHERE txid = ?1 \
+             UNION ALL SELECT txid FROM deposit_nft WHERE txid = ?1 \
+             UNION ALL SELECT txid FROM deposit_multi_asset WHERE txid = ?1)",
+            &[txid],
+        )?;
+        Ok(count > 0)
+    }
+
     pub fn index_handle_at_tip<'a>(&'a self) -> SortitionHandleConn<'a> {
         let sortition_id = SortitionDB::get_canonical_sortition_tip(self.conn()).unwrap();
         self.index_handle(&sortition_id)
@@ -3055,6 +3375,19 @@ impl<'a> SortitionHandleTx<'a> {
 
                 self.insert_deposit_nft(op, sort_id)
             }
+            BlockstackOperationType::DepositMultiAsset(ref op) => {
+                info!(
+                    "ACCEPTED burnchain operation";
+                    "op" => "deposit_multi_asset",
+                    "l1_stacks_block_id" => %op.burn_header_hash,
+                    "txid" => %op.txid,
+                    "stx_amount" => %op.stx_amount,
+                    "num_assets" => op.assets.len(),
+                    "sender" => %op.sender,
+                );
+
+                self.insert_deposit_multi_asset(op, sort_id)
+            }
             BlockstackOperationType::WithdrawStx(ref op) => {
                 info!(
                     "ACCEPTED burnchain operation";
@@ -3064,8 +3397,14 @@ impl<'a> SortitionHandleTx<'a> {
                     "amount" => %op.amount,
                     "recipient" => %op.recipient,
                 );
-                // TODO(subnets) - store operation!
-                Ok(())
+
+                self.insert_withdraw_stx(op, sort_id)?;
+                self.insert_withdrawal_claim(
+                    &op.txid,
+                    &op.burn_header_hash,
+                    &op.withdrawal_leaf_hash,
+                    sort_id,
+                )
             }
             BlockstackOperationType::WithdrawFt(ref op) => {
                 info!(
@@ -3079,8 +3418,12 @@ impl<'a> SortitionHandleTx<'a> {
                     "recipient" => %op.recipient,
                 );
 
-                // TODO(subnets) - store operation!
-                Ok(())
+                self.insert_withdrawal_claim(
+                    &op.txid,
+                    &op.burn_header_hash,
+                    &op.withdrawal_leaf_hash,
+                    sort_id,
+                )
             }
             BlockstackOperationType::WithdrawNft(ref op) => {
                 info!(
@@ -3093,7 +3436,24 @@ impl<'a> SortitionHandleTx<'a> {
                     "recipient" => %op.recipient,
                 );
 
-                // TODO(subnets) - store operation!
+                self.insert_withdrawal_claim(
+                    &op.txid,
+                    &op.burn_header_hash,
+                    &op.withdrawal_leaf_hash,
+                    sort_id,
+                )
+            }
+            BlockstackOperationType::SubnetHalt(ref op) => {
+                info!(
+                    "ACCEPTED burnchain operation";
+                    "op" => "subnet_halt",
+                    "l1_stacks_block_id" => %op.burn_header_hash,
+                    "txid" => %op.txid,
+                    "halted" => op.halted,
+                );
+
+                // the halt status is recorded in the sortition index (see
+                // `index_add_fork_info()`), so that it is inherited by this fork's descendants
                 Ok(())
             }
         }
@@ -3144,6 +3504,30 @@ impl<'a> SortitionHandleTx<'a> {
         Ok(())
     }
 
+    /// Insert a withdraw stx op, if it reports the leaf hash of the withdrawal it claims
+    fn insert_withdraw_stx(
+        &mut self,
+        op: &WithdrawStxOp,
+        sort_id: &SortitionId,
+    ) -> Result<(), db_error> {
+        let withdrawal_leaf_hash = match op.withdrawal_leaf_hash {
+            Some(ref leaf_hash) => leaf_hash,
+            None => return Ok(()),
+        };
+        let args: &[&dyn ToSql] = &[
+            &op.txid,
+            &op.burn_header_hash,
+            &op.amount.to_string(),
+            &op.recipient.to_string(),
+            withdrawal_leaf_hash,
+            sort_id,
+        ];
+
+        self.execute("REPLACE INTO withdraw_stx (txid, l1_block_id, amount, recipient, withdrawal_leaf_hash, sortition_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", args)?;
+
+        Ok(())
+    }
+
     /// Insert a deposit ft op
     fn insert_deposit_ft(
         &mut self,
@@ -3189,6 +3573,45 @@ impl<'a> SortitionHandleTx<'a> {
         Ok(())
     }
 
+    fn insert_deposit_multi_asset(
+        &mut self,
+        op: &DepositMultiAssetOp,
+        sort_id: &SortitionId,
+    ) -> Result<(), db_error> {
+        let args: &[&dyn ToSql] = &[
+            &op.txid,
+            &op.burn_header_hash,
+            &op.stx_amount.to_string(),
+            &serde_json::to_string(&op.assets).unwrap(),
+            &op.sender.to_string(),
+            sort_id,
+        ];
+
+        self.execute("REPLACE INTO deposit_multi_asset (txid, l1_block_id, stx_amount, assets, sender, sortition_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", args)?;
+
+        Ok(())
+    }
+
+    /// Record the L1 claim of a subnet withdrawal.  Withdrawal events from subnet contracts that
+    /// do not report the claimed withdrawal's leaf hash are not recorded.
+    fn insert_withdrawal_claim(
+        &mut self,
+        txid: &Txid,
+        l1_block_id: &BurnchainHeaderHash,
+        withdrawal_leaf_hash: &Option<Sha512Trunc256Sum>,
+        sort_id: &SortitionId,
+    ) -> Result<(), db_error> {
+        let withdrawal_leaf_hash = match withdrawal_leaf_hash {
+            Some(leaf_hash) => leaf_hash,
+            None => return Ok(()),
+        };
+        let args: &[&dyn ToSql] = &[txid, l1_block_id, withdrawal_leaf_hash, sort_id];
+
+        self.execute("REPLACE INTO withdrawal_claims (txid, l1_block_id, withdrawal_leaf_hash, sortition_id) VALUES (?1, ?2, ?3, ?4)", args)?;
+
+        Ok(())
+    }
+
     /// Insert a snapshots row from a block's-worth of operations.
     /// Do not call directly -- use append_chain_tip_snapshot to preserve the fork table structure.
     fn insert_block_snapshot(&self, snapshot: &BlockSnapshot) -> Result<(), db_error> {
@@ -3246,6 +3669,7 @@ impl<'a> SortitionHandleTx<'a> {
     /// * sortdb::stacks::block::${STACKS_BLOCK_HASH} --> ${STACKS_BLOCK_HEIGHT} for each block that has been accepted so far
     /// * sortdb::stacks::block::max_arrival_index --> ${ARRIVAL_INDEX} to set the maximum arrival index processed in this fork
     /// * sortdb::pox_reward_set::${n} --> recipient Bitcoin address, to track the reward set as the permutation progresses
+    /// * sortdb::subnet_halted --> 1 or 0, if the subnet's L1 governance halted or resumed block production in this block
     ///
     /// `recipient_info` is used to pass information to this function about which reward set addresses were consumed
     ///   during this sortition. this object will be None in the following cases:
@@ -3260,7 +3684,7 @@ impl<'a> SortitionHandleTx<'a> {
         &mut self,
         parent_snapshot: &mut BlockSnapshot,
         snapshot: &BlockSnapshot,
-        _block_ops: &Vec<BlockstackOperationType>,
+        block_ops: &Vec<BlockstackOperationType>,
         initialize_bonus: Option<InitialMiningBonus>,
     ) -> Result<TrieHash, db_error> {
         if !snapshot.is_initial() {
@@ -3324,6 +3748,15 @@ impl<'a> SortitionHandleTx<'a> {
             }
         }
 
+        // if the subnet was halted or resumed in this block, the last such op takes effect
+        if let Some(halted) = block_ops.iter().rev().find_map(|op| match op {
+            BlockstackOperationType::SubnetHalt(ref halt_op) => Some(halt_op.halted),
+            _ => None,
+        }) {
+            keys.push(db_keys::subnet_halted().to_string());
+            values.push(if halted { "1" } else { "0" }.to_string());
+        }
+
         // storing null PoX info
         keys.push(db_keys::pox_reward_set_size().to_string());
         values.push(db_keys::reward_set_size_to_string(0));
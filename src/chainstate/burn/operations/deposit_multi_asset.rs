@@ -0,0 +1,47 @@
+use crate::burnchains::{Burnchain, StacksSubnetOp, StacksSubnetOpType};
+use crate::chainstate::burn::db::sortdb::SortitionHandleTx;
+use crate::chainstate::burn::operations::DepositMultiAssetOp;
+use crate::chainstate::burn::operations::Error as op_error;
+use clarity::types::chainstate::BurnchainHeaderHash;
+use std::convert::TryFrom;
+
+impl TryFrom<&StacksSubnetOp> for DepositMultiAssetOp {
+    type Error = op_error;
+
+    fn try_from(value: &StacksSubnetOp) -> Result<Self, Self::Error> {
+        if let StacksSubnetOpType::DepositMultiAsset {
+            ref stx_amount,
+            ref assets,
+            ref sender,
+        } = value.event
+        {
+            Ok(DepositMultiAssetOp {
+                txid: value.txid.clone(),
+                // use the StacksBlockId in the L1 event as the burnchain header hash
+                burn_header_hash: BurnchainHeaderHash(value.in_block.0.clone()),
+                stx_amount: stx_amount.clone(),
+                assets: assets.clone(),
+                sender: sender.clone(),
+            })
+        } else {
+            Err(op_error::InvalidInput)
+        }
+    }
+}
+
+impl DepositMultiAssetOp {
+    pub fn check(
+        &self,
+        _burnchain: &Burnchain,
+        _tx: &mut SortitionHandleTx,
+    ) -> Result<(), op_error> {
+        // a deposit that carries nothing is not worth materializing
+        if self.stx_amount == 0 && self.assets.is_empty() {
+            return Err(op_error::InvalidInput);
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub fn set_burn_height(&mut self, _height: u64) {}
+}
@@ -168,9 +168,21 @@ impl LeaderBlockCommitOp {
     pub fn check(
         &self,
         _burnchain: &Burnchain,
-        _tx: &mut SortitionHandleTx,
+        tx: &mut SortitionHandleTx,
         _reward_set_info: Option<&RewardSetInfo>,
     ) -> Result<(), op_error> {
+        // while the subnet is halted, no new blocks may be committed.  This is checked against
+        // the parent sortition, so commits that land in the same L1 block as the halt are still
+        // accepted.
+        if tx.is_subnet_halted()? {
+            warn!(
+                "Invalid block commit: subnet is halted";
+                "txid" => %self.txid,
+                "commited_block_hash" => %self.block_header_hash,
+            );
+            return Err(op_error::BlockCommitSubnetHalted);
+        }
+
         // good to go!
         Ok(())
     }
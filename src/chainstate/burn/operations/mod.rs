@@ -39,6 +39,7 @@ use crate::types::chainstate::VRFSeed;
 
 use crate::chainstate::burn::ConsensusHash;
 use crate::chainstate::burn::Opcodes;
+use crate::chainstate::stacks::boot::BridgedAssetType;
 use crate::util_lib::db::DBConn;
 use crate::util_lib::db::DBTx;
 use crate::util_lib::db::Error as db_error;
@@ -52,9 +53,11 @@ use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
 use clarity::vm::ClarityName;
 
 pub mod deposit_ft;
+pub mod deposit_multi_asset;
 pub mod deposit_nft;
 pub mod deposit_stx;
 pub mod leader_block_commit;
+pub mod subnet_halt;
 pub mod withdraw_ft;
 pub mod withdraw_nft;
 pub mod withdraw_stx;
@@ -81,6 +84,7 @@ pub enum Error {
     BlockCommitBadModulus,
     BlockCommitBadEpoch,
     MissedBlockCommit(MissedBlockCommit),
+    BlockCommitSubnetHalted,
 
     // all the things that can go wrong with leader key register
     LeaderKeyAlreadyRegistered,
@@ -126,6 +130,9 @@ impl fmt::Display for Error {
             Error::BlockCommitBadEpoch => {
                 write!(f, "Block commit has an invalid epoch")
             }
+            Error::BlockCommitSubnetHalted => {
+                write!(f, "Block commit was made while the subnet is halted")
+            }
             Error::MissedBlockCommit(_) => write!(
                 f,
                 "Block commit included in a burn block that was not intended"
@@ -276,6 +283,99 @@ pub struct DepositNftOp {
     pub sender: PrincipalData,
 }
 
+/// One fungible or non-fungible token carried by a `DepositMultiAssetOp`
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum DepositAsset {
+    Ft {
+        // Contract ID on L1 chain for this fungible token
+        l1_contract_id: QualifiedContractIdentifier,
+        // Contract ID on subnet for this fungible token
+        subnet_contract_id: QualifiedContractIdentifier,
+        // Name of the function to call in the subnet contract to execute deposit
+        subnet_function_name: ClarityName,
+        // Name of fungible token
+        name: String,
+        // Amount of the fungible token that was deposited
+        amount: u128,
+    },
+    Nft {
+        // Contract ID on L1 chain for this NFT
+        l1_contract_id: QualifiedContractIdentifier,
+        // Contract ID on subnet for this NFT
+        subnet_contract_id: QualifiedContractIdentifier,
+        // Name of the function to call in the subnet contract to execute deposit
+        subnet_function_name: ClarityName,
+        // The ID of the NFT transferred
+        id: u128,
+    },
+}
+
+impl DepositAsset {
+    pub fn subnet_contract_id(&self) -> &QualifiedContractIdentifier {
+        match self {
+            DepositAsset::Ft {
+                subnet_contract_id, ..
+            } => subnet_contract_id,
+            DepositAsset::Nft {
+                subnet_contract_id, ..
+            } => subnet_contract_id,
+        }
+    }
+
+    pub fn l1_contract_id(&self) -> &QualifiedContractIdentifier {
+        match self {
+            DepositAsset::Ft { l1_contract_id, .. } => l1_contract_id,
+            DepositAsset::Nft { l1_contract_id, .. } => l1_contract_id,
+        }
+    }
+
+    pub fn asset_type(&self) -> BridgedAssetType {
+        match self {
+            DepositAsset::Ft { .. } => BridgedAssetType::Ft,
+            DepositAsset::Nft { .. } => BridgedAssetType::Nft,
+        }
+    }
+
+    pub fn subnet_function_name(&self) -> &ClarityName {
+        match self {
+            DepositAsset::Ft {
+                subnet_function_name,
+                ..
+            } => subnet_function_name,
+            DepositAsset::Nft {
+                subnet_function_name,
+                ..
+            } => subnet_function_name,
+        }
+    }
+
+    /// The amount of a fungible token, or the ID of a non-fungible token
+    pub fn amount_or_id(&self) -> u128 {
+        match self {
+            DepositAsset::Ft { amount, .. } => *amount,
+            DepositAsset::Nft { id, .. } => *id,
+        }
+    }
+}
+
+/// A deposit of STX and any number of tokens in a single L1 operation.  Either all of its assets
+/// are credited in the subnet, or none of them are.
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
+pub struct DepositMultiAssetOp {
+    /// Transaction ID of this commit op
+    pub txid: Txid,
+    /// Hash of the base chain block that produced this commit op.
+    pub burn_header_hash: BurnchainHeaderHash,
+
+    // Amount of STX that was deposited (may be 0)
+    pub stx_amount: u128,
+    // The tokens that were deposited
+    pub assets: Vec<DepositAsset>,
+    // The principal that performed the deposit
+    pub sender: PrincipalData,
+}
+
 #[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub struct WithdrawStxOp {
     /// Transaction ID of this commit op
@@ -287,6 +387,8 @@ pub struct WithdrawStxOp {
     pub amount: u128,
     // The principal that is the recipient of this withdrawal
     pub recipient: PrincipalData,
+    // The leaf hash of the subnet withdrawal this claims, if the L1 contract reported it
+    pub withdrawal_leaf_hash: Option<Sha512Trunc256Sum>,
 }
 
 #[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
@@ -304,6 +406,8 @@ pub struct WithdrawFtOp {
     pub amount: u128,
     // The principal the contract is sending the fungible token to
     pub recipient: PrincipalData,
+    // The leaf hash of the subnet withdrawal this claims, if the L1 contract reported it
+    pub withdrawal_leaf_hash: Option<Sha512Trunc256Sum>,
 }
 
 #[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
@@ -319,6 +423,19 @@ pub struct WithdrawNftOp {
     pub id: u128,
     // The principal the contract is sending the NFT to
     pub recipient: PrincipalData,
+    // The leaf hash of the subnet withdrawal this claims, if the L1 contract reported it
+    pub withdrawal_leaf_hash: Option<Sha512Trunc256Sum>,
+}
+
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
+pub struct SubnetHaltOp {
+    /// Transaction ID of this op
+    pub txid: Txid,
+    /// Hash of the base chain block that produced this op.
+    pub burn_header_hash: BurnchainHeaderHash,
+
+    // Whether the subnet's governance has halted (true) or resumed (false) block production
+    pub halted: bool,
 }
 
 #[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
@@ -359,9 +476,11 @@ pub enum BlockstackOperationType {
     DepositStx(DepositStxOp),
     DepositFt(DepositFtOp),
     DepositNft(DepositNftOp),
+    DepositMultiAsset(DepositMultiAssetOp),
     WithdrawStx(WithdrawStxOp),
     WithdrawFt(WithdrawFtOp),
     WithdrawNft(WithdrawNftOp),
+    SubnetHalt(SubnetHaltOp),
 }
 
 impl From<LeaderBlockCommitOp> for BlockstackOperationType {
@@ -388,6 +507,12 @@ impl From<DepositNftOp> for BlockstackOperationType {
     }
 }
 
+impl From<DepositMultiAssetOp> for BlockstackOperationType {
+    fn from(op: DepositMultiAssetOp) -> Self {
+        BlockstackOperationType::DepositMultiAsset(op)
+    }
+}
+
 impl From<WithdrawStxOp> for BlockstackOperationType {
     fn from(op: WithdrawStxOp) -> Self {
         BlockstackOperationType::WithdrawStx(op)
@@ -406,6 +531,12 @@ impl From<WithdrawNftOp> for BlockstackOperationType {
     }
 }
 
+impl From<SubnetHaltOp> for BlockstackOperationType {
+    fn from(op: SubnetHaltOp) -> Self {
+        BlockstackOperationType::SubnetHalt(op)
+    }
+}
+
 impl BlockstackOperationType {
     pub fn txid(&self) -> Txid {
         self.txid_ref().clone()
@@ -417,9 +548,11 @@ impl BlockstackOperationType {
             BlockstackOperationType::DepositStx(ref data) => &data.txid,
             BlockstackOperationType::DepositFt(ref data) => &data.txid,
             BlockstackOperationType::DepositNft(ref data) => &data.txid,
+            BlockstackOperationType::DepositMultiAsset(ref data) => &data.txid,
             BlockstackOperationType::WithdrawStx(ref data) => &data.txid,
             BlockstackOperationType::WithdrawFt(ref data) => &data.txid,
             BlockstackOperationType::WithdrawNft(ref data) => &data.txid,
+            BlockstackOperationType::SubnetHalt(ref data) => &data.txid,
         }
     }
 
@@ -437,9 +570,11 @@ impl BlockstackOperationType {
             BlockstackOperationType::DepositStx(ref data) => data.burn_header_hash.clone(),
             BlockstackOperationType::DepositFt(ref data) => data.burn_header_hash.clone(),
             BlockstackOperationType::DepositNft(ref data) => data.burn_header_hash.clone(),
+            BlockstackOperationType::DepositMultiAsset(ref data) => data.burn_header_hash.clone(),
             BlockstackOperationType::WithdrawStx(ref data) => data.burn_header_hash.clone(),
             BlockstackOperationType::WithdrawFt(ref data) => data.burn_header_hash.clone(),
             BlockstackOperationType::WithdrawNft(ref data) => data.burn_header_hash.clone(),
+            BlockstackOperationType::SubnetHalt(ref data) => data.burn_header_hash.clone(),
         }
     }
 
@@ -452,9 +587,13 @@ impl BlockstackOperationType {
             BlockstackOperationType::DepositStx(ref mut data) => data.set_burn_height(height),
             BlockstackOperationType::DepositFt(ref mut data) => data.set_burn_height(height),
             BlockstackOperationType::DepositNft(ref mut data) => data.set_burn_height(height),
+            BlockstackOperationType::DepositMultiAsset(ref mut data) => {
+                data.set_burn_height(height)
+            }
             BlockstackOperationType::WithdrawStx(ref mut data) => data.set_burn_height(height),
             BlockstackOperationType::WithdrawFt(ref mut data) => data.set_burn_height(height),
             BlockstackOperationType::WithdrawNft(ref mut data) => data.set_burn_height(height),
+            BlockstackOperationType::SubnetHalt(ref mut data) => data.set_burn_height(height),
         };
     }
 
@@ -467,9 +606,13 @@ impl BlockstackOperationType {
             BlockstackOperationType::DepositStx(ref mut data) => data.burn_header_hash = hash,
             BlockstackOperationType::DepositFt(ref mut data) => data.burn_header_hash = hash,
             BlockstackOperationType::DepositNft(ref mut data) => data.burn_header_hash = hash,
+            BlockstackOperationType::DepositMultiAsset(ref mut data) => {
+                data.burn_header_hash = hash
+            }
             BlockstackOperationType::WithdrawStx(ref mut data) => data.burn_header_hash = hash,
             BlockstackOperationType::WithdrawFt(ref mut data) => data.burn_header_hash = hash,
             BlockstackOperationType::WithdrawNft(ref mut data) => data.burn_header_hash = hash,
+            BlockstackOperationType::SubnetHalt(ref mut data) => data.burn_header_hash = hash,
         };
     }
 }
@@ -481,9 +624,11 @@ impl fmt::Display for BlockstackOperationType {
             BlockstackOperationType::DepositStx(ref op) => write!(f, "{:?}", op),
             BlockstackOperationType::DepositFt(ref op) => write!(f, "{:?}", op),
             BlockstackOperationType::DepositNft(ref op) => write!(f, "{:?}", op),
+            BlockstackOperationType::DepositMultiAsset(ref op) => write!(f, "{:?}", op),
             BlockstackOperationType::WithdrawStx(ref op) => write!(f, "{:?}", op),
             BlockstackOperationType::WithdrawFt(ref op) => write!(f, "{:?}", op),
             BlockstackOperationType::WithdrawNft(ref op) => write!(f, "{:?}", op),
+            BlockstackOperationType::SubnetHalt(ref op) => write!(f, "{:?}", op),
         }
     }
 }
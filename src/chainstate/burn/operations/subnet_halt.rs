@@ -0,0 +1,38 @@
+use crate::burnchains::{Burnchain, StacksSubnetOp, StacksSubnetOpType};
+use crate::chainstate::burn::db::sortdb::SortitionHandleTx;
+use crate::chainstate::burn::operations::Error as op_error;
+use crate::chainstate::burn::operations::SubnetHaltOp;
+use clarity::types::chainstate::BurnchainHeaderHash;
+use std::convert::TryFrom;
+
+impl TryFrom<&StacksSubnetOp> for SubnetHaltOp {
+    type Error = op_error;
+
+    fn try_from(value: &StacksSubnetOp) -> Result<Self, Self::Error> {
+        if let StacksSubnetOpType::SubnetHalt { ref halted } = value.event {
+            Ok(SubnetHaltOp {
+                txid: value.txid.clone(),
+                // use the StacksBlockId in the L1 event as the burnchain header hash
+                burn_header_hash: BurnchainHeaderHash(value.in_block.0.clone()),
+                halted: *halted,
+            })
+        } else {
+            Err(op_error::InvalidInput)
+        }
+    }
+}
+
+impl SubnetHaltOp {
+    pub fn check(
+        &self,
+        _burnchain: &Burnchain,
+        _tx: &mut SortitionHandleTx,
+    ) -> Result<(), op_error> {
+        // the L1 contract only emits this event for calls from the subnet's governance
+        // principal, so there is nothing more to check.
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub fn set_burn_height(&mut self, _height: u64) {}
+}
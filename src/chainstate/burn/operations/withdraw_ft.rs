@@ -14,6 +14,7 @@ impl TryFrom<&StacksSubnetOp> for WithdrawFtOp {
             ref name,
             ref amount,
             ref recipient,
+            ref withdrawal_leaf_hash,
         } = value.event
         {
             Ok(WithdrawFtOp {
@@ -24,6 +25,7 @@ impl TryFrom<&StacksSubnetOp> for WithdrawFtOp {
                 name: name.clone(),
                 amount: amount.clone(),
                 recipient: recipient.clone(),
+                withdrawal_leaf_hash: withdrawal_leaf_hash.clone(),
             })
         } else {
             Err(op_error::InvalidInput)
@@ -13,6 +13,7 @@ impl TryFrom<&StacksSubnetOp> for WithdrawNftOp {
             ref l1_contract_id,
             ref id,
             ref recipient,
+            ref withdrawal_leaf_hash,
         } = value.event
         {
             Ok(WithdrawNftOp {
@@ -22,6 +23,7 @@ impl TryFrom<&StacksSubnetOp> for WithdrawNftOp {
                 l1_contract_id: l1_contract_id.clone(),
                 id: id.clone(),
                 recipient: recipient.clone(),
+                withdrawal_leaf_hash: withdrawal_leaf_hash.clone(),
             })
         } else {
             Err(op_error::InvalidInput)
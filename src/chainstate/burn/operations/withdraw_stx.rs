@@ -12,6 +12,7 @@ impl TryFrom<&StacksSubnetOp> for WithdrawStxOp {
         if let StacksSubnetOpType::WithdrawStx {
             ref amount,
             ref recipient,
+            ref withdrawal_leaf_hash,
         } = value.event
         {
             Ok(WithdrawStxOp {
@@ -20,6 +21,7 @@ impl TryFrom<&StacksSubnetOp> for WithdrawStxOp {
                 burn_header_hash: BurnchainHeaderHash(value.in_block.0.clone()),
                 amount: amount.clone(),
                 recipient: recipient.clone(),
+                withdrawal_leaf_hash: withdrawal_leaf_hash.clone(),
             })
         } else {
             Err(op_error::InvalidInput)
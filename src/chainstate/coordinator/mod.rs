@@ -318,6 +318,12 @@ impl<'a, T: BlockEventDispatcher, U: RewardSetProvider> ChainsCoordinator<'a, T,
         )
     }
 
+    /// Get a handle to the chainstate this coordinator processes blocks into
+    #[cfg(test)]
+    pub fn chainstate_mut(&mut self) -> &mut StacksChainState {
+        &mut self.chain_state_db
+    }
+
     #[cfg(test)]
     pub fn test_new_with_observer(
         burnchain: &Burnchain,
@@ -452,10 +458,35 @@ impl<
         if let Some(pox_anchor) = self.process_ready_blocks()? {
             self.process_new_pox_anchor(pox_anchor)
         } else {
+            self.prune_chainstate_if_due();
             Ok(())
         }
     }
 
+    /// If the chainstate has a prune depth, prune it below the canonical Stacks tip.  Pruning
+    /// failures are logged, but do not stop block processing.
+    fn prune_chainstate_if_due(&mut self) {
+        let canonical_tip = match self.canonical_chain_tip.as_ref() {
+            Some(canonical_tip) => canonical_tip.clone(),
+            None => return,
+        };
+        match self.chain_state_db.prune_if_due(&canonical_tip) {
+            Ok(Some(stats)) => {
+                info!("Pruned chainstate";
+                      "canonical_tip" => %canonical_tip,
+                      "blocks_pruned" => stats.blocks_pruned,
+                      "microblocks_pruned" => stats.microblocks_pruned,
+                      "tries_pruned" => stats.tries_pruned);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                warn!("Failed to prune chainstate";
+                      "canonical_tip" => %canonical_tip,
+                      "error" => %e);
+            }
+        }
+    }
+
     pub fn handle_new_burnchain_block(&mut self) -> Result<(), Error> {
         // Retrieve canonical burnchain chain tip from the BurnchainBlocksDB
         let canonical_burnchain_tip = self.burnchain_blocks_db.get_canonical_chain_tip()?;
@@ -865,18 +896,50 @@ pub fn migrate_chainstate_dbs(
     if fs::metadata(&sortdb_path).is_ok() {
         info!("Migrating sortition DB to the latest schema version");
         SortitionDB::migrate_if_exists(&sortdb_path, epochs)?;
+
+        // the epoch schedule, including each epoch's block limit, is committed to the sortition
+        // DB when it is instantiated, and every block is validated against it
+        let mut committed_epochs = SortitionDB::get_stacks_epochs_from_path(&sortdb_path)?;
+        let mut configured_epochs = epochs.to_vec();
+        committed_epochs.sort();
+        configured_epochs.sort();
+        if committed_epochs != configured_epochs {
+            error!(
+                "Sortition DB at {} was instantiated with a different epoch schedule",
+                &sortdb_path;
+                "committed" => ?committed_epochs,
+                "configured" => ?configured_epochs
+            );
+            return Err(DBError::EpochScheduleMismatch.into());
+        }
     }
     if fs::metadata(&chainstate_path).is_ok() {
         info!("Migrating chainstate DB to the latest schema version");
         let db_config = StacksChainState::get_db_config_from_path(&chainstate_path)?;
 
         // this does the migration internally
-        let _ = StacksChainState::open(
+        let (mut chainstate, _) = StacksChainState::open(
             db_config.mainnet,
             db_config.chain_id,
             chainstate_path,
             chainstate_marf_opts,
         )?;
+
+        // the epoch schedule is also committed to the genesis block
+        if let Some(mut committed_epochs) = chainstate.get_committed_epoch_schedule() {
+            let mut configured_epochs = epochs.to_vec();
+            committed_epochs.sort();
+            configured_epochs.sort();
+            if committed_epochs != configured_epochs {
+                error!(
+                    "Chainstate DB at {} was instantiated with a different epoch schedule",
+                    &chainstate_path;
+                    "committed" => ?committed_epochs,
+                    "configured" => ?configured_epochs
+                );
+                return Err(DBError::EpochScheduleMismatch.into());
+            }
+        }
     }
     Ok(())
 }
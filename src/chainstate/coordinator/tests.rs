@@ -33,13 +33,14 @@ use crate::chainstate::burn::operations::*;
 use crate::chainstate::burn::*;
 use crate::chainstate::coordinator::{Error as CoordError, *};
 use crate::chainstate::stacks::db::{
-    accounts::MinerReward, ClarityTx, StacksChainState, StacksHeaderInfo,
+    accounts::MinerReward, ChainStateBootData, ClarityTx, StacksChainState, StacksHeaderInfo,
 };
 use crate::chainstate::stacks::*;
 use crate::clarity_vm::clarity::ClarityConnection;
 use crate::core;
 use crate::core::*;
 use crate::monitoring::increment_stx_blocks_processed_counter;
+use crate::util_lib::db::Error as DBError;
 use clarity::vm::{
     costs::{ExecutionCost, LimitedCostTracker},
     types::PrincipalData,
@@ -834,3 +835,56 @@ fn preprocess_block(
         )
         .unwrap();
 }
+
+#[test]
+fn test_migrate_rejects_different_epoch_schedule() {
+    let path = "/tmp/stacks-node-tests/unit-tests/stacks-blockchain-epoch-schedule";
+    let _r = std::fs::remove_dir_all(path);
+
+    let burnchain = get_burnchain(path, None);
+    let chainstate_path = get_chainstate_path_str(path);
+    let epochs = STACKS_EPOCHS_REGTEST.to_vec();
+    let mut boot_data = ChainStateBootData::new(&burnchain, vec![], None);
+    boot_data.epochs = Some(epochs.clone());
+    let (mut chainstate, _) = StacksChainState::open_and_exec(
+        false,
+        0x80000000,
+        &chainstate_path,
+        Some(&mut boot_data),
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        chainstate.get_committed_epoch_schedule(),
+        Some(epochs.clone())
+    );
+    drop(chainstate);
+
+    let sortdb_path = burnchain.get_db_path();
+    SortitionDB::connect(&sortdb_path, burnchain.first_block_height, &epochs, true).unwrap();
+    migrate_chainstate_dbs(&epochs, &sortdb_path, &chainstate_path, None).unwrap();
+
+    // a different subnet-1 start height
+    let mut moved_epochs = epochs.clone();
+    moved_epochs[2].end_height = SUBNET_EPOCH_1_REGTEST_BURN_HEIGHT + 1;
+    moved_epochs[3].start_height = SUBNET_EPOCH_1_REGTEST_BURN_HEIGHT + 1;
+    // a different subnet-1 block limit
+    let mut limited_epochs = epochs.clone();
+    limited_epochs[3].block_limit.runtime /= 2;
+
+    for other_epochs in [moved_epochs, limited_epochs] {
+        // rebuild the sortition DB with the other schedule, so only the chainstate disagrees
+        std::fs::remove_dir_all(&sortdb_path).unwrap();
+        SortitionDB::connect(
+            &sortdb_path,
+            burnchain.first_block_height,
+            &other_epochs,
+            true,
+        )
+        .unwrap();
+        match migrate_chainstate_dbs(&other_epochs, &sortdb_path, &chainstate_path, None) {
+            Err(CoordError::DBError(DBError::EpochScheduleMismatch)) => {}
+            x => panic!("Expected an epoch schedule mismatch, got {:?}", x),
+        }
+    }
+}
@@ -19,14 +19,13 @@ use std::io;
 use std::io::prelude::*;
 use std::io::{Read, Write};
 
-use sha2::Digest;
-use sha2::Sha512_256;
-
 use crate::burnchains::PrivateKey;
 use crate::burnchains::PublicKey;
+use crate::burnchains::Txid;
 use crate::chainstate::burn::operations::*;
 use crate::chainstate::burn::ConsensusHash;
 use crate::chainstate::burn::*;
+use crate::chainstate::stacks::subnet_codec;
 use crate::chainstate::stacks::Error;
 use crate::chainstate::stacks::*;
 use crate::codec::MAX_MESSAGE_LEN;
@@ -35,6 +34,7 @@ use crate::net::Error as net_error;
 use crate::types::StacksPublicKeyBuffer;
 use stacks_common::util::hash::MerkleTree;
 use stacks_common::util::hash::Sha512Trunc256Sum;
+use stacks_common::util::hash::{MerklePathOrder, MerklePathPoint};
 use stacks_common::util::retry::BoundReader;
 use stacks_common::util::secp256k1::MessageSignature;
 use stacks_common::util::vrf::*;
@@ -84,10 +84,7 @@ impl StacksMessageCodec for StacksBlockHeader {
 impl StacksBlockHeader {
     /// Serialize the transaction without the other signatures, and sign the result.
     pub fn sign(&mut self, privk: &StacksPrivateKey) -> Result<(), net_error> {
-        let mut bytes = vec![];
-        self.serialize(&mut bytes, true)
-            .expect("BUG: failed to serialize to a vec");
-        let sha2 = Sha512Trunc256Sum::from_data(bytes.as_slice());
+        let sha2 = subnet_codec::block_header_signing_hash(self);
         let sig = privk
             .sign(sha2.as_ref())
             .map_err(|se| net_error::SigningError(se.to_string()))?;
@@ -567,6 +564,105 @@ impl StacksBlock {
     }
 }
 
+/// Maximum length of a transaction inclusion proof's Merkle path.  A block's transaction Merkle
+/// tree can never be this deep.
+pub const MAX_TRANSACTION_PROOF_PATH_LEN: u32 = 32;
+
+/// A transaction inclusion proof is serialized as:
+/// * the 32-byte txid
+/// * the transaction's index in the block, as a 4-byte big-endian integer
+/// * the number of path entries, as a 4-byte big-endian integer
+/// * each path entry, from the leaf up to the root, as a 1-byte order (0x02 if the sibling hash
+///   is on the right, and 0x03 if it is on the left) followed by the 32-byte sibling hash.
+impl StacksMessageCodec for TransactionInclusionProof {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        write_next(fd, &self.txid)?;
+        write_next(fd, &self.index)?;
+        write_next(fd, &(self.path.len() as u32))?;
+        for point in self.path.iter() {
+            write_next(fd, &(point.order.clone() as u8))?;
+            write_next(fd, &point.hash)?;
+        }
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(
+        fd: &mut R,
+    ) -> Result<TransactionInclusionProof, codec_error> {
+        let txid: Txid = read_next(fd)?;
+        let index: u32 = read_next(fd)?;
+        let path_len: u32 = read_next(fd)?;
+        if path_len > MAX_TRANSACTION_PROOF_PATH_LEN {
+            return Err(codec_error::DeserializeError(format!(
+                "Transaction inclusion proof path is too long ({})",
+                path_len
+            )));
+        }
+
+        let mut path = Vec::with_capacity(path_len as usize);
+        for _ in 0..path_len {
+            let order_byte: u8 = read_next(fd)?;
+            let order = if order_byte == MerklePathOrder::Left as u8 {
+                MerklePathOrder::Left
+            } else if order_byte == MerklePathOrder::Right as u8 {
+                MerklePathOrder::Right
+            } else {
+                return Err(codec_error::DeserializeError(format!(
+                    "Invalid Merkle path order {}",
+                    order_byte
+                )));
+            };
+            let hash: Sha512Trunc256Sum = read_next(fd)?;
+            path.push(MerklePathPoint { order, hash });
+        }
+
+        Ok(TransactionInclusionProof { txid, index, path })
+    }
+}
+
+impl TransactionInclusionProof {
+    /// Make the inclusion proof for the given transaction in the given block.
+    /// Returns None if the transaction is not in the block.
+    pub fn from_block(block: &StacksBlock, txid: &Txid) -> Option<TransactionInclusionProof> {
+        let index = block.txs.iter().position(|tx| tx.txid() == *txid)?;
+        let txid_vecs = block
+            .txs
+            .iter()
+            .map(|tx| tx.txid().as_bytes().to_vec())
+            .collect();
+        let merkle_tree = MerkleTree::<Sha512Trunc256Sum>::new(&txid_vecs);
+        let path = merkle_tree.path_at_index(index)?;
+        Some(TransactionInclusionProof {
+            txid: txid.clone(),
+            index: index as u32,
+            path,
+        })
+    }
+
+    /// Verify this proof against a block header's `tx_merkle_root`.  The path must hash the
+    /// transaction's leaf up to the root, and must lead to the leaf at the proof's index.
+    pub fn verify(&self, tx_merkle_root: &Sha512Trunc256Sum) -> bool {
+        if self.path.len() > MAX_TRANSACTION_PROOF_PATH_LEN as usize {
+            return false;
+        }
+        // the path must use every bit of the index
+        if u64::from(self.index) >> self.path.len() != 0 {
+            return false;
+        }
+        for (level, point) in self.path.iter().enumerate() {
+            let is_left_child = (u64::from(self.index) >> level) & 1 == 0;
+            if is_left_child != (point.order == MerklePathOrder::Left) {
+                return false;
+            }
+        }
+        MerkleTree::<Sha512Trunc256Sum>::path_verify(
+            &self.txid.as_bytes().to_vec(),
+            &self.path,
+            tx_merkle_root,
+        )
+    }
+}
+
 impl StacksMessageCodec for MessageSignatureList {
     fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
         let sigs = self.signatures();
@@ -614,25 +710,21 @@ impl StacksMessageCodec for StacksMicroblockHeader {
 impl StacksMicroblockHeader {
     pub fn sign(&mut self, privk: &StacksPrivateKey) -> Result<(), net_error> {
         self.miner_signatures = MessageSignatureList::empty();
-        let mut bytes = vec![];
-        self.serialize(&mut bytes, true)
-            .expect("BUG: failed to serialize to a vec");
-
-        let mut digest_bits = [0u8; 32];
-        let mut sha2 = Sha512_256::new();
-
-        sha2.update(&bytes[..]);
-        digest_bits.copy_from_slice(sha2.finalize().as_slice());
+        let digest = subnet_codec::microblock_header_signing_hash(self);
 
         let sig = privk
-            .sign(&digest_bits)
+            .sign(digest.as_bytes())
             .map_err(|se| net_error::SigningError(se.to_string()))?;
 
         self.miner_signatures.add_signature(sig);
         Ok(())
     }
 
-    fn serialize<W: Write>(&self, fd: &mut W, empty_sig: bool) -> Result<(), codec_error> {
+    pub(crate) fn serialize<W: Write>(
+        &self,
+        fd: &mut W,
+        empty_sig: bool,
+    ) -> Result<(), codec_error> {
         write_next(fd, &self.version)?;
         write_next(fd, &self.sequence)?;
         write_next(fd, &self.prev_block)?;
@@ -646,20 +738,12 @@ impl StacksMicroblockHeader {
     }
 
     pub fn check_recover_pubkey(&self) -> Result<Vec<Hash160>, net_error> {
-        let mut digest_bits = [0u8; 32];
-        let mut sha2 = Sha512_256::new();
-
-        let mut bytes = vec![];
-        self.serialize(&mut bytes, true)
-            .expect("BUG: failed to serialize to a vec");
-
-        sha2.update(&bytes[..]);
-        digest_bits.copy_from_slice(sha2.finalize().as_slice());
+        let digest = subnet_codec::microblock_header_signing_hash(self);
 
         let mut hashes = vec![];
         for signature in self.miner_signatures.signatures() {
-            let mut pubk =
-                StacksPublicKey::recover_to_pubkey(&digest_bits, &signature).map_err(|_ve| {
+            let mut pubk = StacksPublicKey::recover_to_pubkey(digest.as_bytes(), &signature)
+                .map_err(|_ve| {
                     test_debug!(
                         "Failed to verify signature: failed to recover public key from {:?}: {:?}",
                         &signature,
@@ -1608,6 +1692,91 @@ mod test {
         }
     }
 
+    #[test]
+    fn transaction_inclusion_proofs() {
+        for num_txs in 1..10 {
+            let block = make_codec_test_block(num_txs);
+            for (i, tx) in block.txs.iter().enumerate() {
+                let proof = TransactionInclusionProof::from_block(&block, &tx.txid()).unwrap();
+                assert_eq!(proof.index, i as u32);
+                assert!(proof.verify(&block.header.tx_merkle_root));
+
+                let mut proof_bytes = vec![];
+                proof.consensus_serialize(&mut proof_bytes).unwrap();
+                let decoded =
+                    TransactionInclusionProof::consensus_deserialize(&mut &proof_bytes[..])
+                        .unwrap();
+                assert_eq!(decoded, proof);
+
+                // wrong root
+                assert!(!proof.verify(&Sha512Trunc256Sum([0x11; 32])));
+
+                // wrong txid
+                let mut bad_proof = proof.clone();
+                bad_proof.txid = Txid([0x22; 32]);
+                assert!(!bad_proof.verify(&block.header.tx_merkle_root));
+
+                // wrong index
+                let mut bad_proof = proof.clone();
+                bad_proof.index = proof.index ^ 1;
+                assert!(!bad_proof.verify(&block.header.tx_merkle_root));
+
+                let mut bad_proof = proof.clone();
+                bad_proof.index = proof.index + (1 << proof.path.len());
+                assert!(!bad_proof.verify(&block.header.tx_merkle_root));
+
+                // tampered sibling
+                let mut bad_proof = proof.clone();
+                bad_proof.path[0].hash = Sha512Trunc256Sum([0x33; 32]);
+                assert!(!bad_proof.verify(&block.header.tx_merkle_root));
+            }
+
+            assert!(TransactionInclusionProof::from_block(&block, &Txid([0x44; 32])).is_none());
+        }
+    }
+
+    #[test]
+    fn codec_transaction_inclusion_proof() {
+        let proof = TransactionInclusionProof {
+            txid: Txid([0x01; 32]),
+            index: 2,
+            path: vec![
+                MerklePathPoint {
+                    order: MerklePathOrder::Left,
+                    hash: Sha512Trunc256Sum([0x02; 32]),
+                },
+                MerklePathPoint {
+                    order: MerklePathOrder::Right,
+                    hash: Sha512Trunc256Sum([0x03; 32]),
+                },
+            ],
+        };
+
+        let mut proof_bytes = vec![];
+        proof_bytes.append(&mut vec![0x01; 32]);
+        // index
+        proof_bytes.append(&mut vec![0x00, 0x00, 0x00, 0x02]);
+        // path length
+        proof_bytes.append(&mut vec![0x00, 0x00, 0x00, 0x02]);
+        proof_bytes.push(0x02);
+        proof_bytes.append(&mut vec![0x02; 32]);
+        proof_bytes.push(0x03);
+        proof_bytes.append(&mut vec![0x03; 32]);
+
+        check_codec_and_corruption::<TransactionInclusionProof>(&proof, &proof_bytes);
+
+        // bad path order
+        let mut bad_bytes = proof_bytes.clone();
+        bad_bytes[40] = 0x04;
+        assert!(TransactionInclusionProof::consensus_deserialize(&mut &bad_bytes[..]).is_err());
+
+        // path too long
+        let mut bad_bytes = vec![0x01; 32];
+        bad_bytes.append(&mut vec![0x00, 0x00, 0x00, 0x00]);
+        bad_bytes.append(&mut vec![0x00, 0x00, 0x00, 0x21]);
+        assert!(TransactionInclusionProof::consensus_deserialize(&mut &bad_bytes[..]).is_err());
+    }
+
     // TODO:
     // * size limits
 }
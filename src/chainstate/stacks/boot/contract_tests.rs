@@ -38,7 +38,7 @@ use clarity::vm::types::{
 };
 use stacks_common::address::AddressHashMode;
 use stacks_common::util::hash::to_hex;
-use stacks_common::util::hash::{Sha256Sum, Sha512Trunc256Sum};
+use stacks_common::util::hash::{MerkleTree, Sha256Sum, Sha512Trunc256Sum};
 
 use crate::util_lib::boot::boot_code_addr;
 use crate::util_lib::boot::boot_code_id;
@@ -213,6 +213,18 @@ fn cost_2_contract_is_arithmetic_only() {
     check_arithmetic_only(BOOT_CODE_COSTS_2);
 }
 
+#[test]
+fn cost_3_contract_is_arithmetic_only() {
+    use crate::chainstate::stacks::boot::BOOT_CODE_COSTS_3;
+    check_arithmetic_only(&BOOT_CODE_COSTS_3);
+}
+
+#[test]
+fn cost_subnet_contract_is_arithmetic_only() {
+    use crate::chainstate::stacks::boot::BOOT_CODE_COSTS_SUBNET;
+    check_arithmetic_only(BOOT_CODE_COSTS_SUBNET);
+}
+
 impl HeadersDB for TestSimHeadersDB {
     fn get_burn_header_hash_for_block(
         &self,
@@ -273,6 +285,13 @@ impl HeadersDB for TestSimHeadersDB {
     fn get_miner_address(&self, _id_bhh: &StacksBlockId) -> Option<StacksAddress> {
         Some(MINER_ADDR.clone())
     }
+    fn get_withdrawal_tree_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+    ) -> Option<MerkleTree<Sha512Trunc256Sum>> {
+        self.get_burn_block_height_for_block(id_bhh)?;
+        Some(MerkleTree::empty())
+    }
 }
 
 #[test]
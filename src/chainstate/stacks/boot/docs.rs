@@ -113,6 +113,21 @@ If your name is in a namespace where names do not expire, then you never need to
         "get-reward-set-pox-address",
     ];
 
+    let bridge_registry_descriptions = vec![
+        ("register-ft", "Register the calling contract as the subnet counterpart of the L1 fungible token contract `l1-contract`.
+Only a contract can register, and only itself: the call must come from the registering contract (usually its deploy body),
+so `contract-caller` must differ from `tx-sender`. Registrations are permanent.
+
+Once registered, deposits into the contract are only applied if they come from `l1-contract` and are fungible token deposits."),
+        ("register-nft", "Register the calling contract as the subnet counterpart of the L1 NFT contract `l1-contract`.
+Only a contract can register, and only itself: the call must come from the registering contract (usually its deploy body),
+so `contract-caller` must differ from `tx-sender`. Registrations are permanent.
+
+Once registered, deposits into the contract are only applied if they come from `l1-contract` and are NFT deposits."),
+        ("get-registration", "Returns the L1 contract and asset type (`\"ft\"` or `\"nft\"`) that `subnet-contract` registered, or none
+if it never registered."),
+    ];
+
     let bns_skip_display = vec![
         "namespace-update-function-price",
         "namespace-revoke-function-price-edition",
@@ -135,6 +150,13 @@ If your name is in a namespace where names do not expire, then you never need to
                 skip_func_display: HashSet::from_iter(bns_skip_display.into_iter()),
             },
         ),
+        (
+            "bridge-registry",
+            ContractSupportDocs {
+                descriptions: HashMap::from_iter(bridge_registry_descriptions.into_iter()),
+                skip_func_display: HashSet::new(),
+            },
+        ),
     ])
 }
 
@@ -36,8 +36,8 @@ use clarity::vm::database::{NULL_BURN_STATE_DB, NULL_HEADER_DB};
 use clarity::vm::representations::ClarityName;
 use clarity::vm::representations::ContractName;
 use clarity::vm::types::{
-    PrincipalData, QualifiedContractIdentifier, SequenceData, StandardPrincipalData, TupleData,
-    TypeSignature, Value,
+    CharType, PrincipalData, QualifiedContractIdentifier, SequenceData, StandardPrincipalData,
+    TupleData, TypeSignature, Value,
 };
 use stacks_common::address::AddressHashMode;
 use stacks_common::util::hash::Hash160;
@@ -57,11 +57,17 @@ const BOOT_CODE_LOCKUP: &'static str = std::include_str!("lockup.clar");
 pub const BOOT_CODE_COSTS: &'static str = std::include_str!("costs.clar");
 pub const BOOT_CODE_COSTS_2: &'static str = std::include_str!("costs-2.clar");
 pub const BOOT_CODE_COSTS_2_TESTNET: &'static str = std::include_str!("costs-2-testnet.clar");
+const BOOT_CODE_COSTS_3_ADDITIONS: &'static str = std::include_str!("costs-3.clar");
+pub const BOOT_CODE_COSTS_SUBNET: &'static str = std::include_str!("costs-subnet.clar");
 const BOOT_CODE_COST_VOTING_MAINNET: &'static str = std::include_str!("cost-voting.clar");
 const BOOT_CODE_BNS: &'static str = std::include_str!("bns.clar");
 const BOOT_CODE_GENESIS: &'static str = std::include_str!("genesis.clar");
+const BOOT_CODE_BRIDGE_REGISTRY: &'static str = std::include_str!("bridge-registry.clar");
 pub const COSTS_1_NAME: &'static str = "costs";
 pub const COSTS_2_NAME: &'static str = "costs-2";
+pub const COSTS_3_NAME: &'static str = "costs-3";
+pub const COSTS_SUBNET_NAME: &'static str = "costs-subnet";
+pub const BRIDGE_REGISTRY_NAME: &'static str = "bridge-registry";
 
 pub mod docs;
 
@@ -71,21 +77,29 @@ lazy_static! {
     pub static ref BOOT_CODE_POX_TESTNET: String =
         format!("{}\n{}", BOOT_CODE_POX_TESTNET_CONSTS, BOOT_CODE_POX_BODY);
     pub static ref BOOT_CODE_COST_VOTING_TESTNET: String = make_testnet_cost_voting();
-    pub static ref STACKS_BOOT_CODE_MAINNET: [(&'static str, &'static str); 6] = [
+    pub static ref BOOT_CODE_COSTS_3: String =
+        format!("{}\n{}", BOOT_CODE_COSTS_2, BOOT_CODE_COSTS_3_ADDITIONS);
+    pub static ref BOOT_CODE_COSTS_3_TESTNET: String = format!(
+        "{}\n{}",
+        BOOT_CODE_COSTS_2_TESTNET, BOOT_CODE_COSTS_3_ADDITIONS
+    );
+    pub static ref STACKS_BOOT_CODE_MAINNET: [(&'static str, &'static str); 7] = [
         ("pox", &BOOT_CODE_POX_MAINNET),
         ("lockup", BOOT_CODE_LOCKUP),
         ("costs", BOOT_CODE_COSTS),
         ("cost-voting", BOOT_CODE_COST_VOTING_MAINNET),
         ("bns", &BOOT_CODE_BNS),
         ("genesis", &BOOT_CODE_GENESIS),
+        (BRIDGE_REGISTRY_NAME, BOOT_CODE_BRIDGE_REGISTRY),
     ];
-    pub static ref STACKS_BOOT_CODE_TESTNET: [(&'static str, &'static str); 6] = [
+    pub static ref STACKS_BOOT_CODE_TESTNET: [(&'static str, &'static str); 7] = [
         ("pox", &BOOT_CODE_POX_TESTNET),
         ("lockup", BOOT_CODE_LOCKUP),
         ("costs", BOOT_CODE_COSTS),
         ("cost-voting", &BOOT_CODE_COST_VOTING_TESTNET),
         ("bns", &BOOT_CODE_BNS),
         ("genesis", &BOOT_CODE_GENESIS),
+        (BRIDGE_REGISTRY_NAME, BOOT_CODE_BRIDGE_REGISTRY),
     ];
 }
 
@@ -103,6 +117,62 @@ fn make_testnet_cost_voting() -> String {
         )
 }
 
+/// The kind of asset a contract registered in .bridge-registry bridges
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BridgedAssetType {
+    Ft,
+    Nft,
+}
+
+impl BridgedAssetType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BridgedAssetType::Ft => "ft",
+            BridgedAssetType::Nft => "nft",
+        }
+    }
+
+    pub fn from_str(asset_type: &str) -> Option<BridgedAssetType> {
+        match asset_type {
+            "ft" => Some(BridgedAssetType::Ft),
+            "nft" => Some(BridgedAssetType::Nft),
+            _ => None,
+        }
+    }
+}
+
+/// The `err` code in the receipt of a deposit that does not match the .bridge-registry
+/// registration of its subnet contract
+pub const BRIDGE_REGISTRY_ERR_DEPOSIT_MISMATCH: u128 = 3;
+
+/// A subnet contract's registration in .bridge-registry: the L1 contract whose assets it
+/// mirrors
+#[derive(Debug, Clone, PartialEq)]
+pub struct BridgeRegistration {
+    pub l1_contract: PrincipalData,
+    pub asset_type: BridgedAssetType,
+}
+
+impl BridgeRegistration {
+    /// Parse the tuple stored in .bridge-registry's `registrations` map
+    pub fn from_tuple(tuple: TupleData) -> Option<BridgeRegistration> {
+        let l1_contract = match tuple.get("l1-contract").ok()? {
+            Value::Principal(principal) => principal.clone(),
+            _ => return None,
+        };
+        let asset_type = match tuple.get("asset-type").ok()? {
+            Value::Sequence(SequenceData::String(CharType::ASCII(ascii))) => {
+                BridgedAssetType::from_str(std::str::from_utf8(&ascii.data).ok()?)?
+            }
+            _ => return None,
+        };
+        Some(BridgeRegistration {
+            l1_contract,
+            asset_type,
+        })
+    }
+}
+
 pub fn make_contract_id(addr: &StacksAddress, name: &str) -> QualifiedContractIdentifier {
     QualifiedContractIdentifier::new(
         StandardPrincipalData::from(addr.clone()),
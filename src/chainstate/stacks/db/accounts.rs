@@ -513,6 +513,34 @@ impl StacksChainState {
         }
     }
 
+    /// Get the miner info of the most recent block mined by `miner` in the fork ending at `tip`,
+    /// if it mined any
+    pub fn get_last_miner_info_in_fork(
+        &self,
+        tip: &StacksBlockId,
+        miner: &StacksAddress,
+    ) -> Result<Option<MinerPaymentSchedule>, Error> {
+        let qry = "SELECT * FROM payments WHERE address = ?1 AND miner = 1 ORDER BY stacks_block_height DESC";
+        let args: &[&dyn ToSql] = &[&miner.to_string()];
+        let rows =
+            query_rows::<MinerPaymentSchedule, _>(self.db(), qry, args).map_err(Error::DBError)?;
+
+        let index_conn = self.index_conn()?;
+        for miner_info in rows.into_iter() {
+            let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                &miner_info.consensus_hash,
+                &miner_info.block_hash,
+            );
+            let ancestor = index_conn
+                .get_ancestor_block_hash(miner_info.stacks_block_height, tip)
+                .map_err(Error::DBError)?;
+            if ancestor == Some(index_block_hash) {
+                return Ok(Some(miner_info));
+            }
+        }
+        Ok(None)
+    }
+
     /// What's the commission for reporting a poison microblock stream?
     fn poison_microblock_commission(coinbase: u128) -> u128 {
         (coinbase * POISON_MICROBLOCK_COMMISSION_FRACTION) / 100
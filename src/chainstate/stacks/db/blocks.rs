@@ -35,15 +35,21 @@ use crate::chainstate::burn::db::sortdb::*;
 use crate::chainstate::burn::operations::*;
 use crate::chainstate::burn::BlockSnapshot;
 use crate::chainstate::stacks::db::accounts::MinerReward;
+use crate::chainstate::stacks::db::deposits::{DepositType, PendingDeposit};
+use crate::chainstate::stacks::db::results::{BlockResults, TxResult};
 use crate::chainstate::stacks::db::transactions::TransactionNonceMismatch;
 use crate::chainstate::stacks::db::*;
 use crate::chainstate::stacks::index::MarfTrieId;
+use crate::chainstate::stacks::system_priority::SystemPriorityPolicy;
 use crate::chainstate::stacks::Error;
 use crate::chainstate::stacks::*;
 use crate::chainstate::stacks::{
     C32_ADDRESS_VERSION_MAINNET_MULTISIG, C32_ADDRESS_VERSION_MAINNET_SINGLESIG,
 };
-use crate::clarity_vm::clarity::{ClarityBlockConnection, ClarityConnection, ClarityInstance};
+use crate::clarity_vm::clarity::Error as clarity_error;
+use crate::clarity_vm::clarity::{
+    ClarityBlockConnection, ClarityConnection, ClarityInstance, ClarityTransactionConnection,
+};
 use crate::codec::MAX_MESSAGE_LEN;
 use crate::codec::{read_next, write_next};
 use crate::core::mempool::MemPoolDB;
@@ -72,6 +78,7 @@ use clarity::vm::types::{
     AssetIdentifier, PrincipalData, QualifiedContractIdentifier, SequenceData,
     StandardPrincipalData, TupleData, TypeSignature, Value,
 };
+use clarity::vm::ClarityVersion;
 use stacks_common::util::get_epoch_time_ms;
 use stacks_common::util::get_epoch_time_secs;
 use stacks_common::util::hash::to_hex;
@@ -79,11 +86,15 @@ use stacks_common::util::retry::BoundReader;
 
 use crate::chainstate::coordinator::BlockEventDispatcher;
 use crate::chainstate::stacks::address::StacksAddressExtensions;
+use crate::chainstate::stacks::boot::{
+    BridgeRegistration, BridgedAssetType, BRIDGE_REGISTRY_ERR_DEPOSIT_MISMATCH,
+    BRIDGE_REGISTRY_NAME,
+};
 use crate::chainstate::stacks::Error::NoSuchBlockError;
 use crate::chainstate::stacks::StacksBlockHeader;
 use crate::chainstate::stacks::StacksMicroblockHeader;
-use crate::clarity_vm::withdrawal::create_withdrawal_merkle_tree;
-use crate::monitoring::set_last_execution_cost_observed;
+use crate::clarity_vm::withdrawal::{check_withdrawal_root, get_withdrawal_leaf_hashes};
+use crate::monitoring::{increment_deposits_processed_counter, set_last_execution_cost_observed};
 use crate::util_lib::boot::boot_code_id;
 use crate::{types, util};
 
@@ -141,6 +152,7 @@ pub enum MemPoolRejection {
     NoSuchPublicFunction,
     BadFunctionArgument(CheckError),
     ContractAlreadyExists(QualifiedContractIdentifier),
+    ContractAnalysisFailed(CheckError),
     PoisonMicroblocksDoNotConflict,
     NoAnchorBlockWithPubkeyHash(Hash160),
     NoAnchorBlockWithPubkeyHashes(Vec<Hash160>),
@@ -155,11 +167,33 @@ pub enum MemPoolRejection {
         principal: PrincipalData,
         is_origin: bool,
     },
+    TooManyPendingTransactions {
+        principal: PrincipalData,
+        max_pending: u64,
+    },
+    ContractDeployTooLarge {
+        size: u64,
+        max_size: u64,
+    },
+    ContractCodeTooLarge {
+        size: u64,
+        max_size: u64,
+    },
+    ContractTooDeep {
+        depth: u64,
+        max_depth: u64,
+    },
+    ContractAnalysisTooSlow {
+        time_ms: u64,
+        max_time_ms: u64,
+    },
     BadTransactionVersion,
     TransferRecipientIsSender(PrincipalData),
     TransferAmountMustBePositive,
     DBError(db_error),
     EstimatorError(EstimatorError),
+    BadSponsorship(String),
+    SponsorshipNotAllowed(String),
     Other(String),
 }
 
@@ -174,6 +208,8 @@ pub struct SetupBlockResult<'a, 'b> {
         Option<(MinerReward, Vec<MinerReward>, MinerReward, MinerRewardInfo)>,
     pub evaluated_epoch: StacksEpochId,
     pub applied_epoch_transition: bool,
+    /// the L1 deposit operations that this block materializes
+    pub materialized_deposits: Vec<PendingDeposit>,
 }
 
 pub struct DummyEventDispatcher;
@@ -247,6 +283,52 @@ impl MemPoolRejection {
                     }),
                 ),
             ),
+            TooManyPendingTransactions {
+                principal,
+                max_pending,
+            } => (
+                "TooManyPendingTransactions",
+                Some(
+                    json!({"message": "Origin has too many pending transactions in mempool",
+                            "principal": principal.to_string(),
+                            "max_pending": max_pending}),
+                ),
+            ),
+            ContractDeployTooLarge { size, max_size } => (
+                "ContractDeployTooLarge",
+                Some(
+                    json!({"message": "Contract deploy exceeds this node's size limit",
+                            "size": size,
+                            "max_size": max_size}),
+                ),
+            ),
+            ContractCodeTooLarge { size, max_size } => (
+                "ContractCodeTooLarge",
+                Some(
+                    json!({"message": "Contract code body exceeds this node's size limit",
+                            "size": size,
+                            "max_size": max_size}),
+                ),
+            ),
+            ContractTooDeep { depth, max_depth } => (
+                "ContractTooDeep",
+                Some(
+                    json!({"message": "Contract expressions are nested deeper than this node allows",
+                            "depth": depth,
+                            "max_depth": max_depth}),
+                ),
+            ),
+            ContractAnalysisTooSlow {
+                time_ms,
+                max_time_ms,
+            } => (
+                "ContractAnalysisTooSlow",
+                Some(
+                    json!({"message": "Contract analysis took longer than this node allows",
+                            "time_ms": time_ms,
+                            "max_time_ms": max_time_ms}),
+                ),
+            ),
             BadTransactionVersion => ("BadTransactionVersion", None),
             FailedToValidate(e) => (
                 "SignatureValidation",
@@ -285,6 +367,8 @@ impl MemPoolRejection {
                 })),
             ),
             EstimatorError(e) => ("EstimatorError", Some(json!({"message": e.to_string()}))),
+            BadSponsorship(s) => ("BadSponsorship", Some(json!({ "message": s }))),
+            SponsorshipNotAllowed(s) => ("SponsorshipNotAllowed", Some(json!({ "message": s }))),
             NoSuchContract => ("NoSuchContract", None),
             NoSuchPublicFunction => ("NoSuchPublicFunction", None),
             BadFunctionArgument(e) => (
@@ -296,6 +380,14 @@ impl MemPoolRejection {
                 "ContractAlreadyExists",
                 Some(json!({ "contract_identifier": id.to_string() })),
             ),
+            ContractAnalysisFailed(e) => (
+                "ContractAnalysisFailed",
+                Some(json!({
+                    "message": e.diagnostic.message,
+                    "code": e.diagnostic.code,
+                    "diagnostic": e.diagnostic,
+                })),
+            ),
             PoisonMicroblocksDoNotConflict => ("PoisonMicroblocksDoNotConflict", None),
             NoAnchorBlockWithPubkeyHash(_h) => ("PoisonMicroblockHasUnknownPubKeyHash", None),
             NoAnchorBlockWithPubkeyHashes(_h) => ("PoisonMicroblockHasUnknownPubKeyHashes", None),
@@ -588,6 +680,7 @@ impl StreamCursor {
         tx_query: MemPoolSyncData,
         max_txs: u64,
         height: u64,
+        depth: u64,
         page_id_opt: Option<Txid>,
     ) -> StreamCursor {
         let last_randomized_txid = page_id_opt.unwrap_or_else(|| {
@@ -603,6 +696,7 @@ impl StreamCursor {
             num_txs: 0,
             max_txs: max_txs,
             height: height,
+            depth,
             corked: false,
         })
     }
@@ -2312,10 +2406,13 @@ impl StacksChainState {
             reward_cycle,
         );
 
+        // pruned blocks and microblock streams can't be served, so they aren't advertised
         let sql = "SELECT staging_blocks.consensus_hash, staging_blocks.processed, staging_blocks.orphaned, staging_microblocks.processed, staging_microblocks.orphaned \
                    FROM staging_blocks LEFT JOIN staging_microblocks \
                    ON staging_blocks.parent_microblock_hash = staging_microblocks.microblock_hash \
-                   WHERE staging_blocks.height >= ?1 AND staging_blocks.height <= ?2";
+                   AND staging_microblocks.index_block_hash NOT IN (SELECT index_block_hash FROM pruned_blocks) \
+                   WHERE staging_blocks.height >= ?1 AND staging_blocks.height <= ?2 \
+                   AND staging_blocks.index_block_hash NOT IN (SELECT index_block_hash FROM pruned_blocks)";
         let args: &[&dyn ToSql] = &[&u64_to_sql(start_height)?, &u64_to_sql(end_height)?];
 
         let mut stmt = self.db().prepare(sql)?;
@@ -4490,23 +4587,28 @@ impl StacksChainState {
                 info!("Applying epoch transition"; "new_epoch_id" => %sortition_epoch.epoch_id, "old_epoch_id" => %stacks_parent_epoch);
                 // this assertion failing means that the _parent_ block was invalid: this is bad and should panic.
                 assert!(stacks_parent_epoch < sortition_epoch.epoch_id, "The SortitionDB believes the epoch is earlier than this Stacks block's parent: sortition db epoch = {}, parent epoch = {}", sortition_epoch.epoch_id, stacks_parent_epoch);
-                // time for special cases:
-                match stacks_parent_epoch {
-                    StacksEpochId::Epoch10 => {
-                        panic!("Clarity VM believes it was running in 1.0: pre-Clarity.")
-                    }
-                    StacksEpochId::Epoch20 => {
-                        assert_eq!(
-                            sortition_epoch.epoch_id,
-                            StacksEpochId::Epoch2_05,
-                            "Should only transition from Epoch20 to Epoch2_05"
-                        );
-                        receipts.push(clarity_tx.block.initialize_epoch_2_05()?);
-                        applied = true;
-                    }
-                    StacksEpochId::Epoch2_05 => {
-                        panic!("No defined transition from Epoch2_05 forward")
-                    }
+                // apply each intervening transition in turn, since a subnet whose first block
+                //  is mined late enough starts out several epochs ahead of genesis.
+                let mut current_epoch = stacks_parent_epoch;
+                while current_epoch < sortition_epoch.epoch_id {
+                    // time for special cases:
+                    current_epoch = match current_epoch {
+                        StacksEpochId::Epoch10 => {
+                            panic!("Clarity VM believes it was running in 1.0: pre-Clarity.")
+                        }
+                        StacksEpochId::Epoch20 => {
+                            receipts.push(clarity_tx.block.initialize_epoch_2_05()?);
+                            StacksEpochId::Epoch2_05
+                        }
+                        StacksEpochId::Epoch2_05 => {
+                            receipts.extend(clarity_tx.block.initialize_epoch_subnet_1()?);
+                            StacksEpochId::EpochSubnet1
+                        }
+                        StacksEpochId::EpochSubnet1 => {
+                            panic!("No defined transition from EpochSubnet1 forward")
+                        }
+                    };
+                    applied = true;
                 }
             }
         }
@@ -4643,184 +4745,696 @@ impl StacksChainState {
         all_receipts
     }
 
+    /// Group deposit operations into the batches that will each be applied to the Clarity state
+    /// in a single transaction.  A batch holds consecutive operations from the same L1 block, and
+    /// at most `max_batch_size` of them.  Operations keep their L1 order.
+    fn make_deposit_batches<Op, F>(
+        operations: Vec<Op>,
+        max_batch_size: usize,
+        burn_header_hash: F,
+    ) -> Vec<Vec<Op>>
+    where
+        F: Fn(&Op) -> BurnchainHeaderHash,
+    {
+        let max_batch_size = cmp::max(max_batch_size, 1);
+        let mut batches: Vec<Vec<Op>> = vec![];
+        for op in operations.into_iter() {
+            let new_batch = match batches.last() {
+                Some(batch) => {
+                    batch.len() >= max_batch_size
+                        || burn_header_hash(&batch[0]) != burn_header_hash(&op)
+                }
+                None => true,
+            };
+            if new_batch {
+                batches.push(vec![]);
+            }
+            batches
+                .last_mut()
+                .expect("BUG: no deposit batch to extend")
+                .push(op);
+        }
+        batches
+    }
+
+    /// Invoke the deposit handler of the contract a deposit credited, if it credited a contract
+    /// that defines one.  Returns the handler's events.  The handler runs with a budget of
+    /// `DEPOSIT_HANDLER_COST_LIMIT`, and a failing handler's effects, including those of a
+    /// handler that runs out of budget, are rolled back without affecting the deposit.
+    fn run_deposit_handler(
+        tx: &mut ClarityTransactionConnection,
+        txid: &Txid,
+        recipient: &PrincipalData,
+        asset: Option<PrincipalData>,
+        amount: u128,
+    ) -> Vec<StacksTransactionEvent> {
+        let contract_id = match recipient {
+            PrincipalData::Contract(contract_id) => contract_id,
+            PrincipalData::Standard(_) => return vec![],
+        };
+        let mainnet = tx.is_mainnet();
+        let epoch = tx.get_epoch();
+        let handler_tracker = tx.with_clarity_db(|db| {
+            LimitedCostTracker::new_mid_block(mainnet, DEPOSIT_HANDLER_COST_LIMIT, db, epoch)
+                .map_err(|e| CheckError::from(e).into())
+        });
+        let handler_tracker = match handler_tracker {
+            Ok(tracker) => tracker,
+            Err(e) => {
+                warn!("Failed to instantiate the deposit handler's cost tracker";
+                      "error" => ?e,
+                      "txid" => %txid,
+                      "contract" => %contract_id);
+                return vec![];
+            }
+        };
+        let (result, _) = tx.with_temporary_cost_tracker(handler_tracker, |tx| {
+            tx.run_deposit_handler(recipient, contract_id, asset, amount)
+        });
+        match result {
+            Ok(Some((_, _, events))) => events,
+            Ok(None) => vec![],
+            Err(e) => {
+                info!("Deposit handler error.";
+                      "error" => ?e,
+                      "txid" => %txid,
+                      "contract" => %contract_id);
+                vec![]
+            }
+        }
+    }
+
+    /// Look up the registration of `subnet_contract_id` in .bridge-registry.  Returns None if
+    /// the contract is not registered, or if the chain predates the registry.
+    pub fn get_bridge_registration(
+        tx: &mut ClarityTransactionConnection,
+        mainnet: bool,
+        subnet_contract_id: &QualifiedContractIdentifier,
+    ) -> Option<BridgeRegistration> {
+        let registry_contract_id = boot_code_id(BRIDGE_REGISTRY_NAME, mainnet);
+        let entry = tx
+            .with_clarity_db(|db| {
+                let res = db.fetch_entry_unknown_descriptor(
+                    &registry_contract_id,
+                    "registrations",
+                    &Value::Principal(subnet_contract_id.clone().into()),
+                )?;
+                Ok(res)
+            })
+            .ok()?;
+        match entry.expect_optional()? {
+            Value::Tuple(tuple) => BridgeRegistration::from_tuple(tuple),
+            _ => None,
+        }
+    }
+
+    /// Check a deposit of an `asset_type` asset from the L1 contract `l1_contract_id` into the
+    /// subnet contract `subnet_contract_id` against the subnet contract's registration in
+    /// .bridge-registry.  Deposits into unregistered contracts always pass.
+    fn check_bridge_registration(
+        tx: &mut ClarityTransactionConnection,
+        mainnet: bool,
+        subnet_contract_id: &QualifiedContractIdentifier,
+        l1_contract_id: &QualifiedContractIdentifier,
+        asset_type: BridgedAssetType,
+    ) -> Result<(), String> {
+        let registration =
+            match StacksChainState::get_bridge_registration(tx, mainnet, subnet_contract_id) {
+                Some(registration) => registration,
+                None => return Ok(()),
+            };
+        if registration.l1_contract != PrincipalData::Contract(l1_contract_id.clone()) {
+            return Err(format!(
+                "{} is registered as the counterpart of {}, not {}",
+                subnet_contract_id, registration.l1_contract, l1_contract_id
+            ));
+        }
+        if registration.asset_type != asset_type {
+            return Err(format!(
+                "{} is registered as an {} contract, not an {} contract",
+                subnet_contract_id,
+                registration.asset_type.as_str(),
+                asset_type.as_str()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check the asset withdrawals in a transaction's receipt against .bridge-registry: an asset
+    /// withdrawn from a registered contract must be of the contract's registered asset type.  The
+    /// withdrawn asset is always defined by the withdrawing contract, so the registered L1
+    /// contract is the one the L1 will release it from.
+    pub fn check_withdrawal_registrations(
+        tx: &mut ClarityTransactionConnection,
+        mainnet: bool,
+        receipt: &StacksTransactionReceipt,
+    ) -> Result<(), String> {
+        for event in receipt.events.iter() {
+            let (asset_identifier, asset_type) = match event {
+                StacksTransactionEvent::FTEvent(FTEventType::FTWithdrawEvent(data)) => {
+                    (&data.asset_identifier, BridgedAssetType::Ft)
+                }
+                StacksTransactionEvent::NFTEvent(NFTEventType::NFTWithdrawEvent(data)) => {
+                    (&data.asset_identifier, BridgedAssetType::Nft)
+                }
+                _ => continue,
+            };
+            let subnet_contract_id = &asset_identifier.contract_identifier;
+            let registration =
+                match StacksChainState::get_bridge_registration(tx, mainnet, subnet_contract_id) {
+                    Some(registration) => registration,
+                    None => continue,
+                };
+            if registration.asset_type != asset_type {
+                return Err(format!(
+                    "{} is registered as an {} contract, but withdraws the {} asset {}",
+                    subnet_contract_id,
+                    registration.asset_type.as_str(),
+                    asset_type.as_str(),
+                    asset_identifier.asset_name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The receipt of a deposit that does not match .bridge-registry.  Its result is
+    /// `(err u3)`, and none of its bridged assets are credited: they stay with the L1 contract.
+    /// `events` are the effects of the deposit that are applied regardless.
+    fn make_mismatched_deposit_receipt(
+        txid: Txid,
+        events: Vec<StacksTransactionEvent>,
+    ) -> StacksTransactionReceipt {
+        StacksTransactionReceipt {
+            transaction: TransactionOrigin::Burn(txid),
+            events,
+            result: Value::err_uint(BRIDGE_REGISTRY_ERR_DEPOSIT_MISMATCH),
+            post_condition_aborted: false,
+            stx_burned: 0,
+            contract_analysis: None,
+            execution_cost: ExecutionCost::zero(),
+            microblock_header: None,
+            tx_index: 0,
+        }
+    }
+
+    /// Process one deposit under a copy of `deposit_tracker`, which has a whole block's budget.
+    /// Deposits are not transactions: each one gets the budget to itself, and its cost is not
+    /// charged to the block, so that a burst of deposits is never cut off by the block limit
+    /// and never crowds out the block's transactions.  `todo` returns the deposit's receipt, if
+    /// it has one, and this records the deposit's cost in it.
+    fn with_deposit_cost_tracker<F>(
+        tx: &mut ClarityTransactionConnection,
+        deposit_tracker: &LimitedCostTracker,
+        todo: F,
+    ) -> Option<StacksTransactionReceipt>
+    where
+        F: FnOnce(&mut ClarityTransactionConnection) -> Option<StacksTransactionReceipt>,
+    {
+        let (receipt, tracker) = tx.with_temporary_cost_tracker(deposit_tracker.clone(), todo);
+        receipt.map(|mut receipt| {
+            receipt.execution_cost = tracker.get_total();
+            receipt
+        })
+    }
+
+    /// Process the L1 claims of STX withdrawals: the claimed uSTX are no longer pending
+    /// withdrawal.  A withdrawal is released at most once per fork, so claims that the L1 observer
+    /// replays are ignored.
+    pub fn process_withdraw_stx_ops(clarity_tx: &mut ClarityTx, operations: Vec<WithdrawStxOp>) {
+        if operations.is_empty() {
+            return;
+        }
+        clarity_tx.connection().as_transaction(|tx| {
+            tx.with_clarity_db(|db| {
+                for op in operations.iter() {
+                    let withdrawal_leaf_hash = match op.withdrawal_leaf_hash {
+                        Some(ref leaf_hash) => leaf_hash,
+                        None => continue,
+                    };
+                    let released = db.release_claimed_stx_withdrawal(
+                        withdrawal_leaf_hash,
+                        &op.recipient,
+                        op.amount,
+                    );
+                    debug!("Processed L1 claim of STX withdrawal";
+                           "txid" => %op.txid,
+                           "recipient" => %op.recipient,
+                           "withdrawal_leaf_hash" => %withdrawal_leaf_hash,
+                           "released" => released);
+                }
+                Ok(())
+            })
+            .expect("FATAL: failed to release claimed STX withdrawals")
+        });
+    }
+
     /// Process any deposit STX operations that haven't been processed in this
-    /// subnet fork yet.
+    /// subnet fork yet.  The deposits from each L1 block are applied in batches of at most
+    /// `max_batch_size` operations, each within a single Clarity transaction.
     pub fn process_deposit_stx_ops(
         clarity_tx: &mut ClarityTx,
         operations: Vec<DepositStxOp>,
+        max_batch_size: usize,
     ) -> Vec<StacksTransactionReceipt> {
+        let batches = StacksChainState::make_deposit_batches(operations, max_batch_size, |op| {
+            op.burn_header_hash.clone()
+        });
         let (all_receipts, _) =
             clarity_tx.with_temporary_cost_tracker(LimitedCostTracker::new_free(), |clarity_tx| {
-                operations
-                    .into_iter()
-                    .filter_map(|deposit_stx_op| {
-                        let DepositStxOp {
-                            txid,
-                            amount,
-                            sender,
-                            ..
-                        } = deposit_stx_op;
-                        // call the corresponding deposit function in the subnet contract
-                        let result = clarity_tx.connection().as_transaction(|tx| {
-                            StacksChainState::account_credit(tx, &sender, amount as u64);
-                            StacksTransactionEvent::STXEvent(STXEventType::STXMintEvent(
-                                STXMintEventData {
-                                    recipient: sender,
+                let mut all_receipts = vec![];
+                for batch in batches.into_iter() {
+                    let (receipts, batch_amount) = clarity_tx.connection().as_transaction(|tx| {
+                        let mut batch_amount = 0u128;
+                        let receipts: Vec<_> = batch
+                            .into_iter()
+                            .map(|deposit_stx_op| {
+                                let DepositStxOp {
+                                    txid,
                                     amount,
-                                },
-                            ))
-                        });
-                        // deposits increment the STX liquidity in the layer 2
-                        clarity_tx.increment_ustx_liquid_supply(amount);
-
-                        Some(StacksTransactionReceipt {
-                            transaction: TransactionOrigin::Burn(txid),
-                            events: vec![result],
-                            result: Value::okay_true(),
-                            post_condition_aborted: false,
-                            stx_burned: 0,
-                            contract_analysis: None,
-                            execution_cost: ExecutionCost::zero(),
-                            microblock_header: None,
-                            tx_index: 0,
-                        })
-                    })
-                    .collect()
+                                    sender,
+                                    ..
+                                } = deposit_stx_op;
+                                StacksChainState::account_credit(tx, &sender, amount as u64);
+                                batch_amount = batch_amount
+                                    .checked_add(amount)
+                                    .expect("FATAL: deposited uSTX overflowed");
+                                let mut events = vec![StacksTransactionEvent::STXEvent(
+                                    STXEventType::STXMintEvent(STXMintEventData {
+                                        recipient: sender.clone(),
+                                        amount,
+                                    }),
+                                )];
+                                events.extend(StacksChainState::run_deposit_handler(
+                                    tx, &txid, &sender, None, amount,
+                                ));
+
+                                StacksTransactionReceipt {
+                                    transaction: TransactionOrigin::Burn(txid),
+                                    events,
+                                    result: Value::okay_true(),
+                                    post_condition_aborted: false,
+                                    stx_burned: 0,
+                                    contract_analysis: None,
+                                    execution_cost: ExecutionCost::zero(),
+                                    microblock_header: None,
+                                    tx_index: 0,
+                                }
+                            })
+                            .collect();
+                        (receipts, batch_amount)
+                    });
+                    // deposits increment the STX liquidity in the layer 2
+                    clarity_tx.increment_ustx_liquid_supply(batch_amount);
+                    all_receipts.extend(receipts);
+                }
+                all_receipts
             });
 
         all_receipts
     }
 
     /// Process any deposit fungible token operations that haven't been processed in this
-    /// subnet fork yet.
+    /// subnet fork yet.  The deposits from each L1 block are applied in batches of at most
+    /// `max_batch_size` operations, each within a single Clarity transaction.  A deposit whose
+    /// contract call fails is rolled back on its own, without affecting the rest of its batch.
     pub fn process_deposit_ft_ops(
         clarity_tx: &mut ClarityTx,
         operations: Vec<DepositFtOp>,
+        max_batch_size: usize,
     ) -> Vec<StacksTransactionReceipt> {
-        let cost_so_far = clarity_tx.cost_so_far();
-        // return valid receipts
-        operations
-            .into_iter()
-            .filter_map(|deposit_ft_op| {
-                let DepositFtOp {
-                    txid,
-                    burn_header_hash,
-                    subnet_contract_id,
-                    subnet_function_name,
-                    amount,
-                    sender,
-                    ..
-                } = deposit_ft_op;
-                // call the corresponding deposit function in the subnet contract
-                let result = clarity_tx.connection().as_transaction(|tx| {
-                    tx.run_contract_call(
-                        &sender.clone(),
-                        &subnet_contract_id,
-                        &*subnet_function_name,
-                        &[Value::UInt(amount), Value::Principal(sender)],
-                        |_, _| false,
-                    )
-                });
-                let mut execution_cost = clarity_tx.cost_so_far();
-                execution_cost
-                    .sub(&cost_so_far)
-                    .expect("BUG: cost declined between executions");
-
-                match result {
-                    Ok((value, _, events)) => Some(StacksTransactionReceipt {
-                        transaction: TransactionOrigin::Burn(txid),
-                        events,
-                        result: value,
-                        post_condition_aborted: false,
-                        stx_burned: 0,
-                        contract_analysis: None,
-                        execution_cost,
-                        microblock_header: None,
-                        tx_index: 0,
-                    }),
-                    Err(e) => {
-                        info!("DepositFt op processing error.";
-                              "error" => ?e,
-                              "txid" => %txid,
-                              "burn_block" => %burn_header_hash);
-                        None
-                    }
-                }
-            })
-            .collect()
+        let mainnet = clarity_tx.config.mainnet;
+        let deposit_tracker = clarity_tx.make_unspent_cost_tracker();
+        let batches = StacksChainState::make_deposit_batches(operations, max_batch_size, |op| {
+            op.burn_header_hash.clone()
+        });
+        let mut all_receipts = vec![];
+        for batch in batches.into_iter() {
+            let receipts: Vec<_> = clarity_tx.connection().as_transaction(|tx| {
+                // return valid receipts
+                batch
+                    .into_iter()
+                    .filter_map(|deposit_ft_op| {
+                        StacksChainState::with_deposit_cost_tracker(tx, &deposit_tracker, |tx| {
+                            let DepositFtOp {
+                                txid,
+                                burn_header_hash,
+                                l1_contract_id,
+                                subnet_contract_id,
+                                subnet_function_name,
+                                amount,
+                                sender,
+                                ..
+                            } = deposit_ft_op;
+                            if let Err(reason) = StacksChainState::check_bridge_registration(
+                                tx,
+                                mainnet,
+                                &subnet_contract_id,
+                                &l1_contract_id,
+                                BridgedAssetType::Ft,
+                            ) {
+                                info!("DepositFt op does not match the bridge registry.";
+                                  "reason" => %reason,
+                                  "txid" => %txid,
+                                  "burn_block" => %burn_header_hash);
+                                return Some(StacksChainState::make_mismatched_deposit_receipt(
+                                    txid,
+                                    vec![],
+                                ));
+                            }
+                            // call the corresponding deposit function in the subnet contract
+                            let result = tx.run_contract_call(
+                                &sender.clone(),
+                                &subnet_contract_id,
+                                &*subnet_function_name,
+                                &[Value::UInt(amount), Value::Principal(sender.clone())],
+                                |_, _| false,
+                            );
+                            let result = result.map(|(value, assets, mut events)| {
+                                if matches!(value, Value::Response(ref data) if data.committed) {
+                                    events.extend(StacksChainState::run_deposit_handler(
+                                        tx,
+                                        &txid,
+                                        &sender,
+                                        Some(subnet_contract_id.clone().into()),
+                                        amount,
+                                    ));
+                                }
+                                (value, assets, events)
+                            });
+
+                            match result {
+                                Ok((value, _, events)) => Some(StacksTransactionReceipt {
+                                    transaction: TransactionOrigin::Burn(txid),
+                                    events,
+                                    result: value,
+                                    post_condition_aborted: false,
+                                    stx_burned: 0,
+                                    contract_analysis: None,
+                                    execution_cost: ExecutionCost::zero(),
+                                    microblock_header: None,
+                                    tx_index: 0,
+                                }),
+                                Err(e) => {
+                                    info!("DepositFt op processing error.";
+                                      "error" => ?e,
+                                      "txid" => %txid,
+                                      "burn_block" => %burn_header_hash);
+                                    None
+                                }
+                            }
+                        })
+                    })
+                    .collect()
+            });
+            all_receipts.extend(receipts);
+        }
+        all_receipts
     }
 
     /// Process any deposit NFT operations that haven't been processed in this
-    /// subnet fork yet.
+    /// subnet fork yet.  The deposits from each L1 block are applied in batches of at most
+    /// `max_batch_size` operations, each within a single Clarity transaction.  A deposit whose
+    /// contract call fails is rolled back on its own, without affecting the rest of its batch.
     pub fn process_deposit_nft_ops(
         clarity_tx: &mut ClarityTx,
         operations: Vec<DepositNftOp>,
+        max_batch_size: usize,
     ) -> Vec<StacksTransactionReceipt> {
-        let cost_so_far = clarity_tx.cost_so_far();
-        // return valid receipts
-        operations
-            .into_iter()
-            .filter_map(|deposit_nft_op| {
-                let DepositNftOp {
-                    txid,
-                    burn_header_hash,
-                    subnet_contract_id,
-                    subnet_function_name,
-                    id,
-                    sender,
-                    ..
-                } = deposit_nft_op;
-                let result = clarity_tx.connection().as_transaction(|tx| {
-                    tx.run_contract_call(
-                        &sender.clone(),
-                        &subnet_contract_id,
-                        &*subnet_function_name,
-                        &[Value::UInt(id), Value::Principal(sender)],
-                        |_, _| false,
-                    )
-                });
-                let mut execution_cost = clarity_tx.cost_so_far();
-                execution_cost
-                    .sub(&cost_so_far)
-                    .expect("BUG: cost declined between executions");
-
-                match result {
-                    Ok((value, _, events)) => Some(StacksTransactionReceipt {
-                        transaction: TransactionOrigin::Burn(txid),
-                        events,
-                        result: value,
-                        post_condition_aborted: false,
-                        stx_burned: 0,
-                        contract_analysis: None,
-                        execution_cost,
-                        microblock_header: None,
-                        tx_index: 0,
-                    }),
-                    Err(e) => {
-                        info!("DepositNft op processing error.";
-                              "error" => ?e,
-                              "txid" => %txid,
-                              "burn_block" => %burn_header_hash);
-                        None
-                    }
-                }
-            })
-            .collect()
+        let mainnet = clarity_tx.config.mainnet;
+        let deposit_tracker = clarity_tx.make_unspent_cost_tracker();
+        let batches = StacksChainState::make_deposit_batches(operations, max_batch_size, |op| {
+            op.burn_header_hash.clone()
+        });
+        let mut all_receipts = vec![];
+        for batch in batches.into_iter() {
+            let receipts: Vec<_> = clarity_tx.connection().as_transaction(|tx| {
+                // return valid receipts
+                batch
+                    .into_iter()
+                    .filter_map(|deposit_nft_op| {
+                        StacksChainState::with_deposit_cost_tracker(tx, &deposit_tracker, |tx| {
+                            let DepositNftOp {
+                                txid,
+                                burn_header_hash,
+                                l1_contract_id,
+                                subnet_contract_id,
+                                subnet_function_name,
+                                id,
+                                sender,
+                                ..
+                            } = deposit_nft_op;
+                            if let Err(reason) = StacksChainState::check_bridge_registration(
+                                tx,
+                                mainnet,
+                                &subnet_contract_id,
+                                &l1_contract_id,
+                                BridgedAssetType::Nft,
+                            ) {
+                                info!("DepositNft op does not match the bridge registry.";
+                                  "reason" => %reason,
+                                  "txid" => %txid,
+                                  "burn_block" => %burn_header_hash);
+                                return Some(StacksChainState::make_mismatched_deposit_receipt(
+                                    txid,
+                                    vec![],
+                                ));
+                            }
+                            let result = tx.run_contract_call(
+                                &sender.clone(),
+                                &subnet_contract_id,
+                                &*subnet_function_name,
+                                &[Value::UInt(id), Value::Principal(sender.clone())],
+                                |_, _| false,
+                            );
+                            let result = result.map(|(value, assets, mut events)| {
+                                if matches!(value, Value::Response(ref data) if data.committed) {
+                                    events.extend(StacksChainState::run_deposit_handler(
+                                        tx,
+                                        &txid,
+                                        &sender,
+                                        Some(subnet_contract_id.clone().into()),
+                                        id,
+                                    ));
+                                }
+                                (value, assets, events)
+                            });
+
+                            match result {
+                                Ok((value, _, events)) => Some(StacksTransactionReceipt {
+                                    transaction: TransactionOrigin::Burn(txid),
+                                    events,
+                                    result: value,
+                                    post_condition_aborted: false,
+                                    stx_burned: 0,
+                                    contract_analysis: None,
+                                    execution_cost: ExecutionCost::zero(),
+                                    microblock_header: None,
+                                    tx_index: 0,
+                                }),
+                                Err(e) => {
+                                    info!("DepositNft op processing error.";
+                                      "error" => ?e,
+                                      "txid" => %txid,
+                                      "burn_block" => %burn_header_hash);
+                                    None
+                                }
+                            }
+                        })
+                    })
+                    .collect()
+            });
+            all_receipts.extend(receipts);
+        }
+        all_receipts
+    }
+
+    /// Process any multi-asset deposit operations that haven't been processed in this
+    /// subnet fork yet.  The deposits from each L1 block are applied in batches of at most
+    /// `max_batch_size` operations, each within a single Clarity transaction.  Each deposit is
+    /// materialized atomically: if any of its deposit functions fails or returns an `err`, none of
+    /// its assets (nor its STX) are credited, without affecting the rest of its batch.  A deposit
+    /// that does not match .bridge-registry only credits its STX.
+    pub fn process_deposit_multi_asset_ops(
+        clarity_tx: &mut ClarityTx,
+        operations: Vec<DepositMultiAssetOp>,
+        max_batch_size: usize,
+    ) -> Vec<StacksTransactionReceipt> {
+        let mainnet = clarity_tx.config.mainnet;
+        let deposit_tracker = clarity_tx.make_unspent_cost_tracker();
+        let batches = StacksChainState::make_deposit_batches(operations, max_batch_size, |op| {
+            op.burn_header_hash.clone()
+        });
+        let mut all_receipts = vec![];
+        for batch in batches.into_iter() {
+            let (receipts, batch_amount) = clarity_tx.connection().as_transaction(|tx| {
+                let mut batch_amount = 0u128;
+                let receipts: Vec<_> = batch
+                    .into_iter()
+                    .filter_map(|deposit_op| {
+                        StacksChainState::with_deposit_cost_tracker(tx, &deposit_tracker, |tx| {
+                            let DepositMultiAssetOp {
+                                txid,
+                                burn_header_hash,
+                                stx_amount,
+                                assets,
+                                sender,
+                            } = deposit_op;
+                            // none of the deposit's assets are credited if any of them doesn't
+                            // match, but its STX are
+                            let mismatch = assets.iter().find_map(|asset| {
+                                StacksChainState::check_bridge_registration(
+                                    tx,
+                                    mainnet,
+                                    asset.subnet_contract_id(),
+                                    asset.l1_contract_id(),
+                                    asset.asset_type(),
+                                )
+                                .err()
+                            });
+                            if let Some(reason) = mismatch {
+                                info!("DepositMultiAsset op does not match the bridge registry.";
+                                  "reason" => %reason,
+                                  "txid" => %txid,
+                                  "burn_block" => %burn_header_hash);
+                                let mut events = vec![];
+                                if stx_amount > 0 {
+                                    StacksChainState::account_credit(
+                                        tx,
+                                        &sender,
+                                        stx_amount as u64,
+                                    );
+                                    batch_amount = batch_amount
+                                        .checked_add(stx_amount)
+                                        .expect("FATAL: deposited uSTX overflowed");
+                                    events.push(StacksTransactionEvent::STXEvent(
+                                        STXEventType::STXMintEvent(STXMintEventData {
+                                            recipient: sender.clone(),
+                                            amount: stx_amount,
+                                        }),
+                                    ));
+                                    events.extend(StacksChainState::run_deposit_handler(
+                                        tx, &txid, &sender, None, stx_amount,
+                                    ));
+                                }
+                                return Some(StacksChainState::make_mismatched_deposit_receipt(
+                                    txid, events,
+                                ));
+                            }
+                            // call the deposit function of each asset in its subnet contract
+                            let deposit_calls: Vec<_> = assets
+                                .iter()
+                                .map(|asset| {
+                                    (
+                                        asset.subnet_contract_id().clone(),
+                                        asset.subnet_function_name().clone(),
+                                        vec![
+                                            Value::UInt(asset.amount_or_id()),
+                                            Value::Principal(sender.clone()),
+                                        ],
+                                    )
+                                })
+                                .collect();
+                            let result =
+                                tx.run_multi_asset_deposit(&sender, stx_amount, &deposit_calls);
+                            let result = match result {
+                                Ok((value, _, events)) => {
+                                    batch_amount = batch_amount
+                                        .checked_add(stx_amount)
+                                        .expect("FATAL: deposited uSTX overflowed");
+                                    let mut all_events = vec![];
+                                    if stx_amount > 0 {
+                                        all_events.push(StacksTransactionEvent::STXEvent(
+                                            STXEventType::STXMintEvent(STXMintEventData {
+                                                recipient: sender.clone(),
+                                                amount: stx_amount,
+                                            }),
+                                        ));
+                                        all_events.extend(StacksChainState::run_deposit_handler(
+                                            tx, &txid, &sender, None, stx_amount,
+                                        ));
+                                    }
+                                    all_events.extend(events);
+                                    for asset in assets.iter() {
+                                        all_events.extend(StacksChainState::run_deposit_handler(
+                                            tx,
+                                            &txid,
+                                            &sender,
+                                            Some(asset.subnet_contract_id().clone().into()),
+                                            asset.amount_or_id(),
+                                        ));
+                                    }
+                                    Ok((value, all_events))
+                                }
+                                // a deposit function returned an `err`, so nothing was credited
+                                Err(clarity_error::AbortedByCallback(Some(value), ..)) => {
+                                    Ok((value, vec![]))
+                                }
+                                Err(e) => Err(e),
+                            };
+
+                            match result {
+                                Ok((value, events)) => Some(StacksTransactionReceipt {
+                                    transaction: TransactionOrigin::Burn(txid),
+                                    events,
+                                    result: value,
+                                    post_condition_aborted: false,
+                                    stx_burned: 0,
+                                    contract_analysis: None,
+                                    execution_cost: ExecutionCost::zero(),
+                                    microblock_header: None,
+                                    tx_index: 0,
+                                }),
+                                Err(e) => {
+                                    info!("DepositMultiAsset op processing error.";
+                                      "error" => ?e,
+                                      "txid" => %txid,
+                                      "burn_block" => %burn_header_hash);
+                                    None
+                                }
+                            }
+                        })
+                    })
+                    .collect();
+                (receipts, batch_amount)
+            });
+            // deposits increment the STX liquidity in the layer 2
+            clarity_tx.increment_ustx_liquid_supply(batch_amount);
+            all_receipts.extend(receipts);
+        }
+        all_receipts
     }
 
     /// Process a single anchored block.
     /// Return the fees and burns.
-    fn process_block_transactions(
+    /// If there is a `system_priority` policy, the block's cost may only exceed the policy's normal
+    /// budget through system priority transactions.
+    pub(crate) fn process_block_transactions(
         clarity_tx: &mut ClarityTx,
         block: &StacksBlock,
         mut tx_index: u32,
+        system_priority: Option<&SystemPriorityPolicy>,
     ) -> Result<(u128, u128, Vec<StacksTransactionReceipt>), Error> {
         let mut fees = 0u128;
         let mut burns = 0u128;
         let mut receipts = vec![];
+        let normal_budget = match (system_priority, clarity_tx.block_limit()) {
+            (Some(policy), Some(block_limit)) => Some(policy.normal_budget(&block_limit)),
+            _ => None,
+        };
         for tx in block.txs.iter() {
             let (tx_fee, mut tx_receipt) =
                 StacksChainState::process_transaction(clarity_tx, tx, false)?;
+            if let (Some(policy), Some(normal_budget)) = (system_priority, normal_budget.as_ref()) {
+                if policy.is_held_to_normal_budget(tx)
+                    && clarity_tx.cost_so_far().exceeds(normal_budget)
+                {
+                    return Err(Error::InvalidStacksBlock(format!(
+                        "Transaction {} uses the block budget reserved for system priority transactions",
+                        tx.txid()
+                    )));
+                }
+            }
             fees = fees.checked_add(tx_fee as u128).expect("Fee overflow");
             tx_receipt.tx_index = tx_index;
             burns = burns
@@ -5035,6 +5649,55 @@ impl StacksChainState {
             &burn_tip,
             SortitionDB::get_deposit_nft_ops,
         )?;
+        let deposit_multi_asset_ops = SortitionDB::get_ops_between(
+            conn,
+            &parent_block_burn_block,
+            &burn_tip,
+            SortitionDB::get_deposit_multi_asset_ops,
+        )?;
+        let withdraw_stx_ops = SortitionDB::get_ops_between(
+            conn,
+            &parent_block_burn_block,
+            &burn_tip,
+            SortitionDB::get_withdraw_stx_ops,
+        )?;
+
+        // skip deposits which an ancestor block already materialized, in case the L1 observer
+        // replayed them.
+        let mut materialized_deposits = vec![];
+        let deposit_stx_ops = StacksChainState::filter_materialized_deposits(
+            &mut chainstate_tx.tx,
+            &parent_index_hash,
+            DepositType::Stx,
+            deposit_stx_ops,
+            |op| (op.txid.clone(), op.burn_header_hash.clone()),
+            &mut materialized_deposits,
+        )?;
+        let deposit_ft_ops = StacksChainState::filter_materialized_deposits(
+            &mut chainstate_tx.tx,
+            &parent_index_hash,
+            DepositType::Ft,
+            deposit_ft_ops,
+            |op| (op.txid.clone(), op.burn_header_hash.clone()),
+            &mut materialized_deposits,
+        )?;
+        let deposit_nft_ops = StacksChainState::filter_materialized_deposits(
+            &mut chainstate_tx.tx,
+            &parent_index_hash,
+            DepositType::Nft,
+            deposit_nft_ops,
+            |op| (op.txid.clone(), op.burn_header_hash.clone()),
+            &mut materialized_deposits,
+        )?;
+        let deposit_multi_asset_ops = StacksChainState::filter_materialized_deposits(
+            &mut chainstate_tx.tx,
+            &parent_index_hash,
+            DepositType::MultiAsset,
+            deposit_multi_asset_ops,
+            |op| (op.txid.clone(), op.burn_header_hash.clone()),
+            &mut materialized_deposits,
+        )?;
+        let deposit_batch_size = chainstate_tx.deposit_batch_size;
 
         // load the execution cost of the parent block if the executor is the follower.
         // otherwise, if the executor is the miner, only load the parent cost if the parent
@@ -5060,6 +5723,9 @@ impl StacksChainState {
             ExecutionCost::zero()
         };
 
+        // only blocks that are appended to the chain have a state diff worth keeping
+        let record_state_diffs = miner_id_opt.is_none() && chainstate_tx.record_state_diffs;
+
         let mut clarity_tx = StacksChainState::chainstate_block_begin(
             chainstate_tx,
             clarity_instance,
@@ -5070,6 +5736,10 @@ impl StacksChainState {
             &MINER_BLOCK_HEADER_HASH,
         );
 
+        if record_state_diffs {
+            clarity_tx.record_write_set();
+        }
+
         let evaluated_epoch = clarity_tx.get_epoch();
         clarity_tx.reset_cost(parent_block_cost.clone());
 
@@ -5149,21 +5819,44 @@ impl StacksChainState {
         let (applied_epoch_transition, mut tx_receipts) =
             StacksChainState::process_epoch_transition(&mut clarity_tx, burn_tip_height)?;
 
+        // make this block's L1 height available to `l1-block-height`.  Blocks from before the
+        // subnet epoch must not write the key, or their state roots would change.
+        if clarity_tx.get_epoch() >= StacksEpochId::EpochSubnet1 {
+            clarity_tx.connection().as_transaction(|tx| {
+                tx.with_clarity_db(|db| {
+                    db.set_current_l1_block_height(burn_tip_height);
+                    Ok(())
+                })
+                .expect("FATAL: failed to set L1 block height")
+            });
+        }
+
         tx_receipts.extend(StacksChainState::process_deposit_stx_ops(
             &mut clarity_tx,
             deposit_stx_ops,
+            deposit_batch_size,
         ));
 
         // Process asset deposits
         tx_receipts.extend(StacksChainState::process_deposit_ft_ops(
             &mut clarity_tx,
             deposit_ft_ops,
+            deposit_batch_size,
         ));
         tx_receipts.extend(StacksChainState::process_deposit_nft_ops(
             &mut clarity_tx,
             deposit_nft_ops,
+            deposit_batch_size,
+        ));
+        tx_receipts.extend(StacksChainState::process_deposit_multi_asset_ops(
+            &mut clarity_tx,
+            deposit_multi_asset_ops,
+            deposit_batch_size,
         ));
 
+        // release the STX of claimed withdrawals from escrow
+        StacksChainState::process_withdraw_stx_ops(&mut clarity_tx, withdraw_stx_ops);
+
         Ok(SetupBlockResult {
             clarity_tx,
             tx_receipts,
@@ -5174,6 +5867,7 @@ impl StacksChainState {
             matured_miner_rewards_opt,
             evaluated_epoch,
             applied_epoch_transition,
+            materialized_deposits,
         })
     }
 
@@ -5277,6 +5971,7 @@ impl StacksChainState {
         );
 
         let mainnet = chainstate_tx.get_config().mainnet;
+        let system_priority = chainstate_tx.system_priority.clone();
         let next_block_height = block.header.total_work.work;
 
         // NEW in 2.05
@@ -5362,6 +6057,7 @@ impl StacksChainState {
             matured_miner_rewards_opt,
             evaluated_epoch,
             applied_epoch_transition,
+            materialized_deposits,
         } = StacksChainState::setup_block(
             chainstate_tx,
             clarity_instance,
@@ -5392,6 +6088,8 @@ impl StacksChainState {
             parent_burn_block_timestamp,
             clarity_commit,
             withdrawal_tree,
+            write_set_opt,
+            computed_state_root,
         ) = {
             // get previous burn block stats
             let (parent_burn_block_hash, parent_burn_block_height, parent_burn_block_timestamp) =
@@ -5467,6 +6165,7 @@ impl StacksChainState {
                     &mut clarity_tx,
                     &block,
                     microblock_txs_receipts.len() as u32,
+                    system_priority.as_ref(),
                 ) {
                     Err(e) => {
                         let msg = format!("Invalid Stacks block {}: {:?}", block.block_hash(), &e);
@@ -5549,6 +6248,7 @@ impl StacksChainState {
                 warn!("{}", &msg);
 
                 clarity_tx.rollback_block();
+                chainstate_tx.computed_state_root = Some(root_hash);
                 return Err(Error::InvalidStacksBlock(msg));
             }
 
@@ -5556,26 +6256,32 @@ impl StacksChainState {
                    "microblock cost" => %microblock_execution_cost,
                    "block cost" => %block_cost);
 
-            // Check withdrawal state merkle root
-            // Process withdrawal events
-            let withdrawal_tree =
-                create_withdrawal_merkle_tree(&mut tx_receipts, block.header.total_work.work);
-            let withdrawal_root_hash = withdrawal_tree.root();
-
-            if withdrawal_root_hash != block.header.withdrawal_merkle_root {
-                let msg = format!(
-                    "Block {} withdrawal root mismatch: expected {}, got {}",
-                    block.block_hash(),
-                    withdrawal_root_hash,
-                    block.header.withdrawal_merkle_root
-                );
-                info!("{}", &msg);
+            // Recompute the withdrawal Merkle tree from the block's withdraw events, and check
+            // it against the root committed to in the header
+            let withdrawal_leaves =
+                get_withdrawal_leaf_hashes(&mut tx_receipts, block.header.total_work.work);
+            let withdrawal_tree = match check_withdrawal_root(
+                withdrawal_leaves,
+                &block.header.withdrawal_merkle_root,
+                None,
+            ) {
+                Ok(withdrawal_tree) => withdrawal_tree,
+                Err(mismatch) => {
+                    warn!("Block withdrawal root mismatch";
+                          "block_hash" => %block.block_hash(),
+                          "height" => block.header.total_work.work,
+                          "expected_withdrawal_root" => %mismatch.expected_root,
+                          "computed_withdrawal_root" => %mismatch.computed_root,
+                          "computed_withdrawal_leaves" => ?mismatch.computed_leaves);
+                    let msg = format!("Block {}: {}", block.block_hash(), &mismatch);
 
-                clarity_tx.rollback_block();
-                return Err(Error::InvalidStacksBlock(msg));
-            }
+                    clarity_tx.rollback_block();
+                    return Err(Error::InvalidStacksBlock(msg));
+                }
+            };
 
             // good to go!
+            let write_set_opt = clarity_tx.take_write_set();
             let clarity_commit =
                 clarity_tx.precommit_to_block(chain_tip_consensus_hash, &block.block_hash());
 
@@ -5622,8 +6328,11 @@ impl StacksChainState {
                 parent_burn_block_timestamp,
                 clarity_commit,
                 withdrawal_tree,
+                write_set_opt,
+                root_hash,
             )
         };
+        chainstate_tx.computed_state_root = Some(computed_state_root);
 
         let microblock_tail_opt = match microblocks.len() {
             0 => None,
@@ -5649,9 +6358,42 @@ impl StacksChainState {
         )
         .expect("FATAL: failed to advance chain tip");
 
+        StacksChainState::insert_materialized_deposits(
+            &chainstate_tx.tx,
+            &new_tip.index_block_hash(),
+            new_tip.stacks_block_height,
+            &materialized_deposits,
+        )?;
+
+        StacksChainState::insert_block_costs(
+            &chainstate_tx.tx,
+            &new_tip.index_block_hash(),
+            &block_execution_cost,
+            &microblock_execution_cost,
+            &block_limit,
+        )?;
+
+        StacksChainState::insert_transaction_receipts(
+            &chainstate_tx.tx,
+            &new_tip.index_block_hash(),
+            new_tip.stacks_block_height,
+            &tx_receipts,
+        )?;
+
+        if let Some(write_set) = write_set_opt {
+            StacksChainState::insert_block_state_diff(
+                &chainstate_tx.tx,
+                &new_tip.index_block_hash(),
+                &write_set,
+            )?;
+        }
+
         chainstate_tx.log_transactions_processed(&new_tip.index_block_hash(), &tx_receipts);
 
         set_last_execution_cost_observed(&block_execution_cost, &block_limit);
+        for deposit in materialized_deposits.iter() {
+            increment_deposits_processed_counter(deposit.deposit_type.as_str());
+        }
 
         let epoch_receipt = StacksEpochReceipt {
             header: new_tip,
@@ -5813,6 +6555,7 @@ impl StacksChainState {
         dispatcher_opt: Option<&'a T>,
     ) -> Result<(Option<StacksEpochReceipt>, Option<TransactionPayload>), Error> {
         let blocks_path = self.blocks_path.clone();
+        let record_block_results = self.record_block_results;
         let (mut chainstate_tx, clarity_instance) = self.chainstate_tx_begin()?;
 
         // this is a transaction against both the headers and staging blocks databases!
@@ -6028,6 +6771,22 @@ impl StacksChainState {
                     }
                 }
 
+                if record_block_results {
+                    let results = BlockResults {
+                        index_block_hash: StacksBlockId::new(
+                            &next_staging_block.consensus_hash,
+                            &block.block_hash(),
+                        ),
+                        block_height: parent_header_info.stacks_block_height + 1,
+                        error: Some(format!("{}", &e)),
+                        state_index_root: block.header.state_index_root.clone(),
+                        computed_state_root: chainstate_tx.computed_state_root.clone(),
+                        withdrawal_root: block.header.withdrawal_merkle_root.to_hex(),
+                        tx_results: vec![],
+                    };
+                    StacksChainState::insert_block_results(&chainstate_tx, &results)?;
+                }
+
                 chainstate_tx.commit().map_err(Error::DBError)?;
 
                 return Err(e);
@@ -6104,6 +6863,23 @@ impl StacksChainState {
             true,
         )?;
 
+        if record_block_results {
+            let results = BlockResults {
+                index_block_hash: epoch_receipt.header.index_block_hash(),
+                block_height: epoch_receipt.header.stacks_block_height,
+                error: None,
+                state_index_root: block.header.state_index_root.clone(),
+                computed_state_root: chainstate_tx.computed_state_root.clone(),
+                withdrawal_root: block.header.withdrawal_merkle_root.to_hex(),
+                tx_results: epoch_receipt
+                    .tx_receipts
+                    .iter()
+                    .map(TxResult::from_receipt)
+                    .collect(),
+            };
+            StacksChainState::insert_block_results(&chainstate_tx, &results)?;
+        }
+
         // this will panic if the Clarity commit fails.
         clarity_commit.commit();
         chainstate_tx.commit()
@@ -6340,7 +7116,87 @@ impl StacksChainState {
                 }
             }
             Err(e) => Err(e),
-        }
+        }?;
+
+        self.check_mempool_smart_contract(&current_tip, tx)
+    }
+
+    /// Analyze a smart contract when it is submitted, so that a contract with unchecked
+    /// intermediary responses is rejected with a diagnostic pointing at the offending expressions,
+    /// instead of failing once it is mined.  Other analysis failures are left to block assembly,
+    /// since they may depend on contracts that have not been mined yet.
+    fn check_mempool_smart_contract(
+        &mut self,
+        tip: &StacksBlockId,
+        tx: &StacksTransaction,
+    ) -> Result<(), MemPoolRejection> {
+        let smart_contract = match tx.payload {
+            TransactionPayload::SmartContract(ref smart_contract) => smart_contract,
+            _ => return Ok(()),
+        };
+        let contract_id = QualifiedContractIdentifier::new(
+            tx.origin_address().into(),
+            smart_contract.name.clone(),
+        );
+        let contract_code_str = smart_contract.code_body.to_string();
+        let mainnet = self.mainnet;
+
+        // the analysis is charged to the block budget once the contract is mined, so it is
+        //  bounded by the limit of the block it would build on
+        let analysis_limit = match StacksChainState::get_block_costs(self.db(), tip) {
+            Ok(Some(costs)) => costs.block_limit,
+            _ => BLOCK_LIMIT_MAINNET_205.clone(),
+        };
+
+        self.with_read_only_clarity_tx(&NULL_BURN_STATE_DB, tip, |conn| {
+            let allow_unchecked_intermediary_responses =
+                conn.with_clarity_db_readonly(|db| db.get_allow_unchecked_intermediary_responses());
+            let epoch = conn.get_epoch();
+            let clarity_version = ClarityVersion::default_for_epoch(epoch);
+            let mut cost_track = match conn.with_clarity_db_readonly(|db| {
+                LimitedCostTracker::new_mid_block(mainnet, analysis_limit, db, epoch)
+            }) {
+                Ok(cost_track) => cost_track,
+                // the cost contracts are loaded again when the transaction is mined
+                Err(_) => return Ok(()),
+            };
+            conn.with_analysis_db_readonly(|db| {
+                let mut contract_ast =
+                    match build_ast(&contract_id, &contract_code_str, &mut cost_track) {
+                        Ok(x) => x,
+                        // parse errors are reported when the transaction is mined
+                        Err(_) => return Ok(()),
+                    };
+                match run_analysis(
+                    &contract_id,
+                    &mut contract_ast.expressions,
+                    db,
+                    false,
+                    allow_unchecked_intermediary_responses,
+                    clarity_version,
+                    cost_track,
+                ) {
+                    Ok(contract_analysis) => {
+                        for warning in contract_analysis.warnings.iter() {
+                            warn!("Contract analysis warning for submitted transaction";
+                                  "contract_id" => %contract_id,
+                                  "txid" => %tx.txid(),
+                                  "message" => %warning.message,
+                                  "code" => ?warning.code,
+                                  "spans" => ?warning.spans);
+                        }
+                        Ok(())
+                    }
+                    Err((e, _)) => match e.err {
+                        CheckErrors::UncheckedIntermediaryResponses => {
+                            Err(MemPoolRejection::ContractAnalysisFailed(e))
+                        }
+                        _ => Ok(()),
+                    },
+                }
+            })
+        })
+        .unwrap_or(Ok(()))
     }
 
     /// Given an outstanding clarity connection, can we append the tx to the chain state?
@@ -6624,7 +7480,7 @@ pub mod test {
 
     use super::*;
 
-    use clarity::vm::test_util::TEST_BURN_STATE_DB;
+    use clarity::vm::test_util::{UnitTestBurnStateDB, TEST_BURN_STATE_DB};
     use clarity::vm::types::StacksAddressExtensions;
     use serde_json;
 
@@ -11405,11 +12261,102 @@ pub mod test {
         ];
 
         // process ops
-        let processed_ops = StacksChainState::process_deposit_ft_ops(&mut conn, ops);
+        let processed_ops =
+            StacksChainState::process_deposit_ft_ops(&mut conn, ops, DEFAULT_DEPOSIT_BATCH_SIZE);
 
         assert_eq!(processed_ops.len(), 1);
     }
 
+    #[test]
+    fn test_deposit_burst_exceeds_block_limit() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "test_deposit_burst_exceeds_block_limit");
+
+        let privk_user = StacksPrivateKey::from_hex(
+            "027682d2f7b05c3801fe4467883ab4cff0568b5e36412b5289e83ea5b519de8a01",
+        )
+        .unwrap();
+        let auth_user = TransactionAuth::from_p2pkh(&privk_user).unwrap();
+        let addr_publisher = auth_user.origin().address_testnet();
+
+        let mut conn = chainstate.block_begin(
+            &TEST_BURN_STATE_DB,
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            &ConsensusHash([1u8; 20]),
+            &BlockHeaderHash([1u8; 32]),
+        );
+
+        let subnet_simple_ft = "
+        (define-fungible-token ft-token)
+
+        (define-public (subnet-deposit-ft-token (amount uint) (recipient principal))
+          (ft-mint? ft-token amount recipient)
+        )
+        ";
+        let mut subnet_deposit_contract_tx = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            auth_user.clone(),
+            TransactionPayload::new_smart_contract("subnet-deposit-contract", subnet_simple_ft)
+                .unwrap(),
+        );
+        subnet_deposit_contract_tx.chain_id = 0x80000000;
+        subnet_deposit_contract_tx.set_tx_fee(0);
+        let mut signer = StacksTransactionSigner::new(&subnet_deposit_contract_tx);
+        signer.sign_origin(&privk_user).unwrap();
+        let signed_contract_tx = signer.get_tx().unwrap();
+        StacksChainState::process_transaction(&mut conn, &signed_contract_tx, false).unwrap();
+
+        let make_op = |i: u8| DepositFtOp {
+            txid: Txid([i; 32]),
+            burn_header_hash: BurnchainHeaderHash([0; 32]),
+            l1_contract_id: QualifiedContractIdentifier::local("l1-contract").unwrap(),
+            subnet_contract_id: QualifiedContractIdentifier::new(
+                StandardPrincipalData::from(addr_publisher),
+                ContractName::from("subnet-deposit-contract"),
+            ),
+            subnet_function_name: ClarityName::from("subnet-deposit-ft-token"),
+            name: "ft-token".to_string(),
+            amount: 1,
+            sender: PrincipalData::from(addr_publisher),
+        };
+
+        // find out what one deposit costs, and limit the block to a little more than two
+        let receipts = StacksChainState::process_deposit_ft_ops(
+            &mut conn,
+            vec![make_op(1)],
+            DEFAULT_DEPOSIT_BATCH_SIZE,
+        );
+        assert_eq!(receipts.len(), 1);
+        let deposit_cost = receipts[0].execution_cost.clone();
+        assert!(deposit_cost.runtime > 0);
+        let mut block_limit = deposit_cost.clone();
+        block_limit.add(&deposit_cost).unwrap();
+        block_limit
+            .add(&ExecutionCost {
+                write_length: 1,
+                write_count: 1,
+                read_length: 1,
+                read_count: 1,
+                runtime: 1,
+            })
+            .unwrap();
+        conn.set_block_limit(block_limit);
+        let block_cost = conn.cost_so_far();
+
+        // a burst of deposits that together cost far more than the block limit are all
+        // materialized, and none of them is charged to the block
+        let ops: Vec<_> = (2..12).map(make_op).collect();
+        let receipts =
+            StacksChainState::process_deposit_ft_ops(&mut conn, ops, DEFAULT_DEPOSIT_BATCH_SIZE);
+        assert_eq!(receipts.len(), 10);
+        for receipt in receipts.iter() {
+            assert_eq!(receipt.result, Value::okay_true());
+            assert_eq!(receipt.execution_cost, deposit_cost);
+        }
+        assert_eq!(conn.cost_so_far(), block_cost);
+    }
+
     #[test]
     fn test_process_deposit_nft_ops() {
         let mut chainstate =
@@ -11505,11 +12452,344 @@ pub mod test {
         ];
 
         // process ops
-        let processed_ops = StacksChainState::process_deposit_nft_ops(&mut conn, ops);
+        let processed_ops =
+            StacksChainState::process_deposit_nft_ops(&mut conn, ops, DEFAULT_DEPOSIT_BATCH_SIZE);
 
         assert_eq!(processed_ops.len(), 1);
     }
 
+    #[test]
+    fn test_process_deposit_ops_bridge_registry() {
+        let mut chainstate = instantiate_chainstate(
+            false,
+            0x80000000,
+            "test_process_deposit_ops_bridge_registry",
+        );
+
+        let privk_user = StacksPrivateKey::from_hex(
+            "027682d2f7b05c3801fe4467883ab4cff0568b5e36412b5289e83ea5b519de8a01",
+        )
+        .unwrap();
+        let auth_user = TransactionAuth::from_p2pkh(&privk_user).unwrap();
+        let addr_publisher = auth_user.origin().address_testnet();
+
+        let mut conn = chainstate.block_begin(
+            &TEST_BURN_STATE_DB,
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            &ConsensusHash([1u8; 20]),
+            &BlockHeaderHash([1u8; 32]),
+        );
+
+        let l1_contract_id = QualifiedContractIdentifier::new(
+            StandardPrincipalData::from(addr_publisher),
+            ContractName::from("l1-contract"),
+        );
+        let other_l1_contract_id = QualifiedContractIdentifier::new(
+            StandardPrincipalData::from(addr_publisher),
+            ContractName::from("other-l1-contract"),
+        );
+        let subnet_contract_id = QualifiedContractIdentifier::new(
+            StandardPrincipalData::from(addr_publisher),
+            ContractName::from("subnet-deposit-contract"),
+        );
+
+        // this contract accepts both kinds of deposit, but registers itself as an ft contract
+        let subnet_registered_ft = format!(
+            "
+        (define-fungible-token ft-token)
+        (define-non-fungible-token nft-token uint)
+
+        (define-public (subnet-deposit-ft-token (amount uint) (recipient principal))
+          (ft-mint? ft-token amount recipient)
+        )
+
+        (define-public (subnet-deposit-nft-token (id uint) (recipient principal))
+          (nft-mint? nft-token id recipient)
+        )
+
+        (define-public (subnet-withdraw-ft-token (amount uint) (sender principal))
+          (ft-withdraw? ft-token amount sender)
+        )
+
+        (define-public (subnet-withdraw-nft-token (id uint) (sender principal))
+          (nft-withdraw? nft-token id sender)
+        )
+
+        (unwrap-panic (contract-call? '{}.bridge-registry register-ft '{}))
+        ",
+            boot_code_id(BRIDGE_REGISTRY_NAME, false).issuer,
+            l1_contract_id
+        );
+
+        let mut subnet_deposit_contract_tx = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            auth_user.clone(),
+            TransactionPayload::new_smart_contract(
+                "subnet-deposit-contract",
+                &subnet_registered_ft,
+            )
+            .unwrap(),
+        );
+
+        subnet_deposit_contract_tx.chain_id = 0x80000000;
+        subnet_deposit_contract_tx.set_tx_fee(0);
+
+        let mut signer = StacksTransactionSigner::new(&subnet_deposit_contract_tx);
+        signer.sign_origin(&privk_user).unwrap();
+
+        let signed_contract_tx = signer.get_tx().unwrap();
+
+        // publish contract on the subnet
+        let _ =
+            StacksChainState::process_transaction(&mut conn, &signed_contract_tx, false).unwrap();
+
+        let registration = conn.connection().as_transaction(|tx| {
+            StacksChainState::get_bridge_registration(tx, false, &subnet_contract_id)
+        });
+        assert_eq!(
+            registration,
+            Some(BridgeRegistration {
+                l1_contract: PrincipalData::Contract(l1_contract_id.clone()),
+                asset_type: BridgedAssetType::Ft,
+            })
+        );
+
+        // only contracts can register, so a call straight from the user is rejected
+        let mut register_tx = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            auth_user.clone(),
+            TransactionPayload::new_contract_call(
+                boot_code_id(BRIDGE_REGISTRY_NAME, false).issuer.into(),
+                BRIDGE_REGISTRY_NAME,
+                "register-nft",
+                vec![Value::Principal(PrincipalData::Contract(
+                    other_l1_contract_id.clone(),
+                ))],
+            )
+            .unwrap(),
+        );
+
+        register_tx.chain_id = 0x80000000;
+        register_tx.set_tx_fee(0);
+        register_tx.set_origin_nonce(1);
+
+        let mut signer = StacksTransactionSigner::new(&register_tx);
+        signer.sign_origin(&privk_user).unwrap();
+
+        let signed_register_tx = signer.get_tx().unwrap();
+        let (_, receipt) =
+            StacksChainState::process_transaction(&mut conn, &signed_register_tx, false).unwrap();
+        assert_eq!(receipt.result, Value::error(Value::Int(2)).unwrap());
+
+        let user_registration = conn.connection().as_transaction(|tx| {
+            StacksChainState::get_bridge_registration(
+                tx,
+                false,
+                &QualifiedContractIdentifier::transient(),
+            )
+        });
+        assert_eq!(user_registration, None);
+
+        let ft_ops = vec![
+            // this op matches the registration
+            DepositFtOp {
+                txid: Txid([1; 32]),
+                burn_header_hash: BurnchainHeaderHash([0; 32]),
+                l1_contract_id: l1_contract_id.clone(),
+                subnet_contract_id: subnet_contract_id.clone(),
+                subnet_function_name: ClarityName::from("subnet-deposit-ft-token"),
+                name: "ft-token".to_string(),
+                amount: 2,
+                sender: PrincipalData::from(addr_publisher),
+            },
+            // this op names a different L1 contract than the registered one
+            DepositFtOp {
+                txid: Txid([2; 32]),
+                burn_header_hash: BurnchainHeaderHash([0; 32]),
+                l1_contract_id: other_l1_contract_id.clone(),
+                subnet_contract_id: subnet_contract_id.clone(),
+                subnet_function_name: ClarityName::from("subnet-deposit-ft-token"),
+                name: "ft-token".to_string(),
+                amount: 2,
+                sender: PrincipalData::from(addr_publisher),
+            },
+        ];
+
+        let processed_ops =
+            StacksChainState::process_deposit_ft_ops(&mut conn, ft_ops, DEFAULT_DEPOSIT_BATCH_SIZE);
+        assert_eq!(processed_ops.len(), 2);
+        assert_eq!(processed_ops[0].result, Value::okay_true());
+        // the mismatched deposit is reported as failed, and nothing is minted
+        assert_eq!(
+            processed_ops[1].transaction,
+            TransactionOrigin::Burn(Txid([2; 32]))
+        );
+        assert_eq!(
+            processed_ops[1].result,
+            Value::err_uint(BRIDGE_REGISTRY_ERR_DEPOSIT_MISMATCH)
+        );
+        assert!(processed_ops[1].events.is_empty());
+        let ft_balance = conn.with_clarity_db_readonly(|db| {
+            db.get_ft_balance(
+                &subnet_contract_id,
+                "ft-token",
+                &PrincipalData::from(addr_publisher),
+                None,
+            )
+            .unwrap()
+        });
+        assert_eq!(ft_balance, 2);
+
+        // the contract is registered for fts, so nft deposits into it fail
+        let nft_ops = vec![DepositNftOp {
+            txid: Txid([3; 32]),
+            burn_header_hash: BurnchainHeaderHash([0; 32]),
+            l1_contract_id: l1_contract_id.clone(),
+            subnet_contract_id: subnet_contract_id.clone(),
+            subnet_function_name: ClarityName::from("subnet-deposit-nft-token"),
+            id: 2,
+            sender: PrincipalData::from(addr_publisher),
+        }];
+
+        let processed_ops = StacksChainState::process_deposit_nft_ops(
+            &mut conn,
+            nft_ops,
+            DEFAULT_DEPOSIT_BATCH_SIZE,
+        );
+        assert_eq!(processed_ops.len(), 1);
+        assert_eq!(
+            processed_ops[0].result,
+            Value::err_uint(BRIDGE_REGISTRY_ERR_DEPOSIT_MISMATCH)
+        );
+        assert!(processed_ops[0].events.is_empty());
+
+        // a mismatched multi-asset deposit still credits its STX, but none of its assets
+        let multi_asset_ops = vec![DepositMultiAssetOp {
+            txid: Txid([4; 32]),
+            burn_header_hash: BurnchainHeaderHash([0; 32]),
+            stx_amount: 100,
+            assets: vec![
+                DepositAsset::Ft {
+                    l1_contract_id: l1_contract_id.clone(),
+                    subnet_contract_id: subnet_contract_id.clone(),
+                    subnet_function_name: ClarityName::from("subnet-deposit-ft-token"),
+                    name: "ft-token".to_string(),
+                    amount: 5,
+                },
+                DepositAsset::Nft {
+                    l1_contract_id: l1_contract_id.clone(),
+                    subnet_contract_id: subnet_contract_id.clone(),
+                    subnet_function_name: ClarityName::from("subnet-deposit-nft-token"),
+                    id: 3,
+                },
+            ],
+            sender: PrincipalData::from(addr_publisher),
+        }];
+
+        let orig_balance = StacksChainState::get_account(&mut conn, &addr_publisher.into())
+            .stx_balance
+            .amount_unlocked;
+        let liquid_ustx = conn.with_clarity_db_readonly(|db| db.get_total_liquid_ustx());
+
+        let processed_ops = StacksChainState::process_deposit_multi_asset_ops(
+            &mut conn,
+            multi_asset_ops,
+            DEFAULT_DEPOSIT_BATCH_SIZE,
+        );
+        assert_eq!(processed_ops.len(), 1);
+        assert_eq!(
+            processed_ops[0].result,
+            Value::err_uint(BRIDGE_REGISTRY_ERR_DEPOSIT_MISMATCH)
+        );
+        // only the STX mint
+        assert_eq!(processed_ops[0].events.len(), 1);
+
+        let account = StacksChainState::get_account(&mut conn, &addr_publisher.into());
+        assert_eq!(account.stx_balance.amount_unlocked, orig_balance + 100);
+        assert_eq!(
+            conn.with_clarity_db_readonly(|db| db.get_total_liquid_ustx()),
+            liquid_ustx + 100
+        );
+        let ft_balance = conn.with_clarity_db_readonly(|db| {
+            db.get_ft_balance(
+                &subnet_contract_id,
+                "ft-token",
+                &PrincipalData::from(addr_publisher),
+                None,
+            )
+            .unwrap()
+        });
+        assert_eq!(ft_balance, 2);
+
+        let make_call = |function_name: &str, amount_or_id: u128, nonce: u64| {
+            let mut call_tx = StacksTransaction::new(
+                TransactionVersion::Testnet,
+                auth_user.clone(),
+                TransactionPayload::new_contract_call(
+                    addr_publisher,
+                    "subnet-deposit-contract",
+                    function_name,
+                    vec![
+                        Value::UInt(amount_or_id),
+                        Value::Principal(PrincipalData::from(addr_publisher)),
+                    ],
+                )
+                .unwrap(),
+            );
+            call_tx.chain_id = 0x80000000;
+            call_tx.set_tx_fee(0);
+            call_tx.set_origin_nonce(nonce);
+
+            let mut signer = StacksTransactionSigner::new(&call_tx);
+            signer.sign_origin(&privk_user).unwrap();
+            signer.get_tx().unwrap()
+        };
+
+        // withdrawing the registered asset type is fine
+        let (_, receipt) = StacksChainState::process_transaction(
+            &mut conn,
+            &make_call("subnet-withdraw-ft-token", 1, 2),
+            false,
+        )
+        .unwrap();
+        assert_eq!(receipt.result, Value::okay_true());
+
+        // but the contract can't withdraw nfts, even ones it minted itself
+        let (_, receipt) = StacksChainState::process_transaction(
+            &mut conn,
+            &make_call("subnet-deposit-nft-token", 1, 3),
+            false,
+        )
+        .unwrap();
+        assert_eq!(receipt.result, Value::okay_true());
+
+        match StacksChainState::process_transaction(
+            &mut conn,
+            &make_call("subnet-withdraw-nft-token", 1, 4),
+            false,
+        ) {
+            Err(chainstate_error::InvalidStacksTransaction(msg, false)) => {
+                assert!(
+                    msg.contains("does not match the bridge registry"),
+                    "{}",
+                    msg
+                );
+            }
+            res => panic!("Expected the nft withdrawal to be rejected, got {:?}", res),
+        }
+        let nft_owner = conn.with_clarity_db_readonly(|db| {
+            db.get_nft_owner(
+                &subnet_contract_id,
+                "nft-token",
+                &Value::UInt(1),
+                &TypeSignature::UIntType,
+            )
+            .unwrap()
+        });
+        assert_eq!(nft_owner, PrincipalData::from(addr_publisher));
+    }
+
     #[test]
     fn test_process_deposit_stx_ops() {
         let mut chainstate =
@@ -11545,12 +12825,351 @@ pub mod test {
         ];
 
         // process ops
-        let processed_ops = StacksChainState::process_deposit_stx_ops(&mut conn, ops);
+        let processed_ops =
+            StacksChainState::process_deposit_stx_ops(&mut conn, ops, DEFAULT_DEPOSIT_BATCH_SIZE);
         assert_eq!(processed_ops.len(), 1);
 
         // check that the account now has 2 more micro STX
         let account = StacksChainState::get_account(&mut conn, &addr_publisher.into());
         assert_eq!(orig_balance + 2, account.stx_balance.amount_unlocked);
+
+        // a burst of deposits across two L1 blocks, applied in batches of at most 2
+        let ops: Vec<_> = (0..5u8)
+            .map(|i| DepositStxOp {
+                txid: Txid([i + 2; 32]),
+                burn_header_hash: BurnchainHeaderHash([if i < 3 { 1 } else { 2 }; 32]),
+                amount: 10 + (i as u128),
+                sender: PrincipalData::from(addr_publisher),
+            })
+            .collect();
+        let liquid_ustx = conn.with_clarity_db_readonly(|db| db.get_total_liquid_ustx());
+
+        let processed_ops = StacksChainState::process_deposit_stx_ops(&mut conn, ops, 2);
+        assert_eq!(processed_ops.len(), 5);
+        for (i, receipt) in processed_ops.iter().enumerate() {
+            assert_eq!(
+                receipt.transaction,
+                TransactionOrigin::Burn(Txid([(i as u8) + 2; 32]))
+            );
+            assert_eq!(receipt.events.len(), 1);
+        }
+
+        let account = StacksChainState::get_account(&mut conn, &addr_publisher.into());
+        assert_eq!(orig_balance + 2 + 60, account.stx_balance.amount_unlocked);
+        assert_eq!(
+            conn.with_clarity_db_readonly(|db| db.get_total_liquid_ustx()),
+            liquid_ustx + 60
+        );
+    }
+
+    #[test]
+    fn test_process_deposit_handler() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "test_process_deposit_handler");
+
+        let privk_user = StacksPrivateKey::from_hex(
+            "027682d2f7b05c3801fe4467883ab4cff0568b5e36412b5289e83ea5b519de8a01",
+        )
+        .unwrap();
+        let auth_user = TransactionAuth::from_p2pkh(&privk_user).unwrap();
+        let addr_publisher = auth_user.origin().address_testnet();
+
+        // deposit handlers are a Clarity 2 feature
+        let burn_state_db = UnitTestBurnStateDB {
+            epoch_id: StacksEpochId::EpochSubnet1,
+        };
+        let mut conn = chainstate.block_begin(
+            &burn_state_db,
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            &ConsensusHash([1u8; 20]),
+            &BlockHeaderHash([1u8; 32]),
+        );
+        conn.block.initialize_epoch_2_05().unwrap();
+        conn.block.initialize_epoch_subnet_1().unwrap();
+
+        // the handler rejects deposits of less than 10 uSTX
+        let vault = "
+        (define-data-var total uint u0)
+
+        (define-private (on-deposit (depositor principal) (asset (optional principal)) (amount uint))
+          (begin
+            (asserts! (>= amount u10) (err u1))
+            (var-set total (+ (var-get total) amount))
+            (print { depositor: depositor, asset: asset, amount: amount })
+            (ok true)))
+
+        (define-deposit-handler on-deposit)
+        ";
+
+        // the handler writes more often than a handler's budget allows
+        let spender = "
+        (define-data-var total uint u0)
+        (define-constant TEN (list u1 u2 u3 u4 u5 u6 u7 u8 u9 u10))
+
+        (define-private (bump (i uint))
+          (var-set total (+ (var-get total) i)))
+
+        (define-private (bump-ten (i uint))
+          (map bump TEN))
+
+        (define-private (on-deposit (depositor principal) (asset (optional principal)) (amount uint))
+          (begin
+            (map bump-ten TEN)
+            (print { depositor: depositor, asset: asset, amount: amount })
+            (ok true)))
+
+        (define-deposit-handler on-deposit)
+        ";
+
+        let contracts = [
+            ("vault", vault),
+            ("plain", "(define-data-var total uint u0)"),
+            ("spender", spender),
+        ];
+        for (nonce, (name, code)) in contracts.iter().enumerate() {
+            let mut contract_tx = StacksTransaction::new(
+                TransactionVersion::Testnet,
+                auth_user.clone(),
+                TransactionPayload::new_smart_contract(name, code).unwrap(),
+            );
+            contract_tx.chain_id = 0x80000000;
+            contract_tx.set_tx_fee(0);
+            contract_tx.set_origin_nonce(nonce as u64);
+
+            let mut signer = StacksTransactionSigner::new(&contract_tx);
+            signer.sign_origin(&privk_user).unwrap();
+            StacksChainState::process_transaction(&mut conn, &signer.get_tx().unwrap(), false)
+                .unwrap();
+        }
+
+        let contract_principal = |name: &str| {
+            PrincipalData::from(QualifiedContractIdentifier::new(
+                StandardPrincipalData::from(addr_publisher),
+                ContractName::from(name),
+            ))
+        };
+        let deposit = |i: u8, amount: u128, name: &str| DepositStxOp {
+            txid: Txid([i; 32]),
+            burn_header_hash: BurnchainHeaderHash([0; 32]),
+            amount,
+            sender: contract_principal(name),
+        };
+        let ops = vec![
+            deposit(1, 10, "vault"),
+            deposit(2, 5, "vault"),
+            deposit(3, 10, "plain"),
+            deposit(4, 10, "spender"),
+        ];
+
+        let processed_ops =
+            StacksChainState::process_deposit_stx_ops(&mut conn, ops, DEFAULT_DEPOSIT_BATCH_SIZE);
+        assert_eq!(processed_ops.len(), 4);
+        // the handler ran for the first deposit, and rolled back for the second
+        assert_eq!(processed_ops[0].events.len(), 2);
+        assert_eq!(processed_ops[1].events.len(), 1);
+        // contracts without a handler are only credited
+        assert_eq!(processed_ops[2].events.len(), 1);
+        // a handler that runs out of budget is rolled back
+        assert_eq!(processed_ops[3].events.len(), 1);
+
+        // every deposit was credited, even if its handler failed
+        for (name, amount) in [("vault", 15), ("plain", 10), ("spender", 10)].iter() {
+            let account = StacksChainState::get_account(&mut conn, &contract_principal(name));
+            assert_eq!(account.stx_balance.amount_unlocked, *amount);
+        }
+
+        let vault_id = QualifiedContractIdentifier::new(
+            StandardPrincipalData::from(addr_publisher),
+            ContractName::from("vault"),
+        );
+        let total = conn.with_clarity_db_readonly(|db| {
+            db.lookup_variable_unknown_descriptor(&vault_id, "total")
+                .unwrap()
+        });
+        assert_eq!(total, Value::UInt(10));
+
+        let spender_id = QualifiedContractIdentifier::new(
+            StandardPrincipalData::from(addr_publisher),
+            ContractName::from("spender"),
+        );
+        let total = conn.with_clarity_db_readonly(|db| {
+            db.lookup_variable_unknown_descriptor(&spender_id, "total")
+                .unwrap()
+        });
+        assert_eq!(total, Value::UInt(0));
+    }
+
+    #[test]
+    fn test_process_deposit_multi_asset_ops() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "test_process_deposit_multi_asset_ops");
+
+        let privk_user = StacksPrivateKey::from_hex(
+            "027682d2f7b05c3801fe4467883ab4cff0568b5e36412b5289e83ea5b519de8a01",
+        )
+        .unwrap();
+        let auth_user = TransactionAuth::from_p2pkh(&privk_user).unwrap();
+        let addr_publisher = auth_user.origin().address_testnet();
+        let sender = PrincipalData::from(addr_publisher);
+
+        let mut conn = chainstate.block_begin(
+            &TEST_BURN_STATE_DB,
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            &ConsensusHash([1u8; 20]),
+            &BlockHeaderHash([1u8; 32]),
+        );
+
+        let subnet_assets = "
+        (define-fungible-token ft-token)
+        (define-non-fungible-token nft-token uint)
+
+        (define-public (subnet-deposit-ft-token (amount uint) (recipient principal))
+          (ft-mint? ft-token amount recipient)
+        )
+
+        (define-public (subnet-deposit-nft-token (id uint) (recipient principal))
+          (nft-mint? nft-token id recipient)
+        )
+        ";
+
+        let mut subnet_deposit_contract_tx = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            auth_user.clone(),
+            TransactionPayload::new_smart_contract("subnet-deposit-contract", subnet_assets)
+                .unwrap(),
+        );
+        subnet_deposit_contract_tx.chain_id = 0x80000000;
+        subnet_deposit_contract_tx.set_tx_fee(0);
+
+        let mut signer = StacksTransactionSigner::new(&subnet_deposit_contract_tx);
+        signer.sign_origin(&privk_user).unwrap();
+        StacksChainState::process_transaction(&mut conn, &signer.get_tx().unwrap(), false).unwrap();
+
+        let subnet_contract_id = QualifiedContractIdentifier::new(
+            StandardPrincipalData::from(addr_publisher),
+            ContractName::from("subnet-deposit-contract"),
+        );
+        let ft = |amount: u128, function_name: &str| DepositAsset::Ft {
+            l1_contract_id: QualifiedContractIdentifier::local("l1-ft-contract").unwrap(),
+            subnet_contract_id: subnet_contract_id.clone(),
+            subnet_function_name: ClarityName::from(function_name),
+            name: "ft-token".to_string(),
+            amount,
+        };
+        let nft = |id: u128| DepositAsset::Nft {
+            l1_contract_id: QualifiedContractIdentifier::local("l1-nft-contract").unwrap(),
+            subnet_contract_id: subnet_contract_id.clone(),
+            subnet_function_name: ClarityName::from("subnet-deposit-nft-token"),
+            id,
+        };
+        let deposit = |i: u8, stx_amount: u128, assets: Vec<DepositAsset>| DepositMultiAssetOp {
+            txid: Txid([i; 32]),
+            burn_header_hash: BurnchainHeaderHash([0; 32]),
+            stx_amount,
+            assets,
+            sender: sender.clone(),
+        };
+
+        let ops = vec![
+            // this op is well formed
+            deposit(1, 100, vec![ft(5, "subnet-deposit-ft-token"), nft(1)]),
+            // this op deposits an NFT that was already minted, so nothing in it is credited
+            deposit(2, 50, vec![ft(7, "subnet-deposit-ft-token"), nft(1)]),
+            // this op calls a function that does not exist, so nothing in it is credited
+            deposit(3, 50, vec![ft(7, "subnet-deposit-ft-token-DNE"), nft(2)]),
+        ];
+
+        let orig_balance = StacksChainState::get_account(&mut conn, &sender)
+            .stx_balance
+            .amount_unlocked;
+        let liquid_ustx = conn.with_clarity_db_readonly(|db| db.get_total_liquid_ustx());
+
+        let processed_ops = StacksChainState::process_deposit_multi_asset_ops(
+            &mut conn,
+            ops,
+            DEFAULT_DEPOSIT_BATCH_SIZE,
+        );
+        assert_eq!(processed_ops.len(), 2);
+
+        // the STX mint, and both token mints
+        assert_eq!(processed_ops[0].result, Value::okay_true());
+        assert_eq!(processed_ops[0].events.len(), 3);
+        // the failed NFT mint's `err` is reported, and nothing is minted
+        assert_eq!(
+            processed_ops[1].transaction,
+            TransactionOrigin::Burn(Txid([2; 32]))
+        );
+        assert_eq!(
+            processed_ops[1].result,
+            Value::error(Value::UInt(1)).unwrap()
+        );
+        assert!(processed_ops[1].events.is_empty());
+
+        // only the first deposit was credited
+        let account = StacksChainState::get_account(&mut conn, &sender);
+        assert_eq!(account.stx_balance.amount_unlocked, orig_balance + 100);
+        assert_eq!(
+            conn.with_clarity_db_readonly(|db| db.get_total_liquid_ustx()),
+            liquid_ustx + 100
+        );
+        let ft_balance = conn.with_clarity_db_readonly(|db| {
+            db.get_ft_balance(&subnet_contract_id, "ft-token", &sender, None)
+                .unwrap()
+        });
+        assert_eq!(ft_balance, 5);
+    }
+
+    #[test]
+    fn test_make_deposit_batches() {
+        let op_blocks = [1u8, 1, 1, 2, 3, 3, 3, 3, 3];
+        let ops: Vec<_> = op_blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| (i, BurnchainHeaderHash([*block; 32])))
+            .collect();
+        let batch_ids = |batches: Vec<Vec<(usize, BurnchainHeaderHash)>>| -> Vec<Vec<usize>> {
+            batches
+                .into_iter()
+                .map(|batch| batch.into_iter().map(|(i, _)| i).collect())
+                .collect()
+        };
+
+        // one batch per L1 block
+        let batches =
+            StacksChainState::make_deposit_batches(ops.clone(), 100, |(_, bhh)| bhh.clone());
+        assert_eq!(
+            batch_ids(batches),
+            vec![vec![0, 1, 2], vec![3], vec![4, 5, 6, 7, 8]]
+        );
+
+        // L1 blocks with more deposits than the batch size are split up
+        let batches =
+            StacksChainState::make_deposit_batches(ops.clone(), 2, |(_, bhh)| bhh.clone());
+        assert_eq!(
+            batch_ids(batches),
+            vec![
+                vec![0, 1],
+                vec![2],
+                vec![3],
+                vec![4, 5],
+                vec![6, 7],
+                vec![8]
+            ]
+        );
+
+        // a batch size of 0 is treated as 1
+        let batches =
+            StacksChainState::make_deposit_batches(ops.clone(), 0, |(_, bhh)| bhh.clone());
+        assert_eq!(batch_ids(batches).len(), ops.len());
+
+        let batches = StacksChainState::make_deposit_batches(
+            Vec::<(usize, BurnchainHeaderHash)>::new(),
+            2,
+            |(_, bhh)| bhh.clone(),
+        );
+        assert!(batches.is_empty());
     }
 
     #[cfg(test)]
@@ -11825,5 +13444,24 @@ pub mod test {
             account.stx_balance.get_total_balance(),
             1000000000 + (1000 + 2000 + 3000 + 4000 + 5000 + 6000 + 7000 + 8000 + 9000)
         );
+
+        // each credited deposit was recorded as materialized exactly once in the canonical fork
+        let index_conn = peer.chainstate().index_conn().unwrap();
+        let mut num_materialized = 0;
+        for deposit_op in expected_all_deposit_ops.iter() {
+            let records = StacksChainState::get_materialized_deposits(
+                &index_conn,
+                &tip_hash,
+                &deposit_op.txid,
+            )
+            .unwrap();
+            assert!(records.len() <= 1);
+            for record in records.iter() {
+                assert_eq!(record.deposit_type, DepositType::Stx);
+                assert_eq!(record.l1_block_id, deposit_op.burn_header_hash);
+            }
+            num_materialized += records.len();
+        }
+        assert_eq!(num_materialized, 9);
     }
 }
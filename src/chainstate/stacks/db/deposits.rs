@@ -0,0 +1,318 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A ledger of the L1 deposit operations that each subnet block materialized.  A deposit is
+//! identified by its L1 txid and kind, and is materialized at most once per subnet fork: if the
+//! L1 observer replays the operation (e.g. after a re-sync, or in a different L1 block), it is
+//! skipped instead of being credited again.
+
+use std::collections::HashSet;
+
+use rusqlite::types::ToSql;
+use rusqlite::Row;
+
+use crate::burnchains::Txid;
+use crate::chainstate::stacks::db::{StacksChainState, StacksDBConn, StacksDBTx};
+use crate::chainstate::stacks::Error;
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_rows, u64_to_sql, DBConn, DBTx, FromColumn, FromRow};
+use stacks_common::types::chainstate::{BurnchainHeaderHash, StacksBlockId};
+
+/// The kinds of L1 deposit operations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DepositType {
+    Stx,
+    Ft,
+    Nft,
+    #[serde(rename = "multi-asset")]
+    MultiAsset,
+}
+
+impl DepositType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DepositType::Stx => "stx",
+            DepositType::Ft => "ft",
+            DepositType::Nft => "nft",
+            DepositType::MultiAsset => "multi-asset",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<DepositType> {
+        match s {
+            "stx" => Some(DepositType::Stx),
+            "ft" => Some(DepositType::Ft),
+            "nft" => Some(DepositType::Nft),
+            "multi-asset" => Some(DepositType::MultiAsset),
+            _ => None,
+        }
+    }
+}
+
+/// An L1 deposit operation that a block is about to materialize
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingDeposit {
+    pub l1_txid: Txid,
+    pub deposit_type: DepositType,
+    pub l1_block_id: BurnchainHeaderHash,
+}
+
+/// A record that a block materialized an L1 deposit operation
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterializedDeposit {
+    pub l1_txid: Txid,
+    pub deposit_type: DepositType,
+    /// the L1 block in which the materialized operation was observed
+    pub l1_block_id: BurnchainHeaderHash,
+    pub index_block_hash: StacksBlockId,
+    pub block_height: u64,
+}
+
+impl FromRow<MaterializedDeposit> for MaterializedDeposit {
+    fn from_row<'a>(row: &'a Row) -> Result<MaterializedDeposit, db_error> {
+        let l1_txid = Txid::from_column(row, "l1_txid")?;
+        let deposit_type_str: String = row.get_unwrap("deposit_type");
+        let deposit_type = DepositType::from_str(&deposit_type_str).ok_or(db_error::ParseError)?;
+        let l1_block_id = BurnchainHeaderHash::from_column(row, "l1_block_id")?;
+        let index_block_hash = StacksBlockId::from_column(row, "index_block_hash")?;
+        let block_height = u64::from_column(row, "block_height")?;
+        Ok(MaterializedDeposit {
+            l1_txid,
+            deposit_type,
+            l1_block_id,
+            index_block_hash,
+            block_height,
+        })
+    }
+}
+
+impl StacksChainState {
+    /// Record that the block `index_block_hash` at `block_height` materialized `deposits`.
+    pub fn insert_materialized_deposits(
+        tx: &DBTx,
+        index_block_hash: &StacksBlockId,
+        block_height: u64,
+        deposits: &[PendingDeposit],
+    ) -> Result<(), Error> {
+        let block_height = u64_to_sql(block_height)?;
+        for deposit in deposits.iter() {
+            let args: &[&dyn ToSql] = &[
+                &deposit.l1_txid,
+                &deposit.deposit_type.as_str(),
+                &deposit.l1_block_id,
+                index_block_hash,
+                &block_height,
+            ];
+            tx.execute(
+                "INSERT OR REPLACE INTO materialized_deposits \
+                 (l1_txid, deposit_type, l1_block_id, index_block_hash, block_height) \
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                args,
+            )
+            .map_err(|e| Error::DBError(db_error::SqliteError(e)))?;
+        }
+        Ok(())
+    }
+
+    /// Get every record of a deposit with the given L1 txid being materialized, in any fork.
+    pub fn get_materialized_deposit_records(
+        conn: &DBConn,
+        l1_txid: &Txid,
+    ) -> Result<Vec<MaterializedDeposit>, Error> {
+        query_rows(
+            conn,
+            "SELECT * FROM materialized_deposits WHERE l1_txid = ?1 ORDER BY block_height",
+            &[l1_txid],
+        )
+        .map_err(Error::DBError)
+    }
+
+    /// Get the records of deposits with the given L1 txid that were materialized in the fork
+    /// ending at `tip` (inclusive).
+    pub fn get_materialized_deposits(
+        index_conn: &StacksDBConn,
+        tip: &StacksBlockId,
+        l1_txid: &Txid,
+    ) -> Result<Vec<MaterializedDeposit>, Error> {
+        let mut in_fork = vec![];
+        for record in StacksChainState::get_materialized_deposit_records(index_conn, l1_txid)? {
+            if index_conn
+                .get_ancestor_block_height(&record.index_block_hash, tip)?
+                .is_some()
+            {
+                in_fork.push(record);
+            }
+        }
+        Ok(in_fork)
+    }
+
+    /// Drop the deposit operations in `ops` that were already materialized in the fork ending at
+    /// `parent` (inclusive), or that repeat an earlier operation in `ops`.  Returns the remaining
+    /// operations, and appends them to `pending`.
+    pub fn filter_materialized_deposits<T, F>(
+        tx: &mut StacksDBTx,
+        parent: &StacksBlockId,
+        deposit_type: DepositType,
+        ops: Vec<T>,
+        op_id: F,
+        pending: &mut Vec<PendingDeposit>,
+    ) -> Result<Vec<T>, Error>
+    where
+        F: Fn(&T) -> (Txid, BurnchainHeaderHash),
+    {
+        let mut seen = HashSet::new();
+        let mut unmaterialized = Vec::with_capacity(ops.len());
+        for op in ops.into_iter() {
+            let (l1_txid, l1_block_id) = op_id(&op);
+            if !seen.insert(l1_txid.clone()) {
+                warn!("Skipping deposit operation repeated in L1 history";
+                      "type" => deposit_type.as_str(),
+                      "l1_txid" => %l1_txid,
+                      "l1_block_id" => %l1_block_id);
+                continue;
+            }
+
+            let records = StacksChainState::get_materialized_deposit_records(tx, &l1_txid)?;
+            let mut materialized = None;
+            for record in records.into_iter() {
+                if record.deposit_type != deposit_type {
+                    continue;
+                }
+                if tx
+                    .get_ancestor_block_height(&record.index_block_hash, parent)?
+                    .is_some()
+                {
+                    materialized = Some(record);
+                    break;
+                }
+            }
+
+            if let Some(record) = materialized {
+                warn!("Skipping deposit operation that was already materialized";
+                      "type" => deposit_type.as_str(),
+                      "l1_txid" => %l1_txid,
+                      "l1_block_id" => %l1_block_id,
+                      "materialized_in" => %record.index_block_hash,
+                      "materialized_from" => %record.l1_block_id);
+                continue;
+            }
+
+            pending.push(PendingDeposit {
+                l1_txid,
+                deposit_type,
+                l1_block_id,
+            });
+            unmaterialized.push(op);
+        }
+        Ok(unmaterialized)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::burn::db::sortdb::SortitionDB;
+    use crate::chainstate::burn::operations::{BlockstackOperationType, DepositStxOp};
+    use crate::net::test::*;
+    use clarity::vm::types::PrincipalData;
+    use stacks_common::types::chainstate::StacksAddress;
+    use stacks_common::types::Address;
+
+    fn get_stx_balance(
+        peer: &mut TestPeer,
+        tip: &StacksBlockId,
+        principal: &PrincipalData,
+    ) -> u128 {
+        let sortdb = peer.sortdb.take().unwrap();
+        let account = peer
+            .chainstate()
+            .with_read_only_clarity_tx(&sortdb.index_conn(), tip, |conn| {
+                StacksChainState::get_account(conn, principal)
+            })
+            .unwrap();
+        peer.sortdb = Some(sortdb);
+        account.stx_balance.get_total_balance()
+    }
+
+    fn get_burn_tip_hash(peer: &TestPeer) -> BurnchainHeaderHash {
+        SortitionDB::get_canonical_burn_chain_tip(peer.sortdb.as_ref().unwrap().conn())
+            .unwrap()
+            .burn_header_hash
+    }
+
+    #[test]
+    fn test_replayed_deposit_is_credited_once() {
+        let depositor: PrincipalData =
+            StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV")
+                .unwrap()
+                .into();
+        let deposit = DepositStxOp {
+            txid: Txid([0x11; 32]),
+            burn_header_hash: BurnchainHeaderHash([0x00; 32]),
+            amount: 1000,
+            sender: depositor.clone(),
+        };
+
+        let peer_config =
+            TestPeerConfig::new("test_replayed_deposit_is_credited_once", 21342, 21343);
+        let mut peer = TestPeer::new(peer_config);
+        let mut coinbase_nonce = 0;
+
+        peer.tenure_with_txs_and_ops(
+            &[],
+            vec![BlockstackOperationType::DepositStx(deposit.clone())],
+            &mut coinbase_nonce,
+        );
+        let l1_block_id = get_burn_tip_hash(&peer);
+
+        // the L1 observer reports the same operation again in a later L1 block
+        peer.tenure_with_txs_and_ops(
+            &[],
+            vec![BlockstackOperationType::DepositStx(deposit.clone())],
+            &mut coinbase_nonce,
+        );
+        let replayed_in = get_burn_tip_hash(&peer);
+        assert_eq!(
+            SortitionDB::get_deposit_stx_ops(peer.sortdb.as_ref().unwrap().conn(), &replayed_in)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // mine past the block that would see the replayed operation
+        peer.tenure_with_txs(&[], &mut coinbase_nonce);
+        let tip = peer.tenure_with_txs(&[], &mut coinbase_nonce);
+
+        assert_eq!(get_stx_balance(&mut peer, &tip, &depositor), 1000);
+
+        // only the first observation was materialized, in a block of this fork
+        let records = StacksChainState::get_materialized_deposit_records(
+            peer.chainstate().db(),
+            &deposit.txid,
+        )
+        .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].deposit_type, DepositType::Stx);
+        assert_eq!(records[0].l1_block_id, l1_block_id);
+
+        let index_conn = peer.chainstate().index_conn().unwrap();
+        assert_eq!(
+            StacksChainState::get_materialized_deposits(&index_conn, &tip, &deposit.txid).unwrap(),
+            records
+        );
+    }
+}
@@ -0,0 +1,419 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A subnet's genesis specification: the contracts deployed and the fungible and non-fungible
+//! tokens allocated in the genesis block, on top of the boot code and the initial STX balances.
+//! The specification is materialized in the order it is given, so every node that boots from
+//! the same specification computes the same genesis state root, which is recorded in the
+//! genesis block header.
+
+use crate::chainstate::stacks::db::{ClarityTx, StacksChainState};
+use crate::chainstate::stacks::events::StacksTransactionReceipt;
+use crate::chainstate::stacks::{
+    Error, StacksTransaction, TransactionPayload, TransactionSmartContract, TransactionVersion,
+};
+use crate::util_lib::boot::{boot_code_acc, boot_code_tx_auth};
+use crate::util_lib::strings::StacksString;
+use clarity::vm::clarity::{ClarityConnection, TransactionConnection};
+use clarity::vm::errors::{Error as InterpreterError, RuntimeErrorType};
+use clarity::vm::events::{
+    FTEventType, FTMintEventData, NFTEventType, NFTMintEventData, StacksTransactionEvent,
+};
+use clarity::vm::types::{AssetIdentifier, PrincipalData, QualifiedContractIdentifier, Value};
+use stacks_common::types::chainstate::{StacksAddress, TrieHash};
+
+/// A contract deployed in the genesis block, by its issuer
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenesisContract {
+    pub contract_id: QualifiedContractIdentifier,
+    pub code_body: String,
+}
+
+/// Fungible tokens minted to a principal in the genesis block.  The token must be defined by a
+/// boot contract or by a contract deployed earlier in the genesis specification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenesisFungibleTokenAllocation {
+    pub asset_identifier: AssetIdentifier,
+    pub recipient: PrincipalData,
+    pub amount: u128,
+}
+
+/// A non-fungible token minted to a principal in the genesis block.  The token must be defined
+/// by a boot contract or by a contract deployed earlier in the genesis specification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenesisNonFungibleTokenAllocation {
+    pub asset_identifier: AssetIdentifier,
+    pub recipient: PrincipalData,
+    pub id: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GenesisSpec {
+    pub contracts: Vec<GenesisContract>,
+    pub ft_allocations: Vec<GenesisFungibleTokenAllocation>,
+    pub nft_allocations: Vec<GenesisNonFungibleTokenAllocation>,
+    /// If set, the genesis state root that the node must compute, so that an operator can check
+    /// that their node booted the same genesis as the rest of the subnet
+    pub expected_root_hash: Option<TrieHash>,
+}
+
+impl GenesisSpec {
+    pub fn is_empty(&self) -> bool {
+        self.contracts.is_empty()
+            && self.ft_allocations.is_empty()
+            && self.nft_allocations.is_empty()
+    }
+}
+
+impl StacksChainState {
+    /// Deploy the genesis specification's contracts, and mint its token allocations, in the
+    /// genesis block.  Returns the receipts of the contract deployments, and the mint events of
+    /// the allocations.  Fails if a contract does not deploy, or if an allocation cannot be
+    /// minted: a node must not boot from a specification that it cannot fully materialize.
+    pub fn install_genesis_spec(
+        clarity_tx: &mut ClarityTx,
+        mainnet: bool,
+        genesis_spec: &GenesisSpec,
+    ) -> Result<(Vec<StacksTransactionReceipt>, Vec<StacksTransactionEvent>), Error> {
+        let tx_version = if mainnet {
+            TransactionVersion::Mainnet
+        } else {
+            TransactionVersion::Testnet
+        };
+
+        let mut receipts = vec![];
+        for contract in genesis_spec.contracts.iter() {
+            debug!(
+                "Instantiate genesis contract '{}' ({} bytes)...",
+                &contract.contract_id,
+                contract.code_body.len()
+            );
+
+            let deployer = StacksAddress::from(contract.contract_id.issuer.clone());
+            let code_body = StacksString::from_str(&contract.code_body).ok_or_else(|| {
+                Error::InvalidStacksBlock(format!(
+                    "Genesis contract {} has an invalid code body",
+                    &contract.contract_id
+                ))
+            })?;
+            let deploy_tx = StacksTransaction::new(
+                tx_version.clone(),
+                boot_code_tx_auth(deployer.clone()),
+                TransactionPayload::SmartContract(TransactionSmartContract {
+                    name: contract.contract_id.name.clone(),
+                    code_body,
+                }),
+            );
+
+            let receipt = clarity_tx.connection().as_transaction(|clarity| {
+                StacksChainState::process_transaction_payload(
+                    clarity,
+                    &deploy_tx,
+                    &boot_code_acc(deployer, 0),
+                )
+            })?;
+            let deployed = clarity_tx
+                .connection()
+                .with_clarity_db_readonly(|db| db.has_contract(&contract.contract_id));
+            if !deployed {
+                return Err(Error::InvalidStacksBlock(format!(
+                    "Genesis contract {} failed to deploy",
+                    &contract.contract_id
+                )));
+            }
+            receipts.push(receipt);
+        }
+
+        let mut mint_events = vec![];
+        for allocation in genesis_spec.ft_allocations.iter() {
+            let contract_id = &allocation.asset_identifier.contract_identifier;
+            let token_name = allocation.asset_identifier.asset_name.as_str();
+            clarity_tx.connection().as_transaction(|clarity| {
+                clarity.with_clarity_db(|db| {
+                    let descriptor = db.load_ft(contract_id, token_name)?;
+                    db.checked_increase_token_supply(
+                        contract_id,
+                        token_name,
+                        allocation.amount,
+                        &descriptor,
+                    )?;
+                    let balance = db.get_ft_balance(
+                        contract_id,
+                        token_name,
+                        &allocation.recipient,
+                        Some(&descriptor),
+                    )?;
+                    let new_balance = balance
+                        .checked_add(allocation.amount)
+                        .ok_or(InterpreterError::from(RuntimeErrorType::ArithmeticOverflow))?;
+                    db.set_ft_balance(contract_id, token_name, &allocation.recipient, new_balance)?;
+                    Ok(())
+                })
+            })?;
+            mint_events.push(StacksTransactionEvent::FTEvent(FTEventType::FTMintEvent(
+                FTMintEventData {
+                    asset_identifier: allocation.asset_identifier.clone(),
+                    recipient: allocation.recipient.clone(),
+                    amount: allocation.amount,
+                },
+            )));
+        }
+
+        for allocation in genesis_spec.nft_allocations.iter() {
+            let contract_id = &allocation.asset_identifier.contract_identifier;
+            let asset_name = allocation.asset_identifier.asset_name.as_str();
+            let minted = clarity_tx.connection().as_transaction(|clarity| {
+                clarity.with_clarity_db(|db| {
+                    let key_type = db.get_nft_key_type(contract_id, asset_name)?;
+                    match db.get_nft_owner(contract_id, asset_name, &allocation.id, &key_type) {
+                        Err(InterpreterError::Runtime(RuntimeErrorType::NoSuchToken, _)) => {}
+                        Ok(_) => return Ok(false),
+                        Err(e) => return Err(e.into()),
+                    }
+                    db.set_nft_owner(
+                        contract_id,
+                        asset_name,
+                        &allocation.id,
+                        &allocation.recipient,
+                        &key_type,
+                    )?;
+                    Ok(true)
+                })
+            })?;
+            if !minted {
+                return Err(Error::InvalidStacksBlock(format!(
+                    "Genesis NFT {} of {} is allocated more than once",
+                    &allocation.id, &allocation.asset_identifier
+                )));
+            }
+            mint_events.push(StacksTransactionEvent::NFTEvent(
+                NFTEventType::NFTMintEvent(NFTMintEventData {
+                    asset_identifier: allocation.asset_identifier.clone(),
+                    recipient: allocation.recipient.clone(),
+                    value: allocation.id.clone(),
+                }),
+            ));
+        }
+
+        Ok((receipts, mint_events))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::burnchains::PoxConstants;
+    use crate::chainstate::stacks::db::test::chainstate_path;
+    use crate::chainstate::stacks::db::ChainStateBootData;
+    use crate::chainstate::stacks::StacksBlockHeader;
+    use crate::core::{FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH};
+    use clarity::vm::test_util::TEST_BURN_STATE_DB;
+    use stacks_common::types::chainstate::BurnchainHeaderHash;
+
+    const GENESIS_TOKENS: &str = "
+        (define-fungible-token coin)
+        (define-non-fungible-token badge uint)
+        (define-read-only (get-coin-supply) (ft-get-supply coin))";
+
+    fn boot_genesis(test_name: &str, genesis_spec: GenesisSpec) -> Result<StacksChainState, Error> {
+        boot_genesis_with_analysis_settings(test_name, genesis_spec, false)
+    }
+
+    fn boot_genesis_with_analysis_settings(
+        test_name: &str,
+        genesis_spec: GenesisSpec,
+        allow_unchecked_intermediary_responses: bool,
+    ) -> Result<StacksChainState, Error> {
+        let path = chainstate_path(test_name);
+        if fs::metadata(&path).is_ok() {
+            fs::remove_dir_all(&path).unwrap();
+        }
+
+        let mut boot_data = ChainStateBootData {
+            initial_balances: vec![],
+            post_flight_callback: None,
+            first_burnchain_block_hash: BurnchainHeaderHash::zero(),
+            first_burnchain_block_height: 0,
+            first_burnchain_block_timestamp: 0,
+            pox_constants: PoxConstants::testnet_default(),
+            get_bulk_initial_lockups: None,
+            get_bulk_initial_balances: None,
+            get_bulk_initial_names: None,
+            get_bulk_initial_namespaces: None,
+            genesis_spec,
+            allow_unchecked_intermediary_responses,
+            withdrawal_batch_blocks: 0,
+            epochs: None,
+            system_priority: None,
+            max_tx_event_bytes: None,
+            use_subnet_costs: false,
+        };
+        StacksChainState::open_and_exec(false, 0x80000000, &path, Some(&mut boot_data), None)
+            .map(|(chainstate, _)| chainstate)
+    }
+
+    fn make_genesis_spec() -> GenesisSpec {
+        let contract_id =
+            QualifiedContractIdentifier::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.tokens")
+                .unwrap();
+        let recipient = PrincipalData::parse("ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2").unwrap();
+        GenesisSpec {
+            contracts: vec![GenesisContract {
+                contract_id: contract_id.clone(),
+                code_body: GENESIS_TOKENS.to_string(),
+            }],
+            ft_allocations: vec![GenesisFungibleTokenAllocation {
+                asset_identifier: AssetIdentifier {
+                    contract_identifier: contract_id.clone(),
+                    asset_name: "coin".into(),
+                },
+                recipient: recipient.clone(),
+                amount: 1000,
+            }],
+            nft_allocations: vec![GenesisNonFungibleTokenAllocation {
+                asset_identifier: AssetIdentifier {
+                    contract_identifier: contract_id,
+                    asset_name: "badge".into(),
+                },
+                recipient,
+                id: Value::UInt(1),
+            }],
+            expected_root_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_install_genesis_spec() {
+        let genesis_spec = make_genesis_spec();
+        let contract_id = genesis_spec.contracts[0].contract_id.clone();
+        let recipient = genesis_spec.ft_allocations[0].recipient.clone();
+
+        let mut chainstate = boot_genesis("install-genesis-spec", genesis_spec.clone()).unwrap();
+        let genesis_tip = StacksBlockHeader::make_index_block_hash(
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+        );
+        let (coin_balance, coin_supply, badge_owner) = chainstate
+            .with_read_only_clarity_tx(&TEST_BURN_STATE_DB, &genesis_tip, |conn| {
+                conn.with_clarity_db_readonly(|db| {
+                    let badge_type = db.get_nft_key_type(&contract_id, "badge").unwrap();
+                    (
+                        db.get_ft_balance(&contract_id, "coin", &recipient, None)
+                            .unwrap(),
+                        db.get_ft_supply(&contract_id, "coin").unwrap(),
+                        db.get_nft_owner(&contract_id, "badge", &Value::UInt(1), &badge_type)
+                            .unwrap(),
+                    )
+                })
+            })
+            .unwrap();
+        assert_eq!(coin_balance, 1000);
+        assert_eq!(coin_supply, 1000);
+        assert_eq!(badge_owner, recipient);
+
+        // the genesis header commits to the genesis state
+        let genesis_header = StacksChainState::get_genesis_header_info(chainstate.db()).unwrap();
+        let genesis_root_hash = genesis_header.anchored_header.state_index_root;
+        assert_eq!(
+            genesis_root_hash,
+            chainstate
+                .clarity_state
+                .with_marf(|marf| marf.get_root_hash_at(&genesis_tip).unwrap())
+        );
+
+        // booting the same specification computes the same genesis
+        let mut same_spec = genesis_spec.clone();
+        same_spec.expected_root_hash = Some(genesis_root_hash.clone());
+        boot_genesis("install-genesis-spec-same", same_spec).unwrap();
+
+        // ...and a different one does not
+        let mut other_spec = genesis_spec;
+        other_spec.ft_allocations[0].amount = 999;
+        other_spec.expected_root_hash = Some(genesis_root_hash);
+        match boot_genesis("install-genesis-spec-other", other_spec) {
+            Err(Error::InvalidStacksBlock(_)) => {}
+            Err(e) => panic!("Expected InvalidStacksBlock, got {:?}", &e),
+            Ok(_) => panic!("Booted a genesis with an unexpected root hash"),
+        }
+    }
+
+    #[test]
+    fn test_genesis_commits_unchecked_intermediary_responses() {
+        let genesis_tip = StacksBlockHeader::make_index_block_hash(
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+        );
+        let mut strict = boot_genesis_with_analysis_settings(
+            "genesis-strict-analysis",
+            GenesisSpec::default(),
+            false,
+        )
+        .unwrap();
+        let mut non_strict = boot_genesis_with_analysis_settings(
+            "genesis-non-strict-analysis",
+            GenesisSpec::default(),
+            true,
+        )
+        .unwrap();
+
+        for (chainstate, expected) in [(&mut strict, false), (&mut non_strict, true)] {
+            let allow = chainstate
+                .with_read_only_clarity_tx(&TEST_BURN_STATE_DB, &genesis_tip, |conn| {
+                    conn.with_clarity_db_readonly(|db| {
+                        db.get_allow_unchecked_intermediary_responses()
+                    })
+                })
+                .unwrap();
+            assert_eq!(allow, expected);
+        }
+
+        // the setting is part of the genesis state, so the two subnets have different genesis
+        let strict_root = strict
+            .clarity_state
+            .with_marf(|marf| marf.get_root_hash_at(&genesis_tip).unwrap());
+        let non_strict_root = non_strict
+            .clarity_state
+            .with_marf(|marf| marf.get_root_hash_at(&genesis_tip).unwrap());
+        assert_ne!(strict_root, non_strict_root);
+    }
+
+    #[test]
+    fn test_install_genesis_spec_rejects_bad_allocations() {
+        // tokens of a contract that is not deployed
+        let mut genesis_spec = make_genesis_spec();
+        genesis_spec.contracts.clear();
+        assert!(boot_genesis("genesis-spec-no-contract", genesis_spec).is_err());
+
+        // the same NFT twice
+        let mut genesis_spec = make_genesis_spec();
+        let badge = genesis_spec.nft_allocations[0].clone();
+        genesis_spec.nft_allocations.push(badge);
+        match boot_genesis("genesis-spec-duplicate-nft", genesis_spec) {
+            Err(Error::InvalidStacksBlock(_)) => {}
+            Err(e) => panic!("Expected InvalidStacksBlock, got {:?}", &e),
+            Ok(_) => panic!("Allocated the same NFT twice"),
+        }
+
+        // a contract that does not analyze
+        let mut genesis_spec = make_genesis_spec();
+        genesis_spec.contracts[0].code_body = "(define-read-only (broken) (+ 1 u1))".into();
+        genesis_spec.ft_allocations.clear();
+        genesis_spec.nft_allocations.clear();
+        assert!(boot_genesis("genesis-spec-bad-contract", genesis_spec).is_err());
+    }
+}
@@ -28,12 +28,13 @@ use crate::chainstate::burn::ConsensusHash;
 use crate::chainstate::stacks::db::*;
 use crate::chainstate::stacks::Error;
 use crate::chainstate::stacks::*;
+use crate::clarity_vm::withdrawal::WithdrawalBatchConfig;
 use crate::core::FIRST_BURNCHAIN_CONSENSUS_HASH;
 use crate::core::FIRST_STACKS_BLOCK_HASH;
 use crate::util_lib::db::Error as db_error;
 use crate::util_lib::db::{
-    query_count, query_row, query_row_columns, query_row_panic, query_rows, DBConn, FromColumn,
-    FromRow,
+    query_count, query_row, query_row_columns, query_row_panic, query_rows, u64_to_sql, DBConn,
+    FromColumn, FromRow,
 };
 use clarity::vm::costs::ExecutionCost;
 
@@ -114,6 +115,36 @@ impl FromRow<StacksMicroblockHeader> for StacksMicroblockHeader {
     }
 }
 
+/// The execution costs consumed by a processed block
+#[derive(Debug, Clone, PartialEq)]
+pub struct StacksBlockCosts {
+    pub index_block_hash: StacksBlockId,
+    /// cost of the block's own transactions
+    pub anchored_cost: ExecutionCost,
+    /// cost of the parent microblocks the block confirmed, which is charged to the same budget
+    pub confirmed_microblocks_cost: ExecutionCost,
+    pub total_cost: ExecutionCost,
+    /// the block limit the block was evaluated against
+    pub block_limit: ExecutionCost,
+}
+
+impl FromRow<StacksBlockCosts> for StacksBlockCosts {
+    fn from_row<'a>(row: &'a Row) -> Result<StacksBlockCosts, db_error> {
+        let index_block_hash = StacksBlockId::from_column(row, "index_block_hash")?;
+        let anchored_cost = row.get_unwrap("anchored_cost");
+        let confirmed_microblocks_cost = row.get_unwrap("confirmed_microblocks_cost");
+        let total_cost = row.get_unwrap("total_cost");
+        let block_limit = row.get_unwrap("block_limit");
+        Ok(StacksBlockCosts {
+            index_block_hash,
+            anchored_cost,
+            confirmed_microblocks_cost,
+            total_cost,
+            block_limit,
+        })
+    }
+}
+
 impl StacksChainState {
     /// Insert a block header that is paired with an already-existing block commit and snapshot
     pub fn insert_stacks_block_header(
@@ -219,6 +250,54 @@ impl StacksChainState {
             .map_err(|e| Error::from(db_error::from(e)))
     }
 
+    /// Record the execution costs consumed by the block `index_block_hash`.  The total cost is
+    /// the sum of the anchored and confirmed microblock costs.
+    pub fn insert_block_costs(
+        tx: &DBTx,
+        index_block_hash: &StacksBlockId,
+        anchored_cost: &ExecutionCost,
+        confirmed_microblocks_cost: &ExecutionCost,
+        block_limit: &ExecutionCost,
+    ) -> Result<(), Error> {
+        let mut total_cost = anchored_cost.clone();
+        total_cost.add(confirmed_microblocks_cost).map_err(|_| {
+            Error::CostOverflowError(
+                anchored_cost.clone(),
+                confirmed_microblocks_cost.clone(),
+                block_limit.clone(),
+            )
+        })?;
+        let args: &[&dyn ToSql] = &[
+            index_block_hash,
+            anchored_cost,
+            confirmed_microblocks_cost,
+            &total_cost,
+            block_limit,
+        ];
+        tx.execute(
+            "INSERT OR REPLACE INTO block_costs \
+             (index_block_hash, anchored_cost, confirmed_microblocks_cost, total_cost, block_limit) \
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            args,
+        )
+        .map_err(|e| Error::DBError(db_error::SqliteError(e)))?;
+        Ok(())
+    }
+
+    /// Get the execution costs consumed by a processed block.  Returns None for blocks that
+    /// were processed before costs were recorded.
+    pub fn get_block_costs(
+        conn: &DBConn,
+        index_block_hash: &StacksBlockId,
+    ) -> Result<Option<StacksBlockCosts>, Error> {
+        query_row(
+            conn,
+            "SELECT * FROM block_costs WHERE index_block_hash = ?1",
+            &[index_block_hash],
+        )
+        .map_err(Error::DBError)
+    }
+
     pub fn is_stacks_block_processed(
         conn: &Connection,
         consensus_hash: &ConsensusHash,
@@ -288,6 +367,57 @@ impl StacksChainState {
         }
     }
 
+    /// Get the withdrawal roots of the blocks at heights `first_height` through `last_height`
+    /// (inclusive) in the fork ending at `tip_index_hash`, in height order.
+    /// Returns None if any of those blocks is not in the fork.
+    pub fn get_withdrawal_roots_in_range(
+        &self,
+        tip_index_hash: &StacksBlockId,
+        first_height: u64,
+        last_height: u64,
+    ) -> Result<Option<Vec<Sha512Trunc256Sum>>, Error> {
+        let index_conn = self.index_conn()?;
+        let mut roots = vec![];
+        for height in first_height..=last_height {
+            let block_id = match index_conn
+                .get_ancestor_block_hash(height, tip_index_hash)
+                .map_err(Error::DBError)?
+            {
+                Some(block_id) => block_id,
+                None => return Ok(None),
+            };
+            match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                self.db(),
+                &block_id,
+            )? {
+                Some(block_info) => roots.push(block_info.withdrawal_tree.root()),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(roots))
+    }
+
+    /// Get the withdrawal batching rule in effect in the fork ending at `tip_index_hash`.
+    /// Returns None if the subnet does not batch withdrawals, if the fork has not yet reached
+    /// `EpochSubnet1` (where batching starts), or if there is no such fork.
+    pub fn get_withdrawal_batching(
+        &mut self,
+        burn_dbconn: &dyn BurnStateDB,
+        tip_index_hash: &StacksBlockId,
+    ) -> Option<WithdrawalBatchConfig> {
+        self.with_read_only_clarity_tx(burn_dbconn, tip_index_hash, |conn| {
+            conn.with_clarity_db_readonly(|db| {
+                let batch_blocks = db.get_withdrawal_batch_blocks();
+                db.get_withdrawal_batch_start_height()
+                    .map(|start_height| WithdrawalBatchConfig {
+                        batch_blocks,
+                        start_height,
+                    })
+            })
+        })
+        .flatten()
+    }
+
     /// Get a segment of headers from the canonical chain
     pub fn get_ancestors_headers(
         conn: &Connection,
@@ -325,6 +455,16 @@ impl StacksChainState {
         Ok(row_opt.expect("BUG: no genesis header info"))
     }
 
+    /// Get the headers of all processed blocks at the given height, in every fork
+    pub fn get_stacks_block_headers_at_height(
+        conn: &Connection,
+        block_height: u64,
+    ) -> Result<Vec<StacksHeaderInfo>, Error> {
+        let sql = "SELECT * FROM block_headers WHERE block_height = ?1";
+        let args: &[&dyn ToSql] = &[&u64_to_sql(block_height)?];
+        query_rows(conn, sql, args).map_err(Error::DBError)
+    }
+
     /// Get the parent block ID for this block
     pub fn get_parent_block_id(
         conn: &Connection,
@@ -347,3 +487,76 @@ impl StacksChainState {
             .is_some())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::burn::db::sortdb::SortitionDB;
+    use crate::chainstate::stacks::miner::test::make_user_contract_publish;
+    use crate::net::test::*;
+    use clarity::vm::types::StacksAddressExtensions;
+
+    #[test]
+    fn test_record_processed_block_costs() {
+        let privk = StacksPrivateKey::from_hex(
+            "42faca653724860da7a41bfcef7e6ba78db55146f6900de8cb2a9f760ffac70c01",
+        )
+        .unwrap();
+        let addr = StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(&privk)],
+        )
+        .unwrap();
+
+        let mut peer_config =
+            TestPeerConfig::new("test_record_processed_block_costs", 21346, 21347);
+        peer_config.initial_balances = vec![(addr.to_account_principal(), 1000000000)];
+        let mut peer = TestPeer::new(peer_config);
+        let mut coinbase_nonce = 0;
+
+        let publish =
+            make_user_contract_publish(&privk, 0, 200, "counter", "(define-data-var n int 1)");
+        let tip = peer.tenure_with_txs(&[publish], &mut coinbase_nonce);
+
+        let costs = StacksChainState::get_block_costs(peer.chainstate().db(), &tip)
+            .unwrap()
+            .unwrap();
+        assert_eq!(costs.index_block_hash, tip);
+
+        // the anchored cost is the one charged to the block's header
+        let anchored_cost =
+            StacksChainState::get_stacks_block_anchored_cost(peer.chainstate().db(), &tip)
+                .unwrap()
+                .unwrap();
+        assert_eq!(costs.anchored_cost, anchored_cost);
+        assert!(costs.anchored_cost.runtime > 0);
+        assert!(costs.anchored_cost.write_count > 0);
+
+        // no microblocks were confirmed
+        assert_eq!(costs.confirmed_microblocks_cost, ExecutionCost::zero());
+        assert_eq!(costs.total_cost, costs.anchored_cost);
+
+        // the limit is the one of the epoch the block was processed in
+        let sortdb = peer.sortdb.take().unwrap();
+        let burn_tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn()).unwrap();
+        let epoch = SortitionDB::get_stacks_epoch(sortdb.conn(), burn_tip.block_height)
+            .unwrap()
+            .unwrap();
+        peer.sortdb = Some(sortdb);
+        assert_eq!(costs.block_limit, epoch.block_limit);
+        assert!(
+            costs
+                .block_limit
+                .proportion_largest_dimension(&costs.total_cost)
+                < 100
+        );
+
+        assert_eq!(
+            StacksChainState::get_block_costs(peer.chainstate().db(), &StacksBlockId([0x11; 32]))
+                .unwrap(),
+            None
+        );
+    }
+}
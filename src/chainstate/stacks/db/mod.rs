@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::cmp;
 use std::collections::{btree_map::Entry, BTreeMap};
 use std::fmt;
 use std::fs;
@@ -21,6 +22,7 @@ use std::io;
 use std::io::prelude::*;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 use rusqlite::types::ToSql;
 use rusqlite::Connection;
@@ -38,6 +40,7 @@ use crate::chainstate::burn::ConsensusHash;
 use crate::chainstate::stacks::boot::*;
 use crate::chainstate::stacks::db::accounts::*;
 use crate::chainstate::stacks::db::blocks::*;
+use crate::chainstate::stacks::db::genesis::GenesisSpec;
 use crate::chainstate::stacks::db::unconfirmed::UnconfirmedState;
 use crate::chainstate::stacks::events::*;
 use crate::chainstate::stacks::index::marf::{
@@ -46,6 +49,7 @@ use crate::chainstate::stacks::index::marf::{
 };
 use crate::chainstate::stacks::index::storage::TrieFileStorage;
 use crate::chainstate::stacks::index::MarfTrieId;
+use crate::chainstate::stacks::system_priority::SystemPriorityPolicy;
 use crate::chainstate::stacks::Error;
 use crate::chainstate::stacks::*;
 use crate::chainstate::stacks::{
@@ -63,9 +67,10 @@ use crate::net::Error as net_error;
 use crate::net::MemPoolSyncData;
 use crate::util_lib::db::Error as db_error;
 use crate::util_lib::db::{
-    query_count, query_row, tx_begin_immediate, tx_busy_handler, DBConn, DBTx, FromColumn, FromRow,
-    IndexDBConn, IndexDBTx,
+    query_count, query_row, sqlite_open, tx_begin_immediate, tx_busy_handler, DBConn, DBTx,
+    FromColumn, FromRow, IndexDBConn, IndexDBTx,
 };
+use crate::util_lib::migrations::{SchemaMigration, SchemaMigrations, SchemaStatus};
 use clarity::vm::analysis::analysis_db::AnalysisDatabase;
 use clarity::vm::analysis::run_analysis;
 use clarity::vm::ast::build_ast;
@@ -89,7 +94,7 @@ use crate::chainstate::stacks::address::StacksAddressExtensions;
 use crate::chainstate::stacks::index::{ClarityMarfTrieId, MARFValue};
 use crate::chainstate::stacks::StacksBlockHeader;
 use crate::chainstate::stacks::StacksMicroblockHeader;
-use crate::clarity_vm::database::marf::MarfedKV;
+use crate::clarity_vm::database::marf::{MARFWrite, MarfedKV};
 use crate::clarity_vm::database::HeadersDBConn;
 use crate::util_lib::boot::{boot_code_acc, boot_code_addr, boot_code_id, boot_code_tx_auth};
 use clarity::vm::Value;
@@ -97,7 +102,14 @@ use stacks_common::types::chainstate::{StacksAddress, StacksBlockId, TrieHash};
 pub mod accounts;
 pub mod blocks;
 pub mod contracts;
+pub mod deposits;
+pub mod genesis;
 pub mod headers;
+pub mod pruning;
+pub mod receipts;
+pub mod replay;
+pub mod results;
+pub mod state_diff;
 pub mod transactions;
 pub mod unconfirmed;
 
@@ -117,8 +129,30 @@ pub struct StacksChainState {
     pub root_path: String,
     pub unconfirmed_state: Option<UnconfirmedState>,
     marf_opts: Option<MARFOpenOpts>,
+    /// Maximum number of deposit operations from one L1 block to apply in a single Clarity
+    /// transaction.
+    deposit_batch_size: usize,
+    /// Whether or not to keep a record of the results of each processed block.
+    record_block_results: bool,
+    /// Whether or not to keep a record of the state written by each processed block.
+    record_state_diffs: bool,
+    /// Which transactions are system priority transactions, and how much of each block's budget
+    /// is reserved for them.
+    system_priority: Option<SystemPriorityPolicy>,
+    /// If set, the number of confirmed blocks below the canonical tip whose bodies and state
+    /// are retained.  Older data is periodically pruned.
+    prune_depth: Option<u64>,
+    /// The canonical tip height at which the next pruning pass is due.
+    next_prune_height: u64,
+    /// Idle read-only handles onto the Clarity MARF, shared by every handle onto this
+    /// chainstate.  Read-only queries run through these so that they do not contend with block
+    /// processing on `clarity_state`.
+    read_only_clarity_pool: Arc<Mutex<Vec<ClarityInstance>>>,
 }
 
+/// The most idle read-only Clarity MARF handles that a chainstate's pool keeps open
+pub const MAX_IDLE_READ_ONLY_CLARITY_HANDLES: usize = 8;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct StacksAccount {
     pub principal: PrincipalData,
@@ -193,8 +227,25 @@ impl DBConfig {
     pub fn supports_epoch(&self, epoch_id: StacksEpochId) -> bool {
         match epoch_id {
             StacksEpochId::Epoch10 => false,
-            StacksEpochId::Epoch20 => self.version == "1" || self.version == "2",
-            StacksEpochId::Epoch2_05 => self.version == "2",
+            StacksEpochId::Epoch20 => {
+                self.version == "1"
+                    || self.version == "2"
+                    || self.version == "3"
+                    || self.version == "4"
+                    || self.version == "5"
+                    || self.version == "6"
+                    || self.version == "7"
+                    || self.version == "8"
+            }
+            StacksEpochId::Epoch2_05 | StacksEpochId::EpochSubnet1 => {
+                self.version == "2"
+                    || self.version == "3"
+                    || self.version == "4"
+                    || self.version == "5"
+                    || self.version == "6"
+                    || self.version == "7"
+                    || self.version == "8"
+            }
         }
     }
 }
@@ -346,6 +397,18 @@ impl<'a, 'b> ClarityTx<'a, 'b> {
         self.block.block_limit()
     }
 
+    /// Replace the block limit for the block being created.  Used by the miner to hold some
+    /// transactions to a smaller budget than the block's.
+    pub fn set_block_limit(&mut self, limit: ExecutionCost) -> () {
+        self.block.set_block_limit(limit)
+    }
+
+    /// Make a cost tracker with the cost functions and limit of the block being created, which
+    /// has not spent any of its budget.
+    pub fn make_unspent_cost_tracker(&self) -> LimitedCostTracker {
+        self.block.make_unspent_cost_tracker()
+    }
+
     /// Run `todo` in this ClarityTx with `new_tracker`.
     /// Returns the result of `todo` and the `new_tracker`
     pub fn with_temporary_cost_tracker<F, R>(
@@ -367,6 +430,16 @@ impl<'a, 'b> ClarityTx<'a, 'b> {
         self.block.seal()
     }
 
+    /// Start recording the keys this block writes, with their values before and after it
+    pub fn record_write_set(&mut self) {
+        self.block.record_write_set()
+    }
+
+    /// Take the keys this block has written since `record_write_set` was called
+    pub fn take_write_set(&mut self) -> Option<Vec<MARFWrite>> {
+        self.block.take_write_set()
+    }
+
     #[cfg(test)]
     pub fn commit_block(self) -> () {
         self.block.commit_block();
@@ -431,6 +504,13 @@ pub struct ChainstateTx<'a> {
     pub blocks_path: String,
     pub tx: StacksDBTx<'a>,
     pub root_path: String,
+    pub deposit_batch_size: usize,
+    pub system_priority: Option<SystemPriorityPolicy>,
+    /// Whether to record the state written by each block that is appended
+    pub record_state_diffs: bool,
+    /// The state root this transaction's block evaluated to, once it has been sealed.  This is
+    /// what the block's header is checked against, so it is kept even if the block is rejected.
+    pub computed_state_root: Option<TrieHash>,
 }
 
 impl<'a> ChainstateTx<'a> {
@@ -439,12 +519,19 @@ impl<'a> ChainstateTx<'a> {
         blocks_path: String,
         root_path: String,
         config: DBConfig,
+        deposit_batch_size: usize,
+        system_priority: Option<SystemPriorityPolicy>,
+        record_state_diffs: bool,
     ) -> ChainstateTx<'a> {
         ChainstateTx {
             config,
             blocks_path,
             tx,
             root_path,
+            deposit_batch_size,
+            system_priority,
+            record_state_diffs,
+            computed_state_root: None,
         }
     }
 
@@ -581,12 +668,14 @@ pub struct TxStreamData {
     pub max_txs: u64,
     /// height of the chain at time of query
     pub height: u64,
+    /// how far back from `height` (in Stacks blocks) to look for transactions
+    pub depth: u64,
     /// Are we done sending transactions, and are now in the process of sending the trailing page
     /// ID?
     pub corked: bool,
 }
 
-pub const CHAINSTATE_VERSION: &'static str = "2";
+pub const CHAINSTATE_VERSION: &'static str = "8";
 
 const CHAINSTATE_INITIAL_SCHEMA: &'static [&'static str] = &[
     "PRAGMA foreign_keys = ON;",
@@ -738,11 +827,188 @@ const CHAINSTATE_SCHEMA_2: &'static [&'static str] = &[
     CREATE TABLE epoch_transitions(
         block_id TEXT PRIMARY KEY
     );"#,
+];
+
+const CHAINSTATE_SCHEMA_3: &'static [&'static str] = &[
+    // new in schema version 3
+    // JSON-encoded results of processing each block, if the node records them
+    r#"
+    CREATE TABLE block_results(
+        index_block_hash TEXT PRIMARY KEY,
+        results TEXT NOT NULL
+    );"#,
+];
+
+const CHAINSTATE_SCHEMA_4: &'static [&'static str] = &[
+    // new in schema version 4
+    // L1 deposit operations materialized by each block
+    r#"
+    CREATE TABLE materialized_deposits(
+        l1_txid TEXT NOT NULL,
+        deposit_type TEXT NOT NULL,
+        l1_block_id TEXT NOT NULL,
+        index_block_hash TEXT NOT NULL,
+        block_height INTEGER NOT NULL,
+        PRIMARY KEY(l1_txid, deposit_type, index_block_hash)
+    );"#,
+];
+
+const CHAINSTATE_SCHEMA_5: &'static [&'static str] = &[
+    // new in schema version 5
+    // execution costs consumed by each block, and the block limit it was evaluated against
+    r#"
+    CREATE TABLE block_costs(
+        index_block_hash TEXT PRIMARY KEY,
+        anchored_cost TEXT NOT NULL,
+        confirmed_microblocks_cost TEXT NOT NULL,
+        total_cost TEXT NOT NULL,
+        block_limit TEXT NOT NULL
+    );"#,
+];
+
+const CHAINSTATE_SCHEMA_6: &'static [&'static str] = &[
+    // new in schema version 6
+    // JSON-encoded receipt of each transaction processed by each block
+    r#"
+    CREATE TABLE transaction_receipts(
+        txid TEXT NOT NULL,
+        index_block_hash TEXT NOT NULL,
+        block_height INTEGER NOT NULL,
+        receipt TEXT NOT NULL,
+        PRIMARY KEY(txid, index_block_hash)
+    );"#,
+];
+
+const CHAINSTATE_SCHEMA_7: &'static [&'static str] = &[
+    // new in schema version 7
+    // anchored blocks whose bodies have been deleted by a pruning node
+    r#"
+    CREATE TABLE pruned_blocks(
+        index_block_hash TEXT PRIMARY KEY,
+        block_height INTEGER NOT NULL
+    );"#,
+];
+
+const CHAINSTATE_SCHEMA_8: &'static [&'static str] = &[
+    // new in schema version 8
+    // encoded state written by each block, if the node records it
     r#"
-    UPDATE db_config SET version = "2";
-    "#,
+    CREATE TABLE block_state_diffs(
+        index_block_hash TEXT PRIMARY KEY,
+        write_set BLOB NOT NULL
+    );"#,
 ];
 
+/// Forward migrations for the chainstate DB schema.  The schema version is stamped into
+/// `db_config`.
+pub const CHAINSTATE_MIGRATIONS: SchemaMigrations = SchemaMigrations {
+    db_name: "chainstate",
+    initial_version: 1,
+    migrations: &[
+        SchemaMigration {
+            to_version: 2,
+            description: "add epoch_transitions table (epoch 2.05)",
+            apply: migrate_chainstate_schema_2,
+        },
+        SchemaMigration {
+            to_version: 3,
+            description: "add block_results table",
+            apply: migrate_chainstate_schema_3,
+        },
+        SchemaMigration {
+            to_version: 4,
+            description: "add materialized_deposits table",
+            apply: migrate_chainstate_schema_4,
+        },
+        SchemaMigration {
+            to_version: 5,
+            description: "add block_costs table",
+            apply: migrate_chainstate_schema_5,
+        },
+        SchemaMigration {
+            to_version: 6,
+            description: "add transaction_receipts table",
+            apply: migrate_chainstate_schema_6,
+        },
+        SchemaMigration {
+            to_version: 7,
+            description: "add pruned_blocks table",
+            apply: migrate_chainstate_schema_7,
+        },
+        SchemaMigration {
+            to_version: 8,
+            description: "add block_state_diffs table",
+            apply: migrate_chainstate_schema_8,
+        },
+    ],
+    load_version: load_chainstate_schema_version,
+    store_version: store_chainstate_schema_version,
+};
+
+fn migrate_chainstate_schema_2(tx: &DBTx) -> Result<(), db_error> {
+    for cmd in CHAINSTATE_SCHEMA_2.iter() {
+        tx.execute_batch(cmd)?;
+    }
+    Ok(())
+}
+
+fn migrate_chainstate_schema_3(tx: &DBTx) -> Result<(), db_error> {
+    for cmd in CHAINSTATE_SCHEMA_3.iter() {
+        tx.execute_batch(cmd)?;
+    }
+    Ok(())
+}
+
+fn migrate_chainstate_schema_4(tx: &DBTx) -> Result<(), db_error> {
+    for cmd in CHAINSTATE_SCHEMA_4.iter() {
+        tx.execute_batch(cmd)?;
+    }
+    Ok(())
+}
+
+fn migrate_chainstate_schema_5(tx: &DBTx) -> Result<(), db_error> {
+    for cmd in CHAINSTATE_SCHEMA_5.iter() {
+        tx.execute_batch(cmd)?;
+    }
+    Ok(())
+}
+
+fn migrate_chainstate_schema_6(tx: &DBTx) -> Result<(), db_error> {
+    for cmd in CHAINSTATE_SCHEMA_6.iter() {
+        tx.execute_batch(cmd)?;
+    }
+    Ok(())
+}
+
+fn migrate_chainstate_schema_7(tx: &DBTx) -> Result<(), db_error> {
+    for cmd in CHAINSTATE_SCHEMA_7.iter() {
+        tx.execute_batch(cmd)?;
+    }
+    Ok(())
+}
+
+fn migrate_chainstate_schema_8(tx: &DBTx) -> Result<(), db_error> {
+    for cmd in CHAINSTATE_SCHEMA_8.iter() {
+        tx.execute_batch(cmd)?;
+    }
+    Ok(())
+}
+
+fn load_chainstate_schema_version(conn: &DBConn) -> Result<u32, db_error> {
+    let version: String =
+        conn.query_row("SELECT version FROM db_config LIMIT 1", NO_PARAMS, |row| {
+            row.get(0)
+        })?;
+    version.parse::<u32>().map_err(|_| {
+        db_error::UnsupportedSchema(format!("chainstate schema version '{}'", &version))
+    })
+}
+
+fn store_chainstate_schema_version(tx: &DBTx, version: u32) -> Result<(), db_error> {
+    tx.execute("UPDATE db_config SET version = ?1", &[&version.to_string()])?;
+    Ok(())
+}
+
 const CHAINSTATE_INDEXES: &'static [&'static str] = &[
     "CREATE INDEX IF NOT EXISTS index_block_hash_to_primary_key ON block_headers(index_block_hash,consensus_hash,block_hash);",
     "CREATE INDEX IF NOT EXISTS block_headers_hash_index ON block_headers(block_hash,block_height);",
@@ -780,6 +1046,9 @@ pub const MINER_FEE_WINDOW: u64 = 24; // number of blocks (B) used to smooth ove
 // fraction (out of 100) of the coinbase a user will receive for reporting a microblock stream fork
 pub const POISON_MICROBLOCK_COMMISSION_FRACTION: u128 = 5;
 
+// default maximum number of deposit operations applied in a single Clarity transaction
+pub const DEFAULT_DEPOSIT_BATCH_SIZE: usize = 256;
+
 #[derive(Debug, Clone)]
 pub struct ChainstateAccountBalance {
     pub address: String,
@@ -837,6 +1106,22 @@ pub struct ChainStateBootData {
         Option<Box<dyn FnOnce() -> Box<dyn Iterator<Item = ChainstateBNSNamespace>>>>,
     pub get_bulk_initial_names:
         Option<Box<dyn FnOnce() -> Box<dyn Iterator<Item = ChainstateBNSName>>>>,
+    /// Contracts and token allocations to materialize in the genesis block
+    pub genesis_spec: GenesisSpec,
+    // The settings below are committed to the genesis block's Clarity state, and
+    // `check_committed_settings()` refuses to reopen the chainstate with different ones.
+    /// Whether the subnet accepts contracts that leave intermediary responses unchecked.
+    pub allow_unchecked_intermediary_responses: bool,
+    /// How many consecutive blocks' withdrawal roots are registered with the L1 as one batch.
+    pub withdrawal_batch_blocks: u64,
+    /// The subnet's epoch schedule, with each epoch's start and end heights and block limit.
+    pub epochs: Option<Vec<StacksEpoch>>,
+    /// The policy that decides which transactions may use the reserved slice of each block.
+    pub system_priority: Option<SystemPriorityPolicy>,
+    /// The cap on the size of the events a single transaction may emit.
+    pub max_tx_event_bytes: Option<u64>,
+    /// Whether the subnet switches to `.costs-subnet` at the subnet-1 epoch transition.
+    pub use_subnet_costs: bool,
 }
 
 impl ChainStateBootData {
@@ -856,6 +1141,13 @@ impl ChainStateBootData {
             get_bulk_initial_balances: None,
             get_bulk_initial_namespaces: None,
             get_bulk_initial_names: None,
+            genesis_spec: GenesisSpec::default(),
+            allow_unchecked_intermediary_responses: false,
+            withdrawal_batch_blocks: 0,
+            epochs: None,
+            system_priority: None,
+            max_tx_event_bytes: None,
+            use_subnet_costs: false,
         }
     }
 }
@@ -924,7 +1216,7 @@ impl StacksChainState {
         mainnet: bool,
         chain_id: u32,
     ) -> Result<(), Error> {
-        let mut db_config =
+        let db_config =
             StacksChainState::load_db_config(tx).expect("CORRUPTION: no db_config found");
 
         if db_config.mainnet != mainnet {
@@ -943,31 +1235,30 @@ impl StacksChainState {
             return Err(Error::InvalidChainstateDB);
         }
 
-        if db_config.version != CHAINSTATE_VERSION {
-            while db_config.version != CHAINSTATE_VERSION {
-                match db_config.version.as_str() {
-                    "1" => {
-                        // migrate to 2
-                        info!("Migrating chainstate schema from version 1 to 2");
-                        for cmd in CHAINSTATE_SCHEMA_2.iter() {
-                            tx.execute_batch(cmd)?;
-                        }
-                    }
-                    _ => {
-                        error!(
-                            "Invalid chain state database: expected version = {}, got {}",
-                            CHAINSTATE_VERSION, db_config.version
-                        );
-                        return Err(Error::InvalidChainstateDB);
-                    }
-                }
-                db_config =
-                    StacksChainState::load_db_config(tx).expect("CORRUPTION: no db_config found");
+        CHAINSTATE_MIGRATIONS.migrate(tx).map_err(|e| match e {
+            db_error::UnsupportedSchema(msg) => {
+                error!(
+                    "Invalid chain state database: expected version = {}, got {}",
+                    CHAINSTATE_VERSION, db_config.version; "error" => %msg
+                );
+                Error::InvalidChainstateDB
             }
-        }
+            e => Error::DBError(e),
+        })?;
         Ok(())
     }
 
+    /// Check the schema of the chainstate DB in the given chainstate root without migrating it.
+    pub fn check_schema_migrations(chainstate_root_path: &str) -> Result<SchemaStatus, db_error> {
+        let index_path =
+            StacksChainState::header_index_root_path(PathBuf::from(chainstate_root_path));
+        if fs::metadata(&index_path).is_err() {
+            return Err(db_error::NoDBError);
+        }
+        let conn = sqlite_open(&index_path, OpenFlags::SQLITE_OPEN_READ_ONLY, false)?;
+        CHAINSTATE_MIGRATIONS.check(&conn)
+    }
+
     fn add_indexes<'a>(tx: &DBTx<'a>) -> Result<(), Error> {
         for cmd in CHAINSTATE_INDEXES {
             tx.execute_batch(cmd)?;
@@ -1158,6 +1449,23 @@ impl StacksChainState {
                 allocation_events.push(mint_event);
             }
 
+            if !boot_data.genesis_spec.is_empty() {
+                info!(
+                    "Materializing genesis specification: {} contracts, {} FT and {} NFT allocations",
+                    boot_data.genesis_spec.contracts.len(),
+                    boot_data.genesis_spec.ft_allocations.len(),
+                    boot_data.genesis_spec.nft_allocations.len()
+                );
+            }
+            let (mut genesis_receipts, mut genesis_mint_events) =
+                StacksChainState::install_genesis_spec(
+                    &mut clarity_tx,
+                    mainnet,
+                    &boot_data.genesis_spec,
+                )?;
+            receipts.append(&mut genesis_receipts);
+            allocation_events.append(&mut genesis_mint_events);
+
             clarity_tx.connection().as_transaction(|clarity| {
                 // Balances
                 if let Some(get_balances) = boot_data.get_bulk_initial_balances.take() {
@@ -1452,11 +1760,74 @@ impl StacksChainState {
                 })
                 .expect("FATAL: `ustx-liquid-supply` overflowed");
 
+            if boot_data.allow_unchecked_intermediary_responses {
+                clarity_tx.connection().as_transaction(|tx| {
+                    tx.with_clarity_db(|db| {
+                        db.set_allow_unchecked_intermediary_responses(true);
+                        Ok(())
+                    })
+                    .expect("FATAL: failed to store genesis analysis settings")
+                });
+            }
+
+            if boot_data.withdrawal_batch_blocks > 0 {
+                let withdrawal_batch_blocks = boot_data.withdrawal_batch_blocks;
+                clarity_tx.connection().as_transaction(|tx| {
+                    tx.with_clarity_db(|db| {
+                        db.set_withdrawal_batch_blocks(withdrawal_batch_blocks);
+                        Ok(())
+                    })
+                    .expect("FATAL: failed to store genesis withdrawal batching settings")
+                });
+            }
+
+            if let Some(ref epochs) = boot_data.epochs {
+                clarity_tx.connection().as_transaction(|tx| {
+                    tx.with_clarity_db(|db| {
+                        db.set_epoch_schedule(epochs);
+                        Ok(())
+                    })
+                    .expect("FATAL: failed to store genesis epoch schedule")
+                });
+            }
+
+            if let Some(ref policy) = boot_data.system_priority {
+                let policy = serde_json::to_string(policy)
+                    .expect("FATAL: failed to serialize system priority policy");
+                clarity_tx.connection().as_transaction(|tx| {
+                    tx.with_clarity_db(|db| {
+                        db.set_system_priority_policy(policy);
+                        Ok(())
+                    })
+                    .expect("FATAL: failed to store genesis system priority policy")
+                });
+            }
+
+            if let Some(max_tx_event_bytes) = boot_data.max_tx_event_bytes {
+                clarity_tx.connection().as_transaction(|tx| {
+                    tx.with_clarity_db(|db| {
+                        db.set_max_tx_event_bytes(max_tx_event_bytes);
+                        Ok(())
+                    })
+                    .expect("FATAL: failed to store genesis event size cap")
+                });
+            }
+
+            if boot_data.use_subnet_costs {
+                clarity_tx.connection().as_transaction(|tx| {
+                    tx.with_clarity_db(|db| {
+                        db.set_use_subnet_costs(true);
+                        Ok(())
+                    })
+                    .expect("FATAL: failed to store genesis cost profile")
+                });
+            }
+
             clarity_tx.commit_to_block(&FIRST_BURNCHAIN_CONSENSUS_HASH, &FIRST_STACKS_BLOCK_HASH);
         }
 
         // verify that genesis root hash is as expected
-        {
+        let genesis_root_hash = {
             let genesis_root_hash = chainstate.clarity_state.with_marf(|marf| {
                 let index_block_hash = StacksBlockHeader::make_index_block_hash(
                     &FIRST_BURNCHAIN_CONSENSUS_HASH,
@@ -1476,7 +1847,20 @@ impl StacksChainState {
                     genesis_root_hash.to_string()
                 )
             }
-        }
+
+            if let Some(expected_root_hash) = boot_data.genesis_spec.expected_root_hash.as_ref() {
+                if *expected_root_hash != genesis_root_hash {
+                    error!("Computed a different genesis than expected";
+                           "expected_root_hash" => %expected_root_hash,
+                           "root_hash" => %genesis_root_hash);
+                    return Err(Error::InvalidStacksBlock(format!(
+                        "Genesis root hash {} does not match the expected root hash {}",
+                        &genesis_root_hash, expected_root_hash
+                    )));
+                }
+            }
+            genesis_root_hash
+        };
 
         {
             // add a block header entry for the boot code
@@ -1502,12 +1886,14 @@ impl StacksChainState {
                 &first_index_hash
             );
 
-            let first_tip_info = StacksHeaderInfo::genesis(
+            let mut first_tip_info = StacksHeaderInfo::genesis(
                 first_root_hash,
                 &boot_data.first_burnchain_block_hash,
                 boot_data.first_burnchain_block_height,
                 boot_data.first_burnchain_block_timestamp as u64,
             );
+            // commit to the genesis state, so that nodes can check that they booted the same one
+            first_tip_info.anchored_header.state_index_root = genesis_root_hash;
 
             StacksChainState::insert_stacks_block_header(
                 &mut tx,
@@ -1534,12 +1920,199 @@ impl StacksChainState {
     /// Re-open the chainstate -- i.e. to get a new handle to it using an existing chain state's
     /// parameters
     pub fn reopen(&self) -> Result<(StacksChainState, Vec<StacksTransactionReceipt>), Error> {
-        StacksChainState::open(
+        let (mut chainstate, receipts) = StacksChainState::open(
             self.mainnet,
             self.chain_id,
             &self.root_path,
             self.marf_opts.clone(),
-        )
+        )?;
+        chainstate.deposit_batch_size = self.deposit_batch_size;
+        chainstate.record_block_results = self.record_block_results;
+        chainstate.record_state_diffs = self.record_state_diffs;
+        chainstate.system_priority = self.system_priority.clone();
+        chainstate.prune_depth = self.prune_depth;
+        chainstate
+            .clarity_state
+            .set_max_tx_event_bytes(self.clarity_state.max_tx_event_bytes());
+        chainstate
+            .clarity_state
+            .set_use_subnet_costs(self.clarity_state.use_subnet_costs());
+        chainstate.read_only_clarity_pool = self.read_only_clarity_pool.clone();
+        Ok((chainstate, receipts))
+    }
+
+    /// Set the maximum number of deposit operations from a single L1 block that will be applied
+    /// to the Clarity state in one transaction.  Batching deposits does not change the resulting
+    /// state; it only reduces how often the intermediate state is materialized.  A value of 0 is
+    /// treated as 1.
+    pub fn set_deposit_batch_size(&mut self, deposit_batch_size: usize) {
+        self.deposit_batch_size = cmp::max(deposit_batch_size, 1);
+    }
+
+    pub fn get_deposit_batch_size(&self) -> usize {
+        self.deposit_batch_size
+    }
+
+    /// Set the policy that determines which transactions are system priority transactions, and
+    /// how much of each block's execution budget is reserved for them.  Blocks that spend the
+    /// reserved budget on other transactions fail validation.
+    pub fn set_system_priority_policy(&mut self, policy: Option<SystemPriorityPolicy>) {
+        self.system_priority = policy;
+    }
+
+    pub fn get_system_priority_policy(&self) -> Option<&SystemPriorityPolicy> {
+        self.system_priority.as_ref()
+    }
+
+    /// Get the epoch schedule committed to this chain's genesis block.  Returns None if the chain
+    /// was instantiated without one.
+    pub fn get_committed_epoch_schedule(&mut self) -> Option<Vec<StacksEpoch>> {
+        let genesis_block_id = StacksBlockHeader::make_index_block_hash(
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+        );
+        self.with_read_only_clarity_tx(&NULL_BURN_STATE_DB, &genesis_block_id, |conn| {
+            conn.with_clarity_db_readonly(|db| db.get_epoch_schedule())
+        })
+        .flatten()
+    }
+
+    /// Get the system priority policy committed to this chain's genesis block.  Returns None if
+    /// the chain was instantiated without one.
+    pub fn get_committed_system_priority_policy(&mut self) -> Option<SystemPriorityPolicy> {
+        let genesis_block_id = StacksBlockHeader::make_index_block_hash(
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+        );
+        self.with_read_only_clarity_tx(&NULL_BURN_STATE_DB, &genesis_block_id, |conn| {
+            conn.with_clarity_db_readonly(|db| db.get_system_priority_policy())
+        })
+        .flatten()
+        .map(|policy| {
+            serde_json::from_str(&policy)
+                .expect("FATAL: failed to deserialize committed system priority policy")
+        })
+    }
+
+    /// Get the cap on the size of a transaction's events committed to this chain's genesis block.
+    /// Returns None if the chain was instantiated without one.
+    pub fn get_committed_max_tx_event_bytes(&mut self) -> Option<u64> {
+        let genesis_block_id = StacksBlockHeader::make_index_block_hash(
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+        );
+        self.with_read_only_clarity_tx(&NULL_BURN_STATE_DB, &genesis_block_id, |conn| {
+            conn.with_clarity_db_readonly(|db| db.get_max_tx_event_bytes())
+        })
+        .flatten()
+    }
+
+    /// Get whether this chain's genesis block allows contracts that leave intermediary responses
+    /// unchecked.
+    pub fn get_committed_allow_unchecked_intermediary_responses(&mut self) -> bool {
+        let genesis_block_id = StacksBlockHeader::make_index_block_hash(
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+        );
+        self.with_read_only_clarity_tx(&NULL_BURN_STATE_DB, &genesis_block_id, |conn| {
+            conn.with_clarity_db_readonly(|db| db.get_allow_unchecked_intermediary_responses())
+        })
+        .unwrap_or(false)
+    }
+
+    /// Get the withdrawal batch size committed to this chain's genesis block.  Returns 0 if the
+    /// chain does not batch withdrawals.
+    pub fn get_committed_withdrawal_batch_blocks(&mut self) -> u64 {
+        let genesis_block_id = StacksBlockHeader::make_index_block_hash(
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+        );
+        self.with_read_only_clarity_tx(&NULL_BURN_STATE_DB, &genesis_block_id, |conn| {
+            conn.with_clarity_db_readonly(|db| db.get_withdrawal_batch_blocks())
+        })
+        .unwrap_or(0)
+    }
+
+    /// Get whether this chain's genesis block commits it to the `.costs-subnet` cost functions.
+    pub fn get_committed_use_subnet_costs(&mut self) -> bool {
+        let genesis_block_id = StacksBlockHeader::make_index_block_hash(
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+        );
+        self.with_read_only_clarity_tx(&NULL_BURN_STATE_DB, &genesis_block_id, |conn| {
+            conn.with_clarity_db_readonly(|db| db.get_use_subnet_costs())
+        })
+        .unwrap_or(false)
+    }
+
+    /// Check that the consensus settings in `boot_data` are the ones this chain's genesis block
+    /// was instantiated with.  They cannot change once the chain exists.
+    fn check_committed_settings(&mut self, boot_data: &ChainStateBootData) -> Result<(), Error> {
+        let committed_policy = self.get_committed_system_priority_policy();
+        if committed_policy != boot_data.system_priority {
+            error!(
+                "Chainstate DB at {} was instantiated with a different system priority policy",
+                &self.root_path;
+                "committed" => ?committed_policy,
+                "configured" => ?boot_data.system_priority
+            );
+            return Err(Error::DBError(db_error::GenesisSettingMismatch(
+                "system priority policy".into(),
+            )));
+        }
+
+        let committed_max_tx_event_bytes = self.get_committed_max_tx_event_bytes();
+        if committed_max_tx_event_bytes != boot_data.max_tx_event_bytes {
+            error!(
+                "Chainstate DB at {} was instantiated with a different event size cap",
+                &self.root_path;
+                "committed" => ?committed_max_tx_event_bytes,
+                "configured" => ?boot_data.max_tx_event_bytes
+            );
+            return Err(Error::DBError(db_error::GenesisSettingMismatch(
+                "event size cap".into(),
+            )));
+        }
+
+        let committed_use_subnet_costs = self.get_committed_use_subnet_costs();
+        if committed_use_subnet_costs != boot_data.use_subnet_costs {
+            error!(
+                "Chainstate DB at {} was instantiated with a different cost profile",
+                &self.root_path;
+                "committed" => committed_use_subnet_costs,
+                "configured" => boot_data.use_subnet_costs
+            );
+            return Err(Error::DBError(db_error::GenesisSettingMismatch(
+                "cost profile".into(),
+            )));
+        }
+
+        let committed_allow_unchecked = self.get_committed_allow_unchecked_intermediary_responses();
+        if committed_allow_unchecked != boot_data.allow_unchecked_intermediary_responses {
+            error!(
+                "Chainstate DB at {} was instantiated with a different intermediary response check",
+                &self.root_path;
+                "committed" => committed_allow_unchecked,
+                "configured" => boot_data.allow_unchecked_intermediary_responses
+            );
+            return Err(Error::DBError(db_error::GenesisSettingMismatch(
+                "intermediary response check".into(),
+            )));
+        }
+
+        let committed_withdrawal_batch_blocks = self.get_committed_withdrawal_batch_blocks();
+        if committed_withdrawal_batch_blocks != boot_data.withdrawal_batch_blocks {
+            error!(
+                "Chainstate DB at {} was instantiated with a different withdrawal batch size",
+                &self.root_path;
+                "committed" => committed_withdrawal_batch_blocks,
+                "configured" => boot_data.withdrawal_batch_blocks
+            );
+            return Err(Error::DBError(db_error::GenesisSettingMismatch(
+                "withdrawal batch size".into(),
+            )));
+        }
+        Ok(())
     }
 
     pub fn blocks_path(mut path: PathBuf) -> PathBuf {
@@ -1647,6 +2220,13 @@ impl StacksChainState {
             root_path: path_str.to_string(),
             unconfirmed_state: None,
             marf_opts: marf_opts,
+            deposit_batch_size: DEFAULT_DEPOSIT_BATCH_SIZE,
+            record_block_results: false,
+            record_state_diffs: false,
+            system_priority: None,
+            prune_depth: None,
+            next_prune_height: 0,
+            read_only_clarity_pool: Arc::new(Mutex::new(vec![])),
         };
 
         let mut receipts = vec![];
@@ -1661,7 +2241,10 @@ impl StacksChainState {
                     "StacksChainState initialization is required, but boot_data was not passed."
                 );
             }
-            (false, _) => {}
+            (false, Some(boot_data)) => {
+                chainstate.check_committed_settings(boot_data)?;
+            }
+            (false, None) => {}
         }
 
         Ok((chainstate, receipts))
@@ -1701,8 +2284,15 @@ impl StacksChainState {
         let clarity_instance = &mut self.clarity_state;
         let inner_tx = StacksDBTx::new(&mut self.state_index, ());
 
-        let chainstate_tx =
-            ChainstateTx::new(inner_tx, blocks_path, self.root_path.clone(), config);
+        let chainstate_tx = ChainstateTx::new(
+            inner_tx,
+            blocks_path,
+            self.root_path.clone(),
+            config,
+            self.deposit_batch_size,
+            self.system_priority.clone(),
+            self.record_state_diffs,
+        );
 
         Ok((chainstate_tx, clarity_instance))
     }
@@ -1892,6 +2482,68 @@ impl StacksChainState {
         Some(result)
     }
 
+    /// Run to_do on the state of the Clarity VM at the given chain tip, through a handle from the
+    /// read-only pool rather than through `clarity_state`, so that the query does not contend
+    /// with block processing.  A new handle is opened if the pool has none idle.
+    /// Returns Ok(Some(x: R)) if the given parent_tip exists.
+    /// Returns Ok(None) if not
+    pub fn with_pooled_read_only_clarity_tx<F, R>(
+        &self,
+        burn_dbconn: &dyn BurnStateDB,
+        parent_tip: &StacksBlockId,
+        to_do: F,
+    ) -> Result<Option<R>, Error>
+    where
+        F: FnOnce(&mut ClarityReadOnlyConnection) -> R,
+    {
+        match StacksChainState::has_stacks_block(self.db(), parent_tip) {
+            Ok(true) => {}
+            Ok(false) => {
+                return Ok(None);
+            }
+            Err(e) => {
+                warn!("Failed to query for {}: {:?}", parent_tip, &e);
+                return Ok(None);
+            }
+        }
+
+        let idle_handle = self
+            .read_only_clarity_pool
+            .lock()
+            .expect("FATAL: read-only Clarity pool lock is poisoned")
+            .pop();
+        let mut clarity_instance = match idle_handle {
+            Some(clarity_instance) => clarity_instance,
+            None => self.clarity_state.reopen_readonly()?,
+        };
+
+        let result = {
+            let mut conn = clarity_instance.read_only_connection_checked(
+                parent_tip,
+                &self.state_index,
+                burn_dbconn,
+            )?;
+            to_do(&mut conn)
+        };
+
+        let mut pool = self
+            .read_only_clarity_pool
+            .lock()
+            .expect("FATAL: read-only Clarity pool lock is poisoned");
+        if pool.len() < MAX_IDLE_READ_ONLY_CLARITY_HANDLES {
+            pool.push(clarity_instance);
+        }
+        Ok(Some(result))
+    }
+
+    /// How many read-only Clarity MARF handles are idle in this chainstate's pool
+    pub fn idle_read_only_clarity_handles(&self) -> usize {
+        self.read_only_clarity_pool
+            .lock()
+            .expect("FATAL: read-only Clarity pool lock is poisoned")
+            .len()
+    }
+
     /// Run to_do on the unconfirmed Clarity VM state
     pub fn with_read_only_unconfirmed_clarity_tx<F, R>(
         &mut self,
@@ -1926,8 +2578,8 @@ impl StacksChainState {
     }
 
     /// Run to_do on the unconfirmed Clarity VM state if the tip refers to the unconfirmed state;
-    /// otherwise run to_do on the confirmed state of the Clarity VM. If the tip doesn't exist,
-    /// then return None.
+    /// otherwise run to_do on the confirmed state of the Clarity VM, through the read-only pool.
+    /// If the tip doesn't exist, then return None.
     pub fn maybe_read_only_clarity_tx<F, R>(
         &mut self,
         burn_dbconn: &dyn BurnStateDB,
@@ -1947,7 +2599,7 @@ impl StacksChainState {
         if unconfirmed {
             self.with_read_only_unconfirmed_clarity_tx(burn_dbconn, to_do)
         } else {
-            Ok(self.with_read_only_clarity_tx(burn_dbconn, parent_tip, to_do))
+            self.with_pooled_read_only_clarity_tx(burn_dbconn, parent_tip, to_do)
         }
     }
 
@@ -2211,9 +2863,11 @@ pub mod test {
 
     use crate::chainstate::stacks::db::*;
     use crate::chainstate::stacks::*;
+    use clarity::vm::database::StoreType;
     use clarity::vm::test_util::TEST_BURN_STATE_DB;
 
     use crate::util_lib::boot::boot_code_test_addr;
+    use crate::util_lib::db::table_exists;
 
     use super::*;
 
@@ -2255,6 +2909,13 @@ pub mod test {
             get_bulk_initial_balances: None,
             get_bulk_initial_names: None,
             get_bulk_initial_namespaces: None,
+            genesis_spec: GenesisSpec::default(),
+            allow_unchecked_intermediary_responses: false,
+            withdrawal_batch_blocks: 0,
+            epochs: None,
+            system_priority: None,
+            max_tx_event_bytes: None,
+            use_subnet_costs: false,
         };
 
         StacksChainState::open_and_exec(mainnet, chain_id, &path, Some(&mut boot_data), None)
@@ -2296,4 +2957,227 @@ pub mod test {
             assert!(contract_res.is_some());
         }
     }
+
+    #[test]
+    fn test_reopen_rejects_different_consensus_settings() {
+        let path = chainstate_path("reopen-rejects-different-consensus-settings");
+        let _ = fs::remove_dir_all(&path);
+
+        let make_boot_data = |system_priority: Option<SystemPriorityPolicy>,
+                              max_tx_event_bytes: Option<u64>,
+                              use_subnet_costs: bool| {
+            ChainStateBootData {
+                initial_balances: vec![],
+                post_flight_callback: None,
+                first_burnchain_block_hash: BurnchainHeaderHash::zero(),
+                first_burnchain_block_height: 0,
+                first_burnchain_block_timestamp: 0,
+                pox_constants: PoxConstants::testnet_default(),
+                get_bulk_initial_lockups: None,
+                get_bulk_initial_balances: None,
+                get_bulk_initial_names: None,
+                get_bulk_initial_namespaces: None,
+                genesis_spec: GenesisSpec::default(),
+                allow_unchecked_intermediary_responses: false,
+                withdrawal_batch_blocks: 0,
+                epochs: None,
+                system_priority,
+                max_tx_event_bytes,
+                use_subnet_costs,
+            }
+        };
+        let policy = SystemPriorityPolicy {
+            contracts: vec![QualifiedContractIdentifier::local("governance").unwrap()],
+            authorized_principals: vec![StacksAddress::burn_address(false)],
+            reserved_budget_percent: 10,
+        };
+
+        let mut boot_data = make_boot_data(Some(policy.clone()), Some(4096), true);
+        let (mut chainstate, _) =
+            StacksChainState::open_and_exec(false, 0x80000000, &path, Some(&mut boot_data), None)
+                .unwrap();
+        assert_eq!(
+            chainstate.get_committed_system_priority_policy(),
+            Some(policy.clone())
+        );
+        assert_eq!(chainstate.get_committed_max_tx_event_bytes(), Some(4096));
+        assert!(chainstate.get_committed_use_subnet_costs());
+        assert!(!chainstate.get_committed_allow_unchecked_intermediary_responses());
+        assert_eq!(chainstate.get_committed_withdrawal_batch_blocks(), 0);
+        drop(chainstate);
+
+        // the same settings can be reopened
+        StacksChainState::open_and_exec(false, 0x80000000, &path, Some(&mut boot_data), None)
+            .unwrap();
+
+        let mut other_policy = policy.clone();
+        other_policy.reserved_budget_percent = 20;
+        let mismatches = [
+            (None, Some(4096), true),
+            (Some(other_policy), Some(4096), true),
+            (Some(policy.clone()), None, true),
+            (Some(policy.clone()), Some(8192), true),
+            (Some(policy.clone()), Some(4096), false),
+        ];
+        let mut mismatched_boot_data: Vec<_> = mismatches
+            .into_iter()
+            .map(|(system_priority, max_tx_event_bytes, use_subnet_costs)| {
+                make_boot_data(system_priority, max_tx_event_bytes, use_subnet_costs)
+            })
+            .collect();
+        let mut boot_data = make_boot_data(Some(policy.clone()), Some(4096), true);
+        boot_data.allow_unchecked_intermediary_responses = true;
+        mismatched_boot_data.push(boot_data);
+        let mut boot_data = make_boot_data(Some(policy.clone()), Some(4096), true);
+        boot_data.withdrawal_batch_blocks = 5;
+        mismatched_boot_data.push(boot_data);
+
+        for mut boot_data in mismatched_boot_data {
+            match StacksChainState::open_and_exec(
+                false,
+                0x80000000,
+                &path,
+                Some(&mut boot_data),
+                None,
+            ) {
+                Err(Error::DBError(db_error::GenesisSettingMismatch(_))) => {}
+                Err(e) => panic!("unexpected error: {:?}", e),
+                Ok(_) => panic!("reopened a chainstate with different consensus settings"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_pooled_read_only_clarity_tx() {
+        let mut chainstate =
+            instantiate_chainstate(false, 0x80000000, "pooled-read-only-clarity-tx");
+        let genesis_tip = StacksBlockHeader::make_index_block_hash(
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+        );
+        let (boot_contract_name, _) = STACKS_BOOT_CODE_TESTNET[0];
+        let boot_contract_id = QualifiedContractIdentifier::new(
+            boot_code_test_addr().into(),
+            ContractName::try_from(boot_contract_name.to_string()).unwrap(),
+        );
+        assert_eq!(chainstate.idle_read_only_clarity_handles(), 0);
+
+        // reads through the pool see the same state as reads through the chainstate's own handle
+        let expected = chainstate
+            .with_read_only_clarity_tx(&TEST_BURN_STATE_DB, &genesis_tip, |conn| {
+                conn.with_clarity_db_readonly(|db| db.has_contract(&boot_contract_id))
+            })
+            .unwrap();
+        assert!(expected);
+        for _ in 0..3 {
+            let pooled = chainstate
+                .maybe_read_only_clarity_tx(&TEST_BURN_STATE_DB, &genesis_tip, |conn| {
+                    conn.with_clarity_db_readonly(|db| db.has_contract(&boot_contract_id))
+                })
+                .unwrap()
+                .unwrap();
+            assert_eq!(pooled, expected);
+
+            // the handle is returned to the pool, and reused by the next query
+            assert_eq!(chainstate.idle_read_only_clarity_handles(), 1);
+        }
+
+        // pooled handles can also produce MARF proofs
+        let configured_key = ClarityDatabase::make_key_for_trip(
+            &boot_contract_id,
+            StoreType::Variable,
+            "configured",
+        );
+        let (value, proof) = chainstate
+            .maybe_read_only_clarity_tx(&TEST_BURN_STATE_DB, &genesis_tip, |conn| {
+                conn.with_clarity_db_readonly(|db| db.get_with_proof::<Value>(&configured_key))
+            })
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(value, Value::Bool(true));
+        assert!(!proof.is_empty());
+
+        // unknown tips are reported as such
+        let pooled = chainstate
+            .with_pooled_read_only_clarity_tx(
+                &TEST_BURN_STATE_DB,
+                &StacksBlockId([0x11; 32]),
+                |_| (),
+            )
+            .unwrap();
+        assert!(pooled.is_none());
+
+        // reopened chainstates share the pool
+        let (reopened, _) = chainstate.reopen().unwrap();
+        assert_eq!(reopened.idle_read_only_clarity_handles(), 1);
+    }
+
+    #[test]
+    fn test_chainstate_schema_migrations() {
+        assert_eq!(
+            CHAINSTATE_MIGRATIONS.latest_version().to_string(),
+            CHAINSTATE_VERSION
+        );
+
+        let path = chainstate_path("chainstate-schema-migrations");
+        match fs::metadata(&path) {
+            Ok(_) => {
+                fs::remove_dir_all(&path).unwrap();
+            }
+            Err(_) => {}
+        };
+        assert!(StacksChainState::check_schema_migrations(&path).is_err());
+
+        StacksChainState::make_chainstate_dirs(&path).unwrap();
+        let index_path = StacksChainState::header_index_root_path(PathBuf::from(&path));
+        let index_path = index_path.to_str().unwrap();
+        StacksChainState::open_db_without_migrations(false, 0x80000000, index_path).unwrap();
+
+        let status = StacksChainState::check_schema_migrations(&path).unwrap();
+        assert_eq!(status.current_version, 1);
+        assert_eq!(status.latest_version, 8);
+        assert_eq!(status.pending.len(), 7);
+
+        // checking doesn't migrate
+        let status = StacksChainState::check_schema_migrations(&path).unwrap();
+        assert_eq!(status.current_version, 1);
+
+        let marf = StacksChainState::open_db(false, 0x80000000, index_path).unwrap();
+        assert!(table_exists(marf.sqlite_conn(), "epoch_transitions").unwrap());
+        assert!(table_exists(marf.sqlite_conn(), "block_results").unwrap());
+        assert!(table_exists(marf.sqlite_conn(), "materialized_deposits").unwrap());
+        assert!(table_exists(marf.sqlite_conn(), "block_costs").unwrap());
+        assert!(table_exists(marf.sqlite_conn(), "transaction_receipts").unwrap());
+        assert!(table_exists(marf.sqlite_conn(), "pruned_blocks").unwrap());
+        assert!(table_exists(marf.sqlite_conn(), "block_state_diffs").unwrap());
+        assert_eq!(
+            StacksChainState::load_db_config(marf.sqlite_conn())
+                .unwrap()
+                .version,
+            CHAINSTATE_VERSION
+        );
+
+        let status = StacksChainState::check_schema_migrations(&path).unwrap();
+        assert_eq!(status.current_version, 8);
+        assert!(status.is_up_to_date());
+
+        // a database from a newer node is refused
+        let mut marf = marf;
+        let tx = marf.storage_tx().unwrap();
+        tx.execute("UPDATE db_config SET version = ?1", &[&"9".to_string()])
+            .unwrap();
+        tx.commit().unwrap();
+        drop(marf);
+
+        match StacksChainState::check_schema_migrations(&path) {
+            Err(db_error::UnsupportedSchema(_)) => {}
+            res => panic!("Expected UnsupportedSchema, got {:?}", &res),
+        }
+        match StacksChainState::open_db(false, 0x80000000, index_path) {
+            Err(Error::InvalidChainstateDB) => {}
+            Err(e) => panic!("Expected InvalidChainstateDB, got {:?}", &e),
+            Ok(_) => panic!("Opened a chainstate DB from the future"),
+        }
+    }
 }
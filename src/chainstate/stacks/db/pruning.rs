@@ -0,0 +1,397 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Chainstate pruning.  A node with a prune depth periodically deletes the bodies of anchored
+//! blocks and microblocks that are buried more than that many blocks below its canonical tip,
+//! and drops the MARF tries of blocks that are that deep and not ancestors of any recent block
+//! (i.e. stale forks).  Block headers, and with them withdrawal roots, are always kept, as are
+//! the tries of the canonical fork, since later tries refer back to them.
+//!
+//! Pruned blocks are no longer advertised in block inventories and are never re-downloaded.
+//! A pruning node cannot follow a reorg deeper than its prune depth.
+//!
+//! Dropping a trie removes its row from `marf_data`; tries stored in an external `.blobs` file
+//! still occupy space in that file.
+
+use rusqlite::types::ToSql;
+use rusqlite::OptionalExtension;
+
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::index::trie_sql;
+use crate::chainstate::stacks::Error;
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_row_columns, u64_to_sql, DBConn};
+use stacks_common::types::chainstate::StacksBlockId;
+
+/// How many blocks the canonical tip must advance between pruning passes
+pub const PRUNE_INTERVAL_BLOCKS: u64 = 32;
+
+/// What a pruning pass deleted
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PruneStats {
+    /// anchored blocks whose bodies were deleted
+    pub blocks_pruned: u64,
+    /// microblocks whose bodies were deleted
+    pub microblocks_pruned: u64,
+    /// stale-fork tries dropped from the headers and Clarity MARFs
+    pub tries_pruned: u64,
+}
+
+impl StacksChainState {
+    /// Set the number of confirmed blocks below the canonical tip to retain, or None to keep
+    /// everything.
+    pub fn set_prune_depth(&mut self, prune_depth: Option<u64>) {
+        self.prune_depth = prune_depth;
+        self.next_prune_height = 0;
+    }
+
+    pub fn get_prune_depth(&self) -> Option<u64> {
+        self.prune_depth
+    }
+
+    /// Has this anchored block's body been pruned?
+    pub fn is_block_pruned(conn: &DBConn, index_block_hash: &StacksBlockId) -> Result<bool, Error> {
+        let pruned = conn
+            .query_row(
+                "SELECT 1 FROM pruned_blocks WHERE index_block_hash = ?1",
+                &[index_block_hash],
+                |_row| Ok(()),
+            )
+            .optional()
+            .map_err(|e| Error::DBError(db_error::SqliteError(e)))?;
+        Ok(pruned.is_some())
+    }
+
+    /// Run a pruning pass if this chainstate has a prune depth and the canonical tip has
+    /// advanced far enough since the last pass.
+    pub fn prune_if_due(
+        &mut self,
+        canonical_tip: &StacksBlockId,
+    ) -> Result<Option<PruneStats>, Error> {
+        let keep_blocks = match self.prune_depth {
+            Some(keep_blocks) => keep_blocks,
+            None => return Ok(None),
+        };
+        let tip_height = self.get_block_height(canonical_tip)?;
+        if tip_height < self.next_prune_height {
+            return Ok(None);
+        }
+
+        let stats = self.prune_chainstate(canonical_tip, keep_blocks)?;
+        self.next_prune_height = tip_height.saturating_add(PRUNE_INTERVAL_BLOCKS);
+        Ok(Some(stats))
+    }
+
+    /// Delete the bodies of processed or orphaned blocks more than `keep_blocks` below
+    /// `canonical_tip`, along with the microblock streams they produced, and drop the tries of
+    /// blocks that deep which are not ancestors of any block above it.
+    pub fn prune_chainstate(
+        &mut self,
+        canonical_tip: &StacksBlockId,
+        keep_blocks: u64,
+    ) -> Result<PruneStats, Error> {
+        let tip_height = self.get_block_height(canonical_tip)?;
+        let prune_height = tip_height.saturating_sub(keep_blocks);
+        let mut stats = PruneStats::default();
+
+        let blocks_path = self.blocks_path.clone();
+        let tx = self.db_tx_begin()?;
+
+        // block bodies
+        let args: &[&dyn ToSql] = &[&u64_to_sql(prune_height)?];
+        let pruned_blocks: Vec<(StacksBlockId, i64)> = {
+            let mut stmt = tx.prepare(
+                "SELECT index_block_hash, height FROM staging_blocks \
+                 WHERE height < ?1 AND (processed = 1 OR orphaned = 1) \
+                 AND index_block_hash NOT IN (SELECT index_block_hash FROM pruned_blocks)",
+            )?;
+            let rows = stmt.query_map(args, |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_, _>>()?
+        };
+
+        for (index_block_hash, height) in pruned_blocks.iter() {
+            stats.microblocks_pruned += tx.execute(
+                "DELETE FROM staging_microblocks_data WHERE block_hash IN \
+                 (SELECT microblock_hash FROM staging_microblocks WHERE index_block_hash = ?1)",
+                &[index_block_hash],
+            )? as u64;
+
+            let args: &[&dyn ToSql] = &[index_block_hash, height];
+            tx.execute(
+                "INSERT OR REPLACE INTO pruned_blocks (index_block_hash, block_height) VALUES (?1, ?2)",
+                args,
+            )?;
+        }
+        stats.blocks_pruned = pruned_blocks.len() as u64;
+
+        // stale forks: headers that deep which no header above the prune height descends from
+        let args: &[&dyn ToSql] = &[&u64_to_sql(prune_height)?];
+        let stale_blocks = query_row_columns::<StacksBlockId, _>(
+            &tx,
+            "WITH RECURSIVE live(index_block_hash) AS ( \
+                 SELECT index_block_hash FROM block_headers WHERE block_height >= ?1 \
+                 UNION \
+                 SELECT block_headers.parent_block_id FROM block_headers \
+                 JOIN live ON block_headers.index_block_hash = live.index_block_hash \
+             ) \
+             SELECT index_block_hash FROM block_headers WHERE block_height < ?1 \
+             AND index_block_hash NOT IN (SELECT index_block_hash FROM live)",
+            args,
+            "index_block_hash",
+        )?;
+
+        for stale_block in stale_blocks.iter() {
+            if trie_sql::drop_trie(&tx, stale_block)? {
+                stats.tries_pruned += 1;
+            }
+        }
+        tx.commit()?;
+
+        let clarity_tries_pruned = self.with_clarity_marf(|marf| -> Result<u64, Error> {
+            let tx = marf.storage_tx()?;
+            let mut tries_pruned = 0;
+            for stale_block in stale_blocks.iter() {
+                if trie_sql::drop_trie(&tx, stale_block)? {
+                    tries_pruned += 1;
+                }
+                tx.execute(
+                    "DELETE FROM metadata_table WHERE blockhash = ?1",
+                    &[stale_block],
+                )?;
+            }
+            tx.commit()?;
+            Ok(tries_pruned)
+        })?;
+        stats.tries_pruned += clarity_tries_pruned;
+
+        // only empty the block files once the pruning is durable, so that a crash can't leave
+        // an unpruned block without its body.  Empty block files are treated as absent blocks,
+        // and keeping the file stops the downloader from fetching the block again.
+        for (index_block_hash, _) in pruned_blocks.iter() {
+            if StacksChainState::has_block_indexed(&blocks_path, index_block_hash)? {
+                let block_path =
+                    StacksChainState::get_index_block_path(&blocks_path, index_block_hash)?;
+                StacksChainState::atomic_file_write(&block_path, &vec![])?;
+            }
+        }
+
+        debug!("Pruned chainstate";
+               "prune_height" => prune_height,
+               "canonical_tip" => %canonical_tip,
+               "stats" => ?stats);
+        Ok(stats)
+    }
+
+    fn get_block_height(&self, index_block_hash: &StacksBlockId) -> Result<u64, Error> {
+        StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            self.db(),
+            index_block_hash,
+        )?
+        .map(|header_info| header_info.stacks_block_height)
+        .ok_or(Error::NoSuchBlockError)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::burn::ConsensusHash;
+    use crate::chainstate::stacks::db::blocks::test::*;
+    use crate::chainstate::stacks::db::test::*;
+    use crate::chainstate::stacks::db::StacksHeaderInfo;
+    use crate::chainstate::stacks::{StacksBlock, StacksBlockHeader, StacksPrivateKey};
+    use crate::core::{FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH};
+    use clarity::vm::costs::ExecutionCost;
+    use stacks_common::types::chainstate::BlockHeaderHash;
+
+    /// Store, process and index an empty block at `height` on top of `parent`
+    fn append_block(
+        chainstate: &mut StacksChainState,
+        mblock_key: &StacksPrivateKey,
+        parent: &(ConsensusHash, BlockHeaderHash),
+        consensus_hash: ConsensusHash,
+        height: u64,
+    ) -> (ConsensusHash, BlockHeaderHash) {
+        let mut block: StacksBlock = make_empty_coinbase_block(mblock_key);
+        block.header.parent_block = parent.1.clone();
+        block.header.total_work.work = height;
+
+        store_staging_block(chainstate, &consensus_hash, &block, &parent.0, 1, 2);
+        set_block_processed(chainstate, &consensus_hash, &block.block_hash(), true);
+
+        let mut header_info = StacksHeaderInfo::regtest_genesis();
+        header_info.anchored_header = block.header.clone();
+        header_info.stacks_block_height = height;
+        header_info.consensus_hash = consensus_hash.clone();
+
+        let parent_id = StacksBlockHeader::make_index_block_hash(&parent.0, &parent.1);
+        let mut tx = chainstate.db_tx_begin().unwrap();
+        StacksChainState::insert_stacks_block_header(
+            &mut tx,
+            &parent_id,
+            &header_info,
+            &ExecutionCost::zero(),
+        )
+        .unwrap();
+        tx.commit().unwrap();
+
+        (consensus_hash, block.block_hash())
+    }
+
+    fn insert_trie(chainstate: &mut StacksChainState, block_id: &StacksBlockId) {
+        let tx = chainstate.db_tx_begin().unwrap();
+        tx.execute(
+            "INSERT INTO marf_data (block_hash, data, unconfirmed, external_offset, external_length) \
+             VALUES (?1, x'', 0, 0, 0)",
+            &[block_id],
+        )
+        .unwrap();
+        tx.commit().unwrap();
+    }
+
+    fn has_trie(chainstate: &StacksChainState, block_id: &StacksBlockId) -> bool {
+        trie_sql::get_confirmed_block_identifier(chainstate.db(), block_id)
+            .unwrap()
+            .is_some()
+    }
+
+    #[test]
+    fn test_prune_chainstate() {
+        let mut chainstate = instantiate_chainstate(false, 0x80000000, "prune-chainstate");
+        let mblock_key = StacksPrivateKey::new();
+
+        let mut chain = vec![(FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH)];
+        for height in 1..=10 {
+            let parent = chain.last().cloned().unwrap();
+            let block = append_block(
+                &mut chainstate,
+                &mblock_key,
+                &parent,
+                ConsensusHash([height as u8; 20]),
+                height,
+            );
+            chain.push(block);
+        }
+        let block_id = |block: &(ConsensusHash, BlockHeaderHash)| {
+            StacksBlockHeader::make_index_block_hash(&block.0, &block.1)
+        };
+
+        // a microblock stream produced by block 1
+        let microblocks = make_sample_microblock_stream(&mblock_key, &chain[1].1);
+        for microblock in microblocks.iter() {
+            store_staging_microblock(&mut chainstate, &chain[1].0, &chain[1].1, microblock);
+        }
+
+        // a stale fork off of block 1, and tries for it and for block 1
+        let fork = append_block(
+            &mut chainstate,
+            &mblock_key,
+            &chain[1],
+            ConsensusHash([0xf2; 20]),
+            2,
+        );
+        insert_trie(&mut chainstate, &block_id(&fork));
+        insert_trie(&mut chainstate, &block_id(&chain[1]));
+
+        let tip = block_id(&chain[10]);
+        let stats = chainstate.prune_chainstate(&tip, 5).unwrap();
+        assert_eq!(
+            stats,
+            PruneStats {
+                blocks_pruned: 5,
+                microblocks_pruned: microblocks.len() as u64,
+                tries_pruned: 1,
+            }
+        );
+
+        for height in 1..=10 {
+            let block = &chain[height];
+            let pruned = height < 5;
+            assert_eq!(
+                StacksChainState::is_block_pruned(chainstate.db(), &block_id(block)).unwrap(),
+                pruned
+            );
+            assert_eq!(
+                StacksChainState::load_block(&chainstate.blocks_path, &block.0, &block.1)
+                    .unwrap()
+                    .is_some(),
+                !pruned
+            );
+
+            // headers are kept
+            assert!(
+                StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                    chainstate.db(),
+                    &block_id(block)
+                )
+                .unwrap()
+                .is_some()
+            );
+        }
+        assert!(StacksChainState::is_block_pruned(chainstate.db(), &block_id(&fork)).unwrap());
+        assert!(StacksChainState::load_microblock_stream_fork(
+            chainstate.db(),
+            &chain[1].0,
+            &chain[1].1,
+            &microblocks.last().unwrap().block_hash()
+        )
+        .unwrap()
+        .is_none());
+
+        // only the stale fork's trie is dropped
+        assert!(!has_trie(&chainstate, &block_id(&fork)));
+        assert!(has_trie(&chainstate, &block_id(&chain[1])));
+
+        // pruned blocks are not downloaded again
+        assert!(
+            StacksChainState::has_block_indexed(&chainstate.blocks_path, &block_id(&chain[1]))
+                .unwrap()
+        );
+
+        // pruning is idempotent
+        let stats = chainstate.prune_chainstate(&tip, 5).unwrap();
+        assert_eq!(stats, PruneStats::default());
+    }
+
+    #[test]
+    fn test_prune_if_due() {
+        let mut chainstate = instantiate_chainstate(false, 0x80000000, "prune-if-due");
+        let mblock_key = StacksPrivateKey::new();
+
+        let genesis = (FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH);
+        let block = append_block(
+            &mut chainstate,
+            &mblock_key,
+            &genesis,
+            ConsensusHash([1; 20]),
+            1,
+        );
+        let tip = StacksBlockHeader::make_index_block_hash(&block.0, &block.1);
+
+        // pruning is off by default
+        assert_eq!(chainstate.prune_if_due(&tip).unwrap(), None);
+
+        chainstate.set_prune_depth(Some(0));
+        assert!(chainstate.prune_if_due(&tip).unwrap().is_some());
+        assert_eq!(chainstate.prune_if_due(&tip).unwrap(), None);
+
+        // an unknown tip is an error
+        match chainstate.prune_chainstate(&StacksBlockId([0x11; 32]), 0) {
+            Err(Error::NoSuchBlockError) => {}
+            res => panic!("Expected NoSuchBlockError, got {:?}", &res),
+        }
+    }
+}
@@ -0,0 +1,404 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Receipts of the transactions that each block processed: each transaction's status, result
+//! and cost, its contract events decoded, and the withdrawals it made.  A transaction has one
+//! receipt per fork that processed it.  These back the transaction receipt RPC endpoint, so that
+//! wallets can confirm their transactions without an external API server.
+
+use rusqlite::types::ToSql;
+use rusqlite::Row;
+
+use crate::burnchains::Txid;
+use crate::chainstate::stacks::db::{StacksChainState, StacksDBConn};
+use crate::chainstate::stacks::events::{StacksTransactionEvent, StacksTransactionReceipt};
+use crate::chainstate::stacks::Error;
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_rows, u64_to_sql, DBConn, DBTx, FromRow};
+use clarity::vm::costs::ExecutionCost;
+use clarity::vm::database::ClaritySerializable;
+use clarity::vm::events::{FTEventType, NFTEventType, STXEventType};
+use clarity::vm::types::Value;
+use stacks_common::types::chainstate::StacksBlockId;
+
+/// Whether a processed transaction's changes were committed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    Success,
+    /// the transaction returned an `err` response, so its changes were rolled back
+    AbortByResponse,
+    /// the transaction violated a post-condition, so its changes were rolled back
+    AbortByPostCondition,
+}
+
+/// A contract event emitted by `print` in a processed transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrintEventReceipt {
+    pub contract_identifier: String,
+    /// the topic given to `print`, or "print" if none was given
+    #[serde(default = "default_print_topic")]
+    pub topic: String,
+    /// the printed value, in Clarity syntax
+    pub value: String,
+    /// hex-encoded consensus serialization of the printed value
+    pub raw_value: String,
+}
+
+fn default_print_topic() -> String {
+    "print".into()
+}
+
+/// A withdrawal made by a processed transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WithdrawalReceipt {
+    /// "stx", "ft", "nft", or "stx-tip" for a miner tip paid alongside a withdrawal
+    pub withdrawal_type: String,
+    /// the withdrawal's index in its block's withdrawal Merkle tree
+    pub withdrawal_id: Option<u32>,
+    pub sender: String,
+    /// the withdrawn asset, for FT and NFT withdrawals
+    pub asset_identifier: Option<String>,
+    /// the withdrawn amount, for STX and FT withdrawals
+    pub amount: Option<String>,
+    /// the withdrawn token's id, for NFT withdrawals
+    pub id: Option<String>,
+}
+
+/// What processing one transaction produced, in the block `index_block_hash`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionReceiptRecord {
+    /// hex-encoded txid
+    pub txid: String,
+    pub index_block_hash: StacksBlockId,
+    pub block_height: u64,
+    pub tx_index: u32,
+    /// the microblock that included the transaction, if it was mined in one
+    pub microblock_hash: Option<String>,
+    pub status: TransactionStatus,
+    /// hex-encoded consensus serialization of the transaction's result
+    pub result: String,
+    pub execution_cost: ExecutionCost,
+    pub print_events: Vec<PrintEventReceipt>,
+    pub withdrawals: Vec<WithdrawalReceipt>,
+}
+
+impl TransactionReceiptRecord {
+    pub fn from_receipt(
+        index_block_hash: &StacksBlockId,
+        block_height: u64,
+        receipt: &StacksTransactionReceipt,
+    ) -> TransactionReceiptRecord {
+        let status = if receipt.post_condition_aborted {
+            TransactionStatus::AbortByPostCondition
+        } else {
+            match receipt.result {
+                Value::Response(ref response) if !response.committed => {
+                    TransactionStatus::AbortByResponse
+                }
+                _ => TransactionStatus::Success,
+            }
+        };
+
+        let mut print_events = vec![];
+        let mut withdrawals = vec![];
+        for event in receipt.events.iter() {
+            match event {
+                StacksTransactionEvent::SmartContractEvent(data) => {
+                    print_events.push(PrintEventReceipt {
+                        contract_identifier: data.key.0.to_string(),
+                        topic: data.key.1.clone(),
+                        value: data.value.to_string(),
+                        raw_value: format!("0x{}", data.value.serialize()),
+                    });
+                }
+                StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawEvent(data)) => {
+                    withdrawals.push(WithdrawalReceipt {
+                        withdrawal_type: "stx".into(),
+                        withdrawal_id: data.withdrawal_id,
+                        sender: data.sender.to_string(),
+                        asset_identifier: None,
+                        amount: Some(data.amount.to_string()),
+                        id: None,
+                    });
+                }
+                StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawTipEvent(data)) => {
+                    withdrawals.push(WithdrawalReceipt {
+                        withdrawal_type: "stx-tip".into(),
+                        withdrawal_id: data.withdrawal_id,
+                        sender: data.sender.to_string(),
+                        asset_identifier: None,
+                        amount: Some(data.amount.to_string()),
+                        id: None,
+                    });
+                }
+                StacksTransactionEvent::FTEvent(FTEventType::FTWithdrawEvent(data)) => {
+                    withdrawals.push(WithdrawalReceipt {
+                        withdrawal_type: "ft".into(),
+                        withdrawal_id: data.withdrawal_id,
+                        sender: data.sender.to_string(),
+                        asset_identifier: Some(data.asset_identifier.to_string()),
+                        amount: Some(data.amount.to_string()),
+                        id: None,
+                    });
+                }
+                StacksTransactionEvent::NFTEvent(NFTEventType::NFTWithdrawEvent(data)) => {
+                    withdrawals.push(WithdrawalReceipt {
+                        withdrawal_type: "nft".into(),
+                        withdrawal_id: data.withdrawal_id,
+                        sender: data.sender.to_string(),
+                        asset_identifier: Some(data.asset_identifier.to_string()),
+                        amount: None,
+                        id: Some(data.id.to_string()),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        TransactionReceiptRecord {
+            txid: receipt.transaction.txid().to_hex(),
+            index_block_hash: index_block_hash.clone(),
+            block_height,
+            tx_index: receipt.tx_index,
+            microblock_hash: receipt
+                .microblock_header
+                .as_ref()
+                .map(|header| header.block_hash().to_hex()),
+            status,
+            result: receipt.result.serialize(),
+            execution_cost: receipt.execution_cost.clone(),
+            print_events,
+            withdrawals,
+        }
+    }
+}
+
+impl FromRow<TransactionReceiptRecord> for TransactionReceiptRecord {
+    fn from_row<'a>(row: &'a Row) -> Result<TransactionReceiptRecord, db_error> {
+        let receipt_json: String = row.get_unwrap("receipt");
+        serde_json::from_str(&receipt_json).map_err(db_error::SerializationError)
+    }
+}
+
+impl StacksChainState {
+    /// Record the receipts of the transactions processed by the block `index_block_hash` at
+    /// `block_height`.
+    pub fn insert_transaction_receipts(
+        tx: &DBTx,
+        index_block_hash: &StacksBlockId,
+        block_height: u64,
+        receipts: &[StacksTransactionReceipt],
+    ) -> Result<(), Error> {
+        for receipt in receipts.iter() {
+            let record =
+                TransactionReceiptRecord::from_receipt(index_block_hash, block_height, receipt);
+            let record_json = serde_json::to_string(&record)
+                .expect("FATAL: failed to serialize transaction receipt");
+            let args: &[&dyn ToSql] = &[
+                &receipt.transaction.txid(),
+                index_block_hash,
+                &u64_to_sql(block_height)?,
+                &record_json,
+            ];
+            tx.execute(
+                "INSERT OR REPLACE INTO transaction_receipts \
+                 (txid, index_block_hash, block_height, receipt) VALUES (?1, ?2, ?3, ?4)",
+                args,
+            )
+            .map_err(|e| Error::DBError(db_error::SqliteError(e)))?;
+        }
+        Ok(())
+    }
+
+    /// Get every receipt of the transaction `txid`, in any fork.
+    pub fn get_transaction_receipt_records(
+        conn: &DBConn,
+        txid: &Txid,
+    ) -> Result<Vec<TransactionReceiptRecord>, Error> {
+        query_rows(
+            conn,
+            "SELECT * FROM transaction_receipts WHERE txid = ?1 ORDER BY block_height",
+            &[txid],
+        )
+        .map_err(Error::DBError)
+    }
+
+    /// Get the receipt of the transaction `txid` in the fork ending at `tip` (inclusive), if it
+    /// was processed in that fork.
+    pub fn get_transaction_receipt(
+        index_conn: &StacksDBConn,
+        tip: &StacksBlockId,
+        txid: &Txid,
+    ) -> Result<Option<TransactionReceiptRecord>, Error> {
+        for record in StacksChainState::get_transaction_receipt_records(index_conn, txid)? {
+            if index_conn
+                .get_ancestor_block_height(&record.index_block_hash, tip)?
+                .is_some()
+            {
+                return Ok(Some(record));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::events::TransactionOrigin;
+    use crate::chainstate::stacks::miner::test::{
+        make_user_contract_publish, make_user_stacks_transfer,
+    };
+    use crate::chainstate::stacks::*;
+    use crate::net::test::*;
+    use clarity::vm::events::{STXWithdrawEventData, SmartContractEventData};
+    use clarity::vm::types::StacksAddressExtensions;
+    use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
+    use stacks_common::types::chainstate::StacksAddress;
+    use stacks_common::types::Address;
+
+    fn make_receipt(result: Value, post_condition_aborted: bool) -> StacksTransactionReceipt {
+        let contract =
+            QualifiedContractIdentifier::parse("ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.hello")
+                .unwrap();
+        StacksTransactionReceipt {
+            transaction: TransactionOrigin::Burn(Txid([0x01; 32])),
+            events: vec![
+                StacksTransactionEvent::SmartContractEvent(SmartContractEventData {
+                    key: (contract.clone(), "print".into()),
+                    value: Value::UInt(7),
+                }),
+                StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawEvent(
+                    STXWithdrawEventData {
+                        sender: PrincipalData::Contract(contract),
+                        amount: 100,
+                        withdrawal_id: Some(0),
+                    },
+                )),
+            ],
+            post_condition_aborted,
+            result,
+            stx_burned: 0,
+            contract_analysis: None,
+            execution_cost: ExecutionCost::zero(),
+            microblock_header: None,
+            tx_index: 2,
+        }
+    }
+
+    #[test]
+    fn test_transaction_receipt_record() {
+        let index_block_hash = StacksBlockId([0x11; 32]);
+        let record = TransactionReceiptRecord::from_receipt(
+            &index_block_hash,
+            5,
+            &make_receipt(Value::okay_true(), false),
+        );
+        assert_eq!(record.status, TransactionStatus::Success);
+        assert_eq!(record.tx_index, 2);
+        assert_eq!(
+            record.print_events,
+            vec![PrintEventReceipt {
+                contract_identifier: "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.hello".into(),
+                topic: "print".into(),
+                value: "u7".into(),
+                raw_value: format!("0x{}", Value::UInt(7).serialize()),
+            }]
+        );
+        assert_eq!(record.withdrawals.len(), 1);
+        assert_eq!(record.withdrawals[0].withdrawal_type, "stx");
+        assert_eq!(record.withdrawals[0].withdrawal_id, Some(0));
+        assert_eq!(record.withdrawals[0].amount, Some("100".into()));
+
+        let aborted = TransactionReceiptRecord::from_receipt(
+            &index_block_hash,
+            5,
+            &make_receipt(Value::err_uint(1), false),
+        );
+        assert_eq!(aborted.status, TransactionStatus::AbortByResponse);
+        let aborted = TransactionReceiptRecord::from_receipt(
+            &index_block_hash,
+            5,
+            &make_receipt(Value::okay_true(), true),
+        );
+        assert_eq!(aborted.status, TransactionStatus::AbortByPostCondition);
+    }
+
+    #[test]
+    fn test_record_processed_transaction_receipts() {
+        let privk = StacksPrivateKey::from_hex(
+            "42faca653724860da7a41bfcef7e6ba78db55146f6900de8cb2a9f760ffac70c01",
+        )
+        .unwrap();
+        let addr = StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(&privk)],
+        )
+        .unwrap();
+        let recipient: PrincipalData =
+            StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV")
+                .unwrap()
+                .into();
+
+        let mut peer_config =
+            TestPeerConfig::new("test_record_processed_transaction_receipts", 21344, 21345);
+        peer_config.initial_balances = vec![(addr.to_account_principal(), 1000000000)];
+        let mut peer = TestPeer::new(peer_config);
+        let mut coinbase_nonce = 0;
+
+        let transfer = make_user_stacks_transfer(&privk, 0, 200, &recipient, 1000);
+        let publish = make_user_contract_publish(&privk, 1, 200, "hello", "(print u7)");
+        let tip = peer.tenure_with_txs(&[transfer.clone(), publish.clone()], &mut coinbase_nonce);
+
+        let index_conn = peer.chainstate().index_conn().unwrap();
+        let transfer_record =
+            StacksChainState::get_transaction_receipt(&index_conn, &tip, &transfer.txid())
+                .unwrap()
+                .unwrap();
+        assert_eq!(transfer_record.index_block_hash, tip);
+        assert_eq!(transfer_record.block_height, 1);
+        assert_eq!(transfer_record.tx_index, 1);
+        assert_eq!(transfer_record.status, TransactionStatus::Success);
+        assert_eq!(transfer_record.result, Value::okay_true().serialize());
+        assert_eq!(transfer_record.print_events, vec![]);
+
+        let publish_record =
+            StacksChainState::get_transaction_receipt(&index_conn, &tip, &publish.txid())
+                .unwrap()
+                .unwrap();
+        assert_eq!(publish_record.tx_index, 2);
+        assert_eq!(
+            publish_record.print_events,
+            vec![PrintEventReceipt {
+                contract_identifier: format!("{}.hello", &addr),
+                topic: "print".into(),
+                value: "u7".into(),
+                raw_value: format!("0x{}", Value::UInt(7).serialize()),
+            }]
+        );
+        assert!(publish_record.execution_cost.runtime > 0);
+
+        // transactions this fork never processed have no receipt
+        assert_eq!(
+            StacksChainState::get_transaction_receipt(&index_conn, &tip, &Txid([0x01; 32]))
+                .unwrap(),
+            None
+        );
+    }
+}
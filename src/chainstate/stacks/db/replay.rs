@@ -0,0 +1,385 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Block replay.  Processed blocks keep only their headers, so the receipts that an event
+//! observer was sent when a block was processed can only be recovered by executing the block
+//! again.  Replaying a block re-executes it (and the microblocks it confirmed) against its
+//! parent's state, exactly as `append_block` does, and then rolls everything back, so replay
+//! never changes the chainstate.  Indexers use this to rebuild their databases from a node's
+//! chainstate instead of re-syncing the node.
+//!
+//! Blocks whose bodies were pruned cannot be replayed.
+
+use crate::burnchains::Txid;
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::coordinator::BlockEventDispatcher;
+use crate::chainstate::stacks::db::blocks::SetupBlockResult;
+use crate::chainstate::stacks::db::{StacksChainState, StacksEpochReceipt};
+use crate::chainstate::stacks::Error;
+use crate::chainstate::stacks::{StacksBlock, StacksMicroblock};
+use crate::clarity_vm::withdrawal::get_withdrawal_leaf_hashes;
+use crate::core::{
+    EMPTY_MICROBLOCK_PARENT_HASH, FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH,
+};
+use stacks_common::types::chainstate::{BurnchainHeaderHash, StacksBlockId};
+
+/// A processed block, together with what processing it produced
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayedBlock {
+    pub block: StacksBlock,
+    pub parent_index_block_hash: StacksBlockId,
+    /// txid of the block-commit that elected the block
+    pub winner_txid: Txid,
+    pub epoch_receipt: StacksEpochReceipt,
+}
+
+impl ReplayedBlock {
+    /// Announce the block to `dispatcher`, just as it was announced when it was processed.
+    pub fn announce<T: BlockEventDispatcher>(&self, dispatcher: &T) {
+        dispatcher.announce_block(
+            &self.block,
+            &self.epoch_receipt.header,
+            &self.epoch_receipt.tx_receipts,
+            &self.parent_index_block_hash,
+            self.winner_txid.clone(),
+            &self.epoch_receipt.matured_rewards,
+            self.epoch_receipt.matured_rewards_info.as_ref(),
+            self.epoch_receipt.parent_burn_block_hash.clone(),
+            self.epoch_receipt.parent_burn_block_height,
+            self.epoch_receipt.parent_burn_block_timestamp,
+            &self.epoch_receipt.anchored_block_cost,
+            &self.epoch_receipt.parent_microblocks_cost,
+        );
+    }
+}
+
+impl StacksChainState {
+    /// Get the index block hash of the block at `height` in the canonical Stacks fork, if the
+    /// canonical tip is at least that high.
+    pub fn get_canonical_block_id_at_height(
+        &self,
+        sort_db: &SortitionDB,
+        height: u64,
+    ) -> Result<Option<StacksBlockId>, Error> {
+        let (tip_consensus_hash, tip_block_hash) =
+            SortitionDB::get_canonical_stacks_chain_tip_hash(sort_db.conn())?;
+        let tip = StacksBlockId::new(&tip_consensus_hash, &tip_block_hash);
+        self.index_conn()?
+            .get_ancestor_block_hash(height, &tip)
+            .map_err(Error::DBError)
+    }
+
+    /// Re-execute the processed block `index_block_hash` against its parent's state, and return
+    /// the receipts that processing it produced.  The chainstate is left unchanged.
+    pub fn replay_block(
+        &mut self,
+        sort_db: &SortitionDB,
+        index_block_hash: &StacksBlockId,
+    ) -> Result<ReplayedBlock, Error> {
+        let header_info = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            self.db(),
+            index_block_hash,
+        )?
+        .ok_or(Error::NoSuchBlockError)?;
+        let parent_index_block_hash =
+            StacksChainState::get_parent_block_id(self.db(), index_block_hash)?
+                .ok_or(Error::NoSuchBlockError)?;
+        let parent_header_info =
+            StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                self.db(),
+                &parent_index_block_hash,
+            )?
+            .ok_or(Error::NoSuchBlockError)?;
+
+        if StacksChainState::is_block_pruned(self.db(), index_block_hash)? {
+            return Err(Error::InvalidStacksBlock(format!(
+                "Block {} was pruned, and cannot be replayed",
+                index_block_hash
+            )));
+        }
+        let block = StacksChainState::load_block(
+            &self.blocks_path,
+            &header_info.consensus_hash,
+            &header_info.anchored_header.block_hash(),
+        )?
+        .ok_or(Error::NoSuchBlockError)?;
+
+        let (parent_consensus_hash, parent_block_hash) = if block.is_first_mined() {
+            (
+                FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+                FIRST_STACKS_BLOCK_HASH.clone(),
+            )
+        } else {
+            (
+                parent_header_info.consensus_hash.clone(),
+                parent_header_info.anchored_header.block_hash(),
+            )
+        };
+
+        let microblocks: Vec<StacksMicroblock> = if block.header.parent_microblock
+            == EMPTY_MICROBLOCK_PARENT_HASH
+            && block.header.parent_microblock_sequence == 0
+        {
+            vec![]
+        } else {
+            StacksChainState::load_processed_microblock_stream_fork(
+                self.db(),
+                &parent_consensus_hash,
+                &parent_block_hash,
+                &block.header.parent_microblock,
+            )?
+            .ok_or(Error::NoSuchBlockError)?
+        };
+
+        let snapshot =
+            SortitionDB::get_block_snapshot_consensus(sort_db.conn(), &header_info.consensus_hash)?
+                .ok_or(Error::NoSuchBlockError)?;
+        let (parent_burn_block_hash, parent_burn_block_height, parent_burn_block_timestamp) =
+            if block.is_first_mined() {
+                (BurnchainHeaderHash([0; 32]), 0, 0)
+            } else {
+                match SortitionDB::get_block_snapshot_consensus(
+                    sort_db.conn(),
+                    &parent_consensus_hash,
+                )? {
+                    Some(sn) => (
+                        sn.burn_header_hash,
+                        sn.block_height as u32,
+                        sn.burn_header_timestamp,
+                    ),
+                    None => (BurnchainHeaderHash([0; 32]), 0, 0),
+                }
+            };
+
+        let mainnet = self.mainnet;
+        let burn_dbconn = sort_db.index_conn();
+        let (mut chainstate_tx, clarity_instance) = self.chainstate_tx_begin()?;
+        let system_priority = chainstate_tx.system_priority.clone();
+
+        // this mirrors the block-execution steps of `append_block`; the chainstate transaction
+        // is dropped, and with it anything that setting up the block wrote.
+        let SetupBlockResult {
+            mut clarity_tx,
+            mut tx_receipts,
+            microblock_execution_cost,
+            microblock_txs_receipts,
+            matured_miner_rewards_opt,
+            evaluated_epoch,
+            ..
+        } = StacksChainState::setup_block(
+            &mut chainstate_tx,
+            clarity_instance,
+            &burn_dbconn,
+            sort_db.conn(),
+            &parent_header_info,
+            snapshot.parent_burn_header_hash.clone(),
+            snapshot.block_height as u32,
+            parent_consensus_hash,
+            parent_block_hash,
+            &microblocks,
+            mainnet,
+            None,
+        )?;
+
+        let block_txs_receipts = match StacksChainState::process_block_transactions(
+            &mut clarity_tx,
+            &block,
+            microblock_txs_receipts.len() as u32,
+            system_priority.as_ref(),
+        ) {
+            Ok((_block_fees, _block_burns, block_txs_receipts)) => block_txs_receipts,
+            Err(e) => {
+                clarity_tx.rollback_block();
+                return Err(Error::InvalidStacksBlock(format!(
+                    "Failed to replay block {}: {:?}",
+                    index_block_hash, &e
+                )));
+            }
+        };
+        tx_receipts.extend(block_txs_receipts.into_iter());
+
+        let anchored_block_cost = clarity_tx.cost_so_far();
+
+        let (matured_rewards, matured_rewards_info, miner_payouts_opt) =
+            if let Some((miner_reward, mut user_rewards, parent_reward, reward_ptr)) =
+                matured_miner_rewards_opt
+            {
+                let mut matured_rewards = vec![miner_reward.clone()];
+                matured_rewards.append(&mut user_rewards);
+                matured_rewards.push(parent_reward.clone());
+                (
+                    matured_rewards,
+                    Some(reward_ptr),
+                    Some((miner_reward, user_rewards, parent_reward)),
+                )
+            } else {
+                (vec![], None, None)
+            };
+
+        let mut lockup_events = match StacksChainState::finish_block(
+            &mut clarity_tx,
+            miner_payouts_opt,
+            block.header.total_work.work as u32,
+            block.header.microblock_pubkey_hash,
+        ) {
+            Ok(lockup_events) => lockup_events,
+            Err(e) => {
+                clarity_tx.rollback_block();
+                return Err(e);
+            }
+        };
+        if !lockup_events.is_empty() {
+            if let Some(receipt) = tx_receipts.get_mut(0) {
+                if receipt.is_coinbase_tx() {
+                    receipt.events.append(&mut lockup_events);
+                }
+            }
+        }
+
+        tx_receipts.extend(microblock_txs_receipts.into_iter());
+
+        let root_hash = clarity_tx.seal();
+        clarity_tx.rollback_block();
+        drop(chainstate_tx);
+
+        if root_hash != block.header.state_index_root {
+            return Err(Error::InvalidStacksBlock(format!(
+                "Replayed block {} state root mismatch: expected {}, got {}",
+                index_block_hash, &block.header.state_index_root, &root_hash
+            )));
+        }
+
+        // assigns each withdrawal event its id in the block's withdrawal tree
+        get_withdrawal_leaf_hashes(&mut tx_receipts, block.header.total_work.work);
+
+        Ok(ReplayedBlock {
+            block,
+            parent_index_block_hash,
+            winner_txid: snapshot.winning_block_txid,
+            epoch_receipt: StacksEpochReceipt {
+                header: header_info,
+                tx_receipts,
+                matured_rewards,
+                matured_rewards_info,
+                parent_microblocks_cost: microblock_execution_cost,
+                anchored_block_cost,
+                parent_burn_block_hash,
+                parent_burn_block_height,
+                parent_burn_block_timestamp,
+                evaluated_epoch,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::miner::test::make_user_stacks_transfer;
+    use crate::chainstate::stacks::*;
+    use crate::net::test::*;
+    use clarity::vm::types::{PrincipalData, StacksAddressExtensions};
+    use stacks_common::types::chainstate::StacksAddress;
+    use stacks_common::types::Address;
+
+    #[test]
+    fn test_replay_block() {
+        let privk = StacksPrivateKey::from_hex(
+            "42faca653724860da7a41bfcef7e6ba78db55146f6900de8cb2a9f760ffac70c01",
+        )
+        .unwrap();
+        let addr = StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(&privk)],
+        )
+        .unwrap();
+        let recipient: PrincipalData =
+            StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV")
+                .unwrap()
+                .into();
+
+        let observer = TestEventObserver::new();
+        let mut peer_config = TestPeerConfig::new("test_replay_block", 21330, 21331);
+        peer_config.initial_balances = vec![(addr.to_account_principal(), 1000000000)];
+        let mut peer = TestPeer::new_with_observer(peer_config, Some(&observer));
+
+        let mut coinbase_nonce = 0;
+        for sender_nonce in 0..5 {
+            let transfer = make_user_stacks_transfer(&privk, sender_nonce, 200, &recipient, 1000);
+            peer.tenure_with_txs(&[transfer], &mut coinbase_nonce);
+        }
+
+        let announced = observer.get_blocks();
+        assert_eq!(announced.len(), 5);
+
+        let sortdb = peer.sortdb.take().unwrap();
+        for (i, announced_block) in announced.iter().enumerate() {
+            let index_block_hash = announced_block.metadata.index_block_hash();
+            assert_eq!(
+                peer.chainstate()
+                    .get_canonical_block_id_at_height(&sortdb, (i + 1) as u64)
+                    .unwrap(),
+                Some(index_block_hash.clone())
+            );
+
+            let replayed = peer
+                .chainstate()
+                .replay_block(&sortdb, &index_block_hash)
+                .unwrap();
+            assert_eq!(replayed.block, announced_block.block);
+            assert_eq!(replayed.epoch_receipt.header, announced_block.metadata);
+            assert_eq!(replayed.epoch_receipt.tx_receipts, announced_block.receipts);
+            assert_eq!(replayed.parent_index_block_hash, announced_block.parent);
+            assert_eq!(replayed.winner_txid, announced_block.winner_txid);
+            assert_eq!(
+                replayed.epoch_receipt.matured_rewards,
+                announced_block.matured_rewards
+            );
+            assert_eq!(
+                replayed.epoch_receipt.matured_rewards_info,
+                announced_block.matured_rewards_info
+            );
+
+            // replaying is repeatable, because it changes nothing
+            assert_eq!(
+                peer.chainstate()
+                    .replay_block(&sortdb, &index_block_hash)
+                    .unwrap(),
+                replayed
+            );
+        }
+
+        assert_eq!(
+            peer.chainstate()
+                .get_canonical_block_id_at_height(&sortdb, 6)
+                .unwrap(),
+            None
+        );
+        assert!(matches!(
+            peer.chainstate()
+                .replay_block(&sortdb, &StacksBlockId([0x11; 32])),
+            Err(Error::NoSuchBlockError)
+        ));
+        peer.sortdb = Some(sortdb);
+
+        // the chainstate still processes new blocks after a replay
+        let transfer = make_user_stacks_transfer(&privk, 5, 200, &recipient, 1000);
+        peer.tenure_with_txs(&[transfer], &mut coinbase_nonce);
+        assert_eq!(observer.get_blocks().len(), 6);
+    }
+}
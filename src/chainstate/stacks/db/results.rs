@@ -0,0 +1,371 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-block records of what processing a block produced: whether it was accepted, and the
+//! result, events and cost of each of its transactions.  Nodes only keep these records if asked
+//! to; they exist so that a node running a new build can compare its results against those of a
+//! node running the canonical build (see `BlockResults::compare`).
+
+use rusqlite::types::ToSql;
+use rusqlite::OptionalExtension;
+
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::events::StacksTransactionReceipt;
+use crate::chainstate::stacks::Error;
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{DBConn, DBTx};
+use clarity::vm::costs::ExecutionCost;
+use clarity::vm::database::ClaritySerializable;
+use stacks_common::types::chainstate::{StacksBlockId, TrieHash};
+use stacks_common::util::hash::Sha512Trunc256Sum;
+
+/// What processing one transaction produced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TxResult {
+    pub txid: String,
+    /// hex-encoded serialized Clarity value
+    pub result: String,
+    pub post_condition_aborted: bool,
+    /// hash of the JSON encoding of the transaction's events
+    pub events_hash: String,
+    pub execution_cost: ExecutionCost,
+}
+
+/// What processing one block produced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockResults {
+    pub index_block_hash: StacksBlockId,
+    pub block_height: u64,
+    /// why the block was rejected, if it was
+    pub error: Option<String>,
+    /// the state root committed to by the block header
+    pub state_index_root: TrieHash,
+    /// the state root this node computed by evaluating the block, or None if it rejected the
+    /// block before getting that far
+    #[serde(default)]
+    pub computed_state_root: Option<TrieHash>,
+    /// the withdrawal root committed to by the block header
+    pub withdrawal_root: String,
+    /// results of the block's transactions (empty if the block was rejected)
+    pub tx_results: Vec<TxResult>,
+}
+
+/// One way in which a block's locally-computed results differ from the canonical results
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BlockResultsDivergence {
+    /// The nodes computed different state roots for the block
+    StateRoot {
+        local: Option<TrieHash>,
+        canonical: Option<TrieHash>,
+    },
+    /// The block was accepted by one node and rejected by the other
+    Acceptance {
+        local_error: Option<String>,
+        canonical_error: Option<String>,
+    },
+    /// The nodes produced a different number of transaction receipts
+    TxCount { local: usize, canonical: usize },
+    /// The transaction at `tx_index` produced different results
+    TxResult {
+        tx_index: usize,
+        local: TxResult,
+        canonical: TxResult,
+    },
+}
+
+impl TxResult {
+    pub fn from_receipt(receipt: &StacksTransactionReceipt) -> TxResult {
+        let txid = receipt.transaction.txid();
+        let events: Vec<_> = receipt
+            .events
+            .iter()
+            .enumerate()
+            .map(|(i, event)| event.json_serialize(i, &txid, !receipt.post_condition_aborted))
+            .collect();
+        let events_bytes = serde_json::to_vec(&events).expect("FATAL: failed to encode events");
+
+        TxResult {
+            txid: txid.to_hex(),
+            result: receipt.result.serialize(),
+            post_condition_aborted: receipt.post_condition_aborted,
+            events_hash: Sha512Trunc256Sum::from_data(&events_bytes).to_hex(),
+            execution_cost: receipt.execution_cost.clone(),
+        }
+    }
+}
+
+impl BlockResults {
+    /// Find all the ways in which `self` (computed locally) differs from `canonical`.  Both must
+    /// be for the same block; comparing the results of different blocks is an error.
+    pub fn compare(&self, canonical: &BlockResults) -> Result<Vec<BlockResultsDivergence>, Error> {
+        if self.index_block_hash != canonical.index_block_hash {
+            return Err(Error::InvalidStacksBlock(format!(
+                "Cannot compare results of block {} with results of block {}",
+                &self.index_block_hash, &canonical.index_block_hash
+            )));
+        }
+
+        let mut divergences = vec![];
+        // both nodes check the same header root, so only the roots they computed can differ
+        if self.computed_state_root != canonical.computed_state_root {
+            divergences.push(BlockResultsDivergence::StateRoot {
+                local: self.computed_state_root.clone(),
+                canonical: canonical.computed_state_root.clone(),
+            });
+        }
+
+        if self.error.is_some() != canonical.error.is_some() {
+            divergences.push(BlockResultsDivergence::Acceptance {
+                local_error: self.error.clone(),
+                canonical_error: canonical.error.clone(),
+            });
+            return Ok(divergences);
+        }
+
+        if self.tx_results.len() != canonical.tx_results.len() {
+            divergences.push(BlockResultsDivergence::TxCount {
+                local: self.tx_results.len(),
+                canonical: canonical.tx_results.len(),
+            });
+        }
+        for (tx_index, (local, canonical)) in self
+            .tx_results
+            .iter()
+            .zip(canonical.tx_results.iter())
+            .enumerate()
+        {
+            if local != canonical {
+                divergences.push(BlockResultsDivergence::TxResult {
+                    tx_index,
+                    local: local.clone(),
+                    canonical: canonical.clone(),
+                });
+            }
+        }
+        Ok(divergences)
+    }
+}
+
+impl StacksChainState {
+    /// Should this chainstate keep a record of the results of each block it processes?
+    pub fn set_record_block_results(&mut self, record_block_results: bool) {
+        self.record_block_results = record_block_results;
+    }
+
+    pub fn get_record_block_results(&self) -> bool {
+        self.record_block_results
+    }
+
+    pub fn insert_block_results(tx: &DBTx, results: &BlockResults) -> Result<(), Error> {
+        let results_json =
+            serde_json::to_string(results).expect("FATAL: failed to serialize block results");
+        let args: &[&dyn ToSql] = &[&results.index_block_hash, &results_json];
+        tx.execute(
+            "INSERT OR REPLACE INTO block_results (index_block_hash, results) VALUES (?1, ?2)",
+            args,
+        )
+        .map_err(|e| Error::DBError(db_error::SqliteError(e)))?;
+        Ok(())
+    }
+
+    /// Get the recorded results of processing a block, if any.
+    pub fn get_block_results(
+        conn: &DBConn,
+        index_block_hash: &StacksBlockId,
+    ) -> Result<Option<BlockResults>, Error> {
+        let results_json: Option<String> = conn
+            .query_row(
+                "SELECT results FROM block_results WHERE index_block_hash = ?1",
+                &[index_block_hash],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| Error::DBError(db_error::SqliteError(e)))?;
+
+        match results_json {
+            Some(results_json) => {
+                let results = serde_json::from_str(&results_json)
+                    .map_err(|e| Error::DBError(db_error::SerializationError(e)))?;
+                Ok(Some(results))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::miner::test::make_user_stacks_transfer;
+    use crate::chainstate::stacks::*;
+    use crate::net::test::*;
+    use clarity::vm::types::{PrincipalData, StacksAddressExtensions, Value};
+    use stacks_common::types::chainstate::StacksAddress;
+    use stacks_common::types::Address;
+
+    fn make_tx_result(txid_byte: u8, result: Value) -> TxResult {
+        TxResult {
+            txid: Sha512Trunc256Sum([txid_byte; 32]).to_hex(),
+            result: result.serialize(),
+            post_condition_aborted: false,
+            events_hash: Sha512Trunc256Sum::from_data(&[]).to_hex(),
+            execution_cost: ExecutionCost::zero(),
+        }
+    }
+
+    fn make_block_results(error: Option<String>, tx_results: Vec<TxResult>) -> BlockResults {
+        BlockResults {
+            index_block_hash: StacksBlockId([0x11; 32]),
+            block_height: 12,
+            error,
+            state_index_root: TrieHash([0x22; 32]),
+            computed_state_root: Some(TrieHash([0x22; 32])),
+            withdrawal_root: Sha512Trunc256Sum([0x33; 32]).to_hex(),
+            tx_results,
+        }
+    }
+
+    #[test]
+    fn test_compare_block_results() {
+        let canonical = make_block_results(
+            None,
+            vec![
+                make_tx_result(1, Value::okay_true()),
+                make_tx_result(2, Value::UInt(5)),
+            ],
+        );
+
+        // identical results
+        assert_eq!(canonical.compare(&canonical).unwrap(), vec![]);
+
+        // locally rejected, because the state root this node computed is not the header's
+        let mut rejected = make_block_results(Some("state root mismatch".into()), vec![]);
+        rejected.computed_state_root = Some(TrieHash([0x44; 32]));
+        assert_eq!(
+            rejected.compare(&canonical).unwrap(),
+            vec![
+                BlockResultsDivergence::StateRoot {
+                    local: Some(TrieHash([0x44; 32])),
+                    canonical: Some(TrieHash([0x22; 32])),
+                },
+                BlockResultsDivergence::Acceptance {
+                    local_error: Some("state root mismatch".into()),
+                    canonical_error: None,
+                }
+            ]
+        );
+
+        // different transaction result
+        let mut different = canonical.clone();
+        different.tx_results[1] = make_tx_result(2, Value::UInt(6));
+        assert_eq!(
+            different.compare(&canonical).unwrap(),
+            vec![BlockResultsDivergence::TxResult {
+                tx_index: 1,
+                local: different.tx_results[1].clone(),
+                canonical: canonical.tx_results[1].clone(),
+            }]
+        );
+
+        // different cost
+        let mut different = canonical.clone();
+        different.tx_results[0].execution_cost.runtime += 1;
+        assert_eq!(different.compare(&canonical).unwrap().len(), 1);
+
+        // missing transaction
+        let mut different = canonical.clone();
+        different.tx_results.pop();
+        assert_eq!(
+            different.compare(&canonical).unwrap(),
+            vec![BlockResultsDivergence::TxCount {
+                local: 1,
+                canonical: 2,
+            }]
+        );
+
+        // results of different blocks cannot be compared
+        let mut other_block = canonical.clone();
+        other_block.index_block_hash = StacksBlockId([0x12; 32]);
+        assert!(other_block.compare(&canonical).is_err());
+    }
+
+    #[test]
+    fn test_record_block_results() {
+        let privk = StacksPrivateKey::from_hex(
+            "42faca653724860da7a41bfcef7e6ba78db55146f6900de8cb2a9f760ffac70c01",
+        )
+        .unwrap();
+        let addr = StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(&privk)],
+        )
+        .unwrap();
+        let recipient: PrincipalData =
+            StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV")
+                .unwrap()
+                .into();
+
+        let mut peer_config = TestPeerConfig::new("test_record_block_results", 21340, 21341);
+        peer_config.initial_balances = vec![(addr.to_account_principal(), 1000000000)];
+        let mut peer = TestPeer::new(peer_config);
+
+        let mut coinbase_nonce = 0;
+        // results are only recorded if asked for
+        let transfer = make_user_stacks_transfer(&privk, 0, 200, &recipient, 1000);
+        peer.tenure_with_txs(&[transfer], &mut coinbase_nonce);
+
+        peer.coord.chainstate_mut().set_record_block_results(true);
+        let transfer = make_user_stacks_transfer(&privk, 1, 200, &recipient, 1000);
+        peer.tenure_with_txs(&[transfer.clone()], &mut coinbase_nonce);
+
+        let sortdb = peer.sortdb.take().unwrap();
+        let unrecorded = peer
+            .chainstate()
+            .get_canonical_block_id_at_height(&sortdb, 1)
+            .unwrap()
+            .unwrap();
+        let recorded = peer
+            .chainstate()
+            .get_canonical_block_id_at_height(&sortdb, 2)
+            .unwrap()
+            .unwrap();
+        peer.sortdb = Some(sortdb);
+
+        assert_eq!(
+            StacksChainState::get_block_results(peer.chainstate().db(), &unrecorded).unwrap(),
+            None
+        );
+
+        let results = StacksChainState::get_block_results(peer.chainstate().db(), &recorded)
+            .unwrap()
+            .unwrap();
+        assert_eq!(results.index_block_hash, recorded);
+        assert_eq!(results.block_height, 2);
+        assert_eq!(results.error, None);
+        // the block was accepted, so the root this node computed is the one in its header
+        assert_eq!(
+            results.computed_state_root,
+            Some(results.state_index_root.clone())
+        );
+        // the coinbase and the transfer
+        assert_eq!(results.tx_results.len(), 2);
+        assert_eq!(results.tx_results[1].txid, transfer.txid().to_hex());
+        assert_eq!(results.tx_results[1].result, Value::okay_true().serialize());
+        assert_eq!(results.compare(&results).unwrap(), vec![]);
+    }
+}
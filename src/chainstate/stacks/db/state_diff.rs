@@ -0,0 +1,610 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-block records of the Clarity state that processing a block wrote: each key's value before
+//! and after the block.  Nodes only keep these records if asked to.  The raw write set is stored
+//! in a compact binary encoding, and decoded on demand into the account balance changes, token
+//! movements and data map writes that explorers would otherwise reconstruct by re-executing the
+//! block.
+
+use std::io::{Read, Write};
+
+use rusqlite::types::ToSql;
+use rusqlite::OptionalExtension;
+
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::Error;
+use crate::clarity_vm::database::marf::MARFWrite;
+use crate::codec::{read_next, write_next, Error as codec_error, StacksMessageCodec};
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{DBConn, DBTx};
+use clarity::vm::database::{ClarityDeserializable, ClaritySerializable, STXBalance, StoreType};
+use clarity::vm::types::{PrincipalData, Value};
+use stacks_common::types::chainstate::StacksBlockId;
+use stacks_common::util::hash::hex_bytes;
+
+/// The change in an account's STX balance made by a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct STXBalanceChange {
+    pub principal: String,
+    /// total balance (unlocked and locked) before the block, in microSTX
+    pub before: String,
+    /// total balance (unlocked and locked) after the block, in microSTX
+    pub after: String,
+}
+
+/// The change in an account's balance of a fungible token made by a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FungibleTokenBalanceChange {
+    pub asset_identifier: String,
+    pub principal: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// The change in the owner of a non-fungible token made by a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NonFungibleTokenOwnerChange {
+    pub asset_identifier: String,
+    /// hex-encoded serialized Clarity value identifying the token
+    pub value: String,
+    /// the token's owner before the block, if it existed
+    pub before_owner: Option<String>,
+    /// the token's owner after the block, if it was not burnt
+    pub after_owner: Option<String>,
+}
+
+/// An entry of a contract's data map written by a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataMapWrite {
+    pub contract_id: String,
+    pub map_name: String,
+    /// hex-encoded serialized Clarity value of the entry's key
+    pub key: String,
+    /// hex-encoded serialized Clarity value of the entry before the block, if it existed
+    pub before: Option<String>,
+    /// hex-encoded serialized Clarity value of the entry after the block, if it was not deleted
+    pub after: Option<String>,
+}
+
+/// The account state that processing one block changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockStateDiff {
+    pub index_block_hash: StacksBlockId,
+    pub stx_balances: Vec<STXBalanceChange>,
+    pub ft_balances: Vec<FungibleTokenBalanceChange>,
+    pub nft_owners: Vec<NonFungibleTokenOwnerChange>,
+    pub data_map_writes: Vec<DataMapWrite>,
+}
+
+impl StacksMessageCodec for MARFWrite {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        write_next(fd, &self.key.as_bytes().to_vec())?;
+        match self.before {
+            Some(ref before) => {
+                write_next(fd, &1u8)?;
+                write_next(fd, &before.as_bytes().to_vec())?;
+            }
+            None => {
+                write_next(fd, &0u8)?;
+            }
+        }
+        write_next(fd, &self.after.as_bytes().to_vec())?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<MARFWrite, codec_error> {
+        let key = read_next_string(fd)?;
+        let before = match read_next::<u8, _>(fd)? {
+            0 => None,
+            1 => Some(read_next_string(fd)?),
+            x => {
+                return Err(codec_error::DeserializeError(format!(
+                    "Invalid write-set value flag {}",
+                    x
+                )));
+            }
+        };
+        let after = read_next_string(fd)?;
+        Ok(MARFWrite { key, before, after })
+    }
+}
+
+fn read_next_string<R: Read>(fd: &mut R) -> Result<String, codec_error> {
+    let bytes: Vec<u8> = read_next(fd)?;
+    String::from_utf8(bytes)
+        .map_err(|_| codec_error::DeserializeError("Write-set string is not UTF-8".into()))
+}
+
+/// Decode a serialized `u128` or `Option<Value>` write.  Values in the Clarity store are written
+/// by the VM, so these only fail if the store is corrupt.
+fn decode_u128(value: &str) -> Option<u128> {
+    serde_json::from_str(value).ok()
+}
+
+fn decode_optional_value(value: &str) -> Option<Option<Value>> {
+    match Value::try_deserialize_hex_untyped(value).ok()? {
+        Value::Optional(data) => Some(data.data.map(|value| *value)),
+        _ => None,
+    }
+}
+
+fn decode_stx_balance(value: &str) -> Option<u128> {
+    // STXBalance's deserializer panics on malformed input
+    match hex_bytes(value) {
+        Ok(ref bytes) if bytes.len() == STXBalance::size_of => {}
+        _ => {
+            return None;
+        }
+    }
+    let balance = STXBalance::deserialize(value);
+    balance.amount_unlocked.checked_add(balance.amount_locked)
+}
+
+impl BlockStateDiff {
+    /// Sort a block's write set into the kinds of state change that explorers care about.
+    /// Writes to any other state (nonces, data vars, contract code, etc.) are left out.
+    pub fn from_writes(index_block_hash: StacksBlockId, writes: &[MARFWrite]) -> BlockStateDiff {
+        let mut diff = BlockStateDiff {
+            index_block_hash,
+            stx_balances: vec![],
+            ft_balances: vec![],
+            nft_owners: vec![],
+            data_map_writes: vec![],
+        };
+        for write in writes.iter() {
+            if diff.add_write(write).is_none() {
+                warn!("Failed to decode state diff write"; "key" => &write.key);
+            }
+        }
+        diff
+    }
+
+    /// Add `write` to this diff if it is one of the kinds of change that diffs report.
+    /// Returns None if it is, but could not be decoded.
+    fn add_write(&mut self, write: &MARFWrite) -> Option<()> {
+        if let Some(account_key) = write.key.strip_prefix("vm-account::") {
+            let (principal, store_type) = account_key.rsplit_once("::")?;
+            if store_type == (StoreType::STXBalance as u8).to_string() {
+                let before = match write.before {
+                    Some(ref before) => decode_stx_balance(before)?,
+                    None => 0,
+                };
+                self.stx_balances.push(STXBalanceChange {
+                    principal: principal.to_string(),
+                    before: before.to_string(),
+                    after: decode_stx_balance(&write.after)?.to_string(),
+                });
+            }
+            return Some(());
+        }
+
+        let parts: Vec<&str> = match write.key.strip_prefix("vm::") {
+            Some(contract_key) => contract_key.splitn(4, "::").collect(),
+            None => {
+                return Some(());
+            }
+        };
+        if parts.len() != 4 {
+            return Some(());
+        }
+        let (contract_id, store_type, name, key) = (parts[0], parts[1], parts[2], parts[3]);
+
+        if store_type == (StoreType::FungibleToken as u8).to_string() {
+            let principal: PrincipalData = serde_json::from_str(key).ok()?;
+            let before = match write.before {
+                Some(ref before) => decode_u128(before)?,
+                None => 0,
+            };
+            self.ft_balances.push(FungibleTokenBalanceChange {
+                asset_identifier: format!("{}::{}", contract_id, name),
+                principal: principal.to_string(),
+                before: before.to_string(),
+                after: decode_u128(&write.after)?.to_string(),
+            });
+        } else if store_type == (StoreType::NonFungibleToken as u8).to_string() {
+            let owner = |value: &str| match decode_optional_value(value)? {
+                Some(Value::Principal(owner)) => Some(Some(owner.to_string())),
+                Some(_) => None,
+                None => Some(None),
+            };
+            let before_owner = match write.before {
+                Some(ref before) => owner(before)?,
+                None => None,
+            };
+            self.nft_owners.push(NonFungibleTokenOwnerChange {
+                asset_identifier: format!("{}::{}", contract_id, name),
+                value: key.to_string(),
+                before_owner,
+                after_owner: owner(&write.after)?,
+            });
+        } else if store_type == (StoreType::DataMap as u8).to_string() {
+            let before = match write.before {
+                Some(ref before) => decode_optional_value(before)?,
+                None => None,
+            };
+            self.data_map_writes.push(DataMapWrite {
+                contract_id: contract_id.to_string(),
+                map_name: name.to_string(),
+                key: key.to_string(),
+                before: before.map(|value| value.serialize()),
+                after: decode_optional_value(&write.after)?.map(|value| value.serialize()),
+            });
+        }
+        Some(())
+    }
+}
+
+impl StacksChainState {
+    /// Should this chainstate keep a record of the state written by each block it processes?
+    pub fn set_record_state_diffs(&mut self, record_state_diffs: bool) {
+        self.record_state_diffs = record_state_diffs;
+    }
+
+    pub fn get_record_state_diffs(&self) -> bool {
+        self.record_state_diffs
+    }
+
+    /// Store the write set of a processed block.  Keys that the block wrote back to their prior
+    /// value are left out.
+    pub fn insert_block_state_diff(
+        tx: &DBTx,
+        index_block_hash: &StacksBlockId,
+        writes: &[MARFWrite],
+    ) -> Result<(), Error> {
+        let changed: Vec<MARFWrite> = writes
+            .iter()
+            .filter(|write| write.before.as_ref() != Some(&write.after))
+            .cloned()
+            .collect();
+        let write_set = changed.serialize_to_vec();
+        let args: &[&dyn ToSql] = &[index_block_hash, &write_set];
+        tx.execute(
+            "INSERT OR REPLACE INTO block_state_diffs (index_block_hash, write_set) VALUES (?1, ?2)",
+            args,
+        )
+        .map_err(|e| Error::DBError(db_error::SqliteError(e)))?;
+        Ok(())
+    }
+
+    /// Get the recorded write set of a processed block, if any.
+    pub fn get_block_write_set(
+        conn: &DBConn,
+        index_block_hash: &StacksBlockId,
+    ) -> Result<Option<Vec<MARFWrite>>, Error> {
+        let write_set: Option<Vec<u8>> = conn
+            .query_row(
+                "SELECT write_set FROM block_state_diffs WHERE index_block_hash = ?1",
+                &[index_block_hash],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| Error::DBError(db_error::SqliteError(e)))?;
+
+        match write_set {
+            Some(write_set) => {
+                let writes = Vec::<MARFWrite>::consensus_deserialize(&mut &write_set[..])
+                    .map_err(Error::CodecError)?;
+                Ok(Some(writes))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Get the recorded state diff of a processed block, if any.
+    pub fn get_block_state_diff(
+        conn: &DBConn,
+        index_block_hash: &StacksBlockId,
+    ) -> Result<Option<BlockStateDiff>, Error> {
+        Ok(
+            StacksChainState::get_block_write_set(conn, index_block_hash)?
+                .map(|writes| BlockStateDiff::from_writes(index_block_hash.clone(), &writes)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::index::ClarityMarfTrieId;
+    use crate::chainstate::stacks::miner::test::make_user_stacks_transfer;
+    use crate::chainstate::stacks::*;
+    use crate::clarity_vm::clarity::{ClarityBlockConnection, ClarityInstance};
+    use crate::clarity_vm::database::marf::MarfedKV;
+    use crate::net::test::*;
+    use clarity::vm::clarity::TransactionConnection;
+    use clarity::vm::database::ClarityDatabase;
+    use clarity::vm::test_util::{TEST_BURN_STATE_DB, TEST_HEADER_DB};
+    use clarity::vm::types::{
+        QualifiedContractIdentifier, StacksAddressExtensions, StandardPrincipalData,
+    };
+    use stacks_common::types::chainstate::StacksAddress;
+    use stacks_common::types::Address;
+
+    fn stx_balance(amount_unlocked: u128, amount_locked: u128) -> String {
+        STXBalance {
+            amount_unlocked,
+            amount_locked,
+            unlock_height: 0,
+        }
+        .serialize()
+    }
+
+    #[test]
+    fn test_state_diff_from_writes() {
+        let contract_id = QualifiedContractIdentifier::local("tokens").unwrap();
+        let alice: PrincipalData = StandardPrincipalData::transient().into();
+        let bob: PrincipalData = contract_id.clone().into();
+        let writes = vec![
+            MARFWrite {
+                key: ClarityDatabase::make_key_for_account_balance(&alice),
+                before: Some(stx_balance(100, 50)),
+                after: stx_balance(90, 50),
+            },
+            MARFWrite {
+                key: ClarityDatabase::make_key_for_account_balance(&bob),
+                before: None,
+                after: stx_balance(10, 0),
+            },
+            // nonces aren't part of the diff
+            MARFWrite {
+                key: ClarityDatabase::make_key_for_account_nonce(&alice),
+                before: Some(1u64.serialize()),
+                after: 2u64.serialize(),
+            },
+            MARFWrite {
+                key: ClarityDatabase::make_key_for_quad(
+                    &contract_id,
+                    StoreType::FungibleToken,
+                    "gold",
+                    &bob.serialize(),
+                ),
+                before: None,
+                after: 25u128.serialize(),
+            },
+            MARFWrite {
+                key: ClarityDatabase::make_key_for_quad(
+                    &contract_id,
+                    StoreType::NonFungibleToken,
+                    "badge",
+                    &Value::UInt(7).serialize(),
+                ),
+                before: Some(
+                    Value::some(Value::Principal(alice.clone()))
+                        .unwrap()
+                        .serialize(),
+                ),
+                after: Value::none().serialize(),
+            },
+            MARFWrite {
+                key: ClarityDatabase::make_key_for_data_map_entry(
+                    &contract_id,
+                    "scores",
+                    &Value::Int(1),
+                ),
+                before: None,
+                after: Value::some(Value::Int(99)).unwrap().serialize(),
+            },
+            // undecodable writes are skipped
+            MARFWrite {
+                key: ClarityDatabase::make_key_for_account_balance(&bob),
+                before: None,
+                after: "not a balance".into(),
+            },
+        ];
+
+        let diff = BlockStateDiff::from_writes(StacksBlockId([0x11; 32]), &writes);
+        assert_eq!(
+            diff.stx_balances,
+            vec![
+                STXBalanceChange {
+                    principal: alice.to_string(),
+                    before: "150".into(),
+                    after: "140".into(),
+                },
+                STXBalanceChange {
+                    principal: bob.to_string(),
+                    before: "0".into(),
+                    after: "10".into(),
+                },
+            ]
+        );
+        assert_eq!(
+            diff.ft_balances,
+            vec![FungibleTokenBalanceChange {
+                asset_identifier: format!("{}::gold", &contract_id),
+                principal: bob.to_string(),
+                before: "0".into(),
+                after: "25".into(),
+            }]
+        );
+        assert_eq!(
+            diff.nft_owners,
+            vec![NonFungibleTokenOwnerChange {
+                asset_identifier: format!("{}::badge", &contract_id),
+                value: Value::UInt(7).serialize(),
+                before_owner: Some(alice.to_string()),
+                after_owner: None,
+            }]
+        );
+        assert_eq!(
+            diff.data_map_writes,
+            vec![DataMapWrite {
+                contract_id: contract_id.to_string(),
+                map_name: "scores".into(),
+                key: Value::Int(1).serialize(),
+                before: None,
+                after: Some(Value::Int(99).serialize()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_record_block_write_set() {
+        let marf = MarfedKV::temporary();
+        let mut clarity_instance = ClarityInstance::new(false, marf);
+        let contract_id = QualifiedContractIdentifier::local("tokens").unwrap();
+        let sender: PrincipalData = StandardPrincipalData::transient().into();
+        let contract = "
+            (define-fungible-token gold)
+            (define-map scores int int)
+            (define-public (play (score int))
+                (begin
+                    (map-set scores 1 score)
+                    (ft-mint? gold u10 tx-sender)))";
+
+        clarity_instance
+            .begin_test_genesis_block(
+                &StacksBlockId::sentinel(),
+                &StacksBlockId([0 as u8; 32]),
+                &TEST_HEADER_DB,
+                &TEST_BURN_STATE_DB,
+            )
+            .commit_block();
+
+        let play = |conn: &mut ClarityBlockConnection, score: i128| {
+            conn.as_transaction(|tx| {
+                tx.run_contract_call(
+                    &sender,
+                    &contract_id,
+                    "play",
+                    &[Value::Int(score)],
+                    |_, _| false,
+                )
+            })
+            .unwrap();
+        };
+
+        {
+            let mut conn = clarity_instance.begin_block(
+                &StacksBlockId([0 as u8; 32]),
+                &StacksBlockId([1 as u8; 32]),
+                &TEST_HEADER_DB,
+                &TEST_BURN_STATE_DB,
+            );
+            conn.as_transaction(|tx| {
+                let (ct_ast, ct_analysis) =
+                    tx.analyze_smart_contract(&contract_id, contract).unwrap();
+                tx.initialize_smart_contract(&contract_id, &ct_ast, contract, |_, _| false)
+                    .unwrap();
+                tx.save_analysis(&contract_id, &ct_analysis).unwrap();
+            });
+            play(&mut conn, 5);
+            // writes are only recorded if asked for
+            assert_eq!(conn.take_write_set(), None);
+            conn.commit_block();
+        }
+
+        let mut conn = clarity_instance.begin_block(
+            &StacksBlockId([1 as u8; 32]),
+            &StacksBlockId([2 as u8; 32]),
+            &TEST_HEADER_DB,
+            &TEST_BURN_STATE_DB,
+        );
+        conn.record_write_set();
+        play(&mut conn, 6);
+        play(&mut conn, 7);
+        let writes = conn.take_write_set().unwrap();
+        conn.commit_block();
+
+        // each key is recorded once, with its value from before the block
+        let diff = BlockStateDiff::from_writes(StacksBlockId([2 as u8; 32]), &writes);
+        assert_eq!(
+            diff.ft_balances,
+            vec![FungibleTokenBalanceChange {
+                asset_identifier: format!("{}::gold", &contract_id),
+                principal: sender.to_string(),
+                before: "10".into(),
+                after: "30".into(),
+            }]
+        );
+        assert_eq!(
+            diff.data_map_writes,
+            vec![DataMapWrite {
+                contract_id: contract_id.to_string(),
+                map_name: "scores".into(),
+                key: Value::Int(1).serialize(),
+                before: Some(Value::Int(5).serialize()),
+                after: Some(Value::Int(7).serialize()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_record_processed_block_state_diff() {
+        let privk = StacksPrivateKey::from_hex(
+            "42faca653724860da7a41bfcef7e6ba78db55146f6900de8cb2a9f760ffac70c01",
+        )
+        .unwrap();
+        let addr = StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(&privk)],
+        )
+        .unwrap();
+        let recipient: PrincipalData =
+            StacksAddress::from_string("ST1RFD5Q2QPK3E0F08HG9XDX7SSC7CNRS0QR0SGEV")
+                .unwrap()
+                .into();
+
+        let mut peer_config =
+            TestPeerConfig::new("test_record_processed_block_state_diff", 21348, 21349);
+        peer_config.initial_balances = vec![(addr.to_account_principal(), 1000000000)];
+        let mut peer = TestPeer::new(peer_config);
+        let mut coinbase_nonce = 0;
+
+        // diffs are only recorded if asked for
+        let transfer = make_user_stacks_transfer(&privk, 0, 200, &recipient, 1000);
+        let unrecorded = peer.tenure_with_txs(&[transfer], &mut coinbase_nonce);
+        assert_eq!(
+            StacksChainState::get_block_state_diff(peer.chainstate().db(), &unrecorded).unwrap(),
+            None
+        );
+
+        peer.coord.chainstate_mut().set_record_state_diffs(true);
+        let transfer = make_user_stacks_transfer(&privk, 1, 200, &recipient, 1000);
+        let recorded = peer.tenure_with_txs(&[transfer], &mut coinbase_nonce);
+
+        let diff = StacksChainState::get_block_state_diff(peer.chainstate().db(), &recorded)
+            .unwrap()
+            .unwrap();
+        assert_eq!(diff.index_block_hash, recorded);
+        let balance_change = |principal: &PrincipalData| {
+            diff.stx_balances
+                .iter()
+                .find(|change| change.principal == principal.to_string())
+                .cloned()
+        };
+        // the sender paid the amount and the fee; the recipient got the amount
+        assert_eq!(
+            balance_change(&addr.to_account_principal()),
+            Some(STXBalanceChange {
+                principal: addr.to_account_principal().to_string(),
+                before: "999998800".into(),
+                after: "999997600".into(),
+            })
+        );
+        assert_eq!(
+            balance_change(&recipient),
+            Some(STXBalanceChange {
+                principal: recipient.to_string(),
+                before: "1000".into(),
+                after: "2000".into(),
+            })
+        );
+    }
+}
@@ -30,6 +30,7 @@ use crate::clarity_vm::clarity::{
     ClarityBlockConnection, ClarityConnection, ClarityInstance, ClarityTransactionConnection,
     Error as clarity_error,
 };
+use crate::clarity_vm::withdrawal::withdrawal_tips_below_minimum;
 use crate::net::Error as net_error;
 use crate::util_lib::db::Error as db_error;
 use crate::util_lib::db::{query_count, query_rows, DBConn};
@@ -397,6 +398,11 @@ impl StacksChainState {
         // valid auth?
         tx.verify().map_err(Error::NetError)?;
 
+        StacksChainState::check_transaction_network(config, tx)
+    }
+
+    /// Make sure a transaction is destined for this chain
+    fn check_transaction_network(config: &DBConfig, tx: &StacksTransaction) -> Result<(), Error> {
         // destined for us?
         if config.chain_id != tx.chain_id {
             let msg = format!(
@@ -847,6 +853,15 @@ impl StacksChainState {
                     }
                 };
 
+                for warning in contract_analysis.warnings.iter() {
+                    warn!("Contract analysis warning";
+                          "contract_id" => %contract_id,
+                          "txid" => %tx.txid(),
+                          "message" => %warning.message,
+                          "code" => ?warning.code,
+                          "spans" => ?warning.spans);
+                }
+
                 let mut analysis_cost = clarity_tx.cost_so_far();
                 analysis_cost
                     .sub(&cost_before)
@@ -944,11 +959,57 @@ impl StacksChainState {
         clarity_block: &mut ClarityTx,
         tx: &StacksTransaction,
         quiet: bool,
+    ) -> Result<(u64, StacksTransactionReceipt), Error> {
+        StacksChainState::process_transaction_inner(clarity_block, tx, quiet, true, 0)
+    }
+
+    /// Process a transaction whose signatures the caller has already verified.  The miner uses
+    /// this for transactions that its signature verification workers checked ahead of block
+    /// assembly.  Return the fee and the transaction receipt.
+    pub fn process_transaction_preverified(
+        clarity_block: &mut ClarityTx,
+        tx: &StacksTransaction,
+        quiet: bool,
+    ) -> Result<(u64, StacksTransactionReceipt), Error> {
+        StacksChainState::process_transaction_inner(clarity_block, tx, quiet, false, 0)
+    }
+
+    /// Process a transaction for inclusion in a block this node is mining.  If any withdrawal in
+    /// the transaction pays a miner tip below `min_withdrawal_tip` uSTX, the transaction's effects
+    /// are rolled back and it is skipped with `Error::StacksTransactionSkipped`.  Signatures are
+    /// checked only if `verify_auth` is set.  Return the fee and the transaction receipt.
+    pub fn process_transaction_for_miner(
+        clarity_block: &mut ClarityTx,
+        tx: &StacksTransaction,
+        quiet: bool,
+        verify_auth: bool,
+        min_withdrawal_tip: u128,
+    ) -> Result<(u64, StacksTransactionReceipt), Error> {
+        StacksChainState::process_transaction_inner(
+            clarity_block,
+            tx,
+            quiet,
+            verify_auth,
+            min_withdrawal_tip,
+        )
+    }
+
+    fn process_transaction_inner(
+        clarity_block: &mut ClarityTx,
+        tx: &StacksTransaction,
+        quiet: bool,
+        verify_auth: bool,
+        min_withdrawal_tip: u128,
     ) -> Result<(u64, StacksTransactionReceipt), Error> {
         debug!("Process transaction {} ({})", tx.txid(), tx.payload.name());
 
-        StacksChainState::process_transaction_precheck(&clarity_block.config, tx)?;
+        if verify_auth {
+            StacksChainState::process_transaction_precheck(&clarity_block.config, tx)?;
+        } else {
+            StacksChainState::check_transaction_network(&clarity_block.config, tx)?;
+        }
 
+        let mainnet = clarity_block.config.mainnet;
         let mut transaction = clarity_block.connection().start_transaction_processing();
         let (origin_account, payer_account) =
             StacksChainState::check_transaction_nonces(&mut transaction, tx, quiet)?;
@@ -956,6 +1017,28 @@ impl StacksChainState {
         let tx_receipt =
             StacksChainState::process_transaction_payload(&mut transaction, tx, &origin_account)?;
 
+        if withdrawal_tips_below_minimum(&tx_receipt, min_withdrawal_tip) {
+            // dropping the uncommitted transaction rolls back its effects
+            return Err(Error::StacksTransactionSkipped(format!(
+                "Transaction {} withdraws with a miner tip below the minimum of {} uSTX",
+                tx.txid(),
+                min_withdrawal_tip
+            )));
+        }
+
+        if let Err(reason) =
+            StacksChainState::check_withdrawal_registrations(&mut transaction, mainnet, &tx_receipt)
+        {
+            return Err(Error::InvalidStacksTransaction(
+                format!(
+                    "Transaction {} does not match the bridge registry: {}",
+                    tx.txid(),
+                    reason
+                ),
+                false,
+            ));
+        }
+
         let new_payer_account = StacksChainState::get_payer_account(&mut transaction, tx);
         let fee = tx.get_tx_fee();
         StacksChainState::pay_transaction_fee(&mut transaction, fee, new_payer_account)?;
@@ -99,7 +99,9 @@ impl UnconfirmedState {
             chainstate.marf_opts.clone(),
         )?;
 
-        let clarity_instance = ClarityInstance::new(chainstate.mainnet, marf);
+        let mut clarity_instance = ClarityInstance::new(chainstate.mainnet, marf);
+        clarity_instance.set_max_tx_event_bytes(chainstate.clarity_state.max_tx_event_bytes());
+        clarity_instance.set_use_subnet_costs(chainstate.clarity_state.use_subnet_costs());
         let unconfirmed_tip = MARF::make_unconfirmed_chain_tip(&tip);
         let cost_so_far = StacksChainState::get_stacks_block_anchored_cost(chainstate.db(), &tip)?
             .ok_or(Error::NoSuchBlockError)?;
@@ -136,7 +138,9 @@ impl UnconfirmedState {
             chainstate.marf_opts.clone(),
         )?;
 
-        let clarity_instance = ClarityInstance::new(chainstate.mainnet, marf);
+        let mut clarity_instance = ClarityInstance::new(chainstate.mainnet, marf);
+        clarity_instance.set_max_tx_event_bytes(chainstate.clarity_state.max_tx_event_bytes());
+        clarity_instance.set_use_subnet_costs(chainstate.clarity_state.use_subnet_costs());
         let unconfirmed_tip = MARF::make_unconfirmed_chain_tip(&tip);
         let cost_so_far = StacksChainState::get_stacks_block_anchored_cost(chainstate.db(), &tip)?
             .ok_or(Error::NoSuchBlockError)?;
@@ -2336,15 +2336,13 @@ impl<'a, T: MarfTrieId> TrieStorageConnection<'a, T> {
     /// `TrieHashCalculationMode::All` or `TrieHashCalculationMode::Immediate`.  There is no need
     /// to call if the hash mode is `::Deferred`.  The only way this gets called while not in
     /// `::Deferred` mode is when generating a Merkle proof.
+    ///
+    /// The hashes are only written to `w`, so this is permitted on read-only storage.
     pub fn write_children_hashes<W: Write>(
         &mut self,
         node: &TrieNodeType,
         w: &mut W,
     ) -> Result<(), Error> {
-        if self.data.readonly {
-            return Err(Error::ReadOnlyError);
-        }
-
         trace!("write_children_hashes for {:?}", node);
 
         let mut map = TrieSqlHashMapCursor {
@@ -712,6 +712,18 @@ pub fn drop_unconfirmed_trie<T: MarfTrieId>(conn: &Connection, bhh: &T) -> Resul
     Ok(())
 }
 
+/// Drop a confirmed trie, e.g. one on a stale fork, returning whether or not a trie was dropped.
+/// The most recently-stored trie is never dropped, so that its block_id is not reused.
+pub fn drop_trie<T: MarfTrieId>(conn: &Connection, bhh: &T) -> Result<bool, Error> {
+    debug!("Drop trie sqlite blob {}", bhh);
+    let dropped = conn.execute(
+        "DELETE FROM marf_data WHERE block_hash = ?1 AND unconfirmed = 0 \
+         AND block_id < (SELECT MAX(block_id) FROM marf_data)",
+        &[bhh],
+    )?;
+    Ok(dropped > 0)
+}
+
 pub fn clear_lock_data(conn: &Connection) -> Result<(), Error> {
     conn.execute("DELETE FROM block_extension_locks", NO_PARAMS)?;
     Ok(())
@@ -49,14 +49,16 @@ use stacks_common::util::get_epoch_time_ms;
 use stacks_common::util::hash::hex_bytes;
 use stacks_common::util::hash::MerkleTree;
 use stacks_common::util::hash::Sha512Trunc256Sum;
-use stacks_common::util::secp256k1::{MessageSignature, Secp256k1PrivateKey};
+use stacks_common::util::secp256k1::{MessageSignature, Secp256k1PrivateKey, Secp256k1PublicKey};
 use stacks_common::util::vrf::*;
 
 use crate::chainstate::stacks::address::StacksAddressExtensions;
 use crate::chainstate::stacks::db::blocks::SetupBlockResult;
 use crate::chainstate::stacks::StacksBlockHeader;
 use crate::chainstate::stacks::StacksMicroblockHeader;
-use crate::clarity_vm::withdrawal::create_withdrawal_merkle_tree;
+use crate::clarity_vm::withdrawal::{
+    check_withdrawal_root, get_withdrawal_leaf_hashes, WithdrawalRootMismatch,
+};
 use crate::codec::{read_next, write_next, StacksMessageCodec};
 use crate::types::chainstate::BurnchainHeaderHash;
 use crate::types::chainstate::StacksBlockId;
@@ -69,6 +71,10 @@ use clarity::vm::clarity::TransactionConnection;
 pub const SIP18_DATA_PREFIX_HEX: &'static str =
     "53495030313881c24181e24119f609a28023c4943d3a41592656eb90560c15ee02b8e1ce19b8";
 
+/// Prefixes the hash a multi-miner proposer signs, so that a proposer's signature is never also
+/// a valid signer's signature for the L1 contract.
+const PROPOSER_SIGNATURE_DOMAIN: &[u8] = b"subnet-block-proposer";
+
 #[derive(Debug, Clone)]
 pub struct BlockBuilderSettings {
     pub max_miner_time_ms: u64,
@@ -134,6 +140,8 @@ pub struct AssembledBlockInfo {
     pub mblocks_confirmed: Vec<StacksMicroblock>,
     pub burn_tip: BurnchainHeaderHash,
     pub burn_tip_height: u32,
+    /// Leaves of the block's withdrawal merkle tree
+    pub withdrawal_leaves: Vec<Sha512Trunc256Sum>,
 }
 
 /// Represents a proposed block from the 2-phase commit
@@ -168,6 +176,38 @@ pub struct Proposal {
     ///  Stacks header. In subnets, this is just an incrementing
     ///  value.
     pub total_burn: u64,
+    /// These are the leaves of the proposed block's withdrawal
+    ///  merkle tree. If supplied, a validator that computes a
+    ///  different withdrawal root reports the first leaf at which
+    ///  its tree diverges.
+    #[serde(default)]
+    pub withdrawal_leaves: Option<Vec<Sha512Trunc256Sum>>,
+    /// The proposing miner's signature over this proposal. Signers
+    ///  only sign proposals from the miners that may propose for
+    ///  the proposal's L1 block.
+    #[serde(default)]
+    pub proposer_signature: Option<MessageSignature>,
+}
+
+/// The outcome of processing one of a block proposal's transactions
+#[derive(Debug)]
+pub struct ProposalTxOutcome {
+    pub txid: Txid,
+    /// The transaction's receipt, or the error that keeps it out of the block
+    pub result: Result<StacksTransactionReceipt, Error>,
+}
+
+/// What processing all of a block proposal's transactions on top of its parent produced
+#[derive(Debug)]
+pub struct ProposalEvaluation {
+    /// The block assembled from the transactions that could be mined
+    pub block: StacksBlock,
+    pub execution_cost: ExecutionCost,
+    pub block_size: u64,
+    /// One outcome per proposed transaction, in block order
+    pub tx_outcomes: Vec<ProposalTxOutcome>,
+    /// Whether the proposal's withdrawal root is the one computed from the mined transactions
+    pub withdrawal_check: Result<(), WithdrawalRootMismatch>,
 }
 
 impl From<&UnconfirmedState> for MicroblockMinerRuntime {
@@ -671,8 +711,9 @@ impl<'a> StacksMicroblockBuilder<'a> {
     /// Returns Ok(TransactionResult::Error) if the transaction was not mined due to an error.
     /// Returns Err(e) if an error occurs during the function.
     ///
-    /// This calls `StacksChainState::process_transaction` and also checks certain pre-conditions
-    /// and handles errors.
+    /// This calls `StacksChainState::process_transaction_for_miner` and also checks certain
+    /// pre-conditions and handles errors.  Transactions withdrawing with a miner tip below
+    /// `min_withdrawal_tip` are skipped.
     ///
     /// # Pre-Checks
     /// - skip if the `anchor_mode` rules out micro-blocks
@@ -687,6 +728,7 @@ impl<'a> StacksMicroblockBuilder<'a> {
         tx_len: u64,
         bytes_so_far: u64,
         limit_behavior: &BlockLimitFunction,
+        min_withdrawal_tip: u128,
     ) -> Result<TransactionResult, Error> {
         if tx.anchor_mode != TransactionAnchorMode::OffChainOnly
             && tx.anchor_mode != TransactionAnchorMode::Any
@@ -742,7 +784,13 @@ impl<'a> StacksMicroblockBuilder<'a> {
         };
 
         let quiet = !cfg!(test);
-        match StacksChainState::process_transaction(clarity_tx, &tx, quiet) {
+        match StacksChainState::process_transaction_for_miner(
+            clarity_tx,
+            &tx,
+            quiet,
+            true,
+            min_withdrawal_tip,
+        ) {
             Ok((fee, receipt)) => Ok(TransactionResult::success(&tx, fee, receipt)),
             Err(e) => {
                 match &e {
@@ -820,6 +868,7 @@ impl<'a> StacksMicroblockBuilder<'a> {
                 tx_len,
                 bytes_so_far,
                 &block_limit_hit,
+                self.settings.mempool_settings.min_withdrawal_tip,
             ) {
                 Ok(tx_result) => {
                     tx_events.push(tx_result.convert_to_event());
@@ -923,7 +972,11 @@ impl<'a> StacksMicroblockBuilder<'a> {
         let block_limit = clarity_tx
             .block_limit()
             .expect("No block limit found for clarity_tx.");
-        mem_pool.estimate_tx_rates(100, &block_limit, &stacks_epoch_id)?;
+        mem_pool.estimate_tx_rates(
+            mempool_settings.ranking.fee_rate_estimates_per_walk(),
+            &block_limit,
+            &stacks_epoch_id,
+        )?;
 
         debug!(
             "Microblock transaction selection begins (child of {}), bytes so far: {}",
@@ -961,6 +1014,7 @@ impl<'a> StacksMicroblockBuilder<'a> {
                             mempool_tx.metadata.len,
                             bytes_so_far,
                             &block_limit_hit,
+                            mempool_settings.min_withdrawal_tip,
                         ) {
                             Ok(tx_result) => {
                                 tx_events.push(tx_result.convert_to_event());
@@ -1160,6 +1214,8 @@ impl StacksBlockBuilder {
             miner_payouts: None,
             miner_id: miner_id,
             microblock_tx_receipts: vec![],
+            withdrawal_leaves: vec![],
+            min_withdrawal_tip: 0,
         }
     }
 
@@ -1291,7 +1347,13 @@ impl StacksBlockBuilder {
         tx: &StacksTransaction,
     ) -> Result<TransactionResult, Error> {
         let tx_len = tx.tx_len();
-        match self.try_mine_tx_with_len(clarity_tx, tx, tx_len, &BlockLimitFunction::NO_LIMIT_HIT) {
+        match self.try_mine_tx_with_len(
+            clarity_tx,
+            tx,
+            tx_len,
+            &BlockLimitFunction::NO_LIMIT_HIT,
+            false,
+        ) {
             TransactionResult::Success(s) => Ok(TransactionResult::Success(s)),
             TransactionResult::Skipped(TransactionSkipped { error, .. })
             | TransactionResult::ProcessingError(TransactionError { error, .. }) => Err(error),
@@ -1300,12 +1362,15 @@ impl StacksBlockBuilder {
 
     /// Append a transaction if doing so won't exceed the epoch data size.
     /// Errors out if we exceed budget, or the transaction is invalid.
+    /// If `auth_verified` is set, the transaction's signatures were already verified, and are not
+    /// checked again.
     fn try_mine_tx_with_len(
         &mut self,
         clarity_tx: &mut ClarityTx,
         tx: &StacksTransaction,
         tx_len: u64,
         limit_behavior: &BlockLimitFunction,
+        auth_verified: bool,
     ) -> TransactionResult {
         if self.bytes_so_far + tx_len >= MAX_EPOCH_SIZE.into() {
             return TransactionResult::skipped_due_to_error(&tx, Error::BlockTooBigError);
@@ -1357,8 +1422,13 @@ impl StacksBlockBuilder {
                 );
             }
 
-            let (fee, receipt) = match StacksChainState::process_transaction(clarity_tx, tx, quiet)
-            {
+            let (fee, receipt) = match StacksChainState::process_transaction_for_miner(
+                clarity_tx,
+                tx,
+                quiet,
+                !auth_verified,
+                self.min_withdrawal_tip,
+            ) {
                 Ok((fee, receipt)) => (fee, receipt),
                 Err(e) => match e {
                     Error::CostOverflowError(cost_before, cost_after, total_budget) => {
@@ -1414,8 +1484,13 @@ impl StacksBlockBuilder {
                 );
             }
 
-            let (fee, receipt) = match StacksChainState::process_transaction(clarity_tx, tx, quiet)
-            {
+            let (fee, receipt) = match StacksChainState::process_transaction_for_miner(
+                clarity_tx,
+                tx,
+                quiet,
+                !auth_verified,
+                self.min_withdrawal_tip,
+            ) {
                 Ok((fee, receipt)) => (fee, receipt),
                 Err(e) => match e {
                     Error::CostOverflowError(cost_before, cost_after, total_budget) => {
@@ -1534,8 +1609,10 @@ impl StacksBlockBuilder {
         self.header.tx_merkle_root = tx_merkle_root;
         self.header.state_index_root = state_root_hash;
 
+        self.withdrawal_leaves =
+            get_withdrawal_leaf_hashes(&mut self.tx_receipts, self.header.total_work.work);
         let withdrawal_tree =
-            create_withdrawal_merkle_tree(&mut self.tx_receipts, self.header.total_work.work);
+            MerkleTree::<Sha512Trunc256Sum>::from_leaf_hashes(self.withdrawal_leaves.clone());
         let withdrawal_merkle_root = withdrawal_tree.root();
         self.header.withdrawal_merkle_root = withdrawal_merkle_root;
 
@@ -2049,6 +2126,14 @@ impl StacksBlockBuilder {
 
         let (mut chainstate, _) = chainstate_handle.reopen()?;
 
+        // the candidate check workers open their chainstate handles before the block's
+        // chainstate transaction begins
+        mempool.start_candidate_check_pool(mempool_settings.candidate_check_threads, || {
+            chainstate_handle
+                .reopen()
+                .map(|(chainstate, _)| Box::new(chainstate) as Box<dyn CandidateNonceReader>)
+        })?;
+
         let mut builder = StacksBlockBuilder::make_block_builder(
             chainstate.mainnet,
             parent_stacks_header,
@@ -2057,9 +2142,11 @@ impl StacksBlockBuilder {
             pubkey_hash,
             &MessageSignatureList::empty(),
         )?;
+        builder.min_withdrawal_tip = mempool_settings.min_withdrawal_tip;
 
         let ts_start = get_epoch_time_ms();
 
+        let system_priority_policy = chainstate.get_system_priority_policy().cloned();
         let mut miner_epoch_info = builder.pre_epoch_begin(&mut chainstate, burn_dbconn)?;
 
         let (mut epoch_tx, confirmed_mblock_cost) =
@@ -2079,11 +2166,18 @@ impl StacksBlockBuilder {
 
         mempool.reset_last_known_nonces()?;
 
-        mempool.estimate_tx_rates(100, &block_limit, &stacks_epoch_id)?;
+        mempool.estimate_tx_rates(
+            mempool_settings.ranking.fee_rate_estimates_per_walk(),
+            &block_limit,
+            &stacks_epoch_id,
+        )?;
 
         let mut considered = HashSet::new(); // txids of all transactions we looked at
         let mut mined_origin_nonces: HashMap<StacksAddress, u64> = HashMap::new(); // map addrs of mined transaction origins to the nonces we used
         let mut mined_sponsor_nonces: HashMap<StacksAddress, u64> = HashMap::new(); // map addrs of mined transaction sponsors to the nonces we used
+        let mut deploy_quota =
+            ContractDeployQuotaTracker::new(mempool_settings.contract_deploy_quota.clone());
+        let contract_deploy_limits = mempool_settings.contract_deploy_limits.clone();
 
         let mut invalidated_txs = vec![];
 
@@ -2091,6 +2185,63 @@ impl StacksBlockBuilder {
         let deadline = ts_start + (max_miner_time_ms as u128);
         let mut num_txs = 0;
 
+        // System priority transactions are mined first, whatever their fees, and may use the whole
+        // block budget.  Every other transaction is held to the policy's normal budget.
+        if let Some(policy) = system_priority_policy {
+            let priority_txs = MemPoolDB::get_system_priority_txs(mempool.conn(), &policy)?;
+            for txinfo in priority_txs.iter() {
+                if let Some(nonce) = mined_origin_nonces.get(&txinfo.tx.origin_address()) {
+                    if *nonce >= txinfo.tx.get_origin_nonce() {
+                        continue;
+                    }
+                }
+
+                let tx_result = builder.try_mine_tx_with_len(
+                    &mut epoch_tx,
+                    &txinfo.tx,
+                    txinfo.metadata.len,
+                    &BlockLimitFunction::NO_LIMIT_HIT,
+                    false,
+                );
+                tx_events.push(tx_result.convert_to_event());
+
+                match tx_result {
+                    TransactionResult::Success(..) => {
+                        num_txs += 1;
+                        considered.insert(txinfo.tx.txid());
+                        mined_origin_nonces
+                            .insert(txinfo.tx.origin_address(), txinfo.tx.get_origin_nonce());
+                        if let (Some(sponsor_addr), Some(sponsor_nonce)) =
+                            (txinfo.tx.sponsor_address(), txinfo.tx.get_sponsor_nonce())
+                        {
+                            mined_sponsor_nonces.insert(sponsor_addr, sponsor_nonce);
+                        }
+                    }
+                    TransactionResult::Skipped(TransactionSkipped { error, .. })
+                    | TransactionResult::ProcessingError(TransactionError { error, .. }) => {
+                        // left for the fee-ordered walk below, under the normal budget
+                        debug!("Failed to mine system priority tx";
+                               "txid" => %txinfo.tx.txid(),
+                               "error" => ?error);
+                    }
+                }
+            }
+
+            epoch_tx.set_block_limit(policy.normal_budget(&block_limit));
+        }
+
+        // Check the candidates' signatures and nonces in the worker pool, so that the walk below
+        // only has to apply them.  The workers read nonces as of the parent block, so cache the
+        // nonces of the accounts this block already advanced first.
+        let mut advanced_accounts = vec![coinbase_tx.origin_address()];
+        advanced_accounts.extend(mined_origin_nonces.keys().cloned());
+        advanced_accounts.extend(mined_sponsor_nonces.keys().cloned());
+        mempool.refresh_last_known_nonces(&mut epoch_tx, &advanced_accounts)?;
+        mempool.check_candidates(
+            &parent_stacks_header.index_block_hash(),
+            mempool_settings.max_walk_time_ms,
+        )?;
+
         debug!(
             "Anchored block transaction selection begins (child of {})",
             &parent_stacks_header.anchored_header.block_hash()
@@ -2138,17 +2289,35 @@ impl StacksBlockBuilder {
                         considered.insert(txinfo.tx.txid());
                         num_considered += 1;
 
+                        if !deploy_quota.admits(&txinfo.tx, txinfo.metadata.len) {
+                            debug!("Contract deploy quota reached, skipping tx";
+                                   "txid" => %txinfo.tx.txid(),
+                                   "origin" => %txinfo.tx.origin_address());
+                            return Ok(true);
+                        }
+
+                        if let Err(e) = contract_deploy_limits.check(epoch_tx, &txinfo.tx) {
+                            // the mempool would no longer admit it either
+                            info!("Contract deploy exceeds this node's limits, dropping tx";
+                                  "txid" => %txinfo.tx.txid(),
+                                  "reason" => ?e);
+                            invalidated_txs.push(txinfo.metadata.txid);
+                            return Ok(true);
+                        }
+
                         let tx_result = builder.try_mine_tx_with_len(
                             epoch_tx,
                             &txinfo.tx,
                             txinfo.metadata.len,
                             &block_limit_hit,
+                            to_consider.auth_verified,
                         );
                         tx_events.push(tx_result.convert_to_event());
 
                         match tx_result {
                             TransactionResult::Success(TransactionSuccess { receipt, .. }) => {
                                 num_txs += 1;
+                                deploy_quota.record(&txinfo.tx, txinfo.metadata.len);
                                 if update_estimator {
                                     if let Err(e) = estimator.notify_event(
                                         &txinfo.tx.payload,
@@ -2246,6 +2415,7 @@ impl StacksBlockBuilder {
         // save the block so we can build microblocks off of it
         let block = builder.mine_anchored_block(&mut epoch_tx);
         let size = builder.bytes_so_far;
+        let withdrawal_leaves = builder.withdrawal_leaves.clone();
         let consumed = builder.epoch_finish(epoch_tx);
 
         let ts_end = get_epoch_time_ms();
@@ -2284,6 +2454,7 @@ impl StacksBlockBuilder {
             mblocks_confirmed: miner_epoch_info.parent_microblocks,
             burn_tip: miner_epoch_info.burn_tip,
             burn_tip_height: miner_epoch_info.burn_tip_height,
+            withdrawal_leaves,
         })
     }
 }
@@ -2296,6 +2467,26 @@ impl Proposal {
         signing_key: &Secp256k1PrivateKey,
         signing_contract: QualifiedContractIdentifier,
     ) -> [u8; 65] {
+        let structured_hash = self.structured_hash(signing_contract);
+        let msg_signature = signing_key
+            .sign(structured_hash.as_bytes())
+            .expect("Bad message hash");
+        // format the signature vector as Clarity expects
+        let recov_signature = msg_signature
+            .to_secp256k1_recoverable()
+            .expect("Failed to create recoverable signature");
+        let (rec_id, rec_signature_comp) = recov_signature.serialize_compact();
+        let mut signature = [0; 65];
+        signature[..64].copy_from_slice(&rec_signature_comp);
+        signature[64] = u8::try_from(rec_id.to_i32()).unwrap();
+
+        signature
+    }
+
+    /// Compute the SIP-018 structured data hash that signers sign,
+    /// which commits to the block, its withdrawal root, and its
+    /// target L1 tip.
+    fn structured_hash(&self, signing_contract: QualifiedContractIdentifier) -> Sha256Sum {
         // when using a 2.0 layer-1, must use a constant
         // let structured_hash =
         //     hex_bytes("e2f4d0b1eca5f1b4eb853cd7f1c843540cfb21de8bfdaa59c504a6775cd2cfe9")
@@ -2323,30 +2514,59 @@ impl Proposal {
         let data_hash = Sha256Sum::from_data(&data_tuple.serialize_to_vec());
         let mut hash_input = hex_bytes(SIP18_DATA_PREFIX_HEX).expect("Bad SIP18 data prefix");
         hash_input.extend_from_slice(&data_hash.0);
-        let structured_hash = Sha256Sum::from_data(&hash_input);
+        Sha256Sum::from_data(&hash_input)
+    }
 
-        let msg_signature = signing_key
-            .sign(structured_hash.as_bytes())
+    /// Compute the hash that the proposer signs, which also commits
+    /// to the L1 block height the proposal is made for.
+    fn proposer_hash(&self, signing_contract: QualifiedContractIdentifier) -> Sha256Sum {
+        let mut hash_input = PROPOSER_SIGNATURE_DOMAIN.to_vec();
+        hash_input.extend_from_slice(self.structured_hash(signing_contract).as_bytes());
+        hash_input.extend_from_slice(&self.burn_tip_height.to_be_bytes());
+        Sha256Sum::from_data(&hash_input)
+    }
+
+    /// Sign this proposal as its proposer with `proposer_key`.
+    pub fn sign_as_proposer(
+        &mut self,
+        proposer_key: &Secp256k1PrivateKey,
+        signing_contract: QualifiedContractIdentifier,
+    ) {
+        let proposer_hash = self.proposer_hash(signing_contract);
+        let signature = proposer_key
+            .sign(proposer_hash.as_bytes())
             .expect("Bad message hash");
-        // format the signature vector as Clarity expects
-        let recov_signature = msg_signature
-            .to_secp256k1_recoverable()
-            .expect("Failed to create recoverable signature");
-        let (rec_id, rec_signature_comp) = recov_signature.serialize_compact();
-        let mut signature = [0; 65];
-        signature[..64].copy_from_slice(&rec_signature_comp);
-        signature[64] = u8::try_from(rec_id.to_i32()).unwrap();
+        self.proposer_signature = Some(signature);
+    }
 
-        signature
+    /// The compressed public key of the miner that signed this proposal
+    /// as its proposer.  Returns None if it is not signed, or if the
+    /// signature is malformed.
+    pub fn proposer(&self, signing_contract: QualifiedContractIdentifier) -> Option<[u8; 33]> {
+        let signature = self.proposer_signature.as_ref()?;
+        let proposer_hash = self.proposer_hash(signing_contract);
+        let public_key =
+            Secp256k1PublicKey::recover_to_pubkey(proposer_hash.as_bytes(), signature).ok()?;
+        let mut key = [0u8; 33];
+        key.copy_from_slice(&public_key.to_bytes_compressed());
+        Some(key)
     }
 
-    /// Given access to the mempool, mine an anchored block with no more than the given execution cost.
-    ///   returns the assembled block, and the consumed execution budget.
-    pub fn validate(
+    /// Height of the L1 block this proposal was built on, whose
+    ///  multi-miner leader proposes it.  The proposal itself will be
+    ///  committed in the next L1 block, at `burn_tip_height`.
+    pub fn tenure_burn_height(&self) -> u64 {
+        u64::from(self.burn_tip_height).saturating_sub(1)
+    }
+
+    /// Process each of this proposal's transactions on top of its parent block, without
+    ///   stopping at the first one that fails.  Errors out only if the proposal cannot be
+    ///   evaluated at all (e.g. its parent is unknown).
+    pub fn evaluate(
         &self,
         chainstate_handle: &StacksChainState, // not directly used; used as a handle to open other chainstates
         burn_dbconn: &SortitionDBConn,
-    ) -> Result<(StacksBlock, ExecutionCost, u64), Error> {
+    ) -> Result<ProposalEvaluation, Error> {
         let expected_block_hash = self.block.block_hash();
 
         let can_attach = StacksChainState::can_attach(
@@ -2401,8 +2621,6 @@ impl Proposal {
             &MessageSignatureList::empty(),
         )?;
 
-        let ts_start = get_epoch_time_ms();
-
         // check that no microblocks cross an epoch boundary
         if !self.microblocks_confirmed.is_empty()
             && StacksChainState::block_crosses_epoch_boundary(
@@ -2436,45 +2654,107 @@ impl Proposal {
         let (mut epoch_tx, _confirmed_mblock_cost) =
             builder.epoch_begin(burn_dbconn, &mut miner_epoch_info)?;
 
+        let mut tx_outcomes = Vec::with_capacity(self.block.txs.len());
         for tx in self.block.txs.iter() {
-            if let Err(e) = builder.try_mine_tx(&mut epoch_tx, tx) {
+            let result = match builder.try_mine_tx(&mut epoch_tx, tx) {
+                Ok(TransactionResult::Success(TransactionSuccess { receipt, .. })) => Ok(receipt),
+                Ok(_) => unreachable!("try_mine_tx only returns successes"),
+                Err(e) => {
+                    debug!(
+                        "Proposed transaction failed";
+                        "block_hash" => %expected_block_hash,
+                        "txid" => %tx.txid(),
+                        "tx_error" => %e,
+                    );
+                    Err(e)
+                }
+            };
+            tx_outcomes.push(ProposalTxOutcome {
+                txid: tx.txid(),
+                result,
+            });
+        }
+
+        // a transaction that caused a budget exception is rolled back in process_transaction
+
+        let block = builder.mine_anchored_block(&mut epoch_tx);
+        let block_size = builder.bytes_so_far;
+        let withdrawal_check = check_withdrawal_root(
+            builder.withdrawal_leaves.clone(),
+            &self.block.header.withdrawal_merkle_root,
+            self.withdrawal_leaves.as_deref(),
+        )
+        .map(|_| ());
+        let execution_cost = builder.epoch_finish(epoch_tx);
+
+        Ok(ProposalEvaluation {
+            block,
+            execution_cost,
+            block_size,
+            tx_outcomes,
+            withdrawal_check,
+        })
+    }
+
+    /// Check that this proposal's block is exactly the block its parent and transactions
+    ///   produce.  Returns the block, the consumed execution budget, and the block size.
+    pub fn validate(
+        &self,
+        chainstate_handle: &StacksChainState, // not directly used; used as a handle to open other chainstates
+        burn_dbconn: &SortitionDBConn,
+    ) -> Result<(StacksBlock, ExecutionCost, u64), Error> {
+        let expected_block_hash = self.block.block_hash();
+        let ts_start = get_epoch_time_ms();
+
+        let ProposalEvaluation {
+            block,
+            execution_cost: consumed,
+            block_size: size,
+            tx_outcomes,
+            withdrawal_check,
+        } = self.evaluate(chainstate_handle, burn_dbconn)?;
+
+        for outcome in tx_outcomes.into_iter() {
+            if let Err(e) = outcome.result {
                 warn!(
                     "Rejected proposal";
                     "reason" => "Transaction included with invalidating error",
-                    "parent_block_hash" => %tip_block_hash,
-                    "parent_consensus_hash" => %tip_consensus_hash,
+                    "parent_block_hash" => %self.parent_block_hash,
+                    "parent_consensus_hash" => %self.parent_consensus_hash,
                     "block_hash" => %expected_block_hash,
-                    "txid" => %tx.txid(),
+                    "txid" => %outcome.txid,
                     "tx_error" => %e,
                 );
                 return Err(e);
             }
         }
 
-        // the prior do_rebuild logic wasn't necessary
-        // a transaction that caused a budget exception is rolled back in process_transaction
-
-        // save the block so we can build microblocks off of it
-        let block = builder.mine_anchored_block(&mut epoch_tx);
-        let size = builder.bytes_so_far;
-        let consumed = builder.epoch_finish(epoch_tx);
-
         let ts_end = get_epoch_time_ms();
 
         let computed_block_hash = block.block_hash();
-        let computed_withdrawal_merkle_root = block.header.withdrawal_merkle_root;
 
-        if &computed_withdrawal_merkle_root != &self.block.header.withdrawal_merkle_root {
+        if let Err(mismatch) = withdrawal_check {
+            let (divergent_leaf_index, expected_leaf, computed_leaf) =
+                match mismatch.first_divergent_leaf {
+                    Some(ref leaf) => (
+                        Some(leaf.index),
+                        leaf.expected.as_ref().map(|leaf| leaf.to_string()),
+                        leaf.computed.as_ref().map(|leaf| leaf.to_string()),
+                    ),
+                    None => (None, None, None),
+                };
             warn!(
                 "Rejected proposal";
                 "reason" => "Withdrawal root is not as expected",
-                "expected_withdrawal_root" => %self.block.header.withdrawal_merkle_root,
-                "computed_withdrawal_root" => %computed_withdrawal_merkle_root,
+                "expected_withdrawal_root" => %mismatch.expected_root,
+                "computed_withdrawal_root" => %mismatch.computed_root,
+                "computed_withdrawal_leaves" => mismatch.computed_leaves.len(),
+                "divergent_leaf_index" => ?divergent_leaf_index,
+                "expected_leaf" => ?expected_leaf,
+                "computed_leaf" => ?computed_leaf,
                 "block_hash" => %expected_block_hash,
             );
-            return Err(Error::InvalidStacksBlock(
-                "Withdrawal root is not as expected".into(),
-            ));
+            return Err(Error::InvalidStacksBlock(mismatch.to_string()));
         }
 
         if &computed_block_hash != &expected_block_hash {
@@ -2486,7 +2766,7 @@ impl Proposal {
                 "block_hash" => %expected_block_hash,
             );
             return Err(Error::InvalidStacksBlock(
-                "Withdrawal root is not as expected".into(),
+                "Block hash is not as expected".into(),
             ));
         }
 
@@ -2533,8 +2813,10 @@ pub mod test {
     use crate::chainstate::burn::*;
     use crate::chainstate::coordinator::Error as CoordinatorError;
     use crate::chainstate::stacks::db::blocks::test::store_staging_block;
+    use crate::chainstate::stacks::db::genesis::GenesisSpec;
     use crate::chainstate::stacks::db::test::*;
     use crate::chainstate::stacks::db::*;
+    use crate::chainstate::stacks::system_priority::SystemPriorityPolicy;
     use crate::chainstate::stacks::Error as ChainstateError;
     use crate::chainstate::stacks::C32_ADDRESS_VERSION_TESTNET_SINGLESIG;
     use crate::chainstate::stacks::*;
@@ -5952,6 +6234,326 @@ pub mod test {
         }
     }
 
+    #[test]
+    fn test_build_anchored_blocks_contract_deploy_quota() {
+        let private_keys: Vec<_> = (0..5).map(|_| StacksPrivateKey::new()).collect();
+        let addresses: Vec<_> = private_keys
+            .iter()
+            .map(|sk| {
+                StacksAddress::from_public_keys(
+                    C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+                    &AddressHashMode::SerializeP2PKH,
+                    1,
+                    &vec![StacksPublicKey::from_private(sk)],
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let initial_balances: Vec<_> = addresses
+            .iter()
+            .map(|addr| (addr.to_account_principal(), 100000000000))
+            .collect();
+
+        let mut peer_config =
+            TestPeerConfig::new("build_anchored_contract_deploy_quota", 2032, 2033);
+        peer_config.initial_balances = initial_balances;
+
+        let mut peer = TestPeer::new(peer_config);
+
+        let chainstate_path = peer.chainstate_path.clone();
+
+        let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+        let tip = SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn())
+            .unwrap();
+
+        let (burn_ops, stacks_block, microblocks) = peer.make_tenure(
+            |ref mut miner,
+             ref mut sortdb,
+             ref mut chainstate,
+             vrf_proof,
+             ref parent_opt,
+             ref parent_microblock_header_opt| {
+                let parent_tip = match parent_opt {
+                    None => StacksChainState::get_genesis_header_info(chainstate.db()).unwrap(),
+                    Some(block) => {
+                        let ic = sortdb.index_conn();
+                        let snapshot = SortitionDB::get_block_snapshot_for_winning_stacks_block(
+                            &ic,
+                            &tip.sortition_id,
+                            &block.block_hash(),
+                        )
+                        .unwrap()
+                        .unwrap(); // succeeds because we don't fork
+                        StacksChainState::get_anchored_block_header_info(
+                            chainstate.db(),
+                            &snapshot.consensus_hash,
+                            &snapshot.winning_stacks_block_hash,
+                        )
+                        .unwrap()
+                        .unwrap()
+                    }
+                };
+
+                let parent_header_hash = parent_tip.anchored_header.block_hash();
+                let parent_consensus_hash = parent_tip.consensus_hash.clone();
+                let coinbase_tx = make_coinbase(miner, 0);
+
+                // the first four accounts each deploy 3 contracts, and the last one sends 5
+                // stx-transfers
+                let mut txs = vec![];
+                for privk in private_keys[0..4].iter() {
+                    for tx_nonce in 0..3 {
+                        txs.push(make_user_contract_publish(
+                            privk,
+                            tx_nonce,
+                            2000 * (tx_nonce + 1),
+                            &format!("contract-{}", tx_nonce),
+                            "(define-data-var bar int 0)",
+                        ));
+                    }
+                }
+                for tx_nonce in 0..5 {
+                    txs.push(make_user_stacks_transfer(
+                        &private_keys[4],
+                        tx_nonce,
+                        200,
+                        &addresses[0].to_account_principal(),
+                        100,
+                    ));
+                }
+
+                for tx in txs {
+                    mempool
+                        .submit(
+                            chainstate,
+                            &parent_consensus_hash,
+                            &parent_header_hash,
+                            &tx,
+                            None,
+                            &ExecutionCost::max_value(),
+                            &StacksEpochId::Epoch20,
+                        )
+                        .unwrap();
+                }
+
+                let mut settings = BlockBuilderSettings::limited();
+                settings.mempool_settings.contract_deploy_quota = ContractDeployQuota {
+                    max_deploys_per_block: Some(5),
+                    max_deploy_bytes_per_block: None,
+                    max_deploys_per_origin: Some(2),
+                    max_deploy_bytes_per_origin: None,
+                };
+                // candidates are checked by the candidate check workers
+                settings.mempool_settings.candidate_check_threads = 2;
+
+                let anchored_block = StacksBlockBuilder::build_anchored_block(
+                    chainstate,
+                    &sortdb.index_conn(),
+                    &mut mempool,
+                    &parent_tip,
+                    tip.total_burn,
+                    vrf_proof,
+                    Hash160([0 as u8; 20]),
+                    &coinbase_tx,
+                    settings,
+                    None,
+                )
+                .unwrap();
+                (anchored_block.0, vec![])
+            },
+        );
+
+        peer.next_burnchain_block(burn_ops.clone());
+        peer.process_stacks_epoch_at_tip(&stacks_block, &microblocks);
+
+        let mut deploys_per_origin: HashMap<StacksAddress, u64> = HashMap::new();
+        let mut num_transfers = 0;
+        for tx in stacks_block.txs.iter() {
+            match tx.payload {
+                TransactionPayload::SmartContract(..) => {
+                    *deploys_per_origin.entry(tx.origin_address()).or_insert(0) += 1;
+                }
+                TransactionPayload::TokenTransfer(..) => {
+                    num_transfers += 1;
+                }
+                _ => {}
+            }
+        }
+
+        // the stx-transfers were not crowded out by the deploys
+        assert_eq!(num_transfers, 5);
+        assert_eq!(deploys_per_origin.values().sum::<u64>(), 5);
+        for (_, num_deploys) in deploys_per_origin.iter() {
+            assert!(*num_deploys <= 2);
+        }
+    }
+
+    #[test]
+    fn test_build_anchored_blocks_system_priority() {
+        let governance_privk = StacksPrivateKey::new();
+        let governance_addr = StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(&governance_privk)],
+        )
+        .unwrap();
+        let private_keys: Vec<_> = (0..5).map(|_| StacksPrivateKey::new()).collect();
+        let addresses: Vec<_> = private_keys
+            .iter()
+            .map(|sk| {
+                StacksAddress::from_public_keys(
+                    C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+                    &AddressHashMode::SerializeP2PKH,
+                    1,
+                    &vec![StacksPublicKey::from_private(sk)],
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let mut initial_balances: Vec<_> = addresses
+            .iter()
+            .map(|addr| (addr.to_account_principal(), 100000000000))
+            .collect();
+        initial_balances.push((governance_addr.to_account_principal(), 100000000000));
+
+        let policy = SystemPriorityPolicy {
+            contracts: vec![QualifiedContractIdentifier::new(
+                governance_addr.clone().into(),
+                "governance".into(),
+            )],
+            authorized_principals: vec![governance_addr.clone()],
+            reserved_budget_percent: 10,
+        };
+
+        let mut peer_config = TestPeerConfig::new("build_anchored_system_priority", 2034, 2035);
+        peer_config.initial_balances = initial_balances;
+
+        let mut peer = TestPeer::new(peer_config);
+
+        let chainstate_path = peer.chainstate_path.clone();
+
+        for tenure_id in 0..2 {
+            let tip =
+                SortitionDB::get_canonical_burn_chain_tip(&peer.sortdb.as_ref().unwrap().conn())
+                    .unwrap();
+
+            let (burn_ops, stacks_block, microblocks) = peer.make_tenure(
+                |ref mut miner,
+                 ref mut sortdb,
+                 ref mut chainstate,
+                 vrf_proof,
+                 ref parent_opt,
+                 ref parent_microblock_header_opt| {
+                    chainstate.set_system_priority_policy(Some(policy.clone()));
+
+                    let parent_tip = match parent_opt {
+                        None => StacksChainState::get_genesis_header_info(chainstate.db()).unwrap(),
+                        Some(block) => {
+                            let ic = sortdb.index_conn();
+                            let snapshot =
+                                SortitionDB::get_block_snapshot_for_winning_stacks_block(
+                                    &ic,
+                                    &tip.sortition_id,
+                                    &block.block_hash(),
+                                )
+                                .unwrap()
+                                .unwrap(); // succeeds because we don't fork
+                            StacksChainState::get_anchored_block_header_info(
+                                chainstate.db(),
+                                &snapshot.consensus_hash,
+                                &snapshot.winning_stacks_block_hash,
+                            )
+                            .unwrap()
+                            .unwrap()
+                        }
+                    };
+
+                    let parent_header_hash = parent_tip.anchored_header.block_hash();
+                    let parent_consensus_hash = parent_tip.consensus_hash.clone();
+                    let coinbase_tx = make_coinbase(miner, tenure_id);
+
+                    let mut mempool =
+                        MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+                    let mut txs = vec![];
+                    if tenure_id == 0 {
+                        txs.push(make_user_contract_publish(
+                            &governance_privk,
+                            0,
+                            2000,
+                            "governance",
+                            "(define-public (halt) (ok true))",
+                        ));
+                    } else {
+                        // the stx-transfers pay far more, but the call into the governance
+                        // contract is mined first
+                        for privk in private_keys.iter() {
+                            txs.push(make_user_stacks_transfer(
+                                privk,
+                                0,
+                                10000,
+                                &addresses[0].to_account_principal(),
+                                100,
+                            ));
+                        }
+                        let halt = TransactionPayload::new_contract_call(
+                            governance_addr.clone(),
+                            "governance",
+                            "halt",
+                            vec![],
+                        )
+                        .unwrap();
+                        txs.push(sign_standard_singlesig_tx(halt, &governance_privk, 1, 300));
+                    }
+
+                    for tx in txs {
+                        mempool
+                            .submit(
+                                chainstate,
+                                &parent_consensus_hash,
+                                &parent_header_hash,
+                                &tx,
+                                None,
+                                &ExecutionCost::max_value(),
+                                &StacksEpochId::Epoch20,
+                            )
+                            .unwrap();
+                    }
+
+                    let anchored_block = StacksBlockBuilder::build_anchored_block(
+                        chainstate,
+                        &sortdb.index_conn(),
+                        &mut mempool,
+                        &parent_tip,
+                        tip.total_burn,
+                        vrf_proof,
+                        Hash160([tenure_id as u8; 20]),
+                        &coinbase_tx,
+                        BlockBuilderSettings::limited(),
+                        None,
+                    )
+                    .unwrap();
+                    (anchored_block.0, vec![])
+                },
+            );
+
+            peer.next_burnchain_block(burn_ops.clone());
+            peer.process_stacks_epoch_at_tip(&stacks_block, &microblocks);
+
+            if tenure_id == 1 {
+                assert_eq!(stacks_block.txs.len(), 7);
+                assert!(policy.is_system_priority(&stacks_block.txs[1]));
+                for tx in stacks_block.txs[2..].iter() {
+                    assert!(!policy.is_system_priority(tx));
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_build_anchored_blocks_skip_too_expensive() {
         let privk = StacksPrivateKey::from_hex(
@@ -6713,6 +7315,13 @@ pub mod test {
             get_bulk_initial_balances: None,
             get_bulk_initial_names: None,
             get_bulk_initial_namespaces: None,
+            genesis_spec: GenesisSpec::default(),
+            allow_unchecked_intermediary_responses: false,
+            withdrawal_batch_blocks: 0,
+            epochs: None,
+            system_priority: None,
+            max_tx_event_bytes: None,
+            use_subnet_costs: false,
         };
 
         StacksChainState::open_and_exec(mainnet, chain_id, &path, Some(&mut boot_data), None)
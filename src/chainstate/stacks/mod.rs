@@ -50,6 +50,7 @@ use clarity::vm::types::{
 };
 use stacks_common::address::AddressHashMode;
 use stacks_common::util::hash::Hash160;
+use stacks_common::util::hash::MerklePath;
 use stacks_common::util::hash::Sha512Trunc256Sum;
 use stacks_common::util::hash::HASH160_ENCODED_SIZE;
 use stacks_common::util::secp256k1;
@@ -70,6 +71,8 @@ pub mod db;
 pub mod events;
 pub mod index;
 pub mod miner;
+pub mod subnet_codec;
+pub mod system_priority;
 pub mod transaction;
 
 pub use stacks_common::types::chainstate::{StacksPrivateKey, StacksPublicKey};
@@ -795,6 +798,18 @@ pub struct StacksBlock {
     pub txs: Vec<StacksTransaction>,
 }
 
+/// Proof that a transaction was included in an anchored block, which can be checked against the
+/// block header's `tx_merkle_root` without the rest of the block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionInclusionProof {
+    /// The transaction's ID.  Its bytes are the data of the transaction's Merkle leaf.
+    pub txid: Txid,
+    /// The transaction's position in the block
+    pub index: u32,
+    /// Sibling hashes from the transaction's leaf up to the root
+    pub path: MerklePath<Sha512Trunc256Sum>,
+}
+
 /// A microblock that contains non-blockchain-anchored data,
 /// but is tied to an on-chain block
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -849,6 +864,8 @@ pub struct StacksBlockBuilder {
     /// Receipts of confirmed microblocks. These must be included
     /// when building the withdrawal merkle tree.
     microblock_tx_receipts: Vec<StacksTransactionReceipt>,
+    /// Leaves of the withdrawal merkle tree, set once the anchored block is mined.
+    withdrawal_leaves: Vec<Sha512Trunc256Sum>,
     anchored_done: bool,
     bytes_so_far: u64,
     prev_microblock_header: StacksMicroblockHeader,
@@ -858,6 +875,8 @@ pub struct StacksBlockBuilder {
     parent_header_hash: BlockHeaderHash,
     parent_microblock_hash: Option<BlockHeaderHash>,
     miner_id: usize,
+    /// Minimum miner tip, in uSTX, of each withdrawal in a mined transaction.
+    min_withdrawal_tip: u128,
 }
 
 // maximum amount of data a leader can send during its epoch (2MB)
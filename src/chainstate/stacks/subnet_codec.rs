@@ -0,0 +1,321 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Canonical encodings of the consensus structures that subnets add to Stacks blocks: the
+//! withdrawal Merkle root committed to in block headers, the miner signature lists of blocks and
+//! microblocks, and the leaves of withdrawal Merkle trees.
+//!
+//! Nodes that encode any of these differently compute different block hashes, signing digests,
+//! or withdrawal roots, and split from the network.  The tests in this module pin each encoding
+//! with golden vectors, so a change to one shows up as a failing vector rather than a fork.
+
+use clarity::vm::Value;
+use stacks_common::util::hash::{MerkleTree, Sha512Trunc256Sum};
+
+use crate::chainstate::stacks::{StacksBlockHeader, StacksMicroblockHeader};
+use crate::codec::StacksMessageCodec;
+
+/// The digest that each block miner signs.  This is the hash of the header with an empty
+/// signature list, so every miner signs the same digest no matter which signatures have
+/// already been added.
+pub fn block_header_signing_hash(header: &StacksBlockHeader) -> Sha512Trunc256Sum {
+    let mut bytes = vec![];
+    header
+        .serialize(&mut bytes, true)
+        .expect("BUG: failed to serialize to a vec");
+    Sha512Trunc256Sum::from_data(&bytes)
+}
+
+/// The digest that each microblock miner signs, computed like `block_header_signing_hash`.
+pub fn microblock_header_signing_hash(header: &StacksMicroblockHeader) -> Sha512Trunc256Sum {
+    let mut bytes = vec![];
+    header
+        .serialize(&mut bytes, true)
+        .expect("BUG: failed to serialize to a vec");
+    Sha512Trunc256Sum::from_data(&bytes)
+}
+
+/// The bytes of a withdrawal Merkle tree leaf: the Clarity consensus serialization of the
+/// withdrawal's key tuple.  The subnet contract on the L1 rebuilds these bytes from the
+/// withdrawal's fields when a withdrawal is claimed.
+pub fn withdrawal_leaf_bytes(key: &Value) -> Vec<u8> {
+    key.serialize_to_vec()
+}
+
+/// The hash of a withdrawal Merkle tree leaf
+pub fn withdrawal_leaf_hash(key: &Value) -> Sha512Trunc256Sum {
+    MerkleTree::<Sha512Trunc256Sum>::get_leaf_hash(&withdrawal_leaf_bytes(key))
+}
+
+/// The interface version of the L1 subnet contract whose deposit, withdrawal leaf and block
+/// commit encodings this node implements.  The subnet contract reports its version with
+/// `get-interface-version`; contracts that predate that function implement version 1.  A node
+/// following a contract with a different version would compute withdrawal roots that the
+/// contract cannot verify.
+pub const SUBNET_CONTRACT_INTERFACE_VERSION: u64 = 1;
+
+#[cfg(test)]
+mod tests {
+    use clarity::vm::types::{PrincipalData, StandardPrincipalData};
+    use proptest::prelude::*;
+    use stacks_common::util::hash::{hex_bytes, to_hex, Hash160};
+    use stacks_common::util::secp256k1::MessageSignature;
+    use stacks_common::util::vrf::VRFProof;
+
+    use super::*;
+    use crate::chainstate::stacks::db::blocks::MessageSignatureList;
+    use crate::chainstate::stacks::StacksPrivateKey;
+    use crate::clarity_vm::withdrawal::make_key_for_stx_withdrawal;
+    use crate::core::{EMPTY_MICROBLOCK_PARENT_HASH, FIRST_STACKS_BLOCK_HASH};
+    use crate::types::chainstate::{BlockHeaderHash, StacksWorkScore, TrieHash};
+    use crate::types::PrivateKey;
+
+    const TEST_PROOF_HEX: &str = "9275df67a68c8745c0ff97b48201ee6db447f7c93b23ae24cdc2400f52fdb08a1a6ac7ec71bf9c9c76e96ee4675ebff60625af28718501047bfd87b810c2d2139b73c23bd69de66360953a642c2a330a";
+
+    /// Offset of `withdrawal_merkle_root` in a serialized block header: it follows the version
+    /// (1 byte), work score (16), VRF proof (80), parent block and microblock hashes (32 each),
+    /// parent microblock sequence (2), transaction Merkle root (32) and state index root (32).
+    const WITHDRAWAL_ROOT_OFFSET: usize = 227;
+
+    fn test_proof() -> VRFProof {
+        VRFProof::from_bytes(&hex_bytes(TEST_PROOF_HEX).unwrap()).unwrap()
+    }
+
+    fn test_block_header(withdrawal_merkle_root: Sha512Trunc256Sum) -> StacksBlockHeader {
+        StacksBlockHeader {
+            version: 0x12,
+            total_work: StacksWorkScore {
+                burn: 123,
+                work: 456,
+            },
+            proof: test_proof(),
+            parent_block: FIRST_STACKS_BLOCK_HASH.clone(),
+            parent_microblock: BlockHeaderHash([1u8; 32]),
+            parent_microblock_sequence: 3,
+            tx_merkle_root: Sha512Trunc256Sum([2u8; 32]),
+            state_index_root: TrieHash([3u8; 32]),
+            withdrawal_merkle_root,
+            microblock_pubkey_hash: Hash160([4u8; 20]),
+            miner_signatures: MessageSignatureList::empty(),
+        }
+    }
+
+    /// A valid recoverable signature, made by signing a random message with a random key
+    fn arb_signature() -> impl Strategy<Value = MessageSignature> {
+        (any::<[u8; 32]>(), any::<[u8; 32]>()).prop_filter_map(
+            "seed is not a valid private key",
+            |(seed, message)| {
+                let privk = StacksPrivateKey::from_slice(&seed).ok()?;
+                privk.sign(&message).ok()
+            },
+        )
+    }
+
+    fn arb_signature_list() -> impl Strategy<Value = MessageSignatureList> {
+        prop::collection::vec(arb_signature(), 0..4).prop_map(MessageSignatureList::from_vec)
+    }
+
+    fn arb_block_header() -> impl Strategy<Value = StacksBlockHeader> {
+        (
+            any::<u8>(),
+            any::<(u64, u64)>(),
+            any::<([u8; 32], [u8; 32], u16)>(),
+            any::<([u8; 32], [u8; 32], [u8; 32])>(),
+            any::<[u8; 20]>(),
+            arb_signature_list(),
+        )
+            .prop_map(
+                |(
+                    version,
+                    (burn, work),
+                    (parent_block, parent_microblock, parent_microblock_sequence),
+                    (tx_merkle_root, state_index_root, withdrawal_merkle_root),
+                    microblock_pubkey_hash,
+                    miner_signatures,
+                )| StacksBlockHeader {
+                    version,
+                    total_work: StacksWorkScore { burn, work },
+                    proof: test_proof(),
+                    parent_block: BlockHeaderHash(parent_block),
+                    parent_microblock: BlockHeaderHash(parent_microblock),
+                    parent_microblock_sequence,
+                    tx_merkle_root: Sha512Trunc256Sum(tx_merkle_root),
+                    state_index_root: TrieHash(state_index_root),
+                    withdrawal_merkle_root: Sha512Trunc256Sum(withdrawal_merkle_root),
+                    microblock_pubkey_hash: Hash160(microblock_pubkey_hash),
+                    miner_signatures,
+                },
+            )
+    }
+
+    fn arb_microblock_header() -> impl Strategy<Value = StacksMicroblockHeader> {
+        (
+            any::<(u8, u16)>(),
+            any::<([u8; 32], [u8; 32])>(),
+            arb_signature_list(),
+        )
+            .prop_map(
+                |((version, sequence), (prev_block, tx_merkle_root), miner_signatures)| {
+                    StacksMicroblockHeader {
+                        version,
+                        sequence,
+                        prev_block: BlockHeaderHash(prev_block),
+                        tx_merkle_root: Sha512Trunc256Sum(tx_merkle_root),
+                        miner_signatures,
+                    }
+                },
+            )
+    }
+
+    fn arb_stx_withdrawal_key() -> impl Strategy<Value = Value> {
+        (
+            0u8..32,
+            any::<[u8; 20]>(),
+            any::<u32>(),
+            any::<u128>(),
+            any::<u64>(),
+        )
+            .prop_map(|(version, bytes, withdrawal_id, amount, block_height)| {
+                let recipient = PrincipalData::Standard(StandardPrincipalData(version, bytes));
+                make_key_for_stx_withdrawal(&recipient, withdrawal_id, amount, block_height)
+            })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn signature_list_round_trip(list in arb_signature_list()) {
+            let bytes = list.serialize_to_vec();
+            prop_assert_eq!(bytes.len(), 4 + 65 * list.signatures().len());
+            let decoded = MessageSignatureList::consensus_deserialize(&mut &bytes[..]).unwrap();
+            prop_assert_eq!(decoded, list);
+        }
+
+        #[test]
+        fn block_header_round_trip(header in arb_block_header()) {
+            let bytes = header.serialize_to_vec();
+            prop_assert_eq!(
+                &bytes[WITHDRAWAL_ROOT_OFFSET..WITHDRAWAL_ROOT_OFFSET + 32],
+                header.withdrawal_merkle_root.as_bytes()
+            );
+            let decoded = StacksBlockHeader::consensus_deserialize(&mut &bytes[..]).unwrap();
+            prop_assert_eq!(decoded.block_hash(), header.block_hash());
+            prop_assert_eq!(decoded, header);
+        }
+
+        #[test]
+        fn block_header_signing_hash_ignores_signatures(header in arb_block_header()) {
+            let mut unsigned = header.clone();
+            unsigned.miner_signatures = MessageSignatureList::empty();
+            prop_assert_eq!(
+                block_header_signing_hash(&header),
+                block_header_signing_hash(&unsigned)
+            );
+        }
+
+        #[test]
+        fn microblock_header_round_trip(header in arb_microblock_header()) {
+            let bytes = header.serialize_to_vec();
+            let decoded = StacksMicroblockHeader::consensus_deserialize(&mut &bytes[..]).unwrap();
+            prop_assert_eq!(decoded, header.clone());
+
+            let mut unsigned = header.clone();
+            unsigned.miner_signatures = MessageSignatureList::empty();
+            prop_assert_eq!(
+                microblock_header_signing_hash(&header),
+                microblock_header_signing_hash(&unsigned)
+            );
+        }
+
+        #[test]
+        fn withdrawal_leaf_round_trip(key in arb_stx_withdrawal_key()) {
+            let bytes = withdrawal_leaf_bytes(&key);
+            let decoded = Value::consensus_deserialize(&mut &bytes[..]).unwrap();
+            prop_assert_eq!(withdrawal_leaf_hash(&decoded), withdrawal_leaf_hash(&key));
+            prop_assert_eq!(decoded, key);
+        }
+    }
+
+    #[test]
+    fn signature_list_rejects_unrecoverable_signatures() {
+        // recovery IDs are 0 through 3
+        let list = MessageSignatureList::from_single(MessageSignature([4u8; 65]));
+        let bytes = list.serialize_to_vec();
+        assert!(MessageSignatureList::consensus_deserialize(&mut &bytes[..]).is_err());
+    }
+
+    #[test]
+    fn block_header_signing_hash_golden() {
+        let header = test_block_header(Sha512Trunc256Sum([4u8; 32]));
+        assert_eq!(
+            to_hex(block_header_signing_hash(&header).as_bytes()),
+            "93c4fd62d7c7fe4fa5ad1335fb5ea1a73d6f8cc8bd37419f7ada7332709d9365"
+        );
+    }
+
+    #[test]
+    fn microblock_header_signing_hash_golden() {
+        let header = StacksMicroblockHeader {
+            version: 0x12,
+            sequence: 0x34,
+            prev_block: EMPTY_MICROBLOCK_PARENT_HASH.clone(),
+            tx_merkle_root: Sha512Trunc256Sum([1u8; 32]),
+            miner_signatures: MessageSignatureList::from_single(MessageSignature([2u8; 65])),
+        };
+        assert_eq!(
+            to_hex(microblock_header_signing_hash(&header).as_bytes()),
+            "b744f5f7798a3ca3b9bc792a84354d2b91edae89fd63dec7fbb0096de23fcda5"
+        );
+    }
+
+    #[test]
+    fn withdrawal_leaf_golden() {
+        let recipient = PrincipalData::Standard(StandardPrincipalData(26, [1u8; 20]));
+        let key = make_key_for_stx_withdrawal(&recipient, 0, 100, 5);
+
+        assert_eq!(
+            to_hex(&withdrawal_leaf_bytes(&key)),
+            "0c00000005\
+             06616d6f756e740100000000000000000000000000000064\
+             066865696768740100000000000000000000000000000005\
+             09726563697069656e74051a0101010101010101010101010101010101010101\
+             04747970650d00000003737478\
+             0d7769746864726177616c2d69640100000000000000000000000000000000"
+        );
+        assert_eq!(
+            to_hex(withdrawal_leaf_hash(&key).as_bytes()),
+            "4dbeed1704ff67150b8dbbe915fee0edd5f875e2a55efa256bc2c14e667f49b9"
+        );
+
+        // a block with a single withdrawal duplicates its leaf to make the tree even
+        let tree = MerkleTree::<Sha512Trunc256Sum>::new(&vec![withdrawal_leaf_bytes(&key)]);
+        assert_eq!(
+            to_hex(tree.root().as_bytes()),
+            "7af5b1a404596697f294990065dc180921e061d779d3fd3df68cdf9ed28d3f41"
+        );
+    }
+
+    #[test]
+    fn subnet_contract_interface_version() {
+        // the subnet contract must be changed together with the encodings it relies on
+        let contract = include_str!("../../../core-contracts/contracts/subnet.clar");
+        assert!(contract.contains(&format!(
+            "(define-constant INTERFACE_VERSION u{})",
+            SUBNET_CONTRACT_INTERFACE_VERSION
+        )));
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The "system priority" transaction class: contract calls into a subnet's designated governance
+//! and emergency contracts, sent by one of its authorized principals.
+//!
+//! Miners select system priority transactions ahead of the fee-ordered mempool walk, and a
+//! reserved slice of each block's execution budget can only be used by them, so incident-response
+//! transactions can land even when the subnet is congested.  Block validation rejects blocks that
+//! spend the reserved slice on other transactions, so the policy decides which blocks are valid.
+//!
+//! Deposits observed on the L1 do not need this class.  They are not transactions: every block
+//! materializes the pending deposit operations while it is set up, before its first transaction.
+//! Each deposit runs under its own copy of the block's budget and is not charged to the block (see
+//! `StacksChainState::with_deposit_cost_tracker()`), so neither user traffic nor a burst of other
+//! deposits can crowd it out.
+
+use clarity::vm::costs::ExecutionCost;
+use clarity::vm::types::QualifiedContractIdentifier;
+
+use crate::chainstate::stacks::{StacksTransaction, TransactionPayload};
+use crate::types::chainstate::StacksAddress;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemPriorityPolicy {
+    /// Contracts whose calls can be system priority transactions
+    pub contracts: Vec<QualifiedContractIdentifier>,
+    /// Origin accounts whose calls to `contracts` are system priority transactions
+    pub authorized_principals: Vec<StacksAddress>,
+    /// Percentage of each block's execution budget, in every dimension, that only system
+    /// priority transactions may use.  At most 100.
+    pub reserved_budget_percent: u64,
+}
+
+impl SystemPriorityPolicy {
+    /// Is `tx` a system priority transaction?  It must be a contract call into one of the
+    /// designated contracts, and its origin must be an authorized principal.  The sponsor of a
+    /// sponsored transaction does not matter.
+    pub fn is_system_priority(&self, tx: &StacksTransaction) -> bool {
+        match tx.payload {
+            TransactionPayload::ContractCall(ref cc) => {
+                self.authorized_principals.contains(&tx.origin_address())
+                    && self.contracts.contains(&cc.to_clarity_contract_id())
+            }
+            _ => false,
+        }
+    }
+
+    /// Must the block's cost be within the normal budget after `tx`?  This holds for every
+    /// transaction except system priority transactions and the coinbase, which every block has.
+    pub fn is_held_to_normal_budget(&self, tx: &StacksTransaction) -> bool {
+        match tx.payload {
+            TransactionPayload::Coinbase(..) => false,
+            _ => !self.is_system_priority(tx),
+        }
+    }
+
+    /// The part of `block_limit` that transactions other than system priority transactions may
+    /// use.  A block is invalid if its cost exceeds this budget after any transaction that is
+    /// held to it.
+    pub fn normal_budget(&self, block_limit: &ExecutionCost) -> ExecutionCost {
+        let unreserved_percent = 100u128.saturating_sub(self.reserved_budget_percent as u128);
+        let scale = |limit: u64| ((limit as u128) * unreserved_percent / 100) as u64;
+        ExecutionCost {
+            write_length: scale(block_limit.write_length),
+            write_count: scale(block_limit.write_count),
+            read_length: scale(block_limit.read_length),
+            read_count: scale(block_limit.read_count),
+            runtime: scale(block_limit.runtime),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clarity::vm::types::PrincipalData;
+    use clarity::vm::Value;
+    use stacks_common::address::AddressHashMode;
+
+    use super::*;
+    use crate::chainstate::stacks::*;
+
+    fn make_tx(sender: &StacksPrivateKey, payload: TransactionPayload) -> StacksTransaction {
+        let mut spending_condition = TransactionSpendingCondition::new_singlesig_p2pkh(
+            StacksPublicKey::from_private(sender),
+        )
+        .unwrap();
+        spending_condition.set_tx_fee(1);
+        let auth = TransactionAuth::Standard(spending_condition);
+        let mut tx = StacksTransaction::new(TransactionVersion::Testnet, auth, payload);
+        tx.chain_id = 0x80000000;
+        let mut signer = StacksTransactionSigner::new(&tx);
+        signer.sign_origin(sender).unwrap();
+        signer.get_tx().unwrap()
+    }
+
+    fn address_of(sender: &StacksPrivateKey) -> StacksAddress {
+        StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(sender)],
+        )
+        .unwrap()
+    }
+
+    fn contract_call(contract: &QualifiedContractIdentifier) -> TransactionPayload {
+        TransactionPayload::new_contract_call(
+            StacksAddress::from(contract.issuer.clone()),
+            contract.name.as_str(),
+            "halt",
+            vec![Value::UInt(1)],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn system_priority_requires_designated_contract_and_authorized_origin() {
+        let authorized = StacksPrivateKey::new();
+        let unauthorized = StacksPrivateKey::new();
+        let governance =
+            QualifiedContractIdentifier::parse("ST000000000000000000002AMW42H.governance").unwrap();
+        let other =
+            QualifiedContractIdentifier::parse("ST000000000000000000002AMW42H.other").unwrap();
+
+        let policy = SystemPriorityPolicy {
+            contracts: vec![governance.clone()],
+            authorized_principals: vec![address_of(&authorized)],
+            reserved_budget_percent: 10,
+        };
+
+        assert!(policy.is_system_priority(&make_tx(&authorized, contract_call(&governance))));
+        assert!(!policy.is_system_priority(&make_tx(&unauthorized, contract_call(&governance))));
+        assert!(!policy.is_system_priority(&make_tx(&authorized, contract_call(&other))));
+
+        let transfer = TransactionPayload::TokenTransfer(
+            PrincipalData::from(address_of(&unauthorized)),
+            1,
+            TokenTransferMemo([0; 34]),
+        );
+        assert!(!policy.is_system_priority(&make_tx(&authorized, transfer.clone())));
+
+        assert!(!policy.is_held_to_normal_budget(&make_tx(&authorized, contract_call(&governance))));
+        assert!(policy.is_held_to_normal_budget(&make_tx(&authorized, contract_call(&other))));
+        assert!(policy.is_held_to_normal_budget(&make_tx(&authorized, transfer)));
+        let coinbase = TransactionPayload::Coinbase(CoinbasePayload([0; 32]));
+        assert!(!policy.is_held_to_normal_budget(&make_tx(&authorized, coinbase)));
+    }
+
+    #[test]
+    fn normal_budget_excludes_reserved_slice() {
+        let block_limit = ExecutionCost {
+            write_length: 1000,
+            write_count: 200,
+            read_length: 3000,
+            read_count: 400,
+            runtime: u64::MAX,
+        };
+        let mut policy = SystemPriorityPolicy {
+            contracts: vec![],
+            authorized_principals: vec![],
+            reserved_budget_percent: 10,
+        };
+
+        assert_eq!(
+            policy.normal_budget(&block_limit),
+            ExecutionCost {
+                write_length: 900,
+                write_count: 180,
+                read_length: 2700,
+                read_count: 360,
+                runtime: ((u64::MAX as u128) * 9 / 10) as u64,
+            }
+        );
+
+        policy.reserved_budget_percent = 0;
+        assert_eq!(policy.normal_budget(&block_limit), block_limit);
+
+        policy.reserved_budget_percent = 100;
+        assert_eq!(policy.normal_budget(&block_limit), ExecutionCost::zero());
+    }
+}
@@ -33,7 +33,7 @@ use crate::chainstate::stacks::index::{storage::TrieFileStorage, MarfTrieId};
 use crate::util_lib::db::sqlite_open;
 use crate::util_lib::db::FromColumn;
 use stacks_common::address::c32::c32_address;
-use stacks_common::util::hash::{bytes_to_hex, Sha512Trunc256Sum};
+use stacks_common::util::hash::{bytes_to_hex, MerkleTree, Sha512Trunc256Sum};
 
 use crate::clarity::{
     vm::analysis,
@@ -51,6 +51,7 @@ use crate::clarity::{
     vm::errors::{Error, InterpreterResult, RuntimeErrorType},
     vm::eval_all,
     vm::types::{OptionalData, PrincipalData, QualifiedContractIdentifier},
+    vm::ClarityVersion,
     vm::ContractContext,
     vm::ContractName,
     vm::{SymbolicExpression, SymbolicExpressionType, Value},
@@ -205,6 +206,8 @@ fn run_analysis_free<C: ClarityStorage>(
         expressions,
         &mut marf_kv.get_analysis_db(),
         save_contract,
+        false,
+        ClarityVersion::default_for_epoch(DEFAULT_CLI_EPOCH),
         LimitedCostTracker::new_free(),
     )
 }
@@ -233,6 +236,8 @@ fn run_analysis<C: ClarityStorage>(
         expressions,
         &mut marf_kv.get_analysis_db(),
         save_contract,
+        false,
+        ClarityVersion::default_for_epoch(DEFAULT_CLI_EPOCH),
         cost_track,
     )
 }
@@ -412,7 +417,10 @@ where
 ///  for program evaluation, not by consensus critical code.
 pub fn vm_execute(program: &str) -> Result<Option<Value>, Error> {
     let contract_id = QualifiedContractIdentifier::transient();
-    let mut contract_context = ContractContext::new(contract_id.clone());
+    let mut contract_context = ContractContext::new(
+        contract_id.clone(),
+        ClarityVersion::default_for_epoch(DEFAULT_CLI_EPOCH),
+    );
     let mut marf = MemoryBackingStore::new();
     let conn = marf.as_clarity_db();
     let mut global_context = GlobalContext::new(
@@ -629,6 +637,18 @@ impl HeadersDB for CLIHeadersDB {
     fn get_miner_address(&self, _id_bhh: &StacksBlockId) -> Option<StacksAddress> {
         None
     }
+    fn get_withdrawal_tree_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+    ) -> Option<MerkleTree<Sha512Trunc256Sum>> {
+        // blocks evaluated by the CLI never contain withdrawals
+        let conn = self.conn();
+        if let Some(_) = get_cli_block_height(&conn, id_bhh) {
+            Some(MerkleTree::empty())
+        } else {
+            None
+        }
+    }
 }
 
 fn get_eval_input(invoked_by: &str, args: &[String]) -> EvalInput {
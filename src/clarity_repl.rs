@@ -0,0 +1,613 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! An interactive Clarity REPL for subnet contracts.  Contracts are deployed into an in-memory
+//! store backed by the fixed chain data of the docs `HeadersDB`, L1 deposits can be injected the
+//! way a subnet block materializes them, and the subnet's withdrawal natives can be evaluated
+//! in the context of a deployed contract.
+
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::io::Write;
+
+use clarity::vm::analysis;
+use clarity::vm::ast;
+use clarity::vm::contexts::{Environment, LocalContext, OwnedEnvironment};
+use clarity::vm::costs::LimitedCostTracker;
+use clarity::vm::database::ClarityDatabase;
+use clarity::vm::docs::db::{DOC_HEADER_DB, DOC_POX_STATE_DB};
+use clarity::vm::errors::{Error, RuntimeErrorType};
+use clarity::vm::events::{self, FTEventType, NFTEventType, STXEventType, StacksTransactionEvent};
+use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, StandardPrincipalData};
+use clarity::vm::{eval, ClarityVersion, ContractName, SymbolicExpression, Value};
+use stacks_common::types::StacksEpochId;
+use stacks_common::util::hash::{MerkleTree, Sha512Trunc256Sum};
+
+use crate::clarity_vm::database::MemoryBackingStore;
+use crate::clarity_vm::withdrawal::convert_withdrawal_key_to_bytes;
+
+/// The epoch the REPL runs in: the subnet's own, so contracts are Clarity 2 and can use the
+/// subnet natives
+const REPL_EPOCH: StacksEpochId = StacksEpochId::EpochSubnet1;
+
+/// The `tx-sender` of a new session
+pub const DEFAULT_SENDER: &str = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM";
+
+const HELP: &str = "Enter a Clarity expression to evaluate it, or one of:
+  ::deploy <name> <file>                            deploy a contract as the current sender
+  ::use [<contract>]                                evaluate in a contract's context, or none
+  ::sender [<principal>]                            show or set the tx-sender
+  ::deposit-stx <amount> <recipient>                inject an L1 STX deposit
+  ::deposit-ft <contract> <function> <amount> <recipient>
+                                                    inject an L1 fungible token deposit
+  ::deposit-nft <contract> <function> <id> <recipient>
+                                                    inject an L1 NFT deposit
+  ::withdrawals                                     list withdrawals and their Merkle root
+  ::help                                            show this message
+Contracts may be written as .name, for a contract deployed by the current sender.";
+
+/// A withdrawal made during a session, with its key in the session's withdrawal tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionWithdrawal {
+    pub withdrawal_id: u32,
+    pub event: StacksTransactionEvent,
+    pub key: Value,
+}
+
+pub struct ReplSession {
+    store: MemoryBackingStore,
+    sender: StandardPrincipalData,
+    /// the contract whose context expressions are evaluated in, if any
+    context_contract: Option<QualifiedContractIdentifier>,
+    /// withdrawal events, in the order they were emitted
+    withdrawal_events: Vec<StacksTransactionEvent>,
+}
+
+/// Evaluate `program` in the context of the deployed contract `contract_identifier`, so that
+/// it can refer to the contract's tokens, maps and private functions.  Unlike
+/// `Environment::eval_read_only`, writes are kept.
+fn eval_in_contract(
+    exec_env: &mut Environment,
+    contract_identifier: &QualifiedContractIdentifier,
+    program: &str,
+) -> Result<Value, Error> {
+    let parsed = ast::build_ast(contract_identifier, program, exec_env)?.expressions;
+    if parsed.len() < 1 {
+        return Err(RuntimeErrorType::ParseError(
+            "Expected a program of at least length 1".to_string(),
+        )
+        .into());
+    }
+
+    let contract = exec_env
+        .global_context
+        .database
+        .get_contract(contract_identifier)?;
+    let sender = exec_env.sender.clone();
+    let caller = exec_env.caller.clone();
+    let mut nested_env = Environment::new(
+        exec_env.global_context,
+        &contract.contract_context,
+        exec_env.call_stack,
+        sender,
+        caller,
+    );
+    let local_context = LocalContext::new();
+    let mut result = Value::none();
+    for expression in parsed.iter() {
+        result = eval(expression, &mut nested_env, &local_context)?;
+    }
+    Ok(result)
+}
+
+fn parse_amount(literal: &str) -> Result<u128, String> {
+    literal
+        .trim_start_matches('u')
+        .parse::<u128>()
+        .map_err(|_| format!("Invalid amount: {}", literal))
+}
+
+fn is_withdrawal_event(event: &StacksTransactionEvent) -> bool {
+    match event {
+        StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawEvent(_))
+        | StacksTransactionEvent::FTEvent(FTEventType::FTWithdrawEvent(_))
+        | StacksTransactionEvent::NFTEvent(NFTEventType::NFTWithdrawEvent(_)) => true,
+        _ => false,
+    }
+}
+
+impl ReplSession {
+    pub fn new() -> ReplSession {
+        ReplSession {
+            store: MemoryBackingStore::new(),
+            sender: PrincipalData::parse_standard_principal(DEFAULT_SENDER)
+                .expect("FATAL: invalid default sender"),
+            context_contract: None,
+            withdrawal_events: vec![],
+        }
+    }
+
+    fn clarity_db<'a>(&'a mut self) -> ClarityDatabase<'a> {
+        ClarityDatabase::new(&mut self.store, &DOC_HEADER_DB, &DOC_POX_STATE_DB)
+    }
+
+    /// Run `f` as a transaction sent by `sender`, keeping its writes if it succeeds, and record
+    /// the withdrawals it makes.
+    fn execute<F, A>(&mut self, sender: PrincipalData, f: F) -> Result<A, Error>
+    where
+        F: FnOnce(&mut Environment) -> Result<A, Error>,
+    {
+        let (result, events) = {
+            let mut owned_env = OwnedEnvironment::new_free(false, self.clarity_db(), REPL_EPOCH);
+            let (result, _, events) = owned_env.execute_in_env(sender, f)?;
+            (result, events)
+        };
+        self.withdrawal_events
+            .extend(events.into_iter().filter(is_withdrawal_event));
+        Ok(result)
+    }
+
+    /// Resolve a contract written as `.name` against the current sender
+    fn parse_contract(&self, literal: &str) -> Result<QualifiedContractIdentifier, String> {
+        let parsed = match literal.strip_prefix('.') {
+            Some(name) => ContractName::try_from(name.to_string())
+                .map(|name| QualifiedContractIdentifier::new(self.sender.clone(), name))
+                .map_err(Error::from),
+            None => QualifiedContractIdentifier::parse(literal),
+        };
+        parsed.map_err(|e| format!("Invalid contract {}: {}", literal, e))
+    }
+
+    pub fn sender(&self) -> &StandardPrincipalData {
+        &self.sender
+    }
+
+    pub fn set_sender(&mut self, sender: &str) -> Result<(), String> {
+        self.sender = PrincipalData::parse_standard_principal(sender)
+            .map_err(|e| format!("Invalid sender {}: {}", sender, e))?;
+        Ok(())
+    }
+
+    /// Evaluate expressions in the given contract's context, or in no contract's if `None`
+    pub fn set_context_contract(&mut self, contract: Option<QualifiedContractIdentifier>) {
+        self.context_contract = contract;
+    }
+
+    /// Type-check and deploy a contract named `name`, sent by the current sender
+    pub fn deploy(
+        &mut self,
+        name: &str,
+        source: &str,
+    ) -> Result<QualifiedContractIdentifier, String> {
+        let contract_name = ContractName::try_from(name.to_string())
+            .map_err(|e| format!("Invalid contract name {}: {}", name, e))?;
+        let contract_identifier =
+            QualifiedContractIdentifier::new(self.sender.clone(), contract_name);
+
+        let mut contract_ast = ast::build_ast(&contract_identifier, source, &mut ())
+            .map_err(|e| format!("Parse error:\n{}", e))?;
+        analysis::run_analysis(
+            &contract_identifier,
+            &mut contract_ast.expressions,
+            &mut self.store.as_analysis_db(),
+            true,
+            false,
+            ClarityVersion::default_for_epoch(REPL_EPOCH),
+            LimitedCostTracker::new_free(),
+        )
+        .map_err(|(e, _)| format!("Type check error:\n{}", e))?;
+
+        let deployed = contract_identifier.clone();
+        self.execute(self.sender.clone().into(), |exec_env| {
+            exec_env.initialize_contract_from_ast(deployed, &contract_ast, source)
+        })
+        .map_err(|e| format!("Execution error:\n{}", e))?;
+        Ok(contract_identifier)
+    }
+
+    /// Evaluate `program` as sent by the current sender.  Expressions are not type-checked
+    /// before they run.
+    pub fn eval(&mut self, program: &str) -> Result<Value, String> {
+        let context_contract = self.context_contract.clone();
+        self.execute(
+            self.sender.clone().into(),
+            |exec_env| match context_contract {
+                Some(ref contract_identifier) => {
+                    eval_in_contract(exec_env, contract_identifier, program)
+                }
+                None => exec_env.eval_raw(program),
+            },
+        )
+        .map_err(|e| format!("Execution error:\n{}", e))
+    }
+
+    /// Credit `recipient` with `amount` uSTX, as a subnet block materializing an L1 STX deposit
+    /// would.
+    pub fn deposit_stx(&mut self, recipient: &PrincipalData, amount: u128) -> Result<(), String> {
+        self.execute(recipient.clone(), |exec_env| {
+            let mut snapshot = exec_env
+                .global_context
+                .database
+                .get_stx_balance_snapshot(recipient);
+            snapshot.credit(amount);
+            snapshot.save();
+            exec_env
+                .global_context
+                .database
+                .increment_ustx_liquid_supply(amount)
+        })
+        .map_err(|e| format!("Execution error:\n{}", e))
+    }
+
+    /// Call `function` on `contract` with `(amount-or-id recipient)`, sent by `recipient`, as a
+    /// subnet block materializing an L1 FT or NFT deposit would.
+    pub fn deposit_asset(
+        &mut self,
+        contract: &QualifiedContractIdentifier,
+        function: &str,
+        amount_or_id: u128,
+        recipient: &PrincipalData,
+    ) -> Result<Value, String> {
+        let args = [
+            SymbolicExpression::atom_value(Value::UInt(amount_or_id)),
+            SymbolicExpression::atom_value(Value::Principal(recipient.clone())),
+        ];
+        self.execute(recipient.clone(), |exec_env| {
+            exec_env.execute_contract(contract, function, &args, false)
+        })
+        .map_err(|e| format!("Execution error:\n{}", e))
+    }
+
+    /// The withdrawals made so far, with the IDs and keys they would have if the whole session
+    /// were a single subnet block.
+    pub fn withdrawals(&mut self) -> Vec<SessionWithdrawal> {
+        let block_height = self.clarity_db().get_current_block_height() as u64;
+        self.withdrawal_events
+            .iter()
+            .enumerate()
+            .filter_map(|(index, event)| {
+                let withdrawal_id = index as u32;
+                let mut event = event.clone();
+                let key = match event {
+                    StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawEvent(
+                        ref mut data,
+                    )) => {
+                        data.withdrawal_id = Some(withdrawal_id);
+                        events::make_key_for_stx_withdrawal(
+                            &data.sender,
+                            withdrawal_id,
+                            data.amount,
+                            block_height,
+                        )
+                    }
+                    StacksTransactionEvent::FTEvent(FTEventType::FTWithdrawEvent(ref mut data)) => {
+                        data.withdrawal_id = Some(withdrawal_id);
+                        events::make_key_for_ft_withdrawal(
+                            &data.sender,
+                            withdrawal_id,
+                            &data.asset_identifier.contract_identifier,
+                            data.amount,
+                            block_height,
+                        )
+                    }
+                    StacksTransactionEvent::NFTEvent(NFTEventType::NFTWithdrawEvent(
+                        ref mut data,
+                    )) => {
+                        data.withdrawal_id = Some(withdrawal_id);
+                        events::make_key_for_nft_withdrawal(
+                            &data.sender,
+                            withdrawal_id,
+                            &data.asset_identifier.contract_identifier,
+                            data.id,
+                            block_height,
+                        )
+                    }
+                    _ => return None,
+                };
+                Some(SessionWithdrawal {
+                    withdrawal_id,
+                    event,
+                    key,
+                })
+            })
+            .collect()
+    }
+
+    fn format_withdrawals(&mut self) -> String {
+        let withdrawals = self.withdrawals();
+        if withdrawals.is_empty() {
+            return "No withdrawals".to_string();
+        }
+        let keys: Vec<_> = withdrawals
+            .iter()
+            .map(|withdrawal| convert_withdrawal_key_to_bytes(&withdrawal.key))
+            .collect();
+        let mut lines: Vec<_> = withdrawals
+            .iter()
+            .zip(keys.iter())
+            .map(|(withdrawal, key)| {
+                let description = match withdrawal.event {
+                    StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawEvent(ref data)) => {
+                        format!("stx {} uSTX by {}", data.amount, data.sender)
+                    }
+                    StacksTransactionEvent::FTEvent(FTEventType::FTWithdrawEvent(ref data)) => {
+                        format!(
+                            "ft {} {} by {}",
+                            data.amount, data.asset_identifier, data.sender
+                        )
+                    }
+                    StacksTransactionEvent::NFTEvent(NFTEventType::NFTWithdrawEvent(ref data)) => {
+                        format!(
+                            "nft u{} {} by {}",
+                            data.id, data.asset_identifier, data.sender
+                        )
+                    }
+                    _ => unreachable!("BUG: recorded a non-withdrawal event"),
+                };
+                format!(
+                    "{}: {}\n   leaf hash 0x{}",
+                    withdrawal.withdrawal_id,
+                    description,
+                    MerkleTree::<Sha512Trunc256Sum>::get_leaf_hash(key)
+                )
+            })
+            .collect();
+        let tree = MerkleTree::<Sha512Trunc256Sum>::new(&keys);
+        lines.push(format!("withdrawal root 0x{}", tree.root()));
+        lines.join("\n")
+    }
+
+    fn handle_command(&mut self, command: &str, args: &[&str]) -> Result<String, String> {
+        match (command, args) {
+            ("::help", []) => Ok(HELP.to_string()),
+            ("::deploy", [name, path]) => {
+                let source = fs::read_to_string(path)
+                    .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+                let contract_identifier = self.deploy(name, &source)?;
+                Ok(format!("Deployed {}", contract_identifier))
+            }
+            ("::use", []) => {
+                self.set_context_contract(None);
+                Ok("Evaluating outside of any contract".to_string())
+            }
+            ("::use", [contract]) => {
+                let contract_identifier = self.parse_contract(contract)?;
+                self.set_context_contract(Some(contract_identifier.clone()));
+                Ok(format!(
+                    "Evaluating in the context of {}",
+                    contract_identifier
+                ))
+            }
+            ("::sender", []) => Ok(self.sender.to_string()),
+            ("::sender", [sender]) => {
+                self.set_sender(sender)?;
+                Ok(format!("tx-sender is {}", self.sender))
+            }
+            ("::deposit-stx", [amount, recipient]) => {
+                let recipient = PrincipalData::parse(recipient)
+                    .map_err(|e| format!("Invalid recipient {}: {}", recipient, e))?;
+                self.deposit_stx(&recipient, parse_amount(amount)?)?;
+                Ok("(ok true)".to_string())
+            }
+            ("::deposit-ft", [contract, function, amount, recipient])
+            | ("::deposit-nft", [contract, function, amount, recipient]) => {
+                let contract_identifier = self.parse_contract(contract)?;
+                let recipient = PrincipalData::parse(recipient)
+                    .map_err(|e| format!("Invalid recipient {}: {}", recipient, e))?;
+                let result = self.deposit_asset(
+                    &contract_identifier,
+                    function,
+                    parse_amount(amount)?,
+                    &recipient,
+                )?;
+                Ok(result.to_string())
+            }
+            ("::withdrawals", []) => Ok(self.format_withdrawals()),
+            _ => Err(format!(
+                "Unknown command or wrong arguments: {}\n{}",
+                command, HELP
+            )),
+        }
+    }
+
+    /// Handle one line of input, returning the text to show for it
+    pub fn handle_line(&mut self, line: &str) -> Option<String> {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        let result = if line.starts_with("::") {
+            let mut words = line.split_whitespace();
+            let command = words.next().expect("BUG: non-empty line has no words");
+            let args: Vec<_> = words.collect();
+            self.handle_command(command, &args)
+        } else {
+            self.eval(line).map(|value| value.to_string())
+        };
+        Some(match result {
+            Ok(output) => output,
+            Err(error) => error,
+        })
+    }
+}
+
+/// Run the REPL on stdin and stdout until end of input, returning the exit code
+pub fn run_repl(invoked_by: &str, args: &[String]) -> i32 {
+    if !args.is_empty() {
+        eprintln!("Usage: {}\n\n{}", invoked_by, HELP);
+        return 1;
+    }
+
+    let mut session = ReplSession::new();
+    let mut stdout = io::stdout();
+    println!(
+        "subnet Clarity REPL; tx-sender is {}. Type ::help for commands.",
+        session.sender()
+    );
+    loop {
+        stdout.write(b"> ").unwrap_or_else(|e| {
+            panic!("Failed to write stdout prompt string:\n{}", e);
+        });
+        stdout.flush().unwrap_or_else(|e| {
+            panic!("Failed to flush stdout prompt string:\n{}", e);
+        });
+
+        let mut buffer = String::new();
+        match io::stdin().read_line(&mut buffer) {
+            Ok(0) => return 0,
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("Error reading from stdin:\n{}", error);
+                return 1;
+            }
+        }
+
+        if let Some(output) = session.handle_line(&buffer) {
+            println!("{}", output);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TOKEN_CONTRACT: &str = "
+        (define-fungible-token test-token)
+        (define-non-fungible-token test-nft uint)
+        (define-public (deposit-ft (amount uint) (recipient principal))
+          (ft-mint? test-token amount recipient))
+        (define-public (deposit-nft (id uint) (recipient principal))
+          (nft-mint? test-nft id recipient))";
+
+    #[test]
+    fn test_withdraw_injected_deposits() {
+        let mut session = ReplSession::new();
+        let sender = PrincipalData::from(session.sender().clone());
+        let contract_identifier = session.deploy("token", TOKEN_CONTRACT).unwrap();
+
+        // deposits are materialized by calling the subnet contract
+        session.deposit_stx(&sender, 1000).unwrap();
+        assert_eq!(
+            session
+                .deposit_asset(&contract_identifier, "deposit-ft", 10, &sender)
+                .unwrap(),
+            Value::okay_true()
+        );
+        assert_eq!(
+            session.handle_line(
+                "::deposit-nft .token deposit-nft u7 ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM"
+            ),
+            Some("(ok true)".to_string())
+        );
+        assert_eq!(
+            session.eval("(stx-get-balance tx-sender)").unwrap(),
+            Value::UInt(1000)
+        );
+
+        // token natives resolve against the contract whose context is in use
+        assert!(session
+            .eval("(ft-withdraw? test-token u4 tx-sender)")
+            .is_err());
+        session.set_context_contract(Some(contract_identifier.clone()));
+        assert_eq!(
+            session
+                .eval("(ft-get-balance test-token tx-sender)")
+                .unwrap(),
+            Value::UInt(10)
+        );
+        assert_eq!(
+            session
+                .eval("(ft-withdraw? test-token u4 tx-sender)")
+                .unwrap(),
+            Value::okay_true()
+        );
+        assert_eq!(
+            session
+                .eval("(nft-withdraw? test-nft u7 tx-sender)")
+                .unwrap(),
+            Value::okay_true()
+        );
+        assert_eq!(
+            session.eval("(stx-withdraw? u100 tx-sender)").unwrap(),
+            Value::okay_true()
+        );
+        assert_eq!(
+            session
+                .eval("(ft-get-balance test-token tx-sender)")
+                .unwrap(),
+            Value::UInt(6)
+        );
+
+        let withdrawals = session.withdrawals();
+        assert_eq!(withdrawals.len(), 3);
+        for (i, withdrawal) in withdrawals.iter().enumerate() {
+            assert_eq!(withdrawal.withdrawal_id, i as u32);
+        }
+        match withdrawals[0].event {
+            StacksTransactionEvent::FTEvent(FTEventType::FTWithdrawEvent(ref data)) => {
+                assert_eq!(data.amount, 4);
+                assert_eq!(data.withdrawal_id, Some(0));
+            }
+            ref event => panic!("Expected an FT withdrawal, got {:?}", event),
+        }
+
+        let keys: Vec<_> = withdrawals
+            .iter()
+            .map(|withdrawal| convert_withdrawal_key_to_bytes(&withdrawal.key))
+            .collect();
+        let root = MerkleTree::<Sha512Trunc256Sum>::new(&keys).root();
+        let listing = session.handle_line("::withdrawals").unwrap();
+        assert!(listing.ends_with(&format!("withdrawal root 0x{}", root)));
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut session = ReplSession::new();
+        assert_eq!(session.handle_line("   "), None);
+        assert_eq!(session.handle_line("(+ 1 2)"), Some("3".to_string()));
+        // the subnet's Clarity 2 natives are available
+        assert_eq!(
+            session.handle_line("(slice \"abcd\" u1 u3)"),
+            Some("(some \"bc\")".to_string())
+        );
+        assert_eq!(
+            session.handle_line("::withdrawals"),
+            Some("No withdrawals".to_string())
+        );
+        assert_eq!(
+            session.handle_line("::sender ST2CY5V39NHDPWSXMW9QDT3HC3GD6Q6XX4CFRK9AG"),
+            Some("tx-sender is ST2CY5V39NHDPWSXMW9QDT3HC3GD6Q6XX4CFRK9AG".to_string())
+        );
+        assert_eq!(
+            session.handle_line("tx-sender"),
+            Some("ST2CY5V39NHDPWSXMW9QDT3HC3GD6Q6XX4CFRK9AG".to_string())
+        );
+        assert!(session
+            .handle_line("::deploy")
+            .unwrap()
+            .starts_with("Unknown command or wrong arguments"));
+        assert!(session
+            .handle_line("::use .missing")
+            .unwrap()
+            .starts_with("Evaluating in the context of"));
+        assert!(session
+            .handle_line("(+ 1 2)")
+            .unwrap()
+            .starts_with("Execution error"));
+    }
+}
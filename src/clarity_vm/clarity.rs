@@ -21,8 +21,9 @@ use std::thread;
 
 use crate::chainstate::stacks::boot::BOOT_CODE_COSTS_2_TESTNET;
 use crate::chainstate::stacks::boot::{
-    BOOT_CODE_COSTS, BOOT_CODE_COSTS_2, BOOT_CODE_COST_VOTING_TESTNET as BOOT_CODE_COST_VOTING,
-    BOOT_CODE_POX_TESTNET, COSTS_2_NAME,
+    BOOT_CODE_COSTS, BOOT_CODE_COSTS_2, BOOT_CODE_COSTS_3, BOOT_CODE_COSTS_3_TESTNET,
+    BOOT_CODE_COSTS_SUBNET, BOOT_CODE_COST_VOTING_TESTNET as BOOT_CODE_COST_VOTING,
+    BOOT_CODE_POX_TESTNET, COSTS_2_NAME, COSTS_3_NAME, COSTS_SUBNET_NAME,
 };
 use crate::chainstate::stacks::db::StacksAccount;
 use crate::chainstate::stacks::db::StacksChainState;
@@ -51,7 +52,7 @@ use crate::util_lib::boot::{boot_code_acc, boot_code_addr, boot_code_id, boot_co
 use crate::util_lib::strings::StacksString;
 use crate::{
     burnchains::Burnchain,
-    clarity_vm::database::marf::{MarfedKV, WritableMarfStore},
+    clarity_vm::database::marf::{MARFWrite, MarfedKV, WritableMarfStore},
 };
 use crate::{clarity_vm::database::marf::ReadOnlyMarfStore, core::StacksEpochId};
 use clarity::vm::analysis;
@@ -103,6 +104,8 @@ use clarity::vm::clarity::TransactionConnection;
 pub struct ClarityInstance {
     datastore: MarfedKV,
     mainnet: bool,
+    max_tx_event_bytes: Option<u64>,
+    use_subnet_costs: bool,
 }
 
 ///
@@ -131,6 +134,8 @@ pub struct ClarityBlockConnection<'a, 'b> {
     cost_track: Option<LimitedCostTracker>,
     mainnet: bool,
     epoch: StacksEpochId,
+    max_tx_event_bytes: Option<u64>,
+    use_subnet_costs: bool,
 }
 
 ///
@@ -146,6 +151,7 @@ pub struct ClarityTransactionConnection<'a, 'b> {
     cost_track: &'a mut Option<LimitedCostTracker>,
     mainnet: bool,
     epoch: StacksEpochId,
+    max_tx_event_bytes: Option<u64>,
 }
 
 pub struct ClarityReadOnlyConnection<'a> {
@@ -215,11 +221,58 @@ impl<'a, 'b> ClarityBlockConnection<'a, 'b> {
             None => None,
         }
     }
+
+    /// Replace the block limit that the cost of the block being created is checked against.
+    pub fn set_block_limit(&mut self, limit: ExecutionCost) -> () {
+        if let Some(ref mut cost_tracker) = self.cost_track {
+            cost_tracker.set_limit(limit);
+        }
+    }
+
+    /// Make a cost tracker with the cost functions and limit of the block being created, which
+    /// has not spent any of its budget.
+    pub fn make_unspent_cost_tracker(&self) -> LimitedCostTracker {
+        let mut tracker = self
+            .cost_track
+            .as_ref()
+            .expect("BUG: Clarity block connection lost cost tracker instance")
+            .clone();
+        tracker.set_total(ExecutionCost::zero());
+        tracker
+    }
 }
 
 impl ClarityInstance {
     pub fn new(mainnet: bool, datastore: MarfedKV) -> ClarityInstance {
-        ClarityInstance { datastore, mainnet }
+        ClarityInstance {
+            datastore,
+            mainnet,
+            max_tx_event_bytes: None,
+            use_subnet_costs: false,
+        }
+    }
+
+    /// Cap the bytes of events that a single transaction may emit.  A transaction that emits more
+    /// fails with a runtime error and its changes are rolled back, so the cap determines the
+    /// transaction's result and the block's state root.
+    pub fn set_max_tx_event_bytes(&mut self, max_tx_event_bytes: Option<u64>) {
+        self.max_tx_event_bytes = max_tx_event_bytes;
+    }
+
+    pub fn max_tx_event_bytes(&self) -> Option<u64> {
+        self.max_tx_event_bytes
+    }
+
+    /// Set whether the subnet-1 epoch transition instantiates `.costs-subnet`, whose cost
+    /// functions charge less for storage I/O than `.costs-3`'s.  The instantiated contract is part
+    /// of the state the epoch transition writes, so the setting determines the state root of the
+    /// first subnet-1 block.
+    pub fn set_use_subnet_costs(&mut self, use_subnet_costs: bool) {
+        self.use_subnet_costs = use_subnet_costs;
+    }
+
+    pub fn use_subnet_costs(&self) -> bool {
+        self.use_subnet_costs
     }
 
     pub fn with_marf<F, R>(&mut self, f: F) -> R
@@ -289,6 +342,8 @@ impl ClarityInstance {
             cost_track,
             mainnet: self.mainnet,
             epoch: epoch.epoch_id,
+            max_tx_event_bytes: self.max_tx_event_bytes,
+            use_subnet_costs: self.use_subnet_costs,
         }
     }
 
@@ -312,6 +367,8 @@ impl ClarityInstance {
             cost_track,
             mainnet: self.mainnet,
             epoch,
+            max_tx_event_bytes: self.max_tx_event_bytes,
+            use_subnet_costs: self.use_subnet_costs,
         }
     }
 
@@ -337,6 +394,8 @@ impl ClarityInstance {
             cost_track,
             mainnet: self.mainnet,
             epoch,
+            max_tx_event_bytes: self.max_tx_event_bytes,
+            use_subnet_costs: self.use_subnet_costs,
         };
 
         let use_mainnet = self.mainnet;
@@ -427,9 +486,23 @@ impl ClarityInstance {
             cost_track,
             mainnet: self.mainnet,
             epoch: epoch.epoch_id,
+            max_tx_event_bytes: self.max_tx_event_bytes,
+            use_subnet_costs: self.use_subnet_costs,
         }
     }
 
+    /// Open another instance onto this instance's Clarity MARF, through a read-only handle, with
+    /// the same settings.  The new instance can only open read-only connections.
+    pub fn reopen_readonly(&self) -> Result<ClarityInstance, Error> {
+        let datastore = self.datastore.reopen_readonly()?;
+        Ok(ClarityInstance {
+            datastore,
+            mainnet: self.mainnet,
+            max_tx_event_bytes: self.max_tx_event_bytes,
+            use_subnet_costs: self.use_subnet_costs,
+        })
+    }
+
     /// Open a read-only connection at `at_block`. This will be evaluated in the Stacks epoch that
     ///  was active *during* the evaluation of `at_block`
     pub fn read_only_connection<'a>(
@@ -726,6 +799,126 @@ impl<'a, 'b> ClarityBlockConnection<'a, 'b> {
         })
     }
 
+    /// Instantiate the subnet-1 epoch's cost contracts: `.costs-3`, and `.costs-subnet` if this
+    /// subnet uses the subnet cost profile.  Returns one receipt per contract.
+    pub fn initialize_epoch_subnet_1(&mut self) -> Result<Vec<StacksTransactionReceipt>, Error> {
+        using!(self.cost_track, "cost tracker", |mut old_cost_tracker| {
+            // epoch initialization is *free*
+            self.cost_track.replace(LimitedCostTracker::new_free());
+
+            let mainnet = self.mainnet;
+
+            let tx_version = if mainnet {
+                TransactionVersion::Mainnet
+            } else {
+                TransactionVersion::Testnet
+            };
+
+            let boot_code_address = boot_code_addr(mainnet);
+
+            let boot_code_auth = boot_code_tx_auth(boot_code_address);
+
+            let boot_code_nonce = self.with_clarity_db_readonly(|db| {
+                db.get_account_nonce(&boot_code_address.clone().into())
+            });
+
+            let boot_code_account = boot_code_acc(boot_code_address, boot_code_nonce);
+
+            // instantiate costs 3 contract...
+            let cost_3_code = if mainnet {
+                &*BOOT_CODE_COSTS_3
+            } else {
+                &*BOOT_CODE_COSTS_3_TESTNET
+            };
+
+            let payload = TransactionPayload::SmartContract(TransactionSmartContract {
+                name: ContractName::try_from(COSTS_3_NAME)
+                    .expect("FATAL: invalid boot-code contract name"),
+                code_body: StacksString::from_str(cost_3_code)
+                    .expect("FATAL: invalid boot code body"),
+            });
+
+            let costs_3_contract_tx =
+                StacksTransaction::new(tx_version.clone(), boot_code_auth.clone(), payload);
+
+            // ...and the costs-subnet contract, if this subnet uses it
+            let costs_subnet_contract_tx = if self.use_subnet_costs {
+                let payload = TransactionPayload::SmartContract(TransactionSmartContract {
+                    name: ContractName::try_from(COSTS_SUBNET_NAME)
+                        .expect("FATAL: invalid boot-code contract name"),
+                    code_body: StacksString::from_str(BOOT_CODE_COSTS_SUBNET)
+                        .expect("FATAL: invalid boot code body"),
+                });
+                Some(StacksTransaction::new(
+                    tx_version.clone(),
+                    boot_code_auth.clone(),
+                    payload,
+                ))
+            } else {
+                None
+            };
+
+            let initialization_receipts = self.as_transaction(|tx_conn| {
+                // bump the epoch in the Clarity DB
+                tx_conn
+                    .with_clarity_db(|db| {
+                        db.set_clarity_epoch_version(StacksEpochId::EpochSubnet1);
+                        // withdrawal batching, if this subnet uses it, starts with this block
+                        if db.get_withdrawal_batch_blocks() > 0 {
+                            let start_height = db.get_current_block_height() as u64;
+                            db.set_withdrawal_batch_start_height(start_height);
+                        }
+                        Ok(())
+                    })
+                    .unwrap();
+
+                // initialize with a synthetic transaction
+                let receipt = StacksChainState::process_transaction_payload(
+                    tx_conn,
+                    &costs_3_contract_tx,
+                    &boot_code_account,
+                )
+                .expect("FATAL: Failed to process Costs 3 contract initialization");
+                let mut receipts = vec![receipt];
+
+                if let Some(costs_subnet_contract_tx) = costs_subnet_contract_tx.as_ref() {
+                    let receipt = StacksChainState::process_transaction_payload(
+                        tx_conn,
+                        costs_subnet_contract_tx,
+                        &boot_code_account,
+                    )
+                    .expect("FATAL: Failed to process Costs Subnet contract initialization");
+                    receipts.push(receipt);
+                }
+
+                // the rest of this block is charged by .costs-3 (and .costs-subnet), which
+                //  defines the event emission cost that this epoch starts assessing
+                tx_conn
+                    .with_clarity_db(|db| {
+                        LimitedCostTracker::reload_costs(&mut old_cost_tracker, db)
+                            .expect("FATAL: Failed to load the Costs 3 cost functions");
+                        Ok(())
+                    })
+                    .unwrap();
+
+                receipts
+            });
+
+            for initialization_receipt in initialization_receipts.iter() {
+                if initialization_receipt.result != Value::okay_true()
+                    || initialization_receipt.post_condition_aborted
+                {
+                    panic!(
+                        "FATAL: Failure processing subnet-1 cost contract initialization: {:#?}",
+                        initialization_receipt
+                    );
+                }
+            }
+
+            (old_cost_tracker, Ok(initialization_receipts))
+        })
+    }
+
     pub fn start_transaction_processing<'c>(&'c mut self) -> ClarityTransactionConnection<'c, 'a> {
         let store = &mut self.datastore;
         let cost_track = &mut self.cost_track;
@@ -742,6 +935,7 @@ impl<'a, 'b> ClarityBlockConnection<'a, 'b> {
             log: Some(log),
             mainnet,
             epoch: self.epoch,
+            max_tx_event_bytes: self.max_tx_event_bytes,
         }
     }
 
@@ -759,6 +953,15 @@ impl<'a, 'b> ClarityBlockConnection<'a, 'b> {
         self.datastore.seal()
     }
 
+    /// Start recording the keys this block writes (see `WritableMarfStore::record_write_set`)
+    pub fn record_write_set(&mut self) {
+        self.datastore.record_write_set()
+    }
+
+    pub fn take_write_set(&mut self) -> Option<Vec<MARFWrite>> {
+        self.datastore.take_write_set()
+    }
+
     pub fn destruct(self) -> WritableMarfStore<'a> {
         self.datastore
     }
@@ -845,6 +1048,7 @@ impl<'a, 'b> TransactionConnection for ClarityTransactionConnection<'a, 'b> {
                 db.begin();
                 let mut vm_env =
                     OwnedEnvironment::new_cost_limited(self.mainnet, db, cost_track, self.epoch);
+                vm_env.set_max_event_bytes(self.max_tx_event_bytes);
                 let result = to_do(&mut vm_env);
                 let (mut db, cost_track) = vm_env
                     .destruct()
@@ -921,6 +1125,32 @@ impl<'a, 'b> ClarityTransactionConnection<'a, 'b> {
         }
     }
 
+    pub fn is_mainnet(&self) -> bool {
+        self.mainnet
+    }
+
+    /// Run `todo` in this transaction with `new_tracker`.
+    /// Returns the result of `todo` and the `new_tracker`
+    pub fn with_temporary_cost_tracker<F, R>(
+        &mut self,
+        new_tracker: LimitedCostTracker,
+        todo: F,
+    ) -> (R, LimitedCostTracker)
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        let original_tracker = self
+            .cost_track
+            .replace(new_tracker)
+            .expect("BUG: Transaction connection lost cost tracker connection.");
+        let result = todo(self);
+        let new_tracker = self
+            .cost_track
+            .replace(original_tracker)
+            .expect("BUG: Transaction connection lost cost tracker connection.");
+        (result, new_tracker)
+    }
+
     /// Commit the changes from the edit log.
     /// panics if there is more than one open savepoint
     pub fn commit(mut self) {
@@ -979,7 +1209,7 @@ mod tests {
     use clarity::vm::types::{StandardPrincipalData, Value};
 
     use crate::core::{PEER_VERSION_EPOCH_1_0, PEER_VERSION_EPOCH_2_0, PEER_VERSION_EPOCH_2_05};
-    use clarity::vm::test_util::{TEST_BURN_STATE_DB, TEST_HEADER_DB};
+    use clarity::vm::test_util::{UnitTestBurnStateDB, TEST_BURN_STATE_DB, TEST_HEADER_DB};
 
     use crate::chainstate::stacks::index::ClarityMarfTrieId;
     use crate::clarity_vm::database::marf::MarfedKV;
@@ -1075,6 +1305,84 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn test_constant_folding() {
+        let contract = "
+            (define-constant scale (pow u10 u6))
+            (define-read-only (scaled (x uint)) (* x (* scale (+ u1 u1))))
+            (define-read-only (big) (+ (* 1000 1000) (- 5 (/ 10 2)) (sqrti 144)))
+            (define-read-only (underflow) (- u0 (+ u1 u1)))";
+        let sender = StandardPrincipalData::transient().into();
+
+        let run = |epoch_id: StacksEpochId| {
+            let burn_state_db = UnitTestBurnStateDB { epoch_id };
+            let marf = MarfedKV::temporary();
+            let mut clarity_instance = ClarityInstance::new(false, marf);
+            let contract_identifier = QualifiedContractIdentifier::local("foo").unwrap();
+
+            clarity_instance
+                .begin_test_genesis_block(
+                    &StacksBlockId::sentinel(),
+                    &StacksBlockId([0 as u8; 32]),
+                    &TEST_HEADER_DB,
+                    &TEST_BURN_STATE_DB,
+                )
+                .commit_block();
+
+            let mut conn = clarity_instance.begin_block(
+                &StacksBlockId([0 as u8; 32]),
+                &StacksBlockId([1 as u8; 32]),
+                &TEST_HEADER_DB,
+                &burn_state_db,
+            );
+
+            conn.as_transaction(|conn| {
+                let (ct_ast, ct_analysis) = conn
+                    .analyze_smart_contract(&contract_identifier, &contract)
+                    .unwrap();
+                conn.initialize_smart_contract(&contract_identifier, &ct_ast, &contract, |_, _| {
+                    false
+                })
+                .unwrap();
+                conn.save_analysis(&contract_identifier, &ct_analysis)
+                    .unwrap();
+            });
+
+            let cost_before_calls = conn.cost_so_far();
+            let results: Vec<_> = [
+                ("scaled", vec![Value::UInt(3)]),
+                ("big", vec![]),
+                ("underflow", vec![]),
+            ]
+            .iter()
+            .map(|(function, args)| {
+                conn.as_transaction(|tx| {
+                    tx.run_contract_call(&sender, &contract_identifier, function, args, |_, _| {
+                        false
+                    })
+                })
+                .map(|(result, _, _)| result)
+                .map_err(|e| format!("{:?}", e))
+            })
+            .collect();
+            let mut call_cost = conn.cost_so_far();
+            call_cost.sub(&cost_before_calls).unwrap();
+
+            conn.commit_block();
+            (results, call_cost)
+        };
+
+        // contracts are only folded from the subnet-1 epoch on
+        let (unfolded_results, unfolded_cost) = run(StacksEpochId::Epoch2_05);
+        let (folded_results, folded_cost) = run(StacksEpochId::EpochSubnet1);
+
+        assert_eq!(folded_results, unfolded_results);
+        assert_eq!(folded_results[0], Ok(Value::UInt(6_000_000)));
+        assert_eq!(folded_results[1], Ok(Value::Int(1_000_012)));
+        assert!(folded_results[2].is_err());
+        assert!(folded_cost.runtime < unfolded_cost.runtime);
+    }
+
     #[test]
     pub fn tx_rollback() {
         let marf = MarfedKV::temporary();
@@ -1220,6 +1528,67 @@ mod tests {
         assert!(conn.get_contract_hash(&contract_identifier).is_ok());
     }
 
+    #[test]
+    pub fn test_withdrawal_batching_starts_at_epoch_subnet_1() {
+        for batch_blocks in [0, 4] {
+            let marf = MarfedKV::temporary();
+            let mut clarity_instance = ClarityInstance::new(false, marf);
+
+            let genesis = StacksBlockId([0 as u8; 32]);
+            let mut conn = clarity_instance.begin_test_genesis_block(
+                &StacksBlockId::sentinel(),
+                &genesis,
+                &TEST_HEADER_DB,
+                &TEST_BURN_STATE_DB,
+            );
+            if batch_blocks > 0 {
+                conn.as_transaction(|tx| {
+                    tx.with_clarity_db(|db| {
+                        db.set_withdrawal_batch_blocks(batch_blocks);
+                        Ok(())
+                    })
+                    .unwrap()
+                });
+            }
+            conn.commit_block();
+
+            // a block in epoch 2.05 has not started batching
+            let block_1 = StacksBlockId([1 as u8; 32]);
+            let mut conn = clarity_instance.begin_block(
+                &genesis,
+                &block_1,
+                &TEST_HEADER_DB,
+                &TEST_BURN_STATE_DB,
+            );
+            conn.initialize_epoch_2_05().unwrap();
+            conn.commit_block();
+
+            // the first block of subnet-1 starts batching, if the subnet batches withdrawals
+            let block_2 = StacksBlockId([2 as u8; 32]);
+            let mut conn = clarity_instance.begin_block(
+                &block_1,
+                &block_2,
+                &TEST_HEADER_DB,
+                &TEST_BURN_STATE_DB,
+            );
+            conn.initialize_epoch_subnet_1().unwrap();
+            conn.commit_block();
+
+            for (tip, expected_start_height) in [
+                (&block_1, None),
+                (&block_2, Some(2).filter(|_| batch_blocks > 0)),
+            ] {
+                let start_height = clarity_instance
+                    .read_only_connection(tip, &TEST_HEADER_DB, &TEST_BURN_STATE_DB)
+                    .with_clarity_db_readonly(|db| {
+                        assert_eq!(db.get_withdrawal_batch_blocks(), batch_blocks);
+                        db.get_withdrawal_batch_start_height()
+                    });
+                assert_eq!(start_height, expected_start_height);
+            }
+        }
+    }
+
     #[test]
     pub fn test_block_roll_back() {
         let marf = MarfedKV::temporary();
@@ -1672,6 +2041,14 @@ mod tests {
                 None
             }
 
+            fn get_burn_header_timestamp(
+                &self,
+                _height: u32,
+                _sortition_id: &SortitionId,
+            ) -> Option<u64> {
+                None
+            }
+
             fn get_stacks_epoch(&self, _height: u32) -> Option<StacksEpoch> {
                 // Note: We return this StacksEpoch for every input, because this test is not exercising
                 // this method.
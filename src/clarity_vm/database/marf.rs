@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use rusqlite::Connection;
@@ -65,7 +66,8 @@ impl MarfedKV {
         };
 
         if SqliteConnection::check_schema(&marf.sqlite_conn()).is_ok() {
-            // no need to initialize
+            // no need to initialize, but the chainstate may predate the map key index
+            SqliteConnection::initialize_map_keys(&marf.sqlite_conn())?;
             return Ok(marf);
         }
 
@@ -108,6 +110,19 @@ impl MarfedKV {
         Ok(MarfedKV { marf, chain_tip })
     }
 
+    /// Open another handle onto this MARF's storage, with a read-only SQLite connection.  Reads
+    /// through the new handle do not contend with this handle's connection.
+    pub fn reopen_readonly(&self) -> InterpreterResult<MarfedKV> {
+        let marf = self
+            .marf
+            .reopen_readonly()
+            .map_err(|err| InterpreterError::MarfFailure(err.to_string()))?;
+        Ok(MarfedKV {
+            marf,
+            chain_tip: self.chain_tip.clone(),
+        })
+    }
+
     // used by benchmarks
     pub fn temporary() -> MarfedKV {
         use rand::Rng;
@@ -206,6 +221,7 @@ impl MarfedKV {
         WritableMarfStore {
             chain_tip,
             marf: tx,
+            write_set: None,
         }
     }
 
@@ -227,6 +243,7 @@ impl MarfedKV {
         WritableMarfStore {
             chain_tip,
             marf: tx,
+            write_set: None,
         }
     }
 
@@ -258,6 +275,19 @@ impl MarfedKV {
 pub struct WritableMarfStore<'a> {
     chain_tip: StacksBlockId,
     marf: MarfTransaction<'a, StacksBlockId>,
+    /// If set, every key written to the open block, with its value before the block and its
+    /// latest value
+    write_set: Option<BTreeMap<String, MARFWrite>>,
+}
+
+/// One key written while processing a block
+#[derive(Debug, Clone, PartialEq)]
+pub struct MARFWrite {
+    pub key: String,
+    /// the key's value before the block, if it had one
+    pub before: Option<String>,
+    /// the key's value after the block
+    pub after: String,
 }
 
 pub struct ReadOnlyMarfStore<'a> {
@@ -453,12 +483,14 @@ impl<'a> WritableMarfStore<'a> {
     pub fn rollback_unconfirmed(self) {
         debug!("Drop unconfirmed MARF trie {}", &self.chain_tip);
         SqliteConnection::drop_metadata(self.marf.sqlite_tx(), &self.chain_tip);
+        SqliteConnection::drop_map_keys(self.marf.sqlite_tx(), &self.chain_tip);
         self.marf.drop_unconfirmed();
     }
 
     pub fn commit_to(self, final_bhh: &StacksBlockId) {
         debug!("commit_to({})", final_bhh);
         SqliteConnection::commit_metadata_to(self.marf.sqlite_tx(), &self.chain_tip, final_bhh);
+        SqliteConnection::commit_map_keys_to(self.marf.sqlite_tx(), &self.chain_tip, final_bhh);
 
         let _ = self.marf.commit_to(final_bhh).map_err(|e| {
             error!("Failed to commit to MARF block {}: {:?}", &final_bhh, &e);
@@ -495,6 +527,7 @@ impl<'a> WritableMarfStore<'a> {
         //    _if_ for some reason, we do want to be able to access that mined chain state in the future,
         //    we should probably commit the data to a different table which does not have uniqueness constraints.
         SqliteConnection::drop_metadata(self.marf.sqlite_tx(), &self.chain_tip);
+        SqliteConnection::drop_map_keys(self.marf.sqlite_tx(), &self.chain_tip);
         let _ = self.marf.commit_mined(will_move_to).map_err(|e| {
             error!(
                 "Failed to commit to mined MARF block {}: {:?}",
@@ -507,6 +540,41 @@ impl<'a> WritableMarfStore<'a> {
     pub fn seal(&mut self) -> TrieHash {
         self.marf.seal().expect("FATAL: failed to .seal() MARF")
     }
+
+    /// Start recording the keys written to the open block (see `take_write_set`)
+    pub fn record_write_set(&mut self) {
+        if self.write_set.is_none() {
+            self.write_set = Some(BTreeMap::new());
+        }
+    }
+
+    fn record_write(&mut self, key: &str, value: &str) {
+        let already_written = self
+            .write_set
+            .as_ref()
+            .map(|write_set| write_set.contains_key(key))
+            .unwrap_or(false);
+        // the first write of a key in this block is the one that can see its prior value
+        let before = if already_written { None } else { self.get(key) };
+        if let Some(write_set) = self.write_set.as_mut() {
+            write_set
+                .entry(key.to_string())
+                .and_modify(|write| write.after = value.to_string())
+                .or_insert_with(|| MARFWrite {
+                    key: key.to_string(),
+                    before,
+                    after: value.to_string(),
+                });
+        }
+    }
+
+    /// Take the keys written to the open block since `record_write_set` was called, in key
+    /// order.  Returns None if writes are not being recorded.
+    pub fn take_write_set(&mut self) -> Option<Vec<MARFWrite>> {
+        self.write_set
+            .take()
+            .map(|write_set| write_set.into_iter().map(|(_, write)| write).collect())
+    }
 }
 
 impl<'a> ClarityBackingStore for WritableMarfStore<'a> {
@@ -649,10 +717,19 @@ impl<'a> ClarityBackingStore for WritableMarfStore<'a> {
     fn put_all(&mut self, items: Vec<(String, String)>) {
         let mut keys = Vec::new();
         let mut values = Vec::new();
+        let mut height = None;
         for (key, value) in items.into_iter() {
             trace!("MarfedKV put '{}' = '{}'", &key, &value);
+            if self.write_set.is_some() {
+                self.record_write(&key, &value);
+            }
             let marf_value = MARFValue::from_value(&value);
             SqliteConnection::put(self.get_side_store(), &marf_value.to_hex(), &value);
+            if ClarityDatabase::is_data_map_entry_key(&key) {
+                let height = *height.get_or_insert_with(|| self.get_current_block_height());
+                let tip = self.chain_tip.clone();
+                SqliteConnection::put_map_key(self.get_side_store(), &key, &tip, height);
+            }
             keys.push(key);
             values.push(marf_value);
         }
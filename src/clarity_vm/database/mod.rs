@@ -19,6 +19,7 @@ use crate::chainstate::stacks::index::{ClarityMarfTrieId, TrieMerkleProof};
 use crate::types::chainstate::StacksBlockId;
 use crate::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash, SortitionId};
 use crate::types::chainstate::{StacksAddress, VRFSeed};
+use crate::util::hash::{MerkleTree, Sha512Trunc256Sum};
 
 use crate::core::StacksEpoch;
 use crate::core::StacksEpochId;
@@ -62,6 +63,13 @@ impl<'a> HeadersDB for HeadersDBConn<'a> {
     fn get_miner_address(&self, id_bhh: &StacksBlockId) -> Option<StacksAddress> {
         get_miner_info(self.0, id_bhh).map(|x| x.address)
     }
+
+    fn get_withdrawal_tree_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+    ) -> Option<MerkleTree<Sha512Trunc256Sum>> {
+        get_stacks_header_info(self.0, id_bhh).map(|x| x.withdrawal_tree)
+    }
 }
 
 impl<'a> HeadersDB for ChainstateTx<'a> {
@@ -95,6 +103,13 @@ impl<'a> HeadersDB for ChainstateTx<'a> {
     fn get_miner_address(&self, id_bhh: &StacksBlockId) -> Option<StacksAddress> {
         get_miner_info(self.deref().deref(), id_bhh).map(|x| x.address)
     }
+
+    fn get_withdrawal_tree_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+    ) -> Option<MerkleTree<Sha512Trunc256Sum>> {
+        get_stacks_header_info(self.deref().deref(), id_bhh).map(|x| x.withdrawal_tree)
+    }
 }
 
 impl HeadersDB for crate::chainstate::stacks::index::marf::MARF<StacksBlockId> {
@@ -128,6 +143,13 @@ impl HeadersDB for crate::chainstate::stacks::index::marf::MARF<StacksBlockId> {
     fn get_miner_address(&self, id_bhh: &StacksBlockId) -> Option<StacksAddress> {
         get_miner_info(self.sqlite_conn(), id_bhh).map(|x| x.address)
     }
+
+    fn get_withdrawal_tree_for_block(
+        &self,
+        id_bhh: &StacksBlockId,
+    ) -> Option<MerkleTree<Sha512Trunc256Sum>> {
+        get_stacks_header_info(self.sqlite_conn(), id_bhh).map(|x| x.withdrawal_tree)
+    }
 }
 
 fn get_stacks_header_info(conn: &DBConn, id_bhh: &StacksBlockId) -> Option<StacksHeaderInfo> {
@@ -176,6 +198,20 @@ impl BurnStateDB for SortitionHandleTx<'_> {
         }
     }
 
+    fn get_burn_header_timestamp(&self, height: u32, sortition_id: &SortitionId) -> Option<u64> {
+        let readonly_marf = self
+            .index()
+            .reopen_readonly()
+            .expect("BUG: failure trying to get a read-only interface into the sortition db.");
+        let mut context = self.context.clone();
+        context.chain_tip = sortition_id.clone();
+        let db_handle = SortitionHandleConn::new(&readonly_marf, context);
+        match db_handle.get_block_snapshot_by_height(height as u64) {
+            Ok(Some(x)) => Some(x.burn_header_timestamp),
+            _ => return None,
+        }
+    }
+
     fn get_stacks_epoch(&self, height: u32) -> Option<StacksEpoch> {
         SortitionDB::get_stacks_epoch(self.tx(), height as u64)
             .expect("BUG: failed to get epoch for burn block height")
@@ -207,6 +243,14 @@ impl BurnStateDB for SortitionDBConn<'_> {
         }
     }
 
+    fn get_burn_header_timestamp(&self, height: u32, sortition_id: &SortitionId) -> Option<u64> {
+        let db_handle = SortitionHandleConn::open_reader(self, &sortition_id).ok()?;
+        match db_handle.get_block_snapshot_by_height(height as u64) {
+            Ok(Some(x)) => Some(x.burn_header_timestamp),
+            _ => return None,
+        }
+    }
+
     fn get_stacks_epoch(&self, height: u32) -> Option<StacksEpoch> {
         SortitionDB::get_stacks_epoch(self.conn(), height as u64)
             .expect("BUG: failed to get epoch for burn block height")
@@ -284,7 +328,11 @@ impl ClarityBackingStore for MemoryBackingStore {
     }
 
     fn put_all(&mut self, items: Vec<(String, String)>) {
+        let tip = self.get_open_chain_tip();
         for (key, value) in items.into_iter() {
+            if ClarityDatabase::is_data_map_entry_key(&key) {
+                SqliteConnection::put_map_key(self.get_side_store(), &key, &tip, 0);
+            }
             SqliteConnection::put(self.get_side_store(), &key, &value);
         }
     }
@@ -26,10 +26,11 @@ use clarity::vm::errors::{CheckErrors, Error, RuntimeErrorType};
 use clarity::vm::execute as vm_execute;
 use clarity::vm::functions::NativeFunctions;
 use clarity::vm::representations::SymbolicExpression;
-use clarity::vm::test_util::{TEST_BURN_STATE_DB, TEST_HEADER_DB};
+use clarity::vm::test_util::{UnitTestBurnStateDB, TEST_BURN_STATE_DB, TEST_HEADER_DB};
 use clarity::vm::types::{
     AssetIdentifier, PrincipalData, QualifiedContractIdentifier, ResponseData, Value,
 };
+use clarity::vm::ClarityVersion;
 use stacks_common::util::hash::hex_bytes;
 
 use crate::chainstate::stacks::index::ClarityMarfTrieId;
@@ -77,6 +78,7 @@ pub fn test_tracked_costs(prog: &str, use_mainnet: bool, epoch: StacksEpochId) -
         QualifiedContractIdentifier::new(p1_principal.clone(), "contract-other".into());
     let trait_contract_id =
         QualifiedContractIdentifier::new(p1_principal.clone(), "contract-trait".into());
+    let burn_state_db = UnitTestBurnStateDB { epoch_id: epoch };
 
     clarity_instance
         .begin_test_genesis_block(
@@ -92,12 +94,15 @@ pub fn test_tracked_costs(prog: &str, use_mainnet: bool, epoch: StacksEpochId) -
             &StacksBlockId([0 as u8; 32]),
             &StacksBlockId([1 as u8; 32]),
             &TEST_HEADER_DB,
-            &TEST_BURN_STATE_DB,
+            &burn_state_db,
         );
 
-        if epoch == StacksEpochId::Epoch2_05 {
+        if epoch >= StacksEpochId::Epoch2_05 {
             conn.initialize_epoch_2_05().unwrap();
         }
+        if epoch >= StacksEpochId::EpochSubnet1 {
+            conn.initialize_epoch_subnet_1().unwrap();
+        }
 
         conn.commit_block();
     }
@@ -107,7 +112,7 @@ pub fn test_tracked_costs(prog: &str, use_mainnet: bool, epoch: StacksEpochId) -
             &StacksBlockId([1 as u8; 32]),
             &StacksBlockId([2 as u8; 32]),
             &TEST_HEADER_DB,
-            &TEST_BURN_STATE_DB,
+            &burn_state_db,
         );
 
         assert_eq!(
@@ -135,7 +140,7 @@ pub fn test_tracked_costs(prog: &str, use_mainnet: bool, epoch: StacksEpochId) -
             &StacksBlockId([2 as u8; 32]),
             &StacksBlockId([3 as u8; 32]),
             &TEST_HEADER_DB,
-            &TEST_BURN_STATE_DB,
+            &burn_state_db,
         );
         conn.as_transaction(|conn| {
             let (ct_ast, ct_analysis) = conn
@@ -157,7 +162,7 @@ pub fn test_tracked_costs(prog: &str, use_mainnet: bool, epoch: StacksEpochId) -
             &StacksBlockId([3 as u8; 32]),
             &StacksBlockId([4 as u8; 32]),
             &TEST_HEADER_DB,
-            &TEST_BURN_STATE_DB,
+            &burn_state_db,
         );
 
         conn.as_transaction(|conn| {
@@ -179,6 +184,9 @@ fn test_all(use_mainnet: bool) {
     let baseline = test_tracked_costs("1", use_mainnet, StacksEpochId::Epoch20);
 
     for f in NativeFunctions::ALL.iter() {
+        if f.get_version() > ClarityVersion::default_for_epoch(StacksEpochId::Epoch20) {
+            continue;
+        }
         let test = get_simple_test(f);
         let cost = test_tracked_costs(test, use_mainnet, StacksEpochId::Epoch20);
         assert!(cost.exceeds(&baseline));
@@ -199,6 +207,9 @@ fn epoch_205_test_all(use_mainnet: bool) {
     let baseline = test_tracked_costs("1", use_mainnet, StacksEpochId::Epoch2_05);
 
     for f in NativeFunctions::ALL.iter() {
+        if f.get_version() > ClarityVersion::default_for_epoch(StacksEpochId::Epoch2_05) {
+            continue;
+        }
         let test = get_simple_test(f);
         let cost = test_tracked_costs(test, use_mainnet, StacksEpochId::Epoch2_05);
         assert!(cost.exceeds(&baseline));
@@ -214,3 +225,23 @@ fn epoch_205_test_all_mainnet() {
 fn epoch_205_test_all_testnet() {
     epoch_205_test_all(false)
 }
+
+fn epoch_subnet_1_test_all(use_mainnet: bool) {
+    let baseline = test_tracked_costs("1", use_mainnet, StacksEpochId::EpochSubnet1);
+
+    for f in NativeFunctions::ALL.iter() {
+        let test = get_simple_test(f);
+        let cost = test_tracked_costs(test, use_mainnet, StacksEpochId::EpochSubnet1);
+        assert!(cost.exceeds(&baseline));
+    }
+}
+
+#[test]
+fn epoch_subnet_1_test_all_mainnet() {
+    epoch_subnet_1_test_all(true)
+}
+
+#[test]
+fn epoch_subnet_1_test_all_testnet() {
+    epoch_subnet_1_test_all(false)
+}
@@ -40,6 +40,7 @@ use clarity::vm::test_util::{
 use clarity::vm::types::{
     AssetIdentifier, PrincipalData, QualifiedContractIdentifier, ResponseData, Value,
 };
+use clarity::vm::ClarityVersion;
 use stacks_common::util::hash::hex_bytes;
 use std::collections::HashMap;
 
@@ -59,6 +60,8 @@ pub fn get_simple_test(function: &NativeFunctions) -> &'static str {
         Add => "(+ 1 1)",
         ToUInt => "(to-uint 1)",
         ToInt => "(to-int u1)",
+        BuffToUIntBe => "(buff-to-uint-be 0x0102)",
+        BuffToUIntLe => "(buff-to-uint-le 0x0102)",
         Subtract => "(- 1 1)",
         Multiply => "(* 1 1)",
         Divide => "(/ 1 1)",
@@ -71,6 +74,11 @@ pub fn get_simple_test(function: &NativeFunctions) -> &'static str {
         Sqrti => "(sqrti 81)",
         Log2 => "(log2 8)",
         BitwiseXOR => "(xor 1 2)",
+        BitwiseAnd => "(bit-and 1 2)",
+        BitwiseOr => "(bit-or 1 2)",
+        BitwiseNot => "(bit-not 1)",
+        BitwiseLShift => "(bit-shift-left 1 u2)",
+        BitwiseRShift => "(bit-shift-right 8 u2)",
         And => "(and true false)",
         Or => "(or true false)",
         Not => "(not true)",
@@ -78,6 +86,8 @@ pub fn get_simple_test(function: &NativeFunctions) -> &'static str {
         If => "(if true (+ 1 2) 2)",
         Let => "(let ((x 1)) x)",
         FetchVar => "(var-get var-foo)",
+        VarExists => "(var-exists? var-foo)",
+        MapSize => "(map-size map-foo)",
         SetVar => "(var-set var-foo 1)",
         Map => "(map not list-foo)",
         Filter => "(filter not list-foo)",
@@ -88,6 +98,10 @@ pub fn get_simple_test(function: &NativeFunctions) -> &'static str {
         Len => "(len list-bar)",
         ElementAt => "(element-at list-bar u2)",
         IndexOf => "(index-of list-bar 1)",
+        Slice => "(slice list-bar u1 u3)",
+        ReplaceAt => "(replace-at? list-bar u1 5)",
+        ToConsensusBuff => "(to-consensus-buff? list-bar)",
+        FromConsensusBuff => "(from-consensus-buff? int 0x0000000000000000000000000000000001)",
         ListCons => "(list 1 2 3 4)",
         FetchEntry => "(map-get? map-foo {a: 1})",
         SetEntry => "(map-set map-foo {a: 1} {b: 2})",
@@ -106,6 +120,7 @@ pub fn get_simple_test(function: &NativeFunctions) -> &'static str {
         Secp256k1Verify => "(secp256k1-verify 0xde5b9eb9e7c5592930eb2e30a01369c36586d872082ed8181ee83d2a0ec20f04 0x8738487ebe69b93d8e51583be8eee50bb4213fc49c767d329632730cc193b873554428fc936ca3569afc15f1c9365f6591d6251a89fee9c9ac661116824d3a1301 0x03adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110)",
         Print => "(print 1)",
         ContractCall => "(contract-call? .contract-other foo-exec 1)",
+        TryContractCall => "(try-contract-call? .contract-other foo-exec 1)",
         ContractOf => "(contract-of contract)",
         PrincipalOf => "(principal-of? 0x03adb8de4bfb65db2cfd6120d55c6526ae9c52e675db7e47308636534ba7786110)",
         AsContract => "(as-contract 1)",
@@ -136,11 +151,14 @@ pub fn get_simple_test(function: &NativeFunctions) -> &'static str {
         GetTokenSupply => "(ft-get-supply ft-foo)",
         AtBlock => "(at-block 0x55c9861be5cff984a20ce6d99d4aa65941412889bdc665094136429b84f8c2ee 1)",   // first stacksblockid
         GetStxBalance => "(stx-get-balance 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)",
+        StxGetAccount => "(stx-account 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)",
         StxTransfer => "(stx-transfer? u1 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)",
         StxBurn => "(stx-burn? u1 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)",
         StxWithdraw => "(stx-withdraw? u1 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)",
         WithdrawToken => "(ft-withdraw? ft-foo u1 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)",
         WithdrawAsset => "(nft-withdraw? nft-foo 1 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)",
+        GetWithdrawalInfo => "(get-withdrawal-info? stx u1 u0 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR u1)",
+        GetL1BlockInfo => "(get-l1-block-info? time u1)",
     }
 }
 
@@ -155,11 +173,26 @@ fn execute_transaction(
 }
 
 fn with_owned_env<F, R>(epoch: StacksEpochId, use_mainnet: bool, to_do: F) -> R
+where
+    F: Fn(OwnedEnvironment) -> R,
+{
+    with_owned_env_costs(epoch, use_mainnet, false, to_do)
+}
+
+/// Like `with_owned_env`, but if `use_subnet_costs` is set, the subnet-1 epoch transition also
+///  instantiates .costs-subnet.
+fn with_owned_env_costs<F, R>(
+    epoch: StacksEpochId,
+    use_mainnet: bool,
+    use_subnet_costs: bool,
+    to_do: F,
+) -> R
 where
     F: Fn(OwnedEnvironment) -> R,
 {
     let marf_kv = MarfedKV::temporary();
     let mut clarity_instance = ClarityInstance::new(use_mainnet, marf_kv);
+    clarity_instance.set_use_subnet_costs(use_subnet_costs);
 
     let first_block = StacksBlockId::new(&FIRST_BURNCHAIN_CONSENSUS_HASH, &FIRST_STACKS_BLOCK_HASH);
     clarity_instance
@@ -171,7 +204,7 @@ where
         )
         .commit_block();
 
-    let tip = if epoch == StacksEpochId::Epoch2_05 {
+    let tip = if epoch >= StacksEpochId::Epoch2_05 {
         let next_block = StacksBlockId([1 as u8; 32]);
         let mut clarity_conn = clarity_instance.begin_block(
             &first_block,
@@ -180,6 +213,9 @@ where
             &TEST_BURN_STATE_DB,
         );
         clarity_conn.initialize_epoch_2_05().unwrap();
+        if epoch >= StacksEpochId::EpochSubnet1 {
+            clarity_conn.initialize_epoch_subnet_1().unwrap();
+        }
         clarity_conn.commit_block();
         next_block
     } else {
@@ -198,6 +234,15 @@ where
 }
 
 fn exec_cost(contract: &str, use_mainnet: bool, epoch: StacksEpochId) -> ExecutionCost {
+    exec_cost_with_costs(contract, use_mainnet, epoch, false)
+}
+
+fn exec_cost_with_costs(
+    contract: &str,
+    use_mainnet: bool,
+    epoch: StacksEpochId,
+    use_subnet_costs: bool,
+) -> ExecutionCost {
     let p1 = execute("'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR");
     let p1_principal = match p1 {
         Value::Principal(PrincipalData::Standard(ref data)) => data.clone(),
@@ -205,7 +250,7 @@ fn exec_cost(contract: &str, use_mainnet: bool, epoch: StacksEpochId) -> Executi
     };
     let contract_id = QualifiedContractIdentifier::new(p1_principal.clone(), "self".into());
 
-    with_owned_env(epoch, use_mainnet, |mut owned_env| {
+    with_owned_env_costs(epoch, use_mainnet, use_subnet_costs, |mut owned_env| {
         owned_env
             .initialize_contract(contract_id.clone(), contract)
             .unwrap();
@@ -792,6 +837,206 @@ fn epoch205_nfts_testnet() {
     epoch205_nfts(false)
 }
 
+// Test the event emission cost introduced in epoch subnet-1: events used to be free, and are now
+// charged against the block's write budget in proportion to their payload.
+fn epoch_subnet_1_event_emission(use_mainnet: bool) {
+    // test print
+    let smaller_exec = "(define-public (execute) (begin (print 0x01) (ok 1)))";
+    let larger_exec = format!(
+        "(define-public (execute) (begin (print 0x{}) (ok 1)))",
+        "ab".repeat(1000)
+    );
+    let smaller_cost_epoch_205 = exec_cost(smaller_exec, use_mainnet, StacksEpochId::Epoch2_05);
+    let larger_cost_epoch_205 = exec_cost(&larger_exec, use_mainnet, StacksEpochId::Epoch2_05);
+    let smaller_cost_subnet_1 = exec_cost(smaller_exec, use_mainnet, StacksEpochId::EpochSubnet1);
+    let larger_cost_subnet_1 = exec_cost(&larger_exec, use_mainnet, StacksEpochId::EpochSubnet1);
+
+    assert_eq!(smaller_cost_epoch_205.write_length, 0);
+    assert_eq!(larger_cost_epoch_205.write_length, 0);
+    assert!(larger_cost_subnet_1.write_length - smaller_cost_subnet_1.write_length >= 999);
+    assert!(
+        larger_cost_subnet_1.runtime - smaller_cost_subnet_1.runtime
+            > larger_cost_epoch_205.runtime - smaller_cost_epoch_205.runtime
+    );
+
+    // test ft-mint?, whose event carries a fixed-size payload
+    let exec = "(define-fungible-token tok)
+      (define-public (execute)
+        (begin (unwrap-panic (ft-mint? tok u10 tx-sender))
+               (ok 1)))";
+    let cost_epoch_205 = exec_cost(exec, use_mainnet, StacksEpochId::Epoch2_05);
+    let cost_subnet_1 = exec_cost(exec, use_mainnet, StacksEpochId::EpochSubnet1);
+
+    assert!(cost_subnet_1.write_length > cost_epoch_205.write_length);
+    assert!(cost_subnet_1.runtime > cost_epoch_205.runtime);
+}
+
+#[test]
+fn epoch_subnet_1_event_emission_mainnet() {
+    epoch_subnet_1_event_emission(true)
+}
+
+#[test]
+fn epoch_subnet_1_event_emission_testnet() {
+    epoch_subnet_1_event_emission(false)
+}
+
+// Test that `slice` is charged by .costs-3's `cost_slice` from epoch subnet-1 on, in proportion to
+// the length of the returned sub-sequence.
+fn epoch_subnet_1_slice(use_mainnet: bool) {
+    let buff = format!("0x{}", "ab".repeat(1000));
+    let smaller_exec = format!(
+        "(define-public (execute) (begin (slice {} u0 u1) (ok 1)))",
+        &buff
+    );
+    let larger_exec = format!(
+        "(define-public (execute) (begin (slice {} u0 u1000) (ok 1)))",
+        &buff
+    );
+    let smaller_cost_subnet_1 = exec_cost(&smaller_exec, use_mainnet, StacksEpochId::EpochSubnet1);
+    let larger_cost_subnet_1 = exec_cost(&larger_exec, use_mainnet, StacksEpochId::EpochSubnet1);
+
+    assert!(smaller_cost_subnet_1.runtime > 0);
+    assert!(larger_cost_subnet_1.runtime > smaller_cost_subnet_1.runtime);
+}
+
+#[test]
+fn epoch_subnet_1_slice_mainnet() {
+    epoch_subnet_1_slice(true)
+}
+
+#[test]
+fn epoch_subnet_1_slice_testnet() {
+    epoch_subnet_1_slice(false)
+}
+
+// Test that `replace-at?` is charged by .costs-3's `cost_replace_at` from epoch subnet-1 on, in
+// proportion to the length of the sequence.
+fn epoch_subnet_1_replace_at(use_mainnet: bool) {
+    let smaller_exec = format!(
+        "(define-public (execute) (begin (replace-at? 0x{} u0 0xff) (ok 1)))",
+        "ab".repeat(10)
+    );
+    let larger_exec = format!(
+        "(define-public (execute) (begin (replace-at? 0x{} u0 0xff) (ok 1)))",
+        "ab".repeat(1000)
+    );
+    let smaller_cost_subnet_1 = exec_cost(&smaller_exec, use_mainnet, StacksEpochId::EpochSubnet1);
+    let larger_cost_subnet_1 = exec_cost(&larger_exec, use_mainnet, StacksEpochId::EpochSubnet1);
+
+    assert!(smaller_cost_subnet_1.runtime > 0);
+    assert!(larger_cost_subnet_1.runtime > smaller_cost_subnet_1.runtime);
+}
+
+#[test]
+fn epoch_subnet_1_replace_at_mainnet() {
+    epoch_subnet_1_replace_at(true)
+}
+
+#[test]
+fn epoch_subnet_1_replace_at_testnet() {
+    epoch_subnet_1_replace_at(false)
+}
+
+// Test that `to-consensus-buff?` and `from-consensus-buff?` are charged by .costs-3's
+// `cost_to_consensus_buff` and `cost_from_consensus_buff` from epoch subnet-1 on, in proportion
+// to the length of the serialized value.
+fn epoch_subnet_1_consensus_buff(use_mainnet: bool) {
+    for native in ["to-consensus-buff?", "from-consensus-buff? (buff 1000)"].iter() {
+        let smaller_exec = format!(
+            "(define-public (execute) (begin ({} 0x{}) (ok 1)))",
+            native,
+            "ab".repeat(10)
+        );
+        let larger_exec = format!(
+            "(define-public (execute) (begin ({} 0x{}) (ok 1)))",
+            native,
+            "ab".repeat(1000)
+        );
+        let smaller_cost_subnet_1 =
+            exec_cost(&smaller_exec, use_mainnet, StacksEpochId::EpochSubnet1);
+        let larger_cost_subnet_1 =
+            exec_cost(&larger_exec, use_mainnet, StacksEpochId::EpochSubnet1);
+
+        assert!(smaller_cost_subnet_1.runtime > 0);
+        assert!(larger_cost_subnet_1.runtime > smaller_cost_subnet_1.runtime);
+    }
+}
+
+#[test]
+fn epoch_subnet_1_consensus_buff_mainnet() {
+    epoch_subnet_1_consensus_buff(true)
+}
+
+#[test]
+fn epoch_subnet_1_consensus_buff_testnet() {
+    epoch_subnet_1_consensus_buff(false)
+}
+
+// Test that the subnet cost profile's .costs-subnet charges a quarter of .costs-3's read and write
+// lengths for data var and map I/O, and leaves the rest of the cost unchanged.
+fn epoch_subnet_1_subnet_costs(use_mainnet: bool) {
+    let exec = "(define-data-var db (list 500 int) (list 1))
+      (define-map kv int (list 500 int))
+      (define-public (execute)
+        (begin (var-set db (list 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20))
+               (map-set kv 1 (var-get db))
+               (ok (map-get? kv 1))))";
+    let default_cost = exec_cost(exec, use_mainnet, StacksEpochId::EpochSubnet1);
+    let subnet_cost = exec_cost_with_costs(exec, use_mainnet, StacksEpochId::EpochSubnet1, true);
+
+    assert!(subnet_cost.read_length < default_cost.read_length / 2);
+    assert!(subnet_cost.write_length < default_cost.write_length / 2);
+    assert_eq!(subnet_cost.read_count, default_cost.read_count);
+    assert_eq!(subnet_cost.write_count, default_cost.write_count);
+    assert_eq!(subnet_cost.runtime, default_cost.runtime);
+
+    // before subnet-1, .costs-subnet is not instantiated
+    assert_eq!(
+        exec_cost(exec, use_mainnet, StacksEpochId::Epoch2_05),
+        exec_cost_with_costs(exec, use_mainnet, StacksEpochId::Epoch2_05, true)
+    );
+}
+
+#[test]
+fn epoch_subnet_1_subnet_costs_mainnet() {
+    epoch_subnet_1_subnet_costs(true)
+}
+
+#[test]
+fn epoch_subnet_1_subnet_costs_testnet() {
+    epoch_subnet_1_subnet_costs(false)
+}
+
+// Test that writing to a map whose size is tracked also charges for reading and writing its
+// entry count, on top of what a data var write of the same value costs.
+fn map_size_update_cost(use_mainnet: bool) {
+    let map_exec = "(define-map v int int)
+      (define-public (execute)
+        (begin (map-set v 1 1)
+               (ok 1)))";
+    let var_exec = "(define-data-var v int 0)
+      (define-public (execute)
+        (begin (var-set v 1)
+               (ok 1)))";
+    let map_cost = exec_cost(map_exec, use_mainnet, StacksEpochId::EpochSubnet1);
+    let var_cost = exec_cost(var_exec, use_mainnet, StacksEpochId::EpochSubnet1);
+
+    assert_eq!(map_cost.write_count, var_cost.write_count + 1);
+    assert_eq!(map_cost.read_count, var_cost.read_count + 1);
+    assert!(map_cost.write_length >= var_cost.write_length + 39);
+}
+
+#[test]
+fn map_size_update_cost_mainnet() {
+    map_size_update_cost(true)
+}
+
+#[test]
+fn map_size_update_cost_testnet() {
+    map_size_update_cost(false)
+}
+
 fn test_tracked_costs(prog: &str, use_mainnet: bool, epoch: StacksEpochId) -> ExecutionCost {
     let contract_trait = "(define-trait trait-1 (
                             (foo-exec (int) (response int int))
@@ -865,6 +1110,9 @@ fn test_all(use_mainnet: bool) {
     let baseline = test_tracked_costs("1", use_mainnet, StacksEpochId::Epoch20);
 
     for f in NativeFunctions::ALL.iter() {
+        if f.get_version() > ClarityVersion::default_for_epoch(StacksEpochId::Epoch20) {
+            continue;
+        }
         let test = get_simple_test(f);
         let cost = test_tracked_costs(test, use_mainnet, StacksEpochId::Epoch20);
         assert!(cost.exceeds(&baseline));
@@ -887,6 +1135,9 @@ fn epoch_205_test_all(use_mainnet: bool) {
     let baseline = test_tracked_costs("1", use_mainnet, StacksEpochId::Epoch2_05);
 
     for f in NativeFunctions::ALL.iter() {
+        if f.get_version() > ClarityVersion::default_for_epoch(StacksEpochId::Epoch2_05) {
+            continue;
+        }
         let test = get_simple_test(f);
         let cost = test_tracked_costs(test, use_mainnet, StacksEpochId::Epoch2_05);
         assert!(cost.exceeds(&baseline));
@@ -903,6 +1154,28 @@ fn epoch_205_test_all_testnet() {
     epoch_205_test_all(false)
 }
 
+// test each individual cost function can be correctly invoked as
+//  Clarity code executes in Epoch subnet-1, including the natives that .costs-3 prices
+fn epoch_subnet_1_test_all(use_mainnet: bool) {
+    let baseline = test_tracked_costs("1", use_mainnet, StacksEpochId::EpochSubnet1);
+
+    for f in NativeFunctions::ALL.iter() {
+        let test = get_simple_test(f);
+        let cost = test_tracked_costs(test, use_mainnet, StacksEpochId::EpochSubnet1);
+        assert!(cost.exceeds(&baseline));
+    }
+}
+
+#[test]
+fn epoch_subnet_1_test_all_mainnet() {
+    epoch_subnet_1_test_all(true)
+}
+
+#[test]
+fn epoch_subnet_1_test_all_testnet() {
+    epoch_subnet_1_test_all(false)
+}
+
 fn test_cost_contract_short_circuits(use_mainnet: bool) {
     let marf_kv = MarfedKV::temporary();
     let mut clarity_instance = ClarityInstance::new(use_mainnet, marf_kv);
@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use crate::chainstate::stacks::index::marf::MarfConnection;
 use crate::chainstate::stacks::index::storage::TrieFileStorage;
 use crate::chainstate::stacks::index::ClarityMarfTrieId;
 use clarity::vm::analysis::errors::CheckErrors;
@@ -29,7 +30,7 @@ use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
 use stacks_common::types::chainstate::BlockHeaderHash;
 use stacks_common::types::chainstate::StacksBlockId;
 
-use crate::clarity_vm::database::marf::MarfedKV;
+use crate::clarity_vm::database::marf::{MarfedKV, WritableMarfStore};
 
 const p1_str: &str = "'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR";
 
@@ -250,6 +251,108 @@ fn test_at_block_missing_defines() {
 // with b @ block 3;32
 // with z @ block 4;32
 
+#[test]
+fn test_list_data_map_entries_across_forks() {
+    let contract_identifier = QualifiedContractIdentifier::local("map-owner").unwrap();
+    let sender = PrincipalData::parse(&p1_str[1..]).unwrap();
+    let put = |store: &mut WritableMarfStore, key: i128| {
+        let mut owned_env =
+            OwnedEnvironment::new(store.as_clarity_db(&TEST_HEADER_DB, &TEST_BURN_STATE_DB));
+        let (result, _, _) = owned_env
+            .execute_transaction(
+                sender.clone(),
+                contract_identifier.clone(),
+                "put",
+                &symbols_from_values(vec![Value::Int(key)]),
+            )
+            .unwrap();
+        assert!(is_committed(&result));
+    };
+
+    let mut marf_kv = MarfedKV::temporary();
+    {
+        let mut store = marf_kv.begin(&StacksBlockId::sentinel(), &StacksBlockId([0 as u8; 32]));
+        store
+            .as_clarity_db(&TEST_HEADER_DB, &TEST_BURN_STATE_DB)
+            .initialize();
+        store.test_commit();
+    }
+    {
+        let mut store = marf_kv.begin(&StacksBlockId([0 as u8; 32]), &StacksBlockId([1 as u8; 32]));
+        let mut owned_env =
+            OwnedEnvironment::new(store.as_clarity_db(&TEST_HEADER_DB, &TEST_BURN_STATE_DB));
+        owned_env
+            .initialize_contract(
+                contract_identifier.clone(),
+                "(define-map entries int int)
+                 (define-public (put (key int)) (ok (map-set entries key key)))
+                 (map-insert entries 1 1)",
+            )
+            .unwrap();
+        store.test_commit();
+    }
+    // two forks off of block 1 write different keys
+    {
+        let mut store = marf_kv.begin(&StacksBlockId([1 as u8; 32]), &StacksBlockId([2 as u8; 32]));
+        put(&mut store, 2);
+        store.test_commit();
+    }
+    {
+        let mut store = marf_kv.begin(&StacksBlockId([1 as u8; 32]), &StacksBlockId([3 as u8; 32]));
+        put(&mut store, 3);
+        store.test_commit();
+    }
+    {
+        let mut store = marf_kv.begin(&StacksBlockId([2 as u8; 32]), &StacksBlockId([4 as u8; 32]));
+        put(&mut store, 4);
+        store.test_commit();
+    }
+    // a mined block that is never processed leaves no keys behind
+    {
+        let mut store = marf_kv.begin(&StacksBlockId([3 as u8; 32]), &StacksBlockId([5 as u8; 32]));
+        put(&mut store, 5);
+        store.commit_mined_block(&StacksBlockId([6 as u8; 32]));
+    }
+    let mined_keys: i64 = marf_kv
+        .get_marf()
+        .sqlite_conn()
+        .query_row(
+            "SELECT COUNT(*) FROM map_keys_table WHERE blockhash = ?",
+            &[&StacksBlockId([5 as u8; 32])],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(mined_keys, 0);
+
+    let mut list = |tip: u8, after: Option<i128>, limit: u32| {
+        let mut store = marf_kv.begin_read_only(Some(&StacksBlockId([tip; 32])));
+        let mut db = store.as_clarity_db(&TEST_HEADER_DB, &TEST_BURN_STATE_DB);
+        db.begin();
+        let entries = db
+            .list_data_map_entries(
+                &contract_identifier,
+                "entries",
+                after.map(Value::Int).as_ref(),
+                limit,
+            )
+            .unwrap()
+            .unwrap();
+        db.roll_back();
+        entries
+            .into_iter()
+            .map(|(key, _value)| key)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(list(3, None, 10), vec![Value::Int(1), Value::Int(3)]);
+    assert_eq!(
+        list(4, None, 10),
+        vec![Value::Int(1), Value::Int(2), Value::Int(4)]
+    );
+    // pages skip the keys of other forks
+    assert_eq!(list(3, Some(1), 1), vec![Value::Int(3)]);
+    assert_eq!(list(4, Some(2), 1), vec![Value::Int(4)]);
+}
+
 fn with_separate_forks_environment<F0, F1, F2, F3>(f: F0, a: F1, b: F2, z: F3)
 where
     F0: FnOnce(&mut OwnedEnvironment),
@@ -1,10 +1,16 @@
 use clarity::vm::contexts::OwnedEnvironment;
+use clarity::vm::database::BurnStateDB;
 use clarity::vm::errors::{Error, RuntimeErrorType};
 use clarity::vm::test_util::{TEST_BURN_STATE_DB, TEST_HEADER_DB};
 use clarity::vm::types::QualifiedContractIdentifier;
-use stacks_common::consts::{FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH};
+use clarity::vm::{StacksEpoch, Value};
+use stacks_common::consts::{
+    BITCOIN_REGTEST_FIRST_BLOCK_HASH, FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH,
+};
 use stacks_common::types::chainstate::BlockHeaderHash;
 use stacks_common::types::chainstate::StacksBlockId;
+use stacks_common::types::chainstate::{BurnchainHeaderHash, SortitionId};
+use stacks_common::types::StacksEpochId;
 
 use crate::chainstate::stacks::index::ClarityMarfTrieId;
 use crate::clarity_vm::database::marf::MarfedKV;
@@ -70,3 +76,87 @@ fn test_at_unknown_block() {
 
     with_marfed_environment(test, true);
 }
+
+/// Burn state in which L1 blocks 0 through 2 are only visible from the sortition of the
+/// parent of the block being evaluated.
+struct L1BlocksBurnStateDB {
+    sortition_id: SortitionId,
+}
+
+impl BurnStateDB for L1BlocksBurnStateDB {
+    fn get_burn_block_height(&self, sortition_id: &SortitionId) -> Option<u32> {
+        TEST_BURN_STATE_DB.get_burn_block_height(sortition_id)
+    }
+
+    fn get_burn_header_hash(
+        &self,
+        height: u32,
+        sortition_id: &SortitionId,
+    ) -> Option<BurnchainHeaderHash> {
+        if *sortition_id == self.sortition_id && height <= 2 {
+            Some(BurnchainHeaderHash([height as u8 + 1; 32]))
+        } else {
+            None
+        }
+    }
+
+    fn get_burn_header_timestamp(&self, height: u32, sortition_id: &SortitionId) -> Option<u64> {
+        if *sortition_id == self.sortition_id && height <= 2 {
+            Some(1_600_000_000 + height as u64)
+        } else {
+            None
+        }
+    }
+
+    fn get_stacks_epoch(&self, height: u32) -> Option<StacksEpoch> {
+        TEST_BURN_STATE_DB.get_stacks_epoch(height)
+    }
+
+    fn get_stacks_epoch_by_epoch_id(&self, epoch_id: &StacksEpochId) -> Option<StacksEpoch> {
+        TEST_BURN_STATE_DB.get_stacks_epoch_by_epoch_id(epoch_id)
+    }
+}
+
+#[test]
+fn test_get_l1_block_info() {
+    let first_block = StacksBlockId::new(&FIRST_BURNCHAIN_CONSENSUS_HASH, &FIRST_STACKS_BLOCK_HASH);
+    // the first block was mined in the regtest genesis sortition
+    let burn_state_db = L1BlocksBurnStateDB {
+        sortition_id: SortitionId::new(
+            &BurnchainHeaderHash::from_hex(BITCOIN_REGTEST_FIRST_BLOCK_HASH).unwrap(),
+        ),
+    };
+
+    let mut marf_kv = MarfedKV::temporary();
+    {
+        let mut store = marf_kv.begin(&StacksBlockId::sentinel(), &first_block);
+        store
+            .as_clarity_db(&TEST_HEADER_DB, &TEST_BURN_STATE_DB)
+            .initialize();
+        store.test_commit();
+    }
+
+    let mut store = marf_kv.begin(&first_block, &StacksBlockId([1 as u8; 32]));
+    let mut owned_env = OwnedEnvironment::new(store.as_clarity_db(&TEST_HEADER_DB, &burn_state_db));
+
+    let tests = [
+        (
+            "(get-l1-block-info? header-hash u1)",
+            Value::some(Value::buff_from(vec![2; 32]).unwrap()).unwrap(),
+        ),
+        (
+            "(get-l1-block-info? time u2)",
+            Value::some(Value::UInt(1_600_000_002)).unwrap(),
+        ),
+        // not yet known to the L1 fork of the parent block
+        ("(get-l1-block-info? header-hash u3)", Value::none()),
+        ("(get-l1-block-info? time u3)", Value::none()),
+        // out of range
+        ("(get-l1-block-info? time u4294967296)", Value::none()),
+    ];
+
+    for (program, expected) in tests.iter() {
+        let (result, _, _) = owned_env.eval_raw(program).unwrap();
+        assert_eq!(&result, expected, "Unexpected result for {}", program);
+    }
+}
@@ -1,20 +1,18 @@
-use crate::chainstate::stacks::events::StacksTransactionReceipt;
-use clarity::codec::StacksMessageCodec;
+use crate::chainstate::stacks::events::{StacksTransactionReceipt, TransactionOrigin};
+use crate::chainstate::stacks::subnet_codec;
+use crate::chainstate::stacks::TransactionPayload;
 use clarity::types::chainstate::{BlockHeaderHash, ConsensusHash, StacksBlockId, TrieHash};
-use clarity::util::hash::{MerkleTree, Sha512Trunc256Sum};
+use clarity::util::hash::{MerklePath, MerkleTree, Sha512Trunc256Sum};
 use clarity::vm::database::ClarityBackingStore;
+use clarity::vm::events;
 use clarity::vm::events::{
     FTEventType, FTWithdrawEventData, NFTEventType, NFTWithdrawEventData, STXEventType,
-    STXWithdrawEventData, StacksTransactionEvent,
+    STXWithdrawEventData, STXWithdrawTipEventData, StacksTransactionEvent,
 };
-use clarity::vm::types::{AssetIdentifier, PrincipalData, SequenceData, TupleData};
+use clarity::vm::types::{AssetIdentifier, PrincipalData, SequenceData};
 use clarity::vm::Value;
 use regex::internal::Input;
-
-fn clarity_ascii_str(input: &str) -> Value {
-    Value::string_ascii_from_bytes(input.as_bytes().to_vec())
-        .expect("Supplied string was not ASCII")
-}
+use std::fmt;
 
 pub fn buffer_from_hash(hash: Sha512Trunc256Sum) -> Value {
     Value::buff_from(hash.0.to_vec()).expect("Failed to construct buffer from hash")
@@ -55,6 +53,10 @@ pub fn buffer_from_hash(hash: Sha512Trunc256Sum) -> Value {
 ///     recipient: principal,
 ///     amount: u128 }
 /// ```
+///
+/// A miner tip paid alongside a withdrawal is an `stx` key whose recipient is the miner of the
+/// block.  Tip events whose recipient has not been set yet (see `generate_withdrawal_keys()`)
+/// produce no key.
 
 pub fn generate_key_from_event(
     event: &mut StacksTransactionEvent,
@@ -74,6 +76,11 @@ pub fn generate_key_from_event(
             data.withdrawal_id = Some(withdrawal_id);
             Some(make_key_for_stx_withdrawal_event(data, block_height))
         }
+        StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawTipEvent(data)) => {
+            data.recipient.as_ref()?;
+            data.withdrawal_id = Some(withdrawal_id);
+            Some(make_key_for_stx_withdrawal_tip_event(data, block_height))
+        }
         _ => None,
     }
 }
@@ -131,24 +138,34 @@ pub fn make_key_for_stx_withdrawal_event(data: &STXWithdrawEventData, block_heig
     make_key_for_stx_withdrawal(&data.sender, withdrawal_id, data.amount, block_height)
 }
 
+pub fn make_key_for_stx_withdrawal_tip_event(
+    data: &STXWithdrawTipEventData,
+    block_height: u64,
+) -> Value {
+    let withdrawal_id = data
+        .withdrawal_id
+        .expect("Tried to serialize a withdraw tip event before setting withdrawal ID");
+    let recipient = data
+        .recipient
+        .as_ref()
+        .expect("Tried to serialize a withdraw tip event before setting its recipient");
+    info!("Parsed L2 withdrawal tip event";
+          "type" => "stx",
+          "block_height" => block_height,
+          "sender" => %data.sender,
+          "recipient" => %recipient,
+          "withdrawal_id" => withdrawal_id,
+          "amount" => %data.amount);
+    make_key_for_stx_withdrawal(recipient, withdrawal_id, data.amount, block_height)
+}
+
 pub fn make_key_for_stx_withdrawal(
     recipient: &PrincipalData,
     withdrawal_id: u32,
     amount: u128,
     block_height: u64,
 ) -> Value {
-    TupleData::from_data(vec![
-        ("type".into(), clarity_ascii_str("stx")),
-        ("height".into(), Value::UInt(u128::from(block_height))),
-        (
-            "withdrawal-id".into(),
-            Value::UInt(u128::from(withdrawal_id)),
-        ),
-        ("recipient".into(), Value::Principal(recipient.clone())),
-        ("amount".into(), Value::UInt(amount)),
-    ])
-    .expect("Withdrawal key tuple is too large for Clarity")
-    .into()
+    events::make_key_for_stx_withdrawal(recipient, withdrawal_id, amount, block_height)
 }
 
 pub fn make_key_for_nft_withdrawal(
@@ -158,22 +175,13 @@ pub fn make_key_for_nft_withdrawal(
     id: u128,
     block_height: u64,
 ) -> Value {
-    let asset_contract = Value::Principal(PrincipalData::from(
-        asset_identifier.contract_identifier.clone(),
-    ));
-    TupleData::from_data(vec![
-        ("type".into(), clarity_ascii_str("nft")),
-        ("asset-contract".into(), asset_contract),
-        ("height".into(), Value::UInt(u128::from(block_height))),
-        (
-            "withdrawal-id".into(),
-            Value::UInt(u128::from(withdrawal_id)),
-        ),
-        ("recipient".into(), Value::Principal(sender.clone())),
-        ("nft-id".into(), Value::UInt(id)),
-    ])
-    .expect("Withdrawal key tuple is too large for Clarity")
-    .into()
+    events::make_key_for_nft_withdrawal(
+        sender,
+        withdrawal_id,
+        &asset_identifier.contract_identifier,
+        id,
+        block_height,
+    )
 }
 
 pub fn make_key_for_ft_withdrawal(
@@ -183,39 +191,80 @@ pub fn make_key_for_ft_withdrawal(
     amount: u128,
     block_height: u64,
 ) -> Value {
-    let asset_contract = Value::Principal(PrincipalData::from(
-        asset_identifier.contract_identifier.clone(),
-    ));
-    TupleData::from_data(vec![
-        ("type".into(), clarity_ascii_str("ft")),
-        ("asset-contract".into(), asset_contract),
-        ("height".into(), Value::UInt(u128::from(block_height))),
-        (
-            "withdrawal-id".into(),
-            Value::UInt(u128::from(withdrawal_id)),
-        ),
-        ("recipient".into(), Value::Principal(sender.clone())),
-        ("amount".into(), Value::UInt(amount)),
-    ])
-    .expect("Withdrawal key tuple is too large for Clarity")
-    .into()
+    events::make_key_for_ft_withdrawal(
+        sender,
+        withdrawal_id,
+        &asset_identifier.contract_identifier,
+        amount,
+        block_height,
+    )
 }
 
 pub fn convert_withdrawal_key_to_bytes(key: &Value) -> Vec<u8> {
-    key.serialize_to_vec()
+    subnet_codec::withdrawal_leaf_bytes(key)
+}
+
+/// Get the miner of the block with these transaction receipts: the origin of its coinbase.
+pub fn get_block_miner(tx_receipts: &[StacksTransactionReceipt]) -> Option<PrincipalData> {
+    tx_receipts
+        .iter()
+        .find_map(|receipt| match receipt.transaction {
+            TransactionOrigin::Stacks(ref tx) => match tx.payload {
+                TransactionPayload::Coinbase(..) => Some(tx.origin_address().into()),
+                _ => None,
+            },
+            TransactionOrigin::Burn(_) => None,
+        })
+}
+
+/// Does this transaction receipt withdraw anything without paying a miner tip of at least
+/// `min_tip` uSTX alongside it?  Each withdrawal's tip event immediately follows its withdraw
+/// event.
+pub fn withdrawal_tips_below_minimum(receipt: &StacksTransactionReceipt, min_tip: u128) -> bool {
+    if min_tip == 0 {
+        return false;
+    }
+    let mut events = receipt.events.iter().peekable();
+    while let Some(event) = events.next() {
+        let is_withdrawal = matches!(
+            event,
+            StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawEvent(_))
+                | StacksTransactionEvent::FTEvent(FTEventType::FTWithdrawEvent(_))
+                | StacksTransactionEvent::NFTEvent(NFTEventType::NFTWithdrawEvent(_))
+        );
+        if !is_withdrawal {
+            continue;
+        }
+        let tip = match events.peek() {
+            Some(StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawTipEvent(data))) => {
+                data.amount
+            }
+            _ => 0,
+        };
+        if tip < min_tip {
+            return true;
+        }
+    }
+    false
 }
 
 /// The order of withdrawal events in the transaction receipts will determine the withdrawal IDs
 /// that correspond to each event. These IDs are used to generate the withdrawal key that is
 /// ultimately inserted in the withdrawal Merkle tree.
+/// Miner tips are paid to the miner of the block, so their recipient is filled in here.
 pub fn generate_withdrawal_keys(
     tx_receipts: &mut [StacksTransactionReceipt],
     block_height: u64,
 ) -> Vec<Vec<u8>> {
+    let miner = get_block_miner(tx_receipts);
     let mut items = Vec::new();
     let mut withdrawal_id = 0;
     for receipt in tx_receipts.iter_mut() {
         for event in receipt.events.iter_mut() {
+            if let StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawTipEvent(data)) = event
+            {
+                data.recipient = miner.clone();
+            }
             if let Some(key) = generate_key_from_event(event, withdrawal_id, block_height) {
                 withdrawal_id += 1;
                 items.push(convert_withdrawal_key_to_bytes(&key));
@@ -240,6 +289,155 @@ pub fn create_withdrawal_merkle_tree(
     MerkleTree::<Sha512Trunc256Sum>::new(&items)
 }
 
+/// Get the leaf hashes of the withdrawal Merkle tree of these transaction receipts, in leaf
+/// order (i.e., the leaf at index `i` is the leaf of the withdrawal with ID `i`).
+pub fn get_withdrawal_leaf_hashes(
+    tx_receipts: &mut [StacksTransactionReceipt],
+    block_height: u64,
+) -> Vec<Sha512Trunc256Sum> {
+    generate_withdrawal_keys(tx_receipts, block_height)
+        .iter()
+        .map(|key| MerkleTree::<Sha512Trunc256Sum>::get_leaf_hash(key))
+        .collect()
+}
+
+/// The first leaf at which a block's claimed withdrawal leaves and the withdrawal leaves
+/// recomputed from its events differ.  A leaf is `None` if that side has fewer leaves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DivergentWithdrawalLeaf {
+    pub index: usize,
+    pub expected: Option<Sha512Trunc256Sum>,
+    pub computed: Option<Sha512Trunc256Sum>,
+}
+
+/// A block's header commits to a withdrawal root that is not the root of the withdrawal tree
+/// recomputed from the block's events.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithdrawalRootMismatch {
+    /// root committed to in the block header
+    pub expected_root: Sha512Trunc256Sum,
+    /// root of the tree recomputed from the block's events
+    pub computed_root: Sha512Trunc256Sum,
+    /// leaves of the tree recomputed from the block's events
+    pub computed_leaves: Vec<Sha512Trunc256Sum>,
+    /// only known if the block's producer supplied the leaves of its withdrawal tree
+    pub first_divergent_leaf: Option<DivergentWithdrawalLeaf>,
+}
+
+impl fmt::Display for WithdrawalRootMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Withdrawal root mismatch: header has {}, events produce {} ({} leaves)",
+            self.expected_root,
+            self.computed_root,
+            self.computed_leaves.len()
+        )?;
+        let format_leaf = |leaf: &Option<Sha512Trunc256Sum>| match leaf {
+            Some(leaf) => leaf.to_string(),
+            None => "none".to_string(),
+        };
+        match self.first_divergent_leaf {
+            Some(ref leaf) => write!(
+                f,
+                "; first divergent leaf is {}: expected {}, computed {}",
+                leaf.index,
+                format_leaf(&leaf.expected),
+                format_leaf(&leaf.computed)
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Find the first index at which two lists of withdrawal leaves differ, if any.
+pub fn find_first_divergent_leaf(
+    expected_leaves: &[Sha512Trunc256Sum],
+    computed_leaves: &[Sha512Trunc256Sum],
+) -> Option<DivergentWithdrawalLeaf> {
+    let num_leaves = expected_leaves.len().max(computed_leaves.len());
+    (0..num_leaves)
+        .map(|index| DivergentWithdrawalLeaf {
+            index,
+            expected: expected_leaves.get(index).cloned(),
+            computed: computed_leaves.get(index).cloned(),
+        })
+        .find(|leaf| leaf.expected != leaf.computed)
+}
+
+/// Build the withdrawal Merkle tree from the leaves recomputed from a block's events, and check
+/// that its root is `expected_root`, the root committed to by the block header.  If the block's
+/// producer supplied the leaves of its withdrawal tree in `expected_leaves`, a mismatch reports
+/// the first leaf at which the two trees diverge.
+pub fn check_withdrawal_root(
+    computed_leaves: Vec<Sha512Trunc256Sum>,
+    expected_root: &Sha512Trunc256Sum,
+    expected_leaves: Option<&[Sha512Trunc256Sum]>,
+) -> Result<MerkleTree<Sha512Trunc256Sum>, WithdrawalRootMismatch> {
+    let withdrawal_tree = MerkleTree::from_leaf_hashes(computed_leaves.clone());
+    let computed_root = withdrawal_tree.root();
+    if &computed_root == expected_root {
+        return Ok(withdrawal_tree);
+    }
+
+    let first_divergent_leaf = expected_leaves
+        .and_then(|expected_leaves| find_first_divergent_leaf(expected_leaves, &computed_leaves));
+    Err(WithdrawalRootMismatch {
+        expected_root: expected_root.clone(),
+        computed_root,
+        computed_leaves,
+        first_divergent_leaf,
+    })
+}
+
+/// Withdrawal batching settings.  When batching is enabled, the withdrawal roots of subnet blocks
+/// are not registered with the L1 one block at a time.  Instead, consecutive blocks are grouped
+/// into fixed-size batches, and a single root committing to every block root in the batch is
+/// registered once the batch is complete.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WithdrawalBatchConfig {
+    /// number of subnet blocks in each batch
+    pub batch_blocks: u64,
+    /// height of the first subnet block whose withdrawals are batched
+    pub start_height: u64,
+}
+
+impl WithdrawalBatchConfig {
+    /// Get the first and last block heights of the batch containing the block at
+    /// `block_height`, or None if that block's withdrawals are not batched.
+    pub fn batch_range(&self, block_height: u64) -> Option<(u64, u64)> {
+        if self.batch_blocks == 0 || block_height < self.start_height {
+            return None;
+        }
+        let first_height = self.start_height
+            + ((block_height - self.start_height) / self.batch_blocks) * self.batch_blocks;
+        Some((first_height, first_height + self.batch_blocks - 1))
+    }
+}
+
+/// Put the withdrawal roots of a batch of consecutive blocks into a single Merkle tree.
+/// The block roots are the leaves of the tree, so that a withdrawal's path in its block's tree
+/// can be extended with the block root's path in this tree (see
+/// `extend_withdrawal_path_to_batch()`).
+pub fn create_withdrawal_batch_merkle_tree(
+    block_withdrawal_roots: &[Sha512Trunc256Sum],
+) -> MerkleTree<Sha512Trunc256Sum> {
+    MerkleTree::from_leaf_hashes(block_withdrawal_roots.to_vec())
+}
+
+/// Extend the path of a withdrawal key in its block's withdrawal tree into a path up to the root
+/// of the batch tree, given the block's index in the batch.
+/// Returns None if there is no block at that index.
+pub fn extend_withdrawal_path_to_batch(
+    block_path: MerklePath<Sha512Trunc256Sum>,
+    batch_tree: &MerkleTree<Sha512Trunc256Sum>,
+    block_index: usize,
+) -> Option<MerklePath<Sha512Trunc256Sum>> {
+    let mut path = block_path;
+    path.extend(batch_tree.path_at_index(block_index)?);
+    Some(path)
+}
+
 #[cfg(test)]
 mod test {
     use clarity::types::chainstate::StacksAddress;
@@ -247,6 +445,7 @@ mod test {
     use clarity::util::hash::to_hex;
     use clarity::vm::types::StandardPrincipalData;
 
+    use crate::burnchains::Txid;
     use crate::chainstate::stacks::events::{StacksTransactionReceipt, TransactionOrigin};
     use crate::chainstate::stacks::{
         CoinbasePayload, StacksTransaction, TransactionAuth, TransactionPayload,
@@ -259,18 +458,33 @@ mod test {
     use crate::clarity::vm::costs::ExecutionCost;
     use crate::clarity::vm::events::FTEventType::FTWithdrawEvent;
     use crate::clarity::vm::events::NFTEventType::NFTWithdrawEvent;
-    use crate::clarity::vm::events::STXEventType::STXWithdrawEvent;
-    use crate::clarity::vm::events::{STXWithdrawEventData, StacksTransactionEvent};
+    use crate::clarity::vm::events::STXEventType::{STXWithdrawEvent, STXWithdrawTipEvent};
+    use crate::clarity::vm::events::{
+        STXWithdrawEventData, STXWithdrawTipEventData, StacksTransactionEvent,
+    };
     use crate::clarity::vm::types::{AssetIdentifier, QualifiedContractIdentifier};
     use crate::clarity::vm::Value;
     use crate::clarity_vm::withdrawal::{
-        convert_withdrawal_key_to_bytes, create_withdrawal_merkle_tree, generate_key_from_event,
+        check_withdrawal_root, convert_withdrawal_key_to_bytes,
+        create_withdrawal_batch_merkle_tree, create_withdrawal_merkle_tree,
+        extend_withdrawal_path_to_batch, generate_key_from_event, generate_withdrawal_keys,
+        withdrawal_tips_below_minimum, DivergentWithdrawalLeaf, WithdrawalBatchConfig,
     };
     use crate::net::test::to_addr;
     use crate::vm::events::{FTWithdrawEventData, NFTWithdrawEventData};
     use crate::vm::ClarityName;
     use crate::vm::ContractName;
 
+    use super::{buffer_from_hash, make_key_for_nft_withdrawal, make_key_for_stx_withdrawal};
+    use crate::chainstate::stacks::index::ClarityMarfTrieId;
+    use crate::clarity_vm::database::marf::MarfedKV;
+    use clarity::vm::contexts::OwnedEnvironment;
+    use clarity::vm::database::HeadersDB;
+    use clarity::vm::test_util::{TEST_BURN_STATE_DB, TEST_HEADER_DB};
+    use clarity::vm::types::{PrincipalData, TupleData};
+    use stacks_common::consts::{FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH};
+    use stacks_common::types::chainstate::{BurnchainHeaderHash, VRFSeed};
+
     #[test]
     fn test_verify_withdrawal_merkle_tree() {
         let pk: StacksPrivateKey = StacksPrivateKey::from_hex(
@@ -399,4 +613,373 @@ mod test {
         );
         assert_eq!(root_hash, calculated_root_hash);
     }
+
+    /// Serves a fixed withdrawal tree for the block at height 1, and delegates everything else
+    /// to the unit test headers DB.
+    struct WithdrawalTestHeadersDB {
+        block_1: StacksBlockId,
+        withdrawal_tree: MerkleTree<Sha512Trunc256Sum>,
+    }
+
+    impl HeadersDB for WithdrawalTestHeadersDB {
+        fn get_stacks_block_header_hash_for_block(
+            &self,
+            id_bhh: &StacksBlockId,
+        ) -> Option<BlockHeaderHash> {
+            TEST_HEADER_DB.get_stacks_block_header_hash_for_block(id_bhh)
+        }
+        fn get_burn_header_hash_for_block(
+            &self,
+            id_bhh: &StacksBlockId,
+        ) -> Option<BurnchainHeaderHash> {
+            TEST_HEADER_DB.get_burn_header_hash_for_block(id_bhh)
+        }
+        fn get_vrf_seed_for_block(&self, id_bhh: &StacksBlockId) -> Option<VRFSeed> {
+            TEST_HEADER_DB.get_vrf_seed_for_block(id_bhh)
+        }
+        fn get_burn_block_time_for_block(&self, id_bhh: &StacksBlockId) -> Option<u64> {
+            TEST_HEADER_DB.get_burn_block_time_for_block(id_bhh)
+        }
+        fn get_burn_block_height_for_block(&self, id_bhh: &StacksBlockId) -> Option<u32> {
+            TEST_HEADER_DB.get_burn_block_height_for_block(id_bhh)
+        }
+        fn get_miner_address(&self, id_bhh: &StacksBlockId) -> Option<StacksAddress> {
+            TEST_HEADER_DB.get_miner_address(id_bhh)
+        }
+        fn get_withdrawal_tree_for_block(
+            &self,
+            id_bhh: &StacksBlockId,
+        ) -> Option<MerkleTree<Sha512Trunc256Sum>> {
+            if *id_bhh == self.block_1 {
+                Some(self.withdrawal_tree.clone())
+            } else {
+                TEST_HEADER_DB.get_withdrawal_tree_for_block(id_bhh)
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_withdrawal_info() {
+        let sender = PrincipalData::parse("SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF").unwrap();
+        let nft_contract =
+            QualifiedContractIdentifier::parse("SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF.nft")
+                .unwrap();
+
+        // block 1 contains an STX withdrawal of 100 and a withdrawal of NFT 7
+        let stx_key =
+            convert_withdrawal_key_to_bytes(&make_key_for_stx_withdrawal(&sender, 0, 100, 1));
+        let nft_key = convert_withdrawal_key_to_bytes(&make_key_for_nft_withdrawal(
+            &sender,
+            1,
+            &AssetIdentifier {
+                contract_identifier: nft_contract.clone(),
+                asset_name: ClarityName::from("nft-token"),
+            },
+            7,
+            1,
+        ));
+        let withdrawal_tree =
+            MerkleTree::<Sha512Trunc256Sum>::new(&vec![stx_key.clone(), nft_key.clone()]);
+
+        let first_block =
+            StacksBlockId::new(&FIRST_BURNCHAIN_CONSENSUS_HASH, &FIRST_STACKS_BLOCK_HASH);
+        let block_1 = StacksBlockId([1; 32]);
+        let headers_db = WithdrawalTestHeadersDB {
+            block_1: block_1.clone(),
+            withdrawal_tree: withdrawal_tree.clone(),
+        };
+
+        let mut marf_kv = MarfedKV::temporary();
+        {
+            let mut store = marf_kv.begin(&StacksBlockId::sentinel(), &first_block);
+            store
+                .as_clarity_db(&TEST_HEADER_DB, &TEST_BURN_STATE_DB)
+                .initialize();
+            store.test_commit();
+        }
+        marf_kv.begin(&first_block, &block_1).test_commit();
+
+        let mut store = marf_kv.begin(&block_1, &StacksBlockId([2; 32]));
+        let mut owned_env =
+            OwnedEnvironment::new(store.as_clarity_db(&headers_db, &TEST_BURN_STATE_DB));
+
+        let expected_info = |leaf: &[u8]| {
+            Value::some(
+                TupleData::from_data(vec![
+                    (
+                        "withdrawal-root".into(),
+                        buffer_from_hash(withdrawal_tree.root()),
+                    ),
+                    (
+                        "withdrawal-leaf-hash".into(),
+                        buffer_from_hash(MerkleTree::<Sha512Trunc256Sum>::get_leaf_hash(leaf)),
+                    ),
+                ])
+                .unwrap()
+                .into(),
+            )
+            .unwrap()
+        };
+
+        let tests = [
+            (
+                "(get-withdrawal-info? stx u1 u0 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF u100)",
+                expected_info(&stx_key),
+            ),
+            (
+                "(get-withdrawal-info? nft u1 u1 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF.nft u7)",
+                expected_info(&nft_key),
+            ),
+            // wrong amount
+            (
+                "(get-withdrawal-info? stx u1 u0 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF u99)",
+                Value::none(),
+            ),
+            // wrong withdrawal ID
+            (
+                "(get-withdrawal-info? stx u1 u1 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF u100)",
+                Value::none(),
+            ),
+            // wrong asset type
+            (
+                "(get-withdrawal-info? ft u1 u1 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF.nft u7)",
+                Value::none(),
+            ),
+            // assets are only defined by contracts
+            (
+                "(get-withdrawal-info? nft u1 u1 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF u7)",
+                Value::none(),
+            ),
+            // wrong block
+            (
+                "(get-withdrawal-info? stx u0 u0 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF u100)",
+                Value::none(),
+            ),
+            // the current block's withdrawals are not known yet
+            (
+                "(get-withdrawal-info? stx u2 u0 'SPAXYA5XS51713FDTQ8H94EJ4V579CXMTRNBZKSF u100)",
+                Value::none(),
+            ),
+        ];
+
+        for (program, expected) in tests.iter() {
+            let (result, _, _) = owned_env.eval_raw(program).unwrap();
+            assert_eq!(&result, expected, "Unexpected result for {}", program);
+        }
+    }
+
+    #[test]
+    fn test_withdrawal_batch_range() {
+        let batching = WithdrawalBatchConfig {
+            batch_blocks: 4,
+            start_height: 3,
+        };
+        assert_eq!(batching.batch_range(2), None);
+        assert_eq!(batching.batch_range(3), Some((3, 6)));
+        assert_eq!(batching.batch_range(6), Some((3, 6)));
+        assert_eq!(batching.batch_range(7), Some((7, 10)));
+        assert_eq!(batching.batch_range(12), Some((11, 14)));
+
+        let disabled = WithdrawalBatchConfig {
+            batch_blocks: 0,
+            start_height: 1,
+        };
+        assert_eq!(disabled.batch_range(5), None);
+    }
+
+    #[test]
+    fn test_verify_withdrawal_batch_merkle_tree() {
+        let block_keys: Vec<Vec<Vec<u8>>> = vec![
+            vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]],
+            vec![],
+            vec![vec![10, 11], vec![12, 13]],
+        ];
+        let block_trees: Vec<_> = block_keys
+            .iter()
+            .map(|keys| MerkleTree::<Sha512Trunc256Sum>::new(keys))
+            .collect();
+        let block_roots: Vec<_> = block_trees.iter().map(|tree| tree.root()).collect();
+        assert_eq!(block_roots[1], Sha512Trunc256Sum([0; 32]));
+
+        let batch_tree = create_withdrawal_batch_merkle_tree(&block_roots);
+        let batch_root = batch_tree.root();
+        assert!(batch_root != block_roots[0]);
+
+        for (block_index, keys) in block_keys.iter().enumerate() {
+            for key in keys.iter() {
+                let block_path = block_trees[block_index].path(key).unwrap();
+                assert!(!MerkleTree::path_verify(key, &block_path, &batch_root));
+
+                let path =
+                    extend_withdrawal_path_to_batch(block_path, &batch_tree, block_index).unwrap();
+                assert!(MerkleTree::path_verify(key, &path, &batch_root));
+                assert!(!MerkleTree::path_verify(
+                    key,
+                    &path,
+                    &block_roots[block_index]
+                ));
+            }
+        }
+
+        // a key in one block cannot be proven with another block's position in the batch
+        let block_path = block_trees[2].path(&block_keys[2][0]).unwrap();
+        let path = extend_withdrawal_path_to_batch(block_path, &batch_tree, 0).unwrap();
+        assert!(!MerkleTree::path_verify(
+            &block_keys[2][0],
+            &path,
+            &batch_root
+        ));
+
+        // no block past the end of the batch
+        let block_path = block_trees[0].path(&block_keys[0][0]).unwrap();
+        assert!(extend_withdrawal_path_to_batch(block_path, &batch_tree, 3).is_none());
+    }
+
+    #[test]
+    fn test_check_withdrawal_root() {
+        let leaves: Vec<_> = (0u8..3)
+            .map(|i| MerkleTree::<Sha512Trunc256Sum>::get_leaf_hash(&[i]))
+            .collect();
+        let root = MerkleTree::<Sha512Trunc256Sum>::from_leaf_hashes(leaves.clone()).root();
+
+        let tree = check_withdrawal_root(leaves.clone(), &root, None).unwrap();
+        assert_eq!(tree.root(), root);
+
+        // the claimed leaves are only consulted on a mismatch
+        let other_leaf = MerkleTree::<Sha512Trunc256Sum>::get_leaf_hash(&[9]);
+        let wrong_leaves = vec![leaves[0].clone(), other_leaf.clone(), leaves[2].clone()];
+        assert!(check_withdrawal_root(leaves.clone(), &root, Some(&wrong_leaves)).is_ok());
+
+        let wrong_root =
+            MerkleTree::<Sha512Trunc256Sum>::from_leaf_hashes(wrong_leaves.clone()).root();
+
+        // without the claimed leaves, the divergent leaf is unknown
+        let mismatch = check_withdrawal_root(leaves.clone(), &wrong_root, None).unwrap_err();
+        assert_eq!(mismatch.expected_root, wrong_root);
+        assert_eq!(mismatch.computed_root, root);
+        assert_eq!(mismatch.computed_leaves, leaves);
+        assert_eq!(mismatch.first_divergent_leaf, None);
+
+        let mismatch =
+            check_withdrawal_root(leaves.clone(), &wrong_root, Some(&wrong_leaves)).unwrap_err();
+        assert_eq!(
+            mismatch.first_divergent_leaf,
+            Some(DivergentWithdrawalLeaf {
+                index: 1,
+                expected: Some(other_leaf),
+                computed: Some(leaves[1].clone()),
+            })
+        );
+        assert!(mismatch.to_string().contains(&format!(
+            "first divergent leaf is 1: expected {}",
+            other_leaf
+        )));
+
+        // a missing withdrawal is reported at the first index past the shorter list
+        let extra_leaves = vec![
+            leaves[0].clone(),
+            leaves[1].clone(),
+            leaves[2].clone(),
+            other_leaf.clone(),
+        ];
+        let extra_root =
+            MerkleTree::<Sha512Trunc256Sum>::from_leaf_hashes(extra_leaves.clone()).root();
+        let mismatch =
+            check_withdrawal_root(leaves.clone(), &extra_root, Some(&extra_leaves)).unwrap_err();
+        assert_eq!(
+            mismatch.first_divergent_leaf,
+            Some(DivergentWithdrawalLeaf {
+                index: 3,
+                expected: Some(other_leaf),
+                computed: None,
+            })
+        );
+        assert!(mismatch.to_string().ends_with("computed none"));
+    }
+
+    #[test]
+    fn test_withdrawal_tip_pays_block_miner() {
+        let miner_pk: StacksPrivateKey = StacksPrivateKey::from_hex(
+            "aaf57b4730f713cf942bc63f0801c4a62abe5a6ac8e3da10389f9ca3420b0dc701",
+        )
+        .unwrap();
+        let miner_addr = to_addr(&miner_pk);
+        let user_addr =
+            StacksAddress::from_string("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM").unwrap();
+
+        let mut spending_condition = TransactionSpendingCondition::new_singlesig_p2pkh(
+            StacksPublicKey::from_private(&miner_pk),
+        )
+        .expect("Failed to create p2pkh spending condition from public key.");
+        spending_condition.set_nonce(0);
+        spending_condition.set_tx_fee(1000);
+        let auth = TransactionAuth::Standard(spending_condition);
+
+        let make_receipt = |transaction: TransactionOrigin, events: Vec<StacksTransactionEvent>| {
+            StacksTransactionReceipt {
+                transaction,
+                events,
+                post_condition_aborted: false,
+                result: Value::okay_true(),
+                stx_burned: 0,
+                contract_analysis: None,
+                execution_cost: ExecutionCost::zero(),
+                microblock_header: None,
+                tx_index: 0,
+            }
+        };
+        let coinbase_receipt = make_receipt(
+            TransactionOrigin::Stacks(StacksTransaction::new(
+                TransactionVersion::Testnet,
+                auth,
+                TransactionPayload::Coinbase(CoinbasePayload([0u8; 32])),
+            )),
+            vec![],
+        );
+        let withdrawal_receipt = make_receipt(
+            TransactionOrigin::Burn(Txid([0x01; 32])),
+            vec![
+                StacksTransactionEvent::STXEvent(STXWithdrawEvent(STXWithdrawEventData {
+                    sender: user_addr.into(),
+                    amount: 100,
+                    withdrawal_id: None,
+                })),
+                StacksTransactionEvent::STXEvent(STXWithdrawTipEvent(STXWithdrawTipEventData {
+                    sender: user_addr.into(),
+                    amount: 5,
+                    recipient: None,
+                    withdrawal_id: None,
+                })),
+            ],
+        );
+
+        assert!(!withdrawal_tips_below_minimum(&withdrawal_receipt, 5));
+        assert!(withdrawal_tips_below_minimum(&withdrawal_receipt, 6));
+
+        let mut receipts = vec![coinbase_receipt, withdrawal_receipt];
+        let keys = generate_withdrawal_keys(&mut receipts, 0);
+
+        // the tip takes the withdrawal ID after its withdrawal, and pays the coinbase's origin
+        assert_eq!(keys.len(), 2);
+        assert_eq!(
+            keys[1],
+            convert_withdrawal_key_to_bytes(&make_key_for_stx_withdrawal(
+                &miner_addr.into(),
+                1,
+                5,
+                0
+            ))
+        );
+        match &receipts[1].events[1] {
+            StacksTransactionEvent::STXEvent(STXWithdrawTipEvent(data)) => {
+                assert_eq!(data.recipient, Some(miner_addr.into()));
+                assert_eq!(data.withdrawal_id, Some(1));
+            }
+            event => panic!("Expected a withdraw tip event, got {:?}", event),
+        }
+
+        // without a coinbase, there is no miner to pay, and the tip gets no leaf
+        let mut receipts = vec![receipts.pop().unwrap()];
+        assert_eq!(generate_withdrawal_keys(&mut receipts, 0).len(), 1);
+    }
 }
@@ -14,14 +14,19 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::cell::RefCell;
 use std::cmp;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::hash::Hasher;
 use std::io::{Read, Write};
 use std::ops::Deref;
 use std::ops::DerefMut;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 use rand::distributions::Uniform;
 use rand::prelude::Distribution;
@@ -38,15 +43,17 @@ use siphasher::sip::SipHasher; // this is SipHash-2-4
 
 use crate::burnchains::Txid;
 use crate::chainstate::burn::ConsensusHash;
+use crate::chainstate::stacks::db::blocks::MINIMUM_TX_FEE_RATE_PER_BYTE;
 use crate::chainstate::stacks::{
     db::blocks::MemPoolRejection, db::ClarityTx, db::StacksChainState, db::TxStreamData,
     index::Error as MarfError, Error as ChainstateError, StacksTransaction,
 };
-use crate::chainstate::stacks::{StacksMicroblock, TransactionPayload};
+use crate::chainstate::stacks::{StacksMicroblock, TransactionPayload, MAX_BLOCK_LEN};
 use crate::core::ExecutionCost;
 use crate::core::StacksEpochId;
 use crate::core::FIRST_BURNCHAIN_CONSENSUS_HASH;
 use crate::core::FIRST_STACKS_BLOCK_HASH;
+use crate::monitoring::increment_mempool_gc_evictions_counter;
 use crate::monitoring::increment_stx_mempool_gc;
 use crate::util_lib::db::query_int;
 use crate::util_lib::db::query_row_columns;
@@ -59,6 +66,7 @@ use crate::util_lib::db::Error as db_error;
 use crate::util_lib::db::FromColumn;
 use crate::util_lib::db::{query_row, Error};
 use crate::util_lib::db::{sql_pragma, DBConn, DBTx, FromRow};
+use clarity::vm::database::NULL_BURN_STATE_DB;
 use clarity::vm::types::PrincipalData;
 use stacks_common::util::get_epoch_time_ms;
 use stacks_common::util::get_epoch_time_secs;
@@ -70,10 +78,18 @@ use crate::net::MemPoolSyncData;
 
 use crate::util_lib::bloom::{BloomCounter, BloomFilter, BloomNodeHasher};
 
+use clarity::vm::analysis::run_analysis;
+use clarity::vm::ast::build_ast;
+use clarity::vm::costs::LimitedCostTracker;
+use clarity::vm::representations::SymbolicExpression;
+use clarity::vm::types::QualifiedContractIdentifier;
+use clarity::vm::ClarityVersion;
+
 use crate::clarity_vm::clarity::ClarityConnection;
 
 use crate::chainstate::stacks::events::StacksTransactionReceipt;
 use crate::chainstate::stacks::miner::TransactionEvent;
+use crate::chainstate::stacks::system_priority::SystemPriorityPolicy;
 use crate::chainstate::stacks::StacksBlock;
 use crate::codec::Error as codec_error;
 use crate::codec::StacksMessageCodec;
@@ -86,21 +102,27 @@ use crate::cost_estimates::UnitEstimator;
 use crate::monitoring;
 use crate::types::chainstate::{BlockHeaderHash, StacksAddress, StacksBlockId};
 use crate::util_lib::db::table_exists;
+use crate::util_lib::migrations::{SchemaMigration, SchemaMigrations, SchemaStatus};
 
 // maximum number of confirmations a transaction can have before it's garbage-collected
 pub const MEMPOOL_MAX_TRANSACTION_AGE: u64 = 256;
 pub const MAXIMUM_MEMPOOL_TX_CHAINING: u64 = 25;
 
+/// Default and maximum number of transactions in one page of a mempool listing
+pub const MEMPOOL_LIST_DEFAULT_LIMIT: u32 = 50;
+pub const MEMPOOL_LIST_MAX_LIMIT: u32 = 200;
+
 // name of table for storing the counting bloom filter
 pub const BLOOM_COUNTER_TABLE: &'static str = "txid_bloom_counter";
 
-// bloom filter error rate
+// default bloom filter error rate
 pub const BLOOM_COUNTER_ERROR_RATE: f64 = 0.001;
 
-// expected number of txs in the bloom filter
+// default expected number of txs in the bloom filter
 pub const MAX_BLOOM_COUNTER_TXS: u32 = 8192;
 
-// how far back in time (in Stacks blocks) does the bloom counter maintain tx records?
+// by default, how far back in time (in Stacks blocks) does the bloom counter maintain tx records?
+// This is also the depth assumed of peers that do not say what theirs is.
 pub const BLOOM_COUNTER_DEPTH: usize = 2;
 
 // maximum many tx tags we'll send before sending a bloom filter instead.
@@ -145,6 +167,10 @@ impl StacksMessageCodec for TxTag {
 pub struct MemPoolAdmitter {
     cur_block: BlockHeaderHash,
     cur_consensus_hash: ConsensusHash,
+    origin_quota: MemPoolOriginQuota,
+    contract_deploy_limits: ContractDeployLimits,
+    policy: Box<dyn MempoolAdmissionPolicy>,
+    sponsorship_policy: SponsorshipPolicy,
 }
 
 enum MemPoolWalkResult {
@@ -158,6 +184,10 @@ impl MemPoolAdmitter {
         MemPoolAdmitter {
             cur_block,
             cur_consensus_hash,
+            origin_quota: MemPoolOriginQuota::default(),
+            contract_deploy_limits: ContractDeployLimits::default(),
+            policy: Box::new(DefaultAdmissionPolicy),
+            sponsorship_policy: SponsorshipPolicy::default(),
         }
     }
 
@@ -171,7 +201,280 @@ impl MemPoolAdmitter {
         tx: &StacksTransaction,
         tx_size: u64,
     ) -> Result<(), MemPoolRejection> {
-        chainstate.will_admit_mempool_tx(&self.cur_consensus_hash, &self.cur_block, tx, tx_size)
+        // checked first, since the policy's own checks analyze smart contracts
+        self.check_contract_deploy_limits(chainstate, tx)?;
+        self.check_policy(chainstate, tx, tx_size)
+    }
+
+    /// Check a smart contract deploy against the node's contract deploy limits
+    fn check_contract_deploy_limits(
+        &self,
+        chainstate: &mut StacksChainState,
+        tx: &StacksTransaction,
+    ) -> Result<(), MemPoolRejection> {
+        if self.contract_deploy_limits.is_unlimited() {
+            return Ok(());
+        }
+        let tip = StacksBlockId::new(&self.cur_consensus_hash, &self.cur_block);
+        chainstate
+            .with_read_only_clarity_tx(&NULL_BURN_STATE_DB, &tip, |conn| {
+                self.contract_deploy_limits.check(conn, tx)
+            })
+            .unwrap_or(Ok(()))
+    }
+
+    /// Check `tx`, which is `tx_size` bytes long, against the node's admission policy
+    fn check_policy(
+        &self,
+        chainstate: &mut StacksChainState,
+        tx: &StacksTransaction,
+        tx_size: u64,
+    ) -> Result<(), MemPoolRejection> {
+        self.policy.will_admit_tx(
+            chainstate,
+            &self.cur_consensus_hash,
+            &self.cur_block,
+            tx,
+            tx_size,
+        )
+    }
+}
+
+/// Admission checks a transaction must pass to enter the mempool, evaluated against the chain
+/// tip it was submitted for.  Nodes pick a policy at startup (see
+/// `MemPoolDB::set_admission_policy()`), so a subnet can apply chain-specific rules to what it
+/// relays and mines.  Policies are node-local: they do not affect block validity.
+pub trait MempoolAdmissionPolicy: Send {
+    /// Check whether or not `tx`, which is `tx_size` bytes long, can be admitted on top of the
+    /// given chain tip.
+    fn will_admit_tx(
+        &self,
+        chainstate: &mut StacksChainState,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        tx: &StacksTransaction,
+        tx_size: u64,
+    ) -> Result<(), MemPoolRejection>;
+}
+
+/// The standard checks: the transaction must be valid, pay at least the minimum fee rate, and be
+/// executable by its origin and sponsor at the chain tip.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DefaultAdmissionPolicy;
+
+impl MempoolAdmissionPolicy for DefaultAdmissionPolicy {
+    fn will_admit_tx(
+        &self,
+        chainstate: &mut StacksChainState,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        tx: &StacksTransaction,
+        tx_size: u64,
+    ) -> Result<(), MemPoolRejection> {
+        chainstate.will_admit_mempool_tx(consensus_hash, block_hash, tx, tx_size)
+    }
+}
+
+/// The standard checks, plus limits that keep large contracts and cheap token transfers out of
+/// the mempool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrictAdmissionPolicy {
+    /// largest contract deploy transaction, in bytes, that is admitted
+    pub max_contract_deploy_size: u64,
+    /// smallest fee, in microSTX, that a token transfer must pay
+    pub min_token_transfer_fee: u64,
+}
+
+impl MempoolAdmissionPolicy for StrictAdmissionPolicy {
+    fn will_admit_tx(
+        &self,
+        chainstate: &mut StacksChainState,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        tx: &StacksTransaction,
+        tx_size: u64,
+    ) -> Result<(), MemPoolRejection> {
+        match tx.payload {
+            TransactionPayload::SmartContract(..) if tx_size > self.max_contract_deploy_size => {
+                return Err(MemPoolRejection::ContractDeployTooLarge {
+                    size: tx_size,
+                    max_size: self.max_contract_deploy_size,
+                });
+            }
+            TransactionPayload::TokenTransfer(..)
+                if tx.get_tx_fee() < self.min_token_transfer_fee =>
+            {
+                return Err(MemPoolRejection::FeeTooLow(
+                    tx.get_tx_fee(),
+                    self.min_token_transfer_fee,
+                ));
+            }
+            _ => {}
+        }
+        DefaultAdmissionPolicy.will_admit_tx(chainstate, consensus_hash, block_hash, tx, tx_size)
+    }
+}
+
+/// Node configuration choosing a `MempoolAdmissionPolicy`
+#[derive(Debug, Clone, PartialEq)]
+pub enum MempoolAdmissionPolicyConfig {
+    Default,
+    Strict(StrictAdmissionPolicy),
+}
+
+impl Default for MempoolAdmissionPolicyConfig {
+    fn default() -> MempoolAdmissionPolicyConfig {
+        MempoolAdmissionPolicyConfig::Default
+    }
+}
+
+impl MempoolAdmissionPolicyConfig {
+    pub fn make_policy(&self) -> Box<dyn MempoolAdmissionPolicy> {
+        match self {
+            MempoolAdmissionPolicyConfig::Default => Box::new(DefaultAdmissionPolicy),
+            MempoolAdmissionPolicyConfig::Strict(policy) => Box::new(policy.clone()),
+        }
+    }
+}
+
+/// Node policy limiting how many pending transactions any one origin account may have in the
+/// mempool, on top of the nonce chaining limit (`MAXIMUM_MEMPOOL_TX_CHAINING`).  This keeps a
+/// single account submitting in bulk (e.g., minting many NFTs) from crowding out other users.
+/// A transaction is pending if its origin nonce has not been used in the chain tip it was
+/// submitted against.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemPoolOriginQuota {
+    /// maximum number of pending transactions per origin account; `None` means no limit
+    pub max_pending_txs_per_origin: Option<u64>,
+    /// origin accounts that are not subject to the limit, such as relayers
+    pub exempt_origins: Vec<StacksAddress>,
+}
+
+impl MemPoolOriginQuota {
+    /// Get the pending transaction limit for `origin_address`, if it has one
+    pub fn limit_for(&self, origin_address: &StacksAddress) -> Option<u64> {
+        if self.exempt_origins.contains(origin_address) {
+            None
+        } else {
+            self.max_pending_txs_per_origin
+        }
+    }
+}
+
+/// Parameters of the counting bloom filter that summarizes the mempool's recent transactions
+/// for mempool sync.  Subnets with high transaction rates need a larger capacity than the
+/// default, or the filter saturates and sync accuracy collapses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemPoolBloomConfig {
+    /// how far back in time (in Stacks blocks) the bloom counter maintains tx records
+    pub depth: u64,
+    /// expected number of txs in the bloom counter
+    pub max_txs: u32,
+    /// bloom counter error rate
+    pub error_rate: f64,
+}
+
+impl Default for MemPoolBloomConfig {
+    fn default() -> MemPoolBloomConfig {
+        MemPoolBloomConfig {
+            depth: BLOOM_COUNTER_DEPTH as u64,
+            max_txs: MAX_BLOOM_COUNTER_TXS,
+            error_rate: BLOOM_COUNTER_ERROR_RATE,
+        }
+    }
+}
+
+impl FromRow<MemPoolBloomConfig> for MemPoolBloomConfig {
+    fn from_row<'a>(row: &'a Row) -> Result<MemPoolBloomConfig, db_error> {
+        let depth = u64::from_column(row, "depth")?;
+        let max_txs: u32 = row.get_unwrap("max_txs");
+        let error_rate: f64 = row.get_unwrap("error_rate");
+        Ok(MemPoolBloomConfig {
+            depth,
+            max_txs,
+            error_rate,
+        })
+    }
+}
+
+/// How the mempool evicts transactions once it exceeds its size cap, on top of collecting
+/// transactions that are too old.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemPoolGCStrategy {
+    /// Only collect transactions that are too old.  The mempool's size is unbounded.
+    Age,
+    /// Evict the lowest fee-rate transactions once the mempool holds more than `max_txs`
+    /// transactions.
+    FeeFloor { max_txs: u64 },
+    /// Evict the lowest fee-rate transactions once the mempool's transactions total more than
+    /// `max_bytes` bytes.
+    ByteCap { max_bytes: u64 },
+}
+
+impl MemPoolGCStrategy {
+    /// The reason recorded in the eviction audit table, and in metrics, for transactions this
+    /// strategy evicts
+    pub fn eviction_reason(&self) -> &'static str {
+        match self {
+            MemPoolGCStrategy::Age => "gc_age",
+            MemPoolGCStrategy::FeeFloor { .. } => "gc_fee_floor",
+            MemPoolGCStrategy::ByteCap { .. } => "gc_byte_cap",
+        }
+    }
+}
+
+/// Node policy for garbage-collecting the mempool (see `MemPoolDB::garbage_collect_by_policy()`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemPoolGCPolicy {
+    /// number of blocks after which a transaction is collected
+    pub max_tx_age: u64,
+    pub strategy: MemPoolGCStrategy,
+}
+
+impl Default for MemPoolGCPolicy {
+    fn default() -> MemPoolGCPolicy {
+        MemPoolGCPolicy {
+            max_tx_age: MEMPOOL_MAX_TRANSACTION_AGE,
+            strategy: MemPoolGCStrategy::Age,
+        }
+    }
+}
+
+/// Node policy for sponsorship offers: sponsored transactions that supersede an unsponsored
+/// transaction already in the mempool.  These are made with `MemPoolDB::submit_sponsorship()`,
+/// but any sponsored transaction that would replace an unsponsored one by fee is held to it too.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SponsorshipPolicy {
+    /// whether or not this node accepts sponsorship offers at all
+    pub enabled: bool,
+    /// if non-empty, only these accounts may sponsor transactions
+    pub allowed_sponsors: Vec<StacksAddress>,
+}
+
+impl Default for SponsorshipPolicy {
+    fn default() -> SponsorshipPolicy {
+        SponsorshipPolicy {
+            enabled: false,
+            allowed_sponsors: vec![],
+        }
+    }
+}
+
+impl SponsorshipPolicy {
+    /// Check that this policy lets `sponsor` take over other accounts' transactions
+    pub fn check_sponsor(&self, sponsor: &StacksAddress) -> Result<(), MemPoolRejection> {
+        if !self.enabled {
+            return Err(MemPoolRejection::SponsorshipNotAllowed(
+                "node does not accept sponsorship offers".to_string(),
+            ));
+        }
+        if !self.allowed_sponsors.is_empty() && !self.allowed_sponsors.contains(sponsor) {
+            return Err(MemPoolRejection::SponsorshipNotAllowed(format!(
+                "{} may not sponsor transactions",
+                sponsor
+            )));
+        }
+        Ok(())
     }
 }
 
@@ -180,6 +483,7 @@ pub enum MemPoolDropReason {
     REPLACE_BY_FEE,
     STALE_COLLECT,
     TOO_EXPENSIVE,
+    EVICTED,
 }
 
 #[derive(Debug)]
@@ -189,6 +493,8 @@ pub struct ConsiderTransaction {
     /// If `update_estimator` is set, the iteration should update the estimator
     /// after considering the tx.
     pub update_estimate: bool,
+    /// If set, a candidate check worker already verified the transaction's signatures.
+    pub auth_verified: bool,
 }
 
 #[derive(Debug)]
@@ -206,6 +512,7 @@ impl std::fmt::Display for MemPoolDropReason {
             MemPoolDropReason::TOO_EXPENSIVE => write!(f, "TooExpensive"),
             MemPoolDropReason::REPLACE_ACROSS_FORK => write!(f, "ReplaceAcrossFork"),
             MemPoolDropReason::REPLACE_BY_FEE => write!(f, "ReplaceByFee"),
+            MemPoolDropReason::EVICTED => write!(f, "Evicted"),
         }
     }
 }
@@ -230,6 +537,47 @@ pub trait MemPoolEventDispatcher {
     );
 }
 
+/// Holds on to the mempool drop events generated while admitting a batch of transactions, so
+/// they can be reported only if the batch is committed.
+struct DeferredMemPoolDropEvents {
+    dropped: RefCell<Vec<(Vec<Txid>, MemPoolDropReason)>>,
+}
+
+impl DeferredMemPoolDropEvents {
+    fn new() -> DeferredMemPoolDropEvents {
+        DeferredMemPoolDropEvents {
+            dropped: RefCell::new(vec![]),
+        }
+    }
+}
+
+impl MemPoolEventDispatcher for DeferredMemPoolDropEvents {
+    fn mempool_txs_dropped(&self, txids: Vec<Txid>, reason: MemPoolDropReason) {
+        self.dropped.borrow_mut().push((txids, reason));
+    }
+
+    // not generated by mempool admission
+    fn mined_block_event(
+        &self,
+        _target_burn_height: u64,
+        _block: &StacksBlock,
+        _block_size_bytes: u64,
+        _consumed: &ExecutionCost,
+        _confirmed_microblock_cost: &ExecutionCost,
+        _tx_results: Vec<TransactionEvent>,
+    ) {
+    }
+
+    fn mined_microblock_event(
+        &self,
+        _microblock: &StacksMicroblock,
+        _tx_results: Vec<TransactionEvent>,
+        _anchor_block_consensus_hash: ConsensusHash,
+        _anchor_block: BlockHeaderHash,
+    ) {
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct MemPoolTxInfo {
     pub tx: StacksTransaction,
@@ -248,11 +596,208 @@ pub struct MemPoolTxMetadata {
     pub origin_nonce: u64,
     pub sponsor_address: StacksAddress,
     pub sponsor_nonce: u64,
-    pub last_known_origin_nonce: Option<u64>,
-    pub last_known_sponsor_nonce: Option<u64>,
     pub accept_time: u64,
 }
 
+/// A transaction evicted from the mempool by an operator or by garbage collection, as recorded in
+/// the eviction audit table
+#[derive(Debug, PartialEq, Clone)]
+pub struct MemPoolEviction {
+    pub txid: Txid,
+    pub origin_address: StacksAddress,
+    pub origin_nonce: u64,
+    pub tx_fee: u64,
+    pub reason: String,
+    pub evict_time: u64,
+}
+
+/// Filters on a listing of pending transactions.  Unset filters match every transaction.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct MemPoolListFilter {
+    pub origin: Option<StacksAddress>,
+    /// minimum fee, inclusive
+    pub min_fee: Option<u64>,
+    /// maximum fee, inclusive
+    pub max_fee: Option<u64>,
+    /// the `TransactionPayload::name()` of the transactions' payloads
+    pub payload_type: Option<String>,
+}
+
+/// Where a page of a mempool listing starts.  Listings are ordered by decreasing fee, then
+/// decreasing txid, and a page starts just after the transaction with this fee and txid.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MemPoolListCursor {
+    pub tx_fee: u64,
+    pub txid: Txid,
+}
+
+/// The number of pending transactions matching a filter, bucketed by fee
+#[derive(Debug, PartialEq, Clone)]
+pub struct MemPoolSummary {
+    pub count: u64,
+    /// non-empty buckets in increasing order of fee
+    pub fee_histogram: Vec<MemPoolFeeBucket>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MemPoolFeeBucket {
+    /// minimum fee, inclusive
+    pub min_fee: u64,
+    /// maximum fee, inclusive
+    pub max_fee: u64,
+    pub count: u64,
+}
+
+impl MemPoolListCursor {
+    pub fn from_tx(metadata: &MemPoolTxMetadata) -> MemPoolListCursor {
+        MemPoolListCursor {
+            tx_fee: metadata.tx_fee,
+            txid: metadata.txid.clone(),
+        }
+    }
+
+    /// Parse a cursor encoded by `to_string()`
+    pub fn from_str(cursor: &str) -> Option<MemPoolListCursor> {
+        let (tx_fee, txid) = cursor.split_once('-')?;
+        Some(MemPoolListCursor {
+            tx_fee: tx_fee.parse().ok()?,
+            txid: Txid::from_hex(txid).ok()?,
+        })
+    }
+}
+
+impl std::fmt::Display for MemPoolListCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}-{}", self.tx_fee, &self.txid)
+    }
+}
+
+/// The nonces missing from an account's sequence of pending transactions.  A transaction is only
+/// mined once every nonce before it has been used, so the pending transactions after a missing
+/// nonce are stuck until it is filled.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MemPoolNonceGaps {
+    /// the nonce the account's next transaction must have, per the chain tip
+    pub next_nonce: u64,
+    /// the nonce the last mempool walk expected of the account, if it considered the account's
+    /// transactions
+    pub last_known_nonce: Option<u64>,
+    /// nonces of the account's pending transactions, at or after `next_nonce`
+    pub pending_nonces: Vec<u64>,
+    /// nonces between `next_nonce` and the highest pending nonce that no pending transaction
+    /// has.  Transaction chaining limits keep this list short.
+    pub missing_nonces: Vec<u64>,
+    /// pending transactions which can't be mined until the missing nonces are filled
+    pub blocked_txids: Vec<Txid>,
+}
+
+impl MemPoolNonceGaps {
+    /// Where a new transaction with nonce `nonce` would fall in the account's sequence of
+    /// pending transactions
+    pub fn nonce_status(&self, nonce: u64) -> MemPoolNonceStatus {
+        if nonce < self.next_nonce {
+            return MemPoolNonceStatus::Stale;
+        }
+        if self.pending_nonces.contains(&nonce) {
+            return MemPoolNonceStatus::Replacement;
+        }
+        if nonce == self.next_nonce {
+            return MemPoolNonceStatus::Next;
+        }
+        // the first nonce after the unbroken run of pending nonces from `next_nonce`
+        let mut chain_end = self.next_nonce;
+        while self.pending_nonces.contains(&chain_end) {
+            chain_end += 1;
+        }
+        if nonce == chain_end {
+            MemPoolNonceStatus::Chained
+        } else {
+            MemPoolNonceStatus::Gap
+        }
+    }
+}
+
+/// How a transaction's nonce relates to its account's nonce and pending transactions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemPoolNonceStatus {
+    /// the nonce has already been used on chain
+    Stale,
+    /// the nonce is the one the account's next transaction must have
+    Next,
+    /// the nonce follows on from the account's pending transactions
+    Chained,
+    /// a pending transaction already has the nonce, so this one would replace it
+    Replacement,
+    /// the nonce leaves a gap after the account's pending transactions, so it can't be mined
+    /// until the gap is filled
+    Gap,
+}
+
+/// The stages of mempool admission, in the order a submitted transaction goes through them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemPoolAdmissionCheck {
+    /// the cost estimator must not fail to estimate the transaction's fee rate
+    FeeRateEstimate,
+    /// a smart contract deploy must be within the node's contract deploy limits
+    ContractDeployLimits,
+    /// the node's `MempoolAdmissionPolicy`
+    AdmissionPolicy,
+    /// the mempool's own limits: transaction chaining, origin quotas and replace-by-fee
+    MemPoolLimits,
+}
+
+impl MemPoolAdmissionCheck {
+    pub const ALL: [MemPoolAdmissionCheck; 4] = [
+        MemPoolAdmissionCheck::FeeRateEstimate,
+        MemPoolAdmissionCheck::ContractDeployLimits,
+        MemPoolAdmissionCheck::AdmissionPolicy,
+        MemPoolAdmissionCheck::MemPoolLimits,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MemPoolAdmissionCheck::FeeRateEstimate => "fee_rate_estimate",
+            MemPoolAdmissionCheck::ContractDeployLimits => "contract_deploy_limits",
+            MemPoolAdmissionCheck::AdmissionPolicy => "admission_policy",
+            MemPoolAdmissionCheck::MemPoolLimits => "mempool_limits",
+        }
+    }
+}
+
+/// How far a transaction submitted with `MemPoolDB::submit_with_report()` got through admission
+#[derive(Debug)]
+pub struct MemPoolAdmissionReport {
+    pub result: Result<(), MemPoolRejection>,
+    /// size of the serialized transaction, in bytes
+    pub tx_size: u64,
+    /// the transaction's estimated fee rate, if the cost estimator has an estimate for it
+    pub fee_rate: Option<f64>,
+    /// the checks the transaction passed, in order
+    pub passed_checks: Vec<MemPoolAdmissionCheck>,
+    /// the check that rejected the transaction, if one did.  Later checks were not run.
+    pub failed_check: Option<MemPoolAdmissionCheck>,
+}
+
+impl MemPoolAdmissionReport {
+    /// Record the outcome of one admission check
+    fn record<T>(
+        &mut self,
+        check: MemPoolAdmissionCheck,
+        result: Result<T, MemPoolRejection>,
+    ) -> Result<T, MemPoolRejection> {
+        match result {
+            Ok(value) => {
+                self.passed_checks.push(check);
+                Ok(value)
+            }
+            Err(e) => {
+                self.failed_check = Some(check);
+                Err(e)
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MemPoolWalkSettings {
     /// Minimum transaction fee that will be considered
@@ -264,6 +809,59 @@ pub struct MemPoolWalkSettings {
     /// That is, with x%, when picking the next transaction to include a block, select one that
     /// either failed to get a cost estimate or has not been estimated yet.
     pub consider_no_estimate_tx_prob: u8,
+    /// Limits on the smart contract deploys selected into an anchored block
+    pub contract_deploy_quota: ContractDeployQuota,
+    /// Limits on the size and complexity of each smart contract selected into an anchored block
+    pub contract_deploy_limits: ContractDeployLimits,
+    /// Number of worker threads in the miner's `CandidateCheckPool`, which verify candidate
+    /// transactions' signatures and read their accounts' nonces ahead of block assembly.  If 0,
+    /// both are checked by the mempool walk as each transaction is considered.
+    pub candidate_check_threads: usize,
+    /// Minimum miner tip, in uSTX, that each withdrawal in a mined transaction must pay.
+    /// Transactions with withdrawals tipping less are skipped.
+    pub min_withdrawal_tip: u128,
+    /// How candidate transactions are ranked
+    pub ranking: MemPoolWalkRanking,
+}
+
+/// How the mempool walk ranks candidate transactions for inclusion in a block
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemPoolWalkRanking {
+    /// Transactions with a fee rate estimate by their estimated fee rate, and the rest by their
+    /// fee
+    Fee,
+    /// All transactions by their fee per byte, ignoring fee rate estimates.  A large transaction
+    /// that pays little per byte is considered after the smaller transactions that outbid it.
+    FeePerLength,
+    /// Transactions with a fee rate estimate by their estimated fee rate -- their fee per unit of
+    /// the cost metric, which weighs both execution cost and length -- and the rest by their fee
+    /// per byte.  The miner estimates more fee rates before each walk in this mode.
+    FeePerCost,
+}
+
+impl Default for MemPoolWalkRanking {
+    fn default() -> Self {
+        MemPoolWalkRanking::Fee
+    }
+}
+
+impl MemPoolWalkRanking {
+    pub fn from_str(ranking: &str) -> Option<MemPoolWalkRanking> {
+        match ranking {
+            "fee" => Some(MemPoolWalkRanking::Fee),
+            "fee_per_length" => Some(MemPoolWalkRanking::FeePerLength),
+            "fee_per_cost" => Some(MemPoolWalkRanking::FeePerCost),
+            _ => None,
+        }
+    }
+
+    /// How many mempool transactions' fee rates the miner estimates before each walk
+    pub fn fee_rate_estimates_per_walk(&self) -> u32 {
+        match self {
+            MemPoolWalkRanking::FeePerCost => 1_000,
+            MemPoolWalkRanking::Fee | MemPoolWalkRanking::FeePerLength => 100,
+        }
+    }
 }
 
 impl MemPoolWalkSettings {
@@ -272,6 +870,11 @@ impl MemPoolWalkSettings {
             min_tx_fee: 1,
             max_walk_time_ms: u64::max_value(),
             consider_no_estimate_tx_prob: 5,
+            contract_deploy_quota: ContractDeployQuota::default(),
+            contract_deploy_limits: ContractDeployLimits::default(),
+            candidate_check_threads: 0,
+            min_withdrawal_tip: 0,
+            ranking: MemPoolWalkRanking::Fee,
         }
     }
     pub fn zero() -> MemPoolWalkSettings {
@@ -279,7 +882,379 @@ impl MemPoolWalkSettings {
             min_tx_fee: 0,
             max_walk_time_ms: u64::max_value(),
             consider_no_estimate_tx_prob: 5,
+            contract_deploy_quota: ContractDeployQuota::default(),
+            contract_deploy_limits: ContractDeployLimits::default(),
+            candidate_check_threads: 0,
+            min_withdrawal_tip: 0,
+            ranking: MemPoolWalkRanking::Fee,
+        }
+    }
+}
+
+/// Reads account nonces for the candidate check workers.  Each worker owns its own reader, so
+/// that the workers' reads do not contend with one another or with the mempool walk.
+pub trait CandidateNonceReader: Send {
+    /// Get the nonce of `address` as of the block `tip`, or None if it cannot be read.
+    fn get_nonce(&mut self, tip: &StacksBlockId, address: &StacksAddress) -> Option<u64>;
+}
+
+impl CandidateNonceReader for StacksChainState {
+    fn get_nonce(&mut self, tip: &StacksBlockId, address: &StacksAddress) -> Option<u64> {
+        self.with_read_only_clarity_tx(&NULL_BURN_STATE_DB, tip, |conn| {
+            StacksChainState::get_account(conn, &address.clone().into()).nonce
+        })
+    }
+}
+
+/// The result of checking a candidate transaction in a `CandidateCheckPool`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandidateCheck {
+    pub txid: Txid,
+    /// Whether or not the transaction's signatures are valid
+    pub auth_ok: bool,
+    /// The nonces of the transaction's origin and sponsor accounts, where they could be read
+    pub nonces: Vec<(StacksAddress, u64)>,
+}
+
+struct CandidateCheckJob {
+    tip: StacksBlockId,
+    txs: Vec<StacksTransaction>,
+    results: Sender<CandidateCheck>,
+}
+
+/// A fixed pool of worker threads that check the mempool's candidate transactions ahead of a
+/// mempool walk: each worker verifies its candidates' signatures, and reads the nonces of their
+/// origin and sponsor accounts.  This leaves only the application of each transaction to the walk.
+/// The workers are started once, and serve every walk until the pool is dropped.
+pub struct CandidateCheckPool {
+    jobs: Option<Sender<CandidateCheckJob>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl CandidateCheckPool {
+    /// Start one worker per nonce reader
+    pub fn new(readers: Vec<Box<dyn CandidateNonceReader>>) -> CandidateCheckPool {
+        let (jobs_tx, jobs_rx) = channel::<CandidateCheckJob>();
+        let jobs_rx = Arc::new(Mutex::new(jobs_rx));
+        let workers = readers
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut reader)| {
+                let jobs_rx = jobs_rx.clone();
+                thread::Builder::new()
+                    .name(format!("candidate-check-{}", i))
+                    .spawn(move || loop {
+                        let job = match jobs_rx.lock() {
+                            Ok(jobs_rx) => jobs_rx.recv(),
+                            Err(_) => return,
+                        };
+                        match job {
+                            Ok(job) => CandidateCheckPool::run_job(reader.as_mut(), job),
+                            // the pool was dropped
+                            Err(_) => return,
+                        }
+                    })
+                    .expect("FATAL: failed to start candidate check worker")
+            })
+            .collect();
+
+        CandidateCheckPool {
+            jobs: Some(jobs_tx),
+            workers,
+        }
+    }
+
+    pub fn num_workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    fn run_job(reader: &mut dyn CandidateNonceReader, job: CandidateCheckJob) {
+        let mut nonces: HashMap<StacksAddress, Option<u64>> = HashMap::new();
+        for tx in job.txs.into_iter() {
+            let mut addresses = vec![tx.origin_address()];
+            if let Some(sponsor_address) = tx.sponsor_address() {
+                addresses.push(sponsor_address);
+            }
+            let tx_nonces = addresses
+                .into_iter()
+                .filter_map(|address| {
+                    let nonce = match nonces.get(&address) {
+                        Some(nonce) => *nonce,
+                        None => {
+                            let nonce = reader.get_nonce(&job.tip, &address);
+                            nonces.insert(address.clone(), nonce);
+                            nonce
+                        }
+                    };
+                    nonce.map(|nonce| (address, nonce))
+                })
+                .collect();
+
+            let check = CandidateCheck {
+                txid: tx.txid(),
+                auth_ok: tx.verify().is_ok(),
+                nonces: tx_nonces,
+            };
+            if job.results.send(check).is_err() {
+                // the caller stopped waiting
+                return;
+            }
+        }
+    }
+
+    /// Check `txs` against the chain state at `tip`, split across the workers.  Returns the checks
+    /// that finish within `timeout`.  The checks of a worker that panics are left out, so its
+    /// transactions are checked when they are applied.
+    pub fn check(
+        &self,
+        tip: &StacksBlockId,
+        txs: Vec<StacksTransaction>,
+        timeout: Duration,
+    ) -> Vec<CandidateCheck> {
+        let jobs = match self.jobs.as_ref() {
+            Some(jobs) if !self.workers.is_empty() => jobs,
+            _ => return vec![],
+        };
+
+        // group each account's transactions together, so that its nonce is read once
+        let num_workers = self.workers.len();
+        let mut batches: Vec<Vec<StacksTransaction>> = vec![vec![]; num_workers];
+        for tx in txs.into_iter() {
+            let mut hasher = SipHasher::new();
+            hasher.write(&tx.origin_address().bytes.0);
+            let worker = (hasher.finish() % (num_workers as u64)) as usize;
+            batches[worker].push(tx);
+        }
+
+        let (results_tx, results_rx) = channel();
+        let mut num_txs = 0;
+        for batch in batches.into_iter().filter(|batch| !batch.is_empty()) {
+            num_txs += batch.len();
+            let job = CandidateCheckJob {
+                tip: tip.clone(),
+                txs: batch,
+                results: results_tx.clone(),
+            };
+            if jobs.send(job).is_err() {
+                warn!("Candidate check workers are gone");
+                return vec![];
+            }
+        }
+        drop(results_tx);
+
+        let start_time = Instant::now();
+        let mut checks = Vec::with_capacity(num_txs);
+        while checks.len() < num_txs {
+            let remaining = timeout
+                .checked_sub(start_time.elapsed())
+                .unwrap_or(Duration::from_millis(0));
+            match results_rx.recv_timeout(remaining) {
+                Ok(check) => checks.push(check),
+                Err(RecvTimeoutError::Timeout) => {
+                    debug!("Candidate checks timed out";
+                           "num_checked" => checks.len(),
+                           "num_txs" => num_txs);
+                    break;
+                }
+                // every worker that had a batch is done with it
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        checks
+    }
+}
+
+impl Drop for CandidateCheckPool {
+    fn drop(&mut self) {
+        // hang up on the workers, so they exit
+        self.jobs.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Limits on how many smart contract deploys a miner selects into a single anchored block, and how
+/// many bytes they take up, both in total and from any one origin account.  This keeps a burst of
+/// large deploys from crowding token transfers and contract calls out of the block.  `None` means
+/// no limit.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContractDeployQuota {
+    pub max_deploys_per_block: Option<u64>,
+    pub max_deploy_bytes_per_block: Option<u64>,
+    pub max_deploys_per_origin: Option<u64>,
+    pub max_deploy_bytes_per_origin: Option<u64>,
+}
+
+/// Counts the smart contract deploys selected into a block, to check them against a
+/// `ContractDeployQuota`.
+#[derive(Debug, Clone)]
+pub struct ContractDeployQuotaTracker {
+    quota: ContractDeployQuota,
+    num_deploys: u64,
+    deploy_bytes: u64,
+    /// number of deploys and deploy bytes selected from each origin
+    origins: HashMap<StacksAddress, (u64, u64)>,
+}
+
+impl ContractDeployQuotaTracker {
+    pub fn new(quota: ContractDeployQuota) -> ContractDeployQuotaTracker {
+        ContractDeployQuotaTracker {
+            quota,
+            num_deploys: 0,
+            deploy_bytes: 0,
+            origins: HashMap::new(),
+        }
+    }
+
+    fn is_deploy(tx: &StacksTransaction) -> bool {
+        match tx.payload {
+            TransactionPayload::SmartContract(..) => true,
+            _ => false,
+        }
+    }
+
+    fn within(limit: Option<u64>, value: u64) -> bool {
+        limit.map(|limit| value <= limit).unwrap_or(true)
+    }
+
+    /// Can this transaction, which is `tx_len` bytes long, be added to the block without
+    /// exceeding the quota?  Transactions that are not contract deploys are always admitted.
+    pub fn admits(&self, tx: &StacksTransaction, tx_len: u64) -> bool {
+        if !ContractDeployQuotaTracker::is_deploy(tx) {
+            return true;
+        }
+        let (origin_deploys, origin_bytes) = self
+            .origins
+            .get(&tx.origin_address())
+            .cloned()
+            .unwrap_or((0, 0));
+
+        ContractDeployQuotaTracker::within(self.quota.max_deploys_per_block, self.num_deploys + 1)
+            && ContractDeployQuotaTracker::within(
+                self.quota.max_deploy_bytes_per_block,
+                self.deploy_bytes.saturating_add(tx_len),
+            )
+            && ContractDeployQuotaTracker::within(
+                self.quota.max_deploys_per_origin,
+                origin_deploys + 1,
+            )
+            && ContractDeployQuotaTracker::within(
+                self.quota.max_deploy_bytes_per_origin,
+                origin_bytes.saturating_add(tx_len),
+            )
+    }
+
+    /// Count a transaction that was selected into the block
+    pub fn record(&mut self, tx: &StacksTransaction, tx_len: u64) {
+        if !ContractDeployQuotaTracker::is_deploy(tx) {
+            return;
+        }
+        self.num_deploys += 1;
+        self.deploy_bytes = self.deploy_bytes.saturating_add(tx_len);
+
+        let origin = self.origins.entry(tx.origin_address()).or_insert((0, 0));
+        origin.0 += 1;
+        origin.1 = origin.1.saturating_add(tx_len);
+    }
+}
+
+/// Node limits on the size and complexity of smart contract deploys, enforced when they are
+/// submitted to the mempool and when they are selected into a block.  Subnets with fast blocks
+/// have little time to spare for contracts that are expensive to parse or analyze.  `None` means
+/// no limit.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContractDeployLimits {
+    /// largest contract code body, in bytes
+    pub max_code_body_size: Option<u64>,
+    /// deepest nesting of expressions in the contract's AST
+    pub max_ast_depth: Option<u64>,
+    /// longest time, in milliseconds, that parsing and analyzing the contract may take
+    pub max_analysis_time_ms: Option<u64>,
+}
+
+impl ContractDeployLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_code_body_size.is_none()
+            && self.max_ast_depth.is_none()
+            && self.max_analysis_time_ms.is_none()
+    }
+
+    /// How deeply the lists in `exprs` are nested: `(f a)` has depth 1, and `(f (g a))` depth 2.
+    fn ast_depth(exprs: &[SymbolicExpression]) -> u64 {
+        exprs
+            .iter()
+            .map(|expr| match expr.match_list() {
+                Some(list) => 1 + ContractDeployLimits::ast_depth(list),
+                None => 0,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Check a transaction against these limits.  Transactions that are not smart contract
+    /// deploys always pass.  Contracts are parsed, and analyzed against `conn`'s state if
+    /// analysis time is limited; contracts that fail to parse or analyze pass, since their
+    /// errors are reported when they are mined.
+    pub fn check<C: ClarityConnection>(
+        &self,
+        conn: &mut C,
+        tx: &StacksTransaction,
+    ) -> Result<(), MemPoolRejection> {
+        let smart_contract = match tx.payload {
+            TransactionPayload::SmartContract(ref smart_contract) => smart_contract,
+            _ => return Ok(()),
+        };
+        if let Some(max_size) = self.max_code_body_size {
+            let size = smart_contract.code_body.len() as u64;
+            if size > max_size {
+                return Err(MemPoolRejection::ContractCodeTooLarge { size, max_size });
+            }
+        }
+        if self.max_ast_depth.is_none() && self.max_analysis_time_ms.is_none() {
+            return Ok(());
         }
+
+        let contract_id = QualifiedContractIdentifier::new(
+            tx.origin_address().into(),
+            smart_contract.name.clone(),
+        );
+        let contract_code_str = smart_contract.code_body.to_string();
+        let clarity_version = ClarityVersion::default_for_epoch(conn.get_epoch());
+        let start = Instant::now();
+        conn.with_analysis_db_readonly(|db| {
+            let mut cost_track = LimitedCostTracker::new_free();
+            let mut contract_ast =
+                match build_ast(&contract_id, &contract_code_str, &mut cost_track) {
+                    Ok(x) => x,
+                    Err(_) => return Ok(()),
+                };
+            if let Some(max_depth) = self.max_ast_depth {
+                let depth = ContractDeployLimits::ast_depth(&contract_ast.expressions);
+                if depth > max_depth {
+                    return Err(MemPoolRejection::ContractTooDeep { depth, max_depth });
+                }
+            }
+            if let Some(max_time_ms) = self.max_analysis_time_ms {
+                let _ = run_analysis(
+                    &contract_id,
+                    &mut contract_ast.expressions,
+                    db,
+                    false,
+                    true,
+                    clarity_version,
+                    cost_track,
+                );
+                let time_ms = start.elapsed().as_millis() as u64;
+                if time_ms > max_time_ms {
+                    return Err(MemPoolRejection::ContractAnalysisTooSlow {
+                        time_ms,
+                        max_time_ms,
+                    });
+                }
+            }
+            Ok(())
+        })
     }
 }
 
@@ -302,8 +1277,6 @@ impl FromRow<MemPoolTxMetadata> for MemPoolTxMetadata {
         let origin_nonce = u64::from_column(row, "origin_nonce")?;
         let sponsor_address = StacksAddress::from_column(row, "sponsor_address")?;
         let sponsor_nonce = u64::from_column(row, "sponsor_nonce")?;
-        let last_known_sponsor_nonce = u64::from_column(row, "last_known_sponsor_nonce")?;
-        let last_known_origin_nonce = u64::from_column(row, "last_known_origin_nonce")?;
 
         Ok(MemPoolTxMetadata {
             txid,
@@ -316,8 +1289,6 @@ impl FromRow<MemPoolTxMetadata> for MemPoolTxMetadata {
             origin_nonce,
             sponsor_address,
             sponsor_nonce,
-            last_known_origin_nonce,
-            last_known_sponsor_nonce,
             accept_time,
         })
     }
@@ -341,6 +1312,124 @@ impl FromRow<MemPoolTxInfo> for MemPoolTxInfo {
     }
 }
 
+/// A mempool transaction as a member of the chain of transactions with consecutive nonces paid
+/// for by its sponsor.  The sponsor of an unsponsored transaction is its origin.
+struct NonceChainMember {
+    txid: Txid,
+    sponsor_address: StacksAddress,
+    sponsor_nonce: u64,
+    tx_fee: u64,
+    length: u64,
+    fee_rate: Option<f64>,
+}
+
+impl FromRow<NonceChainMember> for NonceChainMember {
+    fn from_row<'a>(row: &'a Row) -> Result<NonceChainMember, db_error> {
+        Ok(NonceChainMember {
+            txid: Txid::from_column(row, "txid")?,
+            sponsor_address: StacksAddress::from_column(row, "sponsor_address")?,
+            sponsor_nonce: u64::from_column(row, "sponsor_nonce")?,
+            tx_fee: u64::from_column(row, "tx_fee")?,
+            length: u64::from_column(row, "length")?,
+            fee_rate: row.get_unwrap("fee_rate"),
+        })
+    }
+}
+
+/// Score each transaction in `members` as the head of a chain of transactions with consecutive
+/// nonces paid for by the same sponsor.  A transaction's scores are the highest average fee, fee
+/// per byte and fee rate of any prefix of its chain, so a low-fee transaction that unlocks
+/// higher-fee ones scores as well as the chain it unlocks.  A sponsor's chain runs through its sponsor nonces,
+/// whoever the transactions' origins are, so that a sponsor paying for many users has its
+/// transactions ranked by the fees it pays.  Chains are at most `MAXIMUM_MEMPOOL_TX_CHAINING`
+/// long.  A transaction has no fee rate score if it has no fee rate estimate.
+fn score_nonce_chains(members: &[NonceChainMember]) -> Vec<(Txid, f64, f64, Option<f64>)> {
+    // the highest-fee transaction at each nonce continues a chain
+    let mut successors: HashMap<(&StacksAddress, u64), &NonceChainMember> = HashMap::new();
+    for member in members.iter() {
+        let best = successors
+            .entry((&member.sponsor_address, member.sponsor_nonce))
+            .or_insert(member);
+        if member.tx_fee > best.tx_fee {
+            *best = member;
+        }
+    }
+
+    members
+        .iter()
+        .map(|head| {
+            let mut fee_total = head.tx_fee as f64;
+            let mut fee_score = fee_total;
+            let mut length_total = cmp::max(head.length, 1) as f64;
+            let mut fee_per_length_score = fee_total / length_total;
+            let mut fee_rate_total = head.fee_rate;
+            let mut fee_rate_score = head.fee_rate;
+            for length in 2..=MAXIMUM_MEMPOOL_TX_CHAINING {
+                let nonce = head.sponsor_nonce.saturating_add(length - 1);
+                let next = match successors.get(&(&head.sponsor_address, nonce)) {
+                    Some(next) => next,
+                    None => break,
+                };
+                fee_total += next.tx_fee as f64;
+                fee_score = fee_score.max(fee_total / length as f64);
+                length_total += cmp::max(next.length, 1) as f64;
+                fee_per_length_score = fee_per_length_score.max(fee_total / length_total);
+                fee_rate_total = match (fee_rate_total, next.fee_rate) {
+                    (Some(total), Some(fee_rate)) => Some(total + fee_rate),
+                    _ => None,
+                };
+                if let (Some(total), Some(score)) = (fee_rate_total, fee_rate_score) {
+                    fee_rate_score = Some(score.max(total / length as f64));
+                }
+            }
+            (head.txid, fee_score, fee_per_length_score, fee_rate_score)
+        })
+        .collect()
+}
+
+/// Ranks a mempool transaction (`m`) by the fee per byte of its nonce chain (`c`), or of the
+/// transaction alone if it was added after the chains were scored
+const ORDER_BY_FEE_PER_LENGTH: &str =
+    "COALESCE(c.chain_fee_per_length, CAST(m.tx_fee AS REAL) / MAX(m.length, 1))";
+
+/// A transaction the mempool walk may consider next, with the nonces cached for its origin and
+/// sponsor accounts, if they have been loaded.
+struct MemPoolWalkCandidate {
+    tx: MemPoolTxInfo,
+    cached_origin_nonce: Option<u64>,
+    cached_sponsor_nonce: Option<u64>,
+}
+
+impl FromRow<MemPoolWalkCandidate> for MemPoolWalkCandidate {
+    fn from_row<'a>(row: &'a Row) -> Result<MemPoolWalkCandidate, db_error> {
+        Ok(MemPoolWalkCandidate {
+            tx: MemPoolTxInfo::from_row(row)?,
+            cached_origin_nonce: u64::from_column(row, "cached_origin_nonce")?,
+            cached_sponsor_nonce: u64::from_column(row, "cached_sponsor_nonce")?,
+        })
+    }
+}
+
+impl FromRow<MemPoolEviction> for MemPoolEviction {
+    fn from_row<'a>(row: &'a Row) -> Result<MemPoolEviction, db_error> {
+        let txid = Txid::from_column(row, "txid")?;
+        let origin_address = StacksAddress::from_column(row, "origin_address")?;
+        let origin_nonce = u64::from_column(row, "origin_nonce")?;
+        let tx_fee = u64::from_column(row, "tx_fee")?;
+        let reason: String = row.get_unwrap("reason");
+        let evict_time = u64::from_column(row, "evict_time")?;
+
+        Ok(MemPoolEviction {
+            txid,
+            origin_address,
+            origin_nonce,
+            tx_fee,
+            reason,
+            evict_time,
+        })
+    }
+}
+
 impl FromRow<(u64, u64)> for (u64, u64) {
     fn from_row<'a>(row: &'a Row) -> Result<(u64, u64), db_error> {
         let t1: i64 = row.get_unwrap(0);
@@ -388,34 +1477,148 @@ const MEMPOOL_SCHEMA_2_COST_ESTIMATOR: &'static [&'static str] = &[
     ALTER TABLE mempool ADD COLUMN last_known_sponsor_nonce INTEGER;
     "#,
     r#"
-    CREATE TABLE schema_version (version NUMBER, PRIMARY KEY (version));
+    CREATE TABLE schema_version (version NUMBER, PRIMARY KEY (version));
+    "#,
+    r#"
+    INSERT INTO schema_version (version) VALUES (2)
+    "#,
+];
+
+const MEMPOOL_SCHEMA_3_BLOOM_STATE: &'static [&'static str] = &[
+    r#"
+    CREATE TABLE IF NOT EXISTS removed_txids(
+        txid TEXT PRIMARY KEY NOT NULL,
+        FOREIGN KEY(txid) REFERENCES mempool(txid) ON DELETE CASCADE
+    );
+    "#,
+    r#"
+    -- mapping between hash(local-seed,txid) and txid, used for randomized but efficient
+    -- paging when streaming transactions out of the mempool.
+    CREATE TABLE IF NOT EXISTS randomized_txids(
+        txid TEXT PRIMARY KEY NOT NULL,
+        hashed_txid TEXT NOT NULL,
+        FOREIGN KEY(txid) REFERENCES mempool(txid) ON DELETE CASCADE
+    );
+    "#,
+    r#"
+    INSERT INTO schema_version (version) VALUES (3)
+    "#,
+];
+
+const MEMPOOL_SCHEMA_4_EVICTIONS: &'static [&'static str] = &[
+    r#"
+    -- audit log of transactions evicted from the mempool by an operator.  Rows outlive the
+    -- evicted transactions, so there is no foreign key to the mempool table.
+    CREATE TABLE evicted_txs(
+        txid TEXT NOT NULL,
+        origin_address TEXT NOT NULL,
+        origin_nonce INTEGER NOT NULL,
+        tx_fee INTEGER NOT NULL,
+        reason TEXT NOT NULL,
+        evict_time INTEGER NOT NULL
+    );
+    "#,
+    r#"
+    INSERT INTO schema_version (version) VALUES (4)
+    "#,
+];
+
+const MEMPOOL_SCHEMA_5_PAYLOAD_TYPES: &'static [&'static str] = &[
+    r#"
+    -- the TransactionPayload::name() of each transaction's payload, for filtering listings
+    ALTER TABLE mempool ADD COLUMN payload_type TEXT;
     "#,
     r#"
-    INSERT INTO schema_version (version) VALUES (2)
+    INSERT INTO schema_version (version) VALUES (5)
     "#,
 ];
 
-const MEMPOOL_SCHEMA_3_BLOOM_STATE: &'static [&'static str] = &[
+const MEMPOOL_SCHEMA_6_NONCE_CACHE: &'static [&'static str] = &[
     r#"
-    CREATE TABLE IF NOT EXISTS removed_txids(
-        txid TEXT PRIMARY KEY NOT NULL,
-        FOREIGN KEY(txid) REFERENCES mempool(txid) ON DELETE CASCADE
+    -- the nonce of each account at the chain tip of the current mempool walk.  An account has
+    --   one nonce whether it is used as an origin or as a sponsor, so this replaces the
+    --   per-transaction last_known_origin_nonce and last_known_sponsor_nonce columns, which are
+    --   no longer used.
+    CREATE TABLE nonces(
+        address TEXT PRIMARY KEY NOT NULL,
+        nonce INTEGER NOT NULL
     );
     "#,
     r#"
-    -- mapping between hash(local-seed,txid) and txid, used for randomized but efficient
-    -- paging when streaming transactions out of the mempool.
-    CREATE TABLE IF NOT EXISTS randomized_txids(
-        txid TEXT PRIMARY KEY NOT NULL,
-        hashed_txid TEXT NOT NULL,
-        FOREIGN KEY(txid) REFERENCES mempool(txid) ON DELETE CASCADE
+    INSERT INTO schema_version (version) VALUES (6)
+    "#,
+];
+
+const MEMPOOL_SCHEMA_7_BLOOM_CONFIG: &'static [&'static str] = &[
+    r#"
+    -- the parameters the bloom counter was built with, so that it can be rebuilt when the node
+    --   is reconfigured.  Holds a single row.
+    CREATE TABLE bloom_counter_config(
+        depth INTEGER NOT NULL,
+        max_txs INTEGER NOT NULL,
+        error_rate REAL NOT NULL
     );
     "#,
     r#"
-    INSERT INTO schema_version (version) VALUES (3)
+    INSERT INTO schema_version (version) VALUES (7)
     "#,
 ];
 
+/// Forward migrations for the mempool DB schema.  The schema version is stamped into
+/// `schema_version`, which did not exist in version 1.
+const MEMPOOL_MIGRATIONS: SchemaMigrations = SchemaMigrations {
+    db_name: "mempool",
+    initial_version: 1,
+    migrations: &[
+        SchemaMigration {
+            to_version: 2,
+            description: "add fee_estimates table",
+            apply: MemPoolDB::instantiate_cost_estimator,
+        },
+        SchemaMigration {
+            to_version: 3,
+            description: "add transaction bloom filter state",
+            apply: MemPoolDB::instantiate_bloom_state,
+        },
+        SchemaMigration {
+            to_version: 4,
+            description: "add evicted transaction audit table",
+            apply: MemPoolDB::instantiate_evictions,
+        },
+        SchemaMigration {
+            to_version: 5,
+            description: "add transaction payload types",
+            apply: MemPoolDB::instantiate_payload_types,
+        },
+        SchemaMigration {
+            to_version: 6,
+            description: "add per-account nonce cache",
+            apply: MemPoolDB::instantiate_nonce_cache,
+        },
+        SchemaMigration {
+            to_version: 7,
+            description: "add bloom counter parameters",
+            apply: MemPoolDB::instantiate_bloom_config,
+        },
+    ],
+    load_version: load_mempool_schema_version,
+    store_version: store_mempool_schema_version,
+};
+
+fn load_mempool_schema_version(conn: &DBConn) -> Result<u32, db_error> {
+    let version = MemPoolDB::get_schema_version(conn)?.unwrap_or(1);
+    u32::try_from(version)
+        .map_err(|_| db_error::UnsupportedSchema(format!("mempool schema version {}", version)))
+}
+
+fn store_mempool_schema_version(tx: &DBTx, version: u32) -> Result<(), db_error> {
+    tx.execute(
+        "INSERT OR REPLACE INTO schema_version (version) VALUES (?1)",
+        &[&version],
+    )?;
+    Ok(())
+}
+
 const MEMPOOL_INDEXES: &'static [&'static str] = &[
     "CREATE INDEX IF NOT EXISTS by_txid ON mempool(txid);",
     "CREATE INDEX IF NOT EXISTS by_height ON mempool(height);",
@@ -427,6 +1630,9 @@ const MEMPOOL_INDEXES: &'static [&'static str] = &[
     "CREATE INDEX IF NOT EXISTS fee_by_txid ON fee_estimates(txid);",
     "CREATE INDEX IF NOT EXISTS by_ordered_hashed_txid ON randomized_txids(hashed_txid ASC);",
     "CREATE INDEX IF NOT EXISTS by_hashed_txid ON randomized_txids(txid,hashed_txid);",
+    "CREATE INDEX IF NOT EXISTS evicted_by_txid ON evicted_txs(txid);",
+    "CREATE INDEX IF NOT EXISTS by_fee ON mempool(tx_fee, txid);",
+    "CREATE INDEX IF NOT EXISTS by_payload_type ON mempool(payload_type, tx_fee, txid);",
 ];
 
 pub struct MemPoolDB {
@@ -434,15 +1640,21 @@ pub struct MemPoolDB {
     path: String,
     admitter: MemPoolAdmitter,
     bloom_counter: BloomCounter<BloomNodeHasher>,
+    bloom_config: MemPoolBloomConfig,
+    gc_policy: MemPoolGCPolicy,
     max_tx_tags: u32,
     cost_estimator: Box<dyn CostEstimator>,
     metric: Box<dyn CostMetric>,
+    candidate_check_pool: Option<CandidateCheckPool>,
+    /// Whether or not each candidate's signatures are valid, as checked by `check_candidates()`
+    candidate_auth_checks: HashMap<Txid, bool>,
 }
 
 pub struct MemPoolTx<'a> {
     tx: DBTx<'a>,
     admitter: &'a mut MemPoolAdmitter,
     bloom_counter: Option<&'a mut BloomCounter<BloomNodeHasher>>,
+    bloom_config: &'a MemPoolBloomConfig,
 }
 
 impl<'a> Deref for MemPoolTx<'a> {
@@ -463,11 +1675,13 @@ impl<'a> MemPoolTx<'a> {
         tx: DBTx<'a>,
         admitter: &'a mut MemPoolAdmitter,
         bloom_counter: &'a mut BloomCounter<BloomNodeHasher>,
+        bloom_config: &'a MemPoolBloomConfig,
     ) -> MemPoolTx<'a> {
         MemPoolTx {
             tx,
             admitter,
             bloom_counter: Some(bloom_counter),
+            bloom_config,
         }
     }
 
@@ -523,7 +1737,7 @@ impl<'a> MemPoolTx<'a> {
     /// Add the txid to the bloom counter in the mempool DB, optionally replacing a prior
     /// transaction (identified by prior_txid) if the bloom counter is full.
     /// If this is the first txid at this block height, then also garbage-collect the bloom counter to remove no-longer-recent transactions.
-    /// If the bloom counter is saturated -- i.e. it represents more than MAX_BLOOM_COUNTER_TXS
+    /// If the bloom counter is saturated -- i.e. it represents more than its configured `max_txs`
     /// transactions -- then pick another transaction to evict from the bloom filter and return its txid.
     /// (Note that no transactions are ever removed from the mempool; we just don't prioritize them
     /// in the bloom filter).
@@ -537,11 +1751,13 @@ impl<'a> MemPoolTx<'a> {
         let sql = "SELECT 1 FROM mempool WHERE height = ?1";
         let args: &[&dyn ToSql] = &[&u64_to_sql(height)?];
         let present: Option<i64> = query_row(&self.tx, sql, args)?;
-        if present.is_none() && height > (BLOOM_COUNTER_DEPTH as u64) {
+        let depth = self.bloom_config.depth;
+        let max_txs = self.bloom_config.max_txs;
+        if present.is_none() && height > depth {
             // this is the first-ever tx at this height.
             // which means, the bloom filter window has advanced.
             // which means, we need to remove all the txs that are now out of the window.
-            self.prune_bloom_counter(height - (BLOOM_COUNTER_DEPTH as u64))?;
+            self.prune_bloom_counter(height - depth)?;
         }
 
         MemPoolTx::with_bloom_state(self, |ref mut dbtx, ref mut bloom_counter| {
@@ -553,14 +1769,12 @@ impl<'a> MemPoolTx<'a> {
             // keep the bloom counter un-saturated -- remove at most one transaction from it to keep
             // the error rate at or below the target error rate
             let evict_txid = {
-                let num_recents = MemPoolDB::get_num_recent_txs(&dbtx)?;
-                if num_recents >= MAX_BLOOM_COUNTER_TXS.into() {
+                let num_recents = MemPoolDB::get_num_recent_txs(&dbtx, depth)?;
+                if num_recents >= max_txs.into() {
                     // for now, remove lowest-fee tx in the recent tx set.
                     // TODO: In the future, do it by lowest fee rate
                     let sql = "SELECT a.txid FROM mempool AS a LEFT OUTER JOIN removed_txids AS b ON a.txid = b.txid WHERE b.txid IS NULL AND a.height > ?1 ORDER BY a.tx_fee ASC LIMIT 1";
-                    let args: &[&dyn ToSql] = &[&u64_to_sql(
-                        height.saturating_sub(BLOOM_COUNTER_DEPTH as u64),
-                    )?];
+                    let args: &[&dyn ToSql] = &[&u64_to_sql(height.saturating_sub(depth))?];
                     let evict_txid: Option<Txid> = query_row(&dbtx, sql, args)?;
                     if let Some(evict_txid) = evict_txid {
                         bloom_counter.remove_raw(dbtx, &evict_txid.0)?;
@@ -637,8 +1851,6 @@ impl MemPoolTxInfo {
             sponsor_address,
             sponsor_nonce,
             accept_time: get_epoch_time_secs(),
-            last_known_origin_nonce: None,
-            last_known_sponsor_nonce: None,
         };
         MemPoolTxInfo { tx, metadata }
     }
@@ -684,26 +1896,20 @@ impl MemPoolDB {
 
     /// Apply all schema migrations up to the latest schema.
     fn apply_schema_migrations(tx: &mut DBTx) -> Result<(), db_error> {
-        loop {
-            let version = MemPoolDB::get_schema_version(&tx)?.unwrap_or(1);
-            match version {
-                1 => {
-                    MemPoolDB::instantiate_cost_estimator(tx)?;
-                }
-                2 => {
-                    MemPoolDB::instantiate_bloom_state(tx)?;
-                }
-                3 => {
-                    break;
-                }
-                _ => {
-                    panic!("Unknown schema version {}", version);
-                }
-            }
-        }
+        MEMPOOL_MIGRATIONS.migrate(tx)?;
         Ok(())
     }
 
+    /// Check the schema of the mempool DB in the given chainstate directory without migrating it.
+    pub fn check_schema_migrations(chainstate_path: &str) -> Result<SchemaStatus, db_error> {
+        let db_path = MemPoolDB::db_path(chainstate_path)?;
+        if fs::metadata(&db_path).is_err() {
+            return Err(db_error::NoDBError);
+        }
+        let conn = sqlite_open(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY, false)?;
+        MEMPOOL_MIGRATIONS.check(&conn)
+    }
+
     /// Add indexes
     fn add_indexes(tx: &mut DBTx) -> Result<(), db_error> {
         for cmd in MEMPOOL_INDEXES {
@@ -713,7 +1919,7 @@ impl MemPoolDB {
     }
 
     /// Instantiate the on-disk counting bloom filter
-    fn instantiate_bloom_state(tx: &mut DBTx) -> Result<(), db_error> {
+    fn instantiate_bloom_state(tx: &DBTx) -> Result<(), db_error> {
         let node_hasher = BloomNodeHasher::new_random();
         let _ = BloomCounter::new(
             tx,
@@ -729,6 +1935,106 @@ impl MemPoolDB {
         Ok(())
     }
 
+    /// Instantiate the table of bloom counter parameters.  The bloom counter was built with the
+    /// default parameters.
+    fn instantiate_bloom_config(tx: &DBTx) -> Result<(), db_error> {
+        for cmd in MEMPOOL_SCHEMA_7_BLOOM_CONFIG {
+            tx.execute_batch(cmd).map_err(db_error::SqliteError)?;
+        }
+        MemPoolDB::store_bloom_config(tx, &MemPoolBloomConfig::default())
+    }
+
+    fn store_bloom_config(tx: &DBTx, config: &MemPoolBloomConfig) -> Result<(), db_error> {
+        tx.execute("DELETE FROM bloom_counter_config", NO_PARAMS)?;
+        let args: &[&dyn ToSql] = &[
+            &u64_to_sql(config.depth)?,
+            &config.max_txs,
+            &config.error_rate,
+        ];
+        tx.execute(
+            "INSERT INTO bloom_counter_config (depth, max_txs, error_rate) VALUES (?1, ?2, ?3)",
+            args,
+        )?;
+        Ok(())
+    }
+
+    /// Rebuild the bloom counter with the parameters in `config`: resize it, and fill it with the
+    /// recent transactions under the new depth.  If there are more of them than the new capacity,
+    /// the lowest-fee ones are left out, as they would have been evicted.
+    fn rebuild_bloom_counter(
+        tx: &mut DBTx,
+        bloom_counter: &mut BloomCounter<BloomNodeHasher>,
+        config: &MemPoolBloomConfig,
+    ) -> Result<(), db_error> {
+        bloom_counter.reset(tx, config.error_rate, config.max_txs)?;
+
+        // the counter is empty, so no transaction is in it until it is re-inserted below
+        tx.execute(
+            "INSERT OR REPLACE INTO removed_txids (txid) SELECT txid FROM mempool",
+            NO_PARAMS,
+        )?;
+
+        let txids = match MemPoolDB::get_max_height(tx)? {
+            Some(max_height) => {
+                let sql = "SELECT txid FROM mempool WHERE height > ?1 AND height <= ?2 ORDER BY tx_fee DESC LIMIT ?3";
+                let args: &[&dyn ToSql] = &[
+                    &u64_to_sql(max_height.saturating_sub(config.depth))?,
+                    &u64_to_sql(max_height)?,
+                    &config.max_txs,
+                ];
+                query_rows::<Txid, _>(tx, sql, args)?
+            }
+            None => vec![],
+        };
+
+        let num_txids = txids.len();
+        for txid in txids.into_iter() {
+            bloom_counter.insert_raw(tx, &txid.0)?;
+            let args: &[&dyn ToSql] = &[&txid];
+            tx.execute("DELETE FROM removed_txids WHERE txid = ?1", args)?;
+        }
+
+        MemPoolDB::store_bloom_config(tx, config)?;
+        info!(
+            "Rebuilt mempool bloom counter";
+            "depth" => config.depth,
+            "max_txs" => config.max_txs,
+            "error_rate" => config.error_rate,
+            "num_txs" => num_txids,
+        );
+        Ok(())
+    }
+
+    /// Instantiate the evicted transaction audit table
+    fn instantiate_evictions(tx: &DBTx) -> Result<(), db_error> {
+        for cmd in MEMPOOL_SCHEMA_4_EVICTIONS {
+            tx.execute_batch(cmd).map_err(db_error::SqliteError)?;
+        }
+        Ok(())
+    }
+
+    /// Add the payload type column, and fill it in for the transactions already in the mempool
+    fn instantiate_payload_types(tx: &DBTx) -> Result<(), db_error> {
+        for cmd in MEMPOOL_SCHEMA_5_PAYLOAD_TYPES {
+            tx.execute_batch(cmd).map_err(db_error::SqliteError)?;
+        }
+
+        let txs = query_rows::<MemPoolTxInfo, _>(tx, "SELECT * FROM mempool", NO_PARAMS)?;
+        for tx_info in txs.iter() {
+            let args: &[&dyn ToSql] = &[&tx_info.tx.payload.name(), &tx_info.metadata.txid];
+            tx.execute("UPDATE mempool SET payload_type = ?1 WHERE txid = ?2", args)?;
+        }
+        Ok(())
+    }
+
+    /// Instantiate the per-account nonce cache of the mempool walk
+    fn instantiate_nonce_cache(tx: &DBTx) -> Result<(), db_error> {
+        for cmd in MEMPOOL_SCHEMA_6_NONCE_CACHE {
+            tx.execute_batch(cmd).map_err(db_error::SqliteError)?;
+        }
+        Ok(())
+    }
+
     /// Instantiate the cost estimator schema
     fn instantiate_cost_estimator(tx: &DBTx) -> Result<(), db_error> {
         for sql_exec in MEMPOOL_SCHEMA_2_COST_ESTIMATOR {
@@ -758,7 +2064,8 @@ impl MemPoolDB {
         MemPoolDB::open(mainnet, chain_id, chainstate_path, estimator, metric)
     }
 
-    /// Open the mempool db within the chainstate directory.
+    /// Open the mempool db within the chainstate directory, with the default bloom counter
+    /// parameters.
     /// The chainstate must be instantiated already.
     pub fn open(
         mainnet: bool,
@@ -766,6 +2073,27 @@ impl MemPoolDB {
         chainstate_path: &str,
         cost_estimator: Box<dyn CostEstimator>,
         metric: Box<dyn CostMetric>,
+    ) -> Result<MemPoolDB, db_error> {
+        MemPoolDB::open_with_bloom_config(
+            mainnet,
+            chain_id,
+            chainstate_path,
+            cost_estimator,
+            metric,
+            MemPoolBloomConfig::default(),
+        )
+    }
+
+    /// Open the mempool db within the chainstate directory.  If its bloom counter was built with
+    /// parameters other than `bloom_config`, it is rebuilt.
+    /// The chainstate must be instantiated already.
+    pub fn open_with_bloom_config(
+        mainnet: bool,
+        chain_id: u32,
+        chainstate_path: &str,
+        cost_estimator: Box<dyn CostEstimator>,
+        metric: Box<dyn CostMetric>,
+        bloom_config: MemPoolBloomConfig,
     ) -> Result<MemPoolDB, db_error> {
         match fs::metadata(chainstate_path) {
             Ok(md) => {
@@ -806,37 +2134,46 @@ impl MemPoolDB {
             tx.commit().map_err(db_error::SqliteError)?;
         }
 
-        let bloom_counter = BloomCounter::<BloomNodeHasher>::try_load(&conn, BLOOM_COUNTER_TABLE)?
-            .ok_or(db_error::Other(format!("Failed to load bloom counter")))?;
+        let mut bloom_counter =
+            BloomCounter::<BloomNodeHasher>::try_load(&conn, BLOOM_COUNTER_TABLE)?
+                .ok_or(db_error::Other(format!("Failed to load bloom counter")))?;
+
+        let stored_bloom_config: Option<MemPoolBloomConfig> =
+            query_row(&conn, "SELECT * FROM bloom_counter_config", NO_PARAMS)?;
+        if stored_bloom_config.as_ref() != Some(&bloom_config) {
+            let mut tx = tx_begin_immediate(&mut conn)?;
+            MemPoolDB::rebuild_bloom_counter(&mut tx, &mut bloom_counter, &bloom_config)?;
+            tx.commit().map_err(db_error::SqliteError)?;
+        }
 
         Ok(MemPoolDB {
             db: conn,
             path: db_path,
             admitter: admitter,
             bloom_counter,
+            bloom_config,
+            gc_policy: MemPoolGCPolicy::default(),
             max_tx_tags: DEFAULT_MAX_TX_TAGS,
             cost_estimator,
             metric,
+            candidate_check_pool: None,
+            candidate_auth_checks: HashMap::new(),
         })
     }
 
+    /// Forget the account nonces cached by the last mempool walk
     pub fn reset_last_known_nonces(&mut self) -> Result<(), db_error> {
-        let sql =
-            "UPDATE mempool SET last_known_origin_nonce = NULL, last_known_sponsor_nonce = NULL";
-        self.db.execute(sql, rusqlite::NO_PARAMS)?;
+        self.db.execute("DELETE FROM nonces", rusqlite::NO_PARAMS)?;
+        self.candidate_auth_checks.clear();
         Ok(())
     }
 
+    /// Advance the cached nonce of `address`, if it is cached.  The cache holds one row per
+    /// account, so this costs the same however many transactions the account has originated or
+    /// sponsored.
     fn bump_last_known_nonces(&self, address: &StacksAddress) -> Result<(), db_error> {
-        let query_by = address.to_string();
-
-        let sql = "UPDATE mempool SET last_known_origin_nonce = last_known_origin_nonce + 1
-                   WHERE origin_address = ? AND last_known_origin_nonce IS NOT NULL";
-        self.db.execute(sql, &[&query_by])?;
-
-        let sql = "UPDATE mempool SET last_known_sponsor_nonce = last_known_sponsor_nonce + 1
-                   WHERE sponsor_address = ? AND last_known_sponsor_nonce IS NOT NULL";
-        self.db.execute(sql, &[&query_by])?;
+        let sql = "UPDATE nonces SET nonce = nonce + 1 WHERE address = ?";
+        self.db.execute(sql, &[&address.to_string()])?;
         Ok(())
     }
 
@@ -845,44 +2182,81 @@ impl MemPoolDB {
         address: &StacksAddress,
         nonce: u64,
     ) -> Result<(), db_error> {
-        let addr_str = address.to_string();
-        let nonce_i64 = u64_to_sql(nonce)?;
-
-        let sql = "UPDATE mempool SET last_known_origin_nonce = ? WHERE origin_address = ?";
-        self.db
-            .execute(sql, rusqlite::params![nonce_i64, &addr_str])?;
-
-        let sql = "UPDATE mempool SET last_known_sponsor_nonce = ? WHERE sponsor_address = ?";
-        self.db
-            .execute(sql, rusqlite::params![nonce_i64, &addr_str])?;
-
+        let sql = "INSERT OR REPLACE INTO nonces (address, nonce) VALUES (?, ?)";
+        let args: &[&dyn ToSql] = &[&address.to_string(), &u64_to_sql(nonce)?];
+        self.db.execute(sql, args)?;
         Ok(())
     }
 
-    /// Select the next TX to consider from the pool of transactions without cost estimates.
+    /// Select the next TX to consider from the pool of transactions without cost estimates,
+    /// ranked by fee, or by fee per byte if `ranking` is not `Fee`.
     /// If a transaction is found, returns Some object containing the transaction and a boolean indicating
     ///  whether or not the miner should propagate transaction receipts back to the estimator.
     fn get_next_tx_to_consider_no_estimate(
         &self,
-    ) -> Result<Option<(MemPoolTxInfo, bool)>, db_error> {
-        let select_no_estimate = "SELECT * FROM mempool LEFT JOIN fee_estimates as f ON mempool.txid = f.txid WHERE
-                   ((origin_nonce = last_known_origin_nonce AND
-                     sponsor_nonce = last_known_sponsor_nonce) OR (last_known_origin_nonce is NULL) OR (last_known_sponsor_nonce is NULL))
-                   AND f.fee_rate IS NULL ORDER BY tx_fee DESC LIMIT 1";
-        query_row(&self.db, select_no_estimate, rusqlite::NO_PARAMS)
+        ranking: MemPoolWalkRanking,
+    ) -> Result<Option<(MemPoolWalkCandidate, bool)>, db_error> {
+        let order_by = match ranking {
+            MemPoolWalkRanking::Fee => "COALESCE(c.chain_fee, m.tx_fee)",
+            MemPoolWalkRanking::FeePerLength | MemPoolWalkRanking::FeePerCost => {
+                ORDER_BY_FEE_PER_LENGTH
+            }
+        };
+        let select_no_estimate = format!(
+            "SELECT m.*, o.nonce AS cached_origin_nonce, s.nonce AS cached_sponsor_nonce
+                   FROM mempool AS m LEFT JOIN fee_estimates as f ON m.txid = f.txid
+                   LEFT JOIN nonce_chain_scores as c ON m.txid = c.txid
+                   LEFT JOIN nonces AS o ON m.origin_address = o.address
+                   LEFT JOIN nonces AS s ON m.sponsor_address = s.address WHERE
+                   ((m.origin_nonce = o.nonce AND m.sponsor_nonce = s.nonce) OR (o.nonce IS NULL) OR (s.nonce IS NULL))
+                   AND f.fee_rate IS NULL ORDER BY {} DESC LIMIT 1",
+            order_by
+        );
+        query_row(&self.db, &select_no_estimate, rusqlite::NO_PARAMS)
             .map(|opt_tx| opt_tx.map(|tx| (tx, true)))
     }
 
+    /// Select the next TX to consider from the whole pool, ranked by fee per byte.
+    /// If a transaction is found, returns Some object containing the transaction and a boolean indicating
+    ///  whether or not the miner should propagate transaction receipts back to the estimator.
+    fn get_next_tx_to_consider_by_length(
+        &self,
+    ) -> Result<Option<(MemPoolWalkCandidate, bool)>, db_error> {
+        let select_by_length = format!(
+            "SELECT m.*, o.nonce AS cached_origin_nonce, s.nonce AS cached_sponsor_nonce
+                   FROM mempool AS m
+                   LEFT JOIN nonce_chain_scores as c ON m.txid = c.txid
+                   LEFT JOIN nonces AS o ON m.origin_address = o.address
+                   LEFT JOIN nonces AS s ON m.sponsor_address = s.address WHERE
+                   ((m.origin_nonce = o.nonce AND m.sponsor_nonce = s.nonce) OR (o.nonce IS NULL) OR (s.nonce IS NULL))
+                   ORDER BY {} DESC LIMIT 1",
+            ORDER_BY_FEE_PER_LENGTH
+        );
+        let next_tx: Option<MemPoolWalkCandidate> =
+            query_row(&self.db, &select_by_length, rusqlite::NO_PARAMS)?;
+        match next_tx {
+            Some(tx) => {
+                let has_estimate =
+                    MemPoolDB::get_tx_fee_rate(&self.db, &tx.tx.tx.txid())?.is_some();
+                Ok(Some((tx, !has_estimate)))
+            }
+            None => Ok(None),
+        }
+    }
+
     /// Select the next TX to consider from the pool of transactions with cost estimates.
     /// If a transaction is found, returns Some object containing the transaction and a boolean indicating
     ///  whether or not the miner should propagate transaction receipts back to the estimator.
     fn get_next_tx_to_consider_with_estimate(
         &self,
-    ) -> Result<Option<(MemPoolTxInfo, bool)>, db_error> {
-        let select_estimate = "SELECT * FROM mempool LEFT OUTER JOIN fee_estimates as f ON mempool.txid = f.txid WHERE
-                   ((origin_nonce = last_known_origin_nonce AND
-                     sponsor_nonce = last_known_sponsor_nonce) OR (last_known_origin_nonce is NULL) OR (last_known_sponsor_nonce is NULL))
-                   AND f.fee_rate IS NOT NULL ORDER BY f.fee_rate DESC LIMIT 1";
+    ) -> Result<Option<(MemPoolWalkCandidate, bool)>, db_error> {
+        let select_estimate = "SELECT m.*, o.nonce AS cached_origin_nonce, s.nonce AS cached_sponsor_nonce
+                   FROM mempool AS m LEFT OUTER JOIN fee_estimates as f ON m.txid = f.txid
+                   LEFT JOIN nonce_chain_scores as c ON m.txid = c.txid
+                   LEFT JOIN nonces AS o ON m.origin_address = o.address
+                   LEFT JOIN nonces AS s ON m.sponsor_address = s.address WHERE
+                   ((m.origin_nonce = o.nonce AND m.sponsor_nonce = s.nonce) OR (o.nonce IS NULL) OR (s.nonce IS NULL))
+                   AND f.fee_rate IS NOT NULL ORDER BY COALESCE(c.chain_fee_rate, f.fee_rate) DESC LIMIT 1";
         query_row(&self.db, select_estimate, rusqlite::NO_PARAMS)
             .map(|opt_tx| opt_tx.map(|tx| (tx, false)))
     }
@@ -891,46 +2265,186 @@ impl MemPoolDB {
     ///   start by considering transactions without a cost
     ///   estimate, and if none are found, use transactions with a cost estimate.
     ///   Pass `false` for the opposite behavior.
+    ///   Transactions are ranked by `ranking`; when it is `FeePerLength`, transactions with and
+    ///   without estimates are ranked together, and `start_with_no_estimate` is ignored.
     fn get_next_tx_to_consider(
         &self,
         start_with_no_estimate: bool,
+        ranking: MemPoolWalkRanking,
     ) -> Result<ConsiderTransactionResult, db_error> {
-        let (next_tx, update_estimate): (MemPoolTxInfo, bool) = if start_with_no_estimate {
-            match self.get_next_tx_to_consider_no_estimate()? {
-                Some(result) => result,
-                None => match self.get_next_tx_to_consider_with_estimate()? {
+        let (next_tx, update_estimate): (MemPoolWalkCandidate, bool) =
+            if ranking == MemPoolWalkRanking::FeePerLength {
+                match self.get_next_tx_to_consider_by_length()? {
                     Some(result) => result,
                     None => return Ok(ConsiderTransactionResult::NoTransactions),
-                },
-            }
-        } else {
-            match self.get_next_tx_to_consider_with_estimate()? {
-                Some(result) => result,
-                None => match self.get_next_tx_to_consider_no_estimate()? {
+                }
+            } else if start_with_no_estimate {
+                match self.get_next_tx_to_consider_no_estimate(ranking)? {
                     Some(result) => result,
-                    None => return Ok(ConsiderTransactionResult::NoTransactions),
-                },
-            }
-        };
+                    None => match self.get_next_tx_to_consider_with_estimate()? {
+                        Some(result) => result,
+                        None => return Ok(ConsiderTransactionResult::NoTransactions),
+                    },
+                }
+            } else {
+                match self.get_next_tx_to_consider_with_estimate()? {
+                    Some(result) => result,
+                    None => match self.get_next_tx_to_consider_no_estimate(ranking)? {
+                        Some(result) => result,
+                        None => return Ok(ConsiderTransactionResult::NoTransactions),
+                    },
+                }
+            };
 
         let mut needs_nonces = vec![];
-        if next_tx.metadata.last_known_origin_nonce.is_none() {
-            needs_nonces.push(next_tx.metadata.origin_address);
+        if next_tx.cached_origin_nonce.is_none() {
+            needs_nonces.push(next_tx.tx.metadata.origin_address);
         }
-        if next_tx.metadata.last_known_sponsor_nonce.is_none() {
-            needs_nonces.push(next_tx.metadata.sponsor_address);
+        if next_tx.cached_sponsor_nonce.is_none() {
+            needs_nonces.push(next_tx.tx.metadata.sponsor_address);
         }
 
         if !needs_nonces.is_empty() {
             Ok(ConsiderTransactionResult::UpdateNonces(needs_nonces))
         } else {
             Ok(ConsiderTransactionResult::Consider(ConsiderTransaction {
-                tx: next_tx,
+                tx: next_tx.tx,
                 update_estimate,
+                auth_verified: false,
             }))
         }
     }
 
+    /// Read the nonces of `addresses` from `clarity_tx`, and cache them for the mempool walk.  A
+    /// miner calls this for the accounts whose nonces it already advanced in the block it is
+    /// building, since `check_candidates()` reads nonces as of the parent block.
+    pub fn refresh_last_known_nonces<C: ClarityConnection>(
+        &mut self,
+        clarity_tx: &mut C,
+        addresses: &[StacksAddress],
+    ) -> Result<(), db_error> {
+        for address in addresses.iter() {
+            let nonce = StacksChainState::get_account(clarity_tx, &address.clone().into()).nonce;
+            self.update_last_known_nonces(address, nonce)?;
+        }
+        Ok(())
+    }
+
+    /// Start a `CandidateCheckPool` of `num_threads` workers for `check_candidates()`, each
+    /// reading nonces through a reader from `make_reader`.  Does nothing if a pool of that size
+    /// is already running, so that a long-lived mempool handle keeps its workers across blocks.
+    pub fn start_candidate_check_pool<F, E>(
+        &mut self,
+        num_threads: usize,
+        mut make_reader: F,
+    ) -> Result<(), E>
+    where
+        F: FnMut() -> Result<Box<dyn CandidateNonceReader>, E>,
+    {
+        if let Some(pool) = self.candidate_check_pool.as_ref() {
+            if pool.num_workers() == num_threads {
+                return Ok(());
+            }
+        }
+        // stop the old workers first
+        self.candidate_check_pool = None;
+        if num_threads == 0 {
+            return Ok(());
+        }
+
+        let mut readers = Vec::with_capacity(num_threads);
+        for _ in 0..num_threads {
+            readers.push(make_reader()?);
+        }
+        self.candidate_check_pool = Some(CandidateCheckPool::new(readers));
+        Ok(())
+    }
+
+    /// Check every candidate transaction in the `CandidateCheckPool` against the chain state at
+    /// `tip`, ahead of a mempool walk: the workers verify the candidates' signatures, and read
+    /// their accounts' nonces into the walk's nonce cache.  Nonces already in the cache are kept.
+    /// The walk then skips whatever was checked here, and checks anything else itself.  Spends at
+    /// most `max_time_ms` waiting on the workers.  Returns the number of candidates checked.
+    pub fn check_candidates(
+        &mut self,
+        tip: &StacksBlockId,
+        max_time_ms: u64,
+    ) -> Result<usize, db_error> {
+        self.candidate_auth_checks.clear();
+        let pool = match self.candidate_check_pool.as_ref() {
+            Some(pool) => pool,
+            None => return Ok(0),
+        };
+
+        let start_time = Instant::now();
+        let sql = "SELECT m.* FROM mempool AS m
+                   LEFT JOIN nonces AS o ON m.origin_address = o.address
+                   LEFT JOIN nonces AS s ON m.sponsor_address = s.address WHERE
+                   (o.nonce IS NULL OR m.origin_nonce >= o.nonce) AND
+                   (s.nonce IS NULL OR m.sponsor_nonce >= s.nonce)";
+        let candidates = query_rows::<MemPoolTxInfo, _>(&self.db, sql, NO_PARAMS)?;
+        let num_candidates = candidates.len();
+
+        let checks = pool.check(
+            tip,
+            candidates.into_iter().map(|txinfo| txinfo.tx).collect(),
+            Duration::from_millis(max_time_ms),
+        );
+        let num_checked = checks.len();
+
+        let sql_tx = tx_begin_immediate(&mut self.db)?;
+        for check in checks.into_iter() {
+            for (address, nonce) in check.nonces.iter() {
+                sql_tx.execute(
+                    "INSERT OR IGNORE INTO nonces (address, nonce) VALUES (?, ?)",
+                    rusqlite::params![&address.to_string(), &u64_to_sql(*nonce)?],
+                )?;
+            }
+            self.candidate_auth_checks.insert(check.txid, check.auth_ok);
+        }
+        sql_tx.commit()?;
+
+        debug!("Checked mempool candidates";
+               "num_candidates" => num_candidates,
+               "num_checked" => num_checked,
+               "num_threads" => pool.num_workers(),
+               "elapsed_ms" => start_time.elapsed().as_millis());
+        Ok(num_checked)
+    }
+
+    /// Score every transaction in the mempool as the head of its sponsor's chain of transactions
+    /// with consecutive nonces (see `score_nonce_chains()`), so that the mempool walk considers
+    /// a chain as early as its most profitable prefix warrants.  The scores are kept in a
+    /// temporary table of this connection; transactions added during the walk are unscored, and
+    /// are considered by their own fee.
+    fn update_nonce_chain_scores(&mut self) -> Result<(), db_error> {
+        let sql = "SELECT m.txid AS txid, m.sponsor_address AS sponsor_address, m.sponsor_nonce AS sponsor_nonce,
+                   m.tx_fee AS tx_fee, m.length AS length, f.fee_rate AS fee_rate
+                   FROM mempool AS m LEFT OUTER JOIN fee_estimates AS f ON m.txid = f.txid";
+        let members = query_rows::<NonceChainMember, _>(&self.db, sql, NO_PARAMS)?;
+        let scores = score_nonce_chains(&members);
+
+        let sql_tx = tx_begin_immediate(&mut self.db)?;
+        sql_tx.execute(
+            "CREATE TEMP TABLE IF NOT EXISTS nonce_chain_scores(
+                txid TEXT PRIMARY KEY NOT NULL,
+                chain_fee REAL NOT NULL,
+                chain_fee_per_length REAL NOT NULL,
+                chain_fee_rate REAL
+            )",
+            NO_PARAMS,
+        )?;
+        sql_tx.execute("DELETE FROM nonce_chain_scores", NO_PARAMS)?;
+        for (txid, chain_fee, chain_fee_per_length, chain_fee_rate) in scores.into_iter() {
+            sql_tx.execute(
+                "INSERT INTO nonce_chain_scores(txid, chain_fee, chain_fee_per_length, chain_fee_rate) VALUES (?, ?, ?, ?)",
+                rusqlite::params![&txid, chain_fee, chain_fee_per_length, chain_fee_rate],
+            )?;
+        }
+        sql_tx.commit()?;
+        Ok(())
+    }
+
     /// Find the origin addresses who have sent the highest-fee transactions
     fn find_origin_addresses_by_descending_fees(
         &self,
@@ -1006,10 +2520,14 @@ impl MemPoolDB {
     ///
     /// Iterate over candidates in the mempool
     ///  `todo` will be called once for each transaction whose origin nonce is equal
-    ///  to the origin account's nonce. At most one transaction per origin will be
-    ///  considered by this method, and transactions will be considered in
-    ///  highest-fee-first order.  This method is interruptable -- in the `settings` struct, the
-    ///  caller may choose how long to spend iterating before this method stops.
+    ///  to the origin account's nonce. Transactions will be considered in highest-fee-first
+    ///  order, where a transaction's fee is the best average fee of the chain of its sponsor's
+    ///  transactions with consecutive nonces that it unlocks -- so a burst of sequential
+    ///  transactions is selected as a whole, even if an early one has a low fee.  (The sponsor of
+    ///  an unsponsored transaction is its origin.)  `settings.ranking` picks whether the fee is
+    ///  ranked as is, per byte or per unit of estimated cost.  This method is
+    ///  interruptable -- in the `settings` struct, the caller may choose how long to spend
+    ///  iterating before this method stops.
     ///
     ///  `todo` returns a boolean representing whether or not to keep iterating.
     pub fn iterate_candidates<F, E, C>(
@@ -1028,6 +2546,7 @@ impl MemPoolDB {
         let mut total_considered = 0;
 
         debug!("Mempool walk for {}ms", settings.max_walk_time_ms,);
+        self.update_nonce_chain_scores()?;
 
         let tx_consideration_sampler = Uniform::new(0, 100);
         let mut rng = rand::thread_rng();
@@ -1044,9 +2563,14 @@ impl MemPoolDB {
                 tx_consideration_sampler.sample(&mut rng) < settings.consider_no_estimate_tx_prob
             });
 
-            match self.get_next_tx_to_consider(start_with_no_estimate)? {
+            match self.get_next_tx_to_consider(start_with_no_estimate, settings.ranking)? {
                 ConsiderTransactionResult::NoTransactions => {
                     debug!("No more transactions to consider in mempool");
+                    let num_gapped = MemPoolDB::count_nonce_gapped_origins(&self.db)?;
+                    if num_gapped > 0 {
+                        info!("Mempool walk left transactions blocked by nonce gaps";
+                              "num_accounts" => num_gapped);
+                    }
                     break;
                 }
                 ConsiderTransactionResult::UpdateNonces(addresses) => {
@@ -1068,7 +2592,13 @@ impl MemPoolDB {
                         last_addr = Some(address)
                     }
                 }
-                ConsiderTransactionResult::Consider(consider) => {
+                ConsiderTransactionResult::Consider(mut consider) => {
+                    consider.auth_verified = self
+                        .candidate_auth_checks
+                        .get(&consider.tx.metadata.txid)
+                        .cloned()
+                        .unwrap_or(false);
+
                     // if we actually consider the chosen transaction,
                     //  compute a new start_with_no_estimate on the next loop
                     remember_start_with_estimate = None;
@@ -1106,12 +2636,44 @@ impl MemPoolDB {
         &self.db
     }
 
+    /// Set the limit on each origin account's pending transactions, which is enforced when
+    /// transactions are added.
+    pub fn set_origin_quota(&mut self, quota: MemPoolOriginQuota) {
+        self.admitter.origin_quota = quota;
+    }
+
+    /// Set the limits on the smart contract deploys that are admitted, which are enforced when
+    /// transactions are submitted.
+    pub fn set_contract_deploy_limits(&mut self, limits: ContractDeployLimits) {
+        self.admitter.contract_deploy_limits = limits;
+    }
+
+    /// Set the admission checks that transactions must pass when they are submitted.
+    pub fn set_admission_policy(&mut self, policy: Box<dyn MempoolAdmissionPolicy>) {
+        self.admitter.policy = policy;
+    }
+
+    /// Set which sponsorship offers are accepted, which is enforced when transactions are added.
+    pub fn set_sponsorship_policy(&mut self, policy: SponsorshipPolicy) {
+        self.admitter.sponsorship_policy = policy;
+    }
+
+    /// Set how the mempool is garbage-collected by `garbage_collect_by_policy()`.
+    pub fn set_gc_policy(&mut self, policy: MemPoolGCPolicy) {
+        self.gc_policy = policy;
+    }
+
+    pub fn get_gc_policy(&self) -> &MemPoolGCPolicy {
+        &self.gc_policy
+    }
+
     pub fn tx_begin<'a>(&'a mut self) -> Result<MemPoolTx<'a>, db_error> {
         let tx = tx_begin_immediate(&mut self.db)?;
         Ok(MemPoolTx::new(
             tx,
             &mut self.admitter,
             &mut self.bloom_counter,
+            &self.bloom_config,
         ))
     }
 
@@ -1167,6 +2729,25 @@ impl MemPoolDB {
         Ok(rows)
     }
 
+    /// Get the transactions in the mempool that are system priority transactions under `policy`,
+    /// ordered by origin account and then by origin nonce.
+    pub fn get_system_priority_txs(
+        conn: &DBConn,
+        policy: &SystemPriorityPolicy,
+    ) -> Result<Vec<MemPoolTxInfo>, db_error> {
+        let sql = "SELECT * FROM mempool WHERE origin_address = ?1 ORDER BY origin_nonce ASC";
+        let mut txs = vec![];
+        for origin_address in policy.authorized_principals.iter() {
+            let args: &[&dyn ToSql] = &[&origin_address.to_string()];
+            let rows = query_rows::<MemPoolTxInfo, _>(conn, sql, args)?;
+            txs.extend(
+                rows.into_iter()
+                    .filter(|txinfo| policy.is_system_priority(&txinfo.tx)),
+            );
+        }
+        Ok(txs)
+    }
+
     /// Given a chain tip, find the highest block-height from _before_ this tip
     pub fn get_previous_block_height(conn: &DBConn, height: u64) -> Result<Option<u64>, db_error> {
         let sql = "SELECT height FROM mempool WHERE height < ?1 ORDER BY height DESC LIMIT 1";
@@ -1193,35 +2774,266 @@ impl MemPoolDB {
         Ok(rows)
     }
 
-    /// Get a transaction's metadata, given address and nonce, and whether the address is used as a sponsor or an origin.
-    /// Faster than getting the MemPoolTxInfo, since no deserialization will be needed.
-    /// Used to see if there exists a transaction with this info, so as to implement replace-by-fee
-    pub fn get_tx_metadata_by_address(
+    /// Get a transaction's metadata, given address and nonce, and whether the address is used as a sponsor or an origin.
+    /// Faster than getting the MemPoolTxInfo, since no deserialization will be needed.
+    /// Used to see if there exists a transaction with this info, so as to implement replace-by-fee
+    pub fn get_tx_metadata_by_address(
+        conn: &DBConn,
+        is_origin: bool,
+        addr: &StacksAddress,
+        nonce: u64,
+    ) -> Result<Option<MemPoolTxMetadata>, db_error> {
+        let sql = format!(
+            "SELECT 
+                          txid,
+                          origin_address,
+                          origin_nonce,
+                          sponsor_address,
+                          sponsor_nonce,
+                          tx_fee,
+                          length,
+                          consensus_hash,
+                          block_header_hash,
+                          height,
+                          accept_time
+                          FROM mempool WHERE {0}_address = ?1 AND {0}_nonce = ?2",
+            if is_origin { "origin" } else { "sponsor" }
+        );
+        let args: &[&dyn ToSql] = &[&addr.to_string(), &u64_to_sql(nonce)?];
+        query_row(conn, &sql, args)
+    }
+
+    /// Count the transactions in the mempool from `origin_address` whose origin nonce is at least
+    /// `min_nonce`, other than the one whose origin nonce is `excluding_nonce`.
+    pub fn count_pending_txs_by_origin(
+        conn: &DBConn,
+        origin_address: &StacksAddress,
+        min_nonce: u64,
+        excluding_nonce: u64,
+    ) -> Result<u64, db_error> {
+        let sql = "SELECT COUNT(*) FROM mempool
+                   WHERE origin_address = ?1 AND origin_nonce >= ?2 AND origin_nonce != ?3";
+        let args: &[&dyn ToSql] = &[
+            &origin_address.to_string(),
+            &u64_to_sql(min_nonce)?,
+            &u64_to_sql(excluding_nonce)?,
+        ];
+        query_int(conn, sql, args).map(|count| count as u64)
+    }
+
+    /// Find the nonces missing from the pending transactions of `address`, as origin or sponsor,
+    /// given the nonce its next transaction must have.  Also reports the nonce cached for the
+    /// account by the last mempool walk (see `iterate_candidates()`).
+    pub fn get_nonce_gaps(
+        conn: &DBConn,
+        address: &StacksAddress,
+        next_nonce: u64,
+    ) -> Result<MemPoolNonceGaps, db_error> {
+        let sql = "SELECT * FROM mempool WHERE origin_address = ?1 OR sponsor_address = ?1";
+        let args: &[&dyn ToSql] = &[&address.to_string()];
+        let txs = query_rows::<MemPoolTxMetadata, _>(conn, sql, args)?;
+
+        // the account's nonce in each transaction
+        let mut pending: Vec<(u64, Txid)> = vec![];
+        for tx in txs.into_iter() {
+            let nonce = if &tx.origin_address == address {
+                tx.origin_nonce
+            } else {
+                tx.sponsor_nonce
+            };
+            if nonce >= next_nonce {
+                pending.push((nonce, tx.txid));
+            }
+        }
+        pending.sort_by_key(|(nonce, _)| *nonce);
+
+        // the nonce the last walk cached for the account
+        let sql = "SELECT nonce FROM nonces WHERE address = ?1";
+        let last_known_nonce = query_row::<u64, _>(conn, sql, args)?;
+
+        let mut pending_nonces = vec![];
+        let mut missing_nonces = vec![];
+        let mut blocked_txids = vec![];
+        let mut expected_nonce = next_nonce;
+        for (nonce, txid) in pending.into_iter() {
+            missing_nonces.extend(expected_nonce..nonce);
+            if !missing_nonces.is_empty() {
+                blocked_txids.push(txid);
+            }
+            if pending_nonces.last() != Some(&nonce) {
+                pending_nonces.push(nonce);
+            }
+            expected_nonce = expected_nonce.max(nonce + 1);
+        }
+
+        Ok(MemPoolNonceGaps {
+            next_nonce,
+            last_known_nonce,
+            pending_nonces,
+            missing_nonces,
+            blocked_txids,
+        })
+    }
+
+    /// Get the estimated fee rate stored for a pending transaction, if it has one
+    pub fn get_tx_fee_rate(conn: &DBConn, txid: &Txid) -> Result<Option<f64>, db_error> {
+        let sql = "SELECT fee_rate FROM fee_estimates WHERE txid = ?1";
+        let fee_rate = conn
+            .query_row(sql, &[txid as &dyn ToSql], |row| {
+                row.get::<_, Option<f64>>(0)
+            })
+            .optional()?;
+        Ok(fee_rate.flatten())
+    }
+
+    /// The percentage of pending transactions, other than `txid`, whose estimated fee rate is
+    /// below `fee_rate`.  Returns None if no other pending transaction has an estimate.
+    pub fn get_fee_rate_percentile(
+        conn: &DBConn,
+        fee_rate: f64,
+        txid: &Txid,
+    ) -> Result<Option<f64>, db_error> {
+        let sql = "SELECT COUNT(*) FROM mempool AS m JOIN fee_estimates AS f ON m.txid = f.txid
+                   WHERE f.fee_rate IS NOT NULL AND m.txid != ?1";
+        let estimated = query_int(conn, sql, &[txid as &dyn ToSql])?;
+        if estimated == 0 {
+            return Ok(None);
+        }
+        let sql = "SELECT COUNT(*) FROM mempool AS m JOIN fee_estimates AS f ON m.txid = f.txid
+                   WHERE f.fee_rate IS NOT NULL AND m.txid != ?1 AND f.fee_rate < ?2";
+        let args: &[&dyn ToSql] = &[txid, &fee_rate];
+        let below = query_int(conn, sql, args)?;
+        Ok(Some(100.0 * below as f64 / estimated as f64))
+    }
+
+    /// The WHERE clause matching a listing filter, and its arguments
+    fn list_filter_clause(
+        filter: &MemPoolListFilter,
+    ) -> Result<(Vec<&'static str>, Vec<Box<dyn ToSql>>), db_error> {
+        let mut clauses = vec![];
+        let mut args: Vec<Box<dyn ToSql>> = vec![];
+        if let Some(origin) = filter.origin.as_ref() {
+            clauses.push("origin_address = ?");
+            args.push(Box::new(origin.to_string()));
+        }
+        if let Some(min_fee) = filter.min_fee {
+            clauses.push("tx_fee >= ?");
+            args.push(Box::new(u64_to_sql(min_fee)?));
+        }
+        if let Some(max_fee) = filter.max_fee {
+            clauses.push("tx_fee <= ?");
+            args.push(Box::new(u64_to_sql(max_fee)?));
+        }
+        if let Some(payload_type) = filter.payload_type.as_ref() {
+            clauses.push("payload_type = ?");
+            args.push(Box::new(payload_type.clone()));
+        }
+        Ok((clauses, args))
+    }
+
+    /// List up to `limit` pending transactions matching the filter, in order of decreasing fee
+    /// and then decreasing txid, starting just after the cursor.  Also returns the cursor of the
+    /// next page, if there may be one.
+    pub fn list_txs(
+        conn: &DBConn,
+        filter: &MemPoolListFilter,
+        cursor: Option<&MemPoolListCursor>,
+        limit: u32,
+    ) -> Result<(Vec<MemPoolTxInfo>, Option<MemPoolListCursor>), db_error> {
+        let (mut clauses, mut args) = MemPoolDB::list_filter_clause(filter)?;
+        if let Some(cursor) = cursor {
+            clauses.push("(tx_fee, txid) < (?, ?)");
+            args.push(Box::new(u64_to_sql(cursor.tx_fee)?));
+            args.push(Box::new(cursor.txid.clone()));
+        }
+        let where_clause = if clauses.is_empty() {
+            "".to_string()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
+
+        // fetch one extra transaction to find out whether there's another page
+        let limit = cmp::min(limit, MEMPOOL_LIST_MAX_LIMIT);
+        let sql = format!(
+            "SELECT * FROM mempool {} ORDER BY tx_fee DESC, txid DESC LIMIT {}",
+            where_clause,
+            limit + 1
+        );
+        let mut txs = query_rows::<MemPoolTxInfo, _>(conn, &sql, args.iter().map(|a| a.as_ref()))?;
+
+        let next_cursor = if txs.len() > limit as usize {
+            txs.truncate(limit as usize);
+            txs.last()
+                .map(|tx_info| MemPoolListCursor::from_tx(&tx_info.metadata))
+        } else {
+            None
+        };
+        Ok((txs, next_cursor))
+    }
+
+    /// The inclusive fee range of the histogram bucket a fee belongs in.  Zero fees have their
+    /// own bucket, and other fees are bucketed by order of magnitude: [1, 9], [10, 99], etc.
+    fn fee_bucket(tx_fee: u64) -> (u64, u64) {
+        if tx_fee == 0 {
+            return (0, 0);
+        }
+        let mut min_fee = 1;
+        while min_fee <= tx_fee / 10 {
+            min_fee *= 10;
+        }
+        (min_fee, min_fee.saturating_mul(10) - 1)
+    }
+
+    /// Count the pending transactions matching the filter, and bucket them by fee
+    pub fn summarize_txs(
         conn: &DBConn,
-        is_origin: bool,
-        addr: &StacksAddress,
-        nonce: u64,
-    ) -> Result<Option<MemPoolTxMetadata>, db_error> {
+        filter: &MemPoolListFilter,
+    ) -> Result<MemPoolSummary, db_error> {
+        let (clauses, args) = MemPoolDB::list_filter_clause(filter)?;
+        let where_clause = if clauses.is_empty() {
+            "".to_string()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
         let sql = format!(
-            "SELECT 
-                          txid,
-                          origin_address,
-                          origin_nonce,
-                          sponsor_address,
-                          sponsor_nonce,
-                          tx_fee,
-                          length,
-                          consensus_hash,
-                          block_header_hash,
-                          height,
-                          accept_time,
-                          last_known_sponsor_nonce,
-                          last_known_origin_nonce
-                          FROM mempool WHERE {0}_address = ?1 AND {0}_nonce = ?2",
-            if is_origin { "origin" } else { "sponsor" }
+            "SELECT tx_fee FROM mempool {} ORDER BY tx_fee",
+            where_clause
         );
-        let args: &[&dyn ToSql] = &[&addr.to_string(), &u64_to_sql(nonce)?];
-        query_row(conn, &sql, args)
+        let fees: Vec<u64> =
+            query_row_columns(conn, &sql, args.iter().map(|a| a.as_ref()), "tx_fee")?;
+
+        let mut fee_histogram: Vec<MemPoolFeeBucket> = vec![];
+        for tx_fee in fees.iter() {
+            let (min_fee, max_fee) = MemPoolDB::fee_bucket(*tx_fee);
+            match fee_histogram.last_mut() {
+                Some(bucket) if bucket.min_fee == min_fee => bucket.count += 1,
+                _ => fee_histogram.push(MemPoolFeeBucket {
+                    min_fee,
+                    max_fee,
+                    count: 1,
+                }),
+            }
+        }
+
+        Ok(MemPoolSummary {
+            count: fees.len() as u64,
+            fee_histogram,
+        })
+    }
+
+    /// Count the accounts whose pending transactions the last mempool walk could not consider,
+    /// because their nonces are ahead of the accounts' last-known nonces.
+    fn count_nonce_gapped_origins(conn: &DBConn) -> Result<u64, db_error> {
+        let sql = "SELECT COUNT(DISTINCT m.origin_address) FROM mempool AS m
+                   JOIN nonces AS o ON m.origin_address = o.address WHERE m.origin_nonce > o.nonce";
+        query_int(conn, sql, NO_PARAMS).map(|count| count as u64)
+    }
+
+    /// Count the transactions that entered the mempool at or after `timestamp` (in seconds)
+    pub fn count_txs_accepted_since(conn: &DBConn, timestamp: u64) -> Result<u64, db_error> {
+        let sql = "SELECT COUNT(*) FROM mempool WHERE accept_time >= ?1";
+        let args: &[&dyn ToSql] = &[&u64_to_sql(timestamp)?];
+        query_int(conn, sql, args).map(|count| count as u64)
     }
 
     fn are_blocks_in_same_fork(
@@ -1338,8 +3150,60 @@ impl MemPoolDB {
             return Err(MemPoolRejection::ConflictingNonceInMempool);
         }
 
+        // a sponsored transaction that replaces an unsponsored one from the same origin account
+        // and nonce is a sponsorship offer, whichever way it was submitted
+        if let Some(ref prior_tx) = prior_tx {
+            if sponsor_address != origin_address
+                && prior_tx.sponsor_address == prior_tx.origin_address
+                && prior_tx.origin_address == *origin_address
+                && prior_tx.origin_nonce == origin_nonce
+            {
+                let original = MemPoolDB::get_tx(tx, &prior_tx.txid)?
+                    .ok_or(MemPoolRejection::ConflictingNonceInMempool)?
+                    .tx;
+                let offer = StacksTransaction::consensus_deserialize(&mut &tx_bytes[..])
+                    .map_err(MemPoolRejection::DeserializationFailure)?;
+                MemPoolDB::check_sponsorship_offer(&original, &offer)?;
+                tx.admitter
+                    .sponsorship_policy
+                    .check_sponsor(sponsor_address)?;
+            }
+        }
+
+        // would this put the origin over its quota of pending transactions?  Replacing a
+        // transaction with the same origin nonce does not count against it.
+        if let Some(max_pending) = tx.admitter.origin_quota.limit_for(origin_address) {
+            let tip = StacksBlockId::new(consensus_hash, block_header_hash);
+            let account_nonce = chainstate
+                .with_read_only_clarity_tx(&NULL_BURN_STATE_DB, &tip, |conn| {
+                    StacksChainState::get_account(conn, &origin_address.clone().into()).nonce
+                })
+                .unwrap_or(0);
+            let num_pending = MemPoolDB::count_pending_txs_by_origin(
+                tx,
+                origin_address,
+                account_nonce,
+                origin_nonce,
+            )?;
+            if num_pending >= max_pending {
+                info!("TX would exceed origin's pending transaction quota";
+                      "txid" => %txid,
+                      "origin_addr" => %origin_address,
+                      "num_pending" => num_pending,
+                      "max_pending" => max_pending);
+                return Err(MemPoolRejection::TooManyPendingTransactions {
+                    principal: origin_address.clone().into(),
+                    max_pending,
+                });
+            }
+        }
+
         tx.update_bloom_counter(height, &txid, prior_tx.as_ref().map(|tx| tx.txid.clone()))?;
 
+        let payload_type = StacksTransaction::consensus_deserialize(&mut &tx_bytes[..])
+            .ok()
+            .map(|tx| tx.payload.name());
+
         let sql = "INSERT OR REPLACE INTO mempool (
             txid,
             origin_address,
@@ -1352,8 +3216,9 @@ impl MemPoolDB {
             block_header_hash,
             height,
             accept_time,
-            tx)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)";
+            tx,
+            payload_type)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)";
 
         let args: &[&dyn ToSql] = &[
             &txid,
@@ -1368,6 +3233,7 @@ impl MemPoolDB {
             &u64_to_sql(height)?,
             &u64_to_sql(get_epoch_time_secs())?,
             &tx_bytes,
+            &payload_type,
         ];
 
         tx.execute(sql, args)
@@ -1405,6 +3271,95 @@ impl MemPoolDB {
         Ok(())
     }
 
+    /// Garbage-collect the mempool as of chain height `chain_height`, according to its GC
+    /// policy.  Transactions older than the policy's `max_tx_age` are removed, and then, if the
+    /// mempool is still over its size cap, the lowest fee-rate transactions are evicted until it
+    /// is not.  Returns the evicted transactions' audit records.
+    pub fn garbage_collect_by_policy(
+        &mut self,
+        chain_height: u64,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+    ) -> Result<Vec<MemPoolEviction>, db_error> {
+        let policy = self.gc_policy.clone();
+        let mut mempool_tx = self.tx_begin()?;
+        if chain_height > policy.max_tx_age {
+            let min_height = chain_height.saturating_sub(policy.max_tx_age);
+            debug!(
+                "Remove all transactions beneath block height {}",
+                min_height
+            );
+            MemPoolDB::garbage_collect(&mut mempool_tx, min_height, event_observer)?;
+        }
+
+        let txids = MemPoolDB::find_txs_over_size_cap(&mempool_tx, &policy.strategy)?;
+        let reason = policy.strategy.eviction_reason();
+        let mut evictions = vec![];
+        for txid in txids.iter() {
+            if let Some(eviction) = MemPoolDB::evict_tx_in(&mut mempool_tx, txid, reason)? {
+                increment_mempool_gc_evictions_counter(reason);
+                evictions.push(eviction);
+            }
+        }
+        mempool_tx.commit()?;
+
+        if !evictions.is_empty() {
+            info!(
+                "Evicted {} transaction(s) from the mempool", evictions.len();
+                "reason" => reason
+            );
+            if let Some(event_observer) = event_observer {
+                event_observer.mempool_txs_dropped(txids, MemPoolDropReason::EVICTED);
+            }
+        }
+        Ok(evictions)
+    }
+
+    /// Find the transactions that `strategy` evicts to bring the mempool under its size cap,
+    /// lowest fee-rate first.
+    fn find_txs_over_size_cap(
+        conn: &DBConn,
+        strategy: &MemPoolGCStrategy,
+    ) -> Result<Vec<Txid>, db_error> {
+        let by_fee_rate = "ORDER BY CAST(tx_fee AS REAL) / MAX(length, 1) ASC, accept_time ASC";
+        match strategy {
+            MemPoolGCStrategy::Age => Ok(vec![]),
+            MemPoolGCStrategy::FeeFloor { max_txs } => {
+                let num_txs = query_int(conn, "SELECT COUNT(*) FROM mempool", NO_PARAMS)? as u64;
+                if num_txs <= *max_txs {
+                    return Ok(vec![]);
+                }
+                let sql = format!("SELECT txid FROM mempool {} LIMIT ?1", by_fee_rate);
+                let args: &[&dyn ToSql] = &[&u64_to_sql(num_txs - max_txs)?];
+                query_rows(conn, &sql, args)
+            }
+            MemPoolGCStrategy::ByteCap { max_bytes } => {
+                let total_bytes = query_int(
+                    conn,
+                    "SELECT IFNULL(SUM(length), 0) FROM mempool",
+                    NO_PARAMS,
+                )? as u64;
+                if total_bytes <= *max_bytes {
+                    return Ok(vec![]);
+                }
+                let sql = format!("SELECT txid, length FROM mempool {}", by_fee_rate);
+                let mut stmt = conn.prepare(&sql)?;
+                let mut rows = stmt.query(NO_PARAMS)?;
+                let mut excess_bytes = total_bytes - max_bytes;
+                let mut txids = vec![];
+                while let Some(row) = rows.next()? {
+                    if excess_bytes == 0 {
+                        break;
+                    }
+                    let txid = Txid::from_column(row, "txid")?;
+                    let length = u64::from_column(row, "length")?;
+                    excess_bytes = excess_bytes.saturating_sub(length);
+                    txids.push(txid);
+                }
+                Ok(txids)
+            }
+        }
+    }
+
     #[cfg(test)]
     pub fn clear_before_height(&mut self, min_height: u64) -> Result<(), db_error> {
         let mut tx = self.tx_begin()?;
@@ -1578,6 +3533,292 @@ impl MemPoolDB {
         Ok(())
     }
 
+    /// Submit a transaction like `submit()`, and report on each admission check it went
+    /// through.
+    pub fn submit_with_report(
+        &mut self,
+        chainstate: &mut StacksChainState,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        tx: &StacksTransaction,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        block_limit: &ExecutionCost,
+        stacks_epoch_id: &StacksEpochId,
+    ) -> MemPoolAdmissionReport {
+        let mut report = MemPoolAdmissionReport {
+            result: Ok(()),
+            tx_size: tx.tx_len(),
+            fee_rate: None,
+            passed_checks: vec![],
+            failed_check: None,
+        };
+        report.result = self.submit_checks(
+            chainstate,
+            consensus_hash,
+            block_hash,
+            tx,
+            event_observer,
+            block_limit,
+            stacks_epoch_id,
+            &mut report,
+        );
+        report
+    }
+
+    /// Run each admission check in turn, recording the outcomes in `report`
+    fn submit_checks(
+        &mut self,
+        chainstate: &mut StacksChainState,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        tx: &StacksTransaction,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        block_limit: &ExecutionCost,
+        stacks_epoch_id: &StacksEpochId,
+        report: &mut MemPoolAdmissionReport,
+    ) -> Result<(), MemPoolRejection> {
+        let estimator_result = cost_estimates::estimate_fee_rate(
+            tx,
+            self.cost_estimator.as_ref(),
+            self.metric.as_ref(),
+            block_limit,
+            stacks_epoch_id,
+        );
+        let fee_rate = report.record(
+            MemPoolAdmissionCheck::FeeRateEstimate,
+            match estimator_result {
+                Ok(x) => Ok(Some(x)),
+                Err(EstimatorError::NoEstimateAvailable) => Ok(None),
+                Err(e) => {
+                    warn!("Error while estimating mempool tx rate";
+                          "txid" => %tx.txid(),
+                          "error" => ?e);
+                    Err(MemPoolRejection::EstimatorError(e))
+                }
+            },
+        )?;
+        report.fee_rate = fee_rate;
+
+        let mut mempool_tx = self.tx_begin().map_err(MemPoolRejection::DBError)?;
+        mempool_tx
+            .admitter
+            .set_block(block_hash, consensus_hash.clone());
+        report.record(
+            MemPoolAdmissionCheck::ContractDeployLimits,
+            mempool_tx
+                .admitter
+                .check_contract_deploy_limits(chainstate, tx),
+        )?;
+        report.record(
+            MemPoolAdmissionCheck::AdmissionPolicy,
+            mempool_tx
+                .admitter
+                .check_policy(chainstate, tx, report.tx_size),
+        )?;
+        report.record(
+            MemPoolAdmissionCheck::MemPoolLimits,
+            MemPoolDB::tx_submit(
+                &mut mempool_tx,
+                chainstate,
+                consensus_hash,
+                block_hash,
+                tx,
+                false,
+                event_observer,
+                fee_rate,
+            ),
+        )?;
+        mempool_tx.commit().map_err(MemPoolRejection::DBError)?;
+        Ok(())
+    }
+
+    /// Submit a batch of transactions to the mempool, in order.
+    /// If `all_or_nothing` is false, each transaction is admitted or rejected on its own, exactly
+    /// as with `submit()`.  If it is true, the whole batch is admitted in one database
+    /// transaction, which is rolled back at the first rejection; no transactions after it are
+    /// tried.
+    /// Returns one result per transaction tried.
+    pub fn submit_batch(
+        &mut self,
+        chainstate: &mut StacksChainState,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        txs: &[StacksTransaction],
+        all_or_nothing: bool,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        block_limit: &ExecutionCost,
+        stacks_epoch_id: &StacksEpochId,
+    ) -> Result<Vec<Result<(), MemPoolRejection>>, MemPoolRejection> {
+        if !all_or_nothing {
+            return Ok(txs
+                .iter()
+                .map(|tx| {
+                    self.submit(
+                        chainstate,
+                        consensus_hash,
+                        block_hash,
+                        tx,
+                        event_observer,
+                        block_limit,
+                        stacks_epoch_id,
+                    )
+                })
+                .collect());
+        }
+
+        let mut fee_rates = Vec::with_capacity(txs.len());
+        for tx in txs.iter() {
+            let estimator_result = cost_estimates::estimate_fee_rate(
+                tx,
+                self.cost_estimator.as_ref(),
+                self.metric.as_ref(),
+                block_limit,
+                stacks_epoch_id,
+            );
+            let fee_rate = match estimator_result {
+                Ok(x) => Ok(Some(x)),
+                Err(EstimatorError::NoEstimateAvailable) => Ok(None),
+                Err(e) => {
+                    warn!("Error while estimating mempool tx rate";
+                          "txid" => %tx.txid(),
+                          "error" => ?e);
+                    Err(MemPoolRejection::EstimatorError(e))
+                }
+            };
+            fee_rates.push(fee_rate);
+        }
+
+        // replaced transactions are only reported once the batch is committed
+        let deferred_events = DeferredMemPoolDropEvents::new();
+        let mut results = Vec::with_capacity(txs.len());
+        let mut mempool_tx = self.tx_begin().map_err(MemPoolRejection::DBError)?;
+        for (tx, fee_rate) in txs.iter().zip(fee_rates.into_iter()) {
+            let result = fee_rate.and_then(|fee_rate| {
+                MemPoolDB::tx_submit(
+                    &mut mempool_tx,
+                    chainstate,
+                    consensus_hash,
+                    block_hash,
+                    tx,
+                    true,
+                    Some(&deferred_events),
+                    fee_rate,
+                )
+            });
+            let rejected = result.is_err();
+            results.push(result);
+            if rejected {
+                // dropping mempool_tx rolls back the batch
+                return Ok(results);
+            }
+        }
+        mempool_tx.commit().map_err(MemPoolRejection::DBError)?;
+
+        if let Some(event_observer) = event_observer {
+            for (txids, reason) in deferred_events.dropped.into_inner().into_iter() {
+                event_observer.mempool_txs_dropped(txids, reason);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Check that `offer` is a sponsored version of the unsponsored transaction `original`.  The
+    /// offer must be signed by the same origin account with the same nonce, and authorize the
+    /// same payload under the same post-conditions; only the fee and the sponsor may differ.
+    fn check_sponsorship_offer(
+        original: &StacksTransaction,
+        offer: &StacksTransaction,
+    ) -> Result<(), MemPoolRejection> {
+        if !original.auth.is_standard() {
+            return Err(MemPoolRejection::BadSponsorship(
+                "transaction is already sponsored".to_string(),
+            ));
+        }
+        let sponsor_address = match offer.sponsor_address() {
+            Some(address) => address,
+            None => {
+                return Err(MemPoolRejection::BadSponsorship(
+                    "offer is not a sponsored transaction".to_string(),
+                ));
+            }
+        };
+        if sponsor_address == offer.origin_address() {
+            return Err(MemPoolRejection::BadSponsorship(
+                "sponsor must not be the origin".to_string(),
+            ));
+        }
+        if offer.origin_address() != original.origin_address()
+            || offer.get_origin_nonce() != original.get_origin_nonce()
+        {
+            return Err(MemPoolRejection::BadSponsorship(
+                "offer has a different origin account or nonce".to_string(),
+            ));
+        }
+        if offer.version != original.version
+            || offer.chain_id != original.chain_id
+            || offer.anchor_mode != original.anchor_mode
+            || offer.post_condition_mode != original.post_condition_mode
+            || offer.post_conditions != original.post_conditions
+            || offer.payload != original.payload
+        {
+            return Err(MemPoolRejection::BadSponsorship(
+                "offer does not authorize the same transaction".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Submit a sponsorship offer for the unsponsored transaction `sponsored_txid`, which must be
+    /// in the mempool.  If the offer is permitted by the sponsorship policy and authorizes the
+    /// same transaction (see `check_sponsorship_offer()`), it is submitted like any other
+    /// transaction.  Since it
+    /// has the same origin nonce, it replaces the original by fee, so the sponsor must pay more
+    /// than the origin offered to.
+    pub fn submit_sponsorship(
+        &mut self,
+        chainstate: &mut StacksChainState,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        sponsored_txid: &Txid,
+        offer: &StacksTransaction,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        block_limit: &ExecutionCost,
+        stacks_epoch_id: &StacksEpochId,
+    ) -> Result<(), MemPoolRejection> {
+        if !self.admitter.sponsorship_policy.enabled {
+            return Err(MemPoolRejection::SponsorshipNotAllowed(
+                "node does not accept sponsorship offers".to_string(),
+            ));
+        }
+
+        let original = match MemPoolDB::get_tx(self.conn(), sponsored_txid)? {
+            Some(tx_info) => tx_info.tx,
+            None => {
+                return Err(MemPoolRejection::BadSponsorship(format!(
+                    "no transaction {} in the mempool",
+                    sponsored_txid
+                )));
+            }
+        };
+        MemPoolDB::check_sponsorship_offer(&original, offer)?;
+
+        if let Some(sponsor_address) = offer.sponsor_address() {
+            self.admitter
+                .sponsorship_policy
+                .check_sponsor(&sponsor_address)?;
+        }
+
+        self.submit(
+            chainstate,
+            consensus_hash,
+            block_hash,
+            offer,
+            event_observer,
+            block_limit,
+            stacks_epoch_id,
+        )
+    }
+
     /// Directly submit to the mempool, and don't do any admissions checks.
     /// This method is only used during testing, but because it is used by the
     ///  integration tests, it cannot be marked #[cfg(test)].
@@ -1641,6 +3882,70 @@ impl MemPoolDB {
         Ok(())
     }
 
+    /// Evict a transaction from the mempool, and record it and `reason` in the eviction audit
+    /// table.  Unlike `drop_txs`, this also removes the transaction from the bloom counter, so
+    /// peers are not told we have it.  Returns the audit record, or None if the transaction is not
+    /// in the mempool.
+    pub fn evict_tx(
+        &mut self,
+        txid: &Txid,
+        reason: &str,
+    ) -> Result<Option<MemPoolEviction>, db_error> {
+        let mut mempool_tx = self.tx_begin()?;
+        let eviction = MemPoolDB::evict_tx_in(&mut mempool_tx, txid, reason)?;
+        mempool_tx.commit()?;
+        Ok(eviction)
+    }
+
+    /// Evict a transaction from the mempool within an open mempool transaction
+    fn evict_tx_in(
+        mempool_tx: &mut MemPoolTx,
+        txid: &Txid,
+        reason: &str,
+    ) -> Result<Option<MemPoolEviction>, db_error> {
+        let metadata = match MemPoolDB::get_tx(mempool_tx, txid)? {
+            Some(tx_info) => tx_info.metadata,
+            None => return Ok(None),
+        };
+
+        // a transaction is in the bloom counter until it is recorded in removed_txids
+        let sql = "SELECT 1 FROM removed_txids WHERE txid = ?1";
+        let removed: Option<i64> = query_row(mempool_tx, sql, &[txid as &dyn ToSql])?;
+        if removed.is_none() {
+            MemPoolTx::with_bloom_state(mempool_tx, |dbtx, bloom_counter| {
+                bloom_counter.remove_raw(dbtx, &txid.0)
+            })?;
+        }
+
+        let eviction = MemPoolEviction {
+            txid: txid.clone(),
+            origin_address: metadata.origin_address,
+            origin_nonce: metadata.origin_nonce,
+            tx_fee: metadata.tx_fee,
+            reason: reason.to_string(),
+            evict_time: get_epoch_time_secs(),
+        };
+        let sql = "INSERT INTO evicted_txs (txid, origin_address, origin_nonce, tx_fee, reason, evict_time) VALUES (?1, ?2, ?3, ?4, ?5, ?6)";
+        let args: &[&dyn ToSql] = &[
+            &eviction.txid,
+            &eviction.origin_address.to_string(),
+            &u64_to_sql(eviction.origin_nonce)?,
+            &u64_to_sql(eviction.tx_fee)?,
+            &eviction.reason,
+            &u64_to_sql(eviction.evict_time)?,
+        ];
+        mempool_tx.execute(sql, args)?;
+        mempool_tx.execute("DELETE FROM mempool WHERE txid = ?1", &[txid])?;
+
+        Ok(Some(eviction))
+    }
+
+    /// Get the eviction audit records for a transaction, oldest first
+    pub fn get_tx_evictions(conn: &DBConn, txid: &Txid) -> Result<Vec<MemPoolEviction>, db_error> {
+        let sql = "SELECT * FROM evicted_txs WHERE txid = ?1 ORDER BY evict_time ASC, rowid ASC";
+        query_rows(conn, sql, &[txid as &dyn ToSql])
+    }
+
     #[cfg(test)]
     pub fn dump_txs(&self) {
         let sql = "SELECT * FROM mempool";
@@ -1692,7 +3997,7 @@ impl MemPoolDB {
                 return Ok(vec![]);
             }
         };
-        let min_height = max_height.saturating_sub(BLOOM_COUNTER_DEPTH as u64);
+        let min_height = max_height.saturating_sub(self.bloom_config.depth);
         let sql = "SELECT mempool.txid FROM mempool WHERE height > ?1 AND height <= ?2 AND NOT EXISTS (SELECT 1 FROM removed_txids WHERE txid = mempool.txid)";
         let args: &[&dyn ToSql] = &[&u64_to_sql(min_height)?, &u64_to_sql(max_height)?];
         query_rows(&self.conn(), sql, args)
@@ -1710,9 +4015,9 @@ impl MemPoolDB {
         })
     }
 
-    /// How many recent transactions are there -- i.e. within BLOOM_COUNTER_DEPTH block heights of
-    /// the chain tip?
-    pub fn get_num_recent_txs(conn: &DBConn) -> Result<u64, db_error> {
+    /// How many recent transactions are there -- i.e. within `depth` block heights of the chain
+    /// tip?
+    pub fn get_num_recent_txs(conn: &DBConn, depth: u64) -> Result<u64, db_error> {
         let max_height = match MemPoolDB::get_max_height(conn)? {
             Some(h) => h,
             None => {
@@ -1720,7 +4025,7 @@ impl MemPoolDB {
                 return Ok(0);
             }
         };
-        let min_height = max_height.saturating_sub(BLOOM_COUNTER_DEPTH as u64);
+        let min_height = max_height.saturating_sub(depth);
         let sql = "SELECT COUNT(txid) FROM mempool WHERE height > ?1 AND height <= ?2";
         let args: &[&dyn ToSql] = &[&u64_to_sql(min_height)?, &u64_to_sql(max_height)?];
         query_int(conn, sql, args).map(|cnt| cnt as u64)
@@ -1730,7 +4035,7 @@ impl MemPoolDB {
     /// If sufficiently sparse, use a MemPoolSyncData::TxTags variant
     /// Otherwise, use a MemPoolSyncData::BloomFilter variant
     pub fn make_mempool_sync_data(&self) -> Result<MemPoolSyncData, db_error> {
-        let num_tags = MemPoolDB::get_num_recent_txs(self.conn())?;
+        let num_tags = MemPoolDB::get_num_recent_txs(self.conn(), self.bloom_config.depth)?;
         if num_tags < self.max_tx_tags.into() {
             let seed = self.bloom_counter.get_seed().clone();
             let tags = self.get_txtags(&seed)?;
@@ -1747,17 +4052,33 @@ impl MemPoolDB {
         query_row(&self.conn(), sql, args)
     }
 
+    /// The bloom counter depth to use when serving a mempool query from a peer whose bloom
+    /// counter has depth `peer_depth`: transactions older than either node's depth are not
+    /// synced.  Peers that do not say what their depth is use the default.
+    pub fn negotiate_bloom_depth(&self, peer_depth: Option<u64>) -> u64 {
+        cmp::min(
+            self.bloom_config.depth,
+            peer_depth.unwrap_or(BLOOM_COUNTER_DEPTH as u64),
+        )
+    }
+
+    pub fn get_bloom_config(&self) -> &MemPoolBloomConfig {
+        &self.bloom_config
+    }
+
     /// Get the next batch of transactions from our mempool that are *not* represented in the given
     /// MemPoolSyncData.  Transactions are ordered lexicographically by randomized_txids.hashed_txid, since this allows us
     /// to use the txid as a cursor while ensuring that each node returns txids in a deterministic random order
     /// (so if some nodes are configured to return fewer than MAX_BLOOM_COUNTER_TXS transactions,
     /// a requesting node will still have a good chance of getting something useful).
+    /// Only transactions within `depth` blocks of `height` are considered.
     /// Also, return the next value to pass for `last_randomized_txid` to load the next page.
     /// Also, return the number of rows considered.
     pub fn find_next_missing_transactions(
         &self,
         data: &MemPoolSyncData,
         height: u64,
+        depth: u64,
         last_randomized_txid: &Txid,
         max_txs: u64,
         max_run: u64,
@@ -1774,7 +4095,7 @@ impl MemPoolDB {
 
         let args: &[&dyn ToSql] = &[
             &last_randomized_txid,
-            &u64_to_sql(height.saturating_sub(BLOOM_COUNTER_DEPTH as u64))?,
+            &u64_to_sql(height.saturating_sub(depth))?,
             &u64_to_sql(max_run)?,
         ];
 
@@ -1887,6 +4208,7 @@ impl MemPoolDB {
                     .find_next_missing_transactions(
                         &query.tx_query,
                         query.height,
+                        query.depth,
                         &query.last_randomized_txid,
                         1,
                         remaining,
@@ -1957,3 +4279,143 @@ impl MemPoolDB {
         Ok(num_written)
     }
 }
+
+/// Fees paid by the transactions in one subnet block, for estimating subnet fee rates
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubnetBlockFees {
+    /// (fee rate in microSTX per byte, length in bytes) of each fee-paying transaction
+    pub tx_fee_rates: Vec<(f64, u64)>,
+    /// fraction of the block limit consumed by this block, in its most-consumed dimension
+    pub fullness: f64,
+}
+
+/// Fee rate estimates for the next subnet block, in microSTX per byte
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubnetFeeRateEstimate {
+    pub low: f64,
+    pub middle: f64,
+    pub high: f64,
+    /// how many recent blocks were considered
+    pub num_blocks: u64,
+    /// how many fee-paying transactions were in those blocks
+    pub num_transactions: u64,
+    /// mean fraction of the block limit consumed by those blocks
+    pub average_fullness: f64,
+}
+
+/// Estimates fee rates from the transactions actually included in recent subnet blocks.
+///
+/// The L1-oriented fee estimators assume that a block which is not full was space-constrained at
+/// the minimum fee rate, and pad their samples accordingly.  Subnet blocks are produced on every
+/// L1 block regardless of how full they are, so that padding drags every estimate down to the
+/// minimum.  This estimator only considers the fee rates that transactions really paid, weighted
+/// by their lengths, and reports block fullness separately.
+pub struct SubnetFeeEstimator {}
+
+impl SubnetFeeEstimator {
+    /// Default number of recent subnet blocks to estimate from
+    pub const DEFAULT_NUM_BLOCKS: u64 = 32;
+
+    /// Load the fees paid in the last `num_blocks` blocks ending at `tip` (excluding the genesis
+    /// block).  Blocks are returned from highest to lowest.
+    pub fn get_recent_block_fees(
+        chainstate: &StacksChainState,
+        tip: &StacksBlockId,
+        block_limit: &ExecutionCost,
+        num_blocks: u64,
+    ) -> Result<Vec<SubnetBlockFees>, ChainstateError> {
+        let tip_header = match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            chainstate.db(),
+            tip,
+        )? {
+            Some(header) => header,
+            None => {
+                return Ok(vec![]);
+            }
+        };
+
+        let lowest_height = cmp::max(
+            1,
+            (tip_header.stacks_block_height + 1).saturating_sub(num_blocks),
+        );
+        let headers =
+            StacksChainState::get_ancestors_headers(chainstate.db(), tip_header, lowest_height)?;
+
+        let mut block_fees = Vec::with_capacity(headers.len());
+        for header in headers.into_iter() {
+            let block_hash = header.anchored_header.block_hash();
+            let block = match StacksChainState::load_block(
+                &chainstate.blocks_path,
+                &header.consensus_hash,
+                &block_hash,
+            )? {
+                Some(block) => block,
+                None => {
+                    continue;
+                }
+            };
+
+            let tx_fee_rates = block
+                .txs
+                .iter()
+                .filter(|tx| tx.get_tx_fee() > 0)
+                .map(|tx| {
+                    let len = cmp::max(1, tx.tx_len());
+                    ((tx.get_tx_fee() as f64) / (len as f64), len)
+                })
+                .collect();
+
+            let cost_fullness = StacksChainState::get_stacks_block_anchored_cost(
+                chainstate.db(),
+                &header.index_block_hash(),
+            )?
+            .map(|cost| (block_limit.proportion_largest_dimension(&cost) as f64) / 100.0)
+            .unwrap_or(0.0);
+            let len_fullness = (header.anchored_block_size as f64) / (MAX_BLOCK_LEN as f64);
+
+            block_fees.push(SubnetBlockFees {
+                tx_fee_rates,
+                fullness: cost_fullness.max(len_fullness).min(1.0),
+            });
+        }
+        Ok(block_fees)
+    }
+
+    /// Compute the 5th, 50th and 95th percentile fee rates paid in `block_fees`, weighted by
+    /// transaction length.  No estimate is ever lower than the minimum relay fee rate.
+    pub fn estimate(block_fees: &[SubnetBlockFees]) -> SubnetFeeRateEstimate {
+        let mut samples: Vec<(f64, u64)> = block_fees
+            .iter()
+            .flat_map(|block| block.tx_fee_rates.iter().cloned())
+            .collect();
+        samples.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(cmp::Ordering::Equal));
+
+        let total_weight: u64 = samples.iter().map(|(_, len)| *len).sum();
+        let percentile = |p: f64| -> f64 {
+            let target = p * (total_weight as f64);
+            let mut cumulative = 0;
+            for (rate, len) in samples.iter() {
+                cumulative += *len;
+                if (cumulative as f64) >= target {
+                    return rate.max(MINIMUM_TX_FEE_RATE_PER_BYTE as f64);
+                }
+            }
+            MINIMUM_TX_FEE_RATE_PER_BYTE as f64
+        };
+
+        let average_fullness = if block_fees.len() > 0 {
+            block_fees.iter().map(|block| block.fullness).sum::<f64>() / (block_fees.len() as f64)
+        } else {
+            0.0
+        };
+
+        SubnetFeeRateEstimate {
+            low: percentile(0.05),
+            middle: percentile(0.5),
+            high: percentile(0.95),
+            num_blocks: block_fees.len() as u64,
+            num_transactions: samples.len() as u64,
+            average_fullness,
+        }
+    }
+}
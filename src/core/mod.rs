@@ -47,14 +47,20 @@ pub const LAYER_1_CHAIN_ID_TESTNET: u32 = 0x80000000;
 
 // peer version (big-endian)
 // first byte == major network protocol version (currently 0x18)
-// second and third bytes are unused
-// fourth byte == highest epoch supported by this node (0x05 for 2.05)
-pub const PEER_VERSION_MAINNET: u32 = 0x18000005;
-pub const PEER_VERSION_TESTNET: u32 = 0xfacade05;
+// second byte is unused
+// third byte == protocol features; see PEER_VERSION_SUBNET_ID
+// fourth byte == highest epoch supported by this node (0x06 for subnet-1)
+pub const PEER_VERSION_MAINNET: u32 = 0x18000106;
+pub const PEER_VERSION_TESTNET: u32 = 0xfacadf06;
+
+// set in the peer version of nodes whose handshakes carry their subnet ID.  Nodes without it only
+// understand handshakes without one.
+pub const PEER_VERSION_SUBNET_ID: u32 = 0x00000100;
 
 pub const PEER_VERSION_EPOCH_1_0: u8 = 0x00;
 pub const PEER_VERSION_EPOCH_2_0: u8 = 0x00;
 pub const PEER_VERSION_EPOCH_2_05: u8 = 0x05;
+pub const PEER_VERSION_EPOCH_SUBNET_1: u8 = 0x06;
 
 // network identifiers
 pub const NETWORK_ID_MAINNET: u32 = 0x17000000;
@@ -111,6 +117,10 @@ pub const BITCOIN_REGTEST_FIRST_BLOCK_HEIGHT: u64 = 0;
 pub const BITCOIN_REGTEST_FIRST_BLOCK_TIMESTAMP: u32 = 0;
 pub const BITCOIN_REGTEST_FIRST_BLOCK_HASH: &str =
     "0000000000000000000000000000000000000000000000000000000000000000";
+/// Default burn height at which subnets using the regtest epochs enter the subnet-1 epoch.
+/// Subnets can move it with `[[subnet_epochs]]`; whichever schedule a subnet's node starts with
+/// is committed to its genesis block, and cannot change afterwards.
+pub const SUBNET_EPOCH_1_REGTEST_BURN_HEIGHT: u64 = 2000;
 
 pub const FIRST_STACKS_BLOCK_HASH: BlockHeaderHash = BlockHeaderHash([0u8; 32]);
 pub const EMPTY_MICROBLOCK_PARENT_HASH: BlockHeaderHash = BlockHeaderHash([0u8; 32]);
@@ -168,6 +178,16 @@ pub const BLOCK_LIMIT_MAINNET_205: ExecutionCost = ExecutionCost {
     runtime: 5_000_000_000,
 };
 
+// Budget for a single run of a contract's deposit handler.  Deposits are processed without a
+//  cost limit, so each handler is held to this budget instead, and rolled back if it exceeds it.
+pub const DEPOSIT_HANDLER_COST_LIMIT: ExecutionCost = ExecutionCost {
+    write_length: 150_000,
+    write_count: 75,
+    read_length: 1_000_000,
+    read_count: 75,
+    runtime: 50_000_000,
+};
+
 // Block limit for the testnet in Stacks 2.0.
 pub const HELIUM_BLOCK_LIMIT_20: ExecutionCost = ExecutionCost {
     write_length: 15_0_000_000,
@@ -245,7 +265,7 @@ lazy_static! {
 }
 
 lazy_static! {
-    pub static ref STACKS_EPOCHS_REGTEST: [StacksEpoch; 3] = [
+    pub static ref STACKS_EPOCHS_REGTEST: [StacksEpoch; 4] = [
         StacksEpoch {
             epoch_id: StacksEpochId::Epoch10,
             start_height: 0,
@@ -263,10 +283,17 @@ lazy_static! {
         StacksEpoch {
             epoch_id: StacksEpochId::Epoch2_05,
             start_height: 1000,
-            end_height: STACKS_EPOCH_MAX,
+            end_height: SUBNET_EPOCH_1_REGTEST_BURN_HEIGHT,
             block_limit: HELIUM_BLOCK_LIMIT_20.clone(),
             network_epoch: PEER_VERSION_EPOCH_2_05
         },
+        StacksEpoch {
+            epoch_id: StacksEpochId::EpochSubnet1,
+            start_height: SUBNET_EPOCH_1_REGTEST_BURN_HEIGHT,
+            end_height: STACKS_EPOCH_MAX,
+            block_limit: HELIUM_BLOCK_LIMIT_20.clone(),
+            network_epoch: PEER_VERSION_EPOCH_SUBNET_1
+        },
     ];
 }
 
@@ -326,6 +353,14 @@ fn test_ord_for_stacks_epoch_id() {
         StacksEpochId::Epoch20.cmp(&StacksEpochId::Epoch10),
         Ordering::Greater
     );
+    assert_eq!(
+        StacksEpochId::EpochSubnet1.cmp(&StacksEpochId::Epoch2_05),
+        Ordering::Greater
+    );
+    assert_eq!(
+        StacksEpochId::Epoch2_05.cmp(&StacksEpochId::EpochSubnet1),
+        Ordering::Less
+    );
 }
 pub trait StacksEpochExtension {
     #[cfg(test)]
@@ -334,6 +369,8 @@ pub trait StacksEpochExtension {
     fn unit_test_2_05(epoch_2_0_block_height: u64) -> Vec<StacksEpoch>;
     #[cfg(test)]
     fn unit_test_pre_2_05(epoch_2_0_block_height: u64) -> Vec<StacksEpoch>;
+    #[cfg(test)]
+    fn unit_test_subnet_1(epoch_2_0_block_height: u64) -> Vec<StacksEpoch>;
 }
 
 impl StacksEpochExtension for StacksEpoch {
@@ -400,6 +437,20 @@ impl StacksEpochExtension for StacksEpoch {
         ]
     }
 
+    #[cfg(test)]
+    fn unit_test_subnet_1(first_burnchain_height: u64) -> Vec<StacksEpoch> {
+        let mut epochs = StacksEpoch::unit_test_2_05(first_burnchain_height);
+        epochs[2].end_height = first_burnchain_height + 8;
+        epochs.push(StacksEpoch {
+            epoch_id: StacksEpochId::EpochSubnet1,
+            start_height: first_burnchain_height + 8,
+            end_height: STACKS_EPOCH_MAX,
+            block_limit: epochs[2].block_limit.clone(),
+            network_epoch: PEER_VERSION_EPOCH_SUBNET_1,
+        });
+        epochs
+    }
+
     #[cfg(test)]
     fn unit_test(stacks_epoch_id: StacksEpochId, first_burnchain_height: u64) -> Vec<StacksEpoch> {
         match stacks_epoch_id {
@@ -407,6 +458,7 @@ impl StacksEpochExtension for StacksEpoch {
                 StacksEpoch::unit_test_pre_2_05(first_burnchain_height)
             }
             StacksEpochId::Epoch2_05 => StacksEpoch::unit_test_2_05(first_burnchain_height),
+            StacksEpochId::EpochSubnet1 => StacksEpoch::unit_test_subnet_1(first_burnchain_height),
         }
     }
 }
@@ -15,8 +15,9 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::cmp;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::io;
+use std::time::Duration;
 
 use crate::burnchains::Address;
 use crate::burnchains::Txid;
@@ -25,6 +26,9 @@ use crate::chainstate::stacks::db::test::chainstate_path;
 use crate::chainstate::stacks::db::test::instantiate_chainstate;
 use crate::chainstate::stacks::db::test::instantiate_chainstate_with_balances;
 use crate::chainstate::stacks::db::StreamCursor;
+use crate::chainstate::stacks::miner::test::{
+    make_user_contract_publish, make_user_stacks_transfer,
+};
 use crate::chainstate::stacks::test::codec_all_transactions;
 use crate::chainstate::stacks::{
     db::blocks::MemPoolRejection, db::StacksChainState, index::MarfTrieId, CoinbasePayload,
@@ -37,11 +41,26 @@ use crate::chainstate::stacks::{
 use crate::chainstate::stacks::{
     C32_ADDRESS_VERSION_MAINNET_SINGLESIG, C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
 };
+use crate::core::mempool::ContractDeployLimits;
+use crate::core::mempool::MemPoolBloomConfig;
+use crate::core::mempool::MemPoolNonceStatus;
+use crate::core::mempool::MemPoolOriginQuota;
+use crate::core::mempool::MemPoolWalkRanking;
 use crate::core::mempool::MemPoolWalkSettings;
+use crate::core::mempool::MempoolAdmissionPolicy;
+use crate::core::mempool::SponsorshipPolicy;
+use crate::core::mempool::StrictAdmissionPolicy;
 use crate::core::mempool::TxTag;
+use crate::core::mempool::{CandidateCheckPool, CandidateNonceReader};
+use crate::core::mempool::{MemPoolFeeBucket, MemPoolListCursor, MemPoolListFilter};
+use crate::core::mempool::{MemPoolGCPolicy, MemPoolGCStrategy};
+use crate::core::mempool::{SubnetBlockFees, SubnetFeeEstimator};
 use crate::core::mempool::{BLOOM_COUNTER_DEPTH, BLOOM_COUNTER_ERROR_RATE, MAX_BLOOM_COUNTER_TXS};
+use crate::core::StacksEpochId;
 use crate::core::FIRST_BURNCHAIN_CONSENSUS_HASH;
 use crate::core::FIRST_STACKS_BLOCK_HASH;
+use crate::cost_estimates::metrics::UnitMetric;
+use crate::cost_estimates::UnitEstimator;
 use crate::net::Error as NetError;
 use crate::net::HttpResponseType;
 use crate::net::MemPoolSyncData;
@@ -72,6 +91,7 @@ use crate::types::chainstate::{StacksAddress, StacksBlockId, StacksWorkScore, VR
 use crate::{
     chainstate::stacks::db::StacksHeaderInfo, util::vrf::VRFProof, vm::costs::ExecutionCost,
 };
+use clarity::vm::analysis::CheckErrors;
 use clarity::vm::types::StacksAddressExtensions;
 
 use super::MemPoolDB;
@@ -96,6 +116,21 @@ fn mempool_db_init() {
     let _mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
 }
 
+#[test]
+fn mempool_db_check_schema_migrations() {
+    let _chainstate = instantiate_chainstate(false, 0x80000000, "mempool_db_check_migrations");
+    let chainstate_path = chainstate_path("mempool_db_check_migrations");
+
+    // no mempool yet
+    assert!(MemPoolDB::check_schema_migrations(&chainstate_path).is_err());
+
+    let _mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+    let status = MemPoolDB::check_schema_migrations(&chainstate_path).unwrap();
+    assert_eq!(status.current_version, 7);
+    assert_eq!(status.latest_version, 7);
+    assert!(status.is_up_to_date());
+}
+
 #[cfg(test)]
 fn make_block(
     chainstate: &mut StacksChainState,
@@ -641,209 +676,1296 @@ fn mempool_do_not_replace_tx() {
 }
 
 #[test]
-fn mempool_db_load_store_replace_tx() {
-    let mut chainstate =
-        instantiate_chainstate(false, 0x80000000, "mempool_db_load_store_replace_tx");
-    let chainstate_path = chainstate_path("mempool_db_load_store_replace_tx");
+fn mempool_origin_quota() {
+    let mut chainstate = instantiate_chainstate(false, 0x80000000, "mempool_origin_quota");
+    let chainstate_path = chainstate_path("mempool_origin_quota");
     let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
 
-    let mut txs = codec_all_transactions(
+    let origin_address = StacksAddress {
+        version: 22,
+        bytes: Hash160::from_data(&[0; 32]),
+    };
+    let relayer_address = StacksAddress {
+        version: 22,
+        bytes: Hash160::from_data(&[1; 32]),
+    };
+    mempool.set_origin_quota(MemPoolOriginQuota {
+        max_pending_txs_per_origin: Some(2),
+        exempt_origins: vec![relayer_address.clone()],
+    });
+
+    let mut tx = codec_all_transactions(
         &TransactionVersion::Testnet,
         0x80000000,
         &TransactionAnchorMode::Any,
         &TransactionPostConditionMode::Allow,
-    );
-    let num_txs = txs.len() as u64;
+    )
+    .pop()
+    .unwrap();
 
     let mut mempool_tx = mempool.tx_begin().unwrap();
+    let mut try_add = |tx_fee: u64, address: &StacksAddress, nonce: u64| {
+        // vary the fee so that each transaction has its own txid
+        tx.set_tx_fee(tx_fee);
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            tx.txid(),
+            tx.serialize_to_vec(),
+            tx_fee,
+            0,
+            address,
+            nonce,
+            address,
+            nonce,
+            None,
+        )
+    };
 
-    eprintln!("add all txs");
-    for (i, mut tx) in txs.drain(..).enumerate() {
-        // make sure each address is unique per tx (not the case in codec_all_transactions)
-        let origin_address = StacksAddress {
-            version: 22,
-            bytes: Hash160::from_data(&i.to_be_bytes()),
-        };
-        let sponsor_address = StacksAddress {
-            version: 22,
-            bytes: Hash160::from_data(&(i + 1).to_be_bytes()),
-        };
+    try_add(100, &origin_address, 0).unwrap();
+    try_add(101, &origin_address, 1).unwrap();
+
+    // a third pending transaction is over the quota
+    match try_add(102, &origin_address, 2).unwrap_err() {
+        MemPoolRejection::TooManyPendingTransactions {
+            principal,
+            max_pending,
+        } => {
+            assert_eq!(principal, origin_address.clone().into());
+            assert_eq!(max_pending, 2);
+        }
+        e => panic!("Unexpected rejection: {:?}", e),
+    }
 
-        tx.set_tx_fee(123);
+    // replacing a pending transaction does not count against the quota
+    try_add(200, &origin_address, 1).unwrap();
 
-        // test insert
+    // exempt origins have no quota
+    for nonce in 0..5 {
+        try_add(300 + nonce, &relayer_address, nonce).unwrap();
+    }
+}
 
-        let txid = tx.txid();
-        let mut tx_bytes = vec![];
-        tx.consensus_serialize(&mut tx_bytes).unwrap();
-        let expected_tx = tx.clone();
+#[test]
+fn mempool_nonce_gaps() {
+    let mut chainstate = instantiate_chainstate(false, 0x80000000, "mempool_nonce_gaps");
+    let chainstate_path = chainstate_path("mempool_nonce_gaps");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
 
-        let tx_fee = tx.get_tx_fee();
-        let height = 100;
-        let origin_nonce = tx.get_origin_nonce();
-        let sponsor_nonce = match tx.get_sponsor_nonce() {
-            Some(n) => n,
-            None => origin_nonce,
-        };
-        let len = tx_bytes.len() as u64;
+    let origin_address = StacksAddress {
+        version: 22,
+        bytes: Hash160::from_data(&[0; 32]),
+    };
+    let other_address = StacksAddress {
+        version: 22,
+        bytes: Hash160::from_data(&[1; 32]),
+    };
 
-        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+    let mut tx = codec_all_transactions(
+        &TransactionVersion::Testnet,
+        0x80000000,
+        &TransactionAnchorMode::Any,
+        &TransactionPostConditionMode::Allow,
+    )
+    .pop()
+    .unwrap();
 
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    let mut try_add = |tx_fee: u64, address: &StacksAddress, nonce: u64| {
+        // vary the fee so that each transaction has its own txid
+        tx.set_tx_fee(tx_fee);
         MemPoolDB::try_add_tx(
             &mut mempool_tx,
             &mut chainstate,
-            &ConsensusHash([0x1; 20]),
-            &BlockHeaderHash([0x2; 32]),
-            txid,
-            tx_bytes,
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            tx.txid(),
+            tx.serialize_to_vec(),
             tx_fee,
-            height,
-            &origin_address,
-            origin_nonce,
-            &sponsor_address,
-            sponsor_nonce,
+            0,
+            address,
+            nonce,
+            address,
+            nonce,
             None,
         )
         .unwrap();
+        tx.txid()
+    };
 
-        assert!(MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+    try_add(100, &origin_address, 1);
+    try_add(101, &origin_address, 2);
+    let blocked_txid = try_add(102, &origin_address, 5);
+    try_add(103, &other_address, 9);
+    mempool_tx.commit().unwrap();
 
-        // test retrieval
-        let tx_info_opt = MemPoolDB::get_tx(&mempool_tx, &txid).unwrap();
-        let tx_info = tx_info_opt.unwrap();
+    // nonces 3 and 4 are missing, so the transaction with nonce 5 is stuck
+    let gaps = MemPoolDB::get_nonce_gaps(mempool.conn(), &origin_address, 1).unwrap();
+    assert_eq!(gaps.next_nonce, 1);
+    assert_eq!(gaps.last_known_nonce, None);
+    assert_eq!(gaps.pending_nonces, vec![1, 2, 5]);
+    assert_eq!(gaps.missing_nonces, vec![3, 4]);
+    assert_eq!(gaps.blocked_txids, vec![blocked_txid]);
+
+    // a new transaction's nonce is placed among the pending ones
+    assert_eq!(gaps.nonce_status(0), MemPoolNonceStatus::Stale);
+    assert_eq!(gaps.nonce_status(2), MemPoolNonceStatus::Replacement);
+    assert_eq!(gaps.nonce_status(3), MemPoolNonceStatus::Chained);
+    assert_eq!(gaps.nonce_status(4), MemPoolNonceStatus::Gap);
+    assert_eq!(gaps.nonce_status(6), MemPoolNonceStatus::Gap);
+
+    // if the account's next nonce is 0, every pending transaction is stuck
+    let gaps = MemPoolDB::get_nonce_gaps(mempool.conn(), &origin_address, 0).unwrap();
+    assert_eq!(gaps.missing_nonces, vec![0, 3, 4]);
+    assert_eq!(gaps.blocked_txids.len(), 3);
+    assert_eq!(gaps.nonce_status(0), MemPoolNonceStatus::Next);
+
+    // transactions whose nonces were already used are not pending
+    let gaps = MemPoolDB::get_nonce_gaps(mempool.conn(), &origin_address, 3).unwrap();
+    assert_eq!(gaps.pending_nonces, vec![5]);
+    assert_eq!(gaps.missing_nonces, vec![3, 4]);
+
+    // other accounts' transactions are not included
+    let gaps = MemPoolDB::get_nonce_gaps(mempool.conn(), &other_address, 9).unwrap();
+    assert_eq!(gaps.pending_nonces, vec![9]);
+    assert!(gaps.missing_nonces.is_empty());
+    assert!(gaps.blocked_txids.is_empty());
+
+    // the nonce cached by the mempool walk is reported
+    mempool
+        .db
+        .execute(
+            "INSERT INTO nonces (address, nonce) VALUES (?1, 1)",
+            &[&origin_address.to_string()],
+        )
+        .unwrap();
+    let gaps = MemPoolDB::get_nonce_gaps(mempool.conn(), &origin_address, 1).unwrap();
+    assert_eq!(gaps.last_known_nonce, Some(1));
+}
 
-        assert_eq!(tx_info.tx, expected_tx);
-        assert_eq!(tx_info.metadata.len, len);
-        assert_eq!(tx_info.metadata.tx_fee, 123);
-        assert_eq!(tx_info.metadata.origin_address, origin_address);
-        assert_eq!(tx_info.metadata.origin_nonce, origin_nonce);
-        assert_eq!(tx_info.metadata.sponsor_address, sponsor_address);
-        assert_eq!(tx_info.metadata.sponsor_nonce, sponsor_nonce);
-        assert_eq!(tx_info.metadata.consensus_hash, ConsensusHash([0x1; 20]));
-        assert_eq!(
-            tx_info.metadata.block_header_hash,
-            BlockHeaderHash([0x2; 32])
-        );
-        assert_eq!(tx_info.metadata.block_height, height);
+#[test]
+fn mempool_fee_rate_percentile() {
+    let mut chainstate = instantiate_chainstate(false, 0x80000000, "mempool_fee_rate_percentile");
+    let chainstate_path = chainstate_path("mempool_fee_rate_percentile");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
 
-        // test replace-by-fee with a higher fee
-        let old_txid = txid;
+    let origin_address = StacksAddress {
+        version: 22,
+        bytes: Hash160::from_data(&[0; 32]),
+    };
 
-        tx.set_tx_fee(124);
-        assert!(txid != tx.txid());
+    let mut tx = codec_all_transactions(
+        &TransactionVersion::Testnet,
+        0x80000000,
+        &TransactionAnchorMode::Any,
+        &TransactionPostConditionMode::Allow,
+    )
+    .pop()
+    .unwrap();
 
+    let mut txids = vec![];
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    for (nonce, fee_rate) in [Some(1.0), Some(2.0), Some(3.0), None].iter().enumerate() {
+        let tx_fee = 100 + nonce as u64;
+        tx.set_tx_fee(tx_fee);
         let txid = tx.txid();
-        let mut tx_bytes = vec![];
-        tx.consensus_serialize(&mut tx_bytes).unwrap();
-        let expected_tx = tx.clone();
-        let tx_fee = tx.get_tx_fee();
-
-        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
-
-        let tx_info_before =
-            MemPoolDB::get_tx_metadata_by_address(&mempool_tx, true, &origin_address, origin_nonce)
-                .unwrap()
-                .unwrap();
-        assert_eq!(tx_info_before, tx_info.metadata);
-
         MemPoolDB::try_add_tx(
             &mut mempool_tx,
             &mut chainstate,
-            &ConsensusHash([0x1; 20]),
-            &BlockHeaderHash([0x2; 32]),
-            txid,
-            tx_bytes,
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            txid.clone(),
+            tx.serialize_to_vec(),
             tx_fee,
-            height,
+            0,
             &origin_address,
-            origin_nonce,
-            &sponsor_address,
-            sponsor_nonce,
+            nonce as u64,
+            &origin_address,
+            nonce as u64,
             None,
         )
         .unwrap();
+        mempool_tx
+            .execute(
+                "INSERT OR REPLACE INTO fee_estimates(txid, fee_rate) VALUES (?, ?)",
+                rusqlite::params![&txid, fee_rate],
+            )
+            .unwrap();
+        txids.push(txid);
+    }
+    mempool_tx.commit().unwrap();
 
-        // was replaced
-        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &old_txid).unwrap());
-        assert!(MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
-
-        let tx_info_after =
-            MemPoolDB::get_tx_metadata_by_address(&mempool_tx, true, &origin_address, origin_nonce)
-                .unwrap()
-                .unwrap();
-        assert!(tx_info_after != tx_info.metadata);
-
-        // test retrieval -- transaction should have been replaced because it has a higher
-        // estimated fee
-        let tx_info_opt = MemPoolDB::get_tx(&mempool_tx, &txid).unwrap();
+    assert_eq!(
+        MemPoolDB::get_tx_fee_rate(mempool.conn(), &txids[1]).unwrap(),
+        Some(2.0)
+    );
+    assert_eq!(
+        MemPoolDB::get_tx_fee_rate(mempool.conn(), &txids[3]).unwrap(),
+        None
+    );
+    assert_eq!(
+        MemPoolDB::get_tx_fee_rate(mempool.conn(), &Txid([0x11; 32])).unwrap(),
+        None
+    );
 
-        let tx_info = tx_info_opt.unwrap();
-        assert_eq!(tx_info.metadata, tx_info_after);
+    // the transaction itself, and transactions without an estimate, are not ranked against
+    assert_eq!(
+        MemPoolDB::get_fee_rate_percentile(mempool.conn(), 2.0, &txids[1]).unwrap(),
+        Some(50.0)
+    );
+    assert_eq!(
+        MemPoolDB::get_fee_rate_percentile(mempool.conn(), 2.5, &Txid([0x11; 32])).unwrap(),
+        Some(100.0 * 2.0 / 3.0)
+    );
+    assert_eq!(
+        MemPoolDB::get_fee_rate_percentile(mempool.conn(), 0.5, &Txid([0x11; 32])).unwrap(),
+        Some(0.0)
+    );
 
-        assert_eq!(tx_info.tx, expected_tx);
-        assert_eq!(tx_info.metadata.len, len);
-        assert_eq!(tx_info.metadata.tx_fee, 124);
-        assert_eq!(tx_info.metadata.origin_address, origin_address);
-        assert_eq!(tx_info.metadata.origin_nonce, origin_nonce);
-        assert_eq!(tx_info.metadata.sponsor_address, sponsor_address);
-        assert_eq!(tx_info.metadata.sponsor_nonce, sponsor_nonce);
-        assert_eq!(tx_info.metadata.consensus_hash, ConsensusHash([0x1; 20]));
-        assert_eq!(
-            tx_info.metadata.block_header_hash,
-            BlockHeaderHash([0x2; 32])
-        );
-        assert_eq!(tx_info.metadata.block_height, height);
+    mempool
+        .db
+        .execute("DELETE FROM fee_estimates", rusqlite::NO_PARAMS)
+        .unwrap();
+    assert_eq!(
+        MemPoolDB::get_fee_rate_percentile(mempool.conn(), 2.0, &txids[1]).unwrap(),
+        None
+    );
+}
 
-        // test replace-by-fee with a lower fee
-        let old_txid = txid;
+#[test]
+fn mempool_list_and_summarize_txs() {
+    let mut chainstate = instantiate_chainstate(false, 0x80000000, "mempool_list_txs");
+    let chainstate_path = chainstate_path("mempool_list_txs");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
 
-        tx.set_tx_fee(122);
-        assert!(txid != tx.txid());
+    let origin_address = StacksAddress {
+        version: 22,
+        bytes: Hash160::from_data(&[0; 32]),
+    };
+    let other_address = StacksAddress {
+        version: 22,
+        bytes: Hash160::from_data(&[1; 32]),
+    };
 
-        let txid = tx.txid();
-        let mut tx_bytes = vec![];
-        tx.consensus_serialize(&mut tx_bytes).unwrap();
-        let _expected_tx = tx.clone();
-        let tx_fee = tx.get_tx_fee();
+    let mut tx = codec_all_transactions(
+        &TransactionVersion::Testnet,
+        0x80000000,
+        &TransactionAnchorMode::Any,
+        &TransactionPostConditionMode::Allow,
+    )
+    .pop()
+    .unwrap();
+    let payload_type = tx.payload.name().to_string();
 
-        assert!(match MemPoolDB::try_add_tx(
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    let mut try_add = |tx_fee: u64, address: &StacksAddress, nonce: u64| {
+        // vary the fee so that each transaction has its own txid
+        tx.set_tx_fee(tx_fee);
+        MemPoolDB::try_add_tx(
             &mut mempool_tx,
             &mut chainstate,
-            &ConsensusHash([0x1; 20]),
-            &BlockHeaderHash([0x2; 32]),
-            txid,
-            tx_bytes,
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            tx.txid(),
+            tx.serialize_to_vec(),
             tx_fee,
-            height,
-            &origin_address,
-            origin_nonce,
-            &sponsor_address,
-            sponsor_nonce,
+            0,
+            address,
+            nonce,
+            address,
+            nonce,
             None,
         )
-        .unwrap_err()
-        {
-            MemPoolRejection::ConflictingNonceInMempool => true,
-            _ => false,
-        });
+        .unwrap();
+        tx.txid()
+    };
 
-        // was NOT replaced
-        assert!(MemPoolDB::db_has_tx(&mempool_tx, &old_txid).unwrap());
-        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+    let mut expected = vec![];
+    for nonce in 0..5 {
+        expected.push((
+            try_add(5 + 20 * nonce, &origin_address, nonce),
+            5 + 20 * nonce,
+        ));
     }
+    expected.push((try_add(1000, &other_address, 0), 1000));
     mempool_tx.commit().unwrap();
+    expected.sort_by(|a, b| b.1.cmp(&a.1));
+
+    // page through everything, highest fee first
+    let filter = MemPoolListFilter::default();
+    let mut cursor = None;
+    let mut listed = vec![];
+    loop {
+        let (page, next_cursor) =
+            MemPoolDB::list_txs(mempool.conn(), &filter, cursor.as_ref(), 2).unwrap();
+        assert!(page.len() <= 2);
+        listed.extend(
+            page.into_iter()
+                .map(|tx_info| (tx_info.metadata.txid, tx_info.metadata.tx_fee)),
+        );
+        match next_cursor {
+            Some(next_cursor) => cursor = Some(next_cursor),
+            None => break,
+        }
+    }
+    assert_eq!(listed, expected);
+
+    // cursors survive a round trip through their string form
+    let (_, next_cursor) = MemPoolDB::list_txs(mempool.conn(), &filter, None, 2).unwrap();
+    let next_cursor = next_cursor.unwrap();
+    assert_eq!(
+        MemPoolListCursor::from_str(&next_cursor.to_string()),
+        Some(next_cursor)
+    );
+
+    // filter by origin and fee range
+    let filter = MemPoolListFilter {
+        origin: Some(origin_address.clone()),
+        min_fee: Some(20),
+        max_fee: Some(70),
+        payload_type: None,
+    };
+    let (page, next_cursor) = MemPoolDB::list_txs(mempool.conn(), &filter, None, 10).unwrap();
+    assert!(next_cursor.is_none());
+    assert_eq!(
+        page.iter()
+            .map(|tx_info| tx_info.metadata.tx_fee)
+            .collect::<Vec<_>>(),
+        vec![65, 45, 25]
+    );
+
+    // filter by payload type
+    let filter = MemPoolListFilter {
+        payload_type: Some(payload_type.clone()),
+        ..MemPoolListFilter::default()
+    };
+    let (page, _) = MemPoolDB::list_txs(mempool.conn(), &filter, None, 10).unwrap();
+    assert_eq!(page.len(), 6);
+    assert!(page
+        .iter()
+        .all(|tx_info| tx_info.tx.payload.name() == payload_type));
+
+    let other_payload_type = if payload_type == "Coinbase" {
+        "TokenTransfer"
+    } else {
+        "Coinbase"
+    };
+    let filter = MemPoolListFilter {
+        payload_type: Some(other_payload_type.to_string()),
+        ..MemPoolListFilter::default()
+    };
+    let (page, _) = MemPoolDB::list_txs(mempool.conn(), &filter, None, 10).unwrap();
+    assert!(page.is_empty());
+
+    // the summary buckets fees by order of magnitude
+    let summary = MemPoolDB::summarize_txs(mempool.conn(), &MemPoolListFilter::default()).unwrap();
+    assert_eq!(summary.count, 6);
+    assert_eq!(
+        summary.fee_histogram,
+        vec![
+            MemPoolFeeBucket {
+                min_fee: 1,
+                max_fee: 9,
+                count: 1
+            },
+            MemPoolFeeBucket {
+                min_fee: 10,
+                max_fee: 99,
+                count: 4
+            },
+            MemPoolFeeBucket {
+                min_fee: 1000,
+                max_fee: 9999,
+                count: 1
+            },
+        ]
+    );
+
+    let summary = MemPoolDB::summarize_txs(
+        mempool.conn(),
+        &MemPoolListFilter {
+            origin: Some(other_address.clone()),
+            ..MemPoolListFilter::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(summary.count, 1);
+    assert_eq!(summary.fee_histogram.len(), 1);
+}
+
+#[test]
+fn mempool_strict_admission_policy() {
+    let mut chainstate =
+        instantiate_chainstate(false, 0x80000000, "mempool_strict_admission_policy");
+    let policy = StrictAdmissionPolicy {
+        max_contract_deploy_size: 1000,
+        min_token_transfer_fee: 500,
+    };
+
+    let txs = codec_all_transactions(
+        &TransactionVersion::Testnet,
+        0x80000000,
+        &TransactionAnchorMode::Any,
+        &TransactionPostConditionMode::Allow,
+    );
+    let mut deploy = txs
+        .iter()
+        .find(|tx| matches!(tx.payload, TransactionPayload::SmartContract(..)))
+        .unwrap()
+        .clone();
+    let mut transfer = txs
+        .iter()
+        .find(|tx| matches!(tx.payload, TransactionPayload::TokenTransfer(..)))
+        .unwrap()
+        .clone();
+
+    let mut check = |tx: &StacksTransaction, tx_size: u64| {
+        policy.will_admit_tx(
+            &mut chainstate,
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            tx,
+            tx_size,
+        )
+    };
+
+    deploy.set_tx_fee(10_000);
+    match check(&deploy, 1001).unwrap_err() {
+        MemPoolRejection::ContractDeployTooLarge { size, max_size } => {
+            assert_eq!(size, 1001);
+            assert_eq!(max_size, 1000);
+        }
+        e => panic!("Unexpected rejection: {:?}", e),
+    }
+    // small enough deploys are subject to the standard checks only
+    if let Err(MemPoolRejection::ContractDeployTooLarge { .. }) = check(&deploy, 1000) {
+        panic!("Deploy within the size limit was rejected");
+    }
+
+    transfer.set_tx_fee(499);
+    match check(&transfer, 180).unwrap_err() {
+        MemPoolRejection::FeeTooLow(actual, expected) => {
+            assert_eq!(actual, 499);
+            assert_eq!(expected, 500);
+        }
+        e => panic!("Unexpected rejection: {:?}", e),
+    }
+    transfer.set_tx_fee(500);
+    if let Err(MemPoolRejection::FeeTooLow(_, 500)) = check(&transfer, 180) {
+        panic!("Transfer paying the minimum fee was rejected");
+    }
+}
+
+#[test]
+fn mempool_contract_deploy_limits() {
+    let mut chainstate =
+        instantiate_chainstate(false, 0x80000000, "mempool_contract_deploy_limits");
+    let tip = StacksBlockId::new(&FIRST_BURNCHAIN_CONSENSUS_HASH, &FIRST_STACKS_BLOCK_HASH);
+
+    let txs = codec_all_transactions(
+        &TransactionVersion::Testnet,
+        0x80000000,
+        &TransactionAnchorMode::Any,
+        &TransactionPostConditionMode::Allow,
+    );
+    let transfer = txs
+        .iter()
+        .find(|tx| matches!(tx.payload, TransactionPayload::TokenTransfer(..)))
+        .unwrap()
+        .clone();
+    let make_deploy = |code_body: &str| {
+        let mut deploy = transfer.clone();
+        deploy.payload = TransactionPayload::SmartContract(TransactionSmartContract {
+            name: ContractName::try_from("limited").unwrap(),
+            code_body: StacksString::from_str(code_body).unwrap(),
+        });
+        deploy
+    };
+    // 45 bytes, with lists nested 5 deep
+    let deploy = make_deploy("(define-constant a (+ 1 (+ 2 (- 3 (* 4 5)))))");
+    let shallow_deploy = make_deploy("(define-constant a 1)");
+
+    let mut check = |limits: &ContractDeployLimits, tx: &StacksTransaction| {
+        chainstate
+            .with_read_only_clarity_tx(&TEST_BURN_STATE_DB, &tip, |conn| limits.check(conn, tx))
+            .unwrap()
+    };
+
+    assert!(ContractDeployLimits::default().is_unlimited());
+    check(&ContractDeployLimits::default(), &deploy).unwrap();
+
+    let size_limit = ContractDeployLimits {
+        max_code_body_size: Some(30),
+        ..ContractDeployLimits::default()
+    };
+    match check(&size_limit, &deploy).unwrap_err() {
+        MemPoolRejection::ContractCodeTooLarge { size, max_size } => {
+            assert_eq!(size, 45);
+            assert_eq!(max_size, 30);
+        }
+        e => panic!("Unexpected rejection: {:?}", e),
+    }
+    check(&size_limit, &shallow_deploy).unwrap();
+    // only contract deploys are limited
+    check(&size_limit, &transfer).unwrap();
+
+    let depth_limit = ContractDeployLimits {
+        max_ast_depth: Some(4),
+        ..ContractDeployLimits::default()
+    };
+    match check(&depth_limit, &deploy).unwrap_err() {
+        MemPoolRejection::ContractTooDeep { depth, max_depth } => {
+            assert_eq!(depth, 5);
+            assert_eq!(max_depth, 4);
+        }
+        e => panic!("Unexpected rejection: {:?}", e),
+    }
+    check(&depth_limit, &shallow_deploy).unwrap();
+
+    let time_limit = ContractDeployLimits {
+        max_analysis_time_ms: Some(60_000),
+        ..ContractDeployLimits::default()
+    };
+    check(&time_limit, &deploy).unwrap();
+}
+
+#[test]
+fn mempool_walk_considers_nonce_chains() {
+    let mut chainstate = instantiate_chainstate_with_balances(
+        false,
+        0x80000000,
+        "mempool_walk_considers_nonce_chains",
+        vec![],
+    );
+    let b_1 = make_block(
+        &mut chainstate,
+        ConsensusHash([0x1; 20]),
+        &(
+            FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+            FIRST_STACKS_BLOCK_HASH.clone(),
+        ),
+        1,
+        1,
+    );
+    let chainstate_path = chainstate_path("mempool_walk_considers_nonce_chains");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+    let deployer_address = StacksAddress {
+        version: 22,
+        bytes: Hash160::from_data(&[0; 32]),
+    };
+    let other_address = StacksAddress {
+        version: 22,
+        bytes: Hash160::from_data(&[1; 32]),
+    };
+
+    let mut tx = codec_all_transactions(
+        &TransactionVersion::Testnet,
+        0x80000000,
+        &TransactionAnchorMode::Any,
+        &TransactionPostConditionMode::Allow,
+    )
+    .into_iter()
+    .find(|tx| !tx.auth.is_sponsored())
+    .unwrap();
+
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    let mut try_add = |tx_fee: u64, address: &StacksAddress, nonce: u64| {
+        // vary the fee so that each transaction has its own txid
+        tx.set_tx_fee(tx_fee);
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &b_1.0,
+            &b_1.1,
+            tx.txid(),
+            tx.serialize_to_vec(),
+            tx_fee,
+            1,
+            address,
+            nonce,
+            address,
+            nonce,
+            None,
+        )
+        .unwrap();
+        tx.txid()
+    };
+
+    // a burst of sequential transactions, whose first transaction has a low fee
+    let mut expected_txids = vec![
+        try_add(100, &deployer_address, 0),
+        try_add(10_000, &deployer_address, 1),
+        try_add(10_001, &deployer_address, 2),
+    ];
+    expected_txids.push(try_add(1_000, &other_address, 0));
+    mempool_tx.commit().unwrap();
+
+    // the burst averages 6,700 per transaction, so the whole burst is considered before the
+    //  other account's transaction
+    let mut considered_txids = vec![];
+    chainstate.with_read_only_clarity_tx(
+        &TEST_BURN_STATE_DB,
+        &StacksBlockHeader::make_index_block_hash(&b_1.0, &b_1.1),
+        |clarity_conn| {
+            mempool
+                .iterate_candidates::<_, ChainstateError, _>(
+                    clarity_conn,
+                    1,
+                    MemPoolWalkSettings::default(),
+                    |_, available_tx, _| {
+                        considered_txids.push(available_tx.tx.tx.txid());
+                        Ok(true)
+                    },
+                )
+                .unwrap();
+        },
+    );
+    assert_eq!(considered_txids, expected_txids);
+}
+
+#[test]
+fn mempool_walk_considers_sponsor_nonce_chains() {
+    let mut chainstate = instantiate_chainstate_with_balances(
+        false,
+        0x80000000,
+        "mempool_walk_considers_sponsor_nonce_chains",
+        vec![],
+    );
+    let b_1 = make_block(
+        &mut chainstate,
+        ConsensusHash([0x1; 20]),
+        &(
+            FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+            FIRST_STACKS_BLOCK_HASH.clone(),
+        ),
+        1,
+        1,
+    );
+    let chainstate_path = chainstate_path("mempool_walk_considers_sponsor_nonce_chains");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+    let sponsor_address = StacksAddress {
+        version: 22,
+        bytes: Hash160::from_data(&[0; 32]),
+    };
+    let other_address = StacksAddress {
+        version: 22,
+        bytes: Hash160::from_data(&[1; 32]),
+    };
+    let user_address = |i: u8| StacksAddress {
+        version: 22,
+        bytes: Hash160::from_data(&[0x10 + i; 32]),
+    };
+
+    let all_txs = codec_all_transactions(
+        &TransactionVersion::Testnet,
+        0x80000000,
+        &TransactionAnchorMode::Any,
+        &TransactionPostConditionMode::Allow,
+    );
+    let mut sponsored_tx = all_txs
+        .iter()
+        .find(|tx| tx.auth.is_sponsored())
+        .unwrap()
+        .clone();
+    let mut standard_tx = all_txs
+        .into_iter()
+        .find(|tx| !tx.auth.is_sponsored())
+        .unwrap();
+
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    let mut try_add = |tx: &mut StacksTransaction,
+                       tx_fee: u64,
+                       origin: &StacksAddress,
+                       origin_nonce: u64,
+                       sponsor: &StacksAddress,
+                       sponsor_nonce: u64| {
+        // vary the fee so that each transaction has its own txid
+        tx.set_tx_fee(tx_fee);
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &b_1.0,
+            &b_1.1,
+            tx.txid(),
+            tx.serialize_to_vec(),
+            tx_fee,
+            1,
+            origin,
+            origin_nonce,
+            sponsor,
+            sponsor_nonce,
+            None,
+        )
+        .unwrap();
+        tx.txid()
+    };
+
+    // a sponsor pays for one transaction from each of three users, and pays little for the
+    //  first one
+    let mut expected_txids = vec![];
+    for (i, tx_fee) in [100, 10_000, 10_001].iter().enumerate() {
+        expected_txids.push(try_add(
+            &mut sponsored_tx,
+            *tx_fee,
+            &user_address(i as u8),
+            0,
+            &sponsor_address,
+            i as u64,
+        ));
+    }
+    expected_txids.push(try_add(
+        &mut standard_tx,
+        1_000,
+        &other_address,
+        0,
+        &other_address,
+        0,
+    ));
+    mempool_tx.commit().unwrap();
+
+    // the sponsor's transactions average 6,700 per transaction, so they are all considered
+    //  before the other account's transaction
+    let mut considered_txids = vec![];
+    chainstate.with_read_only_clarity_tx(
+        &TEST_BURN_STATE_DB,
+        &StacksBlockHeader::make_index_block_hash(&b_1.0, &b_1.1),
+        |clarity_conn| {
+            mempool
+                .iterate_candidates::<_, ChainstateError, _>(
+                    clarity_conn,
+                    1,
+                    MemPoolWalkSettings::default(),
+                    |_, available_tx, _| {
+                        considered_txids.push(available_tx.tx.tx.txid());
+                        Ok(true)
+                    },
+                )
+                .unwrap();
+        },
+    );
+    assert_eq!(considered_txids, expected_txids);
+
+    // the walk cached one nonce per account, and advanced the sponsor's past its transactions
+    let gaps = MemPoolDB::get_nonce_gaps(mempool.conn(), &sponsor_address, 3).unwrap();
+    assert_eq!(gaps.last_known_nonce, Some(3));
+    let gaps = MemPoolDB::get_nonce_gaps(mempool.conn(), &user_address(1), 1).unwrap();
+    assert_eq!(gaps.last_known_nonce, Some(1));
+}
+
+/// Make a smart contract transaction that serializes to exactly `length` bytes.  `tag` is set
+/// as its nonce, so that transactions with the same fee and length have different txids.
+fn make_sized_tx(length: usize, tx_fee: u64, tag: u64) -> StacksTransaction {
+    let privk = StacksPrivateKey::from_hex(
+        "6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001",
+    )
+    .unwrap();
+    let auth = TransactionAuth::from_p2pkh(&privk).unwrap();
+    let make_tx = |code: &str| {
+        let mut tx = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            auth.clone(),
+            TransactionPayload::new_smart_contract("sized", code).unwrap(),
+        );
+        tx.chain_id = 0x80000000;
+        tx.set_tx_fee(tx_fee);
+        tx.set_origin_nonce(tag);
+        tx
+    };
+    let base_length = make_tx("").serialize_to_vec().len();
+    let tx = make_tx(&" ".repeat(length - base_length));
+    assert_eq!(tx.serialize_to_vec().len(), length);
+    tx
+}
+
+/// A transaction in a mempool snapshot
+struct SnapshotTx {
+    origin: u8,
+    nonce: u64,
+    tx_fee: u64,
+    length: usize,
+    fee_rate: Option<f64>,
+}
+
+/// Add the transactions in `snapshot` to `mempool`, with the fee rate estimates they were
+/// recorded with.  Returns their txids.
+fn add_snapshot_txs(
+    mempool: &mut MemPoolDB,
+    chainstate: &mut StacksChainState,
+    block: &(ConsensusHash, BlockHeaderHash),
+    snapshot: &[SnapshotTx],
+) -> Vec<Txid> {
+    let mut txids = vec![];
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    for (i, snapshot_tx) in snapshot.iter().enumerate() {
+        let address = StacksAddress {
+            version: 22,
+            bytes: Hash160::from_data(&[snapshot_tx.origin; 32]),
+        };
+        let tx = make_sized_tx(snapshot_tx.length, snapshot_tx.tx_fee, i as u64);
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            chainstate,
+            &block.0,
+            &block.1,
+            tx.txid(),
+            tx.serialize_to_vec(),
+            snapshot_tx.tx_fee,
+            1,
+            &address,
+            snapshot_tx.nonce,
+            &address,
+            snapshot_tx.nonce,
+            None,
+        )
+        .unwrap();
+        if let Some(fee_rate) = snapshot_tx.fee_rate {
+            mempool_tx
+                .execute(
+                    "INSERT OR REPLACE INTO fee_estimates(txid, fee_rate) VALUES (?, ?)",
+                    rusqlite::params![&tx.txid(), fee_rate],
+                )
+                .unwrap();
+        }
+        txids.push(tx.txid());
+    }
+    mempool_tx.commit().unwrap();
+    txids
+}
+
+/// Walk `mempool` with `ranking`, and return the txids it considers, in order
+fn walk_mempool_with_ranking(
+    mempool: &mut MemPoolDB,
+    chainstate: &mut StacksChainState,
+    block: &(ConsensusHash, BlockHeaderHash),
+    ranking: MemPoolWalkRanking,
+) -> Vec<Txid> {
+    let mut settings = MemPoolWalkSettings::default();
+    settings.ranking = ranking;
+    // always consider transactions with fee rate estimates first
+    settings.consider_no_estimate_tx_prob = 0;
+
+    let mut considered_txids = vec![];
+    chainstate.with_read_only_clarity_tx(
+        &TEST_BURN_STATE_DB,
+        &StacksBlockHeader::make_index_block_hash(&block.0, &block.1),
+        |clarity_conn| {
+            mempool
+                .iterate_candidates::<_, ChainstateError, _>(
+                    clarity_conn,
+                    1,
+                    settings,
+                    |_, available_tx, _| {
+                        considered_txids.push(available_tx.tx.tx.txid());
+                        Ok(true)
+                    },
+                )
+                .unwrap();
+        },
+    );
+    mempool.reset_last_known_nonces().unwrap();
+    considered_txids
+}
+
+#[test]
+fn mempool_walk_rankings() {
+    let mut chainstate =
+        instantiate_chainstate_with_balances(false, 0x80000000, "mempool_walk_rankings", vec![]);
+    let b_1 = make_block(
+        &mut chainstate,
+        ConsensusHash([0x1; 20]),
+        &(
+            FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+            FIRST_STACKS_BLOCK_HASH.clone(),
+        ),
+        1,
+        1,
+    );
+    let chainstate_path = chainstate_path("mempool_walk_rankings");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+    let snapshot = [
+        // a large transaction, paying the most in total but 1 uSTX per byte
+        SnapshotTx {
+            origin: 0,
+            nonce: 0,
+            tx_fee: 10_000,
+            length: 10_000,
+            fee_rate: None,
+        },
+        // 10 uSTX per byte
+        SnapshotTx {
+            origin: 1,
+            nonce: 0,
+            tx_fee: 2_000,
+            length: 200,
+            fee_rate: None,
+        },
+        // 5 uSTX per byte
+        SnapshotTx {
+            origin: 2,
+            nonce: 0,
+            tx_fee: 1_000,
+            length: 200,
+            fee_rate: None,
+        },
+        // transactions with fee rate estimates
+        SnapshotTx {
+            origin: 3,
+            nonce: 0,
+            tx_fee: 500,
+            length: 200,
+            fee_rate: Some(5.0),
+        },
+        SnapshotTx {
+            origin: 4,
+            nonce: 0,
+            tx_fee: 400,
+            length: 200,
+            fee_rate: Some(9.0),
+        },
+    ];
+    let txids = add_snapshot_txs(&mut mempool, &mut chainstate, &b_1, &snapshot);
+
+    // estimated transactions by fee rate, then the rest by fee
+    assert_eq!(
+        walk_mempool_with_ranking(&mut mempool, &mut chainstate, &b_1, MemPoolWalkRanking::Fee),
+        vec![
+            txids[4].clone(),
+            txids[3].clone(),
+            txids[0].clone(),
+            txids[1].clone(),
+            txids[2].clone()
+        ]
+    );
+    // everything by fee per byte
+    assert_eq!(
+        walk_mempool_with_ranking(
+            &mut mempool,
+            &mut chainstate,
+            &b_1,
+            MemPoolWalkRanking::FeePerLength
+        ),
+        vec![
+            txids[1].clone(),
+            txids[2].clone(),
+            txids[3].clone(),
+            txids[4].clone(),
+            txids[0].clone()
+        ]
+    );
+    // estimated transactions by fee rate, then the rest by fee per byte
+    assert_eq!(
+        walk_mempool_with_ranking(
+            &mut mempool,
+            &mut chainstate,
+            &b_1,
+            MemPoolWalkRanking::FeePerCost
+        ),
+        vec![
+            txids[4].clone(),
+            txids[3].clone(),
+            txids[1].clone(),
+            txids[2].clone(),
+            txids[0].clone()
+        ]
+    );
+}
+
+#[test]
+fn mempool_walk_rankings_on_snapshot() {
+    let mut chainstate = instantiate_chainstate_with_balances(
+        false,
+        0x80000000,
+        "mempool_walk_rankings_on_snapshot",
+        vec![],
+    );
+    let b_1 = make_block(
+        &mut chainstate,
+        ConsensusHash([0x1; 20]),
+        &(
+            FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+            FIRST_STACKS_BLOCK_HASH.clone(),
+        ),
+        1,
+        1,
+    );
+    let chainstate_path = chainstate_path("mempool_walk_rankings_on_snapshot");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+    // a mempool of token transfers and contract calls, a few large contract deploys, and a burst
+    //  of sequential transactions
+    let snapshot: Vec<SnapshotTx> = include_str!("./mempool_snapshot_sample.jsons")
+        .lines()
+        .map(|line| {
+            let json: serde_json::Value = serde_json::from_str(line).unwrap();
+            SnapshotTx {
+                origin: json["origin"].as_u64().unwrap() as u8,
+                nonce: json["nonce"].as_u64().unwrap(),
+                tx_fee: json["tx_fee"].as_u64().unwrap(),
+                length: json["length"].as_u64().unwrap() as usize,
+                fee_rate: json["fee_rate"].as_f64(),
+            }
+        })
+        .collect();
+    let txids = add_snapshot_txs(&mut mempool, &mut chainstate, &b_1, &snapshot);
+    let by_txid: HashMap<_, _> = txids.iter().zip(snapshot.iter()).collect();
+
+    // fill a block of `BLOCK_BYTES` in walk order, skipping transactions that do not fit.
+    //  Returns the fees collected, the number of transactions mined, and whether a large
+    //  deploy was mined.
+    const BLOCK_BYTES: usize = 15_000;
+    let fill_block = |walk: Vec<Txid>| {
+        assert_eq!(walk.len(), snapshot.len());
+        let (mut bytes, mut fees, mut num_txs, mut mined_deploy) = (0, 0, 0, false);
+        for txid in walk.iter() {
+            let snapshot_tx = by_txid[txid];
+            if bytes + snapshot_tx.length <= BLOCK_BYTES {
+                bytes += snapshot_tx.length;
+                fees += snapshot_tx.tx_fee;
+                num_txs += 1;
+                mined_deploy |= snapshot_tx.length >= 8_000;
+            }
+        }
+        (fees, num_txs, mined_deploy)
+    };
+
+    let (fee_fees, fee_num_txs, fee_mined_deploy) = fill_block(walk_mempool_with_ranking(
+        &mut mempool,
+        &mut chainstate,
+        &b_1,
+        MemPoolWalkRanking::Fee,
+    ));
+    let (length_fees, length_num_txs, length_mined_deploy) = fill_block(walk_mempool_with_ranking(
+        &mut mempool,
+        &mut chainstate,
+        &b_1,
+        MemPoolWalkRanking::FeePerLength,
+    ));
+    let (cost_fees, cost_num_txs, cost_mined_deploy) = fill_block(walk_mempool_with_ranking(
+        &mut mempool,
+        &mut chainstate,
+        &b_1,
+        MemPoolWalkRanking::FeePerCost,
+    ));
+
+    // ranking by fee lets a deploy crowd a third of the transactions out of the block
+    assert!(fee_mined_deploy);
+    assert_eq!(fee_num_txs, 26);
+    // ranking by fee per byte or per cost mines everything but the deploys, for more fees
+    assert!(!length_mined_deploy);
+    assert!(!cost_mined_deploy);
+    assert_eq!(length_num_txs, snapshot.len() - 3);
+    assert_eq!(cost_num_txs, snapshot.len() - 3);
+    assert!(length_fees > fee_fees);
+    assert!(cost_fees > fee_fees);
+}
+
+#[test]
+fn mempool_db_load_store_replace_tx() {
+    let mut chainstate =
+        instantiate_chainstate(false, 0x80000000, "mempool_db_load_store_replace_tx");
+    let chainstate_path = chainstate_path("mempool_db_load_store_replace_tx");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+    let mut txs = codec_all_transactions(
+        &TransactionVersion::Testnet,
+        0x80000000,
+        &TransactionAnchorMode::Any,
+        &TransactionPostConditionMode::Allow,
+    );
+    let num_txs = txs.len() as u64;
+
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+
+    for (i, mut tx) in txs.drain(..).enumerate() {
+        // make sure each address is unique per tx (not the case in codec_all_transactions)
+        let origin_address = StacksAddress {
+            version: 22,
+            bytes: Hash160::from_data(&i.to_be_bytes()),
+        };
+        let sponsor_address = StacksAddress {
+            version: 22,
+            bytes: Hash160::from_data(&(i + 1).to_be_bytes()),
+        };
+
+        tx.set_tx_fee(123);
+
+        // test insert
+
+        let txid = tx.txid();
+        let mut tx_bytes = vec![];
+        tx.consensus_serialize(&mut tx_bytes).unwrap();
+        let expected_tx = tx.clone();
+
+        let tx_fee = tx.get_tx_fee();
+        let height = 100;
+        let origin_nonce = tx.get_origin_nonce();
+        let sponsor_nonce = match tx.get_sponsor_nonce() {
+            Some(n) => n,
+            None => origin_nonce,
+        };
+        let len = tx_bytes.len() as u64;
+
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &ConsensusHash([0x1; 20]),
+            &BlockHeaderHash([0x2; 32]),
+            txid,
+            tx_bytes,
+            tx_fee,
+            height,
+            &origin_address,
+            origin_nonce,
+            &sponsor_address,
+            sponsor_nonce,
+            None,
+        )
+        .unwrap();
+
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+
+        // test retrieval
+        let tx_info_opt = MemPoolDB::get_tx(&mempool_tx, &txid).unwrap();
+        let tx_info = tx_info_opt.unwrap();
+
+        assert_eq!(tx_info.tx, expected_tx);
+        assert_eq!(tx_info.metadata.len, len);
+        assert_eq!(tx_info.metadata.tx_fee, 123);
+        assert_eq!(tx_info.metadata.origin_address, origin_address);
+        assert_eq!(tx_info.metadata.origin_nonce, origin_nonce);
+        assert_eq!(tx_info.metadata.sponsor_address, sponsor_address);
+        assert_eq!(tx_info.metadata.sponsor_nonce, sponsor_nonce);
+        assert_eq!(tx_info.metadata.consensus_hash, ConsensusHash([0x1; 20]));
+        assert_eq!(
+            tx_info.metadata.block_header_hash,
+            BlockHeaderHash([0x2; 32])
+        );
+        assert_eq!(tx_info.metadata.block_height, height);
+
+        // test replace-by-fee with a higher fee
+        let old_txid = txid;
+
+        tx.set_tx_fee(124);
+        assert!(txid != tx.txid());
+
+        let txid = tx.txid();
+        let mut tx_bytes = vec![];
+        tx.consensus_serialize(&mut tx_bytes).unwrap();
+        let expected_tx = tx.clone();
+        let tx_fee = tx.get_tx_fee();
+
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+
+        let tx_info_before =
+            MemPoolDB::get_tx_metadata_by_address(&mempool_tx, true, &origin_address, origin_nonce)
+                .unwrap()
+                .unwrap();
+        assert_eq!(tx_info_before, tx_info.metadata);
+
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &ConsensusHash([0x1; 20]),
+            &BlockHeaderHash([0x2; 32]),
+            txid,
+            tx_bytes,
+            tx_fee,
+            height,
+            &origin_address,
+            origin_nonce,
+            &sponsor_address,
+            sponsor_nonce,
+            None,
+        )
+        .unwrap();
+
+        // was replaced
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &old_txid).unwrap());
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+
+        let tx_info_after =
+            MemPoolDB::get_tx_metadata_by_address(&mempool_tx, true, &origin_address, origin_nonce)
+                .unwrap()
+                .unwrap();
+        assert!(tx_info_after != tx_info.metadata);
+
+        // test retrieval -- transaction should have been replaced because it has a higher
+        // estimated fee
+        let tx_info_opt = MemPoolDB::get_tx(&mempool_tx, &txid).unwrap();
+
+        let tx_info = tx_info_opt.unwrap();
+        assert_eq!(tx_info.metadata, tx_info_after);
+
+        assert_eq!(tx_info.tx, expected_tx);
+        assert_eq!(tx_info.metadata.len, len);
+        assert_eq!(tx_info.metadata.tx_fee, 124);
+        assert_eq!(tx_info.metadata.origin_address, origin_address);
+        assert_eq!(tx_info.metadata.origin_nonce, origin_nonce);
+        assert_eq!(tx_info.metadata.sponsor_address, sponsor_address);
+        assert_eq!(tx_info.metadata.sponsor_nonce, sponsor_nonce);
+        assert_eq!(tx_info.metadata.consensus_hash, ConsensusHash([0x1; 20]));
+        assert_eq!(
+            tx_info.metadata.block_header_hash,
+            BlockHeaderHash([0x2; 32])
+        );
+        assert_eq!(tx_info.metadata.block_height, height);
+
+        // test replace-by-fee with a lower fee
+        let old_txid = txid;
+
+        tx.set_tx_fee(122);
+        assert!(txid != tx.txid());
+
+        let txid = tx.txid();
+        let mut tx_bytes = vec![];
+        tx.consensus_serialize(&mut tx_bytes).unwrap();
+        let _expected_tx = tx.clone();
+        let tx_fee = tx.get_tx_fee();
+
+        assert!(match MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &ConsensusHash([0x1; 20]),
+            &BlockHeaderHash([0x2; 32]),
+            txid,
+            tx_bytes,
+            tx_fee,
+            height,
+            &origin_address,
+            origin_nonce,
+            &sponsor_address,
+            sponsor_nonce,
+            None,
+        )
+        .unwrap_err()
+        {
+            MemPoolRejection::ConflictingNonceInMempool => true,
+            _ => false,
+        });
+
+        // was NOT replaced
+        assert!(MemPoolDB::db_has_tx(&mempool_tx, &old_txid).unwrap());
+        assert!(!MemPoolDB::db_has_tx(&mempool_tx, &txid).unwrap());
+    }
+    mempool_tx.commit().unwrap();
+
+    let txs = MemPoolDB::get_txs_after(
+        &mempool.db,
+        &ConsensusHash([0x1; 20]),
+        &BlockHeaderHash([0x2; 32]),
+        0,
+        num_txs,
+    )
+    .unwrap();
+    assert_eq!(txs.len() as u64, num_txs);
 
-    eprintln!("get all txs");
-    let txs = MemPoolDB::get_txs_after(
-        &mempool.db,
-        &ConsensusHash([0x1; 20]),
-        &BlockHeaderHash([0x2; 32]),
-        0,
-        num_txs,
-    )
-    .unwrap();
-    assert_eq!(txs.len() as u64, num_txs);
-
     eprintln!("get empty txs");
     let txs = MemPoolDB::get_txs_after(
         &mempool.db,
@@ -1137,6 +2259,228 @@ fn test_add_txs_bloom_filter() {
     }
 }
 
+#[test]
+fn mempool_evict_tx() {
+    let mut chainstate = instantiate_chainstate(false, 0x80000000, "mempool_evict_tx");
+    let chainstate_path = chainstate_path("mempool_evict_tx");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+    let addr = StacksAddress {
+        version: 1,
+        bytes: Hash160([0xff; 20]),
+    };
+
+    let pk = StacksPrivateKey::new();
+    let mut txids = vec![];
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    for nonce in 0..2 {
+        let mut tx = StacksTransaction {
+            version: TransactionVersion::Testnet,
+            chain_id: 0x80000000,
+            auth: TransactionAuth::from_p2pkh(&pk).unwrap(),
+            anchor_mode: TransactionAnchorMode::Any,
+            post_condition_mode: TransactionPostConditionMode::Allow,
+            post_conditions: vec![],
+            payload: TransactionPayload::TokenTransfer(
+                addr.to_account_principal(),
+                123,
+                TokenTransferMemo([0u8; 34]),
+            ),
+        };
+        tx.set_tx_fee(1000);
+        tx.set_origin_nonce(nonce);
+
+        let txid = tx.txid();
+        let tx_bytes = tx.serialize_to_vec();
+        let origin_addr = tx.origin_address();
+
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &ConsensusHash([0x1; 20]),
+            &BlockHeaderHash([0x2; 32]),
+            txid,
+            tx_bytes,
+            1000,
+            10,
+            &origin_addr,
+            nonce,
+            &origin_addr,
+            nonce,
+            None,
+        )
+        .unwrap();
+        txids.push(txid);
+    }
+    mempool_tx.commit().unwrap();
+
+    let origin_addr = MemPoolDB::get_tx(mempool.conn(), &txids[0])
+        .unwrap()
+        .unwrap()
+        .metadata
+        .origin_address;
+
+    let eviction = mempool
+        .evict_tx(&txids[0], "stuck")
+        .unwrap()
+        .expect("transaction was not evicted");
+    assert_eq!(eviction.txid, txids[0]);
+    assert_eq!(eviction.origin_address, origin_addr);
+    assert_eq!(eviction.origin_nonce, 0);
+    assert_eq!(eviction.tx_fee, 1000);
+    assert_eq!(eviction.reason, "stuck");
+
+    // the transaction is gone from the mempool and its bloom filter, and nothing else is
+    assert!(!MemPoolDB::db_has_tx(mempool.conn(), &txids[0]).unwrap());
+    assert!(MemPoolDB::db_has_tx(mempool.conn(), &txids[1]).unwrap());
+    let bf = mempool.get_txid_bloom_filter().unwrap();
+    assert!(!bf.contains_raw(&txids[0].0));
+    assert!(bf.contains_raw(&txids[1].0));
+
+    // the eviction is recorded in the audit table
+    assert_eq!(
+        MemPoolDB::get_tx_evictions(mempool.conn(), &txids[0]).unwrap(),
+        vec![eviction]
+    );
+    assert!(MemPoolDB::get_tx_evictions(mempool.conn(), &txids[1])
+        .unwrap()
+        .is_empty());
+
+    // evicting a transaction that is not in the mempool is a no-op
+    assert!(mempool.evict_tx(&txids[0], "stuck").unwrap().is_none());
+    assert_eq!(
+        MemPoolDB::get_tx_evictions(mempool.conn(), &txids[0])
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn mempool_garbage_collect_by_policy() {
+    let mut chainstate = instantiate_chainstate(false, 0x80000000, "mempool_gc_policy");
+    let chainstate_path = chainstate_path("mempool_gc_policy");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+    assert_eq!(mempool.get_gc_policy(), &MemPoolGCPolicy::default());
+
+    let addr = StacksAddress {
+        version: 1,
+        bytes: Hash160([0xff; 20]),
+    };
+
+    // six same-sized transactions, with increasing fees
+    let mut txids = vec![];
+    let mut tx_len = 0;
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    for i in 0..6 {
+        let pk = StacksPrivateKey::new();
+        let mut tx = StacksTransaction {
+            version: TransactionVersion::Testnet,
+            chain_id: 0x80000000,
+            auth: TransactionAuth::from_p2pkh(&pk).unwrap(),
+            anchor_mode: TransactionAnchorMode::Any,
+            post_condition_mode: TransactionPostConditionMode::Allow,
+            post_conditions: vec![],
+            payload: TransactionPayload::TokenTransfer(
+                addr.to_account_principal(),
+                123,
+                TokenTransferMemo([0u8; 34]),
+            ),
+        };
+        tx.set_tx_fee(1000 + i);
+        tx.set_origin_nonce(0);
+
+        let txid = tx.txid();
+        let tx_bytes = tx.serialize_to_vec();
+        tx_len = tx_bytes.len() as u64;
+        let origin_addr = tx.origin_address();
+
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &ConsensusHash([0x1; 20]),
+            &BlockHeaderHash([0x2; 32]),
+            txid,
+            tx_bytes,
+            1000 + i,
+            10,
+            &origin_addr,
+            0,
+            &origin_addr,
+            0,
+            None,
+        )
+        .unwrap();
+        txids.push(txid);
+    }
+    mempool_tx.commit().unwrap();
+
+    // the default policy only collects old transactions
+    assert!(mempool
+        .garbage_collect_by_policy(11, None)
+        .unwrap()
+        .is_empty());
+    assert!(txids
+        .iter()
+        .all(|txid| MemPoolDB::db_has_tx(mempool.conn(), txid).unwrap()));
+
+    // the fee floor evicts the cheapest transactions beyond the cap
+    mempool.set_gc_policy(MemPoolGCPolicy {
+        max_tx_age: MemPoolGCPolicy::default().max_tx_age,
+        strategy: MemPoolGCStrategy::FeeFloor { max_txs: 4 },
+    });
+    let evictions = mempool.garbage_collect_by_policy(11, None).unwrap();
+    let evicted: Vec<_> = evictions.iter().map(|e| e.txid.clone()).collect();
+    assert_eq!(evicted, txids[0..2].to_vec());
+    assert!(evictions.iter().all(|e| e.reason == "gc_fee_floor"));
+    assert_eq!(
+        MemPoolDB::get_tx_evictions(mempool.conn(), &txids[0]).unwrap(),
+        vec![evictions[0].clone()]
+    );
+    let bf = mempool.get_txid_bloom_filter().unwrap();
+    assert!(!bf.contains_raw(&txids[0].0));
+    assert!(bf.contains_raw(&txids[2].0));
+
+    // under the cap, nothing more is evicted
+    assert!(mempool
+        .garbage_collect_by_policy(11, None)
+        .unwrap()
+        .is_empty());
+
+    // the byte cap evicts the cheapest transactions until the rest fit
+    mempool.set_gc_policy(MemPoolGCPolicy {
+        max_tx_age: MemPoolGCPolicy::default().max_tx_age,
+        strategy: MemPoolGCStrategy::ByteCap {
+            max_bytes: 2 * tx_len + 1,
+        },
+    });
+    let evictions = mempool.garbage_collect_by_policy(11, None).unwrap();
+    let evicted: Vec<_> = evictions.iter().map(|e| e.txid.clone()).collect();
+    assert_eq!(evicted, txids[2..4].to_vec());
+    assert!(evictions.iter().all(|e| e.reason == "gc_byte_cap"));
+    assert!(MemPoolDB::db_has_tx(mempool.conn(), &txids[4]).unwrap());
+    assert!(MemPoolDB::db_has_tx(mempool.conn(), &txids[5]).unwrap());
+
+    // old transactions are collected under every strategy
+    mempool.set_gc_policy(MemPoolGCPolicy {
+        max_tx_age: 5,
+        strategy: MemPoolGCStrategy::ByteCap {
+            max_bytes: 2 * tx_len + 1,
+        },
+    });
+    assert!(mempool
+        .garbage_collect_by_policy(15, None)
+        .unwrap()
+        .is_empty());
+    assert!(MemPoolDB::db_has_tx(mempool.conn(), &txids[4]).unwrap());
+    assert!(mempool
+        .garbage_collect_by_policy(16, None)
+        .unwrap()
+        .is_empty());
+    assert!(!MemPoolDB::db_has_tx(mempool.conn(), &txids[4]).unwrap());
+    assert!(!MemPoolDB::db_has_tx(mempool.conn(), &txids[5]).unwrap());
+}
+
 #[test]
 fn test_txtags() {
     let mut chainstate = instantiate_chainstate(false, 0x80000000, "mempool_txtags");
@@ -1211,7 +2555,7 @@ fn test_txtags() {
 
         if block_height - 10 >= BLOOM_COUNTER_DEPTH {
             assert_eq!(
-                MemPoolDB::get_num_recent_txs(mempool.conn()).unwrap(),
+                MemPoolDB::get_num_recent_txs(mempool.conn(), BLOOM_COUNTER_DEPTH as u64).unwrap(),
                 (BLOOM_COUNTER_DEPTH * 128) as u64
             );
         }
@@ -1392,7 +2736,8 @@ fn test_make_mempool_sync_data() {
                 );
             }
 
-            let total_count = MemPoolDB::get_num_recent_txs(&mempool.conn()).unwrap();
+            let total_count =
+                MemPoolDB::get_num_recent_txs(&mempool.conn(), BLOOM_COUNTER_DEPTH as u64).unwrap();
             eprintln!(
                 "present_count: {}, absent count: {}, total sent: {}, total recent: {}",
                 present_count,
@@ -1405,13 +2750,145 @@ fn test_make_mempool_sync_data() {
         }
     }
 
-    // average false positive rate for non-recent transactions should be around the bloom
-    // counter false positive rate
-    let num_nonrecent_fp_samples = nonrecent_fp_rates.len() as f64;
-    let avg_nonrecent_fp_rate =
-        nonrecent_fp_rates.iter().fold(0.0f64, |acc, x| acc + x) / num_nonrecent_fp_samples;
+    // average false positive rate for non-recent transactions should be around the bloom
+    // counter false positive rate
+    let num_nonrecent_fp_samples = nonrecent_fp_rates.len() as f64;
+    let avg_nonrecent_fp_rate =
+        nonrecent_fp_rates.iter().fold(0.0f64, |acc, x| acc + x) / num_nonrecent_fp_samples;
+
+    assert!((avg_nonrecent_fp_rate - BLOOM_COUNTER_ERROR_RATE).abs() < 0.001);
+}
+
+#[test]
+fn test_bloom_counter_rebuilt_on_config_change() {
+    let mut chainstate = instantiate_chainstate(false, 0x80000000, "mempool_bloom_config_change");
+    let chainstate_path = chainstate_path("mempool_bloom_config_change");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+    assert_eq!(mempool.get_bloom_config(), &MemPoolBloomConfig::default());
+
+    let addr = StacksAddress {
+        version: 1,
+        bytes: Hash160([0xff; 20]),
+    };
+
+    // four txs in each of four blocks, with distinct fees
+    let mut all_txids: Vec<Vec<Txid>> = vec![];
+    for block_height in 10..14 {
+        let mut txids = vec![];
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        for i in 0..4 {
+            let pk = StacksPrivateKey::new();
+            let mut tx = StacksTransaction {
+                version: TransactionVersion::Testnet,
+                chain_id: 0x80000000,
+                auth: TransactionAuth::from_p2pkh(&pk).unwrap(),
+                anchor_mode: TransactionAnchorMode::Any,
+                post_condition_mode: TransactionPostConditionMode::Allow,
+                post_conditions: vec![],
+                payload: TransactionPayload::TokenTransfer(
+                    addr.to_account_principal(),
+                    123,
+                    TokenTransferMemo([0u8; 34]),
+                ),
+            };
+            tx.set_tx_fee(1000 + 10 * block_height + i);
+            tx.set_origin_nonce(0);
+
+            let txid = tx.txid();
+            let tx_bytes = tx.serialize_to_vec();
+            let origin_addr = tx.origin_address();
+            let origin_nonce = tx.get_origin_nonce();
+            let sponsor_addr = tx.sponsor_address().unwrap_or(origin_addr.clone());
+            let sponsor_nonce = tx.get_sponsor_nonce().unwrap_or(origin_nonce);
+            let tx_fee = tx.get_tx_fee();
+
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                &mut chainstate,
+                &ConsensusHash([0x1 + (block_height as u8); 20]),
+                &BlockHeaderHash([0x2 + (block_height as u8); 32]),
+                txid.clone(),
+                tx_bytes,
+                tx_fee,
+                block_height,
+                &origin_addr,
+                origin_nonce,
+                &sponsor_addr,
+                sponsor_nonce,
+                None,
+            )
+            .unwrap();
+            txids.push(txid);
+        }
+        mempool_tx.commit().unwrap();
+        all_txids.push(txids);
+    }
+
+    // only the last BLOOM_COUNTER_DEPTH blocks' txs are summarized
+    let bloom = mempool.get_txid_bloom_filter().unwrap();
+    for txid in all_txids[0].iter().chain(all_txids[1].iter()) {
+        assert!(!bloom.contains_raw(&txid.0));
+    }
+    for txid in all_txids[2].iter().chain(all_txids[3].iter()) {
+        assert!(bloom.contains_raw(&txid.0));
+    }
+    drop(mempool);
 
-    assert!((avg_nonrecent_fp_rate - BLOOM_COUNTER_ERROR_RATE).abs() < 0.001);
+    // reopening with a longer depth and a smaller capacity rebuilds the bloom counter
+    let bloom_config = MemPoolBloomConfig {
+        depth: 3,
+        max_txs: 10,
+        error_rate: 0.000001,
+    };
+    let mempool = MemPoolDB::open_with_bloom_config(
+        false,
+        0x80000000,
+        &chainstate_path,
+        Box::new(UnitEstimator),
+        Box::new(UnitMetric),
+        bloom_config.clone(),
+    )
+    .unwrap();
+    assert_eq!(mempool.get_bloom_config(), &bloom_config);
+    assert_eq!(mempool.negotiate_bloom_depth(None), 2);
+    assert_eq!(mempool.negotiate_bloom_depth(Some(5)), 3);
+    assert_eq!(mempool.negotiate_bloom_depth(Some(1)), 1);
+    assert_eq!(
+        MemPoolDB::get_num_recent_txs(mempool.conn(), 3).unwrap(),
+        12
+    );
+
+    let bloom = mempool.get_txid_bloom_filter().unwrap();
+    for txid in all_txids[0].iter() {
+        assert!(!bloom.contains_raw(&txid.0));
+    }
+    // the two lowest-fee recent txs did not fit
+    for txid in all_txids[1][0..2].iter() {
+        assert!(!bloom.contains_raw(&txid.0));
+    }
+    for txid in all_txids[1][2..]
+        .iter()
+        .chain(all_txids[2].iter())
+        .chain(all_txids[3].iter())
+    {
+        assert!(bloom.contains_raw(&txid.0));
+    }
+    drop(mempool);
+
+    // the new parameters are remembered
+    let mempool = MemPoolDB::open_with_bloom_config(
+        false,
+        0x80000000,
+        &chainstate_path,
+        Box::new(UnitEstimator),
+        Box::new(UnitMetric),
+        bloom_config.clone(),
+    )
+    .unwrap();
+    let bloom = mempool.get_txid_bloom_filter().unwrap();
+    for txid in all_txids[3].iter() {
+        assert!(bloom.contains_raw(&txid.0));
+    }
 }
 
 #[test]
@@ -1494,6 +2971,7 @@ fn test_find_next_missing_transactions() {
         .find_next_missing_transactions(
             &MemPoolSyncData::TxTags([0u8; 32], txtags.clone()),
             block_height,
+            BLOOM_COUNTER_DEPTH as u64,
             &Txid([0u8; 32]),
             (2 * MAX_BLOOM_COUNTER_TXS) as u64,
             MAX_BLOOM_COUNTER_TXS as u64,
@@ -1514,6 +2992,7 @@ fn test_find_next_missing_transactions() {
         .find_next_missing_transactions(
             &MemPoolSyncData::TxTags([0u8; 32], vec![]),
             block_height,
+            BLOOM_COUNTER_DEPTH as u64,
             &Txid([0u8; 32]),
             (2 * MAX_BLOOM_COUNTER_TXS) as u64,
             MAX_BLOOM_COUNTER_TXS as u64,
@@ -1537,6 +3016,7 @@ fn test_find_next_missing_transactions() {
         .find_next_missing_transactions(
             &MemPoolSyncData::BloomFilter(txid_bloom),
             block_height,
+            BLOOM_COUNTER_DEPTH as u64,
             &Txid([0u8; 32]),
             (2 * MAX_BLOOM_COUNTER_TXS) as u64,
             (2 * MAX_BLOOM_COUNTER_TXS) as u64,
@@ -1569,6 +3049,7 @@ fn test_find_next_missing_transactions() {
         .find_next_missing_transactions(
             &MemPoolSyncData::BloomFilter(empty_bloom.to_bloom_filter(&empty_bloom_conn).unwrap()),
             block_height,
+            BLOOM_COUNTER_DEPTH as u64,
             &Txid([0u8; 32]),
             (2 * MAX_BLOOM_COUNTER_TXS) as u64,
             (2 * MAX_BLOOM_COUNTER_TXS) as u64,
@@ -1594,6 +3075,7 @@ fn test_find_next_missing_transactions() {
             .find_next_missing_transactions(
                 &MemPoolSyncData::TxTags([0u8; 32], vec![]),
                 block_height,
+                BLOOM_COUNTER_DEPTH as u64,
                 &last_txid,
                 (2 * MAX_BLOOM_COUNTER_TXS) as u64,
                 page_size,
@@ -1630,6 +3112,7 @@ fn test_find_next_missing_transactions() {
                     empty_bloom.to_bloom_filter(&empty_bloom_conn).unwrap(),
                 ),
                 block_height,
+                BLOOM_COUNTER_DEPTH as u64,
                 &last_txid,
                 (2 * MAX_BLOOM_COUNTER_TXS) as u64,
                 page_size,
@@ -1664,6 +3147,7 @@ fn test_find_next_missing_transactions() {
         .find_next_missing_transactions(
             &MemPoolSyncData::TxTags([0u8; 32], vec![]),
             block_height + (BLOOM_COUNTER_DEPTH as u64) + 1,
+            BLOOM_COUNTER_DEPTH as u64,
             &last_txid,
             (2 * MAX_BLOOM_COUNTER_TXS) as u64,
             (2 * MAX_BLOOM_COUNTER_TXS) as u64,
@@ -1676,6 +3160,7 @@ fn test_find_next_missing_transactions() {
         .find_next_missing_transactions(
             &MemPoolSyncData::BloomFilter(empty_bloom.to_bloom_filter(&empty_bloom_conn).unwrap()),
             block_height + (BLOOM_COUNTER_DEPTH as u64) + 1,
+            BLOOM_COUNTER_DEPTH as u64,
             &last_txid,
             (2 * MAX_BLOOM_COUNTER_TXS) as u64,
             (2 * MAX_BLOOM_COUNTER_TXS) as u64,
@@ -1756,6 +3241,7 @@ fn test_stream_txs() {
         MemPoolSyncData::TxTags([0u8; 32], vec![]),
         MAX_BLOOM_COUNTER_TXS.into(),
         block_height,
+        BLOOM_COUNTER_DEPTH as u64,
         Some(Txid([0u8; 32])),
     );
     let mut tx_stream_data = if let StreamCursor::MempoolTxs(stream_data) = stream {
@@ -1822,6 +3308,7 @@ fn test_stream_txs() {
             MemPoolSyncData::TxTags([0u8; 32], vec![]),
             1,
             block_height,
+            BLOOM_COUNTER_DEPTH as u64,
             Some(page_id),
         );
 
@@ -1900,6 +3387,7 @@ fn test_stream_txs() {
             MemPoolSyncData::TxTags([0u8; 32], all_txs_tags.clone()),
             1,
             block_height,
+            BLOOM_COUNTER_DEPTH as u64,
             Some(page_id),
         );
 
@@ -2030,3 +3518,582 @@ fn test_decode_tx_stream() {
         }
     }
 }
+
+#[test]
+fn test_subnet_fee_estimator() {
+    // no blocks, or only empty blocks: everything is the minimum
+    let estimate = SubnetFeeEstimator::estimate(&[]);
+    assert_eq!(estimate.low, 1.0);
+    assert_eq!(estimate.middle, 1.0);
+    assert_eq!(estimate.high, 1.0);
+    assert_eq!(estimate.num_blocks, 0);
+    assert_eq!(estimate.num_transactions, 0);
+    assert_eq!(estimate.average_fullness, 0.0);
+
+    let empty_blocks = vec![
+        SubnetBlockFees {
+            tx_fee_rates: vec![],
+            fullness: 0.0,
+        };
+        10
+    ];
+    let estimate = SubnetFeeEstimator::estimate(&empty_blocks);
+    assert_eq!(estimate.middle, 1.0);
+    assert_eq!(estimate.num_blocks, 10);
+    assert_eq!(estimate.num_transactions, 0);
+
+    // mostly-empty blocks don't drag the estimate down to the minimum
+    let mut blocks = empty_blocks.clone();
+    blocks.push(SubnetBlockFees {
+        tx_fee_rates: vec![(100.0, 200), (200.0, 200)],
+        fullness: 0.1,
+    });
+    blocks.push(SubnetBlockFees {
+        tx_fee_rates: vec![(300.0, 200), (400.0, 200), (500.0, 200)],
+        fullness: 0.3,
+    });
+    let estimate = SubnetFeeEstimator::estimate(&blocks);
+    assert_eq!(estimate.low, 100.0);
+    assert_eq!(estimate.middle, 300.0);
+    assert_eq!(estimate.high, 500.0);
+    assert_eq!(estimate.num_blocks, 12);
+    assert_eq!(estimate.num_transactions, 5);
+    assert!((estimate.average_fullness - 0.4 / 12.0).abs() < 1e-9);
+
+    // rates are weighted by transaction length
+    let blocks = vec![SubnetBlockFees {
+        tx_fee_rates: vec![(10.0, 100), (1000.0, 900)],
+        fullness: 1.0,
+    }];
+    let estimate = SubnetFeeEstimator::estimate(&blocks);
+    assert_eq!(estimate.low, 10.0);
+    assert_eq!(estimate.middle, 1000.0);
+    assert_eq!(estimate.high, 1000.0);
+
+    // rates below the minimum relay fee rate are floored
+    let blocks = vec![SubnetBlockFees {
+        tx_fee_rates: vec![(0.5, 100)],
+        fullness: 0.5,
+    }];
+    let estimate = SubnetFeeEstimator::estimate(&blocks);
+    assert_eq!(estimate.low, 1.0);
+    assert_eq!(estimate.high, 1.0);
+}
+
+#[test]
+fn mempool_reject_bad_sponsorship() {
+    let mut chainstate = instantiate_chainstate_with_balances(
+        false,
+        0x80000000,
+        "mempool_reject_bad_sponsorship",
+        vec![],
+    );
+    let b_1 = make_block(
+        &mut chainstate,
+        ConsensusHash([0x1; 20]),
+        &(
+            FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+            FIRST_STACKS_BLOCK_HASH.clone(),
+        ),
+        1,
+        1,
+    );
+
+    let chainstate_path = chainstate_path("mempool_reject_bad_sponsorship");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+    let origin_privk = StacksPrivateKey::from_hex(SK_1).unwrap();
+    let sponsor_privk = StacksPrivateKey::from_hex(SK_2).unwrap();
+    let origin_auth = TransactionAuth::from_p2pkh(&origin_privk).unwrap();
+    let sponsor_auth = TransactionAuth::from_p2pkh(&sponsor_privk).unwrap();
+    let recipient = StacksAddress {
+        version: 26,
+        bytes: Hash160([0xff; 20]),
+    };
+    let payload = TransactionPayload::TokenTransfer(
+        recipient.clone().into(),
+        123,
+        TokenTransferMemo([0u8; 34]),
+    );
+
+    let mut original = StacksTransaction::new(
+        TransactionVersion::Testnet,
+        origin_auth.clone(),
+        payload.clone(),
+    );
+    original.chain_id = 0x80000000;
+    original.set_tx_fee(100);
+
+    let mut offer = original.clone();
+    offer.auth = origin_auth
+        .clone()
+        .into_sponsored(sponsor_auth.clone())
+        .unwrap();
+    offer.set_tx_fee(200);
+
+    // the original is in the mempool
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    let origin_address = original.origin_address();
+    MemPoolDB::try_add_tx(
+        &mut mempool_tx,
+        &mut chainstate,
+        &b_1.0,
+        &b_1.1,
+        original.txid(),
+        original.serialize_to_vec(),
+        original.get_tx_fee(),
+        1,
+        &origin_address,
+        original.get_origin_nonce(),
+        &origin_address,
+        original.get_origin_nonce(),
+        None,
+    )
+    .unwrap();
+    mempool_tx.commit().unwrap();
+
+    let enabled = SponsorshipPolicy {
+        enabled: true,
+        allowed_sponsors: vec![],
+    };
+
+    let mut submit_sponsorship =
+        |sponsored_txid: &Txid, offer: &StacksTransaction, policy: &SponsorshipPolicy| {
+            mempool.set_sponsorship_policy(policy.clone());
+            mempool
+                .submit_sponsorship(
+                    &mut chainstate,
+                    &b_1.0,
+                    &b_1.1,
+                    sponsored_txid,
+                    offer,
+                    None,
+                    &ExecutionCost::max_value(),
+                    &StacksEpochId::Epoch2_05,
+                )
+                .unwrap_err()
+        };
+
+    // offers are rejected unless the node accepts them
+    match submit_sponsorship(&original.txid(), &offer, &SponsorshipPolicy::default()) {
+        MemPoolRejection::SponsorshipNotAllowed(_) => {}
+        e => panic!("Unexpected rejection: {:?}", &e),
+    }
+
+    // the sponsor must be on the allow-list, if there is one
+    let restricted = SponsorshipPolicy {
+        enabled: true,
+        allowed_sponsors: vec![recipient.clone()],
+    };
+    match submit_sponsorship(&original.txid(), &offer, &restricted) {
+        MemPoolRejection::SponsorshipNotAllowed(_) => {}
+        e => panic!("Unexpected rejection: {:?}", &e),
+    }
+
+    // the sponsored transaction must be in the mempool
+    match submit_sponsorship(&Txid([0x11; 32]), &offer, &enabled) {
+        MemPoolRejection::BadSponsorship(_) => {}
+        e => panic!("Unexpected rejection: {:?}", &e),
+    }
+
+    // the offer must be sponsored
+    let mut unsponsored = original.clone();
+    unsponsored.set_tx_fee(200);
+    match submit_sponsorship(&original.txid(), &unsponsored, &enabled) {
+        MemPoolRejection::BadSponsorship(_) => {}
+        e => panic!("Unexpected rejection: {:?}", &e),
+    }
+
+    // the origin may not sponsor itself
+    let mut self_sponsored = offer.clone();
+    self_sponsored.auth = origin_auth
+        .clone()
+        .into_sponsored(origin_auth.clone())
+        .unwrap();
+    match submit_sponsorship(&original.txid(), &self_sponsored, &enabled) {
+        MemPoolRejection::BadSponsorship(_) => {}
+        e => panic!("Unexpected rejection: {:?}", &e),
+    }
+
+    // the offer must authorize the same transaction
+    let mut other_payload = offer.clone();
+    other_payload.payload =
+        TransactionPayload::TokenTransfer(recipient.into(), 456, TokenTransferMemo([0u8; 34]));
+    match submit_sponsorship(&original.txid(), &other_payload, &enabled) {
+        MemPoolRejection::BadSponsorship(_) => {}
+        e => panic!("Unexpected rejection: {:?}", &e),
+    }
+
+    let mut other_nonce = offer.clone();
+    other_nonce.set_origin_nonce(1);
+    match submit_sponsorship(&original.txid(), &other_nonce, &enabled) {
+        MemPoolRejection::BadSponsorship(_) => {}
+        e => panic!("Unexpected rejection: {:?}", &e),
+    }
+}
+
+#[test]
+fn mempool_rbf_sponsorship_policy() {
+    let mut chainstate = instantiate_chainstate_with_balances(
+        false,
+        0x80000000,
+        "mempool_rbf_sponsorship_policy",
+        vec![],
+    );
+    let b_1 = make_block(
+        &mut chainstate,
+        ConsensusHash([0x1; 20]),
+        &(
+            FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+            FIRST_STACKS_BLOCK_HASH.clone(),
+        ),
+        1,
+        1,
+    );
+
+    let chainstate_path = chainstate_path("mempool_rbf_sponsorship_policy");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+    let origin_privk = StacksPrivateKey::from_hex(SK_1).unwrap();
+    let sponsor_privk = StacksPrivateKey::from_hex(SK_2).unwrap();
+    let origin_auth = TransactionAuth::from_p2pkh(&origin_privk).unwrap();
+    let sponsor_auth = TransactionAuth::from_p2pkh(&sponsor_privk).unwrap();
+    let recipient = StacksAddress {
+        version: 26,
+        bytes: Hash160([0xff; 20]),
+    };
+    let payload = TransactionPayload::TokenTransfer(
+        recipient.clone().into(),
+        123,
+        TokenTransferMemo([0u8; 34]),
+    );
+
+    let mut original = StacksTransaction::new(
+        TransactionVersion::Testnet,
+        origin_auth.clone(),
+        payload.clone(),
+    );
+    original.chain_id = 0x80000000;
+    original.set_tx_fee(100);
+
+    let mut offer = original.clone();
+    offer.auth = origin_auth
+        .clone()
+        .into_sponsored(sponsor_auth.clone())
+        .unwrap();
+    offer.set_tx_fee(200);
+
+    // the original is in the mempool
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    let origin_address = original.origin_address();
+    MemPoolDB::try_add_tx(
+        &mut mempool_tx,
+        &mut chainstate,
+        &b_1.0,
+        &b_1.1,
+        original.txid(),
+        original.serialize_to_vec(),
+        original.get_tx_fee(),
+        1,
+        &origin_address,
+        original.get_origin_nonce(),
+        &origin_address,
+        original.get_origin_nonce(),
+        None,
+    )
+    .unwrap();
+    mempool_tx.commit().unwrap();
+
+    // a sponsored transaction that replaces the original by fee is a sponsorship offer, even if it
+    // is submitted like any other transaction
+    let mut try_add_offer = |offer: &StacksTransaction, policy: &SponsorshipPolicy| {
+        mempool.set_sponsorship_policy(policy.clone());
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        let res = MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &b_1.0,
+            &b_1.1,
+            offer.txid(),
+            offer.serialize_to_vec(),
+            offer.get_tx_fee(),
+            1,
+            &offer.origin_address(),
+            offer.get_origin_nonce(),
+            &offer.sponsor_address().unwrap(),
+            offer.get_sponsor_nonce().unwrap(),
+            None,
+        );
+        mempool_tx.commit().unwrap();
+        res
+    };
+
+    match try_add_offer(&offer, &SponsorshipPolicy::default()).unwrap_err() {
+        MemPoolRejection::SponsorshipNotAllowed(_) => {}
+        e => panic!("Unexpected rejection: {:?}", &e),
+    }
+
+    let enabled = SponsorshipPolicy {
+        enabled: true,
+        allowed_sponsors: vec![],
+    };
+    let restricted = SponsorshipPolicy {
+        enabled: true,
+        allowed_sponsors: vec![recipient.clone()],
+    };
+    match try_add_offer(&offer, &restricted).unwrap_err() {
+        MemPoolRejection::SponsorshipNotAllowed(_) => {}
+        e => panic!("Unexpected rejection: {:?}", &e),
+    }
+
+    // the replacement must authorize the same transaction
+    let mut other_payload = offer.clone();
+    other_payload.payload =
+        TransactionPayload::TokenTransfer(recipient.into(), 456, TokenTransferMemo([0u8; 34]));
+    match try_add_offer(&other_payload, &enabled).unwrap_err() {
+        MemPoolRejection::BadSponsorship(_) => {}
+        e => panic!("Unexpected rejection: {:?}", &e),
+    }
+
+    // a permitted offer replaces the original
+    try_add_offer(&offer, &enabled).unwrap();
+    assert!(!mempool.has_tx(&original.txid()));
+    assert!(mempool.has_tx(&offer.txid()));
+}
+
+#[test]
+fn mempool_submit_batch() {
+    let origin_privk = StacksPrivateKey::from_hex(SK_1).unwrap();
+    let origin_address = StacksAddress::from_public_keys(
+        C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+        &AddressHashMode::SerializeP2PKH,
+        1,
+        &vec![StacksPublicKey::from_private(&origin_privk)],
+    )
+    .unwrap();
+    let mut chainstate = instantiate_chainstate_with_balances(
+        false,
+        0x80000000,
+        "mempool_submit_batch",
+        vec![(origin_address.clone(), 1_000_000)],
+    );
+
+    let chainstate_path = chainstate_path("mempool_submit_batch");
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+    let recipient: PrincipalData = StacksAddress {
+        version: C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+        bytes: Hash160([0xff; 20]),
+    }
+    .into();
+    let transfer = |nonce: u64, amount: u64| {
+        make_user_stacks_transfer(&origin_privk, nonce, 1_000, &recipient, amount)
+    };
+    // the origin cannot afford this transfer
+    let overspend = |nonce: u64| transfer(nonce, 10_000_000);
+
+    let mut submit_batch = |mempool: &mut MemPoolDB, txs: &[StacksTransaction], all: bool| {
+        mempool
+            .submit_batch(
+                &mut chainstate,
+                &FIRST_BURNCHAIN_CONSENSUS_HASH,
+                &FIRST_STACKS_BLOCK_HASH,
+                txs,
+                all,
+                None,
+                &ExecutionCost::max_value(),
+                &StacksEpochId::Epoch2_05,
+            )
+            .unwrap()
+    };
+
+    // without all-or-nothing, every transaction is tried, and one rejection does not affect the
+    // others
+    let txs = vec![transfer(0, 100), overspend(1), transfer(2, 100)];
+    let results = submit_batch(&mut mempool, &txs, false);
+    assert_eq!(results.len(), 3);
+    assert!(results[0].is_ok());
+    match &results[1] {
+        Err(MemPoolRejection::NotEnoughFunds(..)) => {}
+        res => panic!("Unexpected result: {:?}", res),
+    }
+    assert!(results[2].is_ok());
+    assert!(mempool.has_tx(&txs[0].txid()));
+    assert!(!mempool.has_tx(&txs[1].txid()));
+    assert!(mempool.has_tx(&txs[2].txid()));
+
+    // with all-or-nothing, the batch stops at the first rejection, and nothing is admitted
+    let txs = vec![transfer(3, 100), overspend(4), transfer(5, 100)];
+    let results = submit_batch(&mut mempool, &txs, true);
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    match &results[1] {
+        Err(MemPoolRejection::NotEnoughFunds(..)) => {}
+        res => panic!("Unexpected result: {:?}", res),
+    }
+    for tx in txs.iter() {
+        assert!(!mempool.has_tx(&tx.txid()));
+    }
+
+    // a full batch is admitted, and its results are in submission order
+    let txs = vec![transfer(5, 200), transfer(3, 200), transfer(4, 200)];
+    let results = submit_batch(&mut mempool, &txs, true);
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(|res| res.is_ok()));
+    for tx in txs.iter() {
+        assert!(mempool.has_tx(&tx.txid()));
+    }
+
+    // later transactions in a batch see the earlier ones: replacing a transaction admitted
+    // earlier in the same batch requires a higher fee
+    let first = make_user_stacks_transfer(&origin_privk, 6, 1_000, &recipient, 300);
+    let same_fee = make_user_stacks_transfer(&origin_privk, 6, 1_000, &recipient, 301);
+    let results = submit_batch(&mut mempool, &[first.clone(), same_fee.clone()], true);
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    match &results[1] {
+        Err(MemPoolRejection::ConflictingNonceInMempool) => {}
+        res => panic!("Unexpected result: {:?}", res),
+    }
+    assert!(!mempool.has_tx(&first.txid()));
+    assert!(!mempool.has_tx(&same_fee.txid()));
+}
+
+/// Serves fixed nonces to candidate check workers
+struct FixedNonceReader(HashMap<StacksAddress, u64>);
+
+impl CandidateNonceReader for FixedNonceReader {
+    fn get_nonce(&mut self, _tip: &StacksBlockId, address: &StacksAddress) -> Option<u64> {
+        self.0.get(address).cloned()
+    }
+}
+
+#[test]
+fn test_candidate_check_pool() {
+    let privk = StacksPrivateKey::from_hex(SK_1).unwrap();
+    let origin_address = StacksAddress::from_public_keys(
+        C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+        &AddressHashMode::SerializeP2PKH,
+        1,
+        &vec![StacksPublicKey::from_private(&privk)],
+    )
+    .unwrap();
+    let unknown_privk = StacksPrivateKey::from_hex(SK_2).unwrap();
+    let recipient = StacksAddress {
+        version: 26,
+        bytes: Hash160([0xff; 20]),
+    };
+
+    let mut expected = HashMap::new();
+    let mut txs = vec![];
+    for nonce in 0..20 {
+        // the last transactions come from an account whose nonce cannot be read
+        let signer = if nonce < 15 { &privk } else { &unknown_privk };
+        let mut tx = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::from_p2pkh(signer).unwrap(),
+            TransactionPayload::TokenTransfer(
+                recipient.clone().into(),
+                123,
+                TokenTransferMemo([0u8; 34]),
+            ),
+        );
+        tx.chain_id = 0x80000000;
+        tx.set_tx_fee(100);
+        tx.set_origin_nonce(nonce);
+
+        // leave every third transaction unsigned
+        let tx = if nonce % 3 == 0 {
+            tx
+        } else {
+            let mut tx_signer = StacksTransactionSigner::new(&tx);
+            tx_signer.sign_origin(signer).unwrap();
+            tx_signer.get_tx().unwrap()
+        };
+        let nonces = if nonce < 15 {
+            vec![(origin_address.clone(), 7)]
+        } else {
+            vec![]
+        };
+        expected.insert(tx.txid(), (nonce % 3 != 0, nonces));
+        txs.push(tx);
+    }
+
+    let tip = StacksBlockId([0x01; 32]);
+    for num_threads in [1, 3, 32].iter() {
+        let readers = (0..*num_threads)
+            .map(|_| {
+                let nonces = vec![(origin_address.clone(), 7)].into_iter().collect();
+                Box::new(FixedNonceReader(nonces)) as Box<dyn CandidateNonceReader>
+            })
+            .collect();
+        let pool = CandidateCheckPool::new(readers);
+        assert_eq!(pool.num_workers(), *num_threads);
+
+        // the same workers serve every check
+        for _ in 0..2 {
+            let results: HashMap<_, _> = pool
+                .check(&tip, txs.clone(), Duration::from_secs(60))
+                .into_iter()
+                .map(|check| (check.txid, (check.auth_ok, check.nonces)))
+                .collect();
+            assert_eq!(results, expected);
+        }
+        assert!(pool.check(&tip, vec![], Duration::from_secs(60)).is_empty());
+    }
+
+    // no workers, no checks
+    let pool = CandidateCheckPool::new(vec![]);
+    assert!(pool.check(&tip, txs, Duration::from_secs(60)).is_empty());
+}
+
+#[test]
+fn mempool_rejects_unchecked_intermediary_responses() {
+    let origin_privk = StacksPrivateKey::from_hex(SK_1).unwrap();
+    let origin_address = StacksAddress::from_public_keys(
+        C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+        &AddressHashMode::SerializeP2PKH,
+        1,
+        &vec![StacksPublicKey::from_private(&origin_privk)],
+    )
+    .unwrap();
+    let mut chainstate = instantiate_chainstate_with_balances(
+        false,
+        0x80000000,
+        "mempool_rejects_unchecked_intermediary_responses",
+        vec![(origin_address.clone(), 1_000_000)],
+    );
+
+    let mut will_admit = |tx: &StacksTransaction| {
+        chainstate.will_admit_mempool_tx(
+            &FIRST_BURNCHAIN_CONSENSUS_HASH,
+            &FIRST_STACKS_BLOCK_HASH,
+            tx,
+            tx.serialize_to_vec().len() as u64,
+        )
+    };
+
+    // the contract is analyzed under the cost limit of the block it would be mined in
+    let checked = make_user_contract_publish(
+        &origin_privk,
+        0,
+        1_000,
+        "checked",
+        "(define-public (f) (begin (unwrap-panic (ok 1)) (ok 2)))",
+    );
+    will_admit(&checked).unwrap();
+
+    let unchecked = make_user_contract_publish(
+        &origin_privk,
+        0,
+        1_000,
+        "unchecked",
+        "(define-public (f) (begin (ok 1) (ok 2)))",
+    );
+    match will_admit(&unchecked) {
+        Err(MemPoolRejection::ContractAnalysisFailed(e)) => {
+            assert_eq!(e.err, CheckErrors::UncheckedIntermediaryResponses)
+        }
+        res => panic!("Unexpected result: {:?}", res),
+    }
+}
@@ -229,6 +229,7 @@ impl PessimisticEstimator {
                     StacksEpochId::Epoch10 => "",
                     StacksEpochId::Epoch20 => "",
                     StacksEpochId::Epoch2_05 => ":2.05",
+                    StacksEpochId::EpochSubnet1 => ":subnet-1",
                 };
                 format!(
                     "cc{}:{}:{}.{}",
@@ -105,6 +105,8 @@ pub mod cost_estimates;
 
 pub mod clarity_cli;
 
+pub mod clarity_repl;
+
 // set via _compile-time_ envars
 const GIT_BRANCH: Option<&'static str> = option_env!("GIT_BRANCH");
 const GIT_COMMIT: Option<&'static str> = option_env!("GIT_COMMIT");
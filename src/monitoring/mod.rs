@@ -19,6 +19,7 @@ use std::{fmt, fs, path::PathBuf};
 use rusqlite::{OpenFlags, OptionalExtension};
 
 use crate::burnchains::BurnchainSigner;
+use crate::net::chat::NeighborStats;
 use crate::util_lib::db::sqlite_open;
 use crate::util_lib::db::Error as DatabaseError;
 use crate::{
@@ -38,9 +39,20 @@ use std::sync::Mutex;
 #[cfg(feature = "monitoring_prom")]
 mod prometheus;
 
+#[cfg(feature = "monitoring_prom")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "monitoring_prom")]
+use std::time::Instant;
+
+/// Most block commits whose submission time is remembered until they are observed on the L1
+#[cfg(feature = "monitoring_prom")]
+const MAX_PENDING_COMMITS: usize = 64;
+
 #[cfg(feature = "monitoring_prom")]
 lazy_static! {
     static ref GLOBAL_BURNCHAIN_SIGNER: Mutex<Option<BurnchainSigner>> = Mutex::new(None);
+    static ref EXPORTED_PEER_LABELS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+    static ref PENDING_COMMITS: Mutex<HashMap<Txid, Instant>> = Mutex::new(HashMap::new());
 }
 
 pub fn increment_rpc_calls_counter() {
@@ -150,6 +162,72 @@ pub fn increment_errors_emitted_counter() {
     prometheus::ERRORS_EMITTED_COUNTER.inc();
 }
 
+pub fn increment_l1_blocks_observed_counter() {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::L1_BLOCKS_OBSERVED_COUNTER.inc();
+}
+
+/// Count a processed L1 deposit of an `asset` ("stx", "ft" or "nft")
+#[allow(unused_variables)]
+pub fn increment_deposits_processed_counter(asset: &str) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::DEPOSITS_PROCESSED_COUNTER_VEC
+        .with_label_values(&[asset])
+        .inc();
+}
+
+/// Count a failed request to the L1 node's RPC interface, labeled by what it was for
+#[allow(unused_variables)]
+pub fn increment_l1_rpc_failures_counter(operation: &str) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::L1_RPC_FAILURES_COUNTER_VEC
+        .with_label_values(&[operation])
+        .inc();
+}
+
+/// Count an RPC request rejected by the node's rate limits, labeled by its endpoint class
+#[allow(unused_variables)]
+pub fn increment_rpc_rate_limited_counter(class: &str) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::RPC_RATE_LIMITED_COUNTER_VEC
+        .with_label_values(&[class])
+        .inc();
+}
+
+/// Note that this miner submitted the L1 block commit transaction `txid`, so that its latency
+/// can be measured once it is observed.
+#[allow(unused_variables)]
+pub fn record_commit_submitted(txid: &Txid) {
+    #[cfg(feature = "monitoring_prom")]
+    {
+        let mut pending = PENDING_COMMITS.lock().unwrap();
+        if pending.len() >= MAX_PENDING_COMMITS {
+            // commits that never land (e.g. replaced ones) must not accumulate
+            if let Some(oldest) = pending
+                .iter()
+                .min_by_key(|(_, submitted_at)| **submitted_at)
+                .map(|(txid, _)| txid.clone())
+            {
+                pending.remove(&oldest);
+            }
+        }
+        pending.insert(txid.clone(), Instant::now());
+    }
+}
+
+/// Note that the L1 block commit transaction `txid` was observed in an L1 block.  Every observed
+/// commit carries a withdrawal root; the commits this miner submitted also record their latency.
+#[allow(unused_variables)]
+pub fn record_commit_observed(txid: &Txid) {
+    #[cfg(feature = "monitoring_prom")]
+    {
+        prometheus::WITHDRAWAL_ROOTS_COMMITTED_COUNTER.inc();
+        if let Some(submitted_at) = PENDING_COMMITS.lock().unwrap().remove(txid) {
+            prometheus::COMMIT_LATENCY_HISTOGRAM.observe(submitted_at.elapsed().as_secs_f64());
+        }
+    }
+}
+
 fn txid_tracking_db(chainstate_root_path: &str) -> Result<DBConn, DatabaseError> {
     let mut path = PathBuf::from(chainstate_root_path);
 
@@ -272,6 +350,51 @@ pub fn update_outbound_neighbors(value: i64) {
     prometheus::OUTBOUND_NEIGHBORS_GAUGE.set(value);
 }
 
+#[allow(unused_variables)]
+pub fn update_outbound_orgs(value: i64) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::OUTBOUND_ORGS_GAUGE.set(value);
+}
+
+#[allow(unused_variables)]
+pub fn increment_diverse_walks_counter() {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::DIVERSE_WALKS_COUNTER.inc();
+}
+
+/// Export the statistics of the given outbound peers, labeled by peer.  Series for peers that
+/// were exported by a previous call but are absent from `peers` are removed, so the number of
+/// series never exceeds the number of peers passed in.
+#[allow(unused_variables)]
+pub fn update_outbound_peer_stats(peers: &[(String, &NeighborStats)]) {
+    #[cfg(feature = "monitoring_prom")]
+    {
+        let mut exported = EXPORTED_PEER_LABELS.lock().unwrap();
+        let current: HashSet<String> = peers.iter().map(|(label, _)| label.clone()).collect();
+        for stale in exported.difference(&current) {
+            let _ = prometheus::PEER_HEALTH_SCORE_GAUGE_VEC.remove_label_values(&[stale]);
+            let _ = prometheus::PEER_BYTES_RX_GAUGE_VEC.remove_label_values(&[stale]);
+            let _ = prometheus::PEER_BYTES_TX_GAUGE_VEC.remove_label_values(&[stale]);
+            let _ = prometheus::PEER_MSGS_ERR_GAUGE_VEC.remove_label_values(&[stale]);
+        }
+        for (label, stats) in peers.iter() {
+            prometheus::PEER_HEALTH_SCORE_GAUGE_VEC
+                .with_label_values(&[label])
+                .set(stats.get_health_score());
+            prometheus::PEER_BYTES_RX_GAUGE_VEC
+                .with_label_values(&[label])
+                .set(stats.bytes_rx as i64);
+            prometheus::PEER_BYTES_TX_GAUGE_VEC
+                .with_label_values(&[label])
+                .set(stats.bytes_tx as i64);
+            prometheus::PEER_MSGS_ERR_GAUGE_VEC
+                .with_label_values(&[label])
+                .set(stats.msgs_err as i64);
+        }
+        *exported = current;
+    }
+}
+
 #[allow(unused_variables)]
 pub fn update_inbound_bandwidth(value: i64) {
     #[cfg(feature = "monitoring_prom")]
@@ -309,6 +432,15 @@ pub fn increment_stx_mempool_gc() {
     prometheus::STX_MEMPOOL_GC.inc();
 }
 
+/// Count a transaction evicted by mempool garbage collection, labeled by the eviction reason
+#[allow(unused_variables)]
+pub fn increment_mempool_gc_evictions_counter(reason: &str) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::MEMPOOL_GC_EVICTIONS_COUNTER_VEC
+        .with_label_values(&[reason])
+        .inc();
+}
+
 pub fn increment_contract_calls_processed() {
     #[cfg(feature = "monitoring_prom")]
     prometheus::CONTRACT_CALLS_PROCESSED_COUNT.inc();
@@ -15,7 +15,8 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use prometheus::{
-    Gauge, Histogram, HistogramTimer, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    Gauge, GaugeVec, Histogram, HistogramTimer, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec,
 };
 
 lazy_static! {
@@ -141,6 +142,40 @@ lazy_static! {
         "Total count of current known outbound neighbors"
     )).unwrap();
 
+    pub static ref OUTBOUND_ORGS_GAUGE: IntGauge = register_int_gauge!(opts!(
+        "stacks_node_neighbors_outbound_orgs",
+        "Number of distinct AS-owning organizations spanned by current outbound neighbors"
+    )).unwrap();
+
+    pub static ref DIVERSE_WALKS_COUNTER: IntCounter = register_int_counter!(opts!(
+        "stacks_node_neighbor_walks_diversified_total",
+        "Total number of neighbor walks started at a peer in an organization we were not connected to"
+    )).unwrap();
+
+    pub static ref PEER_HEALTH_SCORE_GAUGE_VEC: GaugeVec = register_gauge_vec!(
+        "stacks_node_peer_health_score",
+        "Fraction of recent messages to an outbound peer that succeeded",
+        &["peer"]
+    ).unwrap();
+
+    pub static ref PEER_BYTES_RX_GAUGE_VEC: IntGaugeVec = register_int_gauge_vec!(
+        "stacks_node_peer_bytes_rx",
+        "Total bytes received from an outbound peer over the current connection",
+        &["peer"]
+    ).unwrap();
+
+    pub static ref PEER_BYTES_TX_GAUGE_VEC: IntGaugeVec = register_int_gauge_vec!(
+        "stacks_node_peer_bytes_tx",
+        "Total bytes sent to an outbound peer over the current connection",
+        &["peer"]
+    ).unwrap();
+
+    pub static ref PEER_MSGS_ERR_GAUGE_VEC: IntGaugeVec = register_int_gauge_vec!(
+        "stacks_node_peer_msgs_err",
+        "Total invalid messages received from an outbound peer over the current connection",
+        &["peer"]
+    ).unwrap();
+
     pub static ref INBOUND_BANDWIDTH_GAUGE: IntGauge = register_int_gauge!(opts!(
         "stacks_node_bandwidth_inbound",
         "Total inbound bandwidth total in bytes"
@@ -215,6 +250,46 @@ lazy_static! {
         "stacks_node_miner_current_median_commitment_low",
         "Low 64 bits of a miner's median commitment over the mining commitment window."
     )).unwrap();
+
+    pub static ref L1_BLOCKS_OBSERVED_COUNTER: IntCounter = register_int_counter!(opts!(
+        "stacks_node_l1_blocks_observed_total",
+        "Total number of L1 blocks received from the L1 observer"
+    )).unwrap();
+
+    pub static ref DEPOSITS_PROCESSED_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "stacks_node_deposits_processed_total",
+        "Total number of L1 deposit operations processed in subnet blocks, by asset type",
+        &["asset"]
+    ).unwrap();
+
+    pub static ref RPC_RATE_LIMITED_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "stacks_node_rpc_rate_limited_total",
+        "Total number of RPC requests rejected by the node's rate limits, by endpoint class",
+        &["class"]
+    ).unwrap();
+
+    pub static ref WITHDRAWAL_ROOTS_COMMITTED_COUNTER: IntCounter = register_int_counter!(opts!(
+        "stacks_node_withdrawal_roots_committed_total",
+        "Total number of block commits, and the withdrawal roots they carry, observed on the L1"
+    )).unwrap();
+
+    pub static ref COMMIT_LATENCY_HISTOGRAM: Histogram = register_histogram!(histogram_opts!(
+        "stacks_node_commit_latencies_histogram",
+        "Time (seconds) between when this miner submitted a block commit to the L1 and when the commit was observed in an L1 block",
+        vec![5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1200.0, 1800.0, 3600.0]
+    )).unwrap();
+
+    pub static ref L1_RPC_FAILURES_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "stacks_node_l1_rpc_failures_total",
+        "Total number of failed requests to the L1 node's RPC interface, by operation",
+        &["operation"]
+    ).unwrap();
+
+    pub static ref MEMPOOL_GC_EVICTIONS_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "stacks_node_mempool_gc_evictions_total",
+        "Total number of transactions evicted from the mempool by garbage collection, by reason",
+        &["reason"]
+    ).unwrap();
 }
 
 pub fn new_rpc_call_timer(path: &str) -> HistogramTimer {
@@ -0,0 +1,544 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Authorization of admin RPC requests.
+//!
+//! Admin requests carry an `Authorization` header, which is either a shared secret, or a
+//! signature of the request by a secp256k1 key:
+//!
+//! ```text
+//! Authorization: Signature <unix timestamp>:<hex-encoded recoverable signature>
+//! ```
+//!
+//! where the signed message is the SHA512/256 hash of `<method> <path> <timestamp> <body hash>`.
+//! The path includes the query string, if there is one, and the body hash is the hex-encoded
+//! SHA512/256 hash of the request's JSON body (or of no bytes, if the request has no body).  The
+//! node checks the signature against the request as it would itself encode it, so the path and
+//! body must be sent in that form.  A signed request is only honored once.
+//!
+//! Each configured credential grants a role: read-only credentials may query node internals, and
+//! operator credentials may also change node state.
+
+use std::collections::HashMap;
+
+use crate::burnchains::PrivateKey;
+use crate::chainstate::stacks::{StacksPrivateKey, StacksPublicKey};
+use stacks_common::util::hash::Sha512Trunc256Sum;
+use stacks_common::util::secp256k1::MessageSignature;
+
+/// The `Authorization` scheme of signed admin requests
+pub const ADMIN_SIGNATURE_SCHEME: &'static str = "Signature";
+
+/// How far the timestamp of a signed admin request may be from this node's clock, in seconds.
+/// Signed requests are remembered for this long, so that a captured request cannot be replayed.
+pub const ADMIN_SIGNATURE_MAX_SKEW_SECS: u64 = 60;
+
+/// What an admin credential may do.  Roles are ordered: each role may do everything the roles
+/// before it may.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AdminRole {
+    /// May query node internals, such as the node's connected peers
+    ReadOnly,
+    /// May also change node state: evict mempool transactions, ban peers, and capture divergence
+    /// snapshots
+    Operator,
+}
+
+impl AdminRole {
+    pub fn from_str(role: &str) -> Option<AdminRole> {
+        match role {
+            "read_only" => Some(AdminRole::ReadOnly),
+            "operator" => Some(AdminRole::Operator),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminRole::ReadOnly => "read_only",
+            AdminRole::Operator => "operator",
+        }
+    }
+}
+
+/// How an admin request proves it holds a credential
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdminAuth {
+    /// The `Authorization` header is this secret
+    SharedSecret(String),
+    /// The `Authorization` header is a signature of the request by this key
+    PublicKey(StacksPublicKey),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdminCredential {
+    pub role: AdminRole,
+    pub auth: AdminAuth,
+}
+
+/// The hash that a signed admin request signs.  `path` includes the query string, if any, and
+/// `body` is the request body (empty if there is none).
+pub fn admin_request_hash(
+    method: &str,
+    path: &str,
+    body: &[u8],
+    timestamp: u64,
+) -> Sha512Trunc256Sum {
+    let body_hash = Sha512Trunc256Sum::from_data(body);
+    Sha512Trunc256Sum::from_data(
+        format!("{} {} {} {}", method, path, timestamp, body_hash).as_bytes(),
+    )
+}
+
+/// Make the `Authorization` header of an admin request signed by `privkey` at `timestamp`
+pub fn sign_admin_request(
+    privkey: &StacksPrivateKey,
+    method: &str,
+    path: &str,
+    body: &[u8],
+    timestamp: u64,
+) -> Result<String, &'static str> {
+    let hash = admin_request_hash(method, path, body, timestamp);
+    let signature = privkey.sign(hash.as_bytes())?;
+    Ok(format!(
+        "{} {}:{}",
+        ADMIN_SIGNATURE_SCHEME,
+        timestamp,
+        signature.to_hex()
+    ))
+}
+
+/// The signed admin requests that this node has honored recently.  A signed request stays valid
+/// for as long as its timestamp is within `ADMIN_SIGNATURE_MAX_SKEW_SECS` of the node's clock, so
+/// each one is remembered for that long, and refused if it is seen again.
+#[derive(Debug, Default)]
+pub struct AdminReplayCache {
+    /// (signed hash, signer) of each remembered request, mapped to the request's timestamp
+    seen: HashMap<(Sha512Trunc256Sum, Vec<u8>), u64>,
+}
+
+impl AdminReplayCache {
+    pub fn new() -> AdminReplayCache {
+        AdminReplayCache::default()
+    }
+
+    /// Remember a request signed by `signer`.  Returns false if it was already seen.
+    fn insert(
+        &mut self,
+        hash: Sha512Trunc256Sum,
+        signer: Vec<u8>,
+        timestamp: u64,
+        now: u64,
+    ) -> bool {
+        // forget requests which would be rejected as stale anyway
+        self.seen
+            .retain(|_, seen_at| seen_at.saturating_add(ADMIN_SIGNATURE_MAX_SKEW_SECS) >= now);
+        self.seen.insert((hash, signer), timestamp).is_none()
+    }
+}
+
+/// A signed admin request whose signature was recovered
+struct SignedAdminRequest {
+    signer: StacksPublicKey,
+    hash: Sha512Trunc256Sum,
+    timestamp: u64,
+}
+
+/// Recover the key that signed an admin request, given its `Authorization` header.  Returns None
+/// if the header is not a well-formed signature, or if its timestamp is too far from `now`.
+fn recover_admin_request_signer(
+    authorization: &str,
+    method: &str,
+    path: &str,
+    body: &[u8],
+    now: u64,
+) -> Option<SignedAdminRequest> {
+    let signed = authorization.strip_prefix(ADMIN_SIGNATURE_SCHEME)?.trim();
+    let (timestamp, signature) = signed.split_once(':')?;
+    let timestamp = timestamp.parse::<u64>().ok()?;
+    if timestamp.max(now) - timestamp.min(now) > ADMIN_SIGNATURE_MAX_SKEW_SECS {
+        return None;
+    }
+    let signature = MessageSignature::from_hex(signature).ok()?;
+    let hash = admin_request_hash(method, path, body, timestamp);
+    let signer = StacksPublicKey::recover_to_pubkey(hash.as_bytes(), &signature).ok()?;
+    Some(SignedAdminRequest {
+        signer,
+        hash,
+        timestamp,
+    })
+}
+
+/// Does the `Authorization` header `presented` match the shared `secret`?  Both are hashed and
+/// the hashes compared in constant time, so that how long the check takes reveals neither how
+/// much of the secret a guess got right nor how long the secret is.
+fn shared_secret_matches(secret: &str, presented: &str) -> bool {
+    let secret_hash = Sha512Trunc256Sum::from_data(secret.as_bytes());
+    let presented_hash = Sha512Trunc256Sum::from_data(presented.as_bytes());
+    secret_hash
+        .as_bytes()
+        .iter()
+        .zip(presented_hash.as_bytes().iter())
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+/// Find the role granted to an admin request, given its `Authorization` header.  If the header
+/// matches several credentials, the highest of their roles is granted.  Returns None if the
+/// header matches no credential, or if it is a signature that `replay_cache` has already seen.
+pub fn authorize_admin_request(
+    credentials: &[AdminCredential],
+    replay_cache: &mut AdminReplayCache,
+    authorization: Option<&str>,
+    method: &str,
+    path: &str,
+    body: &[u8],
+    now: u64,
+) -> Option<AdminRole> {
+    let authorization = authorization?;
+    let signed = recover_admin_request_signer(authorization, method, path, body, now);
+    let signer = signed
+        .as_ref()
+        .map(|signed| signed.signer.to_bytes_compressed());
+
+    let role = credentials
+        .iter()
+        .filter(|credential| match credential.auth {
+            AdminAuth::SharedSecret(ref secret) => shared_secret_matches(secret, authorization),
+            AdminAuth::PublicKey(ref pubkey) => {
+                signer.as_ref() == Some(&pubkey.to_bytes_compressed())
+            }
+        })
+        .map(|credential| credential.role)
+        .max()?;
+
+    // only requests signed by a configured key are remembered, so that unauthorized requests
+    // cannot fill the cache
+    if let (Some(signed), Some(signer)) = (signed, signer) {
+        if !replay_cache.insert(signed.hash, signer, signed.timestamp, now) {
+            return None;
+        }
+    }
+    Some(role)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn credentials() -> (StacksPrivateKey, Vec<AdminCredential>) {
+        let operator_key = StacksPrivateKey::new();
+        let credentials = vec![
+            AdminCredential {
+                role: AdminRole::ReadOnly,
+                auth: AdminAuth::SharedSecret("metrics-secret".into()),
+            },
+            AdminCredential {
+                role: AdminRole::Operator,
+                auth: AdminAuth::PublicKey(StacksPublicKey::from_private(&operator_key)),
+            },
+        ];
+        (operator_key, credentials)
+    }
+
+    #[test]
+    fn test_shared_secret_matches() {
+        assert!(shared_secret_matches("metrics-secret", "metrics-secret"));
+        assert!(!shared_secret_matches("metrics-secret", "metrics-secreT"));
+        assert!(!shared_secret_matches("metrics-secret", "metrics"));
+        assert!(!shared_secret_matches(
+            "metrics-secret",
+            "metrics-secret-and-more"
+        ));
+        assert!(!shared_secret_matches("metrics-secret", ""));
+    }
+
+    #[test]
+    fn test_authorize_shared_secret() {
+        let (_, credentials) = credentials();
+        let mut cache = AdminReplayCache::new();
+        let path = "/v2/admin/peers";
+        for _ in 0..2 {
+            // shared secrets aren't signatures, so they may be sent repeatedly
+            assert_eq!(
+                authorize_admin_request(
+                    &credentials,
+                    &mut cache,
+                    Some("metrics-secret"),
+                    "GET",
+                    path,
+                    &[],
+                    NOW
+                ),
+                Some(AdminRole::ReadOnly)
+            );
+        }
+        assert_eq!(
+            authorize_admin_request(
+                &credentials,
+                &mut cache,
+                Some("wrong"),
+                "GET",
+                path,
+                &[],
+                NOW
+            ),
+            None
+        );
+        assert_eq!(
+            authorize_admin_request(&credentials, &mut cache, None, "GET", path, &[], NOW),
+            None
+        );
+    }
+
+    #[test]
+    fn test_authorize_signature() {
+        let (operator_key, credentials) = credentials();
+        let path = "/v2/admin/bans/add";
+        let body = br#"{"addr":"10.0.0.1:20444","duration_secs":3600}"#;
+        let header = sign_admin_request(&operator_key, "POST", path, body, NOW).unwrap();
+        assert!(header.starts_with("Signature "));
+
+        // the signature covers the method, path and body
+        let mut cache = AdminReplayCache::new();
+        assert_eq!(
+            authorize_admin_request(
+                &credentials,
+                &mut cache,
+                Some(&header),
+                "GET",
+                path,
+                body,
+                NOW
+            ),
+            None
+        );
+        assert_eq!(
+            authorize_admin_request(
+                &credentials,
+                &mut cache,
+                Some(&header),
+                "POST",
+                "/v2/admin/peers",
+                body,
+                NOW
+            ),
+            None
+        );
+        assert_eq!(
+            authorize_admin_request(
+                &credentials,
+                &mut cache,
+                Some(&header),
+                "POST",
+                path,
+                br#"{"addr":"10.0.0.2:20444","duration_secs":3600}"#,
+                NOW
+            ),
+            None
+        );
+
+        // stale or future-dated signatures are rejected
+        assert_eq!(
+            authorize_admin_request(
+                &credentials,
+                &mut cache,
+                Some(&header),
+                "POST",
+                path,
+                body,
+                NOW + ADMIN_SIGNATURE_MAX_SKEW_SECS + 1
+            ),
+            None
+        );
+        assert_eq!(
+            authorize_admin_request(
+                &credentials,
+                &mut cache,
+                Some(&header),
+                "POST",
+                path,
+                body,
+                NOW - ADMIN_SIGNATURE_MAX_SKEW_SECS - 1
+            ),
+            None
+        );
+
+        assert_eq!(
+            authorize_admin_request(
+                &credentials,
+                &mut cache,
+                Some(&header),
+                "POST",
+                path,
+                body,
+                NOW + 30
+            ),
+            Some(AdminRole::Operator)
+        );
+
+        // keys that aren't configured are rejected
+        let other_key = StacksPrivateKey::new();
+        let header = sign_admin_request(&other_key, "POST", path, body, NOW).unwrap();
+        assert_eq!(
+            authorize_admin_request(
+                &credentials,
+                &mut cache,
+                Some(&header),
+                "POST",
+                path,
+                body,
+                NOW
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_authorize_signature_query() {
+        let (operator_key, credentials) = credentials();
+        let mut cache = AdminReplayCache::new();
+        let path = "/v2/mempool/0101010101010101010101010101010101010101010101010101010101010101";
+        let header = sign_admin_request(
+            &operator_key,
+            "DELETE",
+            &format!("{}?reason=spam", path),
+            &[],
+            NOW,
+        )
+        .unwrap();
+
+        // the signature covers the query string
+        assert_eq!(
+            authorize_admin_request(
+                &credentials,
+                &mut cache,
+                Some(&header),
+                "DELETE",
+                path,
+                &[],
+                NOW
+            ),
+            None
+        );
+        assert_eq!(
+            authorize_admin_request(
+                &credentials,
+                &mut cache,
+                Some(&header),
+                "DELETE",
+                &format!("{}?reason=other", path),
+                &[],
+                NOW
+            ),
+            None
+        );
+        assert_eq!(
+            authorize_admin_request(
+                &credentials,
+                &mut cache,
+                Some(&header),
+                "DELETE",
+                &format!("{}?reason=spam", path),
+                &[],
+                NOW
+            ),
+            Some(AdminRole::Operator)
+        );
+    }
+
+    #[test]
+    fn test_authorize_signature_replay() {
+        let (operator_key, credentials) = credentials();
+        let mut cache = AdminReplayCache::new();
+        let path = "/v2/admin/divergence_snapshot";
+        let header = sign_admin_request(&operator_key, "POST", path, &[], NOW).unwrap();
+
+        assert_eq!(
+            authorize_admin_request(
+                &credentials,
+                &mut cache,
+                Some(&header),
+                "POST",
+                path,
+                &[],
+                NOW
+            ),
+            Some(AdminRole::Operator)
+        );
+
+        // the same request is refused for as long as its timestamp would be accepted
+        for now in [NOW, NOW + 1, NOW + ADMIN_SIGNATURE_MAX_SKEW_SECS] {
+            assert_eq!(
+                authorize_admin_request(
+                    &credentials,
+                    &mut cache,
+                    Some(&header),
+                    "POST",
+                    path,
+                    &[],
+                    now
+                ),
+                None
+            );
+        }
+
+        // a request signed at a different time is a different request
+        let header = sign_admin_request(&operator_key, "POST", path, &[], NOW + 1).unwrap();
+        assert_eq!(
+            authorize_admin_request(
+                &credentials,
+                &mut cache,
+                Some(&header),
+                "POST",
+                path,
+                &[],
+                NOW + 1
+            ),
+            Some(AdminRole::Operator)
+        );
+
+        // stale requests are forgotten once they would be rejected anyway
+        let later = NOW + 1 + ADMIN_SIGNATURE_MAX_SKEW_SECS + 1;
+        let header = sign_admin_request(&operator_key, "POST", path, &[], later).unwrap();
+        assert_eq!(
+            authorize_admin_request(
+                &credentials,
+                &mut cache,
+                Some(&header),
+                "POST",
+                path,
+                &[],
+                later
+            ),
+            Some(AdminRole::Operator)
+        );
+        assert_eq!(cache.seen.len(), 1);
+    }
+
+    #[test]
+    fn test_admin_role_order() {
+        assert!(AdminRole::Operator > AdminRole::ReadOnly);
+        assert_eq!(AdminRole::from_str("operator"), Some(AdminRole::Operator));
+        assert_eq!(
+            AdminRole::from_str(AdminRole::ReadOnly.as_str()),
+            Some(AdminRole::ReadOnly)
+        );
+        assert_eq!(AdminRole::from_str("root"), None);
+    }
+}
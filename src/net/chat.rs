@@ -64,6 +64,7 @@ use stacks_common::util::secp256k1::Secp256k1PrivateKey;
 use stacks_common::util::secp256k1::Secp256k1PublicKey;
 
 use crate::core::StacksEpoch;
+use crate::core::PEER_VERSION_SUBNET_ID;
 use crate::types::StacksPublicKeyBuffer;
 
 // did we or did we not successfully send a message?
@@ -133,11 +134,18 @@ pub struct NeighborStats {
     pub msgs_rx: u64,
     pub msgs_rx_unsolicited: u64,
     pub msgs_err: u64,
+    /// offenses (invalid preambles, or pushes beyond a bandwidth limit) not yet added to the
+    /// peer's misbehavior score in the peer DB
+    pub unrecorded_offenses: u64,
+    /// pushes beyond a bandwidth limit not yet added to the peer's reputation in the peer DB
+    pub unrecorded_bandwidth_violations: u64,
     pub healthpoints: VecDeque<NeighborHealthPoint>,
     pub msg_rx_counts: HashMap<StacksMessageID, u64>,
     pub block_push_rx_counts: VecDeque<(u64, u64)>, // (timestamp, num bytes)
     pub microblocks_push_rx_counts: VecDeque<(u64, u64)>, // (timestamp, num bytes)
     pub transaction_push_rx_counts: VecDeque<(u64, u64)>, // (timestamp, num bytes)
+    pub transaction_push_sig_checks: VecDeque<(u64, bool)>, // (timestamp, signature valid?)
+    pub block_request_latencies: VecDeque<(u64, u64)>, // (timestamp, latency in ms)
     pub relayed_messages: HashMap<NeighborAddress, RelayStats>,
 }
 
@@ -156,11 +164,15 @@ impl NeighborStats {
             msgs_rx: 0,
             msgs_rx_unsolicited: 0,
             msgs_err: 0,
+            unrecorded_offenses: 0,
+            unrecorded_bandwidth_violations: 0,
             healthpoints: VecDeque::new(),
             msg_rx_counts: HashMap::new(),
             block_push_rx_counts: VecDeque::new(),
             microblocks_push_rx_counts: VecDeque::new(),
             transaction_push_rx_counts: VecDeque::new(),
+            transaction_push_sig_checks: VecDeque::new(),
+            block_request_latencies: VecDeque::new(),
             relayed_messages: HashMap::new(),
         }
     }
@@ -176,6 +188,13 @@ impl NeighborStats {
         }
     }
 
+    /// Record that this peer pushed data beyond a bandwidth limit.  This is an offense, and it
+    /// hurts the peer's reputation.
+    pub fn add_bandwidth_violation(&mut self) -> () {
+        self.unrecorded_offenses += 1;
+        self.unrecorded_bandwidth_violations += 1;
+    }
+
     pub fn add_block_push(&mut self, message_size: u64) -> () {
         self.block_push_rx_counts
             .push_back((get_epoch_time_secs(), message_size));
@@ -200,6 +219,24 @@ impl NeighborStats {
         }
     }
 
+    /// Record whether or not a transaction this peer pushed to us had valid signatures
+    pub fn add_transaction_sig_check(&mut self, valid: bool) -> () {
+        self.transaction_push_sig_checks
+            .push_back((get_epoch_time_secs(), valid));
+        while self.transaction_push_sig_checks.len() > NUM_BLOCK_POINTS {
+            self.transaction_push_sig_checks.pop_front();
+        }
+    }
+
+    /// Record how long this peer took to answer one of our block or microblock requests
+    pub fn add_block_request_latency(&mut self, latency_ms: u64) -> () {
+        self.block_request_latencies
+            .push_back((get_epoch_time_secs(), latency_ms));
+        while self.block_request_latencies.len() > NUM_BLOCK_POINTS {
+            self.block_request_latencies.pop_front();
+        }
+    }
+
     pub fn add_relayer(&mut self, addr: &NeighborAddress, num_bytes: u64) -> () {
         if let Some(stats) = self.relayed_messages.get_mut(addr) {
             stats.num_messages += 1;
@@ -279,6 +316,55 @@ impl NeighborStats {
         NeighborStats::get_bandwidth(&self.transaction_push_rx_counts, BLOCK_POINT_LIFETIME)
     }
 
+    /// Get the fraction of recently-pushed transactions from this peer that had invalid
+    /// signatures.  Returns 0.0 if the peer hasn't pushed us any transactions recently.
+    pub fn get_invalid_transaction_rate(&self) -> f64 {
+        let now = get_epoch_time_secs();
+        let mut invalid = 0;
+        let mut total = 0;
+        for (time, valid) in self.transaction_push_sig_checks.iter() {
+            if now < time + BLOCK_POINT_LIFETIME {
+                if !valid {
+                    invalid += 1;
+                }
+                total += 1;
+            }
+        }
+        if total == 0 {
+            return 0.0;
+        }
+        (invalid as f64) / (total as f64)
+    }
+
+    /// Get the given percentile (0-100) of this peer's recent block request latencies, in
+    /// milliseconds.  Returns None if the peer hasn't answered any block requests recently.
+    pub fn get_block_request_latency(&self, percentile: u64) -> Option<u64> {
+        let now = get_epoch_time_secs();
+        let mut latencies: Vec<u64> = self
+            .block_request_latencies
+            .iter()
+            .filter(|(time, _)| now < time + BLOCK_POINT_LIFETIME)
+            .map(|(_, latency_ms)| *latency_ms)
+            .collect();
+        if latencies.len() == 0 {
+            return None;
+        }
+        latencies.sort();
+        let idx = ((latencies.len() - 1) as u64) * cmp::min(percentile, 100) / 100;
+        Some(latencies[idx as usize])
+    }
+
+    /// Get a score for how good a peer is to download blocks from.  Healthier, faster peers score
+    /// higher.  Peers that haven't served us any blocks recently are scored by health alone, so
+    /// that they get a chance to be measured.
+    pub fn get_block_download_score(&self) -> f64 {
+        let health = self.get_health_score();
+        match self.get_block_request_latency(50) {
+            Some(latency_ms) => health / (1.0 + (latency_ms as f64) / 1000.0),
+            None => health,
+        }
+    }
+
     /// Determine how many of a particular message this peer has received
     pub fn get_message_recv_count(&self, msg_id: StacksMessageID) -> u64 {
         *(self.msg_rx_counts.get(&msg_id).unwrap_or(&0))
@@ -803,12 +889,27 @@ impl ConversationP2P {
         private_key: &Secp256k1PrivateKey,
         payload: StacksMessageType,
     ) -> Result<StacksMessage, net_error> {
+        let payload = payload.for_peer_version(self.peer_version);
         let mut msg =
             StacksMessage::from_chain_view(self.version, self.network_id, chain_view, payload);
         msg.sign(self.next_seq(), private_key)?;
         Ok(msg)
     }
 
+    /// Is the peer that sent this handshake bound to our subnet?  Peers which predate subnet IDs
+    /// cannot say, and are given the benefit of the doubt; peers which know about them must send
+    /// theirs.
+    fn is_same_subnet(
+        local_peer: &LocalPeer,
+        preamble: &Preamble,
+        handshake_data: &HandshakeData,
+    ) -> bool {
+        match handshake_data.subnet_id {
+            Some(ref subnet_id) => *subnet_id == local_peer.subnet_id,
+            None => preamble.peer_version & PEER_VERSION_SUBNET_ID == 0,
+        }
+    }
+
     /// Generate a signed forwarded message for this conversation.
     /// Include ourselves as the latest relayer.
     pub fn sign_relay_message(
@@ -837,6 +938,7 @@ impl ConversationP2P {
         payload: StacksMessageType,
         seq: u32,
     ) -> Result<StacksMessage, net_error> {
+        let payload = payload.for_peer_version(self.peer_version);
         let mut msg =
             StacksMessage::from_chain_view(self.version, self.network_id, chain_view, payload);
         msg.sign(seq, private_key)?;
@@ -909,7 +1011,9 @@ impl ConversationP2P {
         let _name = msg.payload.get_message_description();
         let _seq = msg.request_id();
 
-        let mut handle = self.connection.make_relay_handle(self.conn_id)?;
+        let mut handle = self
+            .connection
+            .make_relay_handle_with_priority(self.conn_id, msg.payload.get_message_priority())?;
         msg.consensus_serialize(&mut handle)?;
 
         self.stats.msgs_tx += 1;
@@ -932,9 +1036,12 @@ impl ConversationP2P {
         let _name = msg.get_message_name();
         let _seq = msg.request_id();
 
-        let mut handle =
-            self.connection
-                .make_request_handle(msg.request_id(), ttl, self.conn_id)?;
+        let mut handle = self.connection.make_request_handle_with_priority(
+            msg.request_id(),
+            ttl,
+            self.conn_id,
+            msg.payload.get_message_priority(),
+        )?;
         msg.consensus_serialize(&mut handle)?;
 
         self.stats.msgs_tx += 1;
@@ -1014,6 +1121,15 @@ impl ConversationP2P {
             return Err(net_error::InvalidHandshake);
         }
 
+        if !ConversationP2P::is_same_subnet(local_peer, &message.preamble, &handshake_data) {
+            // bound to a different subnet contract
+            debug!(
+                "{:?}: invalid handshake -- peer is bound to subnet {:?}, but we are bound to {:?}",
+                &self, &handshake_data.subnet_id, &local_peer.subnet_id
+            );
+            return Err(net_error::InvalidHandshake);
+        }
+
         // the handshake cannot come from us
         if handshake_data.node_public_key
             == StacksPublicKeyBuffer::from_public_key(&Secp256k1PublicKey::from_private(
@@ -1132,6 +1248,23 @@ impl ConversationP2P {
             _ => panic!("Message is not a handshake"),
         };
 
+        // refuse peers whose key is banned, whatever address they connect from
+        let public_key_hash = Hash160::from_node_public_key_buffer(&handshake_data.node_public_key);
+        if let Some(ban) = PeerDB::find_peer_ban(
+            peerdb.conn(),
+            &self.peer_addrbytes,
+            Some(&public_key_hash),
+            get_epoch_time_secs(),
+        )
+        .map_err(net_error::DBError)?
+        {
+            info!(
+                "{:?}: refusing handshake from banned peer (ban {}: {})",
+                &self, ban.ban_id, &ban.reason
+            );
+            return Err(net_error::Denied);
+        }
+
         let old_pubkey_opt = self.connection.get_public_key();
         let updated = self.update_from_handshake_data(&message.preamble, &handshake_data)?;
         let _authentic_msg = if !updated {
@@ -1201,9 +1334,17 @@ impl ConversationP2P {
     /// Called from the p2p network thread.
     fn handle_handshake_accept(
         &mut self,
+        local_peer: &LocalPeer,
         preamble: &Preamble,
         handshake_accept: &HandshakeAcceptData,
     ) -> Result<(), net_error> {
+        if !ConversationP2P::is_same_subnet(local_peer, preamble, &handshake_accept.handshake) {
+            debug!(
+                "{:?}: invalid handshake accept -- peer is bound to subnet {:?}, but we are bound to {:?}",
+                &self, &handshake_accept.handshake.subnet_id, &local_peer.subnet_id
+            );
+            return Err(net_error::InvalidHandshake);
+        }
         self.update_from_handshake_data(preamble, &handshake_accept.handshake)?;
         self.peer_heartbeat =
             if handshake_accept.heartbeat_interval > (MAX_PEER_HEARTBEAT_INTERVAL as u32) {
@@ -1596,6 +1737,7 @@ impl ConversationP2P {
                 self.connection.options.max_block_push_bandwidth,
                 self.stats.get_block_push_bandwidth()
             );
+            self.stats.add_bandwidth_violation();
             return self
                 .reply_nack(local_peer, chain_view, preamble, NackErrorCodes::Throttled)
                 .and_then(|handle| Ok(Some(handle)));
@@ -1632,6 +1774,7 @@ impl ConversationP2P {
                 > (self.connection.options.max_microblocks_push_bandwidth as f64)
         {
             debug!("Neighbor {:?} exceeded max microblocks-push bandwidth of {} bytes/sec (currently at {})", &self.to_neighbor_key(), self.connection.options.max_microblocks_push_bandwidth, self.stats.get_microblocks_push_bandwidth());
+            self.stats.add_bandwidth_violation();
             return self
                 .reply_nack(local_peer, chain_view, preamble, NackErrorCodes::Throttled)
                 .and_then(|handle| Ok(Some(handle)));
@@ -1667,6 +1810,7 @@ impl ConversationP2P {
                 > (self.connection.options.max_transaction_push_bandwidth as f64)
         {
             debug!("Neighbor {:?} exceeded max transaction-push bandwidth of {} bytes/sec (currently at {})", &self.to_neighbor_key(), self.connection.options.max_transaction_push_bandwidth, self.stats.get_transaction_push_bandwidth());
+            self.stats.add_bandwidth_violation();
             return self
                 .reply_nack(local_peer, chain_view, preamble, NackErrorCodes::Throttled)
                 .and_then(|handle| Ok(Some(handle)));
@@ -1832,31 +1976,26 @@ impl ConversationP2P {
     }
 
     /// Make progress on in-flight messages.
+    /// Every pending reply is flushed into the connection on each call, so that a large reply
+    /// does not hold back the ones behind it.  The connection's outbox decides the order in
+    /// which they are written to the socket.
     pub fn try_flush(&mut self) -> Result<(), net_error> {
-        // send out responses in the order they were requested
-        let mut drained = false;
-        let mut broken = false;
-        match self.reply_handles.front_mut() {
-            Some(ref mut reply) => {
-                // try moving some data to the connection
-                match reply.try_flush() {
-                    Ok(res) => {
-                        drained = res;
-                    }
-                    Err(e) => {
-                        // dead
-                        warn!("Broken P2P connection: {:?}", &e);
-                        broken = true;
+        let mut pending = VecDeque::with_capacity(self.reply_handles.len());
+        while let Some(mut reply) = self.reply_handles.pop_front() {
+            // try moving some data to the connection
+            match reply.try_flush() {
+                Ok(drained) => {
+                    if !drained {
+                        pending.push_back(reply);
                     }
                 }
+                Err(e) => {
+                    // dead
+                    warn!("Broken P2P connection: {:?}", &e);
+                }
             }
-            None => {}
-        }
-
-        if broken || drained {
-            // done with this stream
-            self.reply_handles.pop_front();
         }
+        self.reply_handles = pending;
         Ok(())
     }
 
@@ -1885,6 +2024,7 @@ impl ConversationP2P {
                         &self
                     );
                     self.stats.msgs_err += 1;
+                    self.stats.unrecorded_offenses += 1;
                     self.stats.add_healthpoint(false);
                     return Err(e);
                 }
@@ -1925,7 +2065,7 @@ impl ConversationP2P {
             }
             StacksMessageType::HandshakeAccept(ref data) => {
                 test_debug!("{:?}: Got HandshakeAccept", &self);
-                self.handle_handshake_accept(&msg.preamble, data)
+                self.handle_handshake_accept(local_peer, &msg.preamble, data)
                     .and_then(|_| Ok(None))
             }
             StacksMessageType::Ping(_) => {
@@ -1997,7 +2137,7 @@ impl ConversationP2P {
             StacksMessageType::HandshakeAccept(ref data) => {
                 if solicited {
                     test_debug!("{:?}: Got unauthenticated HandshakeAccept", &self);
-                    self.handle_handshake_accept(&msg.preamble, data)
+                    self.handle_handshake_accept(local_peer, &msg.preamble, data)
                         .and_then(|_| Ok(None))
                 } else {
                     test_debug!("{:?}: Unsolicited unauthenticated HandshakeAccept", &self);
@@ -2288,6 +2428,7 @@ mod test {
     use crate::net::*;
     use crate::util_lib::test::*;
     use clarity::vm::costs::ExecutionCost;
+    use clarity::vm::types::QualifiedContractIdentifier;
     use stacks_common::util::pipe::*;
     use stacks_common::util::secp256k1::*;
     use stacks_common::util::uint::*;
@@ -2786,6 +2927,239 @@ mod test {
         assert!(convo_2.connection.get_public_key().is_none());
     }
 
+    #[test]
+    fn convo_handshake_reject_other_subnet() {
+        let conn_opts = ConnectionOptions::default();
+        let socketaddr_1 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+        let socketaddr_2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 8081);
+
+        let first_burn_hash = BurnchainHeaderHash::from_hex(
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        )
+        .unwrap();
+
+        let burnchain = testing_burnchain_config();
+
+        let mut chain_view = BurnchainView {
+            burn_block_height: 12348,
+            burn_block_hash: BurnchainHeaderHash([0x11; 32]),
+            burn_stable_block_height: 12341,
+            burn_stable_block_hash: BurnchainHeaderHash([0x22; 32]),
+            last_burn_block_hashes: HashMap::new(),
+        };
+        chain_view.make_test_data();
+
+        let (mut peerdb_1, mut sortdb_1, mut chainstate_1) = make_test_chain_dbs(
+            "convo_handshake_reject_other_subnet_1",
+            &burnchain,
+            0x9abcdef0,
+            12350,
+            "http://peer1.com".into(),
+            &vec![],
+            &vec![],
+        );
+        let (mut peerdb_2, mut sortdb_2, mut chainstate_2) = make_test_chain_dbs(
+            "convo_handshake_reject_other_subnet_2",
+            &burnchain,
+            0x9abcdef0,
+            12351,
+            "http://peer2.com".into(),
+            &vec![],
+            &vec![],
+        );
+
+        db_setup(&mut peerdb_1, &mut sortdb_1, &socketaddr_1, &chain_view);
+        db_setup(&mut peerdb_2, &mut sortdb_2, &socketaddr_2, &chain_view);
+
+        // the peers are bound to different subnet contracts
+        let mut local_peer_1 = PeerDB::get_local_peer(&peerdb_1.conn()).unwrap();
+        let mut local_peer_2 = PeerDB::get_local_peer(&peerdb_2.conn()).unwrap();
+        local_peer_1.subnet_id = SubnetId::from_contract_identifier(
+            &QualifiedContractIdentifier::parse("ST000000000000000000002AMW42H.subnet-1").unwrap(),
+        );
+        local_peer_2.subnet_id = SubnetId::from_contract_identifier(
+            &QualifiedContractIdentifier::parse("ST000000000000000000002AMW42H.subnet-2").unwrap(),
+        );
+
+        let mut convo_1 = ConversationP2P::new(
+            123,
+            456,
+            &burnchain,
+            &socketaddr_2,
+            &conn_opts,
+            true,
+            0,
+            StacksEpoch::unit_test_pre_2_05(0),
+        );
+        let mut convo_2 = ConversationP2P::new(
+            123,
+            456,
+            &burnchain,
+            &socketaddr_1,
+            &conn_opts,
+            true,
+            0,
+            StacksEpoch::unit_test_pre_2_05(0),
+        );
+
+        // no peer public keys known yet
+        assert!(convo_1.connection.get_public_key().is_none());
+        assert!(convo_2.connection.get_public_key().is_none());
+
+        // convo_1 sends a handshake for its subnet to convo_2
+        let handshake_data_1 = HandshakeData::from_local_peer(&local_peer_1);
+        let handshake_1 = convo_1
+            .sign_message(
+                &chain_view,
+                &local_peer_1.private_key,
+                StacksMessageType::Handshake(handshake_data_1.clone()),
+            )
+            .unwrap();
+
+        let mut rh_1 = convo_1.send_signed_request(handshake_1, 1000000).unwrap();
+
+        // convo_2 receives it and automatically rejects it.
+        convo_send_recv(&mut convo_1, vec![&mut rh_1], &mut convo_2);
+        let unhandled_2 = convo_2
+            .chat(
+                &local_peer_2,
+                &mut peerdb_2,
+                &sortdb_2,
+                &mut chainstate_2,
+                &mut BlockHeaderCache::new(),
+                &chain_view,
+            )
+            .unwrap();
+
+        // convo_1 has a handshakreject
+        convo_send_recv(&mut convo_2, vec![&mut rh_1], &mut convo_1);
+        let unhandled_1 = convo_1
+            .chat(
+                &local_peer_1,
+                &mut peerdb_1,
+                &sortdb_1,
+                &mut chainstate_1,
+                &mut BlockHeaderCache::new(),
+                &chain_view,
+            )
+            .unwrap();
+
+        let reply_1 = rh_1.recv(0).unwrap();
+
+        assert_eq!(unhandled_1.len(), 0);
+        assert_eq!(unhandled_2.len(), 0);
+
+        // received a valid HandshakeReject from peer 2
+        match reply_1.payload {
+            StacksMessageType::HandshakeReject => {}
+            _ => {
+                assert!(false);
+            }
+        };
+
+        // neither peer updated their info on one another
+        assert!(convo_1.connection.get_public_key().is_none());
+        assert!(convo_2.connection.get_public_key().is_none());
+    }
+
+    #[test]
+    fn convo_handshake_subnet_id_compat() {
+        let conn_opts = ConnectionOptions::default();
+        let socketaddr_1 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+        let socketaddr_2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 8081);
+
+        let burnchain = testing_burnchain_config();
+
+        let mut chain_view = BurnchainView {
+            burn_block_height: 12348,
+            burn_block_hash: BurnchainHeaderHash([0x11; 32]),
+            burn_stable_block_height: 12341,
+            burn_stable_block_hash: BurnchainHeaderHash([0x22; 32]),
+            last_burn_block_hashes: HashMap::new(),
+        };
+        chain_view.make_test_data();
+
+        let (mut peerdb_1, mut sortdb_1, _) = make_test_chain_dbs(
+            "convo_handshake_subnet_id_compat_1",
+            &burnchain,
+            0x9abcdef0,
+            12350,
+            "http://peer1.com".into(),
+            &vec![],
+            &vec![],
+        );
+        let (mut peerdb_2, mut sortdb_2, mut chainstate_2) = make_test_chain_dbs(
+            "convo_handshake_subnet_id_compat_2",
+            &burnchain,
+            0x9abcdef0,
+            12351,
+            "http://peer2.com".into(),
+            &vec![],
+            &vec![],
+        );
+
+        db_setup(&mut peerdb_1, &mut sortdb_1, &socketaddr_1, &chain_view);
+        db_setup(&mut peerdb_2, &mut sortdb_2, &socketaddr_2, &chain_view);
+
+        let local_peer_1 = PeerDB::get_local_peer(&peerdb_1.conn()).unwrap();
+        let mut local_peer_2 = PeerDB::get_local_peer(&peerdb_2.conn()).unwrap();
+        local_peer_2.subnet_id = SubnetId::from_contract_identifier(
+            &QualifiedContractIdentifier::parse("ST000000000000000000002AMW42H.subnet-2").unwrap(),
+        );
+
+        // (peer version, whether a handshake without a subnet ID is accepted)
+        let old_version = 456 & !PEER_VERSION_SUBNET_ID;
+        for (version_1, accepted) in [(old_version, true), (456, false)] {
+            let mut convo_1 = ConversationP2P::new(
+                123,
+                version_1,
+                &burnchain,
+                &socketaddr_2,
+                &conn_opts,
+                true,
+                0,
+                StacksEpoch::unit_test_pre_2_05(0),
+            );
+            let mut convo_2 = ConversationP2P::new(
+                123,
+                456,
+                &burnchain,
+                &socketaddr_1,
+                &conn_opts,
+                true,
+                0,
+                StacksEpoch::unit_test_pre_2_05(0),
+            );
+
+            // convo_1 sends a handshake in the format that predates subnet IDs
+            let mut handshake_data_1 = HandshakeData::from_local_peer(&local_peer_1);
+            handshake_data_1.subnet_id = None;
+            let handshake_1 = convo_1
+                .sign_message(
+                    &chain_view,
+                    &local_peer_1.private_key,
+                    StacksMessageType::Handshake(handshake_data_1),
+                )
+                .unwrap();
+            let mut rh_1 = convo_1.send_signed_request(handshake_1, 1000000).unwrap();
+
+            convo_send_recv(&mut convo_1, vec![&mut rh_1], &mut convo_2);
+            convo_2
+                .chat(
+                    &local_peer_2,
+                    &mut peerdb_2,
+                    &sortdb_2,
+                    &mut chainstate_2,
+                    &mut BlockHeaderCache::new(),
+                    &chain_view,
+                )
+                .unwrap();
+
+            // convo_2 only takes on convo_1's public key if it accepts the handshake
+            assert_eq!(convo_2.connection.get_public_key().is_some(), accepted);
+        }
+    }
+
     #[test]
     fn convo_handshake_badsignature() {
         let conn_opts = ConnectionOptions::default();
@@ -4376,6 +4750,62 @@ mod test {
             assert_eq!(stats.num_bytes, (msg.preamble.payload_len - 1) as u64);
         }
     }
+
+    #[test]
+    fn test_neighbor_stats_invalid_transaction_rate() {
+        let mut stats = NeighborStats::new(false);
+        assert_eq!(stats.get_invalid_transaction_rate(), 0.0);
+
+        stats.add_transaction_sig_check(true);
+        stats.add_transaction_sig_check(false);
+        stats.add_transaction_sig_check(true);
+        stats.add_transaction_sig_check(false);
+        assert_eq!(stats.get_invalid_transaction_rate(), 0.5);
+
+        // only the last NUM_BLOCK_POINTS checks count
+        for _ in 0..NUM_BLOCK_POINTS {
+            stats.add_transaction_sig_check(false);
+        }
+        assert_eq!(stats.transaction_push_sig_checks.len(), NUM_BLOCK_POINTS);
+        assert_eq!(stats.get_invalid_transaction_rate(), 1.0);
+
+        // stale checks don't count
+        for check in stats.transaction_push_sig_checks.iter_mut() {
+            check.0 = 0;
+        }
+        assert_eq!(stats.get_invalid_transaction_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_neighbor_stats_block_request_latency() {
+        let mut stats = NeighborStats::new(false);
+        assert_eq!(stats.get_block_request_latency(50), None);
+        assert_eq!(stats.get_block_download_score(), stats.get_health_score());
+
+        for latency_ms in 1..=10 {
+            stats.add_block_request_latency(latency_ms * 100);
+        }
+        assert_eq!(stats.get_block_request_latency(0), Some(100));
+        assert_eq!(stats.get_block_request_latency(50), Some(500));
+        assert_eq!(stats.get_block_request_latency(90), Some(900));
+        assert_eq!(stats.get_block_request_latency(100), Some(1000));
+        assert_eq!(stats.get_block_request_latency(200), Some(1000));
+
+        // slower peers score lower
+        let fast_score = stats.get_block_download_score();
+        for _ in 0..NUM_BLOCK_POINTS {
+            stats.add_block_request_latency(10_000);
+        }
+        assert_eq!(stats.block_request_latencies.len(), NUM_BLOCK_POINTS);
+        assert_eq!(stats.get_block_request_latency(0), Some(10_000));
+        assert!(stats.get_block_download_score() < fast_score);
+
+        // stale latencies don't count
+        for latency in stats.block_request_latencies.iter_mut() {
+            latency.0 = 0;
+        }
+        assert_eq!(stats.get_block_request_latency(50), None);
+    }
 }
 
 // TODO: test bandwidth limits
@@ -35,7 +35,9 @@ use crate::chainstate::stacks::StacksMicroblock;
 use crate::chainstate::stacks::StacksPublicKey;
 use crate::chainstate::stacks::StacksTransaction;
 use crate::chainstate::stacks::MAX_BLOCK_LEN;
+use crate::core::PEER_VERSION_SUBNET_ID;
 use crate::core::PEER_VERSION_TESTNET;
+use crate::net::connection::MessagePriority;
 use crate::net::db::LocalPeer;
 use crate::net::Error as net_error;
 use crate::net::*;
@@ -573,10 +575,14 @@ impl HandshakeData {
             ),
             expire_block_height: local_peer.private_key_expire,
             data_url: data_url,
+            subnet_id: Some(local_peer.subnet_id.clone()),
         }
     }
 }
 
+/// The subnet ID is not part of the handshake data's own encoding.  It is sent after it, in the
+/// SubnetHandshake and SubnetHandshakeAccept messages, so that peers which predate subnet IDs can
+/// still decode Handshake and HandshakeAccept.
 impl StacksMessageCodec for HandshakeData {
     fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
         write_next(fd, &self.addrbytes)?;
@@ -608,6 +614,7 @@ impl StacksMessageCodec for HandshakeData {
             node_public_key,
             expire_block_height,
             data_url,
+            subnet_id: None,
         })
     }
 }
@@ -764,10 +771,42 @@ impl StacksMessageCodec for RelayData {
 }
 
 impl StacksMessageType {
+    /// Adapt this message to a peer with the given version.  Peers which predate subnet IDs
+    /// cannot decode SubnetHandshake or SubnetHandshakeAccept, so handshakes sent to them leave
+    /// out our subnet ID.  A peer version of 0 means the peer's version is not yet known.
+    pub fn for_peer_version(self, peer_version: u32) -> StacksMessageType {
+        if peer_version == 0 || peer_version & PEER_VERSION_SUBNET_ID != 0 {
+            return self;
+        }
+        match self {
+            StacksMessageType::Handshake(mut data) => {
+                data.subnet_id = None;
+                StacksMessageType::Handshake(data)
+            }
+            StacksMessageType::HandshakeAccept(mut data) => {
+                data.handshake.subnet_id = None;
+                StacksMessageType::HandshakeAccept(data)
+            }
+            message => message,
+        }
+    }
+
     pub fn get_message_id(&self) -> StacksMessageID {
         match *self {
-            StacksMessageType::Handshake(ref _m) => StacksMessageID::Handshake,
-            StacksMessageType::HandshakeAccept(ref _m) => StacksMessageID::HandshakeAccept,
+            StacksMessageType::Handshake(ref m) => {
+                if m.subnet_id.is_some() {
+                    StacksMessageID::SubnetHandshake
+                } else {
+                    StacksMessageID::Handshake
+                }
+            }
+            StacksMessageType::HandshakeAccept(ref m) => {
+                if m.handshake.subnet_id.is_some() {
+                    StacksMessageID::SubnetHandshakeAccept
+                } else {
+                    StacksMessageID::HandshakeAccept
+                }
+            }
             StacksMessageType::HandshakeReject => StacksMessageID::HandshakeReject,
             StacksMessageType::GetNeighbors => StacksMessageID::GetNeighbors,
             StacksMessageType::Neighbors(ref _m) => StacksMessageID::Neighbors,
@@ -790,6 +829,32 @@ impl StacksMessageType {
         }
     }
 
+    /// Priority class used when queuing this message for sending.
+    /// Small control-plane messages are sent ahead of bulk data.
+    pub fn get_message_priority(&self) -> MessagePriority {
+        match *self {
+            StacksMessageType::Handshake(_)
+            | StacksMessageType::HandshakeAccept(_)
+            | StacksMessageType::HandshakeReject
+            | StacksMessageType::GetNeighbors
+            | StacksMessageType::Neighbors(_)
+            | StacksMessageType::Nack(_)
+            | StacksMessageType::Ping(_)
+            | StacksMessageType::Pong(_)
+            | StacksMessageType::NatPunchRequest(_)
+            | StacksMessageType::NatPunchReply(_) => MessagePriority::Control,
+            StacksMessageType::GetPoxInv(_)
+            | StacksMessageType::PoxInv(_)
+            | StacksMessageType::GetBlocksInv(_)
+            | StacksMessageType::BlocksInv(_)
+            | StacksMessageType::BlocksAvailable(_)
+            | StacksMessageType::MicroblocksAvailable(_)
+            | StacksMessageType::Blocks(_)
+            | StacksMessageType::Microblocks(_)
+            | StacksMessageType::Transaction(_) => MessagePriority::Bulk,
+        }
+    }
+
     pub fn get_message_name(&self) -> &'static str {
         match *self {
             StacksMessageType::Handshake(ref _m) => "Handshake",
@@ -902,6 +967,10 @@ impl StacksMessageCodec for StacksMessageID {
             x if x == StacksMessageID::Pong as u8 => StacksMessageID::Pong,
             x if x == StacksMessageID::NatPunchRequest as u8 => StacksMessageID::NatPunchRequest,
             x if x == StacksMessageID::NatPunchReply as u8 => StacksMessageID::NatPunchReply,
+            x if x == StacksMessageID::SubnetHandshake as u8 => StacksMessageID::SubnetHandshake,
+            x if x == StacksMessageID::SubnetHandshakeAccept as u8 => {
+                StacksMessageID::SubnetHandshakeAccept
+            }
             _ => {
                 return Err(codec_error::DeserializeError(
                     "Unknown message ID".to_string(),
@@ -916,8 +985,18 @@ impl StacksMessageCodec for StacksMessageType {
     fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
         write_next(fd, &(self.get_message_id() as u8))?;
         match *self {
-            StacksMessageType::Handshake(ref m) => write_next(fd, m)?,
-            StacksMessageType::HandshakeAccept(ref m) => write_next(fd, m)?,
+            StacksMessageType::Handshake(ref m) => {
+                write_next(fd, m)?;
+                if let Some(ref subnet_id) = m.subnet_id {
+                    write_next(fd, subnet_id)?;
+                }
+            }
+            StacksMessageType::HandshakeAccept(ref m) => {
+                write_next(fd, m)?;
+                if let Some(ref subnet_id) = m.handshake.subnet_id {
+                    write_next(fd, subnet_id)?;
+                }
+            }
             StacksMessageType::HandshakeReject => {}
             StacksMessageType::GetNeighbors => {}
             StacksMessageType::Neighbors(ref m) => write_next(fd, m)?,
@@ -950,6 +1029,18 @@ impl StacksMessageCodec for StacksMessageType {
                 let m: HandshakeAcceptData = read_next(fd)?;
                 StacksMessageType::HandshakeAccept(m)
             }
+            StacksMessageID::SubnetHandshake => {
+                let mut m: HandshakeData = read_next(fd)?;
+                let subnet_id: SubnetId = read_next(fd)?;
+                m.subnet_id = Some(subnet_id);
+                StacksMessageType::Handshake(m)
+            }
+            StacksMessageID::SubnetHandshakeAccept => {
+                let mut m: HandshakeAcceptData = read_next(fd)?;
+                let subnet_id: SubnetId = read_next(fd)?;
+                m.handshake.subnet_id = Some(subnet_id);
+                StacksMessageType::HandshakeAccept(m)
+            }
             StacksMessageID::HandshakeReject => StacksMessageType::HandshakeReject,
             StacksMessageID::GetNeighbors => StacksMessageType::GetNeighbors,
             StacksMessageID::Neighbors => {
@@ -1785,6 +1876,7 @@ pub mod test {
             .unwrap(),
             expire_block_height: 0x0102030405060708,
             data_url: UrlString::try_from("https://the-new-interwebs.com/data").unwrap(),
+            subnet_id: None,
         };
         let mut bytes = vec![
             // addrbytes
@@ -1823,6 +1915,7 @@ pub mod test {
                 .unwrap(),
                 expire_block_height: 0x0102030405060708,
                 data_url: UrlString::try_from("https://the-new-interwebs.com/data").unwrap(),
+                subnet_id: None,
             },
             heartbeat_interval: 0x01020304,
         };
@@ -1849,6 +1942,71 @@ pub mod test {
         check_codec_and_corruption::<HandshakeAcceptData>(&data, &bytes);
     }
 
+    #[test]
+    fn codec_SubnetHandshake() {
+        let mut data = HandshakeData {
+            addrbytes: PeerAddress([
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+                0x0e, 0x0f,
+            ]),
+            port: 12345,
+            services: 0x0001,
+            node_public_key: StacksPublicKeyBuffer::from_bytes(
+                &hex_bytes("034e316be04870cef1795fba64d581cf64bad0c894b01a068fb9edf85321dcd9bb")
+                    .unwrap(),
+            )
+            .unwrap(),
+            expire_block_height: 0x0102030405060708,
+            data_url: UrlString::try_from("https://the-new-interwebs.com/data").unwrap(),
+            subnet_id: None,
+        };
+        let handshake_bytes = data.serialize_to_vec();
+
+        // without a subnet ID, this is a plain Handshake that older peers can decode
+        let mut bytes = vec![StacksMessageID::Handshake as u8];
+        bytes.extend_from_slice(&handshake_bytes);
+        check_codec_and_corruption::<StacksMessageType>(
+            &StacksMessageType::Handshake(data.clone()),
+            &bytes,
+        );
+
+        // with one, it is a SubnetHandshake that carries the subnet ID after the handshake data
+        data.subnet_id = Some(SubnetId([0x11; 32]));
+        let mut bytes = vec![StacksMessageID::SubnetHandshake as u8];
+        bytes.extend_from_slice(&handshake_bytes);
+        bytes.extend_from_slice(&[0x11; 32]);
+        check_codec_and_corruption::<StacksMessageType>(
+            &StacksMessageType::Handshake(data.clone()),
+            &bytes,
+        );
+
+        let accept = HandshakeAcceptData {
+            handshake: data,
+            heartbeat_interval: 0x01020304,
+        };
+        let mut bytes = vec![StacksMessageID::SubnetHandshakeAccept as u8];
+        bytes.extend_from_slice(&handshake_bytes);
+        bytes.extend_from_slice(&[0x01, 0x02, 0x03, 0x04]);
+        bytes.extend_from_slice(&[0x11; 32]);
+        check_codec_and_corruption::<StacksMessageType>(
+            &StacksMessageType::HandshakeAccept(accept.clone()),
+            &bytes,
+        );
+
+        // peers which predate subnet IDs are sent handshakes without one
+        let old_peer_version = PEER_VERSION_TESTNET & !PEER_VERSION_SUBNET_ID;
+        match StacksMessageType::HandshakeAccept(accept.clone()).for_peer_version(old_peer_version)
+        {
+            StacksMessageType::HandshakeAccept(data) => assert_eq!(data.handshake.subnet_id, None),
+            _ => panic!("not a HandshakeAccept"),
+        }
+        assert_eq!(
+            StacksMessageType::HandshakeAccept(accept.clone())
+                .for_peer_version(PEER_VERSION_TESTNET),
+            StacksMessageType::HandshakeAccept(accept)
+        );
+    }
+
     #[test]
     fn codec_NackData() {
         let data = NackData {
@@ -1953,6 +2111,7 @@ pub mod test {
                 expire_block_height: 0x0102030405060708,
                 data_url: UrlString::try_from("https://the-new-interwebs.com:4008/the-data")
                     .unwrap(),
+                subnet_id: Some(SubnetId([0x11; 32])),
             }),
             StacksMessageType::HandshakeAccept(HandshakeAcceptData {
                 heartbeat_interval: 0x01020304,
@@ -1973,6 +2132,7 @@ pub mod test {
                     expire_block_height: 0x0102030405060708,
                     data_url: UrlString::try_from("https://the-new-interwebs.com:4008/the-data")
                         .unwrap(),
+                    subnet_id: Some(SubnetId([0x11; 32])),
                 },
             }),
             StacksMessageType::HandshakeReject,
@@ -38,11 +38,24 @@ use mio::net as mio_net;
 
 use crate::codec::StacksMessageCodec;
 use crate::codec::MAX_MESSAGE_LEN;
+use crate::core::mempool::ContractDeployLimits;
+use crate::core::mempool::MemPoolBloomConfig;
+use crate::core::mempool::MemPoolGCPolicy;
+use crate::core::mempool::MemPoolOriginQuota;
+use crate::core::mempool::MempoolAdmissionPolicyConfig;
+use crate::core::mempool::SponsorshipPolicy;
 use crate::core::mempool::MAX_BLOOM_COUNTER_TXS;
+use crate::core::BLOCK_LIMIT_MAINNET_205;
+use crate::net::admin::AdminCredential;
 use crate::net::codec::*;
+use crate::net::proposals::{BlockProposalLogHandle, BlockProposerSchedule};
+use crate::net::ratelimit::RPCRateLimits;
+use crate::net::AdminObserverInfo;
 use crate::net::Error as net_error;
 use crate::net::HttpRequestPreamble;
 use crate::net::HttpResponsePreamble;
+use crate::net::L1ContractStatus;
+use crate::net::L1SyncStatusHandle;
 use crate::net::MessageSequence;
 use crate::net::PeerAddress;
 use crate::net::Preamble;
@@ -50,6 +63,9 @@ use crate::net::ProtocolFamily;
 use crate::net::RelayData;
 use crate::net::StacksHttp;
 use crate::net::StacksP2P;
+use crate::net::SubnetId;
+use crate::types::chainstate::StacksAddress;
+use crate::util_lib::strings::UrlString;
 
 use crate::net::download::BLOCK_DOWNLOAD_INTERVAL;
 use crate::net::inv::{INV_REWARD_CYCLES, INV_SYNC_INTERVAL};
@@ -282,12 +298,24 @@ impl<P: ProtocolFamily> Write for NetworkReplyHandle<P> {
     }
 }
 
+/// Priority class of an outbound message.
+/// Queued control-plane messages (pings, pongs, handshakes, NACKs, etc.) are sent ahead of
+/// queued bulk data messages (inventories, blocks, transactions, etc.), so a large reply
+/// cannot hold up a small, time-sensitive one.  Messages of the same class are sent in the
+/// order they were queued, and a message that has begun to be sent is never preempted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MessagePriority {
+    Bulk = 0,
+    Control = 1,
+}
+
 /// In-flight message to a remote peer.
 /// When a reply is received, it may be forwarded along to an optional ReceiverNotify.
 #[derive(Debug)]
 struct InflightMessage<P: ProtocolFamily> {
     pipe_read: Option<PipeRead>,
     notify: Option<ReceiverNotify<P>>,
+    priority: MessagePriority,
 }
 
 #[derive(Debug)]
@@ -337,6 +365,10 @@ pub struct ConnectionOptions {
     pub max_clients_per_host: u64,
     pub soft_max_neighbors_per_host: u64,
     pub soft_max_neighbors_per_org: u64,
+    /// how many distinct AS-owning organizations our outbound neighbors should span.  Until they
+    /// do, neighbor walks prefer to start at peers in organizations we are not yet connected to
+    /// (0 means walks pick their starting peer uniformly at random)
+    pub min_outbound_orgs: u64,
     pub soft_max_clients_per_host: u64,
     pub max_neighbors_of_neighbor: u64,
     pub max_http_clients: u64,
@@ -356,9 +388,17 @@ pub struct ConnectionOptions {
     pub pingback_timeout: u64,
     pub dns_timeout: u128,
     pub max_inflight_blocks: u64,
+    /// which percentile of a peer's recent block request latencies to wait for before also asking
+    /// another peer for the same block (0 disables hedged block requests)
+    pub download_hedge_percentile: u64,
+    /// the shortest time to wait, in milliseconds, before hedging a block request
+    pub download_hedge_min_ms: u64,
     pub max_inflight_attachments: u64,
     pub max_attachment_retry_count: u64,
     pub read_only_call_limit: ExecutionCost,
+    /// the largest cost budget a caller may request for a read-only call.  This is larger than
+    /// `read_only_call_limit` by default, so that callers can opt in to heavier queries.
+    pub read_only_call_max_budget: ExecutionCost,
     pub maximum_call_argument_size: u32,
     pub max_block_push_bandwidth: u64,
     pub max_microblocks_push_bandwidth: u64,
@@ -382,6 +422,23 @@ pub struct ConnectionOptions {
     pub mempool_max_tx_query: u64,
     /// how long a mempool sync is allowed to take, in total, before timing out
     pub mempool_sync_timeout: u64,
+    /// data URLs of nodes to mempool sync with over HTTP, in addition to our outbound peers.
+    /// This lets a node that cannot reach the p2p network still receive transactions.
+    pub mempool_sync_http_urls: Vec<UrlString>,
+    /// parameters of the bloom counter that summarizes our recent transactions for mempool sync
+    pub mempool_bloom: MemPoolBloomConfig,
+    /// how many threads to use for checking the signatures of pushed transactions (0 means check
+    /// them on the p2p thread)
+    pub tx_verify_threads: usize,
+    /// how many outbound peers to export per-peer statistics for as labeled metrics.  The peers
+    /// we exchange the most bytes with are chosen (0 disables per-peer metrics)
+    pub max_peer_stats_metrics: u64,
+    /// how many offenses (invalid preambles, or pushes beyond a bandwidth limit) a peer address
+    /// may commit within `PEER_OFFENSE_WINDOW` before it is banned automatically (0 disables
+    /// automatic bans)
+    pub auto_ban_offense_threshold: u64,
+    /// how long automatic bans last, in seconds
+    pub auto_ban_duration: u64,
 
     // fault injection
     pub disable_neighbor_walk: bool,
@@ -404,6 +461,58 @@ pub struct ConnectionOptions {
     pub subnet_validator: Option<Secp256k1PrivateKey>,
     /// the contract used to submit multiparty commits (if a validator)
     pub subnet_signing_contract: Option<QualifiedContractIdentifier>,
+    /// which miners may propose the blocks this node signs (if a validator)
+    pub subnet_block_proposers: Option<BlockProposerSchedule>,
+    /// the block proposals this node has signed or made
+    pub block_proposal_log: BlockProposalLogHandle,
+    /// the address this node mines subnet blocks as, if it is a miner
+    pub subnet_miner_address: Option<StacksAddress>,
+    /// the key that signs this node's event observer payloads, if any
+    pub event_signing_public_key: Option<Secp256k1PublicKey>,
+    /// the event observers this node sends events to, as reported on GET /v2/admin/observers
+    pub event_observers: Vec<AdminObserverInfo>,
+    /// the outcome of the node's startup check of the L1 subnet contract's interface version
+    pub l1_contract_status: L1ContractStatus,
+    /// the interface version the L1 subnet contract reported, if it was checked
+    pub l1_contract_interface_version: Option<u64>,
+    /// whether this node has caught up with the L1 tip since it started
+    pub l1_sync_status: L1SyncStatusHandle,
+    /// number of blocks that must be built on a block before its withdrawals are final
+    pub withdrawal_confirmation_depth: u64,
+    /// the subnet this node is bound to.  Peers which handshake with a different subnet ID are
+    /// rejected, so that subnets sharing a network ID do not exchange transactions or blocks.
+    pub subnet_id: SubnetId,
+    /// which sponsorship offers for unsponsored mempool transactions this node accepts.  The node
+    /// hands this to its mempools, which enforce it.
+    pub sponsorship_policy: SponsorshipPolicy,
+    /// how many pending transactions each origin account may have in this node's mempool
+    pub mempool_origin_quota: MemPoolOriginQuota,
+    /// which checks transactions must pass to enter this node's mempool
+    pub mempool_admission_policy: MempoolAdmissionPolicyConfig,
+    /// how this node's mempool is garbage-collected
+    pub mempool_gc_policy: MemPoolGCPolicy,
+    /// limits on the size and complexity of the smart contracts this node admits and mines
+    pub contract_deploy_limits: ContractDeployLimits,
+    /// token that must be sent in the `Authorization` header of admin RPC requests.  It grants
+    /// the operator role.  Admin endpoints are disabled if neither this nor
+    /// `admin_credentials` is set.
+    pub auth_token: Option<String>,
+    /// further credentials accepted on admin RPC requests, each granting a role
+    pub admin_credentials: Vec<AdminCredential>,
+    /// directory to write divergence snapshots to.  If not set, snapshots are not captured
+    /// automatically, and manually-captured snapshots are only returned to the caller.
+    pub divergence_snapshot_dir: Option<String>,
+    /// L1 reorgs that orphan at least this many burnchain blocks trigger a divergence snapshot
+    pub divergence_reorg_threshold: u64,
+    /// how often each client IP address may call read-only functions, broadcast transactions,
+    /// validate block proposals, list data maps, fetch proofs, sync and list the mempool, and
+    /// fetch block state diffs over RPC.  Requests on the admin listener are not limited.
+    pub rpc_rate_limits: RPCRateLimits,
+    /// whether the entries of contracts' data maps can be listed over RPC
+    pub enable_map_iteration: bool,
+    /// whether block proposals can be submitted over RPC for validation.  Each validation
+    /// processes a whole block, so only nodes serving trusted block builders should enable it.
+    pub enable_block_proposal_validation: bool,
 }
 
 impl std::default::Default for ConnectionOptions {
@@ -425,6 +534,7 @@ impl std::default::Default for ConnectionOptions {
             max_clients_per_host: 10, // how many inbound connections we can have per IP address, full-stop
             soft_max_neighbors_per_host: 10, // how many outbound connections we can have per IP address, before we start pruning them
             soft_max_neighbors_per_org: 10, // how many outbound connections we can have per AS-owning organization, before we start pruning them
+            min_outbound_orgs: 0,
             soft_max_clients_per_host: 10, // how many inbound connections we can have per IP address, before we start pruning them,
             max_neighbors_of_neighbor: 10,
             max_http_clients: 10,
@@ -444,7 +554,9 @@ impl std::default::Default for ConnectionOptions {
             pingback_timeout: 60,
             dns_timeout: 15_000,            // DNS timeout, in millis
             max_inflight_blocks: 6,         // number of parallel block downloads
-            max_inflight_attachments: 6,    // number of parallel attachments downloads
+            download_hedge_percentile: 90, // hedge block requests that take longer than the peer's p90 latency
+            download_hedge_min_ms: 1_000,  // ...but wait at least a second before doing so
+            max_inflight_attachments: 6,   // number of parallel attachments downloads
             max_attachment_retry_count: 32, // how many attempt to get an attachment before giving up
             read_only_call_limit: ExecutionCost {
                 write_length: 0,
@@ -453,6 +565,14 @@ impl std::default::Default for ConnectionOptions {
                 read_count: 30,
                 runtime: 1_000_000_000,
             },
+            // callers may ask for up to a block's worth of reads and runtime
+            read_only_call_max_budget: ExecutionCost {
+                write_length: 0,
+                write_count: 0,
+                read_length: BLOCK_LIMIT_MAINNET_205.read_length,
+                read_count: BLOCK_LIMIT_MAINNET_205.read_count,
+                runtime: BLOCK_LIMIT_MAINNET_205.runtime,
+            },
             maximum_call_argument_size: 20 * BOUND_VALUE_SERIALIZATION_HEX,
             max_block_push_bandwidth: 0, // infinite upload bandwidth allowed
             max_microblocks_push_bandwidth: 0, // infinite upload bandwidth allowed
@@ -473,6 +593,12 @@ impl std::default::Default for ConnectionOptions {
             mempool_sync_interval: 30, // number of seconds in-between mempool sync
             mempool_max_tx_query: 128, // maximum number of transactions to visit per mempool query
             mempool_sync_timeout: 180, // how long a mempool sync can go for (3 minutes)
+            mempool_sync_http_urls: vec![], // only mempool sync with outbound peers
+            mempool_bloom: MemPoolBloomConfig::default(),
+            tx_verify_threads: 2, // number of threads checking pushed transactions' signatures
+            max_peer_stats_metrics: 16, // number of outbound peers whose statistics we export as metrics
+            auto_ban_offense_threshold: 10, // offenses within the offense window before a peer is banned
+            auto_ban_duration: 3600,        // automatic bans last an hour
 
             // no faults on by default
             disable_neighbor_walk: false,
@@ -492,6 +618,28 @@ impl std::default::Default for ConnectionOptions {
             force_disconnect_interval: None,
             subnet_validator: None,
             subnet_signing_contract: None,
+            subnet_block_proposers: None,
+            block_proposal_log: BlockProposalLogHandle::default(),
+            subnet_miner_address: None,
+            event_signing_public_key: None,
+            event_observers: vec![],
+            l1_contract_status: L1ContractStatus::Unchecked,
+            l1_contract_interface_version: None,
+            l1_sync_status: L1SyncStatusHandle::default(),
+            withdrawal_confirmation_depth: 0,
+            subnet_id: SubnetId([0u8; 32]),
+            sponsorship_policy: SponsorshipPolicy::default(),
+            mempool_origin_quota: MemPoolOriginQuota::default(),
+            mempool_admission_policy: MempoolAdmissionPolicyConfig::default(),
+            mempool_gc_policy: MemPoolGCPolicy::default(),
+            contract_deploy_limits: ContractDeployLimits::default(),
+            auth_token: None,
+            admin_credentials: vec![],
+            divergence_snapshot_dir: None,
+            divergence_reorg_threshold: 3,
+            rpc_rate_limits: RPCRateLimits::default(),
+            enable_map_iteration: false,
+            enable_block_proposal_validation: false,
         }
     }
 }
@@ -1027,6 +1175,7 @@ impl<P: ProtocolFamily> ConnectionOutbox<P> {
         &mut self,
         pipe_read: PipeRead,
         recv_notify: Option<ReceiverNotify<P>>,
+        priority: MessagePriority,
     ) -> Result<(), net_error> {
         if self.outbox.len() > self.outbox_maxlen {
             test_debug!(
@@ -1040,8 +1189,29 @@ impl<P: ProtocolFamily> ConnectionOutbox<P> {
         let inflight = InflightMessage {
             pipe_read: Some(pipe_read),
             notify: recv_notify,
+            priority,
+        };
+
+        // the head of the outbox may already be partially sent, in which case its pipe has
+        // been taken and it must be allowed to finish
+        let first_unsent = match self.outbox.front() {
+            Some(msg) if msg.pipe_read.is_none() => 1,
+            _ => 0,
         };
-        self.outbox.push_back(inflight);
+
+        // insert after every queued message of at least this priority
+        let insert_at = self
+            .outbox
+            .iter()
+            .enumerate()
+            .skip(first_unsent)
+            .find(|(_, msg)| msg.priority < priority)
+            .map(|(i, _)| i);
+
+        match insert_at {
+            Some(i) => self.outbox.insert(i, inflight),
+            None => self.outbox.push_back(inflight),
+        }
         Ok(())
     }
 
@@ -1313,6 +1483,23 @@ impl<P: ProtocolFamily + Clone> NetworkConnection<P> {
         request_id: u32,
         timeout: u64,
         socket_event_id: usize,
+    ) -> Result<NetworkReplyHandle<P>, net_error> {
+        self.make_request_handle_with_priority(
+            request_id,
+            timeout,
+            socket_event_id,
+            MessagePriority::Bulk,
+        )
+    }
+
+    /// Send a message with the given priority class and expect a reply.
+    /// See `make_request_handle()`.
+    pub fn make_request_handle_with_priority(
+        &mut self,
+        request_id: u32,
+        timeout: u64,
+        socket_event_id: usize,
+        priority: MessagePriority,
     ) -> Result<NetworkReplyHandle<P>, net_error> {
         let (send_ch, recv_ch) = sync_channel(1);
         let recv_notify = ReceiverNotify::new(request_id, send_ch, timeout + get_epoch_time_secs());
@@ -1321,7 +1508,8 @@ impl<P: ProtocolFamily + Clone> NetworkConnection<P> {
         let mut recv_handle = NetworkReplyHandle::new(recv_ch, pipe_write, socket_event_id);
         recv_handle.set_deadline(timeout + get_epoch_time_secs());
 
-        self.outbox.queue_message(pipe_read, Some(recv_notify))?;
+        self.outbox
+            .queue_message(pipe_read, Some(recv_notify), priority)?;
         Ok(recv_handle)
     }
 
@@ -1330,9 +1518,19 @@ impl<P: ProtocolFamily + Clone> NetworkConnection<P> {
     pub fn make_relay_handle(
         &mut self,
         socket_event_id: usize,
+    ) -> Result<NetworkReplyHandle<P>, net_error> {
+        self.make_relay_handle_with_priority(socket_event_id, MessagePriority::Bulk)
+    }
+
+    /// Forward a message with the given priority class and expect no reply.
+    /// See `make_relay_handle()`.
+    pub fn make_relay_handle_with_priority(
+        &mut self,
+        socket_event_id: usize,
+        priority: MessagePriority,
     ) -> Result<NetworkReplyHandle<P>, net_error> {
         let (pipe_read, pipe_write) = Pipe::new();
-        self.outbox.queue_message(pipe_read, None)?;
+        self.outbox.queue_message(pipe_read, None, priority)?;
 
         let send_handle = NetworkReplyHandle::new_relay(pipe_write, socket_event_id);
         Ok(send_handle)
@@ -1932,6 +2130,132 @@ mod test {
         assert_eq!(conn.outbox.outbox.len(), 0);
     }
 
+    #[test]
+    fn connection_relay_send_priority() {
+        let mut conn_opts = ConnectionOptions::default();
+        conn_opts.inbox_maxlen = 5;
+        conn_opts.outbox_maxlen = 5;
+
+        let mut conn = ConnectionP2P::new(StacksP2P::new(), &conn_opts, None);
+        let privkey = Secp256k1PrivateKey::new();
+
+        let make_msg = |payload: StacksMessageType, seq: u32| {
+            let mut msg = StacksMessage::new(
+                0x12345678,
+                0x9abcdef0,
+                12345,
+                &BurnchainHeaderHash([0x11; 32]),
+                12339,
+                &BurnchainHeaderHash([0x22; 32]),
+                payload,
+            );
+            msg.sign(seq, &privkey).unwrap();
+            msg
+        };
+
+        // a big bulk reply, queued ahead of two small control-plane replies
+        let blocks_inv = make_msg(
+            StacksMessageType::BlocksInv(BlocksInvData {
+                bitlen: 4096,
+                block_bitvec: vec![0xff; 512],
+                microblocks_bitvec: vec![0xff; 512],
+            }),
+            1,
+        );
+        let blocks_inv_2 = make_msg(
+            StacksMessageType::BlocksInv(BlocksInvData {
+                bitlen: 8,
+                block_bitvec: vec![0x01],
+                microblocks_bitvec: vec![0x01],
+            }),
+            2,
+        );
+        let pong_1 = make_msg(StacksMessageType::Pong(PongData { nonce: 1 }), 3);
+        let pong_2 = make_msg(StacksMessageType::Pong(PongData { nonce: 2 }), 4);
+
+        let mut pipes = vec![];
+        for (msg, priority) in [
+            (&blocks_inv, MessagePriority::Bulk),
+            (&blocks_inv_2, MessagePriority::Bulk),
+            (&pong_1, MessagePriority::Control),
+            (&pong_2, MessagePriority::Control),
+        ]
+        .iter()
+        {
+            let mut pipe = conn.make_relay_handle_with_priority(0, *priority).unwrap();
+            msg.consensus_serialize(&mut pipe).unwrap();
+            pipes.push(pipe);
+        }
+
+        // control-plane messages jump ahead of the unsent bulk messages, in FIFO order
+        let priorities: Vec<_> = conn.outbox.outbox.iter().map(|m| m.priority).collect();
+        assert_eq!(
+            priorities,
+            vec![
+                MessagePriority::Control,
+                MessagePriority::Control,
+                MessagePriority::Bulk,
+                MessagePriority::Bulk
+            ]
+        );
+
+        let serialized: Vec<Vec<u8>> = [&pong_1, &pong_2, &blocks_inv, &blocks_inv_2]
+            .iter()
+            .map(|msg| {
+                let mut bytes = vec![];
+                msg.consensus_serialize(&mut bytes).unwrap();
+                bytes
+            })
+            .collect();
+        let total_len: usize = serialized.iter().map(|bytes| bytes.len()).sum();
+
+        // begin sending the first pong and part of the second
+        let partial_len = serialized[0].len() + serialized[1].len() / 2;
+        let mut partial_buf = vec![0u8; partial_len];
+        let mut partial_fd = NetCursor::new(partial_buf.as_mut_slice());
+        let mut nw = 0;
+        while nw < partial_len {
+            for pipe in pipes.iter_mut() {
+                let _ = pipe.try_flush();
+            }
+            nw += conn.send_data(&mut partial_fd).unwrap();
+        }
+
+        // a new control-plane message must not preempt the partially-sent pong
+        let ping = make_msg(StacksMessageType::Ping(PingData { nonce: 3 }), 5);
+        let mut pipe = conn
+            .make_relay_handle_with_priority(0, MessagePriority::Control)
+            .unwrap();
+        ping.consensus_serialize(&mut pipe).unwrap();
+        pipes.push(pipe);
+
+        let mut ping_bytes = vec![];
+        ping.consensus_serialize(&mut ping_bytes).unwrap();
+
+        let rest_len = total_len + ping_bytes.len() - partial_len;
+        let mut rest_buf = vec![0u8; rest_len];
+        let mut rest_fd = NetCursor::new(rest_buf.as_mut_slice());
+        nw = 0;
+        while nw < rest_len {
+            for pipe in pipes.iter_mut() {
+                let _ = pipe.try_flush();
+            }
+            nw += conn.send_data(&mut rest_fd).unwrap();
+        }
+        assert_eq!(conn.outbox.outbox.len(), 0);
+
+        let mut expected = vec![];
+        expected.extend_from_slice(&serialized[0]);
+        expected.extend_from_slice(&serialized[1]);
+        expected.extend_from_slice(&ping_bytes);
+        expected.extend_from_slice(&serialized[2]);
+        expected.extend_from_slice(&serialized[3]);
+
+        let mut sent = partial_buf.clone();
+        sent.extend_from_slice(&rest_buf);
+        assert_eq!(sent, expected);
+    }
+
     #[test]
     fn connection_relay_send_recv() {
         let privkey = Secp256k1PrivateKey::new();
@@ -15,6 +15,7 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::fmt;
+use std::net::IpAddr;
 
 use rusqlite::types::ToSql;
 use rusqlite::Row;
@@ -28,6 +29,7 @@ use std::fs;
 use crate::util_lib::db::sqlite_open;
 use crate::util_lib::db::tx_begin_immediate;
 use crate::util_lib::db::DBConn;
+use crate::util_lib::db::DBTx;
 use crate::util_lib::db::Error as db_error;
 use crate::util_lib::db::{query_count, query_row, query_rows, u64_to_sql, FromColumn, FromRow};
 
@@ -56,15 +58,17 @@ use crate::net::NeighborAddress;
 use crate::net::NeighborKey;
 use crate::net::PeerAddress;
 use crate::net::ServiceFlags;
+use crate::net::SubnetId;
 
 use crate::burnchains::PrivateKey;
 use crate::burnchains::PublicKey;
 
 use crate::core::NETWORK_P2P_PORT;
 
+use crate::util_lib::migrations::{SchemaMigration, SchemaMigrations, SchemaStatus};
 use crate::util_lib::strings::UrlString;
 
-pub const PEERDB_VERSION: &'static str = "1";
+pub const PEERDB_VERSION: &'static str = "3";
 
 const NUM_SLOTS: usize = 8;
 
@@ -113,6 +117,7 @@ pub struct LocalPeer {
 
     // filled in and curated at runtime
     pub public_ip_address: Option<(PeerAddress, u16)>,
+    pub subnet_id: SubnetId,
 }
 
 impl fmt::Display for LocalPeer {
@@ -182,6 +187,7 @@ impl LocalPeer {
             services: services as u16,
             data_url: data_url,
             public_ip_address: None,
+            subnet_id: SubnetId([0u8; 32]),
         }
     }
 
@@ -234,6 +240,7 @@ impl FromRow<LocalPeer> for LocalPeer {
             services: services,
             data_url: data_url,
             public_ip_address: None,
+            subnet_id: SubnetId([0u8; 32]),
         })
     }
 }
@@ -360,9 +367,255 @@ const PEERDB_INITIAL_SCHEMA: &'static [&'static str] = &[
     );"#,
 ];
 
+const PEERDB_SCHEMA_2_BANS: &'static [&'static str] = &[
+    r#"
+    -- operator and automatic bans.  Each ban applies either to the peers that authenticate with
+    -- a public key, or to the peers whose addresses are in a CIDR range.
+    CREATE TABLE peer_bans(
+        ban_id INTEGER PRIMARY KEY AUTOINCREMENT,
+        public_key_hash TEXT,
+        prefix TEXT,
+        mask INTEGER,
+        reason TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        expires_at INTEGER          -- NULL if the ban never expires
+    );"#,
+    r#"
+    -- misbehavior scores of peer addresses, which outlive their connections
+    CREATE TABLE peer_offenses(
+        addrbytes TEXT NOT NULL,
+        score INTEGER NOT NULL,
+        last_offense_time INTEGER NOT NULL,
+
+        PRIMARY KEY(addrbytes)
+    );"#,
+];
+
+const PEERDB_SCHEMA_3_REPUTATION: &'static [&'static str] = &[r#"
+    -- how reliable outbound peers have been, which outlives their connections and biases
+    -- neighbor selection.  Both scores decay toward those of an unknown peer.
+    CREATE TABLE peer_reputation(
+        network_id INTEGER NOT NULL,
+        addrbytes TEXT NOT NULL,
+        port INTEGER NOT NULL,
+        health_score REAL NOT NULL,
+        bandwidth_violations REAL NOT NULL,
+        last_update_time INTEGER NOT NULL,
+
+        PRIMARY KEY(network_id,addrbytes,port)
+    );"#];
+
+/// Forward migrations for the peer DB schema.  The schema version is stamped into `db_config`.
+pub const PEERDB_MIGRATIONS: SchemaMigrations = SchemaMigrations {
+    db_name: "peer",
+    initial_version: 1,
+    migrations: &[
+        SchemaMigration {
+            to_version: 2,
+            description: "add peer ban and offense tables",
+            apply: PeerDB::instantiate_bans,
+        },
+        SchemaMigration {
+            to_version: 3,
+            description: "add peer reputation table",
+            apply: PeerDB::instantiate_reputation,
+        },
+    ],
+    load_version: load_peerdb_schema_version,
+    store_version: store_peerdb_schema_version,
+};
+
+fn load_peerdb_schema_version(conn: &DBConn) -> Result<u32, db_error> {
+    let version: String =
+        conn.query_row("SELECT version FROM db_config LIMIT 1", NO_PARAMS, |row| {
+            row.get(0)
+        })?;
+    version
+        .parse::<u32>()
+        .map_err(|_| db_error::UnsupportedSchema(format!("peer schema version '{}'", &version)))
+}
+
+fn store_peerdb_schema_version(tx: &DBTx, version: u32) -> Result<(), db_error> {
+    tx.execute("UPDATE db_config SET version = ?1", &[&version.to_string()])?;
+    Ok(())
+}
+
 const PEERDB_INDEXES: &'static [&'static str] =
     &["CREATE INDEX IF NOT EXISTS peer_address_index ON frontier(network_id,addrbytes,port);"];
 
+/// How long a peer address's offense score lasts after its last offense, in seconds
+pub const PEER_OFFENSE_WINDOW: u64 = 3600;
+
+/// How long it takes for a peer's recorded reputation to decay halfway to that of an unknown
+/// peer, in seconds
+pub const PEER_REPUTATION_HALF_LIFE: u64 = 24 * 3600;
+
+/// How long a peer's reputation is kept after it was last updated, in seconds.  By then it has
+/// decayed to almost nothing.
+pub const PEER_REPUTATION_LIFETIME: u64 = 10 * PEER_REPUTATION_HALF_LIFE;
+
+/// How often to save the reputations of the peers we are talking to, in seconds.  Reputations
+/// are also saved whenever a conversation ends.
+pub const PEER_REPUTATION_RECORD_INTERVAL: u64 = 600;
+
+/// The health score of a peer we know nothing about
+pub const PEER_REPUTATION_NEUTRAL_HEALTH: f64 = 0.5;
+
+/// The smallest neighbor selection weight, so that peers with bad reputations are still chosen
+/// now and then, and get a chance to redeem themselves
+pub const PEER_REPUTATION_MIN_WEIGHT: f64 = 0.01;
+
+/// How many candidates to draw per neighbor when choosing neighbors by reputation
+const PEER_REPUTATION_CANDIDATES_PER_NEIGHBOR: u32 = 4;
+
+/// How reliable a peer has been in past conversations
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerReputation {
+    /// The decayed average of the peer's health scores, between 0 and 1
+    pub health_score: f64,
+    /// The decayed number of times the peer pushed data beyond a bandwidth limit
+    pub bandwidth_violations: f64,
+    pub last_update_time: u64,
+}
+
+impl PeerReputation {
+    /// The reputation of a peer we know nothing about
+    pub fn neutral(now: u64) -> PeerReputation {
+        PeerReputation {
+            health_score: PEER_REPUTATION_NEUTRAL_HEALTH,
+            bandwidth_violations: 0.0,
+            last_update_time: now,
+        }
+    }
+
+    /// This reputation as of `now`, having decayed toward a neutral one since it was last
+    /// updated
+    pub fn decayed(&self, now: u64) -> PeerReputation {
+        let elapsed = now.saturating_sub(self.last_update_time) as f64;
+        let retained = 0.5f64.powf(elapsed / (PEER_REPUTATION_HALF_LIFE as f64));
+        PeerReputation {
+            health_score: PEER_REPUTATION_NEUTRAL_HEALTH
+                + (self.health_score - PEER_REPUTATION_NEUTRAL_HEALTH) * retained,
+            bandwidth_violations: self.bandwidth_violations * retained,
+            last_update_time: now.max(self.last_update_time),
+        }
+    }
+
+    /// How strongly to prefer this peer when choosing neighbors.  Each bandwidth violation
+    /// divides the peer's health score further.
+    pub fn selection_weight(&self) -> f64 {
+        (self.health_score / (1.0 + self.bandwidth_violations)).max(PEER_REPUTATION_MIN_WEIGHT)
+    }
+}
+
+impl FromRow<PeerReputation> for PeerReputation {
+    fn from_row<'a>(row: &'a Row) -> Result<PeerReputation, db_error> {
+        let health_score: f64 = row.get_unwrap("health_score");
+        let bandwidth_violations: f64 = row.get_unwrap("bandwidth_violations");
+        let last_update_time = u64::from_column(row, "last_update_time")?;
+        Ok(PeerReputation {
+            health_score,
+            bandwidth_violations,
+            last_update_time,
+        })
+    }
+}
+
+/// The peers a ban applies to
+#[derive(Debug, Clone, PartialEq)]
+pub enum PeerBanTarget {
+    /// Peers that authenticate with a public key of this hash
+    PublicKeyHash(Hash160),
+    /// Peers whose addresses are in this CIDR range.  The mask applies to the 128-bit address, so
+    /// IPv4 ranges have masks above 96.
+    AddressRange(PeerAddress, u32),
+}
+
+impl PeerBanTarget {
+    /// Parse an address range written as `ip/prefix-length`.  The prefix length of an IPv4 range
+    /// counts bits of the IPv4 address.
+    pub fn from_address_range(range: &str) -> Option<PeerBanTarget> {
+        let (ip, prefix_len) = range.split_once('/')?;
+        let ip = ip.parse::<IpAddr>().ok()?;
+        let prefix_len = prefix_len.parse::<u32>().ok()?;
+        let mask = match ip {
+            IpAddr::V4(..) if prefix_len <= 32 => prefix_len + 96,
+            IpAddr::V6(..) if prefix_len <= 128 => prefix_len,
+            _ => return None,
+        };
+        if mask == 0 {
+            return None;
+        }
+        Some(PeerBanTarget::AddressRange(PeerAddress::from_ip(&ip), mask))
+    }
+
+    /// Write an address range as `ip/prefix-length`.  Returns None for public key bans.
+    pub fn to_address_range(&self) -> Option<String> {
+        match self {
+            PeerBanTarget::PublicKeyHash(..) => None,
+            PeerBanTarget::AddressRange(ref prefix, mask) => {
+                let ip = prefix.to_socketaddr(0).ip();
+                if prefix.is_ipv4() && *mask >= 96 {
+                    Some(format!("{}/{}", ip, mask - 96))
+                } else {
+                    Some(format!("{}/{}", ip, mask))
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerBan {
+    pub ban_id: u64,
+    pub target: PeerBanTarget,
+    pub reason: String,
+    pub created_at: u64,
+    /// None if the ban never expires
+    pub expires_at: Option<u64>,
+}
+
+impl PeerBan {
+    /// Does this ban apply to a peer at `addr`, which authenticated with `public_key_hash` (if
+    /// known)?
+    pub fn applies_to(&self, addr: &PeerAddress, public_key_hash: Option<&Hash160>) -> bool {
+        match self.target {
+            PeerBanTarget::PublicKeyHash(ref banned) => public_key_hash == Some(banned),
+            PeerBanTarget::AddressRange(ref prefix, mask) => {
+                PeerDB::is_address_in_cidr(addr, prefix, mask)
+            }
+        }
+    }
+}
+
+impl FromRow<PeerBan> for PeerBan {
+    fn from_row<'a>(row: &'a Row) -> Result<PeerBan, db_error> {
+        let ban_id = u64::from_column(row, "ban_id")?;
+        let public_key_hash: Option<String> = row.get_unwrap("public_key_hash");
+        let target = match public_key_hash {
+            Some(public_key_hash) => PeerBanTarget::PublicKeyHash(
+                Hash160::from_hex(&public_key_hash).map_err(|_| db_error::ParseError)?,
+            ),
+            None => {
+                let prefix = PeerAddress::from_column(row, "prefix")?;
+                let mask: u32 = row.get_unwrap("mask");
+                PeerBanTarget::AddressRange(prefix, mask)
+            }
+        };
+        let reason: String = row.get_unwrap("reason");
+        let created_at = u64::from_column(row, "created_at")?;
+        let expires_at: Option<i64> = row.get_unwrap("expires_at");
+
+        Ok(PeerBan {
+            ban_id,
+            target,
+            reason,
+            created_at,
+            expires_at: expires_at.map(|expires_at| expires_at as u64),
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct PeerDB {
     pub conn: Connection,
@@ -400,9 +653,10 @@ impl PeerDB {
 
         tx.execute(
             "INSERT INTO db_config (version) VALUES (?1)",
-            &[&PEERDB_VERSION],
+            &[&PEERDB_MIGRATIONS.initial_version.to_string()],
         )
         .map_err(db_error::SqliteError)?;
+        PEERDB_MIGRATIONS.migrate(&tx)?;
 
         let local_peer_args: &[&dyn ToSql] = &[
             &network_id,
@@ -447,6 +701,20 @@ impl PeerDB {
         Ok(())
     }
 
+    fn instantiate_bans(tx: &DBTx) -> Result<(), db_error> {
+        for row_text in PEERDB_SCHEMA_2_BANS {
+            tx.execute_batch(row_text).map_err(db_error::SqliteError)?;
+        }
+        Ok(())
+    }
+
+    fn instantiate_reputation(tx: &DBTx) -> Result<(), db_error> {
+        for row_text in PEERDB_SCHEMA_3_REPUTATION {
+            tx.execute_batch(row_text).map_err(db_error::SqliteError)?;
+        }
+        Ok(())
+    }
+
     fn add_indexes(&mut self) -> Result<(), db_error> {
         let tx = self.tx_begin()?;
         for row_text in PEERDB_INDEXES {
@@ -580,6 +848,15 @@ impl PeerDB {
                 }
             }
         } else {
+            if readwrite {
+                let tx = db.tx_begin()?;
+                PEERDB_MIGRATIONS.migrate(&tx)?;
+                tx.commit()?;
+            } else {
+                // make sure we can read this schema
+                PEERDB_MIGRATIONS.check(&db.conn)?;
+            }
+
             db.update_local_peer(network_id, parent_network_id, data_url, p2p_port)?;
 
             {
@@ -608,6 +885,15 @@ impl PeerDB {
         Ok(db)
     }
 
+    /// Check the schema of the peer DB at the given path without migrating it.
+    pub fn check_schema_migrations(path: &str) -> Result<SchemaStatus, db_error> {
+        if fs::metadata(path).is_err() {
+            return Err(db_error::NoDBError);
+        }
+        let conn = sqlite_open(path, OpenFlags::SQLITE_OPEN_READ_ONLY, false)?;
+        PEERDB_MIGRATIONS.check(&conn)
+    }
+
     /// Open a burn database in memory (used for testing)
     #[cfg(test)]
     pub fn connect_memory(
@@ -1179,15 +1465,19 @@ impl PeerDB {
         PeerDB::get_cidr_prefixes(conn, "allowed_prefixes")
     }
 
+    /// Is an address in the CIDR range `prefix/mask`?
+    pub fn is_address_in_cidr(addr: &PeerAddress, prefix: &PeerAddress, mask: u32) -> bool {
+        let addr_int = u128::from_be_bytes(addr.as_bytes().to_owned());
+        let addr_mask = !((1u128 << (128 - mask)) - 1);
+        let mask_int = u128::from_be_bytes(prefix.as_bytes().to_owned()) & addr_mask;
+        mask_int == (addr_int & addr_mask)
+    }
+
     /// Check to see if an address is denied by one of the CIDR deny rows
     pub fn is_address_denied(conn: &DBConn, addr: &PeerAddress) -> Result<bool, db_error> {
         let denied_rows = PeerDB::get_denied_cidrs(conn)?;
-        let addr_int = u128::from_be_bytes(addr.as_bytes().to_owned());
-
         for (prefix, mask) in denied_rows.into_iter() {
-            let addr_mask = !((1u128 << (128 - mask)) - 1);
-            let mask_int = u128::from_be_bytes(prefix.as_bytes().to_owned()) & addr_mask;
-            if mask_int == (addr_int & addr_mask) {
+            if PeerDB::is_address_in_cidr(addr, &prefix, mask) {
                 return Ok(true);
             }
         }
@@ -1256,6 +1546,229 @@ impl PeerDB {
         Ok(())
     }
 
+    /// Ban the peers matching `target` from `now` until `expires_at`, or forever if `expires_at`
+    /// is None.  Returns the new ban's ID.
+    pub fn add_peer_ban<'a>(
+        tx: &mut Transaction<'a>,
+        target: &PeerBanTarget,
+        reason: &str,
+        now: u64,
+        expires_at: Option<u64>,
+    ) -> Result<u64, db_error> {
+        let (public_key_hash, prefix, mask) = match target {
+            PeerBanTarget::PublicKeyHash(public_key_hash) => {
+                (Some(public_key_hash.to_hex()), None, None)
+            }
+            PeerBanTarget::AddressRange(prefix, mask) => {
+                assert!(*mask > 0 && *mask <= 128);
+                (None, Some(prefix.to_bin()), Some(*mask))
+            }
+        };
+        let expires_at = match expires_at {
+            Some(expires_at) => Some(u64_to_sql(expires_at)?),
+            None => None,
+        };
+        let args: &[&dyn ToSql] = &[
+            &public_key_hash,
+            &prefix,
+            &mask,
+            &reason,
+            &u64_to_sql(now)?,
+            &expires_at,
+        ];
+        tx.execute(
+            "INSERT INTO peer_bans (public_key_hash, prefix, mask, reason, created_at, expires_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            args,
+        )
+        .map_err(db_error::SqliteError)?;
+        Ok(tx.last_insert_rowid() as u64)
+    }
+
+    /// Lift a ban.  Returns the lifted ban, or None if there is no such ban.
+    pub fn remove_peer_ban<'a>(
+        tx: &mut Transaction<'a>,
+        ban_id: u64,
+    ) -> Result<Option<PeerBan>, db_error> {
+        let ban_opt = query_row::<PeerBan, _>(
+            tx,
+            "SELECT * FROM peer_bans WHERE ban_id = ?1",
+            &[&u64_to_sql(ban_id)?],
+        )?;
+        if ban_opt.is_some() {
+            tx.execute(
+                "DELETE FROM peer_bans WHERE ban_id = ?1",
+                &[&u64_to_sql(ban_id)?],
+            )
+            .map_err(db_error::SqliteError)?;
+        }
+        Ok(ban_opt)
+    }
+
+    /// Get the bans that are in effect at `now`
+    pub fn get_peer_bans(conn: &DBConn, now: u64) -> Result<Vec<PeerBan>, db_error> {
+        let qry = "SELECT * FROM peer_bans WHERE expires_at IS NULL OR expires_at > ?1 ORDER BY ban_id ASC";
+        query_rows::<PeerBan, _>(conn, qry, &[&u64_to_sql(now)?])
+    }
+
+    /// Find a ban in effect at `now` that applies to a peer at `addr`, which authenticated with
+    /// `public_key_hash` (if known)
+    pub fn find_peer_ban(
+        conn: &DBConn,
+        addr: &PeerAddress,
+        public_key_hash: Option<&Hash160>,
+        now: u64,
+    ) -> Result<Option<PeerBan>, db_error> {
+        Ok(PeerDB::get_peer_bans(conn, now)?
+            .into_iter()
+            .find(|ban| ban.applies_to(addr, public_key_hash)))
+    }
+
+    /// Add `offenses` to the misbehavior score of a peer address.  The score starts over if the
+    /// address's last offense was more than `PEER_OFFENSE_WINDOW` seconds ago.  Returns the new
+    /// score.
+    pub fn record_peer_offenses<'a>(
+        tx: &mut Transaction<'a>,
+        addr: &PeerAddress,
+        offenses: u64,
+        now: u64,
+    ) -> Result<u64, db_error> {
+        let addrbytes = addr.to_bin();
+        let args: &[&dyn ToSql] = &[
+            &addrbytes,
+            &u64_to_sql(now.saturating_sub(PEER_OFFENSE_WINDOW))?,
+        ];
+        tx.execute(
+            "DELETE FROM peer_offenses WHERE addrbytes = ?1 AND last_offense_time < ?2",
+            args,
+        )
+        .map_err(db_error::SqliteError)?;
+
+        let args: &[&dyn ToSql] = &[&addrbytes, &u64_to_sql(offenses)?, &u64_to_sql(now)?];
+        tx.execute(
+            "INSERT OR IGNORE INTO peer_offenses (addrbytes, score, last_offense_time) VALUES (?1, 0, ?3)",
+            args,
+        )
+        .map_err(db_error::SqliteError)?;
+        tx.execute(
+            "UPDATE peer_offenses SET score = score + ?2, last_offense_time = ?3 WHERE addrbytes = ?1",
+            args,
+        )
+        .map_err(db_error::SqliteError)?;
+
+        let score = query_row::<u64, _>(
+            tx,
+            "SELECT score FROM peer_offenses WHERE addrbytes = ?1",
+            &[&addrbytes],
+        )?;
+        Ok(score.unwrap_or(0))
+    }
+
+    /// Forget the misbehavior score of a peer address
+    pub fn clear_peer_offenses<'a>(
+        tx: &mut Transaction<'a>,
+        addr: &PeerAddress,
+    ) -> Result<(), db_error> {
+        tx.execute(
+            "DELETE FROM peer_offenses WHERE addrbytes = ?1",
+            &[&addr.to_bin()],
+        )
+        .map_err(db_error::SqliteError)?;
+        Ok(())
+    }
+
+    /// Get a peer's reputation as of `now`.  Returns None if it has none.
+    pub fn get_peer_reputation(
+        conn: &DBConn,
+        network_id: u32,
+        peer_addr: &PeerAddress,
+        peer_port: u16,
+        now: u64,
+    ) -> Result<Option<PeerReputation>, db_error> {
+        let args: &[&dyn ToSql] = &[&network_id, &peer_addr.to_bin(), &peer_port];
+        let reputation = query_row::<PeerReputation, _>(
+            conn,
+            "SELECT * FROM peer_reputation WHERE network_id = ?1 AND addrbytes = ?2 AND port = ?3",
+            args,
+        )?;
+        Ok(reputation.map(|reputation| reputation.decayed(now)))
+    }
+
+    /// Fold the outcome of a conversation into a peer's reputation: the health score it ended
+    /// with (if the conversation lasted long enough to have one), and how many bandwidth
+    /// violations the peer committed.  Returns the new reputation.
+    pub fn record_peer_reputation<'a>(
+        tx: &mut Transaction<'a>,
+        network_id: u32,
+        peer_addr: &PeerAddress,
+        peer_port: u16,
+        health_score: Option<f64>,
+        bandwidth_violations: u64,
+        now: u64,
+    ) -> Result<PeerReputation, db_error> {
+        let mut reputation =
+            PeerDB::get_peer_reputation(tx, network_id, peer_addr, peer_port, now)?
+                .unwrap_or_else(|| PeerReputation::neutral(now));
+        if let Some(health_score) = health_score {
+            reputation.health_score = (reputation.health_score + health_score) / 2.0;
+        }
+        reputation.bandwidth_violations += bandwidth_violations as f64;
+
+        let args: &[&dyn ToSql] = &[
+            &network_id,
+            &peer_addr.to_bin(),
+            &peer_port,
+            &reputation.health_score,
+            &reputation.bandwidth_violations,
+            &u64_to_sql(reputation.last_update_time)?,
+        ];
+        tx.execute(
+            "INSERT OR REPLACE INTO peer_reputation (network_id, addrbytes, port, health_score, bandwidth_violations, last_update_time) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            args,
+        )
+        .map_err(db_error::SqliteError)?;
+        Ok(reputation)
+    }
+
+    /// Forget the reputations that have not been updated in `PEER_REPUTATION_LIFETIME` seconds
+    pub fn prune_peer_reputations<'a>(tx: &mut Transaction<'a>, now: u64) -> Result<(), db_error> {
+        tx.execute(
+            "DELETE FROM peer_reputation WHERE last_update_time < ?1",
+            &[&u64_to_sql(now.saturating_sub(PEER_REPUTATION_LIFETIME))?],
+        )
+        .map_err(db_error::SqliteError)?;
+        Ok(())
+    }
+
+    /// Choose up to `count` of `candidates` at random, preferring peers with better reputations.
+    /// Each candidate is chosen with probability proportional to its selection weight, without
+    /// replacement.
+    fn choose_by_reputation(
+        conn: &DBConn,
+        candidates: Vec<Neighbor>,
+        count: usize,
+        now: u64,
+    ) -> Result<Vec<Neighbor>, db_error> {
+        let mut rng = thread_rng();
+        let mut keyed = Vec::with_capacity(candidates.len());
+        for candidate in candidates.into_iter() {
+            let weight = PeerDB::get_peer_reputation(
+                conn,
+                candidate.addr.network_id,
+                &candidate.addr.addrbytes,
+                candidate.addr.port,
+                now,
+            )?
+            .unwrap_or_else(|| PeerReputation::neutral(now))
+            .selection_weight();
+            // weighted sampling without replacement: keep the candidates with the largest keys
+            let key = rng.gen::<f64>().powf(1.0 / weight);
+            keyed.push((key, candidate));
+        }
+        keyed.sort_by(|(key_1, _), (key_2, _)| key_2.total_cmp(key_1));
+        keyed.truncate(count);
+        Ok(keyed.into_iter().map(|(_, candidate)| candidate).collect())
+    }
+
     /// Get random neighbors, optionally always including allowed neighbors
     pub fn get_random_neighbors(
         conn: &DBConn,
@@ -1294,7 +1807,8 @@ impl PeerDB {
             return Ok(ret);
         }
 
-        // fill in with non-allowed, randomly-chosen, fresh peers
+        // fill in with non-allowed, randomly-chosen, fresh peers, favoring the ones with good
+        // reputations
         let random_peers_qry = if always_include_allowed {
             "SELECT * FROM frontier WHERE network_id = ?1 AND last_contact_time >= 0 AND ?2 < expire_block_height AND denied < ?3 AND \
                  (allowed >= 0 AND allowed <= ?4) AND (peer_version & 0x000000ff) >= ?5 ORDER BY RANDOM() LIMIT ?6".to_string()
@@ -1303,17 +1817,19 @@ impl PeerDB {
                  (allowed < 0 OR (allowed >= 0 AND allowed <= ?4)) AND (peer_version & 0x000000ff) >= ?5 ORDER BY RANDOM() LIMIT ?6".to_string()
         };
 
+        let num_random_peers = count - (ret.len() as u32);
         let random_peers_args: &[&dyn ToSql] = &[
             &network_id,
             &u64_to_sql(block_height)?,
             &u64_to_sql(now_secs)?,
             &u64_to_sql(now_secs)?,
             &network_epoch,
-            &(count - (ret.len() as u32)),
+            &num_random_peers.saturating_mul(PEER_REPUTATION_CANDIDATES_PER_NEIGHBOR),
         ];
 
+        let candidates = query_rows::<Neighbor, _>(conn, &random_peers_qry, random_peers_args)?;
         let mut random_peers =
-            query_rows::<Neighbor, _>(conn, &random_peers_qry, random_peers_args)?;
+            PeerDB::choose_by_reputation(conn, candidates, num_random_peers as usize, now_secs)?;
 
         ret.append(&mut random_peers);
         Ok(ret)
@@ -1448,6 +1964,33 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_peerdb_schema_version() {
+        let mut db = PeerDB::connect_memory(
+            0x9abcdef0,
+            12345,
+            0,
+            "http://foo.com".into(),
+            &vec![],
+            &vec![],
+        )
+        .unwrap();
+
+        let status = PEERDB_MIGRATIONS.check(db.conn()).unwrap();
+        assert_eq!(status.current_version.to_string(), PEERDB_VERSION);
+        assert!(status.is_up_to_date());
+
+        // a peer DB from a newer node is refused
+        let tx = db.tx_begin().unwrap();
+        tx.execute("UPDATE db_config SET version = ?1", &[&"4".to_string()])
+            .unwrap();
+        tx.commit().unwrap();
+        match PEERDB_MIGRATIONS.check(db.conn()) {
+            Err(db_error::UnsupportedSchema(_)) => {}
+            res => panic!("Expected UnsupportedSchema, got {:?}", &res),
+        }
+    }
+
     #[test]
     fn test_peer_insert_and_retrieval() {
         let neighbor = Neighbor {
@@ -2326,4 +2869,288 @@ mod test {
         assert_eq!(n1.allowed, 0);
         assert_eq!(n2.allowed, 0);
     }
+
+    #[test]
+    fn test_peer_bans() {
+        let mut db = PeerDB::connect_memory(
+            0x9abcdef0,
+            12345,
+            0,
+            "http://foo.com".into(),
+            &vec![],
+            &vec![],
+        )
+        .unwrap();
+
+        let banned_key = Hash160([0x11; 20]);
+        let addr = PeerAddress::from_ipv4(10, 0, 0, 1);
+        let (key_ban, range_ban) = {
+            let mut tx = db.tx_begin().unwrap();
+            let key_ban = PeerDB::add_peer_ban(
+                &mut tx,
+                &PeerBanTarget::PublicKeyHash(banned_key.clone()),
+                "spam",
+                1000,
+                None,
+            )
+            .unwrap();
+            // 10.0.0.0/8
+            let range_ban = PeerDB::add_peer_ban(
+                &mut tx,
+                &PeerBanTarget::AddressRange(PeerAddress::from_ipv4(10, 0, 0, 0), 104),
+                "bad range",
+                1000,
+                Some(2000),
+            )
+            .unwrap();
+            tx.commit().unwrap();
+            (key_ban, range_ban)
+        };
+
+        let bans = PeerDB::get_peer_bans(db.conn(), 1500).unwrap();
+        assert_eq!(bans.len(), 2);
+        assert_eq!(bans[0].ban_id, key_ban);
+        assert_eq!(bans[0].expires_at, None);
+        assert_eq!(bans[1].ban_id, range_ban);
+        assert_eq!(bans[1].reason, "bad range");
+
+        let other_addr = PeerAddress::from_ipv4(11, 0, 0, 1);
+        let ban = PeerDB::find_peer_ban(db.conn(), &addr, None, 1500).unwrap();
+        assert_eq!(ban.unwrap().ban_id, range_ban);
+        let ban = PeerDB::find_peer_ban(db.conn(), &other_addr, Some(&banned_key), 1500).unwrap();
+        assert_eq!(ban.unwrap().ban_id, key_ban);
+        assert!(PeerDB::find_peer_ban(db.conn(), &other_addr, None, 1500)
+            .unwrap()
+            .is_none());
+
+        // the range ban expires
+        assert!(PeerDB::find_peer_ban(db.conn(), &addr, None, 2000)
+            .unwrap()
+            .is_none());
+        assert_eq!(PeerDB::get_peer_bans(db.conn(), 2000).unwrap().len(), 1);
+
+        // lift the key ban
+        let mut tx = db.tx_begin().unwrap();
+        let lifted = PeerDB::remove_peer_ban(&mut tx, key_ban).unwrap().unwrap();
+        assert_eq!(
+            lifted.target,
+            PeerBanTarget::PublicKeyHash(banned_key.clone())
+        );
+        assert!(PeerDB::remove_peer_ban(&mut tx, key_ban).unwrap().is_none());
+        tx.commit().unwrap();
+        assert!(
+            PeerDB::find_peer_ban(db.conn(), &other_addr, Some(&banned_key), 1500)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_peer_ban_address_ranges() {
+        let target = PeerBanTarget::from_address_range("10.0.0.0/8").unwrap();
+        assert_eq!(
+            target,
+            PeerBanTarget::AddressRange(PeerAddress::from_ipv4(10, 0, 0, 0), 104)
+        );
+        assert_eq!(target.to_address_range(), Some("10.0.0.0/8".to_string()));
+
+        let target = PeerBanTarget::from_address_range("2001:db8::/32").unwrap();
+        assert_eq!(target.to_address_range(), Some("2001:db8::/32".to_string()));
+
+        assert!(PeerBanTarget::from_address_range("10.0.0.0").is_none());
+        assert!(PeerBanTarget::from_address_range("10.0.0.0/33").is_none());
+        assert!(PeerBanTarget::from_address_range("::/0").is_none());
+        assert!(PeerBanTarget::from_address_range("not-an-ip/8").is_none());
+        assert_eq!(
+            PeerBanTarget::PublicKeyHash(Hash160([0x11; 20])).to_address_range(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_peer_offenses() {
+        let mut db = PeerDB::connect_memory(
+            0x9abcdef0,
+            12345,
+            0,
+            "http://foo.com".into(),
+            &vec![],
+            &vec![],
+        )
+        .unwrap();
+
+        let addr = PeerAddress::from_ipv4(10, 0, 0, 1);
+        let other_addr = PeerAddress::from_ipv4(10, 0, 0, 2);
+        let mut tx = db.tx_begin().unwrap();
+        assert_eq!(
+            PeerDB::record_peer_offenses(&mut tx, &addr, 2, 10_000).unwrap(),
+            2
+        );
+        assert_eq!(
+            PeerDB::record_peer_offenses(&mut tx, &addr, 3, 10_100).unwrap(),
+            5
+        );
+        assert_eq!(
+            PeerDB::record_peer_offenses(&mut tx, &other_addr, 1, 10_100).unwrap(),
+            1
+        );
+
+        // the score starts over once the last offense is old enough
+        assert_eq!(
+            PeerDB::record_peer_offenses(&mut tx, &addr, 1, 10_101 + PEER_OFFENSE_WINDOW).unwrap(),
+            1
+        );
+
+        PeerDB::clear_peer_offenses(&mut tx, &addr).unwrap();
+        assert_eq!(
+            PeerDB::record_peer_offenses(&mut tx, &addr, 1, 20_000).unwrap(),
+            1
+        );
+        tx.commit().unwrap();
+    }
+
+    #[test]
+    fn test_peer_reputation() {
+        let mut db = PeerDB::connect_memory(
+            0x9abcdef0,
+            12345,
+            0,
+            "http://foo.com".into(),
+            &vec![],
+            &vec![],
+        )
+        .unwrap();
+
+        let addr = PeerAddress::from_ipv4(10, 0, 0, 1);
+        let now = 1_000_000;
+        assert_eq!(
+            PeerDB::get_peer_reputation(db.conn(), 0x9abcdef0, &addr, 20444, now).unwrap(),
+            None
+        );
+
+        let mut tx = db.tx_begin().unwrap();
+        // health scores are averaged into the reputation
+        let reputation =
+            PeerDB::record_peer_reputation(&mut tx, 0x9abcdef0, &addr, 20444, Some(1.0), 0, now)
+                .unwrap();
+        assert_eq!(reputation.health_score, 0.75);
+        assert_eq!(reputation.bandwidth_violations, 0.0);
+
+        // conversations without a health score only add their violations
+        let reputation =
+            PeerDB::record_peer_reputation(&mut tx, 0x9abcdef0, &addr, 20444, None, 2, now)
+                .unwrap();
+        assert_eq!(reputation.health_score, 0.75);
+        assert_eq!(reputation.bandwidth_violations, 2.0);
+        assert_eq!(reputation.selection_weight(), 0.25);
+        tx.commit().unwrap();
+
+        // reputations are per port and network
+        assert_eq!(
+            PeerDB::get_peer_reputation(db.conn(), 0x9abcdef0, &addr, 20445, now).unwrap(),
+            None
+        );
+        assert_eq!(
+            PeerDB::get_peer_reputation(db.conn(), 0x9abcdef1, &addr, 20444, now).unwrap(),
+            None
+        );
+
+        // reputations decay halfway to neutral every half-life
+        let decayed = PeerDB::get_peer_reputation(
+            db.conn(),
+            0x9abcdef0,
+            &addr,
+            20444,
+            now + PEER_REPUTATION_HALF_LIFE,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(decayed.health_score, 0.625);
+        assert_eq!(decayed.bandwidth_violations, 1.0);
+        assert_eq!(decayed.last_update_time, now + PEER_REPUTATION_HALF_LIFE);
+
+        // and are forgotten once they are old enough
+        let mut tx = db.tx_begin().unwrap();
+        PeerDB::prune_peer_reputations(&mut tx, now + PEER_REPUTATION_LIFETIME).unwrap();
+        tx.commit().unwrap();
+        assert!(
+            PeerDB::get_peer_reputation(db.conn(), 0x9abcdef0, &addr, 20444, now)
+                .unwrap()
+                .is_some()
+        );
+
+        let mut tx = db.tx_begin().unwrap();
+        PeerDB::prune_peer_reputations(&mut tx, now + PEER_REPUTATION_LIFETIME + 1).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(
+            PeerDB::get_peer_reputation(db.conn(), 0x9abcdef0, &addr, 20444, now).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_random_neighbors_favor_reputable_peers() {
+        let now_secs = util::get_epoch_time_secs();
+        let mut initial_neighbors = vec![];
+        for i in 0..2 {
+            initial_neighbors.push(Neighbor {
+                addr: NeighborKey {
+                    peer_version: 0x12345678,
+                    network_id: 0x9abcdef0,
+                    addrbytes: PeerAddress([i as u8; 16]),
+                    port: i,
+                },
+                public_key: Secp256k1PublicKey::from_private(&Secp256k1PrivateKey::new()),
+                expire_block: 23456,
+                last_contact_time: 1552509642,
+                allowed: 0,
+                denied: 0,
+                asn: 34567,
+                org: 45678,
+                in_degree: 1,
+                out_degree: 1,
+            });
+        }
+
+        let mut db = PeerDB::connect_memory(
+            0x9abcdef0,
+            12345,
+            0,
+            "http://foo.com".into(),
+            &vec![],
+            &initial_neighbors,
+        )
+        .unwrap();
+
+        // peer 1 keeps exceeding its bandwidth limits
+        let mut tx = db.tx_begin().unwrap();
+        PeerDB::record_peer_reputation(
+            &mut tx,
+            0x9abcdef0,
+            &PeerAddress([1u8; 16]),
+            1,
+            Some(0.0),
+            100,
+            now_secs,
+        )
+        .unwrap();
+        tx.commit().unwrap();
+
+        let mut chose_reputable = 0;
+        for _ in 0..200 {
+            let neighbors =
+                PeerDB::get_random_walk_neighbors(db.conn(), 0x9abcdef0, 0x78, 1, 23455).unwrap();
+            assert_eq!(neighbors.len(), 1);
+            if neighbors[0].addr.port == 0 {
+                chose_reputable += 1;
+            }
+        }
+        // peer 1 is chosen with probability of about 2%
+        assert!(chose_reputable > 150);
+
+        // both peers are still chosen when there's room for both
+        let neighbors =
+            PeerDB::get_random_walk_neighbors(db.conn(), 0x9abcdef0, 0x78, 2, 23455).unwrap();
+        assert_eq!(neighbors.len(), 2);
+    }
 }
@@ -0,0 +1,408 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Chain divergence snapshots.
+//!
+//! When the node sees the L1 chain tip reorganize deeper than its configured threshold, or sees
+//! the canonical subnet chain tip move to a block that does not build on the previous one, it
+//! writes a compact diagnostic bundle to disk: the recent sortitions, the recent subnet block
+//! headers (across all forks), what each connected peer believes the L1 tip to be, and a summary
+//! of the mempool.  Operators can also capture one on demand through the admin RPC endpoint.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use rusqlite::Row;
+use rusqlite::NO_PARAMS;
+
+use crate::types::chainstate::BlockHeaderHash;
+use crate::types::chainstate::BurnchainHeaderHash;
+use crate::types::chainstate::StacksBlockId;
+use stacks_common::util::get_epoch_time_ms;
+use stacks_common::util::get_epoch_time_secs;
+
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::burn::BlockSnapshot;
+use crate::chainstate::burn::ConsensusHash;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::core::mempool::MemPoolDB;
+use crate::net::p2p::PeerNetwork;
+use crate::net::Error as net_error;
+use crate::net::PeerAddress;
+use crate::util_lib::db::Error as db_error;
+use crate::util_lib::db::{query_rows, u64_to_sql, FromColumn, FromRow};
+
+/// How many sortitions and subnet block headers a snapshot records
+pub const DIVERGENCE_SNAPSHOT_DEPTH: u64 = 64;
+
+/// Why a divergence snapshot was captured
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DivergenceReason {
+    /// The L1 chain tip moved to a fork that does not contain the previous tip
+    BurnchainReorg {
+        /// how many of the previous tip's blocks were orphaned
+        depth: u64,
+        old_tip: BurnchainHeaderHash,
+        new_tip: BurnchainHeaderHash,
+    },
+    /// The canonical subnet chain tip moved to a block that does not build on the previous tip
+    SubnetFork {
+        old_tip: StacksBlockId,
+        new_tip: StacksBlockId,
+    },
+    /// Requested through the admin RPC endpoint
+    Manual,
+}
+
+impl DivergenceReason {
+    fn name(&self) -> &'static str {
+        match self {
+            DivergenceReason::BurnchainReorg { .. } => "burnchain-reorg",
+            DivergenceReason::SubnetFork { .. } => "subnet-fork",
+            DivergenceReason::Manual => "manual",
+        }
+    }
+}
+
+/// A processed sortition, as seen by this node when the snapshot was captured
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SortitionSummary {
+    pub block_height: u64,
+    pub burn_header_hash: BurnchainHeaderHash,
+    pub parent_burn_header_hash: BurnchainHeaderHash,
+    pub consensus_hash: ConsensusHash,
+    pub sortition: bool,
+    pub winning_stacks_block_hash: BlockHeaderHash,
+    pub canonical_stacks_tip_height: u64,
+    pub canonical_stacks_tip_hash: BlockHeaderHash,
+    pub canonical_stacks_tip_consensus_hash: ConsensusHash,
+}
+
+impl From<&BlockSnapshot> for SortitionSummary {
+    fn from(sn: &BlockSnapshot) -> SortitionSummary {
+        SortitionSummary {
+            block_height: sn.block_height,
+            burn_header_hash: sn.burn_header_hash.clone(),
+            parent_burn_header_hash: sn.parent_burn_header_hash.clone(),
+            consensus_hash: sn.consensus_hash.clone(),
+            sortition: sn.sortition,
+            winning_stacks_block_hash: sn.winning_stacks_block_hash.clone(),
+            canonical_stacks_tip_height: sn.canonical_stacks_tip_height,
+            canonical_stacks_tip_hash: sn.canonical_stacks_tip_hash.clone(),
+            canonical_stacks_tip_consensus_hash: sn.canonical_stacks_tip_consensus_hash.clone(),
+        }
+    }
+}
+
+/// A processed subnet block header
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StacksHeaderSummary {
+    pub index_block_hash: StacksBlockId,
+    pub parent_block_id: StacksBlockId,
+    pub block_hash: BlockHeaderHash,
+    pub consensus_hash: ConsensusHash,
+    pub block_height: u64,
+    pub burn_header_hash: BurnchainHeaderHash,
+    pub burn_header_height: u64,
+    /// whether or not the block is in the fork of the canonical subnet chain tip
+    pub canonical: bool,
+}
+
+impl FromRow<StacksHeaderSummary> for StacksHeaderSummary {
+    fn from_row<'a>(row: &'a Row) -> Result<StacksHeaderSummary, db_error> {
+        Ok(StacksHeaderSummary {
+            index_block_hash: StacksBlockId::from_column(row, "index_block_hash")?,
+            parent_block_id: StacksBlockId::from_column(row, "parent_block_id")?,
+            block_hash: BlockHeaderHash::from_column(row, "block_hash")?,
+            consensus_hash: ConsensusHash::from_column(row, "consensus_hash")?,
+            block_height: u64::from_column(row, "block_height")?,
+            burn_header_hash: BurnchainHeaderHash::from_column(row, "burn_header_hash")?,
+            burn_header_height: u64::from_column(row, "burn_header_height")?,
+            canonical: false,
+        })
+    }
+}
+
+/// What a connected peer last told us about its view of the L1 chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeerView {
+    #[serde(rename = "ip")]
+    pub addrbytes: PeerAddress,
+    pub port: u16,
+    pub peer_version: u32,
+    pub outbound: bool,
+    pub authenticated: bool,
+    pub burn_block_height: u64,
+    pub burn_header_hash: BurnchainHeaderHash,
+    pub stable_burn_block_height: u64,
+    pub stable_burn_header_hash: BurnchainHeaderHash,
+}
+
+/// Size of the mempool when the snapshot was captured
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MempoolSummary {
+    pub tx_count: u64,
+    pub total_bytes: u64,
+    /// when the oldest transaction still in the mempool was accepted
+    pub oldest_accept_time: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DivergenceSnapshot {
+    pub reason: DivergenceReason,
+    /// seconds since the epoch
+    pub captured_at: u64,
+    /// the most recent sortitions in the canonical L1 fork, newest first
+    pub sortitions: Vec<SortitionSummary>,
+    /// the most recently processed subnet block headers in all forks, highest first
+    pub stacks_headers: Vec<StacksHeaderSummary>,
+    pub peers: Vec<PeerView>,
+    pub mempool: MempoolSummary,
+}
+
+impl DivergenceSnapshot {
+    /// Write this snapshot to a new JSON file in `dir`, and return the file's path
+    pub fn save(&self, dir: &str) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let mut path = PathBuf::from(dir);
+        path.push(format!(
+            "divergence-{}-{}.json",
+            get_epoch_time_ms(),
+            self.reason.name()
+        ));
+        let file = fs::File::create(&path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(path)
+    }
+}
+
+impl PeerNetwork {
+    /// Capture a divergence snapshot from this node's current view of both chains, its peers,
+    /// and its mempool
+    pub fn capture_divergence_snapshot(
+        &self,
+        reason: DivergenceReason,
+        sortdb: &SortitionDB,
+        chainstate: &StacksChainState,
+        mempool: &MemPoolDB,
+    ) -> Result<DivergenceSnapshot, net_error> {
+        let tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+
+        let mut sortitions = vec![];
+        let mut cursor = Some(tip.clone());
+        while let Some(sn) = cursor {
+            if sortitions.len() as u64 >= DIVERGENCE_SNAPSHOT_DEPTH {
+                break;
+            }
+            sortitions.push(SortitionSummary::from(&sn));
+            if sn.block_height <= self.burnchain.first_block_height {
+                break;
+            }
+            cursor = SortitionDB::get_block_snapshot(sortdb.conn(), &sn.parent_sortition_id)?;
+        }
+
+        let canonical_tip = StacksBlockId::new(
+            &tip.canonical_stacks_tip_consensus_hash,
+            &tip.canonical_stacks_tip_hash,
+        );
+        let index_conn = chainstate.index_conn()?;
+        let mut stacks_headers: Vec<StacksHeaderSummary> = query_rows(
+            chainstate.db(),
+            "SELECT index_block_hash, parent_block_id, block_hash, consensus_hash, block_height, burn_header_hash, burn_header_height
+             FROM block_headers ORDER BY block_height DESC LIMIT ?1",
+            &[u64_to_sql(DIVERGENCE_SNAPSHOT_DEPTH)?],
+        )?;
+        for header in stacks_headers.iter_mut() {
+            if header.block_height > tip.canonical_stacks_tip_height {
+                continue;
+            }
+            header.canonical = index_conn
+                .get_ancestor_block_hash(header.block_height, &canonical_tip)?
+                .map(|ancestor| ancestor == header.index_block_hash)
+                .unwrap_or(false);
+        }
+
+        let peers = self
+            .peers
+            .values()
+            .map(|convo| PeerView {
+                addrbytes: convo.peer_addrbytes.clone(),
+                port: convo.peer_port,
+                peer_version: convo.peer_version,
+                outbound: convo.is_outbound(),
+                authenticated: convo.is_authenticated(),
+                burn_block_height: convo.burnchain_tip_height,
+                burn_header_hash: convo.burnchain_tip_burn_header_hash.clone(),
+                stable_burn_block_height: convo.burnchain_stable_tip_height,
+                stable_burn_header_hash: convo.burnchain_stable_tip_burn_header_hash.clone(),
+            })
+            .collect();
+
+        let mempool = mempool
+            .conn()
+            .query_row(
+                "SELECT COUNT(*), IFNULL(SUM(length), 0), MIN(accept_time) FROM mempool",
+                NO_PARAMS,
+                |row| {
+                    let tx_count: i64 = row.get(0)?;
+                    let total_bytes: i64 = row.get(1)?;
+                    let oldest_accept_time: Option<i64> = row.get(2)?;
+                    Ok(MempoolSummary {
+                        tx_count: tx_count as u64,
+                        total_bytes: total_bytes as u64,
+                        oldest_accept_time: oldest_accept_time.map(|t| t as u64),
+                    })
+                },
+            )
+            .map_err(db_error::SqliteError)?;
+
+        Ok(DivergenceSnapshot {
+            reason,
+            captured_at: get_epoch_time_secs(),
+            sortitions,
+            stacks_headers,
+            peers,
+            mempool,
+        })
+    }
+
+    /// Compare the chain tips before and after refreshing our burnchain view, and decide whether
+    /// either chain diverged from the fork we were following.
+    fn find_chain_divergence(
+        &self,
+        prev_tip: &BlockSnapshot,
+        sortdb: &SortitionDB,
+        chainstate: &StacksChainState,
+    ) -> Result<Option<DivergenceReason>, net_error> {
+        let tip = &self.burnchain_tip;
+        if prev_tip.is_initial() {
+            // we haven't loaded a view of either chain yet
+            return Ok(None);
+        }
+        if prev_tip.burn_header_hash == tip.burn_header_hash
+            && prev_tip.canonical_stacks_tip_hash == tip.canonical_stacks_tip_hash
+            && prev_tip.canonical_stacks_tip_consensus_hash
+                == tip.canonical_stacks_tip_consensus_hash
+        {
+            return Ok(None);
+        }
+
+        if prev_tip.burn_header_hash != tip.burn_header_hash {
+            // walk back from the old tip until we find a sortition in the new tip's fork
+            let ic = sortdb.index_conn();
+            let mut cursor = Some(prev_tip.clone());
+            let mut fork_height = None;
+            while let Some(sn) = cursor {
+                if let Some(ancestor) =
+                    SortitionDB::get_ancestor_snapshot(&ic, sn.block_height, &tip.sortition_id)?
+                {
+                    if ancestor.sortition_id == sn.sortition_id {
+                        fork_height = Some(sn.block_height);
+                        break;
+                    }
+                }
+                if sn.block_height <= self.burnchain.first_block_height {
+                    break;
+                }
+                cursor = SortitionDB::get_block_snapshot(sortdb.conn(), &sn.parent_sortition_id)?;
+            }
+
+            let depth =
+                prev_tip.block_height - fork_height.unwrap_or(self.burnchain.first_block_height);
+            if depth > 0 && depth >= self.connection_opts.divergence_reorg_threshold {
+                return Ok(Some(DivergenceReason::BurnchainReorg {
+                    depth,
+                    old_tip: prev_tip.burn_header_hash.clone(),
+                    new_tip: tip.burn_header_hash.clone(),
+                }));
+            }
+        }
+
+        if prev_tip.canonical_stacks_tip_height > 0
+            && (prev_tip.canonical_stacks_tip_hash != tip.canonical_stacks_tip_hash
+                || prev_tip.canonical_stacks_tip_consensus_hash
+                    != tip.canonical_stacks_tip_consensus_hash)
+        {
+            let old_tip = StacksBlockId::new(
+                &prev_tip.canonical_stacks_tip_consensus_hash,
+                &prev_tip.canonical_stacks_tip_hash,
+            );
+            let new_tip = StacksBlockId::new(
+                &tip.canonical_stacks_tip_consensus_hash,
+                &tip.canonical_stacks_tip_hash,
+            );
+            let builds_on_old_tip = prev_tip.canonical_stacks_tip_height
+                < tip.canonical_stacks_tip_height
+                && chainstate
+                    .index_conn()?
+                    .get_ancestor_block_hash(prev_tip.canonical_stacks_tip_height, &new_tip)?
+                    .map(|ancestor| ancestor == old_tip)
+                    .unwrap_or(false);
+            if !builds_on_old_tip {
+                return Ok(Some(DivergenceReason::SubnetFork { old_tip, new_tip }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// If either chain diverged from the fork we were following since `prev_tip`, write a
+    /// divergence snapshot to the configured snapshot directory.  Failures are logged, since
+    /// snapshots are diagnostic only.
+    pub(crate) fn snapshot_chain_divergence(
+        &self,
+        prev_tip: &BlockSnapshot,
+        sortdb: &SortitionDB,
+        chainstate: &StacksChainState,
+        mempool: &MemPoolDB,
+    ) {
+        let snapshot_dir = match self.connection_opts.divergence_snapshot_dir.as_ref() {
+            Some(dir) => dir,
+            None => return,
+        };
+
+        let reason = match self.find_chain_divergence(prev_tip, sortdb, chainstate) {
+            Ok(Some(reason)) => reason,
+            Ok(None) => return,
+            Err(e) => {
+                warn!(
+                    "{:?}: failed to check for chain divergence: {:?}",
+                    &self.local_peer, &e
+                );
+                return;
+            }
+        };
+
+        warn!("{:?}: chain divergence detected", &self.local_peer; "reason" => ?reason);
+        match self
+            .capture_divergence_snapshot(reason, sortdb, chainstate, mempool)
+            .map_err(|e| format!("{:?}", &e))
+            .and_then(|snapshot| snapshot.save(snapshot_dir).map_err(|e| format!("{:?}", &e)))
+        {
+            Ok(path) => info!(
+                "{:?}: saved divergence snapshot to {}",
+                &self.local_peer,
+                path.display()
+            ),
+            Err(e) => warn!(
+                "{:?}: failed to save divergence snapshot: {}",
+                &self.local_peer, &e
+            ),
+        }
+    }
+}
@@ -17,6 +17,7 @@
  along with Blockstack. If not, see <http://www.gnu.org/licenses/>.
 */
 
+use std::cmp;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
@@ -24,6 +25,7 @@ use std::convert::TryFrom;
 use std::hash::{Hash, Hasher};
 use std::io::Read;
 use std::io::Write;
+use std::mem;
 use std::net::IpAddr;
 use std::net::SocketAddr;
 use std::sync::mpsc::sync_channel;
@@ -99,6 +101,10 @@ pub const BLOCK_REREQUEST_INTERVAL: u64 = 60;
 #[cfg(test)]
 pub const BLOCK_REREQUEST_INTERVAL: u64 = 30;
 
+/// If we don't know how quickly a peer usually answers block requests, wait this many
+/// milliseconds before asking another peer for the same block too.
+pub const BLOCK_DOWNLOAD_HEDGE_DEFAULT_MS: u64 = 5_000;
+
 /// This module is responsible for downloading blocks and microblocks from other peers, using block
 /// inventory state (see src/net/inv.rs)
 
@@ -254,6 +260,13 @@ pub struct BlockDownloader {
     /// when did we last request a given block hash
     requested_blocks: HashMap<StacksBlockId, u64>,
     requested_microblocks: HashMap<StacksBlockId, u64>,
+
+    /// when (in milliseconds) we sent each in-flight block or microblock request
+    request_sent_at: HashMap<BlockRequestKey, u128>,
+
+    /// hedged request tuning (see ConnectionOptions)
+    hedge_percentile: u64,
+    hedge_min_ms: u64,
 }
 
 impl BlockDownloader {
@@ -261,6 +274,8 @@ impl BlockDownloader {
         dns_timeout: u128,
         download_interval: u64,
         max_inflight_requests: u64,
+        hedge_percentile: u64,
+        hedge_min_ms: u64,
     ) -> BlockDownloader {
         BlockDownloader {
             state: BlockDownloaderState::DNSLookupBegin,
@@ -298,6 +313,10 @@ impl BlockDownloader {
             download_interval: download_interval,
             requested_blocks: HashMap::new(),
             requested_microblocks: HashMap::new(),
+
+            request_sent_at: HashMap::new(),
+            hedge_percentile,
+            hedge_min_ms,
         }
     }
 
@@ -318,6 +337,7 @@ impl BlockDownloader {
         self.dead_peers.clear();
         self.broken_peers.clear();
         self.broken_neighbors.clear();
+        self.request_sent_at.clear();
 
         // perserve sortition height
         // preserve download accounting
@@ -430,6 +450,10 @@ impl BlockDownloader {
         assert_eq!(self.state, BlockDownloaderState::GetBlocksBegin);
 
         // don't touch blocks-to-try -- that's managed by the peer network directly.
+        let now = get_epoch_time_ms();
+        for block_key in requests.keys() {
+            self.request_sent_at.insert(block_key.clone(), now);
+        }
         self.getblock_requests = requests;
         self.state = BlockDownloaderState::GetBlocksFinish;
     }
@@ -444,7 +468,8 @@ impl BlockDownloader {
         let mut pending_block_requests = HashMap::new();
 
         PeerNetwork::with_http(network, |ref mut network, ref mut http| {
-            for (block_key, event_id) in self.getblock_requests.drain() {
+            let requests = mem::replace(&mut self.getblock_requests, HashMap::new());
+            for (block_key, event_id) in requests.into_iter() {
                 match http.get_conversation(event_id) {
                     None => {
                         if http.is_connecting(event_id) {
@@ -508,8 +533,13 @@ impl BlockDownloader {
                                         info!("Invalid block from {:?} ({:?}): did not ask for block {}/{}", &block_key.neighbor, &block_key.data_url, block_key.consensus_hash, block.block_hash());
                                         self.broken_peers.push(event_id);
                                         self.broken_neighbors.push(block_key.neighbor.clone());
+                                    } else if self.is_fetched(&block_key.index_block_hash, false) {
+                                        // a hedged request to another peer got here first
+                                        debug!("Ignoring duplicate block {}", &block_key);
+                                        self.record_request_latency(network, &block_key);
                                     } else {
                                         // got the block
+                                        self.record_request_latency(network, &block_key);
                                         debug!(
                                             "Got block {}: {}/{}",
                                             &block_key.sortition_height,
@@ -545,6 +575,9 @@ impl BlockDownloader {
             }
         });
 
+        // stop waiting on requests for blocks that a hedged request already fetched
+        self.cancel_hedged_requests(&mut pending_block_requests, false);
+
         // are we done?
         if pending_block_requests.len() == 0 {
             self.state = BlockDownloaderState::GetMicroblocksBegin;
@@ -562,6 +595,10 @@ impl BlockDownloader {
     pub fn getmicroblocks_begin(&mut self, requests: HashMap<BlockRequestKey, usize>) -> () {
         assert_eq!(self.state, BlockDownloaderState::GetMicroblocksBegin);
 
+        let now = get_epoch_time_ms();
+        for block_key in requests.keys() {
+            self.request_sent_at.insert(block_key.clone(), now);
+        }
         self.getmicroblocks_requests = requests;
         self.state = BlockDownloaderState::GetMicroblocksFinish;
     }
@@ -576,7 +613,8 @@ impl BlockDownloader {
         let mut pending_microblock_requests = HashMap::new();
 
         PeerNetwork::with_http(network, |ref mut network, ref mut http| {
-            for (block_key, event_id) in self.getmicroblocks_requests.drain() {
+            let requests = mem::replace(&mut self.getmicroblocks_requests, HashMap::new());
+            for (block_key, event_id) in requests.into_iter() {
                 let rh_block_key = block_key.clone();
                 match http.get_conversation(event_id) {
                     None => {
@@ -631,8 +669,13 @@ impl BlockDownloader {
                                         info!("Got unexpected zero-length microblock stream from {:?} ({:?})", &block_key.neighbor, &block_key.data_url);
                                         self.broken_peers.push(event_id);
                                         self.broken_neighbors.push(block_key.neighbor.clone());
+                                    } else if self.is_fetched(&block_key.index_block_hash, true) {
+                                        // a hedged request to another peer got here first
+                                        debug!("Ignoring duplicate microblocks {}", &block_key);
+                                        self.record_request_latency(network, &block_key);
                                     } else {
                                         // have microblocks (but we don't know yet if they're well-formed)
+                                        self.record_request_latency(network, &block_key);
                                         debug!(
                                             "Got (tentative) microblocks {}: {}/{}-{}",
                                             block_key.sortition_height,
@@ -669,6 +712,9 @@ impl BlockDownloader {
             }
         });
 
+        // stop waiting on requests for streams that a hedged request already fetched
+        self.cancel_hedged_requests(&mut pending_microblock_requests, true);
+
         // are we done?
         if pending_microblock_requests.len() == 0 {
             self.state = BlockDownloaderState::Done;
@@ -682,6 +728,127 @@ impl BlockDownloader {
         return Ok(false);
     }
 
+    /// Record how long a peer took to answer a block or microblock request
+    fn record_request_latency(&mut self, network: &mut PeerNetwork, block_key: &BlockRequestKey) {
+        if let Some(sent_at) = self.request_sent_at.remove(block_key) {
+            let latency_ms = get_epoch_time_ms().saturating_sub(sent_at) as u64;
+            network.add_block_request_latency(&block_key.neighbor, latency_ms);
+        }
+    }
+
+    /// Did we already download this block (or the microblock stream it confirms) in this pass?
+    fn is_fetched(&self, index_block_hash: &StacksBlockId, microblocks: bool) -> bool {
+        if microblocks {
+            self.microblocks
+                .keys()
+                .any(|k| k.index_block_hash == *index_block_hash)
+        } else {
+            self.blocks
+                .keys()
+                .any(|k| k.index_block_hash == *index_block_hash)
+        }
+    }
+
+    /// Stop waiting on pending requests for blocks (or microblock streams) that we already got
+    /// from another peer via a hedged request.  A cancelled request's connection is closed, unless
+    /// another pending request shares it -- in which case we keep waiting, and ignore the reply.
+    fn cancel_hedged_requests(
+        &mut self,
+        pending: &mut HashMap<BlockRequestKey, usize>,
+        microblocks: bool,
+    ) -> () {
+        let (cancelled, kept): (Vec<_>, Vec<_>) = pending
+            .drain()
+            .partition(|(block_key, _)| self.is_fetched(&block_key.index_block_hash, microblocks));
+        pending.extend(kept);
+
+        for (block_key, event_id) in cancelled.into_iter() {
+            self.request_sent_at.remove(&block_key);
+            if pending.values().any(|eid| *eid == event_id) {
+                pending.insert(block_key, event_id);
+                continue;
+            }
+            debug!(
+                "Event {} ({:?}, {:?}): cancel hedged request for {}",
+                event_id, &block_key.neighbor, &block_key.data_url, &block_key.index_block_hash
+            );
+            if !self.dead_peers.contains(&event_id) {
+                self.dead_peers.push(event_id);
+            }
+        }
+    }
+
+    /// How long to wait for a peer to answer a block or microblock request before asking another
+    /// peer for the same data.  This is the configured percentile of the peer's recent request
+    /// latencies, but no less than the configured minimum.
+    fn get_hedge_deadline_ms(&self, network: &PeerNetwork, neighbor_key: &NeighborKey) -> u64 {
+        let latency_ms = network
+            .get_convo(neighbor_key)
+            .and_then(|convo| convo.stats.get_block_request_latency(self.hedge_percentile))
+            .unwrap_or(BLOCK_DOWNLOAD_HEDGE_DEFAULT_MS);
+        cmp::max(latency_ms, self.hedge_min_ms)
+    }
+
+    /// Find the sortition heights whose sole in-flight request has taken longer than its hedge
+    /// deadline, and for which there is another peer we can ask.  Hedged requests count against
+    /// the maximum number of in-flight requests like any other, so at most enough heights are
+    /// returned to fill the remaining request slots.
+    fn get_requests_to_hedge(&self, network: &PeerNetwork, microblocks: bool) -> Vec<u64> {
+        if self.hedge_percentile == 0 {
+            return vec![];
+        }
+
+        let (inflight, to_try) = if microblocks {
+            (&self.getmicroblocks_requests, &self.microblocks_to_try)
+        } else {
+            (&self.getblock_requests, &self.blocks_to_try)
+        };
+
+        let num_slots = self
+            .max_inflight_requests
+            .saturating_sub(inflight.len() as u64);
+        if num_slots == 0 {
+            return vec![];
+        }
+
+        let mut num_inflight: HashMap<u64, usize> = HashMap::new();
+        for block_key in inflight.keys() {
+            *num_inflight.entry(block_key.sortition_height).or_insert(0) += 1;
+        }
+
+        let now = get_epoch_time_ms();
+        let mut heights = vec![];
+        for block_key in inflight.keys() {
+            if num_inflight.get(&block_key.sortition_height) != Some(&1) {
+                // already hedged
+                continue;
+            }
+            if to_try
+                .get(&block_key.sortition_height)
+                .map(|keys| keys.len())
+                .unwrap_or(0)
+                == 0
+            {
+                // no one else to ask
+                continue;
+            }
+            let sent_at = match self.request_sent_at.get(block_key) {
+                Some(sent_at) => *sent_at,
+                None => {
+                    continue;
+                }
+            };
+            let deadline_ms = self.get_hedge_deadline_ms(network, &block_key.neighbor);
+            if now.saturating_sub(sent_at) >= deadline_ms as u128 {
+                heights.push(block_key.sortition_height);
+                if heights.len() as u64 >= num_slots {
+                    break;
+                }
+            }
+        }
+        heights
+    }
+
     /// Get the availability of each block in the given sortition range, using the inv state.
     /// Return the local block headers, paired with the list of peers that can serve them.
     /// Possibly less than the given range request.
@@ -1442,10 +1609,19 @@ impl PeerNetwork {
                 neighbors.len()
             );
 
+            // ask the healthiest, fastest peers first.  Shuffle beforehand so ties are broken at
+            // random.
             (&mut neighbors[..]).shuffle(&mut thread_rng());
+            let mut ranked_neighbors: Vec<(f64, NeighborKey)> = neighbors
+                .drain(..)
+                .map(|nk| (self.get_block_download_score(&nk), nk))
+                .collect();
+            ranked_neighbors.sort_by(|(score_1, _), (score_2, _)| {
+                score_2.partial_cmp(score_1).unwrap_or(cmp::Ordering::Equal)
+            });
 
             let mut requests = VecDeque::new();
-            for nk in neighbors.drain(..) {
+            for (_, nk) in ranked_neighbors.into_iter() {
                 let data_url = match self.get_data_url(&nk) {
                     Some(url) => url,
                     None => {
@@ -2059,14 +2235,70 @@ impl PeerNetwork {
         })
     }
 
-    /// Try to see if all blocks are finished downloading
-    pub fn block_getblocks_try_finish(&mut self) -> Result<bool, net_error> {
-        test_debug!("{:?}: block_getblocks_try_finish", &self.local_peer);
+    /// Ask another peer for each block or microblock stream whose request is taking too long.
+    /// Whichever peer answers first wins; the other request is cancelled.
+    fn block_hedge_requests(
+        &mut self,
+        mempool: &MemPoolDB,
+        chainstate: &mut StacksChainState,
+        microblocks: bool,
+    ) -> Result<(), net_error> {
         PeerNetwork::with_downloader_state(self, |ref mut network, ref mut downloader| {
-            downloader.getblocks_try_finish(network)
+            let slow_heights = downloader.get_requests_to_hedge(network, microblocks);
+            for sortition_height in slow_heights.into_iter() {
+                let keys_opt = if microblocks {
+                    downloader.microblocks_to_try.get_mut(&sortition_height)
+                } else {
+                    downloader.blocks_to_try.get_mut(&sortition_height)
+                };
+                let keys = match keys_opt {
+                    Some(keys) => keys,
+                    None => {
+                        continue;
+                    }
+                };
+                if let Some((key, handle)) = PeerNetwork::begin_request(
+                    network,
+                    &downloader.dns_lookups,
+                    keys,
+                    mempool,
+                    chainstate,
+                ) {
+                    debug!(
+                        "{:?}: Hedge slow request at sortition height {} with {}",
+                        &network.local_peer, sortition_height, &key
+                    );
+                    downloader
+                        .request_sent_at
+                        .insert(key.clone(), get_epoch_time_ms());
+                    if microblocks {
+                        downloader.getmicroblocks_requests.insert(key, handle);
+                    } else {
+                        downloader.getblock_requests.insert(key, handle);
+                    }
+                }
+            }
+            Ok(())
         })
     }
 
+    /// Try to see if all blocks are finished downloading
+    pub fn block_getblocks_try_finish(
+        &mut self,
+        mempool: &MemPoolDB,
+        chainstate: &mut StacksChainState,
+    ) -> Result<bool, net_error> {
+        test_debug!("{:?}: block_getblocks_try_finish", &self.local_peer);
+        let done =
+            PeerNetwork::with_downloader_state(self, |ref mut network, ref mut downloader| {
+                downloader.getblocks_try_finish(network)
+            })?;
+        if !done {
+            self.block_hedge_requests(mempool, chainstate, false)?;
+        }
+        Ok(done)
+    }
+
     /// Proceed to get microblocks
     pub fn block_getmicroblocks_begin(
         &mut self,
@@ -2108,11 +2340,20 @@ impl PeerNetwork {
     }
 
     /// Try to see if all microblocks are finished downloading
-    pub fn block_getmicroblocks_try_finish(&mut self) -> Result<bool, net_error> {
+    pub fn block_getmicroblocks_try_finish(
+        &mut self,
+        mempool: &MemPoolDB,
+        chainstate: &mut StacksChainState,
+    ) -> Result<bool, net_error> {
         test_debug!("{:?}: block_getmicroblocks_try_finish", &self.local_peer);
-        PeerNetwork::with_downloader_state(self, |ref mut network, ref mut downloader| {
-            downloader.getmicroblocks_try_finish(network)
-        })
+        let done =
+            PeerNetwork::with_downloader_state(self, |ref mut network, ref mut downloader| {
+                downloader.getmicroblocks_try_finish(network)
+            })?;
+        if !done {
+            self.block_hedge_requests(mempool, chainstate, true)?;
+        }
+        Ok(done)
     }
 
     /// Process newly-fetched blocks and microblocks.
@@ -2369,6 +2610,8 @@ impl PeerNetwork {
             self.connection_opts.dns_timeout,
             self.connection_opts.download_interval,
             self.connection_opts.max_inflight_blocks,
+            self.connection_opts.download_hedge_percentile,
+            self.connection_opts.download_hedge_min_ms,
         ));
     }
 
@@ -2493,13 +2736,13 @@ impl PeerNetwork {
                     self.block_getblocks_begin(mempool, chainstate)?;
                 }
                 BlockDownloaderState::GetBlocksFinish => {
-                    self.block_getblocks_try_finish()?;
+                    self.block_getblocks_try_finish(mempool, chainstate)?;
                 }
                 BlockDownloaderState::GetMicroblocksBegin => {
                     self.block_getmicroblocks_begin(mempool, chainstate)?;
                 }
                 BlockDownloaderState::GetMicroblocksFinish => {
-                    self.block_getmicroblocks_try_finish()?;
+                    self.block_getmicroblocks_try_finish(mempool, chainstate)?;
                 }
                 BlockDownloaderState::Done => {
                     // did a pass.
@@ -2601,6 +2844,71 @@ pub mod test {
         availability
     }
 
+    fn make_hedge_test_request(sortition_height: u64, port: u16) -> BlockRequestKey {
+        let neighbor = NeighborKey {
+            peer_version: 0x18000000,
+            network_id: 0x80000000,
+            addrbytes: PeerAddress::from_ipv4(127, 0, 0, 1),
+            port,
+        };
+        let data_url = UrlString::try_from(format!("http://127.0.0.1:{}", port)).unwrap();
+        BlockRequestKey::new(
+            neighbor,
+            data_url,
+            ConsensusHash([sortition_height as u8; 20]),
+            BlockHeaderHash([sortition_height as u8; 32]),
+            StacksBlockId([sortition_height as u8; 32]),
+            None,
+            None,
+            sortition_height,
+            BlockRequestKeyKind::Block,
+            0,
+        )
+    }
+
+    #[test]
+    fn test_hedged_requests_count_against_max_inflight_blocks() {
+        let peer_config = TestPeerConfig::new(
+            "test_hedged_requests_count_against_max_inflight_blocks",
+            3290,
+            3291,
+        );
+        let peer = TestPeer::new(peer_config);
+
+        let mut downloader = BlockDownloader::new(15_000, 0, 3, 90, 0);
+
+        // two slow block requests, each of which could be hedged with another peer
+        let sent_at = get_epoch_time_ms() - 2 * BLOCK_DOWNLOAD_HEDGE_DEFAULT_MS as u128;
+        for (sortition_height, event_id) in [(1, 1), (2, 2)] {
+            let request = make_hedge_test_request(sortition_height, 30000);
+            downloader.request_sent_at.insert(request.clone(), sent_at);
+            downloader.getblock_requests.insert(request, event_id);
+
+            let mut others = VecDeque::new();
+            others.push_back(make_hedge_test_request(sortition_height, 30001));
+            downloader.blocks_to_try.insert(sortition_height, others);
+        }
+
+        // only one request slot is left
+        assert_eq!(
+            downloader.get_requests_to_hedge(&peer.network, false).len(),
+            1
+        );
+
+        // no request slots are left
+        downloader.max_inflight_requests = 2;
+        assert_eq!(
+            downloader.get_requests_to_hedge(&peer.network, false),
+            Vec::<u64>::new()
+        );
+
+        // enough slots for both
+        downloader.max_inflight_requests = 4;
+        let mut heights = downloader.get_requests_to_hedge(&peer.network, false);
+        heights.sort();
+        assert_eq!(heights, vec![1, 2]);
+    }
+
     #[test]
     fn test_get_block_availability() {
         with_timeout(600, || {
@@ -38,13 +38,22 @@ use url::{form_urlencoded, Url};
 
 use crate::burnchains::{Address, Txid};
 use crate::chainstate::burn::ConsensusHash;
+use crate::chainstate::stacks::db::receipts::TransactionReceiptRecord;
 use crate::chainstate::stacks::miner::Proposal;
 use crate::chainstate::stacks::{
     StacksBlock, StacksMicroblock, StacksPublicKey, StacksTransaction,
 };
 use crate::deps::httparse;
 use crate::net::atlas::Attachment;
+use crate::net::db::PeerBanTarget;
+use crate::net::AdminObserversResponse;
+use crate::net::AdminPeersResponse;
+use crate::net::BlockCostsResponse;
+use crate::net::BlockHeaderResponse;
+use crate::net::BlockProposalValidationResponse;
 use crate::net::ClientError;
+use crate::net::DepositStatusResponse;
+use crate::net::DivergenceSnapshotResponse;
 use crate::net::Error as net_error;
 use crate::net::Error::ClarityError;
 use crate::net::ExtendedStacksHeader;
@@ -56,14 +65,22 @@ use crate::net::HttpResponseMetadata;
 use crate::net::HttpResponsePreamble;
 use crate::net::HttpResponseType;
 use crate::net::HttpVersion;
+use crate::net::MemPoolEvictionResponse;
+use crate::net::MemPoolListResponse;
+use crate::net::MemPoolSummaryResponse;
 use crate::net::MemPoolSyncData;
 use crate::net::MessageSequence;
 use crate::net::NeighborAddress;
+use crate::net::NonceGapsResponse;
 use crate::net::PeerAddress;
+use crate::net::PeerBanInfo;
+use crate::net::PeerBanRequestBody;
+use crate::net::PeerBansResponse;
 use crate::net::PeerHost;
 use crate::net::ProtocolFamily;
 use crate::net::StacksHttpMessage;
 use crate::net::StacksHttpPreamble;
+use crate::net::SubnetStatusResponse;
 use crate::net::UnconfirmedTransactionResponse;
 use crate::net::UnconfirmedTransactionStatus;
 use crate::net::HTTP_PREAMBLE_MAX_ENCODED_SIZE;
@@ -71,8 +88,16 @@ use crate::net::HTTP_PREAMBLE_MAX_NUM_HEADERS;
 use crate::net::HTTP_REQUEST_ID_RESERVED;
 use crate::net::MAX_HEADERS;
 use crate::net::MAX_MICROBLOCKS_UNCONFIRMED;
+use crate::net::{
+    BlockResultsQuery, ClarityDataKey, ClarityDataProofResponse, GetAttachmentResponse,
+    GetAttachmentsInvResponse, MapEntriesResponse, PostTransactionBatchRequestBody,
+    PostTransactionRequestBody, PostTransactionSponsorshipRequestBody,
+    TransactionAdmissionResponse, TransactionBatchResponse, TransactionProofResponse,
+    WithdrawalStatusResponse, MAP_ENTRIES_DEFAULT_LIMIT, MAP_ENTRIES_MAX_LIMIT,
+    MAX_TRANSACTION_BATCH_LEN,
+};
 use crate::net::{CallReadOnlyRequestBody, TipRequest};
-use crate::net::{GetAttachmentResponse, GetAttachmentsInvResponse, PostTransactionRequestBody};
+use clarity::vm::database::ClaritySerializable;
 use clarity::vm::types::{
     AssetIdentifier, QualifiedContractIdentifier, StandardPrincipalData, TraitIdentifier,
 };
@@ -90,12 +115,18 @@ use stacks_common::util::log;
 use stacks_common::util::retry::BoundReader;
 use stacks_common::util::retry::RetryReader;
 
+use crate::chainstate::stacks::db::results::BlockResults;
+use crate::chainstate::stacks::db::state_diff::BlockStateDiff;
 use crate::chainstate::stacks::StacksBlockHeader;
 use crate::chainstate::stacks::TransactionPayload;
 use crate::codec::{
     read_next, write_next, Error as codec_error, StacksMessageCodec, MAX_MESSAGE_LEN,
     MAX_PAYLOAD_LEN,
 };
+use crate::core::mempool::SubnetFeeRateEstimate;
+use crate::core::mempool::{
+    MemPoolListCursor, MemPoolListFilter, MEMPOOL_LIST_DEFAULT_LIMIT, MEMPOOL_LIST_MAX_LIMIT,
+};
 use crate::types::chainstate::{BlockHeaderHash, StacksAddress, StacksBlockId};
 
 use super::FeeRateEstimateRequestBody;
@@ -103,6 +134,7 @@ use super::FeeRateEstimateRequestBody;
 const MAX_BLOCK_PROPOSAL_LENGTH: u32 = 1024 * 1024 * 15;
 
 pub const PATH_STR_POST_BLOCK_PROPOSAL: &'static str = "/v2/block_proposal";
+pub const PATH_STR_POST_VALIDATE_BLOCK_PROPOSAL: &'static str = "/v2/block_proposal/validate";
 
 lazy_static! {
     static ref PATH_GETINFO: Regex = Regex::new(r#"^/v2/info$"#).unwrap();
@@ -118,8 +150,17 @@ lazy_static! {
         Regex::new(r#"^/v2/microblocks/unconfirmed/([0-9a-f]{64})/([0-9]{1,5})$"#).unwrap();
     static ref PATH_GETTRANSACTION_UNCONFIRMED: Regex =
         Regex::new(r#"^/v2/transactions/unconfirmed/([0-9a-f]{64})$"#).unwrap();
+    static ref PATH_GET_TRANSACTION_PROOF: Regex = Regex::new(
+        "^/v2/transactions/proof/(?P<index_block_hash>[0-9a-f]{64})/(?P<txid>[0-9a-f]{64})$"
+    )
+    .unwrap();
     static ref PATH_POSTTRANSACTION: Regex = Regex::new(r#"^/v2/transactions$"#).unwrap();
+    static ref PATH_POST_TRANSACTION_BATCH: Regex =
+        Regex::new(r#"^/v2/transactions/batch$"#).unwrap();
+    static ref PATH_POST_TRANSACTION_SPONSORSHIP: Regex =
+        Regex::new(r#"^/v2/transactions/sponsor$"#).unwrap();
     static ref PATH_POST_FEE_RATE_ESIMATE: Regex = Regex::new(r#"^/v2/fees/transaction$"#).unwrap();
+    static ref PATH_POST_CLARITY_DATA_PROOF: Regex = Regex::new(r#"^/v2/clarity/proof$"#).unwrap();
     static ref PATH_POSTBLOCK: Regex = Regex::new(r#"^/v2/blocks/upload/([0-9a-f]{40})$"#).unwrap();
     static ref PATH_POSTMICROBLOCK: Regex = Regex::new(r#"^/v2/microblocks$"#).unwrap();
     static ref PATH_GET_STX_WITHDRAWAL: Regex = Regex::new(&format!(
@@ -127,8 +168,48 @@ lazy_static! {
         *PRINCIPAL_DATA_REGEX
     ))
     .unwrap();
+    static ref PATH_GET_WITHDRAWAL_PROOF: Regex = Regex::new(
+        "^/v2/withdrawal/proof/(?P<block_height>[0-9]+)/(?P<withdrawal_id>[0-9]+)$"
+    )
+    .unwrap();
+    static ref PATH_GET_WITHDRAWAL_STATUS: Regex =
+        Regex::new("^/v2/withdrawal/status/(?P<block_height>[0-9]+)$").unwrap();
+    static ref PATH_GET_DEPOSIT_STATUS: Regex =
+        Regex::new("^/v2/deposits/(0x)?(?P<l1_txid>[0-9a-f]{64})$").unwrap();
+    static ref PATH_GET_BLOCK_COSTS: Regex =
+        Regex::new("^/v2/blocks/(?P<block_id>[0-9a-f]{64})/costs$").unwrap();
+    static ref PATH_GET_BLOCK_HEADER: Regex =
+        Regex::new("^/v2/blocks/(?P<block_id>[0-9a-f]{64})/header$").unwrap();
+    static ref PATH_GET_BLOCK_STATE_DIFF: Regex =
+        Regex::new("^/v2/blocks/(?P<block_id>[0-9a-f]{64})/state-diff$").unwrap();
+    static ref PATH_GET_TRANSACTION_RECEIPT: Regex =
+        Regex::new("^/v2/transactions/(0x)?(?P<txid>[0-9a-f]{64})/receipt$").unwrap();
+    static ref PATH_GET_NONCE_GAPS: Regex = Regex::new(&format!(
+        "^/v2/accounts/(?P<address>{})/nonce-gaps$",
+        *STANDARD_PRINCIPAL_REGEX
+    ))
+    .unwrap();
+    static ref PATH_GET_SUBNET_STATUS: Regex = Regex::new("^/v2/subnet/status$").unwrap();
+    static ref PATH_POST_DIVERGENCE_SNAPSHOT: Regex =
+        Regex::new("^/v2/admin/divergence_snapshot$").unwrap();
+    static ref PATH_GET_MEMPOOL_TXS: Regex = Regex::new("^/v2/mempool$").unwrap();
+    static ref PATH_GET_MEMPOOL_SUMMARY: Regex = Regex::new("^/v2/mempool/summary$").unwrap();
+    static ref PATH_DELETE_MEMPOOL_TX: Regex =
+        Regex::new("^/v2/mempool/(0x)?(?P<txid>[0-9a-f]{64})$").unwrap();
+    static ref PATH_GET_ADMIN_PEERS: Regex = Regex::new("^/v2/admin/peers$").unwrap();
+    static ref PATH_GET_ADMIN_OBSERVERS: Regex = Regex::new("^/v2/admin/observers$").unwrap();
+    static ref PATH_GET_PEER_BANS: Regex = Regex::new("^/v2/admin/bans$").unwrap();
+    static ref PATH_POST_PEER_BAN: Regex = Regex::new("^/v2/admin/bans/add$").unwrap();
+    static ref PATH_DELETE_PEER_BAN: Regex =
+        Regex::new("^/v2/admin/bans/(?P<ban_id>[0-9]+)$").unwrap();
+    static ref PATH_GET_BLOCK_RESULTS: Regex =
+        Regex::new("^/v2/block_results/(?P<block_id>[0-9a-f]{64})$").unwrap();
+    static ref PATH_GET_BLOCK_RESULTS_BY_HEIGHT: Regex =
+        Regex::new("^/v2/block_results/height/(?P<block_height>[0-9]+)$").unwrap();
     static ref PATH_POST_BLOCK_PROPOSAL: Regex = Regex::new(&format!("^{}$", PATH_STR_POST_BLOCK_PROPOSAL))
     .unwrap();
+    static ref PATH_POST_VALIDATE_BLOCK_PROPOSAL: Regex =
+        Regex::new(&format!("^{}$", PATH_STR_POST_VALIDATE_BLOCK_PROPOSAL)).unwrap();
     static ref PATH_GET_NFT_WITHDRAWAL: Regex = Regex::new(&format!(
          "^/v2/withdrawal/nft/(?P<block_height>[0-9]+)/(?P<sender>{})/(?P<withdrawal_id>[0-9]+)/(?P<contract_address>{})/(?P<contract_name>{})/(?P<asset_name>{})/(?P<id>[0-9]+)$",
          *PRINCIPAL_DATA_REGEX,  *STANDARD_PRINCIPAL_REGEX, *CONTRACT_NAME_REGEX, *CLARITY_NAME_REGEX
@@ -149,11 +230,21 @@ lazy_static! {
         *STANDARD_PRINCIPAL_REGEX, *CONTRACT_NAME_REGEX, *CLARITY_NAME_REGEX
     ))
     .unwrap();
+    static ref PATH_GET_MAP_ENTRIES: Regex = Regex::new(&format!(
+        "^/v2/map_entries/(?P<address>{})/(?P<contract>{})/(?P<map>{})$",
+        *STANDARD_PRINCIPAL_REGEX, *CONTRACT_NAME_REGEX, *CLARITY_NAME_REGEX
+    ))
+    .unwrap();
     static ref PATH_POST_CALL_READ_ONLY: Regex = Regex::new(&format!(
         "^/v2/contracts/call-read/(?P<address>{})/(?P<contract>{})/(?P<function>{})$",
         *STANDARD_PRINCIPAL_REGEX, *CONTRACT_NAME_REGEX, *CLARITY_NAME_REGEX
     ))
     .unwrap();
+    static ref PATH_POST_TRACE_READ_ONLY: Regex = Regex::new(&format!(
+        "^/v2/contracts/trace/(?P<address>{})/(?P<contract>{})/(?P<function>{})$",
+        *STANDARD_PRINCIPAL_REGEX, *CONTRACT_NAME_REGEX, *CLARITY_NAME_REGEX
+    ))
+    .unwrap();
     static ref PATH_GET_CONTRACT_SRC: Regex = Regex::new(&format!(
         "^/v2/contracts/source/(?P<address>{})/(?P<contract>{})$",
         *STANDARD_PRINCIPAL_REGEX, *CONTRACT_NAME_REGEX
@@ -170,6 +261,8 @@ lazy_static! {
     ))
     .unwrap();
     static ref PATH_GET_TRANSFER_COST: Regex = Regex::new("^/v2/fees/transfer$").unwrap();
+    static ref PATH_GET_SUBNET_FEE_RATE_ESTIMATE: Regex =
+        Regex::new("^/v2/fees/subnet$").unwrap();
     static ref PATH_GET_ATTACHMENTS_INV: Regex = Regex::new("^/v2/attachments/inv$").unwrap();
     static ref PATH_GET_ATTACHMENT: Regex =
         Regex::new(r#"^/v2/attachments/([0-9a-f]{40})$"#).unwrap();
@@ -1552,6 +1645,26 @@ impl HttpRequestType {
                 &PATH_POSTTRANSACTION,
                 &HttpRequestType::parse_posttransaction,
             ),
+            (
+                "POST",
+                &PATH_POST_TRANSACTION_BATCH,
+                &HttpRequestType::parse_post_transaction_batch,
+            ),
+            (
+                "POST",
+                &PATH_POST_TRANSACTION_SPONSORSHIP,
+                &HttpRequestType::parse_post_transaction_sponsorship,
+            ),
+            (
+                "POST",
+                &PATH_POST_CLARITY_DATA_PROOF,
+                &HttpRequestType::parse_get_clarity_data_proof,
+            ),
+            (
+                "GET",
+                &PATH_GET_TRANSACTION_PROOF,
+                &HttpRequestType::parse_get_transaction_proof,
+            ),
             ("POST", &PATH_POSTBLOCK, &HttpRequestType::parse_postblock),
             (
                 "POST",
@@ -1573,11 +1686,21 @@ impl HttpRequestType {
                 &PATH_GET_MAP_ENTRY,
                 &HttpRequestType::parse_get_map_entry,
             ),
+            (
+                "GET",
+                &PATH_GET_MAP_ENTRIES,
+                &HttpRequestType::parse_get_map_entries,
+            ),
             (
                 "GET",
                 &PATH_GET_TRANSFER_COST,
                 &HttpRequestType::parse_get_transfer_cost,
             ),
+            (
+                "GET",
+                &PATH_GET_SUBNET_FEE_RATE_ESTIMATE,
+                &HttpRequestType::parse_get_subnet_fee_rate_estimate,
+            ),
             (
                 "GET",
                 &PATH_GET_CONTRACT_SRC,
@@ -1598,6 +1721,11 @@ impl HttpRequestType {
                 &PATH_POST_CALL_READ_ONLY,
                 &HttpRequestType::parse_call_read_only,
             ),
+            (
+                "POST",
+                &PATH_POST_TRACE_READ_ONLY,
+                &HttpRequestType::parse_trace_read_only,
+            ),
             (
                 "OPTIONS",
                 &PATH_OPTIONS_WILDCARD,
@@ -1628,11 +1756,116 @@ impl HttpRequestType {
                 &PATH_POST_BLOCK_PROPOSAL,
                 &HttpRequestType::parse_block_proposal,
             ),
+            (
+                "POST",
+                &PATH_POST_VALIDATE_BLOCK_PROPOSAL,
+                &HttpRequestType::parse_validate_block_proposal,
+            ),
             (
                 "GET",
                 &PATH_GET_NFT_WITHDRAWAL,
                 &HttpRequestType::parse_get_nft_withdrawal,
             ),
+            (
+                "GET",
+                &PATH_GET_WITHDRAWAL_PROOF,
+                &HttpRequestType::parse_get_withdrawal_proof,
+            ),
+            (
+                "GET",
+                &PATH_GET_WITHDRAWAL_STATUS,
+                &HttpRequestType::parse_get_withdrawal_status,
+            ),
+            (
+                "GET",
+                &PATH_GET_DEPOSIT_STATUS,
+                &HttpRequestType::parse_get_deposit_status,
+            ),
+            (
+                "GET",
+                &PATH_GET_BLOCK_COSTS,
+                &HttpRequestType::parse_get_block_costs,
+            ),
+            (
+                "GET",
+                &PATH_GET_BLOCK_HEADER,
+                &HttpRequestType::parse_get_block_header,
+            ),
+            (
+                "GET",
+                &PATH_GET_BLOCK_STATE_DIFF,
+                &HttpRequestType::parse_get_block_state_diff,
+            ),
+            (
+                "GET",
+                &PATH_GET_TRANSACTION_RECEIPT,
+                &HttpRequestType::parse_get_transaction_receipt,
+            ),
+            (
+                "GET",
+                &PATH_GET_NONCE_GAPS,
+                &HttpRequestType::parse_get_nonce_gaps,
+            ),
+            (
+                "GET",
+                &PATH_GET_SUBNET_STATUS,
+                &HttpRequestType::parse_get_subnet_status,
+            ),
+            (
+                "POST",
+                &PATH_POST_DIVERGENCE_SNAPSHOT,
+                &HttpRequestType::parse_post_divergence_snapshot,
+            ),
+            (
+                "GET",
+                &PATH_GET_MEMPOOL_TXS,
+                &HttpRequestType::parse_get_mempool_txs,
+            ),
+            (
+                "GET",
+                &PATH_GET_MEMPOOL_SUMMARY,
+                &HttpRequestType::parse_get_mempool_summary,
+            ),
+            (
+                "DELETE",
+                &PATH_DELETE_MEMPOOL_TX,
+                &HttpRequestType::parse_delete_mempool_tx,
+            ),
+            (
+                "GET",
+                &PATH_GET_ADMIN_PEERS,
+                &HttpRequestType::parse_get_admin_peers,
+            ),
+            (
+                "GET",
+                &PATH_GET_ADMIN_OBSERVERS,
+                &HttpRequestType::parse_get_admin_observers,
+            ),
+            (
+                "GET",
+                &PATH_GET_PEER_BANS,
+                &HttpRequestType::parse_get_peer_bans,
+            ),
+            (
+                "POST",
+                &PATH_POST_PEER_BAN,
+                &HttpRequestType::parse_post_peer_ban,
+            ),
+            (
+                "DELETE",
+                &PATH_DELETE_PEER_BAN,
+                &HttpRequestType::parse_delete_peer_ban,
+            ),
+            (
+                "GET",
+                &PATH_GET_BLOCK_RESULTS,
+                &HttpRequestType::parse_get_block_results,
+            ),
+            (
+                "GET",
+                &PATH_GET_BLOCK_RESULTS_BY_HEIGHT,
+                &HttpRequestType::parse_get_block_results_by_height,
+            ),
         ];
 
         // use url::Url to parse path and query string
@@ -1734,6 +1967,25 @@ impl HttpRequestType {
         ))
     }
 
+    fn parse_get_subnet_fee_rate_estimate<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetSubnetFeeRateEstimate"
+                    .to_string(),
+            ));
+        }
+
+        Ok(HttpRequestType::GetSubnetFeeRateEstimate(
+            HttpRequestMetadata::from_preamble(preamble),
+        ))
+    }
+
     /// Check whether the given option query string sets proof=0 (setting proof to false).
     /// Defaults to true.
     fn get_proof_query(query: Option<&str>) -> bool {
@@ -1749,6 +2001,19 @@ impl HttpRequestType {
         !no_proof
     }
 
+    /// Check whether the given option query string sets diagnostics=1.
+    /// Defaults to false.
+    fn get_diagnostics_query(query: Option<&str>) -> bool {
+        if let Some(query_string) = query {
+            form_urlencoded::parse(query_string.as_bytes())
+                .find(|(key, _v)| key == "diagnostics")
+                .map(|(_k, value)| value == "1")
+                .unwrap_or(false)
+        } else {
+            false
+        }
+    }
+
     /// get the chain tip optional query argument (`tip`)
     /// Take the first value we can parse.
     fn get_chain_tip_query(query: Option<&str>) -> TipRequest {
@@ -1774,6 +2039,21 @@ impl HttpRequestType {
         }
     }
 
+    /// get the mempool bloom counter depth optional query argument (`bloom_depth`)
+    /// Take the first value we can parse.
+    fn get_mempool_bloom_depth_query(query: Option<&str>) -> Option<u64> {
+        let query_string = query?;
+        for (key, value) in form_urlencoded::parse(query_string.as_bytes()) {
+            if key != "bloom_depth" {
+                continue;
+            }
+            if let Ok(depth) = value.parse::<u64>() {
+                return Some(depth);
+            }
+        }
+        None
+    }
+
     /// get the mempool page ID optional query argument (`page_id`)
     /// Take the first value we can parse.
     fn get_mempool_page_id_query(query: Option<&str>) -> Option<Txid> {
@@ -1858,7 +2138,7 @@ impl HttpRequestType {
         })
     }
 
-    fn parse_get_nft_withdrawal<R: Read>(
+    fn parse_get_withdrawal_proof<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
@@ -1867,523 +2147,441 @@ impl HttpRequestType {
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetAccount".to_string(),
+                "Invalid Http request: expected 0-length body for GetWithdrawalProof".to_string(),
             ));
         }
 
-        let sender = PrincipalData::parse(&captures["sender"]).map_err(|_e| {
-            net_error::DeserializeError("Failed to parse account principal".into())
-        })?;
-
         let withdraw_block_height = u64::from_str(&captures["block_height"])
             .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
 
         let withdrawal_id = u32::from_str(&captures["withdrawal_id"])
-            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
-        let contract_addr =
-            StacksAddress::from_string(&captures["contract_address"]).ok_or_else(|| {
-                net_error::DeserializeError("Failed to parse contract address".into())
-            })?;
-        let contract_name = ContractName::try_from(captures["contract_name"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
-        let asset_name = ClarityName::try_from(captures["asset_name"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse data var name".into()))?;
-        let id = u128::from_str(&captures["id"])
-            .map_err(|_e| net_error::DeserializeError("Failed to parse amount".into()))?;
+            .map_err(|_e| net_error::DeserializeError("Failed to parse withdrawal ID".into()))?;
 
-        Ok(HttpRequestType::GetWithdrawalNft {
+        Ok(HttpRequestType::GetWithdrawalProof {
             metadata: HttpRequestMetadata::from_preamble(preamble),
             withdraw_block_height,
-            sender,
             withdrawal_id,
-            asset_identifier: AssetIdentifier {
-                contract_identifier: QualifiedContractIdentifier::new(
-                    contract_addr.into(),
-                    contract_name,
-                ),
-                asset_name,
-            },
-            id,
         })
     }
 
-    fn parse_get_data_var<R: Read>(
+    fn parse_get_transaction_proof<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
-        query: Option<&str>,
+        _query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let content_len = preamble.get_content_length();
-        if content_len != 0 {
-            return Err(net_error::DeserializeError(format!(
-                "Invalid Http request: invalid body length for GetDataVar ({})",
-                content_len
-            )));
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetTransactionProof".to_string(),
+            ));
         }
 
-        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
-            net_error::DeserializeError("Failed to parse contract address".into())
-        })?;
-        let contract_name = ContractName::try_from(captures["contract"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
-        let var_name = ClarityName::try_from(captures["varname"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse data var name".into()))?;
+        let index_block_hash =
+            StacksBlockId::from_hex(&captures["index_block_hash"]).map_err(|_e| {
+                net_error::DeserializeError("Failed to decode index block hash".into())
+            })?;
 
-        let with_proof = HttpRequestType::get_proof_query(query);
-        let tip = HttpRequestType::get_chain_tip_query(query);
+        let txid = Txid::from_hex(&captures["txid"])
+            .map_err(|_e| net_error::DeserializeError("Failed to decode txid hex".into()))?;
 
-        Ok(HttpRequestType::GetDataVar(
-            HttpRequestMetadata::from_preamble(preamble),
-            contract_addr,
-            contract_name,
-            var_name,
-            tip,
-            with_proof,
-        ))
+        Ok(HttpRequestType::GetTransactionProof {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            index_block_hash,
+            txid,
+        })
     }
 
-    fn parse_get_map_entry<R: Read>(
+    fn parse_get_deposit_status<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
-        query: Option<&str>,
-        fd: &mut R,
+        _query: Option<&str>,
+        _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let content_len = preamble.get_content_length();
-        if !(content_len > 0 && content_len < (BOUND_VALUE_SERIALIZATION_HEX)) {
-            return Err(net_error::DeserializeError(format!(
-                "Invalid Http request: invalid body length for GetMapEntry ({})",
-                content_len
-            )));
-        }
-
-        if preamble.content_type != Some(HttpContentType::JSON) {
+        if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid content-type: expected application/json".into(),
+                "Invalid Http request: expected 0-length body for GetDepositStatus".to_string(),
             ));
         }
 
-        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
-            net_error::DeserializeError("Failed to parse contract address".into())
-        })?;
-        let contract_name = ContractName::try_from(captures["contract"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
-        let map_name = ClarityName::try_from(captures["map"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse map name".into()))?;
-
-        let value_hex: String = serde_json::from_reader(fd)
-            .map_err(|_e| net_error::DeserializeError("Failed to parse JSON body".into()))?;
-
-        let value = Value::try_deserialize_hex_untyped(&value_hex)
-            .map_err(|_e| net_error::DeserializeError("Failed to deserialize key value".into()))?;
-
-        let with_proof = HttpRequestType::get_proof_query(query);
-        let tip = HttpRequestType::get_chain_tip_query(query);
+        let l1_txid = Txid::from_hex(&captures["l1_txid"])
+            .map_err(|_e| net_error::DeserializeError("Failed to decode L1 txid hex".into()))?;
 
-        Ok(HttpRequestType::GetMapEntry(
-            HttpRequestMetadata::from_preamble(preamble),
-            contract_addr,
-            contract_name,
-            map_name,
-            value,
-            tip,
-            with_proof,
-        ))
+        Ok(HttpRequestType::GetDepositStatus {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            l1_txid,
+        })
     }
 
-    fn parse_call_read_only<R: Read>(
-        protocol: &mut StacksHttp,
+    fn parse_get_block_costs<R: Read>(
+        _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
-        query: Option<&str>,
-        fd: &mut R,
+        _query: Option<&str>,
+        _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let content_len = preamble.get_content_length();
-        if !(content_len > 0 && content_len < protocol.maximum_call_argument_size) {
-            return Err(net_error::DeserializeError(format!(
-                "Invalid Http request: invalid body length for CallReadOnly ({})",
-                content_len
-            )));
-        }
-
-        if preamble.content_type != Some(HttpContentType::JSON) {
+        if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid content-type: expected application/json".to_string(),
+                "Invalid Http request: expected 0-length body for GetBlockCosts".to_string(),
             ));
         }
 
-        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
-            net_error::DeserializeError("Failed to parse contract address".into())
-        })?;
-        let contract_name = ContractName::try_from(captures["contract"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
-        let func_name = ClarityName::try_from(captures["function"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+        let index_block_hash = StacksBlockId::from_hex(&captures["block_id"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block hash".into()))?;
 
-        let body: CallReadOnlyRequestBody = serde_json::from_reader(fd)
-            .map_err(|_e| net_error::DeserializeError("Failed to parse JSON body".into()))?;
-
-        let sender = PrincipalData::parse(&body.sender)
-            .map_err(|_e| net_error::DeserializeError("Failed to parse sender principal".into()))?;
-
-        let arguments = body
-            .arguments
-            .into_iter()
-            .map(|hex| Value::try_deserialize_hex_untyped(&hex).ok())
-            .collect::<Option<Vec<Value>>>()
-            .ok_or_else(|| {
-                net_error::DeserializeError("Failed to deserialize argument value".into())
-            })?;
-
-        let tip = HttpRequestType::get_chain_tip_query(query);
-
-        Ok(HttpRequestType::CallReadOnlyFunction(
-            HttpRequestMetadata::from_preamble(preamble),
-            contract_addr,
-            contract_name,
-            sender,
-            func_name,
-            arguments,
-            tip,
-        ))
+        Ok(HttpRequestType::GetBlockCosts {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            index_block_hash,
+        })
     }
 
-    fn parse_block_proposal<R: Read>(
+    fn parse_get_block_header<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        _captures: &Captures,
+        captures: &Captures,
         _query: Option<&str>,
-        fd: &mut R,
+        _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let content_len = preamble.get_content_length();
-        if !(content_len > 0 && content_len < MAX_BLOCK_PROPOSAL_LENGTH) {
-            return Err(net_error::DeserializeError(format!(
-                "Invalid Http request: invalid body length for BlockProposal ({})",
-                content_len
-            )));
-        }
-
-        if preamble.content_type != Some(HttpContentType::JSON) {
+        if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid content-type: expected application/json".to_string(),
+                "Invalid Http request: expected 0-length body for GetBlockHeader".to_string(),
             ));
         }
 
-        let block_proposal: Proposal = serde_json::from_reader(fd).map_err(|_e| {
-            net_error::DeserializeError("Failed to parse block proposal JSON body".into())
-        })?;
+        let index_block_hash = StacksBlockId::from_hex(&captures["block_id"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block hash".into()))?;
 
-        Ok(HttpRequestType::BlockProposal(
-            HttpRequestMetadata::from_preamble(preamble),
-            block_proposal,
-        ))
+        Ok(HttpRequestType::GetBlockHeader {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            index_block_hash,
+        })
     }
 
-    fn parse_get_contract_arguments(
+    fn parse_get_block_state_diff<R: Read>(
+        _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
-    ) -> Result<(HttpRequestMetadata, StacksAddress, ContractName), net_error> {
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body".to_string(),
+                "Invalid Http request: expected 0-length body for GetBlockStateDiff".to_string(),
             ));
         }
 
-        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
-            net_error::DeserializeError("Failed to parse contract address".into())
-        })?;
-        let contract_name = ContractName::try_from(captures["contract"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+        let index_block_hash = StacksBlockId::from_hex(&captures["block_id"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block hash".into()))?;
 
-        Ok((
-            HttpRequestMetadata::from_preamble(preamble),
-            contract_addr,
-            contract_name,
-        ))
+        Ok(HttpRequestType::GetBlockStateDiff {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            index_block_hash,
+        })
     }
 
-    fn parse_get_contract_abi<R: Read>(
+    fn parse_get_transaction_receipt<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
-        query: Option<&str>,
+        _query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let tip = HttpRequestType::get_chain_tip_query(query);
-        HttpRequestType::parse_get_contract_arguments(preamble, captures).map(
-            |(preamble, addr, name)| HttpRequestType::GetContractABI(preamble, addr, name, tip),
-        )
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetTransactionReceipt"
+                    .to_string(),
+            ));
+        }
+
+        let txid = Txid::from_hex(&captures["txid"])
+            .map_err(|_e| net_error::DeserializeError("Failed to decode txid hex".into()))?;
+
+        Ok(HttpRequestType::GetTransactionReceipt {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            txid,
+        })
     }
 
-    fn parse_get_contract_source<R: Read>(
+    fn parse_get_nonce_gaps<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
         query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let with_proof = HttpRequestType::get_proof_query(query);
-        let tip = HttpRequestType::get_chain_tip_query(query);
-        HttpRequestType::parse_get_contract_arguments(preamble, captures).map(
-            |(preamble, addr, name)| {
-                HttpRequestType::GetContractSrc(preamble, addr, name, tip, with_proof)
-            },
-        )
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetNonceGaps".to_string(),
+            ));
+        }
+
+        let address = StacksAddress::from_string(&captures["address"])
+            .ok_or_else(|| net_error::DeserializeError("Failed to parse account address".into()))?;
+
+        Ok(HttpRequestType::GetNonceGaps {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            address,
+            tip_req: HttpRequestType::get_chain_tip_query(query),
+        })
     }
 
-    fn parse_get_is_trait_implemented<R: Read>(
+    fn parse_get_subnet_status<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        captures: &Captures,
-        query: Option<&str>,
+        _captures: &Captures,
+        _query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let tip = HttpRequestType::get_chain_tip_query(query);
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body".to_string(),
+                "Invalid Http request: expected 0-length body for GetSubnetStatus".to_string(),
             ));
         }
 
-        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
-            net_error::DeserializeError("Failed to parse contract address".into())
-        })?;
-        let contract_name = ContractName::try_from(captures["contract"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
-        let trait_name = ClarityName::try_from(captures["traitName"].to_string())
-            .map_err(|_e| net_error::DeserializeError("Failed to parse trait name".into()))?;
-        let trait_contract_addr = StacksAddress::from_string(&captures["traitContractAddr"])
-            .ok_or_else(|| net_error::DeserializeError("Failed to parse contract address".into()))?
-            .into();
-        let trait_contract_name = ContractName::try_from(captures["traitContractName"].to_string())
-            .map_err(|_e| {
-                net_error::DeserializeError("Failed to parse trait contract name".into())
-            })?;
-        let trait_id = TraitIdentifier::new(trait_contract_addr, trait_contract_name, trait_name);
-
-        Ok(HttpRequestType::GetIsTraitImplemented(
+        Ok(HttpRequestType::GetSubnetStatus(
             HttpRequestMetadata::from_preamble(preamble),
-            contract_addr,
-            contract_name,
-            trait_id,
-            tip,
         ))
     }
 
-    fn parse_getheaders<R: Read>(
+    fn parse_post_divergence_snapshot<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        captures: &Captures,
-        query: Option<&str>,
+        _captures: &Captures,
+        _query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetBlock".to_string(),
+                "Invalid Http request: expected 0-length body for PostDivergenceSnapshot"
+                    .to_string(),
             ));
         }
 
-        let quantity_str = captures
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to reward cycle group".to_string(),
-            ))?
-            .as_str();
-
-        let quantity: u64 = quantity_str
-            .parse()
-            .map_err(|_| net_error::DeserializeError("Failed to parse reward cycle".to_string()))?;
+        Ok(HttpRequestType::PostDivergenceSnapshot {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            auth_token: preamble.headers.get("authorization").cloned(),
+        })
+    }
 
-        let tip = HttpRequestType::get_chain_tip_query(query);
+    /// Get the mempool listing filter query arguments (`origin`, `min_fee`, `max_fee` and
+    /// `payload_type`)
+    fn get_mempool_list_filter_query(query: Option<&str>) -> Result<MemPoolListFilter, net_error> {
+        let mut filter = MemPoolListFilter::default();
+        let query_string = match query {
+            Some(query_string) => query_string,
+            None => return Ok(filter),
+        };
+        for (key, value) in form_urlencoded::parse(query_string.as_bytes()) {
+            match key.as_ref() {
+                "origin" => {
+                    filter.origin = Some(StacksAddress::from_string(&value).ok_or_else(|| {
+                        net_error::DeserializeError("Failed to parse origin address".into())
+                    })?);
+                }
+                "min_fee" => {
+                    filter.min_fee = Some(u64::from_str(&value).map_err(|_e| {
+                        net_error::DeserializeError("Failed to parse min_fee".into())
+                    })?);
+                }
+                "max_fee" => {
+                    filter.max_fee = Some(u64::from_str(&value).map_err(|_e| {
+                        net_error::DeserializeError("Failed to parse max_fee".into())
+                    })?);
+                }
+                "payload_type" => match value.as_ref() {
+                    "TokenTransfer" | "ContractCall" | "SmartContract" | "PoisonMicroblock"
+                    | "Coinbase" => {
+                        filter.payload_type = Some(value.to_string());
+                    }
+                    _ => {
+                        return Err(net_error::DeserializeError("Unknown payload_type".into()));
+                    }
+                },
+                _ => {}
+            }
+        }
+        Ok(filter)
+    }
 
-        Ok(HttpRequestType::GetHeaders(
-            HttpRequestMetadata::from_preamble(preamble),
-            quantity,
-            tip,
-        ))
+    fn make_mempool_list_filter_query_string(filter: &MemPoolListFilter) -> Vec<String> {
+        let mut args = vec![];
+        if let Some(origin) = filter.origin.as_ref() {
+            args.push(format!("origin={}", origin));
+        }
+        if let Some(min_fee) = filter.min_fee {
+            args.push(format!("min_fee={}", min_fee));
+        }
+        if let Some(max_fee) = filter.max_fee {
+            args.push(format!("max_fee={}", max_fee));
+        }
+        if let Some(payload_type) = filter.payload_type.as_ref() {
+            args.push(format!("payload_type={}", payload_type));
+        }
+        args
     }
 
-    fn parse_getblock<R: Read>(
+    fn parse_get_mempool_txs<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        captures: &Captures,
-        _query: Option<&str>,
+        _captures: &Captures,
+        query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetBlock".to_string(),
+                "Invalid Http request: expected 0-length body for GetMemPoolTxs".to_string(),
             ));
         }
 
-        let block_hash_str = captures
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to block hash group".to_string(),
-            ))?
-            .as_str();
-
-        let block_hash = StacksBlockId::from_hex(block_hash_str)
-            .map_err(|_e| net_error::DeserializeError("Failed to parse block hash".to_string()))?;
+        let filter = HttpRequestType::get_mempool_list_filter_query(query)?;
+        let mut cursor = None;
+        let mut limit = MEMPOOL_LIST_DEFAULT_LIMIT;
+        if let Some(query_string) = query {
+            for (key, value) in form_urlencoded::parse(query_string.as_bytes()) {
+                if key == "cursor" {
+                    cursor = Some(MemPoolListCursor::from_str(&value).ok_or_else(|| {
+                        net_error::DeserializeError("Failed to parse cursor".into())
+                    })?);
+                } else if key == "limit" {
+                    limit = u32::from_str(&value).map_err(|_e| {
+                        net_error::DeserializeError("Failed to parse limit".into())
+                    })?;
+                }
+            }
+        }
+        if limit == 0 || limit > MEMPOOL_LIST_MAX_LIMIT {
+            return Err(net_error::DeserializeError(format!(
+                "limit must be between 1 and {}",
+                MEMPOOL_LIST_MAX_LIMIT
+            )));
+        }
 
-        Ok(HttpRequestType::GetBlock(
-            HttpRequestMetadata::from_preamble(preamble),
-            block_hash,
-        ))
+        Ok(HttpRequestType::GetMemPoolTxs {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            filter,
+            cursor,
+            limit,
+        })
     }
 
-    fn parse_getmicroblocks_indexed<R: Read>(
+    fn parse_get_mempool_summary<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        captures: &Captures,
-        _query: Option<&str>,
+        _captures: &Captures,
+        query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetMicroblocksIndexed"
-                    .to_string(),
+                "Invalid Http request: expected 0-length body for GetMemPoolSummary".to_string(),
             ));
         }
 
-        let block_hash_str = captures
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to microblock hash group".to_string(),
-            ))?
-            .as_str();
-
-        let block_hash = StacksBlockId::from_hex(block_hash_str).map_err(|_e| {
-            net_error::DeserializeError("Failed to parse microblock hash".to_string())
-        })?;
-
-        Ok(HttpRequestType::GetMicroblocksIndexed(
-            HttpRequestMetadata::from_preamble(preamble),
-            block_hash,
-        ))
+        Ok(HttpRequestType::GetMemPoolSummary {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            filter: HttpRequestType::get_mempool_list_filter_query(query)?,
+        })
     }
 
-    fn parse_getmicroblocks_confirmed<R: Read>(
+    fn parse_delete_mempool_tx<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
-        _query: Option<&str>,
+        query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetMicrolocks".to_string(),
+                "Invalid Http request: expected 0-length body for DeleteMemPoolTx".to_string(),
             ));
         }
 
-        let block_hash_str = captures
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to microblock hash group".to_string(),
-            ))?
-            .as_str();
+        let txid = Txid::from_hex(&captures["txid"])
+            .map_err(|_e| net_error::DeserializeError("Failed to decode txid hex".into()))?;
 
-        let block_hash = StacksBlockId::from_hex(block_hash_str).map_err(|_e| {
-            net_error::DeserializeError("Failed to parse microblock hash".to_string())
-        })?;
+        let reason = query.and_then(|query_string| {
+            form_urlencoded::parse(query_string.as_bytes())
+                .find(|(key, _v)| key == "reason")
+                .map(|(_k, value)| value.to_string())
+        });
 
-        Ok(HttpRequestType::GetMicroblocksConfirmed(
-            HttpRequestMetadata::from_preamble(preamble),
-            block_hash,
-        ))
+        Ok(HttpRequestType::DeleteMemPoolTx {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            txid,
+            reason,
+            auth_token: preamble.headers.get("authorization").cloned(),
+        })
     }
 
-    fn parse_getmicroblocks_unconfirmed<R: Read>(
+    fn parse_get_admin_peers<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        captures: &Captures,
+        _captures: &Captures,
         _query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetMicrolocksUnconfirmed"
-                    .to_string(),
+                "Invalid Http request: expected 0-length body for GetAdminPeers".to_string(),
             ));
         }
 
-        let block_hash_str = captures
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to microblock hash group".to_string(),
-            ))?
-            .as_str();
-
-        let min_seq_str = captures
-            .get(2)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to microblock minimum sequence group".to_string(),
-            ))?
-            .as_str();
-
-        let block_hash = StacksBlockId::from_hex(block_hash_str).map_err(|_e| {
-            net_error::DeserializeError("Failed to parse microblock hash".to_string())
-        })?;
-
-        let min_seq = min_seq_str.parse::<u16>().map_err(|_e| {
-            net_error::DeserializeError("Failed to parse microblock minimum sequence".to_string())
-        })?;
-
-        Ok(HttpRequestType::GetMicroblocksUnconfirmed(
-            HttpRequestMetadata::from_preamble(preamble),
-            block_hash,
-            min_seq,
-        ))
+        Ok(HttpRequestType::GetAdminPeers {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            auth_token: preamble.headers.get("authorization").cloned(),
+        })
     }
 
-    fn parse_gettransaction_unconfirmed<R: Read>(
+    fn parse_get_admin_observers<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        regex: &Captures,
+        _captures: &Captures,
         _query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body for GetMicrolocksUnconfirmed"
-                    .to_string(),
+                "Invalid Http request: expected 0-length body for GetAdminObservers".to_string(),
             ));
         }
 
-        let txid_hex = regex
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to txid group".to_string(),
-            ))?
-            .as_str();
+        Ok(HttpRequestType::GetAdminObservers {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            auth_token: preamble.headers.get("authorization").cloned(),
+        })
+    }
 
-        if txid_hex.len() != 64 {
+    fn parse_get_peer_bans<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid txid: expected 64 bytes".to_string(),
+                "Invalid Http request: expected 0-length body for GetPeerBans".to_string(),
             ));
         }
 
-        let txid = Txid::from_hex(&txid_hex)
-            .map_err(|_e| net_error::DeserializeError("Failed to decode txid hex".to_string()))?;
-
-        Ok(HttpRequestType::GetTransactionUnconfirmed(
-            HttpRequestMetadata::from_preamble(preamble),
-            txid,
-        ))
+        Ok(HttpRequestType::GetPeerBans {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            auth_token: preamble.headers.get("authorization").cloned(),
+        })
     }
 
-    fn parse_post_fee_rate_estimate<R: Read>(
+    fn parse_post_peer_ban<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        _regex: &Captures,
+        _captures: &Captures,
         _query: Option<&str>,
         fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         let content_len = preamble.get_content_length();
         if !(content_len > 0 && content_len < MAX_PAYLOAD_LEN) {
             return Err(net_error::DeserializeError(format!(
-                "Invalid Http request: invalid body length for FeeRateEstimate ({})",
+                "Invalid Http request: invalid body length for PostPeerBan ({})",
                 content_len
             )));
         }
@@ -2395,490 +2593,1545 @@ impl HttpRequestType {
         }
 
         let bound_fd = BoundReader::from_reader(fd, content_len as u64);
-
-        let body: FeeRateEstimateRequestBody = serde_json::from_reader(bound_fd).map_err(|e| {
+        let body: PeerBanRequestBody = serde_json::from_reader(bound_fd).map_err(|e| {
             net_error::DeserializeError(format!("Failed to parse JSON body: {}", e))
         })?;
-
-        let payload_hex = if body.transaction_payload.starts_with("0x") {
-            &body.transaction_payload[2..]
-        } else {
-            &body.transaction_payload
+        let target = match (body.public_key_hash, body.address_range) {
+            (Some(public_key_hash), None) => {
+                let hash = Hash160::from_hex(&public_key_hash).map_err(|_e| {
+                    net_error::DeserializeError(format!(
+                        "Invalid public key hash: {}",
+                        &public_key_hash
+                    ))
+                })?;
+                PeerBanTarget::PublicKeyHash(hash)
+            }
+            (None, Some(address_range)) => PeerBanTarget::from_address_range(&address_range)
+                .ok_or_else(|| {
+                    net_error::DeserializeError(format!(
+                        "Invalid address range: {}",
+                        &address_range
+                    ))
+                })?,
+            _ => {
+                return Err(net_error::DeserializeError(
+                    "Expected exactly one of public_key_hash and address_range".to_string(),
+                ));
+            }
         };
 
-        let payload_data = hex_bytes(payload_hex).map_err(|_e| {
-            net_error::DeserializeError("Bad hex string supplied for transaction payload".into())
-        })?;
-
-        let payload = TransactionPayload::consensus_deserialize(&mut payload_data.as_slice())
-            .map_err(|e| {
-                net_error::DeserializeError(format!(
-                    "Failed to deserialize transaction payload: {}",
-                    e
-                ))
-            })?;
-
-        let estimated_len =
-            std::cmp::max(body.estimated_len.unwrap_or(0), payload_data.len() as u64);
-
-        Ok(HttpRequestType::FeeRateEstimate(
-            HttpRequestMetadata::from_preamble(preamble),
-            payload,
-            estimated_len,
-        ))
+        Ok(HttpRequestType::PostPeerBan {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            target,
+            duration_secs: body.duration_secs,
+            reason: body.reason,
+            auth_token: preamble.headers.get("authorization").cloned(),
+        })
     }
 
-    fn parse_posttransaction<R: Read>(
+    fn parse_delete_peer_ban<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        _regex: &Captures,
+        captures: &Captures,
         _query: Option<&str>,
-        fd: &mut R,
+        _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        if preamble.get_content_length() == 0 {
+        if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected non-zero-length body for PostTransaction"
-                    .to_string(),
+                "Invalid Http request: expected 0-length body for DeletePeerBan".to_string(),
             ));
         }
 
-        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+        let ban_id = captures["ban_id"]
+            .parse::<u64>()
+            .map_err(|_e| net_error::DeserializeError("Failed to parse ban ID".into()))?;
+
+        Ok(HttpRequestType::DeletePeerBan {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            ban_id,
+            auth_token: preamble.headers.get("authorization").cloned(),
+        })
+    }
+
+    fn parse_get_withdrawal_status<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: PostTransaction body is too big".to_string(),
+                "Invalid Http request: expected 0-length body for GetWithdrawalStatus".to_string(),
             ));
         }
 
-        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+        let withdraw_block_height = u64::from_str(&captures["block_height"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
 
-        match preamble.content_type {
-            None => {
-                return Err(net_error::DeserializeError(
-                    "Missing Content-Type for transaction".to_string(),
-                ));
-            }
-            Some(HttpContentType::Bytes) => {
-                HttpRequestType::parse_posttransaction_octets(preamble, &mut bound_fd)
-            }
-            Some(HttpContentType::JSON) => {
-                HttpRequestType::parse_posttransaction_json(preamble, &mut bound_fd)
-            }
-            _ => {
-                return Err(net_error::DeserializeError(
-                    "Wrong Content-Type for transaction; expected application/json".to_string(),
-                ));
-            }
-        }
+        Ok(HttpRequestType::GetWithdrawalStatus {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            withdraw_block_height,
+        })
     }
 
-    fn parse_posttransaction_octets<R: Read>(
+    fn parse_get_block_results<R: Read>(
+        _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        fd: &mut R,
+        captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let tx = StacksTransaction::consensus_deserialize(fd).map_err(|e| {
-            if let codec_error::DeserializeError(msg) = e {
-                net_error::ClientError(ClientError::Message(format!(
-                    "Failed to deserialize posted transaction: {}",
-                    msg
-                )))
-            } else {
-                e.into()
-            }
-        })?;
-        Ok(HttpRequestType::PostTransaction(
-            HttpRequestMetadata::from_preamble(preamble),
-            tx,
-            None,
-        ))
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetBlockResults".to_string(),
+            ));
+        }
+
+        let index_block_hash = StacksBlockId::from_hex(&captures["block_id"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block hash".into()))?;
+
+        Ok(HttpRequestType::GetBlockResults {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            block: BlockResultsQuery::IndexBlockHash(index_block_hash),
+        })
     }
 
-    fn parse_posttransaction_json<R: Read>(
+    fn parse_get_block_results_by_height<R: Read>(
+        _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        fd: &mut R,
+        captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        let body: PostTransactionRequestBody = serde_json::from_reader(fd)
-            .map_err(|_e| net_error::DeserializeError("Failed to parse body".into()))?;
-
-        let tx = {
-            let tx_bytes = hex_bytes(&body.tx)
-                .map_err(|_e| net_error::DeserializeError("Failed to parse tx".into()))?;
-            StacksTransaction::consensus_deserialize(&mut &tx_bytes[..]).map_err(|e| {
-                if let codec_error::DeserializeError(msg) = e {
-                    net_error::ClientError(ClientError::Message(format!(
-                        "Failed to deserialize posted transaction: {}",
-                        msg
-                    )))
-                } else {
-                    e.into()
-                }
-            })
-        }?;
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetBlockResults".to_string(),
+            ));
+        }
 
-        let attachment = match body.attachment {
-            None => None,
-            Some(attachment_content) => {
-                let content = hex_bytes(&attachment_content).map_err(|_e| {
-                    net_error::DeserializeError("Failed to parse attachment".into())
-                })?;
-                Some(Attachment::new(content))
-            }
-        };
+        let block_height = u64::from_str(&captures["block_height"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
 
-        Ok(HttpRequestType::PostTransaction(
-            HttpRequestMetadata::from_preamble(preamble),
-            tx,
-            attachment,
-        ))
+        Ok(HttpRequestType::GetBlockResults {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            block: BlockResultsQuery::Height(block_height),
+        })
     }
 
-    fn parse_postblock<R: Read>(
+    fn parse_get_nft_withdrawal<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        regex: &Captures,
+        captures: &Captures,
         _query: Option<&str>,
-        fd: &mut R,
+        _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        if preamble.get_content_length() == 0 {
+        if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected non-zero-length body for PostBlock".to_string(),
+                "Invalid Http request: expected 0-length body for GetAccount".to_string(),
             ));
         }
 
-        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
-            return Err(net_error::DeserializeError(
-                "Invalid Http request: PostBlock body is too big".to_string(),
-            ));
-        }
+        let sender = PrincipalData::parse(&captures["sender"]).map_err(|_e| {
+            net_error::DeserializeError("Failed to parse account principal".into())
+        })?;
 
-        // content-type must be given, and must be application/octet-stream
-        match preamble.content_type {
-            None => {
-                return Err(net_error::DeserializeError(
-                    "Missing Content-Type for Stacks block".to_string(),
-                ));
-            }
-            Some(ref c) => {
-                if *c != HttpContentType::Bytes {
-                    return Err(net_error::DeserializeError(
-                        "Wrong Content-Type for Stacks block; expected application/octet-stream"
-                            .to_string(),
-                    ));
-                }
-            }
-        };
+        let withdraw_block_height = u64::from_str(&captures["block_height"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
 
-        let consensus_hash_str = regex
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match consensus hash in path group".to_string(),
-            ))?
-            .as_str();
-
-        let consensus_hash: ConsensusHash =
-            ConsensusHash::from_hex(consensus_hash_str).map_err(|_| {
-                net_error::DeserializeError("Failed to parse consensus hash".to_string())
+        let withdrawal_id = u32::from_str(&captures["withdrawal_id"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block height".into()))?;
+        let contract_addr =
+            StacksAddress::from_string(&captures["contract_address"]).ok_or_else(|| {
+                net_error::DeserializeError("Failed to parse contract address".into())
             })?;
+        let contract_name = ContractName::try_from(captures["contract_name"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+        let asset_name = ClarityName::try_from(captures["asset_name"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse data var name".into()))?;
+        let id = u128::from_str(&captures["id"])
+            .map_err(|_e| net_error::DeserializeError("Failed to parse amount".into()))?;
 
-        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
-        let stacks_block = StacksBlock::consensus_deserialize(&mut bound_fd)?;
-
-        Ok(HttpRequestType::PostBlock(
-            HttpRequestMetadata::from_preamble(preamble),
-            consensus_hash,
-            stacks_block,
-        ))
+        Ok(HttpRequestType::GetWithdrawalNft {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            withdraw_block_height,
+            sender,
+            withdrawal_id,
+            asset_identifier: AssetIdentifier {
+                contract_identifier: QualifiedContractIdentifier::new(
+                    contract_addr.into(),
+                    contract_name,
+                ),
+                asset_name,
+            },
+            id,
+        })
     }
 
-    fn parse_postmicroblock<R: Read>(
+    fn parse_get_data_var<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        _regex: &Captures,
+        captures: &Captures,
         query: Option<&str>,
-        fd: &mut R,
+        _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        if preamble.get_content_length() == 0 {
-            return Err(net_error::DeserializeError(
-                "Invalid Http request: expected non-zero-length body for PostMicroblock"
-                    .to_string(),
-            ));
-        }
-
-        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
-            return Err(net_error::DeserializeError(
-                "Invalid Http request: PostMicroblock body is too big".to_string(),
-            ));
+        let content_len = preamble.get_content_length();
+        if content_len != 0 {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid Http request: invalid body length for GetDataVar ({})",
+                content_len
+            )));
         }
 
-        // content-type must be given, and must be application/octet-stream
-        match preamble.content_type {
-            None => {
-                return Err(net_error::DeserializeError(
-                    "Missing Content-Type for microblock".to_string(),
-                ));
-            }
-            Some(ref c) => {
-                if *c != HttpContentType::Bytes {
-                    return Err(net_error::DeserializeError(
-                        "Wrong Content-Type for microblock; expected application/octet-stream"
-                            .to_string(),
-                    ));
-                }
-            }
-        };
-
-        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
+            net_error::DeserializeError("Failed to parse contract address".into())
+        })?;
+        let contract_name = ContractName::try_from(captures["contract"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+        let var_name = ClarityName::try_from(captures["varname"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse data var name".into()))?;
 
-        let mb = StacksMicroblock::consensus_deserialize(&mut bound_fd)?;
+        let with_proof = HttpRequestType::get_proof_query(query);
         let tip = HttpRequestType::get_chain_tip_query(query);
 
-        Ok(HttpRequestType::PostMicroblock(
+        Ok(HttpRequestType::GetDataVar(
             HttpRequestMetadata::from_preamble(preamble),
-            mb,
+            contract_addr,
+            contract_name,
+            var_name,
             tip,
+            with_proof,
         ))
     }
 
-    fn parse_get_attachment<R: Read>(
+    fn parse_get_map_entry<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
         captures: &Captures,
-        _query: Option<&str>,
-        _fd: &mut R,
+        query: Option<&str>,
+        fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        if preamble.get_content_length() != 0 {
+        let content_len = preamble.get_content_length();
+        if !(content_len > 0 && content_len < (BOUND_VALUE_SERIALIZATION_HEX)) {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid Http request: invalid body length for GetMapEntry ({})",
+                content_len
+            )));
+        }
+
+        if preamble.content_type != Some(HttpContentType::JSON) {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body".to_string(),
+                "Invalid content-type: expected application/json".into(),
             ));
         }
-        let hex_content_hash = captures
-            .get(1)
-            .ok_or(net_error::DeserializeError(
-                "Failed to match path to attachment hash group".to_string(),
-            ))?
-            .as_str();
 
-        let content_hash = Hash160::from_hex(&hex_content_hash).map_err(|_| {
-            net_error::DeserializeError("Failed to construct hash160 from inputs".to_string())
+        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
+            net_error::DeserializeError("Failed to parse contract address".into())
         })?;
+        let contract_name = ContractName::try_from(captures["contract"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+        let map_name = ClarityName::try_from(captures["map"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse map name".into()))?;
 
-        Ok(HttpRequestType::GetAttachment(
+        let value_hex: String = serde_json::from_reader(fd)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse JSON body".into()))?;
+
+        let value = Value::try_deserialize_hex_untyped(&value_hex)
+            .map_err(|_e| net_error::DeserializeError("Failed to deserialize key value".into()))?;
+
+        let with_proof = HttpRequestType::get_proof_query(query);
+        let tip = HttpRequestType::get_chain_tip_query(query);
+
+        Ok(HttpRequestType::GetMapEntry(
             HttpRequestMetadata::from_preamble(preamble),
-            content_hash,
+            contract_addr,
+            contract_name,
+            map_name,
+            value,
+            tip,
+            with_proof,
         ))
     }
 
-    fn parse_get_attachments_inv<R: Read>(
+    fn parse_get_map_entries<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        _captures: &Captures,
+        captures: &Captures,
         query: Option<&str>,
         _fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
         if preamble.get_content_length() != 0 {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: expected 0-length body".to_string(),
+                "Invalid Http request: expected 0-length body for GetMapEntries".to_string(),
             ));
         }
 
-        let (index_block_hash, pages_indexes) = match query {
-            None => {
-                return Err(net_error::DeserializeError(
-                    "Invalid Http request: expecting index_block_hash and pages_indexes"
-                        .to_string(),
-                ));
-            }
-            Some(query) => {
-                let mut index_block_hash = None;
-                let mut pages_indexes = HashSet::new();
-
-                for (key, value) in form_urlencoded::parse(query.as_bytes()) {
-                    if key == "index_block_hash" {
-                        index_block_hash = match StacksBlockId::from_hex(&value) {
-                            Ok(index_block_hash) => Some(index_block_hash),
-                            _ => None,
-                        };
-                    } else if key == "pages_indexes" {
-                        if let Ok(pages_indexes_value) = value.parse::<String>() {
-                            for entry in pages_indexes_value.split(",") {
-                                if let Ok(page_index) = entry.parse::<u32>() {
-                                    pages_indexes.insert(page_index);
-                                }
-                            }
-                        }
-                    }
-                }
-
-                let index_block_hash = match index_block_hash {
-                    None => {
-                        return Err(net_error::DeserializeError(
-                            "Invalid Http request: expecting index_block_hash".to_string(),
-                        ));
-                    }
-                    Some(index_block_hash) => index_block_hash,
-                };
+        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
+            net_error::DeserializeError("Failed to parse contract address".into())
+        })?;
+        let contract_name = ContractName::try_from(captures["contract"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+        let map_name = ClarityName::try_from(captures["map"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse map name".into()))?;
 
-                if pages_indexes.is_empty() {
-                    return Err(net_error::DeserializeError(
-                        "Invalid Http request: expecting pages_indexes".to_string(),
-                    ));
+        let mut after = None;
+        let mut limit = MAP_ENTRIES_DEFAULT_LIMIT;
+        if let Some(query_string) = query {
+            for (key, value) in form_urlencoded::parse(query_string.as_bytes()) {
+                if key == "after" {
+                    let key_hex = value.strip_prefix("0x").unwrap_or(&value);
+                    after = Some(Value::try_deserialize_hex_untyped(key_hex).map_err(|_e| {
+                        net_error::DeserializeError("Failed to deserialize after key".into())
+                    })?);
+                } else if key == "limit" {
+                    limit = u32::from_str(&value).map_err(|_e| {
+                        net_error::DeserializeError("Failed to parse limit".into())
+                    })?;
                 }
-
-                (index_block_hash, pages_indexes)
             }
-        };
+        }
+        if limit == 0 || limit > MAP_ENTRIES_MAX_LIMIT {
+            return Err(net_error::DeserializeError(format!(
+                "limit must be between 1 and {}",
+                MAP_ENTRIES_MAX_LIMIT
+            )));
+        }
 
-        Ok(HttpRequestType::GetAttachmentsInv(
-            HttpRequestMetadata::from_preamble(preamble),
-            index_block_hash,
-            pages_indexes,
-        ))
+        Ok(HttpRequestType::GetMapEntries {
+            metadata: HttpRequestMetadata::from_preamble(preamble),
+            contract_addr,
+            contract_name,
+            map_name,
+            after,
+            limit,
+            tip_req: HttpRequestType::get_chain_tip_query(query),
+        })
     }
 
-    fn parse_post_mempool_query<R: Read>(
-        _protocol: &mut StacksHttp,
+    fn parse_call_read_only<R: Read>(
+        protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        _regex: &Captures,
+        captures: &Captures,
         query: Option<&str>,
         fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        if preamble.get_content_length() == 0 {
-            return Err(net_error::DeserializeError(
-                "Invalid Http request: expected non-empty body".to_string(),
-            ));
+        let content_len = preamble.get_content_length();
+        if !(content_len > 0 && content_len < protocol.maximum_call_argument_size) {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid Http request: invalid body length for CallReadOnly ({})",
+                content_len
+            )));
         }
 
-        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+        if preamble.content_type != Some(HttpContentType::JSON) {
             return Err(net_error::DeserializeError(
-                "Invalid Http request: MemPoolQuery body is too big".to_string(),
+                "Invalid content-type: expected application/json".to_string(),
             ));
         }
 
-        // content-type must be given, and must be application/octet-stream
-        match preamble.content_type {
-            None => {
-                return Err(net_error::DeserializeError(
-                    "Missing Content-Type for MemPoolQuery".to_string(),
-                ));
-            }
-            Some(ref c) => {
-                if *c != HttpContentType::Bytes {
-                    return Err(net_error::DeserializeError(
-                        "Wrong Content-Type for MemPoolQuery; expected application/octet-stream"
-                            .to_string(),
-                    ));
-                }
-            }
-        };
-
-        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
-        let mempool_query = MemPoolSyncData::consensus_deserialize(&mut bound_fd)?;
-        let page_id_opt = HttpRequestType::get_mempool_page_id_query(query);
+        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
+            net_error::DeserializeError("Failed to parse contract address".into())
+        })?;
+        let contract_name = ContractName::try_from(captures["contract"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+        let func_name = ClarityName::try_from(captures["function"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
 
-        Ok(HttpRequestType::MemPoolQuery(
+        let body: CallReadOnlyRequestBody = serde_json::from_reader(fd)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse JSON body".into()))?;
+
+        let sender = PrincipalData::parse(&body.sender)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse sender principal".into()))?;
+
+        let arguments = body
+            .arguments
+            .into_iter()
+            .map(|hex| Value::try_deserialize_hex_untyped(&hex).ok())
+            .collect::<Option<Vec<Value>>>()
+            .ok_or_else(|| {
+                net_error::DeserializeError("Failed to deserialize argument value".into())
+            })?;
+
+        let tip = HttpRequestType::get_chain_tip_query(query);
+
+        Ok(HttpRequestType::CallReadOnlyFunction(
             HttpRequestMetadata::from_preamble(preamble),
-            mempool_query,
-            page_id_opt,
+            contract_addr,
+            contract_name,
+            sender,
+            func_name,
+            arguments,
+            body.cost_budget,
+            tip,
         ))
     }
 
-    fn parse_options_preflight<R: Read>(
+    fn parse_trace_read_only<R: Read>(
+        protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        match HttpRequestType::parse_call_read_only(protocol, preamble, captures, query, fd)? {
+            HttpRequestType::CallReadOnlyFunction(
+                md,
+                contract_addr,
+                contract_name,
+                sender,
+                func_name,
+                arguments,
+                cost_budget,
+                tip,
+            ) => Ok(HttpRequestType::TraceReadOnlyFunction(
+                md,
+                contract_addr,
+                contract_name,
+                sender,
+                func_name,
+                arguments,
+                cost_budget,
+                tip,
+            )),
+            _ => unreachable!("parse_call_read_only() only parses read-only calls"),
+        }
+    }
+
+    fn parse_block_proposal<R: Read>(
         _protocol: &mut StacksHttp,
         preamble: &HttpRequestPreamble,
-        _regex: &Captures,
+        _captures: &Captures,
         _query: Option<&str>,
-        _fd: &mut R,
+        fd: &mut R,
     ) -> Result<HttpRequestType, net_error> {
-        Ok(HttpRequestType::OptionsPreflight(
+        let content_len = preamble.get_content_length();
+        if !(content_len > 0 && content_len < MAX_BLOCK_PROPOSAL_LENGTH) {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid Http request: invalid body length for BlockProposal ({})",
+                content_len
+            )));
+        }
+
+        if preamble.content_type != Some(HttpContentType::JSON) {
+            return Err(net_error::DeserializeError(
+                "Invalid content-type: expected application/json".to_string(),
+            ));
+        }
+
+        let block_proposal: Proposal = serde_json::from_reader(fd).map_err(|_e| {
+            net_error::DeserializeError("Failed to parse block proposal JSON body".into())
+        })?;
+
+        Ok(HttpRequestType::BlockProposal(
             HttpRequestMetadata::from_preamble(preamble),
-            preamble.path.to_string(),
+            block_proposal,
         ))
     }
 
-    pub fn metadata(&self) -> &HttpRequestMetadata {
-        match *self {
-            HttpRequestType::GetInfo(ref md) => md,
-            HttpRequestType::GetNeighbors(ref md) => md,
-            HttpRequestType::GetHeaders(ref md, ..) => md,
-            HttpRequestType::GetBlock(ref md, _) => md,
-            HttpRequestType::GetMicroblocksIndexed(ref md, _) => md,
-            HttpRequestType::GetMicroblocksConfirmed(ref md, _) => md,
-            HttpRequestType::GetMicroblocksUnconfirmed(ref md, _, _) => md,
-            HttpRequestType::GetTransactionUnconfirmed(ref md, _) => md,
-            HttpRequestType::PostTransaction(ref md, _, _) => md,
-            HttpRequestType::PostBlock(ref md, ..) => md,
-            HttpRequestType::PostMicroblock(ref md, ..) => md,
-            HttpRequestType::GetAccount(ref md, ..) => md,
-            HttpRequestType::GetDataVar(ref md, ..) => md,
-            HttpRequestType::GetMapEntry(ref md, ..) => md,
-            HttpRequestType::GetTransferCost(ref md) => md,
-            HttpRequestType::GetContractABI(ref md, ..) => md,
-            HttpRequestType::GetContractSrc(ref md, ..) => md,
-            HttpRequestType::GetIsTraitImplemented(ref md, ..) => md,
-            HttpRequestType::CallReadOnlyFunction(ref md, ..) => md,
-            HttpRequestType::OptionsPreflight(ref md, ..) => md,
-            HttpRequestType::GetAttachmentsInv(ref md, ..) => md,
-            HttpRequestType::GetAttachment(ref md, ..) => md,
-            HttpRequestType::MemPoolQuery(ref md, ..) => md,
-            HttpRequestType::FeeRateEstimate(ref md, _, _) => md,
-            HttpRequestType::ClientError(ref md, ..) => md,
-            HttpRequestType::GetWithdrawalStx { ref metadata, .. } => metadata,
-            HttpRequestType::BlockProposal(ref metadata, ..) => metadata,
-            HttpRequestType::GetWithdrawalNft { ref metadata, .. } => metadata,
+    fn parse_validate_block_proposal<R: Read>(
+        protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        match HttpRequestType::parse_block_proposal(protocol, preamble, captures, query, fd)? {
+            HttpRequestType::BlockProposal(md, proposal) => {
+                Ok(HttpRequestType::ValidateBlockProposal(md, proposal))
+            }
+            _ => unreachable!("parse_block_proposal() only parses block proposals"),
         }
     }
 
-    pub fn metadata_mut(&mut self) -> &mut HttpRequestMetadata {
-        match *self {
-            HttpRequestType::GetInfo(ref mut md) => md,
-            HttpRequestType::GetNeighbors(ref mut md) => md,
-            HttpRequestType::GetHeaders(ref mut md, ..) => md,
-            HttpRequestType::GetBlock(ref mut md, _) => md,
-            HttpRequestType::GetMicroblocksIndexed(ref mut md, _) => md,
-            HttpRequestType::GetMicroblocksConfirmed(ref mut md, _) => md,
-            HttpRequestType::GetMicroblocksUnconfirmed(ref mut md, _, _) => md,
-            HttpRequestType::GetTransactionUnconfirmed(ref mut md, _) => md,
-            HttpRequestType::PostTransaction(ref mut md, _, _) => md,
-            HttpRequestType::PostBlock(ref mut md, ..) => md,
-            HttpRequestType::PostMicroblock(ref mut md, ..) => md,
-            HttpRequestType::GetAccount(ref mut md, ..) => md,
-            HttpRequestType::GetDataVar(ref mut md, ..) => md,
-            HttpRequestType::GetMapEntry(ref mut md, ..) => md,
-            HttpRequestType::GetTransferCost(ref mut md) => md,
-            HttpRequestType::GetContractABI(ref mut md, ..) => md,
-            HttpRequestType::GetContractSrc(ref mut md, ..) => md,
-            HttpRequestType::GetIsTraitImplemented(ref mut md, ..) => md,
-            HttpRequestType::CallReadOnlyFunction(ref mut md, ..) => md,
-            HttpRequestType::OptionsPreflight(ref mut md, ..) => md,
-            HttpRequestType::GetAttachmentsInv(ref mut md, ..) => md,
-            HttpRequestType::GetAttachment(ref mut md, ..) => md,
-            HttpRequestType::MemPoolQuery(ref mut md, ..) => md,
-            HttpRequestType::FeeRateEstimate(ref mut md, _, _) => md,
-            HttpRequestType::ClientError(ref mut md, ..) => md,
-            HttpRequestType::BlockProposal(ref mut metadata, ..) => metadata,
-            HttpRequestType::GetWithdrawalStx {
-                ref mut metadata, ..
-            } => metadata,
-            HttpRequestType::GetWithdrawalNft {
-                ref mut metadata, ..
-            } => metadata,
+    fn parse_get_contract_arguments(
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+    ) -> Result<(HttpRequestMetadata, StacksAddress, ContractName), net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body".to_string(),
+            ));
         }
+
+        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
+            net_error::DeserializeError("Failed to parse contract address".into())
+        })?;
+        let contract_name = ContractName::try_from(captures["contract"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+
+        Ok((
+            HttpRequestMetadata::from_preamble(preamble),
+            contract_addr,
+            contract_name,
+        ))
     }
 
-    fn make_tip_query_string(tip_req: &TipRequest, with_proof: bool) -> String {
-        match tip_req {
-            TipRequest::UseLatestUnconfirmedTip => {
-                format!("?tip=latest{}", if with_proof { "" } else { "&proof=0" })
-            }
-            TipRequest::SpecificTip(tip) => {
-                format!("?tip={}{}", tip, if with_proof { "" } else { "&proof=0" })
-            }
-            TipRequest::UseLatestAnchoredTip => {
-                if !with_proof {
-                    format!("?proof=0")
-                } else {
-                    "".to_string()
-                }
-            }
+    fn parse_get_contract_abi<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        let tip = HttpRequestType::get_chain_tip_query(query);
+        HttpRequestType::parse_get_contract_arguments(preamble, captures).map(
+            |(preamble, addr, name)| HttpRequestType::GetContractABI(preamble, addr, name, tip),
+        )
+    }
+
+    fn parse_get_contract_source<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        let with_proof = HttpRequestType::get_proof_query(query);
+        let tip = HttpRequestType::get_chain_tip_query(query);
+        HttpRequestType::parse_get_contract_arguments(preamble, captures).map(
+            |(preamble, addr, name)| {
+                HttpRequestType::GetContractSrc(preamble, addr, name, tip, with_proof)
+            },
+        )
+    }
+
+    fn parse_get_is_trait_implemented<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        let tip = HttpRequestType::get_chain_tip_query(query);
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body".to_string(),
+            ));
         }
+
+        let contract_addr = StacksAddress::from_string(&captures["address"]).ok_or_else(|| {
+            net_error::DeserializeError("Failed to parse contract address".into())
+        })?;
+        let contract_name = ContractName::try_from(captures["contract"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse contract name".into()))?;
+        let trait_name = ClarityName::try_from(captures["traitName"].to_string())
+            .map_err(|_e| net_error::DeserializeError("Failed to parse trait name".into()))?;
+        let trait_contract_addr = StacksAddress::from_string(&captures["traitContractAddr"])
+            .ok_or_else(|| net_error::DeserializeError("Failed to parse contract address".into()))?
+            .into();
+        let trait_contract_name = ContractName::try_from(captures["traitContractName"].to_string())
+            .map_err(|_e| {
+                net_error::DeserializeError("Failed to parse trait contract name".into())
+            })?;
+        let trait_id = TraitIdentifier::new(trait_contract_addr, trait_contract_name, trait_name);
+
+        Ok(HttpRequestType::GetIsTraitImplemented(
+            HttpRequestMetadata::from_preamble(preamble),
+            contract_addr,
+            contract_name,
+            trait_id,
+            tip,
+        ))
+    }
+
+    fn parse_getheaders<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetBlock".to_string(),
+            ));
+        }
+
+        let quantity_str = captures
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to reward cycle group".to_string(),
+            ))?
+            .as_str();
+
+        let quantity: u64 = quantity_str
+            .parse()
+            .map_err(|_| net_error::DeserializeError("Failed to parse reward cycle".to_string()))?;
+
+        let tip = HttpRequestType::get_chain_tip_query(query);
+
+        Ok(HttpRequestType::GetHeaders(
+            HttpRequestMetadata::from_preamble(preamble),
+            quantity,
+            tip,
+        ))
+    }
+
+    fn parse_getblock<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetBlock".to_string(),
+            ));
+        }
+
+        let block_hash_str = captures
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to block hash group".to_string(),
+            ))?
+            .as_str();
+
+        let block_hash = StacksBlockId::from_hex(block_hash_str)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse block hash".to_string()))?;
+
+        Ok(HttpRequestType::GetBlock(
+            HttpRequestMetadata::from_preamble(preamble),
+            block_hash,
+        ))
+    }
+
+    fn parse_getmicroblocks_indexed<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetMicroblocksIndexed"
+                    .to_string(),
+            ));
+        }
+
+        let block_hash_str = captures
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to microblock hash group".to_string(),
+            ))?
+            .as_str();
+
+        let block_hash = StacksBlockId::from_hex(block_hash_str).map_err(|_e| {
+            net_error::DeserializeError("Failed to parse microblock hash".to_string())
+        })?;
+
+        Ok(HttpRequestType::GetMicroblocksIndexed(
+            HttpRequestMetadata::from_preamble(preamble),
+            block_hash,
+        ))
+    }
+
+    fn parse_getmicroblocks_confirmed<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetMicrolocks".to_string(),
+            ));
+        }
+
+        let block_hash_str = captures
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to microblock hash group".to_string(),
+            ))?
+            .as_str();
+
+        let block_hash = StacksBlockId::from_hex(block_hash_str).map_err(|_e| {
+            net_error::DeserializeError("Failed to parse microblock hash".to_string())
+        })?;
+
+        Ok(HttpRequestType::GetMicroblocksConfirmed(
+            HttpRequestMetadata::from_preamble(preamble),
+            block_hash,
+        ))
+    }
+
+    fn parse_getmicroblocks_unconfirmed<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetMicrolocksUnconfirmed"
+                    .to_string(),
+            ));
+        }
+
+        let block_hash_str = captures
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to microblock hash group".to_string(),
+            ))?
+            .as_str();
+
+        let min_seq_str = captures
+            .get(2)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to microblock minimum sequence group".to_string(),
+            ))?
+            .as_str();
+
+        let block_hash = StacksBlockId::from_hex(block_hash_str).map_err(|_e| {
+            net_error::DeserializeError("Failed to parse microblock hash".to_string())
+        })?;
+
+        let min_seq = min_seq_str.parse::<u16>().map_err(|_e| {
+            net_error::DeserializeError("Failed to parse microblock minimum sequence".to_string())
+        })?;
+
+        Ok(HttpRequestType::GetMicroblocksUnconfirmed(
+            HttpRequestMetadata::from_preamble(preamble),
+            block_hash,
+            min_seq,
+        ))
+    }
+
+    fn parse_gettransaction_unconfirmed<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        regex: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body for GetMicrolocksUnconfirmed"
+                    .to_string(),
+            ));
+        }
+
+        let txid_hex = regex
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to txid group".to_string(),
+            ))?
+            .as_str();
+
+        if txid_hex.len() != 64 {
+            return Err(net_error::DeserializeError(
+                "Invalid txid: expected 64 bytes".to_string(),
+            ));
+        }
+
+        let txid = Txid::from_hex(&txid_hex)
+            .map_err(|_e| net_error::DeserializeError("Failed to decode txid hex".to_string()))?;
+
+        Ok(HttpRequestType::GetTransactionUnconfirmed(
+            HttpRequestMetadata::from_preamble(preamble),
+            txid,
+        ))
+    }
+
+    fn parse_post_fee_rate_estimate<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        _query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        let content_len = preamble.get_content_length();
+        if !(content_len > 0 && content_len < MAX_PAYLOAD_LEN) {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid Http request: invalid body length for FeeRateEstimate ({})",
+                content_len
+            )));
+        }
+
+        if preamble.content_type != Some(HttpContentType::JSON) {
+            return Err(net_error::DeserializeError(
+                "Invalid content-type: expected application/json".to_string(),
+            ));
+        }
+
+        let bound_fd = BoundReader::from_reader(fd, content_len as u64);
+
+        let body: FeeRateEstimateRequestBody = serde_json::from_reader(bound_fd).map_err(|e| {
+            net_error::DeserializeError(format!("Failed to parse JSON body: {}", e))
+        })?;
+
+        let payload_hex = if body.transaction_payload.starts_with("0x") {
+            &body.transaction_payload[2..]
+        } else {
+            &body.transaction_payload
+        };
+
+        let payload_data = hex_bytes(payload_hex).map_err(|_e| {
+            net_error::DeserializeError("Bad hex string supplied for transaction payload".into())
+        })?;
+
+        let payload = TransactionPayload::consensus_deserialize(&mut payload_data.as_slice())
+            .map_err(|e| {
+                net_error::DeserializeError(format!(
+                    "Failed to deserialize transaction payload: {}",
+                    e
+                ))
+            })?;
+
+        let estimated_len =
+            std::cmp::max(body.estimated_len.unwrap_or(0), payload_data.len() as u64);
+
+        Ok(HttpRequestType::FeeRateEstimate(
+            HttpRequestMetadata::from_preamble(preamble),
+            payload,
+            estimated_len,
+        ))
+    }
+
+    fn parse_posttransaction<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() == 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected non-zero-length body for PostTransaction"
+                    .to_string(),
+            ));
+        }
+
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: PostTransaction body is too big".to_string(),
+            ));
+        }
+
+        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+        let diagnostics = HttpRequestType::get_diagnostics_query(query);
+
+        match preamble.content_type {
+            None => {
+                return Err(net_error::DeserializeError(
+                    "Missing Content-Type for transaction".to_string(),
+                ));
+            }
+            Some(HttpContentType::Bytes) => {
+                HttpRequestType::parse_posttransaction_octets(preamble, &mut bound_fd, diagnostics)
+            }
+            Some(HttpContentType::JSON) => {
+                HttpRequestType::parse_posttransaction_json(preamble, &mut bound_fd, diagnostics)
+            }
+            _ => {
+                return Err(net_error::DeserializeError(
+                    "Wrong Content-Type for transaction; expected application/json".to_string(),
+                ));
+            }
+        }
+    }
+
+    fn parse_posttransaction_octets<R: Read>(
+        preamble: &HttpRequestPreamble,
+        fd: &mut R,
+        diagnostics: bool,
+    ) -> Result<HttpRequestType, net_error> {
+        let tx = StacksTransaction::consensus_deserialize(fd).map_err(|e| {
+            if let codec_error::DeserializeError(msg) = e {
+                net_error::ClientError(ClientError::Message(format!(
+                    "Failed to deserialize posted transaction: {}",
+                    msg
+                )))
+            } else {
+                e.into()
+            }
+        })?;
+        Ok(HttpRequestType::PostTransaction(
+            HttpRequestMetadata::from_preamble(preamble),
+            tx,
+            None,
+            diagnostics,
+        ))
+    }
+
+    fn parse_posttransaction_json<R: Read>(
+        preamble: &HttpRequestPreamble,
+        fd: &mut R,
+        diagnostics: bool,
+    ) -> Result<HttpRequestType, net_error> {
+        let body: PostTransactionRequestBody = serde_json::from_reader(fd)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse body".into()))?;
+
+        let tx = {
+            let tx_bytes = hex_bytes(&body.tx)
+                .map_err(|_e| net_error::DeserializeError("Failed to parse tx".into()))?;
+            StacksTransaction::consensus_deserialize(&mut &tx_bytes[..]).map_err(|e| {
+                if let codec_error::DeserializeError(msg) = e {
+                    net_error::ClientError(ClientError::Message(format!(
+                        "Failed to deserialize posted transaction: {}",
+                        msg
+                    )))
+                } else {
+                    e.into()
+                }
+            })
+        }?;
+
+        let attachment = match body.attachment {
+            None => None,
+            Some(attachment_content) => {
+                let content = hex_bytes(&attachment_content).map_err(|_e| {
+                    net_error::DeserializeError("Failed to parse attachment".into())
+                })?;
+                Some(Attachment::new(content))
+            }
+        };
+
+        Ok(HttpRequestType::PostTransaction(
+            HttpRequestMetadata::from_preamble(preamble),
+            tx,
+            attachment,
+            diagnostics,
+        ))
+    }
+
+    fn parse_post_transaction_batch<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        _query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() == 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected non-zero-length body for PostTransactionBatch"
+                    .to_string(),
+            ));
+        }
+
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: PostTransactionBatch body is too big".to_string(),
+            ));
+        }
+
+        match preamble.content_type {
+            Some(HttpContentType::JSON) => {}
+            _ => {
+                return Err(net_error::DeserializeError(
+                    "Wrong Content-Type for transaction batch; expected application/json"
+                        .to_string(),
+                ));
+            }
+        }
+
+        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+        let body: PostTransactionBatchRequestBody = serde_json::from_reader(&mut bound_fd)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse body".into()))?;
+
+        if body.txs.len() == 0 {
+            return Err(net_error::ClientError(ClientError::Message(
+                "Transaction batch is empty".to_string(),
+            )));
+        }
+        if body.txs.len() > MAX_TRANSACTION_BATCH_LEN {
+            return Err(net_error::ClientError(ClientError::Message(format!(
+                "Transaction batch has more than {} transactions",
+                MAX_TRANSACTION_BATCH_LEN
+            ))));
+        }
+
+        let mut txs = Vec::with_capacity(body.txs.len());
+        for tx_hex in body.txs.iter() {
+            let tx_bytes = hex_bytes(tx_hex)
+                .map_err(|_e| net_error::DeserializeError("Failed to parse tx".into()))?;
+            let tx = StacksTransaction::consensus_deserialize(&mut &tx_bytes[..]).map_err(|e| {
+                if let codec_error::DeserializeError(msg) = e {
+                    net_error::ClientError(ClientError::Message(format!(
+                        "Failed to deserialize posted transaction: {}",
+                        msg
+                    )))
+                } else {
+                    e.into()
+                }
+            })?;
+            txs.push(tx);
+        }
+
+        Ok(HttpRequestType::PostTransactionBatch(
+            HttpRequestMetadata::from_preamble(preamble),
+            txs,
+            body.all_or_nothing,
+        ))
+    }
+
+    fn parse_post_transaction_sponsorship<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        _query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() == 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected non-zero-length body for PostTransactionSponsorship"
+                    .to_string(),
+            ));
+        }
+
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: PostTransactionSponsorship body is too big".to_string(),
+            ));
+        }
+
+        match preamble.content_type {
+            Some(HttpContentType::JSON) => {}
+            _ => {
+                return Err(net_error::DeserializeError(
+                    "Wrong Content-Type for transaction sponsorship; expected application/json"
+                        .to_string(),
+                ));
+            }
+        }
+
+        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+        let body: PostTransactionSponsorshipRequestBody = serde_json::from_reader(&mut bound_fd)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse body".into()))?;
+
+        let txid = Txid::from_hex(&body.txid)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse txid".into()))?;
+        let tx_bytes = hex_bytes(&body.tx)
+            .map_err(|_e| net_error::DeserializeError("Failed to parse tx".into()))?;
+        let tx = StacksTransaction::consensus_deserialize(&mut &tx_bytes[..]).map_err(|e| {
+            if let codec_error::DeserializeError(msg) = e {
+                net_error::ClientError(ClientError::Message(format!(
+                    "Failed to deserialize posted transaction: {}",
+                    msg
+                )))
+            } else {
+                e.into()
+            }
+        })?;
+
+        Ok(HttpRequestType::PostTransactionSponsorship(
+            HttpRequestMetadata::from_preamble(preamble),
+            txid,
+            tx,
+        ))
+    }
+
+    fn parse_get_clarity_data_proof<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        let content_len = preamble.get_content_length();
+        if !(content_len > 0 && content_len < (BOUND_VALUE_SERIALIZATION_HEX)) {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid Http request: invalid body length for GetClarityDataProof ({})",
+                content_len
+            )));
+        }
+
+        if preamble.content_type != Some(HttpContentType::JSON) {
+            return Err(net_error::DeserializeError(
+                "Invalid content-type: expected application/json".into(),
+            ));
+        }
+
+        let bound_fd = BoundReader::from_reader(fd, content_len as u64);
+        let key: ClarityDataKey = serde_json::from_reader(bound_fd).map_err(|e| {
+            net_error::DeserializeError(format!("Failed to parse JSON body: {}", e))
+        })?;
+
+        // reject keys that the node could never look up
+        key.to_clarity_key().map_err(net_error::DeserializeError)?;
+
+        let tip = HttpRequestType::get_chain_tip_query(query);
+
+        Ok(HttpRequestType::GetClarityDataProof(
+            HttpRequestMetadata::from_preamble(preamble),
+            key,
+            tip,
+        ))
+    }
+
+    fn parse_postblock<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        regex: &Captures,
+        _query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() == 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected non-zero-length body for PostBlock".to_string(),
+            ));
+        }
+
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: PostBlock body is too big".to_string(),
+            ));
+        }
+
+        // content-type must be given, and must be application/octet-stream
+        match preamble.content_type {
+            None => {
+                return Err(net_error::DeserializeError(
+                    "Missing Content-Type for Stacks block".to_string(),
+                ));
+            }
+            Some(ref c) => {
+                if *c != HttpContentType::Bytes {
+                    return Err(net_error::DeserializeError(
+                        "Wrong Content-Type for Stacks block; expected application/octet-stream"
+                            .to_string(),
+                    ));
+                }
+            }
+        };
+
+        let consensus_hash_str = regex
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match consensus hash in path group".to_string(),
+            ))?
+            .as_str();
+
+        let consensus_hash: ConsensusHash =
+            ConsensusHash::from_hex(consensus_hash_str).map_err(|_| {
+                net_error::DeserializeError("Failed to parse consensus hash".to_string())
+            })?;
+
+        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+        let stacks_block = StacksBlock::consensus_deserialize(&mut bound_fd)?;
+
+        Ok(HttpRequestType::PostBlock(
+            HttpRequestMetadata::from_preamble(preamble),
+            consensus_hash,
+            stacks_block,
+        ))
+    }
+
+    fn parse_postmicroblock<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() == 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected non-zero-length body for PostMicroblock"
+                    .to_string(),
+            ));
+        }
+
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: PostMicroblock body is too big".to_string(),
+            ));
+        }
+
+        // content-type must be given, and must be application/octet-stream
+        match preamble.content_type {
+            None => {
+                return Err(net_error::DeserializeError(
+                    "Missing Content-Type for microblock".to_string(),
+                ));
+            }
+            Some(ref c) => {
+                if *c != HttpContentType::Bytes {
+                    return Err(net_error::DeserializeError(
+                        "Wrong Content-Type for microblock; expected application/octet-stream"
+                            .to_string(),
+                    ));
+                }
+            }
+        };
+
+        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+
+        let mb = StacksMicroblock::consensus_deserialize(&mut bound_fd)?;
+        let tip = HttpRequestType::get_chain_tip_query(query);
+
+        Ok(HttpRequestType::PostMicroblock(
+            HttpRequestMetadata::from_preamble(preamble),
+            mb,
+            tip,
+        ))
+    }
+
+    fn parse_get_attachment<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body".to_string(),
+            ));
+        }
+        let hex_content_hash = captures
+            .get(1)
+            .ok_or(net_error::DeserializeError(
+                "Failed to match path to attachment hash group".to_string(),
+            ))?
+            .as_str();
+
+        let content_hash = Hash160::from_hex(&hex_content_hash).map_err(|_| {
+            net_error::DeserializeError("Failed to construct hash160 from inputs".to_string())
+        })?;
+
+        Ok(HttpRequestType::GetAttachment(
+            HttpRequestMetadata::from_preamble(preamble),
+            content_hash,
+        ))
+    }
+
+    fn parse_get_attachments_inv<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() != 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected 0-length body".to_string(),
+            ));
+        }
+
+        let (index_block_hash, pages_indexes) = match query {
+            None => {
+                return Err(net_error::DeserializeError(
+                    "Invalid Http request: expecting index_block_hash and pages_indexes"
+                        .to_string(),
+                ));
+            }
+            Some(query) => {
+                let mut index_block_hash = None;
+                let mut pages_indexes = HashSet::new();
+
+                for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+                    if key == "index_block_hash" {
+                        index_block_hash = match StacksBlockId::from_hex(&value) {
+                            Ok(index_block_hash) => Some(index_block_hash),
+                            _ => None,
+                        };
+                    } else if key == "pages_indexes" {
+                        if let Ok(pages_indexes_value) = value.parse::<String>() {
+                            for entry in pages_indexes_value.split(",") {
+                                if let Ok(page_index) = entry.parse::<u32>() {
+                                    pages_indexes.insert(page_index);
+                                }
+                            }
+                        }
+                    }
+                }
+
+                let index_block_hash = match index_block_hash {
+                    None => {
+                        return Err(net_error::DeserializeError(
+                            "Invalid Http request: expecting index_block_hash".to_string(),
+                        ));
+                    }
+                    Some(index_block_hash) => index_block_hash,
+                };
+
+                if pages_indexes.is_empty() {
+                    return Err(net_error::DeserializeError(
+                        "Invalid Http request: expecting pages_indexes".to_string(),
+                    ));
+                }
+
+                (index_block_hash, pages_indexes)
+            }
+        };
+
+        Ok(HttpRequestType::GetAttachmentsInv(
+            HttpRequestMetadata::from_preamble(preamble),
+            index_block_hash,
+            pages_indexes,
+        ))
+    }
+
+    fn parse_post_mempool_query<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        query: Option<&str>,
+        fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        if preamble.get_content_length() == 0 {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: expected non-empty body".to_string(),
+            ));
+        }
+
+        if preamble.get_content_length() > MAX_PAYLOAD_LEN {
+            return Err(net_error::DeserializeError(
+                "Invalid Http request: MemPoolQuery body is too big".to_string(),
+            ));
+        }
+
+        // content-type must be given, and must be application/octet-stream
+        match preamble.content_type {
+            None => {
+                return Err(net_error::DeserializeError(
+                    "Missing Content-Type for MemPoolQuery".to_string(),
+                ));
+            }
+            Some(ref c) => {
+                if *c != HttpContentType::Bytes {
+                    return Err(net_error::DeserializeError(
+                        "Wrong Content-Type for MemPoolQuery; expected application/octet-stream"
+                            .to_string(),
+                    ));
+                }
+            }
+        };
+
+        let mut bound_fd = BoundReader::from_reader(fd, preamble.get_content_length() as u64);
+        let mempool_query = MemPoolSyncData::consensus_deserialize(&mut bound_fd)?;
+        let page_id_opt = HttpRequestType::get_mempool_page_id_query(query);
+        let bloom_depth_opt = HttpRequestType::get_mempool_bloom_depth_query(query);
+
+        Ok(HttpRequestType::MemPoolQuery(
+            HttpRequestMetadata::from_preamble(preamble),
+            mempool_query,
+            page_id_opt,
+            bloom_depth_opt,
+        ))
+    }
+
+    fn parse_options_preflight<R: Read>(
+        _protocol: &mut StacksHttp,
+        preamble: &HttpRequestPreamble,
+        _regex: &Captures,
+        _query: Option<&str>,
+        _fd: &mut R,
+    ) -> Result<HttpRequestType, net_error> {
+        Ok(HttpRequestType::OptionsPreflight(
+            HttpRequestMetadata::from_preamble(preamble),
+            preamble.path.to_string(),
+        ))
+    }
+
+    pub fn metadata(&self) -> &HttpRequestMetadata {
+        match *self {
+            HttpRequestType::GetInfo(ref md) => md,
+            HttpRequestType::GetNeighbors(ref md) => md,
+            HttpRequestType::GetHeaders(ref md, ..) => md,
+            HttpRequestType::GetBlock(ref md, _) => md,
+            HttpRequestType::GetMicroblocksIndexed(ref md, _) => md,
+            HttpRequestType::GetMicroblocksConfirmed(ref md, _) => md,
+            HttpRequestType::GetMicroblocksUnconfirmed(ref md, _, _) => md,
+            HttpRequestType::GetTransactionUnconfirmed(ref md, _) => md,
+            HttpRequestType::PostTransaction(ref md, ..) => md,
+            HttpRequestType::PostTransactionBatch(ref md, ..) => md,
+            HttpRequestType::PostTransactionSponsorship(ref md, ..) => md,
+            HttpRequestType::PostBlock(ref md, ..) => md,
+            HttpRequestType::PostMicroblock(ref md, ..) => md,
+            HttpRequestType::GetAccount(ref md, ..) => md,
+            HttpRequestType::GetDataVar(ref md, ..) => md,
+            HttpRequestType::GetMapEntry(ref md, ..) => md,
+            HttpRequestType::GetMapEntries { ref metadata, .. } => metadata,
+            HttpRequestType::GetTransferCost(ref md) => md,
+            HttpRequestType::GetSubnetFeeRateEstimate(ref md) => md,
+            HttpRequestType::GetContractABI(ref md, ..) => md,
+            HttpRequestType::GetContractSrc(ref md, ..) => md,
+            HttpRequestType::GetIsTraitImplemented(ref md, ..) => md,
+            HttpRequestType::CallReadOnlyFunction(ref md, ..) => md,
+            HttpRequestType::TraceReadOnlyFunction(ref md, ..) => md,
+            HttpRequestType::OptionsPreflight(ref md, ..) => md,
+            HttpRequestType::GetAttachmentsInv(ref md, ..) => md,
+            HttpRequestType::GetAttachment(ref md, ..) => md,
+            HttpRequestType::MemPoolQuery(ref md, ..) => md,
+            HttpRequestType::FeeRateEstimate(ref md, _, _) => md,
+            HttpRequestType::ClientError(ref md, ..) => md,
+            HttpRequestType::GetWithdrawalStx { ref metadata, .. } => metadata,
+            HttpRequestType::BlockProposal(ref metadata, ..) => metadata,
+            HttpRequestType::ValidateBlockProposal(ref metadata, ..) => metadata,
+            HttpRequestType::GetWithdrawalNft { ref metadata, .. } => metadata,
+            HttpRequestType::GetWithdrawalProof { ref metadata, .. } => metadata,
+            HttpRequestType::GetWithdrawalStatus { ref metadata, .. } => metadata,
+            HttpRequestType::GetBlockResults { ref metadata, .. } => metadata,
+            HttpRequestType::GetClarityDataProof(ref md, ..) => md,
+            HttpRequestType::GetTransactionProof { ref metadata, .. } => metadata,
+            HttpRequestType::GetDepositStatus { ref metadata, .. } => metadata,
+            HttpRequestType::GetBlockCosts { ref metadata, .. } => metadata,
+            HttpRequestType::GetBlockHeader { ref metadata, .. } => metadata,
+            HttpRequestType::GetBlockStateDiff { ref metadata, .. } => metadata,
+            HttpRequestType::GetTransactionReceipt { ref metadata, .. } => metadata,
+            HttpRequestType::GetNonceGaps { ref metadata, .. } => metadata,
+            HttpRequestType::GetSubnetStatus(ref md) => md,
+            HttpRequestType::PostDivergenceSnapshot { ref metadata, .. } => metadata,
+            HttpRequestType::GetMemPoolTxs { ref metadata, .. } => metadata,
+            HttpRequestType::GetMemPoolSummary { ref metadata, .. } => metadata,
+            HttpRequestType::DeleteMemPoolTx { ref metadata, .. } => metadata,
+            HttpRequestType::GetAdminPeers { ref metadata, .. } => metadata,
+            HttpRequestType::GetAdminObservers { ref metadata, .. } => metadata,
+            HttpRequestType::GetPeerBans { ref metadata, .. } => metadata,
+            HttpRequestType::PostPeerBan { ref metadata, .. } => metadata,
+            HttpRequestType::DeletePeerBan { ref metadata, .. } => metadata,
+        }
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut HttpRequestMetadata {
+        match *self {
+            HttpRequestType::GetInfo(ref mut md) => md,
+            HttpRequestType::GetNeighbors(ref mut md) => md,
+            HttpRequestType::GetHeaders(ref mut md, ..) => md,
+            HttpRequestType::GetBlock(ref mut md, _) => md,
+            HttpRequestType::GetMicroblocksIndexed(ref mut md, _) => md,
+            HttpRequestType::GetMicroblocksConfirmed(ref mut md, _) => md,
+            HttpRequestType::GetMicroblocksUnconfirmed(ref mut md, _, _) => md,
+            HttpRequestType::GetTransactionUnconfirmed(ref mut md, _) => md,
+            HttpRequestType::PostTransaction(ref mut md, ..) => md,
+            HttpRequestType::PostTransactionBatch(ref mut md, ..) => md,
+            HttpRequestType::PostTransactionSponsorship(ref mut md, ..) => md,
+            HttpRequestType::PostBlock(ref mut md, ..) => md,
+            HttpRequestType::PostMicroblock(ref mut md, ..) => md,
+            HttpRequestType::GetAccount(ref mut md, ..) => md,
+            HttpRequestType::GetDataVar(ref mut md, ..) => md,
+            HttpRequestType::GetMapEntry(ref mut md, ..) => md,
+            HttpRequestType::GetMapEntries {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetTransferCost(ref mut md) => md,
+            HttpRequestType::GetSubnetFeeRateEstimate(ref mut md) => md,
+            HttpRequestType::GetContractABI(ref mut md, ..) => md,
+            HttpRequestType::GetContractSrc(ref mut md, ..) => md,
+            HttpRequestType::GetIsTraitImplemented(ref mut md, ..) => md,
+            HttpRequestType::CallReadOnlyFunction(ref mut md, ..) => md,
+            HttpRequestType::TraceReadOnlyFunction(ref mut md, ..) => md,
+            HttpRequestType::OptionsPreflight(ref mut md, ..) => md,
+            HttpRequestType::GetAttachmentsInv(ref mut md, ..) => md,
+            HttpRequestType::GetAttachment(ref mut md, ..) => md,
+            HttpRequestType::MemPoolQuery(ref mut md, ..) => md,
+            HttpRequestType::FeeRateEstimate(ref mut md, _, _) => md,
+            HttpRequestType::ClientError(ref mut md, ..) => md,
+            HttpRequestType::BlockProposal(ref mut metadata, ..) => metadata,
+            HttpRequestType::ValidateBlockProposal(ref mut metadata, ..) => metadata,
+            HttpRequestType::GetWithdrawalStx {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetWithdrawalNft {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetWithdrawalProof {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetWithdrawalStatus {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetBlockResults {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetClarityDataProof(ref mut md, ..) => md,
+            HttpRequestType::GetTransactionProof {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetDepositStatus {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetBlockCosts {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetBlockHeader {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetBlockStateDiff {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetTransactionReceipt {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetNonceGaps {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetSubnetStatus(ref mut md) => md,
+            HttpRequestType::PostDivergenceSnapshot {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetMemPoolTxs {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetMemPoolSummary {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::DeleteMemPoolTx {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetAdminPeers {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetAdminObservers {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::GetPeerBans {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::PostPeerBan {
+                ref mut metadata, ..
+            } => metadata,
+            HttpRequestType::DeletePeerBan {
+                ref mut metadata, ..
+            } => metadata,
+        }
+    }
+
+    fn make_tip_query_string(tip_req: &TipRequest, with_proof: bool) -> String {
+        match tip_req {
+            TipRequest::UseLatestUnconfirmedTip => {
+                format!("?tip=latest{}", if with_proof { "" } else { "&proof=0" })
+            }
+            TipRequest::SpecificTip(tip) => {
+                format!("?tip={}{}", tip, if with_proof { "" } else { "&proof=0" })
+            }
+            TipRequest::UseLatestAnchoredTip => {
+                if !with_proof {
+                    format!("?proof=0")
+                } else {
+                    "".to_string()
+                }
+            }
+        }
+    }
+
+    /// The JSON body of an admin request, as this node encodes it.  Signatures of admin requests
+    /// cover it.  Admin requests without a body have an empty one.
+    pub fn admin_request_body(&self) -> Result<Vec<u8>, net_error> {
+        let body = match self {
+            HttpRequestType::PostPeerBan {
+                target,
+                duration_secs,
+                reason,
+                ..
+            } => serde_json::to_vec(&PeerBanRequestBody {
+                public_key_hash: match target {
+                    PeerBanTarget::PublicKeyHash(hash) => Some(hash.to_hex()),
+                    PeerBanTarget::AddressRange(..) => None,
+                },
+                address_range: target.to_address_range(),
+                duration_secs: *duration_secs,
+                reason: reason.clone(),
+            }),
+            _ => return Ok(vec![]),
+        };
+        body.map_err(|e| {
+            net_error::SerializeError(format!("Failed to serialize peer ban to JSON: {:?}", &e))
+        })
     }
 
     pub fn request_path(&self) -> String {
@@ -2907,7 +4160,17 @@ impl HttpRequestType {
             HttpRequestType::GetTransactionUnconfirmed(_md, txid) => {
                 format!("/v2/transactions/unconfirmed/{}", txid)
             }
-            HttpRequestType::PostTransaction(_md, ..) => "/v2/transactions".to_string(),
+            HttpRequestType::PostTransaction(_md, _, _, diagnostics) => {
+                if *diagnostics {
+                    "/v2/transactions?diagnostics=1".to_string()
+                } else {
+                    "/v2/transactions".to_string()
+                }
+            }
+            HttpRequestType::PostTransactionBatch(_md, ..) => "/v2/transactions/batch".to_string(),
+            HttpRequestType::PostTransactionSponsorship(_md, ..) => {
+                "/v2/transactions/sponsor".to_string()
+            }
             HttpRequestType::PostBlock(_md, ch, ..) => format!("/v2/blocks/upload/{}", &ch),
             HttpRequestType::PostMicroblock(_md, _, tip_req) => format!(
                 "/v2/microblocks{}",
@@ -2949,7 +4212,33 @@ impl HttpRequestType {
                 map_name.as_str(),
                 HttpRequestType::make_tip_query_string(tip_req, *with_proof)
             ),
+            HttpRequestType::GetMapEntries {
+                contract_addr,
+                contract_name,
+                map_name,
+                after,
+                limit,
+                tip_req,
+                ..
+            } => {
+                let mut args = vec![];
+                if let Some(after) = after {
+                    args.push(format!("after=0x{}", ClaritySerializable::serialize(after)));
+                }
+                args.push(format!("limit={}", limit));
+                let tip_query = HttpRequestType::make_tip_query_string(tip_req, true);
+                format!(
+                    "/v2/map_entries/{}/{}/{}{}{}{}",
+                    &contract_addr.to_string(),
+                    contract_name.as_str(),
+                    map_name.as_str(),
+                    tip_query,
+                    if tip_query.is_empty() { "?" } else { "&" },
+                    args.join("&")
+                )
+            }
             HttpRequestType::GetTransferCost(_md) => "/v2/fees/transfer".into(),
+            HttpRequestType::GetSubnetFeeRateEstimate(_md) => "/v2/fees/subnet".into(),
             HttpRequestType::GetContractABI(_, contract_addr, contract_name, tip_req) => format!(
                 "/v2/contracts/interface/{}/{}{}",
                 contract_addr,
@@ -2983,155 +4272,682 @@ impl HttpRequestType {
                 trait_id.contract_identifier.name.as_str(),
                 HttpRequestType::make_tip_query_string(tip_req, true)
             ),
-            HttpRequestType::CallReadOnlyFunction(
-                _,
-                contract_addr,
-                contract_name,
-                _,
-                func_name,
-                _,
+            HttpRequestType::CallReadOnlyFunction(
+                _,
+                contract_addr,
+                contract_name,
+                _,
+                func_name,
+                _,
+                _,
+                tip_req,
+            ) => format!(
+                "/v2/contracts/call-read/{}/{}/{}{}",
+                contract_addr,
+                contract_name.as_str(),
+                func_name.as_str(),
+                HttpRequestType::make_tip_query_string(tip_req, true)
+            ),
+            HttpRequestType::TraceReadOnlyFunction(
+                _,
+                contract_addr,
+                contract_name,
+                _,
+                func_name,
+                _,
+                _,
+                tip_req,
+            ) => format!(
+                "/v2/contracts/trace/{}/{}/{}{}",
+                contract_addr,
+                contract_name.as_str(),
+                func_name.as_str(),
+                HttpRequestType::make_tip_query_string(tip_req, true)
+            ),
+            HttpRequestType::OptionsPreflight(_md, path) => path.to_string(),
+            HttpRequestType::GetAttachmentsInv(_md, index_block_hash, pages_indexes) => {
+                let pages_query = match pages_indexes.len() {
+                    0 => format!(""),
+                    _n => {
+                        let mut indexes = pages_indexes
+                            .iter()
+                            .map(|i| format!("{}", i))
+                            .collect::<Vec<String>>();
+                        indexes.sort();
+                        format!("&pages_indexes={}", indexes.join(","))
+                    }
+                };
+                let index_block_hash = format!("index_block_hash={}", index_block_hash);
+                format!("/v2/attachments/inv?{}{}", index_block_hash, pages_query,)
+            }
+            HttpRequestType::GetAttachment(_, content_hash) => {
+                format!("/v2/attachments/{}", to_hex(&content_hash.0[..]))
+            }
+            HttpRequestType::MemPoolQuery(_, _, page_id_opt, bloom_depth_opt) => {
+                match (page_id_opt, bloom_depth_opt) {
+                    (Some(page_id), Some(depth)) => {
+                        format!(
+                            "/v2/mempool/query?page_id={}&bloom_depth={}",
+                            page_id, depth
+                        )
+                    }
+                    (Some(page_id), None) => format!("/v2/mempool/query?page_id={}", page_id),
+                    (None, Some(depth)) => format!("/v2/mempool/query?bloom_depth={}", depth),
+                    (None, None) => "/v2/mempool/query".to_string(),
+                }
+            }
+            HttpRequestType::FeeRateEstimate(_, _, _) => self.get_path().to_string(),
+            HttpRequestType::ClientError(_md, e) => match e {
+                ClientError::NotFound(path) => path.to_string(),
+                _ => "error path unknown".into(),
+            },
+            HttpRequestType::GetWithdrawalStx {
+                metadata: _,
+                withdraw_block_height,
+                sender,
+                withdrawal_id,
+                amount,
+            } => format!(
+                "/v2/withdrawal/stx/{}/{}/{}/{}",
+                withdraw_block_height, sender, withdrawal_id, amount
+            ),
+            HttpRequestType::BlockProposal(..) => self.get_path().to_string(),
+            HttpRequestType::ValidateBlockProposal(..) => self.get_path().to_string(),
+            HttpRequestType::GetWithdrawalNft {
+                metadata: _,
+                withdraw_block_height,
+                sender,
+                withdrawal_id,
+                asset_identifier,
+                id,
+            } => format!(
+                "/v2/withdrawal/nft/{}/{}/{}/{}/{}/{}/{}",
+                withdraw_block_height,
+                sender,
+                withdrawal_id,
+                StacksAddress::from(asset_identifier.clone().contract_identifier.issuer),
+                asset_identifier.contract_identifier.name.as_str(),
+                asset_identifier.asset_name.to_string(),
+                id
+            ),
+            HttpRequestType::GetWithdrawalProof {
+                metadata: _,
+                withdraw_block_height,
+                withdrawal_id,
+            } => format!(
+                "/v2/withdrawal/proof/{}/{}",
+                withdraw_block_height, withdrawal_id
+            ),
+            HttpRequestType::GetWithdrawalStatus {
+                metadata: _,
+                withdraw_block_height,
+            } => format!("/v2/withdrawal/status/{}", withdraw_block_height),
+            HttpRequestType::GetClarityDataProof(_md, _key, tip_req) => format!(
+                "/v2/clarity/proof{}",
+                HttpRequestType::make_tip_query_string(tip_req, true)
+            ),
+            HttpRequestType::GetTransactionProof {
+                metadata: _,
+                index_block_hash,
+                txid,
+            } => format!("/v2/transactions/proof/{}/{}", index_block_hash, txid),
+            HttpRequestType::GetDepositStatus {
+                metadata: _,
+                l1_txid,
+            } => format!("/v2/deposits/{}", l1_txid),
+            HttpRequestType::GetBlockCosts {
+                metadata: _,
+                index_block_hash,
+            } => format!("/v2/blocks/{}/costs", index_block_hash),
+            HttpRequestType::GetBlockHeader {
+                metadata: _,
+                index_block_hash,
+            } => format!("/v2/blocks/{}/header", index_block_hash),
+            HttpRequestType::GetBlockStateDiff {
+                metadata: _,
+                index_block_hash,
+            } => format!("/v2/blocks/{}/state-diff", index_block_hash),
+            HttpRequestType::GetTransactionReceipt { metadata: _, txid } => {
+                format!("/v2/transactions/{}/receipt", txid)
+            }
+            HttpRequestType::GetNonceGaps {
+                metadata: _,
+                address,
                 tip_req,
-            ) => format!(
-                "/v2/contracts/call-read/{}/{}/{}{}",
-                contract_addr,
-                contract_name.as_str(),
-                func_name.as_str(),
+            } => format!(
+                "/v2/accounts/{}/nonce-gaps{}",
+                address,
                 HttpRequestType::make_tip_query_string(tip_req, true)
             ),
-            HttpRequestType::OptionsPreflight(_md, path) => path.to_string(),
-            HttpRequestType::GetAttachmentsInv(_md, index_block_hash, pages_indexes) => {
-                let pages_query = match pages_indexes.len() {
-                    0 => format!(""),
-                    _n => {
-                        let mut indexes = pages_indexes
-                            .iter()
-                            .map(|i| format!("{}", i))
-                            .collect::<Vec<String>>();
-                        indexes.sort();
-                        format!("&pages_indexes={}", indexes.join(","))
+            HttpRequestType::GetSubnetStatus(_md) => "/v2/subnet/status".to_string(),
+            HttpRequestType::PostDivergenceSnapshot { .. } => {
+                "/v2/admin/divergence_snapshot".to_string()
+            }
+            HttpRequestType::GetMemPoolTxs {
+                ref filter,
+                ref cursor,
+                limit,
+                ..
+            } => {
+                let mut args = HttpRequestType::make_mempool_list_filter_query_string(filter);
+                if let Some(cursor) = cursor {
+                    args.push(format!("cursor={}", cursor));
+                }
+                args.push(format!("limit={}", limit));
+                format!("/v2/mempool?{}", args.join("&"))
+            }
+            HttpRequestType::GetMemPoolSummary { ref filter, .. } => {
+                let args = HttpRequestType::make_mempool_list_filter_query_string(filter);
+                if args.is_empty() {
+                    "/v2/mempool/summary".to_string()
+                } else {
+                    format!("/v2/mempool/summary?{}", args.join("&"))
+                }
+            }
+            HttpRequestType::DeleteMemPoolTx {
+                ref txid,
+                ref reason,
+                ..
+            } => match reason {
+                Some(reason) => format!(
+                    "/v2/mempool/{}?reason={}",
+                    txid,
+                    form_urlencoded::byte_serialize(reason.as_bytes()).collect::<String>()
+                ),
+                None => format!("/v2/mempool/{}", txid),
+            },
+            HttpRequestType::GetAdminPeers { .. } => "/v2/admin/peers".to_string(),
+            HttpRequestType::GetAdminObservers { .. } => "/v2/admin/observers".to_string(),
+            HttpRequestType::GetPeerBans { .. } => "/v2/admin/bans".to_string(),
+            HttpRequestType::PostPeerBan { .. } => "/v2/admin/bans/add".to_string(),
+            HttpRequestType::DeletePeerBan { ban_id, .. } => format!("/v2/admin/bans/{}", ban_id),
+            HttpRequestType::GetBlockResults { ref block, .. } => match block {
+                BlockResultsQuery::IndexBlockHash(index_block_hash) => {
+                    format!("/v2/block_results/{}", index_block_hash)
+                }
+                BlockResultsQuery::Height(block_height) => {
+                    format!("/v2/block_results/height/{}", block_height)
+                }
+            },
+        }
+    }
+
+    pub fn get_path(&self) -> &'static str {
+        match self {
+            HttpRequestType::GetInfo(..) => "/v2/info",
+            HttpRequestType::GetNeighbors(..) => "/v2/neighbors",
+            HttpRequestType::GetHeaders(..) => "/v2/headers/:height",
+            HttpRequestType::GetBlock(..) => "/v2/blocks/:hash",
+            HttpRequestType::GetMicroblocksIndexed(..) => "/v2/microblocks/:hash",
+            HttpRequestType::GetMicroblocksConfirmed(..) => "/v2/microblocks/confirmed/:hash",
+            HttpRequestType::GetMicroblocksUnconfirmed(..) => {
+                "/v2/microblocks/unconfirmed/:hash/:seq"
+            }
+            HttpRequestType::GetTransactionUnconfirmed(..) => "/v2/transactions/unconfirmed/:txid",
+            HttpRequestType::PostTransaction(..) => "/v2/transactions",
+            HttpRequestType::PostTransactionBatch(..) => "/v2/transactions/batch",
+            HttpRequestType::PostTransactionSponsorship(..) => "/v2/transactions/sponsor",
+            HttpRequestType::PostBlock(..) => "/v2/blocks/upload/:block",
+            HttpRequestType::PostMicroblock(..) => "/v2/microblocks",
+            HttpRequestType::GetAccount(..) => "/v2/accounts/:principal",
+            HttpRequestType::GetDataVar(..) => "/v2/data_var/:principal/:contract_name/:var_name",
+            HttpRequestType::GetMapEntry(..) => "/v2/map_entry/:principal/:contract_name/:map_name",
+            HttpRequestType::GetMapEntries { .. } => {
+                "/v2/map_entries/:principal/:contract_name/:map_name"
+            }
+            HttpRequestType::GetTransferCost(..) => "/v2/fees/transfer",
+            HttpRequestType::GetSubnetFeeRateEstimate(..) => "/v2/fees/subnet",
+            HttpRequestType::GetContractABI(..) => {
+                "/v2/contracts/interface/:principal/:contract_name"
+            }
+            HttpRequestType::GetContractSrc(..) => "/v2/contracts/source/:principal/:contract_name",
+            HttpRequestType::CallReadOnlyFunction(..) => {
+                "/v2/contracts/call-read/:principal/:contract_name/:func_name"
+            }
+            HttpRequestType::TraceReadOnlyFunction(..) => {
+                "/v2/contracts/trace/:principal/:contract_name/:func_name"
+            }
+            HttpRequestType::GetAttachmentsInv(..) => "/v2/attachments/inv",
+            HttpRequestType::GetAttachment(..) => "/v2/attachments/:hash",
+            HttpRequestType::GetIsTraitImplemented(..) => "/v2/traits/:principal/:contract_name",
+            HttpRequestType::MemPoolQuery(..) => "/v2/mempool/query",
+            HttpRequestType::FeeRateEstimate(_, _, _) => "/v2/fees/transaction",
+            HttpRequestType::OptionsPreflight(..) | HttpRequestType::ClientError(..) => "/",
+            HttpRequestType::GetWithdrawalStx { .. } => {
+                "/v2/withdrawal/stx/:block-height/:sender/:withdrawal_id/:amount"
+            }
+            HttpRequestType::BlockProposal(..) => PATH_STR_POST_BLOCK_PROPOSAL,
+            HttpRequestType::ValidateBlockProposal(..) => PATH_STR_POST_VALIDATE_BLOCK_PROPOSAL,
+            HttpRequestType::GetWithdrawalNft { .. } => {
+                "/v2/withdrawal/nft/:block-height/:sender/:withdrawal_id/:contract_address/:contract_name/:asset_name/:id"
+            }
+            HttpRequestType::GetWithdrawalProof { .. } => {
+                "/v2/withdrawal/proof/:block-height/:withdrawal_id"
+            }
+            HttpRequestType::GetWithdrawalStatus { .. } => "/v2/withdrawal/status/:block-height",
+            HttpRequestType::GetClarityDataProof(..) => "/v2/clarity/proof",
+            HttpRequestType::GetTransactionProof { .. } => {
+                "/v2/transactions/proof/:index_block_hash/:txid"
+            }
+            HttpRequestType::GetDepositStatus { .. } => "/v2/deposits/:l1_txid",
+            HttpRequestType::GetBlockCosts { .. } => "/v2/blocks/:index_block_hash/costs",
+            HttpRequestType::GetBlockHeader { .. } => "/v2/blocks/:index_block_hash/header",
+            HttpRequestType::GetBlockStateDiff { .. } => {
+                "/v2/blocks/:index_block_hash/state-diff"
+            }
+            HttpRequestType::GetTransactionReceipt { .. } => "/v2/transactions/:txid/receipt",
+            HttpRequestType::GetNonceGaps { .. } => "/v2/accounts/:principal/nonce-gaps",
+            HttpRequestType::GetSubnetStatus(..) => "/v2/subnet/status",
+            HttpRequestType::PostDivergenceSnapshot { .. } => "/v2/admin/divergence_snapshot",
+            HttpRequestType::GetMemPoolTxs { .. } => "/v2/mempool",
+            HttpRequestType::GetMemPoolSummary { .. } => "/v2/mempool/summary",
+            HttpRequestType::DeleteMemPoolTx { .. } => "/v2/mempool/:txid",
+            HttpRequestType::GetAdminPeers { .. } => "/v2/admin/peers",
+            HttpRequestType::GetAdminObservers { .. } => "/v2/admin/observers",
+            HttpRequestType::GetPeerBans { .. } => "/v2/admin/bans",
+            HttpRequestType::PostPeerBan { .. } => "/v2/admin/bans/add",
+            HttpRequestType::DeletePeerBan { .. } => "/v2/admin/bans/:ban_id",
+            HttpRequestType::GetBlockResults { ref block, .. } => match block {
+                BlockResultsQuery::IndexBlockHash(_) => "/v2/block_results/:hash",
+                BlockResultsQuery::Height(_) => "/v2/block_results/height/:block-height",
+            },
+        }
+    }
+
+    pub fn send<W: Write>(&self, _protocol: &mut StacksHttp, fd: &mut W) -> Result<(), net_error> {
+        match self {
+            HttpRequestType::PostTransaction(md, tx, attachment, _diagnostics) => {
+                let mut tx_bytes = vec![];
+                write_next(&mut tx_bytes, tx)?;
+                let tx_hex = to_hex(&tx_bytes[..]);
+
+                let (content_type, request_body_bytes) = match attachment {
+                    None => {
+                        // Transaction does not include an attachment: HttpContentType::Bytes (more compressed)
+                        (Some(&HttpContentType::Bytes), tx_bytes)
+                    }
+                    Some(attachment) => {
+                        // Transaction is including an attachment: HttpContentType::JSON
+                        let request_body = PostTransactionRequestBody {
+                            tx: tx_hex,
+                            attachment: Some(to_hex(&attachment.content[..])),
+                        };
+
+                        let mut request_body_bytes = vec![];
+                        serde_json::to_writer(&mut request_body_bytes, &request_body).map_err(
+                            |e| {
+                                net_error::SerializeError(format!(
+                                    "Failed to serialize read-only call to JSON: {:?}",
+                                    &e
+                                ))
+                            },
+                        )?;
+                        (Some(&HttpContentType::JSON), request_body_bytes)
                     }
                 };
-                let index_block_hash = format!("index_block_hash={}", index_block_hash);
-                format!("/v2/attachments/inv?{}{}", index_block_hash, pages_query,)
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(request_body_bytes.len() as u32),
+                    content_type,
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+                fd.write_all(&request_body_bytes)
+                    .map_err(net_error::WriteError)?;
             }
-            HttpRequestType::GetAttachment(_, content_hash) => {
-                format!("/v2/attachments/{}", to_hex(&content_hash.0[..]))
+            HttpRequestType::PostTransactionBatch(md, txs, all_or_nothing) => {
+                let request_body = PostTransactionBatchRequestBody {
+                    txs: txs
+                        .iter()
+                        .map(|tx| {
+                            let mut tx_bytes = vec![];
+                            write_next(&mut tx_bytes, tx)?;
+                            Ok(to_hex(&tx_bytes[..]))
+                        })
+                        .collect::<Result<Vec<String>, net_error>>()?,
+                    all_or_nothing: *all_or_nothing,
+                };
+
+                let mut request_body_bytes = vec![];
+                serde_json::to_writer(&mut request_body_bytes, &request_body).map_err(|e| {
+                    net_error::SerializeError(format!(
+                        "Failed to serialize transaction batch to JSON: {:?}",
+                        &e
+                    ))
+                })?;
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(request_body_bytes.len() as u32),
+                    Some(&HttpContentType::JSON),
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+                fd.write_all(&request_body_bytes)
+                    .map_err(net_error::WriteError)?;
+            }
+            HttpRequestType::GetClarityDataProof(md, key, ..) => {
+                let mut request_body_bytes = vec![];
+                serde_json::to_writer(&mut request_body_bytes, key).map_err(|e| {
+                    net_error::SerializeError(format!(
+                        "Failed to serialize Clarity data key to JSON: {:?}",
+                        &e
+                    ))
+                })?;
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(request_body_bytes.len() as u32),
+                    Some(&HttpContentType::JSON),
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+                fd.write_all(&request_body_bytes)
+                    .map_err(net_error::WriteError)?;
+            }
+            HttpRequestType::PostTransactionSponsorship(md, txid, tx) => {
+                let mut tx_bytes = vec![];
+                write_next(&mut tx_bytes, tx)?;
+                let request_body = PostTransactionSponsorshipRequestBody {
+                    txid: txid.to_hex(),
+                    tx: to_hex(&tx_bytes[..]),
+                };
+
+                let mut request_body_bytes = vec![];
+                serde_json::to_writer(&mut request_body_bytes, &request_body).map_err(|e| {
+                    net_error::SerializeError(format!(
+                        "Failed to serialize transaction sponsorship to JSON: {:?}",
+                        &e
+                    ))
+                })?;
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(request_body_bytes.len() as u32),
+                    Some(&HttpContentType::JSON),
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+                fd.write_all(&request_body_bytes)
+                    .map_err(net_error::WriteError)?;
+            }
+            HttpRequestType::PostBlock(md, _ch, block) => {
+                let mut block_bytes = vec![];
+                write_next(&mut block_bytes, block)?;
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(block_bytes.len() as u32),
+                    Some(&HttpContentType::Bytes),
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+                fd.write_all(&block_bytes).map_err(net_error::WriteError)?;
+            }
+            HttpRequestType::PostMicroblock(md, mb, ..) => {
+                let mut mb_bytes = vec![];
+                write_next(&mut mb_bytes, mb)?;
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(mb_bytes.len() as u32),
+                    Some(&HttpContentType::Bytes),
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+                fd.write_all(&mb_bytes).map_err(net_error::WriteError)?;
+            }
+            HttpRequestType::GetMapEntry(
+                md,
+                _contract_addr,
+                _contract_name,
+                _map_name,
+                key,
+                ..,
+            ) => {
+                let mut request_bytes = vec![];
+                key.serialize_write(&mut request_bytes)
+                    .map_err(net_error::WriteError)?;
+                let request_json = format!("\"{}\"", to_hex(&request_bytes));
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(request_json.as_bytes().len() as u32),
+                    Some(&HttpContentType::JSON),
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+                fd.write_all(&request_json.as_bytes())
+                    .map_err(net_error::WriteError)?;
             }
-            HttpRequestType::MemPoolQuery(_, _, page_id_opt) => match page_id_opt {
-                Some(page_id) => {
-                    format!("/v2/mempool/query?page_id={}", page_id)
-                }
-                None => "/v2/mempool/query".to_string(),
-            },
-            HttpRequestType::FeeRateEstimate(_, _, _) => self.get_path().to_string(),
-            HttpRequestType::ClientError(_md, e) => match e {
-                ClientError::NotFound(path) => path.to_string(),
-                _ => "error path unknown".into(),
-            },
-            HttpRequestType::GetWithdrawalStx {
-                metadata: _,
-                withdraw_block_height,
-                sender,
-                withdrawal_id,
-                amount,
-            } => format!(
-                "/v2/withdrawal/stx/{}/{}/{}/{}",
-                withdraw_block_height, sender, withdrawal_id, amount
-            ),
-            HttpRequestType::BlockProposal(..) => self.get_path().to_string(),
-            HttpRequestType::GetWithdrawalNft {
-                metadata: _,
-                withdraw_block_height,
+            HttpRequestType::CallReadOnlyFunction(
+                md,
+                _contract_addr,
+                _contract_name,
                 sender,
-                withdrawal_id,
-                asset_identifier,
-                id,
-            } => format!(
-                "/v2/withdrawal/nft/{}/{}/{}/{}/{}/{}/{}",
-                withdraw_block_height,
+                _func_name,
+                func_args,
+                cost_budget,
+                ..,
+            )
+            | HttpRequestType::TraceReadOnlyFunction(
+                md,
+                _contract_addr,
+                _contract_name,
                 sender,
-                withdrawal_id,
-                StacksAddress::from(asset_identifier.clone().contract_identifier.issuer),
-                asset_identifier.contract_identifier.name.as_str(),
-                asset_identifier.asset_name.to_string(),
-                id
-            ),
-        }
-    }
+                _func_name,
+                func_args,
+                cost_budget,
+                ..,
+            ) => {
+                let mut args = vec![];
+                for arg in func_args.iter() {
+                    let mut arg_bytes = vec![];
+                    arg.serialize_write(&mut arg_bytes)
+                        .map_err(net_error::WriteError)?;
+                    args.push(to_hex(&arg_bytes));
+                }
 
-    pub fn get_path(&self) -> &'static str {
-        match self {
-            HttpRequestType::GetInfo(..) => "/v2/info",
-            HttpRequestType::GetNeighbors(..) => "/v2/neighbors",
-            HttpRequestType::GetHeaders(..) => "/v2/headers/:height",
-            HttpRequestType::GetBlock(..) => "/v2/blocks/:hash",
-            HttpRequestType::GetMicroblocksIndexed(..) => "/v2/microblocks/:hash",
-            HttpRequestType::GetMicroblocksConfirmed(..) => "/v2/microblocks/confirmed/:hash",
-            HttpRequestType::GetMicroblocksUnconfirmed(..) => {
-                "/v2/microblocks/unconfirmed/:hash/:seq"
+                let request_body = CallReadOnlyRequestBody {
+                    sender: sender.to_string(),
+                    arguments: args,
+                    cost_budget: cost_budget.clone(),
+                };
+
+                let mut request_body_bytes = vec![];
+                serde_json::to_writer(&mut request_body_bytes, &request_body).map_err(|e| {
+                    net_error::SerializeError(format!(
+                        "Failed to serialize read-only call to JSON: {:?}",
+                        &e
+                    ))
+                })?;
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(request_body_bytes.len() as u32),
+                    Some(&HttpContentType::JSON),
+                    |fd| stacks_height_headers(fd, md),
+                )?;
+                fd.write_all(&request_body_bytes)
+                    .map_err(net_error::WriteError)?;
             }
-            HttpRequestType::GetTransactionUnconfirmed(..) => "/v2/transactions/unconfirmed/:txid",
-            HttpRequestType::PostTransaction(..) => "/v2/transactions",
-            HttpRequestType::PostBlock(..) => "/v2/blocks/upload/:block",
-            HttpRequestType::PostMicroblock(..) => "/v2/microblocks",
-            HttpRequestType::GetAccount(..) => "/v2/accounts/:principal",
-            HttpRequestType::GetDataVar(..) => "/v2/data_var/:principal/:contract_name/:var_name",
-            HttpRequestType::GetMapEntry(..) => "/v2/map_entry/:principal/:contract_name/:map_name",
-            HttpRequestType::GetTransferCost(..) => "/v2/fees/transfer",
-            HttpRequestType::GetContractABI(..) => {
-                "/v2/contracts/interface/:principal/:contract_name"
+            HttpRequestType::BlockProposal(md, proposal)
+            | HttpRequestType::ValidateBlockProposal(md, proposal) => {
+                let mut request_body_bytes = vec![];
+                serde_json::to_writer(&mut request_body_bytes, proposal).map_err(|e| {
+                    net_error::SerializeError(format!(
+                        "Failed to serialize block proposal to JSON: {:?}",
+                        &e
+                    ))
+                })?;
+
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(request_body_bytes.len() as u32),
+                    Some(&HttpContentType::JSON),
+                    empty_headers,
+                )?;
+                fd.write_all(&request_body_bytes)
+                    .map_err(net_error::WriteError)?;
             }
-            HttpRequestType::GetContractSrc(..) => "/v2/contracts/source/:principal/:contract_name",
-            HttpRequestType::CallReadOnlyFunction(..) => {
-                "/v2/contracts/call-read/:principal/:contract_name/:func_name"
+            HttpRequestType::MemPoolQuery(md, query, ..) => {
+                let request_body_bytes = query.serialize_to_vec();
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(request_body_bytes.len() as u32),
+                    Some(&HttpContentType::Bytes),
+                    empty_headers,
+                )?;
+                fd.write_all(&request_body_bytes)
+                    .map_err(net_error::WriteError)?;
             }
-            HttpRequestType::GetAttachmentsInv(..) => "/v2/attachments/inv",
-            HttpRequestType::GetAttachment(..) => "/v2/attachments/:hash",
-            HttpRequestType::GetIsTraitImplemented(..) => "/v2/traits/:principal/:contract_name",
-            HttpRequestType::MemPoolQuery(..) => "/v2/mempool/query",
-            HttpRequestType::FeeRateEstimate(_, _, _) => "/v2/fees/transaction",
-            HttpRequestType::OptionsPreflight(..) | HttpRequestType::ClientError(..) => "/",
-            HttpRequestType::GetWithdrawalStx { .. } => {
-                "/v2/withdrawal/stx/:block-height/:sender/:withdrawal_id/:amount"
+            HttpRequestType::PostDivergenceSnapshot {
+                metadata: md,
+                auth_token,
+            } => {
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "POST",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(0),
+                    None,
+                    |fd| {
+                        stacks_height_headers(fd, md)?;
+                        if let Some(auth_token) = auth_token {
+                            fd.write_all(format!("Authorization: {}\r\n", auth_token).as_bytes())
+                                .map_err(codec_error::WriteError)?;
+                        }
+                        Ok(())
+                    },
+                )?;
             }
-            HttpRequestType::BlockProposal(..) => PATH_STR_POST_BLOCK_PROPOSAL,
-            HttpRequestType::GetWithdrawalNft { .. } => {
-                "/v2/withdrawal/nft/:block-height/:sender/:withdrawal_id/:contract_address/:contract_name/:asset_name/:id"
+            HttpRequestType::DeleteMemPoolTx {
+                metadata: md,
+                auth_token,
+                ..
+            } => {
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "DELETE",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    Some(0),
+                    None,
+                    |fd| {
+                        stacks_height_headers(fd, md)?;
+                        if let Some(auth_token) = auth_token {
+                            fd.write_all(format!("Authorization: {}\r\n", auth_token).as_bytes())
+                                .map_err(codec_error::WriteError)?;
+                        }
+                        Ok(())
+                    },
+                )?;
             }
-        }
-    }
-
-    pub fn send<W: Write>(&self, _protocol: &mut StacksHttp, fd: &mut W) -> Result<(), net_error> {
-        match self {
-            HttpRequestType::PostTransaction(md, tx, attachment) => {
-                let mut tx_bytes = vec![];
-                write_next(&mut tx_bytes, tx)?;
-                let tx_hex = to_hex(&tx_bytes[..]);
-
-                let (content_type, request_body_bytes) = match attachment {
-                    None => {
-                        // Transaction does not include an attachment: HttpContentType::Bytes (more compressed)
-                        (Some(&HttpContentType::Bytes), tx_bytes)
-                    }
-                    Some(attachment) => {
-                        // Transaction is including an attachment: HttpContentType::JSON
-                        let request_body = PostTransactionRequestBody {
-                            tx: tx_hex,
-                            attachment: Some(to_hex(&attachment.content[..])),
-                        };
-
-                        let mut request_body_bytes = vec![];
-                        serde_json::to_writer(&mut request_body_bytes, &request_body).map_err(
-                            |e| {
-                                net_error::SerializeError(format!(
-                                    "Failed to serialize read-only call to JSON: {:?}",
-                                    &e
-                                ))
-                            },
-                        )?;
-                        (Some(&HttpContentType::JSON), request_body_bytes)
-                    }
-                };
+            HttpRequestType::GetAdminPeers {
+                metadata: md,
+                auth_token,
+            }
+            | HttpRequestType::GetAdminObservers {
+                metadata: md,
+                auth_token,
+            } => {
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "GET",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    None,
+                    None,
+                    |fd| {
+                        stacks_height_headers(fd, md)?;
+                        if let Some(auth_token) = auth_token {
+                            fd.write_all(format!("Authorization: {}\r\n", auth_token).as_bytes())
+                                .map_err(codec_error::WriteError)?;
+                        }
+                        Ok(())
+                    },
+                )?;
+            }
+            HttpRequestType::GetPeerBans {
+                metadata: md,
+                auth_token,
+            } => {
+                HttpRequestPreamble::new_serialized(
+                    fd,
+                    &md.version,
+                    "GET",
+                    &self.request_path(),
+                    &md.peer,
+                    md.keep_alive,
+                    None,
+                    None,
+                    |fd| {
+                        stacks_height_headers(fd, md)?;
+                        if let Some(auth_token) = auth_token {
+                            fd.write_all(format!("Authorization: {}\r\n", auth_token).as_bytes())
+                                .map_err(codec_error::WriteError)?;
+                        }
+                        Ok(())
+                    },
+                )?;
+            }
+            HttpRequestType::PostPeerBan {
+                metadata: md,
+                auth_token,
+                ..
+            } => {
+                let request_body_bytes = self.admin_request_body()?;
 
                 HttpRequestPreamble::new_serialized(
                     fd,
@@ -3141,764 +4957,1161 @@ impl HttpRequestType {
                     &md.peer,
                     md.keep_alive,
                     Some(request_body_bytes.len() as u32),
-                    content_type,
-                    |fd| stacks_height_headers(fd, md),
+                    Some(&HttpContentType::JSON),
+                    |fd| {
+                        stacks_height_headers(fd, md)?;
+                        if let Some(auth_token) = auth_token {
+                            fd.write_all(format!("Authorization: {}\r\n", auth_token).as_bytes())
+                                .map_err(codec_error::WriteError)?;
+                        }
+                        Ok(())
+                    },
                 )?;
                 fd.write_all(&request_body_bytes)
                     .map_err(net_error::WriteError)?;
             }
-            HttpRequestType::PostBlock(md, _ch, block) => {
-                let mut block_bytes = vec![];
-                write_next(&mut block_bytes, block)?;
-
+            HttpRequestType::DeletePeerBan {
+                metadata: md,
+                auth_token,
+                ..
+            } => {
                 HttpRequestPreamble::new_serialized(
                     fd,
                     &md.version,
-                    "POST",
+                    "DELETE",
                     &self.request_path(),
                     &md.peer,
                     md.keep_alive,
-                    Some(block_bytes.len() as u32),
-                    Some(&HttpContentType::Bytes),
-                    |fd| stacks_height_headers(fd, md),
+                    Some(0),
+                    None,
+                    |fd| {
+                        stacks_height_headers(fd, md)?;
+                        if let Some(auth_token) = auth_token {
+                            fd.write_all(format!("Authorization: {}\r\n", auth_token).as_bytes())
+                                .map_err(codec_error::WriteError)?;
+                        }
+                        Ok(())
+                    },
                 )?;
-                fd.write_all(&block_bytes).map_err(net_error::WriteError)?;
             }
-            HttpRequestType::PostMicroblock(md, mb, ..) => {
-                let mut mb_bytes = vec![];
-                write_next(&mut mb_bytes, mb)?;
-
+            other_type => {
+                let md = other_type.metadata();
+                let request_path = other_type.request_path();
                 HttpRequestPreamble::new_serialized(
                     fd,
                     &md.version,
-                    "POST",
-                    &self.request_path(),
+                    "GET",
+                    &request_path,
                     &md.peer,
                     md.keep_alive,
-                    Some(mb_bytes.len() as u32),
-                    Some(&HttpContentType::Bytes),
+                    None,
+                    None,
                     |fd| stacks_height_headers(fd, md),
                 )?;
-                fd.write_all(&mb_bytes).map_err(net_error::WriteError)?;
             }
-            HttpRequestType::GetMapEntry(
-                md,
-                _contract_addr,
-                _contract_name,
-                _map_name,
-                key,
-                ..,
-            ) => {
-                let mut request_bytes = vec![];
-                key.serialize_write(&mut request_bytes)
-                    .map_err(net_error::WriteError)?;
-                let request_json = format!("\"{}\"", to_hex(&request_bytes));
+        }
+        Ok(())
+    }
+}
 
-                HttpRequestPreamble::new_serialized(
-                    fd,
-                    &md.version,
-                    "POST",
-                    &self.request_path(),
-                    &md.peer,
-                    md.keep_alive,
-                    Some(request_json.as_bytes().len() as u32),
-                    Some(&HttpContentType::JSON),
-                    |fd| stacks_height_headers(fd, md),
-                )?;
-                fd.write_all(&request_json.as_bytes())
-                    .map_err(net_error::WriteError)?;
+impl HttpResponseType {
+    fn try_parse<R: Read, F>(
+        protocol: &mut StacksHttp,
+        regex: &Regex,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        request_path: &String,
+        fd: &mut R,
+        len_hint: Option<usize>,
+        parser: F,
+    ) -> Result<Option<HttpResponseType>, net_error>
+    where
+        F: Fn(
+            &mut StacksHttp,
+            HttpVersion,
+            &HttpResponsePreamble,
+            &mut R,
+            Option<usize>,
+        ) -> Result<HttpResponseType, net_error>,
+    {
+        if regex.is_match(request_path) {
+            let payload = parser(protocol, request_version, preamble, fd, len_hint)?;
+            Ok(Some(payload))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_error<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+    ) -> Result<HttpResponseType, net_error> {
+        if preamble.status_code < 400 || preamble.status_code > 599 {
+            return Err(net_error::DeserializeError(
+                "Inavlid response: not an error".to_string(),
+            ));
+        }
+
+        if preamble.content_type != HttpContentType::Text
+            && preamble.content_type != HttpContentType::JSON
+        {
+            return Err(net_error::DeserializeError(format!(
+                "Invalid error response: expected text/plain or application/json, got {:?}",
+                &preamble.content_type
+            )));
+        }
+
+        let mut error_text = String::new();
+        fd.read_to_string(&mut error_text)
+            .map_err(net_error::ReadError)?;
+
+        let md = HttpResponseMetadata::from_preamble(request_version, preamble);
+        let resp = match preamble.status_code {
+            400 => HttpResponseType::BadRequest(md, error_text),
+            401 => HttpResponseType::Unauthorized(md, error_text),
+            402 => HttpResponseType::PaymentRequired(md, error_text),
+            403 => HttpResponseType::Forbidden(md, error_text),
+            404 => HttpResponseType::NotFound(md, error_text),
+            429 => HttpResponseType::TooManyRequests(md, error_text),
+            500 => HttpResponseType::ServerError(md, error_text),
+            503 => HttpResponseType::ServiceUnavailable(md, error_text),
+            _ => HttpResponseType::Error(md, preamble.status_code, error_text),
+        };
+        Ok(resp)
+    }
+
+    fn parse_bytestream<R: Read, T: StacksMessageCodec>(
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+        max_len: u64,
+    ) -> Result<T, net_error> {
+        // content-type has to be Bytes
+        if preamble.content_type != HttpContentType::Bytes {
+            return Err(net_error::DeserializeError(
+                "Invalid content-type: expected application/octet-stream".to_string(),
+            ));
+        }
+
+        let item: T = if preamble.is_chunked() && len_hint.is_none() {
+            let mut chunked_fd = HttpChunkedTransferReader::from_reader(fd, max_len);
+            read_next(&mut chunked_fd)?
+        } else {
+            let content_length_opt = match (preamble.content_length, len_hint) {
+                (Some(l), _) => Some(l as u32),
+                (None, Some(l)) => Some(l as u32),
+                (None, None) => None,
+            };
+            if let Some(content_length) = content_length_opt {
+                if (content_length as u64) > max_len {
+                    return Err(net_error::DeserializeError(
+                        "Invalid Content-Length header: too long".to_string(),
+                    ));
+                }
+
+                let mut bound_fd = BoundReader::from_reader(fd, content_length as u64);
+                read_next(&mut bound_fd)?
+            } else {
+                // unsupported headers
+                trace!("preamble: {:?}", preamble);
+                return Err(net_error::DeserializeError(
+                    "Invalid headers: need either Transfer-Encoding or Content-Length".to_string(),
+                ));
+            }
+        };
+
+        Ok(item)
+    }
+
+    fn parse_json<R: Read, T: serde::de::DeserializeOwned>(
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+        max_len: u64,
+    ) -> Result<T, net_error> {
+        // content-type has to be JSON
+        if preamble.content_type != HttpContentType::JSON {
+            return Err(net_error::DeserializeError(
+                "Invalid content-type: expected application/json".to_string(),
+            ));
+        }
+
+        let item_result: Result<T, serde_json::Error> = if preamble.is_chunked()
+            && len_hint.is_none()
+        {
+            let chunked_fd = HttpChunkedTransferReader::from_reader(fd, max_len);
+            serde_json::from_reader(chunked_fd)
+        } else {
+            let content_length_opt = match (preamble.content_length, len_hint) {
+                (Some(l), _) => Some(l as u32),
+                (None, Some(l)) => Some(l as u32),
+                (None, None) => None,
+            };
+            if let Some(content_length) = content_length_opt {
+                if (content_length as u64) > max_len {
+                    return Err(net_error::DeserializeError(
+                        "Invalid Content-Length header: too long".to_string(),
+                    ));
+                }
+                let bound_fd = BoundReader::from_reader(fd, content_length as u64);
+                serde_json::from_reader(bound_fd)
+            } else {
+                // unsupported headers
+                trace!("preamble: {:?}", preamble);
+                return Err(net_error::DeserializeError(
+                    "Invalid headers: need either Transfer-Encoding or Content-Length".to_string(),
+                ));
+            }
+        };
+
+        item_result.map_err(|e| {
+            if e.is_eof() {
+                net_error::UnderflowError(format!("Not enough bytes to parse JSON"))
+            } else {
+                net_error::DeserializeError(format!("Failed to parse JSON: {:?}", &e))
+            }
+        })
+    }
+
+    fn parse_text<R: Read>(
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+        max_len: u64,
+    ) -> Result<Vec<u8>, net_error> {
+        // content-type has to be text/plain
+        if preamble.content_type != HttpContentType::Text {
+            return Err(net_error::DeserializeError(
+                "Invalid content-type: expected text/plain".to_string(),
+            ));
+        }
+        let buf = if preamble.is_chunked() && len_hint.is_none() {
+            let mut chunked_fd = HttpChunkedTransferReader::from_reader(fd, max_len);
+            let mut buf = vec![];
+            chunked_fd
+                .read_to_end(&mut buf)
+                .map_err(net_error::ReadError)?;
+            buf
+        } else {
+            let content_length_opt = match (preamble.content_length, len_hint) {
+                (Some(l), _) => Some(l as u32),
+                (None, Some(l)) => Some(l as u32),
+                (None, None) => None,
+            };
+            if let Some(len) = content_length_opt {
+                let mut buf = vec![0u8; len as usize];
+                fd.read_exact(&mut buf).map_err(net_error::ReadError)?;
+                buf
+            } else {
+                // unsupported headers
+                trace!("preamble: {:?}", preamble);
+                return Err(net_error::DeserializeError(
+                    "Invalid headers: need either Transfer-Encoding or Content-Length".to_string(),
+                ));
             }
-            HttpRequestType::CallReadOnlyFunction(
-                md,
-                _contract_addr,
-                _contract_name,
-                sender,
-                _func_name,
-                func_args,
-                ..,
-            ) => {
-                let mut args = vec![];
-                for arg in func_args.iter() {
-                    let mut arg_bytes = vec![];
-                    arg.serialize_write(&mut arg_bytes)
-                        .map_err(net_error::WriteError)?;
-                    args.push(to_hex(&arg_bytes));
-                }
+        };
 
-                let request_body = CallReadOnlyRequestBody {
-                    sender: sender.to_string(),
-                    arguments: args,
-                };
+        Ok(buf)
+    }
 
-                let mut request_body_bytes = vec![];
-                serde_json::to_writer(&mut request_body_bytes, &request_body).map_err(|e| {
-                    net_error::SerializeError(format!(
-                        "Failed to serialize read-only call to JSON: {:?}",
-                        &e
-                    ))
-                })?;
+    // len_hint is given by the StacksHttp protocol implementation
+    pub fn parse<R: Read>(
+        protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        request_path: String,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        if preamble.status_code >= 400 {
+            return HttpResponseType::parse_error(protocol, request_version, preamble, fd);
+        }
 
-                HttpRequestPreamble::new_serialized(
-                    fd,
-                    &md.version,
-                    "POST",
-                    &self.request_path(),
-                    &md.peer,
-                    md.keep_alive,
-                    Some(request_body_bytes.len() as u32),
-                    Some(&HttpContentType::JSON),
-                    |fd| stacks_height_headers(fd, md),
-                )?;
-                fd.write_all(&request_body_bytes)
-                    .map_err(net_error::WriteError)?;
-            }
-            HttpRequestType::MemPoolQuery(md, query, ..) => {
-                let request_body_bytes = query.serialize_to_vec();
-                HttpRequestPreamble::new_serialized(
-                    fd,
-                    &md.version,
-                    "POST",
-                    &self.request_path(),
-                    &md.peer,
-                    md.keep_alive,
-                    Some(request_body_bytes.len() as u32),
-                    Some(&HttpContentType::Bytes),
-                    empty_headers,
-                )?;
-                fd.write_all(&request_body_bytes)
-                    .map_err(net_error::WriteError)?;
-            }
-            other_type => {
-                let md = other_type.metadata();
-                let request_path = other_type.request_path();
-                HttpRequestPreamble::new_serialized(
-                    fd,
-                    &md.version,
-                    "GET",
-                    &request_path,
-                    &md.peer,
-                    md.keep_alive,
-                    None,
-                    None,
-                    |fd| stacks_height_headers(fd, md),
-                )?;
+        // TODO: make this static somehow
+        let RESPONSE_METHODS: &[(
+            &Regex,
+            &dyn Fn(
+                &mut StacksHttp,
+                HttpVersion,
+                &HttpResponsePreamble,
+                &mut R,
+                Option<usize>,
+            ) -> Result<HttpResponseType, net_error>,
+        )] = &[
+            (&PATH_GETINFO, &HttpResponseType::parse_peerinfo),
+            (&PATH_GETPOXINFO, &HttpResponseType::parse_poxinfo),
+            (&PATH_GETNEIGHBORS, &HttpResponseType::parse_neighbors),
+            (&PATH_GETHEADERS, &HttpResponseType::parse_headers),
+            (&PATH_GETBLOCK, &HttpResponseType::parse_block),
+            (&PATH_GET_DATA_VAR, &HttpResponseType::parse_get_data_var),
+            (&PATH_GET_MAP_ENTRY, &HttpResponseType::parse_get_map_entry),
+            (&PATH_GET_MAP_ENTRIES, &HttpResponseType::parse_map_entries),
+            (
+                &PATH_GETMICROBLOCKS_INDEXED,
+                &HttpResponseType::parse_microblocks,
+            ),
+            (
+                &PATH_GETMICROBLOCKS_CONFIRMED,
+                &HttpResponseType::parse_microblocks,
+            ),
+            (
+                &PATH_GETMICROBLOCKS_UNCONFIRMED,
+                &HttpResponseType::parse_microblocks_unconfirmed,
+            ),
+            (
+                &PATH_GETTRANSACTION_UNCONFIRMED,
+                &HttpResponseType::parse_transaction_unconfirmed,
+            ),
+            (
+                &PATH_POSTTRANSACTION,
+                &HttpResponseType::parse_post_transaction,
+            ),
+            (
+                &PATH_POST_TRANSACTION_BATCH,
+                &HttpResponseType::parse_transaction_batch,
+            ),
+            (
+                &PATH_POST_TRANSACTION_SPONSORSHIP,
+                &HttpResponseType::parse_txid,
+            ),
+            (
+                &PATH_POST_CLARITY_DATA_PROOF,
+                &HttpResponseType::parse_clarity_data_proof,
+            ),
+            (
+                &PATH_GET_TRANSACTION_PROOF,
+                &HttpResponseType::parse_transaction_proof,
+            ),
+            (
+                &PATH_GET_WITHDRAWAL_STATUS,
+                &HttpResponseType::parse_withdrawal_status,
+            ),
+            (
+                &PATH_GET_DEPOSIT_STATUS,
+                &HttpResponseType::parse_deposit_status,
+            ),
+            (&PATH_GET_BLOCK_COSTS, &HttpResponseType::parse_block_costs),
+            (
+                &PATH_GET_BLOCK_HEADER,
+                &HttpResponseType::parse_block_header,
+            ),
+            (
+                &PATH_GET_BLOCK_STATE_DIFF,
+                &HttpResponseType::parse_block_state_diff,
+            ),
+            (
+                &PATH_POST_VALIDATE_BLOCK_PROPOSAL,
+                &HttpResponseType::parse_block_proposal_validation,
+            ),
+            (
+                &PATH_GET_TRANSACTION_RECEIPT,
+                &HttpResponseType::parse_transaction_receipt,
+            ),
+            (&PATH_GET_NONCE_GAPS, &HttpResponseType::parse_nonce_gaps),
+            (
+                &PATH_GET_SUBNET_STATUS,
+                &HttpResponseType::parse_subnet_status,
+            ),
+            (
+                &PATH_POST_DIVERGENCE_SNAPSHOT,
+                &HttpResponseType::parse_divergence_snapshot,
+            ),
+            (
+                &PATH_GET_MEMPOOL_TXS,
+                &HttpResponseType::parse_mempool_listing,
+            ),
+            (
+                &PATH_GET_MEMPOOL_SUMMARY,
+                &HttpResponseType::parse_mempool_summary,
+            ),
+            (
+                &PATH_DELETE_MEMPOOL_TX,
+                &HttpResponseType::parse_mempool_eviction,
+            ),
+            (&PATH_GET_ADMIN_PEERS, &HttpResponseType::parse_admin_peers),
+            (
+                &PATH_GET_ADMIN_OBSERVERS,
+                &HttpResponseType::parse_admin_observers,
+            ),
+            (&PATH_GET_PEER_BANS, &HttpResponseType::parse_peer_bans),
+            (&PATH_POST_PEER_BAN, &HttpResponseType::parse_peer_ban),
+            (&PATH_DELETE_PEER_BAN, &HttpResponseType::parse_peer_ban),
+            (
+                &PATH_GET_BLOCK_RESULTS,
+                &HttpResponseType::parse_block_results,
+            ),
+            (
+                &PATH_GET_BLOCK_RESULTS_BY_HEIGHT,
+                &HttpResponseType::parse_block_results,
+            ),
+            (
+                &PATH_GET_SUBNET_FEE_RATE_ESTIMATE,
+                &HttpResponseType::parse_subnet_fee_rate_estimate,
+            ),
+            (
+                &PATH_POSTBLOCK,
+                &HttpResponseType::parse_stacks_block_accepted,
+            ),
+            (
+                &PATH_POSTMICROBLOCK,
+                &HttpResponseType::parse_microblock_hash,
+            ),
+            (&PATH_GET_ACCOUNT, &HttpResponseType::parse_get_account),
+            (
+                &PATH_GET_CONTRACT_SRC,
+                &HttpResponseType::parse_get_contract_src,
+            ),
+            (
+                &PATH_GET_IS_TRAIT_IMPLEMENTED,
+                &HttpResponseType::parse_get_is_trait_implemented,
+            ),
+            (
+                &PATH_GET_CONTRACT_ABI,
+                &HttpResponseType::parse_get_contract_abi,
+            ),
+            (
+                &PATH_POST_CALL_READ_ONLY,
+                &HttpResponseType::parse_call_read_only,
+            ),
+            (
+                &PATH_POST_TRACE_READ_ONLY,
+                &HttpResponseType::parse_trace_read_only,
+            ),
+            (
+                &PATH_GET_ATTACHMENT,
+                &HttpResponseType::parse_get_attachment,
+            ),
+            (
+                &PATH_GET_ATTACHMENTS_INV,
+                &HttpResponseType::parse_get_attachments_inv,
+            ),
+            (
+                &PATH_POST_MEMPOOL_QUERY,
+                &HttpResponseType::parse_post_mempool_query,
+            ),
+        ];
+
+        // use url::Url to parse path and query string
+        //   Url will refuse to parse just a path, so create a dummy URL
+        let local_url = format!("http://local{}", &request_path);
+        let url = Url::parse(&local_url).map_err(|_e| {
+            net_error::DeserializeError("Http request path could not be parsed".to_string())
+        })?;
+
+        let decoded_path = percent_decode_str(url.path()).decode_utf8().map_err(|_e| {
+            net_error::DeserializeError(
+                "Http response path could not be parsed as UTF-8".to_string(),
+            )
+        })?;
+
+        for (regex, parser) in RESPONSE_METHODS.iter() {
+            match HttpResponseType::try_parse(
+                protocol,
+                regex,
+                request_version,
+                preamble,
+                &decoded_path.to_string(),
+                fd,
+                len_hint,
+                parser,
+            ) {
+                Ok(Some(request)) => {
+                    return Ok(request);
+                }
+                Ok(None) => {
+                    continue;
+                }
+                Err(e) => {
+                    test_debug!("Failed to parse {}: {:?}", &request_path, &e);
+                    return Err(e);
+                }
             }
         }
-        Ok(())
+
+        test_debug!(
+            "Failed to match request path '{}' to a handler",
+            &request_path
+        );
+        return Err(net_error::DeserializeError(
+            "Http response could not be parsed".to_string(),
+        ));
+    }
+
+    fn parse_peerinfo<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let peer_info =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::PeerInfo(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            peer_info,
+        ))
     }
-}
 
-impl HttpResponseType {
-    fn try_parse<R: Read, F>(
-        protocol: &mut StacksHttp,
-        regex: &Regex,
+    fn parse_poxinfo<R: Read>(
+        _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
-        request_path: &String,
         fd: &mut R,
         len_hint: Option<usize>,
-        parser: F,
-    ) -> Result<Option<HttpResponseType>, net_error>
-    where
-        F: Fn(
-            &mut StacksHttp,
-            HttpVersion,
-            &HttpResponsePreamble,
-            &mut R,
-            Option<usize>,
-        ) -> Result<HttpResponseType, net_error>,
-    {
-        if regex.is_match(request_path) {
-            let payload = parser(protocol, request_version, preamble, fd, len_hint)?;
-            Ok(Some(payload))
-        } else {
-            Ok(None)
-        }
+    ) -> Result<HttpResponseType, net_error> {
+        let pox_info =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::PoxInfo(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            pox_info,
+        ))
     }
 
-    fn parse_error<R: Read>(
+    fn parse_neighbors<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
+        len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        if preamble.status_code < 400 || preamble.status_code > 599 {
-            return Err(net_error::DeserializeError(
-                "Inavlid response: not an error".to_string(),
-            ));
-        }
+        let neighbors_data =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::Neighbors(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            neighbors_data,
+        ))
+    }
 
-        if preamble.content_type != HttpContentType::Text
-            && preamble.content_type != HttpContentType::JSON
-        {
-            return Err(net_error::DeserializeError(format!(
-                "Invalid error response: expected text/plain or application/json, got {:?}",
-                &preamble.content_type
-            )));
-        }
+    fn parse_headers<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let headers: Vec<ExtendedStacksHeader> =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::Headers(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            headers,
+        ))
+    }
 
-        let mut error_text = String::new();
-        fd.read_to_string(&mut error_text)
-            .map_err(net_error::ReadError)?;
+    fn parse_block<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let block: StacksBlock =
+            HttpResponseType::parse_bytestream(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::Block(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            block,
+        ))
+    }
 
-        let md = HttpResponseMetadata::from_preamble(request_version, preamble);
-        let resp = match preamble.status_code {
-            400 => HttpResponseType::BadRequest(md, error_text),
-            401 => HttpResponseType::Unauthorized(md, error_text),
-            402 => HttpResponseType::PaymentRequired(md, error_text),
-            403 => HttpResponseType::Forbidden(md, error_text),
-            404 => HttpResponseType::NotFound(md, error_text),
-            500 => HttpResponseType::ServerError(md, error_text),
-            503 => HttpResponseType::ServiceUnavailable(md, error_text),
-            _ => HttpResponseType::Error(md, preamble.status_code, error_text),
-        };
-        Ok(resp)
+    fn parse_microblocks<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let microblocks: Vec<StacksMicroblock> =
+            HttpResponseType::parse_bytestream(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::Microblocks(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            microblocks,
+        ))
     }
 
-    fn parse_bytestream<R: Read, T: StacksMessageCodec>(
+    fn parse_get_account<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
-        max_len: u64,
-    ) -> Result<T, net_error> {
-        // content-type has to be Bytes
-        if preamble.content_type != HttpContentType::Bytes {
-            return Err(net_error::DeserializeError(
-                "Invalid content-type: expected application/octet-stream".to_string(),
-            ));
-        }
+    ) -> Result<HttpResponseType, net_error> {
+        let account_entry =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetAccount(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            account_entry,
+        ))
+    }
 
-        let item: T = if preamble.is_chunked() && len_hint.is_none() {
-            let mut chunked_fd = HttpChunkedTransferReader::from_reader(fd, max_len);
-            read_next(&mut chunked_fd)?
-        } else {
-            let content_length_opt = match (preamble.content_length, len_hint) {
-                (Some(l), _) => Some(l as u32),
-                (None, Some(l)) => Some(l as u32),
-                (None, None) => None,
-            };
-            if let Some(content_length) = content_length_opt {
-                if (content_length as u64) > max_len {
-                    return Err(net_error::DeserializeError(
-                        "Invalid Content-Length header: too long".to_string(),
-                    ));
-                }
+    fn parse_get_data_var<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let data_var =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetDataVar(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            data_var,
+        ))
+    }
 
-                let mut bound_fd = BoundReader::from_reader(fd, content_length as u64);
-                read_next(&mut bound_fd)?
-            } else {
-                // unsupported headers
-                trace!("preamble: {:?}", preamble);
-                return Err(net_error::DeserializeError(
-                    "Invalid headers: need either Transfer-Encoding or Content-Length".to_string(),
-                ));
-            }
-        };
+    fn parse_get_map_entry<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let map_entry =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetMapEntry(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            map_entry,
+        ))
+    }
 
-        Ok(item)
+    fn parse_map_entries<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let map_entries: MapEntriesResponse =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::MapEntries(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            map_entries,
+        ))
     }
 
-    fn parse_json<R: Read, T: serde::de::DeserializeOwned>(
+    fn parse_get_contract_src<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
-        max_len: u64,
-    ) -> Result<T, net_error> {
-        // content-type has to be JSON
-        if preamble.content_type != HttpContentType::JSON {
-            return Err(net_error::DeserializeError(
-                "Invalid content-type: expected application/json".to_string(),
-            ));
-        }
+    ) -> Result<HttpResponseType, net_error> {
+        let src_data =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetContractSrc(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            src_data,
+        ))
+    }
 
-        let item_result: Result<T, serde_json::Error> = if preamble.is_chunked()
-            && len_hint.is_none()
-        {
-            let chunked_fd = HttpChunkedTransferReader::from_reader(fd, max_len);
-            serde_json::from_reader(chunked_fd)
-        } else {
-            let content_length_opt = match (preamble.content_length, len_hint) {
-                (Some(l), _) => Some(l as u32),
-                (None, Some(l)) => Some(l as u32),
-                (None, None) => None,
-            };
-            if let Some(content_length) = content_length_opt {
-                if (content_length as u64) > max_len {
-                    return Err(net_error::DeserializeError(
-                        "Invalid Content-Length header: too long".to_string(),
-                    ));
-                }
-                let bound_fd = BoundReader::from_reader(fd, content_length as u64);
-                serde_json::from_reader(bound_fd)
-            } else {
-                // unsupported headers
-                trace!("preamble: {:?}", preamble);
-                return Err(net_error::DeserializeError(
-                    "Invalid headers: need either Transfer-Encoding or Content-Length".to_string(),
-                ));
-            }
-        };
+    fn parse_get_is_trait_implemented<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let src_data =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetIsTraitImplemented(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            src_data,
+        ))
+    }
 
-        item_result.map_err(|e| {
-            if e.is_eof() {
-                net_error::UnderflowError(format!("Not enough bytes to parse JSON"))
-            } else {
-                net_error::DeserializeError(format!("Failed to parse JSON: {:?}", &e))
-            }
-        })
+    fn parse_get_contract_abi<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let abi = HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::GetContractABI(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            abi,
+        ))
     }
 
-    fn parse_text<R: Read>(
+    fn parse_call_read_only<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
-        max_len: u64,
-    ) -> Result<Vec<u8>, net_error> {
-        // content-type has to be text/plain
-        if preamble.content_type != HttpContentType::Text {
-            return Err(net_error::DeserializeError(
-                "Invalid content-type: expected text/plain".to_string(),
-            ));
-        }
-        let buf = if preamble.is_chunked() && len_hint.is_none() {
-            let mut chunked_fd = HttpChunkedTransferReader::from_reader(fd, max_len);
-            let mut buf = vec![];
-            chunked_fd
-                .read_to_end(&mut buf)
-                .map_err(net_error::ReadError)?;
-            buf
-        } else {
-            let content_length_opt = match (preamble.content_length, len_hint) {
-                (Some(l), _) => Some(l as u32),
-                (None, Some(l)) => Some(l as u32),
-                (None, None) => None,
-            };
-            if let Some(len) = content_length_opt {
-                let mut buf = vec![0u8; len as usize];
-                fd.read_exact(&mut buf).map_err(net_error::ReadError)?;
-                buf
-            } else {
-                // unsupported headers
-                trace!("preamble: {:?}", preamble);
-                return Err(net_error::DeserializeError(
-                    "Invalid headers: need either Transfer-Encoding or Content-Length".to_string(),
-                ));
-            }
-        };
+    ) -> Result<HttpResponseType, net_error> {
+        let call_data =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::CallReadOnlyFunction(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            call_data,
+        ))
+    }
 
-        Ok(buf)
+    fn parse_trace_read_only<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let trace_data =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::TraceReadOnlyFunction(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            trace_data,
+        ))
     }
 
-    // len_hint is given by the StacksHttp protocol implementation
-    pub fn parse<R: Read>(
-        protocol: &mut StacksHttp,
+    fn parse_microblocks_unconfirmed<R: Read>(
+        _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
-        request_path: String,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        if preamble.status_code >= 400 {
-            return HttpResponseType::parse_error(protocol, request_version, preamble, fd);
+        // NOTE: there will be no length prefix on this, but we won't ever get more than
+        // MAX_MICROBLOCKS_UNCONFIRMED microblocks
+        let mut microblocks = vec![];
+        let max_len = len_hint.unwrap_or(MAX_MESSAGE_LEN as usize) as u64;
+        let mut bound_reader = BoundReader::from_reader(fd, max_len);
+        loop {
+            let mblock: StacksMicroblock = match read_next(&mut bound_reader) {
+                Ok(mblock) => Ok(mblock),
+                Err(e) => match e {
+                    codec_error::ReadError(ref ioe) => match ioe.kind() {
+                        io::ErrorKind::UnexpectedEof => {
+                            // end of stream -- this is fine
+                            break;
+                        }
+                        _ => Err(e),
+                    },
+                    _ => Err(e),
+                },
+            }?;
+
+            microblocks.push(mblock);
+            if microblocks.len() == MAX_MICROBLOCKS_UNCONFIRMED {
+                break;
+            }
         }
+        Ok(HttpResponseType::Microblocks(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            microblocks,
+        ))
+    }
 
-        // TODO: make this static somehow
-        let RESPONSE_METHODS: &[(
-            &Regex,
-            &dyn Fn(
-                &mut StacksHttp,
-                HttpVersion,
-                &HttpResponsePreamble,
-                &mut R,
-                Option<usize>,
-            ) -> Result<HttpResponseType, net_error>,
-        )] = &[
-            (&PATH_GETINFO, &HttpResponseType::parse_peerinfo),
-            (&PATH_GETPOXINFO, &HttpResponseType::parse_poxinfo),
-            (&PATH_GETNEIGHBORS, &HttpResponseType::parse_neighbors),
-            (&PATH_GETHEADERS, &HttpResponseType::parse_headers),
-            (&PATH_GETBLOCK, &HttpResponseType::parse_block),
-            (&PATH_GET_DATA_VAR, &HttpResponseType::parse_get_data_var),
-            (&PATH_GET_MAP_ENTRY, &HttpResponseType::parse_get_map_entry),
-            (
-                &PATH_GETMICROBLOCKS_INDEXED,
-                &HttpResponseType::parse_microblocks,
-            ),
-            (
-                &PATH_GETMICROBLOCKS_CONFIRMED,
-                &HttpResponseType::parse_microblocks,
-            ),
-            (
-                &PATH_GETMICROBLOCKS_UNCONFIRMED,
-                &HttpResponseType::parse_microblocks_unconfirmed,
-            ),
-            (
-                &PATH_GETTRANSACTION_UNCONFIRMED,
-                &HttpResponseType::parse_transaction_unconfirmed,
-            ),
-            (&PATH_POSTTRANSACTION, &HttpResponseType::parse_txid),
-            (
-                &PATH_POSTBLOCK,
-                &HttpResponseType::parse_stacks_block_accepted,
-            ),
-            (
-                &PATH_POSTMICROBLOCK,
-                &HttpResponseType::parse_microblock_hash,
-            ),
-            (&PATH_GET_ACCOUNT, &HttpResponseType::parse_get_account),
-            (
-                &PATH_GET_CONTRACT_SRC,
-                &HttpResponseType::parse_get_contract_src,
-            ),
-            (
-                &PATH_GET_IS_TRAIT_IMPLEMENTED,
-                &HttpResponseType::parse_get_is_trait_implemented,
-            ),
-            (
-                &PATH_GET_CONTRACT_ABI,
-                &HttpResponseType::parse_get_contract_abi,
-            ),
-            (
-                &PATH_POST_CALL_READ_ONLY,
-                &HttpResponseType::parse_call_read_only,
-            ),
-            (
-                &PATH_GET_ATTACHMENT,
-                &HttpResponseType::parse_get_attachment,
-            ),
-            (
-                &PATH_GET_ATTACHMENTS_INV,
-                &HttpResponseType::parse_get_attachments_inv,
-            ),
-            (
-                &PATH_POST_MEMPOOL_QUERY,
-                &HttpResponseType::parse_post_mempool_query,
-            ),
-        ];
+    fn parse_transaction_unconfirmed<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let unconfirmed_status: UnconfirmedTransactionResponse =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
 
-        // use url::Url to parse path and query string
-        //   Url will refuse to parse just a path, so create a dummy URL
-        let local_url = format!("http://local{}", &request_path);
-        let url = Url::parse(&local_url).map_err(|_e| {
-            net_error::DeserializeError("Http request path could not be parsed".to_string())
+        // tx payload must decode to a transaction
+        let tx_bytes = hex_bytes(&unconfirmed_status.tx).map_err(|_| {
+            net_error::DeserializeError("Unconfirmed transaction is not hex-encoded".to_string())
         })?;
-
-        let decoded_path = percent_decode_str(url.path()).decode_utf8().map_err(|_e| {
+        let _ = StacksTransaction::consensus_deserialize(&mut &tx_bytes[..]).map_err(|_| {
             net_error::DeserializeError(
-                "Http response path could not be parsed as UTF-8".to_string(),
+                "Unconfirmed transaction is not a well-formed Stacks transaction".to_string(),
             )
         })?;
 
-        for (regex, parser) in RESPONSE_METHODS.iter() {
-            match HttpResponseType::try_parse(
-                protocol,
-                regex,
-                request_version,
-                preamble,
-                &decoded_path.to_string(),
-                fd,
-                len_hint,
-                parser,
-            ) {
-                Ok(Some(request)) => {
-                    return Ok(request);
-                }
-                Ok(None) => {
-                    continue;
-                }
-                Err(e) => {
-                    test_debug!("Failed to parse {}: {:?}", &request_path, &e);
-                    return Err(e);
-                }
+        Ok(HttpResponseType::UnconfirmedTransaction(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            unconfirmed_status,
+        ))
+    }
+
+    fn parse_txid<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let txid_hex: String = HttpResponseType::parse_json(preamble, fd, len_hint, 66)?;
+        if txid_hex.len() != 64 {
+            return Err(net_error::DeserializeError(
+                "Invalid txid: expected 64 bytes".to_string(),
+            ));
+        }
+
+        let txid = Txid::from_hex(&txid_hex)
+            .map_err(|_e| net_error::DeserializeError("Failed to decode txid hex".to_string()))?;
+        Ok(HttpResponseType::TransactionID(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            txid,
+        ))
+    }
+
+    /// Parse the response to a posted transaction: its txid, or admission diagnostics if they
+    /// were asked for
+    fn parse_post_transaction<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let response: serde_json::Value =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        if let serde_json::Value::String(txid_hex) = response {
+            if txid_hex.len() != 64 {
+                return Err(net_error::DeserializeError(
+                    "Invalid txid: expected 64 bytes".to_string(),
+                ));
             }
+            let txid = Txid::from_hex(&txid_hex).map_err(|_e| {
+                net_error::DeserializeError("Failed to decode txid hex".to_string())
+            })?;
+            return Ok(HttpResponseType::TransactionID(
+                HttpResponseMetadata::from_preamble(request_version, preamble),
+                txid,
+            ));
         }
 
-        test_debug!(
-            "Failed to match request path '{}' to a handler",
-            &request_path
-        );
-        return Err(net_error::DeserializeError(
-            "Http response could not be parsed".to_string(),
-        ));
+        let admission: TransactionAdmissionResponse =
+            serde_json::from_value(response).map_err(|_e| {
+                net_error::DeserializeError("Failed to parse admission diagnostics".to_string())
+            })?;
+        Ok(HttpResponseType::TransactionAdmission(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            admission,
+        ))
     }
 
-    fn parse_peerinfo<R: Read>(
+    fn parse_transaction_batch<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let peer_info =
+        let batch_response: TransactionBatchResponse =
             HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::PeerInfo(
+        Ok(HttpResponseType::TransactionBatch(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            peer_info,
+            batch_response,
         ))
     }
 
-    fn parse_poxinfo<R: Read>(
+    fn parse_withdrawal_status<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let pox_info =
+        let withdrawal_status: WithdrawalStatusResponse =
             HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::PoxInfo(
+        Ok(HttpResponseType::WithdrawalStatus(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            pox_info,
+            withdrawal_status,
         ))
     }
 
-    fn parse_neighbors<R: Read>(
+    fn parse_deposit_status<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let neighbors_data =
+        let deposit_status: DepositStatusResponse =
             HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::Neighbors(
+        Ok(HttpResponseType::DepositStatus(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            neighbors_data,
+            deposit_status,
         ))
     }
 
-    fn parse_headers<R: Read>(
+    fn parse_block_costs<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let headers: Vec<ExtendedStacksHeader> =
+        let block_costs: BlockCostsResponse =
             HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::Headers(
+        Ok(HttpResponseType::BlockCosts(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            headers,
+            block_costs,
         ))
     }
 
-    fn parse_block<R: Read>(
+    fn parse_block_header<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let block: StacksBlock =
-            HttpResponseType::parse_bytestream(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::Block(
+        let block_header: BlockHeaderResponse =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::BlockHeader(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            block,
+            block_header,
         ))
     }
 
-    fn parse_microblocks<R: Read>(
+    fn parse_block_state_diff<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let microblocks: Vec<StacksMicroblock> =
-            HttpResponseType::parse_bytestream(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::Microblocks(
+        let state_diff: BlockStateDiff =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::BlockStateDiff(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            microblocks,
+            state_diff,
         ))
     }
 
-    fn parse_get_account<R: Read>(
+    fn parse_block_proposal_validation<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let account_entry =
+        let validation: BlockProposalValidationResponse =
             HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::GetAccount(
+        Ok(HttpResponseType::BlockProposalValidation(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            account_entry,
+            validation,
         ))
     }
 
-    fn parse_get_data_var<R: Read>(
+    fn parse_transaction_receipt<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let data_var =
+        let receipt: TransactionReceiptRecord =
             HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::GetDataVar(
+        Ok(HttpResponseType::TransactionReceipt(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            data_var,
+            receipt,
         ))
     }
 
-    fn parse_get_map_entry<R: Read>(
+    fn parse_nonce_gaps<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let map_entry =
+        let nonce_gaps: NonceGapsResponse =
             HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::GetMapEntry(
+        Ok(HttpResponseType::NonceGaps(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            map_entry,
+            nonce_gaps,
         ))
     }
 
-    fn parse_get_contract_src<R: Read>(
+    fn parse_divergence_snapshot<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let src_data =
+        let divergence_snapshot: DivergenceSnapshotResponse =
             HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::GetContractSrc(
+        Ok(HttpResponseType::DivergenceSnapshot(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            src_data,
+            divergence_snapshot,
         ))
     }
 
-    fn parse_get_is_trait_implemented<R: Read>(
+    fn parse_mempool_listing<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let src_data =
+        let listing: MemPoolListResponse =
             HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::GetIsTraitImplemented(
+        Ok(HttpResponseType::MemPoolListing(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            src_data,
+            listing,
         ))
     }
 
-    fn parse_get_contract_abi<R: Read>(
+    fn parse_mempool_summary<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let abi = HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::GetContractABI(
+        let summary: MemPoolSummaryResponse =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::MemPoolSummary(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            abi,
+            summary,
         ))
     }
 
-    fn parse_call_read_only<R: Read>(
+    fn parse_mempool_eviction<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let call_data =
+        let eviction: MemPoolEvictionResponse =
             HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
-        Ok(HttpResponseType::CallReadOnlyFunction(
+        Ok(HttpResponseType::MemPoolEviction(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            call_data,
+            eviction,
         ))
     }
 
-    fn parse_microblocks_unconfirmed<R: Read>(
+    fn parse_admin_peers<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        // NOTE: there will be no length prefix on this, but we won't ever get more than
-        // MAX_MICROBLOCKS_UNCONFIRMED microblocks
-        let mut microblocks = vec![];
-        let max_len = len_hint.unwrap_or(MAX_MESSAGE_LEN as usize) as u64;
-        let mut bound_reader = BoundReader::from_reader(fd, max_len);
-        loop {
-            let mblock: StacksMicroblock = match read_next(&mut bound_reader) {
-                Ok(mblock) => Ok(mblock),
-                Err(e) => match e {
-                    codec_error::ReadError(ref ioe) => match ioe.kind() {
-                        io::ErrorKind::UnexpectedEof => {
-                            // end of stream -- this is fine
-                            break;
-                        }
-                        _ => Err(e),
-                    },
-                    _ => Err(e),
-                },
-            }?;
+        let peers: AdminPeersResponse =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::AdminPeers(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            peers,
+        ))
+    }
 
-            microblocks.push(mblock);
-            if microblocks.len() == MAX_MICROBLOCKS_UNCONFIRMED {
-                break;
-            }
-        }
-        Ok(HttpResponseType::Microblocks(
+    fn parse_admin_observers<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let observers: AdminObserversResponse =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::AdminObservers(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            microblocks,
+            observers,
         ))
     }
 
-    fn parse_transaction_unconfirmed<R: Read>(
+    fn parse_peer_bans<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let unconfirmed_status: UnconfirmedTransactionResponse =
+        let bans: PeerBansResponse =
             HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::PeerBans(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            bans,
+        ))
+    }
 
-        // tx payload must decode to a transaction
-        let tx_bytes = hex_bytes(&unconfirmed_status.tx).map_err(|_| {
-            net_error::DeserializeError("Unconfirmed transaction is not hex-encoded".to_string())
-        })?;
-        let _ = StacksTransaction::consensus_deserialize(&mut &tx_bytes[..]).map_err(|_| {
-            net_error::DeserializeError(
-                "Unconfirmed transaction is not a well-formed Stacks transaction".to_string(),
-            )
-        })?;
+    fn parse_peer_ban<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let ban: PeerBanInfo =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::PeerBan(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            ban,
+        ))
+    }
 
-        Ok(HttpResponseType::UnconfirmedTransaction(
+    fn parse_subnet_status<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let subnet_status: SubnetStatusResponse =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::SubnetStatus(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            unconfirmed_status,
+            subnet_status,
         ))
     }
 
-    fn parse_txid<R: Read>(
+    fn parse_clarity_data_proof<R: Read>(
         _protocol: &mut StacksHttp,
         request_version: HttpVersion,
         preamble: &HttpResponsePreamble,
         fd: &mut R,
         len_hint: Option<usize>,
     ) -> Result<HttpResponseType, net_error> {
-        let txid_hex: String = HttpResponseType::parse_json(preamble, fd, len_hint, 66)?;
-        if txid_hex.len() != 64 {
-            return Err(net_error::DeserializeError(
-                "Invalid txid: expected 64 bytes".to_string(),
-            ));
-        }
+        let proof: ClarityDataProofResponse =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::ClarityDataProof(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            proof,
+        ))
+    }
 
-        let txid = Txid::from_hex(&txid_hex)
-            .map_err(|_e| net_error::DeserializeError("Failed to decode txid hex".to_string()))?;
-        Ok(HttpResponseType::TransactionID(
+    fn parse_transaction_proof<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let proof: TransactionProofResponse =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::TransactionProof(
             HttpResponseMetadata::from_preamble(request_version, preamble),
-            txid,
+            proof,
+        ))
+    }
+
+    fn parse_block_results<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let results: BlockResults =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::BlockResults(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            results,
+        ))
+    }
+
+    fn parse_subnet_fee_rate_estimate<R: Read>(
+        _protocol: &mut StacksHttp,
+        request_version: HttpVersion,
+        preamble: &HttpResponsePreamble,
+        fd: &mut R,
+        len_hint: Option<usize>,
+    ) -> Result<HttpResponseType, net_error> {
+        let estimate: SubnetFeeRateEstimate =
+            HttpResponseType::parse_json(preamble, fd, len_hint, MAX_MESSAGE_LEN as u64)?;
+        Ok(HttpResponseType::SubnetFeeRateEstimate(
+            HttpResponseMetadata::from_preamble(request_version, preamble),
+            estimate,
         ))
     }
 
@@ -4117,6 +6330,7 @@ impl HttpResponseType {
             403 => "Forbidden",
             404 => "Not Found",
             406 => "Not Acceptable",
+            429 => "Too Many Requests",
             500 => "Internal Server Error",
             503 => "Service Temporarily Unavailable",
             _ => "Error",
@@ -4156,16 +6370,21 @@ impl HttpResponseType {
             HttpResponseType::Microblocks(ref md, _) => md,
             HttpResponseType::MicroblockStream(ref md) => md,
             HttpResponseType::TransactionID(ref md, _) => md,
+            HttpResponseType::TransactionBatch(ref md, _) => md,
+            HttpResponseType::TransactionAdmission(ref md, _) => md,
             HttpResponseType::StacksBlockAccepted(ref md, ..) => md,
             HttpResponseType::MicroblockHash(ref md, _) => md,
             HttpResponseType::TokenTransferCost(ref md, _) => md,
+            HttpResponseType::SubnetFeeRateEstimate(ref md, _) => md,
             HttpResponseType::GetDataVar(ref md, _) => md,
             HttpResponseType::GetMapEntry(ref md, _) => md,
+            HttpResponseType::MapEntries(ref md, _) => md,
             HttpResponseType::GetAccount(ref md, _) => md,
             HttpResponseType::GetContractABI(ref md, _) => md,
             HttpResponseType::GetContractSrc(ref md, _) => md,
             HttpResponseType::GetIsTraitImplemented(ref md, _) => md,
             HttpResponseType::CallReadOnlyFunction(ref md, _) => md,
+            HttpResponseType::TraceReadOnlyFunction(ref md, _) => md,
             HttpResponseType::UnconfirmedTransaction(ref md, _) => md,
             HttpResponseType::GetAttachment(ref md, _) => md,
             HttpResponseType::GetAttachmentsInv(ref md, _) => md,
@@ -4174,6 +6393,25 @@ impl HttpResponseType {
             HttpResponseType::OptionsPreflight(ref md) => md,
             HttpResponseType::TransactionFeeEstimation(ref md, _) => md,
             HttpResponseType::GetWithdrawal(ref md, _) => md,
+            HttpResponseType::WithdrawalStatus(ref md, _) => md,
+            HttpResponseType::BlockResults(ref md, _) => md,
+            HttpResponseType::ClarityDataProof(ref md, _) => md,
+            HttpResponseType::TransactionProof(ref md, _) => md,
+            HttpResponseType::DepositStatus(ref md, _) => md,
+            HttpResponseType::BlockCosts(ref md, _) => md,
+            HttpResponseType::BlockHeader(ref md, _) => md,
+            HttpResponseType::BlockStateDiff(ref md, _) => md,
+            HttpResponseType::TransactionReceipt(ref md, _) => md,
+            HttpResponseType::NonceGaps(ref md, _) => md,
+            HttpResponseType::SubnetStatus(ref md, _) => md,
+            HttpResponseType::DivergenceSnapshot(ref md, _) => md,
+            HttpResponseType::MemPoolListing(ref md, _) => md,
+            HttpResponseType::MemPoolSummary(ref md, _) => md,
+            HttpResponseType::MemPoolEviction(ref md, _) => md,
+            HttpResponseType::AdminPeers(ref md, _) => md,
+            HttpResponseType::AdminObservers(ref md, _) => md,
+            HttpResponseType::PeerBans(ref md, _) => md,
+            HttpResponseType::PeerBan(ref md, _) => md,
             // errors
             HttpResponseType::BadRequestJSON(ref md, _) => md,
             HttpResponseType::BadRequest(ref md, _) => md,
@@ -4181,11 +6419,13 @@ impl HttpResponseType {
             HttpResponseType::PaymentRequired(ref md, _) => md,
             HttpResponseType::Forbidden(ref md, _) => md,
             HttpResponseType::NotFound(ref md, _) => md,
+            HttpResponseType::TooManyRequests(ref md, _) => md,
             HttpResponseType::ServerError(ref md, _) => md,
             HttpResponseType::ServiceUnavailable(ref md, _) => md,
             HttpResponseType::Error(ref md, _, _) => md,
             HttpResponseType::BlockProposalValid { ref metadata, .. } => metadata,
             HttpResponseType::BlockProposalInvalid { ref metadata, .. } => metadata,
+            HttpResponseType::BlockProposalValidation(ref md, _) => md,
         }
     }
 
@@ -4275,10 +6515,18 @@ impl HttpResponseType {
                 HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
                 HttpResponseType::send_json(protocol, md, fd, cost)?;
             }
+            HttpResponseType::SubnetFeeRateEstimate(ref md, ref estimate) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, estimate)?;
+            }
             HttpResponseType::CallReadOnlyFunction(ref md, ref data) => {
                 HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
                 HttpResponseType::send_json(protocol, md, fd, data)?;
             }
+            HttpResponseType::TraceReadOnlyFunction(ref md, ref data) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, data)?;
+            }
             HttpResponseType::GetDataVar(ref md, ref var_data) => {
                 HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
                 HttpResponseType::send_json(protocol, md, fd, var_data)?;
@@ -4287,6 +6535,10 @@ impl HttpResponseType {
                 HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
                 HttpResponseType::send_json(protocol, md, fd, map_data)?;
             }
+            HttpResponseType::MapEntries(ref md, ref map_entries) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, map_entries)?;
+            }
             HttpResponseType::PeerInfo(ref md, ref peer_info) => {
                 HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
                 HttpResponseType::send_json(protocol, md, fd, peer_info)?;
@@ -4395,6 +6647,14 @@ impl HttpResponseType {
                 )?;
                 HttpResponseType::send_json(protocol, md, fd, &txid_bytes)?;
             }
+            HttpResponseType::TransactionBatch(ref md, ref batch_response) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, batch_response)?;
+            }
+            HttpResponseType::TransactionAdmission(ref md, ref admission) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, admission)?;
+            }
             HttpResponseType::StacksBlockAccepted(ref md, ref stacks_block_id, ref accepted) => {
                 let accepted_data = StacksBlockAcceptedData {
                     stacks_block_id: stacks_block_id.clone(),
@@ -4497,21 +6757,102 @@ impl HttpResponseType {
                 )?;
                 HttpResponseType::send_json(protocol, md, fd, data)?;
             }
-            HttpResponseType::BadRequest(_, ref msg) => self.error_response(fd, 400, msg)?,
-            HttpResponseType::Unauthorized(_, ref msg) => self.error_response(fd, 401, msg)?,
-            HttpResponseType::PaymentRequired(_, ref msg) => self.error_response(fd, 402, msg)?,
-            HttpResponseType::Forbidden(_, ref msg) => self.error_response(fd, 403, msg)?,
-            HttpResponseType::NotFound(_, ref msg) => self.error_response(fd, 404, msg)?,
-            HttpResponseType::ServerError(_, ref msg) => self.error_response(fd, 500, msg)?,
-            HttpResponseType::ServiceUnavailable(_, ref msg) => {
-                self.error_response(fd, 503, msg)?
+            HttpResponseType::BadRequest(_, ref msg) => self.error_response(fd, 400, msg)?,
+            HttpResponseType::Unauthorized(_, ref msg) => self.error_response(fd, 401, msg)?,
+            HttpResponseType::PaymentRequired(_, ref msg) => self.error_response(fd, 402, msg)?,
+            HttpResponseType::Forbidden(_, ref msg) => self.error_response(fd, 403, msg)?,
+            HttpResponseType::NotFound(_, ref msg) => self.error_response(fd, 404, msg)?,
+            HttpResponseType::TooManyRequests(_, ref msg) => self.error_response(fd, 429, msg)?,
+            HttpResponseType::ServerError(_, ref msg) => self.error_response(fd, 500, msg)?,
+            HttpResponseType::ServiceUnavailable(_, ref msg) => {
+                self.error_response(fd, 503, msg)?
+            }
+            HttpResponseType::Error(_, ref error_code, ref msg) => {
+                self.error_response(fd, *error_code, msg)?
+            }
+            HttpResponseType::GetWithdrawal(ref md, ref json) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, json)?;
+            }
+            HttpResponseType::ClarityDataProof(ref md, ref proof) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, proof)?;
+            }
+            HttpResponseType::TransactionProof(ref md, ref proof) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, proof)?;
+            }
+            HttpResponseType::DepositStatus(ref md, ref deposit_status) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, deposit_status)?;
+            }
+            HttpResponseType::BlockCosts(ref md, ref block_costs) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, block_costs)?;
+            }
+            HttpResponseType::BlockHeader(ref md, ref block_header) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, block_header)?;
+            }
+            HttpResponseType::BlockStateDiff(ref md, ref state_diff) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, state_diff)?;
+            }
+            HttpResponseType::BlockProposalValidation(ref md, ref validation) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, validation)?;
+            }
+            HttpResponseType::TransactionReceipt(ref md, ref receipt) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, receipt)?;
+            }
+            HttpResponseType::NonceGaps(ref md, ref nonce_gaps) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, nonce_gaps)?;
+            }
+            HttpResponseType::SubnetStatus(ref md, ref subnet_status) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, subnet_status)?;
+            }
+            HttpResponseType::DivergenceSnapshot(ref md, ref divergence_snapshot) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, divergence_snapshot)?;
+            }
+            HttpResponseType::MemPoolListing(ref md, ref listing) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, listing)?;
+            }
+            HttpResponseType::MemPoolSummary(ref md, ref summary) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, summary)?;
+            }
+            HttpResponseType::MemPoolEviction(ref md, ref eviction) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, eviction)?;
+            }
+            HttpResponseType::AdminPeers(ref md, ref peers) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, peers)?;
+            }
+            HttpResponseType::AdminObservers(ref md, ref observers) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, observers)?;
+            }
+            HttpResponseType::PeerBans(ref md, ref bans) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, bans)?;
             }
-            HttpResponseType::Error(_, ref error_code, ref msg) => {
-                self.error_response(fd, *error_code, msg)?
+            HttpResponseType::PeerBan(ref md, ref ban) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, ban)?;
             }
-            HttpResponseType::GetWithdrawal(ref md, ref json) => {
+            HttpResponseType::BlockResults(ref md, ref results) => {
                 HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
-                HttpResponseType::send_json(protocol, md, fd, json)?;
+                HttpResponseType::send_json(protocol, md, fd, results)?;
+            }
+            HttpResponseType::WithdrawalStatus(ref md, ref withdrawal_status) => {
+                HttpResponsePreamble::ok_JSON_from_md(fd, md)?;
+                HttpResponseType::send_json(protocol, md, fd, withdrawal_status)?;
             }
             HttpResponseType::BlockProposalValid {
                 metadata: ref md,
@@ -4610,17 +6951,24 @@ impl MessageSequence for StacksHttpMessage {
                 HttpRequestType::GetTransactionUnconfirmed(_, _) => {
                     "HTTP(GetTransactionUnconfirmed)"
                 }
-                HttpRequestType::PostTransaction(_, _, _) => "HTTP(PostTransaction)",
+                HttpRequestType::PostTransaction(..) => "HTTP(PostTransaction)",
+                HttpRequestType::PostTransactionBatch(..) => "HTTP(PostTransactionBatch)",
+                HttpRequestType::PostTransactionSponsorship(..) => {
+                    "HTTP(PostTransactionSponsorship)"
+                }
                 HttpRequestType::PostBlock(..) => "HTTP(PostBlock)",
                 HttpRequestType::PostMicroblock(..) => "HTTP(PostMicroblock)",
                 HttpRequestType::GetAccount(..) => "HTTP(GetAccount)",
                 HttpRequestType::GetDataVar(..) => "HTTP(GetDataVar)",
                 HttpRequestType::GetMapEntry(..) => "HTTP(GetMapEntry)",
+                HttpRequestType::GetMapEntries { .. } => "HTTP(GetMapEntries)",
                 HttpRequestType::GetTransferCost(_) => "HTTP(GetTransferCost)",
+                HttpRequestType::GetSubnetFeeRateEstimate(_) => "HTTP(GetSubnetFeeRateEstimate)",
                 HttpRequestType::GetContractABI(..) => "HTTP(GetContractABI)",
                 HttpRequestType::GetContractSrc(..) => "HTTP(GetContractSrc)",
                 HttpRequestType::GetIsTraitImplemented(..) => "HTTP(GetIsTraitImplemented)",
                 HttpRequestType::CallReadOnlyFunction(..) => "HTTP(CallReadOnlyFunction)",
+                HttpRequestType::TraceReadOnlyFunction(..) => "HTTP(TraceReadOnlyFunction)",
                 HttpRequestType::GetAttachment(..) => "HTTP(GetAttachment)",
                 HttpRequestType::GetAttachmentsInv(..) => "HTTP(GetAttachmentsInv)",
                 HttpRequestType::MemPoolQuery(..) => "HTTP(MemPoolQuery)",
@@ -4629,17 +6977,42 @@ impl MessageSequence for StacksHttpMessage {
                 HttpRequestType::FeeRateEstimate(_, _, _) => "HTTP(FeeRateEstimate)",
                 HttpRequestType::GetWithdrawalStx { .. } => "HTTP(GetWithdrawalStx)",
                 HttpRequestType::BlockProposal(_, _) => "HTTP(BlockProposal)",
+                HttpRequestType::ValidateBlockProposal(_, _) => "HTTP(ValidateBlockProposal)",
                 HttpRequestType::GetWithdrawalNft { .. } => "HTTP(GetWithdrawalNft)",
+                HttpRequestType::GetWithdrawalProof { .. } => "HTTP(GetWithdrawalProof)",
+                HttpRequestType::GetWithdrawalStatus { .. } => "HTTP(GetWithdrawalStatus)",
+                HttpRequestType::GetBlockResults { .. } => "HTTP(GetBlockResults)",
+                HttpRequestType::GetClarityDataProof(..) => "HTTP(GetClarityDataProof)",
+                HttpRequestType::GetTransactionProof { .. } => "HTTP(GetTransactionProof)",
+                HttpRequestType::GetDepositStatus { .. } => "HTTP(GetDepositStatus)",
+                HttpRequestType::GetBlockCosts { .. } => "HTTP(GetBlockCosts)",
+                HttpRequestType::GetBlockHeader { .. } => "HTTP(GetBlockHeader)",
+                HttpRequestType::GetBlockStateDiff { .. } => "HTTP(GetBlockStateDiff)",
+                HttpRequestType::GetTransactionReceipt { .. } => "HTTP(GetTransactionReceipt)",
+                HttpRequestType::GetNonceGaps { .. } => "HTTP(GetNonceGaps)",
+                HttpRequestType::GetSubnetStatus(_) => "HTTP(GetSubnetStatus)",
+                HttpRequestType::PostDivergenceSnapshot { .. } => "HTTP(PostDivergenceSnapshot)",
+                HttpRequestType::GetMemPoolTxs { .. } => "HTTP(GetMemPoolTxs)",
+                HttpRequestType::GetMemPoolSummary { .. } => "HTTP(GetMemPoolSummary)",
+                HttpRequestType::DeleteMemPoolTx { .. } => "HTTP(DeleteMemPoolTx)",
+                HttpRequestType::GetAdminPeers { .. } => "HTTP(GetAdminPeers)",
+                HttpRequestType::GetAdminObservers { .. } => "HTTP(GetAdminObservers)",
+                HttpRequestType::GetPeerBans { .. } => "HTTP(GetPeerBans)",
+                HttpRequestType::PostPeerBan { .. } => "HTTP(PostPeerBan)",
+                HttpRequestType::DeletePeerBan { .. } => "HTTP(DeletePeerBan)",
             },
             StacksHttpMessage::Response(ref res) => match res {
                 HttpResponseType::TokenTransferCost(_, _) => "HTTP(TokenTransferCost)",
+                HttpResponseType::SubnetFeeRateEstimate(_, _) => "HTTP(SubnetFeeRateEstimate)",
                 HttpResponseType::GetDataVar(_, _) => "HTTP(GetDataVar)",
                 HttpResponseType::GetMapEntry(_, _) => "HTTP(GetMapEntry)",
+                HttpResponseType::MapEntries(..) => "HTTP(MapEntries)",
                 HttpResponseType::GetAccount(_, _) => "HTTP(GetAccount)",
                 HttpResponseType::GetContractABI(..) => "HTTP(GetContractABI)",
                 HttpResponseType::GetContractSrc(..) => "HTTP(GetContractSrc)",
                 HttpResponseType::GetIsTraitImplemented(..) => "HTTP(GetIsTraitImplemented)",
                 HttpResponseType::CallReadOnlyFunction(..) => "HTTP(CallReadOnlyFunction)",
+                HttpResponseType::TraceReadOnlyFunction(..) => "HTTP(TraceReadOnlyFunction)",
                 HttpResponseType::GetAttachment(_, _) => "HTTP(GetAttachment)",
                 HttpResponseType::GetAttachmentsInv(_, _) => "HTTP(GetAttachmentsInv)",
                 HttpResponseType::PeerInfo(_, _) => "HTTP(PeerInfo)",
@@ -4652,6 +7025,8 @@ impl MessageSequence for StacksHttpMessage {
                 HttpResponseType::Microblocks(_, _) => "HTTP(Microblocks)",
                 HttpResponseType::MicroblockStream(_) => "HTTP(MicroblockStream)",
                 HttpResponseType::TransactionID(_, _) => "HTTP(Transaction)",
+                HttpResponseType::TransactionBatch(..) => "HTTP(TransactionBatch)",
+                HttpResponseType::TransactionAdmission(..) => "HTTP(TransactionAdmission)",
                 HttpResponseType::StacksBlockAccepted(..) => "HTTP(StacksBlockAccepted)",
                 HttpResponseType::MicroblockHash(_, _) => "HTTP(MicroblockHash)",
                 HttpResponseType::UnconfirmedTransaction(_, _) => "HTTP(UnconfirmedTransaction)",
@@ -4665,6 +7040,7 @@ impl MessageSequence for StacksHttpMessage {
                 HttpResponseType::PaymentRequired(_, _) => "HTTP(402)",
                 HttpResponseType::Forbidden(_, _) => "HTTP(403)",
                 HttpResponseType::NotFound(_, _) => "HTTP(404)",
+                HttpResponseType::TooManyRequests(_, _) => "HTTP(429)",
                 HttpResponseType::ServerError(_, _) => "HTTP(500)",
                 HttpResponseType::ServiceUnavailable(_, _) => "HTTP(503)",
                 HttpResponseType::Error(_, _, _) => "HTTP(other)",
@@ -4672,8 +7048,28 @@ impl MessageSequence for StacksHttpMessage {
                     "HTTP(TransactionFeeEstimation)"
                 }
                 HttpResponseType::GetWithdrawal(_, _) => "HTTP(GetWithdrawal)",
+                HttpResponseType::WithdrawalStatus(..) => "HTTP(WithdrawalStatus)",
+                HttpResponseType::BlockResults(..) => "HTTP(BlockResults)",
+                HttpResponseType::ClarityDataProof(..) => "HTTP(ClarityDataProof)",
+                HttpResponseType::TransactionProof(..) => "HTTP(TransactionProof)",
+                HttpResponseType::DepositStatus(..) => "HTTP(DepositStatus)",
+                HttpResponseType::BlockCosts(..) => "HTTP(BlockCosts)",
+                HttpResponseType::BlockHeader(..) => "HTTP(BlockHeader)",
+                HttpResponseType::BlockStateDiff(..) => "HTTP(BlockStateDiff)",
+                HttpResponseType::TransactionReceipt(..) => "HTTP(TransactionReceipt)",
+                HttpResponseType::NonceGaps(..) => "HTTP(NonceGaps)",
+                HttpResponseType::SubnetStatus(..) => "HTTP(SubnetStatus)",
+                HttpResponseType::DivergenceSnapshot(..) => "HTTP(DivergenceSnapshot)",
+                HttpResponseType::MemPoolListing(..) => "HTTP(MemPoolListing)",
+                HttpResponseType::MemPoolSummary(..) => "HTTP(MemPoolSummary)",
+                HttpResponseType::MemPoolEviction(..) => "HTTP(MemPoolEviction)",
+                HttpResponseType::AdminPeers(..) => "HTTP(AdminPeers)",
+                HttpResponseType::AdminObservers(..) => "HTTP(AdminObservers)",
+                HttpResponseType::PeerBans(..) => "HTTP(PeerBans)",
+                HttpResponseType::PeerBan(..) => "HTTP(PeerBan)",
                 HttpResponseType::BlockProposalValid { .. }
                 | HttpResponseType::BlockProposalInvalid { .. } => "HTTP(BlockProposal)",
+                HttpResponseType::BlockProposalValidation(..) => "HTTP(BlockProposalValidation)",
             },
         }
     }
@@ -5205,8 +7601,20 @@ mod test {
     use crate::chainstate::stacks::TransactionVersion;
     use crate::net::codec::test::check_codec_and_corruption;
     use crate::net::test::*;
+    use crate::net::L1ContractStatus;
+    use crate::net::NodeStartupState;
     use crate::net::RPCNeighbor;
     use crate::net::RPCNeighborsInfo;
+    use crate::net::SubnetMinerStatus;
+    use crate::net::{BlockProposalTxResult, CallReadOnlyResponse, TraceReadOnlyResponse};
+    use crate::net::{TransactionAdmissionCheck, TransactionAdmissionCheckStatus};
+    use crate::net::{TransactionBatchResult, TransactionBatchStatus};
+    use crate::net::{TransactionNonceDiagnostics, TransactionNonceStatus};
+    use crate::types::chainstate::BurnchainHeaderHash;
+    use crate::vm::costs::ExecutionCost;
+    use clarity::vm::database::ClaritySerializable;
+    use clarity::vm::hooks::StepTrace;
+    use clarity::vm::types::StacksAddressExtensions;
     use stacks_common::util::hash::to_hex;
     use stacks_common::util::hash::Hash160;
     use stacks_common::util::hash::MerkleTree;
@@ -5866,942 +8274,2032 @@ mod test {
              HttpResponsePreamble::from_headers(200, "Ok".to_string(), false, None, HttpContentType::Bytes, 0, vec![], vec![])),
         ];
 
-        for (data, response) in tests.iter() {
-            test_debug!("Try parsing:\n{}\n", data);
-            let res = HttpResponsePreamble::consensus_deserialize(&mut data.as_bytes());
-            assert!(res.is_ok(), "{:?}", &res);
-            assert_eq!(res.unwrap(), *response);
+        for (data, response) in tests.iter() {
+            test_debug!("Try parsing:\n{}\n", data);
+            let res = HttpResponsePreamble::consensus_deserialize(&mut data.as_bytes());
+            assert!(res.is_ok(), "{:?}", &res);
+            assert_eq!(res.unwrap(), *response);
+
+            let sres = StacksHttpPreamble::consensus_deserialize(&mut data.as_bytes());
+            assert!(sres.is_ok(), "{:?}", &sres);
+            assert_eq!(
+                sres.unwrap(),
+                StacksHttpPreamble::Response((*response).clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_http_response_case_ok() {
+        let tests = vec![
+            ("HTTP/1.1 200 OK\r\ncOnTeNt-TyPe: aPpLiCaTiOn/oCtEt-StReAm\r\ncOnTeNt-LeNgTh: 123\r\nx-ReQuEsT-iD: 0\r\n\r\n",
+             HttpResponsePreamble::from_headers(200, "OK".to_string(), true, Some(123), HttpContentType::Bytes, 0, vec![], vec![])),
+            ("HTTP/1.1 200 Ok\r\ncOnTeNt-tYpE: aPpLiCaTiOn/OcTeT-sTrEaM\r\ntRaNsFeR-eNcOdInG: cHuNkEd\r\nX-rEqUeSt-Id: 0\r\n\r\n",
+             HttpResponsePreamble::from_headers(200, "Ok".to_string(), true, None, HttpContentType::Bytes, 0, vec![], vec![])),
+            ("HTTP/1.1 200 Ok\r\ncOnNeCtIoN: cLoSe\r\nContent-Type: application/octet-stream\r\nTransfer-encoding: chunked\r\nX-Request-ID: 0\r\n\r\n",
+             HttpResponsePreamble::from_headers(200, "Ok".to_string(), false, None, HttpContentType::Bytes, 0, vec![], vec![])),
+            ("HTTP/1.1 200 Ok\r\ncOnNeCtIoN: kEeP-AlIvE\r\nContent-Type: application/octet-stream\r\nTransfer-encoding: chunked\r\nX-Request-ID: 0\r\n\r\n",
+             HttpResponsePreamble::from_headers(200, "Ok".to_string(), true, None, HttpContentType::Bytes, 0, vec![], vec![])),
+        ];
+
+        for (data, response) in tests.iter() {
+            test_debug!("Try parsing:\n{}\n", data);
+            let res = HttpResponsePreamble::consensus_deserialize(&mut data.as_bytes());
+            assert!(res.is_ok(), "{:?}", &res);
+            assert_eq!(res.unwrap(), *response);
+
+            let sres = StacksHttpPreamble::consensus_deserialize(&mut data.as_bytes());
+            assert!(sres.is_ok(), "{:?}", &sres);
+            assert_eq!(
+                sres.unwrap(),
+                StacksHttpPreamble::Response((*response).clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_http_response_preamble_headers() {
+        let mut res = HttpResponsePreamble::new(
+            200,
+            "OK".to_string(),
+            Some(123),
+            HttpContentType::JSON,
+            true,
+            123,
+        );
+        assert_eq!(res.request_id, 123);
+
+        res.set_request_id(456);
+        assert_eq!(res.request_id, 456);
+
+        res.add_header("foo".to_string(), "bar".to_string());
+        res.add_CORS_headers();
+
+        let mut bytes = vec![];
+        res.consensus_serialize(&mut bytes).unwrap();
+        let txt = String::from_utf8(bytes).unwrap();
+        assert!(
+            txt.find("Server: stacks/2.0\r\n").is_some(),
+            "Server header is missing"
+        );
+        assert!(
+            txt.find("Content-Length: 123\r\n").is_some(),
+            "Content-Length is missing"
+        );
+        assert!(
+            txt.find("Content-Type: application/json\r\n").is_some(),
+            "Content-Type is missing"
+        );
+        assert!(txt.find("Date: ").is_some(), "Date header is missing");
+        assert!(txt.find("foo: bar\r\n").is_some(), "foo header is missing");
+        assert!(
+            txt.find("X-Request-Id: 456\r\n").is_some(),
+            "X-Request-Id is missing"
+        );
+        assert!(
+            txt.find("Access-Control-Allow-Origin: *\r\n").is_some(),
+            "CORS header is missing"
+        );
+        assert!(
+            txt.find("Access-Control-Allow-Headers: origin, content-type\r\n")
+                .is_some(),
+            "CORS header is missing"
+        );
+        assert!(
+            txt.find("Access-Control-Allow-Methods: POST, GET, OPTIONS\r\n")
+                .is_some(),
+            "CORS header is missing"
+        );
+        assert!(txt.find("Connection: ").is_none()); // not sent if keep_alive is true
+    }
+
+    #[test]
+    fn test_parse_http_response_preamble_err() {
+        let tests = vec![
+            ("HTTP/1.1 200",
+            "failed to fill whole buffer"),
+            ("HTTP/1.1 200 OK\r\nfoo: \u{2764}\r\n\r\n",
+            "header value is not ASCII-US"),
+            ("HTTP/1.1 200 OK\r\nfoo: bar\r\nfoo: bar\r\n\r\n",
+             "duplicate header"),
+            ("HTTP/1.1 200 OK\r\nContent-Type: image/png\r\n\r\n",
+             "Unsupported HTTP content type"),
+            ("HTTP/1.1 200 OK\r\nContent-Length: foo\r\n\r\n",
+             "Invalid Content-Length"),
+            ("HTTP/1.1 200 OK\r\nContent-Length: 123\r\n\r\n",
+             "missing Content-Type, Content-Length"),
+            ("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\n",
+             "missing Content-Type, Content-Length"),
+            ("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 123\r\nTransfer-Encoding: chunked\r\n\r\n",
+             "incompatible transfer-encoding and content-length"),
+            ("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 123\r\nConnection: foo\r\n\r\n",
+             "invalid Connection: header"),
+        ];
+
+        for (data, errstr) in tests.iter() {
+            let res = HttpResponsePreamble::consensus_deserialize(&mut data.as_bytes());
+            test_debug!("Expect '{}', got: {:?}", errstr, &res);
+            assert!(res.is_err(), "{:?}", &res);
+            assert!(res.unwrap_err().to_string().find(errstr).is_some());
+        }
+    }
+
+    #[test]
+    fn test_parse_stacks_http_preamble_response_err() {
+        let tests = vec![
+            ("HTTP/1.1 200",
+            "Not enough bytes to form a HTTP request or response"),
+            ("HTTP/1.1 200 OK\r\nfoo: \u{2764}\r\n\r\n",
+             "Failed to decode HTTP request or HTTP response"),
+            ("HTTP/1.1 200 OK\r\nfoo: bar\r\nfoo: bar\r\n\r\n",
+             "Failed to decode HTTP request or HTTP response"),
+            ("HTTP/1.1 200 OK\r\nContent-Type: image/png\r\n\r\n",
+             "Failed to decode HTTP request or HTTP response"),
+            ("HTTP/1.1 200 OK\r\nContent-Length: foo\r\n\r\n",
+             "Failed to decode HTTP request or HTTP response"),
+            ("HTTP/1.1 200 OK\r\nContent-Length: 123\r\n\r\n",
+             "Failed to decode HTTP request or HTTP response"),
+            ("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\n",
+             "Failed to decode HTTP request or HTTP response"),
+            ("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 123\r\nTransfer-Encoding: chunked\r\n\r\n",
+             "Failed to decode HTTP request or HTTP response"),
+            ("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 123\r\nConnection: foo\r\n\r\n",
+             "Failed to decode HTTP request or HTTP response"),
+        ];
+
+        for (data, errstr) in tests.iter() {
+            let sres = StacksHttpPreamble::consensus_deserialize(&mut data.as_bytes());
+            test_debug!("Expect '{}', got: {:?}", errstr, &sres);
+            assert!(sres.is_err(), "{:?}", &sres);
+            assert!(
+                sres.as_ref()
+                    .unwrap_err()
+                    .to_string()
+                    .find(errstr)
+                    .is_some(),
+                "{:?}",
+                &sres
+            );
+        }
+    }
+
+    fn make_test_transaction() -> StacksTransaction {
+        let privk = StacksPrivateKey::from_hex(
+            "6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001",
+        )
+        .unwrap();
+        let auth = TransactionAuth::from_p2pkh(&privk).unwrap();
+        let addr = auth.origin().address_testnet();
+        let recv_addr = StacksAddress {
+            version: 1,
+            bytes: Hash160([0xff; 20]),
+        };
+
+        let mut tx_stx_transfer = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            auth.clone(),
+            TransactionPayload::TokenTransfer(
+                recv_addr.clone().into(),
+                123,
+                TokenTransferMemo([0u8; 34]),
+            ),
+        );
+        tx_stx_transfer.chain_id = 0x80000000;
+        tx_stx_transfer.post_condition_mode = TransactionPostConditionMode::Allow;
+        tx_stx_transfer.set_tx_fee(0);
+        tx_stx_transfer
+    }
+
+    #[test]
+    fn test_http_parse_host_header_value() {
+        let hosts = vec![
+            "1.2.3.4",
+            "1.2.3.4:5678",
+            "[1:203:405:607:809:a0b:c0d:e0f]",
+            "[1:203:405:607:809:a0b:c0d:e0f]:12345",
+            "www.foo.com",
+            "www.foo.com:12345",
+            // invalid IP addresses will be parsed to DNS names
+            "1.2.3.4.5",
+            "[1:203:405:607:809:a0b:c0d:e0f:1011]",
+            // these won't parse at all, since the port is invalid
+            "1.2.3.4:1234567",
+            "1.2.3.4.5:1234567",
+            "[1:203:405:607:809:a0b:c0d:e0f]:1234567",
+            "[1:203:405:607:809:a0b:c0d:e0f:1011]:1234567",
+            "www.foo.com:1234567",
+            ":",
+            ":123",
+        ];
+
+        let peerhosts = vec![
+            Some(PeerHost::IP(
+                PeerAddress([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 1, 2, 3, 4]),
+                80,
+            )),
+            Some(PeerHost::IP(
+                PeerAddress([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 1, 2, 3, 4]),
+                5678,
+            )),
+            Some(PeerHost::IP(
+                PeerAddress([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]),
+                80,
+            )),
+            Some(PeerHost::IP(
+                PeerAddress([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]),
+                12345,
+            )),
+            Some(PeerHost::DNS("www.foo.com".to_string(), 80)),
+            Some(PeerHost::DNS("www.foo.com".to_string(), 12345)),
+            Some(PeerHost::DNS("1.2.3.4.5".to_string(), 80)),
+            Some(PeerHost::DNS(
+                "[1:203:405:607:809:a0b:c0d:e0f:1011]".to_string(),
+                80,
+            )),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ];
+
+        for (host, expected_host) in hosts.iter().zip(peerhosts.iter()) {
+            let peerhost = match host.parse::<PeerHost>() {
+                Ok(ph) => Some(ph),
+                Err(_) => None,
+            };
 
-            let sres = StacksHttpPreamble::consensus_deserialize(&mut data.as_bytes());
-            assert!(sres.is_ok(), "{:?}", &sres);
-            assert_eq!(
-                sres.unwrap(),
-                StacksHttpPreamble::Response((*response).clone())
-            );
+            match (peerhost, expected_host) {
+                (Some(ref ph), Some(ref expected_ph)) => assert_eq!(*ph, *expected_ph),
+                (None, None) => {}
+                (Some(ph), None) => {
+                    eprintln!(
+                        "Parsed {} successfully to {:?}, but expected error",
+                        host, ph
+                    );
+                    assert!(false);
+                }
+                (None, Some(expected_ph)) => {
+                    eprintln!("Failed to parse {} successfully", host);
+                    assert!(false);
+                }
+            }
         }
     }
 
     #[test]
-    fn test_parse_http_response_case_ok() {
+    fn test_http_request_type_codec() {
+        let http_request_metadata_ip = HttpRequestMetadata {
+            version: HttpVersion::Http11,
+            peer: PeerHost::IP(
+                PeerAddress([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]),
+                12345,
+            ),
+            keep_alive: true,
+            canonical_stacks_tip_height: None,
+        };
+        let http_request_metadata_dns = HttpRequestMetadata {
+            version: HttpVersion::Http11,
+            peer: PeerHost::DNS("www.foo.com".to_string(), 80),
+            keep_alive: true,
+            canonical_stacks_tip_height: None,
+        };
+
         let tests = vec![
-            ("HTTP/1.1 200 OK\r\ncOnTeNt-TyPe: aPpLiCaTiOn/oCtEt-StReAm\r\ncOnTeNt-LeNgTh: 123\r\nx-ReQuEsT-iD: 0\r\n\r\n",
-             HttpResponsePreamble::from_headers(200, "OK".to_string(), true, Some(123), HttpContentType::Bytes, 0, vec![], vec![])),
-            ("HTTP/1.1 200 Ok\r\ncOnTeNt-tYpE: aPpLiCaTiOn/OcTeT-sTrEaM\r\ntRaNsFeR-eNcOdInG: cHuNkEd\r\nX-rEqUeSt-Id: 0\r\n\r\n",
-             HttpResponsePreamble::from_headers(200, "Ok".to_string(), true, None, HttpContentType::Bytes, 0, vec![], vec![])),
-            ("HTTP/1.1 200 Ok\r\ncOnNeCtIoN: cLoSe\r\nContent-Type: application/octet-stream\r\nTransfer-encoding: chunked\r\nX-Request-ID: 0\r\n\r\n",
-             HttpResponsePreamble::from_headers(200, "Ok".to_string(), false, None, HttpContentType::Bytes, 0, vec![], vec![])),
-            ("HTTP/1.1 200 Ok\r\ncOnNeCtIoN: kEeP-AlIvE\r\nContent-Type: application/octet-stream\r\nTransfer-encoding: chunked\r\nX-Request-ID: 0\r\n\r\n",
-             HttpResponsePreamble::from_headers(200, "Ok".to_string(), true, None, HttpContentType::Bytes, 0, vec![], vec![])),
+            HttpRequestType::GetNeighbors(http_request_metadata_ip.clone()),
+            HttpRequestType::GetBlock(http_request_metadata_dns.clone(), StacksBlockId([2u8; 32])),
+            HttpRequestType::GetMicroblocksIndexed(
+                http_request_metadata_ip.clone(),
+                StacksBlockId([3u8; 32]),
+            ),
+            HttpRequestType::PostTransaction(
+                http_request_metadata_dns.clone(),
+                make_test_transaction(),
+                None,
+                false,
+            ),
+            HttpRequestType::OptionsPreflight(http_request_metadata_ip.clone(), "/".to_string()),
         ];
 
-        for (data, response) in tests.iter() {
-            test_debug!("Try parsing:\n{}\n", data);
-            let res = HttpResponsePreamble::consensus_deserialize(&mut data.as_bytes());
-            assert!(res.is_ok(), "{:?}", &res);
-            assert_eq!(res.unwrap(), *response);
-
-            let sres = StacksHttpPreamble::consensus_deserialize(&mut data.as_bytes());
-            assert!(sres.is_ok(), "{:?}", &sres);
-            assert_eq!(
-                sres.unwrap(),
-                StacksHttpPreamble::Response((*response).clone())
-            );
-        }
-    }
+        let mut tx_body = vec![];
+        make_test_transaction()
+            .consensus_serialize(&mut tx_body)
+            .unwrap();
 
-    #[test]
-    fn test_http_response_preamble_headers() {
-        let mut res = HttpResponsePreamble::new(
-            200,
-            "OK".to_string(),
-            Some(123),
-            HttpContentType::JSON,
-            true,
-            123,
+        let mut post_transaction_preamble = HttpRequestPreamble::new(
+            HttpVersion::Http11,
+            "POST".to_string(),
+            "/v2/transactions".to_string(),
+            http_request_metadata_dns.peer.hostname(),
+            http_request_metadata_dns.peer.port(),
+            http_request_metadata_dns.keep_alive,
         );
-        assert_eq!(res.request_id, 123);
+        post_transaction_preamble.set_content_type(HttpContentType::Bytes);
+        post_transaction_preamble.set_content_length(tx_body.len() as u32);
 
-        res.set_request_id(456);
-        assert_eq!(res.request_id, 456);
+        // all of these should parse
+        let expected_http_preambles = vec![
+            HttpRequestPreamble::new(
+                HttpVersion::Http11,
+                "GET".to_string(),
+                "/v2/neighbors".to_string(),
+                http_request_metadata_ip.peer.hostname(),
+                http_request_metadata_ip.peer.port(),
+                http_request_metadata_ip.keep_alive,
+            ),
+            HttpRequestPreamble::new(
+                HttpVersion::Http11,
+                "GET".to_string(),
+                format!("/v2/blocks/{}", StacksBlockId([2u8; 32]).to_hex()),
+                http_request_metadata_dns.peer.hostname(),
+                http_request_metadata_dns.peer.port(),
+                http_request_metadata_dns.keep_alive,
+            ),
+            HttpRequestPreamble::new(
+                HttpVersion::Http11,
+                "GET".to_string(),
+                format!("/v2/microblocks/{}", StacksBlockId([3u8; 32]).to_hex()),
+                http_request_metadata_ip.peer.hostname(),
+                http_request_metadata_ip.peer.port(),
+                http_request_metadata_ip.keep_alive,
+            ),
+            post_transaction_preamble,
+            HttpRequestPreamble::new(
+                HttpVersion::Http11,
+                "OPTIONS".to_string(),
+                format!("/"),
+                http_request_metadata_ip.peer.hostname(),
+                http_request_metadata_ip.peer.port(),
+                http_request_metadata_ip.keep_alive,
+            ),
+        ];
 
-        res.add_header("foo".to_string(), "bar".to_string());
-        res.add_CORS_headers();
+        let expected_http_bodies = vec![vec![], vec![], vec![], tx_body];
 
-        let mut bytes = vec![];
-        res.consensus_serialize(&mut bytes).unwrap();
-        let txt = String::from_utf8(bytes).unwrap();
-        assert!(
-            txt.find("Server: stacks/2.0\r\n").is_some(),
-            "Server header is missing"
-        );
-        assert!(
-            txt.find("Content-Length: 123\r\n").is_some(),
-            "Content-Length is missing"
-        );
-        assert!(
-            txt.find("Content-Type: application/json\r\n").is_some(),
-            "Content-Type is missing"
-        );
-        assert!(txt.find("Date: ").is_some(), "Date header is missing");
-        assert!(txt.find("foo: bar\r\n").is_some(), "foo header is missing");
-        assert!(
-            txt.find("X-Request-Id: 456\r\n").is_some(),
-            "X-Request-Id is missing"
-        );
-        assert!(
-            txt.find("Access-Control-Allow-Origin: *\r\n").is_some(),
-            "CORS header is missing"
-        );
-        assert!(
-            txt.find("Access-Control-Allow-Headers: origin, content-type\r\n")
-                .is_some(),
-            "CORS header is missing"
-        );
-        assert!(
-            txt.find("Access-Control-Allow-Methods: POST, GET, OPTIONS\r\n")
-                .is_some(),
-            "CORS header is missing"
-        );
-        assert!(txt.find("Connection: ").is_none()); // not sent if keep_alive is true
-    }
+        for (test, (expected_http_preamble, expected_http_body)) in tests.iter().zip(
+            expected_http_preambles
+                .iter()
+                .zip(expected_http_bodies.iter()),
+        ) {
+            let mut expected_bytes = vec![];
+            expected_http_preamble
+                .consensus_serialize(&mut expected_bytes)
+                .unwrap();
 
-    #[test]
-    fn test_parse_http_response_preamble_err() {
-        let tests = vec![
-            ("HTTP/1.1 200",
-            "failed to fill whole buffer"),
-            ("HTTP/1.1 200 OK\r\nfoo: \u{2764}\r\n\r\n",
-            "header value is not ASCII-US"),
-            ("HTTP/1.1 200 OK\r\nfoo: bar\r\nfoo: bar\r\n\r\n",
-             "duplicate header"),
-            ("HTTP/1.1 200 OK\r\nContent-Type: image/png\r\n\r\n",
-             "Unsupported HTTP content type"),
-            ("HTTP/1.1 200 OK\r\nContent-Length: foo\r\n\r\n",
-             "Invalid Content-Length"),
-            ("HTTP/1.1 200 OK\r\nContent-Length: 123\r\n\r\n",
-             "missing Content-Type, Content-Length"),
-            ("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\n",
-             "missing Content-Type, Content-Length"),
-            ("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 123\r\nTransfer-Encoding: chunked\r\n\r\n",
-             "incompatible transfer-encoding and content-length"),
-            ("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 123\r\nConnection: foo\r\n\r\n",
-             "invalid Connection: header"),
-        ];
+            test_debug!(
+                "Expected preamble:\n{}",
+                str::from_utf8(&expected_bytes).unwrap()
+            );
 
-        for (data, errstr) in tests.iter() {
-            let res = HttpResponsePreamble::consensus_deserialize(&mut data.as_bytes());
-            test_debug!("Expect '{}', got: {:?}", errstr, &res);
-            assert!(res.is_err(), "{:?}", &res);
-            assert!(res.unwrap_err().to_string().find(errstr).is_some());
+            if expected_http_preamble.content_type.is_none()
+                || expected_http_preamble.content_type != Some(HttpContentType::Bytes)
+            {
+                test_debug!(
+                    "Expected http body:\n{}",
+                    str::from_utf8(&expected_http_body).unwrap()
+                );
+            } else {
+                test_debug!("Expected http body (hex):\n{}", to_hex(&expected_http_body));
+            }
+
+            expected_bytes.append(&mut expected_http_body.clone());
+
+            let mut bytes = vec![];
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            http.write_message(&mut bytes, &StacksHttpMessage::Request(test.clone()))
+                .unwrap();
+
+            assert_eq!(bytes, expected_bytes);
         }
     }
 
     #[test]
-    fn test_parse_stacks_http_preamble_response_err() {
-        let tests = vec![
-            ("HTTP/1.1 200",
-            "Not enough bytes to form a HTTP request or response"),
-            ("HTTP/1.1 200 OK\r\nfoo: \u{2764}\r\n\r\n",
-             "Failed to decode HTTP request or HTTP response"),
-            ("HTTP/1.1 200 OK\r\nfoo: bar\r\nfoo: bar\r\n\r\n",
-             "Failed to decode HTTP request or HTTP response"),
-            ("HTTP/1.1 200 OK\r\nContent-Type: image/png\r\n\r\n",
-             "Failed to decode HTTP request or HTTP response"),
-            ("HTTP/1.1 200 OK\r\nContent-Length: foo\r\n\r\n",
-             "Failed to decode HTTP request or HTTP response"),
-            ("HTTP/1.1 200 OK\r\nContent-Length: 123\r\n\r\n",
-             "Failed to decode HTTP request or HTTP response"),
-            ("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\n",
-             "Failed to decode HTTP request or HTTP response"),
-            ("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 123\r\nTransfer-Encoding: chunked\r\n\r\n",
-             "Failed to decode HTTP request or HTTP response"),
-            ("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 123\r\nConnection: foo\r\n\r\n",
-             "Failed to decode HTTP request or HTTP response"),
+    fn test_http_request_type_codec_err() {
+        let bad_content_lengths = vec![
+            "GET /v2/neighbors HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 1\r\n\r\nb",
+            "GET /v2/blocks/1111111111111111111111111111111111111111111111111111111111111111 HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 1\r\n\r\nb",
+            "GET /v2/microblocks/1111111111111111111111111111111111111111111111111111111111111111 HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 1\r\n\r\nb",
+            "POST /v2/transactions HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 0\r\n\r\n",
         ];
+        for bad_content_length in bad_content_lengths {
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let (preamble, offset) = http.read_preamble(bad_content_length.as_bytes()).unwrap();
+            let e = http.read_payload(&preamble, &bad_content_length.as_bytes()[offset..]);
 
-        for (data, errstr) in tests.iter() {
-            let sres = StacksHttpPreamble::consensus_deserialize(&mut data.as_bytes());
-            test_debug!("Expect '{}', got: {:?}", errstr, &sres);
-            assert!(sres.is_err(), "{:?}", &sres);
+            assert!(e.is_err(), "{:?}", &e);
             assert!(
-                sres.as_ref()
+                e.as_ref()
                     .unwrap_err()
                     .to_string()
-                    .find(errstr)
+                    .find("-length body for")
                     .is_some(),
                 "{:?}",
-                &sres
+                &e
             );
         }
+
+        let bad_content_types = vec![
+            "POST /v2/transactions HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 1\r\n\r\nb",
+        ];
+        for bad_content_type in bad_content_types {
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let (preamble, offset) = http.read_preamble(bad_content_type.as_bytes()).unwrap();
+            let e = http.read_payload(&preamble, &bad_content_type.as_bytes()[offset..]);
+            assert!(e.is_err());
+            assert!(e.unwrap_err().to_string().find("Content-Type").is_some());
+        }
     }
 
-    fn make_test_transaction() -> StacksTransaction {
+    #[test]
+    fn test_http_response_type_codec() {
+        let test_neighbors_info = RPCNeighborsInfo {
+            sample: vec![
+                RPCNeighbor {
+                    network_id: 1,
+                    peer_version: 2,
+                    addrbytes: PeerAddress([
+                        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b,
+                        0x0c, 0x0d, 0x0e, 0x0f,
+                    ]),
+                    port: 12345,
+                    public_key_hash: Hash160::from_bytes(
+                        &hex_bytes("1111111111111111111111111111111111111111").unwrap(),
+                    )
+                    .unwrap(),
+                    authenticated: true,
+                },
+                RPCNeighbor {
+                    network_id: 3,
+                    peer_version: 4,
+                    addrbytes: PeerAddress([
+                        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff,
+                        0x01, 0x02, 0x03, 0x04,
+                    ]),
+                    port: 23456,
+                    public_key_hash: Hash160::from_bytes(
+                        &hex_bytes("2222222222222222222222222222222222222222").unwrap(),
+                    )
+                    .unwrap(),
+                    authenticated: false,
+                },
+            ],
+            inbound: vec![],
+            outbound: vec![],
+        };
+
         let privk = StacksPrivateKey::from_hex(
             "6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001",
         )
         .unwrap();
-        let auth = TransactionAuth::from_p2pkh(&privk).unwrap();
-        let addr = auth.origin().address_testnet();
-        let recv_addr = StacksAddress {
-            version: 1,
-            bytes: Hash160([0xff; 20]),
-        };
+        let test_block_info = make_codec_test_block(5);
+        let test_microblock_info =
+            make_sample_microblock_stream(&privk, &test_block_info.block_hash());
 
-        let mut tx_stx_transfer = StacksTransaction::new(
-            TransactionVersion::Testnet,
-            auth.clone(),
-            TransactionPayload::TokenTransfer(
-                recv_addr.clone().into(),
-                123,
-                TokenTransferMemo([0u8; 34]),
+        let mut test_block_info_bytes = vec![];
+        test_block_info
+            .consensus_serialize(&mut test_block_info_bytes)
+            .unwrap();
+
+        let mut test_microblock_info_bytes = vec![];
+        test_microblock_info
+            .consensus_serialize(&mut test_microblock_info_bytes)
+            .unwrap();
+
+        let tests = vec![
+            // length is known
+            (
+                HttpResponseType::Neighbors(
+                    HttpResponseMetadata::new(
+                        HttpVersion::Http11,
+                        123,
+                        Some(serde_json::to_string(&test_neighbors_info).unwrap().len() as u32),
+                        true,
+                        None,
+                    ),
+                    test_neighbors_info.clone(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::Block(
+                    HttpResponseMetadata::new(
+                        HttpVersion::Http11,
+                        123,
+                        Some(test_block_info_bytes.len() as u32),
+                        true,
+                        None,
+                    ),
+                    test_block_info.clone(),
+                ),
+                format!("/v2/blocks/{}", test_block_info.block_hash().to_hex()),
+            ),
+            (
+                HttpResponseType::Microblocks(
+                    HttpResponseMetadata::new(
+                        HttpVersion::Http11,
+                        123,
+                        Some(test_microblock_info_bytes.len() as u32),
+                        true,
+                        None,
+                    ),
+                    test_microblock_info.clone(),
+                ),
+                format!(
+                    "/v2/microblocks/{}",
+                    test_microblock_info[0].block_hash().to_hex()
+                ),
+            ),
+            (
+                HttpResponseType::TransactionID(
+                    HttpResponseMetadata::new(
+                        HttpVersion::Http11,
+                        123,
+                        Some((Txid([0x1; 32]).to_hex().len() + 2) as u32),
+                        true,
+                        None,
+                    ),
+                    Txid([0x1; 32]),
+                ),
+                "/v2/transactions".to_string(),
+            ),
+            // length is unknown
+            (
+                HttpResponseType::Neighbors(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, None, true, None),
+                    test_neighbors_info.clone(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::Block(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, None, true, None),
+                    test_block_info.clone(),
+                ),
+                format!("/v2/blocks/{}", test_block_info.block_hash().to_hex()),
+            ),
+            (
+                HttpResponseType::Microblocks(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, None, true, None),
+                    test_microblock_info.clone(),
+                ),
+                format!(
+                    "/v2/microblocks/{}",
+                    test_microblock_info[0].block_hash().to_hex()
+                ),
+            ),
+            (
+                HttpResponseType::TransactionID(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, None, true, None),
+                    Txid([0x1; 32]),
+                ),
+                "/v2/transactions".to_string(),
+            ),
+            // errors without error messages
+            (
+                HttpResponseType::BadRequest(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
+                    "".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::Unauthorized(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
+                    "".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::PaymentRequired(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
+                    "".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::Forbidden(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
+                    "".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::NotFound(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
+                    "".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::TooManyRequests(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
+                    "".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::ServerError(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
+                    "".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::ServiceUnavailable(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
+                    "".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
             ),
-        );
-        tx_stx_transfer.chain_id = 0x80000000;
-        tx_stx_transfer.post_condition_mode = TransactionPostConditionMode::Allow;
-        tx_stx_transfer.set_tx_fee(0);
-        tx_stx_transfer
-    }
-
-    #[test]
-    fn test_http_parse_host_header_value() {
-        let hosts = vec![
-            "1.2.3.4",
-            "1.2.3.4:5678",
-            "[1:203:405:607:809:a0b:c0d:e0f]",
-            "[1:203:405:607:809:a0b:c0d:e0f]:12345",
-            "www.foo.com",
-            "www.foo.com:12345",
-            // invalid IP addresses will be parsed to DNS names
-            "1.2.3.4.5",
-            "[1:203:405:607:809:a0b:c0d:e0f:1011]",
-            // these won't parse at all, since the port is invalid
-            "1.2.3.4:1234567",
-            "1.2.3.4.5:1234567",
-            "[1:203:405:607:809:a0b:c0d:e0f]:1234567",
-            "[1:203:405:607:809:a0b:c0d:e0f:1011]:1234567",
-            "www.foo.com:1234567",
-            ":",
-            ":123",
-        ];
-
-        let peerhosts = vec![
-            Some(PeerHost::IP(
-                PeerAddress([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 1, 2, 3, 4]),
-                80,
-            )),
-            Some(PeerHost::IP(
-                PeerAddress([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 1, 2, 3, 4]),
-                5678,
-            )),
-            Some(PeerHost::IP(
-                PeerAddress([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]),
-                80,
-            )),
-            Some(PeerHost::IP(
-                PeerAddress([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]),
-                12345,
-            )),
-            Some(PeerHost::DNS("www.foo.com".to_string(), 80)),
-            Some(PeerHost::DNS("www.foo.com".to_string(), 12345)),
-            Some(PeerHost::DNS("1.2.3.4.5".to_string(), 80)),
-            Some(PeerHost::DNS(
-                "[1:203:405:607:809:a0b:c0d:e0f:1011]".to_string(),
-                80,
-            )),
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-        ];
-
-        for (host, expected_host) in hosts.iter().zip(peerhosts.iter()) {
-            let peerhost = match host.parse::<PeerHost>() {
-                Ok(ph) => Some(ph),
-                Err(_) => None,
-            };
-
-            match (peerhost, expected_host) {
-                (Some(ref ph), Some(ref expected_ph)) => assert_eq!(*ph, *expected_ph),
-                (None, None) => {}
-                (Some(ph), None) => {
-                    eprintln!(
-                        "Parsed {} successfully to {:?}, but expected error",
-                        host, ph
-                    );
-                    assert!(false);
-                }
-                (None, Some(expected_ph)) => {
-                    eprintln!("Failed to parse {} successfully", host);
-                    assert!(false);
-                }
-            }
-        }
-    }
-
-    #[test]
-    fn test_http_request_type_codec() {
-        let http_request_metadata_ip = HttpRequestMetadata {
-            version: HttpVersion::Http11,
-            peer: PeerHost::IP(
-                PeerAddress([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]),
-                12345,
+            (
+                HttpResponseType::Error(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
+                    502,
+                    "".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
             ),
-            keep_alive: true,
-            canonical_stacks_tip_height: None,
-        };
-        let http_request_metadata_dns = HttpRequestMetadata {
-            version: HttpVersion::Http11,
-            peer: PeerHost::DNS("www.foo.com".to_string(), 80),
-            keep_alive: true,
-            canonical_stacks_tip_height: None,
-        };
-
-        let tests = vec![
-            HttpRequestType::GetNeighbors(http_request_metadata_ip.clone()),
-            HttpRequestType::GetBlock(http_request_metadata_dns.clone(), StacksBlockId([2u8; 32])),
-            HttpRequestType::GetMicroblocksIndexed(
-                http_request_metadata_ip.clone(),
-                StacksBlockId([3u8; 32]),
+            // errors with specific messages
+            (
+                HttpResponseType::BadRequest(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
+                    "foo".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
             ),
-            HttpRequestType::PostTransaction(
-                http_request_metadata_dns.clone(),
-                make_test_transaction(),
-                None,
+            (
+                HttpResponseType::Unauthorized(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
+                    "foo".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::PaymentRequired(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
+                    "foo".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::Forbidden(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
+                    "foo".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::NotFound(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
+                    "foo".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::TooManyRequests(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
+                    "foo".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::ServerError(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
+                    "foo".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::ServiceUnavailable(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
+                    "foo".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
+            ),
+            (
+                HttpResponseType::Error(
+                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
+                    502,
+                    "foo".to_string(),
+                ),
+                "/v2/neighbors".to_string(),
             ),
-            HttpRequestType::OptionsPreflight(http_request_metadata_ip.clone(), "/".to_string()),
         ];
 
-        let mut tx_body = vec![];
-        make_test_transaction()
-            .consensus_serialize(&mut tx_body)
-            .unwrap();
-
-        let mut post_transaction_preamble = HttpRequestPreamble::new(
-            HttpVersion::Http11,
-            "POST".to_string(),
-            "/v2/transactions".to_string(),
-            http_request_metadata_dns.peer.hostname(),
-            http_request_metadata_dns.peer.port(),
-            http_request_metadata_dns.keep_alive,
-        );
-        post_transaction_preamble.set_content_type(HttpContentType::Bytes);
-        post_transaction_preamble.set_content_length(tx_body.len() as u32);
-
-        // all of these should parse
         let expected_http_preambles = vec![
-            HttpRequestPreamble::new(
-                HttpVersion::Http11,
-                "GET".to_string(),
-                "/v2/neighbors".to_string(),
-                http_request_metadata_ip.peer.hostname(),
-                http_request_metadata_ip.peer.port(),
-                http_request_metadata_ip.keep_alive,
+            // length is known
+            HttpResponsePreamble::new(
+                200,
+                "OK".to_string(),
+                Some(serde_json::to_string(&test_neighbors_info).unwrap().len() as u32),
+                HttpContentType::JSON,
+                true,
+                123,
             ),
-            HttpRequestPreamble::new(
-                HttpVersion::Http11,
-                "GET".to_string(),
-                format!("/v2/blocks/{}", StacksBlockId([2u8; 32]).to_hex()),
-                http_request_metadata_dns.peer.hostname(),
-                http_request_metadata_dns.peer.port(),
-                http_request_metadata_dns.keep_alive,
+            HttpResponsePreamble::new(
+                200,
+                "OK".to_string(),
+                Some(test_block_info_bytes.len() as u32),
+                HttpContentType::Bytes,
+                true,
+                123,
+            ),
+            HttpResponsePreamble::new(
+                200,
+                "OK".to_string(),
+                Some(test_microblock_info_bytes.len() as u32),
+                HttpContentType::Bytes,
+                true,
+                123,
+            ),
+            HttpResponsePreamble::new(
+                200,
+                "OK".to_string(),
+                Some((Txid([0x1; 32]).to_hex().len() + 2) as u32),
+                HttpContentType::JSON,
+                true,
+                123,
+            ),
+            // length is unknown
+            HttpResponsePreamble::new(
+                200,
+                "OK".to_string(),
+                None,
+                HttpContentType::JSON,
+                true,
+                123,
             ),
-            HttpRequestPreamble::new(
-                HttpVersion::Http11,
-                "GET".to_string(),
-                format!("/v2/microblocks/{}", StacksBlockId([3u8; 32]).to_hex()),
-                http_request_metadata_ip.peer.hostname(),
-                http_request_metadata_ip.peer.port(),
-                http_request_metadata_ip.keep_alive,
+            HttpResponsePreamble::new(
+                200,
+                "OK".to_string(),
+                None,
+                HttpContentType::Bytes,
+                true,
+                123,
             ),
-            post_transaction_preamble,
-            HttpRequestPreamble::new(
-                HttpVersion::Http11,
-                "OPTIONS".to_string(),
-                format!("/"),
-                http_request_metadata_ip.peer.hostname(),
-                http_request_metadata_ip.peer.port(),
-                http_request_metadata_ip.keep_alive,
+            HttpResponsePreamble::new(
+                200,
+                "OK".to_string(),
+                None,
+                HttpContentType::Bytes,
+                true,
+                123,
             ),
+            HttpResponsePreamble::new(
+                200,
+                "OK".to_string(),
+                None,
+                HttpContentType::JSON,
+                true,
+                123,
+            ),
+            // errors
+            HttpResponsePreamble::new_error(400, 123, None),
+            HttpResponsePreamble::new_error(401, 123, None),
+            HttpResponsePreamble::new_error(402, 123, None),
+            HttpResponsePreamble::new_error(403, 123, None),
+            HttpResponsePreamble::new_error(404, 123, None),
+            HttpResponsePreamble::new_error(429, 123, None),
+            HttpResponsePreamble::new_error(500, 123, None),
+            HttpResponsePreamble::new_error(503, 123, None),
+            // generic error
+            HttpResponsePreamble::new_error(502, 123, None),
+            // errors with messages
+            HttpResponsePreamble::new_error(400, 123, Some("foo".to_string())),
+            HttpResponsePreamble::new_error(401, 123, Some("foo".to_string())),
+            HttpResponsePreamble::new_error(402, 123, Some("foo".to_string())),
+            HttpResponsePreamble::new_error(403, 123, Some("foo".to_string())),
+            HttpResponsePreamble::new_error(404, 123, Some("foo".to_string())),
+            HttpResponsePreamble::new_error(429, 123, Some("foo".to_string())),
+            HttpResponsePreamble::new_error(500, 123, Some("foo".to_string())),
+            HttpResponsePreamble::new_error(503, 123, Some("foo".to_string())),
+            HttpResponsePreamble::new_error(502, 123, Some("foo".to_string())),
         ];
 
-        let expected_http_bodies = vec![vec![], vec![], vec![], tx_body];
+        let expected_http_bodies = vec![
+            // with content-length
+            serde_json::to_string(&test_neighbors_info)
+                .unwrap()
+                .as_bytes()
+                .to_vec(),
+            test_block_info_bytes.clone(),
+            test_microblock_info_bytes.clone(),
+            Txid([0x1; 32]).to_hex().as_bytes().to_vec(),
+            // with transfer-encoding: chunked
+            serde_json::to_string(&test_neighbors_info)
+                .unwrap()
+                .as_bytes()
+                .to_vec(),
+            test_block_info_bytes,
+            test_microblock_info_bytes,
+            Txid([0x1; 32]).to_hex().as_bytes().to_vec(),
+            // errors
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            // errors with messages
+            "foo".as_bytes().to_vec(),
+            "foo".as_bytes().to_vec(),
+            "foo".as_bytes().to_vec(),
+            "foo".as_bytes().to_vec(),
+            "foo".as_bytes().to_vec(),
+            "foo".as_bytes().to_vec(),
+            "foo".as_bytes().to_vec(),
+            "foo".as_bytes().to_vec(),
+            "foo".as_bytes().to_vec(),
+        ];
 
-        for (test, (expected_http_preamble, expected_http_body)) in tests.iter().zip(
-            expected_http_preambles
-                .iter()
-                .zip(expected_http_bodies.iter()),
-        ) {
-            let mut expected_bytes = vec![];
-            expected_http_preamble
-                .consensus_serialize(&mut expected_bytes)
+        for ((test, request_path), (expected_http_preamble, _expected_http_body)) in
+            tests.iter().zip(
+                expected_http_preambles
+                    .iter()
+                    .zip(expected_http_bodies.iter()),
+            )
+        {
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let mut bytes = vec![];
+            test_debug!("write body:\n{:?}\n", test);
+
+            http.begin_request(HttpVersion::Http11, request_path.to_string());
+            http.write_message(&mut bytes, &StacksHttpMessage::Response((*test).clone()))
                 .unwrap();
 
-            test_debug!(
-                "Expected preamble:\n{}",
-                str::from_utf8(&expected_bytes).unwrap()
-            );
+            let (mut preamble, offset) = match http.read_preamble(&bytes) {
+                Ok((p, o)) => (p, o),
+                Err(e) => {
+                    test_debug!("first 4096 bytes:\n{:?}\n", &bytes[0..].to_vec());
+                    test_debug!("error: {:?}", &e);
+                    assert!(false);
+                    unreachable!();
+                }
+            };
 
-            if expected_http_preamble.content_type.is_none()
-                || expected_http_preamble.content_type != Some(HttpContentType::Bytes)
-            {
-                test_debug!(
-                    "Expected http body:\n{}",
-                    str::from_utf8(&expected_http_body).unwrap()
-                );
+            test_debug!("read preamble of {} bytes\n{:?}\n", offset, preamble);
+
+            test_debug!("read http body\n{:?}\n", &bytes[offset..].to_vec());
+
+            let (message, _total_len) = if expected_http_preamble.is_chunked() {
+                let (msg_opt, len) = http
+                    .stream_payload(&preamble, &mut &bytes[offset..])
+                    .unwrap();
+                (msg_opt.unwrap().0, len)
             } else {
-                test_debug!("Expected http body (hex):\n{}", to_hex(&expected_http_body));
-            }
+                http.read_payload(&preamble, &bytes[offset..]).unwrap()
+            };
 
-            expected_bytes.append(&mut expected_http_body.clone());
+            test_debug!("got message\n{:?}\n", &message);
 
-            let mut bytes = vec![];
-            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
-            http.write_message(&mut bytes, &StacksHttpMessage::Request(test.clone()))
-                .unwrap();
+            // check everything in the parsed preamble except for the extra headers
+            match preamble {
+                StacksHttpPreamble::Response(ref mut req) => {
+                    assert_eq!(req.headers.len(), 5);
+                    assert!(req.headers.get("access-control-allow-headers").is_some());
+                    assert!(req.headers.get("access-control-allow-methods").is_some());
+                    assert!(req.headers.get("access-control-allow-origin").is_some());
+                    assert!(req.headers.get("server").is_some());
+                    assert!(req.headers.get("date").is_some());
+                    req.headers.clear();
+                }
+                StacksHttpPreamble::Request(_) => {
+                    panic!("parsed a request");
+                }
+            }
 
-            assert_eq!(bytes, expected_bytes);
+            assert_eq!(
+                preamble,
+                StacksHttpPreamble::Response((*expected_http_preamble).clone())
+            );
+            assert_eq!(message, StacksHttpMessage::Response((*test).clone()));
+            assert_eq!(http.num_pending(), 0);
         }
     }
 
     #[test]
-    fn test_http_request_type_codec_err() {
-        let bad_content_lengths = vec![
-            "GET /v2/neighbors HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 1\r\n\r\nb",
-            "GET /v2/blocks/1111111111111111111111111111111111111111111111111111111111111111 HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 1\r\n\r\nb",
-            "GET /v2/microblocks/1111111111111111111111111111111111111111111111111111111111111111 HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 1\r\n\r\nb",
-            "POST /v2/transactions HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 0\r\n\r\n",
+    fn test_http_response_type_codec_err() {
+        let request_paths = vec![
+            "/v2/blocks/1111111111111111111111111111111111111111111111111111111111111111",
+            "/v2/transactions",
+            "/v2/neighbors",
+            "/v2/neighbors",
+            "/v2/neighbors",
         ];
-        for bad_content_length in bad_content_lengths {
-            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
-            let (preamble, offset) = http.read_preamble(bad_content_length.as_bytes()).unwrap();
-            let e = http.read_payload(&preamble, &bad_content_length.as_bytes()[offset..]);
-
-            assert!(e.is_err(), "{:?}", &e);
-            assert!(
-                e.as_ref()
-                    .unwrap_err()
-                    .to_string()
-                    .find("-length body for")
-                    .is_some(),
-                "{:?}",
-                &e
+        let bad_request_payloads = vec![
+            "HTTP/1.1 200 OK\r\nServer: stacks/v2.0\r\nX-Request-Id: 123\r\nContent-Type: application/json\r\nContent-length: 2\r\n\r\nab",
+            "HTTP/1.1 200 OK\r\nServer: stacks/v2.0\r\nX-Request-Id: 123\r\nContent-Type: application/json\r\nContent-length: 4\r\n\r\n\"ab\"",
+            "HTTP/1.1 200 OK\r\nServer: stacks/v2.0\r\nX-Request-Id: 123\r\nContent-Type: application/json\r\nContent-length: 1\r\n\r\n{",
+            "HTTP/1.1 200 OK\r\nServer: stacks/v2.0\r\nX-Request-Id: 123\r\nContent-Type: application/json\r\nContent-length: 1\r\n\r\na",
+            "HTTP/1.1 400 Bad Request\r\nServer: stacks/v2.0\r\nX-Request-Id: 123\r\nContent-Type: application/octet-stream\r\nContent-length: 2\r\n\r\n{}",
+        ];
+        let expected_bad_request_payload_errors = vec![
+            "Invalid content-type",
+            "Invalid txid:",
+            "Not enough bytes",
+            "Failed to parse",
+            "expected text/plain",
+        ];
+        for (test, (expected_error, request_path)) in bad_request_payloads.iter().zip(
+            expected_bad_request_payload_errors
+                .iter()
+                .zip(request_paths),
+        ) {
+            test_debug!(
+                "Expect failure:\n{}\nExpected error: '{}'",
+                test,
+                expected_error
             );
-        }
 
-        let bad_content_types = vec![
-            "POST /v2/transactions HTTP/1.1\r\nUser-Agent: stacks/2.0\r\nHost: bad:123\r\nContent-Length: 1\r\n\r\nb",
-        ];
-        for bad_content_type in bad_content_types {
             let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
-            let (preamble, offset) = http.read_preamble(bad_content_type.as_bytes()).unwrap();
-            let e = http.read_payload(&preamble, &bad_content_type.as_bytes()[offset..]);
+            http.begin_request(HttpVersion::Http11, request_path.to_string());
+
+            let (preamble, offset) = http.read_preamble(test.as_bytes()).unwrap();
+            let e = http.read_payload(&preamble, &test.as_bytes()[offset..]);
+            let errstr = format!("{:?}", &e);
             assert!(e.is_err());
-            assert!(e.unwrap_err().to_string().find("Content-Type").is_some());
+            assert!(
+                e.unwrap_err().to_string().find(expected_error).is_some(),
+                "{}",
+                errstr
+            );
         }
     }
 
-    #[test]
-    fn test_http_response_type_codec() {
-        let test_neighbors_info = RPCNeighborsInfo {
-            sample: vec![
-                RPCNeighbor {
-                    network_id: 1,
-                    peer_version: 2,
-                    addrbytes: PeerAddress([
-                        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b,
-                        0x0c, 0x0d, 0x0e, 0x0f,
-                    ]),
-                    port: 12345,
-                    public_key_hash: Hash160::from_bytes(
-                        &hex_bytes("1111111111111111111111111111111111111111").unwrap(),
-                    )
-                    .unwrap(),
-                    authenticated: true,
+    #[test]
+    fn test_http_transaction_batch_codec() {
+        let txs = vec![make_test_transaction(), make_test_transaction()];
+        let request = HttpRequestType::PostTransactionBatch(
+            HttpRequestMetadata::from_host(PeerHost::DNS("www.foo.com".to_string(), 80), None),
+            txs.clone(),
+            true,
+        );
+
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+            .unwrap();
+
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        match message {
+            StacksHttpMessage::Request(HttpRequestType::PostTransactionBatch(
+                _,
+                parsed_txs,
+                all_or_nothing,
+            )) => {
+                assert_eq!(parsed_txs, txs);
+                assert!(all_or_nothing);
+            }
+            _ => panic!("Did not parse a transaction batch request: {:?}", &message),
+        }
+
+        let batch_response = TransactionBatchResponse {
+            accepted: false,
+            all_or_nothing: false,
+            results: vec![
+                TransactionBatchResult {
+                    txid: Txid([0x1; 32]).to_hex(),
+                    status: TransactionBatchStatus::Accepted,
+                    error: None,
                 },
-                RPCNeighbor {
-                    network_id: 3,
-                    peer_version: 4,
-                    addrbytes: PeerAddress([
-                        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff,
-                        0x01, 0x02, 0x03, 0x04,
-                    ]),
-                    port: 23456,
-                    public_key_hash: Hash160::from_bytes(
-                        &hex_bytes("2222222222222222222222222222222222222222").unwrap(),
-                    )
-                    .unwrap(),
-                    authenticated: false,
+                TransactionBatchResult {
+                    txid: Txid([0x2; 32]).to_hex(),
+                    status: TransactionBatchStatus::Rejected,
+                    error: Some(json!({ "reason": "BadNonce" })),
                 },
             ],
-            inbound: vec![],
-            outbound: vec![],
         };
+        let response = HttpResponseType::TransactionBatch(
+            HttpResponseMetadata::new(HttpVersion::Http11, 123, None, true, None),
+            batch_response.clone(),
+        );
 
-        let privk = StacksPrivateKey::from_hex(
-            "6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001",
-        )
-        .unwrap();
-        let test_block_info = make_codec_test_block(5);
-        let test_microblock_info =
-            make_sample_microblock_stream(&privk, &test_block_info.block_hash());
-
-        let mut test_block_info_bytes = vec![];
-        test_block_info
-            .consensus_serialize(&mut test_block_info_bytes)
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.begin_request(HttpVersion::Http11, "/v2/transactions/batch".to_string());
+        http.write_message(&mut bytes, &StacksHttpMessage::Response(response))
             .unwrap();
 
-        let mut test_microblock_info_bytes = vec![];
-        test_microblock_info
-            .consensus_serialize(&mut test_microblock_info_bytes)
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let is_chunked = match preamble {
+            StacksHttpPreamble::Response(ref response_preamble) => response_preamble.is_chunked(),
+            StacksHttpPreamble::Request(_) => panic!("parsed a request"),
+        };
+        let (message, _) = if is_chunked {
+            let (msg_opt, len) = http
+                .stream_payload(&preamble, &mut &bytes[offset..])
+                .unwrap();
+            (msg_opt.unwrap().0, len)
+        } else {
+            http.read_payload(&preamble, &bytes[offset..]).unwrap()
+        };
+        match message {
+            StacksHttpMessage::Response(HttpResponseType::TransactionBatch(_, parsed)) => {
+                assert_eq!(parsed, batch_response);
+            }
+            _ => panic!("Did not parse a transaction batch response: {:?}", &message),
+        }
+    }
+
+    #[test]
+    fn test_http_transaction_admission_codec() {
+        let tx = make_test_transaction();
+        let request = HttpRequestType::PostTransaction(
+            HttpRequestMetadata::from_host(PeerHost::DNS("www.foo.com".to_string(), 80), None),
+            tx.clone(),
+            None,
+            true,
+        );
+        assert_eq!(
+            request.request_path(),
+            "/v2/transactions?diagnostics=1".to_string()
+        );
+
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
             .unwrap();
 
-        let tests = vec![
-            // length is known
-            (
-                HttpResponseType::Neighbors(
-                    HttpResponseMetadata::new(
-                        HttpVersion::Http11,
-                        123,
-                        Some(serde_json::to_string(&test_neighbors_info).unwrap().len() as u32),
-                        true,
-                        None,
-                    ),
-                    test_neighbors_info.clone(),
-                ),
-                "/v2/neighbors".to_string(),
-            ),
-            (
-                HttpResponseType::Block(
-                    HttpResponseMetadata::new(
-                        HttpVersion::Http11,
-                        123,
-                        Some(test_block_info_bytes.len() as u32),
-                        true,
-                        None,
-                    ),
-                    test_block_info.clone(),
-                ),
-                format!("/v2/blocks/{}", test_block_info.block_hash().to_hex()),
-            ),
-            (
-                HttpResponseType::Microblocks(
-                    HttpResponseMetadata::new(
-                        HttpVersion::Http11,
-                        123,
-                        Some(test_microblock_info_bytes.len() as u32),
-                        true,
-                        None,
-                    ),
-                    test_microblock_info.clone(),
-                ),
-                format!(
-                    "/v2/microblocks/{}",
-                    test_microblock_info[0].block_hash().to_hex()
-                ),
-            ),
-            (
-                HttpResponseType::TransactionID(
-                    HttpResponseMetadata::new(
-                        HttpVersion::Http11,
-                        123,
-                        Some((Txid([0x1; 32]).to_hex().len() + 2) as u32),
-                        true,
-                        None,
-                    ),
-                    Txid([0x1; 32]),
-                ),
-                "/v2/transactions".to_string(),
-            ),
-            // length is unknown
-            (
-                HttpResponseType::Neighbors(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, None, true, None),
-                    test_neighbors_info.clone(),
-                ),
-                "/v2/neighbors".to_string(),
-            ),
-            (
-                HttpResponseType::Block(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, None, true, None),
-                    test_block_info.clone(),
-                ),
-                format!("/v2/blocks/{}", test_block_info.block_hash().to_hex()),
-            ),
-            (
-                HttpResponseType::Microblocks(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, None, true, None),
-                    test_microblock_info.clone(),
-                ),
-                format!(
-                    "/v2/microblocks/{}",
-                    test_microblock_info[0].block_hash().to_hex()
-                ),
-            ),
-            (
-                HttpResponseType::TransactionID(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, None, true, None),
-                    Txid([0x1; 32]),
-                ),
-                "/v2/transactions".to_string(),
-            ),
-            // errors without error messages
-            (
-                HttpResponseType::BadRequest(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
-                    "".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
-            ),
-            (
-                HttpResponseType::Unauthorized(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
-                    "".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
-            ),
-            (
-                HttpResponseType::PaymentRequired(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
-                    "".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
-            ),
-            (
-                HttpResponseType::Forbidden(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
-                    "".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
-            ),
-            (
-                HttpResponseType::NotFound(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
-                    "".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
-            ),
-            (
-                HttpResponseType::ServerError(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
-                    "".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
-            ),
-            (
-                HttpResponseType::ServiceUnavailable(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
-                    "".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
-            ),
-            (
-                HttpResponseType::Error(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(0), true, None),
-                    502,
-                    "".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
-            ),
-            // errors with specific messages
-            (
-                HttpResponseType::BadRequest(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
-                    "foo".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        match message {
+            StacksHttpMessage::Request(HttpRequestType::PostTransaction(
+                _,
+                parsed_tx,
+                None,
+                diagnostics,
+            )) => {
+                assert_eq!(parsed_tx, tx);
+                assert!(diagnostics);
+            }
+            _ => panic!("Did not parse a transaction request: {:?}", &message),
+        }
+
+        let admission = TransactionAdmissionResponse {
+            txid: format!("0x{}", &tx.txid()),
+            accepted: true,
+            already_known: false,
+            rejection: None,
+            tx_size: tx.tx_len(),
+            tx_fee: tx.get_tx_fee(),
+            fee_rate: Some(1.5),
+            fee_rate_percentile: None,
+            nonce: TransactionNonceDiagnostics {
+                nonce: 1,
+                expected_nonce: Some(0),
+                pending_nonces: vec![0],
+                status: Some(TransactionNonceStatus::Chained),
+            },
+            checks: vec![TransactionAdmissionCheck {
+                name: "admission_policy".to_string(),
+                status: TransactionAdmissionCheckStatus::Passed,
+            }],
+        };
+
+        // a post with diagnostics gets the diagnostics back, and one without gets the txid
+        let responses = vec![
+            HttpResponseType::TransactionAdmission(
+                HttpResponseMetadata::new(HttpVersion::Http11, 123, None, true, None),
+                admission,
             ),
-            (
-                HttpResponseType::Unauthorized(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
-                    "foo".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
+            HttpResponseType::TransactionID(
+                HttpResponseMetadata::new(HttpVersion::Http11, 123, None, true, None),
+                tx.txid(),
             ),
-            (
-                HttpResponseType::PaymentRequired(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
-                    "foo".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
+        ];
+        for response in responses.into_iter() {
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let mut bytes = vec![];
+            http.begin_request(HttpVersion::Http11, "/v2/transactions".to_string());
+            http.write_message(&mut bytes, &StacksHttpMessage::Response(response.clone()))
+                .unwrap();
+
+            let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+            let is_chunked = match preamble {
+                StacksHttpPreamble::Response(ref response_preamble) => {
+                    response_preamble.is_chunked()
+                }
+                StacksHttpPreamble::Request(_) => panic!("parsed a request"),
+            };
+            let (message, _) = if is_chunked {
+                let (msg_opt, len) = http
+                    .stream_payload(&preamble, &mut &bytes[offset..])
+                    .unwrap();
+                (msg_opt.unwrap().0, len)
+            } else {
+                http.read_payload(&preamble, &bytes[offset..]).unwrap()
+            };
+            match (message, response) {
+                (
+                    StacksHttpMessage::Response(HttpResponseType::TransactionAdmission(_, parsed)),
+                    HttpResponseType::TransactionAdmission(_, expected),
+                ) => assert_eq!(parsed, expected),
+                (
+                    StacksHttpMessage::Response(HttpResponseType::TransactionID(_, parsed)),
+                    HttpResponseType::TransactionID(_, expected),
+                ) => assert_eq!(parsed, expected),
+                (message, _) => panic!("Did not parse a transaction response: {:?}", &message),
+            }
+        }
+    }
+
+    #[test]
+    fn test_http_clarity_data_proof_codec() {
+        let contract = "ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R.hello-world";
+        let keys = vec![
+            ClarityDataKey::DataVar {
+                contract: contract.into(),
+                name: "bar".into(),
+            },
+            ClarityDataKey::MapEntry {
+                contract: contract.into(),
+                name: "unit-map".into(),
+                key: ClaritySerializable::serialize(&Value::UInt(1)),
+            },
+            ClarityDataKey::FtBalance {
+                contract: contract.into(),
+                name: "hello-token".into(),
+                principal: "ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R".into(),
+            },
+            ClarityDataKey::StxBalance {
+                principal: "ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R".into(),
+            },
+        ];
+
+        for key in keys.into_iter() {
+            let request = HttpRequestType::GetClarityDataProof(
+                HttpRequestMetadata::from_host(PeerHost::DNS("www.foo.com".to_string(), 80), None),
+                key.clone(),
+                TipRequest::UseLatestAnchoredTip,
+            );
+
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let mut bytes = vec![];
+            http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+                .unwrap();
+
+            let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+            let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+            match message {
+                StacksHttpMessage::Request(HttpRequestType::GetClarityDataProof(
+                    _,
+                    parsed_key,
+                    tip_req,
+                )) => {
+                    assert_eq!(parsed_key, key);
+                    assert_eq!(tip_req, TipRequest::UseLatestAnchoredTip);
+                }
+                _ => panic!("Did not parse a Clarity data proof request: {:?}", &message),
+            }
+        }
+
+        // keys that do not name valid Clarity data are rejected
+        let bad_key = ClarityDataKey::DataVar {
+            contract: "not-a-contract".into(),
+            name: "bar".into(),
+        };
+        assert!(bad_key.to_clarity_key().is_err());
+        let request = HttpRequestType::GetClarityDataProof(
+            HttpRequestMetadata::from_host(PeerHost::DNS("www.foo.com".to_string(), 80), None),
+            bad_key,
+            TipRequest::UseLatestAnchoredTip,
+        );
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+            .unwrap();
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        assert!(http.read_payload(&preamble, &bytes[offset..]).is_err());
+    }
+
+    #[test]
+    fn test_http_transaction_proof_codec() {
+        let index_block_hash = StacksBlockId([0x11; 32]);
+        let txid = Txid([0x22; 32]);
+        let request = HttpRequestType::GetTransactionProof {
+            metadata: HttpRequestMetadata::from_host(
+                PeerHost::DNS("www.foo.com".to_string(), 80),
+                None,
             ),
-            (
-                HttpResponseType::Forbidden(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
-                    "foo".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
+            index_block_hash: index_block_hash.clone(),
+            txid: txid.clone(),
+        };
+        assert_eq!(
+            request.request_path(),
+            format!("/v2/transactions/proof/{}/{}", &index_block_hash, &txid)
+        );
+
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+            .unwrap();
+
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        match message {
+            StacksHttpMessage::Request(HttpRequestType::GetTransactionProof {
+                index_block_hash: parsed_index_block_hash,
+                txid: parsed_txid,
+                ..
+            }) => {
+                assert_eq!(parsed_index_block_hash, index_block_hash);
+                assert_eq!(parsed_txid, txid);
+            }
+            _ => panic!("Did not parse a transaction proof request: {:?}", &message),
+        }
+    }
+
+    #[test]
+    fn test_http_deposit_status_codec() {
+        let l1_txid = Txid([0x33; 32]);
+        let request = HttpRequestType::GetDepositStatus {
+            metadata: HttpRequestMetadata::from_host(
+                PeerHost::DNS("www.foo.com".to_string(), 80),
+                None,
             ),
-            (
-                HttpResponseType::NotFound(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
-                    "foo".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
+            l1_txid: l1_txid.clone(),
+        };
+        assert_eq!(request.request_path(), format!("/v2/deposits/{}", &l1_txid));
+
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+            .unwrap();
+
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        match message {
+            StacksHttpMessage::Request(HttpRequestType::GetDepositStatus {
+                l1_txid: parsed_l1_txid,
+                ..
+            }) => {
+                assert_eq!(parsed_l1_txid, l1_txid);
+            }
+            _ => panic!("Did not parse a deposit status request: {:?}", &message),
+        }
+
+        // a 0x-prefixed txid is accepted too
+        assert!(PATH_GET_DEPOSIT_STATUS.is_match(&format!("/v2/deposits/0x{}", &l1_txid)));
+        assert!(!PATH_GET_DEPOSIT_STATUS.is_match("/v2/deposits/0x1234"));
+    }
+
+    #[test]
+    fn test_http_block_costs_codec() {
+        let index_block_hash = StacksBlockId([0x44; 32]);
+        let request = HttpRequestType::GetBlockCosts {
+            metadata: HttpRequestMetadata::from_host(
+                PeerHost::DNS("www.foo.com".to_string(), 80),
+                None,
             ),
-            (
-                HttpResponseType::ServerError(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
-                    "foo".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
+            index_block_hash: index_block_hash.clone(),
+        };
+        assert_eq!(
+            request.request_path(),
+            format!("/v2/blocks/{}/costs", &index_block_hash)
+        );
+
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+            .unwrap();
+
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        match message {
+            StacksHttpMessage::Request(HttpRequestType::GetBlockCosts {
+                index_block_hash: parsed_index_block_hash,
+                ..
+            }) => {
+                assert_eq!(parsed_index_block_hash, index_block_hash);
+            }
+            _ => panic!("Did not parse a block costs request: {:?}", &message),
+        }
+
+        // doesn't shadow the block download path
+        assert!(!PATH_GET_BLOCK_COSTS.is_match(&format!("/v2/blocks/{}", &index_block_hash)));
+        assert!(!PATH_GETBLOCK.is_match(&format!("/v2/blocks/{}/costs", &index_block_hash)));
+    }
+
+    #[test]
+    fn test_http_block_header_codec() {
+        let index_block_hash = StacksBlockId([0x45; 32]);
+        let request = HttpRequestType::GetBlockHeader {
+            metadata: HttpRequestMetadata::from_host(
+                PeerHost::DNS("www.foo.com".to_string(), 80),
+                None,
             ),
-            (
-                HttpResponseType::ServiceUnavailable(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
-                    "foo".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
+            index_block_hash: index_block_hash.clone(),
+        };
+        assert_eq!(
+            request.request_path(),
+            format!("/v2/blocks/{}/header", &index_block_hash)
+        );
+
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+            .unwrap();
+
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        match message {
+            StacksHttpMessage::Request(HttpRequestType::GetBlockHeader {
+                index_block_hash: parsed_index_block_hash,
+                ..
+            }) => {
+                assert_eq!(parsed_index_block_hash, index_block_hash);
+            }
+            _ => panic!("Did not parse a block header request: {:?}", &message),
+        }
+
+        // doesn't shadow the block download path
+        assert!(!PATH_GET_BLOCK_HEADER.is_match(&format!("/v2/blocks/{}", &index_block_hash)));
+        assert!(!PATH_GETBLOCK.is_match(&format!("/v2/blocks/{}/header", &index_block_hash)));
+    }
+
+    #[test]
+    fn test_http_block_state_diff_codec() {
+        let index_block_hash = StacksBlockId([0x46; 32]);
+        let request = HttpRequestType::GetBlockStateDiff {
+            metadata: HttpRequestMetadata::from_host(
+                PeerHost::DNS("www.foo.com".to_string(), 80),
+                None,
             ),
-            (
-                HttpResponseType::Error(
-                    HttpResponseMetadata::new(HttpVersion::Http11, 123, Some(3), true, None),
-                    502,
-                    "foo".to_string(),
-                ),
-                "/v2/neighbors".to_string(),
+            index_block_hash: index_block_hash.clone(),
+        };
+        assert_eq!(
+            request.request_path(),
+            format!("/v2/blocks/{}/state-diff", &index_block_hash)
+        );
+
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+            .unwrap();
+
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        match message {
+            StacksHttpMessage::Request(HttpRequestType::GetBlockStateDiff {
+                index_block_hash: parsed_index_block_hash,
+                ..
+            }) => {
+                assert_eq!(parsed_index_block_hash, index_block_hash);
+            }
+            _ => panic!("Did not parse a block state diff request: {:?}", &message),
+        }
+
+        // doesn't shadow the other per-block paths
+        assert!(!PATH_GET_BLOCK_STATE_DIFF.is_match(&format!("/v2/blocks/{}", &index_block_hash)));
+        assert!(!PATH_GETBLOCK.is_match(&format!("/v2/blocks/{}/state-diff", &index_block_hash)));
+        assert!(!PATH_GET_BLOCK_HEADER
+            .is_match(&format!("/v2/blocks/{}/state-diff", &index_block_hash)));
+    }
+
+    #[test]
+    fn test_http_transaction_receipt_codec() {
+        let txid = Txid([0x55; 32]);
+        let request = HttpRequestType::GetTransactionReceipt {
+            metadata: HttpRequestMetadata::from_host(
+                PeerHost::DNS("www.foo.com".to_string(), 80),
+                None,
             ),
-        ];
+            txid: txid.clone(),
+        };
+        assert_eq!(
+            request.request_path(),
+            format!("/v2/transactions/{}/receipt", &txid)
+        );
 
-        let expected_http_preambles = vec![
-            // length is known
-            HttpResponsePreamble::new(
-                200,
-                "OK".to_string(),
-                Some(serde_json::to_string(&test_neighbors_info).unwrap().len() as u32),
-                HttpContentType::JSON,
-                true,
-                123,
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+            .unwrap();
+
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        match message {
+            StacksHttpMessage::Request(HttpRequestType::GetTransactionReceipt {
+                txid: parsed_txid,
+                ..
+            }) => {
+                assert_eq!(parsed_txid, txid);
+            }
+            _ => panic!(
+                "Did not parse a transaction receipt request: {:?}",
+                &message
             ),
-            HttpResponsePreamble::new(
-                200,
-                "OK".to_string(),
-                Some(test_block_info_bytes.len() as u32),
-                HttpContentType::Bytes,
-                true,
-                123,
+        }
+
+        assert!(
+            PATH_GET_TRANSACTION_RECEIPT.is_match(&format!("/v2/transactions/0x{}/receipt", &txid))
+        );
+        assert!(!PATH_GET_TRANSACTION_RECEIPT.is_match(&format!("/v2/transactions/{}", &txid)));
+    }
+
+    #[test]
+    fn test_http_nonce_gaps_codec() {
+        let address =
+            StacksAddress::from_string("ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2").unwrap();
+        let tip = StacksBlockId([0x66; 32]);
+        let request = HttpRequestType::GetNonceGaps {
+            metadata: HttpRequestMetadata::from_host(
+                PeerHost::DNS("www.foo.com".to_string(), 80),
+                None,
             ),
-            HttpResponsePreamble::new(
-                200,
-                "OK".to_string(),
-                Some(test_microblock_info_bytes.len() as u32),
-                HttpContentType::Bytes,
-                true,
+            address: address.clone(),
+            tip_req: TipRequest::SpecificTip(tip.clone()),
+        };
+        assert_eq!(
+            request.request_path(),
+            format!("/v2/accounts/{}/nonce-gaps?tip={}", &address, &tip)
+        );
+
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+            .unwrap();
+
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        match message {
+            StacksHttpMessage::Request(HttpRequestType::GetNonceGaps {
+                address: parsed_address,
+                tip_req: parsed_tip_req,
+                ..
+            }) => {
+                assert_eq!(parsed_address, address);
+                assert_eq!(parsed_tip_req, TipRequest::SpecificTip(tip));
+            }
+            _ => panic!("Did not parse a nonce gaps request: {:?}", &message),
+        }
+
+        // doesn't shadow the account path, and contracts have no pending transactions
+        assert!(!PATH_GET_ACCOUNT.is_match(&format!("/v2/accounts/{}/nonce-gaps", &address)));
+        assert!(!PATH_GET_NONCE_GAPS.is_match(&format!("/v2/accounts/{}.foo/nonce-gaps", &address)));
+    }
+
+    #[test]
+    fn test_http_subnet_status_codec() {
+        let request = HttpRequestType::GetSubnetStatus(HttpRequestMetadata::from_host(
+            PeerHost::DNS("www.foo.com".to_string(), 80),
+            None,
+        ));
+        assert_eq!(request.request_path(), "/v2/subnet/status");
+
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+            .unwrap();
+
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        match message {
+            StacksHttpMessage::Request(HttpRequestType::GetSubnetStatus(..)) => {}
+            _ => panic!("Did not parse a subnet status request: {:?}", &message),
+        }
+
+        let response = SubnetStatusResponse {
+            stacks_tip_height: 12,
+            stacks_tip: BlockHeaderHash([0x11; 32]),
+            stacks_tip_consensus_hash: ConsensusHash([0x22; 20]),
+            anchor_l1_block_hash: BurnchainHeaderHash([0x33; 32]),
+            anchor_l1_block_height: 100,
+            l1_tip_height: 103,
+            l1_blocks_since_last_commit: 3,
+            pending_withdrawals: 4,
+            miner_status: SubnetMinerStatus::NotMining,
+            last_mined_block_height: None,
+            event_signing_public_key: None,
+            l1_contract_status: L1ContractStatus::Incompatible,
+            l1_contract_interface_version: Some(2),
+            startup_state: NodeStartupState::Ready,
+            l1_node_tip_height: None,
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["miner_status"], "not_mining");
+        assert_eq!(json["l1_contract_status"], "incompatible");
+        assert_eq!(json["l1_contract_interface_version"], 2);
+        assert!(json.get("last_mined_block_height").is_none());
+        assert!(json.get("event_signing_public_key").is_none());
+        assert_eq!(json["startup_state"], "ready");
+        assert!(json.get("l1_node_tip_height").is_none());
+        let parsed: SubnetStatusResponse = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, response);
+    }
+
+    #[test]
+    fn test_http_block_proposal_validation_codec() {
+        let block = make_codec_test_block(5);
+        let proposal = Proposal {
+            parent_block_hash: BlockHeaderHash([0x11; 32]),
+            parent_consensus_hash: ConsensusHash([0x22; 20]),
+            block: block.clone(),
+            microblocks_confirmed: vec![],
+            burn_tip: BurnchainHeaderHash([0x33; 32]),
+            burn_tip_height: 100,
+            is_mainnet: false,
+            microblock_pubkey_hash: Hash160([0x44; 20]),
+            total_burn: 10,
+            withdrawal_leaves: None,
+            proposer_signature: None,
+        };
+        let request = HttpRequestType::ValidateBlockProposal(
+            HttpRequestMetadata::from_host(PeerHost::DNS("www.foo.com".to_string(), 80), None),
+            proposal.clone(),
+        );
+        assert_eq!(request.request_path(), "/v2/block_proposal/validate");
+
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+            .unwrap();
+
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        match message {
+            StacksHttpMessage::Request(HttpRequestType::ValidateBlockProposal(
+                _,
+                parsed_proposal,
+            )) => {
+                assert_eq!(parsed_proposal, proposal);
+            }
+            _ => panic!("Did not parse a block proposal validation: {:?}", &message),
+        }
+
+        // doesn't shadow the signing endpoint
+        assert!(!PATH_POST_BLOCK_PROPOSAL.is_match("/v2/block_proposal/validate"));
+
+        let validation = BlockProposalValidationResponse {
+            valid: false,
+            reason: Some("Transaction failed".to_string()),
+            block_hash: block.block_hash(),
+            computed_block_hash: Some(BlockHeaderHash([0x55; 32])),
+            total_cost: ExecutionCost {
+                write_length: 1,
+                write_count: 2,
+                read_length: 3,
+                read_count: 4,
+                runtime: 5,
+            },
+            block_size: 123,
+            tx_results: vec![
+                BlockProposalTxResult {
+                    txid: block.txs[0].txid(),
+                    result: Some("0703".to_string()),
+                    error: None,
+                    execution_cost: ExecutionCost::zero(),
+                },
+                BlockProposalTxResult {
+                    txid: block.txs[1].txid(),
+                    result: None,
+                    error: Some("Transaction failed".to_string()),
+                    execution_cost: ExecutionCost::zero(),
+                },
+            ],
+        };
+        let response = HttpResponseType::BlockProposalValidation(
+            HttpResponseMetadata::new(
+                HttpVersion::Http11,
                 123,
-            ),
-            HttpResponsePreamble::new(
-                200,
-                "OK".to_string(),
-                Some((Txid([0x1; 32]).to_hex().len() + 2) as u32),
-                HttpContentType::JSON,
+                Some(serde_json::to_string(&validation).unwrap().len() as u32),
                 true,
-                123,
+                None,
             ),
-            // length is unknown
-            HttpResponsePreamble::new(
-                200,
-                "OK".to_string(),
+            validation,
+        );
+
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.begin_request(
+            HttpVersion::Http11,
+            PATH_STR_POST_VALIDATE_BLOCK_PROPOSAL.to_string(),
+        );
+        http.write_message(&mut bytes, &StacksHttpMessage::Response(response.clone()))
+            .unwrap();
+
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        assert_eq!(message, StacksHttpMessage::Response(response));
+    }
+
+    #[test]
+    fn test_http_divergence_snapshot_codec() {
+        for auth_token in [Some("admin-secret".to_string()), None] {
+            let request = HttpRequestType::PostDivergenceSnapshot {
+                metadata: HttpRequestMetadata::from_host(
+                    PeerHost::DNS("www.foo.com".to_string(), 80),
+                    None,
+                ),
+                auth_token: auth_token.clone(),
+            };
+            assert_eq!(request.request_path(), "/v2/admin/divergence_snapshot");
+
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let mut bytes = vec![];
+            http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+                .unwrap();
+
+            let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+            let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+            match message {
+                StacksHttpMessage::Request(HttpRequestType::PostDivergenceSnapshot {
+                    auth_token: parsed_auth_token,
+                    ..
+                }) => {
+                    assert_eq!(parsed_auth_token, auth_token);
+                }
+                _ => panic!(
+                    "Did not parse a divergence snapshot request: {:?}",
+                    &message
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_http_delete_mempool_tx_codec() {
+        for reason in [Some("stuck nonce & fee".to_string()), None] {
+            let request = HttpRequestType::DeleteMemPoolTx {
+                metadata: HttpRequestMetadata::from_host(
+                    PeerHost::DNS("www.foo.com".to_string(), 80),
+                    None,
+                ),
+                txid: Txid([0x1; 32]),
+                reason: reason.clone(),
+                auth_token: Some("admin-secret".to_string()),
+            };
+
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let mut bytes = vec![];
+            http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+                .unwrap();
+
+            let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+            let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+            match message {
+                StacksHttpMessage::Request(HttpRequestType::DeleteMemPoolTx {
+                    txid,
+                    reason: parsed_reason,
+                    auth_token,
+                    ..
+                }) => {
+                    assert_eq!(txid, Txid([0x1; 32]));
+                    assert_eq!(parsed_reason, reason);
+                    assert_eq!(auth_token, Some("admin-secret".to_string()));
+                }
+                _ => panic!("Did not parse a mempool eviction request: {:?}", &message),
+            }
+        }
+    }
+
+    #[test]
+    fn test_http_map_entries_codec() {
+        let tip = StacksBlockId([0x2; 32]);
+        let request = HttpRequestType::GetMapEntries {
+            metadata: HttpRequestMetadata::from_host(
+                PeerHost::DNS("www.foo.com".to_string(), 80),
                 None,
-                HttpContentType::JSON,
-                true,
-                123,
             ),
-            HttpResponsePreamble::new(
-                200,
-                "OK".to_string(),
-                None,
-                HttpContentType::Bytes,
-                true,
-                123,
+            contract_addr: StacksAddress::from_string("ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2")
+                .unwrap(),
+            contract_name: "hello-world".try_into().unwrap(),
+            map_name: "unit-map".try_into().unwrap(),
+            after: Some(Value::UInt(7)),
+            limit: 20,
+            tip_req: TipRequest::SpecificTip(tip.clone()),
+        };
+        assert_eq!(
+            request.request_path(),
+            format!(
+                "/v2/map_entries/ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2/hello-world/unit-map?tip={}&after=0x{}&limit=20",
+                &tip,
+                ClaritySerializable::serialize(&Value::UInt(7))
+            )
+        );
+
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+            .unwrap();
+
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        match message {
+            StacksHttpMessage::Request(HttpRequestType::GetMapEntries {
+                contract_name,
+                map_name,
+                after,
+                limit,
+                tip_req,
+                ..
+            }) => {
+                assert_eq!(contract_name.as_str(), "hello-world");
+                assert_eq!(map_name.as_str(), "unit-map");
+                assert_eq!(after, Some(Value::UInt(7)));
+                assert_eq!(limit, 20);
+                assert_eq!(tip_req, TipRequest::SpecificTip(tip));
+            }
+            _ => panic!("Did not parse a map entries request: {:?}", &message),
+        }
+
+        // limits outside of 1..=MAP_ENTRIES_MAX_LIMIT are rejected
+        for limit in [0, MAP_ENTRIES_MAX_LIMIT + 1] {
+            let request = format!(
+                "GET /v2/map_entries/ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2/hello-world/unit-map?limit={} HTTP/1.1\r\nHost: www.foo.com\r\nConnection: close\r\n\r\n",
+                limit
+            );
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let (preamble, offset) = http.read_preamble(request.as_bytes()).unwrap();
+            assert!(http
+                .read_payload(&preamble, &request.as_bytes()[offset..])
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_http_mempool_listing_codec() {
+        let filter = MemPoolListFilter {
+            origin: Some(
+                StacksAddress::from_string("ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2").unwrap(),
             ),
-            HttpResponsePreamble::new(
-                200,
-                "OK".to_string(),
+            min_fee: Some(10),
+            max_fee: Some(1000),
+            payload_type: Some("ContractCall".to_string()),
+        };
+        let cursor = MemPoolListCursor {
+            tx_fee: 500,
+            txid: Txid([0x1; 32]),
+        };
+        let request = HttpRequestType::GetMemPoolTxs {
+            metadata: HttpRequestMetadata::from_host(
+                PeerHost::DNS("www.foo.com".to_string(), 80),
                 None,
-                HttpContentType::Bytes,
-                true,
-                123,
             ),
-            HttpResponsePreamble::new(
-                200,
-                "OK".to_string(),
+            filter: filter.clone(),
+            cursor: Some(cursor.clone()),
+            limit: 20,
+        };
+        assert_eq!(
+            request.request_path(),
+            format!(
+                "/v2/mempool?origin=ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2&min_fee=10&max_fee=1000&payload_type=ContractCall&cursor=500-{}&limit=20",
+                &Txid([0x1; 32])
+            )
+        );
+
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+            .unwrap();
+
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        match message {
+            StacksHttpMessage::Request(HttpRequestType::GetMemPoolTxs {
+                filter: parsed_filter,
+                cursor: parsed_cursor,
+                limit,
+                ..
+            }) => {
+                assert_eq!(parsed_filter, filter);
+                assert_eq!(parsed_cursor, Some(cursor));
+                assert_eq!(limit, 20);
+            }
+            _ => panic!("Did not parse a mempool listing request: {:?}", &message),
+        }
+
+        let request = HttpRequestType::GetMemPoolSummary {
+            metadata: HttpRequestMetadata::from_host(
+                PeerHost::DNS("www.foo.com".to_string(), 80),
                 None,
-                HttpContentType::JSON,
-                true,
-                123,
             ),
-            // errors
-            HttpResponsePreamble::new_error(400, 123, None),
-            HttpResponsePreamble::new_error(401, 123, None),
-            HttpResponsePreamble::new_error(402, 123, None),
-            HttpResponsePreamble::new_error(403, 123, None),
-            HttpResponsePreamble::new_error(404, 123, None),
-            HttpResponsePreamble::new_error(500, 123, None),
-            HttpResponsePreamble::new_error(503, 123, None),
-            // generic error
-            HttpResponsePreamble::new_error(502, 123, None),
-            // errors with messages
-            HttpResponsePreamble::new_error(400, 123, Some("foo".to_string())),
-            HttpResponsePreamble::new_error(401, 123, Some("foo".to_string())),
-            HttpResponsePreamble::new_error(402, 123, Some("foo".to_string())),
-            HttpResponsePreamble::new_error(403, 123, Some("foo".to_string())),
-            HttpResponsePreamble::new_error(404, 123, Some("foo".to_string())),
-            HttpResponsePreamble::new_error(500, 123, Some("foo".to_string())),
-            HttpResponsePreamble::new_error(503, 123, Some("foo".to_string())),
-            HttpResponsePreamble::new_error(502, 123, Some("foo".to_string())),
-        ];
+            filter: MemPoolListFilter::default(),
+        };
+        assert_eq!(request.request_path(), "/v2/mempool/summary");
+
+        // invalid filters and page sizes are rejected
+        for query in [
+            "payload_type=Transfer",
+            "min_fee=-1",
+            "limit=0",
+            "limit=201",
+            "cursor=500",
+        ] {
+            assert!(HttpRequestType::parse_get_mempool_txs(
+                &mut http,
+                &HttpRequestPreamble::new(
+                    HttpVersion::Http11,
+                    "GET".to_string(),
+                    format!("/v2/mempool?{}", query),
+                    "www.foo.com".to_string(),
+                    80,
+                    true,
+                ),
+                &PATH_GET_MEMPOOL_TXS.captures("/v2/mempool").unwrap(),
+                Some(query),
+                &mut &[][..],
+            )
+            .is_err());
+        }
 
-        let expected_http_bodies = vec![
-            // with content-length
-            serde_json::to_string(&test_neighbors_info)
-                .unwrap()
-                .as_bytes()
-                .to_vec(),
-            test_block_info_bytes.clone(),
-            test_microblock_info_bytes.clone(),
-            Txid([0x1; 32]).to_hex().as_bytes().to_vec(),
-            // with transfer-encoding: chunked
-            serde_json::to_string(&test_neighbors_info)
-                .unwrap()
-                .as_bytes()
-                .to_vec(),
-            test_block_info_bytes,
-            test_microblock_info_bytes,
-            Txid([0x1; 32]).to_hex().as_bytes().to_vec(),
-            // errors
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            // errors with messages
-            "foo".as_bytes().to_vec(),
-            "foo".as_bytes().to_vec(),
-            "foo".as_bytes().to_vec(),
-            "foo".as_bytes().to_vec(),
-            "foo".as_bytes().to_vec(),
-            "foo".as_bytes().to_vec(),
-            "foo".as_bytes().to_vec(),
-            "foo".as_bytes().to_vec(),
+        // doesn't shadow the eviction path
+        assert!(!PATH_DELETE_MEMPOOL_TX.is_match("/v2/mempool/summary"));
+    }
+
+    #[test]
+    fn test_http_peer_ban_codec() {
+        let md = HttpRequestMetadata::from_host(PeerHost::DNS("www.foo.com".to_string(), 80), None);
+        let requests = vec![
+            HttpRequestType::PostPeerBan {
+                metadata: md.clone(),
+                target: PeerBanTarget::from_address_range("10.0.0.0/8").unwrap(),
+                duration_secs: Some(3600),
+                reason: Some("spam".to_string()),
+                auth_token: Some("admin-secret".to_string()),
+            },
+            HttpRequestType::PostPeerBan {
+                metadata: md.clone(),
+                target: PeerBanTarget::PublicKeyHash(Hash160([0x11; 20])),
+                duration_secs: None,
+                reason: None,
+                auth_token: Some("admin-secret".to_string()),
+            },
+            HttpRequestType::DeletePeerBan {
+                metadata: md.clone(),
+                ban_id: 12,
+                auth_token: Some("admin-secret".to_string()),
+            },
         ];
 
-        for ((test, request_path), (expected_http_preamble, _expected_http_body)) in
-            tests.iter().zip(
-                expected_http_preambles
-                    .iter()
-                    .zip(expected_http_bodies.iter()),
-            )
-        {
+        for request in requests.into_iter() {
             let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
             let mut bytes = vec![];
-            test_debug!("write body:\n{:?}\n", test);
-
-            http.begin_request(HttpVersion::Http11, request_path.to_string());
-            http.write_message(&mut bytes, &StacksHttpMessage::Response((*test).clone()))
+            http.write_message(&mut bytes, &StacksHttpMessage::Request(request.clone()))
                 .unwrap();
 
-            let (mut preamble, offset) = match http.read_preamble(&bytes) {
-                Ok((p, o)) => (p, o),
-                Err(e) => {
-                    test_debug!("first 4096 bytes:\n{:?}\n", &bytes[0..].to_vec());
-                    test_debug!("error: {:?}", &e);
-                    assert!(false);
-                    unreachable!();
+            let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+            let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+            match message {
+                StacksHttpMessage::Request(mut parsed) => {
+                    // the metadata is rebuilt from the preamble
+                    *parsed.metadata_mut() = md.clone();
+                    assert_eq!(parsed, request);
                 }
-            };
+                _ => panic!("Did not parse a request: {:?}", &message),
+            }
+        }
 
-            test_debug!("read preamble of {} bytes\n{:?}\n", offset, preamble);
+        assert_eq!(
+            HttpRequestType::DeletePeerBan {
+                metadata: md,
+                ban_id: 12,
+                auth_token: None,
+            }
+            .request_path(),
+            "/v2/admin/bans/12"
+        );
+    }
 
-            test_debug!("read http body\n{:?}\n", &bytes[offset..].to_vec());
+    #[test]
+    fn test_http_transaction_sponsorship_codec() {
+        let offer = make_test_transaction();
+        let request = HttpRequestType::PostTransactionSponsorship(
+            HttpRequestMetadata::from_host(PeerHost::DNS("www.foo.com".to_string(), 80), None),
+            Txid([0x1; 32]),
+            offer.clone(),
+        );
 
-            let (message, _total_len) = if expected_http_preamble.is_chunked() {
-                let (msg_opt, len) = http
-                    .stream_payload(&preamble, &mut &bytes[offset..])
-                    .unwrap();
-                (msg_opt.unwrap().0, len)
-            } else {
-                http.read_payload(&preamble, &bytes[offset..]).unwrap()
-            };
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+            .unwrap();
 
-            test_debug!("got message\n{:?}\n", &message);
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        match message {
+            StacksHttpMessage::Request(HttpRequestType::PostTransactionSponsorship(
+                _,
+                sponsored_txid,
+                parsed_offer,
+            )) => {
+                assert_eq!(sponsored_txid, Txid([0x1; 32]));
+                assert_eq!(parsed_offer, offer);
+            }
+            _ => panic!(
+                "Did not parse a transaction sponsorship request: {:?}",
+                &message
+            ),
+        }
+    }
 
-            // check everything in the parsed preamble except for the extra headers
-            match preamble {
-                StacksHttpPreamble::Response(ref mut req) => {
-                    assert_eq!(req.headers.len(), 5);
-                    assert!(req.headers.get("access-control-allow-headers").is_some());
-                    assert!(req.headers.get("access-control-allow-methods").is_some());
-                    assert!(req.headers.get("access-control-allow-origin").is_some());
-                    assert!(req.headers.get("server").is_some());
-                    assert!(req.headers.get("date").is_some());
-                    req.headers.clear();
-                }
-                StacksHttpPreamble::Request(_) => {
-                    panic!("parsed a request");
-                }
-            }
+    #[test]
+    fn test_http_call_read_only_cost_budget_codec() {
+        let cost_budget = ExecutionCost {
+            write_length: 0,
+            write_count: 0,
+            read_length: 200000,
+            read_count: 60,
+            runtime: 2_000_000_000,
+        };
 
-            assert_eq!(
-                preamble,
-                StacksHttpPreamble::Response((*expected_http_preamble).clone())
+        for budget_opt in [None, Some(cost_budget)] {
+            let request = HttpRequestType::CallReadOnlyFunction(
+                HttpRequestMetadata::from_host(PeerHost::DNS("www.foo.com".to_string(), 80), None),
+                StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R").unwrap(),
+                "hello-world".try_into().unwrap(),
+                StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                    .unwrap()
+                    .to_account_principal(),
+                "ro-test".try_into().unwrap(),
+                vec![Value::UInt(1)],
+                budget_opt.clone(),
+                TipRequest::UseLatestAnchoredTip,
             );
-            assert_eq!(message, StacksHttpMessage::Response((*test).clone()));
-            assert_eq!(http.num_pending(), 0);
+
+            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+            let mut bytes = vec![];
+            http.write_message(&mut bytes, &StacksHttpMessage::Request(request))
+                .unwrap();
+
+            let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+            let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+            match message {
+                StacksHttpMessage::Request(HttpRequestType::CallReadOnlyFunction(
+                    _,
+                    _,
+                    _,
+                    _,
+                    _,
+                    args,
+                    parsed_budget,
+                    _,
+                )) => {
+                    assert_eq!(args, vec![Value::UInt(1)]);
+                    assert_eq!(parsed_budget, budget_opt);
+                }
+                _ => panic!("Did not parse a read-only call request: {:?}", &message),
+            }
         }
     }
 
     #[test]
-    fn test_http_response_type_codec_err() {
-        let request_paths = vec![
-            "/v2/blocks/1111111111111111111111111111111111111111111111111111111111111111",
-            "/v2/transactions",
-            "/v2/neighbors",
-            "/v2/neighbors",
-            "/v2/neighbors",
-        ];
-        let bad_request_payloads = vec![
-            "HTTP/1.1 200 OK\r\nServer: stacks/v2.0\r\nX-Request-Id: 123\r\nContent-Type: application/json\r\nContent-length: 2\r\n\r\nab",
-            "HTTP/1.1 200 OK\r\nServer: stacks/v2.0\r\nX-Request-Id: 123\r\nContent-Type: application/json\r\nContent-length: 4\r\n\r\n\"ab\"",
-            "HTTP/1.1 200 OK\r\nServer: stacks/v2.0\r\nX-Request-Id: 123\r\nContent-Type: application/json\r\nContent-length: 1\r\n\r\n{",
-            "HTTP/1.1 200 OK\r\nServer: stacks/v2.0\r\nX-Request-Id: 123\r\nContent-Type: application/json\r\nContent-length: 1\r\n\r\na",
-            "HTTP/1.1 400 Bad Request\r\nServer: stacks/v2.0\r\nX-Request-Id: 123\r\nContent-Type: application/octet-stream\r\nContent-length: 2\r\n\r\n{}",
-        ];
-        let expected_bad_request_payload_errors = vec![
-            "Invalid content-type",
-            "Invalid txid:",
-            "Not enough bytes",
-            "Failed to parse",
-            "expected text/plain",
-        ];
-        for (test, (expected_error, request_path)) in bad_request_payloads.iter().zip(
-            expected_bad_request_payload_errors
-                .iter()
-                .zip(request_paths),
-        ) {
-            test_debug!(
-                "Expect failure:\n{}\nExpected error: '{}'",
-                test,
-                expected_error
-            );
+    fn test_http_trace_read_only_codec() {
+        let request = HttpRequestType::TraceReadOnlyFunction(
+            HttpRequestMetadata::from_host(PeerHost::DNS("www.foo.com".to_string(), 80), None),
+            StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R").unwrap(),
+            "hello-world".try_into().unwrap(),
+            StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                .unwrap()
+                .to_account_principal(),
+            "ro-test".try_into().unwrap(),
+            vec![Value::UInt(1)],
+            None,
+            TipRequest::UseLatestAnchoredTip,
+        );
+        assert!(request
+            .request_path()
+            .starts_with("/v2/contracts/trace/ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R/"));
 
-            let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
-            http.begin_request(HttpVersion::Http11, request_path.to_string());
+        let mut http = StacksHttp::new("127.0.0.1:20443".parse().unwrap());
+        let mut bytes = vec![];
+        http.write_message(&mut bytes, &StacksHttpMessage::Request(request.clone()))
+            .unwrap();
 
-            let (preamble, offset) = http.read_preamble(test.as_bytes()).unwrap();
-            let e = http.read_payload(&preamble, &test.as_bytes()[offset..]);
-            let errstr = format!("{:?}", &e);
-            assert!(e.is_err());
-            assert!(
-                e.unwrap_err().to_string().find(expected_error).is_some(),
-                "{}",
-                errstr
-            );
+        let (preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let (message, _) = http.read_payload(&preamble, &bytes[offset..]).unwrap();
+        match message {
+            StacksHttpMessage::Request(parsed @ HttpRequestType::TraceReadOnlyFunction(..)) => {
+                assert_eq!(parsed.request_path(), request.request_path());
+            }
+            _ => panic!("Did not parse a trace request: {:?}", &message),
         }
+
+        // the call's fields sit alongside the trace
+        let response = TraceReadOnlyResponse {
+            call: CallReadOnlyResponse {
+                okay: true,
+                result: Some("0x0701".to_string()),
+                cause: None,
+                budget_exceeded: None,
+                cost: Some(ExecutionCost::zero()),
+            },
+            trace: StepTrace::default(),
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["okay"], true);
+        assert_eq!(json["result"], "0x0701");
+        assert_eq!(json["trace"]["steps"], serde_json::json!([]));
+        assert_eq!(
+            serde_json::from_value::<TraceReadOnlyResponse>(json).unwrap(),
+            response
+        );
     }
 
     #[test]
@@ -32,6 +32,7 @@ use std::net::Ipv6Addr;
 use std::net::SocketAddr;
 use std::ops::Deref;
 use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 
 use rand::thread_rng;
 use rand::RngCore;
@@ -47,23 +48,33 @@ use crate::burnchains::Txid;
 use crate::chainstate::burn::ConsensusHash;
 use crate::chainstate::coordinator::Error as coordinator_error;
 use crate::chainstate::stacks::db::blocks::MemPoolRejection;
+use crate::chainstate::stacks::db::deposits::DepositType;
+use crate::chainstate::stacks::db::receipts::TransactionReceiptRecord;
+use crate::chainstate::stacks::db::results::BlockResults;
+use crate::chainstate::stacks::db::state_diff::BlockStateDiff;
+use crate::chainstate::stacks::index::node::TriePath;
 use crate::chainstate::stacks::index::Error as marf_error;
+use crate::chainstate::stacks::index::{MARFValue, TrieMerkleProof};
 use crate::chainstate::stacks::miner::Proposal;
 use crate::chainstate::stacks::Error as chainstate_error;
 use crate::chainstate::stacks::{
     Error as chain_error, StacksBlock, StacksMicroblock, StacksPublicKey, StacksTransaction,
-    TransactionPayload,
+    TransactionInclusionProof, TransactionPayload,
 };
 use crate::clarity_vm::clarity::Error as clarity_error;
 use crate::core::mempool::*;
 use crate::net::atlas::{Attachment, AttachmentInstance};
+use crate::net::db::{PeerBan, PeerBanTarget};
+use crate::net::divergence::DivergenceSnapshot;
 use crate::net::http::HttpReservedHeader;
 use crate::util_lib::bloom::{BloomFilter, BloomNodeHasher};
 use crate::util_lib::boot::boot_code_tx_auth;
 use crate::util_lib::db::DBConn;
 use crate::util_lib::db::Error as db_error;
 use crate::util_lib::strings::UrlString;
-use clarity::vm::types::{AssetIdentifier, TraitIdentifier};
+use clarity::vm::database::{ClarityDatabase, ClaritySerializable, StoreType};
+use clarity::vm::hooks::StepTrace;
+use clarity::vm::types::{AssetIdentifier, QualifiedContractIdentifier, TraitIdentifier};
 use clarity::vm::{
     analysis::contract_interface_builder::ContractInterface, types::PrincipalData, ClarityName,
     ContractName, Value,
@@ -73,6 +84,7 @@ use stacks_common::codec::StacksMessageCodec;
 use stacks_common::codec::{read_next, write_next};
 use stacks_common::util::get_epoch_time_secs;
 use stacks_common::util::hash::Hash160;
+use stacks_common::util::hash::Sha512Trunc256Sum;
 use stacks_common::util::hash::DOUBLE_SHA256_ENCODED_SIZE;
 use stacks_common::util::hash::HASH160_ENCODED_SIZE;
 use stacks_common::util::hash::{hex_bytes, to_hex};
@@ -86,7 +98,7 @@ use crate::chainstate::stacks::StacksBlockHeader;
 use crate::codec::BURNCHAIN_HEADER_HASH_ENCODED_SIZE;
 use crate::cost_estimates::FeeRateEstimate;
 use crate::types::chainstate::BlockHeaderHash;
-use crate::types::chainstate::{BurnchainHeaderHash, StacksAddress, StacksBlockId};
+use crate::types::chainstate::{BurnchainHeaderHash, StacksAddress, StacksBlockId, TrieHash};
 use crate::types::StacksPublicKeyBuffer;
 use crate::util::hash::Sha256Sum;
 use crate::vm::costs::ExecutionCost;
@@ -96,6 +108,8 @@ pub use self::http::StacksHttp;
 
 use crate::core::StacksEpoch;
 
+/// Authorizes admin RPC requests, by shared secret or by signature, and grants them roles.
+pub mod admin;
 /// Implements `ASEntry4` object, which is used in db.rs to store the AS number of an IP address.
 pub mod asn;
 /// Implements the Atlas network. This network uses the infrastructure created in `src/net` to
@@ -114,6 +128,9 @@ pub mod chat;
 pub mod codec;
 pub mod connection;
 pub mod db;
+/// Captures diagnostic snapshots of the node's view of both chains when either one diverges from
+/// the fork the node was following.
+pub mod divergence;
 /// Implements `DNSResolver`, a simple DNS resolver state machine. Also implements `DNSClient`,
 /// which serves as an API for `DNSResolver`.  
 pub mod dns;
@@ -127,10 +144,19 @@ pub mod p2p;
 /// sockets. This is used to control how many sockets are allocated for the two network servers: the
 /// p2p server and the http server.
 pub mod poll;
+/// Implements `BlockProposerSchedule`, which decides which multi-miner subnet miners may propose a
+/// block for each L1 block, and `BlockProposalLog`, which records the proposals a node has seen.
+pub mod proposals;
 pub mod prune;
+/// Implements `RPCRateLimiter`, which keeps per-client token buckets for the RPC endpoints that
+/// are expensive to serve.
+pub mod ratelimit;
 pub mod relay;
 pub mod rpc;
 pub mod server;
+/// Implements `TxVerifierPool`, a pool of worker threads that check the signatures of
+/// transactions pushed to us by peers before they are stored.
+pub mod txverify;
 
 #[derive(Debug)]
 pub enum Error {
@@ -459,6 +485,21 @@ impl_array_newtype!(PeerAddress, u8, 16);
 impl_array_hexstring_fmt!(PeerAddress);
 impl_byte_array_newtype!(PeerAddress, u8, 16);
 
+/// Identifies the subnet a node belongs to.  This is derived from the subnet's L1 contract
+/// identifier, so that subnets which share a network ID (e.g. because they were deployed from the
+/// same codebase with default settings) can still tell each other's peers apart.
+pub struct SubnetId([u8; 32]);
+impl_array_newtype!(SubnetId, u8, 32);
+impl_array_hexstring_fmt!(SubnetId);
+impl_byte_array_newtype!(SubnetId, u8, 32);
+
+impl SubnetId {
+    pub fn from_contract_identifier(contract_identifier: &QualifiedContractIdentifier) -> SubnetId {
+        let h = Sha512Trunc256Sum::from_data(contract_identifier.to_string().as_bytes());
+        SubnetId(h.0)
+    }
+}
+
 impl Serialize for PeerAddress {
     fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
         let inst = format!("{}", self.to_socketaddr(0).ip());
@@ -834,6 +875,7 @@ pub struct HandshakeData {
     pub node_public_key: StacksPublicKeyBuffer,
     pub expire_block_height: u64, // burn block height after which this node's key will be revoked,
     pub data_url: UrlString,
+    pub subnet_id: Option<SubnetId>, // the subnet contract this node is bound to, if it says
 }
 
 #[repr(u8)]
@@ -1212,6 +1254,24 @@ pub struct MapEntryResponse {
     pub marf_proof: Option<String>,
 }
 
+/// An entry of a data map, as listed on GET /v2/map_entries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapEntriesItem {
+    /// hex-encoded serialization of the entry's key
+    pub key: String,
+    /// hex-encoded serialization of the entry's value
+    pub value: String,
+}
+
+/// The data we return on GET /v2/map_entries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapEntriesResponse {
+    /// in order of their serialized keys
+    pub entries: Vec<MapEntriesItem>,
+    /// pass as the `after` query argument to get the next page, if there may be one
+    pub next_key: Option<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ContractSrcResponse {
     pub source: String,
@@ -1236,6 +1296,31 @@ pub struct CallReadOnlyResponse {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cause: Option<String>,
+    /// Set if the call was aborted because it exceeded the node's read-only call budget
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub budget_exceeded: Option<ReadOnlyBudgetExceeded>,
+    /// Cost consumed by a successful call
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<ExecutionCost>,
+}
+
+/// A read-only function call, with the trace of the expressions it evaluated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceReadOnlyResponse {
+    #[serde(flatten)]
+    pub call: CallReadOnlyResponse,
+    pub trace: StepTrace,
+}
+
+/// Details of a read-only call that ran over the node's `read_only_call_limit`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadOnlyBudgetExceeded {
+    /// Cost consumed when the call was aborted
+    pub cost: ExecutionCost,
+    /// The read-only call budget that was exceeded
+    pub budget: ExecutionCost,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -1252,11 +1337,608 @@ pub struct AccountEntryResponse {
     pub nonce_proof: Option<String>,
 }
 
+/// Whether a subnet withdrawal has been claimed on the L1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WithdrawalClaimStatus {
+    /// A claim of the withdrawal was observed in the canonical L1 fork
+    Claimed,
+    /// No claim of the withdrawal has been observed in the canonical L1 fork
+    Unclaimed,
+    /// The withdrawal was only included in subnet blocks that are no longer in the canonical
+    /// chain, and whose withdrawal roots are not registered in the canonical L1 fork, so it can
+    /// never be claimed
+    Expired,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WithdrawalResponse {
     pub withdrawal_root: String,
     pub withdrawal_leaf_hash: String,
     pub sibling_hashes: String,
+    pub claim_status: WithdrawalClaimStatus,
+    /// the L1 transaction that claimed the withdrawal, if it has been claimed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub claim_txid: Option<String>,
+    /// the L1 block whose block commit registered `withdrawal_root`, if it is registered in the
+    /// canonical L1 fork
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub commit_l1_block: Option<WithdrawalCommitL1Block>,
+}
+
+/// The L1 block whose block commit registered a withdrawal root
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WithdrawalCommitL1Block {
+    pub l1_block_hash: BurnchainHeaderHash,
+    pub l1_block_height: u64,
+}
+
+/// Whether or not a block's withdrawal root is deep enough in the chain to be final
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WithdrawalRootStatus {
+    /// Fewer than the configured number of blocks have been built on the block
+    Pending,
+    /// The block is at least the configured number of blocks deep
+    Confirmed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WithdrawalStatusResponse {
+    pub block_height: u64,
+    pub index_block_hash: StacksBlockId,
+    pub withdrawal_root: String,
+    /// number of blocks built on top of this block in the canonical chain
+    pub confirmations: u64,
+    pub required_confirmations: u64,
+    pub status: WithdrawalRootStatus,
+    /// the batch of blocks whose withdrawals are registered with this block's, if withdrawals
+    /// are batched.  If so, `withdrawal_root` and `confirmations` refer to the whole batch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub withdrawal_batch: Option<WithdrawalBatchRange>,
+}
+
+/// The range of blocks (inclusive) whose withdrawals are registered with the L1 together
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WithdrawalBatchRange {
+    pub first_block_height: u64,
+    pub last_block_height: u64,
+}
+
+/// Which block to look up recorded block results for
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockResultsQuery {
+    /// The block with this index block hash
+    IndexBlockHash(StacksBlockId),
+    /// The block at this height in the canonical chain
+    Height(u64),
+}
+
+/// A key in the Clarity VM's key-value store whose value can be proven against a block's state
+/// root.  This is the JSON body of POST /v2/clarity/proof.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClarityDataKey {
+    /// A contract's data var
+    DataVar { contract: String, name: String },
+    /// An entry in a contract's data map.  `key` is the hex-encoded serialization of the map key.
+    MapEntry {
+        contract: String,
+        name: String,
+        key: String,
+    },
+    /// A principal's balance of one of a contract's fungible tokens
+    FtBalance {
+        contract: String,
+        name: String,
+        principal: String,
+    },
+    /// A principal's STX balance
+    StxBalance { principal: String },
+}
+
+impl ClarityDataKey {
+    fn parse_contract(contract: &str) -> Result<QualifiedContractIdentifier, String> {
+        QualifiedContractIdentifier::parse(contract)
+            .map_err(|_e| format!("Failed to parse contract identifier '{}'", contract))
+    }
+
+    fn parse_name(name: &str) -> Result<ClarityName, String> {
+        ClarityName::try_from(name.to_string())
+            .map_err(|_e| format!("Failed to parse Clarity name '{}'", name))
+    }
+
+    fn parse_principal(principal: &str) -> Result<PrincipalData, String> {
+        PrincipalData::parse(principal)
+            .map_err(|_e| format!("Failed to parse principal '{}'", principal))
+    }
+
+    /// Encode this key the way the Clarity VM does when it reads and writes it.  The MARF path of
+    /// the value is the hash of this string.
+    pub fn to_clarity_key(&self) -> Result<String, String> {
+        match self {
+            ClarityDataKey::DataVar { contract, name } => Ok(ClarityDatabase::make_key_for_trip(
+                &ClarityDataKey::parse_contract(contract)?,
+                StoreType::Variable,
+                &ClarityDataKey::parse_name(name)?,
+            )),
+            ClarityDataKey::MapEntry {
+                contract,
+                name,
+                key,
+            } => {
+                let key_hex = if key.starts_with("0x") {
+                    &key[2..]
+                } else {
+                    key
+                };
+                let key_value = Value::try_deserialize_hex_untyped(key_hex)
+                    .map_err(|_e| "Failed to deserialize map key".to_string())?;
+                Ok(ClarityDatabase::make_key_for_data_map_entry(
+                    &ClarityDataKey::parse_contract(contract)?,
+                    &ClarityDataKey::parse_name(name)?,
+                    &key_value,
+                ))
+            }
+            ClarityDataKey::FtBalance {
+                contract,
+                name,
+                principal,
+            } => Ok(ClarityDatabase::make_key_for_quad(
+                &ClarityDataKey::parse_contract(contract)?,
+                StoreType::FungibleToken,
+                &ClarityDataKey::parse_name(name)?,
+                &ClaritySerializable::serialize(&ClarityDataKey::parse_principal(principal)?),
+            )),
+            ClarityDataKey::StxBalance { principal } => {
+                Ok(ClarityDatabase::make_key_for_account_balance(
+                    &ClarityDataKey::parse_principal(principal)?,
+                ))
+            }
+        }
+    }
+}
+
+/// The data we return on POST /v2/clarity/proof
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClarityDataProofResponse {
+    pub index_block_hash: StacksBlockId,
+    /// the state root in the block's header, which the proof is against
+    pub state_index_root: TrieHash,
+    /// the key, as encoded by the Clarity VM
+    pub clarity_key: String,
+    /// the value stored under the key, exactly as the Clarity VM stored it
+    pub data: String,
+    /// hex-encoded MARF merkle proof
+    pub marf_proof: String,
+}
+
+impl ClarityDataProofResponse {
+    /// Check the proof against `state_index_root`.  `root_to_block` maps the state root of each
+    /// ancestor block to its index block hash, which light clients can build from block headers.
+    pub fn verify(&self, root_to_block: &HashMap<TrieHash, StacksBlockId>) -> bool {
+        let proof_hex = if self.marf_proof.starts_with("0x") {
+            &self.marf_proof[2..]
+        } else {
+            &self.marf_proof
+        };
+        let proof = match hex_bytes(proof_hex).ok().and_then(|proof_bytes| {
+            TrieMerkleProof::<StacksBlockId>::consensus_deserialize(&mut &proof_bytes[..]).ok()
+        }) {
+            Some(proof) => proof,
+            None => {
+                return false;
+            }
+        };
+        proof.verify(
+            &TriePath::from_key(&self.clarity_key),
+            &MARFValue::from_value(&self.data),
+            &self.state_index_root,
+            root_to_block,
+        )
+    }
+}
+
+/// The data we return on GET /v2/transactions/proof
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionProofResponse {
+    pub index_block_hash: StacksBlockId,
+    /// the transaction merkle root in the block's header, which the proof is against
+    pub tx_merkle_root: Sha512Trunc256Sum,
+    pub txid: String,
+    /// the transaction's position in the block
+    pub index: u32,
+    /// hex-encoded transaction inclusion proof
+    pub proof: String,
+}
+
+impl TransactionProofResponse {
+    /// Check the proof against `tx_merkle_root`, and check that it is a proof for `txid`.
+    pub fn verify(&self) -> bool {
+        let proof_hex = if self.proof.starts_with("0x") {
+            &self.proof[2..]
+        } else {
+            &self.proof
+        };
+        let proof = match hex_bytes(proof_hex).ok().and_then(|proof_bytes| {
+            TransactionInclusionProof::consensus_deserialize(&mut &proof_bytes[..]).ok()
+        }) {
+            Some(proof) => proof,
+            None => {
+                return false;
+            }
+        };
+        let txid_hex = if self.txid.starts_with("0x") {
+            &self.txid[2..]
+        } else {
+            &self.txid
+        };
+        if Txid::from_hex(txid_hex).ok() != Some(proof.txid.clone()) || proof.index != self.index {
+            return false;
+        }
+        proof.verify(&self.tx_merkle_root)
+    }
+}
+
+/// Whether or not an L1 deposit operation has been materialized on the subnet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DepositStatus {
+    /// The operation was observed on the L1, but no block in the canonical chain has
+    /// materialized it yet
+    Pending,
+    /// A block in the canonical chain materialized the operation
+    Materialized,
+}
+
+/// A block in the canonical chain that materialized an L1 deposit operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaterializedDepositInfo {
+    #[serde(rename = "type")]
+    pub deposit_type: DepositType,
+    /// the L1 block in which the materialized operation was observed
+    pub l1_block_id: String,
+    pub index_block_hash: StacksBlockId,
+    pub block_height: u64,
+}
+
+/// The data we return on GET /v2/deposits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DepositStatusResponse {
+    pub l1_txid: String,
+    pub status: DepositStatus,
+    /// one entry per kind of deposit (STX, FT, NFT) the L1 transaction made
+    pub deposits: Vec<MaterializedDepositInfo>,
+}
+
+/// The data we return on GET /v2/blocks/:index_block_hash/costs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockCostsResponse {
+    pub index_block_hash: StacksBlockId,
+    pub anchored_cost: ExecutionCost,
+    pub confirmed_microblocks_cost: ExecutionCost,
+    /// `anchored_cost` plus `confirmed_microblocks_cost`, which share the block's budget
+    pub total_cost: ExecutionCost,
+    pub block_limit: ExecutionCost,
+    /// percentage of `block_limit` consumed by `total_cost`, in its fullest dimension
+    pub percent_full: u64,
+}
+
+/// The data we return on GET /v2/blocks/:index_block_hash/header
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockHeaderResponse {
+    pub index_block_hash: StacksBlockId,
+    pub block_hash: BlockHeaderHash,
+    pub consensus_hash: ConsensusHash,
+    pub block_height: u64,
+    pub burn_block_height: u32,
+    pub parent_block: BlockHeaderHash,
+    pub tx_merkle_root: Sha512Trunc256Sum,
+    pub state_index_root: TrieHash,
+    pub withdrawal_merkle_root: Sha512Trunc256Sum,
+    /// hex-encoded signatures of the miners that signed the block
+    pub miner_signatures: Vec<String>,
+    /// whether the block commit that selected this block is in the canonical L1 fork
+    pub commit_observed_on_l1: bool,
+}
+
+/// What processing one transaction of a block proposal produced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockProposalTxResult {
+    pub txid: Txid,
+    /// hex-encoded serialized Clarity value, if the transaction could be mined
+    pub result: Option<String>,
+    /// why the transaction could not be mined, if it could not
+    pub error: Option<String>,
+    pub execution_cost: ExecutionCost,
+}
+
+/// The data we return on POST /v2/block_proposal/validate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockProposalValidationResponse {
+    pub valid: bool,
+    /// why the proposal is invalid, if it is
+    pub reason: Option<String>,
+    pub block_hash: BlockHeaderHash,
+    /// hash of the block assembled from the proposal's transactions, if they could be processed
+    pub computed_block_hash: Option<BlockHeaderHash>,
+    /// cost of the transactions that could be mined
+    pub total_cost: ExecutionCost,
+    pub block_size: u64,
+    /// one result per proposed transaction, in block order
+    pub tx_results: Vec<BlockProposalTxResult>,
+}
+
+/// The data we return on GET /v2/accounts/:principal/nonce-gaps
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NonceGapsResponse {
+    pub principal: String,
+    /// the nonce the account's next transaction must have
+    pub next_nonce: u64,
+    /// the nonce this node's last mempool walk expected of the account, if it considered the
+    /// account's transactions
+    pub last_known_nonce: Option<u64>,
+    pub pending_nonces: Vec<u64>,
+    pub missing_nonces: Vec<u64>,
+    /// pending transactions which can't be mined until the missing nonces are filled
+    pub blocked_txids: Vec<String>,
+}
+
+/// Whether this node mines subnet blocks, and whether the L1 has accepted its commits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubnetMinerStatus {
+    /// This node is not configured to mine
+    NotMining,
+    /// A block mined by this node is in the canonical chain, so the subnet contract accepted a
+    /// commit from this node's miner
+    Registered,
+    /// No block mined by this node is in the canonical chain.  The subnet contract only accepts
+    /// commits from its registered miners, so this node's miner may not be registered.
+    Unconfirmed,
+}
+
+/// The outcome of this node's startup check of the L1 subnet contract's interface version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum L1ContractStatus {
+    /// This node did not check the contract, e.g. because its L1 is mocked
+    Unchecked,
+    /// The contract's interface version is the one this node implements
+    Compatible,
+    /// The contract's interface version is not the one this node implements, so withdrawals
+    /// committed by this node could not be verified by the contract.  The node runs degraded:
+    /// it follows the subnet, but does not mine.
+    Incompatible,
+}
+
+impl Default for L1ContractStatus {
+    fn default() -> Self {
+        L1ContractStatus::Unchecked
+    }
+}
+
+/// Where this node is in its startup sequence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeStartupState {
+    /// The node has not yet confirmed that it has processed the L1 up to near the L1's tip, so
+    /// its miner does not produce blocks
+    SyncingL1,
+    /// The node has processed the L1 up to near the L1's tip
+    Ready,
+}
+
+impl Default for NodeStartupState {
+    fn default() -> Self {
+        NodeStartupState::Ready
+    }
+}
+
+/// How far this node has processed the L1, as of its last check of the L1's tip
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct L1SyncStatus {
+    pub startup_state: NodeStartupState,
+    /// the L1 tip height reported by the L1 node at the last check, if it answered
+    pub l1_node_tip_height: Option<u64>,
+}
+
+/// A node's `L1SyncStatus`, shared between the run loop, which updates it, and the RPC
+/// interface, which reports it.  Clones share the same status.
+#[derive(Debug, Clone, Default)]
+pub struct L1SyncStatusHandle(Arc<RwLock<L1SyncStatus>>);
+
+impl L1SyncStatusHandle {
+    pub fn get(&self) -> L1SyncStatus {
+        self.0.read().expect("L1 sync status lock poisoned").clone()
+    }
+
+    pub fn set(&self, status: L1SyncStatus) {
+        *self.0.write().expect("L1 sync status lock poisoned") = status;
+    }
+}
+
+impl PartialEq for L1SyncStatusHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// The data we return on GET /v2/subnet/status
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubnetStatusResponse {
+    pub stacks_tip_height: u64,
+    pub stacks_tip: BlockHeaderHash,
+    pub stacks_tip_consensus_hash: ConsensusHash,
+    /// the L1 block whose block commit selected the subnet tip
+    pub anchor_l1_block_hash: BurnchainHeaderHash,
+    pub anchor_l1_block_height: u64,
+    /// the highest L1 block this node has processed
+    pub l1_tip_height: u64,
+    /// number of L1 blocks processed since the last one with a successful block commit
+    pub l1_blocks_since_last_commit: u64,
+    /// number of withdrawals in canonical blocks whose withdrawal roots are not yet confirmed
+    pub pending_withdrawals: u64,
+    pub miner_status: SubnetMinerStatus,
+    /// height of the most recent block in the canonical chain mined by this node, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub last_mined_block_height: Option<u64>,
+    /// hex-encoded public key that signs this node's event observer payloads, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub event_signing_public_key: Option<String>,
+    #[serde(default)]
+    pub l1_contract_status: L1ContractStatus,
+    /// the interface version reported by the L1 subnet contract, if it was checked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub l1_contract_interface_version: Option<u64>,
+    #[serde(default)]
+    pub startup_state: NodeStartupState,
+    /// the L1 tip height reported by the L1 node when this node last checked it, if it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub l1_node_tip_height: Option<u64>,
+}
+
+/// The data we return on POST /v2/admin/divergence_snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DivergenceSnapshotResponse {
+    /// where the snapshot was saved, if the node has a snapshot directory
+    pub path: Option<String>,
+    pub snapshot: DivergenceSnapshot,
+}
+
+/// A pending transaction, as listed on GET /v2/mempool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemPoolTxEntry {
+    pub txid: String,
+    pub origin_address: String,
+    pub origin_nonce: u64,
+    pub sponsor_address: String,
+    pub sponsor_nonce: u64,
+    pub tx_fee: u64,
+    pub tx_len: u64,
+    pub payload_type: String,
+    /// the Stacks block height of the chain tip when the transaction was accepted
+    pub block_height: u64,
+    pub accept_time: u64,
+}
+
+/// The data we return on GET /v2/mempool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemPoolListResponse {
+    /// in order of decreasing fee, then decreasing txid
+    pub transactions: Vec<MemPoolTxEntry>,
+    /// pass as the `cursor` query argument to get the next page, if there is one
+    pub next_cursor: Option<String>,
+}
+
+/// The pending transactions with fees in a range, inclusive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemPoolFeeBucketResponse {
+    pub min_fee: u64,
+    pub max_fee: u64,
+    pub count: u64,
+}
+
+/// The data we return on GET /v2/mempool/summary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemPoolSummaryResponse {
+    pub count: u64,
+    /// non-empty buckets, in increasing order of fee
+    pub fee_histogram: Vec<MemPoolFeeBucketResponse>,
+}
+
+/// The data we return on DELETE /v2/mempool/:txid
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemPoolEvictionResponse {
+    pub txid: String,
+    pub origin_address: String,
+    pub origin_nonce: u64,
+    pub tx_fee: u64,
+    pub reason: String,
+    pub evict_time: u64,
+}
+
+/// A connected peer, as reported on GET /v2/admin/peers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminPeerInfo {
+    #[serde(flatten)]
+    pub neighbor: RPCNeighbor,
+    pub outbound: bool,
+    /// When the peer first completed a handshake with us, or 0 if it has not yet
+    pub first_contact_time: u64,
+}
+
+/// The data we return on GET /v2/admin/peers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminPeersResponse {
+    pub peers: Vec<AdminPeerInfo>,
+}
+
+/// An event observer this node sends events to, as reported on GET /v2/admin/observers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminObserverInfo {
+    pub endpoint: String,
+    /// The events the observer is sent, as they are written in the node's config
+    pub events_keys: Vec<String>,
+}
+
+/// The data we return on GET /v2/admin/observers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminObserversResponse {
+    pub observers: Vec<AdminObserverInfo>,
+}
+
+/// A persistent peer ban, as reported by the /v2/admin/bans endpoints.  Exactly one of
+/// `public_key_hash` and `address_range` is set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeerBanInfo {
+    pub ban_id: u64,
+    /// The hex-encoded hash160 of the banned peer's public key
+    pub public_key_hash: Option<String>,
+    /// The banned address range, as `ip/prefix-length`
+    pub address_range: Option<String>,
+    pub reason: String,
+    pub created_at: u64,
+    /// When the ban lapses, or None if it never does
+    pub expires_at: Option<u64>,
+}
+
+impl PeerBanInfo {
+    pub fn from_peer_ban(ban: &PeerBan) -> PeerBanInfo {
+        PeerBanInfo {
+            ban_id: ban.ban_id,
+            public_key_hash: match ban.target {
+                PeerBanTarget::PublicKeyHash(ref hash) => Some(hash.to_hex()),
+                PeerBanTarget::AddressRange(..) => None,
+            },
+            address_range: ban.target.to_address_range(),
+            reason: ban.reason.clone(),
+            created_at: ban.created_at,
+            expires_at: ban.expires_at,
+        }
+    }
+}
+
+/// The data we return on GET /v2/admin/bans
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeerBansResponse {
+    pub bans: Vec<PeerBanInfo>,
+}
+
+/// The body of POST /v2/admin/bans/add.  Exactly one of `public_key_hash` and `address_range`
+/// must be given.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeerBanRequestBody {
+    pub public_key_hash: Option<String>,
+    pub address_range: Option<String>,
+    /// How long the ban lasts, or None to ban forever
+    pub duration_secs: Option<u64>,
+    pub reason: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -1280,6 +1962,116 @@ pub struct PostTransactionRequestBody {
     pub attachment: Option<String>,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct PostTransactionBatchRequestBody {
+    pub txs: Vec<String>,
+    #[serde(default)]
+    pub all_or_nothing: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PostTransactionSponsorshipRequestBody {
+    /// the unsponsored mempool transaction being sponsored
+    pub txid: String,
+    /// the sponsored transaction, hex-encoded
+    pub tx: String,
+}
+
+/// What happened to one transaction in a posted transaction batch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TransactionBatchStatus {
+    /// Admitted to the mempool
+    Accepted,
+    /// Already in the mempool
+    AlreadyKnown,
+    /// Rejected by the mempool
+    Rejected,
+    /// Admitted, but then removed because another transaction in an all-or-nothing batch was
+    /// rejected
+    RolledBack,
+    /// Not tried, because an earlier transaction in an all-or-nothing batch was rejected
+    NotAttempted,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionBatchResult {
+    pub txid: String,
+    pub status: TransactionBatchStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionBatchResponse {
+    /// true if every transaction in the batch is now in the mempool
+    pub accepted: bool,
+    pub all_or_nothing: bool,
+    pub results: Vec<TransactionBatchResult>,
+}
+
+/// Where a posted transaction's nonce falls, relative to its origin account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionNonceStatus {
+    Stale,
+    Next,
+    Chained,
+    Replacement,
+    Gap,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionNonceDiagnostics {
+    pub nonce: u64,
+    /// the nonce the origin account's next transaction must have.  None if the account could
+    /// not be loaded at the chain tip.
+    pub expected_nonce: Option<u64>,
+    /// nonces of the origin account's other pending transactions
+    pub pending_nonces: Vec<u64>,
+    pub status: Option<TransactionNonceStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionAdmissionCheckStatus {
+    Passed,
+    Failed,
+    /// not run, because an earlier check failed
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionAdmissionCheck {
+    pub name: String,
+    pub status: TransactionAdmissionCheckStatus,
+}
+
+/// Admission diagnostics for a transaction posted with `?diagnostics=1`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionAdmissionResponse {
+    pub txid: String,
+    /// true if the transaction is now in the mempool
+    pub accepted: bool,
+    /// true if the transaction was already in the mempool, and so was not checked again
+    pub already_known: bool,
+    /// why the mempool rejected the transaction, in the same form as a rejected post without
+    /// diagnostics
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub rejection: Option<serde_json::Value>,
+    /// size of the serialized transaction, in bytes
+    pub tx_size: u64,
+    pub tx_fee: u64,
+    /// the transaction's estimated fee rate, if the node's cost estimator has an estimate for it
+    pub fee_rate: Option<f64>,
+    /// percentage of the other pending transactions with a lower estimated fee rate
+    pub fee_rate_percentile: Option<f64>,
+    pub nonce: TransactionNonceDiagnostics,
+    /// the mempool's admission checks, in the order they run
+    pub checks: Vec<TransactionAdmissionCheck>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct GetAttachmentResponse {
     pub attachment: Attachment,
@@ -1369,6 +2161,11 @@ impl HttpRequestMetadata {
 pub struct CallReadOnlyRequestBody {
     pub sender: String,
     pub arguments: Vec<String>,
+    /// Cost budget requested for the call.  Each dimension is capped at the node's
+    /// `read_only_call_max_budget`; if omitted, the node's `read_only_call_limit` applies.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_budget: Option<ExecutionCost>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -1429,7 +2226,16 @@ pub enum HttpRequestType {
     GetMicroblocksConfirmed(HttpRequestMetadata, StacksBlockId),
     GetMicroblocksUnconfirmed(HttpRequestMetadata, StacksBlockId, u16),
     GetTransactionUnconfirmed(HttpRequestMetadata, Txid),
-    PostTransaction(HttpRequestMetadata, StacksTransaction, Option<Attachment>),
+    PostTransaction(
+        HttpRequestMetadata,
+        StacksTransaction,
+        Option<Attachment>,
+        bool,
+    ),
+    /// transactions, and whether or not to reject them all if any one is rejected
+    PostTransactionBatch(HttpRequestMetadata, Vec<StacksTransaction>, bool),
+    /// the unsponsored mempool transaction, and the sponsored transaction to replace it with
+    PostTransactionSponsorship(HttpRequestMetadata, Txid, StacksTransaction),
     PostBlock(HttpRequestMetadata, ConsensusHash, StacksBlock),
     PostMicroblock(HttpRequestMetadata, StacksMicroblock, TipRequest),
     GetWithdrawalStx {
@@ -1439,6 +2245,95 @@ pub enum HttpRequestType {
         withdrawal_id: u32,
         amount: u128,
     },
+    GetWithdrawalProof {
+        metadata: HttpRequestMetadata,
+        withdraw_block_height: u64,
+        withdrawal_id: u32,
+    },
+    GetWithdrawalStatus {
+        metadata: HttpRequestMetadata,
+        withdraw_block_height: u64,
+    },
+    GetBlockResults {
+        metadata: HttpRequestMetadata,
+        block: BlockResultsQuery,
+    },
+    GetClarityDataProof(HttpRequestMetadata, ClarityDataKey, TipRequest),
+    GetTransactionProof {
+        metadata: HttpRequestMetadata,
+        index_block_hash: StacksBlockId,
+        txid: Txid,
+    },
+    GetDepositStatus {
+        metadata: HttpRequestMetadata,
+        l1_txid: Txid,
+    },
+    GetBlockCosts {
+        metadata: HttpRequestMetadata,
+        index_block_hash: StacksBlockId,
+    },
+    GetBlockHeader {
+        metadata: HttpRequestMetadata,
+        index_block_hash: StacksBlockId,
+    },
+    GetBlockStateDiff {
+        metadata: HttpRequestMetadata,
+        index_block_hash: StacksBlockId,
+    },
+    GetTransactionReceipt {
+        metadata: HttpRequestMetadata,
+        txid: Txid,
+    },
+    GetNonceGaps {
+        metadata: HttpRequestMetadata,
+        address: StacksAddress,
+        tip_req: TipRequest,
+    },
+    GetSubnetStatus(HttpRequestMetadata),
+    PostDivergenceSnapshot {
+        metadata: HttpRequestMetadata,
+        auth_token: Option<String>,
+    },
+    GetMemPoolTxs {
+        metadata: HttpRequestMetadata,
+        filter: MemPoolListFilter,
+        cursor: Option<MemPoolListCursor>,
+        limit: u32,
+    },
+    GetMemPoolSummary {
+        metadata: HttpRequestMetadata,
+        filter: MemPoolListFilter,
+    },
+    DeleteMemPoolTx {
+        metadata: HttpRequestMetadata,
+        txid: Txid,
+        reason: Option<String>,
+        auth_token: Option<String>,
+    },
+    GetAdminPeers {
+        metadata: HttpRequestMetadata,
+        auth_token: Option<String>,
+    },
+    GetAdminObservers {
+        metadata: HttpRequestMetadata,
+        auth_token: Option<String>,
+    },
+    GetPeerBans {
+        metadata: HttpRequestMetadata,
+        auth_token: Option<String>,
+    },
+    PostPeerBan {
+        metadata: HttpRequestMetadata,
+        target: PeerBanTarget,
+        duration_secs: Option<u64>,
+        reason: Option<String>,
+        auth_token: Option<String>,
+    },
+    DeletePeerBan {
+        metadata: HttpRequestMetadata,
+        ban_id: u64,
+        auth_token: Option<String>,
+    },
     GetWithdrawalNft {
         metadata: HttpRequestMetadata,
         withdraw_block_height: u64,
@@ -1465,6 +2360,16 @@ pub enum HttpRequestType {
         TipRequest,
         bool,
     ),
+    GetMapEntries {
+        metadata: HttpRequestMetadata,
+        contract_addr: StacksAddress,
+        contract_name: ContractName,
+        map_name: ClarityName,
+        /// list the entries whose keys come after this one
+        after: Option<Value>,
+        limit: u32,
+        tip_req: TipRequest,
+    },
     FeeRateEstimate(HttpRequestMetadata, TransactionPayload, u64),
     CallReadOnlyFunction(
         HttpRequestMetadata,
@@ -1473,9 +2378,23 @@ pub enum HttpRequestType {
         PrincipalData,
         ClarityName,
         Vec<Value>,
+        Option<ExecutionCost>,
+        TipRequest,
+    ),
+    /// Same as `CallReadOnlyFunction`, but the response includes a step trace.  Only served by
+    /// nodes built with the `eval-hooks` feature.
+    TraceReadOnlyFunction(
+        HttpRequestMetadata,
+        StacksAddress,
+        ContractName,
+        PrincipalData,
+        ClarityName,
+        Vec<Value>,
+        Option<ExecutionCost>,
         TipRequest,
     ),
     GetTransferCost(HttpRequestMetadata),
+    GetSubnetFeeRateEstimate(HttpRequestMetadata),
     GetContractSrc(
         HttpRequestMetadata,
         StacksAddress,
@@ -1494,8 +2413,15 @@ pub enum HttpRequestType {
         TraitIdentifier,
         TipRequest,
     ),
-    MemPoolQuery(HttpRequestMetadata, MemPoolSyncData, Option<Txid>),
+    /// the mempool sync data, the page ID, and the depth of the requester's bloom counter
+    MemPoolQuery(
+        HttpRequestMetadata,
+        MemPoolSyncData,
+        Option<Txid>,
+        Option<u64>,
+    ),
     BlockProposal(HttpRequestMetadata, Proposal),
+    ValidateBlockProposal(HttpRequestMetadata, Proposal),
     /// catch-all for any errors we should surface from parsing
     ClientError(HttpRequestMetadata, ClientError),
 }
@@ -1596,14 +2522,38 @@ pub enum HttpResponseType {
     Microblocks(HttpResponseMetadata, Vec<StacksMicroblock>),
     MicroblockStream(HttpResponseMetadata),
     TransactionID(HttpResponseMetadata, Txid),
+    TransactionBatch(HttpResponseMetadata, TransactionBatchResponse),
+    TransactionAdmission(HttpResponseMetadata, TransactionAdmissionResponse),
     StacksBlockAccepted(HttpResponseMetadata, StacksBlockId, bool),
     MicroblockHash(HttpResponseMetadata, BlockHeaderHash),
     TokenTransferCost(HttpResponseMetadata, u64),
+    SubnetFeeRateEstimate(HttpResponseMetadata, SubnetFeeRateEstimate),
     GetDataVar(HttpResponseMetadata, DataVarResponse),
     GetMapEntry(HttpResponseMetadata, MapEntryResponse),
+    MapEntries(HttpResponseMetadata, MapEntriesResponse),
     CallReadOnlyFunction(HttpResponseMetadata, CallReadOnlyResponse),
+    TraceReadOnlyFunction(HttpResponseMetadata, TraceReadOnlyResponse),
     GetAccount(HttpResponseMetadata, AccountEntryResponse),
     GetWithdrawal(HttpResponseMetadata, WithdrawalResponse),
+    WithdrawalStatus(HttpResponseMetadata, WithdrawalStatusResponse),
+    BlockResults(HttpResponseMetadata, BlockResults),
+    ClarityDataProof(HttpResponseMetadata, ClarityDataProofResponse),
+    TransactionProof(HttpResponseMetadata, TransactionProofResponse),
+    DepositStatus(HttpResponseMetadata, DepositStatusResponse),
+    BlockCosts(HttpResponseMetadata, BlockCostsResponse),
+    BlockHeader(HttpResponseMetadata, BlockHeaderResponse),
+    BlockStateDiff(HttpResponseMetadata, BlockStateDiff),
+    TransactionReceipt(HttpResponseMetadata, TransactionReceiptRecord),
+    NonceGaps(HttpResponseMetadata, NonceGapsResponse),
+    SubnetStatus(HttpResponseMetadata, SubnetStatusResponse),
+    DivergenceSnapshot(HttpResponseMetadata, DivergenceSnapshotResponse),
+    MemPoolListing(HttpResponseMetadata, MemPoolListResponse),
+    MemPoolSummary(HttpResponseMetadata, MemPoolSummaryResponse),
+    MemPoolEviction(HttpResponseMetadata, MemPoolEvictionResponse),
+    AdminPeers(HttpResponseMetadata, AdminPeersResponse),
+    AdminObservers(HttpResponseMetadata, AdminObserversResponse),
+    PeerBans(HttpResponseMetadata, PeerBansResponse),
+    PeerBan(HttpResponseMetadata, PeerBanInfo),
     GetContractABI(HttpResponseMetadata, ContractInterface),
     GetContractSrc(HttpResponseMetadata, ContractSrcResponse),
     GetIsTraitImplemented(HttpResponseMetadata, GetIsTraitImplementedResponse),
@@ -1621,6 +2571,7 @@ pub enum HttpResponseType {
     PaymentRequired(HttpResponseMetadata, String),
     Forbidden(HttpResponseMetadata, String),
     NotFound(HttpResponseMetadata, String),
+    TooManyRequests(HttpResponseMetadata, String),
     ServerError(HttpResponseMetadata, String),
     ServiceUnavailable(HttpResponseMetadata, String),
     Error(HttpResponseMetadata, u16, String),
@@ -1632,6 +2583,7 @@ pub enum HttpResponseType {
         metadata: HttpResponseMetadata,
         error_message: String,
     },
+    BlockProposalValidation(HttpResponseMetadata, BlockProposalValidationResponse),
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
@@ -1662,6 +2614,9 @@ pub enum StacksMessageID {
     Pong = 16,
     NatPunchRequest = 17,
     NatPunchReply = 18,
+    // Handshake and HandshakeAccept, followed by the sender's subnet ID
+    SubnetHandshake = 19,
+    SubnetHandshakeAccept = 20,
     // reserved
     Reserved = 255,
 }
@@ -1774,6 +2729,7 @@ pub const BLOCKS_PUSHED_MAX: u32 = 32;
 
 impl_byte_array_message_codec!(PeerAddress, 16);
 impl_byte_array_message_codec!(Txid, 32);
+impl_byte_array_message_codec!(SubnetId, 32);
 
 /// neighbor identifier
 #[derive(Clone, Eq, PartialOrd, Ord)]
@@ -1905,6 +2861,13 @@ pub const MAX_MICROBLOCKS_UNCONFIRMED: usize = 1024;
 // maximum number of block headers we'll get streamed to us
 pub const MAX_HEADERS: usize = 2100;
 
+// maximum number of transactions that can be posted in a single batch
+pub const MAX_TRANSACTION_BATCH_LEN: usize = 128;
+
+// number of data map entries listed per page, by default and at most
+pub const MAP_ENTRIES_DEFAULT_LIMIT: u32 = 50;
+pub const MAP_ENTRIES_MAX_LIMIT: u32 = 200;
+
 // how long a peer will be denied for if it misbehaves
 #[cfg(test)]
 pub const DENY_BAN_DURATION: u64 = 30; // seconds
@@ -2190,6 +3153,19 @@ pub mod test {
                     .unwrap();
                     Ok(())
                 }
+                BlockstackOperationType::DepositMultiAsset(ref op) => {
+                    serde_json::to_writer(
+                        fd,
+                        &json!({
+                            "op": "deposit_multi_asset",
+                            "stx_amount": op.stx_amount,
+                            "assets": op.assets,
+                            "sender": op.sender,
+                        }),
+                    )
+                    .unwrap();
+                    Ok(())
+                }
                 BlockstackOperationType::WithdrawStx(ref op) => {
                     serde_json::to_writer(
                         fd,
@@ -2229,6 +3205,17 @@ pub mod test {
                     .unwrap();
                     Ok(())
                 }
+                BlockstackOperationType::SubnetHalt(ref op) => {
+                    serde_json::to_writer(
+                        fd,
+                        &json!({
+                            "op": "subnet_halt",
+                            "halted": op.halted,
+                        }),
+                    )
+                    .unwrap();
+                    Ok(())
+                }
             }
         }
 
@@ -3370,6 +4357,17 @@ pub mod test {
             &mut self,
             txs: &[StacksTransaction],
             coinbase_nonce: &mut usize,
+        ) -> StacksBlockId {
+            self.tenure_with_txs_and_ops(txs, vec![], coinbase_nonce)
+        }
+
+        /// Mine a block with the given transactions, and put `ops` into the same burnchain block
+        /// as its block-commit.
+        pub fn tenure_with_txs_and_ops(
+            &mut self,
+            txs: &[StacksTransaction],
+            ops: Vec<BlockstackOperationType>,
+            coinbase_nonce: &mut usize,
         ) -> StacksBlockId {
             let microblock_privkey = StacksPrivateKey::new();
             let microblock_pubkeyhash =
@@ -3377,7 +4375,7 @@ pub mod test {
             let tip =
                 SortitionDB::get_canonical_burn_chain_tip(&self.sortdb.as_ref().unwrap().conn())
                     .unwrap();
-            let (burn_ops, stacks_block, microblocks) = self.make_tenure(
+            let (mut burn_ops, stacks_block, microblocks) = self.make_tenure(
                 |ref mut miner,
                  ref mut sortdb,
                  ref mut chainstate,
@@ -3409,6 +4407,7 @@ pub mod test {
                 },
             );
 
+            burn_ops.extend(ops);
             let (_, _, consensus_hash) = self.next_burnchain_block(burn_ops);
             self.process_stacks_epoch_at_tip(&stacks_block, &microblocks);
 
@@ -16,6 +16,7 @@
 
 use crate::core::PEER_VERSION_TESTNET;
 
+use crate::monitoring;
 use crate::net::asn::ASEntry4;
 use crate::net::db::PeerDB;
 use crate::net::Error as net_error;
@@ -70,6 +71,7 @@ pub const WALK_MIN_DURATION: u64 = 20; // minimum number of steps a walk will ta
 pub const WALK_MAX_DURATION: u64 = 40; // maximum number of steps a walk will take before we do a hard reset
 pub const WALK_RESET_PROB: f64 = 0.05; // probability of a walk reset in-between the minimum and maximum duration
 pub const WALK_STATE_TIMEOUT: u64 = 60; // how long the walk can remain in a single state before being reset
+pub const WALK_DIVERSITY_CANDIDATES: u64 = 16; // how many random neighbors to consider when diversifying outbound orgs
 
 #[cfg(test)]
 pub const WALK_RESET_INTERVAL: u64 = 60; // how long a walk can last
@@ -1844,11 +1846,38 @@ impl PeerNetwork {
             .and_then(|handle| Ok(Some(handle)))
     }
 
+    /// Choose which of the candidate neighbors to walk to.
+    /// If our outbound neighbors span fewer than `min_orgs` organizations, then prefer the first
+    /// candidate in an organization we are not yet connected to.  Otherwise, or if there is no
+    /// such candidate, choose the first candidate.
+    /// Returns the index into `candidates`, and whether or not it was chosen for diversity.
+    pub fn choose_walk_neighbor(
+        candidates: &[Neighbor],
+        connected_orgs: &HashSet<u32>,
+        min_orgs: u64,
+    ) -> (usize, bool) {
+        if (connected_orgs.len() as u64) < min_orgs {
+            for (i, candidate) in candidates.iter().enumerate() {
+                if !connected_orgs.contains(&candidate.org) {
+                    return (i, i > 0);
+                }
+            }
+        }
+        (0, false)
+    }
+
     /// Instantiate the neighbor walk from a neighbor routable from us.
     fn instantiate_walk(&mut self) -> Result<(), net_error> {
-        // pick a random neighbor as a walking point
-        let next_neighbors = self
-            .walk_get_random_neighbors(1, self.chain_view.burn_block_height)
+        // pick a random neighbor as a walking point.  If we want our outbound neighbors to span
+        // more organizations, then consider several.
+        let min_outbound_orgs = self.connection_opts.min_outbound_orgs;
+        let num_candidates = if min_outbound_orgs > 0 {
+            WALK_DIVERSITY_CANDIDATES
+        } else {
+            1
+        };
+        let mut next_neighbors = self
+            .walk_get_random_neighbors(num_candidates, self.chain_view.burn_block_height)
             .map_err(|e| {
                 debug!(
                     "{:?}: Failed to load initial walk neighbors: {:?}",
@@ -1857,10 +1886,32 @@ impl PeerNetwork {
                 e
             })?;
 
+        let connected_orgs: HashSet<u32> = self
+            .org_neighbor_distribution(self.peerdb.conn(), &HashSet::new())?
+            .keys()
+            .cloned()
+            .collect();
+        monitoring::update_outbound_orgs(connected_orgs.len() as i64);
+
+        let (idx, diversified) =
+            PeerNetwork::choose_walk_neighbor(&next_neighbors, &connected_orgs, min_outbound_orgs);
+        if diversified {
+            debug!(
+                "{:?}: outbound neighbors span {} of {} organizations; walk to {:?} in organization {}",
+                &self.local_peer,
+                connected_orgs.len(),
+                min_outbound_orgs,
+                &next_neighbors[idx].addr,
+                next_neighbors[idx].org
+            );
+            monitoring::increment_diverse_walks_counter();
+        }
+        let next_neighbor = next_neighbors.swap_remove(idx);
+
         let w = NeighborWalk::new(
             self.local_peer.clone(),
             self.chain_view.clone(),
-            &next_neighbors[0],
+            &next_neighbor,
             true,
             self.walk_pingbacks.clone(),
             &self.connection_opts,
@@ -1868,7 +1919,7 @@ impl PeerNetwork {
 
         debug!(
             "{:?}: instantiated neighbor walk to outbound peer {:?}",
-            &self.local_peer, &next_neighbors[0].addr
+            &self.local_peer, &next_neighbor.addr
         );
 
         self.walk = Some(w);
@@ -3112,10 +3163,73 @@ mod test {
     use crate::net::test::*;
     use crate::util_lib::test::*;
     use stacks_common::util::hash::*;
+    use stacks_common::util::secp256k1::Secp256k1PrivateKey;
     use stacks_common::util::sleep_ms;
 
     const TEST_IN_OUT_DEGREES: u64 = 0x1;
 
+    #[test]
+    fn test_choose_walk_neighbor() {
+        let candidates: Vec<Neighbor> = [1u32, 1, 2, 3]
+            .iter()
+            .enumerate()
+            .map(|(i, org)| {
+                let nk = NeighborKey {
+                    peer_version: 0x12345678,
+                    network_id: 0x9abcdef0,
+                    addrbytes: PeerAddress::from_ipv4(127, 0, 0, 1),
+                    port: 20000 + i as u16,
+                };
+                let mut neighbor = Neighbor::empty(
+                    &nk,
+                    &Secp256k1PublicKey::from_private(&Secp256k1PrivateKey::new()),
+                    0,
+                );
+                neighbor.org = *org;
+                neighbor
+            })
+            .collect();
+
+        let mut connected_orgs = HashSet::new();
+
+        // diversity not requested
+        assert_eq!(
+            PeerNetwork::choose_walk_neighbor(&candidates, &connected_orgs, 0),
+            (0, false)
+        );
+
+        // no connected orgs, so the first candidate is already diverse
+        assert_eq!(
+            PeerNetwork::choose_walk_neighbor(&candidates, &connected_orgs, 2),
+            (0, false)
+        );
+
+        // skip candidates in connected orgs
+        connected_orgs.insert(1);
+        assert_eq!(
+            PeerNetwork::choose_walk_neighbor(&candidates, &connected_orgs, 2),
+            (2, true)
+        );
+        connected_orgs.insert(2);
+        assert_eq!(
+            PeerNetwork::choose_walk_neighbor(&candidates, &connected_orgs, 3),
+            (3, true)
+        );
+
+        // target already met
+        assert_eq!(
+            PeerNetwork::choose_walk_neighbor(&candidates, &connected_orgs, 2),
+            (0, false)
+        );
+
+        // no candidate in an unconnected org
+        connected_orgs.insert(3);
+        assert_eq!(
+            PeerNetwork::choose_walk_neighbor(&candidates, &connected_orgs, 4),
+            (0, false)
+        );
+    }
+
     #[test]
     #[ignore]
     fn test_step_walk_1_neighbor_plain() {
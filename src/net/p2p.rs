@@ -47,28 +47,36 @@ use crate::chainstate::burn::db::sortdb::{BlockHeaderCache, SortitionDB};
 use crate::chainstate::burn::BlockSnapshot;
 use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::{MAX_BLOCK_LEN, MAX_TRANSACTION_LEN};
-use crate::monitoring::{update_inbound_neighbors, update_outbound_neighbors};
+use crate::monitoring::{
+    update_inbound_neighbors, update_outbound_neighbors, update_outbound_peer_stats,
+};
+use crate::net::admin::AdminReplayCache;
 use crate::net::asn::ASEntry4;
 use crate::net::atlas::AtlasDB;
 use crate::net::atlas::{AttachmentInstance, AttachmentsDownloader};
 use crate::net::chat::ConversationP2P;
 use crate::net::chat::NeighborStats;
+use crate::net::chat::NUM_HEALTH_POINTS;
 use crate::net::connection::ConnectionOptions;
 use crate::net::connection::NetworkReplyHandle;
 use crate::net::connection::ReplyHandleP2P;
 use crate::net::db::LocalPeer;
+use crate::net::db::PeerBanTarget;
 use crate::net::db::PeerDB;
+use crate::net::db::PEER_REPUTATION_RECORD_INTERVAL;
 use crate::net::download::BlockDownloader;
 use crate::net::inv::*;
 use crate::net::neighbors::*;
 use crate::net::poll::NetworkPollState;
 use crate::net::poll::NetworkState;
 use crate::net::prune::*;
+use crate::net::ratelimit::RPCRateLimiter;
 use crate::net::relay::RelayerStats;
 use crate::net::relay::*;
 use crate::net::relay::*;
 use crate::net::rpc::RPCHandlerArgs;
 use crate::net::server::*;
+use crate::net::txverify::TxVerifierPool;
 use crate::net::Error as net_error;
 use crate::net::Neighbor;
 use crate::net::NeighborKey;
@@ -84,6 +92,7 @@ use stacks_common::util::log;
 use stacks_common::util::secp256k1::Secp256k1PublicKey;
 
 use crate::chainstate::stacks::StacksBlockHeader;
+use crate::core::mempool::SubnetFeeRateEstimate;
 use crate::types::chainstate::SortitionId;
 
 /// inter-thread request to send a p2p message from another thread in this program.
@@ -253,6 +262,9 @@ pub struct PeerNetwork {
     pub relay_handles: HashMap<usize, VecDeque<ReplyHandleP2P>>,
     pub relayer_stats: RelayerStats,
 
+    // worker threads for checking the signatures of pushed transactions
+    tx_verifier: TxVerifierPool,
+
     // handles for other threads to send/receive data to peers
     handles: VecDeque<NetworkHandleServer>,
 
@@ -260,6 +272,7 @@ pub struct PeerNetwork {
     pub network: Option<NetworkState>,
     p2p_network_handle: usize,
     http_network_handle: usize,
+    admin_network_handle: Option<usize>,
 
     // info on the burn chain we're tracking
     pub burnchain: Burnchain,
@@ -313,6 +326,15 @@ pub struct PeerNetwork {
     // http endpoint, used for driving HTTP conversations (some of which we initiate)
     pub http: Option<HttpPeer>,
 
+    // per-client token buckets for rate-limited RPC endpoints
+    pub rpc_rate_limiter: RPCRateLimiter,
+
+    // signed admin requests honored recently, which may not be replayed
+    pub admin_replay_cache: AdminReplayCache,
+
+    // the last subnet fee rate estimate served, and the Stacks chain tip it was computed at
+    pub subnet_fee_estimate: Option<(StacksBlockId, SubnetFeeRateEstimate)>,
+
     // our own neighbor address that we bind on
     bind_nk: NeighborKey,
 
@@ -346,6 +368,9 @@ pub struct PeerNetwork {
     // can't process yet, but might be able to process on the next chain view update
     pub pending_messages: HashMap<usize, Vec<StacksMessage>>,
 
+    // when did we last save the reputations of the peers we are talking to?
+    last_peer_reputation_record_ts: u64,
+
     // fault injection -- force disconnects
     fault_last_disconnect: u64,
 }
@@ -362,9 +387,11 @@ impl PeerNetwork {
         epochs: Vec<StacksEpoch>,
     ) -> PeerNetwork {
         let http = HttpPeer::new(connection_opts.clone(), 0);
+        let rpc_rate_limiter = RPCRateLimiter::new(connection_opts.rpc_rate_limits.clone());
         let pub_ip = connection_opts.public_ip_address.clone();
         let pub_ip_learned = pub_ip.is_none();
         local_peer.public_ip_address = pub_ip.clone();
+        local_peer.subnet_id = connection_opts.subnet_id.clone();
 
         if connection_opts.disable_inbound_handshakes {
             debug!("{:?}: disable inbound handshakes", &local_peer);
@@ -396,10 +423,13 @@ impl PeerNetwork {
             relay_handles: HashMap::new(),
             relayer_stats: RelayerStats::new(),
 
+            tx_verifier: TxVerifierPool::new(connection_opts.tx_verify_threads),
+
             handles: VecDeque::new(),
             network: None,
             p2p_network_handle: 0,
             http_network_handle: 0,
+            admin_network_handle: None,
 
             burnchain: burnchain,
             connection_opts: connection_opts,
@@ -432,6 +462,9 @@ impl PeerNetwork {
             prune_inbound_counts: HashMap::new(),
 
             http: Some(http),
+            rpc_rate_limiter,
+            admin_replay_cache: AdminReplayCache::new(),
+            subnet_fee_estimate: None,
             bind_nk: NeighborKey {
                 network_id: 0,
                 peer_version: 0,
@@ -457,6 +490,8 @@ impl PeerNetwork {
 
             pending_messages: HashMap::new(),
 
+            last_peer_reputation_record_ts: 0,
+
             fault_last_disconnect: 0,
         };
 
@@ -525,6 +560,30 @@ impl PeerNetwork {
         Ok(())
     }
 
+    /// Serve the admin RPC endpoints on a separate listener.  Once bound, admin requests are
+    /// refused on the public RPC listener.  Must be called after `bind()`.
+    pub fn bind_admin(&mut self, admin_addr: &SocketAddr) -> Result<(), net_error> {
+        let admin_handle = match self.network {
+            Some(ref mut network) => network.bind(admin_addr)?,
+            None => {
+                return Err(net_error::NotConnected);
+            }
+        };
+
+        test_debug!("{:?}: bound admin on {:?}", &self.local_peer, admin_addr);
+
+        self.admin_network_handle = Some(admin_handle);
+        PeerNetwork::with_http(self, |_, ref mut http| {
+            http.set_admin_server_handle(admin_handle);
+        });
+        Ok(())
+    }
+
+    /// Is the admin RPC listener bound?
+    pub fn has_admin_listener(&self) -> bool {
+        self.admin_network_handle.is_some()
+    }
+
     /// Run a closure with the network state
     pub fn with_network_state<F, R>(
         peer_network: &mut PeerNetwork,
@@ -797,6 +856,26 @@ impl PeerNetwork {
         ret
     }
 
+    /// Select the outbound peers whose statistics get exported as labeled metrics: the (at most)
+    /// `max_peers` peers we have exchanged the most bytes with.  Each is labeled with its address.
+    pub fn select_peer_stats_metrics<'a, I>(
+        peers: I,
+        max_peers: u64,
+    ) -> Vec<(String, &'a NeighborStats)>
+    where
+        I: Iterator<Item = (NeighborKey, &'a NeighborStats)>,
+    {
+        let mut outbound: Vec<_> = peers.filter(|(_, stats)| stats.outbound).collect();
+        outbound.sort_by(|(_, stats_a), (_, stats_b)| {
+            (stats_b.bytes_rx + stats_b.bytes_tx).cmp(&(stats_a.bytes_rx + stats_a.bytes_tx))
+        });
+        outbound.truncate(max_peers as usize);
+        outbound
+            .into_iter()
+            .map(|(nk, stats)| (nk.addrbytes.to_socketaddr(nk.port).to_string(), stats))
+            .collect()
+    }
+
     /// Count how many connections to a given IP address we have
     pub fn count_ip_connections(
         ipaddr: &SocketAddr,
@@ -856,6 +935,22 @@ impl PeerNetwork {
                 );
                 return Err(net_error::Denied);
             }
+
+            // don't talk if banned
+            if PeerDB::find_peer_ban(
+                &self.peerdb.conn(),
+                &neighbor.addrbytes,
+                None,
+                get_epoch_time_secs(),
+            )?
+            .is_some()
+            {
+                debug!(
+                    "{:?}: Neighbor {:?} is banned; will not connect",
+                    &self.local_peer, neighbor
+                );
+                return Err(net_error::Denied);
+            }
         }
 
         // already connected?
@@ -1137,6 +1232,152 @@ impl PeerNetwork {
         }
     }
 
+    /// Add a conversation's unrecorded offenses to the misbehavior score of its address, and ban
+    /// the address if its score reaches `auto_ban_offense_threshold`.  Returns the banned address,
+    /// if it was banned.
+    fn record_peer_offenses(&mut self, event_id: usize) -> Result<Option<PeerAddress>, net_error> {
+        let (addrbytes, port, offenses) = match self.peers.get_mut(&event_id) {
+            Some(convo) if convo.stats.unrecorded_offenses > 0 => {
+                let offenses = convo.stats.unrecorded_offenses;
+                convo.stats.unrecorded_offenses = 0;
+                (convo.peer_addrbytes.clone(), convo.peer_port, offenses)
+            }
+            _ => {
+                return Ok(None);
+            }
+        };
+        if self.connection_opts.auto_ban_offense_threshold == 0
+            || PeerDB::is_peer_always_allowed(
+                self.peerdb.conn(),
+                self.local_peer.network_id,
+                &addrbytes,
+                port,
+            )?
+        {
+            return Ok(None);
+        }
+
+        let now = get_epoch_time_secs();
+        let mut tx = self.peerdb.tx_begin()?;
+        let score = PeerDB::record_peer_offenses(&mut tx, &addrbytes, offenses, now)?;
+        let banned = if score >= self.connection_opts.auto_ban_offense_threshold {
+            let reason = format!("automatic: {} offenses", score);
+            let ban_id = PeerDB::add_peer_ban(
+                &mut tx,
+                &PeerBanTarget::AddressRange(addrbytes.clone(), 128),
+                &reason,
+                now,
+                Some(now + self.connection_opts.auto_ban_duration),
+            )?;
+            PeerDB::clear_peer_offenses(&mut tx, &addrbytes)?;
+            info!(
+                "{:?}: Banned misbehaving peer address {} for {}s (ban {}: {})",
+                &self.local_peer,
+                &addrbytes.to_socketaddr(port),
+                self.connection_opts.auto_ban_duration,
+                ban_id,
+                &reason
+            );
+            Some(addrbytes)
+        } else {
+            None
+        };
+        tx.commit()
+            .map_err(|e| net_error::DBError(db_error::SqliteError(e)))?;
+        Ok(banned)
+    }
+
+    /// Score the offenses of all conversations, banning misbehaving peer addresses.  Return the
+    /// event IDs of the conversations with banned addresses, to disconnect from.
+    fn process_peer_offenses(&mut self) -> Result<Vec<usize>, net_error> {
+        let offending: Vec<usize> = self
+            .peers
+            .iter()
+            .filter(|(_, convo)| convo.stats.unrecorded_offenses > 0)
+            .map(|(event_id, _)| *event_id)
+            .collect();
+
+        let mut banned = HashSet::new();
+        for event_id in offending.into_iter() {
+            if let Some(addrbytes) = self.record_peer_offenses(event_id)? {
+                banned.insert(addrbytes);
+            }
+        }
+
+        Ok(self
+            .peers
+            .iter()
+            .filter(|(_, convo)| banned.contains(&convo.peer_addrbytes))
+            .map(|(event_id, _)| *event_id)
+            .collect())
+    }
+
+    /// Fold what a conversation has taught us about its peer into the peer's reputation.  Only
+    /// outbound conversations are scored, since only their ports are the ones we connect to.
+    /// The health score is only recorded once the conversation has seen enough messages to have
+    /// one.
+    fn record_peer_reputation(&mut self, event_id: usize) -> Result<(), net_error> {
+        let (neighbor_key, health_score, bandwidth_violations) = match self.peers.get_mut(&event_id)
+        {
+            Some(convo) if convo.stats.outbound => {
+                let health_score = if convo.stats.healthpoints.len() >= NUM_HEALTH_POINTS {
+                    Some(convo.stats.get_health_score())
+                } else {
+                    None
+                };
+                let bandwidth_violations = convo.stats.unrecorded_bandwidth_violations;
+                convo.stats.unrecorded_bandwidth_violations = 0;
+                (convo.to_neighbor_key(), health_score, bandwidth_violations)
+            }
+            _ => {
+                return Ok(());
+            }
+        };
+        if health_score.is_none() && bandwidth_violations == 0 {
+            return Ok(());
+        }
+
+        let mut tx = self.peerdb.tx_begin()?;
+        let reputation = PeerDB::record_peer_reputation(
+            &mut tx,
+            neighbor_key.network_id,
+            &neighbor_key.addrbytes,
+            neighbor_key.port,
+            health_score,
+            bandwidth_violations,
+            get_epoch_time_secs(),
+        )?;
+        tx.commit()
+            .map_err(|e| net_error::DBError(db_error::SqliteError(e)))?;
+        debug!(
+            "{:?}: Reputation of {:?} is now {:?}",
+            &self.local_peer, &neighbor_key, &reputation
+        );
+        Ok(())
+    }
+
+    /// Save the reputations of all the peers we are talking to, so they survive a restart, and
+    /// forget the reputations of peers we have not talked to in a long time.  Does nothing if
+    /// this was done less than `PEER_REPUTATION_RECORD_INTERVAL` seconds ago.
+    fn record_peer_reputations(&mut self) -> Result<(), net_error> {
+        let now = get_epoch_time_secs();
+        if self.last_peer_reputation_record_ts + PEER_REPUTATION_RECORD_INTERVAL > now {
+            return Ok(());
+        }
+        self.last_peer_reputation_record_ts = now;
+
+        let event_ids: Vec<usize> = self.peers.keys().cloned().collect();
+        for event_id in event_ids.into_iter() {
+            self.record_peer_reputation(event_id)?;
+        }
+
+        let mut tx = self.peerdb.tx_begin()?;
+        PeerDB::prune_peer_reputations(&mut tx, now)?;
+        tx.commit()
+            .map_err(|e| net_error::DBError(db_error::SqliteError(e)))?;
+        Ok(())
+    }
+
     /// Process ban requests.  Update the deny in the peer database.  Return the vec of event IDs to disconnect from.
     fn process_bans(&mut self) -> Result<Vec<usize>, net_error> {
         if cfg!(test) && self.connection_opts.disable_network_bans {
@@ -1313,6 +1554,20 @@ impl PeerNetwork {
             return Err(net_error::Denied);
         }
 
+        // banned?
+        if let Some(ban) = PeerDB::find_peer_ban(
+            &self.peerdb.conn(),
+            &neighbor_key.addrbytes,
+            None,
+            get_epoch_time_secs(),
+        )? {
+            info!(
+                "{:?}: Peer {:?} is banned (ban {}: {}); dropping",
+                &self.local_peer, neighbor_key, ban.ban_id, &ban.reason
+            );
+            return Err(net_error::Denied);
+        }
+
         // already connected?
         if let Some(event_id) = self.get_event_id(&neighbor_key) {
             test_debug!(
@@ -1351,6 +1606,22 @@ impl PeerNetwork {
             return Err(net_error::ConnectionCycle);
         }
 
+        // banned key?
+        if PeerDB::find_peer_ban(
+            &self.peerdb.conn(),
+            &nk.addrbytes,
+            Some(pubkh),
+            get_epoch_time_secs(),
+        )?
+        .is_some()
+        {
+            debug!(
+                "{:?}: Peer {:?} with key {} is banned",
+                &self.local_peer, nk, pubkh
+            );
+            return Err(net_error::Denied);
+        }
+
         self.can_register_peer(nk, outbound).and_then(|_| {
             let other_events = self.get_pubkey_events(pubkh);
             if other_events.len() > 0 {
@@ -1485,6 +1756,24 @@ impl PeerNetwork {
         self.peers.get(&event_id)
     }
 
+    /// Record how long a neighbor took to answer one of our block or microblock requests
+    pub fn add_block_request_latency(&mut self, neighbor_key: &NeighborKey, latency_ms: u64) {
+        if let Some(event_id) = self.events.get(neighbor_key) {
+            if let Some(convo) = self.peers.get_mut(event_id) {
+                convo.stats.add_block_request_latency(latency_ms);
+            }
+        }
+    }
+
+    /// Score a neighbor as a source of blocks (see NeighborStats::get_block_download_score).
+    /// Neighbors we aren't talking to are scored like a peer we know nothing about.
+    pub fn get_block_download_score(&self, neighbor_key: &NeighborKey) -> f64 {
+        match self.get_convo(neighbor_key) {
+            Some(convo) => convo.stats.get_block_download_score(),
+            None => NeighborStats::new(false).get_block_download_score(),
+        }
+    }
+
     /// Deregister a socket from our p2p network instance.
     fn deregister_socket(&mut self, event_id: usize, socket: mio_net::TcpStream) -> () {
         match self.network {
@@ -1499,6 +1788,20 @@ impl PeerNetwork {
     pub fn deregister_peer(&mut self, event_id: usize) -> () {
         debug!("{:?}: Disconnect event {}", &self.local_peer, event_id);
 
+        // peers that send invalid preambles are disconnected straight away, so score them first
+        if let Err(e) = self.record_peer_offenses(event_id) {
+            warn!(
+                "{:?}: Failed to record offenses of event {}: {:?}",
+                &self.local_peer, event_id, &e
+            );
+        }
+        if let Err(e) = self.record_peer_reputation(event_id) {
+            warn!(
+                "{:?}: Failed to record reputation of event {}: {:?}",
+                &self.local_peer, event_id, &e
+            );
+        }
+
         let mut nk_remove: Vec<NeighborKey> = vec![];
         for (neighbor_key, ev_id) in self.events.iter() {
             if *ev_id == event_id {
@@ -1586,7 +1889,8 @@ impl PeerNetwork {
                 Some(ref mut convo) => convo.sign_message(
                     &self.chain_view,
                     &self.local_peer.private_key,
-                    message_payload,
+                    // until the peer handshakes with us, go by the version we last knew it by
+                    message_payload.for_peer_version(peer_key.peer_version),
                 ),
             },
         }
@@ -3148,7 +3452,9 @@ impl PeerNetwork {
         self.mempool_sync_timeout = 0;
     }
 
-    /// Pick a peer to mempool sync with.
+    /// Pick a peer to mempool sync with.  This is either a random outbound peer that supports
+    /// mempool queries, or one of the nodes in `mempool_sync_http_urls`, which we do not need a
+    /// p2p connection to.
     /// Returns Ok(None) if we're done syncing the mempool.
     /// Returns Ok(Some(..)) if we're not done, and can proceed
     /// Returns the new sync state -- either ResolveURL if we need to resolve a data URL,
@@ -3158,53 +3464,39 @@ impl PeerNetwork {
         dns_client_opt: &mut Option<&mut DNSClient>,
         page_id: &Txid,
     ) -> Result<Option<MempoolSyncState>, net_error> {
-        if self.peers.len() == 0 {
+        if self.peers.len() == 0 && self.connection_opts.mempool_sync_http_urls.len() == 0 {
             debug!("No peers connected; cannot do mempool sync");
             return Ok(None);
         }
 
-        let mut idx = thread_rng().gen::<usize>() % self.peers.len();
-        let mut mempool_sync_data_url = None;
-        for _ in 0..self.peers.len() + 1 {
-            let event_id = match self.peers.keys().skip(idx).next() {
-                Some(eid) => *eid,
-                None => {
-                    idx = 0;
-                    continue;
-                }
-            };
-            idx = (idx + 1) % self.peers.len();
-
-            if let Some(convo) = self.peers.get(&event_id) {
-                if !convo.is_authenticated() || !convo.is_outbound() {
-                    continue;
-                }
-                if !ConversationP2P::supports_mempool_query(convo.peer_services) {
-                    continue;
-                }
-                if convo.data_url.len() == 0 {
-                    continue;
-                }
-                let url = convo.data_url.clone();
-                if dns_client_opt.is_none() {
-                    if let Ok(Some(_)) = PeerNetwork::try_get_url_ip(&url) {
-                    } else {
-                        // need a DNS client for this one
-                        continue;
-                    }
-                }
-
-                mempool_sync_data_url = Some(url);
-                break;
+        let mut candidate_urls = vec![];
+        for (_, convo) in self.peers.iter() {
+            if !convo.is_authenticated() || !convo.is_outbound() {
+                continue;
             }
+            if !ConversationP2P::supports_mempool_query(convo.peer_services) {
+                continue;
+            }
+            if convo.data_url.len() == 0 {
+                continue;
+            }
+            candidate_urls.push(convo.data_url.clone());
         }
+        candidate_urls.extend(self.connection_opts.mempool_sync_http_urls.iter().cloned());
 
-        if let Some(url) = mempool_sync_data_url {
-            self.mempool_sync_begin_resolve_data_url(url, dns_client_opt, page_id)
-        } else {
+        if dns_client_opt.is_none() {
+            // without a DNS client, we can only query URLs that contain an IP address
+            candidate_urls.retain(|url| matches!(PeerNetwork::try_get_url_ip(url), Ok(Some(_))));
+        }
+
+        if candidate_urls.len() == 0 {
             debug!("No peer has a data URL, so no mempool sync can happen");
-            Ok(None)
+            return Ok(None);
         }
+
+        let idx = thread_rng().gen::<usize>() % candidate_urls.len();
+        let url = candidate_urls.swap_remove(idx);
+        self.mempool_sync_begin_resolve_data_url(url, dns_client_opt, page_id)
     }
 
     /// Begin resolving the DNS host of a data URL for mempool sync.
@@ -3331,6 +3623,7 @@ impl PeerNetwork {
             ),
             sync_data,
             Some(page_id),
+            Some(mempool.get_bloom_config().depth),
         );
 
         let event_id = self.connect_or_send_http_request(
@@ -4865,10 +5158,11 @@ impl PeerNetwork {
         }
     }
 
-    /// Get the local peer from the peer DB, but also preserve the public IP address
+    /// Get the local peer from the peer DB, but also preserve the public IP address and subnet ID
     pub fn load_local_peer(&self) -> Result<LocalPeer, net_error> {
         let mut lp = PeerDB::get_local_peer(&self.peerdb.conn())?;
         lp.public_ip_address = self.local_peer.public_ip_address.clone();
+        lp.subnet_id = self.local_peer.subnet_id.clone();
         Ok(lp)
     }
 
@@ -5016,6 +5310,7 @@ impl PeerNetwork {
             // (only do this if we're done with all other tasks).
             // Also, process banned peers.
             let mut dead_events = self.process_bans()?;
+            dead_events.append(&mut self.process_peer_offenses()?);
             for dead in dead_events.drain(..) {
                 debug!(
                     "{:?}: Banned connection on event {}",
@@ -5023,6 +5318,12 @@ impl PeerNetwork {
                 );
                 self.deregister_peer(dead);
             }
+            if let Err(e) = self.record_peer_reputations() {
+                warn!(
+                    "{:?}: Failed to record peer reputations: {:?}",
+                    &self.local_peer, &e
+                );
+            }
             self.prune_connections();
         }
 
@@ -5085,6 +5386,16 @@ impl PeerNetwork {
         update_outbound_neighbors(outbound_neighbors as i64);
         update_inbound_neighbors(inbound_neighbors as i64);
 
+        if self.connection_opts.max_peer_stats_metrics > 0 {
+            let peer_stats = PeerNetwork::select_peer_stats_metrics(
+                self.peers
+                    .values()
+                    .map(|convo| (convo.to_neighbor_key(), &convo.stats)),
+                self.connection_opts.max_peer_stats_metrics,
+            );
+            update_outbound_peer_stats(&peer_stats);
+        }
+
         // fault injection -- periodically disconnect from everyone
         if cfg!(test) {
             if let Some(disconnect_interval) = self.connection_opts.force_disconnect_interval {
@@ -5209,6 +5520,56 @@ impl PeerNetwork {
         Ok(())
     }
 
+    /// Check the signatures of all transactions pushed to us on the verifier pool, and drop the
+    /// invalid ones before the relayer tries to store them.  Each pushing peer's stats record
+    /// whether or not its transactions were valid.
+    fn preverify_pushed_transactions(&mut self, network_result: &mut NetworkResult) -> () {
+        let mut origins = vec![];
+        let mut txs = vec![];
+        for (nk, tx_data) in network_result.pushed_transactions.drain() {
+            for (relayers, tx) in tx_data.into_iter() {
+                origins.push(Some((nk.clone(), relayers)));
+                txs.push(tx);
+            }
+        }
+        if txs.len() == 0 {
+            return;
+        }
+
+        for (i, tx, valid) in self.tx_verifier.verify_all(txs).into_iter() {
+            let (nk, relayers) = match origins.get_mut(i).and_then(|origin| origin.take()) {
+                Some(origin) => origin,
+                None => {
+                    continue;
+                }
+            };
+
+            if let Some(event_id) = self.events.get(&nk) {
+                if let Some(convo) = self.peers.get_mut(event_id) {
+                    convo.stats.add_transaction_sig_check(valid);
+                }
+            }
+
+            if !valid {
+                debug!(
+                    "{:?}: Drop transaction {} from {:?}: invalid signature",
+                    &self.local_peer,
+                    &tx.txid(),
+                    &nk
+                );
+                continue;
+            }
+
+            if let Some(tx_data) = network_result.pushed_transactions.get_mut(&nk) {
+                tx_data.push((relayers, tx));
+            } else {
+                network_result
+                    .pushed_transactions
+                    .insert(nk, vec![(relayers, tx)]);
+            }
+        }
+    }
+
     /// Top-level main-loop circuit to take.
     /// -- polls the peer network and http network server sockets to get new sockets and detect ready sockets
     /// -- carries out network conversations
@@ -5246,6 +5607,9 @@ impl PeerNetwork {
         let http_poll_state = poll_states
             .remove(&self.http_network_handle)
             .expect("BUG: no poll state for http network handle");
+        let admin_poll_state = self
+            .admin_network_handle
+            .and_then(|handle| poll_states.remove(&handle));
 
         let mut network_result = NetworkResult::new(
             self.num_state_machine_passes,
@@ -5257,8 +5621,10 @@ impl PeerNetwork {
         self.refresh_local_peer()?;
 
         // update burnchain view, before handling any HTTP connections
+        let prev_burnchain_tip = self.burnchain_tip.clone();
         let unsolicited_buffered_messages = self.refresh_burnchain_view(sortdb, chainstate, ibd)?;
         network_result.consume_unsolicited(unsolicited_buffered_messages);
+        self.snapshot_chain_divergence(&prev_burnchain_tip, sortdb, chainstate, mempool);
 
         // update PoX view, before handling any HTTP connections
         self.refresh_sortition_view(sortdb)?;
@@ -5290,6 +5656,7 @@ impl PeerNetwork {
                     chainstate,
                     mempool,
                     http_poll_state,
+                    admin_poll_state,
                     handler_args,
                 )
             })?;
@@ -5308,6 +5675,8 @@ impl PeerNetwork {
             p2p_poll_state,
         )?;
 
+        self.preverify_pushed_transactions(&mut network_result);
+
         debug!("<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<< End Network Dispatch <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<");
         Ok(network_result)
     }
@@ -6141,4 +6510,195 @@ mod test {
             }
         });
     }
+
+    #[test]
+    fn test_mempool_sync_http_only() {
+        with_timeout(600, || {
+            // peer 1 gets some transactions; verify peer 2 gets them all without ever connecting
+            // to peer 1 over p2p
+            let mut peer_1_config = TestPeerConfig::new("test_mempool_sync_http_only", 2218, 2219);
+            let mut peer_2_config = TestPeerConfig::new("test_mempool_sync_http_only", 2220, 2221);
+
+            peer_1_config.connection_opts.mempool_sync_interval = 1;
+            peer_2_config.connection_opts.mempool_sync_interval = 1;
+            peer_2_config.connection_opts.mempool_sync_http_urls =
+                vec![peer_1_config.data_url.clone()];
+
+            let num_txs = 10;
+            let pks: Vec<_> = (0..num_txs).map(|_| StacksPrivateKey::new()).collect();
+            let addrs: Vec<_> = pks.iter().map(|pk| to_addr(pk)).collect();
+            let initial_balances: Vec<_> = addrs
+                .iter()
+                .map(|a| (a.to_account_principal(), 1000000000))
+                .collect();
+
+            peer_1_config.initial_balances = initial_balances.clone();
+            peer_2_config.initial_balances = initial_balances.clone();
+
+            let mut peer_1 = TestPeer::new(peer_1_config);
+            let mut peer_2 = TestPeer::new(peer_2_config);
+
+            let num_blocks = 10;
+            for _ in 0..num_blocks {
+                let (burn_ops, stacks_block, microblocks) = peer_2.make_default_tenure();
+
+                peer_1.next_burnchain_block(burn_ops.clone());
+                peer_2.next_burnchain_block(burn_ops.clone());
+
+                peer_1.process_stacks_epoch_at_tip(&stacks_block, &microblocks);
+                peer_2.process_stacks_epoch_at_tip(&stacks_block, &microblocks);
+            }
+
+            let addr = StacksAddress {
+                version: C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+                bytes: Hash160([0xff; 20]),
+            };
+
+            let mut txs = HashMap::new();
+            let mut peer_1_mempool = peer_1.mempool.take().unwrap();
+            let mut mempool_tx = peer_1_mempool.tx_begin().unwrap();
+            for i in 0..num_txs {
+                let pk = &pks[i];
+                let mut tx = StacksTransaction {
+                    version: TransactionVersion::Testnet,
+                    chain_id: 0x80000000,
+                    auth: TransactionAuth::from_p2pkh(&pk).unwrap(),
+                    anchor_mode: TransactionAnchorMode::Any,
+                    post_condition_mode: TransactionPostConditionMode::Allow,
+                    post_conditions: vec![],
+                    payload: TransactionPayload::TokenTransfer(
+                        addr.to_account_principal(),
+                        123,
+                        TokenTransferMemo([0u8; 34]),
+                    ),
+                };
+                tx.set_tx_fee(1000);
+                tx.set_origin_nonce(0);
+
+                let mut tx_signer = StacksTransactionSigner::new(&tx);
+                tx_signer.sign_origin(&pk).unwrap();
+
+                let tx = tx_signer.get_tx().unwrap();
+
+                let txid = tx.txid();
+                let tx_bytes = tx.serialize_to_vec();
+                let origin_addr = tx.origin_address();
+                let origin_nonce = tx.get_origin_nonce();
+                let sponsor_addr = tx.sponsor_address().unwrap_or(origin_addr.clone());
+                let sponsor_nonce = tx.get_sponsor_nonce().unwrap_or(origin_nonce);
+                let tx_fee = tx.get_tx_fee();
+
+                txs.insert(tx.txid(), tx.clone());
+
+                // should succeed
+                MemPoolDB::try_add_tx(
+                    &mut mempool_tx,
+                    peer_1.chainstate(),
+                    &ConsensusHash([0x1 + (num_blocks as u8); 20]),
+                    &BlockHeaderHash([0x2 + (num_blocks as u8); 32]),
+                    txid.clone(),
+                    tx_bytes,
+                    tx_fee,
+                    num_blocks as u64,
+                    &origin_addr,
+                    origin_nonce,
+                    &sponsor_addr,
+                    sponsor_nonce,
+                    None,
+                )
+                .unwrap();
+            }
+            mempool_tx.commit().unwrap();
+            peer_1.mempool = Some(peer_1_mempool);
+
+            let mut round = 0;
+            let mut peer_2_mempool_txs = 0;
+
+            while peer_2_mempool_txs < num_txs {
+                let _ = peer_1.step();
+
+                if let Ok(mut result) = peer_2.step() {
+                    let lp = peer_2.network.local_peer.clone();
+                    peer_2
+                        .with_db_state(|sortdb, chainstate, relayer, mempool| {
+                            relayer.process_network_result(
+                                &lp,
+                                &mut result,
+                                sortdb,
+                                chainstate,
+                                mempool,
+                                false,
+                                None,
+                                None,
+                            )
+                        })
+                        .unwrap();
+                }
+
+                // the peers never talk over p2p
+                assert_eq!(peer_1.network.num_peers(), 0);
+                assert_eq!(peer_2.network.num_peers(), 0);
+
+                round += 1;
+
+                let mp = peer_2.mempool.take().unwrap();
+                peer_2_mempool_txs = MemPoolDB::get_all_txs(mp.conn()).unwrap().len();
+                peer_2.mempool.replace(mp);
+
+                info!("Peer 2: {}", peer_2_mempool_txs);
+            }
+
+            info!("Completed mempool sync in {} step(s)", round);
+
+            let mp = peer_2.mempool.take().unwrap();
+            let peer_2_mempool_txs = MemPoolDB::get_all_txs(mp.conn()).unwrap();
+            peer_2.mempool.replace(mp);
+
+            for tx in peer_2_mempool_txs {
+                assert_eq!(&tx.tx, txs.get(&tx.tx.txid()).unwrap());
+            }
+        });
+    }
+
+    #[test]
+    fn test_select_peer_stats_metrics() {
+        let peers: Vec<(NeighborKey, NeighborStats)> =
+            [(true, 100u64), (false, 1000), (true, 300), (true, 200)]
+                .iter()
+                .enumerate()
+                .map(|(i, (outbound, bytes))| {
+                    let nk = NeighborKey {
+                        peer_version: 0x12345678,
+                        network_id: 0x9abcdef0,
+                        addrbytes: PeerAddress::from_ipv4(127, 0, 0, 1),
+                        port: 20000 + i as u16,
+                    };
+                    let mut stats = NeighborStats::new(*outbound);
+                    stats.bytes_rx = *bytes;
+                    stats.bytes_tx = *bytes;
+                    (nk, stats)
+                })
+                .collect();
+
+        // inbound peers are never exported, and the busiest outbound peers come first
+        let selected = PeerNetwork::select_peer_stats_metrics(
+            peers.iter().map(|(nk, stats)| (nk.clone(), stats)),
+            2,
+        );
+        let labels: Vec<_> = selected.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, vec!["127.0.0.1:20002", "127.0.0.1:20003"]);
+
+        let selected = PeerNetwork::select_peer_stats_metrics(
+            peers.iter().map(|(nk, stats)| (nk.clone(), stats)),
+            10,
+        );
+        assert_eq!(selected.len(), 3);
+        assert_eq!(selected[2].1.bytes_rx, 100);
+
+        let selected = PeerNetwork::select_peer_stats_metrics(
+            peers.iter().map(|(nk, stats)| (nk.clone(), stats)),
+            0,
+        );
+        assert!(selected.is_empty());
+    }
 }
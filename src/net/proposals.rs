@@ -0,0 +1,224 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use crate::types::chainstate::BlockHeaderHash;
+
+/// How many L1 blocks below the highest one it has seen a `BlockProposalLog` remembers
+const PROPOSAL_LOG_DEPTH: u64 = 1024;
+
+/// Which members of a multi-miner subnet's signing set may propose a block for each L1 block.
+///
+/// With leader rotation, exactly one member leads each L1 block, chosen round-robin by L1 block
+/// height from the signing set ordered by public key, so that every member agrees on the leader
+/// without any extra messages.  The member after the leader is its fallback, which proposes in
+/// its place if the leader does not.  Without leader rotation, every member may propose.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockProposerSchedule {
+    /// Compressed public keys of every miner in the signing set, sorted
+    signing_set: Vec<[u8; 33]>,
+    leader_rotation: bool,
+}
+
+impl BlockProposerSchedule {
+    pub fn new(mut signing_set: Vec<[u8; 33]>, leader_rotation: bool) -> BlockProposerSchedule {
+        signing_set.sort();
+        signing_set.dedup();
+        BlockProposerSchedule {
+            signing_set,
+            leader_rotation,
+        }
+    }
+
+    /// Public key of the miner that should propose the block for the L1 block at `burn_height`.
+    /// Returns None if leader rotation is disabled or the signing set is empty.
+    pub fn leader_for_height(&self, burn_height: u64) -> Option<&[u8; 33]> {
+        if !self.leader_rotation || self.signing_set.len() == 0 {
+            return None;
+        }
+        let index = burn_height % (self.signing_set.len() as u64);
+        self.signing_set.get(index as usize)
+    }
+
+    /// Public key of the miner that takes over from the leader for the L1 block at
+    /// `burn_height` if the leader does not propose: the next member of the signing set.
+    /// Returns None if leader rotation is disabled, or there is no other member to take over.
+    pub fn fallback_for_height(&self, burn_height: u64) -> Option<&[u8; 33]> {
+        if !self.leader_rotation || self.signing_set.len() < 2 {
+            return None;
+        }
+        let index = burn_height.wrapping_add(1) % (self.signing_set.len() as u64);
+        self.signing_set.get(index as usize)
+    }
+
+    /// May the miner with the compressed public key `public_key` propose a block for the L1
+    /// block at `burn_height`?  Only the leader and its fallback may, or, without leader
+    /// rotation, any member of the signing set.
+    pub fn may_propose(&self, burn_height: u64, public_key: &[u8; 33]) -> bool {
+        if !self.signing_set.contains(public_key) {
+            return false;
+        }
+        match self.leader_for_height(burn_height) {
+            Some(leader) => {
+                leader == public_key || self.fallback_for_height(burn_height) == Some(public_key)
+            }
+            None => true,
+        }
+    }
+}
+
+/// The block proposals a multi-miner subnet node has seen and made, by the height of the L1
+/// block they were proposed for.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlockProposalLog {
+    /// For each L1 block height at which a proposal was seen, the block this node signed or
+    /// proposed for it, if any
+    heights: BTreeMap<u64, Option<BlockHeaderHash>>,
+}
+
+impl BlockProposalLog {
+    fn prune(&mut self) {
+        let highest = match self.heights.keys().next_back() {
+            Some(highest) => *highest,
+            None => return,
+        };
+        let lowest_kept = highest.saturating_sub(PROPOSAL_LOG_DEPTH);
+        self.heights = self.heights.split_off(&lowest_kept);
+    }
+}
+
+/// A node's `BlockProposalLog`, shared between the RPC interface, which records the proposals
+/// it is sent, and the relayer, which records its own proposals and checks whether the leader
+/// proposed before taking over from it.  Clones share the same log.
+#[derive(Debug, Clone, Default)]
+pub struct BlockProposalLogHandle(Arc<Mutex<BlockProposalLog>>);
+
+impl BlockProposalLogHandle {
+    /// Note that a miner that may propose for the L1 block at `burn_height` proposed a block.
+    pub fn note_proposal(&self, burn_height: u64) {
+        let mut log = self.0.lock().expect("Block proposal log lock poisoned");
+        log.heights.entry(burn_height).or_insert(None);
+        log.prune();
+    }
+
+    /// Has a block been proposed for the L1 block at `burn_height`, by another miner or by this
+    /// node?
+    pub fn has_seen_proposal(&self, burn_height: u64) -> bool {
+        let log = self.0.lock().expect("Block proposal log lock poisoned");
+        log.heights.contains_key(&burn_height)
+    }
+
+    /// The block this node signed or proposed for the L1 block at `burn_height`, if any
+    pub fn committed_block(&self, burn_height: u64) -> Option<BlockHeaderHash> {
+        let log = self.0.lock().expect("Block proposal log lock poisoned");
+        log.heights.get(&burn_height).cloned().flatten()
+    }
+
+    /// Record that this node signs or proposes `block_hash` for the L1 block at `burn_height`,
+    /// unless it already signed or proposed a different block for it.  Returns whether or not
+    /// the block was recorded.
+    pub fn try_commit_to_block(&self, burn_height: u64, block_hash: &BlockHeaderHash) -> bool {
+        let mut log = self.0.lock().expect("Block proposal log lock poisoned");
+        let committed = log.heights.entry(burn_height).or_insert(None);
+        match committed {
+            Some(committed_hash) => committed_hash == block_hash,
+            None => {
+                *committed = Some(block_hash.clone());
+                log.prune();
+                true
+            }
+        }
+    }
+}
+
+impl PartialEq for BlockProposalLogHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn only_leader_and_fallback_may_propose() {
+        let keys: Vec<_> = (0..4).map(|i| [i as u8; 33]).collect();
+        let schedule = BlockProposerSchedule::new(keys.clone(), true);
+        let stranger = [0xff; 33];
+
+        for burn_height in 0..8 {
+            let leader = schedule.leader_for_height(burn_height).unwrap().clone();
+            let fallback = schedule.fallback_for_height(burn_height).unwrap().clone();
+            assert_ne!(leader, fallback);
+
+            let proposers: Vec<_> = keys
+                .iter()
+                .filter(|key| schedule.may_propose(burn_height, key))
+                .cloned()
+                .collect();
+            assert_eq!(proposers.len(), 2);
+            assert!(proposers.contains(&leader));
+            assert!(proposers.contains(&fallback));
+            assert!(!schedule.may_propose(burn_height, &stranger));
+        }
+
+        // without leader rotation, any member may propose, but nobody else
+        let schedule = BlockProposerSchedule::new(keys.clone(), false);
+        for key in keys.iter() {
+            assert!(schedule.may_propose(0, key));
+        }
+        assert!(!schedule.may_propose(0, &stranger));
+    }
+
+    #[test]
+    fn proposal_log_commits_to_one_block_per_height() {
+        let log = BlockProposalLogHandle::default();
+        let block_a = BlockHeaderHash([0x0a; 32]);
+        let block_b = BlockHeaderHash([0x0b; 32]);
+
+        assert!(!log.has_seen_proposal(5));
+        log.note_proposal(5);
+        assert!(log.has_seen_proposal(5));
+        assert!(!log.has_seen_proposal(6));
+
+        // the same block may be signed again, but not a different one
+        assert_eq!(log.committed_block(5), None);
+        assert!(log.try_commit_to_block(5, &block_a));
+        assert!(log.try_commit_to_block(5, &block_a));
+        assert_eq!(log.committed_block(5), Some(block_a.clone()));
+        assert!(!log.try_commit_to_block(5, &block_b));
+
+        // clones share the log, and other heights are unaffected
+        let shared = log.clone();
+        assert!(shared.try_commit_to_block(6, &block_b));
+        assert!(log.has_seen_proposal(6));
+        assert!(!log.try_commit_to_block(6, &block_a));
+    }
+
+    #[test]
+    fn proposal_log_forgets_old_heights() {
+        let log = BlockProposalLogHandle::default();
+        log.note_proposal(1);
+        log.note_proposal(1 + PROPOSAL_LOG_DEPTH);
+        assert!(log.has_seen_proposal(1));
+        log.note_proposal(2 + PROPOSAL_LOG_DEPTH);
+        assert!(!log.has_seen_proposal(1));
+        assert!(log.has_seen_proposal(1 + PROPOSAL_LOG_DEPTH));
+    }
+}
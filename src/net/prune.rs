@@ -52,7 +52,7 @@ use rand::thread_rng;
 impl PeerNetwork {
     /// Find out which organizations have which of our outbound neighbors.
     /// Gives back a map from the organization ID to the list of (neighbor, neighbor-stats) tuples
-    fn org_neighbor_distribution(
+    pub(crate) fn org_neighbor_distribution(
         &self,
         peer_dbconn: &DBConn,
         preserve: &HashSet<usize>,
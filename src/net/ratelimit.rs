@@ -0,0 +1,288 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// Tokens are tracked in thousandths, so that a bucket refilling at `rate` tokens per second
+/// gains `rate` of them per millisecond.
+const MILLITOKENS_PER_TOKEN: u64 = 1000;
+
+/// How often to forget the buckets of clients that have gone quiet
+const PRUNE_INTERVAL_MS: u128 = 60_000;
+
+/// Classes of RPC endpoints whose requests are rate-limited separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RPCRateLimitClass {
+    /// read-only function calls, which run Clarity code on the node
+    ReadOnlyCall,
+    /// transaction broadcasts, which check and store transactions in the mempool
+    TxBroadcast,
+    /// block proposal validations, which process a whole candidate block on the node
+    BlockValidation,
+    /// data map listings, which walk a contract's map in the MARF
+    MapIteration,
+    /// MARF and transaction proofs, which hash their way up the node's indexes
+    Proof,
+    /// mempool sync queries, which scan the mempool against the client's bloom filter
+    MempoolSync,
+    /// mempool listings and summaries, which page through and aggregate the mempool
+    MempoolListing,
+    /// block state diffs, which read back every state write a block made
+    StateDiff,
+}
+
+impl RPCRateLimitClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RPCRateLimitClass::ReadOnlyCall => "read_only_call",
+            RPCRateLimitClass::TxBroadcast => "tx_broadcast",
+            RPCRateLimitClass::BlockValidation => "block_validation",
+            RPCRateLimitClass::MapIteration => "map_iteration",
+            RPCRateLimitClass::Proof => "proof",
+            RPCRateLimitClass::MempoolSync => "mempool_sync",
+            RPCRateLimitClass::MempoolListing => "mempool_listing",
+            RPCRateLimitClass::StateDiff => "state_diff",
+        }
+    }
+}
+
+/// A token bucket: requests are admitted at `rate` per second on average, in bursts of up to
+/// `burst` requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenBucketLimit {
+    pub rate: u64,
+    pub burst: u64,
+}
+
+/// Node limits on how often each client IP address may call each class of rate-limited RPC
+/// endpoint.  `None` means no limit.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RPCRateLimits {
+    pub read_only_call: Option<TokenBucketLimit>,
+    pub tx_broadcast: Option<TokenBucketLimit>,
+    pub block_validation: Option<TokenBucketLimit>,
+    pub map_iteration: Option<TokenBucketLimit>,
+    pub proof: Option<TokenBucketLimit>,
+    pub mempool_sync: Option<TokenBucketLimit>,
+    pub mempool_listing: Option<TokenBucketLimit>,
+    pub state_diff: Option<TokenBucketLimit>,
+}
+
+impl RPCRateLimits {
+    pub fn limit_for(&self, class: RPCRateLimitClass) -> Option<&TokenBucketLimit> {
+        match class {
+            RPCRateLimitClass::ReadOnlyCall => self.read_only_call.as_ref(),
+            RPCRateLimitClass::TxBroadcast => self.tx_broadcast.as_ref(),
+            RPCRateLimitClass::BlockValidation => self.block_validation.as_ref(),
+            RPCRateLimitClass::MapIteration => self.map_iteration.as_ref(),
+            RPCRateLimitClass::Proof => self.proof.as_ref(),
+            RPCRateLimitClass::MempoolSync => self.mempool_sync.as_ref(),
+            RPCRateLimitClass::MempoolListing => self.mempool_listing.as_ref(),
+            RPCRateLimitClass::StateDiff => self.state_diff.as_ref(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TokenBucket {
+    millitokens: u64,
+    last_refill_ms: u128,
+}
+
+impl TokenBucket {
+    /// How many millitokens the bucket holds at `now_ms`, once refilled
+    fn refilled(&self, limit: &TokenBucketLimit, now_ms: u128) -> u64 {
+        let elapsed_ms =
+            u64::try_from(now_ms.saturating_sub(self.last_refill_ms)).unwrap_or(u64::MAX);
+        self.millitokens
+            .saturating_add(elapsed_ms.saturating_mul(limit.rate))
+            .min(limit.burst.saturating_mul(MILLITOKENS_PER_TOKEN))
+    }
+}
+
+/// Per-client-IP token buckets for each class of rate-limited RPC endpoint
+#[derive(Debug, Clone)]
+pub struct RPCRateLimiter {
+    limits: RPCRateLimits,
+    buckets: HashMap<(IpAddr, RPCRateLimitClass), TokenBucket>,
+    next_prune_ms: u128,
+}
+
+impl RPCRateLimiter {
+    pub fn new(limits: RPCRateLimits) -> RPCRateLimiter {
+        RPCRateLimiter {
+            limits,
+            buckets: HashMap::new(),
+            next_prune_ms: 0,
+        }
+    }
+
+    /// Take a token from `addr`'s bucket for `class`.  See `try_acquire_n`.
+    pub fn try_acquire(&mut self, addr: IpAddr, class: RPCRateLimitClass, now_ms: u128) -> bool {
+        self.try_acquire_n(addr, class, 1, now_ms)
+    }
+
+    /// Take `tokens` tokens from `addr`'s bucket for `class`, refilling it first for the time
+    /// elapsed since it was last used.  Returns false if the bucket holds fewer, in which case
+    /// the request must be rejected and no tokens are taken.  A request that costs more than
+    /// the burst is never admitted.
+    pub fn try_acquire_n(
+        &mut self,
+        addr: IpAddr,
+        class: RPCRateLimitClass,
+        tokens: u64,
+        now_ms: u128,
+    ) -> bool {
+        let limit = match self.limits.limit_for(class) {
+            Some(limit) => limit,
+            None => {
+                return true;
+            }
+        };
+        let capacity = limit.burst.saturating_mul(MILLITOKENS_PER_TOKEN);
+        let limit = limit.clone();
+        if now_ms >= self.next_prune_ms {
+            self.prune(now_ms);
+            self.next_prune_ms = now_ms.saturating_add(PRUNE_INTERVAL_MS);
+        }
+
+        let bucket = self
+            .buckets
+            .entry((addr, class))
+            .or_insert_with(|| TokenBucket {
+                millitokens: capacity,
+                last_refill_ms: now_ms,
+            });
+
+        bucket.millitokens = bucket.refilled(&limit, now_ms);
+        bucket.last_refill_ms = now_ms;
+
+        let millitokens = tokens.saturating_mul(MILLITOKENS_PER_TOKEN);
+        if bucket.millitokens < millitokens {
+            return false;
+        }
+        bucket.millitokens -= millitokens;
+        true
+    }
+
+    /// Forget the buckets that would have refilled completely by `now_ms`, since a full bucket
+    /// is no different from one that does not exist yet.
+    pub fn prune(&mut self, now_ms: u128) {
+        let limits = &self.limits;
+        self.buckets.retain(|(_, class), bucket| {
+            let limit = match limits.limit_for(*class) {
+                Some(limit) => limit,
+                None => {
+                    return false;
+                }
+            };
+            bucket.refilled(limit, now_ms) < limit.burst.saturating_mul(MILLITOKENS_PER_TOKEN)
+        });
+    }
+
+    pub fn num_buckets(&self) -> usize {
+        self.buckets.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn limiter() -> RPCRateLimiter {
+        RPCRateLimiter::new(RPCRateLimits {
+            read_only_call: Some(TokenBucketLimit { rate: 2, burst: 3 }),
+            tx_broadcast: None,
+            ..RPCRateLimits::default()
+        })
+    }
+
+    #[test]
+    fn test_token_bucket_burst_and_refill() {
+        let mut limiter = limiter();
+        let addr: IpAddr = "10.0.0.1".parse().unwrap();
+        let class = RPCRateLimitClass::ReadOnlyCall;
+
+        // a full bucket admits a burst
+        for _ in 0..3 {
+            assert!(limiter.try_acquire(addr, class, 1000));
+        }
+        assert!(!limiter.try_acquire(addr, class, 1000));
+
+        // at 2 tokens per second, one token comes back in 500ms
+        assert!(!limiter.try_acquire(addr, class, 1499));
+        assert!(limiter.try_acquire(addr, class, 1500));
+        assert!(!limiter.try_acquire(addr, class, 1500));
+
+        // the bucket never holds more than the burst
+        for _ in 0..3 {
+            assert!(limiter.try_acquire(addr, class, 100_000));
+        }
+        assert!(!limiter.try_acquire(addr, class, 100_000));
+    }
+
+    #[test]
+    fn test_token_bucket_acquire_n() {
+        let mut limiter = limiter();
+        let addr: IpAddr = "10.0.0.1".parse().unwrap();
+        let class = RPCRateLimitClass::ReadOnlyCall;
+
+        // requests costing more than the burst are never admitted
+        assert!(!limiter.try_acquire_n(addr, class, 4, 1000));
+
+        // a rejected request takes nothing from the bucket
+        assert!(limiter.try_acquire_n(addr, class, 2, 1000));
+        assert!(!limiter.try_acquire_n(addr, class, 2, 1000));
+        assert!(limiter.try_acquire(addr, class, 1000));
+        assert!(!limiter.try_acquire(addr, class, 1000));
+
+        // two tokens come back in a second
+        assert!(!limiter.try_acquire_n(addr, class, 2, 1999));
+        assert!(limiter.try_acquire_n(addr, class, 2, 2000));
+    }
+
+    #[test]
+    fn test_token_bucket_per_addr_and_class() {
+        let mut limiter = limiter();
+        let addr_1: IpAddr = "10.0.0.1".parse().unwrap();
+        let addr_2: IpAddr = "10.0.0.2".parse().unwrap();
+
+        for _ in 0..3 {
+            assert!(limiter.try_acquire(addr_1, RPCRateLimitClass::ReadOnlyCall, 1000));
+        }
+        assert!(!limiter.try_acquire(addr_1, RPCRateLimitClass::ReadOnlyCall, 1000));
+
+        // other addresses have their own buckets
+        assert!(limiter.try_acquire(addr_2, RPCRateLimitClass::ReadOnlyCall, 1000));
+
+        // unlimited classes are always admitted
+        for _ in 0..100 {
+            assert!(limiter.try_acquire(addr_1, RPCRateLimitClass::TxBroadcast, 1000));
+        }
+        assert_eq!(limiter.num_buckets(), 2);
+
+        // buckets are forgotten once they have refilled
+        limiter.prune(1000);
+        assert_eq!(limiter.num_buckets(), 2);
+        limiter.prune(1500);
+        assert_eq!(limiter.num_buckets(), 1);
+        limiter.prune(2499);
+        assert_eq!(limiter.num_buckets(), 1);
+        limiter.prune(2500);
+        assert_eq!(limiter.num_buckets(), 0);
+    }
+}
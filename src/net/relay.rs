@@ -1082,17 +1082,7 @@ impl Relayer {
         }
 
         // garbage-collect
-        if chain_height > MEMPOOL_MAX_TRANSACTION_AGE {
-            let min_height = chain_height.saturating_sub(MEMPOOL_MAX_TRANSACTION_AGE);
-            let mut mempool_tx = mempool.tx_begin()?;
-
-            debug!(
-                "Remove all transactions beneath block height {}",
-                min_height
-            );
-            MemPoolDB::garbage_collect(&mut mempool_tx, min_height, event_observer)?;
-            mempool_tx.commit()?;
-        }
+        mempool.garbage_collect_by_policy(chain_height, event_observer)?;
         update_stacks_tip_height(chain_height as i64);
 
         Ok(ret)
@@ -17,6 +17,7 @@
  along with Blockstack. If not, see <http://www.gnu.org/licenses/>.
 */
 
+use std::cmp;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
@@ -42,6 +43,7 @@ use crate::burnchains::Burnchain;
 use crate::burnchains::BurnchainView;
 use crate::burnchains::*;
 use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::burn::BlockSnapshot;
 use crate::chainstate::burn::ConsensusHash;
 use crate::chainstate::stacks::db::blocks::CheckError;
 use crate::chainstate::stacks::db::{
@@ -51,32 +53,47 @@ use crate::chainstate::stacks::Error as chain_error;
 use crate::chainstate::stacks::*;
 use crate::clarity_vm::clarity::ClarityConnection;
 use crate::clarity_vm::withdrawal;
+use crate::clarity_vm::withdrawal::WithdrawalBatchConfig;
 use crate::codec::StacksMessageCodec;
 use crate::core::mempool::*;
 use crate::cost_estimates::metrics::CostMetric;
 use crate::cost_estimates::CostEstimator;
 use crate::cost_estimates::FeeEstimator;
 use crate::monitoring;
+use crate::net::admin::{authorize_admin_request, AdminAuth, AdminCredential, AdminRole};
 use crate::net::atlas::{AtlasDB, Attachment, MAX_ATTACHMENT_INV_PAGES_PER_REQUEST};
 use crate::net::connection::ConnectionHttp;
 use crate::net::connection::ConnectionOptions;
 use crate::net::connection::ReplyHandleHttp;
+use crate::net::db::PeerBan;
+use crate::net::db::PeerBanTarget;
 use crate::net::db::PeerDB;
+use crate::net::divergence::DivergenceReason;
 use crate::net::http::*;
 use crate::net::p2p::PeerMap;
 use crate::net::p2p::PeerNetwork;
+use crate::net::ratelimit::RPCRateLimitClass;
 use crate::net::relay::Relayer;
+use crate::net::AdminObserversResponse;
+use crate::net::AdminPeerInfo;
+use crate::net::AdminPeersResponse;
+use crate::net::BlockProposalTxResult;
+use crate::net::BlockProposalValidationResponse;
 use crate::net::BlocksDatum;
+use crate::net::DivergenceSnapshotResponse;
 use crate::net::Error as net_error;
 use crate::net::HttpRequestMetadata;
 use crate::net::HttpRequestType;
 use crate::net::HttpResponseMetadata;
 use crate::net::HttpResponseType;
+use crate::net::MemPoolEvictionResponse;
 use crate::net::MemPoolSyncData;
 use crate::net::MicroblocksData;
 use crate::net::NeighborAddress;
 use crate::net::NeighborsData;
 use crate::net::PeerAddress;
+use crate::net::PeerBanInfo;
+use crate::net::PeerBansResponse;
 use crate::net::PeerHost;
 use crate::net::ProtocolFamily;
 use crate::net::RPCFeeEstimate;
@@ -84,6 +101,9 @@ use crate::net::RPCFeeEstimateResponse;
 use crate::net::StacksHttp;
 use crate::net::StacksHttpMessage;
 use crate::net::StacksMessageType;
+use crate::net::TransactionBatchResponse;
+use crate::net::TransactionBatchResult;
+use crate::net::TransactionBatchStatus;
 use crate::net::UnconfirmedTransactionResponse;
 use crate::net::UnconfirmedTransactionStatus;
 use crate::net::UrlString;
@@ -95,13 +115,34 @@ use crate::net::{
     AccountEntryResponse, AttachmentPage, CallReadOnlyResponse, ContractSrcResponse,
     DataVarResponse, GetAttachmentResponse, GetAttachmentsInvResponse, MapEntryResponse,
 };
+use crate::net::{
+    BlockCostsResponse, BlockHeaderResponse, DepositStatus, DepositStatusResponse,
+    MaterializedDepositInfo, NonceGapsResponse,
+};
+use crate::net::{
+    BlockResultsQuery, WithdrawalBatchRange, WithdrawalClaimStatus, WithdrawalCommitL1Block,
+    WithdrawalRootStatus, WithdrawalStatusResponse,
+};
 use crate::net::{BlocksData, GetIsTraitImplementedResponse};
-use crate::net::{ClientError, TipRequest};
+use crate::net::{ClarityDataKey, ClarityDataProofResponse, TransactionProofResponse};
+use crate::net::{ClientError, ReadOnlyBudgetExceeded, TipRequest, TraceReadOnlyResponse};
+use crate::net::{
+    L1ContractStatus, L1SyncStatus, NodeStartupState, SubnetMinerStatus, SubnetStatusResponse,
+};
+use crate::net::{MapEntriesItem, MapEntriesResponse};
+use crate::net::{
+    MemPoolFeeBucketResponse, MemPoolListResponse, MemPoolSummaryResponse, MemPoolTxEntry,
+};
 use crate::net::{RPCNeighbor, RPCNeighborsInfo};
 use crate::net::{RPCPeerInfoData, RPCPoxInfoData};
+use crate::net::{
+    TransactionAdmissionCheck, TransactionAdmissionCheckStatus, TransactionAdmissionResponse,
+    TransactionNonceDiagnostics, TransactionNonceStatus,
+};
 use crate::util_lib::db::DBConn;
 use crate::util_lib::db::Error as db_error;
 use clarity::vm::database::clarity_store::make_contract_hash_key;
+use clarity::vm::hooks::{EvalHook, StepTracer};
 use clarity::vm::types::TraitIdentifier;
 use clarity::vm::{
     analysis::errors::CheckErrors,
@@ -116,6 +157,7 @@ use clarity::vm::{
     types::{PrincipalData, QualifiedContractIdentifier, StandardPrincipalData},
     ClarityName, ContractName, SymbolicExpression, Value,
 };
+use stacks_common::util::get_epoch_time_ms;
 use stacks_common::util::get_epoch_time_secs;
 use stacks_common::util::hash::Hash160;
 use stacks_common::util::hash::{hex_bytes, to_hex};
@@ -137,6 +179,9 @@ use super::{RPCPoxCurrentCycleInfo, RPCPoxNextCycleInfo};
 
 pub const STREAM_CHUNK_SIZE: u64 = 4096;
 
+/// The most expressions a traced read-only call records in its step trace
+pub const MAX_TRACE_STEPS: usize = 10_000;
+
 #[derive(Default)]
 pub struct RPCHandlerArgs<'a> {
     pub exit_at_block_height: Option<&'a u64>,
@@ -147,6 +192,14 @@ pub struct RPCHandlerArgs<'a> {
     pub cost_metric: Option<&'a dyn CostMetric>,
 }
 
+/// How a withdrawal's leaf is located in a block's withdrawal Merkle tree
+enum WithdrawalLeafQuery {
+    /// The consensus serialization of the withdrawal key
+    Key(Vec<u8>),
+    /// The withdrawal ID, which is the index of the withdrawal's leaf
+    Index(u32),
+}
+
 pub struct ConversationHttp {
     connection: ConnectionHttp,
     conn_id: usize,
@@ -160,6 +213,7 @@ pub struct ConversationHttp {
     last_request_timestamp: u64, // absolute timestamp of the last time we received at least 1 byte in a request
     last_response_timestamp: u64, // absolute timestamp of the last time we sent at least 1 byte in a response
     connection_time: u64,         // when this converation was instantiated
+    on_admin_listener: bool,      // whether this conversation was accepted on the admin listener
 
     canonical_stacks_tip_height: Option<u64>, // chain tip height of the peer's Stacks blockchain
 
@@ -353,9 +407,15 @@ impl ConversationHttp {
             last_request_timestamp: 0,
             last_response_timestamp: 0,
             connection_time: get_epoch_time_secs(),
+            on_admin_listener: false,
         }
     }
 
+    /// Note that this conversation was accepted on the node's admin listener
+    pub fn set_on_admin_listener(&mut self) {
+        self.on_admin_listener = true;
+    }
+
     /// How many ongoing requests do we have on this conversation?
     pub fn num_pending_outbound(&self) -> usize {
         self.reply_streams.len()
@@ -928,6 +988,68 @@ impl ConversationHttp {
         response.send(http, fd).map(|_| ())
     }
 
+    /// Handle a GET for subnet fee rate estimates, computed from the fees actually paid in the
+    /// most recent blocks of the canonical chain.  The estimate only changes with the chain tip,
+    /// so it is computed once per tip and cached in `cached_estimate`.  Reply the entire response.
+    fn handle_get_subnet_fee_rate_estimate<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &StacksChainState,
+        tip: &StacksBlockId,
+        cached_estimate: &mut Option<(StacksBlockId, SubnetFeeRateEstimate)>,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        if let Some((cached_tip, estimate)) = cached_estimate.as_ref() {
+            if cached_tip == tip {
+                return HttpResponseType::SubnetFeeRateEstimate(
+                    response_metadata,
+                    estimate.clone(),
+                )
+                .send(http, fd)
+                .map(|_| ());
+            }
+        }
+
+        let burn_tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+        let stacks_epoch = SortitionDB::get_stacks_epoch(sortdb.conn(), burn_tip.block_height)?
+            .ok_or_else(|| {
+                warn!(
+                    "Failed to get subnet fee rate estimate because could not load Stacks epoch for canonical burn height = {}",
+                    burn_tip.block_height
+                );
+                net_error::ChainstateError(
+                    "Could not load Stacks epoch for canonical burn height".into(),
+                )
+            })?;
+
+        let block_fees = match SubnetFeeEstimator::get_recent_block_fees(
+            chainstate,
+            tip,
+            &stacks_epoch.block_limit,
+            SubnetFeeEstimator::DEFAULT_NUM_BLOCKS,
+        ) {
+            Ok(block_fees) => block_fees,
+            Err(e) => {
+                warn!("Failed to load recent block fees: {:?}", &e);
+                return HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to load recent block fees".into(),
+                )
+                .send(http, fd)
+                .map(|_| ());
+            }
+        };
+        let estimate = SubnetFeeEstimator::estimate(&block_fees);
+        *cached_estimate = Some((tip.clone(), estimate.clone()));
+        HttpResponseType::SubnetFeeRateEstimate(response_metadata, estimate)
+            .send(http, fd)
+            .map(|_| ())
+    }
+
     fn handle_validate_block_proposal<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
@@ -935,13 +1057,12 @@ impl ConversationHttp {
         chainstate: &mut StacksChainState,
         sortdb: &SortitionDB,
         proposal: &miner::Proposal,
-        validator_key: Option<&Secp256k1PrivateKey>,
-        signing_contract: Option<&QualifiedContractIdentifier>,
+        options: &ConnectionOptions,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let validator_key = match validator_key {
+        let validator_key = match options.subnet_validator.as_ref() {
             Some(key) => key,
             None => {
                 let response = HttpResponseType::BlockProposalInvalid {
@@ -953,7 +1074,7 @@ impl ConversationHttp {
             }
         };
 
-        let signing_contract = match signing_contract {
+        let signing_contract = match options.subnet_signing_contract.as_ref() {
             Some(key) => key,
             None => {
                 let response = HttpResponseType::BlockProposalInvalid {
@@ -966,12 +1087,77 @@ impl ConversationHttp {
             }
         };
 
+        let block_proposers = match options.subnet_block_proposers.as_ref() {
+            Some(block_proposers) => block_proposers,
+            None => {
+                let response = HttpResponseType::BlockProposalInvalid {
+                    metadata: response_metadata,
+                    error_message:
+                        "Cannot validate block proposal: not configured with a multiparty signing set"
+                            .into(),
+                };
+                return response.send(http, fd);
+            }
+        };
+
+        // only the miners that may propose for the proposal's L1 block are heard
+        let burn_height = proposal.tenure_burn_height();
+        let proposer = match proposal.proposer(signing_contract.clone()) {
+            Some(proposer) => proposer,
+            None => {
+                let response = HttpResponseType::BlockProposalInvalid {
+                    metadata: response_metadata,
+                    error_message: "Block proposal is not signed by its proposer".into(),
+                };
+                return response.send(http, fd);
+            }
+        };
+        if !block_proposers.may_propose(burn_height, &proposer) {
+            let response = HttpResponseType::BlockProposalInvalid {
+                metadata: response_metadata,
+                error_message: format!(
+                    "Miner {} may not propose a block for L1 block height {}",
+                    to_hex(&proposer),
+                    burn_height
+                ),
+            };
+            return response.send(http, fd);
+        }
+        options.block_proposal_log.note_proposal(burn_height);
+
+        // never sign two different blocks for the same L1 block
+        let block_hash = proposal.block.block_hash();
+        let already_signed_message = format!(
+            "Already signed a different block for L1 block height {}",
+            burn_height
+        );
+        match options.block_proposal_log.committed_block(burn_height) {
+            Some(signed_hash) if signed_hash != block_hash => {
+                let response = HttpResponseType::BlockProposalInvalid {
+                    metadata: response_metadata,
+                    error_message: already_signed_message,
+                };
+                return response.send(http, fd);
+            }
+            _ => {}
+        }
+
         let response = match proposal.validate(chainstate, &sortdb.index_conn()) {
             Ok(_) => {
-                let signature = proposal.sign(validator_key, signing_contract.clone());
-                HttpResponseType::BlockProposalValid {
-                    metadata: response_metadata,
-                    signature,
+                if options
+                    .block_proposal_log
+                    .try_commit_to_block(burn_height, &block_hash)
+                {
+                    let signature = proposal.sign(validator_key, signing_contract.clone());
+                    HttpResponseType::BlockProposalValid {
+                        metadata: response_metadata,
+                        signature,
+                    }
+                } else {
+                    HttpResponseType::BlockProposalInvalid {
+                        metadata: response_metadata,
+                        error_message: already_signed_message,
+                    }
                 }
             }
             Err(e) => HttpResponseType::BlockProposalInvalid {
@@ -982,10 +1168,98 @@ impl ConversationHttp {
         response.send(http, fd)
     }
 
+    /// Fully process a block proposal for an external block builder, reporting the outcome of
+    /// each of its transactions.  The proposal is neither signed nor relayed.  Nodes only serve
+    /// this if `enable_block_proposal_validation` is set.
+    fn handle_evaluate_block_proposal<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        chainstate: &mut StacksChainState,
+        sortdb: &SortitionDB,
+        proposal: &miner::Proposal,
+        options: &ConnectionOptions,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        if !options.enable_block_proposal_validation {
+            let response = HttpResponseType::Forbidden(
+                response_metadata,
+                "Block proposal validation is not enabled on this node".into(),
+            );
+            return response.send(http, fd).map(|_| ());
+        }
+        let block_hash = proposal.block.block_hash();
+
+        let evaluation = match proposal.evaluate(chainstate, &sortdb.index_conn()) {
+            Ok(evaluation) => evaluation,
+            Err(e) => {
+                let validation = BlockProposalValidationResponse {
+                    valid: false,
+                    reason: Some(e.to_string()),
+                    block_hash,
+                    computed_block_hash: None,
+                    total_cost: ExecutionCost::zero(),
+                    block_size: 0,
+                    tx_results: vec![],
+                };
+                return HttpResponseType::BlockProposalValidation(response_metadata, validation)
+                    .send(http, fd);
+            }
+        };
+
+        let tx_results: Vec<_> = evaluation
+            .tx_outcomes
+            .iter()
+            .map(|outcome| match outcome.result {
+                Ok(ref receipt) => BlockProposalTxResult {
+                    txid: outcome.txid.clone(),
+                    result: Some(receipt.result.serialize()),
+                    error: None,
+                    execution_cost: receipt.execution_cost.clone(),
+                },
+                Err(ref e) => BlockProposalTxResult {
+                    txid: outcome.txid.clone(),
+                    result: None,
+                    error: Some(e.to_string()),
+                    execution_cost: ExecutionCost::zero(),
+                },
+            })
+            .collect();
+
+        let computed_block_hash = evaluation.block.block_hash();
+        let reason = if let Some(failed) = tx_results.iter().find(|result| result.error.is_some()) {
+            Some(format!(
+                "Transaction {} failed: {}",
+                failed.txid,
+                failed.error.as_deref().unwrap_or("")
+            ))
+        } else if let Err(ref mismatch) = evaluation.withdrawal_check {
+            Some(mismatch.to_string())
+        } else if computed_block_hash != block_hash {
+            Some("Block hash is not as expected".to_string())
+        } else {
+            None
+        };
+
+        let validation = BlockProposalValidationResponse {
+            valid: reason.is_none(),
+            reason,
+            block_hash,
+            computed_block_hash: Some(computed_block_hash),
+            total_cost: evaluation.execution_cost,
+            block_size: evaluation.block_size,
+            tx_results,
+        };
+        HttpResponseType::BlockProposalValidation(response_metadata, validation).send(http, fd)
+    }
+
     fn handle_get_withdrawal_stx_entry<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
+        sortdb: &SortitionDB,
         chainstate: &mut StacksChainState,
         canonical_tip: &StacksBlockId,
         requested_block_height: u64,
@@ -1004,10 +1278,11 @@ impl ConversationHttp {
             http,
             fd,
             req,
+            sortdb,
             chainstate,
             canonical_tip,
             requested_block_height,
-            withdrawal_key,
+            WithdrawalLeafQuery::Key(withdrawal_key.serialize_to_vec()),
             canonical_stacks_tip_height,
         )
     }
@@ -1016,6 +1291,7 @@ impl ConversationHttp {
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
+        sortdb: &SortitionDB,
         chainstate: &mut StacksChainState,
         canonical_tip: &StacksBlockId,
         requested_block_height: u64,
@@ -1036,27 +1312,71 @@ impl ConversationHttp {
             http,
             fd,
             req,
+            sortdb,
             chainstate,
             canonical_tip,
             requested_block_height,
-            withdrawal_key,
+            WithdrawalLeafQuery::Key(withdrawal_key.serialize_to_vec()),
             canonical_stacks_tip_height,
         )
     }
 
-    fn handle_get_generic_withdrawal_entry<W: Write>(
+    /// Handle a GET for the Merkle proof of the withdrawal with the given withdrawal ID,
+    /// against the withdrawal root of the block at the given height.  The withdrawal ID is
+    /// the index of the withdrawal's leaf in the block's withdrawal Merkle tree, so the
+    /// caller does not need to know the withdrawal's contents.
+    fn handle_get_withdrawal_proof<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        canonical_tip: &StacksBlockId,
+        requested_block_height: u64,
+        withdrawal_id: u32,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        Self::handle_get_generic_withdrawal_entry(
+            http,
+            fd,
+            req,
+            sortdb,
+            chainstate,
+            canonical_tip,
+            requested_block_height,
+            WithdrawalLeafQuery::Index(withdrawal_id),
+            canonical_stacks_tip_height,
+        )
+    }
+
+    /// Handle a GET for the finality of the withdrawal root of the block at the given height in
+    /// the canonical chain.  The root is confirmed once `required_confirmations` blocks have been
+    /// built on top of the block.
+    fn handle_get_withdrawal_status<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
+        sortdb: &SortitionDB,
         chainstate: &mut StacksChainState,
         canonical_tip: &StacksBlockId,
         requested_block_height: u64,
-        withdrawal_key: Value,
+        required_confirmations: u64,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let withdrawal_key_bytes = withdrawal_key.serialize_to_vec();
+
+        let tip_height = match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            chainstate.db(),
+            canonical_tip,
+        ) {
+            Ok(Some(tip_info)) => tip_info.stacks_block_height,
+            Err(_) | Ok(None) => {
+                return HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+                    .send(http, fd)
+                    .map(|_| ())
+            }
+        };
 
         let requested_block = match chainstate
             .index_conn()
@@ -1077,3038 +1397,7777 @@ impl ConversationHttp {
             }
         };
 
-        let block_info_result = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
-            chainstate.db(),
-            &requested_block,
-        );
-        let withdrawal_tree = match block_info_result {
-            Ok(Some(block_info)) => block_info.withdrawal_tree,
-            Err(_) | Ok(None) => {
-                return HttpResponseType::NotFound(
-                    response_metadata,
-                    "Supplied block not found".into(),
-                )
-                .send(http, fd)
-                .map(|_| ())
-            }
-        };
+        let withdrawal_root =
+            match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                chainstate.db(),
+                &requested_block,
+            ) {
+                Ok(Some(block_info)) => block_info.withdrawal_tree.root(),
+                Err(_) | Ok(None) => {
+                    return HttpResponseType::NotFound(
+                        response_metadata,
+                        "Supplied block not found".into(),
+                    )
+                    .send(http, fd)
+                    .map(|_| ())
+                }
+            };
 
-        let merkle_path = match withdrawal_tree.path(&withdrawal_key_bytes) {
-            Some(path) => path,
-            None => {
-                return HttpResponseType::NotFound(
-                    response_metadata,
-                    "Supplied withdrawal key not found".into(),
-                )
-                .send(http, fd)
-                .map(|_| ())
+        let withdrawal_batching =
+            chainstate.get_withdrawal_batching(&sortdb.index_conn(), canonical_tip);
+
+        // a batched withdrawal root is final once the batch's last block is confirmed.  Until
+        // the batch is complete, its root is not known, and is reported as the empty root.
+        let batch_range =
+            withdrawal_batching.and_then(|batching| batching.batch_range(requested_block_height));
+        let (withdrawal_root, last_block_height, withdrawal_batch) = match batch_range {
+            Some((first_height, last_height)) => {
+                let batch_root = match chainstate.get_withdrawal_roots_in_range(
+                    canonical_tip,
+                    first_height,
+                    last_height,
+                ) {
+                    Ok(Some(roots)) => {
+                        withdrawal::create_withdrawal_batch_merkle_tree(&roots).root()
+                    }
+                    Err(_) | Ok(None) => Sha512Trunc256Sum([0; 32]),
+                };
+                let batch = WithdrawalBatchRange {
+                    first_block_height: first_height,
+                    last_block_height: last_height,
+                };
+                (batch_root, last_height, Some(batch))
             }
+            None => (withdrawal_root, requested_block_height, None),
         };
 
-        let tuple_vec: Vec<_> = merkle_path
-            .into_iter()
-            .map(|merkle_point| {
-                let MerklePathPoint {
-                    order,
-                    hash: sibling_hash,
-                } = merkle_point;
-                // the sibling hash is the left sibling if the merkle path point order is right
-                //  because the merkle path point order is in reference to the leaf
-                let is_sibling_left_side = order == MerklePathOrder::Right;
-                // make the clarity tuple
-                Value::Tuple(
-                    TupleData::from_data(vec![
-                        ("hash".into(), withdrawal::buffer_from_hash(sibling_hash)),
-                        ("is-left-side".into(), Value::Bool(is_sibling_left_side)),
-                    ])
-                    .expect("Failed to construct Clarity repr of merkle tree entry"),
-                )
-            })
-            .collect();
-
-        let sibling_hashes = match Value::list_from(tuple_vec) {
-            Ok(list) => list,
-            Err(_) => {
-                error!("Failed to construct a valid Clarity list type out of withdrawal merkle path";
-                       "l2_block_id" => %requested_block);
-                return HttpResponseType::NotFound(
-                    response_metadata,
-                    "Withdrawal merkle tree at this block height is invalid".into(),
-                )
-                .send(http, fd)
-                .map(|_| ());
-            }
+        let confirmations = tip_height.saturating_sub(last_block_height);
+        let status = if tip_height >= last_block_height && confirmations >= required_confirmations {
+            WithdrawalRootStatus::Confirmed
+        } else {
+            WithdrawalRootStatus::Pending
         };
 
-        let withdrawal_root = withdrawal::buffer_from_hash(withdrawal_tree.root());
-        let withdrawal_leaf_hash = withdrawal::buffer_from_hash(
-            MerkleTree::<Sha512Trunc256Sum>::get_leaf_hash(&withdrawal_key_bytes),
-        );
-
-        let response = WithdrawalResponse {
-            withdrawal_root: format!("0x{}", withdrawal_root.serialize()),
-            withdrawal_leaf_hash: format!("0x{}", withdrawal_leaf_hash.serialize()),
-            sibling_hashes: format!("0x{}", sibling_hashes.serialize()),
+        let response = WithdrawalStatusResponse {
+            block_height: requested_block_height,
+            index_block_hash: requested_block,
+            withdrawal_root: format!("0x{}", withdrawal_root),
+            confirmations,
+            required_confirmations,
+            status,
+            withdrawal_batch,
         };
 
-        HttpResponseType::GetWithdrawal(response_metadata, response)
+        HttpResponseType::WithdrawalStatus(response_metadata, response)
             .send(http, fd)
             .map(|_| ())
     }
 
-    /// Handle a GET on an existing account, given the current chain tip.  Optionally supplies a
-    /// MARF proof for each account detail loaded from the chain tip.
-    fn handle_get_account_entry<W: Write>(
+    /// Handle a GET for the recorded results of processing a block, identified either by its
+    /// index block hash or by its height in the canonical chain.  Results are only available if
+    /// this node was configured to record them when it processed the block.
+    fn handle_get_block_results<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        sortdb: &SortitionDB,
         chainstate: &mut StacksChainState,
-        tip: &StacksBlockId,
-        account: &PrincipalData,
-        with_proof: bool,
+        canonical_tip: &StacksBlockId,
+        block: &BlockResultsQuery,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let response =
-            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
-                clarity_tx.with_clarity_db_readonly(|clarity_db| {
-                    let key = ClarityDatabase::make_key_for_account_balance(&account);
-                    let burn_block_height = clarity_db.get_current_burnchain_block_height() as u64;
-                    let (balance, balance_proof) = if with_proof {
-                        clarity_db
-                            .get_with_proof::<STXBalance>(&key)
-                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))
-                            .unwrap_or_else(|| (STXBalance::zero(), Some("".into())))
-                    } else {
-                        clarity_db
-                            .get::<STXBalance>(&key)
-                            .map(|a| (a, None))
-                            .unwrap_or_else(|| (STXBalance::zero(), None))
-                    };
 
-                    let key = ClarityDatabase::make_key_for_account_nonce(&account);
-                    let (nonce, nonce_proof) = if with_proof {
-                        clarity_db
-                            .get_with_proof(&key)
-                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))
-                            .unwrap_or_else(|| (0, Some("".into())))
-                    } else {
-                        clarity_db
-                            .get(&key)
-                            .map(|a| (a, None))
-                            .unwrap_or_else(|| (0, None))
-                    };
-
-                    let unlocked = balance.get_available_balance_at_burn_block(burn_block_height);
-                    let (locked, unlock_height) =
-                        balance.get_locked_balance_at_burn_block(burn_block_height);
-
-                    let balance = format!("0x{}", to_hex(&unlocked.to_be_bytes()));
-                    let locked = format!("0x{}", to_hex(&locked.to_be_bytes()));
-
-                    AccountEntryResponse {
-                        balance,
-                        locked,
-                        unlock_height,
-                        nonce,
-                        balance_proof,
-                        nonce_proof,
-                    }
-                })
-            }) {
-                Ok(Some(data)) => HttpResponseType::GetAccount(response_metadata, data),
-                Ok(None) | Err(_) => {
-                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+        let index_block_hash = match block {
+            BlockResultsQuery::IndexBlockHash(index_block_hash) => index_block_hash.clone(),
+            BlockResultsQuery::Height(block_height) => match chainstate
+                .index_conn()
+                .map_err(|_| {
+                    warn!("Failed to start MARF connection");
+                    net_error::ChainstateError("Could not start MARF connection ".into())
+                })?
+                .get_ancestor_block_hash(*block_height, canonical_tip)
+            {
+                Ok(Some(x)) => x,
+                Err(_) | Ok(None) => {
+                    return HttpResponseType::NotFound(
+                        response_metadata,
+                        "Supplied block height not found".into(),
+                    )
+                    .send(http, fd)
+                    .map(|_| ())
                 }
-            };
+            },
+        };
 
+        let response = match StacksChainState::get_block_results(chainstate.db(), &index_block_hash)
+        {
+            Ok(Some(results)) => HttpResponseType::BlockResults(response_metadata, results),
+            Ok(None) => HttpResponseType::NotFound(
+                response_metadata,
+                format!("No recorded results for block {}", &index_block_hash),
+            ),
+            Err(e) => {
+                warn!(
+                    "Failed to load block results for {}: {:?}",
+                    &index_block_hash, &e
+                );
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to load block results".into(),
+                )
+            }
+        };
         response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a GET on a smart contract's data var, given the current chain tip.  Optionally
-    /// supplies a MARF proof for the value.
-    fn handle_get_data_var<W: Write>(
+    /// Handle a request for the execution costs consumed by a processed block
+    fn handle_get_block_costs<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        sortdb: &SortitionDB,
-        chainstate: &mut StacksChainState,
-        tip: &StacksBlockId,
-        contract_addr: &StacksAddress,
-        contract_name: &ContractName,
-        var_name: &ClarityName,
-        with_proof: bool,
+        chainstate: &StacksChainState,
+        index_block_hash: &StacksBlockId,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let contract_identifier =
-            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
-
-        let response =
-            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
-                clarity_tx.with_clarity_db_readonly(|clarity_db| {
-                    let key = ClarityDatabase::make_key_for_trip(
-                        &contract_identifier,
-                        StoreType::Variable,
-                        var_name,
-                    );
-
-                    let (value, marf_proof) = if with_proof {
-                        clarity_db
-                            .get_with_proof::<Value>(&key)
-                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))?
-                    } else {
-                        clarity_db.get::<Value>(&key).map(|a| (a, None))?
-                    };
-
-                    let data = format!("0x{}", value.serialize());
-                    Some(DataVarResponse { data, marf_proof })
-                })
-            }) {
-                Ok(Some(Some(data))) => HttpResponseType::GetDataVar(response_metadata, data),
-                Ok(Some(None)) => {
-                    HttpResponseType::NotFound(response_metadata, "Data var not found".into())
-                }
-                Ok(None) | Err(_) => {
-                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
-                }
-            };
 
+        let response = match StacksChainState::get_block_costs(chainstate.db(), index_block_hash) {
+            Ok(Some(costs)) => HttpResponseType::BlockCosts(
+                response_metadata,
+                BlockCostsResponse {
+                    index_block_hash: costs.index_block_hash,
+                    percent_full: costs
+                        .block_limit
+                        .proportion_largest_dimension(&costs.total_cost),
+                    anchored_cost: costs.anchored_cost,
+                    confirmed_microblocks_cost: costs.confirmed_microblocks_cost,
+                    total_cost: costs.total_cost,
+                    block_limit: costs.block_limit,
+                },
+            ),
+            Ok(None) => HttpResponseType::NotFound(
+                response_metadata,
+                format!("No recorded costs for block {}", index_block_hash),
+            ),
+            Err(e) => {
+                warn!(
+                    "Failed to load block costs for {}: {:?}",
+                    index_block_hash, &e
+                );
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to load block costs".into(),
+                )
+            }
+        };
         response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a GET on a smart contract's data map, given the current chain tip.  Optionally
-    /// supplies a MARF proof for the value.
-    fn handle_get_map_entry<W: Write>(
-        http: &mut StacksHttp,
-        fd: &mut W,
-        req: &HttpRequestType,
+    /// Has the block commit that selected the block `block_hash` in the sortition with
+    /// `consensus_hash` been observed in the canonical L1 fork?
+    fn is_block_commit_observed(
         sortdb: &SortitionDB,
-        chainstate: &mut StacksChainState,
-        tip: &StacksBlockId,
-        contract_addr: &StacksAddress,
-        contract_name: &ContractName,
-        map_name: &ClarityName,
-        key: &Value,
-        with_proof: bool,
-        canonical_stacks_tip_height: u64,
-    ) -> Result<(), net_error> {
-        let response_metadata =
-            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let contract_identifier =
-            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
-
-        let response =
-            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
-                clarity_tx.with_clarity_db_readonly(|clarity_db| {
-                    let key = ClarityDatabase::make_key_for_data_map_entry(
-                        &contract_identifier,
-                        map_name,
-                        key,
-                    );
-                    let (value, marf_proof) = if with_proof {
-                        clarity_db
-                            .get_with_proof::<Value>(&key)
-                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))
-                            .unwrap_or_else(|| {
-                                test_debug!("No value for '{}' in {}", &key, tip);
-                                (Value::none(), Some("".into()))
-                            })
-                    } else {
-                        clarity_db
-                            .get::<Value>(&key)
-                            .map(|a| (a, None))
-                            .unwrap_or_else(|| {
-                                test_debug!("No value for '{}' in {}", &key, tip);
-                                (Value::none(), None)
-                            })
-                    };
-
-                    let data = format!("0x{}", value.serialize());
-                    MapEntryResponse { data, marf_proof }
-                })
-            }) {
-                Ok(Some(data)) => HttpResponseType::GetMapEntry(response_metadata, data),
-                Ok(None) | Err(_) => {
-                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
-                }
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+    ) -> Result<bool, net_error> {
+        let snapshot =
+            match SortitionDB::get_block_snapshot_consensus(sortdb.conn(), consensus_hash)? {
+                Some(sn) => sn,
+                None => return Ok(false),
             };
-
-        response.send(http, fd).map(|_| ())
+        if !snapshot.sortition || snapshot.winning_stacks_block_hash != *block_hash {
+            return Ok(false);
+        }
+        let canonical_snapshot = sortdb
+            .index_handle_at_tip()
+            .get_block_snapshot(&snapshot.burn_header_hash)?;
+        Ok(canonical_snapshot.map(|sn| sn.sortition_id) == Some(snapshot.sortition_id))
     }
 
-    /// Handle a POST to run a read-only function call with the given parameters on the given chain
-    /// tip.  Returns the result of the function call.  Returns a CallReadOnlyResponse on success.
-    fn handle_readonly_function_call<W: Write>(
+    /// Handle a request for the header of a processed block, with the subnet fields that bridge
+    /// software needs decoded
+    fn handle_get_block_header<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
         sortdb: &SortitionDB,
-        chainstate: &mut StacksChainState,
-        tip: &StacksBlockId,
-        contract_addr: &StacksAddress,
-        contract_name: &ContractName,
-        function: &ClarityName,
-        sender: &PrincipalData,
-        args: &[Value],
-        options: &ConnectionOptions,
+        chainstate: &StacksChainState,
+        index_block_hash: &StacksBlockId,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let contract_identifier =
-            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
-
-        let args: Vec<_> = args
-            .iter()
-            .map(|x| SymbolicExpression::atom_value(x.clone()))
-            .collect();
-        let mainnet = chainstate.mainnet;
-        let mut cost_limit = options.read_only_call_limit.clone();
-        cost_limit.write_length = 0;
-        cost_limit.write_count = 0;
 
-        let data_opt_res =
-            chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
-                let epoch = clarity_tx.get_epoch();
-                let cost_track = clarity_tx
-                    .with_clarity_db_readonly(|clarity_db| {
-                        LimitedCostTracker::new_mid_block(mainnet, cost_limit, clarity_db, epoch)
-                    })
-                    .map_err(|_| {
-                        ClarityRuntimeError::from(InterpreterError::CostContractLoadFailure)
-                    })?;
-
-                clarity_tx.with_readonly_clarity_env(mainnet, sender.clone(), cost_track, |env| {
-                    // we want to execute any function as long as no actual writes are made as
-                    // opposed to be limited to purely calling `define-read-only` functions,
-                    // so use `read_only = false`.  This broadens the number of functions that
-                    // can be called, and also circumvents limitations on `define-read-only`
-                    // functions that can not use `contrac-call?`, even when calling other
-                    // read-only functions
-                    env.execute_contract(&contract_identifier, function.as_str(), &args, false)
-                })
-            });
-
-        let response = match data_opt_res {
-            Ok(Some(Ok(data))) => HttpResponseType::CallReadOnlyFunction(
-                response_metadata,
-                CallReadOnlyResponse {
-                    okay: true,
-                    result: Some(format!("0x{}", data.serialize())),
-                    cause: None,
-                },
-            ),
-            Ok(Some(Err(e))) => match e {
-                Unchecked(CheckErrors::CostBalanceExceeded(actual_cost, _))
-                    if actual_cost.write_count > 0 =>
-                {
-                    HttpResponseType::CallReadOnlyFunction(
-                        response_metadata,
-                        CallReadOnlyResponse {
-                            okay: false,
-                            result: None,
-                            cause: Some("NotReadOnly".to_string()),
-                        },
-                    )
-                }
-                _ => HttpResponseType::CallReadOnlyFunction(
+        let header_info = match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            chainstate.db(),
+            index_block_hash,
+        ) {
+            Ok(Some(header_info)) => header_info,
+            Ok(None) => {
+                return HttpResponseType::NotFound(
                     response_metadata,
-                    CallReadOnlyResponse {
-                        okay: false,
-                        result: None,
-                        cause: Some(e.to_string()),
-                    },
-                ),
-            },
-            Ok(None) | Err(_) => {
-                HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+                    format!("No such processed block {}", index_block_hash),
+                )
+                .send(http, fd)
+                .map(|_| ());
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to load block header for {}: {:?}",
+                    index_block_hash, &e
+                );
+                return HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to load block header".into(),
+                )
+                .send(http, fd)
+                .map(|_| ());
             }
         };
+
+        let header = &header_info.anchored_header;
+        let block_hash = header.block_hash();
+        let commit_observed_on_l1 = ConversationHttp::is_block_commit_observed(
+            sortdb,
+            &header_info.consensus_hash,
+            &block_hash,
+        )?;
+        let response = HttpResponseType::BlockHeader(
+            response_metadata,
+            BlockHeaderResponse {
+                index_block_hash: index_block_hash.clone(),
+                block_hash,
+                consensus_hash: header_info.consensus_hash.clone(),
+                block_height: header_info.stacks_block_height,
+                burn_block_height: header_info.burn_header_height,
+                parent_block: header.parent_block.clone(),
+                tx_merkle_root: header.tx_merkle_root.clone(),
+                state_index_root: header.state_index_root.clone(),
+                withdrawal_merkle_root: header.withdrawal_merkle_root.clone(),
+                miner_signatures: header
+                    .miner_signatures
+                    .signatures()
+                    .iter()
+                    .map(|sig| sig.to_hex())
+                    .collect(),
+                commit_observed_on_l1,
+            },
+        );
         response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a GET to fetch a contract's source code, given the chain tip.  Optionally returns a
-    /// MARF proof as well.
-    fn handle_get_contract_src<W: Write>(
+    /// Handle a request for the account state that a processed block changed.  State diffs are
+    /// only available if this node was configured to record them when it processed the block.
+    fn handle_get_block_state_diff<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        sortdb: &SortitionDB,
-        chainstate: &mut StacksChainState,
-        tip: &StacksBlockId,
-        contract_addr: &StacksAddress,
-        contract_name: &ContractName,
-        with_proof: bool,
+        chainstate: &StacksChainState,
+        index_block_hash: &StacksBlockId,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let contract_identifier =
-            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
 
         let response =
-            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
-                clarity_tx.with_clarity_db_readonly(|db| {
-                    let source = db.get_contract_src(&contract_identifier)?;
-                    let contract_commit_key = make_contract_hash_key(&contract_identifier);
-                    let (contract_commit, proof) = if with_proof {
-                        db.get_with_proof::<ContractCommitment>(&contract_commit_key)
-                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))
-                            .expect("BUG: obtained source, but couldn't get contract commit")
-                    } else {
-                        db.get::<ContractCommitment>(&contract_commit_key)
-                            .map(|a| (a, None))
-                            .expect("BUG: obtained source, but couldn't get contract commit")
-                    };
-
-                    let publish_height = contract_commit.block_height;
-                    Some(ContractSrcResponse {
-                        source,
-                        publish_height,
-                        marf_proof: proof,
-                    })
-                })
-            }) {
-                Ok(Some(Some(data))) => HttpResponseType::GetContractSrc(response_metadata, data),
-                Ok(Some(None)) => HttpResponseType::NotFound(
+            match StacksChainState::get_block_state_diff(chainstate.db(), index_block_hash) {
+                Ok(Some(state_diff)) => {
+                    HttpResponseType::BlockStateDiff(response_metadata, state_diff)
+                }
+                Ok(None) => HttpResponseType::NotFound(
                     response_metadata,
-                    "No contract source data found".into(),
+                    format!("No recorded state diff for block {}", index_block_hash),
                 ),
-                Ok(None) | Err(_) => {
-                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+                Err(e) => {
+                    warn!(
+                        "Failed to load block state diff for {}: {:?}",
+                        index_block_hash, &e
+                    );
+                    HttpResponseType::ServerError(
+                        response_metadata,
+                        "Failed to load block state diff".into(),
+                    )
                 }
             };
-
         response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a GET to fetch whether or not a contract implements a certain trait
-    fn handle_get_is_trait_implemented<W: Write>(
-        http: &mut StacksHttp,
-        fd: &mut W,
+    /// Which class of rate-limited endpoint a request calls, if any, and how many of the class's
+    /// tokens it costs.  A transaction batch costs one token per transaction.
+    fn rate_limit_charge(req: &HttpRequestType) -> Option<(RPCRateLimitClass, u64)> {
+        match req {
+            HttpRequestType::CallReadOnlyFunction(..)
+            | HttpRequestType::TraceReadOnlyFunction(..) => {
+                Some((RPCRateLimitClass::ReadOnlyCall, 1))
+            }
+            HttpRequestType::PostTransaction(..)
+            | HttpRequestType::PostTransactionSponsorship(..) => {
+                Some((RPCRateLimitClass::TxBroadcast, 1))
+            }
+            HttpRequestType::PostTransactionBatch(_, txs, _) => Some((
+                RPCRateLimitClass::TxBroadcast,
+                u64::try_from(txs.len()).unwrap_or(u64::MAX).max(1),
+            )),
+            HttpRequestType::ValidateBlockProposal(..) => {
+                Some((RPCRateLimitClass::BlockValidation, 1))
+            }
+            HttpRequestType::GetMapEntries { .. } => Some((RPCRateLimitClass::MapIteration, 1)),
+            HttpRequestType::GetClarityDataProof(..)
+            | HttpRequestType::GetTransactionProof { .. }
+            | HttpRequestType::GetAccount(.., true)
+            | HttpRequestType::GetDataVar(.., true)
+            | HttpRequestType::GetMapEntry(.., true)
+            | HttpRequestType::GetContractSrc(.., true) => Some((RPCRateLimitClass::Proof, 1)),
+            HttpRequestType::MemPoolQuery(..) => Some((RPCRateLimitClass::MempoolSync, 1)),
+            HttpRequestType::GetMemPoolTxs { .. } | HttpRequestType::GetMemPoolSummary { .. } => {
+                Some((RPCRateLimitClass::MempoolListing, 1))
+            }
+            HttpRequestType::GetBlockStateDiff { .. } => Some((RPCRateLimitClass::StateDiff, 1)),
+            _ => None,
+        }
+    }
+
+    /// Charge a request against its client's rate limit for the endpoint's class.  Requests on
+    /// the admin listener are not limited.  Returns the error response to send if the client has
+    /// exceeded its limit.
+    fn check_rate_limit(
+        &self,
+        network: &mut PeerNetwork,
         req: &HttpRequestType,
-        sortdb: &SortitionDB,
-        chainstate: &mut StacksChainState,
-        tip: &StacksBlockId,
-        contract_addr: &StacksAddress,
-        contract_name: &ContractName,
-        trait_id: &TraitIdentifier,
-        canonical_stacks_tip_height: u64,
-    ) -> Result<(), net_error> {
-        let response_metadata =
-            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let contract_identifier =
-            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
+    ) -> Option<HttpResponseType> {
+        if self.on_admin_listener {
+            return None;
+        }
+        let (class, tokens) = ConversationHttp::rate_limit_charge(req)?;
+        if network.rpc_rate_limiter.try_acquire_n(
+            self.peer_addr.ip(),
+            class,
+            tokens,
+            get_epoch_time_ms(),
+        ) {
+            return None;
+        }
 
-        let response =
-            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
-                clarity_tx.with_clarity_db_readonly(|db| {
-                    let analysis = db.load_contract_analysis(&contract_identifier)?;
-                    if analysis.implemented_traits.contains(trait_id) {
-                        Some(GetIsTraitImplementedResponse {
-                            is_implemented: true,
-                        })
-                    } else {
-                        let trait_defining_contract =
-                            db.load_contract_analysis(&trait_id.contract_identifier)?;
-                        let trait_definition =
-                            trait_defining_contract.get_defined_trait(&trait_id.name)?;
-                        let is_implemented = analysis
-                            .check_trait_compliance(trait_id, trait_definition)
-                            .is_ok();
-                        Some(GetIsTraitImplementedResponse { is_implemented })
-                    }
-                })
-            }) {
-                Ok(Some(Some(data))) => {
-                    HttpResponseType::GetIsTraitImplemented(response_metadata, data)
-                }
-                Ok(Some(None)) => HttpResponseType::NotFound(
-                    response_metadata,
-                    "No contract analysis found or trait definition not found".into(),
-                ),
-                Ok(None) | Err(_) => {
-                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
-                }
-            };
+        debug!("Rate-limited HTTP request";
+               "class" => class.as_str(),
+               "path" => %req.request_path(),
+               "peer_addr" => &self.peer_addr);
+        monitoring::increment_rpc_rate_limited_counter(class.as_str());
 
-        response.send(http, fd).map(|_| ())
+        let response_metadata = HttpResponseMetadata::from_http_request_type(
+            req,
+            Some(network.burnchain_tip.canonical_stacks_tip_height),
+        );
+        Some(HttpResponseType::TooManyRequests(
+            response_metadata,
+            format!(
+                "Too many {} requests from this address; try again later",
+                class.as_str()
+            ),
+        ))
     }
 
-    /// Handle a GET to fetch a contract's analysis data, given the chain tip.  Note that this isn't
-    /// something that's anchored to the blockchain, and can be different across different versions
-    /// of Stacks -- callers must trust the Stacks node to return correct analysis data.
-    /// Callers who don't trust the Stacks node should just fetch the contract source
-    /// code and analyze it offline.
-    fn handle_get_contract_abi<W: Write>(
+    /// Check that an admin request may act with `required_role`.  The request must arrive on the
+    /// admin listener, so admin endpoints are not served at all unless one is bound, and its
+    /// `Authorization` header must match one of this node's admin credentials.  The `auth_token` is an operator credential.  Returns the error
+    /// response to send if the request is not authorized.
+    fn check_admin_auth(
+        network: &mut PeerNetwork,
+        on_admin_listener: bool,
+        req: &HttpRequestType,
+        method: &str,
+        auth_token: Option<&String>,
+        required_role: AdminRole,
+        response_metadata: HttpResponseMetadata,
+    ) -> Option<HttpResponseType> {
+        if !on_admin_listener {
+            // admin endpoints are only served on the admin listener, if one is bound at all.
+            // Don't reveal them on the public listener.
+            return Some(HttpResponseType::NotFound(
+                response_metadata,
+                "No such endpoint".into(),
+            ));
+        }
+
+        let mut credentials = network.connection_opts.admin_credentials.clone();
+        if let Some(token) = network.connection_opts.auth_token.as_ref() {
+            credentials.push(AdminCredential {
+                role: AdminRole::Operator,
+                auth: AdminAuth::SharedSecret(token.clone()),
+            });
+        }
+        if credentials.is_empty() {
+            return Some(HttpResponseType::Forbidden(
+                response_metadata,
+                "Admin endpoints are disabled: no auth_token or admin_credentials are configured"
+                    .into(),
+            ));
+        }
+
+        let body = match req.admin_request_body() {
+            Ok(body) => body,
+            Err(e) => {
+                return Some(HttpResponseType::ServerError(
+                    response_metadata,
+                    format!("Failed to encode request body: {:?}", &e),
+                ));
+            }
+        };
+        let role = match authorize_admin_request(
+            &credentials,
+            &mut network.admin_replay_cache,
+            auth_token.map(|token| token.as_str()),
+            method,
+            &req.request_path(),
+            &body,
+            get_epoch_time_secs(),
+        ) {
+            Some(role) => role,
+            None => {
+                return Some(HttpResponseType::Unauthorized(
+                    response_metadata,
+                    "Missing or invalid Authorization header".into(),
+                ));
+            }
+        };
+        if role < required_role {
+            return Some(HttpResponseType::Forbidden(
+                response_metadata,
+                format!("This endpoint requires the {} role", required_role.as_str()),
+            ));
+        }
+        None
+    }
+
+    /// Handle an admin request to capture a divergence snapshot.  The request requires the
+    /// operator role.
+    fn handle_post_divergence_snapshot<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
+        network: &mut PeerNetwork,
         sortdb: &SortitionDB,
-        chainstate: &mut StacksChainState,
-        tip: &StacksBlockId,
-        contract_addr: &StacksAddress,
-        contract_name: &ContractName,
+        chainstate: &StacksChainState,
+        mempool: &MemPoolDB,
+        on_admin_listener: bool,
+        auth_token: Option<&String>,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let contract_identifier =
-            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
 
-        let response =
-            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
-                clarity_tx.with_analysis_db_readonly(|db| {
-                    let contract = db.load_contract(&contract_identifier)?;
-                    contract.contract_interface
-                })
-            }) {
-                Ok(Some(Some(data))) => HttpResponseType::GetContractABI(response_metadata, data),
-                Ok(Some(None)) => HttpResponseType::NotFound(
-                    response_metadata,
-                    "No contract interface data found".into(),
-                ),
-                Ok(None) | Err(_) => {
-                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
-                }
-            };
+        if let Some(response) = ConversationHttp::check_admin_auth(
+            network,
+            on_admin_listener,
+            req,
+            "POST",
+            auth_token,
+            AdminRole::Operator,
+            response_metadata.clone(),
+        ) {
+            return response.send(http, fd).map(|_| ());
+        }
 
+        let response = match network.capture_divergence_snapshot(
+            DivergenceReason::Manual,
+            sortdb,
+            chainstate,
+            mempool,
+        ) {
+            Ok(snapshot) => {
+                let path = match network.connection_opts.divergence_snapshot_dir.as_ref() {
+                    Some(dir) => match snapshot.save(dir) {
+                        Ok(path) => Some(path.display().to_string()),
+                        Err(e) => {
+                            warn!("Failed to save divergence snapshot to {}: {:?}", dir, &e);
+                            None
+                        }
+                    },
+                    None => None,
+                };
+                HttpResponseType::DivergenceSnapshot(
+                    response_metadata,
+                    DivergenceSnapshotResponse { path, snapshot },
+                )
+            }
+            Err(e) => {
+                warn!("Failed to capture divergence snapshot: {:?}", &e);
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to capture divergence snapshot".into(),
+                )
+            }
+        };
         response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a GET unconfirmed microblock stream.  Start streaming the reply.
-    /// The response's preamble (but not the block data) will be synchronously written to the fd
-    /// (so use a fd that can buffer!)
-    /// Return a StreamCursor struct for the block that we're sending, so we can continue to
-    /// make progress sending it.
-    fn handle_getmicroblocks_unconfirmed<W: Write>(
+    /// Handle an admin request to evict a transaction from the mempool.  The request requires the
+    /// operator role.  The eviction is recorded in the mempool's audit table, and reported to the
+    /// mempool event observer.
+    fn handle_delete_mempool_tx<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        index_anchor_block_hash: &StacksBlockId,
-        min_seq: u16,
-        chainstate: &StacksChainState,
+        network: &mut PeerNetwork,
+        mempool: &mut MemPoolDB,
+        txid: &Txid,
+        reason: Option<&String>,
+        on_admin_listener: bool,
+        auth_token: Option<&String>,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
         canonical_stacks_tip_height: u64,
-    ) -> Result<Option<StreamCursor>, net_error> {
+    ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
 
-        // do we have this unconfirmed microblock stream?
-        match chainstate.has_any_staging_microblock_indexed(index_anchor_block_hash, min_seq) {
-            Ok(false) => {
-                // nope
-                let response = HttpResponseType::NotFound(
+        if let Some(response) = ConversationHttp::check_admin_auth(
+            network,
+            on_admin_listener,
+            req,
+            "DELETE",
+            auth_token,
+            AdminRole::Operator,
+            response_metadata.clone(),
+        ) {
+            return response.send(http, fd).map(|_| ());
+        }
+
+        let reason = reason.map(|r| r.as_str()).unwrap_or("operator request");
+        let response = match mempool.evict_tx(txid, reason) {
+            Ok(Some(eviction)) => {
+                info!("Evicted transaction from mempool";
+                      "txid" => %txid,
+                      "reason" => reason);
+                if let Some(observer) = event_observer {
+                    observer.mempool_txs_dropped(vec![txid.clone()], MemPoolDropReason::EVICTED);
+                }
+                HttpResponseType::MemPoolEviction(
                     response_metadata,
-                    format!(
-                        "No such unconfirmed microblock stream for {} at or after {}",
-                        index_anchor_block_hash.to_hex(),
-                        min_seq
-                    ),
-                );
-                response.send(http, fd).and_then(|_| Ok(None))
+                    MemPoolEvictionResponse {
+                        txid: eviction.txid.to_hex(),
+                        origin_address: eviction.origin_address.to_string(),
+                        origin_nonce: eviction.origin_nonce,
+                        tx_fee: eviction.tx_fee,
+                        reason: eviction.reason,
+                        evict_time: eviction.evict_time,
+                    },
+                )
             }
+            Ok(None) => HttpResponseType::NotFound(
+                response_metadata,
+                format!("No such transaction in the mempool: {}", txid),
+            ),
             Err(e) => {
-                // nope
                 warn!(
-                    "Failed to serve confirmed microblock stream {:?}: {:?}",
-                    req, &e
+                    "Failed to evict transaction {} from mempool: {:?}",
+                    txid, &e
                 );
-                let response = HttpResponseType::ServerError(
+                HttpResponseType::ServerError(
                     response_metadata,
-                    format!(
-                        "Failed to query unconfirmed microblock stream for {} at or after {}",
-                        index_anchor_block_hash.to_hex(),
-                        min_seq
-                    ),
-                );
-                response.send(http, fd).and_then(|_| Ok(None))
-            }
-            Ok(true) => {
-                // yup! start streaming it back
-                let (response, stream_opt) = match StreamCursor::new_microblock_unconfirmed(
-                    chainstate,
-                    index_anchor_block_hash.clone(),
-                    min_seq,
-                ) {
-                    Ok(stream) => (
-                        HttpResponseType::MicroblockStream(response_metadata),
-                        Some(stream),
-                    ),
-                    Err(chain_error::NoSuchBlockError) => (
-                        HttpResponseType::NotFound(
-                            response_metadata,
-                            format!(
-                                "No such unconfirmed microblock stream starting with {}",
-                                index_anchor_block_hash.to_hex()
-                            ),
-                        ),
-                        None,
-                    ),
-                    Err(_e) => {
-                        debug!(
-                            "Failed to load unconfirmed microblock stream {}: {:?}",
-                            &index_anchor_block_hash, &_e
-                        );
-                        (
-                            HttpResponseType::ServerError(
-                                response_metadata,
-                                format!(
-                                    "Failed to query unconfirmed microblock stream {}",
-                                    index_anchor_block_hash.to_hex()
-                                ),
-                            ),
-                            None,
-                        )
-                    }
-                };
-                response.send(http, fd).and_then(|_| Ok(stream_opt))
+                    "Failed to evict transaction".into(),
+                )
             }
-        }
+        };
+        response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a GET unconfirmed transaction.
-    /// The response will be synchronously written to the fd.
-    fn handle_gettransaction_unconfirmed<W: Write>(
+    /// Handle an admin request for this node's connected peers.  The request requires the
+    /// read-only role.
+    fn handle_get_admin_peers<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        chainstate: &StacksChainState,
-        mempool: &MemPoolDB,
-        txid: &Txid,
+        network: &mut PeerNetwork,
+        on_admin_listener: bool,
+        auth_token: Option<&String>,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
 
-        // present in the unconfirmed state?
-        if let Some(ref unconfirmed) = chainstate.unconfirmed_state.as_ref() {
-            if let Some((transaction, mblock_hash, seq)) =
-                unconfirmed.get_unconfirmed_transaction(txid)
-            {
-                let response = HttpResponseType::UnconfirmedTransaction(
-                    response_metadata,
-                    UnconfirmedTransactionResponse {
-                        status: UnconfirmedTransactionStatus::Microblock {
-                            block_hash: mblock_hash,
-                            seq: seq,
-                        },
-                        tx: to_hex(&transaction.serialize_to_vec()),
-                    },
-                );
-                return response.send(http, fd).map(|_| ());
-            }
-        }
-
-        // present in the mempool?
-        if let Some(txinfo) = MemPoolDB::get_tx(mempool.conn(), txid)? {
-            let response = HttpResponseType::UnconfirmedTransaction(
-                response_metadata,
-                UnconfirmedTransactionResponse {
-                    status: UnconfirmedTransactionStatus::Mempool,
-                    tx: to_hex(&txinfo.tx.serialize_to_vec()),
-                },
-            );
+        if let Some(response) = ConversationHttp::check_admin_auth(
+            network,
+            on_admin_listener,
+            req,
+            "GET",
+            auth_token,
+            AdminRole::ReadOnly,
+            response_metadata.clone(),
+        ) {
             return response.send(http, fd).map(|_| ());
         }
 
-        // not found
-        let response = HttpResponseType::NotFound(
-            response_metadata,
-            format!("No such unconfirmed transaction {}", txid),
-        );
-        return response.send(http, fd).map(|_| ());
+        let mut peers: Vec<_> = network
+            .peers
+            .values()
+            .map(|convo| AdminPeerInfo {
+                neighbor: RPCNeighbor::from_neighbor_key_and_pubkh(
+                    convo.to_neighbor_key(),
+                    convo.get_public_key_hash().unwrap_or(Hash160([0u8; 20])),
+                    convo.is_authenticated(),
+                ),
+                outbound: convo.is_outbound(),
+                first_contact_time: convo.stats.first_contact_time,
+            })
+            .collect();
+        peers.sort_by_key(|peer| (peer.neighbor.addrbytes.clone(), peer.neighbor.port));
+
+        let response =
+            HttpResponseType::AdminPeers(response_metadata, AdminPeersResponse { peers });
+        response.send(http, fd).map(|_| ())
     }
 
-    /// Load up the canonical Stacks chain tip.  Note that this is subject to both burn chain block
-    /// Stacks block availability -- different nodes with different partial replicas of the Stacks chain state
-    /// will return different values here.
-    ///
-    /// # Warn
-    /// - There is a potential race condition. If this function is loading the latest unconfirmed
-    /// tip, that tip may get invalidated by the time it is used in `maybe_read_only_clarity_tx`,
-    /// which is used to load clarity state at a particular tip (which would lead to a 404 error).
-    /// If this race condition occurs frequently, we can modify `maybe_read_only_clarity_tx` to
-    /// re-load the unconfirmed chain tip. Refer to issue #2997.
-    ///
-    /// # Inputs
-    /// - `tip_req` is given by the HTTP request as the optional query parameter for the chain tip
-    /// hash.  It will be UseLatestAnchoredTip if there was no parameter given. If it is set to
-    /// `latest`, the parameter will be set to UseLatestUnconfirmedTip.
-    fn handle_load_stacks_chain_tip<W: Write>(
+    /// Handle an admin request for the event observers this node sends events to.  The request
+    /// requires the read-only role.
+    fn handle_get_admin_observers<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        tip_req: &TipRequest,
-        sortdb: &SortitionDB,
-        chainstate: &mut StacksChainState,
+        network: &mut PeerNetwork,
+        on_admin_listener: bool,
+        auth_token: Option<&String>,
         canonical_stacks_tip_height: u64,
-    ) -> Result<Option<StacksBlockId>, net_error> {
-        match tip_req {
-            TipRequest::UseLatestUnconfirmedTip => {
-                let unconfirmed_chain_tip_opt = match &mut chainstate.unconfirmed_state {
-                    Some(unconfirmed_state) => {
-                        match unconfirmed_state.get_unconfirmed_state_if_exists() {
-                            Ok(res) => res,
-                            Err(msg) => {
-                                let response_metadata =
-                                    HttpResponseMetadata::from_http_request_type(
-                                        req,
-                                        Some(canonical_stacks_tip_height),
-                                    );
-                                let response = HttpResponseType::NotFound(response_metadata, msg);
-                                return response.send(http, fd).and_then(|_| Ok(None));
-                            }
-                        }
-                    }
-                    None => None,
-                };
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
 
-                if let Some(unconfirmed_chain_tip) = unconfirmed_chain_tip_opt {
-                    Ok(Some(unconfirmed_chain_tip))
-                } else {
-                    match chainstate.get_stacks_chain_tip(sortdb)? {
-                        Some(tip) => Ok(Some(StacksBlockHeader::make_index_block_hash(
-                            &tip.consensus_hash,
-                            &tip.anchored_block_hash,
-                        ))),
-                        None => {
-                            let response_metadata = HttpResponseMetadata::from_http_request_type(
-                                req,
-                                Some(canonical_stacks_tip_height),
-                            );
-                            warn!("Failed to load Stacks chain tip");
-                            let response = HttpResponseType::NotFound(
-                                response_metadata,
-                                format!("Failed to load Stacks chain tip"),
-                            );
-                            response.send(http, fd).and_then(|_| Ok(None))
-                        }
-                    }
-                }
-            }
-            TipRequest::SpecificTip(tip) => Ok(Some(*tip).clone()),
-            TipRequest::UseLatestAnchoredTip => match chainstate.get_stacks_chain_tip(sortdb)? {
-                Some(tip) => Ok(Some(StacksBlockHeader::make_index_block_hash(
-                    &tip.consensus_hash,
-                    &tip.anchored_block_hash,
-                ))),
-                None => {
-                    let response_metadata = HttpResponseMetadata::from_http_request_type(
-                        req,
-                        Some(canonical_stacks_tip_height),
-                    );
-                    warn!("Failed to load Stacks chain tip");
-                    let response = HttpResponseType::ServerError(
-                        response_metadata,
-                        format!("Failed to load Stacks chain tip"),
-                    );
-                    response.send(http, fd).and_then(|_| Ok(None))
-                }
-            },
+        if let Some(response) = ConversationHttp::check_admin_auth(
+            network,
+            on_admin_listener,
+            req,
+            "GET",
+            auth_token,
+            AdminRole::ReadOnly,
+            response_metadata.clone(),
+        ) {
+            return response.send(http, fd).map(|_| ());
         }
+
+        let response = HttpResponseType::AdminObservers(
+            response_metadata,
+            AdminObserversResponse {
+                observers: network.connection_opts.event_observers.clone(),
+            },
+        );
+        response.send(http, fd).map(|_| ())
     }
 
-    fn handle_load_stacks_chain_tip_hashes<W: Write>(
+    /// Handle an admin request for the peer bans in effect.  The request requires the read-only
+    /// role.
+    fn handle_get_peer_bans<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        tip: StacksBlockId,
-        chainstate: &StacksChainState,
+        network: &mut PeerNetwork,
+        on_admin_listener: bool,
+        auth_token: Option<&String>,
         canonical_stacks_tip_height: u64,
-    ) -> Result<Option<(ConsensusHash, BlockHeaderHash)>, net_error> {
-        match chainstate.get_block_header_hashes(&tip)? {
-            Some((ch, bl)) => {
-                return Ok(Some((ch, bl)));
-            }
-            None => {}
-        }
+    ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        warn!("Failed to load Stacks chain tip");
-        let response = HttpResponseType::ServerError(
-            response_metadata,
-            format!("Failed to load Stacks chain tip"),
-        );
-        response.send(http, fd).and_then(|_| Ok(None))
+
+        if let Some(response) = ConversationHttp::check_admin_auth(
+            network,
+            on_admin_listener,
+            req,
+            "GET",
+            auth_token,
+            AdminRole::ReadOnly,
+            response_metadata.clone(),
+        ) {
+            return response.send(http, fd).map(|_| ());
+        }
+
+        let response = match PeerDB::get_peer_bans(network.peerdb.conn(), get_epoch_time_secs()) {
+            Ok(bans) => HttpResponseType::PeerBans(
+                response_metadata,
+                PeerBansResponse {
+                    bans: bans.iter().map(PeerBanInfo::from_peer_ban).collect(),
+                },
+            ),
+            Err(e) => {
+                warn!("Failed to load peer bans"; "error" => ?e);
+                HttpResponseType::ServerError(response_metadata, "Failed to load peer bans".into())
+            }
+        };
+        response.send(http, fd).map(|_| ())
     }
 
-    fn handle_post_fee_rate_estimate<W: Write>(
+    /// Handle an admin request to add a persistent peer ban, lasting `duration_secs` (or forever).
+    /// Connected peers the ban applies to are disconnected.  The request requires the operator
+    /// role.
+    fn handle_post_peer_ban<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        handler_args: &RPCHandlerArgs,
-        sortdb: &SortitionDB,
-        tx: &TransactionPayload,
-        estimated_len: u64,
+        network: &mut PeerNetwork,
+        target: &PeerBanTarget,
+        duration_secs: Option<u64>,
+        reason: Option<&String>,
+        on_admin_listener: bool,
+        auth_token: Option<&String>,
         canonical_stacks_tip_height: u64,
     ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
-        let stacks_epoch = SortitionDB::get_stacks_epoch(sortdb.conn(), tip.block_height)?
-                .ok_or_else(|| {
-                    warn!(
-                        "Failed to get fee rate estimate because could not load Stacks epoch for canonical burn height = {}",
-                        tip.block_height
-                    );
-                    net_error::ChainstateError("Could not load Stacks epoch for canonical burn height".into())
-                })?;
-        if let Some((cost_estimator, fee_estimator, metric)) = handler_args.get_estimators_ref() {
-            let estimated_cost = match cost_estimator.estimate_cost(tx, &stacks_epoch.epoch_id) {
-                Ok(x) => x,
-                Err(e) => {
-                    debug!(
-                        "Estimator RPC endpoint failed to estimate tx: {}",
-                        tx.name()
-                    );
-                    return HttpResponseType::BadRequestJSON(response_metadata, e.into_json())
-                        .send(http, fd);
-                }
-            };
 
-            let scalar_cost =
-                metric.from_cost_and_len(&estimated_cost, &stacks_epoch.block_limit, estimated_len);
-            let fee_rates = match fee_estimator.get_rate_estimates() {
-                Ok(x) => x,
-                Err(e) => {
-                    debug!(
-                        "Estimator RPC endpoint failed to estimate fees for tx: {}",
-                        tx.name()
-                    );
-                    return HttpResponseType::BadRequestJSON(response_metadata, e.into_json())
-                        .send(http, fd);
-                }
-            };
-
-            let mut estimations = RPCFeeEstimate::estimate_fees(scalar_cost, fee_rates).to_vec();
-
-            let minimum_fee = estimated_len * MINIMUM_TX_FEE_RATE_PER_BYTE;
+        if let Some(response) = ConversationHttp::check_admin_auth(
+            network,
+            on_admin_listener,
+            req,
+            "POST",
+            auth_token,
+            AdminRole::Operator,
+            response_metadata.clone(),
+        ) {
+            return response.send(http, fd).map(|_| ());
+        }
 
-            for estimate in estimations.iter_mut() {
-                if estimate.fee < minimum_fee {
-                    estimate.fee = minimum_fee;
-                }
+        let now = get_epoch_time_secs();
+        let expires_at = duration_secs.map(|duration_secs| now.saturating_add(duration_secs));
+        let reason = reason.map(|r| r.as_str()).unwrap_or("banned by operator");
+        let added = network.peerdb.tx_begin().and_then(|mut tx| {
+            let ban_id = PeerDB::add_peer_ban(&mut tx, target, reason, now, expires_at)?;
+            tx.commit().map_err(db_error::SqliteError)?;
+            Ok(ban_id)
+        });
+        let ban = match added {
+            Ok(ban_id) => PeerBan {
+                ban_id,
+                target: target.clone(),
+                reason: reason.to_string(),
+                created_at: now,
+                expires_at,
+            },
+            Err(e) => {
+                warn!("Failed to add peer ban"; "error" => ?e);
+                let response = HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to add peer ban".into(),
+                );
+                return response.send(http, fd).map(|_| ());
             }
+        };
 
-            let response = HttpResponseType::TransactionFeeEstimation(
-                response_metadata,
-                RPCFeeEstimateResponse {
-                    estimated_cost,
-                    estimations,
-                    estimated_cost_scalar: scalar_cost,
-                    cost_scalar_change_by_byte: metric.change_per_byte(),
-                },
-            );
-            response.send(http, fd)
-        } else {
-            debug!("Fee and cost estimation not configured on this stacks node");
-            let response = HttpResponseType::BadRequestJSON(
-                response_metadata,
-                json!({
-                    "error": "Fee and Cost Estimation not configured on this Stacks node",
-                    "reason": "CostEstimationDisabled",
-                }),
-            );
-            response.send(http, fd)
+        let banned: Vec<_> = network
+            .peers
+            .values()
+            .filter(|convo| {
+                ban.applies_to(&convo.peer_addrbytes, convo.get_public_key_hash().as_ref())
+            })
+            .map(|convo| convo.to_neighbor_key())
+            .collect();
+        for nk in banned.iter() {
+            network.deregister_neighbor(nk);
         }
+        info!("Added peer ban";
+              "ban_id" => ban.ban_id,
+              "target" => ?ban.target,
+              "expires_at" => ?ban.expires_at,
+              "num_disconnected" => banned.len());
+
+        let response =
+            HttpResponseType::PeerBan(response_metadata, PeerBanInfo::from_peer_ban(&ban));
+        response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a transaction.  Directly submit it to the mempool so the client can see any
-    /// rejection reasons up-front (different from how the peer network handles it).  Indicate
-    /// whether or not the transaction was accepted (and thus needs to be forwarded) in the return
-    /// value.
-    fn handle_post_transaction<W: Write>(
+    /// Handle an admin request to lift a persistent peer ban.  The request requires the operator
+    /// role.
+    fn handle_delete_peer_ban<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        chainstate: &mut StacksChainState,
-        sortdb: &SortitionDB,
-        consensus_hash: ConsensusHash,
-        block_hash: BlockHeaderHash,
-        mempool: &mut MemPoolDB,
-        tx: StacksTransaction,
-        atlasdb: &mut AtlasDB,
-        attachment: Option<Attachment>,
-        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        network: &mut PeerNetwork,
+        ban_id: u64,
+        on_admin_listener: bool,
+        auth_token: Option<&String>,
         canonical_stacks_tip_height: u64,
-    ) -> Result<bool, net_error> {
-        let txid = tx.txid();
+    ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let (response, accepted) = if mempool.has_tx(&txid) {
-            debug!("Mempool already has POSTed transaction {}", &txid);
-            (
-                HttpResponseType::TransactionID(response_metadata, txid),
-                false,
-            )
-        } else {
-            let tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
-            let stacks_epoch = sortdb
-                .index_conn()
-                .get_stacks_epoch(tip.block_height as u32)
-                .ok_or_else(|| {
-                    warn!(
-                        "Failed to store transaction because could not load Stacks epoch for canonical burn height = {}",
-                        tip.block_height
-                    );
-                    net_error::ChainstateError("Could not load Stacks epoch for canonical burn height".into())
-                })?;
-
-            match mempool.submit(
-                chainstate,
-                &consensus_hash,
-                &block_hash,
-                &tx,
-                event_observer,
-                &stacks_epoch.block_limit,
-                &stacks_epoch.epoch_id,
-            ) {
-                Ok(_) => {
-                    debug!("Mempool accepted POSTed transaction {}", &txid);
-                    (
-                        HttpResponseType::TransactionID(response_metadata, txid),
-                        true,
-                    )
-                }
-                Err(e) => {
-                    debug!("Mempool rejected POSTed transaction {}: {:?}", &txid, &e);
-                    (
-                        HttpResponseType::BadRequestJSON(response_metadata, e.into_json(&txid)),
-                        false,
-                    )
-                }
-            }
-        };
 
-        if let Some(ref attachment) = attachment {
-            if let TransactionPayload::ContractCall(ref contract_call) = tx.payload {
-                if atlasdb
-                    .should_keep_attachment(&contract_call.to_clarity_contract_id(), &attachment)
-                {
-                    atlasdb
-                        .insert_uninstantiated_attachment(attachment)
-                        .map_err(|e| net_error::DBError(e))?;
-                }
-            }
+        if let Some(response) = ConversationHttp::check_admin_auth(
+            network,
+            on_admin_listener,
+            req,
+            "DELETE",
+            auth_token,
+            AdminRole::Operator,
+            response_metadata.clone(),
+        ) {
+            return response.send(http, fd).map(|_| ());
         }
 
-        response.send(http, fd).and_then(|_| Ok(accepted))
+        let removed = network.peerdb.tx_begin().and_then(|mut tx| {
+            let ban_opt = PeerDB::remove_peer_ban(&mut tx, ban_id)?;
+            tx.commit().map_err(db_error::SqliteError)?;
+            Ok(ban_opt)
+        });
+        let response = match removed {
+            Ok(Some(ban)) => {
+                info!("Lifted peer ban"; "ban_id" => ban_id, "target" => ?ban.target);
+                HttpResponseType::PeerBan(response_metadata, PeerBanInfo::from_peer_ban(&ban))
+            }
+            Ok(None) => HttpResponseType::NotFound(
+                response_metadata,
+                format!("No such peer ban: {}", ban_id),
+            ),
+            Err(e) => {
+                warn!("Failed to lift peer ban"; "ban_id" => ban_id, "error" => ?e);
+                HttpResponseType::ServerError(response_metadata, "Failed to lift peer ban".into())
+            }
+        };
+        response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a block.  Directly submit a Stacks block to this node's chain state.
-    /// Indicate whether or not the block was accepted (i.e. it was new, and valid)
-    fn handle_post_block<W: Write>(
+    /// Handle a request for a summary of the subnet's health: its chain tip and the L1 block that
+    /// anchors it, how far the L1 has moved since the last block commit, how many withdrawals are
+    /// not yet final, whether this node's miner is having its commits accepted, whether the L1
+    /// subnet contract's interface is compatible with this node, and whether the node has caught
+    /// up with the L1 since it started.
+    fn handle_get_subnet_status<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
+        burnchain_tip: &BlockSnapshot,
         sortdb: &SortitionDB,
         chainstate: &mut StacksChainState,
-        consensus_hash: &ConsensusHash,
-        block: &StacksBlock,
-        canonical_stacks_tip_height: u64,
-    ) -> Result<bool, net_error> {
-        let response_metadata =
-            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        // is this a consensus hash we recognize?
-        let (response, accepted) =
-            match SortitionDB::get_sortition_id_by_consensus(&sortdb.conn(), consensus_hash) {
-                Ok(Some(_)) => {
-                    // we recognize this consensus hash
-                    let ic = sortdb.index_conn();
-                    match Relayer::process_new_anchored_block(
-                        &ic,
-                        chainstate,
-                        consensus_hash,
-                        block,
-                        0,
-                    ) {
-                        Ok(true) => {
-                            debug!(
-                                "Accepted Stacks block {}/{}",
-                                consensus_hash,
-                                &block.block_hash()
-                            );
-                            (
-                                HttpResponseType::StacksBlockAccepted(
-                                    response_metadata,
-                                    StacksBlockHeader::make_index_block_hash(
-                                        consensus_hash,
-                                        &block.block_hash(),
-                                    ),
-                                    true,
-                                ),
-                                true,
-                            )
-                        }
-                        Ok(false) => {
-                            debug!(
-                                "Did not accept Stacks block {}/{}",
-                                consensus_hash,
-                                &block.block_hash()
-                            );
-                            (
-                                HttpResponseType::StacksBlockAccepted(
-                                    response_metadata,
-                                    StacksBlockHeader::make_index_block_hash(
-                                        consensus_hash,
-                                        &block.block_hash(),
-                                    ),
-                                    false,
-                                ),
-                                false,
-                            )
-                        }
-                        Err(e) => {
-                            error!(
-                                "Failed to process anchored block {}/{}: {:?}",
-                                consensus_hash,
-                                &block.block_hash(),
-                                &e
-                            );
-                            (
-                                HttpResponseType::ServerError(
-                                    response_metadata,
-                                    format!(
-                                        "Failed to process anchored block {}/{}: {:?}",
-                                        consensus_hash,
-                                        &block.block_hash(),
-                                        &e
-                                    ),
-                                ),
-                                false,
-                            )
-                        }
-                    }
-                }
-                Ok(None) => {
-                    debug!(
-                        "Unrecognized consensus hash {} for block {}",
-                        consensus_hash,
-                        &block.block_hash()
-                    );
-                    (
-                        HttpResponseType::NotFound(
-                            response_metadata,
-                            format!("No such consensus hash '{}'", consensus_hash),
-                        ),
-                        false,
-                    )
-                }
+        options: &ConnectionOptions,
+    ) -> Result<(), net_error> {
+        let response_metadata = HttpResponseMetadata::from_http_request_type(
+            req,
+            Some(burnchain_tip.canonical_stacks_tip_height),
+        );
+
+        let response =
+            match ConversationHttp::get_subnet_status(burnchain_tip, sortdb, chainstate, options) {
+                Ok(status) => HttpResponseType::SubnetStatus(response_metadata, status),
                 Err(e) => {
-                    error!(
-                        "Failed to query sortition ID by consensus '{}'",
-                        consensus_hash
-                    );
-                    (
-                        HttpResponseType::ServerError(
-                            response_metadata,
-                            format!(
-                                "Failed to query sortition ID for consensus hash '{}': {:?}",
-                                consensus_hash, &e
-                            ),
-                        ),
-                        false,
+                    warn!("Failed to load subnet status"; "error" => ?e);
+                    HttpResponseType::ServerError(
+                        response_metadata,
+                        "Failed to load subnet status".into(),
                     )
                 }
             };
-        response.send(http, fd).and_then(|_| Ok(accepted))
+        response.send(http, fd).map(|_| ())
     }
 
-    /// Handle a microblock.  Directly submit it to the microblock store so the client can see any
-    /// rejection reasons up-front (different from how the peer network handles it).  Indicate
-    /// whether or not the microblock was accepted (and thus needs to be forwarded) in the return
-    /// value.
-    fn handle_post_microblock<W: Write>(
+    fn get_subnet_status(
+        burnchain_tip: &BlockSnapshot,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        options: &ConnectionOptions,
+    ) -> Result<SubnetStatusResponse, net_error> {
+        let stacks_tip = StacksBlockHeader::make_index_block_hash(
+            &burnchain_tip.canonical_stacks_tip_consensus_hash,
+            &burnchain_tip.canonical_stacks_tip_hash,
+        );
+        let anchor_snapshot = SortitionDB::get_block_snapshot_consensus(
+            sortdb.conn(),
+            &burnchain_tip.canonical_stacks_tip_consensus_hash,
+        )?
+        .ok_or(net_error::DBError(db_error::NotFoundError))?;
+        let last_commit = sortdb
+            .index_handle(&burnchain_tip.sortition_id)
+            .get_last_snapshot_with_sortition(burnchain_tip.block_height)?;
+
+        let withdrawal_batching =
+            chainstate.get_withdrawal_batching(&sortdb.index_conn(), &stacks_tip);
+        let pending_withdrawals = ConversationHttp::count_pending_withdrawals(
+            chainstate,
+            &stacks_tip,
+            burnchain_tip.canonical_stacks_tip_height,
+            options.withdrawal_confirmation_depth,
+            withdrawal_batching,
+        )?;
+
+        let (miner_status, last_mined_block_height) = match options.subnet_miner_address {
+            None => (SubnetMinerStatus::NotMining, None),
+            Some(ref miner) => match chainstate.get_last_miner_info_in_fork(&stacks_tip, miner)? {
+                Some(miner_info) => (
+                    SubnetMinerStatus::Registered,
+                    Some(miner_info.stacks_block_height),
+                ),
+                None => (SubnetMinerStatus::Unconfirmed, None),
+            },
+        };
+        let l1_sync_status = options.l1_sync_status.get();
+
+        Ok(SubnetStatusResponse {
+            stacks_tip_height: burnchain_tip.canonical_stacks_tip_height,
+            stacks_tip: burnchain_tip.canonical_stacks_tip_hash.clone(),
+            stacks_tip_consensus_hash: burnchain_tip.canonical_stacks_tip_consensus_hash.clone(),
+            anchor_l1_block_hash: anchor_snapshot.burn_header_hash,
+            anchor_l1_block_height: anchor_snapshot.block_height,
+            l1_tip_height: burnchain_tip.block_height,
+            l1_blocks_since_last_commit: burnchain_tip
+                .block_height
+                .saturating_sub(last_commit.block_height),
+            pending_withdrawals,
+            miner_status,
+            last_mined_block_height,
+            event_signing_public_key: options
+                .event_signing_public_key
+                .as_ref()
+                .map(|public_key| public_key.to_hex()),
+            l1_contract_status: options.l1_contract_status.clone(),
+            l1_contract_interface_version: options.l1_contract_interface_version,
+            startup_state: l1_sync_status.startup_state,
+            l1_node_tip_height: l1_sync_status.l1_node_tip_height,
+        })
+    }
+
+    /// Count the withdrawals in the blocks of the fork ending at `tip` whose withdrawal roots are
+    /// not yet confirmed, i.e. fewer than `required_confirmations` blocks have been built on the
+    /// block (or on the last block of its batch, if withdrawals are batched).
+    fn count_pending_withdrawals(
+        chainstate: &StacksChainState,
+        tip: &StacksBlockId,
+        tip_height: u64,
+        required_confirmations: u64,
+        withdrawal_batching: Option<WithdrawalBatchConfig>,
+    ) -> Result<u64, net_error> {
+        let index_conn = chainstate.index_conn()?;
+        let mut pending_withdrawals = 0;
+        for block_height in (0..=tip_height).rev() {
+            let last_block_height = withdrawal_batching
+                .and_then(|batching| batching.batch_range(block_height))
+                .map(|(_, last_height)| last_height)
+                .unwrap_or(block_height);
+            if tip_height >= last_block_height
+                && tip_height - last_block_height >= required_confirmations
+            {
+                // every earlier block is confirmed as well
+                break;
+            }
+
+            let block = match index_conn.get_ancestor_block_hash(block_height, tip)? {
+                Some(block) => block,
+                None => break,
+            };
+            let header = match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                chainstate.db(),
+                &block,
+            )? {
+                Some(header) => header,
+                None => break,
+            };
+            // an odd number of leaves is padded with a duplicate, which is not a withdrawal
+            pending_withdrawals += (0..)
+                .take_while(|leaf_index| {
+                    header.withdrawal_tree.leaf_at_index(*leaf_index).is_some()
+                })
+                .count() as u64;
+        }
+        Ok(pending_withdrawals)
+    }
+
+    fn handle_get_generic_withdrawal_entry<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
-        consensus_hash: &ConsensusHash,
-        block_hash: &BlockHeaderHash,
+        sortdb: &SortitionDB,
         chainstate: &mut StacksChainState,
-        microblock: &StacksMicroblock,
+        canonical_tip: &StacksBlockId,
+        requested_block_height: u64,
+        withdrawal_leaf: WithdrawalLeafQuery,
         canonical_stacks_tip_height: u64,
-    ) -> Result<bool, net_error> {
+    ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let (response, accepted) =
-            match chainstate.preprocess_streamed_microblock(consensus_hash, block_hash, microblock)
-            {
-                Ok(accepted) => {
-                    if accepted {
-                        debug!(
-                            "Accepted uploaded microblock {}/{}-{}",
-                            &consensus_hash,
-                            &block_hash,
-                            &microblock.block_hash()
-                        );
-                    } else {
-                        debug!(
-                            "Did not accept microblock {}/{}-{}",
-                            &consensus_hash,
-                            &block_hash,
-                            &microblock.block_hash()
-                        );
-                    }
 
-                    (
-                        HttpResponseType::MicroblockHash(
-                            response_metadata,
-                            microblock.block_hash(),
-                        ),
-                        accepted,
-                    )
-                }
-                Err(e) => (
-                    HttpResponseType::BadRequestJSON(response_metadata, e.into_json()),
-                    false,
-                ),
+        let requested_block = match chainstate
+            .index_conn()
+            .map_err(|_| {
+                warn!("Failed to start MARF connection");
+                net_error::ChainstateError("Could not start MARF connection ".into())
+            })?
+            .get_ancestor_block_hash(requested_block_height, canonical_tip)
+        {
+            Ok(Some(x)) => x,
+            Err(_) | Ok(None) => {
+                return HttpResponseType::NotFound(
+                    response_metadata,
+                    "Supplied block height not found".into(),
+                )
+                .send(http, fd)
+                .map(|_| ())
+            }
+        };
+
+        let block_info_result = StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            chainstate.db(),
+            &requested_block,
+        );
+        let withdrawal_tree = match block_info_result {
+            Ok(Some(block_info)) => block_info.withdrawal_tree,
+            Err(_) | Ok(None) => {
+                return HttpResponseType::NotFound(
+                    response_metadata,
+                    "Supplied block not found".into(),
+                )
+                .send(http, fd)
+                .map(|_| ())
+            }
+        };
+
+        let merkle_path_and_leaf = match withdrawal_leaf {
+            WithdrawalLeafQuery::Key(ref withdrawal_key_bytes) => {
+                withdrawal_tree.path(withdrawal_key_bytes).map(|path| {
+                    (
+                        path,
+                        MerkleTree::<Sha512Trunc256Sum>::get_leaf_hash(withdrawal_key_bytes),
+                    )
+                })
+            }
+            WithdrawalLeafQuery::Index(withdrawal_id) => {
+                let leaf_index = withdrawal_id as usize;
+                withdrawal_tree
+                    .path_at_index(leaf_index)
+                    .zip(withdrawal_tree.leaf_at_index(leaf_index))
+            }
+        };
+
+        let withdrawal_batching =
+            chainstate.get_withdrawal_batching(&sortdb.index_conn(), canonical_tip);
+
+        // a withdrawal missing from the canonical chain may have been removed from it by a
+        //  reorg.  Only unbatched withdrawals are checked, since their blocks' own withdrawal
+        //  roots are the ones registered on the L1.
+        let expired_tree = match (&merkle_path_and_leaf, &withdrawal_leaf) {
+            (None, WithdrawalLeafQuery::Key(withdrawal_key_bytes))
+                if withdrawal_batching.is_none() =>
+            {
+                Self::find_expired_withdrawal_tree(
+                    chainstate,
+                    sortdb,
+                    requested_block_height,
+                    withdrawal_key_bytes,
+                )?
+            }
+            _ => None,
+        };
+        let (withdrawal_tree, merkle_path, leaf_hash, expired) =
+            match (merkle_path_and_leaf, expired_tree, withdrawal_leaf) {
+                (Some((merkle_path, leaf_hash)), _, _) => {
+                    (withdrawal_tree, merkle_path, leaf_hash, false)
+                }
+                (None, Some(expired_tree), WithdrawalLeafQuery::Key(withdrawal_key_bytes)) => {
+                    let merkle_path = expired_tree
+                        .path(&withdrawal_key_bytes)
+                        .expect("BUG: expired withdrawal tree does not contain the withdrawal");
+                    let leaf_hash =
+                        MerkleTree::<Sha512Trunc256Sum>::get_leaf_hash(&withdrawal_key_bytes);
+                    (expired_tree, merkle_path, leaf_hash, true)
+                }
+                _ => {
+                    return HttpResponseType::NotFound(
+                        response_metadata,
+                        "Supplied withdrawal key not found".into(),
+                    )
+                    .send(http, fd)
+                    .map(|_| ())
+                }
             };
 
-        response.send(http, fd).and_then(|_| Ok(accepted))
+        let claim_txid = if expired {
+            None
+        } else {
+            match sortdb
+                .index_handle_at_tip()
+                .get_withdrawal_claim(&leaf_hash)
+            {
+                Ok(claim) => claim.map(|claim| claim.txid),
+                Err(e) => {
+                    warn!("Failed to load withdrawal claim";
+                          "withdrawal_leaf_hash" => %leaf_hash,
+                          "error" => ?e);
+                    return HttpResponseType::ServerError(
+                        response_metadata,
+                        "Failed to query withdrawal claims".into(),
+                    )
+                    .send(http, fd)
+                    .map(|_| ());
+                }
+            }
+        };
+        let claim_status = match (expired, claim_txid.as_ref()) {
+            (true, _) => WithdrawalClaimStatus::Expired,
+            (false, Some(_)) => WithdrawalClaimStatus::Claimed,
+            (false, None) => WithdrawalClaimStatus::Unclaimed,
+        };
+
+        // batched withdrawals are proven against their batch's root
+        let batch_range = if expired {
+            None
+        } else {
+            withdrawal_batching.and_then(|batching| batching.batch_range(requested_block_height))
+        };
+        let (merkle_path, withdrawal_root) = match batch_range {
+            Some((first_height, last_height)) => {
+                let batch_tree = match chainstate.get_withdrawal_roots_in_range(
+                    canonical_tip,
+                    first_height,
+                    last_height,
+                ) {
+                    Ok(Some(roots)) => withdrawal::create_withdrawal_batch_merkle_tree(&roots),
+                    Err(_) | Ok(None) => {
+                        return HttpResponseType::NotFound(
+                            response_metadata,
+                            "Withdrawal batch for this block height is not complete".into(),
+                        )
+                        .send(http, fd)
+                        .map(|_| ())
+                    }
+                };
+                let block_index = (requested_block_height - first_height) as usize;
+                match withdrawal::extend_withdrawal_path_to_batch(
+                    merkle_path,
+                    &batch_tree,
+                    block_index,
+                ) {
+                    Some(path) => (path, batch_tree.root()),
+                    None => {
+                        return HttpResponseType::NotFound(
+                            response_metadata,
+                            "Supplied block not found in withdrawal batch".into(),
+                        )
+                        .send(http, fd)
+                        .map(|_| ())
+                    }
+                }
+            }
+            None => (merkle_path, withdrawal_tree.root()),
+        };
+
+        let tuple_vec: Vec<_> = merkle_path
+            .into_iter()
+            .map(|merkle_point| {
+                let MerklePathPoint {
+                    order,
+                    hash: sibling_hash,
+                } = merkle_point;
+                // the sibling hash is the left sibling if the merkle path point order is right
+                //  because the merkle path point order is in reference to the leaf
+                let is_sibling_left_side = order == MerklePathOrder::Right;
+                // make the clarity tuple
+                Value::Tuple(
+                    TupleData::from_data(vec![
+                        ("hash".into(), withdrawal::buffer_from_hash(sibling_hash)),
+                        ("is-left-side".into(), Value::Bool(is_sibling_left_side)),
+                    ])
+                    .expect("Failed to construct Clarity repr of merkle tree entry"),
+                )
+            })
+            .collect();
+
+        let sibling_hashes = match Value::list_from(tuple_vec) {
+            Ok(list) => list,
+            Err(_) => {
+                error!("Failed to construct a valid Clarity list type out of withdrawal merkle path";
+                       "l2_block_id" => %requested_block);
+                return HttpResponseType::NotFound(
+                    response_metadata,
+                    "Withdrawal merkle tree at this block height is invalid".into(),
+                )
+                .send(http, fd)
+                .map(|_| ());
+            }
+        };
+
+        // the L1 block that registered the root the withdrawal is proven against
+        let commit_l1_block = if expired {
+            None
+        } else {
+            match sortdb
+                .index_handle_at_tip()
+                .get_block_commit_snapshot_with_withdrawal_root(&withdrawal_root)
+            {
+                Ok(snapshot) => snapshot.map(|snapshot| WithdrawalCommitL1Block {
+                    l1_block_hash: snapshot.burn_header_hash,
+                    l1_block_height: snapshot.block_height,
+                }),
+                Err(e) => {
+                    warn!("Failed to load the block commit of a withdrawal root";
+                          "withdrawal_root" => %withdrawal_root,
+                          "error" => ?e);
+                    return HttpResponseType::ServerError(
+                        response_metadata,
+                        "Failed to query block commits".into(),
+                    )
+                    .send(http, fd)
+                    .map(|_| ());
+                }
+            }
+        };
+
+        let withdrawal_root = withdrawal::buffer_from_hash(withdrawal_root);
+        let withdrawal_leaf_hash = withdrawal::buffer_from_hash(leaf_hash);
+
+        let response = WithdrawalResponse {
+            withdrawal_root: format!("0x{}", withdrawal_root.serialize()),
+            withdrawal_leaf_hash: format!("0x{}", withdrawal_leaf_hash.serialize()),
+            sibling_hashes: format!("0x{}", sibling_hashes.serialize()),
+            claim_status,
+            claim_txid: claim_txid.map(|txid| format!("0x{}", txid)),
+            commit_l1_block,
+        };
+
+        HttpResponseType::GetWithdrawal(response_metadata, response)
+            .send(http, fd)
+            .map(|_| ())
     }
 
-    /// Handle a request for mempool transactions in bulk
-    fn handle_mempool_query<W: Write>(
+    /// Find the withdrawal tree of a block at `block_height` that is no longer in the canonical
+    /// chain and contains the withdrawal with the given key.  Returns None unless every such
+    /// block's withdrawal root is missing from the canonical L1 fork, since the withdrawal can
+    /// still be claimed against any root that is registered there.
+    fn find_expired_withdrawal_tree(
+        chainstate: &StacksChainState,
+        sortdb: &SortitionDB,
+        block_height: u64,
+        withdrawal_key_bytes: &[u8],
+    ) -> Result<Option<MerkleTree<Sha512Trunc256Sum>>, net_error> {
+        let headers =
+            StacksChainState::get_stacks_block_headers_at_height(chainstate.db(), block_height)
+                .map_err(|e| net_error::ChainstateError(format!("{:?}", &e)))?;
+        let sort_handle = sortdb.index_handle_at_tip();
+        let mut expired_tree = None;
+        for header in headers.into_iter() {
+            if header.withdrawal_tree.path(withdrawal_key_bytes).is_none() {
+                continue;
+            }
+            if sort_handle
+                .has_block_commit_with_withdrawal_root(&header.withdrawal_tree.root())
+                .map_err(net_error::DBError)?
+            {
+                return Ok(None);
+            }
+            expired_tree.get_or_insert(header.withdrawal_tree);
+        }
+        Ok(expired_tree)
+    }
+
+    /// Handle a GET on an existing account, given the current chain tip.  Optionally supplies a
+    /// MARF proof for each account detail loaded from the chain tip.
+    fn handle_get_account_entry<W: Write>(
         http: &mut StacksHttp,
         fd: &mut W,
         req: &HttpRequestType,
         sortdb: &SortitionDB,
-        chainstate: &StacksChainState,
-        query: MemPoolSyncData,
-        max_txs: u64,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        account: &PrincipalData,
+        with_proof: bool,
         canonical_stacks_tip_height: u64,
-        page_id: Option<Txid>,
-    ) -> Result<StreamCursor, net_error> {
+    ) -> Result<(), net_error> {
         let response_metadata =
             HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
-        let response = HttpResponseType::MemPoolTxStream(response_metadata);
-        let height = chainstate
-            .get_stacks_chain_tip(sortdb)?
-            .map(|blk| blk.height)
-            .unwrap_or(0);
+        let response =
+            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                clarity_tx.with_clarity_db_readonly(|clarity_db| {
+                    let key = ClarityDatabase::make_key_for_account_balance(&account);
+                    let burn_block_height = clarity_db.get_current_burnchain_block_height() as u64;
+                    let (balance, balance_proof) = if with_proof {
+                        clarity_db
+                            .get_with_proof::<STXBalance>(&key)
+                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))
+                            .unwrap_or_else(|| (STXBalance::zero(), Some("".into())))
+                    } else {
+                        clarity_db
+                            .get::<STXBalance>(&key)
+                            .map(|a| (a, None))
+                            .unwrap_or_else(|| (STXBalance::zero(), None))
+                    };
 
-        debug!(
-            "Begin mempool query";
-            "page_id" => %page_id.map(|txid| format!("{}", &txid)).unwrap_or("(none".to_string()),
-            "block_height" => height,
-            "max_txs" => max_txs
-        );
+                    let key = ClarityDatabase::make_key_for_account_nonce(&account);
+                    let (nonce, nonce_proof) = if with_proof {
+                        clarity_db
+                            .get_with_proof(&key)
+                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))
+                            .unwrap_or_else(|| (0, Some("".into())))
+                    } else {
+                        clarity_db
+                            .get(&key)
+                            .map(|a| (a, None))
+                            .unwrap_or_else(|| (0, None))
+                    };
 
-        let stream = StreamCursor::new_tx_stream(query, max_txs, height, page_id);
-        response.send(http, fd).and_then(|_| Ok(stream))
+                    let unlocked = balance.get_available_balance_at_burn_block(burn_block_height);
+                    let (locked, unlock_height) =
+                        balance.get_locked_balance_at_burn_block(burn_block_height);
+
+                    let balance = format!("0x{}", to_hex(&unlocked.to_be_bytes()));
+                    let locked = format!("0x{}", to_hex(&locked.to_be_bytes()));
+
+                    AccountEntryResponse {
+                        balance,
+                        locked,
+                        unlock_height,
+                        nonce,
+                        balance_proof,
+                        nonce_proof,
+                    }
+                })
+            }) {
+                Ok(Some(data)) => HttpResponseType::GetAccount(response_metadata, data),
+                Ok(None) | Err(_) => {
+                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+                }
+            };
+
+        response.send(http, fd).map(|_| ())
     }
 
-    /// Handle an external HTTP request.
-    /// Some requests, such as those for blocks, will create new reply streams.  This method adds
-    /// those new streams into the `reply_streams` set.
-    /// Returns a StacksMessageType option -- it's Some(...) if we need to forward a message to the
-    /// peer network (like a transaction or a block or microblock)
-    pub fn handle_request(
-        &mut self,
-        req: HttpRequestType,
-        network: &mut PeerNetwork,
+    /// Handle a GET on a smart contract's data var, given the current chain tip.  Optionally
+    /// supplies a MARF proof for the value.
+    fn handle_get_data_var<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
         sortdb: &SortitionDB,
         chainstate: &mut StacksChainState,
-        mempool: &mut MemPoolDB,
-        handler_opts: &RPCHandlerArgs,
-    ) -> Result<Option<StacksMessageType>, net_error> {
-        let mut reply = self.connection.make_relay_handle(self.conn_id)?;
-        let keep_alive = req.metadata().keep_alive;
-        let mut ret = None;
+        tip: &StacksBlockId,
+        contract_addr: &StacksAddress,
+        contract_name: &ContractName,
+        var_name: &ClarityName,
+        with_proof: bool,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let contract_identifier =
+            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
 
-        let stream_opt = match req {
-            HttpRequestType::GetInfo(ref _md) => {
-                ConversationHttp::handle_getinfo(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    network,
-                    chainstate,
-                    handler_opts,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
+        let response =
+            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                clarity_tx.with_clarity_db_readonly(|clarity_db| {
+                    let key = ClarityDatabase::make_key_for_trip(
+                        &contract_identifier,
+                        StoreType::Variable,
+                        var_name,
+                    );
+
+                    let (value, marf_proof) = if with_proof {
+                        clarity_db
+                            .get_with_proof::<Value>(&key)
+                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))?
+                    } else {
+                        clarity_db.get::<Value>(&key).map(|a| (a, None))?
+                    };
+
+                    let data = format!("0x{}", value.serialize());
+                    Some(DataVarResponse { data, marf_proof })
+                })
+            }) {
+                Ok(Some(Some(data))) => HttpResponseType::GetDataVar(response_metadata, data),
+                Ok(Some(None)) => {
+                    HttpResponseType::NotFound(response_metadata, "Data var not found".into())
+                }
+                Ok(None) | Err(_) => {
+                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+                }
+            };
+
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a request for a proof that a transaction was included in the given anchored block,
+    /// against the block header's transaction merkle root.
+    fn handle_get_transaction_proof<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        chainstate: &StacksChainState,
+        index_block_hash: &StacksBlockId,
+        txid: &Txid,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+
+        let block = match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+            chainstate.db(),
+            index_block_hash,
+        ) {
+            Ok(Some(header_info)) => StacksChainState::load_block(
+                &chainstate.blocks_path,
+                &header_info.consensus_hash,
+                &header_info.anchored_header.block_hash(),
+            )
+            .unwrap_or(None),
+            Ok(None) | Err(_) => None,
+        };
+
+        let response = match block {
+            Some(block) => match TransactionInclusionProof::from_block(&block, txid) {
+                Some(proof) => HttpResponseType::TransactionProof(
+                    response_metadata,
+                    TransactionProofResponse {
+                        index_block_hash: index_block_hash.clone(),
+                        tx_merkle_root: block.header.tx_merkle_root.clone(),
+                        txid: format!("0x{}", txid),
+                        index: proof.index,
+                        proof: format!("0x{}", to_hex(&proof.serialize_to_vec())),
+                    },
+                ),
+                None => HttpResponseType::NotFound(
+                    response_metadata,
+                    "Transaction not found in block".into(),
+                ),
+            },
+            None => {
+                HttpResponseType::NotFound(response_metadata, "Anchored block not found".into())
             }
-            HttpRequestType::GetNeighbors(ref _md) => {
-                ConversationHttp::handle_getneighbors(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    network,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
+        };
+
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a request for whether or not the L1 deposit operation(s) in the given L1 transaction
+    /// have been materialized in the fork ending at `tip`.
+    fn handle_get_deposit_status<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &StacksChainState,
+        tip: &StacksBlockId,
+        l1_txid: &Txid,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+
+        let materialized = chainstate.index_conn().and_then(|index_conn| {
+            StacksChainState::get_materialized_deposits(&index_conn, tip, l1_txid)
+        });
+        let materialized = match materialized {
+            Ok(materialized) => materialized,
+            Err(e) => {
+                warn!("Failed to load materialized deposits";
+                      "l1_txid" => %l1_txid,
+                      "error" => ?e);
+                return HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to query materialized deposits".into(),
+                )
+                .send(http, fd)
+                .map(|_| ());
             }
-            HttpRequestType::GetHeaders(ref _md, ref quantity, ref tip_req) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_getheaders(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        &tip,
-                        *quantity,
-                        chainstate,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?
-                } else {
-                    None
+        };
+
+        let status = if !materialized.is_empty() {
+            DepositStatus::Materialized
+        } else {
+            match SortitionDB::has_deposit_op(sortdb.conn(), l1_txid) {
+                Ok(true) => DepositStatus::Pending,
+                Ok(false) => {
+                    return HttpResponseType::NotFound(
+                        response_metadata,
+                        "No deposit operation with this L1 txid".into(),
+                    )
+                    .send(http, fd)
+                    .map(|_| ());
+                }
+                Err(e) => {
+                    warn!("Failed to load deposit operations";
+                          "l1_txid" => %l1_txid,
+                          "error" => ?e);
+                    return HttpResponseType::ServerError(
+                        response_metadata,
+                        "Failed to query deposit operations".into(),
+                    )
+                    .send(http, fd)
+                    .map(|_| ());
                 }
             }
-            HttpRequestType::GetBlock(ref _md, ref index_block_hash) => {
-                ConversationHttp::handle_getblock(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    index_block_hash,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?
+        };
+
+        let response = DepositStatusResponse {
+            l1_txid: format!("0x{}", l1_txid),
+            status,
+            deposits: materialized
+                .into_iter()
+                .map(|record| MaterializedDepositInfo {
+                    deposit_type: record.deposit_type,
+                    l1_block_id: format!("0x{}", &record.l1_block_id),
+                    index_block_hash: record.index_block_hash,
+                    block_height: record.block_height,
+                })
+                .collect(),
+        };
+
+        HttpResponseType::DepositStatus(response_metadata, response)
+            .send(http, fd)
+            .map(|_| ())
+    }
+
+    /// Handle a request for the receipt of the transaction `txid`, if it was processed in the fork
+    /// ending at `tip`.
+    fn handle_get_transaction_receipt<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        chainstate: &StacksChainState,
+        tip: &StacksBlockId,
+        txid: &Txid,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+
+        let receipt = chainstate.index_conn().and_then(|index_conn| {
+            StacksChainState::get_transaction_receipt(&index_conn, tip, txid)
+        });
+        let response = match receipt {
+            Ok(Some(receipt)) => HttpResponseType::TransactionReceipt(response_metadata, receipt),
+            Ok(None) => HttpResponseType::NotFound(
+                response_metadata,
+                "No processed transaction with this txid".into(),
+            ),
+            Err(e) => {
+                warn!("Failed to load transaction receipt";
+                      "txid" => %txid,
+                      "error" => ?e);
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to query transaction receipts".into(),
+                )
             }
-            HttpRequestType::GetMicroblocksIndexed(ref _md, ref index_head_hash) => {
-                ConversationHttp::handle_getmicroblocks_indexed(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    index_head_hash,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?
+        };
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a request for the nonces missing from an account's pending transactions, which keep
+    /// the transactions after them from being mined.
+    fn handle_get_nonce_gaps<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        mempool: &MemPoolDB,
+        tip: &StacksBlockId,
+        address: &StacksAddress,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+
+        let principal = PrincipalData::from(address.clone());
+        let next_nonce =
+            chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                StacksChainState::get_account(clarity_tx, &principal).nonce
+            });
+        let next_nonce = match next_nonce {
+            Ok(Some(nonce)) => nonce,
+            Ok(None) | Err(_) => {
+                return HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+                    .send(http, fd)
+                    .map(|_| ());
             }
-            HttpRequestType::GetMicroblocksConfirmed(ref _md, ref anchor_index_block_hash) => {
-                ConversationHttp::handle_getmicroblocks_confirmed(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    anchor_index_block_hash,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?
+        };
+
+        let response = match MemPoolDB::get_nonce_gaps(mempool.conn(), address, next_nonce) {
+            Ok(gaps) => HttpResponseType::NonceGaps(
+                response_metadata,
+                NonceGapsResponse {
+                    principal: principal.to_string(),
+                    next_nonce: gaps.next_nonce,
+                    last_known_nonce: gaps.last_known_nonce,
+                    pending_nonces: gaps.pending_nonces,
+                    missing_nonces: gaps.missing_nonces,
+                    blocked_txids: gaps
+                        .blocked_txids
+                        .iter()
+                        .map(|txid| format!("0x{}", txid))
+                        .collect(),
+                },
+            ),
+            Err(e) => {
+                warn!("Failed to find nonce gaps";
+                      "address" => %address,
+                      "error" => ?e);
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to query the mempool".into(),
+                )
             }
-            HttpRequestType::GetMicroblocksUnconfirmed(
-                ref _md,
-                ref index_anchor_block_hash,
-                ref min_seq,
-            ) => ConversationHttp::handle_getmicroblocks_unconfirmed(
-                &mut self.connection.protocol,
-                &mut reply,
-                &req,
-                index_anchor_block_hash,
-                *min_seq,
-                chainstate,
-                network.burnchain_tip.canonical_stacks_tip_height,
-            )?,
-            HttpRequestType::GetTransactionUnconfirmed(ref _md, ref txid) => {
-                ConversationHttp::handle_gettransaction_unconfirmed(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    chainstate,
-                    mempool,
-                    txid,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
+        };
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a request for a page of the pending transactions in the mempool, in order of
+    /// decreasing fee.
+    fn handle_get_mempool_txs<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        mempool: &MemPoolDB,
+        filter: &MemPoolListFilter,
+        cursor: Option<&MemPoolListCursor>,
+        limit: u32,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+
+        let response = match MemPoolDB::list_txs(mempool.conn(), filter, cursor, limit) {
+            Ok((txs, next_cursor)) => HttpResponseType::MemPoolListing(
+                response_metadata,
+                MemPoolListResponse {
+                    transactions: txs
+                        .into_iter()
+                        .map(|tx_info| MemPoolTxEntry {
+                            txid: format!("0x{}", tx_info.metadata.txid),
+                            origin_address: tx_info.metadata.origin_address.to_string(),
+                            origin_nonce: tx_info.metadata.origin_nonce,
+                            sponsor_address: tx_info.metadata.sponsor_address.to_string(),
+                            sponsor_nonce: tx_info.metadata.sponsor_nonce,
+                            tx_fee: tx_info.metadata.tx_fee,
+                            tx_len: tx_info.metadata.len,
+                            payload_type: tx_info.tx.payload.name().to_string(),
+                            block_height: tx_info.metadata.block_height,
+                            accept_time: tx_info.metadata.accept_time,
+                        })
+                        .collect(),
+                    next_cursor: next_cursor.map(|cursor| cursor.to_string()),
+                },
+            ),
+            Err(e) => {
+                warn!("Failed to list mempool transactions"; "error" => ?e);
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to query the mempool".into(),
+                )
             }
-            HttpRequestType::GetAccount(ref _md, ref principal, ref tip_req, ref with_proof) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_account_entry(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        sortdb,
-                        chainstate,
-                        &tip,
-                        principal,
-                        *with_proof,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
-                }
-                None
-            }
-            HttpRequestType::GetDataVar(
-                ref _md,
-                ref contract_addr,
-                ref contract_name,
-                ref var_name,
-                ref tip_req,
-                ref with_proof,
-            ) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_data_var(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        sortdb,
-                        chainstate,
-                        &tip,
-                        contract_addr,
-                        contract_name,
-                        var_name,
-                        *with_proof,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
-                }
-                None
-            }
-            HttpRequestType::GetMapEntry(
-                ref _md,
-                ref contract_addr,
-                ref contract_name,
-                ref map_name,
-                ref key,
-                ref tip_req,
-                ref with_proof,
-            ) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_map_entry(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        sortdb,
-                        chainstate,
-                        &tip,
-                        contract_addr,
-                        contract_name,
-                        map_name,
-                        key,
-                        *with_proof,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
-                }
-                None
+        };
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a request for the number of pending transactions in the mempool and a histogram of
+    /// their fees.
+    fn handle_get_mempool_summary<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        mempool: &MemPoolDB,
+        filter: &MemPoolListFilter,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+
+        let response = match MemPoolDB::summarize_txs(mempool.conn(), filter) {
+            Ok(summary) => HttpResponseType::MemPoolSummary(
+                response_metadata,
+                MemPoolSummaryResponse {
+                    count: summary.count,
+                    fee_histogram: summary
+                        .fee_histogram
+                        .into_iter()
+                        .map(|bucket| MemPoolFeeBucketResponse {
+                            min_fee: bucket.min_fee,
+                            max_fee: bucket.max_fee,
+                            count: bucket.count,
+                        })
+                        .collect(),
+                },
+            ),
+            Err(e) => {
+                warn!("Failed to summarize mempool transactions"; "error" => ?e);
+                HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to query the mempool".into(),
+                )
             }
-            HttpRequestType::GetTransferCost(ref _md) => {
-                ConversationHttp::handle_token_transfer_cost(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
+        };
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a request for a MARF proof of the value stored under an arbitrary Clarity data key,
+    /// against the state root of the given anchored block.
+    fn handle_get_clarity_data_proof<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        key: &ClarityDataKey,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+
+        let clarity_key = match key.to_clarity_key() {
+            Ok(clarity_key) => clarity_key,
+            Err(msg) => {
+                return HttpResponseType::BadRequest(response_metadata, msg)
+                    .send(http, fd)
+                    .map(|_| ());
             }
-            HttpRequestType::GetContractABI(
-                ref _md,
-                ref contract_addr,
-                ref contract_name,
-                ref tip_req,
-            ) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_contract_abi(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        sortdb,
-                        chainstate,
-                        &tip,
-                        contract_addr,
-                        contract_name,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
+        };
+
+        // proofs are only useful against a state root that is in a block header
+        let state_index_root =
+            match StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                chainstate.db(),
+                tip,
+            ) {
+                Ok(Some(header_info)) => header_info.anchored_header.state_index_root,
+                Ok(None) | Err(_) => {
+                    return HttpResponseType::NotFound(
+                        response_metadata,
+                        "Anchored block not found".into(),
+                    )
+                    .send(http, fd)
+                    .map(|_| ());
                 }
-                None
-            }
-            HttpRequestType::FeeRateEstimate(ref _md, ref tx, estimated_len) => {
-                ConversationHttp::handle_post_fee_rate_estimate(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    handler_opts,
-                    sortdb,
-                    tx,
-                    estimated_len,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
-            }
-            HttpRequestType::CallReadOnlyFunction(
-                ref _md,
-                ref ctrct_addr,
-                ref ctrct_name,
-                ref as_sender,
-                ref func_name,
-                ref args,
-                ref tip_req,
-            ) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_readonly_function_call(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        sortdb,
-                        chainstate,
-                        &tip,
-                        ctrct_addr,
-                        ctrct_name,
-                        func_name,
-                        as_sender,
-                        args,
-                        &self.connection.options,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
+            };
+
+        let response =
+            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                clarity_tx.with_clarity_db_readonly(|clarity_db| {
+                    clarity_db.get_with_proof::<String>(&clarity_key)
+                })
+            }) {
+                Ok(Some(Some((data, marf_proof)))) => HttpResponseType::ClarityDataProof(
+                    response_metadata,
+                    ClarityDataProofResponse {
+                        index_block_hash: tip.clone(),
+                        state_index_root,
+                        clarity_key,
+                        data,
+                        marf_proof: format!("0x{}", to_hex(&marf_proof)),
+                    },
+                ),
+                Ok(Some(None)) => {
+                    HttpResponseType::NotFound(response_metadata, "Data key not found".into())
                 }
-                None
-            }
-            HttpRequestType::GetContractSrc(
-                ref _md,
-                ref contract_addr,
-                ref contract_name,
-                ref tip_req,
-                ref with_proof,
-            ) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_contract_src(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        sortdb,
-                        chainstate,
-                        &tip,
-                        contract_addr,
-                        contract_name,
-                        *with_proof,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
+                Ok(None) | Err(_) => {
+                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
                 }
-                None
-            }
-            HttpRequestType::PostTransaction(ref _md, ref tx, ref attachment) => {
-                match chainstate.get_stacks_chain_tip(sortdb)? {
-                    Some(tip) => {
-                        let accepted = ConversationHttp::handle_post_transaction(
-                            &mut self.connection.protocol,
-                            &mut reply,
-                            &req,
-                            chainstate,
-                            sortdb,
-                            tip.consensus_hash,
-                            tip.anchored_block_hash,
-                            mempool,
-                            tx.clone(),
-                            &mut network.atlasdb,
-                            attachment.clone(),
-                            handler_opts.event_observer.as_deref(),
-                            network.burnchain_tip.canonical_stacks_tip_height,
-                        )?;
-                        if accepted {
-                            // forward to peer network
-                            ret = Some(StacksMessageType::Transaction(tx.clone()));
-                        }
-                    }
-                    None => {
-                        let response_metadata = HttpResponseMetadata::from_http_request_type(
-                            &req,
-                            Some(network.burnchain_tip.canonical_stacks_tip_height),
-                        );
-                        warn!("Failed to load Stacks chain tip");
-                        let response = HttpResponseType::ServerError(
-                            response_metadata,
-                            format!("Failed to load Stacks chain tip"),
-                        );
-                        response.send(&mut self.connection.protocol, &mut reply)?;
-                    }
+            };
+
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a GET on a smart contract's data map, given the current chain tip.  Optionally
+    /// supplies a MARF proof for the value.
+    fn handle_get_map_entry<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        contract_addr: &StacksAddress,
+        contract_name: &ContractName,
+        map_name: &ClarityName,
+        key: &Value,
+        with_proof: bool,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let contract_identifier =
+            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
+
+        let response =
+            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                clarity_tx.with_clarity_db_readonly(|clarity_db| {
+                    let key = ClarityDatabase::make_key_for_data_map_entry(
+                        &contract_identifier,
+                        map_name,
+                        key,
+                    );
+                    let (value, marf_proof) = if with_proof {
+                        clarity_db
+                            .get_with_proof::<Value>(&key)
+                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))
+                            .unwrap_or_else(|| {
+                                test_debug!("No value for '{}' in {}", &key, tip);
+                                (Value::none(), Some("".into()))
+                            })
+                    } else {
+                        clarity_db
+                            .get::<Value>(&key)
+                            .map(|a| (a, None))
+                            .unwrap_or_else(|| {
+                                test_debug!("No value for '{}' in {}", &key, tip);
+                                (Value::none(), None)
+                            })
+                    };
+
+                    let data = format!("0x{}", value.serialize());
+                    MapEntryResponse { data, marf_proof }
+                })
+            }) {
+                Ok(Some(data)) => HttpResponseType::GetMapEntry(response_metadata, data),
+                Ok(None) | Err(_) => {
+                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
                 }
-                None
-            }
-            HttpRequestType::GetAttachment(ref _md, ref content_hash) => {
-                ConversationHttp::handle_getattachment(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    &mut network.atlasdb,
-                    content_hash.clone(),
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
-            }
-            HttpRequestType::GetAttachmentsInv(
-                ref _md,
-                ref index_block_hash,
-                ref pages_indexes,
-            ) => {
-                ConversationHttp::handle_getattachmentsinv(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    &mut network.atlasdb,
-                    &index_block_hash,
-                    pages_indexes,
-                    &self.connection.options,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
-            }
-            HttpRequestType::PostBlock(ref _md, ref consensus_hash, ref block) => {
-                let accepted = ConversationHttp::handle_post_block(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    sortdb,
-                    chainstate,
-                    consensus_hash,
-                    block,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                if accepted {
-                    // inform the peer network so it can announce its presence
-                    ret = Some(StacksMessageType::Blocks(BlocksData {
-                        blocks: vec![BlocksDatum(consensus_hash.clone(), block.clone())],
-                    }));
+            };
+
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a GET to list the entries of a smart contract's data map at the given chain tip,
+    /// in the order of their serialized keys.  Only answered if the node enables map iteration.
+    fn handle_get_map_entries<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        contract_addr: &StacksAddress,
+        contract_name: &ContractName,
+        map_name: &ClarityName,
+        after: Option<&Value>,
+        limit: u32,
+        options: &ConnectionOptions,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        if !options.enable_map_iteration {
+            let response = HttpResponseType::Forbidden(
+                response_metadata,
+                "Map iteration is not enabled on this node".into(),
+            );
+            return response.send(http, fd).map(|_| ());
+        }
+        let contract_identifier =
+            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
+
+        let response =
+            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                clarity_tx.with_clarity_db_readonly(|clarity_db| {
+                    clarity_db.list_data_map_entries(&contract_identifier, map_name, after, limit)
+                })
+            }) {
+                Ok(Some(Ok(None))) => HttpResponseType::Forbidden(
+                    response_metadata,
+                    "Data map was defined before this node indexed map keys".into(),
+                ),
+                Ok(Some(Ok(Some(entries)))) => {
+                    let next_key = if entries.len() >= limit as usize {
+                        entries
+                            .last()
+                            .map(|(key, _)| format!("0x{}", key.serialize()))
+                    } else {
+                        None
+                    };
+                    let entries = entries
+                        .into_iter()
+                        .map(|(key, value)| MapEntriesItem {
+                            key: format!("0x{}", key.serialize()),
+                            value: format!("0x{}", value.serialize()),
+                        })
+                        .collect();
+                    HttpResponseType::MapEntries(
+                        response_metadata,
+                        MapEntriesResponse { entries, next_key },
+                    )
                 }
-                None
-            }
-            HttpRequestType::PostMicroblock(ref _md, ref mblock, ref tip_req) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    if let Some((consensus_hash, block_hash)) =
-                        ConversationHttp::handle_load_stacks_chain_tip_hashes(
-                            &mut self.connection.protocol,
-                            &mut reply,
-                            &req,
-                            tip,
-                            chainstate,
-                            network.burnchain_tip.canonical_stacks_tip_height,
-                        )?
-                    {
-                        let accepted = ConversationHttp::handle_post_microblock(
-                            &mut self.connection.protocol,
-                            &mut reply,
-                            &req,
-                            &consensus_hash,
-                            &block_hash,
-                            chainstate,
-                            mblock,
-                            network.burnchain_tip.canonical_stacks_tip_height,
-                        )?;
-                        if accepted {
-                            // forward to peer network
-                            let tip = StacksBlockHeader::make_index_block_hash(
-                                &consensus_hash,
-                                &block_hash,
-                            );
-                            ret = Some(StacksMessageType::Microblocks(MicroblocksData {
-                                index_anchor_block: tip,
-                                microblocks: vec![(*mblock).clone()],
-                            }));
-                        }
-                    }
+                Ok(Some(Err(e))) => {
+                    debug!(
+                        "Failed to list entries of {}.{}: {:?}",
+                        &contract_identifier, map_name, &e
+                    );
+                    HttpResponseType::NotFound(response_metadata, "No such data map".into())
                 }
-                None
-            }
-            HttpRequestType::MemPoolQuery(ref _md, ref query, ref page_id_opt) => {
-                Some(ConversationHttp::handle_mempool_query(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    sortdb,
-                    chainstate,
-                    query.clone(),
-                    network.connection_opts.mempool_max_tx_query,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                    page_id_opt.clone(),
-                )?)
-            }
-            HttpRequestType::OptionsPreflight(ref _md, ref _path) => {
-                let response_metadata = HttpResponseMetadata::from_http_request_type(
-                    &req,
-                    Some(network.burnchain_tip.canonical_stacks_tip_height),
-                );
-                let response = HttpResponseType::OptionsPreflight(response_metadata);
-                response
-                    .send(&mut self.connection.protocol, &mut reply)
-                    .map(|_| ())?;
-                None
-            }
-            HttpRequestType::GetIsTraitImplemented(
-                ref _md,
-                ref contract_addr,
-                ref contract_name,
-                ref trait_id,
-                ref tip_req,
-            ) => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    tip_req,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_is_trait_implemented(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        sortdb,
-                        chainstate,
-                        &tip,
-                        contract_addr,
-                        contract_name,
-                        trait_id,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
+                Ok(None) | Err(_) => {
+                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+                }
+            };
+
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a POST to run a read-only function call with the given parameters on the given chain
+    /// tip.  Returns the result of the function call.  Returns a CallReadOnlyResponse on success.
+    /// The call is metered against the caller's requested cost budget, if given (see
+    /// `readonly_call_budget()`), and the node's `read_only_call_limit` otherwise.
+    fn handle_readonly_function_call<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        contract_addr: &StacksAddress,
+        contract_name: &ContractName,
+        function: &ClarityName,
+        sender: &PrincipalData,
+        args: &[Value],
+        cost_budget: Option<&ExecutionCost>,
+        options: &ConnectionOptions,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let budget = ConversationHttp::readonly_call_budget(cost_budget, options);
+        let data_opt_res = ConversationHttp::run_readonly_function_call(
+            sortdb,
+            chainstate,
+            tip,
+            contract_addr,
+            contract_name,
+            function,
+            sender,
+            args,
+            budget.clone(),
+            vec![],
+        );
+
+        let response =
+            match ConversationHttp::readonly_function_call_response(data_opt_res, &budget) {
+                Some(call) => HttpResponseType::CallReadOnlyFunction(response_metadata, call),
+                None => HttpResponseType::NotFound(response_metadata, "Chain tip not found".into()),
+            };
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a POST to run a read-only function call exactly as `handle_readonly_function_call()`
+    /// does, but with a `StepTracer` attached.  Returns a TraceReadOnlyResponse with the call's
+    /// result and the trace of the first `MAX_TRACE_STEPS` expressions it evaluated.  Nodes built
+    /// without the `eval-hooks` feature never invoke the tracer, so they answer with a 404.
+    fn handle_trace_readonly_function_call<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        contract_addr: &StacksAddress,
+        contract_name: &ContractName,
+        function: &ClarityName,
+        sender: &PrincipalData,
+        args: &[Value],
+        cost_budget: Option<&ExecutionCost>,
+        options: &ConnectionOptions,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        if !cfg!(feature = "eval-hooks") {
+            let response = HttpResponseType::NotFound(
+                response_metadata,
+                "Tracing requires a node built with the `eval-hooks` feature".into(),
+            );
+            return response.send(http, fd).map(|_| ());
+        }
+
+        let budget = ConversationHttp::readonly_call_budget(cost_budget, options);
+        let tracer = StepTracer::new(MAX_TRACE_STEPS);
+        let data_opt_res = ConversationHttp::run_readonly_function_call(
+            sortdb,
+            chainstate,
+            tip,
+            contract_addr,
+            contract_name,
+            function,
+            sender,
+            args,
+            budget.clone(),
+            vec![Box::new(tracer.clone())],
+        );
+
+        let response =
+            match ConversationHttp::readonly_function_call_response(data_opt_res, &budget) {
+                Some(call) => HttpResponseType::TraceReadOnlyFunction(
+                    response_metadata,
+                    TraceReadOnlyResponse {
+                        call,
+                        trace: tracer.take_trace(),
+                    },
+                ),
+                None => HttpResponseType::NotFound(response_metadata, "Chain tip not found".into()),
+            };
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Run a read-only function call on the given chain tip, metered against `cost_limit`, with
+    /// `eval_hooks` registered in its global context.  Returns the call's result and the cost it
+    /// consumed, or None if the chain tip is not known.
+    fn run_readonly_function_call(
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        contract_addr: &StacksAddress,
+        contract_name: &ContractName,
+        function: &ClarityName,
+        sender: &PrincipalData,
+        args: &[Value],
+        cost_limit: ExecutionCost,
+        eval_hooks: Vec<Box<dyn EvalHook>>,
+    ) -> Result<Option<Result<(Value, ExecutionCost), ClarityRuntimeError>>, chain_error> {
+        let contract_identifier =
+            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
+
+        let args: Vec<_> = args
+            .iter()
+            .map(|x| SymbolicExpression::atom_value(x.clone()))
+            .collect();
+        let mainnet = chainstate.mainnet;
+
+        chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+            let epoch = clarity_tx.get_epoch();
+            let cost_track = clarity_tx
+                .with_clarity_db_readonly(|clarity_db| {
+                    LimitedCostTracker::new_mid_block(mainnet, cost_limit, clarity_db, epoch)
+                })
+                .map_err(|_| {
+                    ClarityRuntimeError::from(InterpreterError::CostContractLoadFailure)
+                })?;
+
+            clarity_tx.with_readonly_clarity_env(mainnet, sender.clone(), cost_track, |env| {
+                env.global_context.eval_hooks.extend(eval_hooks);
+                // we want to execute any function as long as no actual writes are made as
+                // opposed to be limited to purely calling `define-read-only` functions,
+                // so use `read_only = false`.  This broadens the number of functions that
+                // can be called, and also circumvents limitations on `define-read-only`
+                // functions that can not use `contrac-call?`, even when calling other
+                // read-only functions
+                let result =
+                    env.execute_contract(&contract_identifier, function.as_str(), &args, false)?;
+                Ok((result, env.global_context.cost_track.get_total()))
+            })
+        })
+    }
+
+    /// Translate the outcome of `run_readonly_function_call()` into a CallReadOnlyResponse, or
+    /// None if the chain tip was not found.
+    fn readonly_function_call_response(
+        data_opt_res: Result<
+            Option<Result<(Value, ExecutionCost), ClarityRuntimeError>>,
+            chain_error,
+        >,
+        budget: &ExecutionCost,
+    ) -> Option<CallReadOnlyResponse> {
+        match data_opt_res {
+            Ok(Some(Ok((data, cost)))) => Some(CallReadOnlyResponse {
+                okay: true,
+                result: Some(format!("0x{}", data.serialize())),
+                cause: None,
+                budget_exceeded: None,
+                cost: Some(cost),
+            }),
+            Ok(Some(Err(e))) => Some(ConversationHttp::readonly_function_call_error(e, budget)),
+            Ok(None) | Err(_) => None,
+        }
+    }
+
+    /// Determine the cost budget for a read-only function call.  A caller-requested budget is
+    /// capped in each dimension at the node's `read_only_call_max_budget`; without one, the
+    /// node's `read_only_call_limit` applies.  Read-only calls never get a write budget.
+    fn readonly_call_budget(
+        requested: Option<&ExecutionCost>,
+        options: &ConnectionOptions,
+    ) -> ExecutionCost {
+        let mut budget = match requested {
+            Some(requested) => {
+                let max_budget = &options.read_only_call_max_budget;
+                ExecutionCost {
+                    write_length: 0,
+                    write_count: 0,
+                    read_length: cmp::min(requested.read_length, max_budget.read_length),
+                    read_count: cmp::min(requested.read_count, max_budget.read_count),
+                    runtime: cmp::min(requested.runtime, max_budget.runtime),
                 }
-                None
             }
-            HttpRequestType::ClientError(ref _md, ref err) => {
-                let response_metadata = HttpResponseMetadata::from_http_request_type(
-                    &req,
-                    Some(network.burnchain_tip.canonical_stacks_tip_height),
-                );
-                let response = match err {
-                    ClientError::Message(s) => HttpResponseType::BadRequestJSON(
-                        response_metadata,
-                        serde_json::Value::String(s.to_string()),
-                    ),
-                    ClientError::NotFound(path) => {
-                        HttpResponseType::NotFound(response_metadata, path.clone())
+            None => options.read_only_call_limit.clone(),
+        };
+        budget.write_length = 0;
+        budget.write_count = 0;
+        budget
+    }
+
+    /// Translate a failed read-only function call into a CallReadOnlyResponse.  A call that tried
+    /// to write is reported as `NotReadOnly`; a call that ran out of the node's read-only call
+    /// budget is reported as `ReadOnlyBudgetExceeded`, along with the cost it consumed and the
+    /// budget it exceeded.
+    fn readonly_function_call_error(
+        e: ClarityRuntimeError,
+        budget: &ExecutionCost,
+    ) -> CallReadOnlyResponse {
+        match e {
+            Unchecked(CheckErrors::CostBalanceExceeded(actual_cost, _))
+                if actual_cost.write_count > 0 =>
+            {
+                CallReadOnlyResponse {
+                    okay: false,
+                    result: None,
+                    cause: Some("NotReadOnly".to_string()),
+                    budget_exceeded: None,
+                    cost: None,
+                }
+            }
+            Unchecked(CheckErrors::CostBalanceExceeded(actual_cost, _)) => CallReadOnlyResponse {
+                okay: false,
+                result: None,
+                cause: Some("ReadOnlyBudgetExceeded".to_string()),
+                budget_exceeded: Some(ReadOnlyBudgetExceeded {
+                    cost: actual_cost,
+                    budget: budget.clone(),
+                }),
+                cost: None,
+            },
+            _ => CallReadOnlyResponse {
+                okay: false,
+                result: None,
+                cause: Some(e.to_string()),
+                budget_exceeded: None,
+                cost: None,
+            },
+        }
+    }
+
+    /// Handle a GET to fetch a contract's source code, given the chain tip.  Optionally returns a
+    /// MARF proof as well.
+    fn handle_get_contract_src<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        contract_addr: &StacksAddress,
+        contract_name: &ContractName,
+        with_proof: bool,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let contract_identifier =
+            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
+
+        let response =
+            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                clarity_tx.with_clarity_db_readonly(|db| {
+                    let source = db.get_contract_src(&contract_identifier)?;
+                    let contract_commit_key = make_contract_hash_key(&contract_identifier);
+                    let (contract_commit, proof) = if with_proof {
+                        db.get_with_proof::<ContractCommitment>(&contract_commit_key)
+                            .map(|(a, b)| (a, Some(format!("0x{}", to_hex(&b)))))
+                            .expect("BUG: obtained source, but couldn't get contract commit")
+                    } else {
+                        db.get::<ContractCommitment>(&contract_commit_key)
+                            .map(|a| (a, None))
+                            .expect("BUG: obtained source, but couldn't get contract commit")
+                    };
+
+                    let publish_height = contract_commit.block_height;
+                    Some(ContractSrcResponse {
+                        source,
+                        publish_height,
+                        marf_proof: proof,
+                    })
+                })
+            }) {
+                Ok(Some(Some(data))) => HttpResponseType::GetContractSrc(response_metadata, data),
+                Ok(Some(None)) => HttpResponseType::NotFound(
+                    response_metadata,
+                    "No contract source data found".into(),
+                ),
+                Ok(None) | Err(_) => {
+                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+                }
+            };
+
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a GET to fetch whether or not a contract implements a certain trait
+    fn handle_get_is_trait_implemented<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        contract_addr: &StacksAddress,
+        contract_name: &ContractName,
+        trait_id: &TraitIdentifier,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let contract_identifier =
+            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
+
+        let response =
+            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                clarity_tx.with_clarity_db_readonly(|db| {
+                    let analysis = db.load_contract_analysis(&contract_identifier)?;
+                    if analysis.implemented_traits.contains(trait_id) {
+                        Some(GetIsTraitImplementedResponse {
+                            is_implemented: true,
+                        })
+                    } else {
+                        let trait_defining_contract =
+                            db.load_contract_analysis(&trait_id.contract_identifier)?;
+                        let trait_definition =
+                            trait_defining_contract.get_defined_trait(&trait_id.name)?;
+                        let is_implemented = analysis
+                            .check_trait_compliance(trait_id, trait_definition)
+                            .is_ok();
+                        Some(GetIsTraitImplementedResponse { is_implemented })
+                    }
+                })
+            }) {
+                Ok(Some(Some(data))) => {
+                    HttpResponseType::GetIsTraitImplemented(response_metadata, data)
+                }
+                Ok(Some(None)) => HttpResponseType::NotFound(
+                    response_metadata,
+                    "No contract analysis found or trait definition not found".into(),
+                ),
+                Ok(None) | Err(_) => {
+                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+                }
+            };
+
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a GET to fetch a contract's analysis data, given the chain tip.  Note that this isn't
+    /// something that's anchored to the blockchain, and can be different across different versions
+    /// of Stacks -- callers must trust the Stacks node to return correct analysis data.
+    /// Callers who don't trust the Stacks node should just fetch the contract source
+    /// code and analyze it offline.
+    fn handle_get_contract_abi<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        tip: &StacksBlockId,
+        contract_addr: &StacksAddress,
+        contract_name: &ContractName,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let contract_identifier =
+            QualifiedContractIdentifier::new(contract_addr.clone().into(), contract_name.clone());
+
+        let response =
+            match chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                clarity_tx.with_analysis_db_readonly(|db| {
+                    let contract = db.load_contract(&contract_identifier)?;
+                    contract.contract_interface
+                })
+            }) {
+                Ok(Some(Some(data))) => HttpResponseType::GetContractABI(response_metadata, data),
+                Ok(Some(None)) => HttpResponseType::NotFound(
+                    response_metadata,
+                    "No contract interface data found".into(),
+                ),
+                Ok(None) | Err(_) => {
+                    HttpResponseType::NotFound(response_metadata, "Chain tip not found".into())
+                }
+            };
+
+        response.send(http, fd).map(|_| ())
+    }
+
+    /// Handle a GET unconfirmed microblock stream.  Start streaming the reply.
+    /// The response's preamble (but not the block data) will be synchronously written to the fd
+    /// (so use a fd that can buffer!)
+    /// Return a StreamCursor struct for the block that we're sending, so we can continue to
+    /// make progress sending it.
+    fn handle_getmicroblocks_unconfirmed<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        index_anchor_block_hash: &StacksBlockId,
+        min_seq: u16,
+        chainstate: &StacksChainState,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<Option<StreamCursor>, net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+
+        // do we have this unconfirmed microblock stream?
+        match chainstate.has_any_staging_microblock_indexed(index_anchor_block_hash, min_seq) {
+            Ok(false) => {
+                // nope
+                let response = HttpResponseType::NotFound(
+                    response_metadata,
+                    format!(
+                        "No such unconfirmed microblock stream for {} at or after {}",
+                        index_anchor_block_hash.to_hex(),
+                        min_seq
+                    ),
+                );
+                response.send(http, fd).and_then(|_| Ok(None))
+            }
+            Err(e) => {
+                // nope
+                warn!(
+                    "Failed to serve confirmed microblock stream {:?}: {:?}",
+                    req, &e
+                );
+                let response = HttpResponseType::ServerError(
+                    response_metadata,
+                    format!(
+                        "Failed to query unconfirmed microblock stream for {} at or after {}",
+                        index_anchor_block_hash.to_hex(),
+                        min_seq
+                    ),
+                );
+                response.send(http, fd).and_then(|_| Ok(None))
+            }
+            Ok(true) => {
+                // yup! start streaming it back
+                let (response, stream_opt) = match StreamCursor::new_microblock_unconfirmed(
+                    chainstate,
+                    index_anchor_block_hash.clone(),
+                    min_seq,
+                ) {
+                    Ok(stream) => (
+                        HttpResponseType::MicroblockStream(response_metadata),
+                        Some(stream),
+                    ),
+                    Err(chain_error::NoSuchBlockError) => (
+                        HttpResponseType::NotFound(
+                            response_metadata,
+                            format!(
+                                "No such unconfirmed microblock stream starting with {}",
+                                index_anchor_block_hash.to_hex()
+                            ),
+                        ),
+                        None,
+                    ),
+                    Err(_e) => {
+                        debug!(
+                            "Failed to load unconfirmed microblock stream {}: {:?}",
+                            &index_anchor_block_hash, &_e
+                        );
+                        (
+                            HttpResponseType::ServerError(
+                                response_metadata,
+                                format!(
+                                    "Failed to query unconfirmed microblock stream {}",
+                                    index_anchor_block_hash.to_hex()
+                                ),
+                            ),
+                            None,
+                        )
+                    }
+                };
+                response.send(http, fd).and_then(|_| Ok(stream_opt))
+            }
+        }
+    }
+
+    /// Handle a GET unconfirmed transaction.
+    /// The response will be synchronously written to the fd.
+    fn handle_gettransaction_unconfirmed<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        chainstate: &StacksChainState,
+        mempool: &MemPoolDB,
+        txid: &Txid,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+
+        // present in the unconfirmed state?
+        if let Some(ref unconfirmed) = chainstate.unconfirmed_state.as_ref() {
+            if let Some((transaction, mblock_hash, seq)) =
+                unconfirmed.get_unconfirmed_transaction(txid)
+            {
+                let response = HttpResponseType::UnconfirmedTransaction(
+                    response_metadata,
+                    UnconfirmedTransactionResponse {
+                        status: UnconfirmedTransactionStatus::Microblock {
+                            block_hash: mblock_hash,
+                            seq: seq,
+                        },
+                        tx: to_hex(&transaction.serialize_to_vec()),
+                    },
+                );
+                return response.send(http, fd).map(|_| ());
+            }
+        }
+
+        // present in the mempool?
+        if let Some(txinfo) = MemPoolDB::get_tx(mempool.conn(), txid)? {
+            let response = HttpResponseType::UnconfirmedTransaction(
+                response_metadata,
+                UnconfirmedTransactionResponse {
+                    status: UnconfirmedTransactionStatus::Mempool,
+                    tx: to_hex(&txinfo.tx.serialize_to_vec()),
+                },
+            );
+            return response.send(http, fd).map(|_| ());
+        }
+
+        // not found
+        let response = HttpResponseType::NotFound(
+            response_metadata,
+            format!("No such unconfirmed transaction {}", txid),
+        );
+        return response.send(http, fd).map(|_| ());
+    }
+
+    /// Load up the canonical Stacks chain tip.  Note that this is subject to both burn chain block
+    /// Stacks block availability -- different nodes with different partial replicas of the Stacks chain state
+    /// will return different values here.
+    ///
+    /// # Warn
+    /// - There is a potential race condition. If this function is loading the latest unconfirmed
+    /// tip, that tip may get invalidated by the time it is used in `maybe_read_only_clarity_tx`,
+    /// which is used to load clarity state at a particular tip (which would lead to a 404 error).
+    /// If this race condition occurs frequently, we can modify `maybe_read_only_clarity_tx` to
+    /// re-load the unconfirmed chain tip. Refer to issue #2997.
+    ///
+    /// # Inputs
+    /// - `tip_req` is given by the HTTP request as the optional query parameter for the chain tip
+    /// hash.  It will be UseLatestAnchoredTip if there was no parameter given. If it is set to
+    /// `latest`, the parameter will be set to UseLatestUnconfirmedTip.
+    fn handle_load_stacks_chain_tip<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        tip_req: &TipRequest,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<Option<StacksBlockId>, net_error> {
+        match tip_req {
+            TipRequest::UseLatestUnconfirmedTip => {
+                let unconfirmed_chain_tip_opt = match &mut chainstate.unconfirmed_state {
+                    Some(unconfirmed_state) => {
+                        match unconfirmed_state.get_unconfirmed_state_if_exists() {
+                            Ok(res) => res,
+                            Err(msg) => {
+                                let response_metadata =
+                                    HttpResponseMetadata::from_http_request_type(
+                                        req,
+                                        Some(canonical_stacks_tip_height),
+                                    );
+                                let response = HttpResponseType::NotFound(response_metadata, msg);
+                                return response.send(http, fd).and_then(|_| Ok(None));
+                            }
+                        }
+                    }
+                    None => None,
+                };
+
+                if let Some(unconfirmed_chain_tip) = unconfirmed_chain_tip_opt {
+                    Ok(Some(unconfirmed_chain_tip))
+                } else {
+                    match chainstate.get_stacks_chain_tip(sortdb)? {
+                        Some(tip) => Ok(Some(StacksBlockHeader::make_index_block_hash(
+                            &tip.consensus_hash,
+                            &tip.anchored_block_hash,
+                        ))),
+                        None => {
+                            let response_metadata = HttpResponseMetadata::from_http_request_type(
+                                req,
+                                Some(canonical_stacks_tip_height),
+                            );
+                            warn!("Failed to load Stacks chain tip");
+                            let response = HttpResponseType::NotFound(
+                                response_metadata,
+                                format!("Failed to load Stacks chain tip"),
+                            );
+                            response.send(http, fd).and_then(|_| Ok(None))
+                        }
+                    }
+                }
+            }
+            TipRequest::SpecificTip(tip) => Ok(Some(*tip).clone()),
+            TipRequest::UseLatestAnchoredTip => match chainstate.get_stacks_chain_tip(sortdb)? {
+                Some(tip) => Ok(Some(StacksBlockHeader::make_index_block_hash(
+                    &tip.consensus_hash,
+                    &tip.anchored_block_hash,
+                ))),
+                None => {
+                    let response_metadata = HttpResponseMetadata::from_http_request_type(
+                        req,
+                        Some(canonical_stacks_tip_height),
+                    );
+                    warn!("Failed to load Stacks chain tip");
+                    let response = HttpResponseType::ServerError(
+                        response_metadata,
+                        format!("Failed to load Stacks chain tip"),
+                    );
+                    response.send(http, fd).and_then(|_| Ok(None))
+                }
+            },
+        }
+    }
+
+    fn handle_load_stacks_chain_tip_hashes<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        tip: StacksBlockId,
+        chainstate: &StacksChainState,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<Option<(ConsensusHash, BlockHeaderHash)>, net_error> {
+        match chainstate.get_block_header_hashes(&tip)? {
+            Some((ch, bl)) => {
+                return Ok(Some((ch, bl)));
+            }
+            None => {}
+        }
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        warn!("Failed to load Stacks chain tip");
+        let response = HttpResponseType::ServerError(
+            response_metadata,
+            format!("Failed to load Stacks chain tip"),
+        );
+        response.send(http, fd).and_then(|_| Ok(None))
+    }
+
+    fn handle_post_fee_rate_estimate<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        handler_args: &RPCHandlerArgs,
+        sortdb: &SortitionDB,
+        tx: &TransactionPayload,
+        estimated_len: u64,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<(), net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+        let stacks_epoch = SortitionDB::get_stacks_epoch(sortdb.conn(), tip.block_height)?
+                .ok_or_else(|| {
+                    warn!(
+                        "Failed to get fee rate estimate because could not load Stacks epoch for canonical burn height = {}",
+                        tip.block_height
+                    );
+                    net_error::ChainstateError("Could not load Stacks epoch for canonical burn height".into())
+                })?;
+        if let Some((cost_estimator, fee_estimator, metric)) = handler_args.get_estimators_ref() {
+            let estimated_cost = match cost_estimator.estimate_cost(tx, &stacks_epoch.epoch_id) {
+                Ok(x) => x,
+                Err(e) => {
+                    debug!(
+                        "Estimator RPC endpoint failed to estimate tx: {}",
+                        tx.name()
+                    );
+                    return HttpResponseType::BadRequestJSON(response_metadata, e.into_json())
+                        .send(http, fd);
+                }
+            };
+
+            let scalar_cost =
+                metric.from_cost_and_len(&estimated_cost, &stacks_epoch.block_limit, estimated_len);
+            let fee_rates = match fee_estimator.get_rate_estimates() {
+                Ok(x) => x,
+                Err(e) => {
+                    debug!(
+                        "Estimator RPC endpoint failed to estimate fees for tx: {}",
+                        tx.name()
+                    );
+                    return HttpResponseType::BadRequestJSON(response_metadata, e.into_json())
+                        .send(http, fd);
+                }
+            };
+
+            let mut estimations = RPCFeeEstimate::estimate_fees(scalar_cost, fee_rates).to_vec();
+
+            let minimum_fee = estimated_len * MINIMUM_TX_FEE_RATE_PER_BYTE;
+
+            for estimate in estimations.iter_mut() {
+                if estimate.fee < minimum_fee {
+                    estimate.fee = minimum_fee;
+                }
+            }
+
+            let response = HttpResponseType::TransactionFeeEstimation(
+                response_metadata,
+                RPCFeeEstimateResponse {
+                    estimated_cost,
+                    estimations,
+                    estimated_cost_scalar: scalar_cost,
+                    cost_scalar_change_by_byte: metric.change_per_byte(),
+                },
+            );
+            response.send(http, fd)
+        } else {
+            debug!("Fee and cost estimation not configured on this stacks node");
+            let response = HttpResponseType::BadRequestJSON(
+                response_metadata,
+                json!({
+                    "error": "Fee and Cost Estimation not configured on this Stacks node",
+                    "reason": "CostEstimationDisabled",
+                }),
+            );
+            response.send(http, fd)
+        }
+    }
+
+    /// Handle a transaction.  Directly submit it to the mempool so the client can see any
+    /// rejection reasons up-front (different from how the peer network handles it).  Indicate
+    /// whether or not the transaction was accepted (and thus needs to be forwarded) in the return
+    /// value.  If `diagnostics` is set, the response reports on each of the mempool's admission
+    /// checks instead of just the txid or rejection.
+    fn handle_post_transaction<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        chainstate: &mut StacksChainState,
+        sortdb: &SortitionDB,
+        consensus_hash: ConsensusHash,
+        block_hash: BlockHeaderHash,
+        mempool: &mut MemPoolDB,
+        tx: StacksTransaction,
+        atlasdb: &mut AtlasDB,
+        attachment: Option<Attachment>,
+        diagnostics: bool,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<bool, net_error> {
+        let txid = tx.txid();
+        let stacks_tip = StacksBlockId::new(&consensus_hash, &block_hash);
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let (response, accepted) = if mempool.has_tx(&txid) {
+            debug!("Mempool already has POSTed transaction {}", &txid);
+            let response = if diagnostics {
+                let nonce = ConversationHttp::transaction_nonce_diagnostics(
+                    chainstate,
+                    sortdb,
+                    &stacks_tip,
+                    mempool,
+                    &tx,
+                    true,
+                );
+                let admission =
+                    ConversationHttp::make_transaction_admission(mempool, &tx, nonce, None);
+                HttpResponseType::TransactionAdmission(response_metadata, admission)
+            } else {
+                HttpResponseType::TransactionID(response_metadata, txid)
+            };
+            (response, false)
+        } else {
+            let tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+            let stacks_epoch = sortdb
+                .index_conn()
+                .get_stacks_epoch(tip.block_height as u32)
+                .ok_or_else(|| {
+                    warn!(
+                        "Failed to store transaction because could not load Stacks epoch for canonical burn height = {}",
+                        tip.block_height
+                    );
+                    net_error::ChainstateError("Could not load Stacks epoch for canonical burn height".into())
+                })?;
+
+            if diagnostics {
+                // taken before the transaction is admitted, so it's compared to the others
+                let nonce = ConversationHttp::transaction_nonce_diagnostics(
+                    chainstate,
+                    sortdb,
+                    &stacks_tip,
+                    mempool,
+                    &tx,
+                    false,
+                );
+                let report = mempool.submit_with_report(
+                    chainstate,
+                    &consensus_hash,
+                    &block_hash,
+                    &tx,
+                    event_observer,
+                    &stacks_epoch.block_limit,
+                    &stacks_epoch.epoch_id,
+                );
+                let admission =
+                    ConversationHttp::make_transaction_admission(mempool, &tx, nonce, Some(report));
+                match admission.rejection {
+                    None => {
+                        debug!("Mempool accepted POSTed transaction {}", &txid);
+                        (
+                            HttpResponseType::TransactionAdmission(response_metadata, admission),
+                            true,
+                        )
+                    }
+                    Some(ref e) => {
+                        debug!("Mempool rejected POSTed transaction {}: {}", &txid, e);
+                        let admission_json = serde_json::to_value(&admission).map_err(|e| {
+                            net_error::SerializeError(format!(
+                                "Failed to serialize admission diagnostics: {:?}",
+                                &e
+                            ))
+                        })?;
+                        (
+                            HttpResponseType::BadRequestJSON(response_metadata, admission_json),
+                            false,
+                        )
+                    }
+                }
+            } else {
+                match mempool.submit(
+                    chainstate,
+                    &consensus_hash,
+                    &block_hash,
+                    &tx,
+                    event_observer,
+                    &stacks_epoch.block_limit,
+                    &stacks_epoch.epoch_id,
+                ) {
+                    Ok(_) => {
+                        debug!("Mempool accepted POSTed transaction {}", &txid);
+                        (
+                            HttpResponseType::TransactionID(response_metadata, txid),
+                            true,
+                        )
+                    }
+                    Err(e) => {
+                        debug!("Mempool rejected POSTed transaction {}: {:?}", &txid, &e);
+                        (
+                            HttpResponseType::BadRequestJSON(response_metadata, e.into_json(&txid)),
+                            false,
+                        )
+                    }
+                }
+            }
+        };
+
+        if let Some(ref attachment) = attachment {
+            if let TransactionPayload::ContractCall(ref contract_call) = tx.payload {
+                if atlasdb
+                    .should_keep_attachment(&contract_call.to_clarity_contract_id(), &attachment)
+                {
+                    atlasdb
+                        .insert_uninstantiated_attachment(attachment)
+                        .map_err(|e| net_error::DBError(e))?;
+                }
+            }
+        }
+
+        response.send(http, fd).and_then(|_| Ok(accepted))
+    }
+
+    /// Where a posted transaction's origin nonce falls among its origin account's nonce and
+    /// pending transactions.  If the transaction is `already_known`, it is left out of the
+    /// pending transactions it is compared to.
+    fn transaction_nonce_diagnostics(
+        chainstate: &mut StacksChainState,
+        sortdb: &SortitionDB,
+        tip: &StacksBlockId,
+        mempool: &MemPoolDB,
+        tx: &StacksTransaction,
+        already_known: bool,
+    ) -> TransactionNonceDiagnostics {
+        let nonce = tx.get_origin_nonce();
+        let address = tx.origin_address();
+        let mut diagnostics = TransactionNonceDiagnostics {
+            nonce,
+            expected_nonce: None,
+            pending_nonces: vec![],
+            status: None,
+        };
+
+        let principal = PrincipalData::from(address.clone());
+        let next_nonce =
+            chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                StacksChainState::get_account(clarity_tx, &principal).nonce
+            });
+        let next_nonce = match next_nonce {
+            Ok(Some(nonce)) => nonce,
+            Ok(None) | Err(_) => {
+                return diagnostics;
+            }
+        };
+        diagnostics.expected_nonce = Some(next_nonce);
+
+        let mut gaps = match MemPoolDB::get_nonce_gaps(mempool.conn(), &address, next_nonce) {
+            Ok(gaps) => gaps,
+            Err(e) => {
+                warn!("Failed to find nonce gaps";
+                      "address" => %address,
+                      "error" => ?e);
+                return diagnostics;
+            }
+        };
+        if already_known {
+            // a pending transaction's nonce is not shared with any other pending transaction
+            gaps.pending_nonces.retain(|pending| *pending != nonce);
+        }
+        let status = match gaps.nonce_status(nonce) {
+            MemPoolNonceStatus::Stale => TransactionNonceStatus::Stale,
+            MemPoolNonceStatus::Next => TransactionNonceStatus::Next,
+            MemPoolNonceStatus::Chained => TransactionNonceStatus::Chained,
+            MemPoolNonceStatus::Replacement => TransactionNonceStatus::Replacement,
+            MemPoolNonceStatus::Gap => TransactionNonceStatus::Gap,
+        };
+        diagnostics.status = Some(status);
+        diagnostics.pending_nonces = gaps.pending_nonces;
+        diagnostics
+    }
+
+    /// Build the admission diagnostics for a posted transaction.  `report` is None if the
+    /// transaction was already in the mempool, and so was not submitted.
+    fn make_transaction_admission(
+        mempool: &MemPoolDB,
+        tx: &StacksTransaction,
+        nonce: TransactionNonceDiagnostics,
+        report: Option<MemPoolAdmissionReport>,
+    ) -> TransactionAdmissionResponse {
+        let txid = tx.txid();
+        let fee_rate = match report.as_ref() {
+            Some(report) => report.fee_rate,
+            None => MemPoolDB::get_tx_fee_rate(mempool.conn(), &txid).unwrap_or_else(|e| {
+                warn!("Failed to load fee rate estimate";
+                      "txid" => %txid,
+                      "error" => ?e);
+                None
+            }),
+        };
+        let fee_rate_percentile = fee_rate.and_then(|fee_rate| {
+            MemPoolDB::get_fee_rate_percentile(mempool.conn(), fee_rate, &txid).unwrap_or_else(
+                |e| {
+                    warn!("Failed to rank fee rate estimate";
+                          "txid" => %txid,
+                          "error" => ?e);
+                    None
+                },
+            )
+        });
+        let checks = match report.as_ref() {
+            Some(report) => MemPoolAdmissionCheck::ALL
+                .iter()
+                .map(|check| {
+                    let status = if report.passed_checks.contains(check) {
+                        TransactionAdmissionCheckStatus::Passed
+                    } else if report.failed_check == Some(*check) {
+                        TransactionAdmissionCheckStatus::Failed
+                    } else {
+                        TransactionAdmissionCheckStatus::Skipped
+                    };
+                    TransactionAdmissionCheck {
+                        name: check.name().to_string(),
+                        status,
+                    }
+                })
+                .collect(),
+            None => vec![],
+        };
+        let already_known = report.is_none();
+        let rejection = report
+            .and_then(|report| report.result.err())
+            .map(|e| e.into_json(&txid));
+
+        TransactionAdmissionResponse {
+            txid: format!("0x{}", &txid),
+            accepted: rejection.is_none(),
+            already_known,
+            rejection,
+            tx_size: tx.tx_len(),
+            tx_fee: tx.get_tx_fee(),
+            fee_rate,
+            fee_rate_percentile,
+            nonce,
+            checks,
+        }
+    }
+
+    /// Handle a sponsorship offer for an unsponsored transaction in the mempool.  The offer is
+    /// checked against the node's sponsorship policy and the original transaction, and if it
+    /// passes, it replaces the original in the mempool.  Indicate whether or not the offer was
+    /// accepted (and thus needs to be forwarded) in the return value.
+    fn handle_post_transaction_sponsorship<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        chainstate: &mut StacksChainState,
+        sortdb: &SortitionDB,
+        consensus_hash: ConsensusHash,
+        block_hash: BlockHeaderHash,
+        mempool: &mut MemPoolDB,
+        sponsored_txid: &Txid,
+        offer: &StacksTransaction,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<bool, net_error> {
+        let txid = offer.txid();
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let (response, accepted) = if mempool.has_tx(&txid) {
+            debug!("Mempool already has POSTed sponsorship offer {}", &txid);
+            (
+                HttpResponseType::TransactionID(response_metadata, txid),
+                false,
+            )
+        } else {
+            let tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+            let stacks_epoch = sortdb
+                .index_conn()
+                .get_stacks_epoch(tip.block_height as u32)
+                .ok_or_else(|| {
+                    warn!(
+                        "Failed to store sponsorship offer because could not load Stacks epoch for canonical burn height = {}",
+                        tip.block_height
+                    );
+                    net_error::ChainstateError("Could not load Stacks epoch for canonical burn height".into())
+                })?;
+
+            match mempool.submit_sponsorship(
+                chainstate,
+                &consensus_hash,
+                &block_hash,
+                sponsored_txid,
+                offer,
+                event_observer,
+                &stacks_epoch.block_limit,
+                &stacks_epoch.epoch_id,
+            ) {
+                Ok(_) => {
+                    debug!(
+                        "Mempool accepted sponsorship offer {} for {}",
+                        &txid, sponsored_txid
+                    );
+                    (
+                        HttpResponseType::TransactionID(response_metadata, txid),
+                        true,
+                    )
+                }
+                Err(e) => {
+                    debug!(
+                        "Mempool rejected sponsorship offer {} for {}: {:?}",
+                        &txid, sponsored_txid, &e
+                    );
+                    (
+                        HttpResponseType::BadRequestJSON(response_metadata, e.into_json(&txid)),
+                        false,
+                    )
+                }
+            }
+        };
+
+        response.send(http, fd).and_then(|_| Ok(accepted))
+    }
+
+    /// Check that, within a posted transaction batch, each origin account's transactions have
+    /// consecutive nonces in the order they were given.  Returns the JSON error to send back if
+    /// not.
+    fn check_transaction_batch_nonces(txs: &[StacksTransaction]) -> Result<(), serde_json::Value> {
+        let mut next_nonces: HashMap<StacksAddress, u64> = HashMap::new();
+        for tx in txs.iter() {
+            let origin = tx.origin_address();
+            let nonce = tx.get_origin_nonce();
+            if let Some(expected) = next_nonces.get(&origin) {
+                if *expected != nonce {
+                    return Err(json!({
+                        "txid": tx.txid().to_hex(),
+                        "error": "transaction batch rejected",
+                        "reason": "BadNonceOrdering",
+                        "reason_data": {
+                            "principal": origin.to_string(),
+                            "expected": *expected,
+                            "actual": nonce,
+                        },
+                    }));
+                }
+            }
+            next_nonces.insert(origin, nonce.saturating_add(1));
+        }
+        Ok(())
+    }
+
+    /// Handle a batch of transactions.  Check the nonce ordering within the batch, and then submit
+    /// the transactions to the mempool in order.  If `all_or_nothing` is set, the batch is only
+    /// admitted if every transaction in it is; otherwise, each transaction is admitted or rejected
+    /// on its own, as with `handle_post_transaction()`.
+    /// Returns the newly-admitted transactions, which need to be forwarded.
+    fn handle_post_transaction_batch<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        chainstate: &mut StacksChainState,
+        sortdb: &SortitionDB,
+        consensus_hash: ConsensusHash,
+        block_hash: BlockHeaderHash,
+        mempool: &mut MemPoolDB,
+        txs: &[StacksTransaction],
+        all_or_nothing: bool,
+        event_observer: Option<&dyn MemPoolEventDispatcher>,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<Vec<StacksTransaction>, net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+
+        if let Err(error_json) = ConversationHttp::check_transaction_batch_nonces(txs) {
+            debug!("Rejected POSTed transaction batch: {}", &error_json);
+            let response = HttpResponseType::BadRequestJSON(response_metadata, error_json);
+            return response.send(http, fd).and_then(|_| Ok(vec![]));
+        }
+
+        let tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+        let stacks_epoch = sortdb
+            .index_conn()
+            .get_stacks_epoch(tip.block_height as u32)
+            .ok_or_else(|| {
+                warn!(
+                    "Failed to store transaction batch because could not load Stacks epoch for canonical burn height = {}",
+                    tip.block_height
+                );
+                net_error::ChainstateError("Could not load Stacks epoch for canonical burn height".into())
+            })?;
+
+        // transactions already in the mempool don't need to be submitted again
+        let known: Vec<bool> = txs.iter().map(|tx| mempool.has_tx(&tx.txid())).collect();
+        let new_txs: Vec<StacksTransaction> = txs
+            .iter()
+            .zip(known.iter())
+            .filter(|(_, known)| !**known)
+            .map(|(tx, _)| tx.clone())
+            .collect();
+
+        let submit_results = match mempool.submit_batch(
+            chainstate,
+            &consensus_hash,
+            &block_hash,
+            &new_txs,
+            all_or_nothing,
+            event_observer,
+            &stacks_epoch.block_limit,
+            &stacks_epoch.epoch_id,
+        ) {
+            Ok(results) => results,
+            Err(e) => {
+                warn!("Failed to submit transaction batch: {:?}", &e);
+                let response = HttpResponseType::ServerError(
+                    response_metadata,
+                    "Failed to submit transaction batch".to_string(),
+                );
+                return response.send(http, fd).and_then(|_| Ok(vec![]));
+            }
+        };
+
+        let batch_rejected = all_or_nothing && submit_results.iter().any(|res| res.is_err());
+        let mut submit_results = submit_results.into_iter();
+        let mut results = Vec::with_capacity(txs.len());
+        let mut admitted = vec![];
+        for (tx, known) in txs.iter().zip(known.into_iter()) {
+            let txid = tx.txid();
+            let (status, error) = if known {
+                (TransactionBatchStatus::AlreadyKnown, None)
+            } else {
+                match submit_results.next() {
+                    Some(Ok(())) => {
+                        if batch_rejected {
+                            (TransactionBatchStatus::RolledBack, None)
+                        } else {
+                            admitted.push(tx.clone());
+                            (TransactionBatchStatus::Accepted, None)
+                        }
+                    }
+                    Some(Err(e)) => (TransactionBatchStatus::Rejected, Some(e.into_json(&txid))),
+                    None => (TransactionBatchStatus::NotAttempted, None),
+                }
+            };
+            results.push(TransactionBatchResult {
+                txid: txid.to_hex(),
+                status,
+                error,
+            });
+        }
+
+        let batch_response = TransactionBatchResponse {
+            accepted: results.iter().all(|res| {
+                res.status == TransactionBatchStatus::Accepted
+                    || res.status == TransactionBatchStatus::AlreadyKnown
+            }),
+            all_or_nothing,
+            results,
+        };
+        debug!(
+            "Mempool accepted {} of {} transactions in POSTed batch",
+            admitted.len(),
+            txs.len();
+            "all_or_nothing" => all_or_nothing
+        );
+
+        let response = if batch_rejected {
+            let batch_json = serde_json::to_value(&batch_response).map_err(|e| {
+                net_error::SerializeError(format!(
+                    "Failed to serialize transaction batch response: {:?}",
+                    &e
+                ))
+            })?;
+            HttpResponseType::BadRequestJSON(response_metadata, batch_json)
+        } else {
+            HttpResponseType::TransactionBatch(response_metadata, batch_response)
+        };
+        response.send(http, fd).and_then(|_| Ok(admitted))
+    }
+
+    /// Handle a block.  Directly submit a Stacks block to this node's chain state.
+    /// Indicate whether or not the block was accepted (i.e. it was new, and valid)
+    fn handle_post_block<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        consensus_hash: &ConsensusHash,
+        block: &StacksBlock,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<bool, net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        // is this a consensus hash we recognize?
+        let (response, accepted) =
+            match SortitionDB::get_sortition_id_by_consensus(&sortdb.conn(), consensus_hash) {
+                Ok(Some(_)) => {
+                    // we recognize this consensus hash
+                    let ic = sortdb.index_conn();
+                    match Relayer::process_new_anchored_block(
+                        &ic,
+                        chainstate,
+                        consensus_hash,
+                        block,
+                        0,
+                    ) {
+                        Ok(true) => {
+                            debug!(
+                                "Accepted Stacks block {}/{}",
+                                consensus_hash,
+                                &block.block_hash()
+                            );
+                            (
+                                HttpResponseType::StacksBlockAccepted(
+                                    response_metadata,
+                                    StacksBlockHeader::make_index_block_hash(
+                                        consensus_hash,
+                                        &block.block_hash(),
+                                    ),
+                                    true,
+                                ),
+                                true,
+                            )
+                        }
+                        Ok(false) => {
+                            debug!(
+                                "Did not accept Stacks block {}/{}",
+                                consensus_hash,
+                                &block.block_hash()
+                            );
+                            (
+                                HttpResponseType::StacksBlockAccepted(
+                                    response_metadata,
+                                    StacksBlockHeader::make_index_block_hash(
+                                        consensus_hash,
+                                        &block.block_hash(),
+                                    ),
+                                    false,
+                                ),
+                                false,
+                            )
+                        }
+                        Err(e) => {
+                            error!(
+                                "Failed to process anchored block {}/{}: {:?}",
+                                consensus_hash,
+                                &block.block_hash(),
+                                &e
+                            );
+                            (
+                                HttpResponseType::ServerError(
+                                    response_metadata,
+                                    format!(
+                                        "Failed to process anchored block {}/{}: {:?}",
+                                        consensus_hash,
+                                        &block.block_hash(),
+                                        &e
+                                    ),
+                                ),
+                                false,
+                            )
+                        }
+                    }
+                }
+                Ok(None) => {
+                    debug!(
+                        "Unrecognized consensus hash {} for block {}",
+                        consensus_hash,
+                        &block.block_hash()
+                    );
+                    (
+                        HttpResponseType::NotFound(
+                            response_metadata,
+                            format!("No such consensus hash '{}'", consensus_hash),
+                        ),
+                        false,
+                    )
+                }
+                Err(e) => {
+                    error!(
+                        "Failed to query sortition ID by consensus '{}'",
+                        consensus_hash
+                    );
+                    (
+                        HttpResponseType::ServerError(
+                            response_metadata,
+                            format!(
+                                "Failed to query sortition ID for consensus hash '{}': {:?}",
+                                consensus_hash, &e
+                            ),
+                        ),
+                        false,
+                    )
+                }
+            };
+        response.send(http, fd).and_then(|_| Ok(accepted))
+    }
+
+    /// Handle a microblock.  Directly submit it to the microblock store so the client can see any
+    /// rejection reasons up-front (different from how the peer network handles it).  Indicate
+    /// whether or not the microblock was accepted (and thus needs to be forwarded) in the return
+    /// value.
+    fn handle_post_microblock<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        consensus_hash: &ConsensusHash,
+        block_hash: &BlockHeaderHash,
+        chainstate: &mut StacksChainState,
+        microblock: &StacksMicroblock,
+        canonical_stacks_tip_height: u64,
+    ) -> Result<bool, net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let (response, accepted) =
+            match chainstate.preprocess_streamed_microblock(consensus_hash, block_hash, microblock)
+            {
+                Ok(accepted) => {
+                    if accepted {
+                        debug!(
+                            "Accepted uploaded microblock {}/{}-{}",
+                            &consensus_hash,
+                            &block_hash,
+                            &microblock.block_hash()
+                        );
+                    } else {
+                        debug!(
+                            "Did not accept microblock {}/{}-{}",
+                            &consensus_hash,
+                            &block_hash,
+                            &microblock.block_hash()
+                        );
+                    }
+
+                    (
+                        HttpResponseType::MicroblockHash(
+                            response_metadata,
+                            microblock.block_hash(),
+                        ),
+                        accepted,
+                    )
+                }
+                Err(e) => (
+                    HttpResponseType::BadRequestJSON(response_metadata, e.into_json()),
+                    false,
+                ),
+            };
+
+        response.send(http, fd).and_then(|_| Ok(accepted))
+    }
+
+    /// Handle a request for mempool transactions in bulk
+    fn handle_mempool_query<W: Write>(
+        http: &mut StacksHttp,
+        fd: &mut W,
+        req: &HttpRequestType,
+        sortdb: &SortitionDB,
+        chainstate: &StacksChainState,
+        query: MemPoolSyncData,
+        max_txs: u64,
+        bloom_depth: u64,
+        canonical_stacks_tip_height: u64,
+        page_id: Option<Txid>,
+    ) -> Result<StreamCursor, net_error> {
+        let response_metadata =
+            HttpResponseMetadata::from_http_request_type(req, Some(canonical_stacks_tip_height));
+        let response = HttpResponseType::MemPoolTxStream(response_metadata);
+        let height = chainstate
+            .get_stacks_chain_tip(sortdb)?
+            .map(|blk| blk.height)
+            .unwrap_or(0);
+
+        debug!(
+            "Begin mempool query";
+            "page_id" => %page_id.map(|txid| format!("{}", &txid)).unwrap_or("(none".to_string()),
+            "block_height" => height,
+            "bloom_depth" => bloom_depth,
+            "max_txs" => max_txs
+        );
+
+        let stream = StreamCursor::new_tx_stream(query, max_txs, height, bloom_depth, page_id);
+        response.send(http, fd).and_then(|_| Ok(stream))
+    }
+
+    /// Handle an external HTTP request.
+    /// Some requests, such as those for blocks, will create new reply streams.  This method adds
+    /// those new streams into the `reply_streams` set.
+    /// Returns the StacksMessageTypes we need to forward to the peer network (like transactions
+    /// or a block or microblock), if any
+    pub fn handle_request(
+        &mut self,
+        req: HttpRequestType,
+        network: &mut PeerNetwork,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        mempool: &mut MemPoolDB,
+        handler_opts: &RPCHandlerArgs,
+    ) -> Result<Vec<StacksMessageType>, net_error> {
+        let mut reply = self.connection.make_relay_handle(self.conn_id)?;
+        let keep_alive = req.metadata().keep_alive;
+        let mut ret = vec![];
+
+        if let Some(response) = self.check_rate_limit(network, &req) {
+            response.send(&mut self.connection.protocol, &mut reply)?;
+            self.reply_streams.push_back((reply, None, keep_alive));
+            return Ok(ret);
+        }
+
+        let stream_opt = match req {
+            HttpRequestType::GetInfo(ref _md) => {
+                ConversationHttp::handle_getinfo(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network,
+                    chainstate,
+                    handler_opts,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetNeighbors(ref _md) => {
+                ConversationHttp::handle_getneighbors(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetHeaders(ref _md, ref quantity, ref tip_req) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_getheaders(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        &tip,
+                        *quantity,
+                        chainstate,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?
+                } else {
+                    None
+                }
+            }
+            HttpRequestType::GetBlock(ref _md, ref index_block_hash) => {
+                ConversationHttp::handle_getblock(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    index_block_hash,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?
+            }
+            HttpRequestType::GetMicroblocksIndexed(ref _md, ref index_head_hash) => {
+                ConversationHttp::handle_getmicroblocks_indexed(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    index_head_hash,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?
+            }
+            HttpRequestType::GetMicroblocksConfirmed(ref _md, ref anchor_index_block_hash) => {
+                ConversationHttp::handle_getmicroblocks_confirmed(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    anchor_index_block_hash,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?
+            }
+            HttpRequestType::GetMicroblocksUnconfirmed(
+                ref _md,
+                ref index_anchor_block_hash,
+                ref min_seq,
+            ) => ConversationHttp::handle_getmicroblocks_unconfirmed(
+                &mut self.connection.protocol,
+                &mut reply,
+                &req,
+                index_anchor_block_hash,
+                *min_seq,
+                chainstate,
+                network.burnchain_tip.canonical_stacks_tip_height,
+            )?,
+            HttpRequestType::GetTransactionUnconfirmed(ref _md, ref txid) => {
+                ConversationHttp::handle_gettransaction_unconfirmed(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    chainstate,
+                    mempool,
+                    txid,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetAccount(ref _md, ref principal, ref tip_req, ref with_proof) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_account_entry(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        principal,
+                        *with_proof,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetDataVar(
+                ref _md,
+                ref contract_addr,
+                ref contract_name,
+                ref var_name,
+                ref tip_req,
+                ref with_proof,
+            ) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_data_var(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        contract_addr,
+                        contract_name,
+                        var_name,
+                        *with_proof,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetMapEntry(
+                ref _md,
+                ref contract_addr,
+                ref contract_name,
+                ref map_name,
+                ref key,
+                ref tip_req,
+                ref with_proof,
+            ) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_map_entry(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        contract_addr,
+                        contract_name,
+                        map_name,
+                        key,
+                        *with_proof,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetMapEntries {
+                ref contract_addr,
+                ref contract_name,
+                ref map_name,
+                ref after,
+                limit,
+                ref tip_req,
+                ..
+            } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_map_entries(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        contract_addr,
+                        contract_name,
+                        map_name,
+                        after.as_ref(),
+                        limit,
+                        &self.connection.options,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetClarityDataProof(ref _md, ref key, ref tip_req) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_clarity_data_proof(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        key,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetTransferCost(ref _md) => {
+                ConversationHttp::handle_token_transfer_cost(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetSubnetFeeRateEstimate(ref _md) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_subnet_fee_rate_estimate(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        &mut network.subnet_fee_estimate,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetContractABI(
+                ref _md,
+                ref contract_addr,
+                ref contract_name,
+                ref tip_req,
+            ) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_contract_abi(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        contract_addr,
+                        contract_name,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::FeeRateEstimate(ref _md, ref tx, estimated_len) => {
+                ConversationHttp::handle_post_fee_rate_estimate(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    handler_opts,
+                    sortdb,
+                    tx,
+                    estimated_len,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::CallReadOnlyFunction(
+                ref _md,
+                ref ctrct_addr,
+                ref ctrct_name,
+                ref as_sender,
+                ref func_name,
+                ref args,
+                ref cost_budget,
+                ref tip_req,
+            ) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_readonly_function_call(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        ctrct_addr,
+                        ctrct_name,
+                        func_name,
+                        as_sender,
+                        args,
+                        cost_budget.as_ref(),
+                        &self.connection.options,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::TraceReadOnlyFunction(
+                ref _md,
+                ref ctrct_addr,
+                ref ctrct_name,
+                ref as_sender,
+                ref func_name,
+                ref args,
+                ref cost_budget,
+                ref tip_req,
+            ) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_trace_readonly_function_call(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        ctrct_addr,
+                        ctrct_name,
+                        func_name,
+                        as_sender,
+                        args,
+                        cost_budget.as_ref(),
+                        &self.connection.options,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetContractSrc(
+                ref _md,
+                ref contract_addr,
+                ref contract_name,
+                ref tip_req,
+                ref with_proof,
+            ) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_contract_src(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        contract_addr,
+                        contract_name,
+                        *with_proof,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::PostTransaction(ref _md, ref tx, ref attachment, ref diagnostics) => {
+                match chainstate.get_stacks_chain_tip(sortdb)? {
+                    Some(tip) => {
+                        let accepted = ConversationHttp::handle_post_transaction(
+                            &mut self.connection.protocol,
+                            &mut reply,
+                            &req,
+                            chainstate,
+                            sortdb,
+                            tip.consensus_hash,
+                            tip.anchored_block_hash,
+                            mempool,
+                            tx.clone(),
+                            &mut network.atlasdb,
+                            attachment.clone(),
+                            *diagnostics,
+                            handler_opts.event_observer.as_deref(),
+                            network.burnchain_tip.canonical_stacks_tip_height,
+                        )?;
+                        if accepted {
+                            // forward to peer network
+                            ret.push(StacksMessageType::Transaction(tx.clone()));
+                        }
+                    }
+                    None => {
+                        let response_metadata = HttpResponseMetadata::from_http_request_type(
+                            &req,
+                            Some(network.burnchain_tip.canonical_stacks_tip_height),
+                        );
+                        warn!("Failed to load Stacks chain tip");
+                        let response = HttpResponseType::ServerError(
+                            response_metadata,
+                            format!("Failed to load Stacks chain tip"),
+                        );
+                        response.send(&mut self.connection.protocol, &mut reply)?;
+                    }
+                }
+                None
+            }
+            HttpRequestType::PostTransactionBatch(ref _md, ref txs, ref all_or_nothing) => {
+                match chainstate.get_stacks_chain_tip(sortdb)? {
+                    Some(tip) => {
+                        let admitted = ConversationHttp::handle_post_transaction_batch(
+                            &mut self.connection.protocol,
+                            &mut reply,
+                            &req,
+                            chainstate,
+                            sortdb,
+                            tip.consensus_hash,
+                            tip.anchored_block_hash,
+                            mempool,
+                            txs,
+                            *all_or_nothing,
+                            handler_opts.event_observer.as_deref(),
+                            network.burnchain_tip.canonical_stacks_tip_height,
+                        )?;
+                        // forward to peer network
+                        ret.extend(admitted.into_iter().map(StacksMessageType::Transaction));
+                    }
+                    None => {
+                        let response_metadata = HttpResponseMetadata::from_http_request_type(
+                            &req,
+                            Some(network.burnchain_tip.canonical_stacks_tip_height),
+                        );
+                        warn!("Failed to load Stacks chain tip");
+                        let response = HttpResponseType::ServerError(
+                            response_metadata,
+                            format!("Failed to load Stacks chain tip"),
+                        );
+                        response.send(&mut self.connection.protocol, &mut reply)?;
+                    }
+                }
+                None
+            }
+            HttpRequestType::PostTransactionSponsorship(ref _md, ref sponsored_txid, ref offer) => {
+                match chainstate.get_stacks_chain_tip(sortdb)? {
+                    Some(tip) => {
+                        let accepted = ConversationHttp::handle_post_transaction_sponsorship(
+                            &mut self.connection.protocol,
+                            &mut reply,
+                            &req,
+                            chainstate,
+                            sortdb,
+                            tip.consensus_hash,
+                            tip.anchored_block_hash,
+                            mempool,
+                            sponsored_txid,
+                            offer,
+                            handler_opts.event_observer.as_deref(),
+                            network.burnchain_tip.canonical_stacks_tip_height,
+                        )?;
+                        if accepted {
+                            // forward to peer network
+                            ret.push(StacksMessageType::Transaction(offer.clone()));
+                        }
+                    }
+                    None => {
+                        let response_metadata = HttpResponseMetadata::from_http_request_type(
+                            &req,
+                            Some(network.burnchain_tip.canonical_stacks_tip_height),
+                        );
+                        warn!("Failed to load Stacks chain tip");
+                        let response = HttpResponseType::ServerError(
+                            response_metadata,
+                            format!("Failed to load Stacks chain tip"),
+                        );
+                        response.send(&mut self.connection.protocol, &mut reply)?;
+                    }
+                }
+                None
+            }
+            HttpRequestType::GetAttachment(ref _md, ref content_hash) => {
+                ConversationHttp::handle_getattachment(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &mut network.atlasdb,
+                    content_hash.clone(),
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetAttachmentsInv(
+                ref _md,
+                ref index_block_hash,
+                ref pages_indexes,
+            ) => {
+                ConversationHttp::handle_getattachmentsinv(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &mut network.atlasdb,
+                    &index_block_hash,
+                    pages_indexes,
+                    &self.connection.options,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::PostBlock(ref _md, ref consensus_hash, ref block) => {
+                let accepted = ConversationHttp::handle_post_block(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    sortdb,
+                    chainstate,
+                    consensus_hash,
+                    block,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                if accepted {
+                    // inform the peer network so it can announce its presence
+                    ret.push(StacksMessageType::Blocks(BlocksData {
+                        blocks: vec![BlocksDatum(consensus_hash.clone(), block.clone())],
+                    }));
+                }
+                None
+            }
+            HttpRequestType::PostMicroblock(ref _md, ref mblock, ref tip_req) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    if let Some((consensus_hash, block_hash)) =
+                        ConversationHttp::handle_load_stacks_chain_tip_hashes(
+                            &mut self.connection.protocol,
+                            &mut reply,
+                            &req,
+                            tip,
+                            chainstate,
+                            network.burnchain_tip.canonical_stacks_tip_height,
+                        )?
+                    {
+                        let accepted = ConversationHttp::handle_post_microblock(
+                            &mut self.connection.protocol,
+                            &mut reply,
+                            &req,
+                            &consensus_hash,
+                            &block_hash,
+                            chainstate,
+                            mblock,
+                            network.burnchain_tip.canonical_stacks_tip_height,
+                        )?;
+                        if accepted {
+                            // forward to peer network
+                            let tip = StacksBlockHeader::make_index_block_hash(
+                                &consensus_hash,
+                                &block_hash,
+                            );
+                            ret.push(StacksMessageType::Microblocks(MicroblocksData {
+                                index_anchor_block: tip,
+                                microblocks: vec![(*mblock).clone()],
+                            }));
+                        }
+                    }
+                }
+                None
+            }
+            HttpRequestType::MemPoolQuery(ref _md, ref query, ref page_id_opt, bloom_depth_opt) => {
+                Some(ConversationHttp::handle_mempool_query(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    sortdb,
+                    chainstate,
+                    query.clone(),
+                    network.connection_opts.mempool_max_tx_query,
+                    mempool.negotiate_bloom_depth(bloom_depth_opt),
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                    page_id_opt.clone(),
+                )?)
+            }
+            HttpRequestType::OptionsPreflight(ref _md, ref _path) => {
+                let response_metadata = HttpResponseMetadata::from_http_request_type(
+                    &req,
+                    Some(network.burnchain_tip.canonical_stacks_tip_height),
+                );
+                let response = HttpResponseType::OptionsPreflight(response_metadata);
+                response
+                    .send(&mut self.connection.protocol, &mut reply)
+                    .map(|_| ())?;
+                None
+            }
+            HttpRequestType::GetIsTraitImplemented(
+                ref _md,
+                ref contract_addr,
+                ref contract_name,
+                ref trait_id,
+                ref tip_req,
+            ) => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_is_trait_implemented(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        contract_addr,
+                        contract_name,
+                        trait_id,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::ClientError(ref _md, ref err) => {
+                let response_metadata = HttpResponseMetadata::from_http_request_type(
+                    &req,
+                    Some(network.burnchain_tip.canonical_stacks_tip_height),
+                );
+                let response = match err {
+                    ClientError::Message(s) => HttpResponseType::BadRequestJSON(
+                        response_metadata,
+                        serde_json::Value::String(s.to_string()),
+                    ),
+                    ClientError::NotFound(path) => {
+                        HttpResponseType::NotFound(response_metadata, path.clone())
+                    }
+                };
+
+                response
+                    .send(&mut self.connection.protocol, &mut reply)
+                    .map(|_| ())?;
+                None
+            }
+            HttpRequestType::GetWithdrawalStx {
+                withdraw_block_height,
+                ref sender,
+                withdrawal_id,
+                amount,
+                ..
+            } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_withdrawal_stx_entry(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        withdraw_block_height,
+                        &sender.clone(),
+                        withdrawal_id,
+                        amount,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetWithdrawalProof {
+                withdraw_block_height,
+                withdrawal_id,
+                ..
+            } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_withdrawal_proof(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        withdraw_block_height,
+                        withdrawal_id,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetTransactionProof {
+                ref index_block_hash,
+                ref txid,
+                ..
+            } => {
+                ConversationHttp::handle_get_transaction_proof(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    chainstate,
+                    index_block_hash,
+                    txid,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetDepositStatus { ref l1_txid, .. } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_deposit_status(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        l1_txid,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetNonceGaps {
+                ref address,
+                ref tip_req,
+                ..
+            } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    tip_req,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_nonce_gaps(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        mempool,
+                        &tip,
+                        address,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetMemPoolTxs {
+                ref filter,
+                ref cursor,
+                limit,
+                ..
+            } => {
+                ConversationHttp::handle_get_mempool_txs(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    mempool,
+                    filter,
+                    cursor.as_ref(),
+                    limit,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetMemPoolSummary { ref filter, .. } => {
+                ConversationHttp::handle_get_mempool_summary(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    mempool,
+                    filter,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetTransactionReceipt { ref txid, .. } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_transaction_receipt(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        chainstate,
+                        &tip,
+                        txid,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetWithdrawalStatus {
+                withdraw_block_height,
+                ..
+            } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_withdrawal_status(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        withdraw_block_height,
+                        self.connection.options.withdrawal_confirmation_depth,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetBlockResults { ref block, .. } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_block_results(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        chainstate,
+                        &tip,
+                        block,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+            HttpRequestType::GetBlockCosts {
+                ref index_block_hash,
+                ..
+            } => {
+                ConversationHttp::handle_get_block_costs(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    chainstate,
+                    index_block_hash,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetBlockHeader {
+                ref index_block_hash,
+                ..
+            } => {
+                ConversationHttp::handle_get_block_header(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    sortdb,
+                    chainstate,
+                    index_block_hash,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetBlockStateDiff {
+                ref index_block_hash,
+                ..
+            } => {
+                ConversationHttp::handle_get_block_state_diff(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    chainstate,
+                    index_block_hash,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetSubnetStatus(ref _md) => {
+                ConversationHttp::handle_get_subnet_status(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &network.burnchain_tip,
+                    sortdb,
+                    chainstate,
+                    &self.connection.options,
+                )?;
+                None
+            }
+            HttpRequestType::PostDivergenceSnapshot { ref auth_token, .. } => {
+                let canonical_stacks_tip_height = network.burnchain_tip.canonical_stacks_tip_height;
+                ConversationHttp::handle_post_divergence_snapshot(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network,
+                    sortdb,
+                    chainstate,
+                    mempool,
+                    self.on_admin_listener,
+                    auth_token.as_ref(),
+                    canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::DeleteMemPoolTx {
+                ref txid,
+                ref reason,
+                ref auth_token,
+                ..
+            } => {
+                let canonical_stacks_tip_height = network.burnchain_tip.canonical_stacks_tip_height;
+                ConversationHttp::handle_delete_mempool_tx(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network,
+                    mempool,
+                    txid,
+                    reason.as_ref(),
+                    self.on_admin_listener,
+                    auth_token.as_ref(),
+                    handler_opts.event_observer.as_deref(),
+                    canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetAdminPeers { ref auth_token, .. } => {
+                let canonical_stacks_tip_height = network.burnchain_tip.canonical_stacks_tip_height;
+                ConversationHttp::handle_get_admin_peers(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network,
+                    self.on_admin_listener,
+                    auth_token.as_ref(),
+                    canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetAdminObservers { ref auth_token, .. } => {
+                let canonical_stacks_tip_height = network.burnchain_tip.canonical_stacks_tip_height;
+                ConversationHttp::handle_get_admin_observers(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network,
+                    self.on_admin_listener,
+                    auth_token.as_ref(),
+                    canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetPeerBans { ref auth_token, .. } => {
+                let canonical_stacks_tip_height = network.burnchain_tip.canonical_stacks_tip_height;
+                ConversationHttp::handle_get_peer_bans(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network,
+                    self.on_admin_listener,
+                    auth_token.as_ref(),
+                    canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::PostPeerBan {
+                ref target,
+                duration_secs,
+                ref reason,
+                ref auth_token,
+                ..
+            } => {
+                let canonical_stacks_tip_height = network.burnchain_tip.canonical_stacks_tip_height;
+                ConversationHttp::handle_post_peer_ban(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network,
+                    target,
+                    duration_secs,
+                    reason.as_ref(),
+                    self.on_admin_listener,
+                    auth_token.as_ref(),
+                    canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::DeletePeerBan {
+                ban_id,
+                ref auth_token,
+                ..
+            } => {
+                let canonical_stacks_tip_height = network.burnchain_tip.canonical_stacks_tip_height;
+                ConversationHttp::handle_delete_peer_ban(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    network,
+                    ban_id,
+                    self.on_admin_listener,
+                    auth_token.as_ref(),
+                    canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::BlockProposal(_, ref proposal) => {
+                ConversationHttp::handle_validate_block_proposal(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    chainstate,
+                    sortdb,
+                    &proposal,
+                    &self.connection.options,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::ValidateBlockProposal(_, ref proposal) => {
+                ConversationHttp::handle_evaluate_block_proposal(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    chainstate,
+                    sortdb,
+                    &proposal,
+                    &self.connection.options,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )?;
+                None
+            }
+            HttpRequestType::GetWithdrawalNft {
+                withdraw_block_height,
+                ref sender,
+                withdrawal_id,
+                id,
+                ref asset_identifier,
+                ..
+            } => {
+                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
+                    &mut self.connection.protocol,
+                    &mut reply,
+                    &req,
+                    &TipRequest::UseLatestAnchoredTip,
+                    sortdb,
+                    chainstate,
+                    network.burnchain_tip.canonical_stacks_tip_height,
+                )? {
+                    ConversationHttp::handle_get_withdrawal_nft_entry(
+                        &mut self.connection.protocol,
+                        &mut reply,
+                        &req,
+                        sortdb,
+                        chainstate,
+                        &tip,
+                        withdraw_block_height,
+                        &sender.clone(),
+                        withdrawal_id,
+                        asset_identifier,
+                        id,
+                        network.burnchain_tip.canonical_stacks_tip_height,
+                    )?;
+                }
+                None
+            }
+        };
+
+        match stream_opt {
+            None => {
+                self.reply_streams.push_back((reply, None, keep_alive));
+            }
+            Some(stream) => {
+                self.reply_streams.push_back((
+                    reply,
+                    Some((
+                        HttpChunkedTransferWriterState::new(STREAM_CHUNK_SIZE as usize),
+                        stream,
+                    )),
+                    keep_alive,
+                ));
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Make progress on outbound requests.
+    fn send_outbound_responses(
+        &mut self,
+        mempool: &MemPoolDB,
+        chainstate: &mut StacksChainState,
+    ) -> Result<(), net_error> {
+        // send out streamed responses in the order they were requested
+        let mut drained_handle = false;
+        let mut drained_stream = false;
+        let mut broken = false;
+        let mut do_keep_alive = true;
+
+        test_debug!(
+            "{:?}: {} HTTP replies pending",
+            &self,
+            self.reply_streams.len()
+        );
+        let _self_str = format!("{}", &self);
+
+        match self.reply_streams.front_mut() {
+            Some((ref mut reply, ref mut stream_opt, ref keep_alive)) => {
+                do_keep_alive = *keep_alive;
+
+                // if we're streaming, make some progress on the stream
+                match stream_opt {
+                    Some((ref mut http_chunk_state, ref mut stream)) => {
+                        let mut encoder =
+                            HttpChunkedTransferWriter::from_writer_state(reply, http_chunk_state);
+                        match stream.stream_to(mempool, chainstate, &mut encoder, STREAM_CHUNK_SIZE)
+                        {
+                            Ok(nw) => {
+                                test_debug!("{}: Streamed {} bytes", &_self_str, nw);
+                                if nw == 0 {
+                                    // EOF -- finish chunk and stop sending.
+                                    if !encoder.corked() {
+                                        encoder.flush().map_err(|e| {
+                                            test_debug!(
+                                                "{}: Write error on encoder flush: {:?}",
+                                                &_self_str,
+                                                &e
+                                            );
+                                            net_error::WriteError(e)
+                                        })?;
+
+                                        encoder.cork();
+
+                                        test_debug!("{}: Stream indicates EOF", &_self_str);
+                                    }
+
+                                    // try moving some data to the connection only once we're done
+                                    // streaming
+                                    match reply.try_flush() {
+                                        Ok(res) => {
+                                            test_debug!(
+                                                "{}: Streamed reply is drained?: {}",
+                                                &_self_str,
+                                                res
+                                            );
+                                            drained_handle = res;
+                                        }
+                                        Err(e) => {
+                                            // dead
+                                            warn!(
+                                                "{}: Broken HTTP connection: {:?}",
+                                                &_self_str, &e
+                                            );
+                                            broken = true;
+                                        }
+                                    }
+                                    drained_stream = true;
+                                }
+                            }
+                            Err(e) => {
+                                // broken -- terminate the stream.
+                                // For example, if we're streaming an unconfirmed block or
+                                // microblock, the data can get moved to the chunk store out from
+                                // under the stream.
+                                warn!(
+                                    "{}: Failed to send to HTTP connection: {:?}",
+                                    &_self_str, &e
+                                );
+                                broken = true;
+                            }
+                        }
+                    }
+                    None => {
+                        // not streamed; all data is buffered
+                        drained_stream = true;
+
+                        // try moving some data to the connection
+                        match reply.try_flush() {
+                            Ok(res) => {
+                                test_debug!("{}: Reply is drained", &_self_str);
+                                drained_handle = res;
+                            }
+                            Err(e) => {
+                                // dead
+                                warn!("{}: Broken HTTP connection: {:?}", &_self_str, &e);
+                                broken = true;
+                            }
+                        }
+                    }
+                }
+            }
+            None => {}
+        }
+
+        if broken || (drained_handle && drained_stream) {
+            // done with this stream
+            test_debug!(
+                "{:?}: done with stream (broken={}, drained_handle={}, drained_stream={})",
+                &self,
+                broken,
+                drained_handle,
+                drained_stream
+            );
+            self.total_reply_count += 1;
+            self.reply_streams.pop_front();
+
+            if !do_keep_alive {
+                // encountered "Connection: close"
+                self.keep_alive = false;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn try_send_recv_response(
+        req: ReplyHandleHttp,
+    ) -> Result<HttpResponseType, Result<ReplyHandleHttp, net_error>> {
+        match req.try_send_recv() {
+            Ok(message) => match message {
+                StacksHttpMessage::Request(_) => {
+                    warn!("Received response: not a HTTP response");
+                    return Err(Err(net_error::InvalidMessage));
+                }
+                StacksHttpMessage::Response(http_response) => Ok(http_response),
+            },
+            Err(res) => Err(res),
+        }
+    }
+
+    /// Make progress on our request/response
+    fn recv_inbound_response(&mut self) -> Result<(), net_error> {
+        // make progress on our pending request (if it exists).
+        let inprogress = self.pending_request.is_some();
+        let is_pending = self.pending_response.is_none();
+
+        let pending_request = self.pending_request.take();
+        let response = match pending_request {
+            None => Ok(self.pending_response.take()),
+            Some(req) => match ConversationHttp::try_send_recv_response(req) {
+                Ok(response) => Ok(Some(response)),
+                Err(res) => match res {
+                    Ok(handle) => {
+                        // try again
+                        self.pending_request = Some(handle);
+                        Ok(self.pending_response.take())
+                    }
+                    Err(e) => Err(e),
+                },
+            },
+        }?;
+
+        self.pending_response = response;
+
+        if inprogress && self.pending_request.is_none() {
+            test_debug!(
+                "{:?},id={}: HTTP request finished",
+                &self.peer_host,
+                self.conn_id
+            );
+        }
+
+        if is_pending && self.pending_response.is_some() {
+            test_debug!(
+                "{:?},id={}: HTTP response finished",
+                &self.peer_host,
+                self.conn_id
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Try to get our response
+    pub fn try_get_response(&mut self) -> Option<HttpResponseType> {
+        self.pending_response.take()
+    }
+
+    /// Make progress on in-flight messages.
+    pub fn try_flush(
+        &mut self,
+        mempool: &MemPoolDB,
+        chainstate: &mut StacksChainState,
+    ) -> Result<(), net_error> {
+        self.send_outbound_responses(mempool, chainstate)?;
+        self.recv_inbound_response()?;
+        Ok(())
+    }
+
+    /// Is the connection idle?
+    pub fn is_idle(&self) -> bool {
+        self.pending_response.is_none()
+            && self.connection.inbox_len() == 0
+            && self.connection.outbox_len() == 0
+            && self.reply_streams.len() == 0
+    }
+
+    /// Is the conversation out of pending data?
+    /// Don't consider it drained if we haven't received anything yet
+    pub fn is_drained(&self) -> bool {
+        ((self.total_request_count > 0 && self.total_reply_count > 0)
+            || self.pending_error_response.is_some())
+            && self.is_idle()
+    }
+
+    /// Should the connection be kept alive even if drained?
+    pub fn is_keep_alive(&self) -> bool {
+        self.keep_alive
+    }
+
+    /// When was the last time we got an inbound request?
+    pub fn get_last_request_time(&self) -> u64 {
+        self.last_request_timestamp
+    }
+
+    /// When was the last time we sent data as part of an outbound response?
+    pub fn get_last_response_time(&self) -> u64 {
+        self.last_response_timestamp
+    }
+
+    /// When was this converation conencted?
+    pub fn get_connection_time(&self) -> u64 {
+        self.connection_time
+    }
+
+    /// Make progress on in-flight requests and replies.
+    /// Returns the list of transactions we'll need to forward to the peer network
+    pub fn chat(
+        &mut self,
+        network: &mut PeerNetwork,
+        sortdb: &SortitionDB,
+        chainstate: &mut StacksChainState,
+        mempool: &mut MemPoolDB,
+        handler_args: &RPCHandlerArgs,
+    ) -> Result<Vec<StacksMessageType>, net_error> {
+        // if we have an in-flight error, then don't take any more requests.
+        if self.pending_error_response.is_some() {
+            return Ok(vec![]);
+        }
+
+        // handle in-bound HTTP request(s)
+        let num_inbound = self.connection.inbox_len();
+        let mut ret = vec![];
+        test_debug!("{:?}: {} HTTP requests pending", &self, num_inbound);
+
+        for _i in 0..num_inbound {
+            let msg = match self.connection.next_inbox_message() {
+                None => {
+                    continue;
+                }
+                Some(m) => m,
+            };
+
+            match msg {
+                StacksHttpMessage::Request(req) => {
+                    // new request
+                    self.total_request_count += 1;
+                    self.last_request_timestamp = get_epoch_time_secs();
+                    if req.metadata().canonical_stacks_tip_height.is_some() {
+                        test_debug!(
+                            "Request metadata: canonical stacks tip height is {:?}",
+                            &req.metadata().canonical_stacks_tip_height
+                        );
+                        self.canonical_stacks_tip_height =
+                            req.metadata().canonical_stacks_tip_height;
+                    }
+                    let start_time = Instant::now();
+                    let path = req.get_path();
+                    let msgs = monitoring::instrument_http_request_handler(req, |req| {
+                        self.handle_request(req, network, sortdb, chainstate, mempool, handler_args)
+                    })?;
+
+                    debug!("Processed HTTPRequest"; "path" => %path, "processing_time_ms" => start_time.elapsed().as_millis(), "conn_id" => self.conn_id, "peer_addr" => &self.peer_addr);
+
+                    ret.extend(msgs);
+                }
+                StacksHttpMessage::Response(resp) => {
+                    // Is there someone else waiting for this message?  If so, pass it along.
+                    // (this _should_ be our pending_request handle)
+                    if resp.metadata().canonical_stacks_tip_height.is_some() {
+                        test_debug!(
+                            "Response metadata: canonical stacks tip height is {:?}",
+                            &resp.metadata().canonical_stacks_tip_height
+                        );
+                        self.canonical_stacks_tip_height =
+                            resp.metadata().canonical_stacks_tip_height;
+                    }
+                    match self
+                        .connection
+                        .fulfill_request(StacksHttpMessage::Response(resp))
+                    {
+                        None => {
+                            test_debug!("{:?}: Fulfilled pending HTTP request", &self);
+                        }
+                        Some(_msg) => {
+                            // unsolicited; discard
+                            test_debug!("{:?}: Dropping unsolicited HTTP response", &self);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(ret)
+    }
+
+    /// Remove all timed-out messages, and ding the remote peer as unhealthy
+    pub fn clear_timeouts(&mut self) -> () {
+        self.connection.drain_timeouts();
+    }
+
+    /// Load data into our HTTP connection
+    pub fn recv<R: Read>(&mut self, r: &mut R) -> Result<usize, net_error> {
+        let mut total_recv = 0;
+        loop {
+            let nrecv = match self.connection.recv_data(r) {
+                Ok(nr) => nr,
+                Err(e) => {
+                    debug!("{:?}: failed to recv: {:?}", self, &e);
+                    return Err(e);
+                }
+            };
+
+            total_recv += nrecv;
+            if nrecv > 0 {
+                self.last_request_timestamp = get_epoch_time_secs();
+            } else {
+                break;
+            }
+        }
+        monitoring::update_inbound_rpc_bandwidth(total_recv as i64);
+        Ok(total_recv)
+    }
+
+    /// Write data out of our HTTP connection.  Write as much as we can
+    pub fn send<W: Write>(
+        &mut self,
+        w: &mut W,
+        mempool: &MemPoolDB,
+        chainstate: &mut StacksChainState,
+    ) -> Result<usize, net_error> {
+        let mut total_sz = 0;
+        loop {
+            // prime the Write
+            self.try_flush(mempool, chainstate)?;
+
+            let sz = match self.connection.send_data(w) {
+                Ok(sz) => sz,
+                Err(e) => {
+                    info!("{:?}: failed to send on HTTP conversation: {:?}", self, &e);
+                    return Err(e);
+                }
+            };
+
+            total_sz += sz;
+            if sz > 0 {
+                self.last_response_timestamp = get_epoch_time_secs();
+            } else {
+                break;
+            }
+        }
+        monitoring::update_inbound_rpc_bandwidth(total_sz as i64);
+        Ok(total_sz)
+    }
+
+    /// Make a new getinfo request to this endpoint
+    pub fn new_getinfo(&self, stacks_height: Option<u64>) -> HttpRequestType {
+        HttpRequestType::GetInfo(HttpRequestMetadata::from_host(
+            self.peer_host.clone(),
+            stacks_height,
+        ))
+    }
+
+    /// Make a new getneighbors request to this endpoint
+    pub fn new_getneighbors(&self) -> HttpRequestType {
+        HttpRequestType::GetNeighbors(HttpRequestMetadata::from_host(self.peer_host.clone(), None))
+    }
+
+    /// Make a new getheaders request to this endpoint
+    pub fn new_getheaders(&self, quantity: u64, tip_req: TipRequest) -> HttpRequestType {
+        HttpRequestType::GetHeaders(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            quantity,
+            tip_req,
+        )
+    }
+
+    /// Make a new getblock request to this endpoint
+    pub fn new_getblock(&self, index_block_hash: StacksBlockId) -> HttpRequestType {
+        HttpRequestType::GetBlock(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            index_block_hash,
+        )
+    }
+
+    /// Make a new get-microblocks request to this endpoint
+    pub fn new_getmicroblocks_indexed(
+        &self,
+        index_microblock_hash: StacksBlockId,
+    ) -> HttpRequestType {
+        HttpRequestType::GetMicroblocksIndexed(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            index_microblock_hash,
+        )
+    }
+
+    /// Make a new get-microblocks-confirmed request to this endpoint
+    pub fn new_getmicroblocks_confirmed(
+        &self,
+        index_anchor_block_hash: StacksBlockId,
+    ) -> HttpRequestType {
+        HttpRequestType::GetMicroblocksConfirmed(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            index_anchor_block_hash,
+        )
+    }
+
+    /// Make a new get-microblocks request for unconfirmed microblocks
+    pub fn new_getmicroblocks_unconfirmed(
+        &self,
+        anchored_index_block_hash: StacksBlockId,
+        min_seq: u16,
+    ) -> HttpRequestType {
+        HttpRequestType::GetMicroblocksUnconfirmed(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            anchored_index_block_hash,
+            min_seq,
+        )
+    }
+
+    /// Make a new get-unconfirmed-tx request
+    pub fn new_gettransaction_unconfirmed(&self, txid: Txid) -> HttpRequestType {
+        HttpRequestType::GetTransactionUnconfirmed(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            txid,
+        )
+    }
+
+    /// Make a new post-transaction request
+    pub fn new_post_transaction(&self, tx: StacksTransaction) -> HttpRequestType {
+        HttpRequestType::PostTransaction(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            tx,
+            None,
+            false,
+        )
+    }
+
+    /// Make a new post-transaction request that asks for admission diagnostics
+    pub fn new_post_transaction_with_diagnostics(&self, tx: StacksTransaction) -> HttpRequestType {
+        HttpRequestType::PostTransaction(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            tx,
+            None,
+            true,
+        )
+    }
+
+    /// Make a new post-transaction-batch request
+    pub fn new_post_transaction_batch(
+        &self,
+        txs: Vec<StacksTransaction>,
+        all_or_nothing: bool,
+    ) -> HttpRequestType {
+        HttpRequestType::PostTransactionBatch(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            txs,
+            all_or_nothing,
+        )
+    }
+
+    /// Make a new post-transaction-sponsorship request
+    pub fn new_post_transaction_sponsorship(
+        &self,
+        sponsored_txid: Txid,
+        offer: StacksTransaction,
+    ) -> HttpRequestType {
+        HttpRequestType::PostTransactionSponsorship(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            sponsored_txid,
+            offer,
+        )
+    }
+
+    /// Make a new post-block request
+    pub fn new_post_block(&self, ch: ConsensusHash, block: StacksBlock) -> HttpRequestType {
+        HttpRequestType::PostBlock(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            ch,
+            block,
+        )
+    }
+
+    /// Make a new post-microblock request
+    pub fn new_post_microblock(
+        &self,
+        mblock: StacksMicroblock,
+        tip_req: TipRequest,
+    ) -> HttpRequestType {
+        HttpRequestType::PostMicroblock(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            mblock,
+            tip_req,
+        )
+    }
+
+    /// Make a new request for an account
+    pub fn new_getaccount(
+        &self,
+        principal: PrincipalData,
+        tip_req: TipRequest,
+        with_proof: bool,
+    ) -> HttpRequestType {
+        HttpRequestType::GetAccount(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            principal,
+            tip_req,
+            with_proof,
+        )
+    }
+
+    /// Make a new request for a data var
+    pub fn new_getdatavar(
+        &self,
+        contract_addr: StacksAddress,
+        contract_name: ContractName,
+        var_name: ClarityName,
+        tip_req: TipRequest,
+        with_proof: bool,
+    ) -> HttpRequestType {
+        HttpRequestType::GetDataVar(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            contract_addr,
+            contract_name,
+            var_name,
+            tip_req,
+            with_proof,
+        )
+    }
+
+    /// Make a new request for a data map
+    pub fn new_getmapentry(
+        &self,
+        contract_addr: StacksAddress,
+        contract_name: ContractName,
+        map_name: ClarityName,
+        key: Value,
+        tip_req: TipRequest,
+        with_proof: bool,
+    ) -> HttpRequestType {
+        HttpRequestType::GetMapEntry(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            contract_addr,
+            contract_name,
+            map_name,
+            key,
+            tip_req,
+            with_proof,
+        )
+    }
+
+    /// Make a new request for a page of a data map's entries
+    pub fn new_get_map_entries(
+        &self,
+        contract_addr: StacksAddress,
+        contract_name: ContractName,
+        map_name: ClarityName,
+        after: Option<Value>,
+        limit: u32,
+        tip_req: TipRequest,
+    ) -> HttpRequestType {
+        HttpRequestType::GetMapEntries {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            contract_addr,
+            contract_name,
+            map_name,
+            after,
+            limit,
+            tip_req,
+        }
+    }
+
+    /// Make a new request for a transaction's inclusion proof
+    pub fn new_gettransactionproof(
+        &self,
+        index_block_hash: StacksBlockId,
+        txid: Txid,
+    ) -> HttpRequestType {
+        HttpRequestType::GetTransactionProof {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            index_block_hash,
+            txid,
+        }
+    }
+
+    /// Make a new request for the status of an L1 deposit
+    pub fn new_getdepositstatus(&self, l1_txid: Txid) -> HttpRequestType {
+        HttpRequestType::GetDepositStatus {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            l1_txid,
+        }
+    }
+
+    /// Make a new request for the execution costs consumed by a block
+    pub fn new_getblockcosts(&self, index_block_hash: StacksBlockId) -> HttpRequestType {
+        HttpRequestType::GetBlockCosts {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            index_block_hash,
+        }
+    }
+
+    /// Make a new request for the header of a processed block
+    pub fn new_getblockheader(&self, index_block_hash: StacksBlockId) -> HttpRequestType {
+        HttpRequestType::GetBlockHeader {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            index_block_hash,
+        }
+    }
+
+    /// Make a new request for the account state that a processed block changed
+    pub fn new_getblockstatediff(&self, index_block_hash: StacksBlockId) -> HttpRequestType {
+        HttpRequestType::GetBlockStateDiff {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            index_block_hash,
+        }
+    }
+
+    /// Make a new request to evaluate a block proposal without signing it
+    pub fn new_validateblockproposal(&self, proposal: miner::Proposal) -> HttpRequestType {
+        HttpRequestType::ValidateBlockProposal(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            proposal,
+        )
+    }
+
+    /// Make a new request for a multi-miner signer to sign a block proposal
+    pub fn new_blockproposal(&self, proposal: miner::Proposal) -> HttpRequestType {
+        HttpRequestType::BlockProposal(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            proposal,
+        )
+    }
+
+    /// Make a new request for the receipt of a processed transaction
+    pub fn new_gettransactionreceipt(&self, txid: Txid) -> HttpRequestType {
+        HttpRequestType::GetTransactionReceipt {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            txid,
+        }
+    }
+
+    /// Make a new request for the nonces missing from an account's pending transactions
+    pub fn new_getnoncegaps(&self, address: StacksAddress, tip_req: TipRequest) -> HttpRequestType {
+        HttpRequestType::GetNonceGaps {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            address,
+            tip_req,
+        }
+    }
+
+    /// Make a new request for a page of the pending transactions in the mempool
+    pub fn new_get_mempool_txs(
+        &self,
+        filter: MemPoolListFilter,
+        cursor: Option<MemPoolListCursor>,
+        limit: u32,
+    ) -> HttpRequestType {
+        HttpRequestType::GetMemPoolTxs {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            filter,
+            cursor,
+            limit,
+        }
+    }
+
+    /// Make a new request for a summary of the pending transactions in the mempool
+    pub fn new_get_mempool_summary(&self, filter: MemPoolListFilter) -> HttpRequestType {
+        HttpRequestType::GetMemPoolSummary {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            filter,
+        }
+    }
+
+    /// Make a new request for a summary of the subnet's health
+    pub fn new_getsubnetstatus(&self) -> HttpRequestType {
+        HttpRequestType::GetSubnetStatus(HttpRequestMetadata::from_host(
+            self.peer_host.clone(),
+            None,
+        ))
+    }
+
+    /// Make a new admin request to capture a divergence snapshot
+    pub fn new_post_divergence_snapshot(&self, auth_token: Option<String>) -> HttpRequestType {
+        HttpRequestType::PostDivergenceSnapshot {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            auth_token,
+        }
+    }
+
+    /// Make a new admin request to evict a transaction from the mempool
+    pub fn new_delete_mempool_tx(
+        &self,
+        txid: Txid,
+        reason: Option<String>,
+        auth_token: Option<String>,
+    ) -> HttpRequestType {
+        HttpRequestType::DeleteMemPoolTx {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            txid,
+            reason,
+            auth_token,
+        }
+    }
+
+    /// Make a new admin request for this node's connected peers
+    pub fn new_get_admin_observers(&self, auth_token: Option<String>) -> HttpRequestType {
+        HttpRequestType::GetAdminObservers {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            auth_token,
+        }
+    }
+
+    pub fn new_get_admin_peers(&self, auth_token: Option<String>) -> HttpRequestType {
+        HttpRequestType::GetAdminPeers {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            auth_token,
+        }
+    }
+
+    /// Make a new admin request for the peer bans in effect
+    pub fn new_get_peer_bans(&self, auth_token: Option<String>) -> HttpRequestType {
+        HttpRequestType::GetPeerBans {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            auth_token,
+        }
+    }
+
+    /// Make a new admin request to add a persistent peer ban
+    pub fn new_post_peer_ban(
+        &self,
+        target: PeerBanTarget,
+        duration_secs: Option<u64>,
+        reason: Option<String>,
+        auth_token: Option<String>,
+    ) -> HttpRequestType {
+        HttpRequestType::PostPeerBan {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            target,
+            duration_secs,
+            reason,
+            auth_token,
+        }
+    }
+
+    /// Make a new admin request to lift a persistent peer ban
+    pub fn new_delete_peer_ban(&self, ban_id: u64, auth_token: Option<String>) -> HttpRequestType {
+        HttpRequestType::DeletePeerBan {
+            metadata: HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            ban_id,
+            auth_token,
+        }
+    }
+
+    /// Make a new request for a MARF proof of a Clarity data key
+    pub fn new_getclaritydataproof(
+        &self,
+        key: ClarityDataKey,
+        tip_req: TipRequest,
+    ) -> HttpRequestType {
+        HttpRequestType::GetClarityDataProof(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            key,
+            tip_req,
+        )
+    }
+
+    /// Make a new request to get a contract's source
+    pub fn new_getcontractsrc(
+        &self,
+        contract_addr: StacksAddress,
+        contract_name: ContractName,
+        tip_req: TipRequest,
+        with_proof: bool,
+    ) -> HttpRequestType {
+        HttpRequestType::GetContractSrc(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            contract_addr,
+            contract_name,
+            tip_req,
+            with_proof,
+        )
+    }
+
+    /// Make a new request to get a contract's ABI
+    pub fn new_getcontractabi(
+        &self,
+        contract_addr: StacksAddress,
+        contract_name: ContractName,
+        tip_req: TipRequest,
+    ) -> HttpRequestType {
+        HttpRequestType::GetContractABI(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            contract_addr,
+            contract_name,
+            tip_req,
+        )
+    }
+
+    /// Make a new request to run a read-only function
+    pub fn new_callreadonlyfunction(
+        &self,
+        contract_addr: StacksAddress,
+        contract_name: ContractName,
+        sender: PrincipalData,
+        function_name: ClarityName,
+        function_args: Vec<Value>,
+        cost_budget: Option<ExecutionCost>,
+        tip_req: TipRequest,
+    ) -> HttpRequestType {
+        HttpRequestType::CallReadOnlyFunction(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            contract_addr,
+            contract_name,
+            sender,
+            function_name,
+            function_args,
+            cost_budget,
+            tip_req,
+        )
+    }
+
+    /// Make a new request to run a read-only function call with a step trace
+    pub fn new_tracereadonlyfunction(
+        &self,
+        contract_addr: StacksAddress,
+        contract_name: ContractName,
+        sender: PrincipalData,
+        function_name: ClarityName,
+        function_args: Vec<Value>,
+        cost_budget: Option<ExecutionCost>,
+        tip_req: TipRequest,
+    ) -> HttpRequestType {
+        HttpRequestType::TraceReadOnlyFunction(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            contract_addr,
+            contract_name,
+            sender,
+            function_name,
+            function_args,
+            cost_budget,
+            tip_req,
+        )
+    }
+
+    /// Make a new request for attachment inventory page
+    pub fn new_getattachmentsinv(
+        &self,
+        index_block_hash: StacksBlockId,
+        pages_indexes: HashSet<u32>,
+    ) -> HttpRequestType {
+        HttpRequestType::GetAttachmentsInv(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            index_block_hash,
+            pages_indexes,
+        )
+    }
+
+    /// Make a new request for mempool contents
+    pub fn new_mempool_query(
+        &self,
+        query: MemPoolSyncData,
+        page_id_opt: Option<Txid>,
+        bloom_depth_opt: Option<u64>,
+    ) -> HttpRequestType {
+        HttpRequestType::MemPoolQuery(
+            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
+            query,
+            page_id_opt,
+            bloom_depth_opt,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::convert::TryInto;
+    use std::iter::FromIterator;
+
+    use crate::burnchains::Burnchain;
+    use crate::burnchains::BurnchainView;
+    use crate::burnchains::*;
+    use crate::chainstate::burn::ConsensusHash;
+    use crate::chainstate::stacks::db::blocks::test::*;
+    use crate::chainstate::stacks::db::StacksChainState;
+    use crate::chainstate::stacks::db::StreamCursor;
+    use crate::chainstate::stacks::miner::test::make_user_stacks_transfer;
+    use crate::chainstate::stacks::miner::*;
+    use crate::chainstate::stacks::test::*;
+    use crate::chainstate::stacks::Error as chain_error;
+    use crate::chainstate::stacks::*;
+    use crate::net::codec::*;
+    use crate::net::http::*;
+    use crate::net::proposals::BlockProposerSchedule;
+    use crate::net::ratelimit::{RPCRateLimiter, RPCRateLimits, TokenBucketLimit};
+    use crate::net::test::*;
+    use crate::net::*;
+    use clarity::vm::types::*;
+    use stacks_common::address::*;
+    use stacks_common::util::get_epoch_time_secs;
+    use stacks_common::util::hash::hex_bytes;
+    use stacks_common::util::pipe::*;
+
+    use crate::chainstate::stacks::C32_ADDRESS_VERSION_TESTNET_SINGLESIG;
+    use crate::types::chainstate::BlockHeaderHash;
+    use crate::types::chainstate::BurnchainHeaderHash;
+    use crate::types::chainstate::TrieHash;
+    use crate::util_lib::db::FromColumn;
+
+    use crate::chainstate::stacks::db::state_diff::STXBalanceChange;
+    use crate::clarity_vm::database::marf::MARFWrite;
+    use crate::core::mempool::{
+        BLOOM_COUNTER_DEPTH, BLOOM_COUNTER_ERROR_RATE, MAX_BLOOM_COUNTER_TXS,
+    };
+    use crate::core::BLOCK_LIMIT_MAINNET_205;
+    use clarity::vm::database::{ClarityDatabase, ClaritySerializable, STXBalance};
+
+    use super::*;
+
+    const TEST_CONTRACT: &'static str = "
+        (define-data-var bar int 0)
+        (define-map unit-map { account: principal } { units: int })
+        (define-public (get-bar) (ok (var-get bar)))
+        (define-public (set-bar (x int) (y int))
+          (begin (var-set bar (/ x y)) (ok (var-get bar))))
+        (define-public (add-unit)
+          (begin
+            (map-set unit-map { account: tx-sender } { units: 1 } )
+            (var-set bar 1)
+            (ok 1)))
+        (begin
+          (map-set unit-map { account: 'ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R } { units: 123 }))";
+
+    const TEST_CONTRACT_UNCONFIRMED: &'static str = "(define-read-only (ro-test) (ok 1))";
+
+    /// auth token configured on the node that serves test requests
+    const TEST_AUTH_TOKEN: &'static str = "test-auth-token";
+
+    fn convo_send_recv(
+        sender: &mut ConversationHttp,
+        sender_mempool: &MemPoolDB,
+        sender_chainstate: &mut StacksChainState,
+        receiver: &mut ConversationHttp,
+        receiver_mempool: &MemPoolDB,
+        receiver_chainstate: &mut StacksChainState,
+    ) -> () {
+        let (mut pipe_read, mut pipe_write) = Pipe::new();
+        pipe_read.set_nonblocking(true);
+
+        loop {
+            let res = true;
+
+            sender.try_flush(sender_mempool, sender_chainstate).unwrap();
+            receiver
+                .try_flush(sender_mempool, receiver_chainstate)
+                .unwrap();
+
+            pipe_write.try_flush().unwrap();
+
+            let all_relays_flushed =
+                receiver.num_pending_outbound() == 0 && sender.num_pending_outbound() == 0;
+
+            let nw = sender
+                .send(&mut pipe_write, sender_mempool, sender_chainstate)
+                .unwrap();
+            let nr = receiver.recv(&mut pipe_read).unwrap();
+
+            test_debug!(
+                "res = {}, all_relays_flushed = {} ({},{}), nr = {}, nw = {}",
+                res,
+                all_relays_flushed,
+                receiver.num_pending_outbound(),
+                sender.num_pending_outbound(),
+                nr,
+                nw
+            );
+            if res && all_relays_flushed && nr == 0 && nw == 0 {
+                test_debug!("Breaking send_recv");
+                break;
+            }
+        }
+    }
+
+    /// General testing function to test RPC calls.
+    /// This function sets up two peers, a client and a server.
+    /// It takes in a function of type F that generates the request to be sent to the server
+    /// It takes in another function of type C that verifies that the result from
+    /// the server is as expected.
+    /// The parameter `include_microblocks` determines whether a microblock stream is mined or not.
+    fn test_rpc<F, C>(
+        test_name: &str,
+        peer_1_p2p: u16,
+        peer_1_http: u16,
+        peer_2_p2p: u16,
+        peer_2_http: u16,
+        include_microblocks: bool,
+        make_request: F,
+        check_result: C,
+    ) -> ()
+    where
+        F: FnOnce(
+            &mut TestPeer,
+            &mut ConversationHttp,
+            &mut TestPeer,
+            &mut ConversationHttp,
+        ) -> HttpRequestType,
+        C: FnOnce(
+            &HttpRequestType,
+            &HttpResponseType,
+            &mut TestPeer,
+            &mut TestPeer,
+            &ConversationHttp,
+            &ConversationHttp,
+        ) -> bool,
+    {
+        let mut peer_1_config = TestPeerConfig::new(test_name, peer_1_p2p, peer_1_http);
+        let mut peer_2_config = TestPeerConfig::new(test_name, peer_2_p2p, peer_2_http);
+        peer_2_config.connection_opts.auth_token = Some(TEST_AUTH_TOKEN.to_string());
+        peer_2_config.connection_opts.enable_map_iteration = true;
+        peer_2_config
+            .connection_opts
+            .enable_block_proposal_validation = true;
+
+        // ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R
+        let privk1 = StacksPrivateKey::from_hex(
+            "9f1f85a512a96a244e4c0d762788500687feb97481639572e3bffbd6860e6ab001",
+        )
+        .unwrap();
+
+        // STVN97YYA10MY5F6KQJHKNYJNM24C4A1AT39WRW
+        let privk2 = StacksPrivateKey::from_hex(
+            "94c319327cc5cd04da7147d32d836eb2e4c44f4db39aa5ede7314a761183d0c701",
+        )
+        .unwrap();
+        let microblock_privkey = StacksPrivateKey::new();
+        let microblock_pubkeyhash =
+            Hash160::from_node_public_key(&StacksPublicKey::from_private(&microblock_privkey));
+
+        let addr1 = StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(&privk1)],
+        )
+        .unwrap();
+        let addr2 = StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(&privk2)],
+        )
+        .unwrap();
+
+        peer_1_config.initial_balances = vec![
+            (addr1.to_account_principal(), 1000000000),
+            (addr2.to_account_principal(), 1000000000),
+        ];
+
+        peer_2_config.initial_balances = vec![
+            (addr1.to_account_principal(), 1000000000),
+            (addr2.to_account_principal(), 1000000000),
+        ];
+
+        peer_1_config.add_neighbor(&peer_2_config.to_neighbor());
+        peer_2_config.add_neighbor(&peer_1_config.to_neighbor());
+
+        let mut peer_1 = TestPeer::new(peer_1_config);
+        let mut peer_2 = TestPeer::new(peer_2_config);
+
+        // mine one block with a contract in it
+        // first the coinbase
+        // make a coinbase for this miner
+        let mut tx_coinbase = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::from_p2pkh(&privk1).unwrap(),
+            TransactionPayload::Coinbase(CoinbasePayload([0x00; 32])),
+        );
+        tx_coinbase.chain_id = 0x80000000;
+        tx_coinbase.anchor_mode = TransactionAnchorMode::OnChainOnly;
+        tx_coinbase.auth.set_origin_nonce(0);
+
+        let mut tx_signer = StacksTransactionSigner::new(&tx_coinbase);
+        tx_signer.sign_origin(&privk1).unwrap();
+        let tx_coinbase_signed = tx_signer.get_tx().unwrap();
+
+        // next the contract
+        let contract = TEST_CONTRACT.clone();
+        let mut tx_contract = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::from_p2pkh(&privk1).unwrap(),
+            TransactionPayload::new_smart_contract(&format!("hello-world"), &contract.to_string())
+                .unwrap(),
+        );
+
+        tx_contract.chain_id = 0x80000000;
+        tx_contract.auth.set_origin_nonce(1);
+        tx_contract.set_tx_fee(0);
+
+        let mut tx_signer = StacksTransactionSigner::new(&tx_contract);
+        tx_signer.sign_origin(&privk1).unwrap();
+        let tx_contract_signed = tx_signer.get_tx().unwrap();
+
+        // update account and state in a microblock that will be unconfirmed
+        let mut tx_cc = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::from_p2pkh(&privk1).unwrap(),
+            TransactionPayload::new_contract_call(addr1.clone(), "hello-world", "add-unit", vec![])
+                .unwrap(),
+        );
+
+        tx_cc.chain_id = 0x80000000;
+        tx_cc.auth.set_origin_nonce(2);
+        tx_cc.set_tx_fee(123);
+
+        let mut tx_signer = StacksTransactionSigner::new(&tx_cc);
+        tx_signer.sign_origin(&privk1).unwrap();
+        let tx_cc_signed = tx_signer.get_tx().unwrap();
+        let tx_cc_len = {
+            let mut bytes = vec![];
+            tx_cc_signed.consensus_serialize(&mut bytes).unwrap();
+            bytes.len() as u64
+        };
+
+        // make an unconfirmed contract
+        let unconfirmed_contract = TEST_CONTRACT_UNCONFIRMED.clone();
+        let mut tx_unconfirmed_contract = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::from_p2pkh(&privk1).unwrap(),
+            TransactionPayload::new_smart_contract(
+                &format!("hello-world-unconfirmed"),
+                &unconfirmed_contract.to_string(),
+            )
+            .unwrap(),
+        );
+
+        tx_unconfirmed_contract.chain_id = 0x80000000;
+        tx_unconfirmed_contract.auth.set_origin_nonce(3);
+        tx_unconfirmed_contract.set_tx_fee(0);
+
+        let mut tx_signer = StacksTransactionSigner::new(&tx_unconfirmed_contract);
+        tx_signer.sign_origin(&privk1).unwrap();
+        let tx_unconfirmed_contract_signed = tx_signer.get_tx().unwrap();
+        let tx_unconfirmed_contract_len = {
+            let mut bytes = vec![];
+            tx_unconfirmed_contract_signed
+                .consensus_serialize(&mut bytes)
+                .unwrap();
+            bytes.len() as u64
+        };
+
+        let tip =
+            SortitionDB::get_canonical_burn_chain_tip(&peer_1.sortdb.as_ref().unwrap().conn())
+                .unwrap();
+        let mut anchor_cost = ExecutionCost::zero();
+        let mut anchor_size = 0;
+
+        // make a block and a microblock.
+        // Put the coinbase and smart-contract in the anchored block.
+        // Put the contract-call in the microblock
+        let (burn_ops, stacks_block, microblocks) = peer_1.make_tenure(
+            |ref mut miner, ref mut sortdb, ref mut chainstate, vrf_proof, ref parent_opt, _| {
+                let parent_tip = match parent_opt {
+                    None => StacksChainState::get_genesis_header_info(chainstate.db()).unwrap(),
+                    Some(block) => {
+                        let ic = sortdb.index_conn();
+                        let snapshot = SortitionDB::get_block_snapshot_for_winning_stacks_block(
+                            &ic,
+                            &tip.sortition_id,
+                            &block.block_hash(),
+                        )
+                        .unwrap()
+                        .unwrap(); // succeeds because we don't fork
+                        StacksChainState::get_anchored_block_header_info(
+                            chainstate.db(),
+                            &snapshot.consensus_hash,
+                            &snapshot.winning_stacks_block_hash,
+                        )
+                        .unwrap()
+                        .unwrap()
                     }
                 };
 
-                response
-                    .send(&mut self.connection.protocol, &mut reply)
-                    .map(|_| ())?;
-                None
-            }
-            HttpRequestType::GetWithdrawalStx {
-                withdraw_block_height,
-                ref sender,
-                withdrawal_id,
-                amount,
-                ..
-            } => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    &TipRequest::UseLatestAnchoredTip,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_withdrawal_stx_entry(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
+                let block_builder = StacksBlockBuilder::make_regtest_block_builder(
+                    &parent_tip,
+                    vrf_proof,
+                    tip.total_burn,
+                    microblock_pubkeyhash,
+                )
+                .unwrap();
+                let (anchored_block, anchored_block_size, anchored_block_cost) =
+                    StacksBlockBuilder::make_anchored_block_from_txs(
+                        block_builder,
                         chainstate,
-                        &tip,
-                        withdraw_block_height,
-                        &sender.clone(),
-                        withdrawal_id,
-                        amount,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
-                }
-                None
-            }
-            HttpRequestType::BlockProposal(_, ref proposal) => {
-                let validator_key = self.connection.options.subnet_validator.as_ref();
-                let signing_contract = self.connection.options.subnet_signing_contract.as_ref();
+                        &sortdb.index_conn(),
+                        vec![tx_coinbase_signed.clone(), tx_contract_signed.clone()],
+                    )
+                    .unwrap();
+
+                anchor_size = anchored_block_size;
+                anchor_cost = anchored_block_cost;
+
+                (anchored_block, vec![])
+            },
+        );
+
+        let (_, _, consensus_hash) = peer_1.next_burnchain_block(burn_ops.clone());
+        peer_2.next_burnchain_block(burn_ops.clone());
+
+        peer_1.process_stacks_epoch_at_tip(&stacks_block, &vec![]);
+        peer_2.process_stacks_epoch_at_tip(&stacks_block, &vec![]);
+
+        // begin microblock section
+        if include_microblocks {
+            // build 1-block microblock stream with the contract-call and the unconfirmed contract
+            let microblock = {
+                let sortdb = peer_1.sortdb.take().unwrap();
+                Relayer::setup_unconfirmed_state(peer_1.chainstate(), &sortdb).unwrap();
+                let mblock = {
+                    let sort_iconn = sortdb.index_conn();
+                    let mut microblock_builder = StacksMicroblockBuilder::new(
+                        stacks_block.block_hash(),
+                        consensus_hash.clone(),
+                        peer_1.chainstate(),
+                        &sort_iconn,
+                        BlockBuilderSettings::max_value(),
+                    )
+                    .unwrap();
+                    let microblock = microblock_builder
+                        .mine_next_microblock_from_txs(
+                            vec![
+                                (tx_cc_signed, tx_cc_len),
+                                (tx_unconfirmed_contract_signed, tx_unconfirmed_contract_len),
+                            ],
+                            &microblock_privkey,
+                        )
+                        .unwrap();
+                    microblock
+                };
+                peer_1.sortdb = Some(sortdb);
+                mblock
+            };
+
+            // store microblock stream
+            peer_1
+                .chainstate()
+                .preprocess_streamed_microblock(
+                    &consensus_hash,
+                    &stacks_block.block_hash(),
+                    &microblock,
+                )
+                .unwrap();
+            peer_2
+                .chainstate()
+                .preprocess_streamed_microblock(
+                    &consensus_hash,
+                    &stacks_block.block_hash(),
+                    &microblock,
+                )
+                .unwrap();
+
+            // process microblock stream to generate unconfirmed state
+            let canonical_tip = StacksBlockHeader::make_index_block_hash(
+                &consensus_hash,
+                &stacks_block.block_hash(),
+            );
+            let sortdb1 = peer_1.sortdb.take().unwrap();
+            let sortdb2 = peer_2.sortdb.take().unwrap();
+            peer_1
+                .chainstate()
+                .reload_unconfirmed_state(&sortdb1.index_conn(), canonical_tip.clone())
+                .unwrap();
+            peer_2
+                .chainstate()
+                .reload_unconfirmed_state(&sortdb2.index_conn(), canonical_tip.clone())
+                .unwrap();
+            peer_1.sortdb = Some(sortdb1);
+            peer_2.sortdb = Some(sortdb2);
+        }
+        // end microblock section
+
+        // stuff some transactions into peer_2's mempool
+        // (relates to mempool query tests)
+        let mut mempool = peer_2.mempool.take().unwrap();
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        for i in 0..10 {
+            let pk = StacksPrivateKey::new();
+            let addr = StacksAddress::from_public_keys(
+                C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+                &AddressHashMode::SerializeP2PKH,
+                1,
+                &vec![StacksPublicKey::from_private(&StacksPrivateKey::new())],
+            )
+            .unwrap();
+            let mut tx = StacksTransaction {
+                version: TransactionVersion::Testnet,
+                chain_id: 0x80000000,
+                auth: TransactionAuth::from_p2pkh(&pk).unwrap(),
+                anchor_mode: TransactionAnchorMode::Any,
+                post_condition_mode: TransactionPostConditionMode::Allow,
+                post_conditions: vec![],
+                payload: TransactionPayload::TokenTransfer(
+                    addr.to_account_principal(),
+                    123,
+                    TokenTransferMemo([0u8; 34]),
+                ),
+            };
+            tx.set_tx_fee(1000);
+            tx.set_origin_nonce(0);
 
-                // TODO(#135): add sender validation. This method should only
-                //  be sent by the leader: we should not accept proposals
-                //  not signed by the leader
-                ConversationHttp::handle_validate_block_proposal(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    chainstate,
-                    sortdb,
-                    &proposal,
-                    validator_key,
-                    signing_contract,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )?;
-                None
-            }
-            HttpRequestType::GetWithdrawalNft {
-                withdraw_block_height,
-                ref sender,
-                withdrawal_id,
-                id,
-                ref asset_identifier,
-                ..
-            } => {
-                if let Some(tip) = ConversationHttp::handle_load_stacks_chain_tip(
-                    &mut self.connection.protocol,
-                    &mut reply,
-                    &req,
-                    &TipRequest::UseLatestAnchoredTip,
-                    sortdb,
-                    chainstate,
-                    network.burnchain_tip.canonical_stacks_tip_height,
-                )? {
-                    ConversationHttp::handle_get_withdrawal_nft_entry(
-                        &mut self.connection.protocol,
-                        &mut reply,
-                        &req,
-                        chainstate,
-                        &tip,
-                        withdraw_block_height,
-                        &sender.clone(),
-                        withdrawal_id,
-                        asset_identifier,
-                        id,
-                        network.burnchain_tip.canonical_stacks_tip_height,
-                    )?;
-                }
-                None
-            }
-        };
+            let txid = tx.txid();
+            let tx_bytes = tx.serialize_to_vec();
+            let origin_addr = tx.origin_address();
+            let origin_nonce = tx.get_origin_nonce();
+            let sponsor_addr = tx.sponsor_address().unwrap_or(origin_addr.clone());
+            let sponsor_nonce = tx.get_sponsor_nonce().unwrap_or(origin_nonce);
+            let tx_fee = tx.get_tx_fee();
 
-        match stream_opt {
-            None => {
-                self.reply_streams.push_back((reply, None, keep_alive));
-            }
-            Some(stream) => {
-                self.reply_streams.push_back((
-                    reply,
-                    Some((
-                        HttpChunkedTransferWriterState::new(STREAM_CHUNK_SIZE as usize),
-                        stream,
-                    )),
-                    keep_alive,
-                ));
-            }
+            // should succeed
+            MemPoolDB::try_add_tx(
+                &mut mempool_tx,
+                peer_1.chainstate(),
+                &consensus_hash,
+                &stacks_block.block_hash(),
+                txid.clone(),
+                tx_bytes,
+                tx_fee,
+                stacks_block.header.total_work.work,
+                &origin_addr,
+                origin_nonce,
+                &sponsor_addr,
+                sponsor_nonce,
+                None,
+            )
+            .unwrap();
         }
-        Ok(ret)
-    }
+        mempool_tx.commit().unwrap();
+        peer_2.mempool.replace(mempool);
 
-    /// Make progress on outbound requests.
-    fn send_outbound_responses(
-        &mut self,
-        mempool: &MemPoolDB,
-        chainstate: &mut StacksChainState,
-    ) -> Result<(), net_error> {
-        // send out streamed responses in the order they were requested
-        let mut drained_handle = false;
-        let mut drained_stream = false;
-        let mut broken = false;
-        let mut do_keep_alive = true;
+        let peer_1_sortdb = peer_1.sortdb.take().unwrap();
+        let peer_1_stacks_node = peer_1.stacks_node.take().unwrap();
+        let _ = peer_1
+            .network
+            .refresh_burnchain_view(&peer_1_sortdb, &peer_1_stacks_node.chainstate, false)
+            .unwrap();
+        peer_1.sortdb = Some(peer_1_sortdb);
+        peer_1.stacks_node = Some(peer_1_stacks_node);
+
+        let peer_2_sortdb = peer_2.sortdb.take().unwrap();
+        let peer_2_stacks_node = peer_2.stacks_node.take().unwrap();
+        let _ = peer_2
+            .network
+            .refresh_burnchain_view(&peer_2_sortdb, &peer_2_stacks_node.chainstate, false)
+            .unwrap();
+        peer_2.sortdb = Some(peer_2_sortdb);
+        peer_2.stacks_node = Some(peer_2_stacks_node);
+
+        let view_1 = peer_1.get_burnchain_view().unwrap();
+        let view_2 = peer_2.get_burnchain_view().unwrap();
+
+        let mut convo_1 = ConversationHttp::new(
+            format!("127.0.0.1:{}", peer_1_http)
+                .parse::<SocketAddr>()
+                .unwrap(),
+            Some(UrlString::try_from(format!("http://peer1.com")).unwrap()),
+            peer_1.to_peer_host(),
+            &peer_1.config.connection_opts,
+            0,
+        );
+
+        let mut convo_2 = ConversationHttp::new(
+            format!("127.0.0.1:{}", peer_2_http)
+                .parse::<SocketAddr>()
+                .unwrap(),
+            Some(UrlString::try_from(format!("http://peer2.com")).unwrap()),
+            peer_2.to_peer_host(),
+            &peer_2.config.connection_opts,
+            1,
+        );
+
+        let req = make_request(&mut peer_1, &mut convo_1, &mut peer_2, &mut convo_2);
+
+        convo_1.send_request(req.clone()).unwrap();
+        let mut peer_1_mempool = peer_1.mempool.take().unwrap();
+        let peer_2_mempool = peer_2.mempool.take().unwrap();
+
+        test_debug!("convo1 sends to convo2");
+        convo_send_recv(
+            &mut convo_1,
+            &peer_1_mempool,
+            peer_1.chainstate(),
+            &mut convo_2,
+            &peer_2_mempool,
+            peer_2.chainstate(),
+        );
+
+        // hack around the borrow-checker
+        let mut peer_1_sortdb = peer_1.sortdb.take().unwrap();
+        let mut peer_1_stacks_node = peer_1.stacks_node.take().unwrap();
+
+        Relayer::setup_unconfirmed_state(&mut peer_1_stacks_node.chainstate, &peer_1_sortdb)
+            .unwrap();
+
+        convo_1
+            .chat(
+                &mut peer_1.network,
+                &mut peer_1_sortdb,
+                &mut peer_1_stacks_node.chainstate,
+                &mut peer_1_mempool,
+                &RPCHandlerArgs::default(),
+            )
+            .unwrap();
+
+        peer_1.sortdb = Some(peer_1_sortdb);
+        peer_1.stacks_node = Some(peer_1_stacks_node);
+        peer_1.mempool = Some(peer_1_mempool);
+        peer_2.mempool = Some(peer_2_mempool);
+
+        test_debug!("convo2 sends to convo1");
+
+        // hack around the borrow-checker
+        let mut peer_2_sortdb = peer_2.sortdb.take().unwrap();
+        let mut peer_2_stacks_node = peer_2.stacks_node.take().unwrap();
+        let mut peer_2_mempool = peer_2.mempool.take().unwrap();
+
+        let _ = peer_2
+            .network
+            .refresh_burnchain_view(&peer_2_sortdb, &peer_2_stacks_node.chainstate, false)
+            .unwrap();
+
+        Relayer::setup_unconfirmed_state(&mut peer_2_stacks_node.chainstate, &peer_2_sortdb)
+            .unwrap();
+
+        convo_2
+            .chat(
+                &mut peer_2.network,
+                &mut peer_2_sortdb,
+                &mut peer_2_stacks_node.chainstate,
+                &mut peer_2_mempool,
+                &RPCHandlerArgs::default(),
+            )
+            .unwrap();
+
+        peer_2.sortdb = Some(peer_2_sortdb);
+        peer_2.stacks_node = Some(peer_2_stacks_node);
+        let mut peer_1_mempool = peer_1.mempool.take().unwrap();
+
+        convo_send_recv(
+            &mut convo_2,
+            &peer_2_mempool,
+            peer_2.chainstate(),
+            &mut convo_1,
+            &peer_1_mempool,
+            peer_1.chainstate(),
+        );
+
+        test_debug!("flush convo1");
+
+        // hack around the borrow-checker
+        convo_send_recv(
+            &mut convo_1,
+            &peer_1_mempool,
+            peer_1.chainstate(),
+            &mut convo_2,
+            &peer_2_mempool,
+            peer_2.chainstate(),
+        );
+
+        peer_2.mempool = Some(peer_2_mempool);
+
+        let mut peer_1_sortdb = peer_1.sortdb.take().unwrap();
+        let mut peer_1_stacks_node = peer_1.stacks_node.take().unwrap();
+
+        let _ = peer_1
+            .network
+            .refresh_burnchain_view(&peer_1_sortdb, &peer_1_stacks_node.chainstate, false)
+            .unwrap();
+
+        Relayer::setup_unconfirmed_state(&mut peer_1_stacks_node.chainstate, &peer_1_sortdb)
+            .unwrap();
+
+        convo_1
+            .chat(
+                &mut peer_1.network,
+                &mut peer_1_sortdb,
+                &mut peer_1_stacks_node.chainstate,
+                &mut peer_1_mempool,
+                &RPCHandlerArgs::default(),
+            )
+            .unwrap();
+
+        convo_1
+            .try_flush(&peer_1_mempool, &mut peer_1_stacks_node.chainstate)
+            .unwrap();
 
-        test_debug!(
-            "{:?}: {} HTTP replies pending",
-            &self,
-            self.reply_streams.len()
-        );
-        let _self_str = format!("{}", &self);
+        peer_1.sortdb = Some(peer_1_sortdb);
+        peer_1.stacks_node = Some(peer_1_stacks_node);
+        peer_1.mempool = Some(peer_1_mempool);
 
-        match self.reply_streams.front_mut() {
-            Some((ref mut reply, ref mut stream_opt, ref keep_alive)) => {
-                do_keep_alive = *keep_alive;
+        // should have gotten a reply
+        let resp_opt = convo_1.try_get_response();
+        assert!(resp_opt.is_some());
 
-                // if we're streaming, make some progress on the stream
-                match stream_opt {
-                    Some((ref mut http_chunk_state, ref mut stream)) => {
-                        let mut encoder =
-                            HttpChunkedTransferWriter::from_writer_state(reply, http_chunk_state);
-                        match stream.stream_to(mempool, chainstate, &mut encoder, STREAM_CHUNK_SIZE)
-                        {
-                            Ok(nw) => {
-                                test_debug!("{}: Streamed {} bytes", &_self_str, nw);
-                                if nw == 0 {
-                                    // EOF -- finish chunk and stop sending.
-                                    if !encoder.corked() {
-                                        encoder.flush().map_err(|e| {
-                                            test_debug!(
-                                                "{}: Write error on encoder flush: {:?}",
-                                                &_self_str,
-                                                &e
-                                            );
-                                            net_error::WriteError(e)
-                                        })?;
+        let resp = resp_opt.unwrap();
+        assert!(check_result(
+            &req,
+            &resp,
+            &mut peer_1,
+            &mut peer_2,
+            &convo_1,
+            &convo_2
+        ));
+    }
 
-                                        encoder.cork();
+    /// This test tests two things:
+    /// (1) the get info RPC call
+    /// (2) whether the ConversationHttp object gets correctly updated with a peer's canonical
+    /// stacks tip height, which is sent in HTTP headers as part of the request/response
+    #[test]
+    #[ignore]
+    fn test_rpc_getinfo() {
+        let peer_server_info = RefCell::new(None);
+        let client_stacks_height = 17;
+        test_rpc(
+            "test_rpc_getinfo",
+            40000,
+            40001,
+            50000,
+            50001,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let peer_info = RPCPeerInfoData::from_network(
+                    &peer_server.network,
+                    &peer_server.stacks_node.as_ref().unwrap().chainstate,
+                    &None,
+                    &Sha256Sum::zero(),
+                );
 
-                                        test_debug!("{}: Stream indicates EOF", &_self_str);
-                                    }
+                *peer_server_info.borrow_mut() = Some(peer_info);
 
-                                    // try moving some data to the connection only once we're done
-                                    // streaming
-                                    match reply.try_flush() {
-                                        Ok(res) => {
-                                            test_debug!(
-                                                "{}: Streamed reply is drained?: {}",
-                                                &_self_str,
-                                                res
-                                            );
-                                            drained_handle = res;
-                                        }
-                                        Err(e) => {
-                                            // dead
-                                            warn!(
-                                                "{}: Broken HTTP connection: {:?}",
-                                                &_self_str, &e
-                                            );
-                                            broken = true;
-                                        }
-                                    }
-                                    drained_stream = true;
-                                }
-                            }
-                            Err(e) => {
-                                // broken -- terminate the stream.
-                                // For example, if we're streaming an unconfirmed block or
-                                // microblock, the data can get moved to the chunk store out from
-                                // under the stream.
-                                warn!(
-                                    "{}: Failed to send to HTTP connection: {:?}",
-                                    &_self_str, &e
-                                );
-                                broken = true;
-                            }
-                        }
+                convo_client.new_getinfo(Some(client_stacks_height))
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                assert_eq!(convo_client.canonical_stacks_tip_height, Some(1));
+                assert_eq!(
+                    convo_server.canonical_stacks_tip_height,
+                    Some(client_stacks_height)
+                );
+                match http_response {
+                    HttpResponseType::PeerInfo(response_md, peer_data) => {
+                        assert_eq!(Some((*peer_data).clone()), *peer_server_info.borrow());
+                        assert!(peer_data.node_public_key.is_some());
+                        assert!(peer_data.node_public_key_hash.is_some());
+                        assert_eq!(
+                            peer_data.node_public_key_hash,
+                            Some(Hash160::from_node_public_key(
+                                &peer_data
+                                    .node_public_key
+                                    .clone()
+                                    .unwrap()
+                                    .to_public_key()
+                                    .unwrap()
+                            ))
+                        );
+                        true
                     }
-                    None => {
-                        // not streamed; all data is buffered
-                        drained_stream = true;
-
-                        // try moving some data to the connection
-                        match reply.try_flush() {
-                            Ok(res) => {
-                                test_debug!("{}: Reply is drained", &_self_str);
-                                drained_handle = res;
-                            }
-                            Err(e) => {
-                                // dead
-                                warn!("{}: Broken HTTP connection: {:?}", &_self_str, &e);
-                                broken = true;
-                            }
-                        }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
                     }
                 }
-            }
-            None => {}
-        }
-
-        if broken || (drained_handle && drained_stream) {
-            // done with this stream
-            test_debug!(
-                "{:?}: done with stream (broken={}, drained_handle={}, drained_stream={})",
-                &self,
-                broken,
-                drained_handle,
-                drained_stream
-            );
-            self.total_reply_count += 1;
-            self.reply_streams.pop_front();
-
-            if !do_keep_alive {
-                // encountered "Connection: close"
-                self.keep_alive = false;
-            }
-        }
-        Ok(())
+            },
+        );
     }
 
-    pub fn try_send_recv_response(
-        req: ReplyHandleHttp,
-    ) -> Result<HttpResponseType, Result<ReplyHandleHttp, net_error>> {
-        match req.try_send_recv() {
-            Ok(message) => match message {
-                StacksHttpMessage::Request(_) => {
-                    warn!("Received response: not a HTTP response");
-                    return Err(Err(net_error::InvalidMessage));
+    #[test]
+    #[ignore]
+    fn test_rpc_getneighbors() {
+        test_rpc(
+            "test_rpc_getneighbors",
+            40010,
+            40011,
+            50010,
+            50011,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| { convo_client.new_getneighbors() },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::Neighbors(response_md, neighbor_info) => {
+                        assert_eq!(neighbor_info.sample.len(), 1);
+                        assert_eq!(neighbor_info.sample[0].port, peer_client.config.server_port); // we see ourselves as the neighbor
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
                 }
-                StacksHttpMessage::Response(http_response) => Ok(http_response),
             },
-            Err(res) => Err(res),
-        }
+        );
     }
 
-    /// Make progress on our request/response
-    fn recv_inbound_response(&mut self) -> Result<(), net_error> {
-        // make progress on our pending request (if it exists).
-        let inprogress = self.pending_request.is_some();
-        let is_pending = self.pending_response.is_none();
+    #[test]
+    #[ignore]
+    fn test_rpc_getheaders() {
+        let server_blocks_cell = RefCell::new(None);
 
-        let pending_request = self.pending_request.take();
-        let response = match pending_request {
-            None => Ok(self.pending_response.take()),
-            Some(req) => match ConversationHttp::try_send_recv_response(req) {
-                Ok(response) => Ok(Some(response)),
-                Err(res) => match res {
-                    Ok(handle) => {
-                        // try again
-                        self.pending_request = Some(handle);
-                        Ok(self.pending_response.take())
-                    }
-                    Err(e) => Err(e),
-                },
-            },
-        }?;
+        test_rpc(
+            "test_rpc_getheaders",
+            40012,
+            40013,
+            50012,
+            50013,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                // have "server" peer store a few continuous block to staging
+                let mut blocks: Vec<StacksBlock> = vec![];
+                let mut index_block_hashes = vec![];
+                for i in 0..25 {
+                    let mut peer_server_block = make_codec_test_block(25);
 
-        self.pending_response = response;
+                    peer_server_block.header.total_work.work = (i + 1) as u64;
+                    peer_server_block.header.total_work.burn = (i + 1) as u64;
+                    peer_server_block.header.parent_block = blocks
+                        .last()
+                        .map(|blk| blk.block_hash())
+                        .unwrap_or(BlockHeaderHash([0u8; 32]));
 
-        if inprogress && self.pending_request.is_none() {
-            test_debug!(
-                "{:?},id={}: HTTP request finished",
-                &self.peer_host,
-                self.conn_id
-            );
-        }
+                    let peer_server_consensus_hash = ConsensusHash([(i + 1) as u8; 20]);
+                    let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                        &peer_server_consensus_hash,
+                        &peer_server_block.block_hash(),
+                    );
 
-        if is_pending && self.pending_response.is_some() {
-            test_debug!(
-                "{:?},id={}: HTTP response finished",
-                &self.peer_host,
-                self.conn_id
-            );
-        }
+                    test_debug!("Store peer server index block {:?}", &index_block_hash);
+                    store_staging_block(
+                        peer_server.chainstate(),
+                        &peer_server_consensus_hash,
+                        &peer_server_block,
+                        &ConsensusHash([i as u8; 20]),
+                        456,
+                        123,
+                    );
+                    set_block_processed(
+                        peer_server.chainstate(),
+                        &peer_server_consensus_hash,
+                        &peer_server_block.block_hash(),
+                        true,
+                    );
 
-        Ok(())
-    }
+                    index_block_hashes.push(index_block_hash);
+                    blocks.push(peer_server_block);
+                }
 
-    /// Try to get our response
-    pub fn try_get_response(&mut self) -> Option<HttpResponseType> {
-        self.pending_response.take()
-    }
+                let rev_blocks: Vec<_> = blocks.into_iter().rev().collect();
+                let rev_ibhs: Vec<_> = index_block_hashes.into_iter().rev().collect();
 
-    /// Make progress on in-flight messages.
-    pub fn try_flush(
-        &mut self,
-        mempool: &MemPoolDB,
-        chainstate: &mut StacksChainState,
-    ) -> Result<(), net_error> {
-        self.send_outbound_responses(mempool, chainstate)?;
-        self.recv_inbound_response()?;
-        Ok(())
-    }
+                let tip = rev_ibhs[0].clone();
+                *server_blocks_cell.borrow_mut() = Some((rev_blocks, rev_ibhs));
 
-    /// Is the connection idle?
-    pub fn is_idle(&self) -> bool {
-        self.pending_response.is_none()
-            && self.connection.inbox_len() == 0
-            && self.connection.outbox_len() == 0
-            && self.reply_streams.len() == 0
+                // now ask for it
+                convo_client.new_getheaders(25, TipRequest::SpecificTip(tip))
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::Headers(response_md, headers) => {
+                        assert_eq!(headers.len(), 25);
+                        let expected = server_blocks_cell.borrow().clone().unwrap();
+                        for (i, h) in headers.iter().enumerate() {
+                            assert_eq!(h.header, expected.0[i].header);
+                            assert_eq!(h.consensus_hash, ConsensusHash([(25 - i) as u8; 20]));
+                            if i + 1 < headers.len() {
+                                assert_eq!(h.parent_block_id, expected.1[i + 1]);
+                            }
+                        }
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
     }
 
-    /// Is the conversation out of pending data?
-    /// Don't consider it drained if we haven't received anything yet
-    pub fn is_drained(&self) -> bool {
-        ((self.total_request_count > 0 && self.total_reply_count > 0)
-            || self.pending_error_response.is_some())
-            && self.is_idle()
-    }
+    #[test]
+    #[ignore]
+    fn test_rpc_unconfirmed_getblock() {
+        let server_block_cell = RefCell::new(None);
 
-    /// Should the connection be kept alive even if drained?
-    pub fn is_keep_alive(&self) -> bool {
-        self.keep_alive
-    }
+        test_rpc(
+            "test_rpc_unconfirmed_getblock",
+            40020,
+            40021,
+            50020,
+            50021,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                // have "server" peer store a block to staging
+                let peer_server_block = make_codec_test_block(25);
+                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
+                let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &peer_server_consensus_hash,
+                    &peer_server_block.block_hash(),
+                );
 
-    /// When was the last time we got an inbound request?
-    pub fn get_last_request_time(&self) -> u64 {
-        self.last_request_timestamp
-    }
+                test_debug!("Store peer server index block {:?}", &index_block_hash);
+                store_staging_block(
+                    peer_server.chainstate(),
+                    &peer_server_consensus_hash,
+                    &peer_server_block,
+                    &ConsensusHash([0x03; 20]),
+                    456,
+                    123,
+                );
 
-    /// When was the last time we sent data as part of an outbound response?
-    pub fn get_last_response_time(&self) -> u64 {
-        self.last_response_timestamp
-    }
+                *server_block_cell.borrow_mut() = Some(peer_server_block);
 
-    /// When was this converation conencted?
-    pub fn get_connection_time(&self) -> u64 {
-        self.connection_time
+                // now ask for it
+                convo_client.new_getblock(index_block_hash)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::Block(response_md, block_info) => {
+                        assert_eq!(
+                            block_info.block_hash(),
+                            (*server_block_cell.borrow()).as_ref().unwrap().block_hash()
+                        );
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
     }
 
-    /// Make progress on in-flight requests and replies.
-    /// Returns the list of transactions we'll need to forward to the peer network
-    pub fn chat(
-        &mut self,
-        network: &mut PeerNetwork,
-        sortdb: &SortitionDB,
-        chainstate: &mut StacksChainState,
-        mempool: &mut MemPoolDB,
-        handler_args: &RPCHandlerArgs,
-    ) -> Result<Vec<StacksMessageType>, net_error> {
-        // if we have an in-flight error, then don't take any more requests.
-        if self.pending_error_response.is_some() {
-            return Ok(vec![]);
-        }
-
-        // handle in-bound HTTP request(s)
-        let num_inbound = self.connection.inbox_len();
-        let mut ret = vec![];
-        test_debug!("{:?}: {} HTTP requests pending", &self, num_inbound);
+    #[test]
+    #[ignore]
+    fn test_rpc_confirmed_getblock() {
+        let server_block_cell = RefCell::new(None);
 
-        for _i in 0..num_inbound {
-            let msg = match self.connection.next_inbox_message() {
-                None => {
-                    continue;
-                }
-                Some(m) => m,
-            };
+        test_rpc(
+            "test_rpc_confirmed_getblock",
+            40030,
+            40031,
+            50030,
+            50031,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                // have "server" peer store a block to staging
+                let peer_server_block = make_codec_test_block(25);
+                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
+                let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &peer_server_consensus_hash,
+                    &peer_server_block.block_hash(),
+                );
 
-            match msg {
-                StacksHttpMessage::Request(req) => {
-                    // new request
-                    self.total_request_count += 1;
-                    self.last_request_timestamp = get_epoch_time_secs();
-                    if req.metadata().canonical_stacks_tip_height.is_some() {
-                        test_debug!(
-                            "Request metadata: canonical stacks tip height is {:?}",
-                            &req.metadata().canonical_stacks_tip_height
-                        );
-                        self.canonical_stacks_tip_height =
-                            req.metadata().canonical_stacks_tip_height;
-                    }
-                    let start_time = Instant::now();
-                    let path = req.get_path();
-                    let msg_opt = monitoring::instrument_http_request_handler(req, |req| {
-                        self.handle_request(req, network, sortdb, chainstate, mempool, handler_args)
-                    })?;
+                test_debug!("Store peer server index block {:?}", &index_block_hash);
+                store_staging_block(
+                    peer_server.chainstate(),
+                    &peer_server_consensus_hash,
+                    &peer_server_block,
+                    &ConsensusHash([0x03; 20]),
+                    456,
+                    123,
+                );
+                set_block_processed(
+                    peer_server.chainstate(),
+                    &peer_server_consensus_hash,
+                    &peer_server_block.block_hash(),
+                    true,
+                );
 
-                    debug!("Processed HTTPRequest"; "path" => %path, "processing_time_ms" => start_time.elapsed().as_millis(), "conn_id" => self.conn_id, "peer_addr" => &self.peer_addr);
+                *server_block_cell.borrow_mut() = Some(peer_server_block);
 
-                    if let Some(msg) = msg_opt {
-                        ret.push(msg);
-                    }
-                }
-                StacksHttpMessage::Response(resp) => {
-                    // Is there someone else waiting for this message?  If so, pass it along.
-                    // (this _should_ be our pending_request handle)
-                    if resp.metadata().canonical_stacks_tip_height.is_some() {
-                        test_debug!(
-                            "Response metadata: canonical stacks tip height is {:?}",
-                            &resp.metadata().canonical_stacks_tip_height
+                // now ask for it
+                convo_client.new_getblock(index_block_hash)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::Block(response_md, block_info) => {
+                        assert_eq!(
+                            block_info.block_hash(),
+                            (*server_block_cell.borrow()).as_ref().unwrap().block_hash()
                         );
-                        self.canonical_stacks_tip_height =
-                            resp.metadata().canonical_stacks_tip_height;
+                        true
                     }
-                    match self
-                        .connection
-                        .fulfill_request(StacksHttpMessage::Response(resp))
-                    {
-                        None => {
-                            test_debug!("{:?}: Fulfilled pending HTTP request", &self);
-                        }
-                        Some(_msg) => {
-                            // unsolicited; discard
-                            test_debug!("{:?}: Dropping unsolicited HTTP response", &self);
-                        }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
                     }
                 }
-            }
-        }
-
-        Ok(ret)
-    }
-
-    /// Remove all timed-out messages, and ding the remote peer as unhealthy
-    pub fn clear_timeouts(&mut self) -> () {
-        self.connection.drain_timeouts();
+            },
+        );
     }
 
-    /// Load data into our HTTP connection
-    pub fn recv<R: Read>(&mut self, r: &mut R) -> Result<usize, net_error> {
-        let mut total_recv = 0;
-        loop {
-            let nrecv = match self.connection.recv_data(r) {
-                Ok(nr) => nr,
-                Err(e) => {
-                    debug!("{:?}: failed to recv: {:?}", self, &e);
-                    return Err(e);
-                }
-            };
-
-            total_recv += nrecv;
-            if nrecv > 0 {
-                self.last_request_timestamp = get_epoch_time_secs();
-            } else {
-                break;
-            }
-        }
-        monitoring::update_inbound_rpc_bandwidth(total_recv as i64);
-        Ok(total_recv)
-    }
+    #[test]
+    #[ignore]
+    fn test_rpc_get_indexed_microblocks() {
+        let server_microblocks_cell = RefCell::new(vec![]);
 
-    /// Write data out of our HTTP connection.  Write as much as we can
-    pub fn send<W: Write>(
-        &mut self,
-        w: &mut W,
-        mempool: &MemPoolDB,
-        chainstate: &mut StacksChainState,
-    ) -> Result<usize, net_error> {
-        let mut total_sz = 0;
-        loop {
-            // prime the Write
-            self.try_flush(mempool, chainstate)?;
+        test_rpc(
+            "test_rpc_indexed_microblocks",
+            40040,
+            40041,
+            50040,
+            50041,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let privk = StacksPrivateKey::from_hex(
+                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                )
+                .unwrap();
 
-            let sz = match self.connection.send_data(w) {
-                Ok(sz) => sz,
-                Err(e) => {
-                    info!("{:?}: failed to send on HTTP conversation: {:?}", self, &e);
-                    return Err(e);
-                }
-            };
+                let parent_block = make_codec_test_block(25);
+                let parent_consensus_hash = ConsensusHash([0x02; 20]);
+                let parent_index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &parent_consensus_hash,
+                    &parent_block.block_hash(),
+                );
 
-            total_sz += sz;
-            if sz > 0 {
-                self.last_response_timestamp = get_epoch_time_secs();
-            } else {
-                break;
-            }
-        }
-        monitoring::update_inbound_rpc_bandwidth(total_sz as i64);
-        Ok(total_sz)
-    }
+                let mut mblocks = make_sample_microblock_stream(&privk, &parent_block.block_hash());
+                mblocks.truncate(15);
 
-    /// Make a new getinfo request to this endpoint
-    pub fn new_getinfo(&self, stacks_height: Option<u64>) -> HttpRequestType {
-        HttpRequestType::GetInfo(HttpRequestMetadata::from_host(
-            self.peer_host.clone(),
-            stacks_height,
-        ))
-    }
+                let mut child_block = make_codec_test_block(25);
+                let child_consensus_hash = ConsensusHash([0x03; 20]);
 
-    /// Make a new getneighbors request to this endpoint
-    pub fn new_getneighbors(&self) -> HttpRequestType {
-        HttpRequestType::GetNeighbors(HttpRequestMetadata::from_host(self.peer_host.clone(), None))
-    }
+                child_block.header.parent_block = parent_block.block_hash();
+                child_block.header.parent_microblock =
+                    mblocks.last().as_ref().unwrap().block_hash();
+                child_block.header.parent_microblock_sequence =
+                    mblocks.last().as_ref().unwrap().header.sequence;
 
-    /// Make a new getheaders request to this endpoint
-    pub fn new_getheaders(&self, quantity: u64, tip_req: TipRequest) -> HttpRequestType {
-        HttpRequestType::GetHeaders(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            quantity,
-            tip_req,
-        )
-    }
+                store_staging_block(
+                    peer_server.chainstate(),
+                    &parent_consensus_hash,
+                    &parent_block,
+                    &ConsensusHash([0x01; 20]),
+                    456,
+                    123,
+                );
+                set_block_processed(
+                    peer_server.chainstate(),
+                    &parent_consensus_hash,
+                    &parent_block.block_hash(),
+                    true,
+                );
 
-    /// Make a new getblock request to this endpoint
-    pub fn new_getblock(&self, index_block_hash: StacksBlockId) -> HttpRequestType {
-        HttpRequestType::GetBlock(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            index_block_hash,
-        )
-    }
+                store_staging_block(
+                    peer_server.chainstate(),
+                    &child_consensus_hash,
+                    &child_block,
+                    &parent_consensus_hash,
+                    456,
+                    123,
+                );
+                set_block_processed(
+                    peer_server.chainstate(),
+                    &child_consensus_hash,
+                    &child_block.block_hash(),
+                    true,
+                );
 
-    /// Make a new get-microblocks request to this endpoint
-    pub fn new_getmicroblocks_indexed(
-        &self,
-        index_microblock_hash: StacksBlockId,
-    ) -> HttpRequestType {
-        HttpRequestType::GetMicroblocksIndexed(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            index_microblock_hash,
-        )
-    }
+                let index_microblock_hash = StacksBlockHeader::make_index_block_hash(
+                    &parent_consensus_hash,
+                    &mblocks.last().as_ref().unwrap().block_hash(),
+                );
 
-    /// Make a new get-microblocks-confirmed request to this endpoint
-    pub fn new_getmicroblocks_confirmed(
-        &self,
-        index_anchor_block_hash: StacksBlockId,
-    ) -> HttpRequestType {
-        HttpRequestType::GetMicroblocksConfirmed(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            index_anchor_block_hash,
-        )
-    }
+                for mblock in mblocks.iter() {
+                    store_staging_microblock(
+                        peer_server.chainstate(),
+                        &parent_consensus_hash,
+                        &parent_block.block_hash(),
+                        &mblock,
+                    );
+                }
 
-    /// Make a new get-microblocks request for unconfirmed microblocks
-    pub fn new_getmicroblocks_unconfirmed(
-        &self,
-        anchored_index_block_hash: StacksBlockId,
-        min_seq: u16,
-    ) -> HttpRequestType {
-        HttpRequestType::GetMicroblocksUnconfirmed(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            anchored_index_block_hash,
-            min_seq,
-        )
-    }
+                set_microblocks_processed(
+                    peer_server.chainstate(),
+                    &child_consensus_hash,
+                    &child_block.block_hash(),
+                    &mblocks.last().as_ref().unwrap().block_hash(),
+                );
 
-    /// Make a new get-unconfirmed-tx request
-    pub fn new_gettransaction_unconfirmed(&self, txid: Txid) -> HttpRequestType {
-        HttpRequestType::GetTransactionUnconfirmed(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            txid,
-        )
-    }
+                *server_microblocks_cell.borrow_mut() = mblocks;
 
-    /// Make a new post-transaction request
-    pub fn new_post_transaction(&self, tx: StacksTransaction) -> HttpRequestType {
-        HttpRequestType::PostTransaction(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            tx,
-            None,
-        )
+                convo_client.new_getmicroblocks_indexed(index_microblock_hash)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match (*http_response).clone() {
+                    HttpResponseType::Microblocks(_, mut microblocks) => {
+                        microblocks.reverse();
+                        assert_eq!(microblocks.len(), (*server_microblocks_cell.borrow()).len());
+                        assert_eq!(microblocks, *server_microblocks_cell.borrow());
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", http_response);
+                        false
+                    }
+                }
+            },
+        );
     }
 
-    /// Make a new post-block request
-    pub fn new_post_block(&self, ch: ConsensusHash, block: StacksBlock) -> HttpRequestType {
-        HttpRequestType::PostBlock(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            ch,
-            block,
-        )
-    }
+    #[test]
+    #[ignore]
+    fn test_rpc_get_confirmed_microblocks() {
+        let server_microblocks_cell = RefCell::new(vec![]);
 
-    /// Make a new post-microblock request
-    pub fn new_post_microblock(
-        &self,
-        mblock: StacksMicroblock,
-        tip_req: TipRequest,
-    ) -> HttpRequestType {
-        HttpRequestType::PostMicroblock(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            mblock,
-            tip_req,
-        )
-    }
+        test_rpc(
+            "test_rpc_confirmed_microblocks",
+            40042,
+            40043,
+            50042,
+            50043,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let privk = StacksPrivateKey::from_hex(
+                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                )
+                .unwrap();
 
-    /// Make a new request for an account
-    pub fn new_getaccount(
-        &self,
-        principal: PrincipalData,
-        tip_req: TipRequest,
-        with_proof: bool,
-    ) -> HttpRequestType {
-        HttpRequestType::GetAccount(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            principal,
-            tip_req,
-            with_proof,
-        )
-    }
+                let parent_block = make_codec_test_block(25);
+                let parent_consensus_hash = ConsensusHash([0x02; 20]);
 
-    /// Make a new request for a data var
-    pub fn new_getdatavar(
-        &self,
-        contract_addr: StacksAddress,
-        contract_name: ContractName,
-        var_name: ClarityName,
-        tip_req: TipRequest,
-        with_proof: bool,
-    ) -> HttpRequestType {
-        HttpRequestType::GetDataVar(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            contract_addr,
-            contract_name,
-            var_name,
-            tip_req,
-            with_proof,
-        )
-    }
+                let mut mblocks = make_sample_microblock_stream(&privk, &parent_block.block_hash());
+                mblocks.truncate(15);
 
-    /// Make a new request for a data map
-    pub fn new_getmapentry(
-        &self,
-        contract_addr: StacksAddress,
-        contract_name: ContractName,
-        map_name: ClarityName,
-        key: Value,
-        tip_req: TipRequest,
-        with_proof: bool,
-    ) -> HttpRequestType {
-        HttpRequestType::GetMapEntry(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            contract_addr,
-            contract_name,
-            map_name,
-            key,
-            tip_req,
-            with_proof,
-        )
-    }
+                let mut child_block = make_codec_test_block(25);
+                let child_consensus_hash = ConsensusHash([0x03; 20]);
 
-    /// Make a new request to get a contract's source
-    pub fn new_getcontractsrc(
-        &self,
-        contract_addr: StacksAddress,
-        contract_name: ContractName,
-        tip_req: TipRequest,
-        with_proof: bool,
-    ) -> HttpRequestType {
-        HttpRequestType::GetContractSrc(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            contract_addr,
-            contract_name,
-            tip_req,
-            with_proof,
-        )
-    }
+                child_block.header.parent_block = parent_block.block_hash();
+                child_block.header.parent_microblock =
+                    mblocks.last().as_ref().unwrap().block_hash();
+                child_block.header.parent_microblock_sequence =
+                    mblocks.last().as_ref().unwrap().header.sequence;
 
-    /// Make a new request to get a contract's ABI
-    pub fn new_getcontractabi(
-        &self,
-        contract_addr: StacksAddress,
-        contract_name: ContractName,
-        tip_req: TipRequest,
-    ) -> HttpRequestType {
-        HttpRequestType::GetContractABI(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            contract_addr,
-            contract_name,
-            tip_req,
-        )
-    }
+                let child_index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &child_consensus_hash,
+                    &child_block.block_hash(),
+                );
 
-    /// Make a new request to run a read-only function
-    pub fn new_callreadonlyfunction(
-        &self,
-        contract_addr: StacksAddress,
-        contract_name: ContractName,
-        sender: PrincipalData,
-        function_name: ClarityName,
-        function_args: Vec<Value>,
-        tip_req: TipRequest,
-    ) -> HttpRequestType {
-        HttpRequestType::CallReadOnlyFunction(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            contract_addr,
-            contract_name,
-            sender,
-            function_name,
-            function_args,
-            tip_req,
-        )
-    }
+                store_staging_block(
+                    peer_server.chainstate(),
+                    &parent_consensus_hash,
+                    &parent_block,
+                    &ConsensusHash([0x01; 20]),
+                    456,
+                    123,
+                );
+                set_block_processed(
+                    peer_server.chainstate(),
+                    &parent_consensus_hash,
+                    &parent_block.block_hash(),
+                    true,
+                );
 
-    /// Make a new request for attachment inventory page
-    pub fn new_getattachmentsinv(
-        &self,
-        index_block_hash: StacksBlockId,
-        pages_indexes: HashSet<u32>,
-    ) -> HttpRequestType {
-        HttpRequestType::GetAttachmentsInv(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            index_block_hash,
-            pages_indexes,
-        )
-    }
+                store_staging_block(
+                    peer_server.chainstate(),
+                    &child_consensus_hash,
+                    &child_block,
+                    &parent_consensus_hash,
+                    456,
+                    123,
+                );
+                set_block_processed(
+                    peer_server.chainstate(),
+                    &child_consensus_hash,
+                    &child_block.block_hash(),
+                    true,
+                );
 
-    /// Make a new request for mempool contents
-    pub fn new_mempool_query(
-        &self,
-        query: MemPoolSyncData,
-        page_id_opt: Option<Txid>,
-    ) -> HttpRequestType {
-        HttpRequestType::MemPoolQuery(
-            HttpRequestMetadata::from_host(self.peer_host.clone(), None),
-            query,
-            page_id_opt,
-        )
-    }
-}
+                for mblock in mblocks.iter() {
+                    store_staging_microblock(
+                        peer_server.chainstate(),
+                        &parent_consensus_hash,
+                        &parent_block.block_hash(),
+                        &mblock,
+                    );
+                }
+
+                set_microblocks_processed(
+                    peer_server.chainstate(),
+                    &child_consensus_hash,
+                    &child_block.block_hash(),
+                    &mblocks.last().as_ref().unwrap().block_hash(),
+                );
 
-#[cfg(test)]
-mod test {
-    use std::cell::RefCell;
-    use std::convert::TryInto;
-    use std::iter::FromIterator;
+                *server_microblocks_cell.borrow_mut() = mblocks;
 
-    use crate::burnchains::Burnchain;
-    use crate::burnchains::BurnchainView;
-    use crate::burnchains::*;
-    use crate::chainstate::burn::ConsensusHash;
-    use crate::chainstate::stacks::db::blocks::test::*;
-    use crate::chainstate::stacks::db::StacksChainState;
-    use crate::chainstate::stacks::db::StreamCursor;
-    use crate::chainstate::stacks::miner::*;
-    use crate::chainstate::stacks::test::*;
-    use crate::chainstate::stacks::Error as chain_error;
-    use crate::chainstate::stacks::*;
-    use crate::net::codec::*;
-    use crate::net::http::*;
-    use crate::net::test::*;
-    use crate::net::*;
-    use clarity::vm::types::*;
-    use stacks_common::address::*;
-    use stacks_common::util::get_epoch_time_secs;
-    use stacks_common::util::hash::hex_bytes;
-    use stacks_common::util::pipe::*;
+                convo_client.new_getmicroblocks_confirmed(child_index_block_hash)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match (*http_response).clone() {
+                    HttpResponseType::Microblocks(_, mut microblocks) => {
+                        microblocks.reverse();
+                        assert_eq!(microblocks.len(), (*server_microblocks_cell.borrow()).len());
+                        assert_eq!(microblocks, *server_microblocks_cell.borrow());
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-    use crate::chainstate::stacks::C32_ADDRESS_VERSION_TESTNET_SINGLESIG;
-    use crate::types::chainstate::BlockHeaderHash;
-    use crate::types::chainstate::BurnchainHeaderHash;
+    #[test]
+    #[ignore]
+    fn test_rpc_unconfirmed_microblocks() {
+        let server_microblocks_cell = RefCell::new(vec![]);
 
-    use crate::core::mempool::{BLOOM_COUNTER_ERROR_RATE, MAX_BLOOM_COUNTER_TXS};
+        test_rpc(
+            "test_rpc_unconfirmed_microblocks",
+            40050,
+            40051,
+            50050,
+            50051,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let privk = StacksPrivateKey::from_hex(
+                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                )
+                .unwrap();
 
-    use super::*;
+                let consensus_hash = ConsensusHash([0x02; 20]);
+                let anchored_block_hash = BlockHeaderHash([0x03; 32]);
+                let index_block_hash =
+                    StacksBlockHeader::make_index_block_hash(&consensus_hash, &anchored_block_hash);
 
-    const TEST_CONTRACT: &'static str = "
-        (define-data-var bar int 0)
-        (define-map unit-map { account: principal } { units: int })
-        (define-public (get-bar) (ok (var-get bar)))
-        (define-public (set-bar (x int) (y int))
-          (begin (var-set bar (/ x y)) (ok (var-get bar))))
-        (define-public (add-unit)
-          (begin
-            (map-set unit-map { account: tx-sender } { units: 1 } )
-            (var-set bar 1)
-            (ok 1)))
-        (begin
-          (map-set unit-map { account: 'ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R } { units: 123 }))";
+                let mut mblocks = make_sample_microblock_stream(&privk, &anchored_block_hash);
+                mblocks.truncate(15);
 
-    const TEST_CONTRACT_UNCONFIRMED: &'static str = "(define-read-only (ro-test) (ok 1))";
+                for mblock in mblocks.iter() {
+                    store_staging_microblock(
+                        peer_server.chainstate(),
+                        &consensus_hash,
+                        &anchored_block_hash,
+                        &mblock,
+                    );
+                }
 
-    fn convo_send_recv(
-        sender: &mut ConversationHttp,
-        sender_mempool: &MemPoolDB,
-        sender_chainstate: &mut StacksChainState,
-        receiver: &mut ConversationHttp,
-        receiver_mempool: &MemPoolDB,
-        receiver_chainstate: &mut StacksChainState,
-    ) -> () {
-        let (mut pipe_read, mut pipe_write) = Pipe::new();
-        pipe_read.set_nonblocking(true);
+                *server_microblocks_cell.borrow_mut() = mblocks;
 
-        loop {
-            let res = true;
+                // start at seq 5
+                convo_client.new_getmicroblocks_unconfirmed(index_block_hash, 5)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::Microblocks(response_md, microblocks) => {
+                        assert_eq!(microblocks.len(), 10);
+                        assert_eq!(
+                            *microblocks,
+                            (*server_microblocks_cell.borrow())[5..].to_vec()
+                        );
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-            sender.try_flush(sender_mempool, sender_chainstate).unwrap();
-            receiver
-                .try_flush(sender_mempool, receiver_chainstate)
+    #[test]
+    #[ignore]
+    fn test_rpc_unconfirmed_transaction() {
+        let last_txid = RefCell::new(Txid([0u8; 32]));
+        let last_mblock = RefCell::new(BlockHeaderHash([0u8; 32]));
+
+        test_rpc(
+            "test_rpc_unconfirmed_transaction",
+            40052,
+            40053,
+            50052,
+            50053,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let privk = StacksPrivateKey::from_hex(
+                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                )
                 .unwrap();
 
-            pipe_write.try_flush().unwrap();
+                let sortdb = peer_server.sortdb.take().unwrap();
+                Relayer::setup_unconfirmed_state(peer_server.chainstate(), &sortdb).unwrap();
+                peer_server.sortdb = Some(sortdb);
 
-            let all_relays_flushed =
-                receiver.num_pending_outbound() == 0 && sender.num_pending_outbound() == 0;
+                assert!(peer_server.chainstate().unconfirmed_state.is_some());
+                let (txid, mblock_hash) = match peer_server.chainstate().unconfirmed_state {
+                    Some(ref unconfirmed) => {
+                        assert!(unconfirmed.mined_txs.len() > 0);
+                        let mut txid = Txid([0u8; 32]);
+                        let mut mblock_hash = BlockHeaderHash([0u8; 32]);
+                        for (next_txid, (_, mbh, ..)) in unconfirmed.mined_txs.iter() {
+                            txid = next_txid.clone();
+                            mblock_hash = mbh.clone();
+                            break;
+                        }
+                        (txid, mblock_hash)
+                    }
+                    None => {
+                        panic!("No unconfirmed state");
+                    }
+                };
 
-            let nw = sender
-                .send(&mut pipe_write, sender_mempool, sender_chainstate)
-                .unwrap();
-            let nr = receiver.recv(&mut pipe_read).unwrap();
+                *last_txid.borrow_mut() = txid.clone();
+                *last_mblock.borrow_mut() = mblock_hash.clone();
 
-            test_debug!(
-                "res = {}, all_relays_flushed = {} ({},{}), nr = {}, nw = {}",
-                res,
-                all_relays_flushed,
-                receiver.num_pending_outbound(),
-                sender.num_pending_outbound(),
-                nr,
-                nw
-            );
-            if res && all_relays_flushed && nr == 0 && nw == 0 {
-                test_debug!("Breaking send_recv");
-                break;
-            }
-        }
+                convo_client.new_gettransaction_unconfirmed(txid)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::UnconfirmedTransaction(response_md, unconfirmed_resp) => {
+                        assert_eq!(
+                            unconfirmed_resp.status,
+                            UnconfirmedTransactionStatus::Microblock {
+                                block_hash: (*last_mblock.borrow()).clone(),
+                                seq: 0
+                            }
+                        );
+                        let tx = StacksTransaction::consensus_deserialize(
+                            &mut &hex_bytes(&unconfirmed_resp.tx).unwrap()[..],
+                        )
+                        .unwrap();
+                        assert_eq!(tx.txid(), *last_txid.borrow());
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn test_rpc_missing_getblock() {
+        test_rpc(
+            "test_rpc_missing_getblock",
+            40060,
+            40061,
+            50060,
+            50061,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let peer_server_block_hash = BlockHeaderHash([0x04; 32]);
+                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
+                let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &peer_server_consensus_hash,
+                    &peer_server_block_hash,
+                );
+
+                // now ask for it
+                convo_client.new_getblock(index_block_hash)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::NotFound(response_md, msg) => true,
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
     }
 
-    /// General testing function to test RPC calls.
-    /// This function sets up two peers, a client and a server.
-    /// It takes in a function of type F that generates the request to be sent to the server
-    /// It takes in another function of type C that verifies that the result from
-    /// the server is as expected.
-    /// The parameter `include_microblocks` determines whether a microblock stream is mined or not.
-    fn test_rpc<F, C>(
-        test_name: &str,
-        peer_1_p2p: u16,
-        peer_1_http: u16,
-        peer_2_p2p: u16,
-        peer_2_http: u16,
-        include_microblocks: bool,
-        make_request: F,
-        check_result: C,
-    ) -> ()
-    where
-        F: FnOnce(
-            &mut TestPeer,
-            &mut ConversationHttp,
-            &mut TestPeer,
-            &mut ConversationHttp,
-        ) -> HttpRequestType,
-        C: FnOnce(
-            &HttpRequestType,
-            &HttpResponseType,
-            &mut TestPeer,
-            &mut TestPeer,
-            &ConversationHttp,
-            &ConversationHttp,
-        ) -> bool,
-    {
-        let mut peer_1_config = TestPeerConfig::new(test_name, peer_1_p2p, peer_1_http);
-        let mut peer_2_config = TestPeerConfig::new(test_name, peer_2_p2p, peer_2_http);
+    #[test]
+    #[ignore]
+    fn test_rpc_missing_index_getmicroblocks() {
+        test_rpc(
+            "test_rpc_missing_index_getmicroblocks",
+            40070,
+            40071,
+            50070,
+            50071,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let peer_server_block_hash = BlockHeaderHash([0x04; 32]);
+                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
+                let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &peer_server_consensus_hash,
+                    &peer_server_block_hash,
+                );
 
-        // ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R
-        let privk1 = StacksPrivateKey::from_hex(
-            "9f1f85a512a96a244e4c0d762788500687feb97481639572e3bffbd6860e6ab001",
-        )
-        .unwrap();
+                // now ask for it
+                convo_client.new_getmicroblocks_indexed(index_block_hash)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::NotFound(response_md, msg) => true,
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-        // STVN97YYA10MY5F6KQJHKNYJNM24C4A1AT39WRW
-        let privk2 = StacksPrivateKey::from_hex(
-            "94c319327cc5cd04da7147d32d836eb2e4c44f4db39aa5ede7314a761183d0c701",
-        )
-        .unwrap();
-        let microblock_privkey = StacksPrivateKey::new();
-        let microblock_pubkeyhash =
-            Hash160::from_node_public_key(&StacksPublicKey::from_private(&microblock_privkey));
+    #[test]
+    #[ignore]
+    fn test_rpc_missing_confirmed_getmicroblocks() {
+        test_rpc(
+            "test_rpc_missing_confirmed_getmicroblocks",
+            40072,
+            40073,
+            50072,
+            50073,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let peer_server_block_hash = BlockHeaderHash([0x04; 32]);
+                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
+                let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &peer_server_consensus_hash,
+                    &peer_server_block_hash,
+                );
 
-        let addr1 = StacksAddress::from_public_keys(
-            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
-            &AddressHashMode::SerializeP2PKH,
-            1,
-            &vec![StacksPublicKey::from_private(&privk1)],
-        )
-        .unwrap();
-        let addr2 = StacksAddress::from_public_keys(
-            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
-            &AddressHashMode::SerializeP2PKH,
-            1,
-            &vec![StacksPublicKey::from_private(&privk2)],
-        )
-        .unwrap();
+                // now ask for it
+                convo_client.new_getmicroblocks_confirmed(index_block_hash)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::NotFound(response_md, msg) => true,
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-        peer_1_config.initial_balances = vec![
-            (addr1.to_account_principal(), 1000000000),
-            (addr2.to_account_principal(), 1000000000),
-        ];
+    #[test]
+    #[ignore]
+    fn test_rpc_missing_unconfirmed_microblocks() {
+        let server_microblocks_cell = RefCell::new(vec![]);
 
-        peer_2_config.initial_balances = vec![
-            (addr1.to_account_principal(), 1000000000),
-            (addr2.to_account_principal(), 1000000000),
-        ];
+        test_rpc(
+            "test_rpc_missing_unconfirmed_microblocks",
+            40080,
+            40081,
+            50080,
+            50081,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let privk = StacksPrivateKey::from_hex(
+                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                )
+                .unwrap();
 
-        peer_1_config.add_neighbor(&peer_2_config.to_neighbor());
-        peer_2_config.add_neighbor(&peer_1_config.to_neighbor());
+                let consensus_hash = ConsensusHash([0x02; 20]);
+                let anchored_block_hash = BlockHeaderHash([0x03; 32]);
+                let index_block_hash =
+                    StacksBlockHeader::make_index_block_hash(&consensus_hash, &anchored_block_hash);
 
-        let mut peer_1 = TestPeer::new(peer_1_config);
-        let mut peer_2 = TestPeer::new(peer_2_config);
+                let mut mblocks = make_sample_microblock_stream(&privk, &anchored_block_hash);
+                mblocks.truncate(15);
 
-        // mine one block with a contract in it
-        // first the coinbase
-        // make a coinbase for this miner
-        let mut tx_coinbase = StacksTransaction::new(
-            TransactionVersion::Testnet,
-            TransactionAuth::from_p2pkh(&privk1).unwrap(),
-            TransactionPayload::Coinbase(CoinbasePayload([0x00; 32])),
-        );
-        tx_coinbase.chain_id = 0x80000000;
-        tx_coinbase.anchor_mode = TransactionAnchorMode::OnChainOnly;
-        tx_coinbase.auth.set_origin_nonce(0);
+                for mblock in mblocks.iter() {
+                    store_staging_microblock(
+                        peer_server.chainstate(),
+                        &consensus_hash,
+                        &anchored_block_hash,
+                        &mblock,
+                    );
+                }
 
-        let mut tx_signer = StacksTransactionSigner::new(&tx_coinbase);
-        tx_signer.sign_origin(&privk1).unwrap();
-        let tx_coinbase_signed = tx_signer.get_tx().unwrap();
+                *server_microblocks_cell.borrow_mut() = mblocks;
 
-        // next the contract
-        let contract = TEST_CONTRACT.clone();
-        let mut tx_contract = StacksTransaction::new(
-            TransactionVersion::Testnet,
-            TransactionAuth::from_p2pkh(&privk1).unwrap(),
-            TransactionPayload::new_smart_contract(&format!("hello-world"), &contract.to_string())
-                .unwrap(),
+                // start at seq 16 (which doesn't exist)
+                convo_client.new_getmicroblocks_unconfirmed(index_block_hash, 16)
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::NotFound(response_md, msg) => true,
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
         );
+    }
 
-        tx_contract.chain_id = 0x80000000;
-        tx_contract.auth.set_origin_nonce(1);
-        tx_contract.set_tx_fee(0);
-
-        let mut tx_signer = StacksTransactionSigner::new(&tx_contract);
-        tx_signer.sign_origin(&privk1).unwrap();
-        let tx_contract_signed = tx_signer.get_tx().unwrap();
+    #[test]
+    #[ignore]
+    fn test_rpc_get_contract_src() {
+        // Test v2/contracts/source (aka GetContractSrc) endpoint.
+        // In this test, we don't set any tip parameters, and allow the endpoint to execute against
+        // the canonical Stacks tip.
+        // The contract source we are querying for exists in the anchored state, so we expect the
+        // query to succeed.
+        test_rpc(
+            "test_rpc_get_contract_src",
+            40090,
+            40091,
+            50090,
+            50091,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                convo_client.new_getcontractsrc(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::GetContractSrc(response_md, data) => {
+                        assert_eq!(data.source, TEST_CONTRACT);
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-        // update account and state in a microblock that will be unconfirmed
-        let mut tx_cc = StacksTransaction::new(
-            TransactionVersion::Testnet,
-            TransactionAuth::from_p2pkh(&privk1).unwrap(),
-            TransactionPayload::new_contract_call(addr1.clone(), "hello-world", "add-unit", vec![])
-                .unwrap(),
+    #[test]
+    #[ignore]
+    fn test_rpc_get_contract_src_unconfirmed_with_canonical_tip() {
+        // Test v2/contracts/source (aka GetContractSrc) endpoint.
+        // In this test, we don't set any tip parameters, and allow the endpoint to execute against
+        // the canonical Stacks tip.
+        // The contract source we are querying for only exists in the unconfirmed state, so we
+        // expect the query to fail.
+        test_rpc(
+            "test_rpc_get_contract_src_unconfirmed_with_canonical_tip",
+            40100,
+            40101,
+            50100,
+            50101,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                convo_client.new_getcontractsrc(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world-unconfirmed".try_into().unwrap(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::NotFound(_, error_str) => {
+                        assert_eq!(error_str, "No contract source data found");
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
         );
+    }
 
-        tx_cc.chain_id = 0x80000000;
-        tx_cc.auth.set_origin_nonce(2);
-        tx_cc.set_tx_fee(123);
+    #[test]
+    #[ignore]
+    fn test_rpc_get_contract_src_with_unconfirmed_tip() {
+        // Test v2/contracts/source (aka GetContractSrc) endpoint.
+        // In this test, we set `tip_req` to be the unconfirmed chain tip.
+        // The contract source we are querying for exists in the unconfirmed state, so we expect
+        // the query to succeed.
+        test_rpc(
+            "test_rpc_get_contract_src_with_unconfirmed_tip",
+            40102,
+            40103,
+            50102,
+            50103,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let unconfirmed_tip = peer_client
+                    .chainstate()
+                    .unconfirmed_state
+                    .as_ref()
+                    .unwrap()
+                    .unconfirmed_chain_tip
+                    .clone();
+                convo_client.new_getcontractsrc(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world-unconfirmed".try_into().unwrap(),
+                    TipRequest::SpecificTip(unconfirmed_tip),
+                    false,
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::GetContractSrc(response_md, data) => {
+                        assert_eq!(data.source, TEST_CONTRACT_UNCONFIRMED);
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-        let mut tx_signer = StacksTransactionSigner::new(&tx_cc);
-        tx_signer.sign_origin(&privk1).unwrap();
-        let tx_cc_signed = tx_signer.get_tx().unwrap();
-        let tx_cc_len = {
-            let mut bytes = vec![];
-            tx_cc_signed.consensus_serialize(&mut bytes).unwrap();
-            bytes.len() as u64
-        };
+    #[test]
+    #[ignore]
+    fn test_rpc_get_contract_src_use_latest_tip() {
+        // Test v2/contracts/source (aka GetContractSrc) endpoint.
+        // In this test, we set `tip_req` to UseLatestUnconfirmedTip.
+        // The contract source we are querying for exists in the unconfirmed state, so we expect
+        // the query to succeed.
+        test_rpc(
+            "test_rpc_get_contract_src_use_latest_tip",
+            40104,
+            40105,
+            50104,
+            50105,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                convo_client.new_getcontractsrc(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world-unconfirmed".try_into().unwrap(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::GetContractSrc(response_md, data) => {
+                        assert_eq!(data.source, TEST_CONTRACT_UNCONFIRMED);
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-        // make an unconfirmed contract
-        let unconfirmed_contract = TEST_CONTRACT_UNCONFIRMED.clone();
-        let mut tx_unconfirmed_contract = StacksTransaction::new(
-            TransactionVersion::Testnet,
-            TransactionAuth::from_p2pkh(&privk1).unwrap(),
-            TransactionPayload::new_smart_contract(
-                &format!("hello-world-unconfirmed"),
-                &unconfirmed_contract.to_string(),
-            )
-            .unwrap(),
+    #[test]
+    #[ignore]
+    fn test_rpc_get_account() {
+        test_rpc(
+            "test_rpc_get_account",
+            40110,
+            40111,
+            50110,
+            50111,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                convo_client.new_getaccount(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::GetAccount(response_md, data) => {
+                        assert_eq!(data.nonce, 2);
+                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
+                        assert_eq!(balance, 1000000000);
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
         );
+    }
 
-        tx_unconfirmed_contract.chain_id = 0x80000000;
-        tx_unconfirmed_contract.auth.set_origin_nonce(3);
-        tx_unconfirmed_contract.set_tx_fee(0);
-
-        let mut tx_signer = StacksTransactionSigner::new(&tx_unconfirmed_contract);
-        tx_signer.sign_origin(&privk1).unwrap();
-        let tx_unconfirmed_contract_signed = tx_signer.get_tx().unwrap();
-        let tx_unconfirmed_contract_len = {
-            let mut bytes = vec![];
-            tx_unconfirmed_contract_signed
-                .consensus_serialize(&mut bytes)
-                .unwrap();
-            bytes.len() as u64
-        };
-
-        let tip =
-            SortitionDB::get_canonical_burn_chain_tip(&peer_1.sortdb.as_ref().unwrap().conn())
-                .unwrap();
-        let mut anchor_cost = ExecutionCost::zero();
-        let mut anchor_size = 0;
-
-        // make a block and a microblock.
-        // Put the coinbase and smart-contract in the anchored block.
-        // Put the contract-call in the microblock
-        let (burn_ops, stacks_block, microblocks) = peer_1.make_tenure(
-            |ref mut miner, ref mut sortdb, ref mut chainstate, vrf_proof, ref parent_opt, _| {
-                let parent_tip = match parent_opt {
-                    None => StacksChainState::get_genesis_header_info(chainstate.db()).unwrap(),
-                    Some(block) => {
-                        let ic = sortdb.index_conn();
-                        let snapshot = SortitionDB::get_block_snapshot_for_winning_stacks_block(
-                            &ic,
-                            &tip.sortition_id,
-                            &block.block_hash(),
-                        )
-                        .unwrap()
-                        .unwrap(); // succeeds because we don't fork
-                        StacksChainState::get_anchored_block_header_info(
-                            chainstate.db(),
-                            &snapshot.consensus_hash,
-                            &snapshot.winning_stacks_block_hash,
-                        )
-                        .unwrap()
+    /// In this test, the query parameter `tip_req` is set to UseLatestUnconfirmedTip, and so we expect the
+    /// tip used for the query to be the latest microblock.
+    /// We check that the account state matches the state in the most recent microblock.
+    #[test]
+    #[ignore]
+    fn test_rpc_get_account_use_latest_tip() {
+        test_rpc(
+            "test_rpc_get_account_use_latest_tip",
+            40112,
+            40113,
+            50112,
+            50113,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                convo_client.new_getaccount(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
                         .unwrap()
+                        .to_account_principal(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::GetAccount(response_md, data) => {
+                        assert_eq!(data.nonce, 4);
+                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
+                        assert_eq!(balance, 999999877);
+                        true
                     }
-                };
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-                let block_builder = StacksBlockBuilder::make_regtest_block_builder(
-                    &parent_tip,
-                    vrf_proof,
-                    tip.total_burn,
-                    microblock_pubkeyhash,
+    /// In this test, the query parameter `tip_req` is set to UseLatestUnconfirmedTip, but we did not generate
+    /// microblocks in the rpc test. Thus, we expect the tip used for the query to be the previous
+    /// anchor block (which is the latest tip).
+    /// We check that the account state matches the state in the previous anchor block.
+    #[test]
+    #[ignore]
+    fn test_rpc_get_account_use_latest_tip_no_microblocks() {
+        test_rpc(
+            "test_rpc_get_account",
+            40114,
+            40115,
+            50114,
+            50115,
+            false,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                convo_client.new_getaccount(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
                 )
-                .unwrap();
-                let (anchored_block, anchored_block_size, anchored_block_cost) =
-                    StacksBlockBuilder::make_anchored_block_from_txs(
-                        block_builder,
-                        chainstate,
-                        &sortdb.index_conn(),
-                        vec![tx_coinbase_signed.clone(), tx_contract_signed.clone()],
-                    )
-                    .unwrap();
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::GetAccount(response_md, data) => {
+                        assert_eq!(data.nonce, 2);
+                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
+                        assert_eq!(balance, 1000000000);
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-                anchor_size = anchored_block_size;
-                anchor_cost = anchored_block_cost;
+    #[test]
+    #[ignore]
+    fn test_rpc_get_account_unconfirmed() {
+        test_rpc(
+            "test_rpc_get_account_unconfirmed",
+            40120,
+            40121,
+            50120,
+            50121,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let unconfirmed_tip = peer_client
+                    .chainstate()
+                    .unconfirmed_state
+                    .as_ref()
+                    .unwrap()
+                    .unconfirmed_chain_tip
+                    .clone();
+                convo_client.new_getaccount(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal(),
+                    TipRequest::SpecificTip(unconfirmed_tip),
+                    false,
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::GetAccount(response_md, data) => {
+                        assert_eq!(data.nonce, 4);
+                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
+                        assert_eq!(balance, 1000000000 - 123);
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-                (anchored_block, vec![])
+    #[test]
+    #[ignore]
+    fn test_rpc_get_data_var() {
+        test_rpc(
+            "test_rpc_get_data_var",
+            40122,
+            40123,
+            50122,
+            50123,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                convo_client.new_getdatavar(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    "bar".try_into().unwrap(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::GetDataVar(response_md, data) => {
+                        assert_eq!(
+                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
+                            Value::Int(0)
+                        );
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
             },
         );
+    }
 
-        let (_, _, consensus_hash) = peer_1.next_burnchain_block(burn_ops.clone());
-        peer_2.next_burnchain_block(burn_ops.clone());
-
-        peer_1.process_stacks_epoch_at_tip(&stacks_block, &vec![]);
-        peer_2.process_stacks_epoch_at_tip(&stacks_block, &vec![]);
-
-        // begin microblock section
-        if include_microblocks {
-            // build 1-block microblock stream with the contract-call and the unconfirmed contract
-            let microblock = {
-                let sortdb = peer_1.sortdb.take().unwrap();
-                Relayer::setup_unconfirmed_state(peer_1.chainstate(), &sortdb).unwrap();
-                let mblock = {
-                    let sort_iconn = sortdb.index_conn();
-                    let mut microblock_builder = StacksMicroblockBuilder::new(
-                        stacks_block.block_hash(),
-                        consensus_hash.clone(),
-                        peer_1.chainstate(),
-                        &sort_iconn,
-                        BlockBuilderSettings::max_value(),
-                    )
-                    .unwrap();
-                    let microblock = microblock_builder
-                        .mine_next_microblock_from_txs(
-                            vec![
-                                (tx_cc_signed, tx_cc_len),
-                                (tx_unconfirmed_contract_signed, tx_unconfirmed_contract_len),
-                            ],
-                            &microblock_privkey,
-                        )
-                        .unwrap();
-                    microblock
-                };
-                peer_1.sortdb = Some(sortdb);
-                mblock
-            };
-
-            // store microblock stream
-            peer_1
-                .chainstate()
-                .preprocess_streamed_microblock(
-                    &consensus_hash,
-                    &stacks_block.block_hash(),
-                    &microblock,
-                )
-                .unwrap();
-            peer_2
-                .chainstate()
-                .preprocess_streamed_microblock(
-                    &consensus_hash,
-                    &stacks_block.block_hash(),
-                    &microblock,
+    #[test]
+    #[ignore]
+    fn test_rpc_get_data_var_unconfirmed() {
+        test_rpc(
+            "test_rpc_get_data_var_unconfirmed",
+            40124,
+            40125,
+            50124,
+            50125,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let unconfirmed_tip = peer_client
+                    .chainstate()
+                    .unconfirmed_state
+                    .as_ref()
+                    .unwrap()
+                    .unconfirmed_chain_tip
+                    .clone();
+                convo_client.new_getdatavar(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    "bar".try_into().unwrap(),
+                    TipRequest::SpecificTip(unconfirmed_tip),
+                    false,
                 )
-                .unwrap();
-
-            // process microblock stream to generate unconfirmed state
-            let canonical_tip = StacksBlockHeader::make_index_block_hash(
-                &consensus_hash,
-                &stacks_block.block_hash(),
-            );
-            let sortdb1 = peer_1.sortdb.take().unwrap();
-            let sortdb2 = peer_2.sortdb.take().unwrap();
-            peer_1
-                .chainstate()
-                .reload_unconfirmed_state(&sortdb1.index_conn(), canonical_tip.clone())
-                .unwrap();
-            peer_2
-                .chainstate()
-                .reload_unconfirmed_state(&sortdb2.index_conn(), canonical_tip.clone())
-                .unwrap();
-            peer_1.sortdb = Some(sortdb1);
-            peer_2.sortdb = Some(sortdb2);
-        }
-        // end microblock section
-
-        // stuff some transactions into peer_2's mempool
-        // (relates to mempool query tests)
-        let mut mempool = peer_2.mempool.take().unwrap();
-        let mut mempool_tx = mempool.tx_begin().unwrap();
-        for i in 0..10 {
-            let pk = StacksPrivateKey::new();
-            let addr = StacksAddress::from_public_keys(
-                C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
-                &AddressHashMode::SerializeP2PKH,
-                1,
-                &vec![StacksPublicKey::from_private(&StacksPrivateKey::new())],
-            )
-            .unwrap();
-            let mut tx = StacksTransaction {
-                version: TransactionVersion::Testnet,
-                chain_id: 0x80000000,
-                auth: TransactionAuth::from_p2pkh(&pk).unwrap(),
-                anchor_mode: TransactionAnchorMode::Any,
-                post_condition_mode: TransactionPostConditionMode::Allow,
-                post_conditions: vec![],
-                payload: TransactionPayload::TokenTransfer(
-                    addr.to_account_principal(),
-                    123,
-                    TokenTransferMemo([0u8; 34]),
-                ),
-            };
-            tx.set_tx_fee(1000);
-            tx.set_origin_nonce(0);
-
-            let txid = tx.txid();
-            let tx_bytes = tx.serialize_to_vec();
-            let origin_addr = tx.origin_address();
-            let origin_nonce = tx.get_origin_nonce();
-            let sponsor_addr = tx.sponsor_address().unwrap_or(origin_addr.clone());
-            let sponsor_nonce = tx.get_sponsor_nonce().unwrap_or(origin_nonce);
-            let tx_fee = tx.get_tx_fee();
-
-            // should succeed
-            MemPoolDB::try_add_tx(
-                &mut mempool_tx,
-                peer_1.chainstate(),
-                &consensus_hash,
-                &stacks_block.block_hash(),
-                txid.clone(),
-                tx_bytes,
-                tx_fee,
-                stacks_block.header.total_work.work,
-                &origin_addr,
-                origin_nonce,
-                &sponsor_addr,
-                sponsor_nonce,
-                None,
-            )
-            .unwrap();
-        }
-        mempool_tx.commit().unwrap();
-        peer_2.mempool.replace(mempool);
-
-        let peer_1_sortdb = peer_1.sortdb.take().unwrap();
-        let peer_1_stacks_node = peer_1.stacks_node.take().unwrap();
-        let _ = peer_1
-            .network
-            .refresh_burnchain_view(&peer_1_sortdb, &peer_1_stacks_node.chainstate, false)
-            .unwrap();
-        peer_1.sortdb = Some(peer_1_sortdb);
-        peer_1.stacks_node = Some(peer_1_stacks_node);
-
-        let peer_2_sortdb = peer_2.sortdb.take().unwrap();
-        let peer_2_stacks_node = peer_2.stacks_node.take().unwrap();
-        let _ = peer_2
-            .network
-            .refresh_burnchain_view(&peer_2_sortdb, &peer_2_stacks_node.chainstate, false)
-            .unwrap();
-        peer_2.sortdb = Some(peer_2_sortdb);
-        peer_2.stacks_node = Some(peer_2_stacks_node);
-
-        let view_1 = peer_1.get_burnchain_view().unwrap();
-        let view_2 = peer_2.get_burnchain_view().unwrap();
-
-        let mut convo_1 = ConversationHttp::new(
-            format!("127.0.0.1:{}", peer_1_http)
-                .parse::<SocketAddr>()
-                .unwrap(),
-            Some(UrlString::try_from(format!("http://peer1.com")).unwrap()),
-            peer_1.to_peer_host(),
-            &peer_1.config.connection_opts,
-            0,
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::GetDataVar(response_md, data) => {
+                        assert_eq!(
+                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
+                            Value::Int(1)
+                        );
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
         );
+    }
 
-        let mut convo_2 = ConversationHttp::new(
-            format!("127.0.0.1:{}", peer_2_http)
-                .parse::<SocketAddr>()
-                .unwrap(),
-            Some(UrlString::try_from(format!("http://peer2.com")).unwrap()),
-            peer_2.to_peer_host(),
-            &peer_2.config.connection_opts,
-            1,
+    #[test]
+    #[ignore]
+    fn test_rpc_get_data_var_nonexistant() {
+        test_rpc(
+            "test_rpc_get_data_var_nonexistant",
+            40125,
+            40126,
+            50125,
+            50126,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                convo_client.new_getdatavar(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    "bar-nonexistant".try_into().unwrap(),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::NotFound(_, msg) => {
+                        assert_eq!(msg, "Data var not found");
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
         );
+    }
 
-        let req = make_request(&mut peer_1, &mut convo_1, &mut peer_2, &mut convo_2);
-
-        convo_1.send_request(req.clone()).unwrap();
-        let mut peer_1_mempool = peer_1.mempool.take().unwrap();
-        let peer_2_mempool = peer_2.mempool.take().unwrap();
-
-        test_debug!("convo1 sends to convo2");
-        convo_send_recv(
-            &mut convo_1,
-            &peer_1_mempool,
-            peer_1.chainstate(),
-            &mut convo_2,
-            &peer_2_mempool,
-            peer_2.chainstate(),
+    #[test]
+    #[ignore]
+    fn test_rpc_get_map_entry() {
+        // Test v2/map_entry (aka GetMapEntry) endpoint.
+        // In this test, we don't set any tip parameters, and we expect that querying for map data
+        // against the canonical Stacks tip will succeed.
+        test_rpc(
+            "test_rpc_get_map_entry",
+            40130,
+            40131,
+            50130,
+            50131,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let principal =
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal();
+                convo_client.new_getmapentry(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    "unit-map".try_into().unwrap(),
+                    Value::Tuple(
+                        TupleData::from_data(vec![("account".into(), Value::Principal(principal))])
+                            .unwrap(),
+                    ),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::GetMapEntry(response_md, data) => {
+                        assert_eq!(
+                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
+                            Value::some(Value::Tuple(
+                                TupleData::from_data(vec![("units".into(), Value::Int(123))])
+                                    .unwrap()
+                            ))
+                            .unwrap()
+                        );
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
         );
+    }
 
-        // hack around the borrow-checker
-        let mut peer_1_sortdb = peer_1.sortdb.take().unwrap();
-        let mut peer_1_stacks_node = peer_1.stacks_node.take().unwrap();
-
-        Relayer::setup_unconfirmed_state(&mut peer_1_stacks_node.chainstate, &peer_1_sortdb)
-            .unwrap();
-
-        convo_1
-            .chat(
-                &mut peer_1.network,
-                &mut peer_1_sortdb,
-                &mut peer_1_stacks_node.chainstate,
-                &mut peer_1_mempool,
-                &RPCHandlerArgs::default(),
-            )
-            .unwrap();
-
-        peer_1.sortdb = Some(peer_1_sortdb);
-        peer_1.stacks_node = Some(peer_1_stacks_node);
-        peer_1.mempool = Some(peer_1_mempool);
-        peer_2.mempool = Some(peer_2_mempool);
-
-        test_debug!("convo2 sends to convo1");
-
-        // hack around the borrow-checker
-        let mut peer_2_sortdb = peer_2.sortdb.take().unwrap();
-        let mut peer_2_stacks_node = peer_2.stacks_node.take().unwrap();
-        let mut peer_2_mempool = peer_2.mempool.take().unwrap();
-
-        let _ = peer_2
-            .network
-            .refresh_burnchain_view(&peer_2_sortdb, &peer_2_stacks_node.chainstate, false)
-            .unwrap();
-
-        Relayer::setup_unconfirmed_state(&mut peer_2_stacks_node.chainstate, &peer_2_sortdb)
-            .unwrap();
-
-        convo_2
-            .chat(
-                &mut peer_2.network,
-                &mut peer_2_sortdb,
-                &mut peer_2_stacks_node.chainstate,
-                &mut peer_2_mempool,
-                &RPCHandlerArgs::default(),
-            )
-            .unwrap();
-
-        peer_2.sortdb = Some(peer_2_sortdb);
-        peer_2.stacks_node = Some(peer_2_stacks_node);
-        let mut peer_1_mempool = peer_1.mempool.take().unwrap();
+    #[test]
+    fn test_rpc_get_subnet_fee_rate_estimate_cached() {
+        // The estimate is computed once per chain tip: a cached estimate for the canonical tip is
+        // served as is, without reloading recent blocks.
+        let cached = SubnetFeeRateEstimate {
+            low: 11.0,
+            middle: 22.0,
+            high: 33.0,
+            num_blocks: 4,
+            num_transactions: 5,
+            average_fullness: 0.5,
+        };
+        test_rpc(
+            "test_rpc_get_subnet_fee_rate_estimate_cached",
+            40857,
+            40858,
+            50857,
+            50858,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                let (consensus_hash, block_hash) =
+                    SortitionDB::get_canonical_stacks_chain_tip_hash(
+                        peer_server.sortdb.as_ref().unwrap().conn(),
+                    )
+                    .unwrap();
+                let tip = StacksBlockHeader::make_index_block_hash(&consensus_hash, &block_hash);
+                peer_server.network.subnet_fee_estimate = Some((tip, cached.clone()));
 
-        convo_send_recv(
-            &mut convo_2,
-            &peer_2_mempool,
-            peer_2.chainstate(),
-            &mut convo_1,
-            &peer_1_mempool,
-            peer_1.chainstate(),
+                HttpRequestType::GetSubnetFeeRateEstimate(HttpRequestMetadata::from_host(
+                    convo_client.peer_host.clone(),
+                    None,
+                ))
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| match http_response {
+                HttpResponseType::SubnetFeeRateEstimate(_response_md, estimate) => {
+                    assert_eq!(estimate, &cached);
+                    true
+                }
+                _ => {
+                    error!("Invalid response: {:?}", &http_response);
+                    false
+                }
+            },
         );
 
-        test_debug!("flush convo1");
+        // A cached estimate for another tip is replaced by one computed at the canonical tip.
+        test_rpc(
+            "test_rpc_get_subnet_fee_rate_estimate_stale",
+            40859,
+            40860,
+            50859,
+            50860,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                peer_server.network.subnet_fee_estimate =
+                    Some((StacksBlockId([0x11; 32]), cached.clone()));
 
-        // hack around the borrow-checker
-        convo_send_recv(
-            &mut convo_1,
-            &peer_1_mempool,
-            peer_1.chainstate(),
-            &mut convo_2,
-            &peer_2_mempool,
-            peer_2.chainstate(),
+                HttpRequestType::GetSubnetFeeRateEstimate(HttpRequestMetadata::from_host(
+                    convo_client.peer_host.clone(),
+                    None,
+                ))
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let (consensus_hash, block_hash) =
+                    SortitionDB::get_canonical_stacks_chain_tip_hash(
+                        peer_server.sortdb.as_ref().unwrap().conn(),
+                    )
+                    .unwrap();
+                let tip = StacksBlockHeader::make_index_block_hash(&consensus_hash, &block_hash);
+                match http_response {
+                    HttpResponseType::SubnetFeeRateEstimate(_response_md, estimate) => {
+                        assert_ne!(estimate, &cached);
+                        assert_eq!(
+                            peer_server.network.subnet_fee_estimate,
+                            Some((tip, estimate.clone()))
+                        );
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response: {:?}", &http_response);
+                        false
+                    }
+                }
+            },
         );
+    }
 
-        peer_2.mempool = Some(peer_2_mempool);
-
-        let mut peer_1_sortdb = peer_1.sortdb.take().unwrap();
-        let mut peer_1_stacks_node = peer_1.stacks_node.take().unwrap();
-
-        let _ = peer_1
-            .network
-            .refresh_burnchain_view(&peer_1_sortdb, &peer_1_stacks_node.chainstate, false)
-            .unwrap();
-
-        Relayer::setup_unconfirmed_state(&mut peer_1_stacks_node.chainstate, &peer_1_sortdb)
-            .unwrap();
-
-        convo_1
-            .chat(
-                &mut peer_1.network,
-                &mut peer_1_sortdb,
-                &mut peer_1_stacks_node.chainstate,
-                &mut peer_1_mempool,
-                &RPCHandlerArgs::default(),
-            )
-            .unwrap();
-
-        convo_1
-            .try_flush(&peer_1_mempool, &mut peer_1_stacks_node.chainstate)
-            .unwrap();
-
-        peer_1.sortdb = Some(peer_1_sortdb);
-        peer_1.stacks_node = Some(peer_1_stacks_node);
-        peer_1.mempool = Some(peer_1_mempool);
-
-        // should have gotten a reply
-        let resp_opt = convo_1.try_get_response();
-        assert!(resp_opt.is_some());
-
-        let resp = resp_opt.unwrap();
-        assert!(check_result(
-            &req,
-            &resp,
-            &mut peer_1,
-            &mut peer_2,
-            &convo_1,
-            &convo_2
-        ));
+    #[test]
+    fn test_rpc_get_map_entries() {
+        // Test v2/map_entries (aka GetMapEntries) endpoint.
+        // The map has a single entry at the canonical Stacks tip, set when the contract was
+        // deployed.
+        test_rpc(
+            "test_rpc_get_map_entries",
+            40855,
+            40856,
+            50855,
+            50856,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                convo_client.new_get_map_entries(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    "unit-map".try_into().unwrap(),
+                    None,
+                    10,
+                    TipRequest::UseLatestAnchoredTip,
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let principal =
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal();
+                match http_response {
+                    HttpResponseType::MapEntries(_response_md, data) => {
+                        assert_eq!(data.entries.len(), 1);
+                        assert_eq!(
+                            Value::try_deserialize_hex_untyped(&data.entries[0].key).unwrap(),
+                            Value::Tuple(
+                                TupleData::from_data(vec![(
+                                    "account".into(),
+                                    Value::Principal(principal)
+                                )])
+                                .unwrap()
+                            )
+                        );
+                        assert_eq!(
+                            Value::try_deserialize_hex_untyped(&data.entries[0].value).unwrap(),
+                            Value::Tuple(
+                                TupleData::from_data(vec![("units".into(), Value::Int(123))])
+                                    .unwrap()
+                            )
+                        );
+                        assert_eq!(data.next_key, None);
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
     }
 
-    /// This test tests two things:
-    /// (1) the get info RPC call
-    /// (2) whether the ConversationHttp object gets correctly updated with a peer's canonical
-    /// stacks tip height, which is sent in HTTP headers as part of the request/response
     #[test]
-    #[ignore]
-    fn test_rpc_getinfo() {
-        let peer_server_info = RefCell::new(None);
-        let client_stacks_height = 17;
+    fn test_rpc_get_clarity_data_proof() {
+        // Test v2/clarity/proof (aka GetClarityDataProof) endpoint.
+        // The returned proof must verify against the tip's state root, using only the state roots
+        // in the block headers.
         test_rpc(
-            "test_rpc_getinfo",
-            40000,
-            40001,
-            50000,
-            50001,
+            "test_rpc_get_clarity_data_proof",
+            40134,
+            40135,
+            50134,
+            50135,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let peer_info = RPCPeerInfoData::from_network(
-                    &peer_server.network,
-                    &peer_server.stacks_node.as_ref().unwrap().chainstate,
-                    &None,
-                    &Sha256Sum::zero(),
+                let principal =
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal();
+                let key = Value::Tuple(
+                    TupleData::from_data(vec![("account".into(), Value::Principal(principal))])
+                        .unwrap(),
                 );
-
-                *peer_server_info.borrow_mut() = Some(peer_info);
-
-                convo_client.new_getinfo(Some(client_stacks_height))
+                convo_client.new_getclaritydataproof(
+                    ClarityDataKey::MapEntry {
+                        contract: "ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R.hello-world".into(),
+                        name: "unit-map".into(),
+                        key: ClaritySerializable::serialize(&key),
+                    },
+                    TipRequest::UseLatestAnchoredTip,
+                )
             },
             |ref http_request,
              ref http_response,
@@ -4116,32 +9175,46 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                assert_eq!(convo_client.canonical_stacks_tip_height, Some(1));
-                assert_eq!(
-                    convo_server.canonical_stacks_tip_height,
-                    Some(client_stacks_height)
-                );
                 match http_response {
-                    HttpResponseType::PeerInfo(response_md, peer_data) => {
-                        assert_eq!(Some((*peer_data).clone()), *peer_server_info.borrow());
-                        assert!(peer_data.node_public_key.is_some());
-                        assert!(peer_data.node_public_key_hash.is_some());
+                    HttpResponseType::ClarityDataProof(_response_md, proof) => {
                         assert_eq!(
-                            peer_data.node_public_key_hash,
-                            Some(Hash160::from_node_public_key(
-                                &peer_data
-                                    .node_public_key
-                                    .clone()
-                                    .unwrap()
-                                    .to_public_key()
+                            Value::try_deserialize_hex_untyped(&proof.data).unwrap(),
+                            Value::some(Value::Tuple(
+                                TupleData::from_data(vec![("units".into(), Value::Int(123))])
                                     .unwrap()
                             ))
+                            .unwrap()
+                        );
+
+                        let mut root_to_block = HashMap::new();
+                        let mut stmt = peer_server
+                            .chainstate()
+                            .db()
+                            .prepare("SELECT index_root, index_block_hash FROM block_headers")
+                            .unwrap();
+                        let mut rows = stmt.query(NO_PARAMS).unwrap();
+                        while let Some(row) = rows.next().unwrap() {
+                            let index_root = TrieHash::from_column(row, "index_root").unwrap();
+                            let index_block_hash =
+                                StacksBlockId::from_column(row, "index_block_hash").unwrap();
+                            root_to_block.insert(index_root, index_block_hash);
+                        }
+                        assert!(proof.verify(&root_to_block));
+
+                        // a proof for some other value must not verify
+                        let mut bad_proof = proof.clone();
+                        bad_proof.data = ClaritySerializable::serialize(
+                            &Value::some(Value::Tuple(
+                                TupleData::from_data(vec![("units".into(), Value::Int(124))])
+                                    .unwrap(),
+                            ))
+                            .unwrap(),
                         );
+                        assert!(!bad_proof.verify(&root_to_block));
                         true
                     }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4151,18 +9224,45 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_getneighbors() {
+    fn test_rpc_get_map_entry_unconfirmed() {
+        // Test v2/map_entry (aka GetMapEntry) endpoint.
+        // In this test, we set `tip_req` to UseLatestUnconfirmedTip, and we expect that querying for map data
+        // against the unconfirmed state will succeed.
         test_rpc(
-            "test_rpc_getneighbors",
-            40010,
-            40011,
-            50010,
-            50011,
+            "test_rpc_get_map_entry_unconfirmed",
+            40140,
+            40141,
+            50140,
+            50141,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
-             ref mut convo_server| { convo_client.new_getneighbors() },
+             ref mut convo_server| {
+                let unconfirmed_tip = peer_client
+                    .chainstate()
+                    .unconfirmed_state
+                    .as_ref()
+                    .unwrap()
+                    .unconfirmed_chain_tip
+                    .clone();
+                let principal =
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal();
+                convo_client.new_getmapentry(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    "unit-map".try_into().unwrap(),
+                    Value::Tuple(
+                        TupleData::from_data(vec![("account".into(), Value::Principal(principal))])
+                            .unwrap(),
+                    ),
+                    TipRequest::SpecificTip(unconfirmed_tip),
+                    false,
+                )
+            },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
@@ -4171,13 +9271,19 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::Neighbors(response_md, neighbor_info) => {
-                        assert_eq!(neighbor_info.sample.len(), 1);
-                        assert_eq!(neighbor_info.sample[0].port, peer_client.config.server_port); // we see ourselves as the neighbor
+                    HttpResponseType::GetMapEntry(response_md, data) => {
+                        assert_eq!(
+                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
+                            Value::some(Value::Tuple(
+                                TupleData::from_data(vec![("units".into(), Value::Int(1))])
+                                    .unwrap()
+                            ))
+                            .unwrap()
+                        );
                         true
                     }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4187,67 +9293,86 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_getheaders() {
-        let server_blocks_cell = RefCell::new(None);
-
+    fn test_rpc_get_map_entry_use_latest_tip() {
         test_rpc(
-            "test_rpc_getheaders",
-            40012,
-            40013,
-            50012,
-            50013,
+            "test_rpc_get_map_entry_use_latest_tip",
+            40142,
+            40143,
+            50142,
+            50143,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                // have "server" peer store a few continuous block to staging
-                let mut blocks: Vec<StacksBlock> = vec![];
-                let mut index_block_hashes = vec![];
-                for i in 0..25 {
-                    let mut peer_server_block = make_codec_test_block(25);
-
-                    peer_server_block.header.total_work.work = (i + 1) as u64;
-                    peer_server_block.header.total_work.burn = (i + 1) as u64;
-                    peer_server_block.header.parent_block = blocks
-                        .last()
-                        .map(|blk| blk.block_hash())
-                        .unwrap_or(BlockHeaderHash([0u8; 32]));
-
-                    let peer_server_consensus_hash = ConsensusHash([(i + 1) as u8; 20]);
-                    let index_block_hash = StacksBlockHeader::make_index_block_hash(
-                        &peer_server_consensus_hash,
-                        &peer_server_block.block_hash(),
-                    );
-
-                    test_debug!("Store peer server index block {:?}", &index_block_hash);
-                    store_staging_block(
-                        peer_server.chainstate(),
-                        &peer_server_consensus_hash,
-                        &peer_server_block,
-                        &ConsensusHash([i as u8; 20]),
-                        456,
-                        123,
-                    );
-                    set_block_processed(
-                        peer_server.chainstate(),
-                        &peer_server_consensus_hash,
-                        &peer_server_block.block_hash(),
-                        true,
-                    );
-
-                    index_block_hashes.push(index_block_hash);
-                    blocks.push(peer_server_block);
+                let principal =
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal();
+                convo_client.new_getmapentry(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    "unit-map".try_into().unwrap(),
+                    Value::Tuple(
+                        TupleData::from_data(vec![("account".into(), Value::Principal(principal))])
+                            .unwrap(),
+                    ),
+                    TipRequest::UseLatestAnchoredTip,
+                    false,
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                let req_md = http_request.metadata().clone();
+                match http_response {
+                    HttpResponseType::GetMapEntry(response_md, data) => {
+                        assert_eq!(
+                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
+                            Value::some(Value::Tuple(
+                                TupleData::from_data(vec![("units".into(), Value::Int(1))])
+                                    .unwrap()
+                            ))
+                            .unwrap()
+                        );
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
                 }
+            },
+        );
+    }
 
-                let rev_blocks: Vec<_> = blocks.into_iter().rev().collect();
-                let rev_ibhs: Vec<_> = index_block_hashes.into_iter().rev().collect();
-
-                let tip = rev_ibhs[0].clone();
-                *server_blocks_cell.borrow_mut() = Some((rev_blocks, rev_ibhs));
-
-                // now ask for it
-                convo_client.new_getheaders(25, TipRequest::SpecificTip(tip))
+    #[test]
+    #[ignore]
+    fn test_rpc_get_contract_abi() {
+        // Test /v2/contracts/interface (aka GetContractABI) endpoint.
+        // In this test, we don't set any tip parameters, and we expect that querying
+        // against the canonical Stacks tip will succeed.
+        test_rpc(
+            "test_rpc_get_contract_abi",
+            40150,
+            40151,
+            50150,
+            50151,
+            true,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                convo_client.new_getcontractabi(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world-unconfirmed".try_into().unwrap(),
+                    TipRequest::UseLatestAnchoredTip,
+                )
             },
             |ref http_request,
              ref http_response,
@@ -4257,20 +9382,12 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::Headers(response_md, headers) => {
-                        assert_eq!(headers.len(), 25);
-                        let expected = server_blocks_cell.borrow().clone().unwrap();
-                        for (i, h) in headers.iter().enumerate() {
-                            assert_eq!(h.header, expected.0[i].header);
-                            assert_eq!(h.consensus_hash, ConsensusHash([(25 - i) as u8; 20]));
-                            if i + 1 < headers.len() {
-                                assert_eq!(h.parent_block_id, expected.1[i + 1]);
-                            }
-                        }
+                    HttpResponseType::NotFound(..) => {
+                        // not confirmed yet
                         true
                     }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4280,42 +9397,34 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_unconfirmed_getblock() {
-        let server_block_cell = RefCell::new(None);
-
+    fn test_rpc_get_contract_abi_unconfirmed() {
+        // Test /v2/contracts/interface (aka GetContractABI) endpoint.
+        // In this test, we set `tip_req` to UseLatestUnconfirmedTip, and we expect that querying
+        // against the unconfirmed state will succeed.
         test_rpc(
-            "test_rpc_unconfirmed_getblock",
-            40020,
-            40021,
-            50020,
-            50021,
+            "test_rpc_get_contract_abi_unconfirmed",
+            40152,
+            40153,
+            50152,
+            50153,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                // have "server" peer store a block to staging
-                let peer_server_block = make_codec_test_block(25);
-                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
-                let index_block_hash = StacksBlockHeader::make_index_block_hash(
-                    &peer_server_consensus_hash,
-                    &peer_server_block.block_hash(),
-                );
-
-                test_debug!("Store peer server index block {:?}", &index_block_hash);
-                store_staging_block(
-                    peer_server.chainstate(),
-                    &peer_server_consensus_hash,
-                    &peer_server_block,
-                    &ConsensusHash([0x03; 20]),
-                    456,
-                    123,
-                );
-
-                *server_block_cell.borrow_mut() = Some(peer_server_block);
-
-                // now ask for it
-                convo_client.new_getblock(index_block_hash)
+                let unconfirmed_tip = peer_client
+                    .chainstate()
+                    .unconfirmed_state
+                    .as_ref()
+                    .unwrap()
+                    .unconfirmed_chain_tip
+                    .clone();
+                convo_client.new_getcontractabi(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world-unconfirmed".try_into().unwrap(),
+                    TipRequest::SpecificTip(unconfirmed_tip),
+                )
             },
             |ref http_request,
              ref http_response,
@@ -4325,15 +9434,9 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::Block(response_md, block_info) => {
-                        assert_eq!(
-                            block_info.block_hash(),
-                            (*server_block_cell.borrow()).as_ref().unwrap().block_hash()
-                        );
-                        true
-                    }
+                    HttpResponseType::GetContractABI(response_md, data) => true,
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4343,48 +9446,24 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_confirmed_getblock() {
-        let server_block_cell = RefCell::new(None);
-
+    fn test_rpc_get_contract_abi_use_latest_tip() {
         test_rpc(
-            "test_rpc_confirmed_getblock",
-            40030,
-            40031,
-            50030,
-            50031,
+            "test_rpc_get_contract_abi_use_latest_tip",
+            40154,
+            40155,
+            50154,
+            50155,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                // have "server" peer store a block to staging
-                let peer_server_block = make_codec_test_block(25);
-                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
-                let index_block_hash = StacksBlockHeader::make_index_block_hash(
-                    &peer_server_consensus_hash,
-                    &peer_server_block.block_hash(),
-                );
-
-                test_debug!("Store peer server index block {:?}", &index_block_hash);
-                store_staging_block(
-                    peer_server.chainstate(),
-                    &peer_server_consensus_hash,
-                    &peer_server_block,
-                    &ConsensusHash([0x03; 20]),
-                    456,
-                    123,
-                );
-                set_block_processed(
-                    peer_server.chainstate(),
-                    &peer_server_consensus_hash,
-                    &peer_server_block.block_hash(),
-                    true,
-                );
-
-                *server_block_cell.borrow_mut() = Some(peer_server_block);
-
-                // now ask for it
-                convo_client.new_getblock(index_block_hash)
+                convo_client.new_getcontractabi(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world-unconfirmed".try_into().unwrap(),
+                    TipRequest::UseLatestAnchoredTip,
+                )
             },
             |ref http_request,
              ref http_response,
@@ -4394,15 +9473,9 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::Block(response_md, block_info) => {
-                        assert_eq!(
-                            block_info.block_hash(),
-                            (*server_block_cell.borrow()).as_ref().unwrap().block_hash()
-                        );
-                        true
-                    }
+                    HttpResponseType::GetContractABI(response_md, data) => true,
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4411,99 +9484,127 @@ mod test {
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_indexed_microblocks() {
-        let server_microblocks_cell = RefCell::new(vec![]);
+    fn test_rate_limit_charge_classes() {
+        let md = || HttpRequestMetadata::new("127.0.0.1".into(), 20443, None);
+        let charge = |req: HttpRequestType| ConversationHttp::rate_limit_charge(&req);
+
+        assert_eq!(
+            charge(HttpRequestType::MemPoolQuery(
+                md(),
+                MemPoolSyncData::TxTags([0u8; 32], vec![]),
+                None,
+                None
+            )),
+            Some((RPCRateLimitClass::MempoolSync, 1))
+        );
+        assert_eq!(
+            charge(HttpRequestType::GetMemPoolTxs {
+                metadata: md(),
+                filter: MemPoolListFilter::default(),
+                cursor: None,
+                limit: 10,
+            }),
+            Some((RPCRateLimitClass::MempoolListing, 1))
+        );
+        assert_eq!(
+            charge(HttpRequestType::GetMemPoolSummary {
+                metadata: md(),
+                filter: MemPoolListFilter::default(),
+            }),
+            Some((RPCRateLimitClass::MempoolListing, 1))
+        );
+        assert_eq!(
+            charge(HttpRequestType::GetBlockStateDiff {
+                metadata: md(),
+                index_block_hash: StacksBlockId([0u8; 32]),
+            }),
+            Some((RPCRateLimitClass::StateDiff, 1))
+        );
+        assert_eq!(charge(HttpRequestType::GetInfo(md())), None);
+    }
 
+    #[test]
+    fn test_rpc_rate_limited_call_read_only() {
         test_rpc(
-            "test_rpc_indexed_microblocks",
-            40040,
-            40041,
-            50040,
-            50041,
-            true,
+            "test_rpc_rate_limited_call_read_only",
+            40843,
+            40844,
+            50843,
+            50844,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let privk = StacksPrivateKey::from_hex(
-                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
-                )
-                .unwrap();
-
-                let parent_block = make_codec_test_block(25);
-                let parent_consensus_hash = ConsensusHash([0x02; 20]);
-                let parent_index_block_hash = StacksBlockHeader::make_index_block_hash(
-                    &parent_consensus_hash,
-                    &parent_block.block_hash(),
-                );
-
-                let mut mblocks = make_sample_microblock_stream(&privk, &parent_block.block_hash());
-                mblocks.truncate(15);
-
-                let mut child_block = make_codec_test_block(25);
-                let child_consensus_hash = ConsensusHash([0x03; 20]);
-
-                child_block.header.parent_block = parent_block.block_hash();
-                child_block.header.parent_microblock =
-                    mblocks.last().as_ref().unwrap().block_hash();
-                child_block.header.parent_microblock_sequence =
-                    mblocks.last().as_ref().unwrap().header.sequence;
-
-                store_staging_block(
-                    peer_server.chainstate(),
-                    &parent_consensus_hash,
-                    &parent_block,
-                    &ConsensusHash([0x01; 20]),
-                    456,
-                    123,
-                );
-                set_block_processed(
-                    peer_server.chainstate(),
-                    &parent_consensus_hash,
-                    &parent_block.block_hash(),
-                    true,
-                );
-
-                store_staging_block(
-                    peer_server.chainstate(),
-                    &child_consensus_hash,
-                    &child_block,
-                    &parent_consensus_hash,
-                    456,
-                    123,
-                );
-                set_block_processed(
-                    peer_server.chainstate(),
-                    &child_consensus_hash,
-                    &child_block.block_hash(),
-                    true,
-                );
-
-                let index_microblock_hash = StacksBlockHeader::make_index_block_hash(
-                    &parent_consensus_hash,
-                    &mblocks.last().as_ref().unwrap().block_hash(),
-                );
-
-                for mblock in mblocks.iter() {
-                    store_staging_microblock(
-                        peer_server.chainstate(),
-                        &parent_consensus_hash,
-                        &parent_block.block_hash(),
-                        &mblock,
-                    );
-                }
-
-                set_microblocks_processed(
-                    peer_server.chainstate(),
-                    &child_consensus_hash,
-                    &child_block.block_hash(),
-                    &mblocks.last().as_ref().unwrap().block_hash(),
-                );
+                // give the client a single read-only call, and use it up
+                peer_server.network.rpc_rate_limiter = RPCRateLimiter::new(RPCRateLimits {
+                    read_only_call: Some(TokenBucketLimit { rate: 1, burst: 1 }),
+                    ..RPCRateLimits::default()
+                });
+                assert!(peer_server.network.rpc_rate_limiter.try_acquire(
+                    convo_server.get_peer_addr().ip(),
+                    RPCRateLimitClass::ReadOnlyCall,
+                    get_epoch_time_ms()
+                ));
 
-                *server_microblocks_cell.borrow_mut() = mblocks;
+                convo_client.new_callreadonlyfunction(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal(),
+                    "ro-test".try_into().unwrap(),
+                    vec![],
+                    None,
+                    TipRequest::UseLatestAnchoredTip,
+                )
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| {
+                match http_response {
+                    HttpResponseType::TooManyRequests(_, msg) => {
+                        assert!(msg.contains("read_only_call"));
+                        true
+                    }
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
+                }
+            },
+        );
+    }
 
-                convo_client.new_getmicroblocks_indexed(index_microblock_hash)
+    #[test]
+    fn test_rpc_rate_limited_post_transaction_batch() {
+        test_rpc(
+            "test_rpc_rate_limited_post_transaction_batch",
+            40869,
+            40870,
+            50869,
+            50870,
+            false,
+            |ref mut peer_client,
+             ref mut convo_client,
+             ref mut peer_server,
+             ref mut convo_server| {
+                // the client may broadcast two transactions at once, so a batch of three is
+                // rejected
+                peer_server.network.rpc_rate_limiter = RPCRateLimiter::new(RPCRateLimits {
+                    tx_broadcast: Some(TokenBucketLimit { rate: 1, burst: 2 }),
+                    ..RPCRateLimits::default()
+                });
+
+                let mempool = peer_server.mempool.as_ref().unwrap();
+                let tx = MemPoolDB::get_all_txs(mempool.conn()).unwrap()[0]
+                    .tx
+                    .clone();
+                convo_client.new_post_transaction_batch(vec![tx.clone(), tx.clone(), tx], false)
             },
             |ref http_request,
              ref http_response,
@@ -4511,16 +9612,20 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                match (*http_response).clone() {
-                    HttpResponseType::Microblocks(_, mut microblocks) => {
-                        microblocks.reverse();
-                        assert_eq!(microblocks.len(), (*server_microblocks_cell.borrow()).len());
-                        assert_eq!(microblocks, *server_microblocks_cell.borrow());
+                match http_response {
+                    HttpResponseType::TooManyRequests(_, msg) => {
+                        assert!(msg.contains("tx_broadcast"));
+                        // the rejected batch took no tokens
+                        assert!(peer_server.network.rpc_rate_limiter.try_acquire_n(
+                            convo_server.get_peer_addr().ip(),
+                            RPCRateLimitClass::TxBroadcast,
+                            2,
+                            get_epoch_time_ms()
+                        ));
                         true
                     }
                     _ => {
-                        error!("Invalid response: {:?}", http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4529,95 +9634,32 @@ mod test {
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_confirmed_microblocks() {
-        let server_microblocks_cell = RefCell::new(vec![]);
-
+    fn test_rpc_trace_read_only() {
+        // Test /v2/contracts/trace (aka TraceReadOnlyFunction) endpoint.  Only nodes built with
+        // the `eval-hooks` feature serve it.
         test_rpc(
-            "test_rpc_confirmed_microblocks",
-            40042,
-            40043,
-            50042,
-            50043,
-            true,
+            "test_rpc_trace_read_only",
+            40845,
+            40846,
+            50845,
+            50846,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let privk = StacksPrivateKey::from_hex(
-                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                convo_client.new_tracereadonlyfunction(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world".try_into().unwrap(),
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal(),
+                    "get-bar".try_into().unwrap(),
+                    vec![],
+                    None,
+                    TipRequest::UseLatestAnchoredTip,
                 )
-                .unwrap();
-
-                let parent_block = make_codec_test_block(25);
-                let parent_consensus_hash = ConsensusHash([0x02; 20]);
-
-                let mut mblocks = make_sample_microblock_stream(&privk, &parent_block.block_hash());
-                mblocks.truncate(15);
-
-                let mut child_block = make_codec_test_block(25);
-                let child_consensus_hash = ConsensusHash([0x03; 20]);
-
-                child_block.header.parent_block = parent_block.block_hash();
-                child_block.header.parent_microblock =
-                    mblocks.last().as_ref().unwrap().block_hash();
-                child_block.header.parent_microblock_sequence =
-                    mblocks.last().as_ref().unwrap().header.sequence;
-
-                let child_index_block_hash = StacksBlockHeader::make_index_block_hash(
-                    &child_consensus_hash,
-                    &child_block.block_hash(),
-                );
-
-                store_staging_block(
-                    peer_server.chainstate(),
-                    &parent_consensus_hash,
-                    &parent_block,
-                    &ConsensusHash([0x01; 20]),
-                    456,
-                    123,
-                );
-                set_block_processed(
-                    peer_server.chainstate(),
-                    &parent_consensus_hash,
-                    &parent_block.block_hash(),
-                    true,
-                );
-
-                store_staging_block(
-                    peer_server.chainstate(),
-                    &child_consensus_hash,
-                    &child_block,
-                    &parent_consensus_hash,
-                    456,
-                    123,
-                );
-                set_block_processed(
-                    peer_server.chainstate(),
-                    &child_consensus_hash,
-                    &child_block.block_hash(),
-                    true,
-                );
-
-                for mblock in mblocks.iter() {
-                    store_staging_microblock(
-                        peer_server.chainstate(),
-                        &parent_consensus_hash,
-                        &parent_block.block_hash(),
-                        &mblock,
-                    );
-                }
-
-                set_microblocks_processed(
-                    peer_server.chainstate(),
-                    &child_consensus_hash,
-                    &child_block.block_hash(),
-                    &mblocks.last().as_ref().unwrap().block_hash(),
-                );
-
-                *server_microblocks_cell.borrow_mut() = mblocks;
-
-                convo_client.new_getmicroblocks_confirmed(child_index_block_hash)
             },
             |ref http_request,
              ref http_response,
@@ -4625,16 +9667,34 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                match (*http_response).clone() {
-                    HttpResponseType::Microblocks(_, mut microblocks) => {
-                        microblocks.reverse();
-                        assert_eq!(microblocks.len(), (*server_microblocks_cell.borrow()).len());
-                        assert_eq!(microblocks, *server_microblocks_cell.borrow());
+                match http_response {
+                    HttpResponseType::TraceReadOnlyFunction(_, data) => {
+                        assert!(cfg!(feature = "eval-hooks"));
+                        assert!(data.call.okay);
+                        assert!(!data.trace.truncated);
+
+                        let body = &data.trace.steps[0];
+                        assert_eq!(body.expression, "( ok ( var-get bar ) )");
+                        assert_eq!(body.depth, 0);
+                        assert_eq!(body.result, Some("(ok 0)".to_string()));
+                        // the call's cost also covers loading the contract
+                        let call_cost = data.call.cost.clone().unwrap();
+                        assert!(body.cost.runtime > 0);
+                        assert!(body.cost.runtime < call_cost.runtime);
+
+                        let var_get = &data.trace.steps[1];
+                        assert_eq!(var_get.expression, "( var-get bar )");
+                        assert_eq!(var_get.depth, 1);
+                        assert_eq!(var_get.cost.read_count, 1);
+                        true
+                    }
+                    HttpResponseType::NotFound(_, msg) => {
+                        assert!(!cfg!(feature = "eval-hooks"));
+                        assert!(msg.contains("eval-hooks"));
                         true
                     }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4644,46 +9704,33 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_unconfirmed_microblocks() {
-        let server_microblocks_cell = RefCell::new(vec![]);
-
+    fn test_rpc_call_read_only() {
+        // Test /v2/contracts/call-read (aka CallReadOnlyFunction) endpoint.
+        // In this test, we don't set any tip parameters, and we expect that querying
+        // against the canonical Stacks tip will succeed.
         test_rpc(
-            "test_rpc_unconfirmed_microblocks",
-            40050,
-            40051,
-            50050,
-            50051,
+            "test_rpc_call_read_only",
+            40170,
+            40171,
+            50170,
+            50171,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let privk = StacksPrivateKey::from_hex(
-                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                convo_client.new_callreadonlyfunction(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world-unconfirmed".try_into().unwrap(),
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal(),
+                    "ro-test".try_into().unwrap(),
+                    vec![],
+                    None,
+                    TipRequest::UseLatestAnchoredTip,
                 )
-                .unwrap();
-
-                let consensus_hash = ConsensusHash([0x02; 20]);
-                let anchored_block_hash = BlockHeaderHash([0x03; 32]);
-                let index_block_hash =
-                    StacksBlockHeader::make_index_block_hash(&consensus_hash, &anchored_block_hash);
-
-                let mut mblocks = make_sample_microblock_stream(&privk, &anchored_block_hash);
-                mblocks.truncate(15);
-
-                for mblock in mblocks.iter() {
-                    store_staging_microblock(
-                        peer_server.chainstate(),
-                        &consensus_hash,
-                        &anchored_block_hash,
-                        &mblock,
-                    );
-                }
-
-                *server_microblocks_cell.borrow_mut() = mblocks;
-
-                // start at seq 5
-                convo_client.new_getmicroblocks_unconfirmed(index_block_hash, 5)
             },
             |ref http_request,
              ref http_response,
@@ -4693,16 +9740,15 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::Microblocks(response_md, microblocks) => {
-                        assert_eq!(microblocks.len(), 10);
-                        assert_eq!(
-                            *microblocks,
-                            (*server_microblocks_cell.borrow())[5..].to_vec()
-                        );
+                    HttpResponseType::CallReadOnlyFunction(response_md, data) => {
+                        assert!(data.cause.is_some());
+                        assert!(data.cause.clone().unwrap().find("NoSuchContract").is_some());
+                        assert!(!data.okay);
+                        assert!(data.result.is_none());
                         true
                     }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4712,52 +9758,33 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_unconfirmed_transaction() {
-        let last_txid = RefCell::new(Txid([0u8; 32]));
-        let last_mblock = RefCell::new(BlockHeaderHash([0u8; 32]));
-
+    fn test_rpc_call_read_only_use_latest_tip() {
+        // Test /v2/contracts/call-read (aka CallReadOnlyFunction) endpoint.
+        // In this test, we set `tip_req` to UseLatestUnconfirmedTip, and we expect that querying
+        // against the unconfirmed state will succeed.
         test_rpc(
-            "test_rpc_unconfirmed_transaction",
-            40052,
-            40053,
-            50052,
-            50053,
+            "test_rpc_call_read_only_use_latest_tip",
+            40172,
+            40173,
+            50172,
+            50173,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let privk = StacksPrivateKey::from_hex(
-                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+                convo_client.new_callreadonlyfunction(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world-unconfirmed".try_into().unwrap(),
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal(),
+                    "ro-test".try_into().unwrap(),
+                    vec![],
+                    None,
+                    TipRequest::UseLatestAnchoredTip,
                 )
-                .unwrap();
-
-                let sortdb = peer_server.sortdb.take().unwrap();
-                Relayer::setup_unconfirmed_state(peer_server.chainstate(), &sortdb).unwrap();
-                peer_server.sortdb = Some(sortdb);
-
-                assert!(peer_server.chainstate().unconfirmed_state.is_some());
-                let (txid, mblock_hash) = match peer_server.chainstate().unconfirmed_state {
-                    Some(ref unconfirmed) => {
-                        assert!(unconfirmed.mined_txs.len() > 0);
-                        let mut txid = Txid([0u8; 32]);
-                        let mut mblock_hash = BlockHeaderHash([0u8; 32]);
-                        for (next_txid, (_, mbh, ..)) in unconfirmed.mined_txs.iter() {
-                            txid = next_txid.clone();
-                            mblock_hash = mbh.clone();
-                            break;
-                        }
-                        (txid, mblock_hash)
-                    }
-                    None => {
-                        panic!("No unconfirmed state");
-                    }
-                };
-
-                *last_txid.borrow_mut() = txid.clone();
-                *last_mblock.borrow_mut() = mblock_hash.clone();
-
-                convo_client.new_gettransaction_unconfirmed(txid)
             },
             |ref http_request,
              ref http_response,
@@ -4767,23 +9794,18 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::UnconfirmedTransaction(response_md, unconfirmed_resp) => {
+                    HttpResponseType::CallReadOnlyFunction(response_md, data) => {
+                        assert!(data.okay);
                         assert_eq!(
-                            unconfirmed_resp.status,
-                            UnconfirmedTransactionStatus::Microblock {
-                                block_hash: (*last_mblock.borrow()).clone(),
-                                seq: 0
-                            }
+                            Value::try_deserialize_hex_untyped(&data.result.clone().unwrap())
+                                .unwrap(),
+                            Value::okay(Value::Int(1)).unwrap()
                         );
-                        let tx = StacksTransaction::consensus_deserialize(
-                            &mut &hex_bytes(&unconfirmed_resp.tx).unwrap()[..],
-                        )
-                        .unwrap();
-                        assert_eq!(tx.txid(), *last_txid.borrow());
+                        assert!(data.cause.is_none());
                         true
                     }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4793,27 +9815,37 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_missing_getblock() {
+    fn test_rpc_call_read_only_unconfirmed() {
         test_rpc(
-            "test_rpc_missing_getblock",
-            40060,
-            40061,
-            50060,
-            50061,
+            "test_rpc_call_read_only_unconfirmed",
+            40180,
+            40181,
+            50180,
+            50181,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let peer_server_block_hash = BlockHeaderHash([0x04; 32]);
-                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
-                let index_block_hash = StacksBlockHeader::make_index_block_hash(
-                    &peer_server_consensus_hash,
-                    &peer_server_block_hash,
-                );
-
-                // now ask for it
-                convo_client.new_getblock(index_block_hash)
+                let unconfirmed_tip = peer_client
+                    .chainstate()
+                    .unconfirmed_state
+                    .as_ref()
+                    .unwrap()
+                    .unconfirmed_chain_tip
+                    .clone();
+                convo_client.new_callreadonlyfunction(
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap(),
+                    "hello-world-unconfirmed".try_into().unwrap(),
+                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                        .unwrap()
+                        .to_account_principal(),
+                    "ro-test".try_into().unwrap(),
+                    vec![],
+                    None,
+                    TipRequest::SpecificTip(unconfirmed_tip),
+                )
             },
             |ref http_request,
              ref http_response,
@@ -4823,9 +9855,18 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::NotFound(response_md, msg) => true,
+                    HttpResponseType::CallReadOnlyFunction(response_md, data) => {
+                        assert!(data.okay);
+                        assert_eq!(
+                            Value::try_deserialize_hex_untyped(&data.result.clone().unwrap())
+                                .unwrap(),
+                            Value::okay(Value::Int(1)).unwrap()
+                        );
+                        assert!(data.cause.is_none());
+                        true
+                    }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4835,27 +9876,20 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_missing_index_getmicroblocks() {
+    fn test_rpc_getattachmentsinv_limit_reached() {
         test_rpc(
-            "test_rpc_missing_index_getmicroblocks",
-            40070,
-            40071,
-            50070,
-            50071,
+            "test_rpc_getattachmentsinv",
+            40190,
+            40191,
+            50190,
+            50191,
             true,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let peer_server_block_hash = BlockHeaderHash([0x04; 32]);
-                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
-                let index_block_hash = StacksBlockHeader::make_index_block_hash(
-                    &peer_server_consensus_hash,
-                    &peer_server_block_hash,
-                );
-
-                // now ask for it
-                convo_client.new_getmicroblocks_indexed(index_block_hash)
+                let pages_indexes = HashSet::from_iter(vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+                convo_client.new_getattachmentsinv(StacksBlockId([0x00; 32]), pages_indexes)
             },
             |ref http_request,
              ref http_response,
@@ -4865,11 +9899,14 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::NotFound(response_md, msg) => true,
-                    _ => {
-                        error!("Invalid response: {:?}", &http_response);
-                        false
+                    HttpResponseType::BadRequest(_, msg) => {
+                        assert_eq!(
+                            msg,
+                            "Number of attachment inv pages is limited by 8 per request"
+                        );
+                        true
                     }
+                    _ => false,
                 }
             },
         );
@@ -4877,27 +9914,23 @@ mod test {
 
     #[test]
     #[ignore]
-    fn test_rpc_missing_confirmed_getmicroblocks() {
+    fn test_rpc_mempool_query_txtags() {
         test_rpc(
-            "test_rpc_missing_confirmed_getmicroblocks",
-            40072,
-            40073,
-            50072,
-            50073,
-            true,
+            "test_rpc_mempool_query_txtags",
+            40813,
+            40814,
+            50813,
+            50814,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let peer_server_block_hash = BlockHeaderHash([0x04; 32]);
-                let peer_server_consensus_hash = ConsensusHash([0x02; 20]);
-                let index_block_hash = StacksBlockHeader::make_index_block_hash(
-                    &peer_server_consensus_hash,
-                    &peer_server_block_hash,
-                );
-
-                // now ask for it
-                convo_client.new_getmicroblocks_confirmed(index_block_hash)
+                convo_client.new_mempool_query(
+                    MemPoolSyncData::TxTags([0u8; 32], vec![]),
+                    Some(Txid([0u8; 32])),
+                    None,
+                )
             },
             |ref http_request,
              ref http_response,
@@ -4907,58 +9940,32 @@ mod test {
              ref convo_server| {
                 let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::NotFound(response_md, msg) => true,
-                    _ => {
-                        error!("Invalid response: {:?}", &http_response);
-                        false
+                    HttpResponseType::MemPoolTxs(_, _, txs) => {
+                        // got everything
+                        assert_eq!(txs.len(), 10);
+                        true
                     }
+                    _ => false,
                 }
             },
         );
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_missing_unconfirmed_microblocks() {
-        let server_microblocks_cell = RefCell::new(vec![]);
-
+    fn test_rpc_post_divergence_snapshot() {
         test_rpc(
-            "test_rpc_missing_unconfirmed_microblocks",
-            40080,
-            40081,
-            50080,
-            50081,
-            true,
+            "test_rpc_post_divergence_snapshot",
+            40817,
+            40818,
+            50817,
+            50818,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let privk = StacksPrivateKey::from_hex(
-                    "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
-                )
-                .unwrap();
-
-                let consensus_hash = ConsensusHash([0x02; 20]);
-                let anchored_block_hash = BlockHeaderHash([0x03; 32]);
-                let index_block_hash =
-                    StacksBlockHeader::make_index_block_hash(&consensus_hash, &anchored_block_hash);
-
-                let mut mblocks = make_sample_microblock_stream(&privk, &anchored_block_hash);
-                mblocks.truncate(15);
-
-                for mblock in mblocks.iter() {
-                    store_staging_microblock(
-                        peer_server.chainstate(),
-                        &consensus_hash,
-                        &anchored_block_hash,
-                        &mblock,
-                    );
-                }
-
-                *server_microblocks_cell.borrow_mut() = mblocks;
-
-                // start at seq 16 (which doesn't exist)
-                convo_client.new_getmicroblocks_unconfirmed(index_block_hash, 16)
+                convo_server.set_on_admin_listener();
+                convo_client.new_post_divergence_snapshot(Some(TEST_AUTH_TOKEN.to_string()))
             },
             |ref http_request,
              ref http_response,
@@ -4966,11 +9973,27 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::NotFound(response_md, msg) => true,
+                    HttpResponseType::DivergenceSnapshot(_, response) => {
+                        // no snapshot directory is configured
+                        assert!(response.path.is_none());
+                        assert_eq!(response.snapshot.reason, DivergenceReason::Manual);
+                        let tip = peer_server.network.burnchain_tip.clone();
+                        assert_eq!(
+                            response.snapshot.sortitions[0].burn_header_hash,
+                            tip.burn_header_hash
+                        );
+                        assert!(response
+                            .snapshot
+                            .stacks_headers
+                            .iter()
+                            .any(|header| header.canonical
+                                && header.block_height == tip.canonical_stacks_tip_height));
+                        assert_eq!(response.snapshot.mempool.tx_count, 10);
+                        true
+                    }
                     _ => {
-                        error!("Invalid response: {:?}", &http_response);
+                        error!("Invalid response; {:?}", &http_response);
                         false
                     }
                 }
@@ -4979,132 +10002,88 @@ mod test {
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_contract_src() {
-        // Test v2/contracts/source (aka GetContractSrc) endpoint.
-        // In this test, we don't set any tip parameters, and allow the endpoint to execute against
-        // the canonical Stacks tip.
-        // The contract source we are querying for exists in the anchored state, so we expect the
-        // query to succeed.
+    fn test_rpc_admin_endpoint_not_on_public_listener() {
+        // no admin listener is bound, so a valid admin request on the public listener is turned
+        // away as if the endpoint did not exist
         test_rpc(
-            "test_rpc_get_contract_src",
-            40090,
-            40091,
-            50090,
-            50091,
-            true,
+            "test_rpc_admin_endpoint_not_on_public_listener",
+            40871,
+            40872,
+            50871,
+            50872,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getcontractsrc(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world".try_into().unwrap(),
-                    TipRequest::UseLatestAnchoredTip,
-                    false,
-                )
+                convo_client.new_get_admin_peers(Some(TEST_AUTH_TOKEN.to_string()))
             },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
-             ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                match http_response {
-                    HttpResponseType::GetContractSrc(response_md, data) => {
-                        assert_eq!(data.source, TEST_CONTRACT);
-                        true
-                    }
-                    _ => {
-                        error!("Invalid response; {:?}", &http_response);
-                        false
-                    }
+             ref convo_server| match http_response {
+                HttpResponseType::NotFound(..) => true,
+                _ => {
+                    error!("Invalid response; {:?}", &http_response);
+                    false
                 }
             },
         );
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_contract_src_unconfirmed_with_canonical_tip() {
-        // Test v2/contracts/source (aka GetContractSrc) endpoint.
-        // In this test, we don't set any tip parameters, and allow the endpoint to execute against
-        // the canonical Stacks tip.
-        // The contract source we are querying for only exists in the unconfirmed state, so we
-        // expect the query to fail.
+    fn test_rpc_post_divergence_snapshot_unauthorized() {
         test_rpc(
-            "test_rpc_get_contract_src_unconfirmed_with_canonical_tip",
-            40100,
-            40101,
-            50100,
-            50101,
-            true,
+            "test_rpc_post_divergence_snapshot_unauthorized",
+            40819,
+            40820,
+            50819,
+            50820,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getcontractsrc(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world-unconfirmed".try_into().unwrap(),
-                    TipRequest::UseLatestAnchoredTip,
-                    false,
-                )
-            },
-            |ref http_request,
-             ref http_response,
-             ref mut peer_client,
-             ref mut peer_server,
-             ref convo_client,
-             ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                match http_response {
-                    HttpResponseType::NotFound(_, error_str) => {
-                        assert_eq!(error_str, "No contract source data found");
-                        true
-                    }
-                    _ => {
-                        error!("Invalid response; {:?}", &http_response);
-                        false
-                    }
+                convo_server.set_on_admin_listener();
+                convo_client.new_post_divergence_snapshot(Some("wrong-token".to_string()))
+            },
+            |ref http_request,
+             ref http_response,
+             ref mut peer_client,
+             ref mut peer_server,
+             ref convo_client,
+             ref convo_server| match http_response {
+                HttpResponseType::Unauthorized(..) => true,
+                _ => {
+                    error!("Invalid response; {:?}", &http_response);
+                    false
                 }
             },
         );
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_contract_src_with_unconfirmed_tip() {
-        // Test v2/contracts/source (aka GetContractSrc) endpoint.
-        // In this test, we set `tip_req` to be the unconfirmed chain tip.
-        // The contract source we are querying for exists in the unconfirmed state, so we expect
-        // the query to succeed.
+    fn test_rpc_delete_mempool_tx() {
         test_rpc(
-            "test_rpc_get_contract_src_with_unconfirmed_tip",
-            40102,
-            40103,
-            50102,
-            50103,
-            true,
+            "test_rpc_delete_mempool_tx",
+            40821,
+            40822,
+            50821,
+            50822,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let unconfirmed_tip = peer_client
-                    .chainstate()
-                    .unconfirmed_state
-                    .as_ref()
-                    .unwrap()
-                    .unconfirmed_chain_tip
-                    .clone();
-                convo_client.new_getcontractsrc(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world-unconfirmed".try_into().unwrap(),
-                    TipRequest::SpecificTip(unconfirmed_tip),
-                    false,
+                convo_server.set_on_admin_listener();
+                let mempool = peer_server.mempool.as_ref().unwrap();
+                let txid = MemPoolDB::get_all_txs(mempool.conn()).unwrap()[0].tx.txid();
+                convo_client.new_delete_mempool_tx(
+                    txid,
+                    Some("stuck".to_string()),
+                    Some(TEST_AUTH_TOKEN.to_string()),
                 )
             },
             |ref http_request,
@@ -5113,10 +10092,19 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::GetContractSrc(response_md, data) => {
-                        assert_eq!(data.source, TEST_CONTRACT_UNCONFIRMED);
+                    HttpResponseType::MemPoolEviction(_, response) => {
+                        assert_eq!(response.reason, "stuck");
+                        assert_eq!(response.tx_fee, 1000);
+                        let txid = Txid::from_hex(&response.txid).unwrap();
+                        let mempool = peer_server.mempool.as_ref().unwrap();
+                        assert!(!MemPoolDB::db_has_tx(mempool.conn(), &txid).unwrap());
+                        assert_eq!(
+                            MemPoolDB::get_tx_evictions(mempool.conn(), &txid)
+                                .unwrap()
+                                .len(),
+                            1
+                        );
                         true
                     }
                     _ => {
@@ -5129,29 +10117,23 @@ mod test {
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_contract_src_use_latest_tip() {
-        // Test v2/contracts/source (aka GetContractSrc) endpoint.
-        // In this test, we set `tip_req` to UseLatestUnconfirmedTip.
-        // The contract source we are querying for exists in the unconfirmed state, so we expect
-        // the query to succeed.
+    fn test_rpc_delete_mempool_tx_not_found() {
         test_rpc(
-            "test_rpc_get_contract_src_use_latest_tip",
-            40104,
-            40105,
-            50104,
-            50105,
-            true,
+            "test_rpc_delete_mempool_tx_not_found",
+            40823,
+            40824,
+            50823,
+            50824,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getcontractsrc(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world-unconfirmed".try_into().unwrap(),
-                    TipRequest::UseLatestAnchoredTip,
-                    false,
+                convo_server.set_on_admin_listener();
+                convo_client.new_delete_mempool_tx(
+                    Txid([0x11; 32]),
+                    None,
+                    Some(TEST_AUTH_TOKEN.to_string()),
                 )
             },
             |ref http_request,
@@ -5159,43 +10141,30 @@ mod test {
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
-             ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                match http_response {
-                    HttpResponseType::GetContractSrc(response_md, data) => {
-                        assert_eq!(data.source, TEST_CONTRACT_UNCONFIRMED);
-                        true
-                    }
-                    _ => {
-                        error!("Invalid response; {:?}", &http_response);
-                        false
-                    }
+             ref convo_server| match http_response {
+                HttpResponseType::NotFound(..) => true,
+                _ => {
+                    error!("Invalid response; {:?}", &http_response);
+                    false
                 }
             },
         );
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_account() {
+    fn test_rpc_get_mempool_txs() {
         test_rpc(
-            "test_rpc_get_account",
-            40110,
-            40111,
-            50110,
-            50111,
-            true,
+            "test_rpc_get_mempool_txs",
+            40837,
+            40838,
+            50837,
+            50838,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getaccount(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal(),
-                    TipRequest::UseLatestAnchoredTip,
-                    false,
-                )
+                convo_client.new_get_mempool_txs(MemPoolListFilter::default(), None, 4)
             },
             |ref http_request,
              ref http_response,
@@ -5203,12 +10172,17 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::GetAccount(response_md, data) => {
-                        assert_eq!(data.nonce, 2);
-                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
-                        assert_eq!(balance, 1000000000);
+                    HttpResponseType::MemPoolListing(_, response) => {
+                        assert_eq!(response.transactions.len(), 4);
+                        assert!(response
+                            .transactions
+                            .windows(2)
+                            .all(|txs| txs[0].tx_fee >= txs[1].tx_fee));
+                        let cursor =
+                            MemPoolListCursor::from_str(response.next_cursor.as_ref().unwrap())
+                                .unwrap();
+                        assert_eq!(format!("0x{}", &cursor.txid), response.transactions[3].txid);
                         true
                     }
                     _ => {
@@ -5220,30 +10194,20 @@ mod test {
         );
     }
 
-    /// In this test, the query parameter `tip_req` is set to UseLatestUnconfirmedTip, and so we expect the
-    /// tip used for the query to be the latest microblock.
-    /// We check that the account state matches the state in the most recent microblock.
     #[test]
-    #[ignore]
-    fn test_rpc_get_account_use_latest_tip() {
+    fn test_rpc_get_mempool_summary() {
         test_rpc(
-            "test_rpc_get_account_use_latest_tip",
-            40112,
-            40113,
-            50112,
-            50113,
-            true,
+            "test_rpc_get_mempool_summary",
+            40839,
+            40840,
+            50839,
+            50840,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getaccount(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal(),
-                    TipRequest::UseLatestAnchoredTip,
-                    false,
-                )
+                convo_client.new_get_mempool_summary(MemPoolListFilter::default())
             },
             |ref http_request,
              ref http_response,
@@ -5251,12 +10215,19 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
                 match http_response {
-                    HttpResponseType::GetAccount(response_md, data) => {
-                        assert_eq!(data.nonce, 4);
-                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
-                        assert_eq!(balance, 999999877);
+                    HttpResponseType::MemPoolSummary(_, response) => {
+                        let mempool = peer_server.mempool.as_ref().unwrap();
+                        let num_txs = MemPoolDB::get_all_txs(mempool.conn()).unwrap().len();
+                        assert_eq!(response.count, num_txs as u64);
+                        assert_eq!(
+                            response
+                                .fee_histogram
+                                .iter()
+                                .map(|bucket| bucket.count)
+                                .sum::<u64>(),
+                            response.count
+                        );
                         true
                     }
                     _ => {
@@ -5268,128 +10239,107 @@ mod test {
         );
     }
 
-    /// In this test, the query parameter `tip_req` is set to UseLatestUnconfirmedTip, but we did not generate
-    /// microblocks in the rpc test. Thus, we expect the tip used for the query to be the previous
-    /// anchor block (which is the latest tip).
-    /// We check that the account state matches the state in the previous anchor block.
     #[test]
-    #[ignore]
-    fn test_rpc_get_account_use_latest_tip_no_microblocks() {
+    fn test_rpc_get_admin_observers() {
+        let observer = AdminObserverInfo {
+            endpoint: "localhost:3700".to_string(),
+            events_keys: vec!["stx".to_string(), "memtx".to_string()],
+        };
+        let expected = observer.clone();
         test_rpc(
-            "test_rpc_get_account",
-            40114,
-            40115,
-            50114,
-            50115,
+            "test_rpc_get_admin_observers",
+            40861,
+            40862,
+            50861,
+            50862,
             false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getaccount(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal(),
-                    TipRequest::UseLatestAnchoredTip,
-                    false,
-                )
+                convo_server.set_on_admin_listener();
+                peer_server.network.connection_opts.event_observers = vec![observer];
+                convo_client.new_get_admin_observers(Some(TEST_AUTH_TOKEN.to_string()))
             },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
-             ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                match http_response {
-                    HttpResponseType::GetAccount(response_md, data) => {
-                        assert_eq!(data.nonce, 2);
-                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
-                        assert_eq!(balance, 1000000000);
-                        true
-                    }
-                    _ => {
-                        error!("Invalid response; {:?}", &http_response);
-                        false
-                    }
+             ref convo_server| match http_response {
+                HttpResponseType::AdminObservers(_, response) => {
+                    assert_eq!(response.observers, vec![expected]);
+                    true
+                }
+                _ => {
+                    error!("Invalid response; {:?}", &http_response);
+                    false
                 }
             },
         );
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_account_unconfirmed() {
+    fn test_rpc_get_admin_peers() {
         test_rpc(
-            "test_rpc_get_account_unconfirmed",
-            40120,
-            40121,
-            50120,
-            50121,
-            true,
+            "test_rpc_get_admin_peers",
+            40827,
+            40828,
+            50827,
+            50828,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let unconfirmed_tip = peer_client
-                    .chainstate()
-                    .unconfirmed_state
-                    .as_ref()
-                    .unwrap()
-                    .unconfirmed_chain_tip
-                    .clone();
-                convo_client.new_getaccount(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal(),
-                    TipRequest::SpecificTip(unconfirmed_tip),
-                    false,
-                )
+                convo_server.set_on_admin_listener();
+                convo_client.new_get_admin_peers(Some(TEST_AUTH_TOKEN.to_string()))
             },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
-             ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                match http_response {
-                    HttpResponseType::GetAccount(response_md, data) => {
-                        assert_eq!(data.nonce, 4);
-                        let balance = u128::from_str_radix(&data.balance[2..], 16).unwrap();
-                        assert_eq!(balance, 1000000000 - 123);
-                        true
-                    }
-                    _ => {
-                        error!("Invalid response; {:?}", &http_response);
-                        false
-                    }
+             ref convo_server| match http_response {
+                HttpResponseType::AdminPeers(_, response) => {
+                    assert_eq!(response.peers.len(), peer_server.network.peers.len());
+                    true
+                }
+                _ => {
+                    error!("Invalid response; {:?}", &http_response);
+                    false
                 }
             },
         );
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_data_var() {
+    fn test_rpc_post_peer_ban_read_only() {
         test_rpc(
-            "test_rpc_get_data_var",
-            40122,
-            40123,
-            50122,
-            50123,
-            true,
+            "test_rpc_post_peer_ban_read_only",
+            40831,
+            40832,
+            50831,
+            50832,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getdatavar(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world".try_into().unwrap(),
-                    "bar".try_into().unwrap(),
-                    TipRequest::UseLatestAnchoredTip,
-                    false,
+                convo_server.set_on_admin_listener();
+                peer_server
+                    .network
+                    .connection_opts
+                    .admin_credentials
+                    .push(AdminCredential {
+                        role: AdminRole::ReadOnly,
+                        auth: AdminAuth::SharedSecret("metrics-secret".to_string()),
+                    });
+                convo_client.new_post_peer_ban(
+                    PeerBanTarget::from_address_range("127.0.0.1/32").unwrap(),
+                    Some(3600),
+                    None,
+                    Some("metrics-secret".to_string()),
                 )
             },
             |ref http_request,
@@ -5397,100 +10347,107 @@ mod test {
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
-             ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                match http_response {
-                    HttpResponseType::GetDataVar(response_md, data) => {
-                        assert_eq!(
-                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
-                            Value::Int(0)
-                        );
-                        true
-                    }
-                    _ => {
-                        error!("Invalid response; {:?}", &http_response);
-                        false
-                    }
+             ref convo_server| match http_response {
+                HttpResponseType::Forbidden(..) => true,
+                _ => {
+                    error!("Invalid response; {:?}", &http_response);
+                    false
                 }
             },
         );
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_data_var_unconfirmed() {
+    fn test_rpc_post_peer_ban_signed() {
         test_rpc(
-            "test_rpc_get_data_var_unconfirmed",
-            40124,
-            40125,
-            50124,
-            50125,
-            true,
+            "test_rpc_post_peer_ban_signed",
+            40863,
+            40864,
+            50863,
+            50864,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let unconfirmed_tip = peer_client
-                    .chainstate()
-                    .unconfirmed_state
-                    .as_ref()
-                    .unwrap()
-                    .unconfirmed_chain_tip
-                    .clone();
-                convo_client.new_getdatavar(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world".try_into().unwrap(),
-                    "bar".try_into().unwrap(),
-                    TipRequest::SpecificTip(unconfirmed_tip),
-                    false,
+                convo_server.set_on_admin_listener();
+                let operator_key = StacksPrivateKey::new();
+                peer_server
+                    .network
+                    .connection_opts
+                    .admin_credentials
+                    .push(AdminCredential {
+                        role: AdminRole::Operator,
+                        auth: AdminAuth::PublicKey(StacksPublicKey::from_private(&operator_key)),
+                    });
+                let target = PeerBanTarget::from_address_range("127.0.0.1/32").unwrap();
+                let request =
+                    convo_client.new_post_peer_ban(target.clone(), Some(3600), None, None);
+                let authorization = crate::net::admin::sign_admin_request(
+                    &operator_key,
+                    "POST",
+                    &request.request_path(),
+                    &request.admin_request_body().unwrap(),
+                    get_epoch_time_secs(),
                 )
+                .unwrap();
+                convo_client.new_post_peer_ban(target, Some(3600), None, Some(authorization))
             },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
-             ref convo_client,
-             ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                match http_response {
-                    HttpResponseType::GetDataVar(response_md, data) => {
-                        assert_eq!(
-                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
-                            Value::Int(1)
-                        );
-                        true
-                    }
-                    _ => {
-                        error!("Invalid response; {:?}", &http_response);
-                        false
-                    }
+             ref convo_client,
+             ref convo_server| match http_response {
+                HttpResponseType::PeerBan(_, response) => {
+                    assert_eq!(response.address_range, Some("127.0.0.1/32".to_string()));
+                    // the signed request is remembered, so it can't be replayed
+                    assert_eq!(
+                        crate::net::admin::authorize_admin_request(
+                            &peer_server.network.connection_opts.admin_credentials,
+                            &mut peer_server.network.admin_replay_cache,
+                            match http_request {
+                                HttpRequestType::PostPeerBan { auth_token, .. } => {
+                                    auth_token.as_deref()
+                                }
+                                _ => panic!("Not a peer ban request"),
+                            },
+                            "POST",
+                            &http_request.request_path(),
+                            &http_request.admin_request_body().unwrap(),
+                            get_epoch_time_secs(),
+                        ),
+                        None
+                    );
+                    true
+                }
+                _ => {
+                    error!("Invalid response; {:?}", &http_response);
+                    false
                 }
             },
         );
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_data_var_nonexistant() {
+    fn test_rpc_post_peer_ban() {
         test_rpc(
-            "test_rpc_get_data_var_nonexistant",
-            40125,
-            40126,
-            50125,
-            50126,
-            true,
+            "test_rpc_post_peer_ban",
+            40833,
+            40834,
+            50833,
+            50834,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getdatavar(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world".try_into().unwrap(),
-                    "bar-nonexistant".try_into().unwrap(),
-                    TipRequest::UseLatestAnchoredTip,
-                    false,
+                convo_server.set_on_admin_listener();
+                convo_client.new_post_peer_ban(
+                    PeerBanTarget::from_address_range("192.168.0.0/16").unwrap(),
+                    Some(3600),
+                    Some("spam".to_string()),
+                    Some(TEST_AUTH_TOKEN.to_string()),
                 )
             },
             |ref http_request,
@@ -5498,124 +10455,114 @@ mod test {
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
-             ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                match http_response {
-                    HttpResponseType::NotFound(_, msg) => {
-                        assert_eq!(msg, "Data var not found");
-                        true
-                    }
-                    _ => {
-                        error!("Invalid response; {:?}", &http_response);
-                        false
-                    }
+             ref convo_server| match http_response {
+                HttpResponseType::PeerBan(_, response) => {
+                    assert_eq!(response.address_range, Some("192.168.0.0/16".to_string()));
+                    assert_eq!(response.reason, "spam");
+                    assert_eq!(response.expires_at, Some(response.created_at + 3600));
+                    let bans = PeerDB::get_peer_bans(
+                        peer_server.network.peerdb.conn(),
+                        get_epoch_time_secs(),
+                    )
+                    .unwrap();
+                    assert_eq!(bans.len(), 1);
+                    assert_eq!(bans[0].ban_id, response.ban_id);
+                    assert!(bans[0].applies_to(&PeerAddress::from_ipv4(192, 168, 1, 2), None));
+                    true
+                }
+                _ => {
+                    error!("Invalid response; {:?}", &http_response);
+                    false
                 }
             },
         );
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_map_entry() {
-        // Test v2/map_entry (aka GetMapEntry) endpoint.
-        // In this test, we don't set any tip parameters, and we expect that querying for map data
-        // against the canonical Stacks tip will succeed.
+    fn test_rpc_delete_peer_ban() {
         test_rpc(
-            "test_rpc_get_map_entry",
-            40130,
-            40131,
-            50130,
-            50131,
-            true,
+            "test_rpc_delete_peer_ban",
+            40835,
+            40836,
+            50835,
+            50836,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let principal =
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal();
-                convo_client.new_getmapentry(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world".try_into().unwrap(),
-                    "unit-map".try_into().unwrap(),
-                    Value::Tuple(
-                        TupleData::from_data(vec![("account".into(), Value::Principal(principal))])
-                            .unwrap(),
-                    ),
-                    TipRequest::UseLatestAnchoredTip,
-                    false,
+                convo_server.set_on_admin_listener();
+                let mut tx = peer_server.network.peerdb.tx_begin().unwrap();
+                let ban_id = PeerDB::add_peer_ban(
+                    &mut tx,
+                    &PeerBanTarget::PublicKeyHash(Hash160([0x11; 20])),
+                    "spam",
+                    get_epoch_time_secs(),
+                    None,
                 )
+                .unwrap();
+                tx.commit().unwrap();
+                convo_client.new_delete_peer_ban(ban_id, Some(TEST_AUTH_TOKEN.to_string()))
             },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
-             ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                match http_response {
-                    HttpResponseType::GetMapEntry(response_md, data) => {
-                        assert_eq!(
-                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
-                            Value::some(Value::Tuple(
-                                TupleData::from_data(vec![("units".into(), Value::Int(123))])
-                                    .unwrap()
-                            ))
-                            .unwrap()
-                        );
-                        true
-                    }
-                    _ => {
-                        error!("Invalid response; {:?}", &http_response);
-                        false
-                    }
+             ref convo_server| match http_response {
+                HttpResponseType::PeerBan(_, response) => {
+                    assert_eq!(response.public_key_hash, Some(Hash160([0x11; 20]).to_hex()));
+                    assert!(PeerDB::get_peer_bans(
+                        peer_server.network.peerdb.conn(),
+                        get_epoch_time_secs()
+                    )
+                    .unwrap()
+                    .is_empty());
+                    true
+                }
+                _ => {
+                    error!("Invalid response; {:?}", &http_response);
+                    false
                 }
             },
         );
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_map_entry_unconfirmed() {
-        // Test v2/map_entry (aka GetMapEntry) endpoint.
-        // In this test, we set `tip_req` to UseLatestUnconfirmedTip, and we expect that querying for map data
-        // against the unconfirmed state will succeed.
+    fn test_rpc_get_subnet_status() {
         test_rpc(
-            "test_rpc_get_map_entry_unconfirmed",
-            40140,
-            40141,
-            50140,
-            50141,
-            true,
+            "test_rpc_get_subnet_status",
+            40825,
+            40826,
+            50825,
+            50826,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let unconfirmed_tip = peer_client
-                    .chainstate()
-                    .unconfirmed_state
-                    .as_ref()
-                    .unwrap()
-                    .unconfirmed_chain_tip
-                    .clone();
-                let principal =
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal();
-                convo_client.new_getmapentry(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world".try_into().unwrap(),
-                    "unit-map".try_into().unwrap(),
-                    Value::Tuple(
-                        TupleData::from_data(vec![("account".into(), Value::Principal(principal))])
-                            .unwrap(),
-                    ),
-                    TipRequest::SpecificTip(unconfirmed_tip),
-                    false,
+                let tip = peer_server.network.burnchain_tip.clone();
+                let miner_info = StacksChainState::get_miner_info(
+                    peer_server.chainstate().db(),
+                    &tip.canonical_stacks_tip_consensus_hash,
+                    &tip.canonical_stacks_tip_hash,
                 )
+                .unwrap()
+                .unwrap();
+                let options = &mut convo_server.connection.options;
+                options.subnet_miner_address = Some(miner_info.address);
+                options.withdrawal_confirmation_depth = 10;
+                options.event_signing_public_key = Some(StacksPublicKey::from_private(
+                    &peer_server.network.local_peer.private_key,
+                ));
+                options.l1_contract_status = L1ContractStatus::Compatible;
+                options.l1_contract_interface_version = Some(1);
+                options.l1_sync_status.set(L1SyncStatus {
+                    startup_state: NodeStartupState::SyncingL1,
+                    l1_node_tip_height: Some(tip.block_height + 10),
+                });
+
+                convo_client.new_getsubnetstatus()
             },
             |ref http_request,
              ref http_response,
@@ -5623,17 +10570,51 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
+                let tip = peer_server.network.burnchain_tip.clone();
                 match http_response {
-                    HttpResponseType::GetMapEntry(response_md, data) => {
+                    HttpResponseType::SubnetStatus(_, response) => {
+                        let anchor_snapshot = SortitionDB::get_block_snapshot_consensus(
+                            peer_server.sortdb.as_ref().unwrap().conn(),
+                            &tip.canonical_stacks_tip_consensus_hash,
+                        )
+                        .unwrap()
+                        .unwrap();
+
+                        assert_eq!(response.stacks_tip_height, tip.canonical_stacks_tip_height);
+                        assert_eq!(response.stacks_tip, tip.canonical_stacks_tip_hash);
                         assert_eq!(
-                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
-                            Value::some(Value::Tuple(
-                                TupleData::from_data(vec![("units".into(), Value::Int(1))])
-                                    .unwrap()
-                            ))
-                            .unwrap()
+                            response.anchor_l1_block_hash,
+                            anchor_snapshot.burn_header_hash
+                        );
+                        assert_eq!(
+                            response.anchor_l1_block_height,
+                            anchor_snapshot.block_height
+                        );
+                        assert_eq!(response.l1_tip_height, tip.block_height);
+                        assert!(
+                            response.l1_blocks_since_last_commit
+                                <= tip.block_height - anchor_snapshot.block_height
+                        );
+                        // none of the test blocks withdraw anything
+                        assert_eq!(response.pending_withdrawals, 0);
+                        assert_eq!(response.miner_status, SubnetMinerStatus::Registered);
+                        assert_eq!(
+                            response.last_mined_block_height,
+                            Some(tip.canonical_stacks_tip_height)
+                        );
+                        assert_eq!(
+                            response.event_signing_public_key,
+                            Some(
+                                StacksPublicKey::from_private(
+                                    &peer_server.network.local_peer.private_key
+                                )
+                                .to_hex()
+                            )
                         );
+                        assert_eq!(response.l1_contract_status, L1ContractStatus::Compatible);
+                        assert_eq!(response.l1_contract_interface_version, Some(1));
+                        assert_eq!(response.startup_state, NodeStartupState::SyncingL1);
+                        assert_eq!(response.l1_node_tip_height, Some(tip.block_height + 10));
                         true
                     }
                     _ => {
@@ -5646,35 +10627,23 @@ mod test {
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_map_entry_use_latest_tip() {
+    fn test_rpc_get_block_header() {
         test_rpc(
-            "test_rpc_get_map_entry_use_latest_tip",
-            40142,
-            40143,
-            50142,
-            50143,
-            true,
+            "test_rpc_get_block_header",
+            40841,
+            40842,
+            50841,
+            50842,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let principal =
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal();
-                convo_client.new_getmapentry(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world".try_into().unwrap(),
-                    "unit-map".try_into().unwrap(),
-                    Value::Tuple(
-                        TupleData::from_data(vec![("account".into(), Value::Principal(principal))])
-                            .unwrap(),
-                    ),
-                    TipRequest::UseLatestAnchoredTip,
-                    false,
-                )
+                let tip = peer_server.network.burnchain_tip.clone();
+                convo_client.new_getblockheader(StacksBlockHeader::make_index_block_hash(
+                    &tip.canonical_stacks_tip_consensus_hash,
+                    &tip.canonical_stacks_tip_hash,
+                ))
             },
             |ref http_request,
              ref http_response,
@@ -5682,17 +10651,42 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
+                let tip = peer_server.network.burnchain_tip.clone();
+                let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &tip.canonical_stacks_tip_consensus_hash,
+                    &tip.canonical_stacks_tip_hash,
+                );
+                let header_info =
+                    StacksChainState::get_stacks_block_header_info_by_index_block_hash(
+                        peer_server.chainstate().db(),
+                        &index_block_hash,
+                    )
+                    .unwrap()
+                    .unwrap();
                 match http_response {
-                    HttpResponseType::GetMapEntry(response_md, data) => {
+                    HttpResponseType::BlockHeader(_, response) => {
+                        let header = &header_info.anchored_header;
+                        assert_eq!(response.index_block_hash, index_block_hash);
+                        assert_eq!(response.block_hash, tip.canonical_stacks_tip_hash);
                         assert_eq!(
-                            Value::try_deserialize_hex_untyped(&data.data).unwrap(),
-                            Value::some(Value::Tuple(
-                                TupleData::from_data(vec![("units".into(), Value::Int(1))])
-                                    .unwrap()
-                            ))
-                            .unwrap()
+                            response.consensus_hash,
+                            tip.canonical_stacks_tip_consensus_hash
+                        );
+                        assert_eq!(response.block_height, tip.canonical_stacks_tip_height);
+                        assert_eq!(
+                            response.withdrawal_merkle_root,
+                            header.withdrawal_merkle_root
                         );
+                        assert_eq!(
+                            response.miner_signatures,
+                            header
+                                .miner_signatures
+                                .signatures()
+                                .iter()
+                                .map(|sig| sig.to_hex())
+                                .collect::<Vec<_>>()
+                        );
+                        assert!(response.commit_observed_on_l1);
                         true
                     }
                     _ => {
@@ -5705,28 +10699,36 @@ mod test {
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_contract_abi() {
-        // Test /v2/contracts/interface (aka GetContractABI) endpoint.
-        // In this test, we don't set any tip parameters, and we expect that querying
-        // against the canonical Stacks tip will succeed.
+    fn test_rpc_get_block_state_diff() {
         test_rpc(
-            "test_rpc_get_contract_abi",
-            40150,
-            40151,
-            50150,
-            50151,
-            true,
+            "test_rpc_get_block_state_diff",
+            40847,
+            40848,
+            50847,
+            50848,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getcontractabi(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world-unconfirmed".try_into().unwrap(),
-                    TipRequest::UseLatestAnchoredTip,
-                )
+                let tip = peer_server.network.burnchain_tip.clone();
+                let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &tip.canonical_stacks_tip_consensus_hash,
+                    &tip.canonical_stacks_tip_hash,
+                );
+                let principal: PrincipalData = StandardPrincipalData::transient().into();
+                let write = MARFWrite {
+                    key: ClarityDatabase::make_key_for_account_balance(&principal),
+                    before: None,
+                    after: ClaritySerializable::serialize(&STXBalance::initial(1000)),
+                };
+                let chainstate = peer_server.chainstate();
+                let tx = chainstate.db_tx_begin().unwrap();
+                StacksChainState::insert_block_state_diff(&tx, &index_block_hash, &[write])
+                    .unwrap();
+                tx.commit().unwrap();
+
+                convo_client.new_getblockstatediff(index_block_hash)
             },
             |ref http_request,
              ref http_response,
@@ -5734,10 +10736,23 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
+                let tip = peer_server.network.burnchain_tip.clone();
+                let index_block_hash = StacksBlockHeader::make_index_block_hash(
+                    &tip.canonical_stacks_tip_consensus_hash,
+                    &tip.canonical_stacks_tip_hash,
+                );
                 match http_response {
-                    HttpResponseType::NotFound(..) => {
-                        // not confirmed yet
+                    HttpResponseType::BlockStateDiff(_, response) => {
+                        assert_eq!(response.index_block_hash, index_block_hash);
+                        assert_eq!(
+                            response.stx_balances,
+                            vec![STXBalanceChange {
+                                principal: StandardPrincipalData::transient().to_string(),
+                                before: "0".into(),
+                                after: "1000".into(),
+                            }]
+                        );
+                        assert!(response.ft_balances.is_empty());
                         true
                     }
                     _ => {
@@ -5750,35 +10765,44 @@ mod test {
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_contract_abi_unconfirmed() {
-        // Test /v2/contracts/interface (aka GetContractABI) endpoint.
-        // In this test, we set `tip_req` to UseLatestUnconfirmedTip, and we expect that querying
-        // against the unconfirmed state will succeed.
+    fn test_rpc_validate_block_proposal() {
         test_rpc(
-            "test_rpc_get_contract_abi_unconfirmed",
-            40152,
-            40153,
-            50152,
-            50153,
-            true,
+            "test_rpc_validate_block_proposal",
+            40849,
+            40850,
+            50849,
+            50850,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let unconfirmed_tip = peer_client
-                    .chainstate()
-                    .unconfirmed_state
-                    .as_ref()
-                    .unwrap()
-                    .unconfirmed_chain_tip
-                    .clone();
-                convo_client.new_getcontractabi(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world-unconfirmed".try_into().unwrap(),
-                    TipRequest::SpecificTip(unconfirmed_tip),
+                let tip = peer_server.network.burnchain_tip.clone();
+                let chainstate = peer_server.chainstate();
+                let tip_block = StacksChainState::load_block(
+                    &chainstate.blocks_path,
+                    &tip.canonical_stacks_tip_consensus_hash,
+                    &tip.canonical_stacks_tip_hash,
                 )
+                .unwrap()
+                .unwrap();
+
+                // re-propose the tip's transactions on top of the tip, where they have
+                // already been mined
+                let proposal = miner::Proposal {
+                    parent_block_hash: tip.canonical_stacks_tip_hash.clone(),
+                    parent_consensus_hash: tip.canonical_stacks_tip_consensus_hash.clone(),
+                    block: tip_block,
+                    microblocks_confirmed: vec![],
+                    burn_tip: tip.burn_header_hash.clone(),
+                    burn_tip_height: tip.block_height as u32,
+                    is_mainnet: false,
+                    microblock_pubkey_hash: Hash160([0x11; 20]),
+                    total_burn: tip.total_burn + 1,
+                    withdrawal_leaves: None,
+                    proposer_signature: None,
+                };
+                convo_client.new_validateblockproposal(proposal)
             },
             |ref http_request,
              ref http_response,
@@ -5786,9 +10810,32 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
+                let tip = peer_server.network.burnchain_tip.clone();
+                let chainstate = peer_server.chainstate();
+                let tip_block = StacksChainState::load_block(
+                    &chainstate.blocks_path,
+                    &tip.canonical_stacks_tip_consensus_hash,
+                    &tip.canonical_stacks_tip_hash,
+                )
+                .unwrap()
+                .unwrap();
                 match http_response {
-                    HttpResponseType::GetContractABI(response_md, data) => true,
+                    HttpResponseType::BlockProposalValidation(_, response) => {
+                        assert!(!response.valid);
+                        assert!(response.reason.is_some());
+                        assert_eq!(response.block_hash, tip_block.block_hash());
+                        assert!(response.computed_block_hash.is_some());
+
+                        // every transaction is reported, and each one is a replay
+                        assert_eq!(response.tx_results.len(), tip_block.txs.len());
+                        for (tx_result, tx) in response.tx_results.iter().zip(tip_block.txs.iter())
+                        {
+                            assert_eq!(tx_result.txid, tx.txid());
+                            assert!(tx_result.result.is_none());
+                            assert!(tx_result.error.is_some());
+                        }
+                        true
+                    }
                     _ => {
                         error!("Invalid response; {:?}", &http_response);
                         false
@@ -5799,125 +10846,228 @@ mod test {
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_get_contract_abi_use_latest_tip() {
-        test_rpc(
-            "test_rpc_get_contract_abi_use_latest_tip",
-            40154,
-            40155,
-            50154,
-            50155,
-            true,
+    fn test_rpc_validate_block_proposal_valid() {
+        test_rpc(
+            "test_rpc_validate_block_proposal_valid",
+            40865,
+            40866,
+            50865,
+            50866,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_getcontractabi(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world-unconfirmed".try_into().unwrap(),
-                    TipRequest::UseLatestAnchoredTip,
+                let tip = peer_server.network.burnchain_tip.clone();
+                let tip_block = StacksChainState::load_block(
+                    &peer_server.chainstate().blocks_path,
+                    &tip.canonical_stacks_tip_consensus_hash,
+                    &tip.canonical_stacks_tip_hash,
+                )
+                .unwrap()
+                .unwrap();
+
+                // ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R, which deployed hello-world
+                let privk1 = StacksPrivateKey::from_hex(
+                    "9f1f85a512a96a244e4c0d762788500687feb97481639572e3bffbd6860e6ab001",
+                )
+                .unwrap();
+                let addr1 = StacksAddress::from_public_keys(
+                    C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+                    &AddressHashMode::SerializeP2PKH,
+                    1,
+                    &vec![StacksPublicKey::from_private(&privk1)],
                 )
+                .unwrap();
+                let mut tx_cc = StacksTransaction::new(
+                    TransactionVersion::Testnet,
+                    TransactionAuth::from_p2pkh(&privk1).unwrap(),
+                    TransactionPayload::new_contract_call(addr1, "hello-world", "add-unit", vec![])
+                        .unwrap(),
+                );
+                tx_cc.chain_id = 0x80000000;
+                tx_cc.auth.set_origin_nonce(2);
+                tx_cc.set_tx_fee(123);
+                let mut tx_signer = StacksTransactionSigner::new(&tx_cc);
+                tx_signer.sign_origin(&privk1).unwrap();
+                let tx_cc_signed = tx_signer.get_tx().unwrap();
+
+                let mut proposal = miner::Proposal {
+                    parent_block_hash: tip.canonical_stacks_tip_hash.clone(),
+                    parent_consensus_hash: tip.canonical_stacks_tip_consensus_hash.clone(),
+                    block: StacksBlock {
+                        header: tip_block.header.clone(),
+                        txs: vec![tx_cc_signed],
+                    },
+                    microblocks_confirmed: vec![],
+                    burn_tip: tip.burn_header_hash.clone(),
+                    burn_tip_height: tip.block_height as u32,
+                    is_mainnet: false,
+                    microblock_pubkey_hash: Hash160([0x11; 20]),
+                    total_burn: tip.total_burn + 1,
+                    withdrawal_leaves: None,
+                    proposer_signature: None,
+                };
+
+                // propose the block that the node itself builds from these transactions
+                let sortdb = peer_server.sortdb.take().unwrap();
+                let evaluation = proposal
+                    .evaluate(peer_server.chainstate(), &sortdb.index_conn())
+                    .unwrap();
+                peer_server.sortdb = Some(sortdb);
+                proposal.block = evaluation.block;
+
+                convo_client.new_validateblockproposal(proposal)
             },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
-             ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                match http_response {
-                    HttpResponseType::GetContractABI(response_md, data) => true,
-                    _ => {
-                        error!("Invalid response; {:?}", &http_response);
-                        false
-                    }
+             ref convo_server| match http_response {
+                HttpResponseType::BlockProposalValidation(_, response) => {
+                    assert!(response.valid, "{:?}", &response.reason);
+                    assert!(response.reason.is_none());
+                    assert_eq!(response.computed_block_hash, Some(response.block_hash));
+                    assert_eq!(response.tx_results.len(), 1);
+                    assert!(response.tx_results[0].result.is_some());
+                    assert!(response.tx_results[0].error.is_none());
+
+                    // the total cost is that of the contract call
+                    assert!(response.total_cost.runtime > 0);
+                    assert_eq!(response.total_cost, response.tx_results[0].execution_cost);
+                    true
+                }
+                _ => {
+                    error!("Invalid response; {:?}", &http_response);
+                    false
                 }
             },
         );
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_call_read_only() {
-        // Test /v2/contracts/call-read (aka CallReadOnlyFunction) endpoint.
-        // In this test, we don't set any tip parameters, and we expect that querying
-        // against the canonical Stacks tip will succeed.
+    fn test_rpc_validate_block_proposal_disabled() {
         test_rpc(
-            "test_rpc_call_read_only",
-            40170,
-            40171,
-            50170,
-            50171,
-            true,
+            "test_rpc_validate_block_proposal_disabled",
+            40867,
+            40868,
+            50867,
+            50868,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_callreadonlyfunction(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world-unconfirmed".try_into().unwrap(),
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal(),
-                    "ro-test".try_into().unwrap(),
-                    vec![],
-                    TipRequest::UseLatestAnchoredTip,
+                convo_server
+                    .connection
+                    .options
+                    .enable_block_proposal_validation = false;
+                let tip = peer_server.network.burnchain_tip.clone();
+                let tip_block = StacksChainState::load_block(
+                    &peer_server.chainstate().blocks_path,
+                    &tip.canonical_stacks_tip_consensus_hash,
+                    &tip.canonical_stacks_tip_hash,
                 )
+                .unwrap()
+                .unwrap();
+                let proposal = miner::Proposal {
+                    parent_block_hash: tip.canonical_stacks_tip_hash.clone(),
+                    parent_consensus_hash: tip.canonical_stacks_tip_consensus_hash.clone(),
+                    block: tip_block,
+                    microblocks_confirmed: vec![],
+                    burn_tip: tip.burn_header_hash.clone(),
+                    burn_tip_height: tip.block_height as u32,
+                    is_mainnet: false,
+                    microblock_pubkey_hash: Hash160([0x11; 20]),
+                    total_burn: tip.total_burn + 1,
+                    withdrawal_leaves: None,
+                    proposer_signature: None,
+                };
+                convo_client.new_validateblockproposal(proposal)
             },
             |ref http_request,
              ref http_response,
              ref mut peer_client,
              ref mut peer_server,
              ref convo_client,
-             ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                match http_response {
-                    HttpResponseType::CallReadOnlyFunction(response_md, data) => {
-                        assert!(data.cause.is_some());
-                        assert!(data.cause.clone().unwrap().find("NoSuchContract").is_some());
-                        assert!(!data.okay);
-                        assert!(data.result.is_none());
-                        true
-                    }
-                    _ => {
-                        error!("Invalid response; {:?}", &http_response);
-                        false
-                    }
+             ref convo_server| match http_response {
+                HttpResponseType::Forbidden(..) => true,
+                _ => {
+                    error!("Invalid response; {:?}", &http_response);
+                    false
                 }
             },
         );
     }
 
+    /// Configure `options` as a multi-miner signer, with leader rotation through the signing set
+    /// of `proposers`.
+    fn set_up_block_proposal_signer(
+        options: &mut ConnectionOptions,
+        proposers: &[StacksPrivateKey],
+    ) {
+        let signing_set = proposers
+            .iter()
+            .map(|sk| {
+                let mut key = [0u8; 33];
+                key.copy_from_slice(&StacksPublicKey::from_private(sk).to_bytes_compressed());
+                key
+            })
+            .collect();
+        options.subnet_validator = Some(StacksPrivateKey::new());
+        options.subnet_signing_contract = Some(QualifiedContractIdentifier::transient());
+        options.subnet_block_proposers = Some(BlockProposerSchedule::new(signing_set, true));
+    }
+
+    /// A proposal on top of the canonical tip, for the L1 block at the tip
+    fn make_tip_block_proposal(peer_server: &mut TestPeer) -> miner::Proposal {
+        let tip = peer_server.network.burnchain_tip.clone();
+        let tip_block = StacksChainState::load_block(
+            &peer_server.chainstate().blocks_path,
+            &tip.canonical_stacks_tip_consensus_hash,
+            &tip.canonical_stacks_tip_hash,
+        )
+        .unwrap()
+        .unwrap();
+        miner::Proposal {
+            parent_block_hash: tip.canonical_stacks_tip_hash.clone(),
+            parent_consensus_hash: tip.canonical_stacks_tip_consensus_hash.clone(),
+            block: tip_block,
+            microblocks_confirmed: vec![],
+            burn_tip: tip.burn_header_hash.clone(),
+            burn_tip_height: tip.block_height as u32 + 1,
+            is_mainnet: false,
+            microblock_pubkey_hash: Hash160([0x11; 20]),
+            total_burn: tip.total_burn + 1,
+            withdrawal_leaves: None,
+            proposer_signature: None,
+        }
+    }
+
     #[test]
-    #[ignore]
-    fn test_rpc_call_read_only_use_latest_tip() {
-        // Test /v2/contracts/call-read (aka CallReadOnlyFunction) endpoint.
-        // In this test, we set `tip_req` to UseLatestUnconfirmedTip, and we expect that querying
-        // against the unconfirmed state will succeed.
+    fn test_rpc_block_proposal_from_unscheduled_miner() {
         test_rpc(
-            "test_rpc_call_read_only_use_latest_tip",
-            40172,
-            40173,
-            50172,
-            50173,
-            true,
+            "test_rpc_block_proposal_from_unscheduled_miner",
+            40873,
+            40874,
+            50873,
+            50874,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_callreadonlyfunction(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world-unconfirmed".try_into().unwrap(),
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal(),
-                    "ro-test".try_into().unwrap(),
-                    vec![],
-                    TipRequest::UseLatestAnchoredTip,
-                )
+                let proposers: Vec<_> = (0..3).map(|_| StacksPrivateKey::new()).collect();
+                set_up_block_proposal_signer(&mut convo_server.connection.options, &proposers);
+
+                // signed by a miner outside of the signing set
+                let mut proposal = make_tip_block_proposal(peer_server);
+                proposal.sign_as_proposer(
+                    &StacksPrivateKey::new(),
+                    QualifiedContractIdentifier::transient(),
+                );
+                convo_client.new_blockproposal(proposal)
             },
             |ref http_request,
              ref http_response,
@@ -5925,16 +11075,16 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
+                let burn_height = peer_server.network.burnchain_tip.block_height;
                 match http_response {
-                    HttpResponseType::CallReadOnlyFunction(response_md, data) => {
-                        assert!(data.okay);
-                        assert_eq!(
-                            Value::try_deserialize_hex_untyped(&data.result.clone().unwrap())
-                                .unwrap(),
-                            Value::okay(Value::Int(1)).unwrap()
-                        );
-                        assert!(data.cause.is_none());
+                    HttpResponseType::Error(_, 406, ref message) => {
+                        assert!(message.contains("may not propose"), "{}", message);
+                        // the proposal does not count as the leader's
+                        assert!(!convo_server
+                            .connection
+                            .options
+                            .block_proposal_log
+                            .has_seen_proposal(burn_height));
                         true
                     }
                     _ => {
@@ -5947,37 +11097,45 @@ mod test {
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_call_read_only_unconfirmed() {
+    fn test_rpc_block_proposal_second_block_at_height() {
         test_rpc(
-            "test_rpc_call_read_only_unconfirmed",
-            40180,
-            40181,
-            50180,
-            50181,
-            true,
+            "test_rpc_block_proposal_second_block_at_height",
+            40875,
+            40876,
+            50875,
+            50876,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let unconfirmed_tip = peer_client
-                    .chainstate()
-                    .unconfirmed_state
+                let proposers: Vec<_> = (0..3).map(|_| StacksPrivateKey::new()).collect();
+                set_up_block_proposal_signer(&mut convo_server.connection.options, &proposers);
+
+                // signed by the leader for its L1 block
+                let mut proposal = make_tip_block_proposal(peer_server);
+                let burn_height = proposal.tenure_burn_height();
+                let options = &convo_server.connection.options;
+                let leader = options
+                    .subnet_block_proposers
                     .as_ref()
                     .unwrap()
-                    .unconfirmed_chain_tip
+                    .leader_for_height(burn_height)
+                    .unwrap()
                     .clone();
-                convo_client.new_callreadonlyfunction(
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap(),
-                    "hello-world-unconfirmed".try_into().unwrap(),
-                    StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
-                        .unwrap()
-                        .to_account_principal(),
-                    "ro-test".try_into().unwrap(),
-                    vec![],
-                    TipRequest::SpecificTip(unconfirmed_tip),
-                )
+                let leader_sk = proposers
+                    .iter()
+                    .find(|sk| {
+                        StacksPublicKey::from_private(sk).to_bytes_compressed() == leader.to_vec()
+                    })
+                    .unwrap();
+                proposal.sign_as_proposer(leader_sk, QualifiedContractIdentifier::transient());
+
+                // ...but the signer already signed another block for that L1 block
+                assert!(options
+                    .block_proposal_log
+                    .try_commit_to_block(burn_height, &BlockHeaderHash([0xee; 32])));
+                convo_client.new_blockproposal(proposal)
             },
             |ref http_request,
              ref http_response,
@@ -5985,16 +11143,16 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
+                let burn_height = peer_server.network.burnchain_tip.block_height;
+                let log = &convo_server.connection.options.block_proposal_log;
                 match http_response {
-                    HttpResponseType::CallReadOnlyFunction(response_md, data) => {
-                        assert!(data.okay);
+                    HttpResponseType::Error(_, 406, ref message) => {
+                        assert!(message.contains("Already signed"), "{}", message);
+                        assert!(log.has_seen_proposal(burn_height));
                         assert_eq!(
-                            Value::try_deserialize_hex_untyped(&data.result.clone().unwrap())
-                                .unwrap(),
-                            Value::okay(Value::Int(1)).unwrap()
+                            log.committed_block(burn_height),
+                            Some(BlockHeaderHash([0xee; 32]))
                         );
-                        assert!(data.cause.is_none());
                         true
                     }
                     _ => {
@@ -6007,21 +11165,23 @@ mod test {
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_getattachmentsinv_limit_reached() {
+    fn test_rpc_post_transaction_diagnostics_already_known() {
         test_rpc(
-            "test_rpc_getattachmentsinv",
-            40190,
-            40191,
-            50190,
-            50191,
-            true,
+            "test_rpc_post_transaction_diagnostics_already_known",
+            40851,
+            40852,
+            50851,
+            50852,
+            false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                let pages_indexes = HashSet::from_iter(vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
-                convo_client.new_getattachmentsinv(StacksBlockId([0x00; 32]), pages_indexes)
+                let mempool = peer_server.mempool.as_ref().unwrap();
+                let tx = MemPoolDB::get_all_txs(mempool.conn()).unwrap()[0]
+                    .tx
+                    .clone();
+                convo_client.new_post_transaction_with_diagnostics(tx)
             },
             |ref http_request,
              ref http_response,
@@ -6029,40 +11189,57 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                println!("{:?}", http_response);
                 match http_response {
-                    HttpResponseType::BadRequest(_, msg) => {
-                        assert_eq!(
-                            msg,
-                            "Number of attachment inv pages is limited by 8 per request"
-                        );
+                    HttpResponseType::TransactionAdmission(_, response) => {
+                        assert!(response.accepted);
+                        assert!(response.already_known);
+                        assert!(response.rejection.is_none());
+                        // the transaction is not checked again
+                        assert!(response.checks.is_empty());
+
+                        let mempool = peer_server.mempool.as_ref().unwrap();
+                        let txid = Txid::from_hex(&response.txid[2..]).unwrap();
+                        let tx_info = MemPoolDB::get_tx(mempool.conn(), &txid).unwrap().unwrap();
+                        assert_eq!(response.tx_fee, tx_info.metadata.tx_fee);
+                        assert_eq!(response.tx_size, tx_info.metadata.len);
+                        assert_eq!(response.nonce.nonce, tx_info.metadata.origin_nonce);
+                        // it is only compared to the account's other pending transactions
+                        assert!(!response
+                            .nonce
+                            .pending_nonces
+                            .contains(&response.nonce.nonce));
+                        assert!(response.nonce.status.is_some());
                         true
                     }
-                    _ => false,
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
                 }
             },
         );
     }
 
     #[test]
-    #[ignore]
-    fn test_rpc_mempool_query_txtags() {
+    fn test_rpc_post_transaction_diagnostics_rejected() {
         test_rpc(
-            "test_rpc_mempool_query_txtags",
-            40813,
-            40814,
-            50813,
-            50814,
+            "test_rpc_post_transaction_diagnostics_rejected",
+            40853,
+            40854,
+            50853,
+            50854,
             false,
             |ref mut peer_client,
              ref mut convo_client,
              ref mut peer_server,
              ref mut convo_server| {
-                convo_client.new_mempool_query(
-                    MemPoolSyncData::TxTags([0u8; 32], vec![]),
-                    Some(Txid([0u8; 32])),
-                )
+                let mempool = peer_server.mempool.as_ref().unwrap();
+                let mut tx = MemPoolDB::get_all_txs(mempool.conn()).unwrap()[0]
+                    .tx
+                    .clone();
+                // changing the fee invalidates the signature
+                tx.set_tx_fee(tx.get_tx_fee() + 1);
+                convo_client.new_post_transaction_with_diagnostics(tx)
             },
             |ref http_request,
              ref http_response,
@@ -6070,15 +11247,46 @@ mod test {
              ref mut peer_server,
              ref convo_client,
              ref convo_server| {
-                let req_md = http_request.metadata().clone();
-                println!("{:?}", http_response);
                 match http_response {
-                    HttpResponseType::MemPoolTxs(_, _, txs) => {
-                        // got everything
-                        assert_eq!(txs.len(), 10);
+                    HttpResponseType::BadRequest(_, msg) => {
+                        let response: TransactionAdmissionResponse =
+                            serde_json::from_str(msg).unwrap();
+                        assert!(!response.accepted);
+                        assert!(!response.already_known);
+                        assert!(response.rejection.is_some());
+
+                        let checks: Vec<_> = response
+                            .checks
+                            .iter()
+                            .map(|check| (check.name.as_str(), check.status.clone()))
+                            .collect();
+                        assert_eq!(
+                            checks,
+                            vec![
+                                ("fee_rate_estimate", TransactionAdmissionCheckStatus::Passed),
+                                (
+                                    "contract_deploy_limits",
+                                    TransactionAdmissionCheckStatus::Passed
+                                ),
+                                ("admission_policy", TransactionAdmissionCheckStatus::Failed),
+                                ("mempool_limits", TransactionAdmissionCheckStatus::Skipped),
+                            ]
+                        );
+
+                        // the original transaction with this nonce is still pending
+                        assert_eq!(
+                            response.nonce.status,
+                            Some(TransactionNonceStatus::Replacement)
+                        );
+                        let mempool = peer_server.mempool.as_ref().unwrap();
+                        let txid = Txid::from_hex(&response.txid[2..]).unwrap();
+                        assert!(!MemPoolDB::db_has_tx(mempool.conn(), &txid).unwrap());
                         true
                     }
-                    _ => false,
+                    _ => {
+                        error!("Invalid response; {:?}", &http_response);
+                        false
+                    }
                 }
             },
         );
@@ -6106,6 +11314,7 @@ mod test {
                         BloomNodeHasher::new(&[0u8; 32]),
                     )),
                     Some(Txid([0u8; 32])),
+                    Some(BLOOM_COUNTER_DEPTH as u64),
                 )
             },
             |ref http_request,
@@ -6145,4 +11354,234 @@ mod test {
             let _v: RPCPeerInfoData = serde_json::from_str(json_obj).unwrap();
         }
     }
+
+    #[test]
+    fn test_readonly_function_call_error() {
+        let budget = ExecutionCost {
+            write_length: 0,
+            write_count: 0,
+            read_length: 100,
+            read_count: 3,
+            runtime: 1000,
+        };
+
+        // ran out of runtime
+        let cost = ExecutionCost {
+            write_length: 0,
+            write_count: 0,
+            read_length: 10,
+            read_count: 1,
+            runtime: 1001,
+        };
+        let response = ConversationHttp::readonly_function_call_error(
+            Unchecked(CheckErrors::CostBalanceExceeded(
+                cost.clone(),
+                budget.clone(),
+            )),
+            &budget,
+        );
+        assert!(!response.okay);
+        assert!(response.result.is_none());
+        assert_eq!(response.cause, Some("ReadOnlyBudgetExceeded".to_string()));
+        assert_eq!(
+            response.budget_exceeded,
+            Some(ReadOnlyBudgetExceeded {
+                cost,
+                budget: budget.clone(),
+            })
+        );
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CallReadOnlyResponse>(&json).unwrap(),
+            response
+        );
+
+        // tried to write
+        let cost = ExecutionCost {
+            write_length: 10,
+            write_count: 1,
+            read_length: 0,
+            read_count: 0,
+            runtime: 10,
+        };
+        let response = ConversationHttp::readonly_function_call_error(
+            Unchecked(CheckErrors::CostBalanceExceeded(cost, budget.clone())),
+            &budget,
+        );
+        assert_eq!(response.cause, Some("NotReadOnly".to_string()));
+        assert!(response.budget_exceeded.is_none());
+
+        // any other error
+        let response = ConversationHttp::readonly_function_call_error(
+            Unchecked(CheckErrors::NoSuchContract("foo".into())),
+            &budget,
+        );
+        assert!(response.cause.unwrap().find("NoSuchContract").is_some());
+        assert!(response.budget_exceeded.is_none());
+
+        // older responses still parse
+        let response: CallReadOnlyResponse =
+            serde_json::from_str(r#"{"okay":false,"cause":"NotReadOnly"}"#).unwrap();
+        assert!(response.budget_exceeded.is_none());
+    }
+
+    #[test]
+    fn test_readonly_call_budget() {
+        let mut options = ConnectionOptions::default();
+        options.read_only_call_limit = ExecutionCost {
+            write_length: 10,
+            write_count: 1,
+            read_length: 100,
+            read_count: 3,
+            runtime: 1000,
+        };
+        options.read_only_call_max_budget = ExecutionCost {
+            write_length: 10,
+            write_count: 1,
+            read_length: 1000,
+            read_count: 30,
+            runtime: 10000,
+        };
+
+        // no requested budget -- use the default limit, without writes
+        assert_eq!(
+            ConversationHttp::readonly_call_budget(None, &options),
+            ExecutionCost {
+                write_length: 0,
+                write_count: 0,
+                read_length: 100,
+                read_count: 3,
+                runtime: 1000,
+            }
+        );
+
+        // requested budget within the maximum is granted, without writes
+        let requested = ExecutionCost {
+            write_length: 10,
+            write_count: 1,
+            read_length: 500,
+            read_count: 1,
+            runtime: 5000,
+        };
+        assert_eq!(
+            ConversationHttp::readonly_call_budget(Some(&requested), &options),
+            ExecutionCost {
+                write_length: 0,
+                write_count: 0,
+                read_length: 500,
+                read_count: 1,
+                runtime: 5000,
+            }
+        );
+
+        // requested budget is capped at the maximum
+        let requested = ExecutionCost {
+            write_length: 0,
+            write_count: 0,
+            read_length: 5000,
+            read_count: 20,
+            runtime: u64::MAX,
+        };
+        assert_eq!(
+            ConversationHttp::readonly_call_budget(Some(&requested), &options),
+            ExecutionCost {
+                write_length: 0,
+                write_count: 0,
+                read_length: 1000,
+                read_count: 20,
+                runtime: 10000,
+            }
+        );
+
+        // responses report the cost consumed
+        let response = CallReadOnlyResponse {
+            okay: true,
+            result: Some("0x03".to_string()),
+            cause: None,
+            budget_exceeded: None,
+            cost: Some(requested.clone()),
+        };
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CallReadOnlyResponse>(&json).unwrap(),
+            response
+        );
+    }
+
+    #[test]
+    fn test_readonly_call_budget_default() {
+        let options = ConnectionOptions::default();
+
+        // by default, a caller can ask for more than the default limit...
+        let requested = ExecutionCost {
+            write_length: 0,
+            write_count: 0,
+            read_length: options.read_only_call_limit.read_length * 10,
+            read_count: options.read_only_call_limit.read_count * 10,
+            runtime: options.read_only_call_limit.runtime * 2,
+        };
+        assert_eq!(
+            ConversationHttp::readonly_call_budget(Some(&requested), &options),
+            requested
+        );
+
+        // ...but no more than a block's worth of reads and runtime
+        let requested = ExecutionCost::max_value();
+        assert_eq!(
+            ConversationHttp::readonly_call_budget(Some(&requested), &options),
+            ExecutionCost {
+                write_length: 0,
+                write_count: 0,
+                read_length: BLOCK_LIMIT_MAINNET_205.read_length,
+                read_count: BLOCK_LIMIT_MAINNET_205.read_count,
+                runtime: BLOCK_LIMIT_MAINNET_205.runtime,
+            }
+        );
+    }
+
+    #[test]
+    fn test_check_transaction_batch_nonces() {
+        let alice = StacksPrivateKey::new();
+        let bob = StacksPrivateKey::new();
+        let recipient: PrincipalData = StacksAddress {
+            version: C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            bytes: Hash160([0xff; 20]),
+        }
+        .into();
+        let transfer = |sender: &StacksPrivateKey, nonce: u64| {
+            make_user_stacks_transfer(sender, nonce, 1_000, &recipient, 100)
+        };
+        let check = |txs: &[StacksTransaction]| {
+            ConversationHttp::check_transaction_batch_nonces(txs).map_err(|error_json| {
+                (
+                    error_json["txid"].as_str().unwrap().to_string(),
+                    error_json["reason_data"]["expected"].as_u64().unwrap(),
+                    error_json["reason_data"]["actual"].as_u64().unwrap(),
+                )
+            })
+        };
+
+        // each origin's transactions must be consecutive, but origins may interleave, and the
+        // first nonce of each origin is not constrained
+        check(&[transfer(&alice, 3), transfer(&bob, 0), transfer(&alice, 4)]).unwrap();
+        check(&[]).unwrap();
+
+        // out of order
+        let txs = [transfer(&alice, 1), transfer(&alice, 0)];
+        assert_eq!(check(&txs), Err((txs[1].txid().to_hex(), 2, 0)));
+
+        // a gap
+        let txs = [
+            transfer(&alice, 0),
+            transfer(&bob, 5),
+            transfer(&alice, 1),
+            transfer(&bob, 7),
+        ];
+        assert_eq!(check(&txs), Err((txs[3].txid().to_hex(), 6, 7)));
+
+        // a repeated nonce
+        let txs = [transfer(&bob, 2), transfer(&bob, 2)];
+        assert_eq!(check(&txs), Err((txs[1].txid().to_hex(), 3, 2)));
+    }
 }
@@ -73,6 +73,9 @@ pub struct HttpPeer {
     // server network handle
     pub http_server_handle: usize,
 
+    // admin server network handle, if the admin listener is bound
+    pub admin_server_handle: Option<usize>,
+
     // connection options
     pub connection_opts: ConnectionOptions,
 }
@@ -85,6 +88,7 @@ impl HttpPeer {
 
             connecting: HashMap::new(),
             http_server_handle: server_handle,
+            admin_server_handle: None,
 
             connection_opts: conn_opts,
         }
@@ -94,6 +98,10 @@ impl HttpPeer {
         self.http_server_handle = h;
     }
 
+    pub fn set_admin_server_handle(&mut self, h: usize) -> () {
+        self.admin_server_handle = Some(h);
+    }
+
     /// Is there a HTTP conversation open to this data_url that is not in progress?
     pub fn find_free_conversation(&self, data_url: &UrlString) -> Option<usize> {
         for (event_id, convo) in self.peers.iter() {
@@ -367,7 +375,8 @@ impl HttpPeer {
         Ok(())
     }
 
-    /// Process new inbound HTTP connections we just accepted.
+    /// Process new inbound HTTP connections we just accepted on the listener `server_handle`.
+    /// Conversations accepted on the admin listener are marked as such.
     /// Returns the event IDs of sockets we need to register
     fn process_new_sockets(
         &mut self,
@@ -375,15 +384,14 @@ impl HttpPeer {
         mempool: &MemPoolDB,
         chainstate: &mut StacksChainState,
         poll_state: &mut NetworkPollState,
+        server_handle: usize,
+        on_admin_listener: bool,
     ) -> Result<Vec<usize>, net_error> {
         let mut registered = vec![];
 
         for (hint_event_id, client_sock) in poll_state.new.drain() {
-            let event_id = match network_state.register(
-                self.http_server_handle,
-                hint_event_id,
-                &client_sock,
-            ) {
+            let event_id = match network_state.register(server_handle, hint_event_id, &client_sock)
+            {
                 Ok(event_id) => event_id,
                 Err(e) => {
                     warn!(
@@ -417,6 +425,11 @@ impl HttpPeer {
                 // NOTE: register_http will deregister the socket for us
                 continue;
             }
+            if on_admin_listener {
+                if let Some(convo) = self.peers.get_mut(&event_id) {
+                    convo.set_on_admin_listener();
+                }
+            }
             registered.push(event_id);
         }
 
@@ -679,10 +692,35 @@ impl HttpPeer {
         chainstate: &mut StacksChainState,
         mempool: &mut MemPoolDB,
         mut poll_state: NetworkPollState,
+        admin_poll_state: Option<NetworkPollState>,
         handler_args: &RPCHandlerArgs,
     ) -> Result<Vec<StacksMessageType>, net_error> {
         // set up new inbound conversations
-        self.process_new_sockets(network_state, mempool, chainstate, &mut poll_state)?;
+        let server_handle = self.http_server_handle;
+        self.process_new_sockets(
+            network_state,
+            mempool,
+            chainstate,
+            &mut poll_state,
+            server_handle,
+            false,
+        )?;
+
+        // set up new inbound admin conversations.  Once set up, they are driven like any other
+        // conversation.
+        if let (Some(admin_server_handle), Some(mut admin_poll_state)) =
+            (self.admin_server_handle, admin_poll_state)
+        {
+            self.process_new_sockets(
+                network_state,
+                mempool,
+                chainstate,
+                &mut admin_poll_state,
+                admin_server_handle,
+                true,
+            )?;
+            poll_state.ready.append(&mut admin_poll_state.ready);
+        }
 
         // set up connected sockets
         self.process_connecting_sockets(network_state, mempool, chainstate, &mut poll_state);
@@ -1220,6 +1258,7 @@ mod test {
                     ),
                     signed_contract_tx,
                     None,
+                    false,
                 );
                 request.metadata_mut().keep_alive = false;
 
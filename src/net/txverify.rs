@@ -0,0 +1,222 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+
+use crate::chainstate::stacks::StacksTransaction;
+
+/// A signature-verification job: the transaction's position in the batch, and the transaction.
+type TxVerifyJob = (usize, StacksTransaction);
+
+/// A signature-verification result: the transaction's position in the batch, the transaction, and
+/// whether or not its signatures are valid.
+type TxVerifyResult = (usize, StacksTransaction, bool);
+
+/// Pool of worker threads that check the signatures of transactions pushed to us by peers.  This
+/// runs before the relayer stores them, so that invalid transactions are dropped without ever
+/// taking the mempool's DB locks.
+#[derive(Debug)]
+pub struct TxVerifierPool {
+    job_tx: Option<Sender<TxVerifyJob>>,
+    result_rx: Receiver<TxVerifyResult>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl TxVerifierPool {
+    /// Start a pool with `num_workers` threads.  If `num_workers` is 0, transactions are verified
+    /// on the calling thread instead.
+    pub fn new(num_workers: usize) -> TxVerifierPool {
+        let (job_tx, job_rx) = channel::<TxVerifyJob>();
+        let (result_tx, result_rx) = channel::<TxVerifyResult>();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+
+        let mut workers = Vec::with_capacity(num_workers);
+        for i in 0..num_workers {
+            let job_rx = job_rx.clone();
+            let result_tx = result_tx.clone();
+            let worker = thread::Builder::new()
+                .name(format!("tx-verifier-{}", i))
+                .spawn(move || TxVerifierPool::worker_main(job_rx, result_tx))
+                .expect("FATAL: failed to start transaction verifier thread");
+            workers.push(worker);
+        }
+
+        TxVerifierPool {
+            job_tx: Some(job_tx),
+            result_rx,
+            workers,
+        }
+    }
+
+    fn worker_main(job_rx: Arc<Mutex<Receiver<TxVerifyJob>>>, result_tx: Sender<TxVerifyResult>) {
+        loop {
+            let next_job = match job_rx.lock() {
+                Ok(rx) => rx.recv(),
+                Err(_) => {
+                    return;
+                }
+            };
+            let (index, tx) = match next_job {
+                Ok(job) => job,
+                Err(_) => {
+                    // pool is shutting down
+                    return;
+                }
+            };
+            let valid = tx.verify().is_ok();
+            if result_tx.send((index, tx, valid)).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// How many worker threads does this pool have?
+    pub fn num_workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Check the signatures of a batch of transactions.  Returns each transaction along with its
+    /// position in `txs` and whether or not it is valid, in the same order as `txs`.
+    pub fn verify_all(&self, txs: Vec<StacksTransaction>) -> Vec<TxVerifyResult> {
+        let job_tx = match self.job_tx {
+            Some(ref job_tx) if self.workers.len() > 0 => job_tx,
+            _ => {
+                return txs
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, tx)| {
+                        let valid = tx.verify().is_ok();
+                        (i, tx, valid)
+                    })
+                    .collect();
+            }
+        };
+
+        let mut num_jobs = 0;
+        for (i, tx) in txs.into_iter().enumerate() {
+            if let Err(e) = job_tx.send((i, tx)) {
+                warn!("Failed to send transaction to verifier thread: {:?}", &e);
+                continue;
+            }
+            num_jobs += 1;
+        }
+
+        let mut results = Vec::with_capacity(num_jobs);
+        for _ in 0..num_jobs {
+            match self.result_rx.recv() {
+                Ok(result) => {
+                    results.push(result);
+                }
+                Err(e) => {
+                    warn!("Transaction verifier threads are gone: {:?}", &e);
+                    break;
+                }
+            }
+        }
+
+        results.sort_by_key(|(i, _, _)| *i);
+        results
+    }
+}
+
+impl Drop for TxVerifierPool {
+    fn drop(&mut self) {
+        // closing the job channel makes each worker exit
+        self.job_tx.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chainstate::stacks::*;
+    use crate::types::chainstate::StacksAddress;
+    use stacks_common::util::hash::Hash160;
+    use stacks_common::util::secp256k1::Secp256k1PrivateKey;
+
+    fn make_transfers(num_txs: u64) -> Vec<StacksTransaction> {
+        let privk = Secp256k1PrivateKey::new();
+        let recipient = StacksAddress {
+            version: 1,
+            bytes: Hash160([0xff; 20]),
+        };
+        let mut txs = vec![];
+        for nonce in 0..num_txs {
+            let mut tx = StacksTransaction::new(
+                TransactionVersion::Testnet,
+                TransactionAuth::from_p2pkh(&privk).unwrap(),
+                TransactionPayload::TokenTransfer(
+                    recipient.clone().into(),
+                    123,
+                    TokenTransferMemo([0u8; 34]),
+                ),
+            );
+            tx.chain_id = 0x80000000;
+            tx.auth.set_origin_nonce(nonce);
+            tx.set_tx_fee(1000);
+
+            let mut tx_signer = StacksTransactionSigner::new(&tx);
+            tx_signer.sign_origin(&privk).unwrap();
+            txs.push(tx_signer.get_tx().unwrap());
+        }
+        txs
+    }
+
+    fn check_verify_all(pool: &TxVerifierPool) {
+        let mut txs = make_transfers(16);
+
+        // invalidate every third transaction's signature by altering it after signing
+        for (i, tx) in txs.iter_mut().enumerate() {
+            if i % 3 == 0 {
+                tx.set_tx_fee(1001);
+            }
+        }
+        let txids: Vec<_> = txs.iter().map(|tx| tx.txid()).collect();
+
+        let results = pool.verify_all(txs);
+        assert_eq!(results.len(), txids.len());
+        for (i, (index, tx, valid)) in results.into_iter().enumerate() {
+            assert_eq!(index, i);
+            assert_eq!(tx.txid(), txids[i]);
+            assert_eq!(valid, i % 3 != 0);
+        }
+
+        assert_eq!(pool.verify_all(vec![]).len(), 0);
+    }
+
+    #[test]
+    fn test_tx_verifier_pool() {
+        let pool = TxVerifierPool::new(4);
+        assert_eq!(pool.num_workers(), 4);
+        check_verify_all(&pool);
+
+        // pool can be reused
+        check_verify_all(&pool);
+    }
+
+    #[test]
+    fn test_tx_verifier_pool_inline() {
+        let pool = TxVerifierPool::new(0);
+        assert_eq!(pool.num_workers(), 0);
+        check_verify_all(&pool);
+    }
+}
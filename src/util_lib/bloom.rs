@@ -356,7 +356,7 @@ pub struct BloomCounter<H: BloomHash + Clone + StacksMessageCodec> {
 impl<H: BloomHash + Clone + StacksMessageCodec> BloomCounter<H> {
     /// Make a new bloom counter with the given error rate and expected number of items
     pub fn new(
-        tx: &mut DBTx,
+        tx: &DBTx,
         table_name: &str,
         error_rate: f64,
         max_items: u32,
@@ -421,6 +421,32 @@ impl<H: BloomHash + Clone + StacksMessageCodec> BloomCounter<H> {
         self.hasher.get_seed()
     }
 
+    pub fn num_bins(&self) -> u32 {
+        self.num_bins
+    }
+
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Empty the bloom counter, and resize it for the given error rate and expected number of
+    /// items.  The hasher is kept.
+    pub fn reset(&mut self, tx: &DBTx, error_rate: f64, max_items: u32) -> Result<(), db_error> {
+        let (num_bits, num_hashes) = bloom_hash_count(error_rate, max_items);
+        let counts_vec = vec![0u8; (num_bits * 4) as usize];
+
+        let sql = format!(
+            "UPDATE {} SET counts = ?1, num_bins = ?2, num_hashes = ?3 WHERE rowid = ?4",
+            &self.table_name
+        );
+        let args: &[&dyn ToSql] = &[&counts_vec, &num_bits, &num_hashes, &self.counts_rowid];
+        tx.execute(&sql, args).map_err(db_error::SqliteError)?;
+
+        self.num_bins = num_bits;
+        self.num_hashes = num_hashes;
+        Ok(())
+    }
+
     /// Get a handle to the underlying bins list
     fn open_counts_blob<'a>(
         &self,
@@ -1013,4 +1039,35 @@ pub mod test {
         }
         tx.commit().unwrap();
     }
+
+    #[test]
+    fn test_bloom_counter_reset() {
+        let mut db = setup_bloom_counter("reset");
+        let hasher = BloomNodeHasher::new(&[0u8; 32]);
+
+        let mut tx = tx_begin_immediate(&mut db).unwrap();
+        let mut bc = BloomCounter::new(&mut tx, "bloom_counter", 0.001, 8192, hasher).unwrap();
+        bc.insert_raw(&mut tx, &[0x01; 32]).unwrap();
+        assert_eq!(bc.count_raw(&tx, &[0x01; 32]).unwrap(), 1);
+
+        bc.reset(&tx, 0.0001, 16384).unwrap();
+        assert_eq!(
+            (bc.num_bins(), bc.num_hashes()),
+            bloom_hash_count(0.0001, 16384)
+        );
+        assert_eq!(bc.count_raw(&tx, &[0x01; 32]).unwrap(), 0);
+        bc.insert_raw(&mut tx, &[0x02; 32]).unwrap();
+        tx.commit().unwrap();
+
+        // the resized counter is what gets loaded, with the same hasher
+        let loaded = BloomCounter::<BloomNodeHasher>::try_load(&db, "bloom_counter")
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded, bc);
+        assert_eq!(loaded.count_raw(&db, &[0x02; 32]).unwrap(), 1);
+        assert_eq!(
+            loaded.to_bloom_filter(&db).unwrap().bits.num_bits(),
+            bc.num_bins()
+        );
+    }
 }
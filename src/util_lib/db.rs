@@ -111,6 +111,12 @@ pub enum Error {
     OldSchema(u64),
     /// Database is too old for epoch
     TooOldForEpoch,
+    /// Database was instantiated with a different epoch schedule
+    EpochScheduleMismatch,
+    /// Database was instantiated with a different value of a consensus setting
+    GenesisSettingMismatch(String),
+    /// Database schema version is not one this node knows how to migrate from
+    UnsupportedSchema(String),
     /// Other error
     Other(String),
 }
@@ -136,6 +142,16 @@ impl fmt::Display for Error {
             Error::TooOldForEpoch => {
                 write!(f, "Database is not compatible with current system epoch")
             }
+            Error::EpochScheduleMismatch => {
+                write!(
+                    f,
+                    "Database was instantiated with a different epoch schedule"
+                )
+            }
+            Error::GenesisSettingMismatch(ref s) => {
+                write!(f, "Database was instantiated with a different {}", s)
+            }
+            Error::UnsupportedSchema(ref s) => write!(f, "Unsupported database schema: {}", s),
             Error::Other(ref s) => fmt::Display::fmt(s, f),
         }
     }
@@ -160,6 +176,9 @@ impl error::Error for Error {
             Error::IndexError(ref e) => Some(e),
             Error::OldSchema(ref _s) => None,
             Error::TooOldForEpoch => None,
+            Error::EpochScheduleMismatch => None,
+            Error::GenesisSettingMismatch(ref _s) => None,
+            Error::UnsupportedSchema(ref _s) => None,
             Error::Other(ref _s) => None,
         }
     }
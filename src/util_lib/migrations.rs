@@ -0,0 +1,284 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Schema migrations for the node's SQLite databases.
+//!
+//! Each database stamps the version of its schema into the database itself.  A database's
+//! `SchemaMigrations` lists the forward migrations that bring a database from the version it was
+//! instantiated with up to the latest version this node understands, so that a node can open a
+//! database written by an older release and upgrade it in place.  Databases written by a *newer*
+//! release are refused, since this node cannot know what changed.
+
+use std::fmt;
+
+use rusqlite::Connection;
+
+use crate::util_lib::db::DBTx;
+use crate::util_lib::db::Error as db_error;
+
+/// A single forward migration, which brings a schema from `to_version - 1` to `to_version`.
+pub struct SchemaMigration {
+    /// Schema version after this migration is applied
+    pub to_version: u32,
+    /// Human-readable summary of what the migration does
+    pub description: &'static str,
+    /// Apply the migration.  This does not need to stamp the new version.
+    pub apply: fn(&DBTx) -> Result<(), db_error>,
+}
+
+/// The version history of one database's schema.
+pub struct SchemaMigrations {
+    /// Name of the database, for logging
+    pub db_name: &'static str,
+    /// Version of the initial schema, before any migrations are applied
+    pub initial_version: u32,
+    /// Forward migrations, in order.  The i-th migration must migrate to `initial_version + i + 1`.
+    pub migrations: &'static [SchemaMigration],
+    /// Load the version stamped into the database
+    pub load_version: fn(&Connection) -> Result<u32, db_error>,
+    /// Stamp a new version into the database
+    pub store_version: fn(&DBTx, u32) -> Result<(), db_error>,
+}
+
+/// Result of checking a database's schema against the latest schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaStatus {
+    pub db_name: &'static str,
+    /// Version currently stamped into the database
+    pub current_version: u32,
+    /// Latest version this node understands
+    pub latest_version: u32,
+    /// Migrations that would be applied on the next open, as (to_version, description)
+    pub pending: Vec<(u32, &'static str)>,
+}
+
+impl SchemaStatus {
+    pub fn is_up_to_date(&self) -> bool {
+        self.pending.len() == 0
+    }
+}
+
+impl fmt::Display for SchemaStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_up_to_date() {
+            return write!(
+                f,
+                "{}: schema version {} is up to date",
+                self.db_name, self.current_version
+            );
+        }
+        write!(
+            f,
+            "{}: schema version {} will be migrated to version {}",
+            self.db_name, self.current_version, self.latest_version
+        )?;
+        for (to_version, description) in self.pending.iter() {
+            write!(f, "\n  -> {}: {}", to_version, description)?;
+        }
+        Ok(())
+    }
+}
+
+impl SchemaMigrations {
+    /// The latest schema version this node understands
+    pub fn latest_version(&self) -> u32 {
+        self.initial_version + (self.migrations.len() as u32)
+    }
+
+    /// Find the migrations that must be applied to a schema at `current_version`.
+    /// Fails if this node does not know how to migrate from `current_version` -- i.e. it predates
+    /// the initial schema, or it was written by a newer node.
+    pub fn pending_migrations(
+        &self,
+        current_version: u32,
+    ) -> Result<&'static [SchemaMigration], db_error> {
+        if current_version < self.initial_version || current_version > self.latest_version() {
+            return Err(db_error::UnsupportedSchema(format!(
+                "{} schema version {} is not between {} and {}",
+                self.db_name,
+                current_version,
+                self.initial_version,
+                self.latest_version()
+            )));
+        }
+        let start = (current_version - self.initial_version) as usize;
+        Ok(&self.migrations[start..])
+    }
+
+    /// Check the database's schema without changing it.
+    pub fn check(&self, conn: &Connection) -> Result<SchemaStatus, db_error> {
+        let current_version = (self.load_version)(conn)?;
+        let pending = self
+            .pending_migrations(current_version)?
+            .iter()
+            .map(|migration| (migration.to_version, migration.description))
+            .collect();
+
+        Ok(SchemaStatus {
+            db_name: self.db_name,
+            current_version,
+            latest_version: self.latest_version(),
+            pending,
+        })
+    }
+
+    /// Apply all pending migrations within the given transaction, stamping the new version after
+    /// each one.  Returns the version the database had beforehand.
+    pub fn migrate(&self, tx: &DBTx) -> Result<u32, db_error> {
+        let prior_version = (self.load_version)(tx)?;
+        for migration in self.pending_migrations(prior_version)?.iter() {
+            info!(
+                "Migrating {} schema from version {} to {}: {}",
+                self.db_name,
+                migration.to_version - 1,
+                migration.to_version,
+                migration.description
+            );
+            (migration.apply)(tx)?;
+            (self.store_version)(tx, migration.to_version)?;
+        }
+        Ok(prior_version)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rusqlite::NO_PARAMS;
+
+    use crate::util_lib::db::tx_begin_immediate;
+
+    fn load_test_version(conn: &Connection) -> Result<u32, db_error> {
+        let version: u32 =
+            conn.query_row("SELECT version FROM db_config", NO_PARAMS, |row| row.get(0))?;
+        Ok(version)
+    }
+
+    fn store_test_version(tx: &DBTx, version: u32) -> Result<(), db_error> {
+        tx.execute("UPDATE db_config SET version = ?1", &[&version])?;
+        Ok(())
+    }
+
+    fn add_foo(tx: &DBTx) -> Result<(), db_error> {
+        tx.execute_batch("CREATE TABLE foo(a INTEGER NOT NULL);")?;
+        Ok(())
+    }
+
+    fn add_foo_b(tx: &DBTx) -> Result<(), db_error> {
+        tx.execute_batch("ALTER TABLE foo ADD COLUMN b INTEGER;")?;
+        Ok(())
+    }
+
+    const TEST_MIGRATIONS: SchemaMigrations = SchemaMigrations {
+        db_name: "test",
+        initial_version: 1,
+        migrations: &[
+            SchemaMigration {
+                to_version: 2,
+                description: "add foo",
+                apply: add_foo,
+            },
+            SchemaMigration {
+                to_version: 3,
+                description: "add foo.b",
+                apply: add_foo_b,
+            },
+        ],
+        load_version: load_test_version,
+        store_version: store_test_version,
+    };
+
+    fn make_test_db(version: u32) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE db_config(version INTEGER NOT NULL);")
+            .unwrap();
+        conn.execute("INSERT INTO db_config (version) VALUES (?1)", &[&version])
+            .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_migrate_from_initial_version() {
+        let mut conn = make_test_db(1);
+
+        let status = TEST_MIGRATIONS.check(&conn).unwrap();
+        assert_eq!(status.current_version, 1);
+        assert_eq!(status.latest_version, 3);
+        assert_eq!(status.pending, vec![(2, "add foo"), (3, "add foo.b")]);
+        assert!(!status.is_up_to_date());
+
+        let tx = tx_begin_immediate(&mut conn).unwrap();
+        assert_eq!(TEST_MIGRATIONS.migrate(&tx).unwrap(), 1);
+        tx.commit().unwrap();
+
+        conn.execute("INSERT INTO foo (a, b) VALUES (1, 2)", NO_PARAMS)
+            .unwrap();
+        let status = TEST_MIGRATIONS.check(&conn).unwrap();
+        assert_eq!(status.current_version, 3);
+        assert!(status.is_up_to_date());
+
+        // migrating again is a no-op
+        let tx = tx_begin_immediate(&mut conn).unwrap();
+        assert_eq!(TEST_MIGRATIONS.migrate(&tx).unwrap(), 3);
+        tx.commit().unwrap();
+    }
+
+    #[test]
+    fn test_migrate_from_intermediate_version() {
+        let mut conn = make_test_db(2);
+        conn.execute_batch("CREATE TABLE foo(a INTEGER NOT NULL);")
+            .unwrap();
+
+        let status = TEST_MIGRATIONS.check(&conn).unwrap();
+        assert_eq!(status.pending, vec![(3, "add foo.b")]);
+
+        let tx = tx_begin_immediate(&mut conn).unwrap();
+        assert_eq!(TEST_MIGRATIONS.migrate(&tx).unwrap(), 2);
+        tx.commit().unwrap();
+        assert_eq!(load_test_version(&conn).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_migrate_failure_rolls_back() {
+        let mut conn = make_test_db(1);
+        // migration to version 2 will fail, since foo already exists
+        conn.execute_batch("CREATE TABLE foo(c INTEGER);").unwrap();
+
+        let tx = tx_begin_immediate(&mut conn).unwrap();
+        assert!(TEST_MIGRATIONS.migrate(&tx).is_err());
+        drop(tx);
+
+        assert_eq!(load_test_version(&conn).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_unsupported_versions() {
+        for version in [0, 4].iter() {
+            let mut conn = make_test_db(*version);
+            match TEST_MIGRATIONS.check(&conn) {
+                Err(db_error::UnsupportedSchema(_)) => {}
+                res => panic!("Expected UnsupportedSchema, got {:?}", &res),
+            }
+
+            let tx = tx_begin_immediate(&mut conn).unwrap();
+            match TEST_MIGRATIONS.migrate(&tx) {
+                Err(db_error::UnsupportedSchema(_)) => {}
+                res => panic!("Expected UnsupportedSchema, got {:?}", &res),
+            }
+        }
+    }
+}
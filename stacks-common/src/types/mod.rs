@@ -63,13 +63,16 @@ pub trait Address: Clone + fmt::Debug + fmt::Display {
 pub const PEER_VERSION_EPOCH_1_0: u8 = 0x00;
 pub const PEER_VERSION_EPOCH_2_0: u8 = 0x00;
 pub const PEER_VERSION_EPOCH_2_05: u8 = 0x05;
+pub const PEER_VERSION_EPOCH_SUBNET_1: u8 = 0x06;
 
 #[repr(u32)]
-#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Copy, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Copy, Serialize, Deserialize)]
 pub enum StacksEpochId {
     Epoch10 = 0x01000,
     Epoch20 = 0x02000,
     Epoch2_05 = 0x02005,
+    /// The first subnet-only epoch, which charges for event emission
+    EpochSubnet1 = 0x02100,
 }
 
 impl std::fmt::Display for StacksEpochId {
@@ -78,6 +81,7 @@ impl std::fmt::Display for StacksEpochId {
             StacksEpochId::Epoch10 => write!(f, "1.0"),
             StacksEpochId::Epoch20 => write!(f, "2.0"),
             StacksEpochId::Epoch2_05 => write!(f, "2.05"),
+            StacksEpochId::EpochSubnet1 => write!(f, "subnet-1"),
         }
     }
 }
@@ -90,6 +94,7 @@ impl TryFrom<u32> for StacksEpochId {
             x if x == StacksEpochId::Epoch10 as u32 => Ok(StacksEpochId::Epoch10),
             x if x == StacksEpochId::Epoch20 as u32 => Ok(StacksEpochId::Epoch20),
             x if x == StacksEpochId::Epoch2_05 as u32 => Ok(StacksEpochId::Epoch2_05),
+            x if x == StacksEpochId::EpochSubnet1 as u32 => Ok(StacksEpochId::EpochSubnet1),
             _ => Err("Invalid epoch"),
         }
     }
@@ -217,7 +222,7 @@ impl Address for StacksAddress {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct StacksEpoch<L> {
     pub epoch_id: StacksEpochId,
     pub start_height: u64,
@@ -410,11 +410,21 @@ where
             return MerkleTree { nodes: vec![] };
         }
 
-        let mut leaf_hashes: Vec<H> = data
+        let leaf_hashes: Vec<H> = data
             .iter()
             .map(|buf| MerkleTree::get_leaf_hash(&buf[..]))
             .collect();
 
+        MerkleTree::from_leaf_hashes(leaf_hashes)
+    }
+
+    /// Build a tree whose leaves are the given hashes, which are used as-is rather than hashed
+    /// with the leaf tag.  This lets the roots of other Merkle trees be committed to as leaves.
+    pub fn from_leaf_hashes(mut leaf_hashes: Vec<H>) -> MerkleTree<H> {
+        if leaf_hashes.len() == 0 {
+            return MerkleTree { nodes: vec![] };
+        }
+
         // force even number
         if leaf_hashes.len() % 2 != 0 {
             let dup = leaf_hashes[leaf_hashes.len() - 1].clone();
@@ -546,6 +556,12 @@ where
         }
     }
 
+    /// Get the number of leaves in the tree.
+    /// this includes the duplicate leaf that was appended to pad an odd number of leaves.
+    pub fn num_leaves(&self) -> usize {
+        self.nodes.get(0).map(|leaves| leaves.len()).unwrap_or(0)
+    }
+
     /// Get the path from the given data's leaf up to the root.
     /// will be None if the data isn't a leaf.
     pub fn path(&self, data: &[u8]) -> Option<MerklePath<H>> {
@@ -585,6 +601,51 @@ where
         Some(path)
     }
 
+    /// Get the hash of the leaf at the given index.
+    /// will be None if the index is out of range, or if it refers to the duplicate
+    /// leaf that was appended to pad an odd number of leaves.
+    pub fn leaf_at_index(&self, leaf_index: usize) -> Option<H> {
+        let leaves = self.nodes.get(0)?;
+        let leaf = leaves.get(leaf_index)?;
+        if leaf_index > 0 && leaf_index % 2 == 1 && leaf_index == leaves.len() - 1 {
+            // the last leaf of an even-length row is padding if it duplicates its sibling
+            if leaves[leaf_index - 1] == *leaf {
+                return None;
+            }
+        }
+        Some(leaf.clone())
+    }
+
+    /// Get the path from the leaf at the given index up to the root.
+    /// will be None if there is no leaf at that index (see `leaf_at_index()`).
+    pub fn path_at_index(&self, leaf_index: usize) -> Option<MerklePath<H>> {
+        self.leaf_at_index(leaf_index)?;
+
+        let mut path: MerklePath<H> = vec![];
+        path.reserve(self.nodes.len());
+
+        let mut hash_index = leaf_index;
+        for i in 0..self.nodes.len() - 1 {
+            let (left, right) = self.find_siblings(i, hash_index);
+            if hash_index % 2 == 0 {
+                // this is the left hash
+                path.push(MerklePathPoint {
+                    order: MerklePathOrder::Left,
+                    hash: right,
+                });
+            } else {
+                // this is the right hash
+                path.push(MerklePathPoint {
+                    order: MerklePathOrder::Right,
+                    hash: left,
+                });
+            }
+            hash_index /= 2;
+        }
+
+        Some(path)
+    }
+
     /// Verify a datum and its Merkle path against a Merkle root
     pub fn path_verify(data: &Vec<u8>, path: &MerklePath<H>, root: &H) -> bool {
         if path.len() < 1 {
@@ -809,12 +870,22 @@ mod test {
                     assert_eq!(tree.root(), DoubleSha256::empty());
                 }
 
-                for d in fixture.data {
+                for (i, d) in fixture.data.iter().enumerate() {
                     let path = tree.path(&d).unwrap();
                     assert_eq!(path.len(), tree.nodes.len() - 1);
                     assert!(MerkleTree::path_verify(&d, &path, &tree.root()));
+
+                    assert_eq!(
+                        tree.leaf_at_index(i),
+                        Some(MerkleTree::<DoubleSha256>::get_leaf_hash(&d))
+                    );
+                    assert_eq!(tree.path_at_index(i), Some(path));
                 }
 
+                // padding leaves and out-of-range indexes have no path
+                assert!(tree.leaf_at_index(fixture.data.len()).is_none());
+                assert!(tree.path_at_index(fixture.data.len()).is_none());
+
                 if nodes.len() > 0 {
                     let no_path = tree.path(&hex_bytes("012345").unwrap());
                     assert!(no_path.is_none());
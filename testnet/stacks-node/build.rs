@@ -0,0 +1,5 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/subnet_events.proto")
+        .expect("FATAL: failed to compile the gRPC protobuf definitions");
+}
@@ -1,3 +1,6 @@
+use std::sync::Arc;
+use std::time::Duration;
+
 use reqwest::StatusCode;
 use serde_json::json;
 use stacks::address::AddressHashMode;
@@ -7,6 +10,7 @@ use stacks::chainstate::stacks::{
     TransactionContractCall, TransactionPostConditionMode, TransactionSpendingCondition,
     TransactionVersion,
 };
+use stacks::monitoring;
 use stacks::net::http::HttpBlockProposalRejected;
 use stacks::net::RPCFeeEstimateResponse;
 use stacks::util::hash::hex_bytes;
@@ -18,6 +22,7 @@ use stacks_common::util::hash::{to_hex, Sha512Trunc256Sum};
 
 use crate::config::BurnchainConfig;
 use crate::operations::BurnchainOpSigner;
+use crate::run_loop::l1_observer::L1RpcEndpoints;
 use crate::stacks_common::codec::StacksMessageCodec;
 
 use super::ClaritySignature;
@@ -28,15 +33,9 @@ const DEFAULT_MINER_COMMITMENT_FEE: u64 = 100_000u64;
 pub trait Layer1Committer {
     /// Return the number of signatures that need to be included alongside a commit transaction
     fn commit_required_signatures(&self) -> u8;
-    /// Send a block proposal to the participant indicated by `participant_index`.
-    /// If `participant_index >= (self.commit_required_signatures - 1)`, this will return an error because:
-    ///   * It refers to a non-existent participant (if >= self.commit_required_signatures)
-    ///   * Or it refers to the current miner (which is logically = index self.commit_required_signatures - 1)
-    fn propose_block_to(
-        &self,
-        participant_index: u8,
-        proposal: &Proposal,
-    ) -> Result<ClaritySignature, Error>;
+    /// Return a handle for sending block proposals to the other participants, or None if
+    /// commits need no signatures.
+    fn block_proposer(&self) -> Option<BlockProposer>;
     fn make_commit_tx(
         &self,
         committed_block_hash: BlockHeaderHash,
@@ -46,10 +45,21 @@ pub trait Layer1Committer {
         attempt: u64,
         op_signer: &mut BurnchainOpSigner,
     ) -> Result<StacksTransaction, Error>;
+    /// Make the L1 contract-call, signed by the current miner, that schedules the rotation of
+    /// the subnet miner to `next_miner` from L1 block `activation_height` on.  The call's nonce
+    /// is at least `min_nonce`, so that it does not replace a pending block commit.
+    fn make_miner_rotation_tx(
+        &self,
+        next_miner: &StacksAddress,
+        activation_height: u64,
+        min_nonce: u64,
+        op_signer: &mut BurnchainOpSigner,
+    ) -> Result<StacksTransaction, Error>;
 }
 
 pub struct DirectCommitter {
     pub config: BurnchainConfig,
+    pub l1_rpc: Arc<L1RpcEndpoints>,
 }
 
 #[derive(Clone, Debug)]
@@ -58,8 +68,16 @@ pub struct MultiMinerParticipant {
     pub rpc_server: String,
 }
 
+/// Sends block proposals to the other participants of a multi-miner subnet. It can be shared
+/// between threads, so that each participant can be asked from its own thread.
+#[derive(Clone, Debug)]
+pub struct BlockProposer {
+    other_participants: Vec<MultiMinerParticipant>,
+}
+
 pub struct MultiPartyCommitter {
     pub config: BurnchainConfig,
+    l1_rpc: Arc<L1RpcEndpoints>,
     other_participants: Vec<MultiMinerParticipant>,
     required_signers: u8,
     contract: QualifiedContractIdentifier,
@@ -82,9 +100,10 @@ pub enum Error {
     NoSuchParticipant,
     BlockProposalRequest(String),
     BlockProposalRejected(String),
+    MinerRotationUnsupported,
 }
 
-fn l1_addr_from_signer(is_mainnet: bool, signer: &BurnchainOpSigner) -> StacksAddress {
+pub fn l1_addr_from_signer(is_mainnet: bool, signer: &BurnchainOpSigner) -> StacksAddress {
     let hash_mode = AddressHashMode::SerializeP2PKH;
     let addr_version = if is_mainnet {
         hash_mode.to_version_mainnet()
@@ -95,13 +114,19 @@ fn l1_addr_from_signer(is_mainnet: bool, signer: &BurnchainOpSigner) -> StacksAd
         .expect("Failed to make Stacks address from public key")
 }
 
-fn l1_get_nonce(l1_rpc_interface: &str, address: &StacksAddress) -> Result<u64, Error> {
-    let url = format!("{}/v2/accounts/{}?proof=0", l1_rpc_interface, address);
-    let response_json: RpcAccountResponse = reqwest::blocking::get(url)
-        .map_err(|e| Error::NonceGetFailure(e.to_string()))?
-        .json()
-        .map_err(|e| Error::NonceGetFailure(e.to_string()))?;
-    Ok(response_json.nonce)
+fn l1_get_nonce(l1_rpc: &L1RpcEndpoints, address: &StacksAddress) -> Result<u64, Error> {
+    l1_rpc.request(|l1_rpc_interface| {
+        let url = format!("{}/v2/accounts/{}?proof=0", l1_rpc_interface, address);
+        let response_json: Result<RpcAccountResponse, _> =
+            reqwest::blocking::get(url).and_then(|response| response.json());
+        match response_json {
+            Ok(response_json) => Ok(Ok(response_json.nonce)),
+            Err(e) => {
+                monitoring::increment_l1_rpc_failures_counter("get_nonce");
+                Err(Error::NonceGetFailure(e.to_string()))
+            }
+        }
+    })
 }
 
 /// Compute an effective fee to use, based on a transaction, and response scalars. Use the equation:
@@ -171,20 +196,27 @@ pub enum FeeCalculationError {
 /// if it exists, or else return None.
 fn calculate_l1_fee_for_transaction(
     transaction: &StacksTransaction,
-    http_origin: &str,
+    l1_rpc: &L1RpcEndpoints,
 ) -> Result<u64, FeeCalculationError> {
     // query L1 for an estimate response
     let client = reqwest::blocking::Client::new();
-    let path = format!("{}/v2/fees/transaction", &http_origin);
     let payload_data = transaction.payload.serialize_to_vec();
     let payload_hex = format!("0x{}", to_hex(&payload_data));
     let body = json!({ "transaction_payload": payload_hex.clone() });
-    let res = client.post(&path).json(&body).send().map_err(|e| {
-        warn!("Error getting response from L1 about fee rate: {:?}", &e);
-        FeeCalculationError::L1ResponseFailure
+    let json_response = l1_rpc.request(|http_origin| {
+        let path = format!("{}/v2/fees/transaction", http_origin);
+        let res = client.post(&path).json(&body).send().map_err(|e| {
+            warn!("Error getting response from L1 about fee rate: {:?}", &e);
+            monitoring::increment_l1_rpc_failures_counter("fee_estimate");
+            FeeCalculationError::L1ResponseFailure
+        })?;
+        let json_response: reqwest::Result<RPCFeeEstimateResponse> =
+            res.json::<RPCFeeEstimateResponse>();
+        if json_response.is_err() {
+            monitoring::increment_l1_rpc_failures_counter("fee_estimate");
+        }
+        Ok(Ok(json_response))
     })?;
-    let json_response: reqwest::Result<RPCFeeEstimateResponse> =
-        res.json::<RPCFeeEstimateResponse>();
 
     // parse the response and calculate a fee
     compute_fee_from_response_and_transaction(transaction, &json_response)
@@ -206,6 +238,73 @@ impl std::fmt::Display for Error {
                 f,
                 "Participant index refers to a non-existent participant or the current node (self)"
             ),
+            Error::MinerRotationUnsupported => {
+                write!(f, "Miner rotation is not supported by this commit strategy")
+            }
+        }
+    }
+}
+
+impl BlockProposer {
+    /// Send a block proposal to the participant indicated by `participant_index`, waiting at
+    /// most `timeout` for a response. `participant_index` indexes the *other* participants, so
+    /// this will return an error if it is >= the number of other participants.
+    pub fn propose_block_to(
+        &self,
+        participant_index: u8,
+        proposal: &Proposal,
+        timeout: Duration,
+    ) -> Result<ClaritySignature, Error> {
+        let propose_to = self
+            .other_participants
+            .get(participant_index as usize)
+            .ok_or(Error::NoSuchParticipant)?;
+        let url = format!(
+            "{}{}",
+            &propose_to.rpc_server,
+            stacks::net::http::PATH_STR_POST_BLOCK_PROPOSAL
+        );
+        let response = reqwest::blocking::Client::new()
+            .post(url)
+            .timeout(timeout)
+            .json(proposal)
+            .send()
+            .map_err(|e| Error::BlockProposalRequest(e.to_string()))?;
+        match response.status() {
+            StatusCode::OK => {
+                let signature_hex: String = response
+                    .json()
+                    .map_err(|e| Error::BlockProposalRequest(e.to_string()))?;
+                // 132 = 65 * 2 + "0x" prefix
+                if signature_hex.len() != 132 {
+                    return Err(Error::BlockProposalRequest(
+                        "Bad signature hex length".into(),
+                    ));
+                }
+
+                let signature_bytes = hex_bytes(&signature_hex[2..])
+                    .map_err(|_| Error::BlockProposalRequest("Bad hex bytes".into()))?;
+                if signature_bytes.len() != 65 {
+                    return Err(Error::BlockProposalRequest(
+                        "Bad signature byte length".into(),
+                    ));
+                }
+                let mut signature_buff = [0u8; 65];
+                signature_buff.copy_from_slice(&signature_bytes);
+                Ok(ClaritySignature(signature_buff))
+            }
+            StatusCode::NOT_ACCEPTABLE => {
+                let error_struct: HttpBlockProposalRejected = response
+                    .json()
+                    .map_err(|e| Error::BlockProposalRequest(e.to_string()))?;
+                Err(Error::BlockProposalRejected(error_struct.error_message))
+            }
+            _ => {
+                let error_message = response
+                    .text()
+                    .map_err(|e| Error::BlockProposalRequest(e.to_string()))?;
+                Err(Error::BlockProposalRequest(error_message))
+            }
         }
     }
 }
@@ -213,12 +312,14 @@ impl std::fmt::Display for Error {
 impl MultiPartyCommitter {
     pub fn new(
         config: &BurnchainConfig,
+        l1_rpc: Arc<L1RpcEndpoints>,
         required_signers: u8,
         contract: &QualifiedContractIdentifier,
         other_participants: Vec<MultiMinerParticipant>,
     ) -> Self {
         Self {
             config: config.clone(),
+            l1_rpc,
             required_signers,
             contract: contract.clone(),
             other_participants,
@@ -313,7 +414,7 @@ impl MultiPartyCommitter {
 
         // figure out the miner's nonce
         let miner_address = l1_addr_from_signer(self.config.is_mainnet(), op_signer);
-        let nonce = l1_get_nonce(&self.config.get_rpc_url(), &miner_address).map_err(|e| {
+        let nonce = l1_get_nonce(&self.l1_rpc, &miner_address).map_err(|e| {
             error!("Failed to obtain miner nonce: {}", e);
             e
         })?;
@@ -333,13 +434,12 @@ impl MultiPartyCommitter {
                 error!("Failed to construct contract call operation: {}", e);
                 e
             })?;
-        let computed_fee =
-            calculate_l1_fee_for_transaction(&pre_transaction, &self.config.get_rpc_url())
-                .map_err(|e| {
-                    error!("Failed to get L1 fee estimate: {:?}", &e);
-                    e
-                })
-                .unwrap_or(DEFAULT_MINER_COMMITMENT_FEE);
+        let computed_fee = calculate_l1_fee_for_transaction(&pre_transaction, &self.l1_rpc)
+            .map_err(|e| {
+                error!("Failed to get L1 fee estimate: {:?}", &e);
+                e
+            })
+            .unwrap_or(DEFAULT_MINER_COMMITMENT_FEE);
 
         // create the call
         self.make_mine_contract_call(
@@ -364,64 +464,13 @@ impl Layer1Committer for MultiPartyCommitter {
         self.required_signers.saturating_sub(1)
     }
 
-    fn propose_block_to(
-        &self,
-        participant_index: u8,
-        proposal: &Proposal,
-    ) -> Result<ClaritySignature, Error> {
-        if self.required_signers == 0
-            || participant_index >= self.required_signers - 1
-            || participant_index as usize >= self.other_participants.len()
-        {
-            return Err(Error::NoSuchParticipant);
-        }
-        let propose_to = &self.other_participants[participant_index as usize];
-        let url = format!(
-            "{}{}",
-            &propose_to.rpc_server,
-            stacks::net::http::PATH_STR_POST_BLOCK_PROPOSAL
-        );
-        let response = reqwest::blocking::Client::new()
-            .post(url)
-            .json(proposal)
-            .send()
-            .map_err(|e| Error::BlockProposalRequest(e.to_string()))?;
-        match response.status() {
-            StatusCode::OK => {
-                let signature_hex: String = response
-                    .json()
-                    .map_err(|e| Error::BlockProposalRequest(e.to_string()))?;
-                // 132 = 65 * 2 + "0x" prefix
-                if signature_hex.len() != 132 {
-                    return Err(Error::BlockProposalRequest(
-                        "Bad signature hex length".into(),
-                    ));
-                }
-
-                let signature_bytes = hex_bytes(&signature_hex[2..])
-                    .map_err(|_| Error::BlockProposalRequest("Bad hex bytes".into()))?;
-                if signature_bytes.len() != 65 {
-                    return Err(Error::BlockProposalRequest(
-                        "Bad signature byte length".into(),
-                    ));
-                }
-                let mut signature_buff = [0u8; 65];
-                signature_buff.copy_from_slice(&signature_bytes);
-                Ok(ClaritySignature(signature_buff))
-            }
-            StatusCode::NOT_ACCEPTABLE => {
-                let error_struct: HttpBlockProposalRejected = response
-                    .json()
-                    .map_err(|e| Error::BlockProposalRequest(e.to_string()))?;
-                Err(Error::BlockProposalRejected(error_struct.error_message))
-            }
-            _ => {
-                let error_message = response
-                    .text()
-                    .map_err(|e| Error::BlockProposalRequest(e.to_string()))?;
-                Err(Error::BlockProposalRequest(error_message))
-            }
+    fn block_proposer(&self) -> Option<BlockProposer> {
+        if self.required_signers == 0 {
+            return None;
         }
+        Some(BlockProposer {
+            other_participants: self.other_participants.clone(),
+        })
     }
 
     fn make_commit_tx(
@@ -442,6 +491,17 @@ impl Layer1Committer for MultiPartyCommitter {
             op_signer,
         )
     }
+
+    fn make_miner_rotation_tx(
+        &self,
+        _next_miner: &StacksAddress,
+        _activation_height: u64,
+        _min_nonce: u64,
+        _op_signer: &mut BurnchainOpSigner,
+    ) -> Result<StacksTransaction, Error> {
+        // the multi-miner contract, not an individual key, is the subnet contract's miner
+        Err(Error::MinerRotationUnsupported)
+    }
 }
 
 impl Layer1Committer for DirectCommitter {
@@ -467,12 +527,18 @@ impl Layer1Committer for DirectCommitter {
         )
     }
 
-    fn propose_block_to(
+    fn block_proposer(&self) -> Option<BlockProposer> {
+        None
+    }
+
+    fn make_miner_rotation_tx(
         &self,
-        _participant_index: u8,
-        _proposal: &Proposal,
-    ) -> Result<ClaritySignature, Error> {
-        Err(Error::NoSuchParticipant)
+        next_miner: &StacksAddress,
+        activation_height: u64,
+        min_nonce: u64,
+        op_signer: &mut BurnchainOpSigner,
+    ) -> Result<StacksTransaction, Error> {
+        self.make_miner_rotation_tx(next_miner, activation_height, min_nonce, op_signer)
     }
 }
 
@@ -545,7 +611,7 @@ impl DirectCommitter {
 
         // figure out the miner's nonce
         let miner_address = l1_addr_from_signer(self.config.is_mainnet(), op_signer);
-        let nonce = l1_get_nonce(&self.config.get_rpc_url(), &miner_address).map_err(|e| {
+        let nonce = l1_get_nonce(&self.l1_rpc, &miner_address).map_err(|e| {
             error!("Failed to obtain miner nonce: {}", e);
             e
         })?;
@@ -564,13 +630,12 @@ impl DirectCommitter {
                 error!("Failed to construct contract call operation: {}", e);
                 e
             })?;
-        let computed_fee =
-            calculate_l1_fee_for_transaction(&pre_transaction, &self.config.get_rpc_url())
-                .map_err(|e| {
-                    error!("Failed to get L1 fee estimate: {:?}", &e);
-                    e
-                })
-                .unwrap_or(DEFAULT_MINER_COMMITMENT_FEE);
+        let computed_fee = calculate_l1_fee_for_transaction(&pre_transaction, &self.l1_rpc)
+            .map_err(|e| {
+                error!("Failed to get L1 fee estimate: {:?}", &e);
+                e
+            })
+            .unwrap_or(DEFAULT_MINER_COMMITMENT_FEE);
 
         // create the call
         self.make_mine_contract_call(
@@ -586,4 +651,90 @@ impl DirectCommitter {
             e
         })
     }
+
+    fn make_rotate_contract_call(
+        &self,
+        sender: &StacksPrivateKey,
+        sender_nonce: u64,
+        tx_fee: u64,
+        next_miner: &StacksAddress,
+        activation_height: u64,
+    ) -> StacksTransaction {
+        let QualifiedContractIdentifier {
+            issuer: contract_addr,
+            name: contract_name,
+        } = self.config.contract_identifier.clone();
+        let version = if self.config.is_mainnet() {
+            TransactionVersion::Mainnet
+        } else {
+            TransactionVersion::Testnet
+        };
+        let payload = TransactionContractCall {
+            address: contract_addr.into(),
+            contract_name,
+            function_name: ClarityName::from("register-next-miner"),
+            function_args: vec![
+                ClarityValue::Principal(next_miner.clone().into()),
+                ClarityValue::UInt(activation_height.into()),
+            ],
+        };
+
+        let mut sender_spending_condition = TransactionSpendingCondition::new_singlesig_p2pkh(
+            StacksPublicKey::from_private(sender),
+        )
+        .expect("Failed to create p2pkh spending condition from public key.");
+        sender_spending_condition.set_nonce(sender_nonce);
+        sender_spending_condition.set_tx_fee(tx_fee);
+        let auth = TransactionAuth::Standard(sender_spending_condition);
+
+        let mut unsigned_tx = StacksTransaction::new(version, auth, payload.into());
+        unsigned_tx.anchor_mode = self.config.anchor_mode.clone();
+        unsigned_tx.post_condition_mode = TransactionPostConditionMode::Allow;
+        unsigned_tx.chain_id = self.config.chain_id;
+
+        let mut tx_signer = StacksTransactionSigner::new(&unsigned_tx);
+        tx_signer.sign_origin(sender).unwrap();
+
+        tx_signer
+            .get_tx()
+            .expect("Failed to get signed transaction from signer")
+    }
+
+    pub fn make_miner_rotation_tx(
+        &self,
+        next_miner: &StacksAddress,
+        activation_height: u64,
+        min_nonce: u64,
+        op_signer: &mut BurnchainOpSigner,
+    ) -> Result<StacksTransaction, Error> {
+        let miner_address = l1_addr_from_signer(self.config.is_mainnet(), op_signer);
+        let nonce = l1_get_nonce(&self.l1_rpc, &miner_address)
+            .map_err(|e| {
+                error!("Failed to obtain miner nonce: {}", e);
+                e
+            })?
+            .max(min_nonce);
+
+        let pre_transaction = self.make_rotate_contract_call(
+            op_signer.get_sk(),
+            nonce,
+            DEFAULT_MINER_COMMITMENT_FEE,
+            next_miner,
+            activation_height,
+        );
+        let computed_fee = calculate_l1_fee_for_transaction(&pre_transaction, &self.l1_rpc)
+            .map_err(|e| {
+                error!("Failed to get L1 fee estimate: {:?}", &e);
+                e
+            })
+            .unwrap_or(DEFAULT_MINER_COMMITMENT_FEE);
+
+        Ok(self.make_rotate_contract_call(
+            op_signer.get_sk(),
+            nonce,
+            computed_fee,
+            next_miner,
+            activation_height,
+        ))
+    }
 }
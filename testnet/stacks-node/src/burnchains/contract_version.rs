@@ -0,0 +1,93 @@
+use std::convert::TryFrom;
+
+use stacks::chainstate::stacks::subnet_codec::SUBNET_CONTRACT_INTERFACE_VERSION;
+use stacks::monitoring;
+use stacks::net::{CallReadOnlyRequestBody, CallReadOnlyResponse, L1ContractStatus};
+use stacks::vm::types::QualifiedContractIdentifier;
+use stacks::vm::Value as ClarityValue;
+
+use super::Error;
+use crate::run_loop::l1_observer::L1RpcEndpoints;
+
+/// The subnet contract's read-only function that reports its interface version
+pub const INTERFACE_VERSION_FUNCTION: &str = "get-interface-version";
+
+/// The interface version implemented by subnet contracts that predate `get-interface-version`
+pub const UNVERSIONED_CONTRACT_INTERFACE_VERSION: u64 = 1;
+
+/// Interpret the L1's answer to a call of the subnet contract's `get-interface-version`.
+/// Returns None if the contract has not been deployed yet.
+pub fn parse_interface_version_response(
+    response: &CallReadOnlyResponse,
+) -> Result<Option<u64>, Error> {
+    if !response.okay {
+        let cause = response.cause.as_deref().unwrap_or("");
+        if cause.contains("UndefinedFunction") {
+            return Ok(Some(UNVERSIONED_CONTRACT_INTERFACE_VERSION));
+        }
+        if cause.contains("NoSuchContract") {
+            return Ok(None);
+        }
+        return Err(Error::RPCError(format!(
+            "{} failed: {}",
+            INTERFACE_VERSION_FUNCTION, cause
+        )));
+    }
+
+    let result_hex = response.result.as_deref().ok_or_else(|| {
+        Error::RPCError(format!("{} returned no result", INTERFACE_VERSION_FUNCTION))
+    })?;
+    match ClarityValue::try_deserialize_hex_untyped(result_hex) {
+        Ok(ClarityValue::UInt(version)) => u64::try_from(version)
+            .map(Some)
+            .map_err(|_| Error::RPCError(format!("Interface version {} is out of range", version))),
+        Ok(value) => Err(Error::RPCError(format!(
+            "{} returned {}, expected a uint",
+            INTERFACE_VERSION_FUNCTION, value
+        ))),
+        Err(e) => Err(Error::RPCError(format!(
+            "Failed to decode {} result: {}",
+            INTERFACE_VERSION_FUNCTION, e
+        ))),
+    }
+}
+
+/// Ask the L1 for the interface version of the subnet contract `contract`.
+/// Returns None if the contract has not been deployed yet.
+pub fn l1_get_interface_version(
+    l1_rpc: &L1RpcEndpoints,
+    contract: &QualifiedContractIdentifier,
+) -> Result<Option<u64>, Error> {
+    let client = reqwest::blocking::Client::new();
+    let body = CallReadOnlyRequestBody {
+        sender: contract.issuer.to_string(),
+        arguments: vec![],
+        cost_budget: None,
+    };
+    let response = l1_rpc.request(|l1_rpc_interface| {
+        let url = format!(
+            "{}/v2/contracts/call-read/{}/{}/{}",
+            l1_rpc_interface, contract.issuer, contract.name, INTERFACE_VERSION_FUNCTION
+        );
+        let res = client.post(url).json(&body).send().map_err(|e| {
+            monitoring::increment_l1_rpc_failures_counter("get_interface_version");
+            Error::from(e)
+        })?;
+        if res.status().is_success() {
+            Ok(res.json::<CallReadOnlyResponse>().map_err(Error::from))
+        } else {
+            monitoring::increment_l1_rpc_failures_counter("get_interface_version");
+            Ok(Err(Error::RPCError(res.text()?)))
+        }
+    })?;
+    parse_interface_version_response(&response)
+}
+
+/// Whether this node can follow a subnet contract with interface version `version`
+pub fn check_interface_version(version: u64) -> L1ContractStatus {
+    if version == SUBNET_CONTRACT_INTERFACE_VERSION {
+        L1ContractStatus::Compatible
+    } else {
+        L1ContractStatus::Incompatible
+    }
+}
@@ -283,6 +283,7 @@ impl BurnchainChannel for DBBurnBlockInputChannel {
         }
 
         transaction.commit()?;
+        stacks::monitoring::increment_l1_blocks_observed_counter();
 
         Ok(())
     }
@@ -534,7 +535,7 @@ impl BurnchainIndexer for DBBurnchainIndexer {
     }
 
     fn get_stacks_epochs(&self) -> Vec<StacksEpoch> {
-        stacks::core::STACKS_EPOCHS_REGTEST.to_vec()
+        self.config.get_stacks_epochs()
     }
 
     fn get_headers_path(&self) -> String {
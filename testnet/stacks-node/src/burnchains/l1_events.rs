@@ -1,6 +1,6 @@
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use stacks::burnchains::db::BurnchainDB;
 use stacks::burnchains::events::NewBlock;
@@ -13,17 +13,21 @@ use stacks::chainstate::stacks::miner::Proposal;
 use stacks::chainstate::stacks::StacksTransaction;
 use stacks::codec::StacksMessageCodec;
 use stacks::core::StacksEpoch;
+use stacks::monitoring;
 use stacks::util::hash::hex_bytes;
 use stacks::util::sleep_ms;
-use stacks_common::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash, StacksBlockId};
+use stacks_common::types::chainstate::{
+    BlockHeaderHash, BurnchainHeaderHash, StacksAddress, StacksBlockId,
+};
 
-use super::commitment::{Layer1Committer, MultiPartyCommitter};
+use super::commitment::{l1_addr_from_signer, BlockProposer, Layer1Committer, MultiPartyCommitter};
 use super::db_indexer::DBBurnchainIndexer;
 use super::{burnchain_from_config, BurnchainChannel, ClaritySignature, Error};
 
 use crate::burnchains::commitment::DirectCommitter;
 use crate::config::CommitStrategy;
 use crate::operations::BurnchainOpSigner;
+use crate::run_loop::l1_observer::L1RpcEndpoints;
 use crate::util::hash::Sha512Trunc256Sum;
 use crate::{BurnchainController, BurnchainTip, Config};
 
@@ -46,6 +50,10 @@ pub struct L1Controller {
     chain_tip: Option<BurnchainTip>,
 
     committer: Box<dyn Layer1Committer + Send>,
+    /// the L1 nodes that RPC requests are sent to
+    l1_rpc: Arc<L1RpcEndpoints>,
+    /// L1 address and nonce of the last block commit submitted
+    last_commit_nonce: Option<(StacksAddress, u64)>,
 }
 
 impl L1Channel {
@@ -93,32 +101,49 @@ impl L1Controller {
             true,
         )?;
         let burnchain = burnchain_from_config(&config.get_burn_db_path(), &config.burnchain)?;
+        let l1_rpc = Arc::new(L1RpcEndpoints::from_config(&config.burnchain));
         let committer: Box<dyn Layer1Committer + Send> = match &config.burnchain.commit_strategy {
             CommitStrategy::Direct => Box::new(DirectCommitter {
                 config: config.burnchain.clone(),
+                l1_rpc: l1_rpc.clone(),
             }),
             CommitStrategy::MultiMiner {
                 required_signers,
                 contract,
                 other_participants,
                 leader: _,
-            } => Box::new(MultiPartyCommitter::new(
-                &config.burnchain,
-                *required_signers,
-                contract,
-                other_participants.clone(),
-            )),
+            } => {
+                // `RunLoop::spawn_chains_coordinator()` refuses to combine this with a chainstate that
+                // batches withdrawals, since participants sign each block's own withdrawal root
+                Box::new(MultiPartyCommitter::new(
+                    &config.burnchain,
+                    l1_rpc.clone(),
+                    *required_signers,
+                    contract,
+                    other_participants.clone(),
+                ))
+            }
         };
+        let should_keep_running = Arc::new(AtomicBool::new(true));
+        if !config.burnchain.fallback_rpc_urls.is_empty() {
+            L1RpcEndpoints::spawn_health_checker(
+                l1_rpc.clone(),
+                config.burnchain.rpc_health_check_interval_ms,
+                should_keep_running.clone(),
+            );
+        }
         Ok(L1Controller {
             burnchain,
             config,
             indexer,
             db: None,
             burnchain_db: None,
-            should_keep_running: Some(Arc::new(AtomicBool::new(true))),
+            should_keep_running: Some(should_keep_running),
             coordinator,
             chain_tip: None,
             committer,
+            l1_rpc,
+            last_commit_nonce: None,
         })
     }
 
@@ -207,25 +232,29 @@ impl L1Controller {
         }
     }
 
-    fn l1_rpc_interface(&self) -> String {
-        self.config.burnchain.get_rpc_url()
-    }
-
     pub fn l1_submit_tx(&self, tx: StacksTransaction) -> Result<Txid, Error> {
         let client = reqwest::blocking::Client::new();
-        let url = format!("{}/v2/transactions", self.l1_rpc_interface());
-        let res = client
-            .post(url)
-            .header("Content-Type", "application/octet-stream")
-            .body(tx.serialize_to_vec())
-            .send()?;
-
-        if res.status().is_success() {
-            let res: String = res.json().unwrap();
-            Txid::from_hex(&res).map_err(|e| Error::RPCError(e.to_string()))
-        } else {
-            Err(Error::RPCError(res.text()?))
-        }
+        let tx_bytes = tx.serialize_to_vec();
+        self.l1_rpc.request(|l1_rpc_interface| {
+            let url = format!("{}/v2/transactions", l1_rpc_interface);
+            let res = client
+                .post(url)
+                .header("Content-Type", "application/octet-stream")
+                .body(tx_bytes.clone())
+                .send()
+                .map_err(|e| {
+                    monitoring::increment_l1_rpc_failures_counter("submit_tx");
+                    e
+                })?;
+
+            if res.status().is_success() {
+                let res: String = res.json().unwrap();
+                Ok(Txid::from_hex(&res).map_err(|e| Error::RPCError(e.to_string())))
+            } else {
+                monitoring::increment_l1_rpc_failures_counter("submit_tx");
+                Ok(Err(Error::RPCError(res.text()?)))
+            }
+        })
     }
 }
 
@@ -247,17 +276,8 @@ impl BurnchainController for L1Controller {
         self.committer.commit_required_signatures()
     }
 
-    fn propose_block(
-        &self,
-        participant_index: u8,
-        proposal: &Proposal,
-    ) -> Result<ClaritySignature, Error> {
-        self.committer
-            .propose_block_to(participant_index, proposal)
-            .map_err(|e| {
-                warn!("Block proposal failed"; "error" => %e);
-                Error::BadCommitment(e)
-            })
+    fn block_proposer(&self) -> Option<BlockProposer> {
+        self.committer.block_proposer()
     }
 
     fn submit_commit(
@@ -277,6 +297,32 @@ impl BurnchainController for L1Controller {
             attempt,
             op_signer,
         )?;
+        let commit_nonce = (tx.origin_address(), tx.get_origin_nonce());
+
+        let txid = self.l1_submit_tx(tx)?;
+        self.last_commit_nonce = Some(commit_nonce);
+        monitoring::record_commit_submitted(&txid);
+        Ok(txid)
+    }
+
+    fn submit_miner_key_rotation(
+        &mut self,
+        next_miner: &StacksAddress,
+        activation_height: u64,
+        op_signer: &mut BurnchainOpSigner,
+    ) -> Result<Txid, Error> {
+        // don't replace the miner's pending block commit, if there is one
+        let miner_address = l1_addr_from_signer(self.config.burnchain.is_mainnet(), op_signer);
+        let min_nonce = match self.last_commit_nonce {
+            Some((ref address, nonce)) if address == &miner_address => nonce + 1,
+            _ => 0,
+        };
+        let tx = self.committer.make_miner_rotation_tx(
+            next_miner,
+            activation_height,
+            min_nonce,
+            op_signer,
+        )?;
 
         self.l1_submit_tx(tx)
     }
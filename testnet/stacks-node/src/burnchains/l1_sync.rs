@@ -0,0 +1,40 @@
+use stacks::monitoring;
+use stacks::net::NodeStartupState;
+
+use super::Error;
+use crate::run_loop::l1_observer::L1RpcEndpoints;
+
+/// The part of the L1 node's `/v2/info` response that the startup check needs
+#[derive(Debug, Deserialize)]
+struct L1InfoResponse {
+    stacks_tip_height: u64,
+}
+
+/// Ask the L1 for the height of its chain tip
+pub fn l1_get_tip_height(l1_rpc: &L1RpcEndpoints) -> Result<u64, Error> {
+    let client = reqwest::blocking::Client::new();
+    let info = l1_rpc.request(|l1_rpc_interface| {
+        let url = format!("{}/v2/info", l1_rpc_interface);
+        let res = client.get(url).send().map_err(|e| {
+            monitoring::increment_l1_rpc_failures_counter("get_tip_height");
+            Error::from(e)
+        })?;
+        if res.status().is_success() {
+            Ok(res.json::<L1InfoResponse>().map_err(Error::from))
+        } else {
+            monitoring::increment_l1_rpc_failures_counter("get_tip_height");
+            Ok(Err(Error::RPCError(res.text()?)))
+        }
+    })?;
+    Ok(info.stacks_tip_height)
+}
+
+/// The startup state of a node that has processed the L1 up to `local_height`, when the L1's tip
+/// is at `l1_tip_height`.  The node is ready once it is within `max_lag` blocks of the tip.
+pub fn check_l1_sync(local_height: u64, l1_tip_height: u64, max_lag: u64) -> NodeStartupState {
+    if l1_tip_height.saturating_sub(local_height) <= max_lag {
+        NodeStartupState::Ready
+    } else {
+        NodeStartupState::SyncingL1
+    }
+}
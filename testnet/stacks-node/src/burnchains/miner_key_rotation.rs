@@ -0,0 +1,106 @@
+use stacks::address::AddressHashMode;
+use stacks::chainstate::stacks::{StacksPrivateKey, StacksPublicKey};
+use stacks::types::chainstate::StacksAddress;
+
+use crate::config::{Config, MiningKeyRotation};
+
+/// Rotates the miner's key on the schedule in its config.
+///
+/// The subnet contract only accepts commits from its current miner, so each rotation's key is
+/// registered with the L1 contract (`register-next-miner`), signed by the key it replaces,
+/// `mining_key_rotation_lead_blocks` L1 blocks before the rotation activates.  A rotation is only
+/// registered once the rotation before it has activated, because registering replaces any
+/// rotation that has not yet activated.  From the activation height on, the miner signs its block
+/// commits with the new key.
+pub struct MinerKeyRotator {
+    /// the key that signs until the first rotation activates
+    initial_key: StacksPrivateKey,
+    rotations: Vec<MiningKeyRotation>,
+    lead_blocks: u64,
+    l1_is_mainnet: bool,
+    /// number of rotations, from the start of the schedule, that have been registered
+    registered: usize,
+}
+
+impl MinerKeyRotator {
+    /// Instantiate a rotator for this node.
+    /// Returns None if no key rotations are scheduled.
+    pub fn from_config(config: &Config) -> Option<MinerKeyRotator> {
+        if config.node.mining_key_rotation.is_empty() {
+            return None;
+        }
+        Some(MinerKeyRotator {
+            initial_key: config
+                .node
+                .mining_key
+                .expect("FATAL: miner key rotation requires a mining key"),
+            rotations: config.node.mining_key_rotation.clone(),
+            lead_blocks: config.node.mining_key_rotation_lead_blocks,
+            l1_is_mainnet: config.burnchain.is_mainnet(),
+            registered: 0,
+        })
+    }
+
+    /// Number of rotations that have activated by L1 block `burn_height`
+    fn num_activated(&self, burn_height: u64) -> usize {
+        self.rotations
+            .iter()
+            .take_while(|rotation| rotation.activation_burn_height <= burn_height)
+            .count()
+    }
+
+    /// The key that signs L1 transactions landing in block `burn_height`
+    pub fn key_at(&self, burn_height: u64) -> &StacksPrivateKey {
+        match self.num_activated(burn_height) {
+            0 => &self.initial_key,
+            activated => &self.rotations[activated - 1].mining_key,
+        }
+    }
+
+    /// The rotation to register with an L1 transaction landing in block `burn_height`, if one is
+    /// due: returns the L1 address of its key and its activation height.
+    pub fn due_registration(&self, burn_height: u64) -> Option<(StacksAddress, u64)> {
+        let next_index = self.num_activated(burn_height);
+        if next_index < self.registered {
+            return None;
+        }
+        let rotation = self.rotations.get(next_index)?;
+        let first_registration_height = rotation
+            .activation_burn_height
+            .saturating_sub(self.lead_blocks);
+        if burn_height < first_registration_height {
+            return None;
+        }
+        Some((
+            self.l1_address(&rotation.mining_key),
+            rotation.activation_burn_height,
+        ))
+    }
+
+    /// Note that the rotation activating at `activation_height` has been registered.
+    pub fn note_registered(&mut self, activation_height: u64) {
+        if let Some(index) = self
+            .rotations
+            .iter()
+            .position(|rotation| rotation.activation_burn_height == activation_height)
+        {
+            self.registered = self.registered.max(index + 1);
+        }
+    }
+
+    fn l1_address(&self, key: &StacksPrivateKey) -> StacksAddress {
+        let hash_mode = AddressHashMode::SerializeP2PKH;
+        let version = if self.l1_is_mainnet {
+            hash_mode.to_version_mainnet()
+        } else {
+            hash_mode.to_version_testnet()
+        };
+        StacksAddress::from_public_keys(
+            version,
+            &hash_mode,
+            1,
+            &vec![StacksPublicKey::from_private(key)],
+        )
+        .expect("Failed to make Stacks address from public key")
+    }
+}
@@ -1,9 +1,9 @@
 use std::cmp;
 use std::collections::HashMap;
 use std::convert::TryInto;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use stacks::burnchains::db::BurnchainDB;
 use stacks::burnchains::events::{ContractEvent, NewBlockTxEvent};
@@ -21,7 +21,9 @@ use stacks::chainstate::coordinator::comm::CoordinatorChannels;
 use stacks::chainstate::stacks::index::ClarityMarfTrieId;
 use stacks::chainstate::stacks::miner::Proposal;
 use stacks::core::StacksEpoch;
-use stacks::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash, StacksBlockId};
+use stacks::types::chainstate::{
+    BlockHeaderHash, BurnchainHeaderHash, StacksAddress, StacksBlockId,
+};
 use stacks::util::sleep_ms;
 use stacks::vm::types::{QualifiedContractIdentifier, TupleData};
 use stacks::vm::Value as ClarityValue;
@@ -29,6 +31,7 @@ use stacks::vm::Value as ClarityValue;
 use crate::operations::BurnchainOpSigner;
 use crate::{BurnchainController, BurnchainTip, Config};
 
+use super::commitment::BlockProposer;
 use super::db_indexer::DBBurnchainIndexer;
 use super::ClaritySignature;
 use super::{burnchain_from_config, BurnchainChannel, Error};
@@ -59,6 +62,17 @@ pub struct MockController {
     next_commit_and_withdrawal_root: Arc<Mutex<Option<(BlockHeaderHash, Sha512Trunc256Sum)>>>,
     burn_block_to_height: HashMap<u64, u64>,
     burn_block_to_parent: HashMap<u64, u64>,
+
+    /// Subnet contract events (e.g., deposits) to include in the next mocked block
+    staged_events: Vec<ClarityValue>,
+    /// Block commits included in the mocked blocks, and the withdrawal roots they committed to
+    committed_withdrawal_roots: HashMap<BlockHeaderHash, Sha512Trunc256Sum>,
+    /// The block commit included in each mocked block, by block index
+    burn_block_to_commit: HashMap<u64, BlockHeaderHash>,
+    /// Other nodes' burnchain channels, which receive every mocked block as well
+    followers: Vec<Arc<dyn BurnchainChannel>>,
+    /// If set, the timestamp of mocked blocks is read from here instead of the block index
+    block_time_source: Option<Arc<AtomicU64>>,
 }
 
 pub struct MockIndexer {
@@ -102,6 +116,11 @@ fn make_mock_txid(from: &BlockHeaderHash) -> Txid {
     Txid(from.0.clone())
 }
 
+/// The header hash of the mocked layer-1 block with index `burn_block`
+pub fn mock_burn_header_hash(burn_block: u64) -> BurnchainHeaderHash {
+    BurnchainHeaderHash(make_mock_byte_string(burn_block.try_into().unwrap()))
+}
+
 /// Resets the global static variables used for `MockController`-based tests. Call
 /// this at the beginning of the test, and mark as `ignore` to run with `test-threads=1`.
 pub fn reset_static_burnblock_simulator_channel() {
@@ -183,6 +202,32 @@ impl MockBlockDownloader {
 
 impl MockController {
     pub fn new(config: Config, coordinator: CoordinatorChannels) -> MockController {
+        MockController::with_block_counters(
+            config,
+            coordinator,
+            NEXT_BURN_BLOCK.clone(),
+            NEXT_COMMIT_AND_WTIHDRAWAL_ROOT.clone(),
+        )
+    }
+
+    /// Like `new`, but the controller numbers its blocks and stages commits on its own, instead
+    /// of sharing the global state that every other `MockController` uses. Tests that only
+    /// produce blocks and submit commits through this controller can run in parallel.
+    pub fn new_standalone(config: Config, coordinator: CoordinatorChannels) -> MockController {
+        MockController::with_block_counters(
+            config,
+            coordinator,
+            Arc::new(Mutex::new(1)),
+            Arc::new(Mutex::new(None)),
+        )
+    }
+
+    fn with_block_counters(
+        config: Config,
+        coordinator: CoordinatorChannels,
+        next_burn_block: Arc<Mutex<u64>>,
+        next_commit_and_withdrawal_root: Arc<Mutex<Option<(BlockHeaderHash, Sha512Trunc256Sum)>>>,
+    ) -> MockController {
         let contract_identifier = config.burnchain.contract_identifier.clone();
         let indexer = DBBurnchainIndexer::new(
             &config.get_burnchain_path_str(),
@@ -202,13 +247,53 @@ impl MockController {
             should_keep_running: Some(Arc::new(AtomicBool::new(true))),
             coordinator,
             chain_tip: None,
-            next_burn_block: NEXT_BURN_BLOCK.clone(),
-            next_commit_and_withdrawal_root: NEXT_COMMIT_AND_WTIHDRAWAL_ROOT.clone(),
+            next_burn_block,
+            next_commit_and_withdrawal_root,
             burn_block_to_height: HashMap::new(),
             burn_block_to_parent: HashMap::new(),
+            staged_events: vec![],
+            committed_withdrawal_roots: HashMap::new(),
+            burn_block_to_commit: HashMap::new(),
+            followers: vec![],
+            block_time_source: None,
         }
     }
 
+    /// Push every mocked block to `channel` as well, so that several nodes can follow the same
+    /// mocked layer-1 chain.
+    pub fn add_follower(&mut self, channel: Arc<dyn BurnchainChannel>) {
+        self.followers.push(channel);
+    }
+
+    /// Read the timestamp of the mocked blocks from `source`, rather than using the block index.
+    pub fn set_block_time_source(&mut self, source: Arc<AtomicU64>) {
+        self.block_time_source = Some(source);
+    }
+
+    /// Include a `print` event from the subnet contract with `value` in the next mocked block.
+    pub fn stage_event(&mut self, value: ClarityValue) {
+        self.staged_events.push(value);
+    }
+
+    /// The subnet block committed to in the mocked block with index `burn_block`, if any.
+    pub fn get_commit_in_block(&self, burn_block: u64) -> Option<BlockHeaderHash> {
+        self.burn_block_to_commit.get(&burn_block).cloned()
+    }
+
+    /// The withdrawal root committed to along with `block_hash`, if a mocked block included its
+    /// commit.
+    pub fn get_committed_withdrawal_root(
+        &self,
+        block_hash: &BlockHeaderHash,
+    ) -> Option<Sha512Trunc256Sum> {
+        self.committed_withdrawal_roots.get(block_hash).cloned()
+    }
+
+    /// The height of the mocked block with index `burn_block`, if it has been produced.
+    pub fn get_block_height(&self, burn_block: u64) -> Option<u64> {
+        self.burn_block_to_height.get(&burn_block).cloned()
+    }
+
     /// Produce the next mocked layer-1 block. If `next_commit` is staged,
     /// this mocked block will contain that commitment.
     ///
@@ -225,6 +310,10 @@ impl MockController {
             next_commit_and_withdrawal_root
                 .take()
                 .map(|(next_commit, next_withdrawal_root)| {
+                    self.committed_withdrawal_roots
+                        .insert(next_commit.clone(), next_withdrawal_root.clone());
+                    self.burn_block_to_commit
+                        .insert(this_burn_block, next_commit.clone());
                     let mocked_txid = make_mock_txid(&next_commit);
                     let topic = "print".into();
                     let contract_identifier = self.contract_identifier.clone();
@@ -285,12 +374,36 @@ impl MockController {
         let index_block_hash =
             StacksBlockId(make_mock_byte_string(this_burn_block.try_into().unwrap()));
 
+        let mut events: Vec<_> = tx_event.into_iter().collect();
+        for value in self.staged_events.drain(..) {
+            let event_index = events.len();
+            let mut txid_bytes = make_mock_byte_string(this_burn_block.try_into().unwrap());
+            txid_bytes[0..8].copy_from_slice(&(event_index as u64).to_be_bytes());
+            txid_bytes[8] = 1;
+            events.push(NewBlockTxEvent {
+                txid: Txid(txid_bytes),
+                event_index,
+                committed: true,
+                event_type: TxEventType::ContractEvent,
+                contract_event: Some(ContractEvent {
+                    topic: "print".into(),
+                    contract_identifier: self.contract_identifier.clone(),
+                    value,
+                }),
+            });
+        }
+
+        let burn_block_time = match self.block_time_source {
+            Some(ref source) => source.load(Ordering::SeqCst),
+            None => this_burn_block,
+        };
+
         let new_block = NewBlock {
             block_height,
-            burn_block_time: this_burn_block,
+            burn_block_time,
             index_block_hash,
             parent_index_block_hash,
-            events: tx_event.into_iter().collect(),
+            events,
         };
 
         self.burn_block_to_height
@@ -303,6 +416,12 @@ impl MockController {
             "index_block_hash" => %new_block.index_block_hash,
             "parent_index_block_hash" => %new_block.parent_index_block_hash);
 
+        for follower in self.followers.iter() {
+            follower
+                .push_block(new_block.clone())
+                .expect("`push_block` has failed.");
+        }
+
         self.indexer
             .get_channel()
             .push_block(new_block)
@@ -438,6 +557,21 @@ impl BurnchainController for MockController {
         Ok(mocked_txid)
     }
 
+    fn submit_miner_key_rotation(
+        &mut self,
+        next_miner: &StacksAddress,
+        activation_height: u64,
+        _op_signer: &mut BurnchainOpSigner,
+    ) -> Result<Txid, Error> {
+        // the simulated subnet contract accepts commits from any miner
+        info!("Mocknet controller accepted a miner rotation";
+              "next_miner" => %next_miner,
+              "activation_height" => activation_height);
+        let mut rotation_bytes = next_miner.bytes.as_bytes().to_vec();
+        rotation_bytes.extend_from_slice(&activation_height.to_be_bytes());
+        Ok(Txid(Sha512Trunc256Sum::from_data(&rotation_bytes).0))
+    }
+
     fn sync(&mut self, target_block_height_opt: Option<u64>) -> Result<(BurnchainTip, u64), Error> {
         self.receive_blocks(true, target_block_height_opt)
     }
@@ -530,12 +664,8 @@ impl BurnchainController for MockController {
         todo!()
     }
 
-    fn propose_block(
-        &self,
-        _participant_index: u8,
-        _proposal: &Proposal,
-    ) -> Result<ClaritySignature, Error> {
-        panic!()
+    fn block_proposer(&self) -> Option<BlockProposer> {
+        None
     }
 }
 
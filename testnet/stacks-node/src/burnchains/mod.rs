@@ -4,8 +4,9 @@ use super::operations::BurnchainOpSigner;
 
 use std::fmt;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
+use self::commitment::BlockProposer;
 use self::commitment::Error as CommitmentError;
 use reqwest::Error as ReqwestError;
 use stacks::burnchains;
@@ -19,6 +20,7 @@ use stacks::chainstate::stacks::miner::Proposal;
 use stacks::core::StacksEpoch;
 use stacks::types::chainstate::BlockHeaderHash;
 use stacks::types::chainstate::BurnchainHeaderHash;
+use stacks::types::chainstate::StacksAddress;
 use stacks::util::hash::Sha512Trunc256Sum;
 
 /// This module implements a burnchain controller that
@@ -35,6 +37,23 @@ pub mod db_indexer;
 /// This module defines structs for producing block commitments
 pub mod commitment;
 
+/// This module coordinates the collection of block commitment signatures
+/// among the miners of a multi-miner subnet
+pub mod signer_coordinator;
+
+/// This module chooses the withdrawal roots registered with block commits when
+/// withdrawals are batched
+pub mod withdrawal_batcher;
+
+/// This module schedules the rotation of the miner's key
+pub mod miner_key_rotation;
+
+/// This module checks that the L1 subnet contract's interface version is supported
+pub mod contract_version;
+
+/// This module checks whether the node has caught up with the L1 tip
+pub mod l1_sync;
+
 #[cfg(test)]
 mod tests;
 
@@ -101,14 +120,21 @@ pub trait BurnchainController {
         attempt: u64,
     ) -> Result<Txid, Error>;
 
+    /// Schedule the rotation of the subnet miner to `next_miner`, from L1 block
+    /// `activation_height` on.  `op_signer` must be the current miner.
+    fn submit_miner_key_rotation(
+        &mut self,
+        next_miner: &StacksAddress,
+        activation_height: u64,
+        op_signer: &mut BurnchainOpSigner,
+    ) -> Result<Txid, Error>;
+
     /// Returns the number of signatures necessary to provide
     /// to the block committer.
     fn commit_required_signatures(&self) -> u8;
-    fn propose_block(
-        &self,
-        participant_index: u8,
-        proposal: &Proposal,
-    ) -> Result<ClaritySignature, Error>;
+    /// Returns a handle for sending block proposals to the other multi-miner participants, or
+    /// None if commits need no signatures.
+    fn block_proposer(&self) -> Option<BlockProposer>;
 
     fn sync(&mut self, target_block_height_opt: Option<u64>) -> Result<(BurnchainTip, u64), Error>;
     fn sortdb_ref(&self) -> &SortitionDB;
@@ -211,15 +237,20 @@ impl BurnchainController for PanicController {
         panic!()
     }
 
+    fn submit_miner_key_rotation(
+        &mut self,
+        _next_miner: &StacksAddress,
+        _activation_height: u64,
+        _op_signer: &mut BurnchainOpSigner,
+    ) -> Result<Txid, Error> {
+        panic!()
+    }
+
     fn commit_required_signatures(&self) -> u8 {
         panic!()
     }
 
-    fn propose_block(
-        &self,
-        _participant_index: u8,
-        _proposal: &Proposal,
-    ) -> Result<ClaritySignature, Error> {
+    fn block_proposer(&self) -> Option<BlockProposer> {
         panic!()
     }
 }
@@ -0,0 +1,202 @@
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use stacks::chainstate::stacks::miner::Proposal;
+use stacks::chainstate::stacks::{StacksPrivateKey, StacksPublicKey};
+use stacks::net::proposals::{BlockProposalLogHandle, BlockProposerSchedule};
+use stacks::types::chainstate::BlockHeaderHash;
+use stacks::vm::types::QualifiedContractIdentifier;
+
+use crate::config::{CommitStrategy, Config};
+
+use super::{ClaritySignature, Error};
+
+/// Coordinates the collection of block signatures among the miners of a multi-miner subnet.
+///
+/// The signing set is this node plus every configured `other_participant`. When leader
+/// rotation is enabled, exactly one member of the signing set proposes a block for each
+/// L1 block (see `BlockProposerSchedule`). The leader sends its block proposal to all of the
+/// other participants at once, and waits until it has collected a quorum of signatures or the
+/// signing timeout elapses.
+///
+/// So that an offline leader does not stall the subnet, the member of the signing set after
+/// the leader is its fallback: once the leader has had the signing timeout to propose, the
+/// fallback proposes a block itself if it has not been sent a proposal from the leader.
+/// Signers sign at most one block for each L1 block, so the leader and the fallback cannot
+/// both collect a quorum.
+pub struct SignerCoordinator {
+    /// Which members of the signing set may propose each block
+    schedule: BlockProposerSchedule,
+    /// This node's compressed public key, if it has a mining key.
+    local_key: Option<[u8; 33]>,
+    /// The multi-miner contract that commits are submitted through
+    contract: QualifiedContractIdentifier,
+    /// The block proposals this node has signed or made
+    proposal_log: BlockProposalLogHandle,
+    /// Number of other participants' signatures that must accompany a commit.
+    required_signatures: u8,
+    /// Number of other participants that can be asked for a signature.
+    num_participants: u8,
+    /// How long to spend collecting signatures for a single proposal.
+    timeout: Duration,
+    /// Whether or not the proposing miner rotates with each L1 block.
+    leader_rotation: bool,
+}
+
+impl SignerCoordinator {
+    /// Instantiate a coordinator for this node's commit strategy.
+    /// Returns None if the node commits directly to the subnet contract.
+    pub fn from_config(config: &Config) -> Option<SignerCoordinator> {
+        let schedule = config.block_proposer_schedule()?;
+        let (required_signers, contract, num_participants) = match &config.burnchain.commit_strategy
+        {
+            CommitStrategy::Direct => return None,
+            CommitStrategy::MultiMiner {
+                required_signers,
+                contract,
+                other_participants,
+                ..
+            } => (*required_signers, contract, other_participants.len()),
+        };
+
+        let local_key = config.node.mining_key.as_ref().map(|sk| {
+            let mut key = [0u8; 33];
+            key.copy_from_slice(&StacksPublicKey::from_private(sk).to_bytes_compressed());
+            key
+        });
+
+        Some(SignerCoordinator {
+            schedule,
+            local_key,
+            contract: contract.clone(),
+            proposal_log: config.connection_options.block_proposal_log.clone(),
+            // the submitted transaction itself counts as a signature
+            required_signatures: required_signers.saturating_sub(1),
+            num_participants: u8::try_from(num_participants).unwrap_or(u8::MAX),
+            timeout: Duration::from_millis(config.miner.signing_timeout_ms),
+            leader_rotation: config.miner.leader_rotation,
+        })
+    }
+
+    /// Public key of the miner that should propose the block for the L1 block at `burn_height`.
+    /// Returns None if leader rotation is disabled or the signing set is empty.
+    pub fn leader_for_height(&self, burn_height: u64) -> Option<&[u8; 33]> {
+        self.schedule.leader_for_height(burn_height)
+    }
+
+    /// Should this node propose a block for the L1 block at `burn_height`?
+    /// Always true if leader rotation is disabled.
+    pub fn is_leader(&self, burn_height: u64) -> bool {
+        match self.leader_for_height(burn_height) {
+            Some(leader) => self.local_key.as_ref() == Some(leader),
+            None => true,
+        }
+    }
+
+    /// Public key of the miner that takes over from the leader for the L1 block at
+    /// `burn_height` if the leader does not propose. Returns None if leader rotation is
+    /// disabled, or there is no other member to take over.
+    pub fn fallback_for_height(&self, burn_height: u64) -> Option<&[u8; 33]> {
+        self.schedule.fallback_for_height(burn_height)
+    }
+
+    /// Should this node take over from a silent leader for the L1 block at `burn_height`?
+    pub fn is_fallback(&self, burn_height: u64) -> bool {
+        match self.fallback_for_height(burn_height) {
+            Some(fallback) => self.local_key.as_ref() == Some(fallback),
+            None => false,
+        }
+    }
+
+    /// How long the fallback waits for the leader to propose before taking over from it: as
+    /// long as the leader may spend collecting signatures.
+    pub fn fallback_delay(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Has this node been sent a block proposal for the L1 block at `burn_height`, or made one
+    /// itself? Only the leader and its fallback can have sent one.
+    pub fn has_seen_proposal(&self, burn_height: u64) -> bool {
+        self.proposal_log.has_seen_proposal(burn_height)
+    }
+
+    /// Record that this node proposes `block_hash` for the L1 block at `burn_height`, so that
+    /// it does not also sign another miner's block for it. Returns false, and records nothing,
+    /// if this node already signed or proposed a different block for it, since the signers
+    /// would not sign this one either. Always true if leader rotation is disabled.
+    pub fn commit_to_proposal(&self, burn_height: u64, block_hash: &BlockHeaderHash) -> bool {
+        if !self.leader_rotation {
+            return true;
+        }
+        self.proposal_log
+            .try_commit_to_block(burn_height, block_hash)
+    }
+
+    /// Sign `proposal` as its proposer with this node's mining key, so that the signers know
+    /// that it comes from a miner that may propose it.
+    pub fn sign_proposal(&self, proposal: &mut Proposal, mining_key: &StacksPrivateKey) {
+        proposal.sign_as_proposer(mining_key, self.contract.clone());
+    }
+
+    /// Send `proposal` to all of the other participants at once, each from its own worker
+    /// thread, and wait until `required_signatures` of them have signed it, or until the signing
+    /// timeout elapses. Each request is given only the time left before the timeout elapses,
+    /// and every worker is joined before this returns, so no request outlives the timeout.
+    ///
+    /// `propose` sends the proposal to the participant at the given index, with the given timeout.
+    ///
+    /// Returns the signatures collected, which may be fewer than required.
+    pub fn collect_signatures<F>(&self, proposal: &Proposal, propose: F) -> Vec<ClaritySignature>
+    where
+        F: Fn(u8, &Proposal, Duration) -> Result<ClaritySignature, Error> + Sync,
+    {
+        let mut signatures = vec![];
+        if self.required_signatures == 0 || self.num_participants == 0 {
+            return signatures;
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        let propose = &propose;
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| {
+            for participant_index in 0..self.num_participants {
+                let sender = sender.clone();
+                let spawned = thread::Builder::new()
+                    .name(format!("block-proposal-{}", participant_index))
+                    .spawn_scoped(scope, move || {
+                        let timeout = deadline.saturating_duration_since(Instant::now());
+                        let result = propose(participant_index, proposal, timeout);
+                        // the receiver stops listening once enough signatures were collected
+                        let _ = sender.send((participant_index, result));
+                    });
+                if let Err(e) = spawned {
+                    warn!("Failed to spawn block proposal thread"; "participant_index" => participant_index, "error" => %e);
+                }
+            }
+            // once every worker has answered, the channel disconnects
+            drop(sender);
+
+            while signatures.len() < self.required_signatures as usize {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                match receiver.recv_timeout(timeout) {
+                    Ok((_, Ok(signature))) => signatures.push(signature),
+                    Ok((participant_index, Err(rejection))) => {
+                        warn!("Failed to obtain approval"; "participant_index" => participant_index, "error" => %rejection);
+                    }
+                    Err(RecvTimeoutError::Timeout) => {
+                        warn!(
+                            "Timed out collecting block proposal signatures";
+                            "signatures_obtained" => signatures.len(),
+                            "required" => self.required_signatures
+                        );
+                        break;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+
+        signatures
+    }
+}
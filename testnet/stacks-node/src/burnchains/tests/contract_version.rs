@@ -0,0 +1,71 @@
+use stacks::net::{CallReadOnlyResponse, L1ContractStatus};
+use stacks::vm::database::ClaritySerializable;
+use stacks::vm::Value as ClarityValue;
+
+use crate::burnchains::contract_version::{
+    check_interface_version, parse_interface_version_response,
+    UNVERSIONED_CONTRACT_INTERFACE_VERSION,
+};
+
+fn call_response(
+    okay: bool,
+    result: Option<ClarityValue>,
+    cause: Option<&str>,
+) -> CallReadOnlyResponse {
+    CallReadOnlyResponse {
+        okay,
+        result: result.map(|value| format!("0x{}", value.serialize())),
+        cause: cause.map(|cause| cause.to_string()),
+        budget_exceeded: None,
+        cost: None,
+    }
+}
+
+#[test]
+fn parses_reported_version() {
+    let response = call_response(true, Some(ClarityValue::UInt(2)), None);
+    assert_eq!(
+        parse_interface_version_response(&response).unwrap(),
+        Some(2)
+    );
+
+    // the version must be a uint
+    let response = call_response(true, Some(ClarityValue::Int(2)), None);
+    assert!(parse_interface_version_response(&response).is_err());
+    let response = call_response(true, Some(ClarityValue::UInt(u128::MAX)), None);
+    assert!(parse_interface_version_response(&response).is_err());
+    let response = call_response(true, None, None);
+    assert!(parse_interface_version_response(&response).is_err());
+}
+
+#[test]
+fn parses_unversioned_and_missing_contracts() {
+    // contracts without get-interface-version implement the first interface
+    let response = call_response(
+        false,
+        None,
+        Some("Unchecked(UndefinedFunction(\"get-interface-version\"))"),
+    );
+    assert_eq!(
+        parse_interface_version_response(&response).unwrap(),
+        Some(UNVERSIONED_CONTRACT_INTERFACE_VERSION)
+    );
+
+    // a contract that has not been deployed yet has no version
+    let response = call_response(
+        false,
+        None,
+        Some("Unchecked(NoSuchContract(\"ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.subnet\"))"),
+    );
+    assert_eq!(parse_interface_version_response(&response).unwrap(), None);
+
+    let response = call_response(false, None, Some("ReadOnlyBudgetExceeded"));
+    assert!(parse_interface_version_response(&response).is_err());
+}
+
+#[test]
+fn only_the_implemented_version_is_compatible() {
+    assert_eq!(check_interface_version(1), L1ContractStatus::Compatible);
+    assert_eq!(check_interface_version(0), L1ContractStatus::Incompatible);
+    assert_eq!(check_interface_version(2), L1ContractStatus::Incompatible);
+}
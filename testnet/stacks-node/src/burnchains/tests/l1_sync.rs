@@ -0,0 +1,14 @@
+use stacks::net::NodeStartupState;
+
+use crate::burnchains::l1_sync::check_l1_sync;
+
+#[test]
+fn ready_within_max_lag() {
+    assert_eq!(check_l1_sync(100, 100, 2), NodeStartupState::Ready);
+    assert_eq!(check_l1_sync(98, 100, 2), NodeStartupState::Ready);
+    assert_eq!(check_l1_sync(97, 100, 2), NodeStartupState::SyncingL1);
+    assert_eq!(check_l1_sync(99, 100, 0), NodeStartupState::SyncingL1);
+
+    // a node ahead of a lagging L1 node has nothing to catch up on
+    assert_eq!(check_l1_sync(105, 100, 0), NodeStartupState::Ready);
+}
@@ -0,0 +1,86 @@
+use stacks::chainstate::stacks::StacksPrivateKey;
+
+use crate::burnchains::miner_key_rotation::MinerKeyRotator;
+use crate::config::{Config, MiningKeyRotation};
+
+fn make_rotator(
+    initial_key: &StacksPrivateKey,
+    rotations: &[(&StacksPrivateKey, u64)],
+) -> MinerKeyRotator {
+    let mut config = Config::default();
+    config.node.mining_key = Some(*initial_key);
+    config.node.mining_key_rotation = rotations
+        .iter()
+        .map(|(key, activation_burn_height)| MiningKeyRotation {
+            mining_key: **key,
+            activation_burn_height: *activation_burn_height,
+        })
+        .collect();
+    config.node.mining_key_rotation_lead_blocks = 5;
+    MinerKeyRotator::from_config(&config).expect("Rotations are scheduled")
+}
+
+#[test]
+fn no_rotator_without_rotations() {
+    let mut config = Config::default();
+    config.node.mining_key = Some(StacksPrivateKey::new());
+    assert!(MinerKeyRotator::from_config(&config).is_none());
+}
+
+#[test]
+fn signs_with_the_active_key() {
+    let initial_key = StacksPrivateKey::new();
+    let second_key = StacksPrivateKey::new();
+    let third_key = StacksPrivateKey::new();
+    let rotator = make_rotator(&initial_key, &[(&second_key, 100), (&third_key, 200)]);
+
+    assert_eq!(rotator.key_at(1), &initial_key);
+    assert_eq!(rotator.key_at(99), &initial_key);
+    assert_eq!(rotator.key_at(100), &second_key);
+    assert_eq!(rotator.key_at(199), &second_key);
+    assert_eq!(rotator.key_at(200), &third_key);
+    assert_eq!(rotator.key_at(u64::MAX), &third_key);
+}
+
+#[test]
+fn registers_each_rotation_once_within_lead() {
+    let initial_key = StacksPrivateKey::new();
+    let second_key = StacksPrivateKey::new();
+    let third_key = StacksPrivateKey::new();
+    let mut rotator = make_rotator(&initial_key, &[(&second_key, 100), (&third_key, 103)]);
+
+    // not yet within the lead of the first rotation
+    assert!(rotator.due_registration(94).is_none());
+
+    let (next_miner, activation_height) = rotator.due_registration(95).unwrap();
+    assert_eq!(activation_height, 100);
+    assert_eq!(
+        rotator.due_registration(99),
+        Some((next_miner.clone(), 100))
+    );
+    rotator.note_registered(100);
+    assert!(rotator.due_registration(96).is_none());
+
+    // the second rotation is due, but waits for the first to activate
+    assert!(rotator.due_registration(99).is_none());
+    let (third_miner, activation_height) = rotator.due_registration(100).unwrap();
+    assert_eq!(activation_height, 103);
+    assert_ne!(third_miner, next_miner);
+    rotator.note_registered(103);
+
+    // nothing left to register
+    assert!(rotator.due_registration(101).is_none());
+    assert!(rotator.due_registration(200).is_none());
+}
+
+#[test]
+fn retries_unregistered_rotation() {
+    let initial_key = StacksPrivateKey::new();
+    let second_key = StacksPrivateKey::new();
+    let rotator = make_rotator(&initial_key, &[(&second_key, 100)]);
+
+    // a rotation stays due until it is registered or activates
+    assert!(rotator.due_registration(95).is_some());
+    assert!(rotator.due_registration(98).is_some());
+    assert!(rotator.due_registration(100).is_none());
+}
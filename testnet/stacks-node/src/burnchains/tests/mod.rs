@@ -11,7 +11,11 @@ use stacks::{
 };
 
 mod commitment;
+mod contract_version;
 pub mod db_indexer;
+mod l1_sync;
+mod miner_key_rotation;
+mod signer_coordinator;
 
 pub fn random_sortdb_test_dir() -> String {
     let mut rng = rand::thread_rng();
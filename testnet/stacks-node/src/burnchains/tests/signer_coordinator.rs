@@ -0,0 +1,343 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use stacks::chainstate::burn::ConsensusHash;
+use stacks::chainstate::stacks::miner::Proposal;
+use stacks::chainstate::stacks::{
+    StacksBlock, StacksBlockHeader, StacksPrivateKey, StacksPublicKey,
+};
+use stacks::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash};
+use stacks::util::hash::Hash160;
+use stacks::vm::types::QualifiedContractIdentifier;
+
+use crate::burnchains::commitment::{Error as CommitmentError, MultiMinerParticipant};
+use crate::burnchains::signer_coordinator::SignerCoordinator;
+use crate::burnchains::{ClaritySignature, Error};
+use crate::config::CommitStrategy;
+use crate::Config;
+
+fn make_test_proposal() -> Proposal {
+    Proposal {
+        parent_block_hash: BlockHeaderHash([0; 32]),
+        parent_consensus_hash: ConsensusHash([0; 20]),
+        block: StacksBlock {
+            header: StacksBlockHeader::genesis_block_header(),
+            txs: vec![],
+        },
+        microblocks_confirmed: vec![],
+        burn_tip: BurnchainHeaderHash([0; 32]),
+        burn_tip_height: 0,
+        is_mainnet: false,
+        microblock_pubkey_hash: Hash160([0; 20]),
+        total_burn: 0,
+        withdrawal_leaves: None,
+        proposer_signature: None,
+    }
+}
+
+fn public_key_bytes(sk: &StacksPrivateKey) -> [u8; 33] {
+    let mut key = [0u8; 33];
+    key.copy_from_slice(&StacksPublicKey::from_private(sk).to_bytes_compressed());
+    key
+}
+
+/// Make configs for `num_miners` miners which all know about each other.
+fn make_test_configs(
+    num_miners: usize,
+    required_signers: u8,
+    leader_rotation: bool,
+) -> Vec<Config> {
+    let keys: Vec<_> = (0..num_miners).map(|_| StacksPrivateKey::new()).collect();
+    keys.iter()
+        .enumerate()
+        .map(|(i, sk)| {
+            let mut config = Config::default();
+            config.node.mining_key = Some(sk.clone());
+            config.miner.leader_rotation = leader_rotation;
+            config.burnchain.commit_strategy = CommitStrategy::MultiMiner {
+                required_signers,
+                contract: QualifiedContractIdentifier::transient(),
+                other_participants: keys
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(j, other_sk)| MultiMinerParticipant {
+                        public_key: public_key_bytes(other_sk),
+                        rpc_server: format!("http://127.0.0.1:{}", 20443 + j),
+                    })
+                    .collect(),
+                leader: true,
+            };
+            config
+        })
+        .collect()
+}
+
+#[test]
+fn direct_commits_have_no_coordinator() {
+    assert!(SignerCoordinator::from_config(&Config::default()).is_none());
+}
+
+#[test]
+fn leader_rotation_is_round_robin() {
+    let configs = make_test_configs(3, 2, true);
+    let coordinators: Vec<_> = configs
+        .iter()
+        .map(|config| SignerCoordinator::from_config(config).unwrap())
+        .collect();
+
+    for burn_height in 0..30 {
+        // every participant agrees on who the leader is...
+        let leader = coordinators[0].leader_for_height(burn_height).unwrap();
+        for coordinator in coordinators.iter() {
+            assert_eq!(coordinator.leader_for_height(burn_height).unwrap(), leader);
+        }
+
+        // ...and exactly one of them is it
+        let leaders: Vec<_> = coordinators
+            .iter()
+            .enumerate()
+            .filter(|(_, coordinator)| coordinator.is_leader(burn_height))
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(leaders.len(), 1);
+
+        // the leader changes with every L1 block, and cycles through every miner
+        let next_leader = coordinators[0].leader_for_height(burn_height + 1).unwrap();
+        assert_ne!(leader, next_leader);
+        assert_eq!(
+            coordinators[0].leader_for_height(burn_height + 3).unwrap(),
+            leader
+        );
+    }
+}
+
+#[test]
+fn no_leader_rotation_always_leads() {
+    let configs = make_test_configs(3, 2, false);
+    for config in configs.iter() {
+        let coordinator = SignerCoordinator::from_config(config).unwrap();
+        for burn_height in 0..10 {
+            assert!(coordinator.leader_for_height(burn_height).is_none());
+            assert!(coordinator.is_leader(burn_height));
+        }
+    }
+}
+
+#[test]
+fn fallback_is_next_after_leader() {
+    let configs = make_test_configs(3, 2, true);
+    let coordinators: Vec<_> = configs
+        .iter()
+        .map(|config| SignerCoordinator::from_config(config).unwrap())
+        .collect();
+
+    for burn_height in 0..30 {
+        // every participant agrees on who the fallback is, and it leads the next L1 block
+        let fallback = coordinators[0].fallback_for_height(burn_height).unwrap();
+        assert_eq!(
+            fallback,
+            coordinators[0].leader_for_height(burn_height + 1).unwrap()
+        );
+
+        // exactly one of them is it, and it isn't the leader
+        let fallbacks: Vec<_> = coordinators
+            .iter()
+            .filter(|coordinator| coordinator.is_fallback(burn_height))
+            .collect();
+        assert_eq!(fallbacks.len(), 1);
+        assert!(!fallbacks[0].is_leader(burn_height));
+    }
+}
+
+#[test]
+fn no_fallback_without_leader_rotation() {
+    let configs = make_test_configs(3, 2, false);
+    for config in configs.iter() {
+        let coordinator = SignerCoordinator::from_config(config).unwrap();
+        for burn_height in 0..10 {
+            assert!(coordinator.fallback_for_height(burn_height).is_none());
+            assert!(!coordinator.is_fallback(burn_height));
+        }
+    }
+
+    // a lone miner has nobody to take over for it
+    let configs = make_test_configs(1, 1, true);
+    let coordinator = SignerCoordinator::from_config(&configs[0]).unwrap();
+    assert!(coordinator.fallback_for_height(0).is_none());
+}
+
+#[test]
+fn collect_signatures_stops_at_quorum() {
+    // 5 miners, 3 required: the leader needs 2 signatures from the 4 others
+    let configs = make_test_configs(5, 3, false);
+    let coordinator = SignerCoordinator::from_config(&configs[0]).unwrap();
+    let proposal = make_test_proposal();
+
+    let signatures = coordinator.collect_signatures(&proposal, |index, _proposal, _timeout| {
+        Ok(ClaritySignature([index; 65]))
+    });
+    assert_eq!(signatures.len(), 2);
+}
+
+#[test]
+fn collect_signatures_skips_rejections() {
+    let configs = make_test_configs(5, 3, false);
+    let coordinator = SignerCoordinator::from_config(&configs[0]).unwrap();
+    let proposal = make_test_proposal();
+
+    let signatures = coordinator.collect_signatures(&proposal, |index, _proposal, _timeout| {
+        if index % 2 == 0 {
+            Err(Error::BadCommitment(
+                CommitmentError::BlockProposalRejected("test".into()),
+            ))
+        } else {
+            Ok(ClaritySignature([index; 65]))
+        }
+    });
+    let mut signers: Vec<_> = signatures.iter().map(|sig| sig.0[0]).collect();
+    signers.sort();
+    assert_eq!(signers, vec![1, 3]);
+
+    // not enough participants will sign: every participant is asked
+    let asked = Arc::new(Mutex::new(vec![]));
+    let asked_ref = asked.clone();
+    let signatures =
+        coordinator.collect_signatures(&proposal, move |index, _proposal, _timeout| {
+            asked_ref.lock().unwrap().push(index);
+            if index == 0 {
+                Ok(ClaritySignature([index; 65]))
+            } else {
+                Err(Error::RPCError("test".into()))
+            }
+        });
+    assert_eq!(signatures.len(), 1);
+    let mut asked = asked.lock().unwrap().clone();
+    asked.sort();
+    assert_eq!(asked, vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn collect_signatures_asks_concurrently() {
+    let mut configs = make_test_configs(5, 3, false);
+    configs[0].miner.signing_timeout_ms = 2_000;
+    let coordinator = SignerCoordinator::from_config(&configs[0]).unwrap();
+    let proposal = make_test_proposal();
+
+    // slow participants are asked at the same time, not one after another
+    let start = Instant::now();
+    let signatures = coordinator.collect_signatures(&proposal, |index, _proposal, _timeout| {
+        thread::sleep(Duration::from_millis(500));
+        Ok(ClaritySignature([index; 65]))
+    });
+    assert_eq!(signatures.len(), 2);
+    assert!(start.elapsed() < Duration::from_millis(2_000));
+}
+
+#[test]
+fn collect_signatures_times_out() {
+    let mut configs = make_test_configs(5, 3, false);
+    configs[0].miner.signing_timeout_ms = 500;
+    let coordinator = SignerCoordinator::from_config(&configs[0]).unwrap();
+    let proposal = make_test_proposal();
+
+    let finished = Arc::new(Mutex::new(vec![]));
+    let start = Instant::now();
+    let signatures = coordinator.collect_signatures(&proposal, |index, _proposal, timeout| {
+        assert!(timeout <= Duration::from_millis(500));
+        // every participant is unresponsive until its request times out
+        thread::sleep(timeout);
+        finished.lock().unwrap().push(index);
+        Err(Error::RPCError("timed out".into()))
+    });
+    assert_eq!(signatures.len(), 0);
+    assert!(start.elapsed() < Duration::from_millis(2_000));
+
+    // no request outlives the collection
+    let mut finished = finished.lock().unwrap().clone();
+    finished.sort();
+    assert_eq!(finished, vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn fallback_takes_over_only_without_a_proposal() {
+    let configs = make_test_configs(3, 2, true);
+    let coordinators: Vec<_> = configs
+        .iter()
+        .map(|config| SignerCoordinator::from_config(config).unwrap())
+        .collect();
+    let burn_height = 7;
+    let fallback = coordinators
+        .iter()
+        .position(|coordinator| coordinator.is_fallback(burn_height))
+        .unwrap();
+
+    // the fallback's RPC interface records the leader's proposal in the shared log
+    assert!(!coordinators[fallback].has_seen_proposal(burn_height));
+    configs[fallback]
+        .connection_options
+        .block_proposal_log
+        .note_proposal(burn_height);
+    assert!(coordinators[fallback].has_seen_proposal(burn_height));
+    assert!(!coordinators[fallback].has_seen_proposal(burn_height + 1));
+}
+
+#[test]
+fn proposers_commit_to_one_block_per_height() {
+    let configs = make_test_configs(3, 2, true);
+    let coordinator = SignerCoordinator::from_config(&configs[0]).unwrap();
+    let block_a = BlockHeaderHash([0x0a; 32]);
+    let block_b = BlockHeaderHash([0x0b; 32]);
+
+    assert!(coordinator.commit_to_proposal(3, &block_a));
+    assert!(coordinator.has_seen_proposal(3));
+    assert!(coordinator.commit_to_proposal(3, &block_a));
+    assert!(!coordinator.commit_to_proposal(3, &block_b));
+
+    // having signed another miner's block, a node does not propose its own
+    configs[0]
+        .connection_options
+        .block_proposal_log
+        .try_commit_to_block(4, &block_b);
+    assert!(!coordinator.commit_to_proposal(4, &block_a));
+
+    // without leader rotation, every miner proposes for every L1 block
+    let configs = make_test_configs(3, 2, false);
+    let coordinator = SignerCoordinator::from_config(&configs[0]).unwrap();
+    assert!(coordinator.commit_to_proposal(3, &block_a));
+    assert!(coordinator.commit_to_proposal(3, &block_b));
+}
+
+#[test]
+fn signers_accept_only_scheduled_proposers() {
+    let configs = make_test_configs(3, 2, true);
+    let coordinators: Vec<_> = configs
+        .iter()
+        .map(|config| SignerCoordinator::from_config(config).unwrap())
+        .collect();
+    let signer_schedule = configs[0].block_proposer_schedule().unwrap();
+    let contract = QualifiedContractIdentifier::transient();
+
+    let mut proposal = make_test_proposal();
+    proposal.burn_tip_height = 11;
+    let burn_height = proposal.tenure_burn_height();
+    assert_eq!(proposal.proposer(contract.clone()), None);
+
+    for (config, coordinator) in configs.iter().zip(coordinators.iter()) {
+        let mining_key = config.node.mining_key.as_ref().unwrap();
+        coordinator.sign_proposal(&mut proposal, mining_key);
+        let proposer = proposal.proposer(contract.clone()).unwrap();
+        assert_eq!(proposer, public_key_bytes(mining_key));
+        assert_eq!(
+            signer_schedule.may_propose(burn_height, &proposer),
+            coordinator.is_leader(burn_height) || coordinator.is_fallback(burn_height)
+        );
+    }
+
+    // a miner outside the signing set may never propose
+    let stranger = StacksPrivateKey::new();
+    proposal.sign_as_proposer(&stranger, contract.clone());
+    let proposer = proposal.proposer(contract.clone()).unwrap();
+    assert!(!signer_schedule.may_propose(burn_height, &proposer));
+}
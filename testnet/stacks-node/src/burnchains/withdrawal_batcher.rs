@@ -0,0 +1,164 @@
+use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::stacks::db::StacksChainState;
+use stacks::chainstate::stacks::Error as ChainstateError;
+use stacks::clarity_vm::withdrawal::{create_withdrawal_batch_merkle_tree, WithdrawalBatchConfig};
+use stacks::types::chainstate::StacksBlockId;
+use stacks::util::hash::Sha512Trunc256Sum;
+
+use crate::config::Config;
+
+/// Chooses the withdrawal root a miner registers with each of its block commits when withdrawal
+/// batching is enabled.
+///
+/// The batching rule is read from the chainstate of the fork being built on: it is committed to
+/// the genesis block, and takes effect from the first block of `EpochSubnet1`.  Blocks in the
+/// batching range do not register their own withdrawal root.  Instead, once every
+/// block of a batch has been built on by `withdrawal_confirmation_depth` blocks, the root of the
+/// batch tree (see `create_withdrawal_batch_merkle_tree()`) is registered by the miner's next
+/// block commit, so a single L1 contract-call covers every withdrawal made in the batch.  Commits
+/// with nothing to register carry the empty withdrawal root.
+pub struct WithdrawalBatcher {
+    /// the batching rule of the fork last built on, once it has reached `EpochSubnet1`
+    batching: Option<WithdrawalBatchConfig>,
+    confirmation_depth: u64,
+    /// first block height of the oldest batch not yet known to be registered on the L1
+    next_batch_height: u64,
+    /// first block height and root of the last batch found to be registered on the L1
+    last_registered_batch: Option<(u64, Sha512Trunc256Sum)>,
+}
+
+impl WithdrawalBatcher {
+    /// Instantiate a batcher for this node.  Whether withdrawals are batched at all is up to the
+    /// chainstate, not the node's config.
+    pub fn from_config(config: &Config) -> WithdrawalBatcher {
+        WithdrawalBatcher {
+            batching: None,
+            confirmation_depth: config.burnchain.withdrawal_confirmation_depth,
+            next_batch_height: 0,
+            last_registered_batch: None,
+        }
+    }
+
+    /// Get the withdrawal root to submit in the commit for a block at `block_height`, built on
+    /// `parent_block_id`.  Blocks that precede the batching range register their own root, as
+    /// does the first block of `EpochSubnet1`, whose parent has not started batching yet.
+    pub fn get_commit_withdrawal_root(
+        &mut self,
+        chainstate: &mut StacksChainState,
+        sortdb: &SortitionDB,
+        parent_block_id: &StacksBlockId,
+        block_height: u64,
+        block_withdrawal_root: Sha512Trunc256Sum,
+    ) -> Sha512Trunc256Sum {
+        let batching =
+            match chainstate.get_withdrawal_batching(&sortdb.index_conn(), parent_block_id) {
+                Some(batching) => batching,
+                None => return block_withdrawal_root,
+            };
+        // forks can start batching at different heights
+        if self.batching != Some(batching) {
+            self.batching = Some(batching);
+            self.next_batch_height = batching.start_height;
+            self.last_registered_batch = None;
+        }
+        if batching.batch_range(block_height).is_none() {
+            return block_withdrawal_root;
+        }
+        let parent_height = block_height.saturating_sub(1);
+        match self.get_unregistered_batch_root(
+            chainstate,
+            sortdb,
+            &batching,
+            parent_block_id,
+            parent_height,
+        ) {
+            Ok(Some(batch_root)) => {
+                info!(
+                    "Registering withdrawal batch root with block commit";
+                    "block_height" => block_height,
+                    "batch_first_height" => self.next_batch_height,
+                    "withdrawal_root" => %batch_root
+                );
+                batch_root
+            }
+            Ok(None) => Sha512Trunc256Sum([0; 32]),
+            Err(e) => {
+                warn!(
+                    "Failed to find the next withdrawal batch to register: {:?}",
+                    &e
+                );
+                Sha512Trunc256Sum([0; 32])
+            }
+        }
+    }
+
+    /// Find the root of the oldest complete batch in the fork ending at `parent_block_id` that
+    /// has withdrawals and is not registered in the canonical L1 fork.
+    fn get_unregistered_batch_root(
+        &mut self,
+        chainstate: &StacksChainState,
+        sortdb: &SortitionDB,
+        batching: &WithdrawalBatchConfig,
+        parent_block_id: &StacksBlockId,
+        parent_height: u64,
+    ) -> Result<Option<Sha512Trunc256Sum>, ChainstateError> {
+        let sort_tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())
+            .map_err(ChainstateError::DBError)?;
+        let sort_handle = sortdb.index_handle(&sort_tip.sortition_id);
+
+        // a subnet or L1 fork may have replaced the last batch found to be registered; if so,
+        // every batch is checked again
+        if let Some((first_height, root)) = self.last_registered_batch.clone() {
+            let last_height = first_height + batching.batch_blocks - 1;
+            let still_registered = match chainstate.get_withdrawal_roots_in_range(
+                parent_block_id,
+                first_height,
+                last_height,
+            )? {
+                Some(roots) => {
+                    create_withdrawal_batch_merkle_tree(&roots).root() == root
+                        && sort_handle
+                            .has_block_commit_with_withdrawal_root(&root)
+                            .map_err(ChainstateError::DBError)?
+                }
+                None => false,
+            };
+            if !still_registered {
+                debug!(
+                    "Last registered withdrawal batch is no longer canonical; rescanning batches"
+                );
+                self.next_batch_height = batching.start_height;
+                self.last_registered_batch = None;
+            }
+        }
+
+        loop {
+            let first_height = self.next_batch_height;
+            let last_height = first_height + batching.batch_blocks - 1;
+            if parent_height < last_height + self.confirmation_depth {
+                return Ok(None);
+            }
+            let roots = match chainstate.get_withdrawal_roots_in_range(
+                parent_block_id,
+                first_height,
+                last_height,
+            )? {
+                Some(roots) => roots,
+                None => return Ok(None),
+            };
+
+            let has_withdrawals = roots.iter().any(|root| *root != Sha512Trunc256Sum([0; 32]));
+            if has_withdrawals {
+                let batch_root = create_withdrawal_batch_merkle_tree(&roots).root();
+                if !sort_handle
+                    .has_block_commit_with_withdrawal_root(&batch_root)
+                    .map_err(ChainstateError::DBError)?
+                {
+                    return Ok(Some(batch_root));
+                }
+                self.last_registered_batch = Some((first_height, batch_root));
+            }
+            self.next_batch_height = last_height + 1;
+        }
+    }
+}
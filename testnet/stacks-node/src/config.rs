@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::convert::TryInto;
 use std::fs;
 use std::net::{SocketAddr, ToSocketAddrs};
@@ -5,15 +6,27 @@ use std::path::PathBuf;
 
 use rand::RngCore;
 
+use stacks::burnchains::Address;
 use stacks::chainstate::coordinator::comm::CoordinatorChannels;
+use stacks::chainstate::stacks::db::genesis::{
+    GenesisContract, GenesisFungibleTokenAllocation, GenesisNonFungibleTokenAllocation, GenesisSpec,
+};
+use stacks::chainstate::stacks::db::DEFAULT_DEPOSIT_BATCH_SIZE;
 use stacks::chainstate::stacks::index::marf::MARFOpenOpts;
 use stacks::chainstate::stacks::index::storage::TrieHashCalculationMode;
 use stacks::chainstate::stacks::miner::BlockBuilderSettings;
+use stacks::chainstate::stacks::system_priority::SystemPriorityPolicy;
 use stacks::chainstate::stacks::StacksPrivateKey;
 use stacks::chainstate::stacks::TransactionAnchorMode;
 use stacks::chainstate::stacks::MAX_BLOCK_LEN;
-use stacks::core::mempool::MemPoolWalkSettings;
-use stacks::core::{StacksEpoch, NETWORK_ID_TESTNET};
+use stacks::core::mempool::{
+    ContractDeployLimits, ContractDeployQuota, MemPoolBloomConfig, MemPoolGCPolicy,
+    MemPoolGCStrategy, MemPoolOriginQuota, MemPoolWalkRanking, MemPoolWalkSettings,
+    MempoolAdmissionPolicyConfig, SponsorshipPolicy, StrictAdmissionPolicy,
+};
+use stacks::core::{
+    StacksEpoch, StacksEpochId, NETWORK_ID_TESTNET, STACKS_EPOCHS_REGTEST, STACKS_EPOCH_MAX,
+};
 use stacks::core::{
     LAYER_1_CHAIN_ID_MAINNET, LAYER_1_CHAIN_ID_TESTNET, PEER_VERSION_MAINNET, PEER_VERSION_TESTNET,
 };
@@ -25,13 +38,18 @@ use stacks::cost_estimates::metrics::ProportionalDotProduct;
 use stacks::cost_estimates::CostEstimator;
 use stacks::cost_estimates::FeeEstimator;
 use stacks::cost_estimates::PessimisticEstimator;
+use stacks::net::admin::{AdminAuth, AdminCredential, AdminRole};
 use stacks::net::connection::ConnectionOptions;
-use stacks::net::{Neighbor, NeighborKey, PeerAddress};
+use stacks::net::proposals::BlockProposerSchedule;
+use stacks::net::ratelimit::{RPCRateLimits, TokenBucketLimit};
+use stacks::net::{Neighbor, NeighborKey, PeerAddress, SubnetId};
+use stacks::types::chainstate::{StacksAddress, TrieHash};
 use stacks::util::get_epoch_time_ms;
-use stacks::util::hash::hex_bytes;
+use stacks::util::hash::{hex_bytes, Sha256Sum};
 use stacks::util::secp256k1::Secp256k1PrivateKey;
 use stacks::util::secp256k1::Secp256k1PublicKey;
-use stacks::vm::types::{AssetIdentifier, PrincipalData, QualifiedContractIdentifier};
+use stacks::util_lib::strings::UrlString;
+use stacks::vm::types::{AssetIdentifier, PrincipalData, QualifiedContractIdentifier, Value};
 
 use crate::burnchains::commitment::MultiMinerParticipant;
 use crate::burnchains::l1_events::L1Controller;
@@ -46,6 +64,10 @@ pub const BURNCHAIN_NAME_STACKS_TESTNET_L1: &str = "stacks_layer_1";
 pub const BURNCHAIN_NAME_STACKS_MAINNET_L1: &str = "stacks_layer_1::mainnet";
 pub const BURNCHAIN_NAME_MOCKSTACK: &str = "mockstack";
 pub const DEFAULT_L1_OBSERVER_PORT: u16 = 50303;
+/// Default percentage of each block's execution budget reserved for system priority transactions
+pub const DEFAULT_SYSTEM_PRIORITY_RESERVED_PERCENT: u64 = 10;
+/// Default number of L1 blocks ahead of a miner key rotation that the next key is registered
+pub const DEFAULT_MINING_KEY_ROTATION_LEAD_BLOCKS: u64 = 6;
 
 pub const SUBNET_SUBDIR_NAME: &str = "subnet";
 
@@ -58,6 +80,9 @@ pub struct ConfigFile {
     pub connection_options: Option<ConnectionOptionsFile>,
     pub fee_estimation: Option<FeeEstimationConfigFile>,
     pub miner: Option<MinerConfigFile>,
+    pub subnet_epochs: Option<Vec<SubnetEpochConfigFile>>,
+    pub system_priority: Option<SystemPriorityConfigFile>,
+    pub genesis: Option<GenesisConfigFile>,
 }
 
 #[derive(Clone, Deserialize, Default)]
@@ -68,6 +93,7 @@ pub struct LegacyMstxConfigFile {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use stacks::vm::ClarityName;
 
     #[test]
     fn should_load_legacy_mstx_balances_toml() {
@@ -112,6 +138,733 @@ mod tests {
             "ST2TFVBMRPS5SSNP98DQKQ5JNB2B6NZM91C4K3P7B"
         );
     }
+
+    #[test]
+    fn should_advertise_public_address() {
+        assert_eq!(
+            resolve_public_ip_address("1.2.3.4:30444"),
+            Ok("1.2.3.4:30444".parse::<SocketAddr>().unwrap())
+        );
+        assert_eq!(
+            resolve_public_ip_address("[::1]:30444"),
+            Ok("[::1]:30444".parse::<SocketAddr>().unwrap())
+        );
+        // no port, so this fails without a DNS lookup
+        assert!(resolve_public_ip_address("1.2.3.4").is_err());
+
+        assert_eq!(make_public_rpc_port(None, "10.0.0.5:30443"), Ok(30443));
+        assert_eq!(make_public_rpc_port(Some(443), "10.0.0.5:30443"), Ok(443));
+        assert!(make_public_rpc_port(Some(0), "10.0.0.5:30443").is_err());
+        assert!(make_public_rpc_port(None, "not-an-address").is_err());
+
+        assert_eq!(
+            make_public_data_url("1.2.3.4:30444", 30443),
+            "http://1.2.3.4:30443"
+        );
+        assert_eq!(
+            make_public_data_url("subnet.example.com:30444", 443),
+            "http://subnet.example.com:443"
+        );
+        assert_eq!(
+            make_public_data_url("[::1]:30444", 30443),
+            "http://[::1]:30443"
+        );
+    }
+
+    #[test]
+    fn should_advertise_public_rpc_port() {
+        let config = Config::from_config_file(ConfigFile::from_str(
+            r#"
+            [node]
+            rpc_bind = "0.0.0.0:30443"
+
+            [connection_options]
+            public_ip_address = "1.2.3.4:30444"
+            public_rpc_port = 8080
+            "#,
+        ));
+        assert_eq!(config.node.data_url, "http://1.2.3.4:8080");
+
+        // an explicit data URL takes precedence
+        let config = Config::from_config_file(ConfigFile::from_str(
+            r#"
+            [node]
+            rpc_bind = "0.0.0.0:30443"
+            data_url = "https://subnet.example.com"
+
+            [connection_options]
+            public_ip_address = "1.2.3.4:30444"
+            public_rpc_port = 8080
+            "#,
+        ));
+        assert_eq!(config.node.data_url, "https://subnet.example.com");
+    }
+
+    #[test]
+    fn should_load_subnet_epochs_toml() {
+        let config = ConfigFile::from_str(
+            r#"
+            [[subnet_epochs]]
+            epoch_name = "2.05"
+            start_height = 500
+
+            [[subnet_epochs]]
+            epoch_name = "subnet-1"
+            start_height = 3000
+            runtime = 200000000000
+            read_count = 100000
+            cost_profile = "subnet"
+            "#,
+        );
+        let epoch_configs = config.subnet_epochs.expect("Failed to parse subnet epochs");
+        let epochs = make_subnet_epochs(&STACKS_EPOCHS_REGTEST[..], &epoch_configs).unwrap();
+        assert!(make_subnet_cost_profile(&epoch_configs).unwrap());
+
+        assert_eq!(epochs.len(), 4);
+        assert_eq!(epochs[1].epoch_id, StacksEpochId::Epoch20);
+        assert_eq!(epochs[1].end_height, 500);
+        assert_eq!(epochs[2].epoch_id, StacksEpochId::Epoch2_05);
+        assert_eq!(epochs[2].start_height, 500);
+        assert_eq!(epochs[2].end_height, 3000);
+        assert_eq!(epochs[2].block_limit, STACKS_EPOCHS_REGTEST[2].block_limit);
+        assert_eq!(epochs[3].epoch_id, StacksEpochId::EpochSubnet1);
+        assert_eq!(epochs[3].start_height, 3000);
+        assert_eq!(epochs[3].end_height, STACKS_EPOCH_MAX);
+        assert_eq!(epochs[3].block_limit.runtime, 200_000_000_000);
+        assert_eq!(epochs[3].block_limit.read_count, 100_000);
+        assert_eq!(
+            epochs[3].block_limit.write_count,
+            STACKS_EPOCHS_REGTEST[3].block_limit.write_count
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_subnet_epochs() {
+        let epoch_config = |epoch_name: &str| SubnetEpochConfigFile {
+            epoch_name: epoch_name.to_string(),
+            ..SubnetEpochConfigFile::default()
+        };
+
+        // unknown epoch
+        assert!(make_subnet_epochs(&STACKS_EPOCHS_REGTEST[..], &[epoch_config("3.0")]).is_err());
+        // the pre-subnet epoch
+        assert!(make_subnet_epochs(&STACKS_EPOCHS_REGTEST[..], &[epoch_config("1.0")]).is_err());
+        // duplicate epoch
+        assert!(make_subnet_epochs(
+            &STACKS_EPOCHS_REGTEST[..],
+            &[epoch_config("2.05"), epoch_config("2.05")]
+        )
+        .is_err());
+        // epochs out of order
+        assert!(make_subnet_epochs(
+            &STACKS_EPOCHS_REGTEST[..],
+            &[SubnetEpochConfigFile {
+                start_height: Some(2500),
+                ..epoch_config("2.05")
+            }]
+        )
+        .is_err());
+        // zero block limit
+        assert!(make_subnet_epochs(
+            &STACKS_EPOCHS_REGTEST[..],
+            &[SubnetEpochConfigFile {
+                write_count: Some(0),
+                ..epoch_config("subnet-1")
+            }]
+        )
+        .is_err());
+        // cost profile of an epoch without cost contracts
+        assert!(make_subnet_cost_profile(&[SubnetEpochConfigFile {
+            cost_profile: Some("subnet".into()),
+            ..epoch_config("2.05")
+        }])
+        .is_err());
+        // unknown cost profile
+        assert!(make_subnet_cost_profile(&[SubnetEpochConfigFile {
+            cost_profile: Some("cheap".into()),
+            ..epoch_config("subnet-1")
+        }])
+        .is_err());
+    }
+
+    #[test]
+    fn should_load_system_priority_toml() {
+        let config = ConfigFile::from_str(
+            r#"
+            [system_priority]
+            contracts = ["ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.governance"]
+            principals = ["ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2"]
+            reserved_budget_percent = 25
+            "#,
+        );
+        let policy = make_system_priority_policy(
+            &config
+                .system_priority
+                .expect("Failed to parse system priority section"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            policy.contracts,
+            vec![QualifiedContractIdentifier::parse(
+                "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.governance"
+            )
+            .unwrap()]
+        );
+        assert_eq!(
+            policy.authorized_principals,
+            vec![StacksAddress::from_string("ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2").unwrap()]
+        );
+        assert_eq!(policy.reserved_budget_percent, 25);
+    }
+
+    #[test]
+    fn should_load_genesis_toml() {
+        let config = ConfigFile::from_str(
+            r#"
+            [genesis]
+            expected_root_hash = "0101010101010101010101010101010101010101010101010101010101010101"
+
+            [[genesis.contracts]]
+            contract_id = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.tokens"
+            code_body = "(define-fungible-token coin) (define-non-fungible-token badge uint)"
+
+            [[genesis.ft_allocations]]
+            contract_id = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.tokens"
+            token_name = "coin"
+            recipient = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM"
+            amount = 1000
+
+            [[genesis.nft_allocations]]
+            contract_id = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.tokens"
+            token_name = "badge"
+            recipient = "ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.vault"
+            id = 7
+            "#,
+        );
+        let genesis_spec =
+            make_genesis_spec(&config.genesis.expect("Failed to parse genesis section")).unwrap();
+
+        let contract_id =
+            QualifiedContractIdentifier::parse("ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.tokens")
+                .unwrap();
+        assert_eq!(genesis_spec.contracts.len(), 1);
+        assert_eq!(genesis_spec.contracts[0].contract_id, contract_id);
+        assert_eq!(genesis_spec.ft_allocations[0].amount, 1000);
+        assert_eq!(
+            genesis_spec.ft_allocations[0].asset_identifier,
+            AssetIdentifier {
+                contract_identifier: contract_id,
+                asset_name: ClarityName::try_from("coin".to_string()).unwrap(),
+            }
+        );
+        assert_eq!(genesis_spec.nft_allocations[0].id, Value::UInt(7));
+        assert_eq!(
+            genesis_spec.nft_allocations[0].recipient,
+            PrincipalData::parse("ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.vault").unwrap()
+        );
+        assert_eq!(genesis_spec.expected_root_hash, Some(TrieHash([0x01; 32])));
+
+        // a contract needs exactly one source for its code
+        assert!(make_genesis_spec(&GenesisConfigFile {
+            contracts: Some(vec![GenesisContractConfigFile {
+                contract_id: "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.tokens".into(),
+                code_body: None,
+                path: None,
+            }]),
+            ..GenesisConfigFile::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn should_reject_invalid_system_priority() {
+        let system_priority = SystemPriorityConfigFile {
+            contracts: vec!["ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.governance".into()],
+            principals: vec!["ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2".into()],
+            reserved_budget_percent: None,
+        };
+        assert_eq!(
+            make_system_priority_policy(&system_priority)
+                .unwrap()
+                .reserved_budget_percent,
+            DEFAULT_SYSTEM_PRIORITY_RESERVED_PERCENT
+        );
+
+        // no contracts
+        assert!(make_system_priority_policy(&SystemPriorityConfigFile {
+            contracts: vec![],
+            ..system_priority.clone()
+        })
+        .is_err());
+        // a contract principal is never a transaction's origin
+        assert!(make_system_priority_policy(&SystemPriorityConfigFile {
+            principals: vec!["ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.governance".into()],
+            ..system_priority.clone()
+        })
+        .is_err());
+        // nothing left for other transactions
+        assert!(make_system_priority_policy(&SystemPriorityConfigFile {
+            reserved_budget_percent: Some(100),
+            ..system_priority.clone()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn should_load_mempool_admission_policy() {
+        let config = ConfigFile::from_str(
+            r#"
+            [connection_options]
+            mempool_admission_policy = "strict"
+            mempool_max_contract_deploy_size = 4096
+            mempool_min_token_transfer_fee = 1000
+            "#,
+        );
+        let opts = config
+            .connection_options
+            .expect("Failed to parse connection options section");
+        assert_eq!(
+            make_mempool_admission_policy(&opts).unwrap(),
+            MempoolAdmissionPolicyConfig::Strict(StrictAdmissionPolicy {
+                max_contract_deploy_size: 4096,
+                min_token_transfer_fee: 1000,
+            })
+        );
+
+        assert_eq!(
+            make_mempool_admission_policy(&ConnectionOptionsFile::default()).unwrap(),
+            MempoolAdmissionPolicyConfig::Default
+        );
+        // strict limits are required
+        assert!(make_mempool_admission_policy(&ConnectionOptionsFile {
+            mempool_max_contract_deploy_size: None,
+            ..opts.clone()
+        })
+        .is_err());
+        assert!(make_mempool_admission_policy(&ConnectionOptionsFile {
+            mempool_admission_policy: Some("lenient".into()),
+            ..opts.clone()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn should_load_contract_deploy_limits() {
+        let config = Config::from_config_file(ConfigFile::from_str(
+            r#"
+            [connection_options]
+            max_contract_code_body_size = 20000
+            max_contract_ast_depth = 16
+
+            [burnchain]
+            contract_identifier = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.subnet"
+            "#,
+        ));
+        let limits = ContractDeployLimits {
+            max_code_body_size: Some(20000),
+            max_ast_depth: Some(16),
+            max_analysis_time_ms: None,
+        };
+        assert_eq!(config.connection_options.contract_deploy_limits, limits);
+        assert_eq!(
+            config
+                .make_block_builder_settings(1, false)
+                .mempool_settings
+                .contract_deploy_limits,
+            limits
+        );
+    }
+
+    #[test]
+    fn should_load_mempool_sync_http_urls() {
+        let config = Config::from_config_file(ConfigFile::from_str(
+            r#"
+            [connection_options]
+            mempool_sync_http_urls = ["http://10.0.0.5:30443", "https://subnet.example.com"]
+
+            [burnchain]
+            contract_identifier = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.subnet"
+            "#,
+        ));
+        assert_eq!(
+            config.connection_options.mempool_sync_http_urls,
+            vec![
+                UrlString::try_from("http://10.0.0.5:30443").unwrap(),
+                UrlString::try_from("https://subnet.example.com").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_load_mempool_bloom_config() {
+        let config = Config::from_config_file(ConfigFile::from_str(
+            r#"
+            [connection_options]
+            mempool_bloom_depth = 4
+            mempool_bloom_max_txs = 32768
+
+            [burnchain]
+            contract_identifier = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.subnet"
+            "#,
+        ));
+        assert_eq!(
+            config.connection_options.mempool_bloom,
+            MemPoolBloomConfig {
+                depth: 4,
+                max_txs: 32768,
+                error_rate: MemPoolBloomConfig::default().error_rate,
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "mempool_bloom_error_rate must be between 0 and 1")]
+    fn should_reject_invalid_mempool_bloom_error_rate() {
+        Config::from_config_file(ConfigFile::from_str(
+            r#"
+            [connection_options]
+            mempool_bloom_error_rate = 1.5
+
+            [burnchain]
+            contract_identifier = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.subnet"
+            "#,
+        ));
+    }
+
+    #[test]
+    fn should_load_mempool_gc_policy() {
+        let config = Config::from_config_file(ConfigFile::from_str(
+            r#"
+            [connection_options]
+            mempool_gc_strategy = "byte_cap"
+            mempool_max_bytes = 100000000
+            mempool_max_tx_age = 64
+
+            [burnchain]
+            contract_identifier = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.subnet"
+            "#,
+        ));
+        assert_eq!(
+            config.connection_options.mempool_gc_policy,
+            MemPoolGCPolicy {
+                max_tx_age: 64,
+                strategy: MemPoolGCStrategy::ByteCap {
+                    max_bytes: 100000000
+                },
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the fee_floor strategy requires mempool_max_txs")]
+    fn should_reject_fee_floor_without_cap() {
+        Config::from_config_file(ConfigFile::from_str(
+            r#"
+            [connection_options]
+            mempool_gc_strategy = "fee_floor"
+
+            [burnchain]
+            contract_identifier = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.subnet"
+            "#,
+        ));
+    }
+
+    #[test]
+    fn should_load_rpc_rate_limits() {
+        let config = Config::from_config_file(ConfigFile::from_str(
+            r#"
+            [connection_options]
+            read_only_call_rate_limit = 20
+            read_only_call_rate_burst = 50
+            tx_broadcast_rate_limit = 5
+            block_validation_rate_limit = 1
+            block_validation_rate_burst = 2
+            map_iteration_rate_limit = 3
+            proof_rate_limit = 10
+            proof_rate_burst = 30
+            mempool_sync_rate_limit = 2
+            mempool_listing_rate_limit = 4
+            mempool_listing_rate_burst = 8
+            state_diff_rate_limit = 6
+
+            [burnchain]
+            contract_identifier = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.subnet"
+            "#,
+        ));
+        assert_eq!(
+            config.connection_options.rpc_rate_limits,
+            RPCRateLimits {
+                read_only_call: Some(TokenBucketLimit {
+                    rate: 20,
+                    burst: 50
+                }),
+                tx_broadcast: Some(TokenBucketLimit { rate: 5, burst: 5 }),
+                block_validation: Some(TokenBucketLimit { rate: 1, burst: 2 }),
+                map_iteration: Some(TokenBucketLimit { rate: 3, burst: 3 }),
+                proof: Some(TokenBucketLimit {
+                    rate: 10,
+                    burst: 30
+                }),
+                mempool_sync: Some(TokenBucketLimit { rate: 2, burst: 2 }),
+                mempool_listing: Some(TokenBucketLimit { rate: 4, burst: 8 }),
+                state_diff: Some(TokenBucketLimit { rate: 6, burst: 6 }),
+            }
+        );
+    }
+
+    #[test]
+    fn should_load_admin_credentials() {
+        let public_key = Secp256k1PublicKey::from_private(&Secp256k1PrivateKey::new());
+        let config = ConfigFile::from_str(&format!(
+            r#"
+            [[connection_options.admin_credentials]]
+            role = "read_only"
+            secret = "metrics-secret"
+
+            [[connection_options.admin_credentials]]
+            role = "operator"
+            public_key = "{}"
+            "#,
+            public_key.to_hex()
+        ));
+        let opts = config
+            .connection_options
+            .expect("Failed to parse connection options section");
+        assert_eq!(
+            make_admin_credentials(&opts).unwrap(),
+            vec![
+                AdminCredential {
+                    role: AdminRole::ReadOnly,
+                    auth: AdminAuth::SharedSecret("metrics-secret".into()),
+                },
+                AdminCredential {
+                    role: AdminRole::Operator,
+                    auth: AdminAuth::PublicKey(public_key),
+                },
+            ]
+        );
+
+        assert!(make_admin_credentials(&ConnectionOptionsFile::default())
+            .unwrap()
+            .is_empty());
+        let invalid = |role: &str, secret: Option<&str>, public_key: Option<&str>| {
+            make_admin_credentials(&ConnectionOptionsFile {
+                admin_credentials: Some(vec![AdminCredentialFile {
+                    role: role.into(),
+                    secret: secret.map(String::from),
+                    public_key: public_key.map(String::from),
+                }]),
+                ..ConnectionOptionsFile::default()
+            })
+            .is_err()
+        };
+        assert!(invalid("root", Some("secret"), None));
+        assert!(invalid("operator", None, None));
+        assert!(invalid(
+            "operator",
+            Some("secret"),
+            Some(&public_key.to_hex())
+        ));
+        assert!(invalid("operator", None, Some("not-a-key")));
+    }
+
+    #[test]
+    fn should_load_block_production_mode() {
+        let config = ConfigFile::from_str(
+            r#"
+            [miner]
+            block_production = "timer"
+            block_interval_ms = 2000
+            block_mempool_threshold = 50
+            anchor_interval = 5
+            "#,
+        );
+        let miner = config.miner.expect("Failed to parse miner section");
+        let mode = make_block_production_mode(&miner).unwrap();
+        assert_eq!(
+            mode,
+            BlockProductionMode::Timer {
+                interval_ms: 2000,
+                mempool_threshold: Some(50),
+                anchor_interval: 5,
+            }
+        );
+        assert!(mode.should_anchor_at(10));
+        assert!(!mode.should_anchor_at(11));
+        assert_eq!(mode.microblock_interval_ms(30_000), 2000);
+
+        let default_mode = make_block_production_mode(&MinerConfigFile::default()).unwrap();
+        assert_eq!(default_mode, BlockProductionMode::L1Cadence);
+        assert!(default_mode.should_anchor_at(11));
+
+        // timer mode needs an interval
+        assert!(make_block_production_mode(&MinerConfigFile {
+            block_interval_ms: None,
+            ..miner.clone()
+        })
+        .is_err());
+        assert!(make_block_production_mode(&MinerConfigFile {
+            anchor_interval: Some(0),
+            ..miner.clone()
+        })
+        .is_err());
+        assert!(make_block_production_mode(&MinerConfigFile {
+            block_production: Some("eager".into()),
+            ..miner.clone()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn should_load_tx_ranking() {
+        let config = ConfigFile::from_str(
+            r#"
+            [miner]
+            tx_ranking = "fee_per_length"
+            "#,
+        );
+        let miner = config.miner.expect("Failed to parse miner section");
+        assert_eq!(
+            make_tx_ranking(&miner).unwrap(),
+            MemPoolWalkRanking::FeePerLength
+        );
+        assert_eq!(
+            make_tx_ranking(&MinerConfigFile {
+                tx_ranking: Some("fee_per_cost".into()),
+                ..miner.clone()
+            })
+            .unwrap(),
+            MemPoolWalkRanking::FeePerCost
+        );
+        assert_eq!(
+            make_tx_ranking(&MinerConfigFile::default()).unwrap(),
+            MemPoolWalkRanking::Fee
+        );
+        assert!(make_tx_ranking(&MinerConfigFile {
+            tx_ranking: Some("fee_per_byte".into()),
+            ..miner.clone()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn should_load_l1_rpc_failover() {
+        let config = Config::from_config_file(ConfigFile::from_str(
+            r#"
+            [burnchain]
+            contract_identifier = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.subnet"
+            fallback_rpc_urls = ["http://10.0.0.2:20443", "https://l1.example.com"]
+            rpc_failover_backoff_ms = 500
+            "#,
+        ));
+        assert_eq!(
+            config.burnchain.fallback_rpc_urls,
+            vec!["http://10.0.0.2:20443", "https://l1.example.com"]
+        );
+        assert_eq!(config.burnchain.rpc_failover_backoff_ms, 500);
+        assert!(validate_l1_rpc_failover(&config.burnchain).is_ok());
+
+        assert!(validate_l1_rpc_failover(&BurnchainConfig {
+            fallback_rpc_urls: vec!["10.0.0.2:20443".into()],
+            ..config.burnchain.clone()
+        })
+        .is_err());
+        assert!(validate_l1_rpc_failover(&BurnchainConfig {
+            rpc_failover_max_backoff_ms: 100,
+            ..config.burnchain.clone()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn should_load_contract_version_mismatch_action() {
+        let config = Config::from_config_file(ConfigFile::from_str(
+            r#"
+            [burnchain]
+            contract_identifier = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.subnet"
+            "#,
+        ));
+        assert_eq!(
+            config.burnchain.contract_version_mismatch,
+            ContractVersionMismatchAction::Exit
+        );
+
+        let config = Config::from_config_file(ConfigFile::from_str(
+            r#"
+            [burnchain]
+            contract_identifier = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.subnet"
+            contract_version_mismatch = "degrade"
+            "#,
+        ));
+        assert_eq!(
+            config.burnchain.contract_version_mismatch,
+            ContractVersionMismatchAction::Degrade
+        );
+
+        assert!(make_contract_version_mismatch_action(Some("ignore")).is_err());
+    }
+
+    #[test]
+    fn should_load_mining_key_rotation_toml() {
+        let config = Config::from_config_file(ConfigFile::from_str(
+            r#"
+            [node]
+            mining_key = "7287ba251d44a4d3fd9276c88ce34c5c52a038955511cccaf77e61068649c17801"
+            mining_key_rotation_lead_blocks = 10
+
+            [[node.mining_key_rotation]]
+            mining_key = "530d9f61984c888536871c6573073bdfc0058896dc1adfe9a6a10dfacadc209101"
+            activation_burn_height = 100
+
+            [[node.mining_key_rotation]]
+            mining_key = "d655b2523bcd65e34889725c73064feb17ceb796831c0e111ba1a552b0f31b3901"
+            activation_burn_height = 200
+
+            [burnchain]
+            contract_identifier = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.subnet"
+            "#,
+        ));
+
+        let rotations = &config.node.mining_key_rotation;
+        assert_eq!(rotations.len(), 2);
+        assert_eq!(
+            rotations[0].mining_key.to_hex(),
+            "530d9f61984c888536871c6573073bdfc0058896dc1adfe9a6a10dfacadc209101"
+        );
+        assert_eq!(rotations[0].activation_burn_height, 100);
+        assert_eq!(rotations[1].activation_burn_height, 200);
+        assert_eq!(config.node.mining_key_rotation_lead_blocks, 10);
+    }
+
+    #[test]
+    fn should_reject_invalid_mining_key_rotation() {
+        let rotation = |activation_burn_height: u64| MiningKeyRotationFile {
+            mining_key: "530d9f61984c888536871c6573073bdfc0058896dc1adfe9a6a10dfacadc209101".into(),
+            activation_burn_height,
+        };
+        assert_eq!(
+            make_mining_key_rotations(true, &[rotation(100), rotation(200)])
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // no initial key to rotate from
+        assert!(make_mining_key_rotations(false, &[rotation(100)]).is_err());
+        // rotations out of order
+        assert!(make_mining_key_rotations(true, &[rotation(200), rotation(100)]).is_err());
+        assert!(make_mining_key_rotations(true, &[rotation(100), rotation(100)]).is_err());
+        // bad key
+        assert!(make_mining_key_rotations(
+            true,
+            &[MiningKeyRotationFile {
+                mining_key: "not a key".into(),
+                ..rotation(100)
+            }]
+        )
+        .is_err());
+    }
 }
 
 impl ConfigFile {
@@ -215,6 +968,9 @@ pub struct Config {
     pub burnchain: BurnchainConfig,
     pub node: NodeConfig,
     pub initial_balances: Vec<InitialBalance>,
+    /// Contracts and token allocations to materialize in the genesis block, on top of
+    /// `initial_balances`
+    pub genesis: GenesisSpec,
     pub events_observers: Vec<EventObserverConfig>,
     pub connection_options: ConnectionOptions,
     pub miner: MinerConfig,
@@ -238,6 +994,7 @@ lazy_static! {
         max_clients_per_host: 4,        // maximum number of inbound p2p connections per host we permit
         soft_max_neighbors_per_host: 1, // soft limit on the number of neighbors per host we permit
         soft_max_neighbors_per_org: 32, // soft limit on the number of neighbors per AS we permit (TODO: for now it must be greater than num_neighbors)
+        min_outbound_orgs: 3,           // number of distinct AS-owning organizations our outbound neighbors should span
         soft_max_clients_per_host: 4,   // soft limit on how many inbound p2p connections per host we permit
         max_http_clients: 1000,         // maximum number of HTTP connections
         max_neighbors_of_neighbor: 10,  // maximum number of neighbors we'll handshake with when doing a neighbor walk (I/O for this can be expensive, so keep small-ish)
@@ -252,12 +1009,509 @@ lazy_static! {
     };
 }
 
+/// Resolve the `host:port` address that this node advertises to its peers.  The host may be an
+/// IP address or a DNS name, so that nodes behind a load balancer can advertise a stable name.
+/// The name is resolved once, when the configuration is loaded.
+fn resolve_public_ip_address(public_ip_address: &str) -> Result<SocketAddr, String> {
+    public_ip_address
+        .to_socket_addrs()
+        .map_err(|e| {
+            format!(
+                "failed to resolve public_ip_address {}: {:?}",
+                public_ip_address, &e
+            )
+        })?
+        .next()
+        .ok_or_else(|| {
+            format!(
+                "public_ip_address {} did not resolve to any address",
+                public_ip_address
+            )
+        })
+}
+
+/// Get the port at which this node's RPC interface is reachable from outside.  This is
+/// `public_rpc_port` if it is set, and otherwise the port `rpc_bind` binds to, which is only
+/// right if nothing between the node and its clients remaps ports.
+fn make_public_rpc_port(public_rpc_port: Option<u16>, rpc_bind: &str) -> Result<u16, String> {
+    match public_rpc_port {
+        Some(0) => Err("public_rpc_port must be positive".into()),
+        Some(port) => Ok(port),
+        None => rpc_bind
+            .parse::<SocketAddr>()
+            .map(|addr| addr.port())
+            .map_err(|e| format!("failed to parse rpc_bind {}: {:?}", rpc_bind, &e)),
+    }
+}
+
+/// Make the data URL advertised for a node whose p2p interface is reachable at
+/// `public_ip_address`, and whose RPC interface is reachable at `public_rpc_port` on that host.
+fn make_public_data_url(public_ip_address: &str, public_rpc_port: u16) -> String {
+    let public_host = match public_ip_address.rsplit_once(':') {
+        Some((host, _port)) => host,
+        None => public_ip_address,
+    };
+    format!("http://{}:{}", public_host, public_rpc_port)
+}
+
+/// Apply the `[[subnet_epochs]]` overrides to `default_epochs`, and check that the resulting
+/// schedule is one the sortition DB will accept.  Each epoch's end height is the next epoch's
+/// start height.  The resulting schedule, block limits included, is committed to the genesis
+/// block, so it cannot change once the chainstate exists.
+/// The multi-miner signing set of a node with this commit strategy and mining key -- every other
+/// participant, and the node itself -- and which of them may propose each block.  Returns None if
+/// the node commits directly to the subnet contract.
+fn make_block_proposer_schedule(
+    commit_strategy: &CommitStrategy,
+    mining_key: Option<&Secp256k1PrivateKey>,
+    leader_rotation: bool,
+) -> Option<BlockProposerSchedule> {
+    let other_participants = match commit_strategy {
+        CommitStrategy::Direct => return None,
+        CommitStrategy::MultiMiner {
+            other_participants, ..
+        } => other_participants,
+    };
+    let mut signing_set: Vec<_> = other_participants.iter().map(|p| p.public_key).collect();
+    if let Some(mining_key) = mining_key {
+        let mut key = [0u8; 33];
+        key.copy_from_slice(&Secp256k1PublicKey::from_private(mining_key).to_bytes_compressed());
+        signing_set.push(key);
+    }
+    Some(BlockProposerSchedule::new(signing_set, leader_rotation))
+}
+
+fn make_subnet_epochs(
+    default_epochs: &[StacksEpoch],
+    epoch_configs: &[SubnetEpochConfigFile],
+) -> Result<Vec<StacksEpoch>, String> {
+    let mut epochs = default_epochs.to_vec();
+    epochs.sort();
+
+    let mut seen_epochs = HashSet::new();
+    for epoch_config in epoch_configs.iter() {
+        let epoch = epochs
+            .iter_mut()
+            .find(|epoch| epoch.epoch_id.to_string() == epoch_config.epoch_name)
+            .ok_or_else(|| format!("unknown epoch '{}'", epoch_config.epoch_name))?;
+        if epoch.epoch_id == StacksEpochId::Epoch10 {
+            return Err("epoch 1.0 precedes the subnet and cannot be configured".into());
+        }
+        if !seen_epochs.insert(epoch.epoch_id) {
+            return Err(format!("epoch {} is configured twice", epoch.epoch_id));
+        }
+
+        if let Some(start_height) = epoch_config.start_height {
+            epoch.start_height = start_height;
+        }
+        let block_limit = &mut epoch.block_limit;
+        block_limit.write_length = epoch_config
+            .write_length
+            .unwrap_or(block_limit.write_length);
+        block_limit.write_count = epoch_config.write_count.unwrap_or(block_limit.write_count);
+        block_limit.read_length = epoch_config.read_length.unwrap_or(block_limit.read_length);
+        block_limit.read_count = epoch_config.read_count.unwrap_or(block_limit.read_count);
+        block_limit.runtime = epoch_config.runtime.unwrap_or(block_limit.runtime);
+    }
+
+    for i in 0..epochs.len() {
+        let end_height = match epochs.get(i + 1) {
+            Some(next_epoch) => next_epoch.start_height,
+            None => STACKS_EPOCH_MAX,
+        };
+        let epoch = &mut epochs[i];
+        if end_height < epoch.start_height || epoch.start_height >= STACKS_EPOCH_MAX {
+            return Err(format!(
+                "epoch {} starts at height {}, after the epoch that follows it",
+                epoch.epoch_id, epoch.start_height
+            ));
+        }
+        epoch.end_height = end_height;
+
+        let block_limit = &epoch.block_limit;
+        if epoch.epoch_id != StacksEpochId::Epoch10
+            && (block_limit.write_length == 0
+                || block_limit.write_count == 0
+                || block_limit.read_length == 0
+                || block_limit.read_count == 0
+                || block_limit.runtime == 0)
+        {
+            return Err(format!(
+                "epoch {} has a zero block limit, so no block could include a transaction",
+                epoch.epoch_id
+            ));
+        }
+    }
+
+    Ok(epochs)
+}
+
+/// Whether the `[[subnet_epochs]]` overrides select the subnet cost profile.
+fn make_subnet_cost_profile(epoch_configs: &[SubnetEpochConfigFile]) -> Result<bool, String> {
+    let mut use_subnet_costs = false;
+    for epoch_config in epoch_configs.iter() {
+        let cost_profile = match epoch_config.cost_profile.as_ref() {
+            Some(cost_profile) => cost_profile,
+            None => continue,
+        };
+        if epoch_config.epoch_name != StacksEpochId::EpochSubnet1.to_string() {
+            return Err(format!(
+                "epoch {} does not instantiate cost contracts, so it cannot select a cost profile",
+                epoch_config.epoch_name
+            ));
+        }
+        use_subnet_costs = match cost_profile.as_str() {
+            "default" => false,
+            "subnet" => true,
+            _ => return Err(format!("unknown cost profile '{}'", cost_profile)),
+        };
+    }
+    Ok(use_subnet_costs)
+}
+
+/// Make the subnet's system priority policy from its `[system_priority]` section.
+fn make_system_priority_policy(
+    system_priority: &SystemPriorityConfigFile,
+) -> Result<SystemPriorityPolicy, String> {
+    let contracts = system_priority
+        .contracts
+        .iter()
+        .map(|contract| {
+            QualifiedContractIdentifier::parse(contract)
+                .map_err(|_| format!("invalid contract identifier '{}'", contract))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let authorized_principals = system_priority
+        .principals
+        .iter()
+        .map(|principal| {
+            StacksAddress::from_string(principal)
+                .ok_or_else(|| format!("invalid standard principal '{}'", principal))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if contracts.is_empty() || authorized_principals.is_empty() {
+        return Err("at least one contract and one principal must be configured".into());
+    }
+
+    let reserved_budget_percent = system_priority
+        .reserved_budget_percent
+        .unwrap_or(DEFAULT_SYSTEM_PRIORITY_RESERVED_PERCENT);
+    if reserved_budget_percent >= 100 {
+        return Err(format!(
+            "reserved_budget_percent is {}, which leaves no budget for other transactions",
+            reserved_budget_percent
+        ));
+    }
+
+    Ok(SystemPriorityPolicy {
+        contracts,
+        authorized_principals,
+        reserved_budget_percent,
+    })
+}
+
+/// Make the subnet's genesis specification from its `[genesis]` section.  A contract's code is
+/// given either inline, as `code_body`, or as the `path` of a file to read it from.
+fn make_genesis_spec(genesis: &GenesisConfigFile) -> Result<GenesisSpec, String> {
+    let parse_contract_id = |contract_id: &str| {
+        QualifiedContractIdentifier::parse(contract_id)
+            .map_err(|_| format!("invalid contract identifier '{}'", contract_id))
+    };
+    let parse_asset = |contract_id: &str, token_name: &str| -> Result<AssetIdentifier, String> {
+        Ok(AssetIdentifier {
+            contract_identifier: parse_contract_id(contract_id)?,
+            asset_name: token_name
+                .to_string()
+                .try_into()
+                .map_err(|_| format!("invalid token name '{}'", token_name))?,
+        })
+    };
+    let parse_principal = |principal: &str| {
+        PrincipalData::parse(principal).map_err(|_| format!("invalid principal '{}'", principal))
+    };
+
+    let mut contracts = vec![];
+    for contract in genesis.contracts.iter().flatten() {
+        let code_body = match (&contract.code_body, &contract.path) {
+            (Some(code_body), None) => code_body.clone(),
+            (None, Some(path)) => fs::read_to_string(path)
+                .map_err(|e| format!("failed to read contract code from '{}': {}", path, e))?,
+            _ => {
+                return Err(format!(
+                    "contract '{}' needs exactly one of code_body or path",
+                    &contract.contract_id
+                ))
+            }
+        };
+        contracts.push(GenesisContract {
+            contract_id: parse_contract_id(&contract.contract_id)?,
+            code_body,
+        });
+    }
+
+    let mut ft_allocations = vec![];
+    for allocation in genesis.ft_allocations.iter().flatten() {
+        if allocation.amount == 0 {
+            return Err(format!(
+                "allocation of {}.{} to {} is zero",
+                &allocation.contract_id, &allocation.token_name, &allocation.recipient
+            ));
+        }
+        ft_allocations.push(GenesisFungibleTokenAllocation {
+            asset_identifier: parse_asset(&allocation.contract_id, &allocation.token_name)?,
+            recipient: parse_principal(&allocation.recipient)?,
+            amount: allocation.amount.into(),
+        });
+    }
+
+    let mut nft_allocations = vec![];
+    for allocation in genesis.nft_allocations.iter().flatten() {
+        nft_allocations.push(GenesisNonFungibleTokenAllocation {
+            asset_identifier: parse_asset(&allocation.contract_id, &allocation.token_name)?,
+            recipient: parse_principal(&allocation.recipient)?,
+            id: Value::UInt(allocation.id.into()),
+        });
+    }
+
+    let expected_root_hash = match genesis.expected_root_hash {
+        Some(ref root_hash) => Some(
+            TrieHash::from_hex(root_hash)
+                .map_err(|_| format!("invalid expected_root_hash '{}'", root_hash))?,
+        ),
+        None => None,
+    };
+
+    Ok(GenesisSpec {
+        contracts,
+        ft_allocations,
+        nft_allocations,
+        expected_root_hash,
+    })
+}
+
+/// Make the miner's block production mode from the `block_production` setting in its `[miner]`
+/// section: "l1", or "timer", which also needs `block_interval_ms`, and optionally takes
+/// `block_mempool_threshold` and `anchor_interval` (1 by default).
+fn make_block_production_mode(miner: &MinerConfigFile) -> Result<BlockProductionMode, String> {
+    match miner.block_production.as_deref() {
+        None | Some("l1") => Ok(BlockProductionMode::L1Cadence),
+        Some("timer") => {
+            let interval_ms = miner
+                .block_interval_ms
+                .ok_or("timer block production requires block_interval_ms")?;
+            if interval_ms == 0 {
+                return Err("block_interval_ms must be positive".into());
+            }
+            if miner.block_mempool_threshold == Some(0) {
+                return Err("block_mempool_threshold must be positive".into());
+            }
+            let anchor_interval = miner.anchor_interval.unwrap_or(1);
+            if anchor_interval == 0 {
+                return Err("anchor_interval must be positive".into());
+            }
+            Ok(BlockProductionMode::Timer {
+                interval_ms,
+                mempool_threshold: miner.block_mempool_threshold,
+                anchor_interval,
+            })
+        }
+        Some(other) => Err(format!("unknown block production mode '{}'", other)),
+    }
+}
+
+/// How the miner ranks mempool transactions: by "fee" (the default), "fee_per_length" or
+/// "fee_per_cost".
+fn make_tx_ranking(miner: &MinerConfigFile) -> Result<MemPoolWalkRanking, String> {
+    match miner.tx_ranking.as_deref() {
+        None => Ok(MemPoolWalkRanking::Fee),
+        Some(ranking) => MemPoolWalkRanking::from_str(ranking)
+            .ok_or_else(|| format!("unknown transaction ranking '{}'", ranking)),
+    }
+}
+
+fn make_contract_version_mismatch_action(
+    action: Option<&str>,
+) -> Result<ContractVersionMismatchAction, String> {
+    match action {
+        None | Some("exit") => Ok(ContractVersionMismatchAction::Exit),
+        Some("degrade") => Ok(ContractVersionMismatchAction::Degrade),
+        Some(other) => Err(format!(
+            "unknown contract_version_mismatch action '{}'",
+            other
+        )),
+    }
+}
+
+/// Check the L1 RPC failover settings of the `[burnchain]` section.
+fn validate_l1_rpc_failover(burnchain: &BurnchainConfig) -> Result<(), String> {
+    for url in burnchain.fallback_rpc_urls.iter() {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!(
+                "fallback RPC URL '{}' must start with http:// or https://",
+                url
+            ));
+        }
+    }
+    if burnchain.rpc_failover_backoff_ms == 0 {
+        return Err("rpc_failover_backoff_ms must be positive".into());
+    }
+    if burnchain.rpc_failover_max_backoff_ms < burnchain.rpc_failover_backoff_ms {
+        return Err(format!(
+            "rpc_failover_max_backoff_ms ({}) is less than rpc_failover_backoff_ms ({})",
+            burnchain.rpc_failover_max_backoff_ms, burnchain.rpc_failover_backoff_ms
+        ));
+    }
+    Ok(())
+}
+
+/// Make the node's mempool admission policy from the `mempool_admission_policy` setting in its
+/// `[connection_options]` section: "default", or "strict", which also needs
+/// `mempool_max_contract_deploy_size` and `mempool_min_token_transfer_fee`.
+fn make_mempool_admission_policy(
+    opts: &ConnectionOptionsFile,
+) -> Result<MempoolAdmissionPolicyConfig, String> {
+    match opts.mempool_admission_policy.as_deref() {
+        None | Some("default") => Ok(MempoolAdmissionPolicyConfig::Default),
+        Some("strict") => {
+            let max_contract_deploy_size = opts
+                .mempool_max_contract_deploy_size
+                .ok_or("the strict policy requires mempool_max_contract_deploy_size")?;
+            let min_token_transfer_fee = opts
+                .mempool_min_token_transfer_fee
+                .ok_or("the strict policy requires mempool_min_token_transfer_fee")?;
+            Ok(MempoolAdmissionPolicyConfig::Strict(
+                StrictAdmissionPolicy {
+                    max_contract_deploy_size,
+                    min_token_transfer_fee,
+                },
+            ))
+        }
+        Some(other) => Err(format!("unknown policy '{}'", other)),
+    }
+}
+
+/// Make the mempool's garbage collection policy.  `mempool_gc_strategy` is "age" (the default),
+/// "fee_floor", which requires `mempool_max_txs`, or "byte_cap", which requires
+/// `mempool_max_bytes`.
+fn make_mempool_gc_policy(opts: &ConnectionOptionsFile) -> Result<MemPoolGCPolicy, String> {
+    let strategy = match opts.mempool_gc_strategy.as_deref() {
+        None | Some("age") => MemPoolGCStrategy::Age,
+        Some("fee_floor") => MemPoolGCStrategy::FeeFloor {
+            max_txs: opts
+                .mempool_max_txs
+                .ok_or("the fee_floor strategy requires mempool_max_txs")?,
+        },
+        Some("byte_cap") => MemPoolGCStrategy::ByteCap {
+            max_bytes: opts
+                .mempool_max_bytes
+                .ok_or("the byte_cap strategy requires mempool_max_bytes")?,
+        },
+        Some(other) => return Err(format!("unknown mempool GC strategy '{}'", other)),
+    };
+    Ok(MemPoolGCPolicy {
+        max_tx_age: opts
+            .mempool_max_tx_age
+            .unwrap_or(MemPoolGCPolicy::default().max_tx_age),
+        strategy,
+    })
+}
+
+/// Make the parameters of the mempool sync bloom counter, falling back to the defaults for any
+/// that are not configured.
+fn make_mempool_bloom_config(opts: &ConnectionOptionsFile) -> Result<MemPoolBloomConfig, String> {
+    let default = MemPoolBloomConfig::default();
+    let config = MemPoolBloomConfig {
+        depth: opts.mempool_bloom_depth.unwrap_or(default.depth),
+        max_txs: opts.mempool_bloom_max_txs.unwrap_or(default.max_txs),
+        error_rate: opts.mempool_bloom_error_rate.unwrap_or(default.error_rate),
+    };
+    if config.depth == 0 {
+        return Err("mempool_bloom_depth must be positive".into());
+    }
+    if config.max_txs == 0 {
+        return Err("mempool_bloom_max_txs must be positive".into());
+    }
+    if !(config.error_rate > 0.0 && config.error_rate < 1.0) {
+        return Err("mempool_bloom_error_rate must be between 0 and 1".into());
+    }
+    Ok(config)
+}
+
+/// Make the node's admin credentials from the `[[connection_options.admin_credentials]]` entries
+/// in its config.  Each entry has a `role`, "read_only" or "operator", and either a shared
+/// `secret` or the hex-encoded `public_key` that signs requests.
+fn make_admin_credentials(opts: &ConnectionOptionsFile) -> Result<Vec<AdminCredential>, String> {
+    let mut credentials = vec![];
+    for entry in opts.admin_credentials.iter().flatten() {
+        let role = AdminRole::from_str(&entry.role)
+            .ok_or_else(|| format!("unknown admin role '{}'", &entry.role))?;
+        let auth = match (entry.secret.as_ref(), entry.public_key.as_ref()) {
+            (Some(secret), None) => AdminAuth::SharedSecret(secret.clone()),
+            (None, Some(public_key)) => AdminAuth::PublicKey(
+                Secp256k1PublicKey::from_hex(public_key)
+                    .map_err(|_| format!("invalid admin public key '{}'", public_key))?,
+            ),
+            _ => {
+                return Err(
+                    "each admin credential needs exactly one of secret or public_key".into(),
+                );
+            }
+        };
+        credentials.push(AdminCredential { role, auth });
+    }
+    Ok(credentials)
+}
+
+/// Make the miner's key rotation schedule from its `[[node.mining_key_rotation]]` entries.  A
+/// schedule needs an initial `node.mining_key` to rotate from.
+fn make_mining_key_rotations(
+    has_mining_key: bool,
+    rotations: &[MiningKeyRotationFile],
+) -> Result<Vec<MiningKeyRotation>, String> {
+    if !has_mining_key {
+        return Err("node.mining_key must be set to rotate from".into());
+    }
+
+    let mut last_activation_height = 0;
+    rotations
+        .iter()
+        .map(|rotation| {
+            let mining_key = Secp256k1PrivateKey::from_hex(&rotation.mining_key)
+                .map_err(|_| "invalid mining_key".to_string())?;
+            if rotation.activation_burn_height <= last_activation_height {
+                return Err(format!(
+                    "rotation at height {} is not after the rotation before it",
+                    rotation.activation_burn_height
+                ));
+            }
+            last_activation_height = rotation.activation_burn_height;
+            Ok(MiningKeyRotation {
+                mining_key,
+                activation_burn_height: rotation.activation_burn_height,
+            })
+        })
+        .collect()
+}
+
 impl Config {
     pub fn from_config_file(config_file: ConfigFile) -> Config {
         let default_node_config = NodeConfig::default();
+        let data_url_configured = config_file
+            .node
+            .as_ref()
+            .map_or(false, |node| node.data_url.is_some());
         let (mut node, bootstrap_node, deny_nodes) = match config_file.node {
             Some(node) => {
                 let rpc_bind = node.rpc_bind.unwrap_or(default_node_config.rpc_bind);
+                let mining_key_rotation = match node.mining_key_rotation {
+                    Some(ref rotations) => {
+                        make_mining_key_rotations(node.mining_key.is_some(), rotations)
+                            .unwrap_or_else(|e| {
+                                panic!("Invalid [[node.mining_key_rotation]] configuration: {}", e)
+                            })
+                    }
+                    None => vec![],
+                };
                 let node_config = NodeConfig {
                     name: node.name.unwrap_or(default_node_config.name),
                     seed: match node.seed {
@@ -286,7 +1540,9 @@ impl Config {
                         }
                         None => default_node_config.local_peer_seed,
                     },
-                    miner: node.miner.unwrap_or(default_node_config.miner),
+                    // a shadow node never participates in consensus
+                    miner: node.shadow_primary.is_none()
+                        && node.miner.unwrap_or(default_node_config.miner),
                     mock_mining: node.mock_mining.unwrap_or(default_node_config.mock_mining),
                     mine_microblocks: node
                         .mine_microblocks
@@ -301,6 +1557,9 @@ impl Config {
                         .wait_time_for_microblocks
                         .unwrap_or(default_node_config.wait_time_for_microblocks),
                     prometheus_bind: node.prometheus_bind,
+                    grpc_bind: node.grpc_bind,
+                    ws_bind: node.ws_bind,
+                    admin_bind: node.admin_bind,
                     marf_cache_strategy: node.marf_cache_strategy,
                     marf_defer_hashing: node
                         .marf_defer_hashing
@@ -312,6 +1571,19 @@ impl Config {
                     wait_before_first_anchored_block: node
                         .wait_before_first_anchored_block
                         .unwrap_or(default_node_config.wait_before_first_anchored_block),
+                    record_block_results: node.shadow_primary.is_some()
+                        || node
+                            .record_block_results
+                            .unwrap_or(default_node_config.record_block_results),
+                    record_state_diffs: node
+                        .record_state_diffs
+                        .unwrap_or(default_node_config.record_state_diffs),
+                    shadow_primary: node.shadow_primary.clone(),
+                    prune_depth: node.prune_depth,
+                    mining_key_rotation,
+                    mining_key_rotation_lead_blocks: node
+                        .mining_key_rotation_lead_blocks
+                        .unwrap_or(default_node_config.mining_key_rotation_lead_blocks),
                     ..default_node_config
                 };
                 (node_config, node.bootstrap_node, node.deny_nodes)
@@ -321,7 +1593,7 @@ impl Config {
 
         let default_burnchain_config = BurnchainConfig::default();
 
-        let burnchain = match config_file.burnchain {
+        let mut burnchain = match config_file.burnchain {
             Some(burnchain) => {
                 let chain = burnchain.chain.unwrap_or(default_burnchain_config.chain);
                 BurnchainConfig {
@@ -371,6 +1643,18 @@ impl Config {
                     rpc_ssl: burnchain
                         .rpc_ssl
                         .unwrap_or(default_burnchain_config.rpc_ssl),
+                    fallback_rpc_urls: burnchain
+                        .fallback_rpc_urls
+                        .unwrap_or(default_burnchain_config.fallback_rpc_urls),
+                    rpc_failover_backoff_ms: burnchain
+                        .rpc_failover_backoff_ms
+                        .unwrap_or(default_burnchain_config.rpc_failover_backoff_ms),
+                    rpc_failover_max_backoff_ms: burnchain
+                        .rpc_failover_max_backoff_ms
+                        .unwrap_or(default_burnchain_config.rpc_failover_max_backoff_ms),
+                    rpc_health_check_interval_ms: burnchain
+                        .rpc_health_check_interval_ms
+                        .unwrap_or(default_burnchain_config.rpc_health_check_interval_ms),
                     timeout: burnchain
                         .timeout
                         .unwrap_or(default_burnchain_config.timeout),
@@ -397,11 +1681,55 @@ impl Config {
                     first_burn_header_height: burnchain
                         .first_burn_header_height
                         .unwrap_or(default_burnchain_config.first_burn_header_height),
+                    withdrawal_confirmation_depth: burnchain
+                        .withdrawal_confirmation_depth
+                        .unwrap_or(default_burnchain_config.withdrawal_confirmation_depth),
+                    withdrawal_batch_blocks: burnchain
+                        .withdrawal_batch_blocks
+                        .unwrap_or(default_burnchain_config.withdrawal_batch_blocks),
+                    max_deposit_batch_size: burnchain
+                        .max_deposit_batch_size
+                        .unwrap_or(default_burnchain_config.max_deposit_batch_size),
+                    allow_unchecked_intermediary_responses: burnchain
+                        .allow_unchecked_intermediary_responses
+                        .unwrap_or(default_burnchain_config.allow_unchecked_intermediary_responses),
+                    max_tx_event_bytes: burnchain.max_tx_event_bytes,
+                    contract_version_mismatch: make_contract_version_mismatch_action(
+                        burnchain.contract_version_mismatch.as_deref(),
+                    )
+                    .unwrap_or_else(|e| panic!("Invalid [burnchain] configuration: {}", e)),
+                    max_l1_sync_lag: burnchain
+                        .max_l1_sync_lag
+                        .unwrap_or(default_burnchain_config.max_l1_sync_lag),
                     ..BurnchainConfig::default()
                 }
             }
             None => default_burnchain_config,
         };
+        if let Err(e) = validate_l1_rpc_failover(&burnchain) {
+            panic!("Invalid [burnchain] configuration: {}", e);
+        }
+        if let Some(ref epoch_configs) = config_file.subnet_epochs {
+            let epochs = make_subnet_epochs(&burnchain.get_stacks_epochs(), epoch_configs)
+                .unwrap_or_else(|e| panic!("Invalid [[subnet_epochs]] configuration: {}", e));
+            burnchain.epochs = Some(epochs);
+            burnchain.use_subnet_costs = make_subnet_cost_profile(epoch_configs)
+                .unwrap_or_else(|e| panic!("Invalid [[subnet_epochs]] configuration: {}", e));
+        }
+        if let Some(ref system_priority) = config_file.system_priority {
+            let policy = make_system_priority_policy(system_priority)
+                .unwrap_or_else(|e| panic!("Invalid [system_priority] configuration: {}", e));
+            burnchain.system_priority = Some(policy);
+        }
+        if !node.mining_key_rotation.is_empty() {
+            if let CommitStrategy::MultiMiner { .. } = burnchain.commit_strategy {
+                // the multi-miner contract, not this node's key, is the subnet contract's miner
+                panic!("Invalid [[node.mining_key_rotation]] configuration: miner key rotation requires direct block commits");
+            }
+            if node.mining_key_rotation_lead_blocks == 0 {
+                panic!("Invalid [[node.mining_key_rotation]] configuration: mining_key_rotation_lead_blocks must be positive");
+            }
+        }
 
         let miner_default_config = MinerConfig::default();
         let miner = match config_file.miner {
@@ -419,6 +1747,28 @@ impl Config {
                 probability_pick_no_estimate_tx: miner
                     .probability_pick_no_estimate_tx
                     .unwrap_or(miner_default_config.probability_pick_no_estimate_tx),
+                signing_timeout_ms: miner
+                    .signing_timeout_ms
+                    .unwrap_or(miner_default_config.signing_timeout_ms),
+                leader_rotation: miner
+                    .leader_rotation
+                    .unwrap_or(miner_default_config.leader_rotation),
+                contract_deploy_quota: ContractDeployQuota {
+                    max_deploys_per_block: miner.max_contract_deploys_per_block,
+                    max_deploy_bytes_per_block: miner.max_contract_deploy_bytes_per_block,
+                    max_deploys_per_origin: miner.max_contract_deploys_per_origin,
+                    max_deploy_bytes_per_origin: miner.max_contract_deploy_bytes_per_origin,
+                },
+                candidate_check_threads: miner
+                    .candidate_check_threads
+                    .unwrap_or(miner_default_config.candidate_check_threads),
+                block_production: make_block_production_mode(miner)
+                    .unwrap_or_else(|e| panic!("Invalid [miner] configuration: {}", e)),
+                min_withdrawal_tip: miner
+                    .min_withdrawal_tip
+                    .unwrap_or(miner_default_config.min_withdrawal_tip),
+                tx_ranking: make_tx_ranking(miner)
+                    .unwrap_or_else(|e| panic!("Invalid [miner] configuration: {}", e)),
             },
             None => miner_default_config,
         };
@@ -448,6 +1798,12 @@ impl Config {
             None => vec![],
         };
 
+        let genesis = match config_file.genesis {
+            Some(ref genesis) => make_genesis_spec(genesis)
+                .unwrap_or_else(|e| panic!("Invalid [genesis] configuration: {}", e)),
+            None => GenesisSpec::default(),
+        };
+
         let mut events_observers = match config_file.events_observer {
             Some(raw_observers) => {
                 let mut observers = vec![];
@@ -463,6 +1819,10 @@ impl Config {
                     observers.push(EventObserverConfig {
                         endpoint,
                         events_keys,
+                        max_event_bytes: observer.max_event_bytes,
+                        max_payload_bytes: observer.max_payload_bytes,
+                        retry_backoff_ms: observer.retry_backoff_ms,
+                        max_retry_backoff_ms: observer.max_retry_backoff_ms,
                     });
                 }
                 observers
@@ -475,16 +1835,49 @@ impl Config {
             Ok(val) => events_observers.push(EventObserverConfig {
                 endpoint: val,
                 events_keys: vec![EventKeyType::AnyEvent],
+                max_event_bytes: None,
+                max_payload_bytes: None,
+                retry_backoff_ms: None,
+                max_retry_backoff_ms: None,
             }),
             _ => (),
         };
 
-        let connection_options = match config_file.connection_options {
+        let mut connection_options = match config_file.connection_options {
             Some(opts) => {
+                let mempool_admission_policy =
+                    make_mempool_admission_policy(&opts).unwrap_or_else(|e| {
+                        panic!("Invalid [connection_options] configuration: {}", e)
+                    });
+                let mempool_bloom = make_mempool_bloom_config(&opts).unwrap_or_else(|e| {
+                    panic!("Invalid [connection_options] configuration: {}", e)
+                });
+                let mempool_gc_policy = make_mempool_gc_policy(&opts).unwrap_or_else(|e| {
+                    panic!("Invalid [connection_options] configuration: {}", e)
+                });
+                let admin_credentials = make_admin_credentials(&opts).unwrap_or_else(|e| {
+                    panic!("Invalid [connection_options] configuration: {}", e)
+                });
                 let ip_addr = match opts.public_ip_address {
                     Some(public_ip_address) => {
-                        let addr = public_ip_address.parse::<SocketAddr>().unwrap();
-                        debug!("addr.parse {:?}", addr);
+                        let addr =
+                            resolve_public_ip_address(&public_ip_address).unwrap_or_else(|e| {
+                                panic!("Invalid [connection_options] configuration: {}", e)
+                            });
+                        if !data_url_configured {
+                            // advertise the RPC interface at the public address as well,
+                            // rather than at the (possibly internal) interface it binds to
+                            let rpc_port =
+                                make_public_rpc_port(opts.public_rpc_port, &node.rpc_bind)
+                                    .unwrap_or_else(|e| {
+                                        panic!("Invalid [connection_options] configuration: {}", e)
+                                    });
+                            node.data_url = make_public_data_url(&public_ip_address, rpc_port);
+                        }
+                        info!(
+                            "Advertising public address {} ({}) to peers, with data URL {}",
+                            &public_ip_address, &addr, &node.data_url
+                        );
                         Some((PeerAddress::from_socketaddr(&addr), addr.port()))
                     }
                     None => None,
@@ -507,8 +1900,21 @@ impl Config {
                 opts.read_only_call_limit_runtime.map(|x| {
                     read_only_call_limit.runtime = x;
                 });
+                let mut read_only_call_max_budget = HELIUM_DEFAULT_CONNECTION_OPTIONS
+                    .read_only_call_max_budget
+                    .clone();
+                opts.read_only_call_max_budget_read_length.map(|x| {
+                    read_only_call_max_budget.read_length = x;
+                });
+                opts.read_only_call_max_budget_read_count.map(|x| {
+                    read_only_call_max_budget.read_count = x;
+                });
+                opts.read_only_call_max_budget_runtime.map(|x| {
+                    read_only_call_max_budget.runtime = x;
+                });
                 let mut result_opts = ConnectionOptions {
                     read_only_call_limit,
+                    read_only_call_max_budget,
                     inbox_maxlen: opts
                         .inbox_maxlen
                         .unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.inbox_maxlen.clone()),
@@ -565,6 +1971,9 @@ impl Config {
                                 .clone()
                         },
                     ),
+                    min_outbound_orgs: opts
+                        .min_outbound_orgs
+                        .unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.min_outbound_orgs),
                     soft_max_clients_per_host: opts.soft_max_clients_per_host.unwrap_or_else(
                         || {
                             HELIUM_DEFAULT_CONNECTION_OPTIONS
@@ -583,6 +1992,18 @@ impl Config {
                             .max_inflight_blocks
                             .clone()
                     }),
+                    download_hedge_percentile: opts.download_hedge_percentile.unwrap_or_else(
+                        || {
+                            HELIUM_DEFAULT_CONNECTION_OPTIONS
+                                .download_hedge_percentile
+                                .clone()
+                        },
+                    ),
+                    download_hedge_min_ms: opts.download_hedge_min_ms.unwrap_or_else(|| {
+                        HELIUM_DEFAULT_CONNECTION_OPTIONS
+                            .download_hedge_min_ms
+                            .clone()
+                    }),
                     max_inflight_attachments: opts.max_inflight_attachments.unwrap_or_else(|| {
                         HELIUM_DEFAULT_CONNECTION_OPTIONS
                             .max_inflight_attachments
@@ -619,7 +2040,111 @@ impl Config {
                     handshake_timeout: opts.connect_timeout.unwrap_or(5),
                     max_sockets: opts.max_sockets.unwrap_or(800) as usize,
                     antientropy_public: opts.antientropy_public.unwrap_or(true),
+                    tx_verify_threads: opts.tx_verify_threads.unwrap_or_else(|| {
+                        HELIUM_DEFAULT_CONNECTION_OPTIONS.tx_verify_threads.clone()
+                    }),
+                    max_peer_stats_metrics: opts.max_peer_stats_metrics.unwrap_or_else(|| {
+                        HELIUM_DEFAULT_CONNECTION_OPTIONS.max_peer_stats_metrics
+                    }),
+                    auto_ban_offense_threshold: opts.auto_ban_offense_threshold.unwrap_or_else(
+                        || HELIUM_DEFAULT_CONNECTION_OPTIONS.auto_ban_offense_threshold,
+                    ),
+                    auto_ban_duration: opts
+                        .auto_ban_duration
+                        .unwrap_or_else(|| HELIUM_DEFAULT_CONNECTION_OPTIONS.auto_ban_duration),
                     subnet_validator: node.mining_key.clone(),
+                    sponsorship_policy: SponsorshipPolicy {
+                        enabled: opts.accept_sponsorship_offers.unwrap_or(false),
+                        allowed_sponsors: opts
+                            .sponsorship_allowed_sponsors
+                            .unwrap_or(vec![])
+                            .iter()
+                            .map(|addr| {
+                                StacksAddress::from_string(addr).expect(
+                                    "Invalid address configured in sponsorship_allowed_sponsors",
+                                )
+                            })
+                            .collect(),
+                    },
+                    mempool_origin_quota: MemPoolOriginQuota {
+                        max_pending_txs_per_origin: opts.max_pending_txs_per_origin,
+                        exempt_origins: opts
+                            .pending_tx_quota_exempt_origins
+                            .unwrap_or(vec![])
+                            .iter()
+                            .map(|addr| {
+                                StacksAddress::from_string(addr).expect(
+                                    "Invalid address configured in pending_tx_quota_exempt_origins",
+                                )
+                            })
+                            .collect(),
+                    },
+                    mempool_admission_policy,
+                    mempool_gc_policy,
+                    contract_deploy_limits: ContractDeployLimits {
+                        max_code_body_size: opts.max_contract_code_body_size,
+                        max_ast_depth: opts.max_contract_ast_depth,
+                        max_analysis_time_ms: opts.max_contract_analysis_time_ms,
+                    },
+                    mempool_sync_http_urls: opts
+                        .mempool_sync_http_urls
+                        .unwrap_or(vec![])
+                        .iter()
+                        .map(|url| {
+                            UrlString::try_from(url.as_str())
+                                .expect("Invalid URL configured in mempool_sync_http_urls")
+                        })
+                        .collect(),
+                    mempool_bloom,
+                    rpc_rate_limits: RPCRateLimits {
+                        read_only_call: opts.read_only_call_rate_limit.map(|rate| {
+                            TokenBucketLimit {
+                                rate,
+                                burst: opts.read_only_call_rate_burst.unwrap_or(rate),
+                            }
+                        }),
+                        tx_broadcast: opts.tx_broadcast_rate_limit.map(|rate| TokenBucketLimit {
+                            rate,
+                            burst: opts.tx_broadcast_rate_burst.unwrap_or(rate),
+                        }),
+                        block_validation: opts.block_validation_rate_limit.map(|rate| {
+                            TokenBucketLimit {
+                                rate,
+                                burst: opts.block_validation_rate_burst.unwrap_or(rate),
+                            }
+                        }),
+                        map_iteration: opts.map_iteration_rate_limit.map(|rate| TokenBucketLimit {
+                            rate,
+                            burst: opts.map_iteration_rate_burst.unwrap_or(rate),
+                        }),
+                        proof: opts.proof_rate_limit.map(|rate| TokenBucketLimit {
+                            rate,
+                            burst: opts.proof_rate_burst.unwrap_or(rate),
+                        }),
+                        mempool_sync: opts.mempool_sync_rate_limit.map(|rate| TokenBucketLimit {
+                            rate,
+                            burst: opts.mempool_sync_rate_burst.unwrap_or(rate),
+                        }),
+                        mempool_listing: opts.mempool_listing_rate_limit.map(|rate| {
+                            TokenBucketLimit {
+                                rate,
+                                burst: opts.mempool_listing_rate_burst.unwrap_or(rate),
+                            }
+                        }),
+                        state_diff: opts.state_diff_rate_limit.map(|rate| TokenBucketLimit {
+                            rate,
+                            burst: opts.state_diff_rate_burst.unwrap_or(rate),
+                        }),
+                    },
+                    auth_token: opts.auth_token,
+                    admin_credentials,
+                    divergence_reorg_threshold: opts
+                        .divergence_reorg_threshold
+                        .unwrap_or(HELIUM_DEFAULT_CONNECTION_OPTIONS.divergence_reorg_threshold),
+                    enable_map_iteration: opts.enable_map_iteration.unwrap_or(false),
+                    enable_block_proposal_validation: opts
+                        .enable_block_proposal_validation
+                        .unwrap_or(false),
                     ..ConnectionOptions::default()
                 };
                 if let CommitStrategy::MultiMiner { ref contract, .. } = &burnchain.commit_strategy
@@ -631,6 +2156,26 @@ impl Config {
             }
             None => HELIUM_DEFAULT_CONNECTION_OPTIONS.clone(),
         };
+        connection_options.withdrawal_confirmation_depth = burnchain.withdrawal_confirmation_depth;
+        connection_options.subnet_id =
+            SubnetId::from_contract_identifier(&burnchain.contract_identifier);
+        connection_options.subnet_block_proposers = make_block_proposer_schedule(
+            &burnchain.commit_strategy,
+            node.mining_key.as_ref(),
+            miner.leader_rotation,
+        );
+        connection_options.divergence_snapshot_dir = Some(
+            Config::divergence_snapshot_path(&node.working_dir)
+                .to_str()
+                .expect("Unable to produce path")
+                .to_string(),
+        );
+        if node.shadow_primary.is_some() {
+            // a shadow node only replays blocks; it must not relay them as if they were its own
+            connection_options.disable_block_advertisement = true;
+            connection_options.disable_block_push = true;
+            connection_options.disable_microblock_push = true;
+        }
 
         let estimation = match config_file.fee_estimation {
             Some(f) => FeeEstimationConfig::from(f),
@@ -641,6 +2186,7 @@ impl Config {
             node,
             burnchain,
             initial_balances,
+            genesis,
             events_observers,
             connection_options,
             estimation,
@@ -673,6 +2219,14 @@ impl Config {
         path
     }
 
+    /// Directory that divergence snapshots are written to
+    fn divergence_snapshot_path(working_dir: &str) -> PathBuf {
+        let mut path = PathBuf::from(working_dir);
+        path.push(SUBNET_SUBDIR_NAME);
+        path.push("divergence");
+        path
+    }
+
     pub fn get_chainstate_path_str(&self) -> String {
         self.get_chainstate_path()
             .to_str()
@@ -712,6 +2266,12 @@ impl Config {
         path.to_str().expect("Unable to produce path").to_string()
     }
 
+    pub fn get_event_queue_db_file_path(&self) -> String {
+        let mut path = self.get_chainstate_path();
+        path.set_file_name("event_observers.sqlite");
+        path.to_str().expect("Unable to produce path").to_string()
+    }
+
     pub fn get_atlas_db_file_path(&self) -> String {
         let mut path = self.get_chainstate_path();
         path.set_file_name("atlas.sqlite");
@@ -740,6 +2300,16 @@ impl Config {
         BURNCHAIN_NAME_STACKS_MAINNET_L1 == self.burnchain.chain.as_str()
     }
 
+    /// Which multi-miner signing set members may propose each block, or None if this node
+    /// commits directly to the subnet contract.
+    pub fn block_proposer_schedule(&self) -> Option<BlockProposerSchedule> {
+        make_block_proposer_schedule(
+            &self.burnchain.commit_strategy,
+            self.node.mining_key.as_ref(),
+            self.miner.leader_rotation,
+        )
+    }
+
     pub fn is_node_event_driven(&self) -> bool {
         self.events_observers.len() > 0
     }
@@ -781,6 +2351,11 @@ impl Config {
                     self.miner.subsequent_attempt_time_ms
                 },
                 consider_no_estimate_tx_prob: self.miner.probability_pick_no_estimate_tx,
+                contract_deploy_quota: self.miner.contract_deploy_quota.clone(),
+                contract_deploy_limits: self.connection_options.contract_deploy_limits.clone(),
+                candidate_check_threads: self.miner.candidate_check_threads,
+                min_withdrawal_tip: self.miner.min_withdrawal_tip.into(),
+                ranking: self.miner.tx_ranking,
             },
         }
     }
@@ -804,6 +2379,7 @@ impl std::default::Default for Config {
             burnchain,
             node,
             initial_balances: vec![],
+            genesis: GenesisSpec::default(),
             events_observers: vec![],
             connection_options,
             estimation,
@@ -850,6 +2426,17 @@ pub struct BurnchainConfig {
     pub rpc_port: u16,
     /// Whether or not to use SSL for L1 rpc communications
     pub rpc_ssl: bool,
+    /// RPC URLs of other L1 nodes (e.g., `http://10.0.0.2:20443`), in order of preference, to
+    /// fail over to when the L1 node at `peer_host` cannot be reached
+    pub fallback_rpc_urls: Vec<String>,
+    /// How long an unreachable L1 RPC endpoint is skipped for after its first failure.  This
+    /// doubles with each consecutive failure, up to `rpc_failover_max_backoff_ms`.
+    pub rpc_failover_backoff_ms: u64,
+    /// The longest an unreachable L1 RPC endpoint is skipped for
+    pub rpc_failover_max_backoff_ms: u64,
+    /// How frequently unreachable L1 RPC endpoints are health-checked, when fallback endpoints
+    /// are configured
+    pub rpc_health_check_interval_ms: u64,
     /// The number of ms before synchronous L1 communications timeout
     pub timeout: u32,
     /// When set, will configure the node to exit at the specified L1 block height
@@ -861,8 +2448,8 @@ pub struct BurnchainConfig {
     pub max_rbf: u64,
     /// How much to increment the fee for each iteration of replace-by-fee for miner commitments
     pub rbf_fee_increment: u64,
-    /// Custom override for the definitions of the epochs. This will only be applied for testnet and
-    /// regtest nodes.
+    /// Custom override for the definitions of the epochs. Any `[[subnet_epochs]]` sections in the
+    /// config file are applied on top of these (or of the default regtest epochs).
     pub epochs: Option<Vec<StacksEpoch>>,
     /// The layer 1 contract that the subnet will watch for Stacks events.
     pub contract_identifier: QualifiedContractIdentifier,
@@ -874,6 +2461,44 @@ pub struct BurnchainConfig {
     /// the miner should directly submit to the subnet contract, or they need to
     /// submit through another contract (e.g., a multi-party commit contract
     pub commit_strategy: CommitStrategy,
+    /// The number of subnet blocks that must be built on top of a block before its
+    /// withdrawal root is considered final. Until then, its withdrawals are reported as
+    /// pending, and are not relayed to `subnet_withdrawals` event observers. A value of 0
+    /// means withdrawals are final as soon as their block is processed.
+    pub withdrawal_confirmation_depth: u64,
+    /// If nonzero, withdrawals from blocks in `EpochSubnet1` and later are registered with the
+    /// L1 one batch of this many consecutive blocks at a time, rather than one block at a time.
+    /// The batch size is committed to the genesis block when the chainstate is instantiated, and
+    /// is read back from the chainstate afterwards.  Only supported with
+    /// `CommitStrategy::Direct`.
+    pub withdrawal_batch_blocks: u64,
+    /// The maximum number of deposit operations from a single L1 block that are applied
+    /// to the subnet state together, in one Clarity transaction.
+    pub max_deposit_batch_size: usize,
+    /// If set, the subnet accepts contracts whose `begin` or `let` bodies leave intermediary
+    /// responses unchecked, and reports them as warnings.  By default (strict mode), such
+    /// contracts are rejected, as they are on the L1.  This is committed to the subnet's
+    /// genesis block, so it only takes effect when the chainstate is first instantiated.
+    pub allow_unchecked_intermediary_responses: bool,
+    /// If set, the most bytes of events that a single transaction may emit.  A transaction that
+    /// emits more fails with a runtime error, so the cap is committed to the genesis block, and
+    /// the node refuses to start with a cap that differs from the committed one.
+    pub max_tx_event_bytes: Option<u64>,
+    /// If set, the subnet-1 epoch instantiates the subnet cost profile's `.costs-subnet`, which
+    /// charges less for storage I/O than `.costs-3`.  The setting is committed to the genesis
+    /// block, and the node refuses to start with a setting that differs from the committed one.
+    pub use_subnet_costs: bool,
+    /// If set, which transactions are system priority transactions, and how much of each
+    /// block's execution budget is reserved for them.  The policy decides which blocks are
+    /// valid, so it is committed to the genesis block, and the node refuses to start with a
+    /// policy that differs from the committed one.
+    pub system_priority: Option<SystemPriorityPolicy>,
+    /// What the node does if, on startup, the L1 subnet contract reports an interface version
+    /// that the node does not implement
+    pub contract_version_mismatch: ContractVersionMismatchAction,
+    /// How many blocks behind the L1 tip the node may be for its miner to start producing
+    /// blocks.  Until the node has confirmed this with the L1 node, it does not mine.
+    pub max_l1_sync_lag: u64,
 }
 
 impl Default for BurnchainConfig {
@@ -888,6 +2513,10 @@ impl Default for BurnchainConfig {
             peer_port: 8333,
             rpc_port: 8332,
             rpc_ssl: false,
+            fallback_rpc_urls: vec![],
+            rpc_failover_backoff_ms: 1_000,
+            rpc_failover_max_backoff_ms: 60_000,
+            rpc_health_check_interval_ms: 5_000,
             timeout: 300,
             process_exit_at_block_height: None,
             poll_time_secs: 10, // TODO: this is a testnet specific value.
@@ -898,6 +2527,15 @@ impl Default for BurnchainConfig {
             first_burn_header_height: 0u64,
             anchor_mode: TransactionAnchorMode::Any,
             commit_strategy: CommitStrategy::Direct,
+            withdrawal_confirmation_depth: 0,
+            withdrawal_batch_blocks: 0,
+            max_deposit_batch_size: DEFAULT_DEPOSIT_BATCH_SIZE,
+            allow_unchecked_intermediary_responses: false,
+            max_tx_event_bytes: None,
+            use_subnet_costs: false,
+            system_priority: None,
+            contract_version_mismatch: ContractVersionMismatchAction::Exit,
+            max_l1_sync_lag: 2,
         }
     }
 }
@@ -929,6 +2567,14 @@ impl BurnchainConfig {
         let sock_addr = addrs_iter.next().unwrap();
         sock_addr
     }
+
+    /// The epoch schedule the subnet runs with, including each epoch's block limit.
+    pub fn get_stacks_epochs(&self) -> Vec<StacksEpoch> {
+        match self.epochs {
+            Some(ref epochs) => epochs.clone(),
+            None => STACKS_EPOCHS_REGTEST.to_vec(),
+        }
+    }
 }
 
 #[derive(Clone, Deserialize, Default)]
@@ -940,6 +2586,10 @@ pub struct BurnchainConfigFile {
     pub peer_port: Option<u16>,
     pub rpc_port: Option<u16>,
     pub rpc_ssl: Option<bool>,
+    pub fallback_rpc_urls: Option<Vec<String>>,
+    pub rpc_failover_backoff_ms: Option<u64>,
+    pub rpc_failover_max_backoff_ms: Option<u64>,
+    pub rpc_health_check_interval_ms: Option<u64>,
     pub timeout: Option<u32>,
     pub process_exit_at_block_height: Option<u64>,
     pub poll_time_secs: Option<u64>,
@@ -948,6 +2598,14 @@ pub struct BurnchainConfigFile {
     pub epochs: Option<Vec<StacksEpoch>>,
     pub contract_identifier: Option<String>,
     pub first_burn_header_height: Option<u64>,
+    pub withdrawal_confirmation_depth: Option<u64>,
+    pub withdrawal_batch_blocks: Option<u64>,
+    pub max_deposit_batch_size: Option<usize>,
+    pub allow_unchecked_intermediary_responses: Option<bool>,
+    pub max_tx_event_bytes: Option<u64>,
+    /// "exit" (the default) or "degrade"
+    pub contract_version_mismatch: Option<String>,
+    pub max_l1_sync_lag: Option<u64>,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -984,6 +2642,15 @@ pub struct NodeConfig {
     /// anchored block for that burn block.
     pub wait_before_first_anchored_block: u64,
     pub prometheus_bind: Option<String>,
+    /// If set, stream processed blocks and withdrawals to gRPC clients on this address.  Requires
+    /// the node to be built with the `grpc` feature.
+    pub grpc_bind: Option<String>,
+    /// If set, push the events of processed blocks to WebSocket clients subscribed to the
+    /// principals or contracts involved, on this address.
+    pub ws_bind: Option<String>,
+    /// If set, serve the admin RPC endpoints on this address, and only on this address.  If not
+    /// set, the admin RPC endpoints are not served at all.
+    pub admin_bind: Option<String>,
     pub marf_cache_strategy: Option<String>,
     pub marf_defer_hashing: bool,
     pub pox_sync_sample_secs: u64,
@@ -992,6 +2659,33 @@ pub struct NodeConfig {
     ///  as the validation key when running as a subnet 'validator' (i.e.,
     ///  the follower in the two-phase commit protocol)
     pub mining_key: Option<StacksPrivateKey>,
+    /// Scheduled rotations of the mining key, ordered by activation height.  `mining_key` signs
+    /// until the first rotation activates.
+    pub mining_key_rotation: Vec<MiningKeyRotation>,
+    /// Register each rotation's key with the L1 contract this many L1 blocks before it activates
+    pub mining_key_rotation_lead_blocks: u64,
+    /// If true, keep a record of the results of each processed block, so that shadow nodes can
+    /// compare their own results against this node's.
+    pub record_block_results: bool,
+    /// If true, keep a record of the state written by each processed block, which is served by
+    /// the `/v2/blocks/:index_block_hash/state-diff` RPC endpoint.
+    pub record_state_diffs: bool,
+    /// RPC URL of a node running the canonical build.  If set, this node runs in shadow mode: it
+    /// does not mine or relay blocks, and compares the results of each block it processes
+    /// against the results recorded by this primary node, reporting any divergence.
+    pub shadow_primary: Option<String>,
+    /// If set, periodically delete block bodies and stale-fork state more than this many blocks
+    /// below the canonical tip.  Headers and withdrawal roots are kept, but a pruning node
+    /// cannot serve pruned blocks to its peers, or follow a reorg deeper than this.
+    pub prune_depth: Option<u64>,
+}
+
+/// A scheduled rotation of the miner's key
+#[derive(Clone, Debug)]
+pub struct MiningKeyRotation {
+    pub mining_key: StacksPrivateKey,
+    /// First L1 block height whose block commits are signed by `mining_key`
+    pub activation_burn_height: u64,
 }
 
 #[derive(Clone, Debug)]
@@ -1286,11 +2980,36 @@ impl NodeConfig {
             wait_time_for_microblocks: 30_000,
             wait_before_first_anchored_block: 5 * 60_000,
             prometheus_bind: None,
+            grpc_bind: None,
+            ws_bind: None,
+            admin_bind: None,
             marf_cache_strategy: None,
             marf_defer_hashing: true,
             pox_sync_sample_secs: 30,
             use_test_genesis_chainstate: None,
             mining_key: None,
+            mining_key_rotation: vec![],
+            mining_key_rotation_lead_blocks: DEFAULT_MINING_KEY_ROTATION_LEAD_BLOCKS,
+            record_block_results: false,
+            record_state_diffs: false,
+            shadow_primary: None,
+            prune_depth: None,
+        }
+    }
+
+    /// The node's private key, derived from `local_peer_seed`.  It is the node's initial p2p key,
+    /// and it signs the payloads sent to event observers.
+    pub fn get_node_private_key(&self) -> Secp256k1PrivateKey {
+        let mut re_hashed_seed = self.local_peer_seed.clone();
+        loop {
+            match Secp256k1PrivateKey::from_slice(&re_hashed_seed[..]) {
+                Ok(sk) => return sk,
+                Err(_) => {
+                    re_hashed_seed = Sha256Sum::from_data(&re_hashed_seed[..])
+                        .as_bytes()
+                        .to_vec()
+                }
+            }
         }
     }
 
@@ -1389,6 +3108,93 @@ pub struct MinerConfig {
     pub subsequent_attempt_time_ms: u64,
     pub microblock_attempt_time_ms: u64,
     pub probability_pick_no_estimate_tx: u8,
+    /// When committing through a multi-miner contract, how long (in milliseconds) to spend
+    /// collecting the other participants' signatures for a block proposal. The participants
+    /// are asked concurrently, so this bounds the whole collection, not each request.
+    pub signing_timeout_ms: u64,
+    /// When committing through a multi-miner contract, rotate the proposing miner
+    /// round-robin through the signing set with each L1 block. If false, this node
+    /// proposes a block for every L1 block. With rotation, the next miner in the signing set
+    /// proposes in place of the leader if it has not seen a proposal from the leader once
+    /// `signing_timeout_ms` has passed, and signers only sign proposals from the leader or its
+    /// fallback, and only one block per L1 block. Every participant must agree on this setting.
+    pub leader_rotation: bool,
+    /// Limits on the smart contract deploys this node selects into each block it mines
+    pub contract_deploy_quota: ContractDeployQuota,
+    /// Number of worker threads that verify mempool transactions' signatures and read their
+    /// accounts' nonces ahead of block assembly.  The workers are started once and reused for
+    /// every block.  If 0, the mempool walk checks each transaction itself.
+    pub candidate_check_threads: usize,
+    /// How the miner paces the subnet blocks it produces
+    pub block_production: BlockProductionMode,
+    /// Minimum miner tip, in uSTX, that each withdrawal in a transaction must pay for this node
+    /// to mine the transaction
+    pub min_withdrawal_tip: u64,
+    /// How the miner ranks the mempool transactions it considers for each block
+    pub tx_ranking: MemPoolWalkRanking,
+}
+
+/// How the miner paces the subnet blocks it produces
+#[derive(Clone, Debug, PartialEq)]
+pub enum BlockProductionMode {
+    /// Mine one anchored block, and commit it to the L1, for each L1 block
+    L1Cadence,
+    /// Produce subnet blocks as microblocks every `interval_ms`, or as soon as
+    /// `mempool_threshold` transactions have arrived since the last one, and mine an anchored
+    /// block confirming them, committed to the L1, only every `anchor_interval` L1 blocks.
+    /// All the microblocks between two anchored blocks share the next anchored block's
+    /// execution budget.
+    Timer {
+        interval_ms: u64,
+        mempool_threshold: Option<u64>,
+        anchor_interval: u64,
+    },
+}
+
+impl Default for BlockProductionMode {
+    fn default() -> Self {
+        BlockProductionMode::L1Cadence
+    }
+}
+
+/// What a node does when the L1 subnet contract's interface version is not the one it
+/// implements.  Withdrawals committed by such a node could not be verified by the contract.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContractVersionMismatchAction {
+    /// Refuse to start
+    Exit,
+    /// Follow the subnet, but do not mine
+    Degrade,
+}
+
+impl BlockProductionMode {
+    /// Should the miner mine an anchored block off of the L1 block at `burn_height`?
+    pub fn should_anchor_at(&self, burn_height: u64) -> bool {
+        match self {
+            BlockProductionMode::L1Cadence => true,
+            BlockProductionMode::Timer {
+                anchor_interval, ..
+            } => burn_height % anchor_interval == 0,
+        }
+    }
+
+    /// How often the miner produces microblocks, given the node's `microblock_frequency`
+    pub fn microblock_interval_ms(&self, microblock_frequency: u64) -> u64 {
+        match self {
+            BlockProductionMode::L1Cadence => microblock_frequency,
+            BlockProductionMode::Timer { interval_ms, .. } => *interval_ms,
+        }
+    }
+
+    /// How many new mempool transactions make the miner produce a microblock early, if any
+    pub fn mempool_threshold(&self) -> Option<u64> {
+        match self {
+            BlockProductionMode::L1Cadence => None,
+            BlockProductionMode::Timer {
+                mempool_threshold, ..
+            } => *mempool_threshold,
+        }
+    }
 }
 
 impl MinerConfig {
@@ -1399,6 +3205,13 @@ impl MinerConfig {
             subsequent_attempt_time_ms: 30_000,
             microblock_attempt_time_ms: 30_000,
             probability_pick_no_estimate_tx: 5,
+            signing_timeout_ms: 30_000,
+            leader_rotation: false,
+            contract_deploy_quota: ContractDeployQuota::default(),
+            candidate_check_threads: 4,
+            block_production: BlockProductionMode::L1Cadence,
+            min_withdrawal_tip: 0,
+            tx_ranking: MemPoolWalkRanking::Fee,
         }
     }
 }
@@ -1422,28 +3235,118 @@ pub struct ConnectionOptionsFile {
     pub max_clients_per_host: Option<u64>,
     pub soft_max_neighbors_per_host: Option<u64>,
     pub soft_max_neighbors_per_org: Option<u64>,
+    pub min_outbound_orgs: Option<u64>,
     pub soft_max_clients_per_host: Option<u64>,
     pub max_sockets: Option<u64>,
     pub walk_interval: Option<u64>,
     pub dns_timeout: Option<u64>,
     pub max_inflight_blocks: Option<u64>,
+    pub download_hedge_percentile: Option<u64>,
+    pub download_hedge_min_ms: Option<u64>,
     pub max_inflight_attachments: Option<u64>,
     pub read_only_call_limit_write_length: Option<u64>,
     pub read_only_call_limit_read_length: Option<u64>,
     pub read_only_call_limit_write_count: Option<u64>,
     pub read_only_call_limit_read_count: Option<u64>,
     pub read_only_call_limit_runtime: Option<u64>,
+    pub read_only_call_max_budget_read_length: Option<u64>,
+    pub read_only_call_max_budget_read_count: Option<u64>,
+    pub read_only_call_max_budget_runtime: Option<u64>,
     pub maximum_call_argument_size: Option<u32>,
     pub download_interval: Option<u64>,
     pub inv_sync_interval: Option<u64>,
     pub full_inv_sync_interval: Option<u64>,
     pub inv_reward_cycles: Option<u64>,
     pub public_ip_address: Option<String>,
+    /// The port at which clients reach this node's RPC interface, if it differs from the port
+    /// `rpc_bind` binds to (e.g. behind NAT).  Only used to derive the advertised data URL from
+    /// `public_ip_address` when `node.data_url` is not set.
+    pub public_rpc_port: Option<u16>,
     pub disable_inbound_walks: Option<bool>,
     pub disable_inbound_handshakes: Option<bool>,
     pub disable_block_download: Option<bool>,
     pub force_disconnect_interval: Option<u64>,
     pub antientropy_public: Option<bool>,
+    pub tx_verify_threads: Option<usize>,
+    pub max_peer_stats_metrics: Option<u64>,
+    /// How many offenses (invalid preambles, exceeded bandwidth limits) a peer address may commit
+    /// within an hour before it is banned automatically.  0 disables automatic bans.
+    pub auto_ban_offense_threshold: Option<u64>,
+    /// How long automatic bans last, in seconds
+    pub auto_ban_duration: Option<u64>,
+    pub accept_sponsorship_offers: Option<bool>,
+    pub sponsorship_allowed_sponsors: Option<Vec<String>>,
+    pub max_pending_txs_per_origin: Option<u64>,
+    pub pending_tx_quota_exempt_origins: Option<Vec<String>>,
+    pub mempool_admission_policy: Option<String>,
+    pub mempool_max_contract_deploy_size: Option<u64>,
+    pub mempool_min_token_transfer_fee: Option<u64>,
+    /// How the mempool evicts transactions beyond its size cap: "age", "fee_floor" or "byte_cap"
+    pub mempool_gc_strategy: Option<String>,
+    /// Most transactions the mempool holds under the "fee_floor" strategy
+    pub mempool_max_txs: Option<u64>,
+    /// Most bytes of transactions the mempool holds under the "byte_cap" strategy
+    pub mempool_max_bytes: Option<u64>,
+    /// Number of blocks after which a transaction is garbage-collected
+    pub mempool_max_tx_age: Option<u64>,
+    /// Largest contract code body, in bytes, that this node admits and mines
+    pub max_contract_code_body_size: Option<u64>,
+    /// Deepest nesting of expressions in a contract that this node admits and mines
+    pub max_contract_ast_depth: Option<u64>,
+    /// Longest time, in milliseconds, that parsing and analyzing a contract may take for this node
+    /// to admit and mine it
+    pub max_contract_analysis_time_ms: Option<u64>,
+    /// Data URLs of nodes to fetch mempool transactions from over HTTP, without connecting to
+    /// them over p2p
+    pub mempool_sync_http_urls: Option<Vec<String>>,
+    /// How many recent Stacks blocks' transactions the mempool sync bloom filter summarizes
+    pub mempool_bloom_depth: Option<u64>,
+    /// How many transactions the mempool sync bloom filter is sized for
+    pub mempool_bloom_max_txs: Option<u32>,
+    /// False positive rate of the mempool sync bloom filter when it holds `mempool_bloom_max_txs`
+    pub mempool_bloom_error_rate: Option<f64>,
+    /// Read-only function calls each client IP address may make per second
+    pub read_only_call_rate_limit: Option<u64>,
+    /// Read-only function calls each client IP address may make at once (defaults to the rate)
+    pub read_only_call_rate_burst: Option<u64>,
+    /// Transactions each client IP address may broadcast per second
+    pub tx_broadcast_rate_limit: Option<u64>,
+    /// Transactions each client IP address may broadcast at once (defaults to the rate)
+    pub tx_broadcast_rate_burst: Option<u64>,
+    /// Block proposals each client IP address may submit for validation per second
+    pub block_validation_rate_limit: Option<u64>,
+    /// Block proposals each client IP address may submit for validation at once (defaults to
+    /// the rate)
+    pub block_validation_rate_burst: Option<u64>,
+    /// Data map listings each client IP address may request per second
+    pub map_iteration_rate_limit: Option<u64>,
+    /// Data map listings each client IP address may request at once (defaults to the rate)
+    pub map_iteration_rate_burst: Option<u64>,
+    /// MARF and transaction proofs each client IP address may request per second
+    pub proof_rate_limit: Option<u64>,
+    /// MARF and transaction proofs each client IP address may request at once (defaults to the
+    /// rate)
+    pub proof_rate_burst: Option<u64>,
+    /// Mempool sync queries each client IP address may make per second
+    pub mempool_sync_rate_limit: Option<u64>,
+    /// Mempool sync queries each client IP address may make at once (defaults to the rate)
+    pub mempool_sync_rate_burst: Option<u64>,
+    /// Mempool listings and summaries each client IP address may request per second
+    pub mempool_listing_rate_limit: Option<u64>,
+    /// Mempool listings and summaries each client IP address may request at once (defaults to
+    /// the rate)
+    pub mempool_listing_rate_burst: Option<u64>,
+    /// Block state diffs each client IP address may request per second
+    pub state_diff_rate_limit: Option<u64>,
+    /// Block state diffs each client IP address may request at once (defaults to the rate)
+    pub state_diff_rate_burst: Option<u64>,
+    pub auth_token: Option<String>,
+    pub admin_credentials: Option<Vec<AdminCredentialFile>>,
+    pub divergence_reorg_threshold: Option<u64>,
+    /// Whether the entries of contracts' data maps can be listed over RPC
+    pub enable_map_iteration: Option<bool>,
+    /// Whether block proposals can be submitted over RPC for validation
+    pub enable_block_proposal_validation: Option<bool>,
 }
 
 #[derive(Clone, Deserialize, Default)]
@@ -1466,11 +3369,109 @@ pub struct NodeConfigFile {
     pub wait_time_for_microblocks: Option<u64>,
     pub wait_before_first_anchored_block: Option<u64>,
     pub prometheus_bind: Option<String>,
+    pub grpc_bind: Option<String>,
+    pub ws_bind: Option<String>,
+    pub admin_bind: Option<String>,
     pub marf_cache_strategy: Option<String>,
     pub marf_defer_hashing: Option<bool>,
     pub pox_sync_sample_secs: Option<u64>,
     pub use_test_genesis_chainstate: Option<bool>,
     pub mining_key: Option<String>,
+    pub mining_key_rotation: Option<Vec<MiningKeyRotationFile>>,
+    pub mining_key_rotation_lead_blocks: Option<u64>,
+    pub record_block_results: Option<bool>,
+    pub record_state_diffs: Option<bool>,
+    pub shadow_primary: Option<String>,
+    pub prune_depth: Option<u64>,
+}
+
+/// A credential for the admin RPC endpoints, from a `[[connection_options.admin_credentials]]`
+/// entry
+#[derive(Clone, Deserialize, Default)]
+pub struct AdminCredentialFile {
+    pub role: String,
+    pub secret: Option<String>,
+    /// Hex-encoded public key whose signatures authorize requests
+    pub public_key: Option<String>,
+}
+
+/// A scheduled rotation of the miner's key, from a `[[node.mining_key_rotation]]` entry
+#[derive(Clone, Deserialize, Default)]
+pub struct MiningKeyRotationFile {
+    /// Hex-encoded private key to mine with from `activation_burn_height` on
+    pub mining_key: String,
+    pub activation_burn_height: u64,
+}
+
+/// The subnet's system priority transaction class: calls to any of `contracts` whose origin is
+/// any of `principals`.
+#[derive(Clone, Deserialize, Default)]
+pub struct SystemPriorityConfigFile {
+    /// Fully-qualified identifiers of the governance and emergency contracts
+    pub contracts: Vec<String>,
+    /// Addresses of the standard principals authorized to call them
+    pub principals: Vec<String>,
+    /// Percentage of each block's execution budget that only system priority transactions may
+    /// use
+    pub reserved_budget_percent: Option<u64>,
+}
+
+/// The subnet's genesis specification: contracts to deploy and tokens to allocate in the genesis
+/// block.  Each specification yields its own genesis state root, which `expected_root_hash`
+/// pins.
+#[derive(Clone, Deserialize, Default)]
+pub struct GenesisConfigFile {
+    /// If set, the hex-encoded genesis state root hash that the node must compute
+    pub expected_root_hash: Option<String>,
+    pub contracts: Option<Vec<GenesisContractConfigFile>>,
+    pub ft_allocations: Option<Vec<GenesisFtAllocationConfigFile>>,
+    pub nft_allocations: Option<Vec<GenesisNftAllocationConfigFile>>,
+}
+
+/// A contract deployed in the genesis block, by the issuer of `contract_id`
+#[derive(Clone, Deserialize, Default)]
+pub struct GenesisContractConfigFile {
+    pub contract_id: String,
+    pub code_body: Option<String>,
+    /// Path of a file holding the contract's code, if `code_body` is not given
+    pub path: Option<String>,
+}
+
+/// Fungible tokens minted to `recipient` in the genesis block
+#[derive(Clone, Deserialize, Default)]
+pub struct GenesisFtAllocationConfigFile {
+    pub contract_id: String,
+    pub token_name: String,
+    pub recipient: String,
+    pub amount: u64,
+}
+
+/// A non-fungible token, keyed by a `uint`, minted to `recipient` in the genesis block
+#[derive(Clone, Deserialize, Default)]
+pub struct GenesisNftAllocationConfigFile {
+    pub contract_id: String,
+    pub token_name: String,
+    pub recipient: String,
+    pub id: u64,
+}
+
+/// Overrides for one epoch of the subnet's epoch schedule.  Unset fields keep the epoch's
+/// default start height and block limit.
+#[derive(Clone, Deserialize, Default)]
+pub struct SubnetEpochConfigFile {
+    /// The epoch's name, e.g. "2.05" or "subnet-1"
+    pub epoch_name: String,
+    /// The L1 block height at which the epoch starts
+    pub start_height: Option<u64>,
+    pub write_length: Option<u64>,
+    pub write_count: Option<u64>,
+    pub read_length: Option<u64>,
+    pub read_count: Option<u64>,
+    pub runtime: Option<u64>,
+    /// The cost profile that the epoch's cost contracts implement: "default", or "subnet" for
+    /// cheaper storage I/O.  Only the subnet-1 epoch instantiates cost contracts, so only it can
+    /// select a profile.
+    pub cost_profile: Option<String>,
 }
 
 #[derive(Clone, Deserialize)]
@@ -1505,18 +3506,47 @@ pub struct MinerConfigFile {
     pub subsequent_attempt_time_ms: Option<u64>,
     pub microblock_attempt_time_ms: Option<u64>,
     pub probability_pick_no_estimate_tx: Option<u8>,
+    pub signing_timeout_ms: Option<u64>,
+    pub leader_rotation: Option<bool>,
+    pub max_contract_deploys_per_block: Option<u64>,
+    pub max_contract_deploy_bytes_per_block: Option<u64>,
+    pub max_contract_deploys_per_origin: Option<u64>,
+    pub max_contract_deploy_bytes_per_origin: Option<u64>,
+    pub candidate_check_threads: Option<usize>,
+    pub min_withdrawal_tip: Option<u64>,
+    /// "l1" (the default) or "timer"
+    pub block_production: Option<String>,
+    pub block_interval_ms: Option<u64>,
+    pub block_mempool_threshold: Option<u64>,
+    pub anchor_interval: Option<u64>,
+    /// "fee" (the default), "fee_per_length" or "fee_per_cost"
+    pub tx_ranking: Option<String>,
 }
 
 #[derive(Clone, Deserialize, Default)]
 pub struct EventObserverConfigFile {
     pub endpoint: String,
     pub events_keys: Vec<String>,
+    pub max_event_bytes: Option<u64>,
+    pub max_payload_bytes: Option<u64>,
+    pub retry_backoff_ms: Option<u64>,
+    pub max_retry_backoff_ms: Option<u64>,
 }
 
 #[derive(Clone, Default)]
 pub struct EventObserverConfig {
     pub endpoint: String,
     pub events_keys: Vec<EventKeyType>,
+    /// Events whose JSON serialization is larger than this many bytes are sent
+    /// as an omission marker instead. Unlimited if `None`.
+    pub max_event_bytes: Option<u64>,
+    /// Payloads whose JSON serialization is larger than this many bytes have their
+    /// largest events sent as omission markers until they fit. Unlimited if `None`.
+    pub max_payload_bytes: Option<u64>,
+    /// Milliseconds to wait before retrying a failed delivery. The wait doubles after
+    /// each consecutive failure, up to `max_retry_backoff_ms`.
+    pub retry_backoff_ms: Option<u64>,
+    pub max_retry_backoff_ms: Option<u64>,
 }
 
 #[derive(Clone)]
@@ -1525,6 +3555,7 @@ pub enum EventKeyType {
     AssetEvent(AssetIdentifier),
     STXEvent,
     WithdrawalEvent,
+    SubnetWithdrawals,
     MemPoolTransactions,
     Microblocks,
     AnyEvent,
@@ -1534,6 +3565,29 @@ pub enum EventKeyType {
 }
 
 impl EventKeyType {
+    /// The key as it is written in the config file.  Keys that cannot be written there get a
+    /// descriptive name instead.
+    pub fn to_config_string(&self) -> String {
+        match self {
+            EventKeyType::SmartContractEvent((contract_identifier, event_name)) => {
+                format!("{}::{}", contract_identifier, event_name)
+            }
+            EventKeyType::AssetEvent(asset_identifier) => format!(
+                "{}.{}",
+                asset_identifier.contract_identifier, asset_identifier.asset_name
+            ),
+            EventKeyType::STXEvent => "stx".into(),
+            EventKeyType::WithdrawalEvent => "withdrawals".into(),
+            EventKeyType::SubnetWithdrawals => "subnet_withdrawals".into(),
+            EventKeyType::MemPoolTransactions => "memtx".into(),
+            EventKeyType::Microblocks => "microblocks".into(),
+            EventKeyType::AnyEvent => "*".into(),
+            EventKeyType::BurnchainBlocks => "burn_blocks".into(),
+            EventKeyType::MinedBlocks => "mined_blocks".into(),
+            EventKeyType::MinedMicroblocks => "mined_microblocks".into(),
+        }
+    }
+
     fn from_string(raw_key: &str) -> Option<EventKeyType> {
         if raw_key == "*" {
             return Some(EventKeyType::AnyEvent);
@@ -1555,6 +3609,10 @@ impl EventKeyType {
             return Some(EventKeyType::Microblocks);
         }
 
+        if raw_key == "subnet_withdrawals" {
+            return Some(EventKeyType::SubnetWithdrawals);
+        }
+
         let comps: Vec<_> = raw_key.split("::").collect();
         if comps.len() == 1 {
             let split: Vec<_> = comps[0].split(".").collect();
@@ -0,0 +1,516 @@
+//! Operator commands.
+//!
+//! `ctl` subcommands inspect and manage a running node through its RPC interface, so that
+//! operators do not need to craft requests to it by hand for routine operations.  Commands that
+//! use the admin endpoints authenticate with `--auth-token` (a shared secret), or with
+//! `--admin-key` (a private key whose signature of each request the node checks).  The node only
+//! serves admin endpoints on its admin listener (`node.admin_bind`), so those commands must be
+//! pointed at that address with `--node`.
+
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use reqwest::{Method, Url};
+use serde::de::DeserializeOwned;
+
+use stacks::burnchains::Txid;
+use stacks::chainstate::stacks::StacksPrivateKey;
+use stacks::net::admin::sign_admin_request;
+use stacks::net::db::PeerBanTarget;
+use stacks::net::{
+    AdminObserversResponse, AdminPeerInfo, AdminPeersResponse, MemPoolEvictionResponse,
+    MemPoolSummaryResponse, PeerBanInfo, PeerBanRequestBody, RPCPeerInfoData,
+};
+use stacks::util::get_epoch_time_secs;
+
+/// RPC URL used when `--node` is not given
+pub const DEFAULT_CTL_NODE_URL: &str = "http://127.0.0.1:20443";
+
+/// How long `peers ban` bans a peer for, if no duration is given
+pub const DEFAULT_CTL_BAN_DURATION_SECS: u64 = 86_400;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The request could not be sent, or the response could not be read
+    Request(String),
+    /// The node answered with a non-success status
+    Status(u16, String),
+    /// The node's response could not be decoded
+    Decode(String),
+    /// The admin request could not be signed
+    Sign(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Request(msg) => write!(f, "request failed: {}", msg),
+            Error::Status(status, body) => write!(f, "node returned HTTP {}: {}", status, body),
+            Error::Decode(msg) => write!(f, "failed to decode response: {}", msg),
+            Error::Sign(msg) => write!(f, "failed to sign admin request: {}", msg),
+        }
+    }
+}
+
+/// A `ctl` subcommand
+#[derive(Debug, Clone, PartialEq)]
+pub enum CtlCommand {
+    /// Show the node's chain tip
+    TipInfo,
+    /// List the peers the node is connected to
+    PeersList,
+    /// Ban a range of peer addresses, and close the connections of the peers in it
+    PeersBan {
+        /// the banned range, as `ip/prefix-length`
+        address_range: String,
+        duration_secs: u64,
+        reason: Option<String>,
+    },
+    /// Show how many transactions are in the node's mempool, and their fees
+    MempoolStats,
+    /// Evict a transaction from the node's mempool
+    MempoolPurge { txid: Txid, reason: Option<String> },
+    /// List the event observers the node sends events to
+    ObserversList,
+}
+
+impl CtlCommand {
+    /// Parse the words following `ctl`, e.g. `["peers", "list"]`
+    pub fn from_args(args: &[String]) -> Option<CtlCommand> {
+        let words: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+        match words.as_slice() {
+            ["tip"] | ["tip", "info"] => Some(CtlCommand::TipInfo),
+            ["peers"] | ["peers", "list"] => Some(CtlCommand::PeersList),
+            ["peers", "ban", addr] => Some(CtlCommand::PeersBan {
+                address_range: ban_address_range(addr)?,
+                duration_secs: DEFAULT_CTL_BAN_DURATION_SECS,
+                reason: None,
+            }),
+            ["peers", "ban", addr, duration_secs] => Some(CtlCommand::PeersBan {
+                address_range: ban_address_range(addr)?,
+                duration_secs: duration_secs.parse().ok()?,
+                reason: None,
+            }),
+            ["peers", "ban", addr, duration_secs, reason] => Some(CtlCommand::PeersBan {
+                address_range: ban_address_range(addr)?,
+                duration_secs: duration_secs.parse().ok()?,
+                reason: Some(reason.to_string()),
+            }),
+            ["mempool"] | ["mempool", "stats"] => Some(CtlCommand::MempoolStats),
+            ["mempool", "purge", txid] => Some(CtlCommand::MempoolPurge {
+                txid: Txid::from_hex(txid.trim_start_matches("0x")).ok()?,
+                reason: None,
+            }),
+            ["mempool", "purge", txid, reason] => Some(CtlCommand::MempoolPurge {
+                txid: Txid::from_hex(txid.trim_start_matches("0x")).ok()?,
+                reason: Some(reason.to_string()),
+            }),
+            ["observers"] | ["observers", "list"] => Some(CtlCommand::ObserversList),
+            _ => None,
+        }
+    }
+}
+
+/// The address range that `peers ban` bans, given a peer's p2p address as `ip:port` (as
+/// `peers list` shows it), a bare IP address, or a range as `ip/prefix-length`.  A single
+/// address is banned as a range holding just that address, whatever its port.
+fn ban_address_range(addr: &str) -> Option<String> {
+    let ip = if let Ok(sock_addr) = addr.parse::<SocketAddr>() {
+        sock_addr.ip()
+    } else if let Ok(ip) = addr.parse::<IpAddr>() {
+        ip
+    } else {
+        return PeerBanTarget::from_address_range(addr)?.to_address_range();
+    };
+    let prefix_len = if ip.is_ipv4() { 32 } else { 128 };
+    Some(format!("{}/{}", ip, prefix_len))
+}
+
+/// How `ctl` authenticates to the node's admin endpoints
+pub enum CtlAuth {
+    /// Send this shared secret as the `Authorization` header
+    Token(String),
+    /// Sign each request with this key
+    Key(StacksPrivateKey),
+}
+
+/// Client for a node's RPC interface
+pub struct CtlClient {
+    node_url: String,
+    client: reqwest::blocking::Client,
+    auth: Option<CtlAuth>,
+}
+
+impl CtlClient {
+    pub fn new(node_url: &str, auth: Option<CtlAuth>) -> CtlClient {
+        CtlClient {
+            node_url: node_url.trim_end_matches('/').to_string(),
+            client: reqwest::blocking::Client::new(),
+            auth,
+        }
+    }
+
+    /// The `Authorization` header of a `method` request to `path` with `body`, if `ctl` has
+    /// credentials.  `path` includes the query string, if any.
+    fn authorization(
+        &self,
+        method: &Method,
+        path: &str,
+        body: &[u8],
+    ) -> Result<Option<String>, Error> {
+        match self.auth {
+            None => Ok(None),
+            Some(CtlAuth::Token(ref token)) => Ok(Some(token.clone())),
+            Some(CtlAuth::Key(ref privkey)) => {
+                sign_admin_request(privkey, method.as_str(), path, body, get_epoch_time_secs())
+                    .map(Some)
+                    .map_err(|e| Error::Sign(e.to_string()))
+            }
+        }
+    }
+
+    /// Send a `method` request to `path`, with `query` parameters and a JSON `body`, and decode
+    /// the JSON response
+    fn request_json<T: DeserializeOwned, B: serde::Serialize>(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<&B>,
+    ) -> Result<T, Error> {
+        let mut url = Url::parse(&format!("{}{}", &self.node_url, path))
+            .map_err(|e| Error::Request(e.to_string()))?;
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        // sign exactly the query string and body that are sent
+        let signed_path = match url.query() {
+            Some(query) => format!("{}?{}", path, query),
+            None => path.to_string(),
+        };
+        let body = match body {
+            Some(body) => serde_json::to_vec(body).map_err(|e| Error::Request(e.to_string()))?,
+            None => vec![],
+        };
+
+        let mut request = self.client.request(method.clone(), url);
+        if let Some(authorization) = self.authorization(&method, &signed_path, &body)? {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+        if !body.is_empty() {
+            request = request.header(CONTENT_TYPE, "application/json").body(body);
+        }
+        let response = request.send().map_err(|e| Error::Request(e.to_string()))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().unwrap_or_default();
+            return Err(Error::Status(status.as_u16(), body));
+        }
+        response.json().map_err(|e| Error::Decode(e.to_string()))
+    }
+
+    fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+        self.request_json::<T, ()>(Method::GET, path, &[], None)
+    }
+
+    pub fn get_info(&self) -> Result<RPCPeerInfoData, Error> {
+        self.get_json("/v2/info")
+    }
+
+    pub fn get_peers(&self) -> Result<AdminPeersResponse, Error> {
+        self.get_json("/v2/admin/peers")
+    }
+
+    pub fn ban_peers(
+        &self,
+        address_range: &str,
+        duration_secs: u64,
+        reason: Option<&String>,
+    ) -> Result<PeerBanInfo, Error> {
+        let body = PeerBanRequestBody {
+            public_key_hash: None,
+            address_range: Some(address_range.to_string()),
+            duration_secs: Some(duration_secs),
+            reason: reason.cloned(),
+        };
+        self.request_json(Method::POST, "/v2/admin/bans/add", &[], Some(&body))
+    }
+
+    pub fn get_mempool_summary(&self) -> Result<MemPoolSummaryResponse, Error> {
+        self.get_json("/v2/mempool/summary")
+    }
+
+    pub fn evict_mempool_tx(
+        &self,
+        txid: &Txid,
+        reason: Option<&String>,
+    ) -> Result<MemPoolEvictionResponse, Error> {
+        let path = format!("/v2/mempool/{}", txid);
+        let query: Vec<_> = reason
+            .iter()
+            .map(|reason| ("reason", reason.as_str()))
+            .collect();
+        self.request_json::<_, ()>(Method::DELETE, &path, &query, None)
+    }
+
+    pub fn get_observers(&self) -> Result<AdminObserversResponse, Error> {
+        self.get_json("/v2/admin/observers")
+    }
+
+    /// Run `command`, and return its output
+    pub fn run(&self, command: &CtlCommand) -> Result<String, Error> {
+        match command {
+            CtlCommand::TipInfo => self.get_info().map(|info| format_tip_info(&info)),
+            CtlCommand::PeersList => self.get_peers().map(|peers| format_peers_list(&peers)),
+            CtlCommand::PeersBan {
+                address_range,
+                duration_secs,
+                reason,
+            } => self
+                .ban_peers(address_range, *duration_secs, reason.as_ref())
+                .map(|ban| {
+                    format!(
+                        "ban {}: banned {} until {}: {}",
+                        ban.ban_id,
+                        ban.address_range.as_deref().unwrap_or(address_range),
+                        ban.expires_at
+                            .map(|expires_at| expires_at.to_string())
+                            .unwrap_or("forever".to_string()),
+                        &ban.reason
+                    )
+                }),
+            CtlCommand::MempoolStats => self
+                .get_mempool_summary()
+                .map(|summary| format_mempool_summary(&summary)),
+            CtlCommand::MempoolPurge { txid, reason } => self
+                .evict_mempool_tx(txid, reason.as_ref())
+                .map(|eviction| {
+                    format!(
+                        "evicted {} (origin {}, nonce {}, fee {}): {}",
+                        &eviction.txid,
+                        &eviction.origin_address,
+                        eviction.origin_nonce,
+                        eviction.tx_fee,
+                        &eviction.reason
+                    )
+                }),
+            CtlCommand::ObserversList => self
+                .get_observers()
+                .map(|observers| format_observers_list(&observers)),
+        }
+    }
+}
+
+pub fn format_tip_info(info: &RPCPeerInfoData) -> String {
+    let mut lines = vec![
+        format!("server_version: {}", &info.server_version),
+        format!("network_id: {}", info.network_id),
+        format!("stacks_tip_height: {}", info.stacks_tip_height),
+        format!("stacks_tip: {}", &info.stacks_tip),
+        format!(
+            "stacks_tip_consensus_hash: {}",
+            &info.stacks_tip_consensus_hash
+        ),
+        format!("burn_block_height: {}", info.burn_block_height),
+    ];
+    if let (Some(tip), Some(seq)) = (info.unanchored_tip.as_ref(), info.unanchored_seq) {
+        lines.push(format!("unanchored_tip: {} (seq {})", tip, seq));
+    }
+    lines.join("\n")
+}
+
+fn format_peer(peer: &AdminPeerInfo) -> String {
+    let neighbor = &peer.neighbor;
+    format!(
+        "{}\t{}\t{}\t{}\t{}",
+        if peer.outbound { "outbound" } else { "inbound" },
+        neighbor.addrbytes.to_socketaddr(neighbor.port),
+        &neighbor.public_key_hash,
+        if neighbor.authenticated {
+            "authenticated"
+        } else {
+            "unauthenticated"
+        },
+        peer.first_contact_time
+    )
+}
+
+pub fn format_peers_list(peers: &AdminPeersResponse) -> String {
+    let mut lines =
+        vec!["direction\taddress\tpublic_key_hash\tstatus\tfirst_contact_time".to_string()];
+    lines.extend(peers.peers.iter().map(format_peer));
+    lines.join("\n")
+}
+
+pub fn format_mempool_summary(summary: &MemPoolSummaryResponse) -> String {
+    let mut lines = vec![
+        format!("count: {}", summary.count),
+        "min_fee\tmax_fee\tcount".to_string(),
+    ];
+    lines.extend(
+        summary
+            .fee_histogram
+            .iter()
+            .map(|bucket| format!("{}\t{}\t{}", bucket.min_fee, bucket.max_fee, bucket.count)),
+    );
+    lines.join("\n")
+}
+
+pub fn format_observers_list(observers: &AdminObserversResponse) -> String {
+    let mut lines = vec!["endpoint\tevents_keys".to_string()];
+    lines.extend(
+        observers
+            .observers
+            .iter()
+            .map(|observer| format!("{}\t{}", &observer.endpoint, observer.events_keys.join(","))),
+    );
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use stacks::net::{AdminObserverInfo, PeerAddress, RPCNeighbor};
+    use stacks::util::hash::Hash160;
+
+    fn args(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_ctl_commands() {
+        assert_eq!(
+            CtlCommand::from_args(&args(&["tip", "info"])),
+            Some(CtlCommand::TipInfo)
+        );
+        assert_eq!(
+            CtlCommand::from_args(&args(&["tip"])),
+            Some(CtlCommand::TipInfo)
+        );
+        assert_eq!(
+            CtlCommand::from_args(&args(&["peers", "list"])),
+            Some(CtlCommand::PeersList)
+        );
+        assert_eq!(CtlCommand::from_args(&args(&["peers", "ban"])), None);
+        assert_eq!(
+            CtlCommand::from_args(&args(&["peers", "ban", "1.2.3.4:20444"])),
+            Some(CtlCommand::PeersBan {
+                address_range: "1.2.3.4/32".to_string(),
+                duration_secs: DEFAULT_CTL_BAN_DURATION_SECS,
+                reason: None,
+            })
+        );
+        assert_eq!(
+            CtlCommand::from_args(&args(&["peers", "ban", "1.2.3.4", "60"])),
+            Some(CtlCommand::PeersBan {
+                address_range: "1.2.3.4/32".to_string(),
+                duration_secs: 60,
+                reason: None,
+            })
+        );
+        assert_eq!(
+            CtlCommand::from_args(&args(&["peers", "ban", "10.0.0.0/8", "60", "spam"])),
+            Some(CtlCommand::PeersBan {
+                address_range: "10.0.0.0/8".to_string(),
+                duration_secs: 60,
+                reason: Some("spam".to_string()),
+            })
+        );
+        assert_eq!(
+            CtlCommand::from_args(&args(&["peers", "ban", "[::1]:20444"])),
+            Some(CtlCommand::PeersBan {
+                address_range: "::1/128".to_string(),
+                duration_secs: DEFAULT_CTL_BAN_DURATION_SECS,
+                reason: None,
+            })
+        );
+        assert_eq!(
+            CtlCommand::from_args(&args(&["peers", "ban", "10.0.0.0/33"])),
+            None
+        );
+        assert_eq!(
+            CtlCommand::from_args(&args(&["peers", "ban", "not-an-address"])),
+            None
+        );
+        assert_eq!(
+            CtlCommand::from_args(&args(&["mempool", "stats"])),
+            Some(CtlCommand::MempoolStats)
+        );
+        let txid = Txid([0x22; 32]);
+        assert_eq!(
+            CtlCommand::from_args(&args(&["mempool", "purge", &format!("0x{}", txid)])),
+            Some(CtlCommand::MempoolPurge {
+                txid: txid.clone(),
+                reason: None,
+            })
+        );
+        assert_eq!(
+            CtlCommand::from_args(&args(&["mempool", "purge", &txid.to_string(), "spam"])),
+            Some(CtlCommand::MempoolPurge {
+                txid,
+                reason: Some("spam".to_string()),
+            })
+        );
+        assert_eq!(CtlCommand::from_args(&args(&["mempool", "purge"])), None);
+        assert_eq!(
+            CtlCommand::from_args(&args(&["observers", "list"])),
+            Some(CtlCommand::ObserversList)
+        );
+        assert_eq!(CtlCommand::from_args(&args(&[])), None);
+    }
+
+    #[test]
+    fn format_observers() {
+        let observers = AdminObserversResponse {
+            observers: vec![AdminObserverInfo {
+                endpoint: "localhost:3700".to_string(),
+                events_keys: vec!["stx".to_string(), "memtx".to_string()],
+            }],
+        };
+        assert_eq!(
+            format_observers_list(&observers),
+            "endpoint\tevents_keys\nlocalhost:3700\tstx,memtx"
+        );
+    }
+
+    #[test]
+    fn format_peers() {
+        let neighbor = RPCNeighbor {
+            network_id: 1,
+            peer_version: 2,
+            addrbytes: PeerAddress::from_ipv4(127, 0, 0, 1),
+            port: 20444,
+            public_key_hash: Hash160([0x11; 20]),
+            authenticated: true,
+        };
+        let peers = AdminPeersResponse {
+            peers: vec![
+                AdminPeerInfo {
+                    neighbor: neighbor.clone(),
+                    outbound: true,
+                    first_contact_time: 1_600_000_000,
+                },
+                AdminPeerInfo {
+                    neighbor: RPCNeighbor {
+                        authenticated: false,
+                        ..neighbor
+                    },
+                    outbound: false,
+                    first_contact_time: 0,
+                },
+            ],
+        };
+        assert_eq!(
+            format_peers_list(&peers),
+            format!(
+                "direction\taddress\tpublic_key_hash\tstatus\tfirst_contact_time\n\
+                 outbound\t127.0.0.1:20444\t{}\tauthenticated\t1600000000\n\
+                 inbound\t127.0.0.1:20444\t{}\tunauthenticated\t0",
+                Hash160([0x11; 20]),
+                Hash160([0x11; 20])
+            )
+        );
+    }
+}
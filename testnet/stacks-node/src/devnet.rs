@@ -0,0 +1,185 @@
+//! Local development network.
+//!
+//! `stacks-node devnet` runs a subnet miner against an in-process mock L1 chain, so that
+//! contract developers get a working subnet from one command.  The mock L1 produces a block every
+//! `block_time_ms`, and its simulated subnet contract accepts the miner's block commits, so there
+//! is no L1 node or contract deployment to orchestrate.  The subnet's genesis block prefunds a set
+//! of accounts whose keys are derived deterministically, so they are the same on every run.
+
+use std::thread;
+use std::time::Duration;
+
+use stacks::address::AddressHashMode;
+use stacks::chainstate::stacks::{
+    StacksPrivateKey, StacksPublicKey, C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+};
+use stacks::types::chainstate::StacksAddress;
+use stacks::util::get_epoch_time_secs;
+use stacks::util::hash::Sha256Sum;
+
+use crate::burnchains::mock_events::MockController;
+use crate::config::{
+    BurnchainConfigFile, InitialBalanceFile, NodeConfigFile, BURNCHAIN_NAME_MOCKSTACK,
+};
+use crate::{neon, Config, ConfigFile};
+
+/// The simulated L1 subnet contract that the mock L1 chain reports block commits from
+pub const DEVNET_L1_CONTRACT: &str = "ST000000000000000000002AMW42H.subnet-v1";
+/// Default number of prefunded accounts
+pub const DEVNET_DEFAULT_ACCOUNTS: usize = 10;
+/// Default time between mock L1 blocks
+pub const DEVNET_DEFAULT_BLOCK_TIME_MS: u64 = 10_000;
+/// Balance of each prefunded account, in uSTX
+pub const DEVNET_ACCOUNT_BALANCE: u64 = 100_000_000_000_000;
+
+pub struct DevnetOptions {
+    /// Directory for the node's chainstate.  A fresh directory under /tmp is used if unset.
+    pub working_dir: Option<String>,
+    /// Milliseconds between mock L1 blocks
+    pub block_time_ms: u64,
+    /// Number of prefunded accounts
+    pub num_accounts: usize,
+}
+
+impl Default for DevnetOptions {
+    fn default() -> DevnetOptions {
+        DevnetOptions {
+            working_dir: None,
+            block_time_ms: DEVNET_DEFAULT_BLOCK_TIME_MS,
+            num_accounts: DEVNET_DEFAULT_ACCOUNTS,
+        }
+    }
+}
+
+/// A prefunded devnet account
+pub struct DevnetAccount {
+    pub address: StacksAddress,
+    pub private_key: StacksPrivateKey,
+}
+
+impl DevnetAccount {
+    /// The `index`th devnet account.  Its key is derived from the index alone.
+    pub fn new(index: usize) -> DevnetAccount {
+        let seed = Sha256Sum::from_data(format!("stacks-subnet-devnet-{}", index).as_bytes());
+        let mut private_key = StacksPrivateKey::from_slice(seed.as_bytes())
+            .expect("FATAL: devnet account seed is not a valid private key");
+        private_key.set_compress_public(true);
+        let address = StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![StacksPublicKey::from_private(&private_key)],
+        )
+        .expect("FATAL: failed to make devnet account address");
+        DevnetAccount {
+            address,
+            private_key,
+        }
+    }
+}
+
+/// Make the config file for a devnet node that mines against the mock L1 chain, and prefunds
+/// `accounts`.
+pub fn devnet_config_file(working_dir: &str, accounts: &[DevnetAccount]) -> ConfigFile {
+    let burnchain = BurnchainConfigFile {
+        chain: Some(BURNCHAIN_NAME_MOCKSTACK.to_string()),
+        contract_identifier: Some(DEVNET_L1_CONTRACT.to_string()),
+        first_burn_header_height: Some(1),
+        poll_time_secs: Some(1),
+        ..BurnchainConfigFile::default()
+    };
+
+    let node = NodeConfigFile {
+        working_dir: Some(working_dir.to_string()),
+        seed: Some("0000000000000000000000000000000000000000000000000000000000000000".into()),
+        miner: Some(true),
+        ..NodeConfigFile::default()
+    };
+
+    let balances = accounts
+        .iter()
+        .map(|account| InitialBalanceFile {
+            address: account.address.to_string(),
+            amount: DEVNET_ACCOUNT_BALANCE,
+        })
+        .collect();
+
+    ConfigFile {
+        burnchain: Some(burnchain),
+        node: Some(node),
+        ustx_balance: Some(balances),
+        ..ConfigFile::default()
+    }
+}
+
+/// Start a devnet: a subnet miner, and a mock L1 chain that produces a block every
+/// `options.block_time_ms`.  This does not return.
+pub fn run(options: DevnetOptions) {
+    let working_dir = options
+        .working_dir
+        .unwrap_or_else(|| format!("/tmp/stacks-subnet-devnet/{}", get_epoch_time_secs()));
+    let accounts: Vec<_> = (0..options.num_accounts).map(DevnetAccount::new).collect();
+    let conf = Config::from_config_file(devnet_config_file(&working_dir, &accounts));
+
+    println!("Starting devnet in {}", &working_dir);
+    println!("Subnet RPC: http://{}", &conf.node.rpc_bind);
+    println!("Simulated L1 subnet contract: {}", DEVNET_L1_CONTRACT);
+    println!("Prefunded accounts ({} uSTX each):", DEVNET_ACCOUNT_BALANCE);
+    for account in accounts.iter() {
+        println!(
+            "  {} (secret key {})",
+            &account.address,
+            account.private_key.to_hex()
+        );
+    }
+
+    let mut run_loop = neon::RunLoop::new(conf.clone());
+    let coordinator = run_loop
+        .get_coordinator_channel()
+        .expect("FATAL: run loop has no coordinator channel");
+    let mut l1_controller = MockController::new(conf, coordinator);
+
+    thread::spawn(move || run_loop.start(None, 0));
+
+    loop {
+        thread::sleep(Duration::from_millis(options.block_time_ms));
+        l1_controller.next_block(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn devnet_accounts_are_deterministic() {
+        let first = DevnetAccount::new(0);
+        assert_eq!(first.address, DevnetAccount::new(0).address);
+        assert_eq!(
+            first.private_key.to_hex(),
+            DevnetAccount::new(0).private_key.to_hex()
+        );
+        assert_ne!(first.address, DevnetAccount::new(1).address);
+    }
+
+    #[test]
+    fn devnet_config_prefunds_accounts() {
+        let accounts: Vec<_> = (0..3).map(DevnetAccount::new).collect();
+        let conf = Config::from_config_file(devnet_config_file(
+            "/tmp/stacks-subnet-devnet-test",
+            &accounts,
+        ));
+
+        assert_eq!(conf.burnchain.chain, BURNCHAIN_NAME_MOCKSTACK);
+        assert_eq!(
+            conf.burnchain.contract_identifier.to_string(),
+            DEVNET_L1_CONTRACT
+        );
+        assert!(conf.node.miner);
+        assert_eq!(conf.initial_balances.len(), 3);
+        for (balance, account) in conf.initial_balances.iter().zip(accounts.iter()) {
+            assert_eq!(balance.address.to_string(), account.address.to_string());
+            assert_eq!(balance.amount, DEVNET_ACCOUNT_BALANCE);
+        }
+    }
+}
@@ -1,4 +1,7 @@
+use std::borrow::Cow;
+use std::cmp;
 use std::collections::hash_map::Entry;
+use std::collections::VecDeque;
 use std::thread::sleep;
 use std::time::Duration;
 use std::{
@@ -11,7 +14,7 @@ use async_std::net::TcpStream;
 use http_types::{Method, Request, Url};
 use serde_json::json;
 
-use stacks::burnchains::Txid;
+use stacks::burnchains::{PrivateKey, Txid};
 use stacks::chainstate::coordinator::BlockEventDispatcher;
 use stacks::chainstate::stacks::db::StacksHeaderInfo;
 use stacks::chainstate::stacks::events::{
@@ -21,26 +24,47 @@ use stacks::chainstate::stacks::{
     db::accounts::MinerReward, db::MinerRewardInfo, StacksTransaction,
 };
 use stacks::chainstate::stacks::{StacksBlock, StacksMicroblock};
+use stacks::clarity_vm::withdrawal;
 use stacks::codec::StacksMessageCodec;
 use stacks::core::mempool::{MemPoolDropReason, MemPoolEventDispatcher};
 use stacks::net::atlas::{Attachment, AttachmentInstance};
 use stacks::types::chainstate::{
     BlockHeaderHash, BurnchainHeaderHash, StacksAddress, StacksBlockId,
 };
-use stacks::util::hash::bytes_to_hex;
+use stacks::util::hash::{bytes_to_hex, MerkleTree, Sha256Sum, Sha512Trunc256Sum};
+use stacks::util::secp256k1::Secp256k1PrivateKey;
 use stacks::vm::analysis::contract_interface_builder::build_contract_interface;
 use stacks::vm::costs::ExecutionCost;
 use stacks::vm::events::{FTEventType, NFTEventType, STXEventType};
 use stacks::vm::types::{AssetIdentifier, QualifiedContractIdentifier, Value};
 
 use super::config::{EventKeyType, EventObserverConfig};
+use super::event_queue::{EventQueueDB, QueuedSubnetWithdrawals};
+use super::grpc::GrpcEventStreams;
+use super::websocket::WebSocketEventStreams;
 use stacks::chainstate::burn::ConsensusHash;
 use stacks::chainstate::stacks::db::unconfirmed::ProcessedUnconfirmedState;
 use stacks::chainstate::stacks::miner::TransactionEvent;
+use stacks::util_lib::db::Error as db_error;
 
 #[derive(Debug, Clone)]
 struct EventObserver {
     endpoint: String,
+    /// Serialized events larger than this are replaced by an omission marker
+    max_event_bytes: Option<u64>,
+    /// Serialized payloads larger than this have their largest events replaced by
+    /// omission markers until the payload fits
+    max_payload_bytes: Option<u64>,
+    /// Delay before retrying a failed POST.  Doubles after each failure, up to
+    /// `max_retry_backoff`.
+    retry_backoff: Duration,
+    max_retry_backoff: Duration,
+    /// If set, payloads are persisted here until the observer acknowledges them, and are
+    /// re-sent after a restart
+    event_queue: Option<Arc<Mutex<EventQueueDB>>>,
+    /// If set, every POST carries a signature over its body by this key, in the
+    /// `EVENT_SIGNATURE_HEADER` header
+    signing_key: Option<Secp256k1PrivateKey>,
 }
 
 struct ReceiptPayloadInfo<'a> {
@@ -64,6 +88,15 @@ pub const PATH_MINED_MICROBLOCK: &str = "mined_microblock";
 pub const PATH_BURN_BLOCK_SUBMIT: &str = "new_burn_block";
 pub const PATH_BLOCK_PROCESSED: &str = "new_block";
 pub const PATH_ATTACHMENT_PROCESSED: &str = "attachments/new";
+pub const PATH_SUBNET_WITHDRAWALS: &str = "subnet_withdrawals";
+pub const PATH_WITHDRAWAL_INVALIDATED: &str = "withdrawal_invalidated";
+
+/// Header carrying the hex-encoded recoverable secp256k1 signature over the SHA-256 hash of a
+/// POST body
+pub const EVENT_SIGNATURE_HEADER: &str = "X-Stacks-Node-Signature";
+
+pub const DEFAULT_RETRY_BACKOFF_MS: u64 = 1_000;
+pub const DEFAULT_MAX_RETRY_BACKOFF_MS: u64 = 30_000;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MinedBlockEvent {
@@ -86,6 +119,105 @@ pub struct MinedMicroblockEvent {
 }
 
 impl EventObserver {
+    /// Build an omission marker for an event whose serialization was too large to send.
+    /// The marker keeps the fields needed to look the event up from the transaction
+    /// receipt (`txid`, `event_index`, `committed`, and `type`).
+    fn make_omitted_event_marker(event: &serde_json::Value, event_size: u64) -> serde_json::Value {
+        let mut marker = serde_json::Map::new();
+        for field in ["txid", "event_index", "committed", "type"].iter() {
+            if let Some(value) = event.get(*field) {
+                marker.insert(field.to_string(), value.clone());
+            }
+        }
+        marker.insert("payload_omitted".into(), json!(true));
+        marker.insert("payload_size".into(), json!(event_size));
+        serde_json::Value::Object(marker)
+    }
+
+    /// Enforce this observer's payload size limits on the `events` array of a payload.
+    ///
+    /// Every event whose serialization exceeds `max_event_bytes` is replaced by an
+    /// omission marker. Then, if the serialized payload still exceeds `max_payload_bytes`,
+    /// the largest remaining events are replaced by omission markers until it fits (or no
+    /// events are left to omit). Payloads without an `events` array are returned as-is.
+    fn apply_payload_limits<'a>(
+        &self,
+        payload: &'a serde_json::Value,
+        payload_size: u64,
+    ) -> Cow<'a, serde_json::Value> {
+        if self.max_event_bytes.is_none() && self.max_payload_bytes.is_none() {
+            return Cow::Borrowed(payload);
+        }
+        let events = match payload.get("events").and_then(|events| events.as_array()) {
+            Some(events) => events,
+            None => return Cow::Borrowed(payload),
+        };
+
+        let event_sizes: Vec<u64> = events
+            .iter()
+            .map(|event| {
+                serde_json::to_vec(event)
+                    .map(|bytes| bytes.len() as u64)
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let mut omitted = HashSet::new();
+        let mut remaining_size = payload_size;
+
+        if let Some(max_event_bytes) = self.max_event_bytes {
+            for (event_index, event_size) in event_sizes.iter().enumerate() {
+                if *event_size > max_event_bytes {
+                    omitted.insert(event_index);
+                    remaining_size = remaining_size.saturating_sub(*event_size);
+                }
+            }
+        }
+
+        if let Some(max_payload_bytes) = self.max_payload_bytes {
+            let mut by_size: Vec<_> = event_sizes
+                .iter()
+                .enumerate()
+                .filter(|(event_index, _)| !omitted.contains(event_index))
+                .collect();
+            by_size.sort_by(|a, b| b.1.cmp(a.1));
+            for (event_index, event_size) in by_size.into_iter() {
+                if remaining_size <= max_payload_bytes {
+                    break;
+                }
+                omitted.insert(event_index);
+                remaining_size = remaining_size.saturating_sub(*event_size);
+            }
+        }
+
+        if omitted.is_empty() {
+            return Cow::Borrowed(payload);
+        }
+
+        warn!(
+            "Event dispatcher: omitting oversized events from payload";
+            "endpoint" => &self.endpoint,
+            "omitted_events" => omitted.len(),
+            "payload_size" => payload_size,
+        );
+
+        let limited_events: Vec<_> = events
+            .iter()
+            .enumerate()
+            .map(|(event_index, event)| {
+                if omitted.contains(&event_index) {
+                    EventObserver::make_omitted_event_marker(event, event_sizes[event_index])
+                } else {
+                    event.clone()
+                }
+            })
+            .collect();
+
+        let mut limited_payload = payload.clone();
+        limited_payload["events"] = serde_json::Value::Array(limited_events);
+        Cow::Owned(limited_payload)
+    }
+
     fn send_payload(&self, payload: &serde_json::Value, path: &str) {
         let body = match serde_json::to_vec(&payload) {
             Ok(body) => body,
@@ -94,7 +226,78 @@ impl EventObserver {
                 return;
             }
         };
+        let body = match self.apply_payload_limits(payload, body.len() as u64) {
+            Cow::Borrowed(_) => body,
+            Cow::Owned(limited_payload) => match serde_json::to_vec(&limited_payload) {
+                Ok(body) => body,
+                Err(err) => {
+                    error!("Event dispatcher: serialization failed  - {:?}", err);
+                    return;
+                }
+            },
+        };
 
+        match self.event_queue {
+            Some(ref event_queue) => {
+                // hold the lock while delivering, so that queued payloads are sent in order
+                let event_queue = event_queue
+                    .lock()
+                    .expect("FATAL: event observer queue lock poisoned");
+                self.send_queued_payload(&event_queue, &body, path);
+            }
+            None => self.send_body(&body, path),
+        }
+    }
+
+    /// Queue a payload, then send every payload queued for this observer up to and including
+    /// it, removing each one once the observer acknowledges it.  Anything queued before this
+    /// payload (e.g. by a previous run of the node) is sent first.
+    fn send_queued_payload(&self, event_queue: &EventQueueDB, body: &[u8], path: &str) {
+        let queued_id = match event_queue.push(&self.endpoint, path, body) {
+            Ok(id) => id,
+            Err(e) => {
+                error!(
+                    "Event dispatcher: failed to queue payload, sending it directly - {:?}",
+                    e
+                );
+                self.send_body(body, path);
+                return;
+            }
+        };
+        let pending = match event_queue.get_pending(&self.endpoint, queued_id) {
+            Ok(pending) => pending,
+            Err(e) => {
+                error!("Event dispatcher: failed to load queued payloads - {:?}", e);
+                return;
+            }
+        };
+        for event in pending.iter() {
+            self.send_body(&event.payload, &event.path);
+            if let Err(e) = event_queue.remove(event.id) {
+                // it will be sent again, which observers must tolerate anyway
+                warn!(
+                    "Event dispatcher: failed to dequeue delivered payload {} - {:?}",
+                    event.id, e
+                );
+            }
+        }
+    }
+
+    /// Sign the SHA-256 hash of a serialized payload with this observer's signing key, if it has
+    /// one.  Returns the hex-encoded signature.
+    fn sign_body(&self, body: &[u8]) -> Option<String> {
+        let signing_key = self.signing_key.as_ref()?;
+        match signing_key.sign(Sha256Sum::from_data(body).as_bytes()) {
+            Ok(signature) => Some(signature.to_hex()),
+            Err(e) => {
+                error!("Event dispatcher: failed to sign payload - {}", e);
+                None
+            }
+        }
+    }
+
+    /// POST a serialized payload to this observer, retrying with backoff until it succeeds.
+    fn send_body(&self, body: &[u8], path: &str) {
         let url = {
             let joined_components = match path.starts_with("/") {
                 true => format!("{}{}", &self.endpoint, path),
@@ -107,12 +310,16 @@ impl EventObserver {
             ))
         };
 
-        let backoff = Duration::from_millis((1.0 * 1_000.0) as u64);
+        let signature = self.sign_body(body);
+        let mut backoff = self.retry_backoff;
 
         loop {
-            let body = body.clone();
+            let body = body.to_vec();
             let mut req = Request::new(Method::Post, url.clone());
             req.append_header("Content-Type", "application/json");
+            if let Some(ref signature) = signature {
+                req.append_header(EVENT_SIGNATURE_HEADER, signature.as_str());
+            }
             req.set_body(body);
 
             let response = async_std::task::block_on(async {
@@ -146,6 +353,7 @@ impl EventObserver {
                 }
             }
             sleep(backoff);
+            backoff = cmp::min(backoff * 2, self.max_retry_backoff);
         }
     }
 
@@ -259,6 +467,150 @@ impl EventObserver {
         })
     }
 
+    /// Returns the JSON description of a single withdrawal event, including the
+    /// leaf hash of its key in the block's withdrawal Merkle tree. Returns `None` if
+    /// the event is not a withdrawal, or if its withdrawal ID has not been assigned.
+    fn make_subnet_withdrawal_entry(
+        txid: &Txid,
+        event: &StacksTransactionEvent,
+        block_height: u64,
+    ) -> Option<serde_json::Value> {
+        let (withdrawal_type, withdrawal_id, sender, asset_identifier, amount, nft_id, key) =
+            match event {
+                StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawEvent(data)) => {
+                    let withdrawal_id = data.withdrawal_id?;
+                    let key = withdrawal::make_key_for_stx_withdrawal(
+                        &data.sender,
+                        withdrawal_id,
+                        data.amount,
+                        block_height,
+                    );
+                    (
+                        "stx",
+                        withdrawal_id,
+                        &data.sender,
+                        None,
+                        Some(data.amount),
+                        None,
+                        key,
+                    )
+                }
+                StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawTipEvent(data)) => {
+                    let withdrawal_id = data.withdrawal_id?;
+                    // the tip's leaf pays the miner, not the sender
+                    let key = withdrawal::make_key_for_stx_withdrawal(
+                        data.recipient.as_ref()?,
+                        withdrawal_id,
+                        data.amount,
+                        block_height,
+                    );
+                    (
+                        "stx-tip",
+                        withdrawal_id,
+                        &data.sender,
+                        None,
+                        Some(data.amount),
+                        None,
+                        key,
+                    )
+                }
+                StacksTransactionEvent::FTEvent(FTEventType::FTWithdrawEvent(data)) => {
+                    let withdrawal_id = data.withdrawal_id?;
+                    let key = withdrawal::make_key_for_ft_withdrawal(
+                        &data.sender,
+                        withdrawal_id,
+                        &data.asset_identifier,
+                        data.amount,
+                        block_height,
+                    );
+                    (
+                        "ft",
+                        withdrawal_id,
+                        &data.sender,
+                        Some(&data.asset_identifier),
+                        Some(data.amount),
+                        None,
+                        key,
+                    )
+                }
+                StacksTransactionEvent::NFTEvent(NFTEventType::NFTWithdrawEvent(data)) => {
+                    let withdrawal_id = data.withdrawal_id?;
+                    let key = withdrawal::make_key_for_nft_withdrawal(
+                        &data.sender,
+                        withdrawal_id,
+                        &data.asset_identifier,
+                        data.id,
+                        block_height,
+                    );
+                    (
+                        "nft",
+                        withdrawal_id,
+                        &data.sender,
+                        Some(&data.asset_identifier),
+                        None,
+                        Some(data.id),
+                        key,
+                    )
+                }
+                _ => return None,
+            };
+
+        let key_bytes = withdrawal::convert_withdrawal_key_to_bytes(&key);
+        let leaf_hash = MerkleTree::<Sha512Trunc256Sum>::get_leaf_hash(&key_bytes);
+
+        Some(json!({
+            "txid": format!("0x{}", txid),
+            "type": withdrawal_type,
+            "withdrawal_id": withdrawal_id,
+            "leaf_index": withdrawal_id,
+            "sender": sender.to_string(),
+            "asset_identifier": asset_identifier.map(|id| id.to_string()),
+            "amount": amount.map(|amount| amount.to_string()),
+            "nft_id": nft_id.map(|id| id.to_string()),
+            "withdrawal_key": format!("0x{}", bytes_to_hex(&key_bytes)),
+            "withdrawal_leaf_hash": format!("0x{}", leaf_hash),
+        }))
+    }
+
+    /// Returns the JSON payload for the `subnet_withdrawals` path: every withdrawal
+    /// in the block, ordered by leaf index in the block's withdrawal Merkle tree.
+    fn make_subnet_withdrawals_payload(
+        block: &StacksBlock,
+        metadata: &StacksHeaderInfo,
+        receipts: &Vec<StacksTransactionReceipt>,
+    ) -> serde_json::Value {
+        let block_height = block.header.total_work.work;
+        let mut withdrawals = vec![];
+        for receipt in receipts.iter() {
+            let txid = receipt.transaction.txid();
+            for event in receipt.events.iter() {
+                if let Some(entry) =
+                    EventObserver::make_subnet_withdrawal_entry(&txid, event, block_height)
+                {
+                    withdrawals.push(entry);
+                }
+            }
+        }
+
+        json!({
+            "block_hash": format!("0x{}", block.block_hash()),
+            "block_height": metadata.stacks_block_height,
+            "index_block_hash": format!("0x{}", metadata.index_block_hash()),
+            "burn_block_hash": format!("0x{}", metadata.burn_header_hash),
+            "burn_block_height": metadata.burn_header_height,
+            "withdrawal_root": format!("0x{}", block.header.withdrawal_merkle_root),
+            "withdrawals": withdrawals,
+        })
+    }
+
+    fn send_subnet_withdrawals(&self, payload: &serde_json::Value) {
+        self.send_payload(payload, PATH_SUBNET_WITHDRAWALS);
+    }
+
+    fn send_withdrawal_invalidated(&self, payload: &serde_json::Value) {
+        self.send_payload(payload, PATH_WITHDRAWAL_INVALIDATED);
+    }
+
     fn send_new_attachments(&self, payload: &serde_json::Value) {
         self.send_payload(payload, PATH_ATTACHMENT_PROCESSED);
     }
@@ -313,8 +665,8 @@ impl EventObserver {
         self.send_payload(payload, PATH_BURN_BLOCK_SUBMIT);
     }
 
-    fn send(
-        &self,
+    /// Returns the JSON payload for the `new_block` path, carrying the given events
+    fn make_new_block_payload(
         filtered_events: Vec<(usize, &(bool, Txid, &StacksTransactionEvent))>,
         block: &StacksBlock,
         metadata: &StacksHeaderInfo,
@@ -328,7 +680,7 @@ impl EventObserver {
         parent_burn_block_timestamp: u64,
         anchored_consumed: &ExecutionCost,
         mblock_confirmed_consumed: &ExecutionCost,
-    ) {
+    ) -> serde_json::Value {
         // Serialize events to JSON
         let serialized_events: Vec<serde_json::Value> = filtered_events
             .iter()
@@ -346,8 +698,14 @@ impl EventObserver {
             tx_index += 1;
         }
 
+        // the confirmed microblocks are charged to the same budget as the anchored block
+        let mut total_consumed = anchored_consumed.clone();
+        if total_consumed.add(mblock_confirmed_consumed).is_err() {
+            total_consumed = ExecutionCost::max_value();
+        }
+
         // Wrap events
-        let payload = json!({
+        json!({
             "block_hash": format!("0x{}", block.block_hash()),
             "block_height": metadata.stacks_block_height,
             "burn_block_hash": format!("0x{}", metadata.burn_header_hash),
@@ -367,13 +725,318 @@ impl EventObserver {
             "parent_burn_block_timestamp": parent_burn_block_timestamp,
             "anchored_cost": anchored_consumed,
             "confirmed_microblocks_cost": mblock_confirmed_consumed,
-        });
+            "total_cost": total_consumed,
+        })
+    }
+
+    fn send(
+        &self,
+        filtered_events: Vec<(usize, &(bool, Txid, &StacksTransactionEvent))>,
+        block: &StacksBlock,
+        metadata: &StacksHeaderInfo,
+        receipts: &Vec<StacksTransactionReceipt>,
+        parent_index_hash: &StacksBlockId,
+        boot_receipts: &Vec<StacksTransactionReceipt>,
+        winner_txid: &Txid,
+        mature_rewards: &serde_json::Value,
+        parent_burn_block_hash: BurnchainHeaderHash,
+        parent_burn_block_height: u32,
+        parent_burn_block_timestamp: u64,
+        anchored_consumed: &ExecutionCost,
+        mblock_confirmed_consumed: &ExecutionCost,
+    ) {
+        let payload = EventObserver::make_new_block_payload(
+            filtered_events,
+            block,
+            metadata,
+            receipts,
+            parent_index_hash,
+            boot_receipts,
+            winner_txid,
+            mature_rewards,
+            parent_burn_block_hash,
+            parent_burn_block_height,
+            parent_burn_block_timestamp,
+            anchored_consumed,
+            mblock_confirmed_consumed,
+        );
 
         // Send payload
         self.send_payload(&payload, PATH_BLOCK_PROCESSED);
     }
 }
 
+/// How many released `subnet_withdrawals` payloads are remembered, so that their invalidation can
+/// be reported if a later reorg orphans their blocks
+const MAX_RELEASED_SUBNET_WITHDRAWALS: usize = 256;
+
+/// The `subnet_withdrawals` payload of a processed block
+struct BlockSubnetWithdrawals {
+    block_height: u64,
+    index_block_hash: StacksBlockId,
+    parent_index_block_hash: StacksBlockId,
+    payload: serde_json::Value,
+}
+
+/// The result of adding a newly processed block to a `WithdrawalConfirmationQueue`
+pub struct WithdrawalQueueUpdate {
+    /// `subnet_withdrawals` payloads whose blocks are now confirmed, oldest first
+    pub confirmed: Vec<serde_json::Value>,
+    /// `withdrawal_invalidated` payloads for previously-confirmed blocks that the new block
+    /// orphaned, oldest first
+    pub invalidated: Vec<serde_json::Value>,
+}
+
+/// Holds back `subnet_withdrawals` payloads until `confirmation_depth` blocks have been built on
+/// top of their block, so that a shallow reorg cannot invalidate a withdrawal root that has
+/// already been relayed. Payloads from blocks that are reorged out while pending are dropped.
+/// The most recently released payloads are remembered, and if a deeper reorg orphans one of
+/// their blocks, a `withdrawal_invalidated` payload is produced for it.
+/// Once a database is attached with `open_db()`, the queue is written to it after every block,
+/// and pending payloads are released after a restart just as if the node had kept running.
+pub struct WithdrawalConfirmationQueue {
+    confirmation_depth: u64,
+    pending: VecDeque<BlockSubnetWithdrawals>,
+    released: VecDeque<BlockSubnetWithdrawals>,
+    db: Option<EventQueueDB>,
+}
+
+impl WithdrawalConfirmationQueue {
+    pub fn new(confirmation_depth: u64) -> WithdrawalConfirmationQueue {
+        WithdrawalConfirmationQueue {
+            confirmation_depth,
+            pending: VecDeque::new(),
+            released: VecDeque::new(),
+            db: None,
+        }
+    }
+
+    /// Persist the queue in the event observer queue at `path`, and restore whatever it held
+    /// when the node last stopped.
+    pub fn open_db(&mut self, path: &str) -> Result<(), db_error> {
+        let db = EventQueueDB::open(path)?;
+        self.pending.clear();
+        self.released.clear();
+        for entry in db.get_subnet_withdrawals()?.into_iter() {
+            let payload = serde_json::from_str(&entry.payload)
+                .map_err(|e| db_error::SerializationError(e))?;
+            let block = BlockSubnetWithdrawals {
+                block_height: entry.block_height,
+                index_block_hash: entry.index_block_hash,
+                parent_index_block_hash: entry.parent_index_block_hash,
+                payload,
+            };
+            if entry.released {
+                self.released.push_back(block);
+            } else {
+                self.pending.push_back(block);
+            }
+        }
+        if !self.pending.is_empty() {
+            info!(
+                "Event dispatcher: {} blocks of withdrawals awaiting confirmation",
+                self.pending.len()
+            );
+        }
+        self.db = Some(db);
+        Ok(())
+    }
+
+    /// Write the queue to its database, if it has one
+    fn persist(&mut self) {
+        let db = match self.db.as_mut() {
+            Some(db) => db,
+            None => return,
+        };
+        let entries: Vec<_> = self
+            .released
+            .iter()
+            .map(|block| (block, true))
+            .chain(self.pending.iter().map(|block| (block, false)))
+            .map(|(block, released)| QueuedSubnetWithdrawals {
+                index_block_hash: block.index_block_hash.clone(),
+                block_height: block.block_height,
+                parent_index_block_hash: block.parent_index_block_hash.clone(),
+                payload: block.payload.to_string(),
+                released,
+            })
+            .collect();
+        if let Err(e) = db.set_subnet_withdrawals(&entries) {
+            error!("Failed to persist the withdrawal confirmation queue"; "error" => ?e);
+        }
+    }
+
+    /// Remove the entries that are not on the fork whose block at `expected` is known, walking
+    /// back from the newest entry.  `expected` is updated to the oldest ancestor visited.
+    /// Returns the removed entries, newest first.
+    fn retain_fork(
+        entries: &mut VecDeque<BlockSubnetWithdrawals>,
+        expected: &mut Option<(u64, StacksBlockId)>,
+    ) -> Vec<BlockSubnetWithdrawals> {
+        let mut ancestors = VecDeque::with_capacity(entries.len());
+        let mut orphaned = vec![];
+        while let Some(entry) = entries.pop_back() {
+            let on_fork = match expected {
+                Some((height, _)) if entry.block_height > *height => false,
+                Some((height, hash)) if entry.block_height == *height => {
+                    &entry.index_block_hash == hash
+                }
+                // can't tell -- the ancestor at this height isn't queued
+                _ => true,
+            };
+            if on_fork {
+                *expected = Some((
+                    entry.block_height.saturating_sub(1),
+                    entry.parent_index_block_hash.clone(),
+                ));
+                ancestors.push_front(entry);
+            } else {
+                if let Some((height, _)) = expected {
+                    if entry.block_height == *height {
+                        // the new block's ancestor at this height isn't queued
+                        *expected = None;
+                    }
+                }
+                orphaned.push(entry);
+            }
+        }
+        *entries = ancestors;
+        orphaned
+    }
+
+    /// Make the `withdrawal_invalidated` payload for a released block that was orphaned.  Each
+    /// invalidated withdrawal is matched to the same withdrawal in a block on the new fork, if
+    /// its transaction has been mined there.
+    fn make_withdrawal_invalidated_payload(
+        &self,
+        orphaned: &BlockSubnetWithdrawals,
+        new_tip: &StacksBlockId,
+    ) -> serde_json::Value {
+        let on_fork: Vec<_> = self.released.iter().chain(self.pending.iter()).collect();
+        let withdrawals: Vec<_> = WithdrawalConfirmationQueue::withdrawals_by_tx(&orphaned.payload)
+            .into_iter()
+            .map(|(txid, ordinal, withdrawal)| {
+                let replacement = on_fork.iter().find_map(|block| {
+                    WithdrawalConfirmationQueue::withdrawals_by_tx(&block.payload)
+                        .into_iter()
+                        .find(|(other_txid, other_ordinal, _)| {
+                            other_txid == &txid && *other_ordinal == ordinal
+                        })
+                        .map(|(_, _, replacement)| {
+                            json!({
+                                "block_height": block.block_height,
+                                "index_block_hash": format!("0x{}", block.index_block_hash),
+                                "withdrawal_root": block.payload["withdrawal_root"],
+                                "withdrawal_id": replacement["withdrawal_id"],
+                                "withdrawal_key": replacement["withdrawal_key"],
+                                "withdrawal_leaf_hash": replacement["withdrawal_leaf_hash"],
+                            })
+                        })
+                });
+                json!({
+                    "txid": txid,
+                    "withdrawal_id": withdrawal["withdrawal_id"],
+                    "withdrawal_key": withdrawal["withdrawal_key"],
+                    "replacement": replacement,
+                })
+            })
+            .collect();
+
+        json!({
+            "block_hash": orphaned.payload["block_hash"],
+            "block_height": orphaned.block_height,
+            "index_block_hash": format!("0x{}", orphaned.index_block_hash),
+            "withdrawal_root": orphaned.payload["withdrawal_root"],
+            "new_tip_index_block_hash": format!("0x{}", new_tip),
+            "withdrawals": withdrawals,
+        })
+    }
+
+    /// The withdrawals in a `subnet_withdrawals` payload, each with its txid and its position
+    /// among that transaction's withdrawals
+    fn withdrawals_by_tx(payload: &serde_json::Value) -> Vec<(String, usize, &serde_json::Value)> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        payload["withdrawals"]
+            .as_array()
+            .map(|withdrawals| withdrawals.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|withdrawal| {
+                let txid = withdrawal["txid"].as_str().unwrap_or_default().to_string();
+                let count = counts.entry(txid.clone()).or_insert(0);
+                let ordinal = *count;
+                *count += 1;
+                (txid, ordinal, withdrawal)
+            })
+            .collect()
+    }
+
+    /// Add the payload of a newly processed block, which is the new chain tip. Pending payloads
+    /// from blocks that are not ancestors of the new block are dropped, and released payloads
+    /// from such blocks are reported as invalidated.
+    pub fn push(
+        &mut self,
+        block_height: u64,
+        index_block_hash: StacksBlockId,
+        parent_index_block_hash: StacksBlockId,
+        payload: serde_json::Value,
+    ) -> WithdrawalQueueUpdate {
+        // walk back from the new block's parent, dropping anything not on its fork
+        let mut expected = Some((
+            block_height.saturating_sub(1),
+            parent_index_block_hash.clone(),
+        ));
+        for pending in WithdrawalConfirmationQueue::retain_fork(&mut self.pending, &mut expected) {
+            warn!(
+                "Dropping pending withdrawals from orphaned block";
+                "block_height" => pending.block_height,
+                "index_block_hash" => %pending.index_block_hash
+            );
+        }
+        let orphaned = WithdrawalConfirmationQueue::retain_fork(&mut self.released, &mut expected);
+
+        self.pending.push_back(BlockSubnetWithdrawals {
+            block_height,
+            index_block_hash: index_block_hash.clone(),
+            parent_index_block_hash,
+            payload,
+        });
+
+        let invalidated = orphaned
+            .iter()
+            .rev()
+            .map(|released| {
+                warn!(
+                    "Withdrawals from a confirmed block were invalidated by a reorg";
+                    "block_height" => released.block_height,
+                    "index_block_hash" => %released.index_block_hash
+                );
+                self.make_withdrawal_invalidated_payload(released, &index_block_hash)
+            })
+            .collect();
+
+        let mut confirmed = vec![];
+        while let Some(oldest) = self.pending.front() {
+            if oldest.block_height.saturating_add(self.confirmation_depth) > block_height {
+                break;
+            }
+            if let Some(oldest) = self.pending.pop_front() {
+                confirmed.push(oldest.payload.clone());
+                self.released.push_back(oldest);
+            }
+        }
+        while self.released.len() > MAX_RELEASED_SUBNET_WITHDRAWALS {
+            self.released.pop_front();
+        }
+        self.persist();
+
+        WithdrawalQueueUpdate {
+            confirmed,
+            invalidated,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct EventDispatcher {
     registered_observers: Vec<EventObserver>,
@@ -384,10 +1047,14 @@ pub struct EventDispatcher {
     microblock_observers_lookup: HashSet<u16>,
     stx_observers_lookup: HashSet<u16>,
     withdrawal_observers_lookup: HashSet<u16>,
+    subnet_withdrawals_observers_lookup: HashSet<u16>,
     any_event_observers_lookup: HashSet<u16>,
     miner_observers_lookup: HashSet<u16>,
     mined_microblocks_observers_lookup: HashSet<u16>,
     boot_receipts: Arc<Mutex<Option<Vec<StacksTransactionReceipt>>>>,
+    pending_subnet_withdrawals: Arc<Mutex<WithdrawalConfirmationQueue>>,
+    grpc_streams: GrpcEventStreams,
+    ws_streams: WebSocketEventStreams,
 }
 
 impl MemPoolEventDispatcher for EventDispatcher {
@@ -494,6 +1161,7 @@ impl EventDispatcher {
             assets_observers_lookup: HashMap::new(),
             stx_observers_lookup: HashSet::new(),
             withdrawal_observers_lookup: HashSet::new(),
+            subnet_withdrawals_observers_lookup: HashSet::new(),
             any_event_observers_lookup: HashSet::new(),
             burn_block_observers_lookup: HashSet::new(),
             mempool_observers_lookup: HashSet::new(),
@@ -501,6 +1169,59 @@ impl EventDispatcher {
             boot_receipts: Arc::new(Mutex::new(None)),
             miner_observers_lookup: HashSet::new(),
             mined_microblocks_observers_lookup: HashSet::new(),
+            pending_subnet_withdrawals: Arc::new(Mutex::new(WithdrawalConfirmationQueue::new(0))),
+            grpc_streams: GrpcEventStreams::disabled(),
+            ws_streams: WebSocketEventStreams::disabled(),
+        }
+    }
+
+    /// Only relay a block's withdrawals to `subnet_withdrawals` observers once `depth` blocks
+    /// have been built on top of it.
+    pub fn set_withdrawal_confirmation_depth(&mut self, depth: u64) {
+        self.pending_subnet_withdrawals
+            .lock()
+            .expect("Unexpected concurrent access to pending withdrawals in the event dispatcher!")
+            .confirmation_depth = depth;
+    }
+
+    /// Also stream every processed block, and every confirmed block's withdrawals, to gRPC
+    /// clients.
+    pub fn set_grpc_streams(&mut self, grpc_streams: GrpcEventStreams) {
+        self.grpc_streams = grpc_streams;
+    }
+
+    /// Also push every processed block's events to the WebSocket clients subscribed to them.
+    pub fn set_ws_streams(&mut self, ws_streams: WebSocketEventStreams) {
+        self.ws_streams = ws_streams;
+    }
+
+    /// Persist payloads to the queue at `path` until each registered observer acknowledges
+    /// them.  Payloads left over from a previous run are sent before the next new payload.
+    /// Withdrawals awaiting confirmation are also kept there.
+    pub fn set_event_queue_path(&mut self, path: &str) -> Result<(), db_error> {
+        self.pending_subnet_withdrawals
+            .lock()
+            .expect("Unexpected concurrent access to pending withdrawals in the event dispatcher!")
+            .open_db(path)?;
+        for observer in self.registered_observers.iter_mut() {
+            let event_queue = EventQueueDB::open(path)?;
+            let pending = event_queue.count_pending(&observer.endpoint)?;
+            if pending > 0 {
+                info!(
+                    "Event dispatcher: {} undelivered payloads queued for {}",
+                    pending, &observer.endpoint
+                );
+            }
+            observer.event_queue = Some(Arc::new(Mutex::new(event_queue)));
+        }
+        Ok(())
+    }
+
+    /// Sign every payload sent to each registered observer with `signing_key`, so that observers
+    /// can check that payloads came from this node.
+    pub fn set_signing_key(&mut self, signing_key: &Secp256k1PrivateKey) {
+        for observer in self.registered_observers.iter_mut() {
+            observer.signing_key = Some(signing_key.clone());
         }
     }
 
@@ -582,7 +1303,8 @@ impl EventDispatcher {
                             dispatch_matrix[*o_i as usize].insert(i);
                         }
                     }
-                    StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawEvent(_)) => {
+                    StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawEvent(_))
+                    | StacksTransactionEvent::STXEvent(STXEventType::STXWithdrawTipEvent(_)) => {
                         for o_i in &self.stx_observers_lookup {
                             dispatch_matrix[*o_i as usize].insert(i);
                         }
@@ -704,7 +1426,8 @@ impl EventDispatcher {
 
         let (dispatch_matrix, events) = self.create_dispatch_matrix_and_event_vector(&all_receipts);
 
-        if dispatch_matrix.len() > 0 {
+        let streams_enabled = self.grpc_streams.is_enabled() || self.ws_streams.is_enabled();
+        if dispatch_matrix.len() > 0 || streams_enabled {
             let mature_rewards_vec = if let Some(rewards_info) = mature_rewards_info {
                 mature_rewards
                     .iter()
@@ -749,6 +1472,81 @@ impl EventDispatcher {
                     mblock_confirmed_consumed,
                 );
             }
+
+            if streams_enabled {
+                let payload = EventObserver::make_new_block_payload(
+                    events.iter().enumerate().collect(),
+                    block,
+                    metadata,
+                    receipts,
+                    parent_index_hash,
+                    &boot_receipts,
+                    &winner_txid,
+                    &mature_rewards,
+                    parent_burn_block_hash,
+                    parent_burn_block_height,
+                    parent_burn_block_timestamp,
+                    anchored_consumed,
+                    mblock_confirmed_consumed,
+                );
+                self.grpc_streams.announce_block(&payload);
+                self.ws_streams.announce_block(&payload);
+            }
+        }
+
+        self.process_subnet_withdrawals(block, metadata, receipts, parent_index_hash);
+    }
+
+    /// Sends the structured list of withdrawals in a newly processed block to every
+    /// observer subscribed to `subnet_withdrawals`. Observers are notified for every
+    /// block, even ones without withdrawals, so that they can track the withdrawal root.
+    /// If a withdrawal confirmation depth is configured, a block's withdrawals are only sent
+    /// once that many blocks have been built on top of it. If the block orphans blocks whose
+    /// withdrawals were already sent, those observers are sent a `withdrawal_invalidated`
+    /// payload for each of them first.
+    pub fn process_subnet_withdrawals(
+        &self,
+        block: &StacksBlock,
+        metadata: &StacksHeaderInfo,
+        receipts: &Vec<StacksTransactionReceipt>,
+        parent_index_hash: &StacksBlockId,
+    ) {
+        // lazily assemble payload only if we have observers
+        let interested_observers: Vec<_> = self
+            .registered_observers
+            .iter()
+            .enumerate()
+            .filter(|(obs_id, _observer)| {
+                self.subnet_withdrawals_observers_lookup
+                    .contains(&(*obs_id as u16))
+            })
+            .collect();
+        if interested_observers.len() < 1 && !self.grpc_streams.is_enabled() {
+            return;
+        }
+
+        let payload = EventObserver::make_subnet_withdrawals_payload(block, metadata, receipts);
+        let update = self
+            .pending_subnet_withdrawals
+            .lock()
+            .expect("Unexpected concurrent access to pending withdrawals in the event dispatcher!")
+            .push(
+                metadata.stacks_block_height,
+                metadata.index_block_hash(),
+                parent_index_hash.clone(),
+                payload,
+            );
+
+        for payload in update.invalidated.iter() {
+            for (_, observer) in interested_observers.iter() {
+                observer.send_withdrawal_invalidated(payload);
+            }
+        }
+        for payload in update.confirmed.iter() {
+            for (_, observer) in interested_observers.iter() {
+                observer.send_subnet_withdrawals(payload);
+            }
+            self.grpc_streams.announce_subnet_withdrawals(payload);
         }
     }
 
@@ -971,6 +1769,17 @@ impl EventDispatcher {
         info!("Registering event observer at: {}", conf.endpoint);
         let event_observer = EventObserver {
             endpoint: conf.endpoint.clone(),
+            max_event_bytes: conf.max_event_bytes,
+            max_payload_bytes: conf.max_payload_bytes,
+            retry_backoff: Duration::from_millis(
+                conf.retry_backoff_ms.unwrap_or(DEFAULT_RETRY_BACKOFF_MS),
+            ),
+            max_retry_backoff: Duration::from_millis(
+                conf.max_retry_backoff_ms
+                    .unwrap_or(DEFAULT_MAX_RETRY_BACKOFF_MS),
+            ),
+            event_queue: None,
+            signing_key: None,
         };
 
         let observer_index = self.registered_observers.len() as u16;
@@ -1007,6 +1816,10 @@ impl EventDispatcher {
                 EventKeyType::WithdrawalEvent => {
                     self.withdrawal_observers_lookup.insert(observer_index);
                 }
+                EventKeyType::SubnetWithdrawals => {
+                    self.subnet_withdrawals_observers_lookup
+                        .insert(observer_index);
+                }
                 EventKeyType::AssetEvent(event_key) => {
                     match self.assets_observers_lookup.entry(event_key.clone()) {
                         Entry::Occupied(observer_indexes) => {
@@ -1035,3 +1848,335 @@ impl EventDispatcher {
         self.registered_observers.push(event_observer);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        EventObserver, WithdrawalConfirmationQueue, WithdrawalQueueUpdate,
+        DEFAULT_MAX_RETRY_BACKOFF_MS, DEFAULT_RETRY_BACKOFF_MS,
+    };
+    use serde_json::json;
+    use stacks::burnchains::PublicKey;
+    use stacks::types::chainstate::StacksBlockId;
+    use stacks::util::hash::Sha256Sum;
+    use stacks::util::secp256k1::{MessageSignature, Secp256k1PrivateKey, Secp256k1PublicKey};
+    use std::fs;
+    use std::time::Duration;
+
+    fn make_observer(
+        max_event_bytes: Option<u64>,
+        max_payload_bytes: Option<u64>,
+    ) -> EventObserver {
+        EventObserver {
+            endpoint: "localhost:3700".into(),
+            max_event_bytes,
+            max_payload_bytes,
+            retry_backoff: Duration::from_millis(DEFAULT_RETRY_BACKOFF_MS),
+            max_retry_backoff: Duration::from_millis(DEFAULT_MAX_RETRY_BACKOFF_MS),
+            event_queue: None,
+            signing_key: None,
+        }
+    }
+
+    fn make_print_event(event_index: u64, data_len: usize) -> serde_json::Value {
+        json!({
+            "txid": format!("0x{:064x}", event_index),
+            "event_index": event_index,
+            "committed": true,
+            "type": "contract_event",
+            "contract_event": {
+                "topic": "print",
+                "raw_value": format!("0x{}", "00".repeat(data_len)),
+            }
+        })
+    }
+
+    fn payload_size(payload: &serde_json::Value) -> u64 {
+        serde_json::to_vec(payload).unwrap().len() as u64
+    }
+
+    #[test]
+    fn test_payload_limits_unset() {
+        let payload = json!({
+            "block_height": 1,
+            "events": [make_print_event(0, 10_000)],
+        });
+        let observer = make_observer(None, None);
+        let limited = observer.apply_payload_limits(&payload, payload_size(&payload));
+        assert_eq!(limited.as_ref(), &payload);
+    }
+
+    #[test]
+    fn test_payload_limits_max_event_bytes() {
+        let payload = json!({
+            "block_height": 1,
+            "events": [make_print_event(0, 10), make_print_event(1, 10_000), make_print_event(2, 10)],
+        });
+        let observer = make_observer(Some(1_000), None);
+        let limited = observer.apply_payload_limits(&payload, payload_size(&payload));
+
+        let events = limited["events"].as_array().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], payload["events"][0]);
+        assert_eq!(events[2], payload["events"][2]);
+
+        let marker = &events[1];
+        assert_eq!(marker["payload_omitted"], json!(true));
+        assert_eq!(marker["event_index"], json!(1));
+        assert_eq!(marker["txid"], payload["events"][1]["txid"]);
+        assert_eq!(marker["type"], json!("contract_event"));
+        assert_eq!(
+            marker["payload_size"],
+            json!(payload_size(&payload["events"][1]))
+        );
+        assert!(marker.get("contract_event").is_none());
+        assert_eq!(limited["block_height"], json!(1));
+    }
+
+    #[test]
+    fn test_payload_limits_max_payload_bytes() {
+        let payload = json!({
+            "block_height": 1,
+            "events": [
+                make_print_event(0, 3_000),
+                make_print_event(1, 5_000),
+                make_print_event(2, 4_000),
+                make_print_event(3, 10),
+            ],
+        });
+        // only fits after omitting the two largest events
+        let observer = make_observer(None, Some(8_000));
+        let limited = observer.apply_payload_limits(&payload, payload_size(&payload));
+
+        let events = limited["events"].as_array().unwrap();
+        assert_eq!(events[0], payload["events"][0]);
+        assert_eq!(events[1]["payload_omitted"], json!(true));
+        assert_eq!(events[2]["payload_omitted"], json!(true));
+        assert_eq!(events[3], payload["events"][3]);
+        assert!(payload_size(limited.as_ref()) <= 8_000);
+    }
+
+    #[test]
+    fn test_payload_limits_no_events() {
+        let payload = json!(["0x00", "0x01"]);
+        let observer = make_observer(Some(1), Some(1));
+        let limited = observer.apply_payload_limits(&payload, payload_size(&payload));
+        assert_eq!(limited.as_ref(), &payload);
+    }
+
+    fn block_id(fork: u8, height: u64) -> StacksBlockId {
+        let mut bytes = [fork; 32];
+        bytes[24..].copy_from_slice(&height.to_be_bytes());
+        StacksBlockId(bytes)
+    }
+
+    /// Push the block at `height` on `fork`, whose parent is on `parent_fork`, and return the
+    /// heights of the blocks whose withdrawals were released.
+    fn push_block(
+        queue: &mut WithdrawalConfirmationQueue,
+        fork: u8,
+        parent_fork: u8,
+        height: u64,
+    ) -> Vec<(u8, u64)> {
+        queue
+            .push(
+                height,
+                block_id(fork, height),
+                block_id(parent_fork, height - 1),
+                json!({ "fork": fork, "block_height": height }),
+            )
+            .confirmed
+            .iter()
+            .map(|payload| {
+                (
+                    payload["fork"].as_u64().unwrap() as u8,
+                    payload["block_height"].as_u64().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sign_body() {
+        let body = serde_json::to_vec(&json!({ "block_height": 1, "events": [] })).unwrap();
+
+        let mut observer = make_observer(None, None);
+        assert!(observer.sign_body(&body).is_none());
+
+        let signing_key = Secp256k1PrivateKey::new();
+        observer.signing_key = Some(signing_key.clone());
+        let signature = MessageSignature::from_hex(&observer.sign_body(&body).unwrap()).unwrap();
+
+        let body_hash = Sha256Sum::from_data(&body);
+        let public_key = Secp256k1PublicKey::from_private(&signing_key);
+        assert!(public_key.verify(body_hash.as_bytes(), &signature).unwrap());
+        assert_eq!(
+            Secp256k1PublicKey::recover_to_pubkey(body_hash.as_bytes(), &signature).unwrap(),
+            public_key
+        );
+    }
+
+    #[test]
+    fn test_withdrawal_confirmation_depth_zero() {
+        let mut queue = WithdrawalConfirmationQueue::new(0);
+        for height in 1..5 {
+            assert_eq!(push_block(&mut queue, 0, 0, height), vec![(0, height)]);
+        }
+    }
+
+    #[test]
+    fn test_withdrawal_confirmation_depth() {
+        let mut queue = WithdrawalConfirmationQueue::new(2);
+        assert_eq!(push_block(&mut queue, 0, 0, 1), vec![]);
+        assert_eq!(push_block(&mut queue, 0, 0, 2), vec![]);
+        assert_eq!(push_block(&mut queue, 0, 0, 3), vec![(0, 1)]);
+        assert_eq!(push_block(&mut queue, 0, 0, 4), vec![(0, 2)]);
+    }
+
+    #[test]
+    fn test_withdrawal_confirmation_reorg() {
+        let mut queue = WithdrawalConfirmationQueue::new(2);
+        assert_eq!(push_block(&mut queue, 0, 0, 1), vec![]);
+        assert_eq!(push_block(&mut queue, 0, 0, 2), vec![]);
+
+        // fork 1 replaces block 2 of fork 0
+        assert_eq!(push_block(&mut queue, 1, 0, 2), vec![]);
+        assert_eq!(push_block(&mut queue, 1, 1, 3), vec![(0, 1)]);
+        // the orphaned block 2 is never released
+        assert_eq!(push_block(&mut queue, 1, 1, 4), vec![(1, 2)]);
+        assert_eq!(push_block(&mut queue, 1, 1, 5), vec![(1, 3)]);
+
+        // a sibling of the tip replaces it
+        assert_eq!(push_block(&mut queue, 2, 1, 5), vec![]);
+        assert_eq!(push_block(&mut queue, 2, 2, 6), vec![(1, 4)]);
+        assert_eq!(push_block(&mut queue, 2, 2, 7), vec![(2, 5)]);
+    }
+
+    #[test]
+    fn test_withdrawal_confirmation_survives_restart() {
+        let path = "/tmp/stacks-node-tests/event-queue/test_withdrawal_confirmation_survives_restart.sqlite";
+        if fs::metadata(path).is_ok() {
+            fs::remove_file(path).unwrap();
+        }
+
+        {
+            let mut queue = WithdrawalConfirmationQueue::new(2);
+            queue.open_db(path).unwrap();
+            assert_eq!(push_block(&mut queue, 0, 0, 1), vec![]);
+            assert_eq!(push_block(&mut queue, 0, 0, 2), vec![]);
+            assert_eq!(push_block(&mut queue, 0, 0, 3), vec![(0, 1)]);
+        }
+
+        // blocks 2 and 3 were still held back when the node stopped
+        let mut queue = WithdrawalConfirmationQueue::new(2);
+        queue.open_db(path).unwrap();
+        assert_eq!(push_block(&mut queue, 0, 0, 4), vec![(0, 2)]);
+
+        // a reorg after the restart still orphans the block it replaces
+        assert_eq!(push_block(&mut queue, 1, 0, 4), vec![]);
+        assert_eq!(push_block(&mut queue, 1, 1, 5), vec![(0, 3)]);
+        assert_eq!(push_block(&mut queue, 1, 1, 6), vec![(1, 4)]);
+    }
+
+    fn push_block_with_withdrawals(
+        queue: &mut WithdrawalConfirmationQueue,
+        fork: u8,
+        parent_fork: u8,
+        height: u64,
+        txids: &[&str],
+    ) -> WithdrawalQueueUpdate {
+        let withdrawals: Vec<_> = txids
+            .iter()
+            .enumerate()
+            .map(|(withdrawal_id, txid)| {
+                json!({
+                    "txid": txid,
+                    "withdrawal_id": withdrawal_id,
+                    "withdrawal_key": format!("0x{:02x}{:02x}{:02x}", fork, height, withdrawal_id),
+                    "withdrawal_leaf_hash": format!("0x{:02x}{:02x}", fork, height),
+                })
+            })
+            .collect();
+        queue.push(
+            height,
+            block_id(fork, height),
+            block_id(parent_fork, height - 1),
+            json!({
+                "block_hash": format!("0x{:02x}{:02x}", fork, height),
+                "block_height": height,
+                "withdrawal_root": format!("0x{:02x}{:02x}", fork, height),
+                "withdrawals": withdrawals,
+            }),
+        )
+    }
+
+    #[test]
+    fn test_withdrawal_invalidation() {
+        let mut queue = WithdrawalConfirmationQueue::new(0);
+        push_block_with_withdrawals(&mut queue, 0, 0, 1, &[]);
+        push_block_with_withdrawals(&mut queue, 0, 0, 2, &["0xaa", "0xbb", "0xaa"]);
+        let update = push_block_with_withdrawals(&mut queue, 0, 0, 3, &["0xcc"]);
+        assert_eq!(update.confirmed.len(), 1);
+        assert!(update.invalidated.is_empty());
+
+        // fork 1 replaces blocks 2 and 3 of fork 0, re-mining one of the transactions
+        let update = push_block_with_withdrawals(&mut queue, 1, 0, 2, &["0xdd", "0xaa", "0xaa"]);
+        assert_eq!(update.confirmed.len(), 1);
+        assert_eq!(update.invalidated.len(), 2);
+
+        let invalidated = &update.invalidated[0];
+        assert_eq!(invalidated["block_height"], json!(2));
+        assert_eq!(
+            invalidated["index_block_hash"],
+            json!(format!("0x{}", block_id(0, 2)))
+        );
+        assert_eq!(
+            invalidated["new_tip_index_block_hash"],
+            json!(format!("0x{}", block_id(1, 2)))
+        );
+        let withdrawals = invalidated["withdrawals"].as_array().unwrap();
+        assert_eq!(withdrawals.len(), 3);
+        // each of the transaction's withdrawals is matched to the same one on the new fork
+        assert_eq!(withdrawals[0]["txid"], json!("0xaa"));
+        assert_eq!(withdrawals[0]["replacement"]["withdrawal_id"], json!(1));
+        assert_eq!(
+            withdrawals[0]["replacement"]["index_block_hash"],
+            json!(format!("0x{}", block_id(1, 2)))
+        );
+        assert_eq!(withdrawals[1]["txid"], json!("0xbb"));
+        assert_eq!(withdrawals[1]["replacement"], json!(null));
+        assert_eq!(withdrawals[2]["withdrawal_id"], json!(2));
+        assert_eq!(withdrawals[2]["replacement"]["withdrawal_id"], json!(2));
+
+        let invalidated = &update.invalidated[1];
+        assert_eq!(invalidated["block_height"], json!(3));
+        assert_eq!(invalidated["withdrawals"][0]["replacement"], json!(null));
+
+        // the orphaned blocks are only reported once
+        let update = push_block_with_withdrawals(&mut queue, 1, 1, 3, &[]);
+        assert!(update.invalidated.is_empty());
+    }
+
+    #[test]
+    fn test_withdrawal_invalidation_pending() {
+        // blocks that were never released are not reported as invalidated
+        let mut queue = WithdrawalConfirmationQueue::new(2);
+        push_block_with_withdrawals(&mut queue, 0, 0, 1, &["0xaa"]);
+        push_block_with_withdrawals(&mut queue, 0, 0, 2, &["0xbb"]);
+        let update = push_block_with_withdrawals(&mut queue, 0, 0, 3, &[]);
+        assert_eq!(update.confirmed.len(), 1);
+
+        let update = push_block_with_withdrawals(&mut queue, 1, 0, 2, &["0xbb"]);
+        assert!(update.confirmed.is_empty());
+        assert!(update.invalidated.is_empty());
+
+        // a reorg past the released block is reported
+        let update = push_block_with_withdrawals(&mut queue, 2, 0, 1, &[]);
+        assert_eq!(update.invalidated.len(), 1);
+        assert_eq!(update.invalidated[0]["block_height"], json!(1));
+        assert_eq!(
+            update.invalidated[0]["withdrawals"][0]["replacement"],
+            json!(null)
+        );
+    }
+}
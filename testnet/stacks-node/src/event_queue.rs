@@ -0,0 +1,240 @@
+use std::fmt;
+
+use rusqlite::{OpenFlags, Row, ToSql};
+use stacks::types::chainstate::StacksBlockId;
+use stacks::util_lib::db::{
+    ensure_base_directory_exists, query_rows, sqlite_open, tx_begin_immediate, u64_to_sql, DBConn,
+    Error as db_error, FromColumn, FromRow,
+};
+
+/// Schema for the event observer queue.
+const EVENT_QUEUE_SCHEMA: &'static [&'static str] = &[
+    r#"
+    CREATE TABLE IF NOT EXISTS pending_events(
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        endpoint TEXT NOT NULL,     -- the observer this payload is addressed to
+        path TEXT NOT NULL,         -- the path the payload is POSTed to
+        payload BLOB NOT NULL       -- the serialized JSON body
+    );
+    "#,
+    "CREATE INDEX IF NOT EXISTS pending_events_by_endpoint ON pending_events(endpoint, id);",
+    r#"
+    CREATE TABLE IF NOT EXISTS subnet_withdrawals(
+        index_block_hash TEXT PRIMARY KEY,
+        block_height INTEGER NOT NULL,
+        parent_index_block_hash TEXT NOT NULL,
+        payload TEXT NOT NULL,      -- the block's `subnet_withdrawals` JSON payload
+        released INTEGER NOT NULL   -- 1 if the payload has been sent to observers
+    );
+    "#,
+];
+
+/// A payload that has been queued for an observer, but not yet acknowledged by it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingEvent {
+    pub id: i64,
+    pub endpoint: String,
+    pub path: String,
+    pub payload: Vec<u8>,
+}
+
+impl FromRow<PendingEvent> for PendingEvent {
+    fn from_row<'a>(row: &'a Row) -> Result<PendingEvent, db_error> {
+        Ok(PendingEvent {
+            id: row.get_unwrap("id"),
+            endpoint: row.get_unwrap("endpoint"),
+            path: row.get_unwrap("path"),
+            payload: row.get_unwrap("payload"),
+        })
+    }
+}
+
+/// A block's `subnet_withdrawals` payload, as held by the withdrawal confirmation queue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedSubnetWithdrawals {
+    pub index_block_hash: StacksBlockId,
+    pub block_height: u64,
+    pub parent_index_block_hash: StacksBlockId,
+    pub payload: String,
+    pub released: bool,
+}
+
+impl FromRow<QueuedSubnetWithdrawals> for QueuedSubnetWithdrawals {
+    fn from_row<'a>(row: &'a Row) -> Result<QueuedSubnetWithdrawals, db_error> {
+        let released: i64 = row.get_unwrap("released");
+        Ok(QueuedSubnetWithdrawals {
+            index_block_hash: row.get_unwrap("index_block_hash"),
+            block_height: u64::from_column(row, "block_height")?,
+            parent_index_block_hash: row.get_unwrap("parent_index_block_hash"),
+            payload: row.get_unwrap("payload"),
+            released: released != 0,
+        })
+    }
+}
+
+/// On-disk queue of payloads bound for event observers.
+///
+/// Each payload is written to the queue before it is sent, and only removed once the observer has
+/// acknowledged it.  Payloads that were not acknowledged before the node stopped are sent again
+/// after a restart, so observers receive every payload at least once and in the order they were
+/// queued.
+pub struct EventQueueDB {
+    conn: DBConn,
+}
+
+impl fmt::Debug for EventQueueDB {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EventQueueDB")
+    }
+}
+
+impl EventQueueDB {
+    /// Open the queue at `path`, creating it if it does not exist.
+    pub fn open(path: &str) -> Result<EventQueueDB, db_error> {
+        ensure_base_directory_exists(path)?;
+        let open_flags = OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE;
+        let mut conn = sqlite_open(path, open_flags, false)?;
+
+        let tx = tx_begin_immediate(&mut conn)?;
+        for cmd in EVENT_QUEUE_SCHEMA {
+            tx.execute_batch(cmd)?;
+        }
+        tx.commit()?;
+
+        Ok(EventQueueDB { conn })
+    }
+
+    /// Queue a payload for `endpoint`.  Returns the payload's position in the queue.
+    pub fn push(&self, endpoint: &str, path: &str, payload: &[u8]) -> Result<i64, db_error> {
+        let args: &[&dyn ToSql] = &[&endpoint, &path, &payload];
+        self.conn.execute(
+            "INSERT INTO pending_events (endpoint, path, payload) VALUES (?1, ?2, ?3)",
+            args,
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Get the payloads queued for `endpoint` at or before position `last_id`, oldest first.
+    pub fn get_pending(&self, endpoint: &str, last_id: i64) -> Result<Vec<PendingEvent>, db_error> {
+        let args: &[&dyn ToSql] = &[&endpoint, &last_id];
+        query_rows(
+            &self.conn,
+            "SELECT * FROM pending_events WHERE endpoint = ?1 AND id <= ?2 ORDER BY id ASC",
+            args,
+        )
+    }
+
+    /// Count the payloads queued for `endpoint`.
+    pub fn count_pending(&self, endpoint: &str) -> Result<u64, db_error> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM pending_events WHERE endpoint = ?1",
+            &[&endpoint],
+            |row| row.get(0),
+        )?;
+        Ok(count as u64)
+    }
+
+    /// Remove a payload once its observer has acknowledged it.
+    pub fn remove(&self, id: i64) -> Result<(), db_error> {
+        self.conn
+            .execute("DELETE FROM pending_events WHERE id = ?1", &[&id])?;
+        Ok(())
+    }
+
+    /// Get the contents of the withdrawal confirmation queue, oldest block first.
+    pub fn get_subnet_withdrawals(&self) -> Result<Vec<QueuedSubnetWithdrawals>, db_error> {
+        query_rows(
+            &self.conn,
+            "SELECT * FROM subnet_withdrawals ORDER BY block_height ASC",
+            rusqlite::NO_PARAMS,
+        )
+    }
+
+    /// Replace the contents of the withdrawal confirmation queue.
+    pub fn set_subnet_withdrawals(
+        &mut self,
+        entries: &[QueuedSubnetWithdrawals],
+    ) -> Result<(), db_error> {
+        let tx = tx_begin_immediate(&mut self.conn)?;
+        tx.execute("DELETE FROM subnet_withdrawals", rusqlite::NO_PARAMS)?;
+        for entry in entries.iter() {
+            let args: &[&dyn ToSql] = &[
+                &entry.index_block_hash,
+                &u64_to_sql(entry.block_height)?,
+                &entry.parent_index_block_hash,
+                &entry.payload,
+                &(entry.released as i64),
+            ];
+            tx.execute(
+                "INSERT INTO subnet_withdrawals
+                 (index_block_hash, block_height, parent_index_block_hash, payload, released)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                args,
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::EventQueueDB;
+    use std::fs;
+
+    fn open_test_queue(name: &str) -> EventQueueDB {
+        let path = format!("/tmp/stacks-node-tests/event-queue/{}.sqlite", name);
+        if fs::metadata(&path).is_ok() {
+            fs::remove_file(&path).unwrap();
+        }
+        EventQueueDB::open(&path).unwrap()
+    }
+
+    #[test]
+    fn test_event_queue_order_per_endpoint() {
+        let queue = open_test_queue("test_event_queue_order_per_endpoint");
+        let first = queue.push("observer-a:3700", "new_block", b"1").unwrap();
+        let other = queue.push("observer-b:3700", "new_block", b"2").unwrap();
+        let second = queue
+            .push("observer-a:3700", "new_burn_block", b"3")
+            .unwrap();
+        let third = queue.push("observer-a:3700", "new_block", b"4").unwrap();
+
+        let pending = queue.get_pending("observer-a:3700", second).unwrap();
+        assert_eq!(
+            pending.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![first, second]
+        );
+        assert_eq!(pending[1].path, "new_burn_block");
+        assert_eq!(pending[1].payload, b"3".to_vec());
+
+        assert_eq!(queue.count_pending("observer-a:3700").unwrap(), 3);
+        assert_eq!(queue.count_pending("observer-b:3700").unwrap(), 1);
+
+        queue.remove(first).unwrap();
+        queue.remove(other).unwrap();
+        let pending = queue.get_pending("observer-a:3700", third).unwrap();
+        assert_eq!(
+            pending.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![second, third]
+        );
+        assert_eq!(queue.count_pending("observer-b:3700").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_event_queue_survives_reopen() {
+        let path = "/tmp/stacks-node-tests/event-queue/test_event_queue_survives_reopen.sqlite";
+        if fs::metadata(path).is_ok() {
+            fs::remove_file(path).unwrap();
+        }
+        let id = {
+            let queue = EventQueueDB::open(path).unwrap();
+            queue.push("observer-a:3700", "new_block", b"1").unwrap()
+        };
+
+        let queue = EventQueueDB::open(path).unwrap();
+        let pending = queue.get_pending("observer-a:3700", id).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].payload, b"1".to_vec());
+    }
+}
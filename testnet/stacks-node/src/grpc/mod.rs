@@ -0,0 +1,66 @@
+#![allow(unused_variables)]
+
+//! Optional gRPC server which streams processed subnet blocks, transaction receipts and
+//! withdrawals to clients.  The server is only available if the node is built with the `grpc`
+//! feature; otherwise, `node.grpc_bind` is ignored.
+
+#[cfg(feature = "grpc")]
+mod server;
+
+/// Handle the event dispatcher uses to publish blocks and withdrawals to gRPC clients.  A
+/// disabled handle drops everything it is given.
+#[derive(Clone)]
+pub struct GrpcEventStreams {
+    #[cfg(feature = "grpc")]
+    senders: Option<server::StreamSenders>,
+}
+
+impl GrpcEventStreams {
+    pub fn disabled() -> GrpcEventStreams {
+        GrpcEventStreams {
+            #[cfg(feature = "grpc")]
+            senders: None,
+        }
+    }
+
+    #[allow(unreachable_code)]
+    pub fn is_enabled(&self) -> bool {
+        #[cfg(feature = "grpc")]
+        return self.senders.is_some();
+        false
+    }
+
+    /// Publish a processed block, given its `new_block` event observer payload
+    pub fn announce_block(&self, payload: &serde_json::Value) {
+        #[cfg(feature = "grpc")]
+        if let Some(senders) = self.senders.as_ref() {
+            senders.send_block(payload);
+        }
+    }
+
+    /// Publish a confirmed block's withdrawals, given its `subnet_withdrawals` event observer
+    /// payload
+    pub fn announce_subnet_withdrawals(&self, payload: &serde_json::Value) {
+        #[cfg(feature = "grpc")]
+        if let Some(senders) = self.senders.as_ref() {
+            senders.send_withdrawals(payload);
+        }
+    }
+}
+
+/// Start serving the gRPC streams on `bind_address`, in a separate thread.
+#[allow(unreachable_code)]
+pub fn start_serving_grpc_streams(bind_address: String) -> GrpcEventStreams {
+    #[cfg(feature = "grpc")]
+    {
+        info!("Start serving gRPC event streams");
+        return GrpcEventStreams {
+            senders: Some(server::start_serving_grpc_streams(bind_address)),
+        };
+    }
+    warn!(
+        "gRPC event streams requested on {}, but this node was built without the `grpc` feature",
+        &bind_address
+    );
+    GrpcEventStreams::disabled()
+}
@@ -0,0 +1,336 @@
+use std::net::SocketAddr;
+use std::thread;
+
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+
+pub mod proto {
+    tonic::include_proto!("subnet.events.v1");
+}
+
+use self::proto::subnet_events_server::{SubnetEvents, SubnetEventsServer};
+
+/// How many messages a client may fall behind by before its stream is closed
+const STREAM_BUFFER_SIZE: usize = 256;
+
+#[derive(Clone)]
+pub struct StreamSenders {
+    blocks: broadcast::Sender<proto::Block>,
+    withdrawals: broadcast::Sender<proto::WithdrawalBatch>,
+}
+
+impl StreamSenders {
+    fn new() -> StreamSenders {
+        let (blocks, _) = broadcast::channel(STREAM_BUFFER_SIZE);
+        let (withdrawals, _) = broadcast::channel(STREAM_BUFFER_SIZE);
+        StreamSenders {
+            blocks,
+            withdrawals,
+        }
+    }
+
+    pub fn send_block(&self, payload: &serde_json::Value) {
+        if self.blocks.receiver_count() > 0 {
+            // fails only if every client disconnected in the meantime
+            let _ = self.blocks.send(make_block(payload));
+        }
+    }
+
+    pub fn send_withdrawals(&self, payload: &serde_json::Value) {
+        if self.withdrawals.receiver_count() > 0 {
+            let _ = self.withdrawals.send(make_withdrawal_batch(payload));
+        }
+    }
+}
+
+fn json_string(value: &serde_json::Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn json_u64(value: &serde_json::Value, key: &str) -> u64 {
+    value.get(key).and_then(|v| v.as_u64()).unwrap_or_default()
+}
+
+fn json_array<'a>(value: &'a serde_json::Value, key: &str) -> &'a [serde_json::Value] {
+    value
+        .get(key)
+        .and_then(|v| v.as_array())
+        .map(|v| v.as_slice())
+        .unwrap_or_default()
+}
+
+fn make_execution_cost(value: &serde_json::Value, key: &str) -> Option<proto::ExecutionCost> {
+    let cost = value.get(key)?;
+    Some(proto::ExecutionCost {
+        write_length: json_u64(cost, "write_length"),
+        write_count: json_u64(cost, "write_count"),
+        read_length: json_u64(cost, "read_length"),
+        read_count: json_u64(cost, "read_count"),
+        runtime: json_u64(cost, "runtime"),
+    })
+}
+
+/// Convert a `new_block` event observer payload to a `Block` message
+fn make_block(payload: &serde_json::Value) -> proto::Block {
+    let receipts = json_array(payload, "transactions")
+        .iter()
+        .map(|tx| proto::TransactionReceipt {
+            txid: json_string(tx, "txid"),
+            tx_index: json_u64(tx, "tx_index") as u32,
+            status: json_string(tx, "status"),
+            raw_result: json_string(tx, "raw_result"),
+            raw_tx: json_string(tx, "raw_tx"),
+            execution_cost: make_execution_cost(tx, "execution_cost"),
+        })
+        .collect();
+
+    let events = json_array(payload, "events")
+        .iter()
+        .map(|event| proto::TransactionEvent {
+            txid: json_string(event, "txid"),
+            event_index: json_u64(event, "event_index"),
+            committed: event
+                .get("committed")
+                .and_then(|v| v.as_bool())
+                .unwrap_or_default(),
+            r#type: json_string(event, "type"),
+            json: event.to_string(),
+        })
+        .collect();
+
+    proto::Block {
+        block_hash: json_string(payload, "block_hash"),
+        block_height: json_u64(payload, "block_height"),
+        index_block_hash: json_string(payload, "index_block_hash"),
+        parent_block_hash: json_string(payload, "parent_block_hash"),
+        parent_index_block_hash: json_string(payload, "parent_index_block_hash"),
+        burn_block_hash: json_string(payload, "burn_block_hash"),
+        burn_block_height: json_u64(payload, "burn_block_height"),
+        burn_block_time: json_u64(payload, "burn_block_time"),
+        miner_txid: json_string(payload, "miner_txid"),
+        anchored_cost: make_execution_cost(payload, "anchored_cost"),
+        confirmed_microblocks_cost: make_execution_cost(payload, "confirmed_microblocks_cost"),
+        receipts,
+        events,
+        total_cost: make_execution_cost(payload, "total_cost"),
+    }
+}
+
+/// Convert a `subnet_withdrawals` event observer payload to a `WithdrawalBatch` message
+fn make_withdrawal_batch(payload: &serde_json::Value) -> proto::WithdrawalBatch {
+    let withdrawals = json_array(payload, "withdrawals")
+        .iter()
+        .map(|withdrawal| proto::Withdrawal {
+            txid: json_string(withdrawal, "txid"),
+            r#type: json_string(withdrawal, "type"),
+            withdrawal_id: json_u64(withdrawal, "withdrawal_id"),
+            sender: json_string(withdrawal, "sender"),
+            asset_identifier: json_string(withdrawal, "asset_identifier"),
+            amount: json_string(withdrawal, "amount"),
+            nft_id: json_string(withdrawal, "nft_id"),
+            withdrawal_key: json_string(withdrawal, "withdrawal_key"),
+            withdrawal_leaf_hash: json_string(withdrawal, "withdrawal_leaf_hash"),
+        })
+        .collect();
+
+    proto::WithdrawalBatch {
+        block_hash: json_string(payload, "block_hash"),
+        block_height: json_u64(payload, "block_height"),
+        index_block_hash: json_string(payload, "index_block_hash"),
+        burn_block_hash: json_string(payload, "burn_block_hash"),
+        burn_block_height: json_u64(payload, "burn_block_height"),
+        withdrawal_root: json_string(payload, "withdrawal_root"),
+        withdrawals,
+    }
+}
+
+/// Forward broadcast messages to a single client's stream.  A client that falls more than
+/// `STREAM_BUFFER_SIZE` messages behind is sent `DATA_LOSS` and its stream is closed, so that it
+/// never silently misses a message.
+fn forward_to_client<T: Clone + Send + 'static>(
+    mut receiver: broadcast::Receiver<T>,
+) -> ReceiverStream<Result<T, Status>> {
+    let (client_sender, client_receiver) = mpsc::channel(STREAM_BUFFER_SIZE);
+    tokio::spawn(async move {
+        loop {
+            let message = match receiver.recv().await {
+                Ok(message) => Ok(message),
+                Err(broadcast::error::RecvError::Lagged(missed)) => Err(Status::data_loss(
+                    format!("client fell behind by {} messages", missed),
+                )),
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+            let lagged = message.is_err();
+            if client_sender.send(message).await.is_err() || lagged {
+                return;
+            }
+        }
+    });
+    ReceiverStream::new(client_receiver)
+}
+
+struct SubnetEventsService {
+    senders: StreamSenders,
+}
+
+#[tonic::async_trait]
+impl SubnetEvents for SubnetEventsService {
+    type StreamBlocksStream = ReceiverStream<Result<proto::Block, Status>>;
+    type StreamWithdrawalsStream = ReceiverStream<Result<proto::WithdrawalBatch, Status>>;
+
+    async fn stream_blocks(
+        &self,
+        _request: Request<proto::StreamBlocksRequest>,
+    ) -> Result<Response<Self::StreamBlocksStream>, Status> {
+        Ok(Response::new(forward_to_client(
+            self.senders.blocks.subscribe(),
+        )))
+    }
+
+    async fn stream_withdrawals(
+        &self,
+        _request: Request<proto::StreamWithdrawalsRequest>,
+    ) -> Result<Response<Self::StreamWithdrawalsStream>, Status> {
+        Ok(Response::new(forward_to_client(
+            self.senders.withdrawals.subscribe(),
+        )))
+    }
+}
+
+/// Spawn a thread serving the gRPC streams on `bind_address`, and return the senders that feed
+/// them.
+pub fn start_serving_grpc_streams(bind_address: String) -> StreamSenders {
+    let addr: SocketAddr = bind_address
+        .parse()
+        .expect("gRPC event streams: unable to parse bind address");
+    let senders = StreamSenders::new();
+    let service = SubnetEventsService {
+        senders: senders.clone(),
+    };
+
+    thread::Builder::new()
+        .name("grpc".to_string())
+        .spawn(move || {
+            let rt = tokio::runtime::Runtime::new().expect("Failed to initialize tokio");
+            info!("gRPC event streams: server listening on {}", &addr);
+            rt.block_on(
+                Server::builder()
+                    .add_service(SubnetEventsServer::new(service))
+                    .serve(addr),
+            )
+            .expect("gRPC event streams: server failed");
+        })
+        .expect("FATAL: failed to start gRPC thread");
+
+    senders
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_make_block() {
+        let payload = json!({
+            "block_hash": "0x01",
+            "block_height": 5,
+            "index_block_hash": "0x02",
+            "parent_block_hash": "0x03",
+            "parent_index_block_hash": "0x04",
+            "burn_block_hash": "0x05",
+            "burn_block_height": 105,
+            "burn_block_time": 1000,
+            "miner_txid": "0x06",
+            "anchored_cost": {
+                "write_length": 1, "write_count": 2, "read_length": 3, "read_count": 4, "runtime": 5
+            },
+            "confirmed_microblocks_cost": {
+                "write_length": 0, "write_count": 0, "read_length": 0, "read_count": 0, "runtime": 0
+            },
+            "total_cost": {
+                "write_length": 1, "write_count": 2, "read_length": 3, "read_count": 4, "runtime": 5
+            },
+            "transactions": [{
+                "txid": "0x07",
+                "tx_index": 0,
+                "status": "success",
+                "raw_result": "0x0703",
+                "raw_tx": "0x00",
+                "execution_cost": {
+                    "write_length": 1, "write_count": 2, "read_length": 3, "read_count": 4, "runtime": 5
+                },
+            }],
+            "events": [{
+                "txid": "0x07",
+                "event_index": 0,
+                "committed": true,
+                "type": "stx_transfer_event",
+                "stx_transfer_event": { "sender": "a", "recipient": "b", "amount": "1" },
+            }],
+        });
+
+        let block = make_block(&payload);
+        assert_eq!(block.block_hash, "0x01");
+        assert_eq!(block.block_height, 5);
+        assert_eq!(block.burn_block_height, 105);
+        assert_eq!(block.anchored_cost.as_ref().unwrap().runtime, 5);
+        assert_eq!(block.total_cost.as_ref().unwrap().runtime, 5);
+        assert_eq!(block.receipts.len(), 1);
+        assert_eq!(block.receipts[0].status, "success");
+        assert_eq!(
+            block.receipts[0]
+                .execution_cost
+                .as_ref()
+                .unwrap()
+                .read_count,
+            4
+        );
+        assert_eq!(block.events.len(), 1);
+        assert_eq!(block.events[0].r#type, "stx_transfer_event");
+        assert!(block.events[0].committed);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&block.events[0].json).unwrap(),
+            payload["events"][0]
+        );
+    }
+
+    #[test]
+    fn test_make_withdrawal_batch() {
+        let payload = json!({
+            "block_hash": "0x01",
+            "block_height": 5,
+            "index_block_hash": "0x02",
+            "burn_block_hash": "0x03",
+            "burn_block_height": 105,
+            "withdrawal_root": "0x04",
+            "withdrawals": [{
+                "txid": "0x05",
+                "type": "stx",
+                "withdrawal_id": 0,
+                "leaf_index": 0,
+                "sender": "ST000000000000000000002AMW42H",
+                "asset_identifier": null,
+                "amount": "100",
+                "nft_id": null,
+                "withdrawal_key": "0x06",
+                "withdrawal_leaf_hash": "0x07",
+            }],
+        });
+
+        let batch = make_withdrawal_batch(&payload);
+        assert_eq!(batch.withdrawal_root, "0x04");
+        assert_eq!(batch.burn_block_height, 105);
+        assert_eq!(batch.withdrawals.len(), 1);
+        assert_eq!(batch.withdrawals[0].r#type, "stx");
+        assert_eq!(batch.withdrawals[0].amount, "100");
+        assert_eq!(batch.withdrawals[0].asset_identifier, "");
+        assert_eq!(batch.withdrawals[0].nft_id, "");
+    }
+}
@@ -59,6 +59,16 @@ impl Keychain {
         Keychain::new(vec![secret_key], 1, AddressHashMode::SerializeP2PKH)
     }
 
+    /// Replace the keychain's signing key with `secret_key`, as a single-sig P2PKH signer.  The
+    /// microblock and VRF keys generated so far are kept.
+    pub fn set_signing_key(&mut self, secret_key: StacksPrivateKey) {
+        let rotated = Keychain::single_signer(secret_key);
+        self.secret_keys = rotated.secret_keys;
+        self.threshold = rotated.threshold;
+        self.hash_mode = rotated.hash_mode;
+        self.hashed_secret_state = rotated.hashed_secret_state;
+    }
+
     pub fn default(seed: Vec<u8>) -> Keychain {
         let mut re_hashed_seed = seed;
         let secret_key = loop {
@@ -16,21 +16,33 @@ extern crate stacks;
 #[macro_use(o, slog_log, slog_trace, slog_debug, slog_info, slog_warn, slog_error)]
 extern crate slog;
 
+use stacks::chainstate::stacks::db::StacksChainState;
+use stacks::chainstate::stacks::StacksPrivateKey;
+use stacks::core::mempool::MemPoolDB;
+use stacks::net::db::PeerDB;
 pub use stacks::util;
 use stacks::util::hash::hex_bytes;
+use stacks::util_lib::db::Error as db_error;
 
 pub mod monitoring;
 
 pub mod burnchains;
 pub mod config;
+pub mod ctl;
+pub mod devnet;
 pub mod event_dispatcher;
+pub mod event_queue;
 pub mod genesis_data;
+pub mod grpc;
 pub mod keychain;
 pub mod neon_node;
 pub mod node;
 pub mod operations;
+pub mod replay;
 pub mod run_loop;
+pub mod shadow;
 pub mod syncctl;
+pub mod websocket;
 
 pub use self::burnchains::{BurnchainController, BurnchainTip};
 pub use self::config::{Config, ConfigFile};
@@ -87,6 +99,7 @@ fn main() {
         );
     }
 
+    let mut check_migrations = false;
     let config_file = match subcommand.as_str() {
         "mocknet" => {
             args.finish().unwrap();
@@ -96,8 +109,26 @@ fn main() {
             args.finish().unwrap();
             ConfigFile::mainnet()
         }
+        "devnet" => {
+            let default_options = devnet::DevnetOptions::default();
+            let options = devnet::DevnetOptions {
+                working_dir: args.opt_value_from_str("--working-dir").unwrap(),
+                block_time_ms: args
+                    .opt_value_from_str("--block-time")
+                    .expect("Failed to parse --block-time argument")
+                    .unwrap_or(default_options.block_time_ms),
+                num_accounts: args
+                    .opt_value_from_str("--accounts")
+                    .expect("Failed to parse --accounts argument")
+                    .unwrap_or(default_options.num_accounts),
+            };
+            args.finish().unwrap();
+            devnet::run(options);
+            return;
+        }
         "start" => {
             let config_path: String = args.value_from_str("--config").unwrap();
+            check_migrations = args.contains("--check-migrations");
             args.finish().unwrap();
             info!("Loading config at path {}", config_path);
             ConfigFile::from_path(&config_path)
@@ -106,6 +137,65 @@ fn main() {
             println!("{}", &version());
             return;
         }
+        "ctl" => {
+            let node_url: String = args
+                .opt_value_from_str("--node")
+                .unwrap()
+                .unwrap_or(ctl::DEFAULT_CTL_NODE_URL.to_string());
+            let auth_token: Option<String> = args.opt_value_from_str("--auth-token").unwrap();
+            let admin_key: Option<String> = args.opt_value_from_str("--admin-key").unwrap();
+            let auth = match (auth_token, admin_key) {
+                (Some(token), None) => Some(ctl::CtlAuth::Token(token)),
+                (None, Some(key)) => match StacksPrivateKey::from_hex(&key) {
+                    Ok(key) => Some(ctl::CtlAuth::Key(key)),
+                    Err(e) => {
+                        eprintln!("Invalid --admin-key: {}", e);
+                        process::exit(1);
+                    }
+                },
+                (None, None) => None,
+                (Some(_), Some(_)) => {
+                    eprintln!("Pass at most one of --auth-token and --admin-key");
+                    process::exit(1);
+                }
+            };
+            let free_args = args.free().unwrap();
+            let command = match ctl::CtlCommand::from_args(&free_args) {
+                Some(command) => command,
+                None => {
+                    print_help();
+                    process::exit(1);
+                }
+            };
+            match ctl::CtlClient::new(&node_url, auth).run(&command) {
+                Ok(output) => println!("{}", output),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
+        "replay-events" => {
+            let config_path: String = args.value_from_str("--config").unwrap();
+            let from_height: u64 = args
+                .value_from_str("--from-height")
+                .expect("Failed to parse --from-height argument");
+            let to_height: u64 = args
+                .value_from_str("--to-height")
+                .expect("Failed to parse --to-height argument");
+            args.finish().unwrap();
+            info!("Loading config at path {}", config_path);
+            let conf = Config::from_config_file(ConfigFile::from_path(&config_path));
+            match replay::replay_events(&conf, from_height, to_height) {
+                Ok(num_replayed) => println!("Replayed the events of {} blocks", num_replayed),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
         "key-for-seed" => {
             let seed = {
                 let config_path: Option<String> = args.opt_value_from_str("--config").unwrap();
@@ -143,10 +233,48 @@ fn main() {
     debug!("burnchain configuration {:?}", &conf.burnchain);
     debug!("connection configuration {:?}", &conf.connection_options);
 
+    if check_migrations {
+        let ok = check_schema_migrations(&conf);
+        process::exit(if ok { 0 } else { 1 });
+    }
+
     let mut run_loop = neon::RunLoop::new(conf);
     run_loop.start(None, mine_start.unwrap_or(0));
 }
 
+/// Report the schema migrations that the node would apply to each of its databases on startup,
+/// without applying them.  Returns false if any database cannot be migrated.
+fn check_schema_migrations(conf: &Config) -> bool {
+    let chainstate_path = conf.get_chainstate_path_str();
+    let peer_db_path = conf.get_peer_db_file_path();
+    let checks = vec![
+        (
+            "chainstate",
+            StacksChainState::check_schema_migrations(&chainstate_path),
+        ),
+        (
+            "mempool",
+            MemPoolDB::check_schema_migrations(&chainstate_path),
+        ),
+        ("peer", PeerDB::check_schema_migrations(&peer_db_path)),
+    ];
+
+    let mut ok = true;
+    for (db_name, check) in checks.into_iter() {
+        match check {
+            Ok(status) => println!("{}", &status),
+            Err(db_error::NoDBError) => {
+                println!("{}: does not exist yet, and will be instantiated", db_name)
+            }
+            Err(e) => {
+                println!("{}: cannot be migrated: {}", db_name, &e);
+                ok = false;
+            }
+        }
+    }
+    ok
+}
+
 fn version() -> String {
     stacks::version_string(
         "stacks-node",
@@ -173,6 +301,15 @@ mainnet\t\tStart a node that will join and stream blocks from the public mainnet
 
 mocknet\t\tStart a node based on a fast local setup emulating a burnchain. Ideal for smart contract development. 
 
+devnet\t\tStart a subnet miner against an in-process mock L1 chain, with prefunded accounts. Prints the
+\t\taccounts' addresses and secret keys on startup.
+\t\tArguments:
+\t\t  --working-dir: directory for the node's chainstate (default: a fresh directory under /tmp).
+\t\t  --block-time: milliseconds between mock L1 blocks (default: 10000).
+\t\t  --accounts: number of prefunded accounts (default: 10).
+\t\tExample:
+\t\t  stacks-node devnet --block-time=5000
+
 helium\t\tStart a node based on a local setup relying on a local instance of bitcoind.
 \t\tThe following bitcoin.conf is expected:
 \t\t  chain=regtest
@@ -187,11 +324,40 @@ testnet\t\tStart a node that will join and stream blocks from the public testnet
 start\t\tStart a node with a config of your own. Can be used for joining a network, starting new chain, etc.
 \t\tArguments:
 \t\t  --config: path of the config (such as https://github.com/blockstack/stacks-blockchain/blob/master/testnet/stacks-node/conf/testnet-follower-conf.toml).
+\t\t  --check-migrations: report the schema migrations that would be applied to the node's databases, and exit
+\t\t    without applying them. Exits with a non-zero status if any database cannot be migrated.
 \t\tExample:
 \t\t  stacks-node start --config=/path/to/config.toml
 
 version\t\tDisplay information about the current version and our release cycle.
 
+ctl\t\tInspect and manage a running node through its RPC interface.
+\t\tCommands:
+\t\t  tip info: show the node's Stacks chain tip.
+\t\t  peers list: list the peers the node is connected to.
+\t\t  peers ban <ip:port|ip|ip/prefix> [<seconds>] [<reason>]: ban a peer's address, or a range of
+\t\t    addresses (default: 1 day), and close the connections of the peers in it.
+\t\t  mempool stats: show the number of mempool transactions, and their fees.
+\t\t  mempool purge <txid> [<reason>]: evict a transaction from the mempool.
+\t\t  observers list: list the event observers the node sends events to.
+\t\tArguments:
+\t\t  --node: RPC URL of the node (default: http://127.0.0.1:20443). The peers, purge, and observers
+\t\t    commands use admin endpoints, which are only served on the node's admin_bind address.
+\t\t  --auth-token: the node's admin auth token, for the peers, purge, and observers commands.
+\t\t  --admin-key: hex private key of an admin credential, to sign admin requests with instead.
+\t\tExample:
+\t\t  stacks-node ctl --node=http://127.0.0.1:20443 --auth-token=<token> peers list
+
+replay-events\tRe-execute a range of the canonical chain's blocks from a stopped node's chainstate, and send
+\t\tthe resulting block and withdrawal events to the config's event observers, in order. Use this to
+\t\trebuild an indexer's database without re-syncing the node.
+\t\tArguments:
+\t\t  --config: path of the node's config.
+\t\t  --from-height: Stacks block height of the first block to replay (at least 1).
+\t\t  --to-height: Stacks block height of the last block to replay.
+\t\tExample:
+\t\t  stacks-node replay-events --config=/path/to/config.toml --from-height=1 --to-height=1000
+
 key-for-seed\tOutput the associated secret key for a burnchain signer created with a given seed.
 \t\tCan be passed a config file for the seed via the `--config=<file>` option *or* by supplying the hex seed on
 \t\tthe command line directly.
@@ -5,11 +5,16 @@ use std::collections::{HashSet, VecDeque};
 use std::convert::TryFrom;
 use std::default::Default;
 use std::net::SocketAddr;
-use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::mpsc::{
+    channel, sync_channel, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError,
+};
 use std::sync::{atomic::Ordering, Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{thread, thread::JoinHandle};
 
+use crate::burnchains::miner_key_rotation::MinerKeyRotator;
+use crate::burnchains::signer_coordinator::SignerCoordinator;
+use crate::burnchains::withdrawal_batcher::WithdrawalBatcher;
 use crate::burnchains::BurnchainController;
 use stacks::burnchains::BurnchainParameters;
 use stacks::chainstate::burn::db::sortdb::SortitionDB;
@@ -42,13 +47,13 @@ use stacks::net::{
     p2p::PeerNetwork,
     relay::Relayer,
     rpc::RPCHandlerArgs,
-    Error as NetError, NetworkResult, PeerAddress, ServiceFlags,
+    AdminObserverInfo, Error as NetError, NetworkResult, PeerAddress, ServiceFlags,
 };
 use stacks::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash, SortitionId, StacksAddress};
 use stacks::util::get_epoch_time_ms;
 use stacks::util::get_epoch_time_secs;
 use stacks::util::hash::{to_hex, Hash160, Sha256Sum};
-use stacks::util::secp256k1::Secp256k1PrivateKey;
+use stacks::util::secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey};
 use stacks::util::vrf::VRFProof;
 use stacks::util_lib::strings::{UrlString, VecDisplay};
 use stacks::vm::costs::ExecutionCost;
@@ -64,10 +69,10 @@ use stacks::monitoring;
 
 pub const RELAYER_MAX_BUFFER: usize = 100;
 
-struct AssembledAnchorBlock {
-    parent_consensus_hash: ConsensusHash,
+pub(crate) struct AssembledAnchorBlock {
+    pub(crate) parent_consensus_hash: ConsensusHash,
     my_burn_hash: BurnchainHeaderHash,
-    anchored_block: StacksBlock,
+    pub(crate) anchored_block: StacksBlock,
     attempt: u64,
 }
 
@@ -86,6 +91,9 @@ enum RelayerDirective {
     HandleNetResult(NetworkResult),
     ProcessTenure(ConsensusHash, BurnchainHeaderHash, BlockHeaderHash),
     RunTenure, // (vrf key, chain tip, time of issuance in ms)
+    /// run a tenure for the L1 block in place of its multi-miner leader, if the leader has not
+    /// proposed a block for it and the L1 block is still the canonical tip
+    RunFallbackTenure(BurnchainHeaderHash),
     RunMicroblockTenure(BlockSnapshot, u128), // time of issuance in ms
     Exit,
 }
@@ -103,6 +111,86 @@ pub struct StacksNode {
     pub relayer_thread_handle: JoinHandle<()>,
     /// Lock used for the timer thread before issuing a tenure directive
     is_tenure_timer_running: Arc<Mutex<bool>>,
+    /// Issues `RunFallbackTenure` directives, if this node is a multi-miner miner
+    fallback_timer: Option<FallbackTimer>,
+}
+
+/// Sends the relayer a `RunFallbackTenure` directive for an L1 block once the multi-miner
+/// leader's time to propose a block for it has passed.  A single worker thread waits for one L1
+/// block at a time: scheduling a newer L1 block cancels the wait for the previous one, whose
+/// fallback tenure could only be stale.  Dropping the timer cancels any wait, and joins the
+/// worker.
+struct FallbackTimer {
+    schedule_send: Option<Sender<(BurnchainHeaderHash, Instant)>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl FallbackTimer {
+    fn spawn(relay_channel: SyncSender<RelayerDirective>) -> FallbackTimer {
+        let (schedule_send, schedule_recv) = channel();
+        let worker = thread::Builder::new()
+            .name("fallback-timer".to_string())
+            .spawn(move || Self::run(schedule_recv, relay_channel))
+            .expect("Failed to spawn fallback timer thread");
+        FallbackTimer {
+            schedule_send: Some(schedule_send),
+            worker: Some(worker),
+        }
+    }
+
+    fn run(
+        schedule_recv: Receiver<(BurnchainHeaderHash, Instant)>,
+        relay_channel: SyncSender<RelayerDirective>,
+    ) {
+        let mut pending: Option<(BurnchainHeaderHash, Instant)> = None;
+        loop {
+            let next = match pending.as_ref() {
+                Some((_, fire_at)) => {
+                    schedule_recv.recv_timeout(fire_at.saturating_duration_since(Instant::now()))
+                }
+                None => schedule_recv
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match next {
+                Ok(scheduled) => pending = Some(scheduled),
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Some((burn_header_hash, _)) = pending.take() {
+                        if relay_channel
+                            .send(RelayerDirective::RunFallbackTenure(burn_header_hash))
+                            .is_err()
+                        {
+                            // the relayer has exited
+                            return;
+                        }
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+
+    /// Send the relayer a `RunFallbackTenure` directive for `burn_header_hash` at `fire_at`,
+    /// instead of for any L1 block scheduled before.
+    fn schedule(&self, burn_header_hash: BurnchainHeaderHash, fire_at: Instant) {
+        if let Some(schedule_send) = self.schedule_send.as_ref() {
+            if schedule_send.send((burn_header_hash, fire_at)).is_err() {
+                warn!("Fallback timer has exited; will not take over from the multi-miner leader");
+            }
+        }
+    }
+}
+
+impl Drop for FallbackTimer {
+    fn drop(&mut self) {
+        // hanging up wakes the worker and ends it
+        self.schedule_send.take();
+        if let Some(worker) = self.worker.take() {
+            if worker.join().is_err() {
+                error!("Fallback timer thread panicked");
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -148,7 +236,7 @@ struct MiningTenureInformation {
 
 /// Process artifacts from the tenure.
 /// At this point, we're modifying the chainstate, and merging the artifacts from the previous tenure.
-fn inner_process_tenure(
+pub(crate) fn inner_process_tenure(
     anchored_block: &StacksBlock,
     consensus_hash: &ConsensusHash,
     parent_consensus_hash: &ConsensusHash,
@@ -322,6 +410,21 @@ fn mine_one_microblock(
     return Ok(mined_microblock);
 }
 
+/// Have at least `threshold` transactions entered the mempool since `since_ms`?
+fn mempool_threshold_reached(mem_pool: &MemPoolDB, threshold: Option<u64>, since_ms: u128) -> bool {
+    let threshold = match threshold {
+        Some(threshold) => threshold,
+        None => return false,
+    };
+    match MemPoolDB::count_txs_accepted_since(mem_pool.conn(), (since_ms / 1000) as u64) {
+        Ok(count) => count >= threshold,
+        Err(e) => {
+            warn!("Failed to count new mempool transactions: {:?}", &e);
+            false
+        }
+    }
+}
+
 fn try_mine_microblock(
     config: &Config,
     microblock_miner_state: &mut Option<MicroblockMinerState>,
@@ -354,7 +457,10 @@ fn try_mine_microblock(
                     parent_consensus_hash: ch.clone(),
                     parent_block_hash: bhh.clone(),
                     miner_key: microblock_privkey.clone(),
-                    frequency: config.node.microblock_frequency,
+                    frequency: config
+                        .miner
+                        .block_production
+                        .microblock_interval_ms(config.node.microblock_frequency),
                     last_mined: 0,
                     quantity: 0,
                     cost_so_far: cost_so_far,
@@ -381,6 +487,11 @@ fn try_mine_microblock(
         {
             if microblock_miner.last_mined + (microblock_miner.frequency as u128)
                 < get_epoch_time_ms()
+                || mempool_threshold_reached(
+                    mem_pool,
+                    config.miner.block_production.mempool_threshold(),
+                    microblock_miner.last_mined,
+                )
             {
                 // opportunistically try and mine, but only if there are no attachable blocks in
                 // recent history (i.e. in the last 10 minutes)
@@ -558,6 +669,14 @@ fn spawn_peer(
     let exit_at_block_height = config.burnchain.process_exit_at_block_height;
 
     this.bind(p2p_sock, rpc_sock).unwrap();
+    if let Some(admin_bind) = config.node.admin_bind.as_ref() {
+        let admin_sock: SocketAddr = admin_bind.parse().expect(&format!(
+            "Failed to parse socket address for admin RPC: {}",
+            admin_bind
+        ));
+        this.bind_admin(&admin_sock).unwrap();
+        info!("Serving admin RPC endpoints on {}", &admin_sock);
+    }
     let (mut dns_resolver, mut dns_client) = DNSResolver::new(10);
     let sortdb = SortitionDB::open(&burn_db_path, false).map_err(NetError::DBError)?;
 
@@ -568,6 +687,16 @@ fn spawn_peer(
         Some(config.node.get_marf_opts()),
     )
     .map_err(|e| NetError::ChainstateError(e.to_string()))?;
+    chainstate.set_deposit_batch_size(config.burnchain.max_deposit_batch_size);
+    chainstate.set_record_block_results(config.node.record_block_results);
+    chainstate.set_record_state_diffs(config.node.record_state_diffs);
+    chainstate.set_system_priority_policy(config.burnchain.system_priority.clone());
+    chainstate
+        .clarity_state
+        .set_max_tx_event_bytes(config.burnchain.max_tx_event_bytes);
+    chainstate
+        .clarity_state
+        .set_use_subnet_costs(config.burnchain.use_subnet_costs);
 
     // buffer up blocks to store without stalling the p2p thread
     let mut results_with_data = VecDeque::new();
@@ -584,14 +713,26 @@ fn spawn_peer(
                 .unwrap_or_else(|| Box::new(UnitMetric));
             let fee_estimator = config.make_fee_estimator();
 
-            let mut mem_pool = MemPoolDB::open(
+            let mut mem_pool = MemPoolDB::open_with_bloom_config(
                 is_mainnet,
                 config.node.chain_id,
                 &stacks_chainstate_path,
                 cost_estimator,
                 metric,
+                config.connection_options.mempool_bloom.clone(),
             )
             .expect("Database failure opening mempool");
+            mem_pool.set_origin_quota(config.connection_options.mempool_origin_quota.clone());
+            mem_pool.set_contract_deploy_limits(
+                config.connection_options.contract_deploy_limits.clone(),
+            );
+            mem_pool.set_admission_policy(
+                config
+                    .connection_options
+                    .mempool_admission_policy
+                    .make_policy(),
+            );
+            mem_pool.set_sponsorship_policy(config.connection_options.sponsorship_policy.clone());
 
             let cost_estimator = config
                 .make_cost_estimator()
@@ -614,7 +755,12 @@ fn spawn_peer(
             let mut num_p2p_state_machine_passes = 0;
             let mut num_inv_sync_passes = 0;
             let mut num_download_passes = 0;
-            let mut mblock_deadline = 0;
+            let mut mblock_deadline: u128 = 0;
+            let microblock_interval_ms = config
+                .miner
+                .block_production
+                .microblock_interval_ms(config.node.microblock_frequency);
+            let mempool_threshold = config.miner.block_production.mempool_threshold();
 
             while should_keep_running.load(Ordering::SeqCst) {
                 // initial block download?
@@ -629,7 +775,7 @@ fn spawn_peer(
                     );
                     1
                 } else {
-                    cmp::min(poll_timeout, config.node.microblock_frequency)
+                    cmp::min(poll_timeout, microblock_interval_ms)
                 };
 
                 let mut expected_attachments = match attachments_rx.try_recv() {
@@ -682,15 +828,24 @@ fn spawn_peer(
                         }
 
                         // only do this on the Ok() path, even if we're mining, because an error in
-                        // network dispatching is likely due to resource exhaustion
-                        if mblock_deadline < get_epoch_time_ms() {
+                        // network dispatching is likely due to resource exhaustion.  In timer
+                        // block production, enough new transactions schedule one early.
+                        let mblock_started_ms =
+                            mblock_deadline.saturating_sub(microblock_interval_ms as u128);
+                        if mblock_deadline < get_epoch_time_ms()
+                            || mempool_threshold_reached(
+                                &mem_pool,
+                                mempool_threshold,
+                                mblock_started_ms,
+                            )
+                        {
                             info!("P2P: schedule microblock tenure");
                             results_with_data.push_back(RelayerDirective::RunMicroblockTenure(
                                 this.burnchain_tip.clone(),
                                 get_epoch_time_ms(),
                             ));
                             mblock_deadline =
-                                get_epoch_time_ms() + (config.node.microblock_frequency as u128);
+                                get_epoch_time_ms() + (microblock_interval_ms as u128);
                         }
                     }
                     Err(e) => {
@@ -811,6 +966,16 @@ fn spawn_miner_relayer(
         Some(config.node.get_marf_opts()),
     )
     .map_err(|e| NetError::ChainstateError(e.to_string()))?;
+    chainstate.set_deposit_batch_size(config.burnchain.max_deposit_batch_size);
+    chainstate.set_record_block_results(config.node.record_block_results);
+    chainstate.set_record_state_diffs(config.node.record_state_diffs);
+    chainstate.set_system_priority_policy(config.burnchain.system_priority.clone());
+    chainstate
+        .clarity_state
+        .set_max_tx_event_bytes(config.burnchain.max_tx_event_bytes);
+    chainstate
+        .clarity_state
+        .set_use_subnet_costs(config.burnchain.use_subnet_costs);
 
     let mut last_mined_blocks: HashMap<
         BurnchainHeaderHash,
@@ -823,6 +988,8 @@ fn spawn_miner_relayer(
     let mut microblock_miner_state: Option<MicroblockMinerState> = None;
     let mut miner_tip = None; // only set if we won the last sortition
     let mut last_microblock_tenure_time = 0;
+    let mut withdrawal_batcher = WithdrawalBatcher::from_config(&config);
+    let mut miner_key_rotator = MinerKeyRotator::from_config(&config);
 
     let relayer_handle = thread::Builder::new().name("relayer".to_string()).spawn(move || {
         let cost_estimator = config.make_cost_estimator()
@@ -830,8 +997,14 @@ fn spawn_miner_relayer(
         let metric = config.make_cost_metric()
             .unwrap_or_else(|| Box::new(UnitMetric));
 
-        let mut mem_pool = MemPoolDB::open(is_mainnet, chain_id, &stacks_chainstate_path, cost_estimator, metric)
+        let mut mem_pool = MemPoolDB::open_with_bloom_config(is_mainnet, chain_id, &stacks_chainstate_path, cost_estimator, metric, config.connection_options.mempool_bloom.clone())
             .expect("Database failure opening mempool");
+        mem_pool.set_origin_quota(config.connection_options.mempool_origin_quota.clone());
+        mem_pool
+            .set_contract_deploy_limits(config.connection_options.contract_deploy_limits.clone());
+        mem_pool.set_admission_policy(config.connection_options.mempool_admission_policy.make_policy());
+        mem_pool.set_sponsorship_policy(config.connection_options.sponsorship_policy.clone());
+        mem_pool.set_gc_policy(config.connection_options.mempool_gc_policy.clone());
 
         while let Ok(mut directive) = relay_channel.recv() {
             match directive {
@@ -999,12 +1172,26 @@ fn spawn_miner_relayer(
                         );
                     }
                 }
-                RelayerDirective::RunTenure => {
+                RelayerDirective::RunTenure | RelayerDirective::RunFallbackTenure(_) => {
                     let burn_tenure_snapshot = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())
                         .expect("FATAL: failed to query sortition DB for canonical burn chain tip");
 
                     let burn_header_hash = burn_tenure_snapshot.burn_header_hash.clone();
 
+                    let as_fallback = if let RelayerDirective::RunFallbackTenure(ref fallback_burn_hash) = directive {
+                        if fallback_burn_hash != &burn_header_hash {
+                            debug!("Relayer: Drop stale RunFallbackTenure for {}: current burn block is {}", fallback_burn_hash, &burn_header_hash);
+                            continue;
+                        }
+                        if last_mined_blocks.get(&burn_header_hash).map(|blocks| blocks.len() > 0).unwrap_or(false) {
+                            debug!("Relayer: Drop RunFallbackTenure for {}: already mined a block for it", &burn_header_hash);
+                            continue;
+                        }
+                        true
+                    } else {
+                        false
+                    };
+
                     let burn_chain_tip = burn_tenure_snapshot
                         .burn_header_hash
                         .clone();
@@ -1024,6 +1211,16 @@ fn spawn_miner_relayer(
                         "last_mined_blocks_vec.len()" => last_mined_blocks_vec.len(),
                     );
 
+                    // this tenure's L1 transactions land in the next L1 block
+                    let landing_burn_height = burn_tenure_snapshot.block_height + 1;
+                    if let Some(rotator) = miner_key_rotator.as_ref() {
+                        let mining_key = *rotator.key_at(landing_burn_height);
+                        if keychain.generate_op_signer().get_sk() != &mining_key {
+                            info!("Relayer: Rotating to the next miner key"; "burn_height" => landing_burn_height);
+                            keychain.set_signing_key(mining_key);
+                        }
+                    }
+
                     let last_mined_block_opt = StacksNode::relayer_run_tenure(
                         &config,
                         &mut chainstate,
@@ -1034,6 +1231,8 @@ fn spawn_miner_relayer(
                         &mut *bitcoin_controller,
                         &last_mined_blocks_vec.iter().map(|(blk, _)| blk).collect(),
                         &event_dispatcher,
+                        &mut withdrawal_batcher,
+                        as_fallback,
                     );
                     if let Some((last_mined_block, microblock_privkey)) = last_mined_block_opt {
                         if last_mined_blocks_vec.len() == 0 {
@@ -1043,6 +1242,16 @@ fn spawn_miner_relayer(
                     }
                     last_mined_blocks.insert(burn_header_hash, last_mined_blocks_vec);
 
+                    if let Some(rotator) = miner_key_rotator.as_mut() {
+                        StacksNode::relayer_register_next_miner_key(
+                            &config,
+                            rotator,
+                            landing_burn_height,
+                            &keychain,
+                            &mut *bitcoin_controller,
+                        );
+                    }
+
                     let last_tenure_issue_time = get_epoch_time_ms();
                     debug!("Relayer: RunTenure finished at {} (in {}ms)", last_tenure_issue_time, last_tenure_issue_time.saturating_sub(tenure_begin));
                 }
@@ -1190,20 +1399,7 @@ impl StacksNode {
             "Failed to parse socket: {}",
             &config.node.p2p_address
         ));
-        let node_privkey = {
-            let mut re_hashed_seed = config.node.local_peer_seed.clone();
-            let my_private_key = loop {
-                match Secp256k1PrivateKey::from_slice(&re_hashed_seed[..]) {
-                    Ok(sk) => break sk,
-                    Err(_) => {
-                        re_hashed_seed = Sha256Sum::from_data(&re_hashed_seed[..])
-                            .as_bytes()
-                            .to_vec()
-                    }
-                }
-            };
-            my_private_key
-        };
+        let node_privkey = config.node.get_node_private_key();
 
         let mut peerdb = PeerDB::connect(
             &config.get_peer_db_file_path(),
@@ -1292,15 +1488,38 @@ impl StacksNode {
             .make_cost_metric()
             .unwrap_or_else(|| Box::new(UnitMetric));
 
-        let _ = MemPoolDB::open(
+        let _ = MemPoolDB::open_with_bloom_config(
             config.is_mainnet(),
             config.node.chain_id,
             &config.get_chainstate_path_str(),
             cost_estimator,
             metric,
+            config.connection_options.mempool_bloom.clone(),
         )
         .expect("BUG: failed to instantiate mempool");
 
+        // the RPC interface reports whether this node's miner has had blocks committed, the
+        //  event observers, and the key that signs the payloads sent to them
+        let mut connection_options = config.connection_options.clone();
+        connection_options.event_signing_public_key = Some(Secp256k1PublicKey::from_private(
+            &config.node.get_node_private_key(),
+        ));
+        connection_options.event_observers = config
+            .events_observers
+            .iter()
+            .map(|observer| AdminObserverInfo {
+                endpoint: observer.endpoint.clone(),
+                events_keys: observer
+                    .events_keys
+                    .iter()
+                    .map(|key| key.to_config_string())
+                    .collect(),
+            })
+            .collect();
+        if miner {
+            connection_options.subnet_miner_address = keychain.origin_address(config.is_mainnet());
+        }
+
         // now we're ready to instantiate a p2p network object, the relayer, and the event dispatcher
         let mut p2p_net = PeerNetwork::new(
             peerdb,
@@ -1309,7 +1528,7 @@ impl StacksNode {
             config.burnchain.peer_version,
             burnchain.clone(),
             view,
-            config.connection_options.clone(),
+            connection_options,
             epochs,
         );
 
@@ -1358,6 +1577,10 @@ impl StacksNode {
         info!("Start P2P server on: {}", &config.node.p2p_bind);
 
         let is_miner = miner;
+        let fallback_timer = match SignerCoordinator::from_config(&config) {
+            Some(_) if is_miner => Some(FallbackTimer::spawn(relay_send.clone())),
+            _ => None,
+        };
 
         StacksNode {
             config,
@@ -1369,6 +1592,7 @@ impl StacksNode {
             p2p_thread_handle,
             relayer_thread_handle,
             is_tenure_timer_running: Arc::new(Mutex::new(false)),
+            fallback_timer,
         }
     }
 
@@ -1381,6 +1605,20 @@ impl StacksNode {
         }
 
         if let Some(burnchain_tip) = get_last_sortition(&self.last_sortition) {
+            if !self
+                .config
+                .miner
+                .block_production
+                .should_anchor_at(burnchain_tip.block_height)
+            {
+                // timer block production: keep producing microblocks off of the last anchor
+                debug!(
+                    "relayer_issue_tenure: not anchoring at this L1 block";
+                    "burn_height" => burnchain_tip.block_height,
+                );
+                return true;
+            }
+
             let relay_channel = self.relay_channel.clone();
             let wait_before_first_anchored_block =
                 self.config.node.wait_before_first_anchored_block;
@@ -1428,6 +1666,23 @@ impl StacksNode {
                     channel_accepted
                 });
             }
+
+            // If this node is the multi-miner fallback for this L1 block, check back once the
+            // leader's signing window has passed, in case the leader never proposed a block.
+            if let (Some(signer_coordinator), Some(fallback_timer)) = (
+                SignerCoordinator::from_config(&self.config),
+                self.fallback_timer.as_ref(),
+            ) {
+                if signer_coordinator.is_fallback(burnchain_tip.block_height) {
+                    let fallback_wait =
+                        time::Duration::from_millis(wait_before_first_anchored_block)
+                            + signer_coordinator.fallback_delay();
+                    fallback_timer.schedule(
+                        burnchain_tip.burn_header_hash.clone(),
+                        Instant::now() + fallback_wait,
+                    );
+                }
+            }
             true
         } else {
             warn!("Tenure: Do not know the last burn block. As a miner, this is bad.");
@@ -1553,9 +1808,77 @@ impl StacksNode {
         })
     }
 
+    /// Register the miner's next key with the L1 contract, if a scheduled rotation is due for
+    /// L1 transactions landing in block `landing_burn_height`.  The registration is signed by
+    /// the keychain's current key, and is retried in later tenures if it can't be submitted.
+    fn relayer_register_next_miner_key(
+        config: &Config,
+        rotator: &mut MinerKeyRotator,
+        landing_burn_height: u64,
+        keychain: &Keychain,
+        bitcoin_controller: &mut (dyn BurnchainController + Send),
+    ) {
+        let (next_miner, activation_height) = match rotator.due_registration(landing_burn_height) {
+            Some(registration) => registration,
+            None => return,
+        };
+        if config.node.mock_mining {
+            debug!("Mock-mining enabled; not registering the next miner key");
+            return;
+        }
+
+        let mut op_signer = keychain.generate_op_signer();
+        match bitcoin_controller.submit_miner_key_rotation(
+            &next_miner,
+            activation_height,
+            &mut op_signer,
+        ) {
+            Ok(txid) => {
+                info!(
+                    "Submitted next miner key registration L1 transaction";
+                    "txid" => %txid,
+                    "next_miner" => %next_miner,
+                    "activation_height" => activation_height
+                );
+                rotator.note_registered(activation_height);
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to submit next miner key registration L1 transaction: {}",
+                    e
+                );
+            }
+        }
+    }
+
+    /// Should this node, the multi-miner fallback for `burn_block`, propose a block for it in
+    /// place of the leader?  Only if the leader has not sent this node a proposal for it.
+    fn should_take_over_from_leader(
+        signer_coordinator: &SignerCoordinator,
+        burn_block: &BlockSnapshot,
+    ) -> bool {
+        if !signer_coordinator.is_fallback(burn_block.block_height) {
+            return false;
+        }
+        if signer_coordinator.has_seen_proposal(burn_block.block_height) {
+            debug!(
+                "Multi-miner leader proposed a block; will not propose a block in its place";
+                "burn_block_height" => burn_block.block_height,
+                "burn_block_hash" => %burn_block.burn_header_hash
+            );
+            return false;
+        }
+        info!(
+            "No proposal from the multi-miner leader; proposing a block in its place";
+            "burn_block_height" => burn_block.block_height,
+            "burn_block_hash" => %burn_block.burn_header_hash
+        );
+        true
+    }
+
     /// Return the assembled anchor block info and microblock private key on success.
     /// Return None if we couldn't build a block for whatever reason
-    fn relayer_run_tenure(
+    pub(crate) fn relayer_run_tenure(
         config: &Config,
         chain_state: &mut StacksChainState,
         burn_db: &mut SortitionDB,
@@ -1565,7 +1888,45 @@ impl StacksNode {
         bitcoin_controller: &mut (dyn BurnchainController + Send),
         last_mined_blocks: &Vec<&AssembledAnchorBlock>,
         event_dispatcher: &EventDispatcher,
+        withdrawal_batcher: &mut WithdrawalBatcher,
+        as_fallback: bool,
     ) -> Option<(AssembledAnchorBlock, Secp256k1PrivateKey)> {
+        // a commit for a block mined now would be rejected, so don't mine one
+        match burn_db
+            .index_handle(&burn_block.sortition_id)
+            .is_subnet_halted()
+        {
+            Ok(false) => {}
+            Ok(true) => {
+                info!(
+                    "Subnet is halted by its L1 governance; will not mine a block";
+                    "burn_block_height" => burn_block.block_height,
+                    "burn_block_hash" => %burn_block.burn_header_hash
+                );
+                return None;
+            }
+            Err(e) => {
+                error!("Failed to check if the subnet is halted: {:?}", &e);
+                return None;
+            }
+        }
+
+        let signer_coordinator = SignerCoordinator::from_config(config);
+        if let Some(signer_coordinator) = signer_coordinator.as_ref() {
+            if as_fallback {
+                if !Self::should_take_over_from_leader(signer_coordinator, &burn_block) {
+                    return None;
+                }
+            } else if !signer_coordinator.is_leader(burn_block.block_height) {
+                debug!(
+                    "Not the multi-miner leader for this L1 block; will not propose a block";
+                    "burn_block_height" => burn_block.block_height,
+                    "burn_block_hash" => %burn_block.burn_header_hash
+                );
+                return None;
+            }
+        }
+
         let MiningTenureInformation {
             mut stacks_parent_header,
             parent_consensus_hash,
@@ -1884,6 +2245,7 @@ impl StacksNode {
             mblocks_confirmed,
             burn_tip,
             burn_tip_height,
+            withdrawal_leaves,
             ..
         } = built_info;
 
@@ -1898,36 +2260,65 @@ impl StacksNode {
         // collect required contents for commit
         let committed_block_hash = anchored_block.block_hash();
         let target_burn_hash = burn_block.burn_header_hash.clone();
-        let withdrawal_merkle_root = anchored_block.header.withdrawal_merkle_root;
+        let withdrawal_merkle_root = withdrawal_batcher.get_commit_withdrawal_root(
+            chain_state,
+            burn_db,
+            &stacks_parent_header.index_block_hash(),
+            block_height,
+            anchored_block.header.withdrawal_merkle_root,
+        );
 
         let required_signatures = bitcoin_controller.commit_required_signatures();
-        let signatures = if required_signatures > 0 {
-            // if we need to collect signatures, assemble the proposal and send to other participants
-            let proposal = Proposal {
-                parent_block_hash: stacks_parent_header.anchored_header.block_hash(),
-                parent_consensus_hash: stacks_parent_header.consensus_hash.clone(),
-                block: anchored_block.clone(),
-                microblocks_confirmed: mblocks_confirmed,
-                burn_tip,
-                burn_tip_height,
-                total_burn: parent_block_total_burn,
-                is_mainnet: config.is_mainnet(),
-                microblock_pubkey_hash: mblock_pubkey_hash.clone(),
-            };
-
-            (0..required_signatures)
-                .filter_map(|participant_index| {
-                    match bitcoin_controller.propose_block(participant_index, &proposal) {
-                        Ok(signature) => Some(signature),
-                        Err(rejection) => {
-                            warn!("Failed to obtain approval"; "error" => %rejection);
-                            None
-                        }
+        let signatures = match signer_coordinator.as_ref() {
+            Some(signer_coordinator) if required_signatures > 0 => {
+                // if we need to collect signatures, assemble the proposal and send to other participants
+                let mut proposal = Proposal {
+                    parent_block_hash: stacks_parent_header.anchored_header.block_hash(),
+                    parent_consensus_hash: stacks_parent_header.consensus_hash.clone(),
+                    block: anchored_block.clone(),
+                    microblocks_confirmed: mblocks_confirmed,
+                    burn_tip,
+                    burn_tip_height,
+                    total_burn: parent_block_total_burn,
+                    is_mainnet: config.is_mainnet(),
+                    microblock_pubkey_hash: mblock_pubkey_hash.clone(),
+                    withdrawal_leaves: Some(withdrawal_leaves),
+                    proposer_signature: None,
+                };
+                let mining_key = match config.node.mining_key.as_ref() {
+                    Some(mining_key) => mining_key,
+                    None => {
+                        error!("Cannot propose a multi-party block without a mining key");
+                        return None;
                     }
-                })
-                .collect()
-        } else {
-            vec![]
+                };
+                signer_coordinator.sign_proposal(&mut proposal, mining_key);
+
+                // signers sign one block per L1 block, so this node won't sign another
+                if !signer_coordinator
+                    .commit_to_proposal(burn_block.block_height, &committed_block_hash)
+                {
+                    info!(
+                        "Already proposed or signed a different block for this L1 block; will not propose another";
+                        "burn_block_height" => burn_block.block_height,
+                        "burn_block_hash" => %burn_block.burn_header_hash
+                    );
+                    return None;
+                }
+
+                match bitcoin_controller.block_proposer() {
+                    Some(block_proposer) => signer_coordinator.collect_signatures(
+                        &proposal,
+                        move |participant_index, proposal, timeout| {
+                            block_proposer
+                                .propose_block_to(participant_index, proposal, timeout)
+                                .map_err(crate::burnchains::Error::BadCommitment)
+                        },
+                    ),
+                    None => vec![],
+                }
+            }
+            _ => vec![],
         };
 
         if signatures.len() < required_signatures as usize {
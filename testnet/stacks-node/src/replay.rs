@@ -0,0 +1,76 @@
+//! Event replay.
+//!
+//! `stacks-node replay-events` re-executes a range of the canonical Stacks fork's blocks from the
+//! node's chainstate, and sends the `new_block` and `subnet_withdrawals` payloads that processing
+//! them produced to the configured event observers, in height order.  Indexers use this to
+//! rebuild their databases without re-syncing the node.
+//!
+//! Replay does not change the chainstate, but it does open it, so the node must be stopped while
+//! events are replayed.  Payloads are sent directly rather than through the node's event queue,
+//! and withdrawals are sent with their block, regardless of `withdrawal_confirmation_depth`.
+
+use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::stacks::db::StacksChainState;
+
+use crate::{Config, EventDispatcher};
+
+/// Replay the events of the canonical blocks at heights `from_height` through `to_height`
+/// (inclusive) to `config`'s event observers.  Returns the number of blocks replayed.
+pub fn replay_events(config: &Config, from_height: u64, to_height: u64) -> Result<u64, String> {
+    if from_height == 0 {
+        return Err("--from-height must be at least 1: the boot block cannot be replayed".into());
+    }
+    if from_height > to_height {
+        return Err(format!(
+            "--from-height {} is above --to-height {}",
+            from_height, to_height
+        ));
+    }
+    if config.events_observers.is_empty() {
+        return Err("No event observers are configured".into());
+    }
+
+    let sortdb = SortitionDB::open(&config.get_burn_db_file_path(), false)
+        .map_err(|e| format!("Failed to open the sortition DB: {:?}", &e))?;
+    let (mut chainstate, _) = StacksChainState::open(
+        config.is_mainnet(),
+        config.node.chain_id,
+        &config.get_chainstate_path_str(),
+        Some(config.node.get_marf_opts()),
+    )
+    .map_err(|e| format!("Failed to open the chainstate: {:?}", &e))?;
+    chainstate.set_deposit_batch_size(config.burnchain.max_deposit_batch_size);
+    chainstate.set_system_priority_policy(config.burnchain.system_priority.clone());
+    chainstate
+        .clarity_state
+        .set_max_tx_event_bytes(config.burnchain.max_tx_event_bytes);
+    chainstate
+        .clarity_state
+        .set_use_subnet_costs(config.burnchain.use_subnet_costs);
+
+    let mut event_dispatcher = EventDispatcher::new();
+    for observer in config.events_observers.iter() {
+        event_dispatcher.register_observer(observer);
+    }
+    event_dispatcher.set_signing_key(&config.node.get_node_private_key());
+
+    let mut num_replayed = 0;
+    for height in from_height..=to_height {
+        let block_id = chainstate
+            .get_canonical_block_id_at_height(&sortdb, height)
+            .map_err(|e| format!("Failed to find the block at height {}: {:?}", height, &e))?
+            .ok_or_else(|| format!("The canonical Stacks tip is below height {}", height))?;
+        let replayed = chainstate
+            .replay_block(&sortdb, &block_id)
+            .map_err(|e| format!("Failed to replay block {}: {:?}", &block_id, &e))?;
+
+        info!("Replaying events of block";
+              "height" => height,
+              "index_block_hash" => %block_id,
+              "num_receipts" => replayed.epoch_receipt.tx_receipts.len());
+        replayed.announce(&event_dispatcher);
+        num_replayed += 1;
+    }
+
+    Ok(num_replayed)
+}
@@ -1,8 +1,12 @@
 use std::convert::Infallible;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use stacks::burnchains::events::NewBlock;
 use stacks::burnchains::indexer::BurnchainChannel;
+use stacks::monitoring;
+use stacks::util::sleep_ms;
 use std::thread;
 use tokio::sync::oneshot;
 use tokio::sync::oneshot::Receiver;
@@ -11,6 +15,8 @@ use tokio::task::JoinError;
 use warp;
 use warp::Filter;
 
+use crate::config::BurnchainConfig;
+
 /// Adds in `channel` to downstream functions.
 fn with_db(
     channel: Arc<dyn BurnchainChannel>,
@@ -80,3 +86,271 @@ pub fn spawn(channel: Arc<dyn BurnchainChannel>, observer_port: u16) -> Sender<(
         .expect("`spawn` has failed.");
     signal_sender
 }
+
+/// How long a health check waits for an L1 node to answer
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Health of one L1 RPC endpoint
+#[derive(Debug, Clone)]
+struct L1Endpoint {
+    url: String,
+    /// number of requests that failed in a row
+    consecutive_failures: u32,
+    /// while set, the endpoint is backing off, and is used only if no endpoint is healthy
+    retry_at: Option<Instant>,
+}
+
+/// The L1 nodes that this node sends RPC requests to: the configured L1 node, followed by the
+/// fallback endpoints, in order of preference.
+///
+/// Requests go to the most preferred healthy endpoint.  An endpoint that cannot be reached is
+/// skipped for a backoff period, which doubles with each consecutive failure up to a maximum,
+/// and is healthy again once a request to it or a health check succeeds.  This way, a single
+/// flaky L1 node does not stop the miner from committing blocks.
+#[derive(Debug)]
+pub struct L1RpcEndpoints {
+    endpoints: Mutex<Vec<L1Endpoint>>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl L1RpcEndpoints {
+    pub fn new(urls: Vec<String>, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        assert!(!urls.is_empty(), "At least one L1 RPC endpoint is required");
+        let endpoints = urls
+            .into_iter()
+            .map(|url| L1Endpoint {
+                url,
+                consecutive_failures: 0,
+                retry_at: None,
+            })
+            .collect();
+        L1RpcEndpoints {
+            endpoints: Mutex::new(endpoints),
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    pub fn from_config(config: &BurnchainConfig) -> Self {
+        let mut urls = vec![config.get_rpc_url()];
+        urls.extend(config.fallback_rpc_urls.iter().cloned());
+        L1RpcEndpoints::new(
+            urls,
+            Duration::from_millis(config.rpc_failover_backoff_ms),
+            Duration::from_millis(config.rpc_failover_max_backoff_ms),
+        )
+    }
+
+    /// How long an endpoint backs off after `consecutive_failures` failures in a row
+    fn backoff(&self, consecutive_failures: u32) -> Duration {
+        let exponent = consecutive_failures.saturating_sub(1).min(31);
+        self.initial_backoff
+            .checked_mul(1 << exponent)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    /// The endpoint URLs in the order requests should try them: the healthy endpoints in order
+    /// of preference, then the backing-off endpoints, soonest retry first.
+    fn candidates(&self, now: Instant) -> Vec<String> {
+        let endpoints = self.endpoints.lock().expect("L1 endpoint lock poisoned");
+        let (mut healthy, mut backing_off): (Vec<_>, Vec<_>) = endpoints
+            .iter()
+            .partition(|endpoint| endpoint.retry_at.map_or(true, |retry_at| retry_at <= now));
+        backing_off.sort_by_key(|endpoint| endpoint.retry_at);
+        healthy.append(&mut backing_off);
+        healthy
+            .into_iter()
+            .map(|endpoint| endpoint.url.clone())
+            .collect()
+    }
+
+    /// The URL of the endpoint the next request goes to
+    pub fn current(&self) -> String {
+        self.candidates(Instant::now()).remove(0)
+    }
+
+    pub fn report_success(&self, url: &str) {
+        let mut endpoints = self.endpoints.lock().expect("L1 endpoint lock poisoned");
+        if let Some(endpoint) = endpoints.iter_mut().find(|endpoint| endpoint.url == url) {
+            if endpoint.consecutive_failures > 0 {
+                info!("L1 RPC endpoint is healthy again"; "url" => url);
+            }
+            endpoint.consecutive_failures = 0;
+            endpoint.retry_at = None;
+        }
+    }
+
+    pub fn report_failure(&self, url: &str) {
+        let mut endpoints = self.endpoints.lock().expect("L1 endpoint lock poisoned");
+        if let Some(endpoint) = endpoints.iter_mut().find(|endpoint| endpoint.url == url) {
+            endpoint.consecutive_failures = endpoint.consecutive_failures.saturating_add(1);
+            let backoff = self.backoff(endpoint.consecutive_failures);
+            endpoint.retry_at = Some(Instant::now() + backoff);
+            warn!("L1 RPC endpoint failed, backing off";
+                  "url" => url,
+                  "consecutive_failures" => endpoint.consecutive_failures,
+                  "backoff_ms" => backoff.as_millis() as u64);
+        }
+    }
+
+    /// Make an L1 RPC request, failing over to the next endpoint if an endpoint cannot be
+    /// reached.  `send` is given the endpoint URL, and returns `Err` if the endpoint could not be
+    /// reached, or `Ok` with the outcome of the request if it answered: an endpoint that answers
+    /// with an error (e.g., rejects a transaction) is not failed over from.
+    /// Returns the error of the last endpoint tried if none of them can be reached.
+    pub fn request<T, E>(
+        &self,
+        mut send: impl FnMut(&str) -> Result<Result<T, E>, E>,
+    ) -> Result<T, E> {
+        let mut last_error = None;
+        for url in self.candidates(Instant::now()) {
+            match send(&url) {
+                Ok(outcome) => {
+                    self.report_success(&url);
+                    return outcome;
+                }
+                Err(e) => {
+                    self.report_failure(&url);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.expect("At least one L1 RPC endpoint is required"))
+    }
+
+    /// Probe the backing-off endpoints whose backoff has expired, so that they are used again as
+    /// soon as they recover, rather than on the next request that fails over to them.
+    pub fn check_health(&self) {
+        let now = Instant::now();
+        let due: Vec<String> = self
+            .endpoints
+            .lock()
+            .expect("L1 endpoint lock poisoned")
+            .iter()
+            .filter(|endpoint| endpoint.retry_at.map_or(false, |retry_at| retry_at <= now))
+            .map(|endpoint| endpoint.url.clone())
+            .collect();
+        if due.is_empty() {
+            return;
+        }
+        let client = match reqwest::blocking::Client::builder()
+            .timeout(HEALTH_CHECK_TIMEOUT)
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("Failed to build L1 health check client: {:?}", &e);
+                return;
+            }
+        };
+        for url in due {
+            let healthy = client
+                .get(format!("{}/v2/info", &url))
+                .send()
+                .map_or(false, |response| response.status().is_success());
+            if healthy {
+                self.report_success(&url);
+            } else {
+                monitoring::increment_l1_rpc_failures_counter("health_check");
+                self.report_failure(&url);
+            }
+        }
+    }
+
+    /// Spawn a thread that health-checks the endpoints every `interval_ms`, for as long as
+    /// `should_keep_running` is set.
+    pub fn spawn_health_checker(
+        endpoints: Arc<L1RpcEndpoints>,
+        interval_ms: u64,
+        should_keep_running: Arc<AtomicBool>,
+    ) {
+        thread::Builder::new()
+            .name("l1-health-check".into())
+            .spawn(move || {
+                while should_keep_running.load(Ordering::SeqCst) {
+                    endpoints.check_health();
+                    sleep_ms(interval_ms);
+                }
+            })
+            .expect("FATAL: failed to start L1 health check thread");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PRIMARY: &str = "http://l1-primary:20443";
+    const FALLBACK: &str = "http://l1-fallback:20443";
+
+    fn endpoints() -> L1RpcEndpoints {
+        L1RpcEndpoints::new(
+            vec![PRIMARY.to_string(), FALLBACK.to_string()],
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+        )
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let endpoints = endpoints();
+        assert_eq!(endpoints.backoff(1), Duration::from_secs(1));
+        assert_eq!(endpoints.backoff(2), Duration::from_secs(2));
+        assert_eq!(endpoints.backoff(4), Duration::from_secs(8));
+        assert_eq!(endpoints.backoff(5), Duration::from_secs(10));
+        assert_eq!(endpoints.backoff(u32::MAX), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_failover_and_recovery() {
+        let endpoints = endpoints();
+        assert_eq!(endpoints.current(), PRIMARY);
+
+        endpoints.report_failure(PRIMARY);
+        assert_eq!(endpoints.current(), FALLBACK);
+        // once its backoff expires, the primary is preferred again
+        let later = Instant::now() + Duration::from_secs(2);
+        assert_eq!(endpoints.candidates(later), vec![PRIMARY, FALLBACK]);
+
+        // with every endpoint backing off, the one that retries soonest is used
+        endpoints.report_failure(PRIMARY);
+        endpoints.report_failure(FALLBACK);
+        assert_eq!(endpoints.current(), FALLBACK);
+
+        endpoints.report_success(PRIMARY);
+        assert_eq!(endpoints.current(), PRIMARY);
+    }
+
+    #[test]
+    fn test_request_fails_over() {
+        let endpoints = endpoints();
+        let mut tried = vec![];
+        let result: Result<u64, String> = endpoints.request(|url| {
+            tried.push(url.to_string());
+            if url == PRIMARY {
+                Err("connection refused".into())
+            } else {
+                Ok(Ok(7))
+            }
+        });
+        assert_eq!(result, Ok(7));
+        assert_eq!(tried, vec![PRIMARY, FALLBACK]);
+        assert_eq!(endpoints.current(), FALLBACK);
+
+        // an endpoint that answers with an error is not failed over from
+        let mut tried = vec![];
+        let result: Result<u64, String> = endpoints.request(|url| {
+            tried.push(url.to_string());
+            Ok(Err("rejected".into()))
+        });
+        assert_eq!(result, Err("rejected".into()));
+        assert_eq!(tried, vec![FALLBACK]);
+
+        // if no endpoint can be reached, the last error is returned
+        let result: Result<u64, String> =
+            endpoints.request(|url| Err(format!("{} unreachable", url)));
+        assert_eq!(result, Err(format!("{} unreachable", PRIMARY)));
+    }
+}
@@ -23,16 +23,26 @@ use stacks::chainstate::coordinator::{
     Error as coord_error,
 };
 use stacks::chainstate::stacks::db::{ChainStateBootData, StacksChainState};
+use stacks::chainstate::stacks::subnet_codec::SUBNET_CONTRACT_INTERFACE_VERSION;
+use stacks::chainstate::stacks::Error as ChainstateError;
 use stacks::net::atlas::ATTACHMENTS_CHANNEL_SIZE;
 use stacks::net::atlas::{AtlasConfig, AttachmentInstance};
+use stacks::net::{L1ContractStatus, L1SyncStatus, NodeStartupState};
+use stacks::util::sleep_ms;
 use stacks::util_lib::db::Error as db_error;
 use tokio::sync::oneshot::Sender;
 
+use crate::burnchains::contract_version;
+use crate::burnchains::l1_sync;
+use crate::config::{CommitStrategy, ContractVersionMismatchAction};
 use crate::run_loop::l1_observer;
 
+use crate::grpc::start_serving_grpc_streams;
 use crate::monitoring::start_serving_monitoring_metrics;
 use crate::neon_node::StacksNode;
+use crate::shadow::ShadowMonitor;
 use crate::syncctl::{PoxSyncWatchdog, PoxSyncWatchdogComms};
+use crate::websocket::start_serving_ws_subscriptions;
 use crate::{BurnchainController, Config, EventDispatcher};
 
 use super::RunLoopCallbacks;
@@ -159,6 +169,12 @@ impl RunLoop {
         for observer in config.events_observers.iter() {
             event_dispatcher.register_observer(observer);
         }
+        event_dispatcher
+            .set_withdrawal_confirmation_depth(config.burnchain.withdrawal_confirmation_depth);
+        event_dispatcher.set_signing_key(&config.node.get_node_private_key());
+        event_dispatcher
+            .set_event_queue_path(&config.get_event_queue_db_file_path())
+            .expect("FATAL: failed to open the event observer queue");
 
         Self {
             config,
@@ -276,6 +292,101 @@ impl RunLoop {
         }
     }
 
+    /// Check that the L1 subnet contract's interface version is the one this node implements,
+    /// waiting for the contract to be deployed if it has not been yet.  If it is not, the node
+    /// either exits or runs without mining, as configured.  The outcome is reported by the RPC
+    /// interface's subnet status.
+    fn check_l1_contract_version(&mut self) {
+        if !self.config.burnchain.spawn_l1_observer() {
+            // the L1 is mocked, so there is no contract to check
+            return;
+        }
+        let l1_rpc = l1_observer::L1RpcEndpoints::from_config(&self.config.burnchain);
+        let contract = self.config.burnchain.contract_identifier.clone();
+        let version = loop {
+            if !self.should_keep_running.load(Ordering::SeqCst) {
+                return;
+            }
+            match contract_version::l1_get_interface_version(&l1_rpc, &contract) {
+                Ok(Some(version)) => break version,
+                Ok(None) => {
+                    info!("Waiting for the subnet contract to be deployed to the L1"; "contract" => %contract);
+                }
+                Err(e) => {
+                    warn!("Failed to query the subnet contract's interface version"; "contract" => %contract, "error" => %e);
+                }
+            }
+            sleep_ms(5000);
+        };
+
+        let status = contract_version::check_interface_version(version);
+        self.config.connection_options.l1_contract_status = status.clone();
+        self.config.connection_options.l1_contract_interface_version = Some(version);
+        if status == L1ContractStatus::Compatible {
+            info!("Subnet contract interface version is supported"; "contract" => %contract, "version" => version);
+            return;
+        }
+        match self.config.burnchain.contract_version_mismatch {
+            ContractVersionMismatchAction::Exit => {
+                error!(
+                    "FATAL: the subnet contract {} has interface version {}, but this node implements version {}",
+                    contract, version, SUBNET_CONTRACT_INTERFACE_VERSION
+                );
+                panic!();
+            }
+            ContractVersionMismatchAction::Degrade => {
+                warn!(
+                    "The subnet contract {} has interface version {}, but this node implements version {}; will not mine",
+                    contract, version, SUBNET_CONTRACT_INTERFACE_VERSION
+                );
+                self.config.node.miner = false;
+            }
+        }
+    }
+
+    /// Check whether the node has processed the L1 up to within `max_l1_sync_lag` blocks of the
+    /// L1 node's tip, which it must before its miner produces blocks.  Once the node has caught
+    /// up, it is not checked again.  The outcome is reported by the RPC interface's subnet status.
+    fn check_l1_synced(
+        &self,
+        l1_rpc: Option<&l1_observer::L1RpcEndpoints>,
+        local_height: u64,
+    ) -> bool {
+        let status_handle = &self.config.connection_options.l1_sync_status;
+        let mut status = status_handle.get();
+        if status.startup_state == NodeStartupState::Ready {
+            return true;
+        }
+        let l1_rpc = match l1_rpc {
+            Some(l1_rpc) => l1_rpc,
+            None => {
+                // the L1 is mocked, so this node's view of it is always current
+                status.startup_state = NodeStartupState::Ready;
+                status_handle.set(status);
+                return true;
+            }
+        };
+
+        let max_lag = self.config.burnchain.max_l1_sync_lag;
+        match l1_sync::l1_get_tip_height(l1_rpc) {
+            Ok(l1_tip_height) => {
+                status.l1_node_tip_height = Some(l1_tip_height);
+                status.startup_state = l1_sync::check_l1_sync(local_height, l1_tip_height, max_lag);
+                if status.startup_state == NodeStartupState::Ready {
+                    info!("Caught up with the L1 tip"; "local_height" => local_height, "l1_tip_height" => l1_tip_height);
+                } else {
+                    info!("Waiting to catch up with the L1 tip before mining"; "local_height" => local_height, "l1_tip_height" => l1_tip_height, "max_lag" => max_lag);
+                }
+            }
+            Err(e) => {
+                warn!("Failed to query the L1 tip height; will not mine until it is known"; "error" => %e);
+            }
+        }
+        let ready = status.startup_state == NodeStartupState::Ready;
+        status_handle.set(status);
+        ready
+    }
+
     /// Instantiate the burnchain client and databases.
     /// Fetches headers and instantiates the burnchain.
     /// Panics on failure.
@@ -318,6 +429,12 @@ impl RunLoop {
                 );
                 panic!();
             }
+            Err(coord_error::DBError(db_error::EpochScheduleMismatch)) => {
+                error!(
+                    "FATAL: chainstate database(s) were created with a different epoch schedule; the [[subnet_epochs]] configuration cannot change once the node has started"
+                );
+                panic!();
+            }
             Err(e) => {
                 panic!("FATAL: unable to query filesystem or databases: {:?}", &e);
             }
@@ -395,16 +512,60 @@ impl RunLoop {
             get_bulk_initial_balances: None,
             get_bulk_initial_namespaces: None,
             get_bulk_initial_names: None,
+            genesis_spec: self.config.genesis.clone(),
+            allow_unchecked_intermediary_responses: self
+                .config
+                .burnchain
+                .allow_unchecked_intermediary_responses,
+            withdrawal_batch_blocks: self.config.burnchain.withdrawal_batch_blocks,
+            epochs: Some(self.config.burnchain.get_stacks_epochs()),
+            system_priority: self.config.burnchain.system_priority.clone(),
+            max_tx_event_bytes: self.config.burnchain.max_tx_event_bytes,
+            use_subnet_costs: self.config.burnchain.use_subnet_costs,
         };
 
-        let (chain_state_db, receipts) = StacksChainState::open_and_exec(
+        let (mut chain_state_db, receipts) = match StacksChainState::open_and_exec(
             self.config.is_mainnet(),
             self.config.node.chain_id,
             &self.config.get_chainstate_path_str(),
             Some(&mut boot_data),
             Some(self.config.node.get_marf_opts()),
-        )
-        .unwrap();
+        ) {
+            Ok(res) => res,
+            Err(ChainstateError::DBError(db_error::GenesisSettingMismatch(setting))) => {
+                error!(
+                    "FATAL: chainstate database was created with a different {}; it cannot change once the node has started",
+                    setting
+                );
+                panic!();
+            }
+            Err(e) => {
+                panic!("FATAL: unable to open chainstate: {:?}", &e);
+            }
+        };
+        chain_state_db.set_deposit_batch_size(self.config.burnchain.max_deposit_batch_size);
+        chain_state_db.set_record_block_results(self.config.node.record_block_results);
+        chain_state_db.set_record_state_diffs(self.config.node.record_state_diffs);
+        chain_state_db.set_prune_depth(self.config.node.prune_depth);
+        let system_priority = chain_state_db.get_committed_system_priority_policy();
+        chain_state_db.set_system_priority_policy(system_priority);
+        let max_tx_event_bytes = chain_state_db.get_committed_max_tx_event_bytes();
+        chain_state_db
+            .clarity_state
+            .set_max_tx_event_bytes(max_tx_event_bytes);
+        let use_subnet_costs = chain_state_db.get_committed_use_subnet_costs();
+        chain_state_db
+            .clarity_state
+            .set_use_subnet_costs(use_subnet_costs);
+        if chain_state_db.get_committed_withdrawal_batch_blocks() > 0 {
+            if let CommitStrategy::MultiMiner { .. } = self.config.burnchain.commit_strategy {
+                // participants sign the withdrawal root of the proposed block itself
+                error!(
+                    "FATAL: chainstate batches withdrawals, which requires direct block commits"
+                );
+                panic!();
+            }
+        }
         self.event_dispatcher.dispatch_boot_receipts(receipts);
 
         // NOTE: re-instantiate AtlasConfig so we don't have to keep the genesis attachments around
@@ -436,6 +597,42 @@ impl RunLoop {
         (coordinator_thread_handle, attachments_rx)
     }
 
+    /// Boot the burnchain controller and the chains coordinator, without any of the threads that
+    /// drive them, so that a test can step the node itself. Returns once the coordinator has
+    /// processed every L1 block the node already has.
+    #[cfg(test)]
+    pub(crate) fn boot_without_run_loop(
+        &mut self,
+    ) -> (
+        Box<dyn BurnchainController>,
+        CoordinatorChannels,
+        JoinHandle<()>,
+        Receiver<HashSet<AttachmentInstance>>,
+    ) {
+        let (coordinator_receivers, coordinator_senders) = self
+            .coordinator_channels
+            .take()
+            .expect("Run loop already started, can only start once after initialization.");
+        let (mut burnchain, _) =
+            self.instantiate_burnchain_state(None, coordinator_senders.clone());
+        let burnchain_config = burnchain.get_burnchain();
+        self.burnchain = Some(burnchain_config.clone());
+
+        let (coordinator_thread_handle, attachments_rx) =
+            self.spawn_chains_coordinator(&burnchain_config, coordinator_receivers);
+        coordinator_senders.announce_new_burn_block();
+        burnchain
+            .wait_for_sortitions(None)
+            .expect("Unable to get burnchain tip");
+
+        (
+            burnchain,
+            coordinator_senders,
+            coordinator_thread_handle,
+            attachments_rx,
+        )
+    }
+
     /// Instantiate the PoX watchdog
     fn instantiate_pox_watchdog(&mut self) {
         let pox_watchdog = PoxSyncWatchdog::new(&self.config, self.should_keep_running.clone())
@@ -456,6 +653,31 @@ impl RunLoop {
         }
     }
 
+    /// Start streaming blocks and withdrawals to gRPC clients, if configured.  Must be called
+    /// before the event dispatcher is handed to the chains coordinator.
+    fn start_grpc(&mut self) {
+        if let Some(grpc_bind) = self.config.node.grpc_bind.clone() {
+            self.event_dispatcher
+                .set_grpc_streams(start_serving_grpc_streams(grpc_bind));
+        }
+    }
+
+    /// Start pushing events to WebSocket subscribers, if configured.  Must be called before the
+    /// event dispatcher is handed to the chains coordinator.
+    fn start_ws_subscriptions(&mut self) {
+        if let Some(ws_bind) = self.config.node.ws_bind.clone() {
+            self.event_dispatcher
+                .set_ws_streams(start_serving_ws_subscriptions(ws_bind));
+        }
+    }
+
+    /// Start comparing block results against the primary node, if running in shadow mode
+    fn start_shadow_monitor(&mut self) {
+        if let Some(monitor) = ShadowMonitor::new(&self.config, self.should_keep_running.clone()) {
+            monitor.spawn();
+        }
+    }
+
     /// Get the sortition DB's highest block height
     fn get_sortition_db_height(sortdb: &SortitionDB, burnchain_config: &Burnchain) -> u64 {
         let sortition_db_height = {
@@ -500,10 +722,29 @@ impl RunLoop {
         let burnchain_config = burnchain.get_burnchain();
         self.burnchain = Some(burnchain_config.clone());
 
+        self.check_l1_contract_version();
         let is_miner = self.check_is_miner();
         self.is_miner = Some(is_miner);
 
+        // until the node confirms that it has caught up with the L1, it does not mine
+        let l1_rpc = if self.config.burnchain.spawn_l1_observer() {
+            self.config
+                .connection_options
+                .l1_sync_status
+                .set(L1SyncStatus {
+                    startup_state: NodeStartupState::SyncingL1,
+                    l1_node_tip_height: None,
+                });
+            Some(l1_observer::L1RpcEndpoints::from_config(
+                &self.config.burnchain,
+            ))
+        } else {
+            None
+        };
+
         // have headers; boot up the chains coordinator and instantiate the chain state
+        self.start_grpc();
+        self.start_ws_subscriptions();
         let (coordinator_thread_handle, attachments_rx) =
             self.spawn_chains_coordinator(&burnchain_config, coordinator_receivers);
         self.instantiate_pox_watchdog();
@@ -532,6 +773,7 @@ impl RunLoop {
         // Start the runloop
         debug!("Begin run loop");
         self.start_prometheus();
+        self.start_shadow_monitor();
         self.counters.bump_blocks_processed();
 
         let mut burnchain_height = sortition_db_height;
@@ -692,6 +934,9 @@ impl RunLoop {
             );
 
             if sortition_db_height >= burnchain_height && !ibd {
+                if !self.check_l1_synced(l1_rpc.as_ref(), sortition_db_height) {
+                    continue;
+                }
                 let canonical_stacks_tip_height =
                     SortitionDB::get_canonical_burn_chain_tip(burnchain.sortdb_ref().conn())
                         .map(|snapshot| snapshot.canonical_stacks_tip_height)
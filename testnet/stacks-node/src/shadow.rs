@@ -0,0 +1,207 @@
+//! Shadow mode.
+//!
+//! A shadow node follows the chain like any other follower, but does not mine or relay blocks.
+//! It records the results of every block it processes, and compares them against the results
+//! recorded by a primary node running the canonical build.  Federations use this to check that a
+//! new node build processes live blocks exactly like the build it will replace, before
+//! coordinating a switch.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+
+use reqwest::StatusCode;
+
+use stacks::chainstate::stacks::db::results::{BlockResults, BlockResultsDivergence};
+use stacks::net::RPCPeerInfoData;
+use stacks::util::sleep_ms;
+
+use crate::Config;
+
+/// How long to wait between polls of the primary and local nodes, in milliseconds
+const SHADOW_POLL_INTERVAL_MS: u64 = 5_000;
+
+/// The outcome of checking the block at one height
+#[derive(Debug, PartialEq)]
+enum ShadowCheck {
+    /// The local and canonical results were compared
+    Compared,
+    /// The block could not be compared, and never will be
+    Skipped,
+    /// The local node has not processed the block yet
+    NotReady,
+}
+
+pub struct ShadowMonitor {
+    /// RPC URL of the node running the canonical build
+    primary_url: String,
+    /// RPC URL of this node
+    local_url: String,
+    client: reqwest::blocking::Client,
+    /// height of the next block to check
+    next_height: u64,
+    num_divergent_blocks: u64,
+    should_keep_running: Arc<AtomicBool>,
+}
+
+impl ShadowMonitor {
+    /// Make a monitor for this node, if it is configured to run in shadow mode.
+    pub fn new(config: &Config, should_keep_running: Arc<AtomicBool>) -> Option<ShadowMonitor> {
+        let primary_url = config.node.shadow_primary.clone()?;
+        Some(ShadowMonitor {
+            primary_url: primary_url.trim_end_matches('/').to_string(),
+            local_url: config.node.data_url.trim_end_matches('/').to_string(),
+            client: reqwest::blocking::Client::new(),
+            // the boot block's results are never recorded
+            next_height: 1,
+            num_divergent_blocks: 0,
+            should_keep_running,
+        })
+    }
+
+    /// Run the monitor on its own thread until the node shuts down.
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        thread::Builder::new()
+            .name("shadow-monitor".to_string())
+            .spawn(move || self.run())
+            .expect("FATAL: failed to start shadow monitor thread")
+    }
+
+    fn run(&mut self) {
+        info!(
+            "Shadow mode: comparing block results against primary node {}",
+            &self.primary_url
+        );
+        while self.should_keep_running.load(Ordering::SeqCst) {
+            if let Err(e) = self.check_new_blocks() {
+                warn!("Shadow mode: failed to check block results: {}", &e);
+            }
+            sleep_ms(SHADOW_POLL_INTERVAL_MS);
+        }
+    }
+
+    /// Check every block the primary has processed since the last pass, stopping at the first
+    /// one this node has not processed yet.
+    fn check_new_blocks(&mut self) -> Result<(), String> {
+        let primary_tip_height = self.get_stacks_tip_height(&self.primary_url)?;
+        while self.next_height <= primary_tip_height {
+            if !self.should_keep_running.load(Ordering::SeqCst) {
+                break;
+            }
+            if self.check_height(self.next_height)? == ShadowCheck::NotReady {
+                break;
+            }
+            self.next_height += 1;
+        }
+        Ok(())
+    }
+
+    fn check_height(&mut self, height: u64) -> Result<ShadowCheck, String> {
+        let canonical =
+            match self.get_block_results(&self.primary_url, &format!("height/{}", height))? {
+                Some(results) => results,
+                None => {
+                    warn!(
+                        "Shadow mode: primary node has no recorded results for block";
+                        "block_height" => height
+                    );
+                    return Ok(ShadowCheck::Skipped);
+                }
+            };
+
+        let block_id = canonical.index_block_hash.to_hex();
+        if let Some(local) = self.get_block_results(&self.local_url, &block_id)? {
+            let divergences = local
+                .compare(&canonical)
+                .map_err(|e| format!("Failed to compare block results: {:?}", &e))?;
+            if divergences.is_empty() {
+                debug!(
+                    "Shadow mode: block results match";
+                    "block_height" => height,
+                    "index_block_hash" => %canonical.index_block_hash
+                );
+            } else {
+                self.report_divergences(&canonical, &divergences);
+            }
+            return Ok(ShadowCheck::Compared);
+        }
+
+        // this node has no results for the canonical block.  Either it hasn't processed it yet,
+        // or it chose a different block at this height.
+        let local_tip_height = self.get_stacks_tip_height(&self.local_url)?;
+        if local_tip_height < height {
+            return Ok(ShadowCheck::NotReady);
+        }
+        match self.get_block_results(&self.local_url, &format!("height/{}", height))? {
+            Some(local) if local.index_block_hash != canonical.index_block_hash => {
+                self.num_divergent_blocks += 1;
+                error!(
+                    "Shadow mode: local node chose a different block than the primary";
+                    "block_height" => height,
+                    "local_index_block_hash" => %local.index_block_hash,
+                    "canonical_index_block_hash" => %canonical.index_block_hash,
+                    "num_divergent_blocks" => self.num_divergent_blocks
+                );
+                Ok(ShadowCheck::Compared)
+            }
+            _ => {
+                warn!(
+                    "Shadow mode: local node has no recorded results for block";
+                    "block_height" => height,
+                    "index_block_hash" => %canonical.index_block_hash
+                );
+                Ok(ShadowCheck::Skipped)
+            }
+        }
+    }
+
+    fn report_divergences(
+        &mut self,
+        canonical: &BlockResults,
+        divergences: &[BlockResultsDivergence],
+    ) {
+        self.num_divergent_blocks += 1;
+        for divergence in divergences.iter() {
+            let divergence_json =
+                serde_json::to_string(divergence).unwrap_or_else(|_| format!("{:?}", divergence));
+            error!(
+                "Shadow mode: block results diverge from the primary";
+                "block_height" => canonical.block_height,
+                "index_block_hash" => %canonical.index_block_hash,
+                "divergence" => divergence_json,
+                "num_divergent_blocks" => self.num_divergent_blocks
+            );
+        }
+    }
+
+    fn get_stacks_tip_height(&self, url: &str) -> Result<u64, String> {
+        let info: RPCPeerInfoData = self
+            .client
+            .get(format!("{}/v2/info", url))
+            .send()
+            .map_err(|e| format!("Failed to query {}/v2/info: {}", url, e))?
+            .json()
+            .map_err(|e| format!("Failed to decode {}/v2/info: {}", url, e))?;
+        Ok(info.stacks_tip_height)
+    }
+
+    /// Get a node's recorded results for a block, or None if it has none.
+    fn get_block_results(&self, url: &str, block: &str) -> Result<Option<BlockResults>, String> {
+        let path = format!("{}/v2/block_results/{}", url, block);
+        let response = self
+            .client
+            .get(&path)
+            .send()
+            .map_err(|e| format!("Failed to query {}: {}", &path, e))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let results = response
+            .error_for_status()
+            .map_err(|e| format!("Failed to query {}: {}", &path, e))?
+            .json()
+            .map_err(|e| format!("Failed to decode {}: {}", &path, e))?;
+        Ok(Some(results))
+    }
+}
@@ -647,7 +647,8 @@ fn integration_test_get_info() {
 
                 let body = CallReadOnlyRequestBody {
                     sender: "'SP139Q3N9RXCJCD1XVA4N5RYWQ5K9XQ0T9PKQ8EE5".into(),
-                    arguments: vec![Value::UInt(3).serialize()]
+                    arguments: vec![Value::UInt(3).serialize()],
+                    cost_budget: None,
                 };
 
                 let res = client.post(&path)
@@ -668,7 +669,8 @@ fn integration_test_get_info() {
 
                 let body = CallReadOnlyRequestBody {
                     sender: "'SP139Q3N9RXCJCD1XVA4N5RYWQ5K9XQ0T9PKQ8EE5".into(),
-                    arguments: vec![]
+                    arguments: vec![],
+                    cost_budget: None,
                 };
 
                 let res = client.post(&path)
@@ -694,7 +696,8 @@ fn integration_test_get_info() {
 
                 let body = CallReadOnlyRequestBody {
                     sender: "'SP139Q3N9RXCJCD1XVA4N5RYWQ5K9XQ0T9PKQ8EE5".into(),
-                    arguments: vec![]
+                    arguments: vec![],
+                    cost_budget: None,
                 };
 
                 let res = client.post(&path)
@@ -712,7 +715,8 @@ fn integration_test_get_info() {
 
                 let body = CallReadOnlyRequestBody {
                     sender: "'SP139Q3N9RXCJCD1XVA4N5RYWQ5K9XQ0T9PKQ8EE5".into(),
-                    arguments: vec![Value::UInt(3).serialize()]
+                    arguments: vec![Value::UInt(3).serialize()],
+                    cost_budget: None,
                 };
 
                 let res = client.post(&path)
@@ -734,7 +738,8 @@ fn integration_test_get_info() {
 
                 let body = CallReadOnlyRequestBody {
                     sender: "'SP139Q3N9RXCJCD1XVA4N5RYWQ5K9XQ0T9PKQ8EE5".into(),
-                    arguments: vec![Value::UInt(100).serialize()]
+                    arguments: vec![Value::UInt(100).serialize()],
+                    cost_budget: None,
                 };
 
                 let res = client.post(&path)
@@ -752,7 +757,8 @@ fn integration_test_get_info() {
 
                 let body = CallReadOnlyRequestBody {
                     sender: "'SP139Q3N9RXCJCD1XVA4N5RYWQ5K9XQ0T9PKQ8EE5".into(),
-                    arguments: vec![]
+                    arguments: vec![],
+                    cost_budget: None,
                 };
 
                 let res = client.post(&path)
@@ -18,6 +18,7 @@ use crate::tests::to_addr;
 use stacks::core::LAYER_1_CHAIN_ID_TESTNET;
 
 use stacks::burnchains::Burnchain;
+use stacks::util::secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey};
 
 use stacks::vm::types::PrincipalData;
 use stacks::vm::types::QualifiedContractIdentifier;
@@ -30,6 +31,13 @@ use std::time::Duration;
 /// This is the height to wait for the L1 mocknet node to reach the 2.1 epoch
 pub const MOCKNET_EPOCH_2_1: u64 = 4;
 
+/// The compressed public key that identifies the miner with `sk` to the other participants
+fn compressed_public_key(sk: &Secp256k1PrivateKey) -> [u8; 33] {
+    let mut key = [0u8; 33];
+    key.copy_from_slice(&Secp256k1PublicKey::from_private(sk).to_bytes_compressed());
+    key
+}
+
 /// Uses MOCKNET_PRIVATE_KEY_1 to publish the subnet contract and supporting
 ///  trait contracts
 pub fn publish_multiparty_contract_to_l1(
@@ -211,12 +219,14 @@ fn l1_multiparty_2_of_2_integration_test() {
         contract: multi_party_contract.clone(),
         other_participants: vec![MultiMinerParticipant {
             rpc_server: l2_rpc_origin.clone(),
-            public_key: [0; 33],
+            public_key: compressed_public_key(&MOCKNET_PRIVATE_KEY_2),
         }],
         leader: false,
     };
 
     follower_config.connection_options.subnet_signing_contract = Some(multi_party_contract.clone());
+    follower_config.connection_options.subnet_block_proposers =
+        follower_config.block_proposer_schedule();
 
     follower_config.add_bootstrap_node(
         "024d4b6cd1361032ca9bd2aeb9d900aa4d45d9ead80ac9423374c451a7254d0766@127.0.0.1:30444",
@@ -229,7 +239,7 @@ fn l1_multiparty_2_of_2_integration_test() {
         contract: multi_party_contract.clone(),
         other_participants: vec![MultiMinerParticipant {
             rpc_server: follower_rpc_origin.clone(),
-            public_key: [0; 33],
+            public_key: compressed_public_key(&MOCKNET_PRIVATE_KEY_3),
         }],
         leader: true,
     };
@@ -5,8 +5,8 @@ use std::thread::{self, JoinHandle};
 use crate::config::{EventKeyType, EventObserverConfig};
 use crate::tests::l1_multiparty::MOCKNET_EPOCH_2_1;
 use crate::tests::neon_integrations::{
-    filter_map_events, get_account, get_nft_withdrawal_entry, get_withdrawal_entry, submit_tx,
-    test_observer,
+    filter_map_events, get_account, get_nft_withdrawal_entry, get_withdrawal_entry,
+    get_withdrawal_proof, submit_tx, test_observer,
 };
 use crate::tests::{make_contract_call, make_contract_publish, to_addr};
 use crate::{neon, Config};
@@ -34,9 +34,9 @@ use stacks::clarity_vm::withdrawal::{
 };
 use stacks::codec::StacksMessageCodec;
 use stacks::core::LAYER_1_CHAIN_ID_TESTNET;
-use stacks::net::CallReadOnlyRequestBody;
+use stacks::net::{CallReadOnlyRequestBody, WithdrawalClaimStatus};
 
-use stacks::util::hash::hex_bytes;
+use stacks::util::hash::{hex_bytes, to_hex};
 use stacks::vm::costs::ExecutionCost;
 use stacks::vm::events::FTEventType::FTWithdrawEvent;
 use stacks::vm::events::{FTWithdrawEventData, StacksTransactionEvent};
@@ -96,6 +96,7 @@ pub fn call_read_only(
     let body = CallReadOnlyRequestBody {
         sender: principal.to_string(),
         arguments: args,
+        cost_budget: None,
     };
 
     let read_info = client
@@ -453,6 +454,7 @@ fn l1_deposit_and_withdraw_asset_integration_test() {
     config.events_observers.push(EventObserverConfig {
         endpoint: format!("localhost:{}", test_observer::EVENT_OBSERVER_PORT),
         events_keys: vec![EventKeyType::AnyEvent],
+        ..EventObserverConfig::default()
     });
 
     test_observer::spawn();
@@ -1253,7 +1255,8 @@ fn l1_deposit_and_withdraw_stx_integration_test() {
 
     config.events_observers.push(EventObserverConfig {
         endpoint: format!("localhost:{}", test_observer::EVENT_OBSERVER_PORT),
-        events_keys: vec![EventKeyType::AnyEvent],
+        events_keys: vec![EventKeyType::AnyEvent, EventKeyType::SubnetWithdrawals],
+        ..EventObserverConfig::default()
     });
 
     test_observer::spawn();
@@ -1499,6 +1502,79 @@ fn l1_deposit_and_withdraw_stx_integration_test() {
         withdrawal_id,
         withdrawal_amount,
     );
+    assert_eq!(
+        withdrawal_entry.claim_status,
+        WithdrawalClaimStatus::Unclaimed
+    );
+
+    // Looking the withdrawal up by its ID alone should produce the same proof
+    let withdrawal_proof = get_withdrawal_proof(&l2_rpc_origin, withdrawal_height, withdrawal_id);
+    assert_eq!(withdrawal_proof.root_hash, withdrawal_entry.root_hash);
+    assert_eq!(withdrawal_proof.leaf_hash, withdrawal_entry.leaf_hash);
+    assert_eq!(withdrawal_proof.siblings, withdrawal_entry.siblings);
+
+    // The subnet withdrawals stream should report the same withdrawal, committed
+    // under the same root and leaf hash as the RPC entry.
+    let withdrawal_streams: Vec<_> = test_observer::get_subnet_withdrawals()
+        .into_iter()
+        .filter(|payload| {
+            !payload
+                .get("withdrawals")
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .is_empty()
+        })
+        .collect();
+    assert_eq!(withdrawal_streams.len(), 1);
+    let withdrawal_stream = &withdrawal_streams[0];
+    assert_eq!(
+        withdrawal_stream
+            .get("block_height")
+            .unwrap()
+            .as_u64()
+            .unwrap(),
+        withdrawal_height
+    );
+    assert_eq!(
+        withdrawal_stream
+            .get("withdrawal_root")
+            .unwrap()
+            .as_str()
+            .unwrap(),
+        format!(
+            "0x{}",
+            to_hex(&withdrawal_entry.root_hash.clone().expect_buff(32))
+        )
+    );
+    let streamed_withdrawal = &withdrawal_stream.get("withdrawals").unwrap()[0];
+    assert_eq!(
+        streamed_withdrawal.get("type").unwrap().as_str().unwrap(),
+        "stx"
+    );
+    assert_eq!(
+        streamed_withdrawal
+            .get("leaf_index")
+            .unwrap()
+            .as_u64()
+            .unwrap(),
+        withdrawal_id
+    );
+    assert_eq!(
+        streamed_withdrawal.get("sender").unwrap().as_str().unwrap(),
+        user_addr.to_string()
+    );
+    assert_eq!(
+        streamed_withdrawal
+            .get("withdrawal_leaf_hash")
+            .unwrap()
+            .as_str()
+            .unwrap(),
+        format!(
+            "0x{}",
+            to_hex(&withdrawal_entry.leaf_hash.clone().expect_buff(32))
+        )
+    );
 
     // Check that the user does not own any additional STX anymore on the subnet now
     let account = get_account(&l2_rpc_origin, &user_addr);
@@ -1632,6 +1708,18 @@ fn l1_deposit_and_withdraw_stx_integration_test() {
         account.balance,
         (l1_starting_account_balance - default_fee * l1_nonce) as u128
     );
+    // The subnet should now report the withdrawal as claimed on the L1
+    let withdrawal_entry = get_withdrawal_entry(
+        &l2_rpc_origin,
+        withdrawal_height,
+        &user_addr,
+        withdrawal_id,
+        withdrawal_amount,
+    );
+    assert_eq!(
+        withdrawal_entry.claim_status,
+        WithdrawalClaimStatus::Claimed
+    );
 
     termination_switch.store(false, Ordering::SeqCst);
     stacks_l1_controller.kill_process();
@@ -1661,6 +1749,7 @@ fn l2_simple_contract_calls() {
     config.events_observers.push(EventObserverConfig {
         endpoint: format!("localhost:{}", test_observer::EVENT_OBSERVER_PORT),
         events_keys: vec![EventKeyType::AnyEvent],
+        ..EventObserverConfig::default()
     });
 
     test_observer::spawn();
@@ -1786,6 +1875,7 @@ fn nft_deposit_and_withdraw_integration_test() {
     config.events_observers.push(EventObserverConfig {
         endpoint: format!("localhost:{}", test_observer::EVENT_OBSERVER_PORT),
         events_keys: vec![EventKeyType::AnyEvent],
+        ..EventObserverConfig::default()
     });
 
     test_observer::spawn();
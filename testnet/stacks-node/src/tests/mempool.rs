@@ -546,6 +546,29 @@ fn mempool_setup_chainstate() {
                     false
                 });
 
+                let tx_bytes = make_contract_publish(
+                    &contract_sk,
+                    5,
+                    1000,
+                    "unchecked_contract",
+                    "(define-public (foo) (begin (stx-burn? u1 tx-sender) (ok true)))",
+                );
+                let tx =
+                    StacksTransaction::consensus_deserialize(&mut tx_bytes.as_slice()).unwrap();
+                let e = chain_state
+                    .will_admit_mempool_tx(consensus_hash, block_hash, &tx, tx_bytes.len() as u64)
+                    .unwrap_err();
+                let e_json = e.into_json(&tx.txid());
+                assert_eq!(e_json["reason"], "ContractAnalysisFailed");
+                assert_eq!(
+                    e_json["reason_data"]["code"],
+                    "UncheckedIntermediaryResponses"
+                );
+                assert_eq!(
+                    e_json["reason_data"]["diagnostic"]["spans"][0]["start_column"],
+                    29
+                );
+
                 let microblock_1 = StacksMicroblockHeader {
                     version: 0,
                     sequence: 0,
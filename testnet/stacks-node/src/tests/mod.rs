@@ -29,6 +29,7 @@ pub mod l1_multiparty;
 pub mod l1_observer_test;
 #[allow(dead_code)]
 pub mod neon_integrations;
+pub mod simulation;
 
 // $ cat /tmp/out.clar
 pub const STORE_CONTRACT: &str = r#"(define-map store { key: (string-ascii 32) } { value: (string-ascii 32) })
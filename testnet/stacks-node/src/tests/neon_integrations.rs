@@ -8,7 +8,10 @@ use stacks::chainstate::burn::db::sortdb::SortitionDB;
 use stacks::chainstate::burn::ConsensusHash;
 use stacks::chainstate::stacks::TransactionPayload;
 use stacks::codec::StacksMessageCodec;
-use stacks::net::{AccountEntryResponse, ContractSrcResponse, RPCPeerInfoData, WithdrawalResponse};
+use stacks::net::{
+    AccountEntryResponse, ContractSrcResponse, RPCPeerInfoData, WithdrawalClaimStatus,
+    WithdrawalResponse,
+};
 use stacks::types::chainstate::{BlockHeaderHash, StacksAddress};
 use stacks::util::get_epoch_time_secs;
 use stacks::util::hash::{hex_bytes, Hash160};
@@ -90,6 +93,9 @@ pub mod test_observer {
         pub static ref MEMTXS: Mutex<Vec<String>> = Mutex::new(Vec::new());
         pub static ref MEMTXS_DROPPED: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
         pub static ref ATTACHMENTS: Mutex<Vec<serde_json::Value>> = Mutex::new(Vec::new());
+        pub static ref SUBNET_WITHDRAWALS: Mutex<Vec<serde_json::Value>> = Mutex::new(Vec::new());
+        pub static ref WITHDRAWAL_INVALIDATIONS: Mutex<Vec<serde_json::Value>> =
+            Mutex::new(Vec::new());
     }
 
     async fn handle_burn_block(
@@ -202,6 +208,22 @@ pub mod test_observer {
         Ok(warp::http::StatusCode::OK)
     }
 
+    async fn handle_subnet_withdrawals(
+        withdrawals: serde_json::Value,
+    ) -> Result<impl warp::Reply, Infallible> {
+        let mut withdrawal_events = SUBNET_WITHDRAWALS.lock().unwrap();
+        withdrawal_events.push(withdrawals);
+        Ok(warp::http::StatusCode::OK)
+    }
+
+    async fn handle_withdrawal_invalidated(
+        invalidated: serde_json::Value,
+    ) -> Result<impl warp::Reply, Infallible> {
+        let mut invalidations = WITHDRAWAL_INVALIDATIONS.lock().unwrap();
+        invalidations.push(invalidated);
+        Ok(warp::http::StatusCode::OK)
+    }
+
     pub fn get_memtxs() -> Vec<String> {
         MEMTXS.lock().unwrap().clone()
     }
@@ -234,6 +256,14 @@ pub mod test_observer {
         MINED_MICROBLOCKS.lock().unwrap().clone()
     }
 
+    pub fn get_subnet_withdrawals() -> Vec<serde_json::Value> {
+        SUBNET_WITHDRAWALS.lock().unwrap().clone()
+    }
+
+    pub fn get_withdrawal_invalidations() -> Vec<serde_json::Value> {
+        WITHDRAWAL_INVALIDATIONS.lock().unwrap().clone()
+    }
+
     /// each path here should correspond to one of the paths listed in `event_dispatcher.rs`
     async fn serve() {
         let new_blocks = warp::path!("new_block")
@@ -268,6 +298,14 @@ pub mod test_observer {
             .and(warp::post())
             .and(warp::body::json())
             .and_then(handle_mined_microblock);
+        let subnet_withdrawals = warp::path!("subnet_withdrawals")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and_then(handle_subnet_withdrawals);
+        let withdrawal_invalidated = warp::path!("withdrawal_invalidated")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and_then(handle_withdrawal_invalidated);
 
         info!("Spawning warp server");
         warp::serve(
@@ -278,7 +316,9 @@ pub mod test_observer {
                 .or(new_attachments)
                 .or(new_microblocks)
                 .or(mined_blocks)
-                .or(mined_microblocks),
+                .or(mined_microblocks)
+                .or(subnet_withdrawals)
+                .or(withdrawal_invalidated),
         )
         .run(([127, 0, 0, 1], EVENT_OBSERVER_PORT))
         .await
@@ -300,6 +340,8 @@ pub mod test_observer {
         MEMTXS_DROPPED.lock().unwrap().clear();
         MINED_BLOCKS.lock().unwrap().clear();
         NEW_MICROBLOCKS.lock().unwrap().clear();
+        SUBNET_WITHDRAWALS.lock().unwrap().clear();
+        WITHDRAWAL_INVALIDATIONS.lock().unwrap().clear();
     }
 }
 
@@ -481,7 +523,7 @@ pub fn get_chain_info(conf: &Config) -> RPCPeerInfoData {
     tip_info
 }
 
-fn get_tip_anchored_block(conf: &Config) -> (ConsensusHash, StacksBlock) {
+pub fn get_tip_anchored_block(conf: &Config) -> (ConsensusHash, StacksBlock) {
     let tip_info = get_chain_info(conf);
 
     // get the canonical chain tip
@@ -664,6 +706,7 @@ pub struct WithdrawalEntry {
     pub leaf_hash: ClarityValue,
     pub root_hash: ClarityValue,
     pub siblings: ClarityValue,
+    pub claim_status: WithdrawalClaimStatus,
 }
 
 pub fn get_account<F: std::fmt::Display>(http_origin: &str, account: &F) -> Account {
@@ -706,6 +749,7 @@ pub fn get_withdrawal_entry<F: std::fmt::Display>(
         leaf_hash: ClarityValue::try_deserialize_hex_untyped(&res.withdrawal_leaf_hash).unwrap(),
         root_hash: ClarityValue::try_deserialize_hex_untyped(&res.withdrawal_root).unwrap(),
         siblings: ClarityValue::try_deserialize_hex_untyped(&res.sibling_hashes).unwrap(),
+        claim_status: res.claim_status,
     }
 }
 
@@ -741,6 +785,33 @@ pub fn get_nft_withdrawal_entry<F: std::fmt::Display>(
         leaf_hash: ClarityValue::try_deserialize_hex_untyped(&res.withdrawal_leaf_hash).unwrap(),
         root_hash: ClarityValue::try_deserialize_hex_untyped(&res.withdrawal_root).unwrap(),
         siblings: ClarityValue::try_deserialize_hex_untyped(&res.sibling_hashes).unwrap(),
+        claim_status: res.claim_status,
+    }
+}
+
+pub fn get_withdrawal_proof(
+    http_origin: &str,
+    block_height: u64,
+    withdrawal_id: u64,
+) -> WithdrawalEntry {
+    let client = reqwest::blocking::Client::new();
+    let path = format!(
+        "{}/v2/withdrawal/proof/{}/{}",
+        http_origin, block_height, withdrawal_id
+    );
+
+    let res = client
+        .get(&path)
+        .send()
+        .unwrap()
+        .json::<WithdrawalResponse>()
+        .unwrap();
+    info!("Withdrawal proof response: {:#?}", res);
+    WithdrawalEntry {
+        leaf_hash: ClarityValue::try_deserialize_hex_untyped(&res.withdrawal_leaf_hash).unwrap(),
+        root_hash: ClarityValue::try_deserialize_hex_untyped(&res.withdrawal_root).unwrap(),
+        siblings: ClarityValue::try_deserialize_hex_untyped(&res.sibling_hashes).unwrap(),
+        claim_status: res.claim_status,
     }
 }
 
@@ -1131,6 +1202,7 @@ fn transactions_in_block_and_microblock() {
     conf.events_observers.push(EventObserverConfig {
         endpoint: format!("localhost:{}", test_observer::EVENT_OBSERVER_PORT),
         events_keys: vec![EventKeyType::AnyEvent],
+        ..EventObserverConfig::default()
     });
 
     info!(
@@ -1404,6 +1476,7 @@ fn transactions_microblocks_then_block() {
     conf.events_observers.push(EventObserverConfig {
         endpoint: format!("localhost:{}", test_observer::EVENT_OBSERVER_PORT),
         events_keys: vec![EventKeyType::AnyEvent],
+        ..EventObserverConfig::default()
     });
 
     test_observer::spawn();
@@ -0,0 +1,488 @@
+//! A harness for running several subnet nodes in-process against a mocked L1.
+//!
+//! `SimNetwork` boots one miner and any number of follower nodes, all of which follow the same
+//! `MockController`-driven layer-1 chain. Tests decide when L1 blocks are produced, what
+//! timestamp they carry, which subnet contract events they include, and which L1 block they
+//! build on, so L1 reorgs can be scripted.
+//!
+//! The nodes do not run their own run loops. The harness steps them from the test thread
+//! instead: it hands every new L1 block to each node and waits for the node's chains coordinator
+//! to process it, runs the miner's tenure when the test asks for a subnet block, and hands the
+//! mined block to each node. Every step finishes before the next one begins, and the only clock
+//! the nodes see is the `SimClock`, so a test runs the same way every time and can assert on
+//! the state of every node after any step.
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use stacks::chainstate::burn::db::sortdb::SortitionDB;
+use stacks::chainstate::burn::{BlockSnapshot, ConsensusHash};
+use stacks::chainstate::coordinator::comm::CoordinatorChannels;
+use stacks::chainstate::stacks::db::StacksChainState;
+use stacks::chainstate::stacks::{StacksBlock, StacksBlockHeader, StacksPrivateKey};
+use stacks::core::mempool::MemPoolDB;
+use stacks::cost_estimates::metrics::UnitMetric;
+use stacks::cost_estimates::UnitEstimator;
+use stacks::net::atlas::AttachmentInstance;
+use stacks::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash};
+use stacks::vm::types::{PrincipalData, TupleData};
+use stacks::vm::Value as ClarityValue;
+
+use crate::burnchains::mock_events::{mock_burn_header_hash, MockController};
+use crate::burnchains::withdrawal_batcher::WithdrawalBatcher;
+use crate::neon_node::{inner_process_tenure, AssembledAnchorBlock, StacksNode};
+use crate::tests::neon_integrations::mockstack_test_conf;
+use crate::tests::to_addr;
+use crate::{neon, BurnchainController, Config, EventDispatcher, Keychain};
+
+/// Timestamp of the first mocked L1 block.
+const SIM_GENESIS_TIME: u64 = 1_600_000_000;
+
+/// The clock used for the timestamps of mocked L1 blocks. It only moves when the test advances
+/// it.
+#[derive(Clone)]
+pub struct SimClock {
+    now: Arc<AtomicU64>,
+}
+
+impl SimClock {
+    pub fn new(start: u64) -> SimClock {
+        SimClock {
+            now: Arc::new(AtomicU64::new(start)),
+        }
+    }
+
+    pub fn now(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+
+    pub fn advance(&self, secs: u64) {
+        self.now.fetch_add(secs, Ordering::SeqCst);
+    }
+}
+
+/// A subnet node booted in this process, which only does work when the harness steps it.
+pub struct SimNode {
+    pub name: String,
+    pub config: Config,
+    burnchain: Box<dyn BurnchainController>,
+    coordinator: CoordinatorChannels,
+    coordinator_thread: Option<JoinHandle<()>>,
+    /// The coordinator sends the attachments it finds here, and fails if nobody listens
+    _attachments_rx: Receiver<HashSet<AttachmentInstance>>,
+    sortdb: SortitionDB,
+    chainstate: StacksChainState,
+}
+
+impl SimNode {
+    fn boot(name: String, config: Config) -> SimNode {
+        let mut run_loop = neon::RunLoop::new(config.clone());
+        let (burnchain, coordinator, coordinator_thread, attachments_rx) =
+            run_loop.boot_without_run_loop();
+
+        let sortdb = SortitionDB::open(&config.get_burn_db_file_path(), true)
+            .expect("Failed to open sortition DB");
+        let (chainstate, _) = StacksChainState::open(
+            config.is_mainnet(),
+            config.node.chain_id,
+            &config.get_chainstate_path_str(),
+            Some(config.node.get_marf_opts()),
+        )
+        .expect("Failed to open chainstate");
+
+        SimNode {
+            name,
+            config,
+            burnchain,
+            coordinator,
+            coordinator_thread: Some(coordinator_thread),
+            _attachments_rx: attachments_rx,
+            sortdb,
+            chainstate,
+        }
+    }
+
+    fn burn_tip_snapshot(&self) -> BlockSnapshot {
+        SortitionDB::get_canonical_burn_chain_tip(self.sortdb.conn())
+            .expect("Failed to query the canonical sortition")
+    }
+
+    /// The L1 block this node's canonical sortition was built from.
+    pub fn burn_tip(&self) -> BurnchainHeaderHash {
+        self.burn_tip_snapshot().burn_header_hash
+    }
+
+    /// The subnet block at the tip of this node's canonical fork.
+    pub fn stacks_tip(&self) -> BlockHeaderHash {
+        SortitionDB::get_canonical_stacks_chain_tip_hash(self.sortdb.conn())
+            .expect("Failed to query the canonical subnet tip")
+            .1
+    }
+
+    /// The block itself at the tip of this node's canonical subnet fork.
+    pub fn stacks_tip_block(&self) -> StacksBlock {
+        let tip = self
+            .chainstate
+            .get_stacks_chain_tip(&self.sortdb)
+            .expect("Failed to query the canonical subnet tip")
+            .unwrap_or_else(|| panic!("{} has not accepted any subnet blocks", &self.name));
+        StacksChainState::load_block(
+            &self.chainstate.blocks_path,
+            &tip.consensus_hash,
+            &tip.anchored_block_hash,
+        )
+        .expect("Failed to load the canonical subnet tip")
+        .expect("The canonical subnet tip is not stored")
+    }
+
+    /// The uSTX balance of `principal` at the tip of this node's canonical fork.
+    pub fn stx_balance(&mut self, principal: &PrincipalData) -> u128 {
+        let tip = self
+            .chainstate
+            .get_stacks_chain_tip(&self.sortdb)
+            .expect("Failed to query the canonical subnet tip")
+            .unwrap_or_else(|| panic!("{} has not accepted any subnet blocks", &self.name));
+        let tip_index_hash =
+            StacksBlockHeader::make_index_block_hash(&tip.consensus_hash, &tip.anchored_block_hash);
+        self.chainstate
+            .with_read_only_clarity_tx(&self.sortdb.index_conn(), &tip_index_hash, |conn| {
+                StacksChainState::get_account(conn, principal)
+            })
+            .expect("The canonical subnet tip has no state")
+            .stx_balance
+            .get_total_balance()
+    }
+
+    /// Process every L1 block this node has been sent, returning once its chains coordinator
+    /// has processed them.
+    fn sync_l1(&mut self) {
+        self.burnchain
+            .sync(None)
+            .unwrap_or_else(|e| panic!("{} failed to sync with the L1: {}", &self.name, e));
+    }
+
+    /// Process `block`, which the sortition at this node's L1 tip selected, returning once the
+    /// chains coordinator has accepted it.
+    fn accept_block(&mut self, block: &StacksBlock, parent_consensus_hash: &ConsensusHash) {
+        let burn_tip = self.burn_tip_snapshot();
+        assert_eq!(
+            burn_tip.winning_stacks_block_hash,
+            block.block_hash(),
+            "{} did not select the committed subnet block",
+            &self.name
+        );
+        let coordinator_running = inner_process_tenure(
+            block,
+            &burn_tip.consensus_hash,
+            parent_consensus_hash,
+            &mut self.sortdb,
+            &mut self.chainstate,
+            &self.coordinator,
+        )
+        .unwrap_or_else(|e| panic!("{} rejected subnet block: {}", &self.name, e));
+        assert!(coordinator_running, "{}'s coordinator stopped", &self.name);
+        assert_eq!(
+            self.stacks_tip(),
+            block.block_hash(),
+            "{} did not accept subnet block {}",
+            &self.name,
+            block.block_hash()
+        );
+    }
+}
+
+impl Drop for SimNode {
+    fn drop(&mut self) {
+        self.coordinator.stop_chains_coordinator();
+        if let Some(coordinator_thread) = self.coordinator_thread.take() {
+            if coordinator_thread.join().is_err() && !thread::panicking() {
+                panic!("{}'s coordinator panicked", &self.name);
+            }
+        }
+    }
+}
+
+/// The miner's state between tenures, besides its chain state.
+struct SimMiner {
+    keychain: Keychain,
+    mem_pool: MemPoolDB,
+    event_dispatcher: EventDispatcher,
+    withdrawal_batcher: WithdrawalBatcher,
+}
+
+impl SimMiner {
+    fn new(config: &Config) -> SimMiner {
+        let keychain = match config.node.mining_key.clone() {
+            Some(key) => Keychain::single_signer(key),
+            None => Keychain::default(config.node.seed.clone()),
+        };
+        let mem_pool = MemPoolDB::open(
+            config.is_mainnet(),
+            config.node.chain_id,
+            &config.get_chainstate_path_str(),
+            Box::new(UnitEstimator),
+            Box::new(UnitMetric),
+        )
+        .expect("Failed to open mempool");
+
+        SimMiner {
+            keychain,
+            mem_pool,
+            event_dispatcher: EventDispatcher::new(),
+            withdrawal_batcher: WithdrawalBatcher::from_config(config),
+        }
+    }
+}
+
+/// A miner and its followers, sharing one mocked L1 chain.
+pub struct SimNetwork {
+    pub clock: SimClock,
+    pub l1: MockController,
+    /// The miner is `nodes[0]`.
+    pub nodes: Vec<SimNode>,
+    miner: SimMiner,
+    /// The mocked L1 block that the next L1 block builds on.
+    l1_tip: u64,
+}
+
+impl SimNetwork {
+    /// Boot a miner and `num_followers` follower nodes, and produce the first mocked L1 blocks.
+    pub fn start(num_followers: usize) -> SimNetwork {
+        let (miner_conf, _) = mockstack_test_conf();
+        let follower_confs: Vec<_> = (0..num_followers)
+            .map(|_| {
+                let (mut conf, _) = mockstack_test_conf();
+                conf.node.miner = false;
+                conf.node.chain_id = miner_conf.node.chain_id;
+                conf
+            })
+            .collect();
+
+        let clock = SimClock::new(SIM_GENESIS_TIME);
+
+        let mut nodes = vec![SimNode::boot("miner".into(), miner_conf.clone())];
+        for (i, conf) in follower_confs.into_iter().enumerate() {
+            nodes.push(SimNode::boot(format!("follower {}", i + 1), conf));
+        }
+
+        let mut l1 =
+            MockController::new_standalone(miner_conf.clone(), nodes[0].coordinator.clone());
+        l1.set_block_time_source(clock.now.clone());
+        for node in nodes.iter().skip(1) {
+            l1.add_follower(node.burnchain.get_channel());
+        }
+
+        let mut network = SimNetwork {
+            clock,
+            l1,
+            nodes,
+            miner: SimMiner::new(&miner_conf),
+            l1_tip: 0,
+        };
+        // the first L1 block is the sortition DB's first block, which nodes have no sortition
+        // for, so there is nothing to wait on until the second
+        network.l1_tip = network.l1.next_block(None);
+        network.mine_l1_block();
+        network
+    }
+
+    pub fn miner(&self) -> &SimNode {
+        &self.nodes[0]
+    }
+
+    /// Include a deposit of `amount` uSTX to `recipient` in the next L1 block.
+    pub fn deposit_stx(&mut self, recipient: &PrincipalData, amount: u128) {
+        let event = TupleData::from_data(vec![
+            (
+                "event".into(),
+                ClarityValue::string_ascii_from_bytes("deposit-stx".as_bytes().to_vec()).unwrap(),
+            ),
+            ("amount".into(), ClarityValue::UInt(amount)),
+            ("sender".into(), ClarityValue::Principal(recipient.clone())),
+        ])
+        .expect("Should be a legal Clarity tuple")
+        .into();
+        self.l1.stage_event(event);
+    }
+
+    /// Produce an L1 block on top of the current L1 tip, and have every node process it.
+    pub fn mine_l1_block(&mut self) -> u64 {
+        let parent = self.l1_tip;
+        self.mine_l1_block_on(parent)
+    }
+
+    /// Produce an L1 block on top of the L1 block `parent`, and have every node process it.
+    /// Every node must end up on the longest L1 fork.
+    pub fn mine_l1_block_on(&mut self, parent: u64) -> u64 {
+        let parent_height = self.l1.get_block_height(parent).unwrap_or(0);
+        let tip_height = self.l1.get_block_height(self.l1_tip).unwrap_or(0);
+
+        let burn_block = self.l1.next_block(Some(parent));
+        if parent_height + 1 > tip_height {
+            self.l1_tip = burn_block;
+        }
+        for node in self.nodes.iter_mut() {
+            node.sync_l1();
+        }
+        self.assert_burn_tip(self.l1_tip);
+        burn_block
+    }
+
+    /// Produce a competing L1 fork of `length` blocks on top of the L1 block `fork_point`.
+    /// Returns the tip of the new fork.
+    pub fn reorg_l1(&mut self, fork_point: u64, length: u64) -> u64 {
+        let mut tip = fork_point;
+        for _ in 0..length {
+            tip = self.mine_l1_block_on(tip);
+        }
+        tip
+    }
+
+    /// Run the miner's tenure on top of its L1 tip, produce the L1 block that includes its
+    /// commit, and have every node accept the committed subnet block. Returns the block.
+    pub fn produce_subnet_block(&mut self) -> BlockHeaderHash {
+        let mined = self.run_miner_tenure();
+        let burn_block = self.mine_l1_block();
+        let block_hash = self.assert_block_produced(burn_block);
+        assert_eq!(
+            block_hash,
+            mined.anchored_block.block_hash(),
+            "The L1 block committed to a block the miner did not mine"
+        );
+        for node in self.nodes.iter_mut() {
+            node.accept_block(&mined.anchored_block, &mined.parent_consensus_hash);
+        }
+        block_hash
+    }
+
+    /// Run the miner's tenure on top of its L1 tip, which stages its commit for the next L1
+    /// block.
+    fn run_miner_tenure(&mut self) -> AssembledAnchorBlock {
+        let miner_node = &mut self.nodes[0];
+        let burn_tip = miner_node.burn_tip_snapshot();
+        let (mined, _) = StacksNode::relayer_run_tenure(
+            &miner_node.config,
+            &mut miner_node.chainstate,
+            &mut miner_node.sortdb,
+            burn_tip,
+            &mut self.miner.keychain,
+            &mut self.miner.mem_pool,
+            &mut self.l1,
+            &vec![],
+            &self.miner.event_dispatcher,
+            &mut self.miner.withdrawal_batcher,
+            false,
+        )
+        .expect("The miner did not commit to a block");
+        mined
+    }
+
+    /// Assert that the L1 block `burn_block` included a subnet block commit, and return the
+    /// committed block.
+    pub fn assert_block_produced(&self, burn_block: u64) -> BlockHeaderHash {
+        self.l1
+            .get_commit_in_block(burn_block)
+            .unwrap_or_else(|| panic!("L1 block {} did not include a commit", burn_block))
+    }
+
+    /// Assert that every node credited `recipient` with `expected` uSTX.
+    pub fn assert_balance(&mut self, recipient: &PrincipalData, expected: u128) {
+        for node in self.nodes.iter_mut() {
+            assert_eq!(
+                node.stx_balance(recipient),
+                expected,
+                "{} has the wrong balance for {}",
+                &node.name,
+                recipient
+            );
+        }
+    }
+
+    /// Assert that every node's canonical subnet tip carries the withdrawal root that was
+    /// committed to the L1 along with it.
+    pub fn assert_withdrawal_roots(&self) {
+        for node in self.nodes.iter() {
+            let tip_block = node.stacks_tip_block();
+            let committed_root = self
+                .l1
+                .get_committed_withdrawal_root(&tip_block.block_hash())
+                .expect("The tip subnet block was never committed to the L1");
+            assert_eq!(
+                tip_block.header.withdrawal_merkle_root, committed_root,
+                "{} has a withdrawal root that was not committed",
+                &node.name
+            );
+        }
+    }
+
+    /// Assert that every node's canonical sortition was built from the L1 block `burn_block`.
+    pub fn assert_burn_tip(&self, burn_block: u64) {
+        let expected = mock_burn_header_hash(burn_block);
+        for node in self.nodes.iter() {
+            assert_eq!(
+                node.burn_tip(),
+                expected,
+                "{} is not on L1 block {}",
+                &node.name,
+                burn_block
+            );
+        }
+    }
+
+    /// Assert that every node's canonical subnet tip is `block_hash`.
+    pub fn assert_stacks_tip(&self, block_hash: &BlockHeaderHash) {
+        for node in self.nodes.iter() {
+            assert_eq!(
+                &node.stacks_tip(),
+                block_hash,
+                "{} is not on subnet block {}",
+                &node.name,
+                block_hash
+            );
+        }
+    }
+}
+
+#[test]
+fn sim_followers_track_miner() {
+    let mut network = SimNetwork::start(2);
+
+    for _ in 0..3 {
+        network.clock.advance(600);
+        let block_hash = network.produce_subnet_block();
+        network.assert_stacks_tip(&block_hash);
+        network.assert_withdrawal_roots();
+    }
+}
+
+#[test]
+fn sim_deposit_credit() {
+    let mut network = SimNetwork::start(1);
+    let recipient: PrincipalData = to_addr(&StacksPrivateKey::new()).into();
+
+    network.deposit_stx(&recipient, 1_000);
+    network.produce_subnet_block();
+    network.produce_subnet_block();
+
+    network.assert_balance(&recipient, 1_000);
+}
+
+#[test]
+fn sim_l1_reorg() {
+    let mut network = SimNetwork::start(1);
+
+    let fork_point = network.mine_l1_block();
+    network.produce_subnet_block();
+    let orphaned_tip = network.produce_subnet_block();
+
+    // a longer L1 fork that excludes both commits
+    network.reorg_l1(fork_point, 3);
+    assert_ne!(network.miner().stacks_tip(), orphaned_tip);
+
+    let new_tip = network.produce_subnet_block();
+    assert_ne!(new_tip, orphaned_tip);
+    network.assert_stacks_tip(&new_tip);
+    network.assert_withdrawal_roots();
+}
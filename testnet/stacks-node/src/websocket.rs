@@ -0,0 +1,324 @@
+//! Optional WebSocket server which pushes, as each block is processed, the events that involve
+//! the principals and contracts a client subscribed to.  Clients connect to
+//! `/v2/events/subscribe?principals=<principal>,...&contracts=<contract id>,...` on
+//! `node.ws_bind`.
+
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::thread;
+
+use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::json;
+use tokio::sync::broadcast;
+use warp::http::StatusCode;
+use warp::ws::{Message, WebSocket};
+use warp::Filter;
+
+/// How many blocks a client may fall behind by before its connection is closed
+const SUBSCRIPTION_BUFFER_SIZE: usize = 256;
+
+/// Handle the event dispatcher uses to publish blocks to WebSocket subscribers.  A disabled
+/// handle drops everything it is given.
+#[derive(Clone)]
+pub struct WebSocketEventStreams {
+    blocks: Option<broadcast::Sender<Arc<serde_json::Value>>>,
+}
+
+impl WebSocketEventStreams {
+    pub fn disabled() -> WebSocketEventStreams {
+        WebSocketEventStreams { blocks: None }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.blocks.is_some()
+    }
+
+    /// Publish a processed block, given its `new_block` event observer payload
+    pub fn announce_block(&self, payload: &serde_json::Value) {
+        if let Some(blocks) = self.blocks.as_ref() {
+            if blocks.receiver_count() > 0 {
+                // fails only if every client disconnected in the meantime
+                let _ = blocks.send(Arc::new(payload.clone()));
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SubscriptionQuery {
+    principals: Option<String>,
+    contracts: Option<String>,
+}
+
+/// The principals and contracts a client is subscribed to
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SubscriptionFilter {
+    /// Principals whose events are pushed: events with the principal as sender or recipient
+    pub principals: HashSet<String>,
+    /// Contracts whose events are pushed: the contract's prints, and the events of assets it
+    /// defines
+    pub contracts: HashSet<String>,
+}
+
+fn split_list(list: Option<&str>) -> impl Iterator<Item = &str> {
+    list.unwrap_or_default()
+        .split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+}
+
+impl SubscriptionFilter {
+    /// Parse a filter from comma-separated lists of principals and contract identifiers.  At
+    /// least one principal or contract is required.
+    pub fn from_lists(
+        principals: Option<&str>,
+        contracts: Option<&str>,
+    ) -> Result<SubscriptionFilter, String> {
+        let mut filter = SubscriptionFilter::default();
+        for principal in split_list(principals) {
+            let principal = PrincipalData::parse(principal)
+                .map_err(|_| format!("Invalid principal: {}", principal))?;
+            filter.principals.insert(principal.to_string());
+        }
+        for contract in split_list(contracts) {
+            let contract = QualifiedContractIdentifier::parse(contract)
+                .map_err(|_| format!("Invalid contract identifier: {}", contract))?;
+            filter.contracts.insert(contract.to_string());
+        }
+        if filter.principals.is_empty() && filter.contracts.is_empty() {
+            return Err("Subscribe to at least one principal or contract".into());
+        }
+        Ok(filter)
+    }
+
+    /// Does the client subscribe to `event`, an entry of the `new_block` payload's `events`?
+    /// Events of transactions that were rolled back are never pushed.
+    pub fn matches(&self, event: &serde_json::Value) -> bool {
+        if event.get("committed").and_then(|c| c.as_bool()) != Some(true) {
+            return false;
+        }
+        let data = match event
+            .get("type")
+            .and_then(|t| t.as_str())
+            .and_then(|t| event.get(t))
+        {
+            Some(data) => data,
+            None => return false,
+        };
+
+        let involves_principal = ["sender", "recipient"].iter().any(|key| {
+            data.get(*key)
+                .and_then(|p| p.as_str())
+                .map_or(false, |p| self.principals.contains(p))
+        });
+        // prints carry their contract, and asset events carry `<contract id>::<asset name>`
+        let contract = data
+            .get("contract_identifier")
+            .or_else(|| data.get("asset_identifier"))
+            .and_then(|id| id.as_str())
+            .and_then(|id| id.split("::").next());
+        let involves_contract = contract.map_or(false, |c| self.contracts.contains(c));
+
+        involves_principal || involves_contract
+    }
+
+    /// The message to push to the client for a processed block, given its `new_block` payload.
+    /// Returns None if none of the block's events match.
+    pub fn block_delta(&self, payload: &serde_json::Value) -> Option<serde_json::Value> {
+        let events: Vec<_> = payload
+            .get("events")
+            .and_then(|events| events.as_array())
+            .map(|events| events.iter().filter(|e| self.matches(e)).collect())
+            .unwrap_or_default();
+        if events.is_empty() {
+            return None;
+        }
+        Some(json!({
+            "block_hash": payload.get("block_hash"),
+            "block_height": payload.get("block_height"),
+            "index_block_hash": payload.get("index_block_hash"),
+            "burn_block_height": payload.get("burn_block_height"),
+            "events": events,
+        }))
+    }
+}
+
+/// Push matching block deltas to a client until it disconnects or falls behind
+async fn serve_subscription(
+    socket: WebSocket,
+    filter: SubscriptionFilter,
+    mut blocks: broadcast::Receiver<Arc<serde_json::Value>>,
+) {
+    let (mut outgoing, mut incoming) = socket.split();
+    loop {
+        tokio::select! {
+            block = blocks.recv() => match block {
+                Ok(payload) => {
+                    if let Some(delta) = filter.block_delta(&payload) {
+                        if outgoing.send(Message::text(delta.to_string())).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!(
+                        "WebSocket subscriptions: closing client that fell {} blocks behind",
+                        skipped
+                    );
+                    break;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            message = incoming.next() => match message {
+                // clients have nothing to say after subscribing
+                Some(Ok(message)) if !message.is_close() => {}
+                _ => break,
+            },
+        }
+    }
+    let _ = outgoing.close().await;
+}
+
+/// Route handler: upgrade the connection if the client's filter is valid
+fn handle_subscribe(
+    query: SubscriptionQuery,
+    ws: warp::ws::Ws,
+    blocks: &broadcast::Sender<Arc<serde_json::Value>>,
+) -> Box<dyn warp::Reply> {
+    match SubscriptionFilter::from_lists(query.principals.as_deref(), query.contracts.as_deref()) {
+        Ok(filter) => {
+            let blocks = blocks.subscribe();
+            Box::new(ws.on_upgrade(move |socket| serve_subscription(socket, filter, blocks)))
+        }
+        Err(msg) => Box::new(warp::reply::with_status(msg, StatusCode::BAD_REQUEST)),
+    }
+}
+
+/// Start serving WebSocket subscriptions on `bind_address`, in a separate thread.
+pub fn start_serving_ws_subscriptions(bind_address: String) -> WebSocketEventStreams {
+    let addr: SocketAddr = bind_address
+        .parse()
+        .expect("WebSocket subscriptions: unable to parse bind address");
+    let (blocks, _) = broadcast::channel(SUBSCRIPTION_BUFFER_SIZE);
+    let streams = WebSocketEventStreams {
+        blocks: Some(blocks.clone()),
+    };
+
+    let subscribe = warp::path!("v2" / "events" / "subscribe")
+        .and(warp::query::<SubscriptionQuery>())
+        .and(warp::ws())
+        .map(move |query, ws| handle_subscribe(query, ws, &blocks));
+
+    thread::Builder::new()
+        .name("ws-subscriptions".to_string())
+        .spawn(move || {
+            let rt = tokio::runtime::Runtime::new().expect("Failed to initialize tokio");
+            info!("WebSocket subscriptions: server listening on {}", &addr);
+            rt.block_on(warp::serve(subscribe).run(addr));
+        })
+        .expect("FATAL: failed to start WebSocket subscriptions thread");
+
+    streams
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ALICE: &str = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2";
+    const BOB: &str = "ST319CF5WV77KYR1H3GT0GZ7B8Q4AQPY42ETP1VPF";
+    const TOKEN: &str = "ST2QKZ4FKHAH1NQKYKYAYZPY440FEPK7GZ1R5HBP2.token";
+
+    fn ft_transfer(sender: &str, recipient: &str, committed: bool) -> serde_json::Value {
+        json!({
+            "txid": "0x01",
+            "event_index": 0,
+            "committed": committed,
+            "type": "ft_transfer_event",
+            "ft_transfer_event": {
+                "asset_identifier": format!("{}::credits", TOKEN),
+                "sender": sender,
+                "recipient": recipient,
+                "amount": "10",
+            }
+        })
+    }
+
+    fn print(contract: &str) -> serde_json::Value {
+        json!({
+            "txid": "0x02",
+            "event_index": 1,
+            "committed": true,
+            "type": "contract_event",
+            "contract_event": {
+                "contract_identifier": contract,
+                "topic": "print",
+                "value": null,
+                "raw_value": "0x03",
+            }
+        })
+    }
+
+    #[test]
+    fn test_filter_from_lists() {
+        let filter =
+            SubscriptionFilter::from_lists(Some(&format!("{}, {}", ALICE, BOB)), None).unwrap();
+        assert_eq!(filter.principals.len(), 2);
+        assert!(filter.contracts.is_empty());
+
+        let filter = SubscriptionFilter::from_lists(None, Some(TOKEN)).unwrap();
+        assert!(filter.contracts.contains(TOKEN));
+
+        assert!(SubscriptionFilter::from_lists(None, None).is_err());
+        assert!(SubscriptionFilter::from_lists(Some(","), Some("")).is_err());
+        assert!(SubscriptionFilter::from_lists(Some("not-a-principal"), None).is_err());
+        assert!(SubscriptionFilter::from_lists(None, Some(ALICE)).is_err());
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let by_bob = SubscriptionFilter::from_lists(Some(BOB), None).unwrap();
+        assert!(by_bob.matches(&ft_transfer(ALICE, BOB, true)));
+        assert!(by_bob.matches(&ft_transfer(BOB, ALICE, true)));
+        assert!(!by_bob.matches(&ft_transfer(BOB, ALICE, false)));
+        assert!(!by_bob.matches(&print(TOKEN)));
+
+        let by_token = SubscriptionFilter::from_lists(None, Some(TOKEN)).unwrap();
+        assert!(by_token.matches(&ft_transfer(ALICE, BOB, true)));
+        assert!(by_token.matches(&print(TOKEN)));
+        assert!(!by_token.matches(&print(&format!("{}.other", ALICE))));
+
+        // events whose payload was omitted carry only their type
+        let omitted = json!({
+            "txid": "0x01",
+            "event_index": 0,
+            "committed": true,
+            "type": "ft_transfer_event",
+            "payload_omitted": true,
+        });
+        assert!(!by_token.matches(&omitted));
+    }
+
+    #[test]
+    fn test_block_delta() {
+        let filter = SubscriptionFilter::from_lists(Some(BOB), None).unwrap();
+        let payload = json!({
+            "block_hash": "0x0a",
+            "block_height": 5,
+            "index_block_hash": "0x0b",
+            "burn_block_height": 105,
+            "events": [ft_transfer(ALICE, BOB, true), print(TOKEN)],
+        });
+
+        let delta = filter.block_delta(&payload).unwrap();
+        assert_eq!(delta["block_height"], json!(5));
+        assert_eq!(delta["index_block_hash"], json!("0x0b"));
+        assert_eq!(delta["events"], json!([ft_transfer(ALICE, BOB, true)]));
+
+        let filter = SubscriptionFilter::from_lists(Some(ALICE), None).unwrap();
+        let payload = json!({ "block_height": 6, "events": [print(TOKEN)] });
+        assert!(filter.block_delta(&payload).is_none());
+    }
+}